@@ -0,0 +1,436 @@
+//! Handles ACP requests from the client and drives one Codex conversation per
+//! ACP session.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use codex_common::CliConfigOverrides;
+use codex_core::AuthManager;
+use codex_core::CodexConversation;
+use codex_core::ConversationManager;
+use codex_core::DynToolExecutor;
+use codex_core::NewConversation;
+use codex_core::config::Config;
+use codex_core::config::ConfigOverrides;
+use codex_core::default_tool_executor;
+use codex_core::protocol::Event;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::Op;
+use codex_core::protocol::ReviewDecision;
+use codex_protocol::protocol::SessionSource;
+use codex_protocol::user_input::UserInput;
+use mcp_types::JSONRPCError;
+use mcp_types::JSONRPCNotification;
+use mcp_types::JSONRPCRequest;
+use mcp_types::JSONRPCResponse;
+use mcp_types::RequestId;
+use serde::de::DeserializeOwned;
+use tokio::sync::Mutex;
+use tracing::info;
+use tracing::warn;
+
+use crate::client_fs::ClientFsExecutor;
+use crate::error_code::INTERNAL_ERROR_CODE;
+use crate::error_code::INVALID_PARAMS_ERROR_CODE;
+use crate::error_code::INVALID_REQUEST_ERROR_CODE;
+use crate::error_code::METHOD_NOT_FOUND_ERROR_CODE;
+use crate::events::exec_tool_call;
+use crate::events::patch_tool_call;
+use crate::events::permission_options;
+use crate::events::review_decision;
+use crate::events::session_update;
+use crate::outgoing::OutgoingMessageSender;
+use crate::schema::AgentCapabilities;
+use crate::schema::CancelNotification;
+use crate::schema::ClientCapabilities;
+use crate::schema::ContentBlock;
+use crate::schema::InitializeRequest;
+use crate::schema::InitializeResponse;
+use crate::schema::NewSessionRequest;
+use crate::schema::NewSessionResponse;
+use crate::schema::PROTOCOL_VERSION;
+use crate::schema::PromptCapabilities;
+use crate::schema::PromptRequest;
+use crate::schema::PromptResponse;
+use crate::schema::REQUEST_PERMISSION_METHOD;
+use crate::schema::RequestPermissionRequest;
+use crate::schema::RequestPermissionResponse;
+use crate::schema::SESSION_UPDATE_METHOD;
+use crate::schema::SessionNotification;
+use crate::schema::StopReason;
+use crate::schema::ToolCall;
+
+pub(crate) struct Agent {
+    outgoing: Arc<OutgoingMessageSender>,
+    codex_linux_sandbox_exe: Option<PathBuf>,
+    cli_config_overrides: CliConfigOverrides,
+    /// Set by `initialize`; sessions cannot be created before it.
+    client_capabilities: Option<ClientCapabilities>,
+    conversation_manager: Option<Arc<ConversationManager>>,
+    sessions: HashMap<String, Arc<AcpSession>>,
+}
+
+struct AcpSession {
+    session_id: String,
+    conversation: Arc<CodexConversation>,
+    /// The `session/prompt` request answered when the running turn ends.
+    pending_prompt: Mutex<Option<RequestId>>,
+}
+
+impl Agent {
+    pub(crate) fn new(
+        outgoing: OutgoingMessageSender,
+        codex_linux_sandbox_exe: Option<PathBuf>,
+        cli_config_overrides: CliConfigOverrides,
+    ) -> Self {
+        Self {
+            outgoing: Arc::new(outgoing),
+            codex_linux_sandbox_exe,
+            cli_config_overrides,
+            client_capabilities: None,
+            conversation_manager: None,
+            sessions: HashMap::new(),
+        }
+    }
+
+    pub(crate) async fn process_request(&mut self, request: JSONRPCRequest) {
+        let JSONRPCRequest {
+            id, method, params, ..
+        } = request;
+        let params = params.unwrap_or(serde_json::Value::Null);
+        match method.as_str() {
+            "initialize" => {
+                if let Some(params) = self.parse_params::<InitializeRequest>(&id, params) {
+                    self.initialize(id, params);
+                }
+            }
+            "authenticate" => self.outgoing.send_response(id, serde_json::json!({})),
+            "session/new" => {
+                if let Some(params) = self.parse_params::<NewSessionRequest>(&id, params) {
+                    self.new_session(id, params).await;
+                }
+            }
+            "session/prompt" => {
+                if let Some(params) = self.parse_params::<PromptRequest>(&id, params) {
+                    self.prompt(id, params).await;
+                }
+            }
+            _ => self.outgoing.send_error(
+                id,
+                METHOD_NOT_FOUND_ERROR_CODE,
+                format!("method not found: {method}"),
+            ),
+        }
+    }
+
+    pub(crate) async fn process_notification(&self, notification: JSONRPCNotification) {
+        if notification.method != "session/cancel" {
+            info!("ignoring notification {}", notification.method);
+            return;
+        }
+        let params = notification.params.unwrap_or(serde_json::Value::Null);
+        let Ok(CancelNotification { session_id }) = serde_json::from_value(params) else {
+            warn!("invalid session/cancel params");
+            return;
+        };
+        let Some(session) = self.sessions.get(&session_id) else {
+            warn!("session/cancel for unknown session {session_id}");
+            return;
+        };
+        if let Err(err) = session.conversation.submit(Op::Interrupt).await {
+            warn!("failed to interrupt session {session_id}: {err}");
+        }
+    }
+
+    pub(crate) async fn process_response(&self, response: JSONRPCResponse) {
+        self.outgoing
+            .notify_client_response(response.id, Ok(response.result))
+            .await;
+    }
+
+    pub(crate) async fn process_error(&self, error: JSONRPCError) {
+        self.outgoing
+            .notify_client_response(error.id, Err(error.error))
+            .await;
+    }
+
+    fn parse_params<T: DeserializeOwned>(
+        &self,
+        id: &RequestId,
+        params: serde_json::Value,
+    ) -> Option<T> {
+        match serde_json::from_value(params) {
+            Ok(params) => Some(params),
+            Err(err) => {
+                self.outgoing.send_error(
+                    id.clone(),
+                    INVALID_PARAMS_ERROR_CODE,
+                    format!("invalid params: {err}"),
+                );
+                None
+            }
+        }
+    }
+
+    fn initialize(&mut self, id: RequestId, params: InitializeRequest) {
+        info!(
+            "client speaks ACP version {}; capabilities: {:?}",
+            params.protocol_version, params.client_capabilities
+        );
+        self.client_capabilities = Some(params.client_capabilities);
+        self.outgoing.send_response(
+            id,
+            InitializeResponse {
+                protocol_version: PROTOCOL_VERSION,
+                agent_capabilities: AgentCapabilities {
+                    load_session: false,
+                    prompt_capabilities: PromptCapabilities {
+                        image: true,
+                        audio: false,
+                        embedded_context: true,
+                    },
+                },
+                auth_methods: Vec::new(),
+            },
+        );
+    }
+
+    async fn new_session(&mut self, id: RequestId, params: NewSessionRequest) {
+        let Some(client_capabilities) = self.client_capabilities else {
+            self.outgoing.send_error(
+                id,
+                INVALID_REQUEST_ERROR_CODE,
+                "session/new called before initialize".to_string(),
+            );
+            return;
+        };
+        let config = match self.load_config(params.cwd).await {
+            Ok(config) => config,
+            Err(message) => {
+                self.outgoing.send_error(id, INTERNAL_ERROR_CODE, message);
+                return;
+            }
+        };
+        let conversation_manager = self
+            .conversation_manager
+            .get_or_insert_with(|| {
+                let auth_manager = AuthManager::shared(
+                    config.codex_home.clone(),
+                    false,
+                    config.cli_auth_credentials_store_mode,
+                );
+                let executor: DynToolExecutor = if client_capabilities.fs.write_text_file {
+                    Arc::new(ClientFsExecutor::new(
+                        self.outgoing.clone(),
+                        default_tool_executor(),
+                    ))
+                } else {
+                    default_tool_executor()
+                };
+                Arc::new(ConversationManager::with_tool_executor(
+                    auth_manager,
+                    SessionSource::Mcp,
+                    executor,
+                ))
+            })
+            .clone();
+        let NewConversation {
+            conversation_id,
+            conversation,
+            ..
+        } = match conversation_manager.new_conversation(config).await {
+            Ok(conversation) => conversation,
+            Err(err) => {
+                self.outgoing.send_error(
+                    id,
+                    INTERNAL_ERROR_CODE,
+                    format!("failed to start session: {err}"),
+                );
+                return;
+            }
+        };
+
+        let session_id = conversation_id.to_string();
+        let session = Arc::new(AcpSession {
+            session_id: session_id.clone(),
+            conversation,
+            pending_prompt: Mutex::new(None),
+        });
+        self.sessions.insert(session_id.clone(), session.clone());
+        tokio::spawn(run_session(session, self.outgoing.clone()));
+        self.outgoing
+            .send_response(id, NewSessionResponse { session_id });
+    }
+
+    async fn load_config(&self, cwd: PathBuf) -> Result<Config, String> {
+        let cli_kv_overrides = self
+            .cli_config_overrides
+            .parse_overrides()
+            .map_err(|err| format!("error parsing -c overrides: {err}"))?;
+        let overrides = ConfigOverrides {
+            cwd: Some(cwd),
+            codex_linux_sandbox_exe: self.codex_linux_sandbox_exe.clone(),
+            ..Default::default()
+        };
+        Config::load_with_cli_overrides(cli_kv_overrides, overrides)
+            .await
+            .map_err(|err| format!("error loading config: {err}"))
+    }
+
+    async fn prompt(&self, id: RequestId, params: PromptRequest) {
+        let Some(session) = self.sessions.get(&params.session_id) else {
+            self.outgoing.send_error(
+                id,
+                INVALID_PARAMS_ERROR_CODE,
+                format!("unknown session: {}", params.session_id),
+            );
+            return;
+        };
+        {
+            let mut pending_prompt = session.pending_prompt.lock().await;
+            if pending_prompt.is_some() {
+                drop(pending_prompt);
+                self.outgoing.send_error(
+                    id,
+                    INVALID_REQUEST_ERROR_CODE,
+                    "a prompt is already running in this session".to_string(),
+                );
+                return;
+            }
+            *pending_prompt = Some(id.clone());
+        }
+        let items = params.prompt.into_iter().map(user_input).collect();
+        if let Err(err) = session.conversation.submit(Op::UserInput { items }).await {
+            session.pending_prompt.lock().await.take();
+            self.outgoing.send_error(
+                id,
+                INTERNAL_ERROR_CODE,
+                format!("failed to submit prompt: {err}"),
+            );
+        }
+    }
+}
+
+/// Forward the conversation's events to the client until it shuts down.
+async fn run_session(session: Arc<AcpSession>, outgoing: Arc<OutgoingMessageSender>) {
+    loop {
+        let event = match session.conversation.next_event().await {
+            Ok(event) => event,
+            Err(err) => {
+                warn!("session {} stopped: {err}", session.session_id);
+                break;
+            }
+        };
+        match &event.msg {
+            EventMsg::ExecApprovalRequest(request) => {
+                let tool_call = exec_tool_call(&request.call_id, &request.command, &request.cwd);
+                tokio::spawn(request_permission(
+                    session.clone(),
+                    outgoing.clone(),
+                    event.clone(),
+                    tool_call,
+                ));
+            }
+            EventMsg::ApplyPatchApprovalRequest(request) => {
+                let tool_call = patch_tool_call(&request.call_id, &request.changes);
+                tokio::spawn(request_permission(
+                    session.clone(),
+                    outgoing.clone(),
+                    event.clone(),
+                    tool_call,
+                ));
+            }
+            EventMsg::TaskComplete(_) => {
+                finish_prompt(&session, &outgoing, StopReason::EndTurn).await;
+            }
+            EventMsg::TurnAborted(_) => {
+                finish_prompt(&session, &outgoing, StopReason::Cancelled).await;
+            }
+            EventMsg::Error(error) => {
+                if let Some(id) = session.pending_prompt.lock().await.take() {
+                    outgoing.send_error(id, INTERNAL_ERROR_CODE, error.message.clone());
+                }
+            }
+            EventMsg::ShutdownComplete => break,
+            msg => {
+                if let Some(update) = session_update(msg) {
+                    outgoing.send_notification(
+                        SESSION_UPDATE_METHOD,
+                        SessionNotification {
+                            session_id: session.session_id.clone(),
+                            update,
+                        },
+                    );
+                }
+            }
+        }
+    }
+}
+
+async fn finish_prompt(
+    session: &AcpSession,
+    outgoing: &OutgoingMessageSender,
+    stop_reason: StopReason,
+) {
+    if let Some(id) = session.pending_prompt.lock().await.take() {
+        outgoing.send_response(id, PromptResponse { stop_reason });
+    }
+}
+
+/// Ask the client to approve the request in `event` and pass its answer on
+/// to the conversation.
+async fn request_permission(
+    session: Arc<AcpSession>,
+    outgoing: Arc<OutgoingMessageSender>,
+    event: Event,
+    tool_call: ToolCall,
+) {
+    let request = RequestPermissionRequest {
+        session_id: session.session_id.clone(),
+        tool_call,
+        options: permission_options(),
+    };
+    let decision = match outgoing.request(REQUEST_PERMISSION_METHOD, request).await {
+        Ok(result) => match serde_json::from_value::<RequestPermissionResponse>(result) {
+            Ok(response) => review_decision(&response.outcome),
+            Err(err) => {
+                warn!("invalid session/request_permission response: {err}");
+                ReviewDecision::Denied
+            }
+        },
+        Err(err) => {
+            warn!("session/request_permission failed: {}", err.message);
+            ReviewDecision::Denied
+        }
+    };
+    let op = match event.msg {
+        EventMsg::ApplyPatchApprovalRequest(_) => Op::PatchApproval {
+            id: event.id,
+            decision,
+        },
+        _ => Op::ExecApproval {
+            id: event.id,
+            decision,
+        },
+    };
+    if let Err(err) = session.conversation.submit(op).await {
+        warn!("failed to submit approval: {err}");
+    }
+}
+
+fn user_input(block: ContentBlock) -> UserInput {
+    match block {
+        ContentBlock::Text { text } => UserInput::Text { text },
+        ContentBlock::Image { data, mime_type } => UserInput::Image {
+            image_url: format!("data:{mime_type};base64,{data}"),
+        },
+        ContentBlock::ResourceLink { uri, .. } => UserInput::Text { text: uri },
+        ContentBlock::Resource { resource } => UserInput::Text {
+            text: match resource.text {
+                Some(text) => format!("<context ref=\"{}\">\n{text}\n</context>", resource.uri),
+                None => resource.uri,
+            },
+        },
+    }
+}
@@ -0,0 +1,219 @@
+//! Patch application through the client's file system.
+//!
+//! When the client can write text files, patches are written through
+//! `fs/write_text_file` so the editor updates its open buffers and records the
+//! edit in its own undo history. Patches that delete or rename files have no
+//! ACP equivalent and, like shell commands, still run on disk.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+
+use async_trait::async_trait;
+use codex_apply_patch::AffectedPaths;
+use codex_apply_patch::ApplyPatchAction;
+use codex_apply_patch::ApplyPatchFileChange;
+use codex_apply_patch::MaybeApplyPatchVerified;
+use codex_apply_patch::maybe_parse_apply_patch_verified;
+use codex_apply_patch::print_summary;
+use codex_apply_patch::read_text_file;
+use codex_core::ApplyPatchRequest;
+use codex_core::DynToolExecutor;
+use codex_core::SandboxAttempt;
+use codex_core::ShellRequest;
+use codex_core::ToolCtx;
+use codex_core::ToolError;
+use codex_core::ToolExecutor;
+use codex_core::exec::ExecToolCallOutput;
+use codex_core::exec::ExitReason;
+use codex_core::exec::StreamOutput;
+
+use crate::outgoing::OutgoingMessageSender;
+use crate::schema::WRITE_TEXT_FILE_METHOD;
+use crate::schema::WriteTextFileRequest;
+
+pub(crate) struct ClientFsExecutor {
+    outgoing: Arc<OutgoingMessageSender>,
+    fallback: DynToolExecutor,
+}
+
+impl ClientFsExecutor {
+    pub(crate) fn new(outgoing: Arc<OutgoingMessageSender>, fallback: DynToolExecutor) -> Self {
+        Self { outgoing, fallback }
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for ClientFsExecutor {
+    async fn run_shell(
+        &self,
+        req: &ShellRequest,
+        attempt: &SandboxAttempt<'_>,
+        ctx: &ToolCtx<'_>,
+    ) -> Result<ExecToolCallOutput, ToolError> {
+        self.fallback.run_shell(req, attempt, ctx).await
+    }
+
+    async fn run_apply_patch(
+        &self,
+        req: &ApplyPatchRequest,
+        attempt: &SandboxAttempt<'_>,
+        ctx: &ToolCtx<'_>,
+    ) -> Result<ExecToolCallOutput, ToolError> {
+        let argv = vec!["apply_patch".to_string(), req.patch.clone()];
+        let writes = match maybe_parse_apply_patch_verified(&argv, &req.cwd) {
+            MaybeApplyPatchVerified::Body(action) => client_writes(&action),
+            _ => None,
+        };
+        let Some(writes) = writes else {
+            return self.fallback.run_apply_patch(req, attempt, ctx).await;
+        };
+
+        let started = Instant::now();
+        let session_id = ctx.conversation_id().to_string();
+        let mut affected = AffectedPaths {
+            added: Vec::new(),
+            modified: Vec::new(),
+            deleted: Vec::new(),
+        };
+        for write in writes {
+            let request = WriteTextFileRequest {
+                session_id: session_id.clone(),
+                path: write.path.clone(),
+                content: write.content,
+            };
+            if let Err(err) = self.outgoing.request(WRITE_TEXT_FILE_METHOD, request).await {
+                let message = format!(
+                    "failed to write {} through the editor: {}\n",
+                    write.path.display(),
+                    err.message
+                );
+                return Ok(output(1, String::new(), message, started.elapsed()));
+            }
+            if write.added {
+                affected.added.push(write.path);
+            } else {
+                affected.modified.push(write.path);
+            }
+        }
+        let mut summary = Vec::new();
+        let _ = print_summary(&affected, &mut summary);
+        let summary = String::from_utf8_lossy(&summary).into_owned();
+        Ok(output(0, summary, String::new(), started.elapsed()))
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct ClientWrite {
+    path: PathBuf,
+    content: String,
+    added: bool,
+}
+
+/// The file writes that apply `action`, sorted by path, or `None` if it also
+/// deletes or renames files or writes binary ones. Updated files keep the BOM and line endings
+/// they have on disk.
+fn client_writes(action: &ApplyPatchAction) -> Option<Vec<ClientWrite>> {
+    let mut writes = Vec::new();
+    for (path, change) in action.changes() {
+        let write = match change {
+            ApplyPatchFileChange::Add { content } => ClientWrite {
+                path: path.clone(),
+                content: content.clone(),
+                added: true,
+            },
+            ApplyPatchFileChange::Update {
+                move_path: None,
+                new_content,
+                ..
+            } => ClientWrite {
+                path: path.clone(),
+                content: match read_text_file(path) {
+                    Ok((_, format)) => format.restore(new_content),
+                    Err(_) => new_content.clone(),
+                },
+                added: false,
+            },
+            ApplyPatchFileChange::Update { .. }
+            | ApplyPatchFileChange::Delete { .. }
+            | ApplyPatchFileChange::Binary { .. } => {
+                return None;
+            }
+        };
+        writes.push(write);
+    }
+    writes.sort_by(|a, b| a.path.cmp(&b.path));
+    Some(writes)
+}
+
+fn output(
+    exit_code: i32,
+    stdout: String,
+    stderr: String,
+    duration: Duration,
+) -> ExecToolCallOutput {
+    let stream = |text: String| StreamOutput {
+        text,
+        truncated_after_lines: None,
+    };
+    ExecToolCallOutput {
+        exit_code,
+        aggregated_output: stream(format!("{stdout}{stderr}")),
+        stdout: stream(stdout),
+        stderr: stream(stderr),
+        duration,
+        full_output_path: None,
+        exit_reason: ExitReason::from_exit_code(exit_code),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::tempdir;
+
+    fn verify(patch: &str, cwd: &std::path::Path) -> ApplyPatchAction {
+        let argv = vec!["apply_patch".to_string(), patch.to_string()];
+        match maybe_parse_apply_patch_verified(&argv, cwd) {
+            MaybeApplyPatchVerified::Body(action) => action,
+            _ => panic!("expected a verified patch"),
+        }
+    }
+
+    #[test]
+    fn edits_become_client_writes_in_the_original_layout() {
+        let dir = tempdir().expect("tempdir");
+        std::fs::write(dir.path().join("a.txt"), "one\r\ntwo\r\n").expect("write");
+        let action = verify(
+            "*** Begin Patch\n\
+             *** Update File: a.txt\n@@\n one\n-two\n+2\n\
+             *** Add File: b.txt\n+new\n\
+             *** End Patch",
+            dir.path(),
+        );
+
+        assert_eq!(
+            client_writes(&action),
+            Some(vec![
+                ClientWrite {
+                    path: dir.path().join("a.txt"),
+                    content: "one\r\n2\r\n".to_string(),
+                    added: false,
+                },
+                ClientWrite {
+                    path: dir.path().join("b.txt"),
+                    content: "new\n".to_string(),
+                    added: true,
+                },
+            ])
+        );
+
+        let delete = verify(
+            "*** Begin Patch\n*** Delete File: a.txt\n*** End Patch",
+            dir.path(),
+        );
+        assert_eq!(client_writes(&delete), None);
+    }
+}
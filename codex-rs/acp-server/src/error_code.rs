@@ -0,0 +1,4 @@
+pub(crate) const INVALID_REQUEST_ERROR_CODE: i64 = -32600;
+pub(crate) const METHOD_NOT_FOUND_ERROR_CODE: i64 = -32601;
+pub(crate) const INVALID_PARAMS_ERROR_CODE: i64 = -32602;
+pub(crate) const INTERNAL_ERROR_CODE: i64 = -32603;
@@ -0,0 +1,317 @@
+//! Translation of Codex events into ACP session updates and permission
+//! requests.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::FileChange;
+use codex_core::protocol::McpInvocation;
+use codex_core::protocol::ReviewDecision;
+use codex_protocol::plan_tool::StepStatus;
+
+use crate::schema::ContentBlock;
+use crate::schema::PermissionOption;
+use crate::schema::PermissionOptionKind;
+use crate::schema::PlanEntry;
+use crate::schema::PlanEntryPriority;
+use crate::schema::PlanEntryStatus;
+use crate::schema::RequestPermissionOutcome;
+use crate::schema::SessionUpdate;
+use crate::schema::ToolCall;
+use crate::schema::ToolCallContent;
+use crate::schema::ToolCallLocation;
+use crate::schema::ToolCallStatus;
+use crate::schema::ToolKind;
+
+/// The session update that reports `msg` to the client, if it has one.
+/// Approval requests and the end of a turn are handled by the caller.
+pub(crate) fn session_update(msg: &EventMsg) -> Option<SessionUpdate> {
+    let update = match msg {
+        EventMsg::AgentMessageDelta(event) => SessionUpdate::AgentMessageChunk {
+            content: text(&event.delta),
+        },
+        EventMsg::AgentReasoningDelta(event) => SessionUpdate::AgentThoughtChunk {
+            content: text(&event.delta),
+        },
+        EventMsg::ExecCommandBegin(event) => SessionUpdate::ToolCall(ToolCall {
+            status: Some(ToolCallStatus::InProgress),
+            ..exec_tool_call(&event.call_id, &event.command, &event.cwd)
+        }),
+        EventMsg::ExecCommandEnd(event) => SessionUpdate::ToolCallUpdate(ToolCall {
+            tool_call_id: event.call_id.clone(),
+            status: Some(if event.exit_code == 0 {
+                ToolCallStatus::Completed
+            } else {
+                ToolCallStatus::Failed
+            }),
+            content: Some(vec![ToolCallContent::Content {
+                content: text(&event.aggregated_output),
+            }]),
+            ..ToolCall::default()
+        }),
+        EventMsg::PatchApplyBegin(event) => SessionUpdate::ToolCall(ToolCall {
+            status: Some(ToolCallStatus::InProgress),
+            ..patch_tool_call(&event.call_id, &event.changes)
+        }),
+        EventMsg::PatchApplyEnd(event) => {
+            let output = if event.success {
+                &event.stdout
+            } else {
+                &event.stderr
+            };
+            SessionUpdate::ToolCallUpdate(ToolCall {
+                tool_call_id: event.call_id.clone(),
+                status: Some(if event.success {
+                    ToolCallStatus::Completed
+                } else {
+                    ToolCallStatus::Failed
+                }),
+                content: Some(vec![ToolCallContent::Content {
+                    content: text(output),
+                }]),
+                ..ToolCall::default()
+            })
+        }
+        EventMsg::McpToolCallBegin(event) => SessionUpdate::ToolCall(ToolCall {
+            status: Some(ToolCallStatus::InProgress),
+            ..mcp_tool_call(&event.call_id, &event.invocation)
+        }),
+        EventMsg::McpToolCallEnd(event) => {
+            let output = match &event.result {
+                Ok(result) => result
+                    .content
+                    .iter()
+                    .filter_map(|block| match block {
+                        mcp_types::ContentBlock::TextContent(content) => {
+                            Some(content.text.as_str())
+                        }
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+                Err(err) => err.clone(),
+            };
+            SessionUpdate::ToolCallUpdate(ToolCall {
+                tool_call_id: event.call_id.clone(),
+                status: Some(if event.is_success() {
+                    ToolCallStatus::Completed
+                } else {
+                    ToolCallStatus::Failed
+                }),
+                content: Some(vec![ToolCallContent::Content {
+                    content: text(&output),
+                }]),
+                ..ToolCall::default()
+            })
+        }
+        EventMsg::PlanUpdate(args) => SessionUpdate::Plan {
+            entries: args
+                .plan
+                .iter()
+                .map(|item| PlanEntry {
+                    content: item.step.clone(),
+                    priority: PlanEntryPriority::Medium,
+                    status: match item.status {
+                        StepStatus::Pending => PlanEntryStatus::Pending,
+                        StepStatus::InProgress => PlanEntryStatus::InProgress,
+                        StepStatus::Completed => PlanEntryStatus::Completed,
+                    },
+                })
+                .collect(),
+        },
+        _ => return None,
+    };
+    Some(update)
+}
+
+pub(crate) fn exec_tool_call(call_id: &str, command: &[String], cwd: &Path) -> ToolCall {
+    ToolCall {
+        tool_call_id: call_id.to_string(),
+        title: Some(command.join(" ")),
+        kind: Some(ToolKind::Execute),
+        locations: Some(vec![ToolCallLocation {
+            path: cwd.to_path_buf(),
+        }]),
+        raw_input: Some(serde_json::json!({ "command": command, "cwd": cwd })),
+        ..ToolCall::default()
+    }
+}
+
+pub(crate) fn patch_tool_call(call_id: &str, changes: &HashMap<PathBuf, FileChange>) -> ToolCall {
+    let mut changes: Vec<_> = changes.iter().collect();
+    changes.sort_by(|(a, _), (b, _)| a.cmp(b));
+    let title = changes
+        .iter()
+        .map(|(path, _)| path.display().to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let content = changes
+        .iter()
+        .map(|(path, change)| match change {
+            FileChange::Add { content } => ToolCallContent::Diff {
+                path: path.to_path_buf(),
+                old_text: None,
+                new_text: content.clone(),
+            },
+            FileChange::Delete { content } => ToolCallContent::Diff {
+                path: path.to_path_buf(),
+                old_text: Some(content.clone()),
+                new_text: String::new(),
+            },
+            // Only the diff of an update is known here, not the whole file.
+            FileChange::Update { unified_diff, .. } => ToolCallContent::Content {
+                content: text(&format!("{}\n{unified_diff}", path.display())),
+            },
+            FileChange::Binary {
+                size,
+                previous_size,
+            } => ToolCallContent::Content {
+                content: text(&format!(
+                    "{}\n{}",
+                    path.display(),
+                    FileChange::binary_summary(*size, *previous_size)
+                )),
+            },
+        })
+        .collect();
+    ToolCall {
+        tool_call_id: call_id.to_string(),
+        title: Some(format!("Edit {title}")),
+        kind: Some(ToolKind::Edit),
+        content: Some(content),
+        locations: Some(
+            changes
+                .iter()
+                .map(|(path, _)| ToolCallLocation {
+                    path: path.to_path_buf(),
+                })
+                .collect(),
+        ),
+        ..ToolCall::default()
+    }
+}
+
+fn mcp_tool_call(call_id: &str, invocation: &McpInvocation) -> ToolCall {
+    ToolCall {
+        tool_call_id: call_id.to_string(),
+        title: Some(format!("{}.{}", invocation.server, invocation.tool)),
+        kind: Some(ToolKind::Other),
+        raw_input: invocation.arguments.clone(),
+        ..ToolCall::default()
+    }
+}
+
+/// The choices offered for every approval request, in the order shown.
+pub(crate) fn permission_options() -> Vec<PermissionOption> {
+    [
+        ("allow_once", "Allow", PermissionOptionKind::AllowOnce),
+        (
+            "allow_always",
+            "Allow for this session",
+            PermissionOptionKind::AllowAlways,
+        ),
+        ("reject_once", "Reject", PermissionOptionKind::RejectOnce),
+    ]
+    .into_iter()
+    .map(|(option_id, name, kind)| PermissionOption {
+        option_id: option_id.to_string(),
+        name: name.to_string(),
+        kind,
+    })
+    .collect()
+}
+
+/// The review decision for the option the user picked. A cancelled request
+/// (the prompt was cancelled while the question was open) aborts the turn.
+pub(crate) fn review_decision(outcome: &RequestPermissionOutcome) -> ReviewDecision {
+    match outcome {
+        RequestPermissionOutcome::Cancelled => ReviewDecision::Abort,
+        RequestPermissionOutcome::Selected { option_id } => match option_id.as_str() {
+            "allow_once" => ReviewDecision::Approved,
+            "allow_always" => ReviewDecision::ApprovedForSession,
+            _ => ReviewDecision::Denied,
+        },
+    }
+}
+
+fn text(text: &str) -> ContentBlock {
+    ContentBlock::Text {
+        text: text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_core::protocol::AgentMessageDeltaEvent;
+    use codex_core::protocol::ExecCommandBeginEvent;
+    use codex_core::protocol::ExecCommandSource;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn exec_events_become_tool_calls() {
+        let begin = EventMsg::ExecCommandBegin(ExecCommandBeginEvent {
+            call_id: "call-1".to_string(),
+            turn_id: "turn-1".to_string(),
+            command: vec!["cargo".to_string(), "test".to_string()],
+            cwd: PathBuf::from("/repo"),
+            parsed_cmd: Vec::new(),
+            source: ExecCommandSource::Agent,
+            interaction_input: None,
+        });
+        assert_eq!(
+            session_update(&begin),
+            Some(SessionUpdate::ToolCall(ToolCall {
+                tool_call_id: "call-1".to_string(),
+                title: Some("cargo test".to_string()),
+                kind: Some(ToolKind::Execute),
+                status: Some(ToolCallStatus::InProgress),
+                content: None,
+                locations: Some(vec![ToolCallLocation {
+                    path: PathBuf::from("/repo"),
+                }]),
+                raw_input: Some(serde_json::json!({
+                    "command": ["cargo", "test"],
+                    "cwd": "/repo",
+                })),
+            }))
+        );
+
+        let delta = EventMsg::AgentMessageDelta(AgentMessageDeltaEvent {
+            delta: "Done.".to_string(),
+        });
+        assert_eq!(
+            serde_json::to_value(session_update(&delta)).expect("serialize"),
+            serde_json::json!({
+                "sessionUpdate": "agent_message_chunk",
+                "content": { "type": "text", "text": "Done." },
+            })
+        );
+    }
+
+    #[test]
+    fn permission_options_map_to_review_decisions() {
+        let decisions: Vec<ReviewDecision> = permission_options()
+            .into_iter()
+            .map(|option| {
+                review_decision(&RequestPermissionOutcome::Selected {
+                    option_id: option.option_id,
+                })
+            })
+            .collect();
+        assert_eq!(
+            decisions,
+            vec![
+                ReviewDecision::Approved,
+                ReviewDecision::ApprovedForSession,
+                ReviewDecision::Denied,
+            ]
+        );
+        assert_eq!(
+            review_decision(&RequestPermissionOutcome::Cancelled),
+            ReviewDecision::Abort
+        );
+    }
+}
@@ -0,0 +1,117 @@
+//! Agent Client Protocol (ACP) agent for Codex.
+//!
+//! Editors that speak ACP, such as Zed, start `codex acp` and talk to it over
+//! stdio. Each ACP session is a Codex conversation: prompts become user turns,
+//! agent output and tool calls are streamed back as `session/update`
+//! notifications, approval requests are asked with
+//! `session/request_permission`, and patches are written through the
+//! editor's `fs/write_text_file` when it offers one.
+#![deny(clippy::print_stdout, clippy::print_stderr)]
+
+use std::io::ErrorKind;
+use std::io::Result as IoResult;
+use std::path::PathBuf;
+
+use codex_common::CliConfigOverrides;
+use mcp_types::JSONRPCMessage;
+use tokio::io::AsyncBufReadExt;
+use tokio::io::AsyncWriteExt;
+use tokio::io::BufReader;
+use tokio::io::{self};
+use tokio::sync::mpsc;
+use tracing::debug;
+use tracing::error;
+use tracing::info;
+use tracing_subscriber::EnvFilter;
+
+mod agent;
+mod client_fs;
+mod error_code;
+mod events;
+mod outgoing;
+mod schema;
+
+use crate::agent::Agent;
+use crate::outgoing::OutgoingMessageSender;
+
+/// Size of the bounded channel for messages read from stdin.
+const CHANNEL_CAPACITY: usize = 128;
+
+pub async fn run_main(
+    codex_linux_sandbox_exe: Option<PathBuf>,
+    cli_config_overrides: CliConfigOverrides,
+) -> IoResult<()> {
+    // Stdout carries the protocol, so logs go to stderr. Users can control
+    // the log level with `RUST_LOG`.
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .with_env_filter(EnvFilter::from_default_env())
+        .init();
+
+    // Reject malformed `-c` overrides up front rather than on the first
+    // `session/new`.
+    cli_config_overrides.parse_overrides().map_err(|e| {
+        std::io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("error parsing -c overrides: {e}"),
+        )
+    })?;
+
+    let (incoming_tx, mut incoming_rx) = mpsc::channel::<JSONRPCMessage>(CHANNEL_CAPACITY);
+    let (outgoing_tx, mut outgoing_rx) = mpsc::unbounded_channel::<JSONRPCMessage>();
+
+    // Task: read from stdin, push to `incoming_tx`.
+    let stdin_reader_handle = tokio::spawn(async move {
+        let mut lines = BufReader::new(io::stdin()).lines();
+        while let Some(line) = lines.next_line().await.unwrap_or_default() {
+            match serde_json::from_str::<JSONRPCMessage>(&line) {
+                Ok(msg) => {
+                    if incoming_tx.send(msg).await.is_err() {
+                        break;
+                    }
+                }
+                Err(e) => error!("Failed to deserialize JSONRPCMessage: {e}"),
+            }
+        }
+        debug!("stdin reader finished (EOF)");
+    });
+
+    // Task: process incoming messages.
+    let processor_handle = tokio::spawn(async move {
+        let mut agent = Agent::new(
+            OutgoingMessageSender::new(outgoing_tx),
+            codex_linux_sandbox_exe,
+            cli_config_overrides,
+        );
+        while let Some(msg) = incoming_rx.recv().await {
+            match msg {
+                JSONRPCMessage::Request(r) => agent.process_request(r).await,
+                JSONRPCMessage::Response(r) => agent.process_response(r).await,
+                JSONRPCMessage::Notification(n) => agent.process_notification(n).await,
+                JSONRPCMessage::Error(e) => agent.process_error(e).await,
+            }
+        }
+        info!("processor task exited (channel closed)");
+    });
+
+    // Task: write outgoing messages to stdout.
+    let stdout_writer_handle = tokio::spawn(async move {
+        let mut stdout = io::stdout();
+        while let Some(msg) = outgoing_rx.recv().await {
+            match serde_json::to_string(&msg) {
+                Ok(json) => {
+                    if let Err(e) = stdout.write_all(format!("{json}\n").as_bytes()).await {
+                        error!("Failed to write to stdout: {e}");
+                        break;
+                    }
+                }
+                Err(e) => error!("Failed to serialize JSONRPCMessage: {e}"),
+            }
+        }
+        info!("stdout writer exited (channel closed)");
+    });
+
+    let _ = tokio::join!(stdin_reader_handle, processor_handle, stdout_writer_handle);
+
+    Ok(())
+}
@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+use std::sync::atomic::AtomicI64;
+use std::sync::atomic::Ordering;
+
+use mcp_types::JSONRPC_VERSION;
+use mcp_types::JSONRPCError;
+use mcp_types::JSONRPCErrorError;
+use mcp_types::JSONRPCMessage;
+use mcp_types::JSONRPCNotification;
+use mcp_types::JSONRPCRequest;
+use mcp_types::JSONRPCResponse;
+use mcp_types::RequestId;
+use serde::Serialize;
+use tokio::sync::Mutex;
+use tokio::sync::mpsc;
+use tokio::sync::oneshot;
+use tracing::warn;
+
+use crate::error_code::INTERNAL_ERROR_CODE;
+
+/// What the client answered to one of our requests.
+pub(crate) type ClientResult = Result<serde_json::Value, JSONRPCErrorError>;
+
+/// Sends messages to the client and routes its answers to our requests.
+pub(crate) struct OutgoingMessageSender {
+    next_request_id: AtomicI64,
+    sender: mpsc::UnboundedSender<JSONRPCMessage>,
+    request_id_to_callback: Mutex<HashMap<RequestId, oneshot::Sender<ClientResult>>>,
+}
+
+impl OutgoingMessageSender {
+    pub(crate) fn new(sender: mpsc::UnboundedSender<JSONRPCMessage>) -> Self {
+        Self {
+            next_request_id: AtomicI64::new(0),
+            sender,
+            request_id_to_callback: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Send a request to the client and wait for its answer. Returns an error
+    /// if the client answers with one or the connection goes away first.
+    pub(crate) async fn request<P: Serialize>(&self, method: &str, params: P) -> ClientResult {
+        let id = RequestId::Integer(self.next_request_id.fetch_add(1, Ordering::Relaxed));
+        let (tx, rx) = oneshot::channel();
+        self.request_id_to_callback
+            .lock()
+            .await
+            .insert(id.clone(), tx);
+
+        let _ = self.sender.send(JSONRPCMessage::Request(JSONRPCRequest {
+            jsonrpc: JSONRPC_VERSION.into(),
+            id,
+            method: method.to_string(),
+            params: serde_json::to_value(params).ok(),
+        }));
+        rx.await.unwrap_or_else(|_| {
+            Err(JSONRPCErrorError {
+                code: INTERNAL_ERROR_CODE,
+                message: "connection closed before the client answered".to_string(),
+                data: None,
+            })
+        })
+    }
+
+    pub(crate) async fn notify_client_response(&self, id: RequestId, result: ClientResult) {
+        let callback = self.request_id_to_callback.lock().await.remove(&id);
+        match callback {
+            Some(callback) => {
+                if callback.send(result).is_err() {
+                    warn!("nobody is waiting for the answer to request {id:?}");
+                }
+            }
+            None => warn!("could not find callback for {id:?}"),
+        }
+    }
+
+    pub(crate) fn send_response<T: Serialize>(&self, id: RequestId, response: T) {
+        match serde_json::to_value(response) {
+            Ok(result) => {
+                let _ = self.sender.send(JSONRPCMessage::Response(JSONRPCResponse {
+                    jsonrpc: JSONRPC_VERSION.into(),
+                    id,
+                    result,
+                }));
+            }
+            Err(err) => self.send_error(
+                id,
+                INTERNAL_ERROR_CODE,
+                format!("failed to serialize response: {err}"),
+            ),
+        }
+    }
+
+    pub(crate) fn send_error(&self, id: RequestId, code: i64, message: String) {
+        let _ = self.sender.send(JSONRPCMessage::Error(JSONRPCError {
+            jsonrpc: JSONRPC_VERSION.into(),
+            id,
+            error: JSONRPCErrorError {
+                code,
+                message,
+                data: None,
+            },
+        }));
+    }
+
+    pub(crate) fn send_notification<P: Serialize>(&self, method: &str, params: P) {
+        let _ = self
+            .sender
+            .send(JSONRPCMessage::Notification(JSONRPCNotification {
+                jsonrpc: JSONRPC_VERSION.into(),
+                method: method.to_string(),
+                params: serde_json::to_value(params).ok(),
+            }));
+    }
+}
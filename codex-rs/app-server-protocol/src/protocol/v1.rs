@@ -12,11 +12,13 @@ use codex_protocol::parse_command::ParsedCommand;
 use codex_protocol::protocol::AskForApproval;
 use codex_protocol::protocol::EventMsg;
 use codex_protocol::protocol::FileChange;
+use codex_protocol::protocol::PatchHunk;
 use codex_protocol::protocol::ReviewDecision;
 use codex_protocol::protocol::SandboxCommandAssessment;
 use codex_protocol::protocol::SandboxPolicy;
 use codex_protocol::protocol::SessionSource;
 use codex_protocol::protocol::TurnAbortReason;
+use codex_protocol::protocol::UserChange;
 use schemars::JsonSchema;
 use serde::Deserialize;
 use serde::Serialize;
@@ -208,12 +210,23 @@ pub struct ApplyPatchApprovalParams {
     /// When set, the agent is asking the user to allow writes under this root
     /// for the remainder of the session (unclear if this is honored today).
     pub grant_root: Option<PathBuf>,
+    /// The patch split into hunks that can be answered one by one with
+    /// `hunkDecisions`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hunks: Vec<PatchHunk>,
+    /// Uncommitted changes the user made to files the patch would modify.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub user_changes: Vec<UserChange>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, JsonSchema, TS)]
 #[serde(rename_all = "camelCase")]
 pub struct ApplyPatchApprovalResponse {
     pub decision: ReviewDecision,
+    /// One decision per entry of the request's `hunks`. When set, only the
+    /// approved hunks are applied and `decision` is ignored.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hunk_decisions: Option<Vec<ReviewDecision>>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, JsonSchema, TS)]
@@ -223,6 +223,7 @@ impl SandboxCommandAssessment {
         CoreSandboxCommandAssessment {
             description: self.description,
             risk_level: self.risk_level.to_core(),
+            reasons: Vec::new(),
         }
     }
 }
@@ -1071,6 +1072,18 @@ pub struct ReasoningTextDeltaNotification {
 pub struct CommandExecutionOutputDeltaNotification {
     pub item_id: String,
     pub delta: String,
+    /// The stream the command wrote `delta` to.
+    pub stream: CommandExecutionOutputStream,
+    /// Orders the deltas of the item across both streams, starting at 0.
+    pub seq: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, JsonSchema, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export_to = "v2/")]
+pub enum CommandExecutionOutputStream {
+    Stdout,
+    Stderr,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, JsonSchema, TS)]
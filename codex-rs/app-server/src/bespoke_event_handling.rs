@@ -11,6 +11,7 @@ use codex_app_server_protocol::ApprovalDecision;
 use codex_app_server_protocol::CodexErrorInfo as V2CodexErrorInfo;
 use codex_app_server_protocol::CommandAction as V2ParsedCommand;
 use codex_app_server_protocol::CommandExecutionOutputDeltaNotification;
+use codex_app_server_protocol::CommandExecutionOutputStream;
 use codex_app_server_protocol::CommandExecutionRequestApprovalParams;
 use codex_app_server_protocol::CommandExecutionRequestApprovalResponse;
 use codex_app_server_protocol::CommandExecutionStatus;
@@ -47,6 +48,7 @@ use codex_core::protocol::Event;
 use codex_core::protocol::EventMsg;
 use codex_core::protocol::ExecApprovalRequestEvent;
 use codex_core::protocol::ExecCommandEndEvent;
+use codex_core::protocol::ExecOutputStream;
 use codex_core::protocol::FileChange as CoreFileChange;
 use codex_core::protocol::McpToolCallBeginEvent;
 use codex_core::protocol::McpToolCallEndEvent;
@@ -84,6 +86,8 @@ pub(crate) async fn apply_bespoke_event_handling(
             changes,
             reason,
             grant_root,
+            hunks,
+            user_changes,
         }) => match api_version {
             ApiVersion::V1 => {
                 let params = ApplyPatchApprovalParams {
@@ -92,6 +96,8 @@ pub(crate) async fn apply_bespoke_event_handling(
                     file_changes: changes.clone(),
                     reason,
                     grant_root,
+                    hunks,
+                    user_changes,
                 };
                 let rx = outgoing
                     .send_request(ServerRequestPayload::ApplyPatchApproval(params))
@@ -156,6 +162,9 @@ pub(crate) async fn apply_bespoke_event_handling(
             reason,
             risk,
             parsed_cmd,
+            scripts: _,
+            expansions: _,
+            trust_prefix: _,
         }) => match api_version {
             ApiVersion::V1 => {
                 let params = ExecCommandApprovalParams {
@@ -415,6 +424,11 @@ pub(crate) async fn apply_bespoke_event_handling(
             let notification = CommandExecutionOutputDeltaNotification {
                 item_id: exec_command_output_delta_event.call_id.clone(),
                 delta: String::from_utf8_lossy(&exec_command_output_delta_event.chunk).to_string(),
+                stream: match exec_command_output_delta_event.stream {
+                    ExecOutputStream::Stdout => CommandExecutionOutputStream::Stdout,
+                    ExecOutputStream::Stderr => CommandExecutionOutputStream::Stderr,
+                },
+                seq: exec_command_output_delta_event.seq,
             };
             outgoing
                 .send_server_notification(ServerNotification::CommandExecutionOutputDelta(
@@ -639,16 +653,21 @@ async fn on_patch_approval_response(
             error!("failed to deserialize ApplyPatchApprovalResponse: {err}");
             ApplyPatchApprovalResponse {
                 decision: ReviewDecision::Denied,
+                hunk_decisions: None,
             }
         });
 
-    if let Err(err) = codex
-        .submit(Op::PatchApproval {
+    let op = match response.hunk_decisions {
+        Some(decisions) => Op::PatchHunkApproval {
+            id: event_id,
+            decisions,
+        },
+        None => Op::PatchApproval {
             id: event_id,
             decision: response.decision,
-        })
-        .await
-    {
+        },
+    };
+    if let Err(err) = codex.submit(op).await {
         error!("failed to submit PatchApproval: {err}");
     }
 }
@@ -731,6 +750,11 @@ fn map_patch_change_kind(change: &CoreFileChange) -> V2PatchChangeKind {
         CoreFileChange::Update { move_path, .. } => V2PatchChangeKind::Update {
             move_path: move_path.clone(),
         },
+        CoreFileChange::Binary {
+            previous_size: None,
+            ..
+        } => V2PatchChangeKind::Add,
+        CoreFileChange::Binary { .. } => V2PatchChangeKind::Update { move_path: None },
     }
 }
 
@@ -738,6 +762,10 @@ fn format_file_change_diff(change: &CoreFileChange) -> String {
     match change {
         CoreFileChange::Add { content } => content.clone(),
         CoreFileChange::Delete { content } => content.clone(),
+        CoreFileChange::Binary {
+            size,
+            previous_size,
+        } => CoreFileChange::binary_summary(*size, *previous_size),
         CoreFileChange::Update {
             unified_diff,
             move_path,
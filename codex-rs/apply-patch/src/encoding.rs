@@ -0,0 +1,108 @@
+//! Text encoding of the files being patched.
+//!
+//! Source files are not always UTF-8: older projects keep Shift_JIS or
+//! Latin-1 files around. Such files are decoded with a detected encoding so
+//! that the patch can be matched against their text, and written back in the
+//! same encoding so that applying a patch does not silently re-encode them.
+
+use std::borrow::Cow;
+
+use chardetng::EncodingDetector;
+use encoding_rs::Encoding;
+use encoding_rs::UTF_8;
+
+/// The encoding a text file was read in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileEncoding(&'static Encoding);
+
+impl FileEncoding {
+    pub fn utf8() -> Self {
+        FileEncoding(UTF_8)
+    }
+
+    /// The WHATWG label of the encoding, e.g. `Shift_JIS` or `windows-1252`.
+    pub fn name(self) -> &'static str {
+        self.0.name()
+    }
+
+    pub fn is_utf8(self) -> bool {
+        self.0 == UTF_8
+    }
+
+    /// Decode `bytes`, detecting a legacy encoding when they are not valid
+    /// UTF-8. Returns `None` for contents that do not look like text in any
+    /// encoding, such as binary files.
+    pub fn decode(bytes: &[u8]) -> Option<(Cow<'_, str>, Self)> {
+        if let Ok(text) = std::str::from_utf8(bytes) {
+            return Some((Cow::Borrowed(text), Self::utf8()));
+        }
+        if bytes.contains(&0) {
+            return None;
+        }
+        let mut detector = EncodingDetector::new();
+        detector.feed(bytes, true);
+        let encoding = detector.guess(None, false);
+        let (text, had_errors) = encoding.decode_without_bom_handling(bytes);
+        if had_errors {
+            return None;
+        }
+        Some((text, FileEncoding(encoding)))
+    }
+
+    /// Encode `text` back into this encoding, or `None` if it contains
+    /// characters the encoding cannot represent.
+    pub fn encode(self, text: &str) -> Option<Cow<'_, [u8]>> {
+        if self.is_utf8() {
+            return Some(Cow::Borrowed(text.as_bytes()));
+        }
+        let (bytes, _, had_unmappable) = self.0.encode(text);
+        (!had_unmappable).then_some(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn utf8_is_borrowed_as_is() {
+        let (text, encoding) = FileEncoding::decode("caf\u{e9}\n".as_bytes()).expect("text");
+        assert_eq!(
+            (text.as_ref(), encoding),
+            ("caf\u{e9}\n", FileEncoding::utf8())
+        );
+    }
+
+    #[test]
+    fn legacy_encodings_round_trip() {
+        // "こんにちは、世界" in Shift_JIS.
+        let shift_jis = b"\x82\xb1\x82\xf1\x82\xc9\x82\xbf\x82\xcd\x81\x41\x90\xa2\x8a\x45\n";
+        let (text, encoding) = FileEncoding::decode(shift_jis).expect("text");
+        assert_eq!(
+            (text.as_ref(), encoding.name()),
+            ("こんにちは、世界\n", "Shift_JIS")
+        );
+        assert_eq!(
+            encoding.encode(&text).as_deref(),
+            Some(shift_jis.as_slice())
+        );
+
+        let latin1 = b"na\xefve caf\xe9 cr\xe8me br\xfbl\xe9e\n";
+        let (text, encoding) = FileEncoding::decode(latin1).expect("text");
+        assert_eq!(
+            (text.as_ref(), encoding.name()),
+            (
+                "na\u{ef}ve caf\u{e9} cr\u{e8}me br\u{fb}l\u{e9}e\n",
+                "windows-1252"
+            )
+        );
+        assert_eq!(encoding.encode(&text).as_deref(), Some(latin1.as_slice()));
+        assert_eq!(encoding.encode("caf\u{e9} \u{2603}"), None);
+    }
+
+    #[test]
+    fn binary_is_not_text() {
+        assert_eq!(FileEncoding::decode(b"\x89PNG\r\n\x1a\n\x00\x00"), None);
+    }
+}
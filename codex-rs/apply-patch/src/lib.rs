@@ -1,6 +1,11 @@
+mod encoding;
+mod merge;
 mod parser;
+mod review;
 mod seek_sequence;
 mod standalone_executable;
+mod text_format;
+mod transaction;
 
 use std::collections::HashMap;
 use std::path::Path;
@@ -8,13 +13,18 @@ use std::path::PathBuf;
 use std::str::Utf8Error;
 use std::sync::LazyLock;
 
-use anyhow::Context;
 use anyhow::Result;
+pub use encoding::FileEncoding;
+pub use merge::MergedPatch;
+pub use merge::merge_drifted_patch;
 pub use parser::Hunk;
+pub use parser::MAX_BINARY_FILE_BYTES;
 pub use parser::ParseError;
 use parser::ParseError::*;
 pub use parser::UpdateFileChunk;
 pub use parser::parse_patch;
+pub use review::ReviewHunk;
+pub use review::binary_summary;
 use similar::TextDiff;
 use thiserror::Error;
 use tree_sitter::LanguageError;
@@ -25,6 +35,10 @@ use tree_sitter::StreamingIterator;
 use tree_sitter_bash::LANGUAGE as BASH;
 
 pub use standalone_executable::main;
+pub use text_format::LineEnding;
+pub use text_format::TextFormat;
+pub use text_format::read_text_file;
+pub use text_format::write_text_file;
 
 /// Detailed instructions for gpt-4.1 on how to use the `apply_patch` tool.
 pub const APPLY_PATCH_TOOL_INSTRUCTIONS: &str = include_str!("../apply_patch_tool_instructions.md");
@@ -145,6 +159,12 @@ pub enum ApplyPatchFileChange {
         /// new_content that will result after the unified_diff is applied.
         new_content: String,
     },
+    /// A file created or replaced with raw bytes.
+    Binary {
+        content: Vec<u8>,
+        /// Size of the file being replaced, `None` when the file is created.
+        previous_size: Option<u64>,
+    },
 }
 
 #[derive(Debug, PartialEq)]
@@ -259,55 +279,10 @@ pub fn maybe_parse_apply_patch_verified(argv: &[String], cwd: &Path) -> MaybeApp
                     }
                 })
                 .unwrap_or_else(|| cwd.to_path_buf());
-            let mut changes = HashMap::new();
-            for hunk in hunks {
-                let path = hunk.resolve_path(&effective_cwd);
-                match hunk {
-                    Hunk::AddFile { contents, .. } => {
-                        changes.insert(path, ApplyPatchFileChange::Add { content: contents });
-                    }
-                    Hunk::DeleteFile { .. } => {
-                        let content = match std::fs::read_to_string(&path) {
-                            Ok(content) => content,
-                            Err(e) => {
-                                return MaybeApplyPatchVerified::CorrectnessError(
-                                    ApplyPatchError::IoError(IoError {
-                                        context: format!("Failed to read {}", path.display()),
-                                        source: e,
-                                    }),
-                                );
-                            }
-                        };
-                        changes.insert(path, ApplyPatchFileChange::Delete { content });
-                    }
-                    Hunk::UpdateFile {
-                        move_path, chunks, ..
-                    } => {
-                        let ApplyPatchFileUpdate {
-                            unified_diff,
-                            content: contents,
-                        } = match unified_diff_from_chunks(&path, &chunks) {
-                            Ok(diff) => diff,
-                            Err(e) => {
-                                return MaybeApplyPatchVerified::CorrectnessError(e);
-                            }
-                        };
-                        changes.insert(
-                            path,
-                            ApplyPatchFileChange::Update {
-                                unified_diff,
-                                move_path: move_path.map(|p| effective_cwd.join(p)),
-                                new_content: contents,
-                            },
-                        );
-                    }
-                }
+            match verify_hunks(patch, hunks, effective_cwd) {
+                Ok(action) => MaybeApplyPatchVerified::Body(action),
+                Err(e) => MaybeApplyPatchVerified::CorrectnessError(e),
             }
-            MaybeApplyPatchVerified::Body(ApplyPatchAction {
-                changes,
-                patch,
-                cwd: effective_cwd,
-            })
         }
         MaybeApplyPatch::ShellParseError(e) => MaybeApplyPatchVerified::ShellParseError(e),
         MaybeApplyPatch::PatchParseError(e) => MaybeApplyPatchVerified::CorrectnessError(e.into()),
@@ -315,6 +290,70 @@ pub fn maybe_parse_apply_patch_verified(argv: &[String], cwd: &Path) -> MaybeApp
     }
 }
 
+/// Resolve the paths in `hunks` against `cwd` and compute the change each hunk
+/// makes, failing if the patch cannot be applied to the files as they are.
+fn verify_hunks(
+    patch: String,
+    hunks: Vec<Hunk>,
+    cwd: PathBuf,
+) -> std::result::Result<ApplyPatchAction, ApplyPatchError> {
+    let mut changes = HashMap::new();
+    for hunk in hunks {
+        let path = hunk.resolve_path(&cwd);
+        match hunk {
+            Hunk::AddFile { contents, .. } => {
+                changes.insert(path, ApplyPatchFileChange::Add { content: contents });
+            }
+            Hunk::DeleteFile { .. } => {
+                let content = match read_text_file(&path) {
+                    Ok((content, _)) => content,
+                    Err(e) => {
+                        return Err(ApplyPatchError::IoError(IoError {
+                            context: format!("Failed to read {}", path.display()),
+                            source: e,
+                        }));
+                    }
+                };
+                changes.insert(path, ApplyPatchFileChange::Delete { content });
+            }
+            Hunk::BinaryFile { contents, .. } => {
+                let previous_size = std::fs::metadata(&path)
+                    .ok()
+                    .filter(std::fs::Metadata::is_file)
+                    .map(|metadata| metadata.len());
+                changes.insert(
+                    path,
+                    ApplyPatchFileChange::Binary {
+                        content: contents,
+                        previous_size,
+                    },
+                );
+            }
+            Hunk::UpdateFile {
+                move_path, chunks, ..
+            } => {
+                let ApplyPatchFileUpdate {
+                    unified_diff,
+                    content: contents,
+                } = unified_diff_from_chunks(&path, &chunks)?;
+                changes.insert(
+                    path,
+                    ApplyPatchFileChange::Update {
+                        unified_diff,
+                        move_path: move_path.map(|p| cwd.join(p)),
+                        new_content: contents,
+                    },
+                );
+            }
+        }
+    }
+    Ok(ApplyPatchAction {
+        changes,
+        patch,
+        cwd,
+    })
+}
+
 /// Extract the heredoc body (and optional `cd` workdir) from a `bash -lc` script
 /// that invokes the apply_patch tool using a heredoc.
 ///
@@ -522,6 +561,8 @@ pub fn apply_hunks(
                 // The file is being added, so it doesn't exist yet.
                 None
             }
+            // The file is written whole, whether or not it exists.
+            Hunk::BinaryFile { .. } => None,
             Hunk::DeleteFile { path } => Some(path.as_path()),
             Hunk::UpdateFile {
                 path, move_path, ..
@@ -572,67 +613,12 @@ pub struct AffectedPaths {
 }
 
 /// Apply the hunks to the filesystem, returning which files were added, modified, or deleted.
-/// Returns an error if the patch could not be applied.
+/// Returns an error if the patch could not be applied, in which case no file is left changed.
 fn apply_hunks_to_files(hunks: &[Hunk]) -> anyhow::Result<AffectedPaths> {
     if hunks.is_empty() {
         anyhow::bail!("No files were modified.");
     }
-
-    let mut added: Vec<PathBuf> = Vec::new();
-    let mut modified: Vec<PathBuf> = Vec::new();
-    let mut deleted: Vec<PathBuf> = Vec::new();
-    for hunk in hunks {
-        match hunk {
-            Hunk::AddFile { path, contents } => {
-                if let Some(parent) = path.parent()
-                    && !parent.as_os_str().is_empty()
-                {
-                    std::fs::create_dir_all(parent).with_context(|| {
-                        format!("Failed to create parent directories for {}", path.display())
-                    })?;
-                }
-                std::fs::write(path, contents)
-                    .with_context(|| format!("Failed to write file {}", path.display()))?;
-                added.push(path.clone());
-            }
-            Hunk::DeleteFile { path } => {
-                std::fs::remove_file(path)
-                    .with_context(|| format!("Failed to delete file {}", path.display()))?;
-                deleted.push(path.clone());
-            }
-            Hunk::UpdateFile {
-                path,
-                move_path,
-                chunks,
-            } => {
-                let AppliedPatch { new_contents, .. } =
-                    derive_new_contents_from_chunks(path, chunks)?;
-                if let Some(dest) = move_path {
-                    if let Some(parent) = dest.parent()
-                        && !parent.as_os_str().is_empty()
-                    {
-                        std::fs::create_dir_all(parent).with_context(|| {
-                            format!("Failed to create parent directories for {}", dest.display())
-                        })?;
-                    }
-                    std::fs::write(dest, new_contents)
-                        .with_context(|| format!("Failed to write file {}", dest.display()))?;
-                    std::fs::remove_file(path)
-                        .with_context(|| format!("Failed to remove original {}", path.display()))?;
-                    modified.push(dest.clone());
-                } else {
-                    std::fs::write(path, new_contents)
-                        .with_context(|| format!("Failed to write file {}", path.display()))?;
-                    modified.push(path.clone());
-                }
-            }
-        }
-    }
-    Ok(AffectedPaths {
-        added,
-        modified,
-        deleted,
-    })
+    transaction::apply_hunks_atomically(hunks)
 }
 
 struct AppliedPatch {
@@ -646,8 +632,8 @@ fn derive_new_contents_from_chunks(
     path: &Path,
     chunks: &[UpdateFileChunk],
 ) -> std::result::Result<AppliedPatch, ApplyPatchError> {
-    let original_contents = match std::fs::read_to_string(path) {
-        Ok(contents) => contents,
+    let (original_contents, _) = match read_text_file(path) {
+        Ok(read) => read,
         Err(err) => {
             return Err(ApplyPatchError::IoError(IoError {
                 context: format!("Failed to read file to update {}", path.display()),
@@ -1168,6 +1154,51 @@ PATCH"#,
         assert_eq!(contents, "foo\nbaz\n");
     }
 
+    #[test]
+    fn test_update_file_hunk_preserves_legacy_encoding() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("latin1.txt");
+        fs::write(&path, b"caf\xe9 au lait\nna\xefve cr\xe8me br\xfbl\xe9e\n").unwrap();
+        let patch = wrap_patch(&format!(
+            r#"*** Update File: {}
+@@
+-café au lait
++café noir"#,
+            path.display()
+        ));
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        apply_patch(&patch, &mut stdout, &mut stderr).unwrap();
+        assert_eq!(
+            fs::read(&path).unwrap(),
+            b"caf\xe9 noir\nna\xefve cr\xe8me br\xfbl\xe9e\n".to_vec()
+        );
+    }
+
+    #[test]
+    fn test_update_file_hunk_preserves_line_endings() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("mixed.txt");
+        fs::write(&path, "one\r\ntwo\nthree\r\nfour\r\n").unwrap();
+        let patch = wrap_patch(&format!(
+            r#"*** Update File: {}
+@@
+ one
+ two
+-three
++3
++3.5"#,
+            path.display()
+        ));
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        apply_patch(&patch, &mut stdout, &mut stderr).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "one\r\ntwo\n3\r\n3.5\r\nfour\r\n"
+        );
+    }
+
     #[test]
     fn test_update_file_hunk_can_move_file() {
         let dir = tempdir().unwrap();
@@ -0,0 +1,195 @@
+//! Three-way merge of a patch whose context no longer matches.
+//!
+//! A patch is written against the contents the model last saw. When the file
+//! has changed since, its chunks may not find their context. If those earlier
+//! contents are known, the patch is applied to them instead, and the result is
+//! merged with the current contents, with the earlier contents as the common
+//! ancestor. A clean merge becomes a new patch against the current contents;
+//! overlapping edits are reported with conflict markers.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+use similar::Algorithm;
+use similar::capture_diff_slices;
+use similar::group_diff_ops;
+
+use crate::Hunk;
+use crate::UpdateFileChunk;
+use crate::apply_chunks_to_contents;
+use crate::parse_patch;
+use crate::read_text_file;
+use crate::review::format_patch;
+
+/// Unchanged lines kept around each chunk of a rebuilt patch.
+const CONTEXT_LINES: usize = 3;
+
+/// A patch brought up to date with files that changed after it was written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MergedPatch {
+    /// Every drifted file merged cleanly; `patch` applies to the files as
+    /// they are now.
+    Clean {
+        patch: String,
+        /// The files whose hunks were merged, in patch order.
+        merged: Vec<PathBuf>,
+    },
+    /// The merged contents of every file whose edits overlap, with conflict
+    /// markers, in patch order.
+    Conflicts(Vec<(PathBuf, String)>),
+}
+
+/// Merge the updated files of `patch` whose chunks no longer apply, using
+/// `bases`, the contents the patch was written against, keyed by absolute
+/// path. Returns `None` when the patch does not parse, when every chunk
+/// applies as is, or when a file that does not apply has no base or does not
+/// apply to its base either.
+pub fn merge_drifted_patch(
+    patch: &str,
+    cwd: &Path,
+    bases: &HashMap<PathBuf, String>,
+) -> Option<MergedPatch> {
+    let hunks = parse_patch(patch).ok()?.hunks;
+    let mut rebuilt = Vec::with_capacity(hunks.len());
+    let mut merged = Vec::new();
+    let mut conflicts = Vec::new();
+    for hunk in hunks {
+        let Hunk::UpdateFile {
+            path,
+            move_path,
+            chunks,
+        } = &hunk
+        else {
+            rebuilt.push(hunk);
+            continue;
+        };
+        let resolved = hunk.resolve_path(cwd);
+        let (current, _) = read_text_file(&resolved).ok()?;
+        if apply_chunks_to_contents(&current, &resolved, chunks).is_ok() {
+            rebuilt.push(hunk);
+            continue;
+        }
+        let base = bases.get(&resolved)?;
+        let ours = apply_chunks_to_contents(base, &resolved, chunks).ok()?;
+        match diffy::merge(base, &ours, &current) {
+            Ok(contents) => {
+                rebuilt.push(Hunk::UpdateFile {
+                    path: path.clone(),
+                    move_path: move_path.clone(),
+                    chunks: rebase_chunks(&current, &contents, &resolved),
+                });
+                merged.push(resolved);
+            }
+            Err(marked) => conflicts.push((resolved, marked)),
+        }
+    }
+    if !conflicts.is_empty() {
+        return Some(MergedPatch::Conflicts(conflicts));
+    }
+    if merged.is_empty() {
+        return None;
+    }
+    Some(MergedPatch::Clean {
+        patch: format_patch(&rebuilt),
+        merged,
+    })
+}
+
+/// Chunks turning `current` into `merged`: one per group of changed lines,
+/// or a single chunk replacing the whole file when the groups' context is
+/// not distinctive enough to place them.
+fn rebase_chunks(current: &str, merged: &str, path: &Path) -> Vec<UpdateFileChunk> {
+    let old: Vec<&str> = current.lines().collect();
+    let new: Vec<&str> = merged.lines().collect();
+    let ops = capture_diff_slices(Algorithm::Myers, &old, &new);
+    let chunks: Vec<UpdateFileChunk> = group_diff_ops(ops, CONTEXT_LINES)
+        .iter()
+        .filter_map(|group| {
+            let (first, last) = (group.first()?, group.last()?);
+            let old_range = first.old_range().start..last.old_range().end;
+            let new_range = first.new_range().start..last.new_range().end;
+            Some(UpdateFileChunk {
+                change_context: None,
+                old_lines: to_owned(&old[old_range]),
+                new_lines: to_owned(&new[new_range]),
+                is_end_of_file: false,
+            })
+        })
+        .collect();
+    let places_correctly = !old.is_empty()
+        && apply_chunks_to_contents(current, path, &chunks)
+            .is_ok_and(|applied| applied.lines().eq(merged.lines()));
+    if places_correctly {
+        return chunks;
+    }
+    vec![UpdateFileChunk {
+        change_context: None,
+        old_lines: to_owned(&old),
+        new_lines: to_owned(&new),
+        is_end_of_file: false,
+    }]
+}
+
+fn to_owned(lines: &[&str]) -> Vec<String> {
+    lines.iter().map(ToString::to_string).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::tempdir;
+
+    const BASE: &str = "fn a() {\n    1\n}\n\nfn b() {\n    let x = 1;\n    2\n}\n";
+    const PATCH: &str = "*** Begin Patch\n*** Update File: lib.rs\n\
+                         @@\n fn b() {\n     let x = 1;\n-    2\n+    20\n }\n*** End Patch";
+
+    #[test]
+    fn drifted_context_is_merged_into_a_patch_against_the_current_file() {
+        let dir = tempdir().expect("tempdir");
+        let path = dir.path().join("lib.rs");
+        // `a` and the signature of `b` changed after the model read the file.
+        let current = "fn a() {\n    10\n}\n\nfn b(y: u8) {\n    let x = 1;\n    2\n}\n";
+        std::fs::write(&path, current).expect("write");
+        assert_eq!(
+            merge_drifted_patch(PATCH, dir.path(), &HashMap::new()),
+            None
+        );
+
+        let bases = HashMap::from([(path.clone(), BASE.to_string())]);
+        let Some(MergedPatch::Clean { patch, merged }) =
+            merge_drifted_patch(PATCH, dir.path(), &bases)
+        else {
+            panic!("expected a clean merge");
+        };
+        assert_eq!(merged, vec![path.clone()]);
+        let hunks = parse_patch(&patch).expect("parse").hunks;
+        let Hunk::UpdateFile { chunks, .. } = &hunks[0] else {
+            panic!("expected an update");
+        };
+        assert_eq!(
+            apply_chunks_to_contents(current, &path, chunks).expect("apply"),
+            "fn a() {\n    10\n}\n\nfn b(y: u8) {\n    let x = 1;\n    20\n}\n"
+        );
+    }
+
+    #[test]
+    fn overlapping_edits_are_reported_with_conflict_markers() {
+        let dir = tempdir().expect("tempdir");
+        let path = dir.path().join("lib.rs");
+        let current = "fn a() {\n    1\n}\n\nfn b() {\n    let x = 1;\n    3\n}\n";
+        std::fs::write(&path, current).expect("write");
+        let bases = HashMap::from([(path.clone(), BASE.to_string())]);
+
+        assert_eq!(
+            merge_drifted_patch(PATCH, dir.path(), &bases),
+            Some(MergedPatch::Conflicts(vec![(
+                path,
+                "fn a() {\n    1\n}\n\nfn b() {\n    let x = 1;\n<<<<<<< ours\n    20\n\
+                 ||||||| original\n    2\n=======\n    3\n>>>>>>> theirs\n}\n"
+                    .to_string()
+            )]))
+        );
+    }
+}
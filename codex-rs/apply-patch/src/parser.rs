@@ -7,12 +7,14 @@
 //! begin_patch: "*** Begin Patch" LF
 //! end_patch: "*** End Patch" LF?
 //!
-//! hunk: add_hunk | delete_hunk | update_hunk
+//! hunk: add_hunk | delete_hunk | update_hunk | binary_hunk
 //! add_hunk: "*** Add File: " filename LF add_line+
 //! delete_hunk: "*** Delete File: " filename LF
 //! update_hunk: "*** Update File: " filename LF change_move? change?
 //! filename: /(.+)/
 //! add_line: "+" /(.+)/ LF -> line
+//! binary_hunk: "*** Binary File: " filename LF base64_line+
+//! base64_line: "+" /[A-Za-z0-9+\/=]+/ LF
 //!
 //! change_move: "*** Move to: " filename LF
 //! change: (change_context | change_line)+ eof_line?
@@ -22,7 +24,11 @@
 //!
 //! The parser below is a little more lenient than the explicit spec and allows for
 //! leading/trailing whitespace around patch markers.
+//!
+//! A binary hunk creates or replaces a file with the bytes its lines encode,
+//! in standard base64, decoding to at most [`MAX_BINARY_FILE_BYTES`].
 use crate::ApplyPatchArgs;
+use base64::Engine;
 use std::path::Path;
 use std::path::PathBuf;
 
@@ -33,6 +39,7 @@ const END_PATCH_MARKER: &str = "*** End Patch";
 const ADD_FILE_MARKER: &str = "*** Add File: ";
 const DELETE_FILE_MARKER: &str = "*** Delete File: ";
 const UPDATE_FILE_MARKER: &str = "*** Update File: ";
+const BINARY_FILE_MARKER: &str = "*** Binary File: ";
 const MOVE_TO_MARKER: &str = "*** Move to: ";
 const EOF_MARKER: &str = "*** End of File";
 const CHANGE_CONTEXT_MARKER: &str = "@@ ";
@@ -46,6 +53,9 @@ const EMPTY_CHANGE_CONTEXT_MARKER: &str = "@@";
 /// gpt-4.1.
 const PARSE_IN_STRICT_MODE: bool = false;
 
+/// Largest file a binary hunk may write.
+pub const MAX_BINARY_FILE_BYTES: usize = 8 * 1024 * 1024;
+
 #[derive(Debug, PartialEq, Error, Clone)]
 pub enum ParseError {
     #[error("invalid patch: {0}")]
@@ -73,6 +83,11 @@ pub enum Hunk {
         /// should occur later in the file than the previous chunk.
         chunks: Vec<UpdateFileChunk>,
     },
+    /// Create `path`, or replace it, with raw bytes.
+    BinaryFile {
+        path: PathBuf,
+        contents: Vec<u8>,
+    },
 }
 
 impl Hunk {
@@ -81,6 +96,7 @@ impl Hunk {
             Hunk::AddFile { path, .. } => cwd.join(path),
             Hunk::DeleteFile { path } => cwd.join(path),
             Hunk::UpdateFile { path, .. } => cwd.join(path),
+            Hunk::BinaryFile { path, .. } => cwd.join(path),
         }
     }
 }
@@ -273,6 +289,42 @@ fn parse_one_hunk(lines: &[&str], line_number: usize) -> Result<(Hunk, usize), P
             },
             1,
         ));
+    } else if let Some(path) = first_line.strip_prefix(BINARY_FILE_MARKER) {
+        let encoded: Vec<&str> = lines[1..]
+            .iter()
+            .map_while(|line| line.strip_prefix('+'))
+            .map(str::trim)
+            .collect();
+        let invalid = |message: String| InvalidHunkError {
+            message,
+            line_number,
+        };
+        if encoded.is_empty() {
+            return Err(invalid(format!(
+                "Binary file hunk for path '{path}' is empty"
+            )));
+        }
+        let parsed_lines = 1 + encoded.len();
+        let encoded = encoded.concat();
+        let contents = base64::engine::general_purpose::STANDARD
+            .decode(encoded.as_bytes())
+            .map_err(|err| {
+                invalid(format!(
+                    "Binary file for path '{path}' is not valid base64: {err}"
+                ))
+            })?;
+        if contents.len() > MAX_BINARY_FILE_BYTES {
+            return Err(invalid(format!(
+                "Binary file for path '{path}' is larger than {MAX_BINARY_FILE_BYTES} bytes"
+            )));
+        }
+        return Ok((
+            BinaryFile {
+                path: PathBuf::from(path),
+                contents,
+            },
+            parsed_lines,
+        ));
     } else if let Some(path) = first_line.strip_prefix(UPDATE_FILE_MARKER) {
         // Update File
         let mut remaining_lines = &lines[1..];
@@ -331,7 +383,7 @@ fn parse_one_hunk(lines: &[&str], line_number: usize) -> Result<(Hunk, usize), P
 
     Err(InvalidHunkError {
         message: format!(
-            "'{first_line}' is not a valid hunk header. Valid hunk headers: '*** Add File: {{path}}', '*** Delete File: {{path}}', '*** Update File: {{path}}'"
+            "'{first_line}' is not a valid hunk header. Valid hunk headers: '*** Add File: {{path}}', '*** Delete File: {{path}}', '*** Update File: {{path}}', '*** Binary File: {{path}}'"
         ),
         line_number,
     })
@@ -653,7 +705,7 @@ fn test_parse_one_hunk() {
         parse_one_hunk(&["bad"], 234),
         Err(InvalidHunkError {
             message: "'bad' is not a valid hunk header. \
-            Valid hunk headers: '*** Add File: {path}', '*** Delete File: {path}', '*** Update File: {path}'".to_string(),
+            Valid hunk headers: '*** Add File: {path}', '*** Delete File: {path}', '*** Update File: {path}', '*** Binary File: {path}'".to_string(),
             line_number: 234
         })
     );
@@ -739,3 +791,27 @@ fn test_update_file_chunk() {
         ))
     );
 }
+
+#[test]
+fn test_parse_binary_file_hunk() {
+    // "\x89PNG\r\n" split over two lines.
+    assert_eq!(
+        parse_one_hunk(&["*** Binary File: logo.png", "+iVBO", "+Rw0K", "+"], 4),
+        Ok((
+            BinaryFile {
+                path: PathBuf::from("logo.png"),
+                contents: b"\x89PNG\r\n".to_vec(),
+            },
+            4
+        ))
+    );
+    assert_eq!(
+        parse_one_hunk(&["*** Binary File: logo.png", "+ab d"], 4),
+        Err(InvalidHunkError {
+            message: "Binary file for path 'logo.png' is not valid base64: Invalid symbol 32, \
+                      offset 2."
+                .to_string(),
+            line_number: 4
+        })
+    );
+}
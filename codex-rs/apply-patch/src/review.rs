@@ -0,0 +1,296 @@
+//! Hunk-by-hunk review of a patch.
+//!
+//! A patch awaiting approval can be shown as separate hunks so that a reviewer
+//! accepts some of them and rejects the rest. Each added or deleted file is one
+//! hunk, each chunk of an updated file is one hunk, and a rename without
+//! content changes is one hunk. A renamed file that also changes is renamed
+//! only if at least one of its chunks is accepted. The accepted hunks are
+//! turned back into a patch of their own, which is verified like any other
+//! patch before it is applied.
+
+use std::path::PathBuf;
+
+use base64::Engine;
+use similar::TextDiff;
+
+use crate::ApplyPatchAction;
+use crate::ApplyPatchError;
+use crate::ApplyPatchFileChange;
+use crate::Hunk;
+use crate::UpdateFileChunk;
+use crate::parse_patch;
+use crate::unified_diff_from_chunks;
+use crate::verify_hunks;
+
+/// Base64 characters per line of a binary hunk.
+const BASE64_LINE_LEN: usize = 76;
+
+/// One hunk of a pending patch, as shown to the reviewer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReviewHunk {
+    /// Absolute path of the file the hunk changes.
+    pub path: PathBuf,
+    /// Unified diff of this hunk alone; empty for a plain rename.
+    pub unified_diff: String,
+}
+
+impl ApplyPatchAction {
+    /// The hunks of this patch, in the order decisions are given for them.
+    pub fn review_hunks(&self) -> Vec<ReviewHunk> {
+        let Ok(args) = parse_patch(&self.patch) else {
+            return Vec::new();
+        };
+        let mut review = Vec::new();
+        for hunk in &args.hunks {
+            let path = hunk.resolve_path(&self.cwd);
+            match hunk {
+                Hunk::AddFile { contents, .. } => review.push(ReviewHunk {
+                    unified_diff: diff("", contents),
+                    path,
+                }),
+                Hunk::DeleteFile { .. } => {
+                    let content = match self.changes.get(&path) {
+                        Some(ApplyPatchFileChange::Delete { content }) => content.as_str(),
+                        _ => "",
+                    };
+                    review.push(ReviewHunk {
+                        unified_diff: diff(content, ""),
+                        path,
+                    });
+                }
+                Hunk::BinaryFile { contents, .. } => {
+                    let previous_size = match self.changes.get(&path) {
+                        Some(ApplyPatchFileChange::Binary { previous_size, .. }) => *previous_size,
+                        _ => None,
+                    };
+                    review.push(ReviewHunk {
+                        unified_diff: binary_summary(contents.len(), previous_size),
+                        path,
+                    });
+                }
+                Hunk::UpdateFile { chunks, .. } if chunks.is_empty() => review.push(ReviewHunk {
+                    path,
+                    unified_diff: String::new(),
+                }),
+                Hunk::UpdateFile { chunks, .. } => {
+                    for chunk in chunks {
+                        let unified_diff =
+                            unified_diff_from_chunks(&path, std::slice::from_ref(chunk))
+                                .map_or_else(|_| format_chunk(chunk), |update| update.unified_diff);
+                        review.push(ReviewHunk {
+                            path: path.clone(),
+                            unified_diff,
+                        });
+                    }
+                }
+            }
+        }
+        review
+    }
+
+    /// The patch made of the hunks at the `accepted` positions of
+    /// [`ApplyPatchAction::review_hunks`], or `None` if none was accepted.
+    pub fn select_hunks(
+        &self,
+        accepted: &[usize],
+    ) -> Result<Option<ApplyPatchAction>, ApplyPatchError> {
+        let args = parse_patch(&self.patch)?;
+        let mut index = 0;
+        let mut take = || {
+            let taken = accepted.contains(&index);
+            index += 1;
+            taken
+        };
+        let mut selected = Vec::new();
+        for hunk in args.hunks {
+            match hunk {
+                Hunk::UpdateFile {
+                    path,
+                    move_path,
+                    chunks,
+                } if !chunks.is_empty() => {
+                    let chunks: Vec<UpdateFileChunk> =
+                        chunks.into_iter().filter(|_| take()).collect();
+                    if !chunks.is_empty() {
+                        selected.push(Hunk::UpdateFile {
+                            path,
+                            move_path,
+                            chunks,
+                        });
+                    }
+                }
+                hunk => {
+                    if take() {
+                        selected.push(hunk);
+                    }
+                }
+            }
+        }
+        if selected.is_empty() {
+            return Ok(None);
+        }
+        verify_hunks(format_patch(&selected), selected, self.cwd.clone()).map(Some)
+    }
+}
+
+fn diff(old: &str, new: &str) -> String {
+    TextDiff::from_lines(old, new)
+        .unified_diff()
+        .context_radius(1)
+        .to_string()
+}
+
+/// What is shown for a binary file in place of a diff.
+pub fn binary_summary(size: usize, previous_size: Option<u64>) -> String {
+    match previous_size {
+        Some(previous) => {
+            format!("Binary file replaced: {previous} bytes before, {size} bytes after\n")
+        }
+        None => format!("Binary file added: {size} bytes\n"),
+    }
+}
+
+/// Render `hunks` in the `apply_patch` format.
+pub(crate) fn format_patch(hunks: &[Hunk]) -> String {
+    let mut patch = String::from("*** Begin Patch\n");
+    for hunk in hunks {
+        match hunk {
+            Hunk::AddFile { path, contents } => {
+                patch.push_str(&format!("*** Add File: {}\n", path.display()));
+                for line in contents.lines() {
+                    patch.push_str(&format!("+{line}\n"));
+                }
+            }
+            Hunk::DeleteFile { path } => {
+                patch.push_str(&format!("*** Delete File: {}\n", path.display()));
+            }
+            Hunk::BinaryFile { path, contents } => {
+                patch.push_str(&format!("*** Binary File: {}\n", path.display()));
+                let encoded = base64::engine::general_purpose::STANDARD.encode(contents);
+                for line in encoded.as_bytes().chunks(BASE64_LINE_LEN) {
+                    patch.push('+');
+                    patch.push_str(&String::from_utf8_lossy(line));
+                    patch.push('\n');
+                }
+            }
+            Hunk::UpdateFile {
+                path,
+                move_path,
+                chunks,
+            } => {
+                patch.push_str(&format!("*** Update File: {}\n", path.display()));
+                if let Some(move_path) = move_path {
+                    patch.push_str(&format!("*** Move to: {}\n", move_path.display()));
+                }
+                for chunk in chunks {
+                    patch.push_str(&format_chunk(chunk));
+                }
+            }
+        }
+    }
+    patch.push_str("*** End Patch\n");
+    patch
+}
+
+/// Render one chunk, keeping the lines it leaves unchanged at either end as
+/// context.
+fn format_chunk(chunk: &UpdateFileChunk) -> String {
+    let UpdateFileChunk {
+        change_context,
+        old_lines,
+        new_lines,
+        is_end_of_file,
+    } = chunk;
+    let mut out = match change_context {
+        Some(context) => format!("@@ {context}\n"),
+        None => "@@\n".to_string(),
+    };
+    let prefix = old_lines
+        .iter()
+        .zip(new_lines)
+        .take_while(|(old, new)| old == new)
+        .count();
+    let suffix = old_lines[prefix..]
+        .iter()
+        .rev()
+        .zip(new_lines[prefix..].iter().rev())
+        .take_while(|(old, new)| old == new)
+        .count();
+    for line in &old_lines[..prefix] {
+        out.push_str(&format!(" {line}\n"));
+    }
+    for line in &old_lines[prefix..old_lines.len() - suffix] {
+        out.push_str(&format!("-{line}\n"));
+    }
+    for line in &new_lines[prefix..new_lines.len() - suffix] {
+        out.push_str(&format!("+{line}\n"));
+    }
+    for line in &old_lines[old_lines.len() - suffix..] {
+        out.push_str(&format!(" {line}\n"));
+    }
+    if *is_end_of_file {
+        out.push_str("*** End of File\n");
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MaybeApplyPatchVerified;
+    use crate::maybe_parse_apply_patch_verified;
+    use pretty_assertions::assert_eq;
+    use tempfile::tempdir;
+
+    #[test]
+    fn accepted_hunks_become_a_patch_of_their_own() {
+        let dir = tempdir().expect("tempdir");
+        std::fs::write(dir.path().join("lib.rs"), "a\nb\nc\nd\ne\nf\ng\n").expect("write");
+        let patch = "*** Begin Patch\n\
+                     *** Update File: lib.rs\n\
+                     @@\n a\n-b\n+B\n c\n\
+                     @@\n e\n-f\n+F\n g\n\
+                     *** Add File: new.txt\n+hello\n\
+                     *** End Patch";
+        let argv = vec!["apply_patch".to_string(), patch.to_string()];
+        let MaybeApplyPatchVerified::Body(action) =
+            maybe_parse_apply_patch_verified(&argv, dir.path())
+        else {
+            panic!("expected a verified patch");
+        };
+
+        let lib = dir.path().join("lib.rs");
+        assert_eq!(
+            action.review_hunks(),
+            vec![
+                ReviewHunk {
+                    path: lib.clone(),
+                    unified_diff: "@@ -1,3 +1,3 @@\n a\n-b\n+B\n c\n".to_string(),
+                },
+                ReviewHunk {
+                    path: lib.clone(),
+                    unified_diff: "@@ -5,3 +5,3 @@\n e\n-f\n+F\n g\n".to_string(),
+                },
+                ReviewHunk {
+                    path: dir.path().join("new.txt"),
+                    unified_diff: "@@ -0,0 +1 @@\n+hello\n".to_string(),
+                },
+            ]
+        );
+
+        let partial = action.select_hunks(&[1]).expect("select").expect("some");
+        assert_eq!(
+            partial.patch,
+            "*** Begin Patch\n*** Update File: lib.rs\n@@\n e\n-f\n+F\n g\n*** End Patch\n"
+        );
+        assert_eq!(
+            partial.changes().get(&lib),
+            Some(&ApplyPatchFileChange::Update {
+                unified_diff: "@@ -5,3 +5,3 @@\n e\n-f\n+F\n g\n".to_string(),
+                move_path: None,
+                new_content: "a\nb\nc\nd\ne\nF\ng\n".to_string(),
+            })
+        );
+        assert_eq!(action.select_hunks(&[]).expect("select"), None);
+    }
+}
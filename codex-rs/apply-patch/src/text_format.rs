@@ -0,0 +1,265 @@
+//! The on-disk layout of a text file: its encoding, byte order mark and line
+//! endings.
+//!
+//! Everything that rewrites a file works on normalized text (UTF-8, no BOM,
+//! `\n` line endings) and restores the original layout when writing it back,
+//! so that a patch does not turn a CRLF file into a mixed-ending one or drop
+//! its BOM. Files that already mix endings keep the ending of every line that
+//! survives the edit; new lines get the file's predominant ending.
+
+use std::io;
+use std::path::Path;
+
+use similar::ChangeTag;
+use similar::TextDiff;
+
+use crate::encoding::FileEncoding;
+
+const BOM: char = '\u{feff}';
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineEnding {
+    Lf,
+    CrLf,
+}
+
+impl LineEnding {
+    fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum LineEndings {
+    Uniform(LineEnding),
+    /// The normalized original text and the ending of each of its lines, used
+    /// to give unchanged lines their original ending back.
+    Mixed {
+        original: String,
+        endings: Vec<LineEnding>,
+    },
+}
+
+/// How a text file was laid out when it was read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextFormat {
+    encoding: FileEncoding,
+    bom: bool,
+    line_endings: LineEndings,
+}
+
+impl Default for TextFormat {
+    /// The layout of newly created files: UTF-8 with `\n` line endings.
+    fn default() -> Self {
+        Self {
+            encoding: FileEncoding::utf8(),
+            bom: false,
+            line_endings: LineEndings::Uniform(LineEnding::Lf),
+        }
+    }
+}
+
+impl TextFormat {
+    /// Decode `bytes` and normalize the text, recording the layout needed to
+    /// restore it. Returns `None` for contents that are not text.
+    pub fn decode(bytes: &[u8]) -> Option<(String, Self)> {
+        let (text, encoding) = FileEncoding::decode(bytes)?;
+        let (text, bom) = match text.strip_prefix(BOM) {
+            Some(rest) => (rest, true),
+            None => (text.as_ref(), false),
+        };
+        let (normalized, line_endings) = normalize_line_endings(text);
+        Some((
+            normalized,
+            Self {
+                encoding,
+                bom,
+                line_endings,
+            },
+        ))
+    }
+
+    pub fn encoding(&self) -> FileEncoding {
+        self.encoding
+    }
+
+    pub fn has_bom(&self) -> bool {
+        self.bom
+    }
+
+    /// Ending used for lines that did not exist in the original file.
+    pub fn line_ending(&self) -> LineEnding {
+        match &self.line_endings {
+            LineEndings::Uniform(ending) => *ending,
+            LineEndings::Mixed { endings, .. } => predominant(endings),
+        }
+    }
+
+    /// Turn normalized `text` back into the layout the file was read in.
+    pub fn restore(&self, text: &str) -> String {
+        let mut restored = String::with_capacity(text.len() + usize::from(self.bom) * 3);
+        if self.bom {
+            restored.push(BOM);
+        }
+        match &self.line_endings {
+            LineEndings::Uniform(LineEnding::Lf) => restored.push_str(text),
+            LineEndings::Uniform(LineEnding::CrLf) => {
+                restored.push_str(&text.replace('\n', "\r\n"));
+            }
+            LineEndings::Mixed { original, endings } => {
+                let new_line_ending = predominant(endings);
+                for change in TextDiff::from_lines(original.as_str(), text).iter_all_changes() {
+                    let ending = match (change.tag(), change.old_index()) {
+                        (ChangeTag::Delete, _) => continue,
+                        (ChangeTag::Equal, Some(index)) => {
+                            endings.get(index).copied().unwrap_or(new_line_ending)
+                        }
+                        _ => new_line_ending,
+                    };
+                    let line = change.value();
+                    match line.strip_suffix('\n') {
+                        Some(line) => {
+                            restored.push_str(line);
+                            restored.push_str(ending.as_str());
+                        }
+                        None => restored.push_str(line),
+                    }
+                }
+            }
+        }
+        restored
+    }
+
+    /// [`TextFormat::restore`] followed by encoding the result.
+    pub fn encode(&self, text: &str) -> io::Result<Vec<u8>> {
+        let restored = self.restore(text);
+        match self.encoding.encode(&restored) {
+            Some(bytes) => Ok(bytes.into_owned()),
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "the new contents contain characters that cannot be written in {}",
+                    self.encoding.name()
+                ),
+            )),
+        }
+    }
+}
+
+/// Read `path` as normalized text, along with the layout it was read in.
+pub fn read_text_file(path: &Path) -> io::Result<(String, TextFormat)> {
+    let bytes = std::fs::read(path)?;
+    TextFormat::decode(&bytes).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            "file is not text in UTF-8 or any detected encoding",
+        )
+    })
+}
+
+/// Write normalized `text` to `path` in `format`.
+pub fn write_text_file(path: &Path, text: &str, format: &TextFormat) -> io::Result<()> {
+    std::fs::write(path, format.encode(text)?)
+}
+
+fn normalize_line_endings(text: &str) -> (String, LineEndings) {
+    let mut endings = Vec::new();
+    for line in text.split_inclusive('\n') {
+        if line.ends_with("\r\n") {
+            endings.push(LineEnding::CrLf);
+        } else if line.ends_with('\n') {
+            endings.push(LineEnding::Lf);
+        }
+    }
+    let crlf_lines = endings
+        .iter()
+        .filter(|ending| **ending == LineEnding::CrLf)
+        .count();
+    if crlf_lines == 0 {
+        return (text.to_string(), LineEndings::Uniform(LineEnding::Lf));
+    }
+    let normalized = text.replace("\r\n", "\n");
+    if crlf_lines == endings.len() {
+        return (normalized, LineEndings::Uniform(LineEnding::CrLf));
+    }
+    (
+        normalized.clone(),
+        LineEndings::Mixed {
+            original: normalized,
+            endings,
+        },
+    )
+}
+
+/// The more common of the two endings, `\n` on a tie.
+fn predominant(endings: &[LineEnding]) -> LineEnding {
+    let crlf_lines = endings
+        .iter()
+        .filter(|ending| **ending == LineEnding::CrLf)
+        .count();
+    if crlf_lines * 2 > endings.len() {
+        LineEnding::CrLf
+    } else {
+        LineEnding::Lf
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn round_trip(original: &[u8], edit: impl Fn(&str) -> String) -> Vec<u8> {
+        let (text, format) = TextFormat::decode(original).expect("text");
+        format.encode(&edit(&text)).expect("encode")
+    }
+
+    #[test]
+    fn untouched_files_are_written_back_byte_for_byte() {
+        let files: [&[u8]; 4] = [
+            b"one\ntwo\n",
+            b"one\r\ntwo\r\n",
+            b"\xef\xbb\xbfone\r\ntwo",
+            b"one\r\ntwo\nthree\r\n",
+        ];
+        for original in files {
+            assert_eq!(round_trip(original, str::to_string), original.to_vec());
+        }
+    }
+
+    #[test]
+    fn crlf_and_bom_are_restored_after_an_edit() {
+        let (text, format) = TextFormat::decode(b"\xef\xbb\xbfone\r\ntwo\r\n").expect("text");
+        assert_eq!(text, "one\ntwo\n");
+        assert_eq!(
+            (format.has_bom(), format.line_ending()),
+            (true, LineEnding::CrLf)
+        );
+        assert_eq!(
+            format.encode("one\n2\nthree\n").expect("encode"),
+            b"\xef\xbb\xbfone\r\n2\r\nthree\r\n".to_vec()
+        );
+    }
+
+    #[test]
+    fn mixed_endings_are_kept_per_line() {
+        // Mostly CRLF, with one LF line that must stay LF.
+        let original = b"a\r\nb\nc\r\nd\r\n";
+        let edited = round_trip(original, |text| {
+            assert_eq!(text, "a\nb\nc\nd\n");
+            "a\nb\nC\nnew\nd\n".to_string()
+        });
+        assert_eq!(edited, b"a\r\nb\nC\r\nnew\r\nd\r\n".to_vec());
+    }
+
+    #[test]
+    fn new_files_use_lf() {
+        assert_eq!(
+            TextFormat::default().encode("one\ntwo\n").expect("encode"),
+            b"one\ntwo\n".to_vec()
+        );
+    }
+}
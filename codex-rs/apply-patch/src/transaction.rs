@@ -0,0 +1,725 @@
+//! Applying a patch to the filesystem as a single change.
+//!
+//! Every hunk is first resolved in memory against the files as the earlier
+//! hunks leave them, so a hunk that does not apply fails before anything is
+//! written. The new contents are then written to temporary files beside their
+//! targets and synced to disk, and only then renamed into place, with the
+//! patch's deletions carried out alongside. If any of that fails, the files
+//! already replaced or deleted get their previous contents back, so the
+//! workspace is left as it was before the patch.
+//!
+//! Patching files that no other hunk touches and writing the temporary files
+//! are spread over a bounded pool of threads, which pays off for refactors
+//! that touch hundreds of files. Results are gathered in hunk order, so the
+//! outcome, and the hunk an error is reported for, do not depend on timing.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::io::Write;
+use std::num::NonZeroUsize;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+use anyhow::Context;
+
+use crate::AffectedPaths;
+use crate::ApplyPatchError;
+use crate::IoError;
+use crate::apply_chunks_to_contents;
+use crate::parser::Hunk;
+use crate::parser::UpdateFileChunk;
+use crate::text_format::TextFormat;
+use crate::text_format::read_text_file;
+
+/// New contents of a file.
+#[derive(Clone)]
+enum Contents {
+    /// Normalized text, written back in the file's format.
+    Text(String, TextFormat),
+    /// Bytes written as they are.
+    Binary(Vec<u8>),
+}
+
+/// What the patch leaves at one path.
+struct StagedFile {
+    path: PathBuf,
+    /// The new contents, or `None` when the patch deletes the file.
+    outcome: Option<Contents>,
+    /// Index of the last hunk that touched the path.
+    hunk: usize,
+}
+
+/// A staged file whose new contents sit in a temporary file.
+struct PreparedFile<'a> {
+    staged: &'a StagedFile,
+    /// Where new contents go: the path itself, or what it links to.
+    target: PathBuf,
+    tmp: Option<PathBuf>,
+    /// Permissions of the file being replaced, given to its new contents.
+    permissions: Option<fs::Permissions>,
+}
+
+/// Most threads patching or writing files at once.
+const MAX_WORKERS: usize = 8;
+
+/// Patches touching fewer files than this are applied on the calling thread.
+const MIN_PARALLEL_FILES: usize = 16;
+
+/// Apply `hunks` so that either all of them take effect or none do.
+pub(crate) fn apply_hunks_atomically(hunks: &[Hunk]) -> anyhow::Result<AffectedPaths> {
+    let (staged, affected) = stage(hunks)
+        .map_err(|(index, err)| with_note(hunks, index, err, "no files were changed"))?;
+    commit(&staged).map_err(|failure| {
+        let note = if failure.undone == 0 {
+            "no files were changed".to_string()
+        } else if failure.unrestored.is_empty() {
+            "the files it had already changed were restored".to_string()
+        } else {
+            format!("could not restore {}", failure.unrestored.join(", "))
+        };
+        with_note(hunks, failure.hunk, failure.error, &note)
+    })?;
+    Ok(affected)
+}
+
+/// Name the failed hunk when there is more than one.
+fn with_note(hunks: &[Hunk], index: usize, err: anyhow::Error, note: &str) -> anyhow::Error {
+    if hunks.len() < 2 {
+        return err;
+    }
+    let header = match &hunks[index] {
+        Hunk::AddFile { path, .. } => format!("Add File: {}", path.display()),
+        Hunk::DeleteFile { path } => format!("Delete File: {}", path.display()),
+        Hunk::UpdateFile { path, .. } => format!("Update File: {}", path.display()),
+        Hunk::BinaryFile { path, .. } => format!("Binary File: {}", path.display()),
+    };
+    anyhow::anyhow!(
+        "{err}\nHunk {} of {} ({header}) failed; {note}.",
+        index + 1,
+        hunks.len()
+    )
+}
+
+fn stage(hunks: &[Hunk]) -> Result<(Vec<StagedFile>, AffectedPaths), (usize, anyhow::Error)> {
+    let mut patched = patch_independent_files(hunks);
+    let mut staged: Vec<StagedFile> = Vec::new();
+    let mut affected = AffectedPaths {
+        added: Vec::new(),
+        modified: Vec::new(),
+        deleted: Vec::new(),
+    };
+    for (index, hunk) in hunks.iter().enumerate() {
+        stage_hunk(
+            &mut staged,
+            &mut affected,
+            hunk,
+            index,
+            patched.remove(&index),
+        )
+        .map_err(|err| (index, err))?;
+    }
+    Ok((staged, affected))
+}
+
+type Patched = anyhow::Result<(String, TextFormat)>;
+
+/// The new contents of the files updated by exactly one hunk and touched by
+/// no other, keyed by hunk index. They do not depend on the rest of the
+/// patch, so they are worked out up front and in parallel.
+fn patch_independent_files(hunks: &[Hunk]) -> HashMap<usize, Patched> {
+    let mut touches: HashMap<&Path, usize> = HashMap::new();
+    for hunk in hunks {
+        for path in hunk_paths(hunk) {
+            *touches.entry(path).or_default() += 1;
+        }
+    }
+    let independent: Vec<(usize, &Path, &[UpdateFileChunk])> = hunks
+        .iter()
+        .enumerate()
+        .filter_map(|(index, hunk)| match hunk {
+            Hunk::UpdateFile { path, chunks, .. }
+                if hunk_paths(hunk).all(|path| touches.get(path) == Some(&1)) =>
+            {
+                Some((index, path.as_path(), chunks.as_slice()))
+            }
+            _ => None,
+        })
+        .collect();
+    if independent.len() < MIN_PARALLEL_FILES {
+        return HashMap::new();
+    }
+    let patched = map_bounded(&independent, |(_, path, chunks)| {
+        patch_file(read_text_file(path), path, chunks)
+    });
+    independent
+        .iter()
+        .map(|(index, ..)| *index)
+        .zip(patched)
+        .collect()
+}
+
+fn hunk_paths(hunk: &Hunk) -> impl Iterator<Item = &Path> {
+    let (path, move_path) = match hunk {
+        Hunk::AddFile { path, .. } | Hunk::DeleteFile { path } | Hunk::BinaryFile { path, .. } => {
+            (path, None)
+        }
+        Hunk::UpdateFile {
+            path, move_path, ..
+        } => (path, move_path.as_ref()),
+    };
+    std::iter::once(path.as_path()).chain(move_path.map(PathBuf::as_path))
+}
+
+/// Apply `chunks` to the `current` contents of `path`.
+fn patch_file(
+    current: io::Result<(String, TextFormat)>,
+    path: &Path,
+    chunks: &[UpdateFileChunk],
+) -> Patched {
+    let (original, format) = current.map_err(|source| {
+        ApplyPatchError::IoError(IoError {
+            context: format!("Failed to read file to update {}", path.display()),
+            source,
+        })
+    })?;
+    let new_contents = apply_chunks_to_contents(&original, path, chunks)?;
+    Ok((new_contents, format))
+}
+
+fn stage_hunk(
+    staged: &mut Vec<StagedFile>,
+    affected: &mut AffectedPaths,
+    hunk: &Hunk,
+    index: usize,
+    patched: Option<Patched>,
+) -> anyhow::Result<()> {
+    match hunk {
+        Hunk::AddFile { path, contents } => {
+            set(
+                staged,
+                path,
+                Some(Contents::Text(contents.clone(), TextFormat::default())),
+                index,
+            );
+            affected.added.push(path.clone());
+        }
+        Hunk::BinaryFile { path, contents } => {
+            let exists = match staged.iter().find(|file| &file.path == path) {
+                Some(file) => file.outcome.is_some(),
+                None => path.is_file(),
+            };
+            set(
+                staged,
+                path,
+                Some(Contents::Binary(contents.clone())),
+                index,
+            );
+            if exists {
+                affected.modified.push(path.clone());
+            } else {
+                affected.added.push(path.clone());
+            }
+        }
+        Hunk::DeleteFile { path } => {
+            let exists = match staged.iter().find(|file| &file.path == path) {
+                Some(file) => file.outcome.is_some(),
+                None => path.is_file(),
+            };
+            if !exists {
+                anyhow::bail!("Failed to delete file {}", path.display());
+            }
+            set(staged, path, None, index);
+            affected.deleted.push(path.clone());
+        }
+        Hunk::UpdateFile {
+            path,
+            move_path,
+            chunks,
+        } => {
+            let (new_contents, format) = match patched {
+                Some(patched) => patched?,
+                None => {
+                    let current = match staged.iter().find(|file| &file.path == path) {
+                        Some(file) => match &file.outcome {
+                            Some(Contents::Text(text, format)) => {
+                                Ok((text.clone(), format.clone()))
+                            }
+                            Some(Contents::Binary(_)) => Err(io::Error::new(
+                                io::ErrorKind::InvalidData,
+                                "an earlier hunk wrote it as a binary file",
+                            )),
+                            None => Err(io::Error::from(io::ErrorKind::NotFound)),
+                        },
+                        None => read_text_file(path),
+                    };
+                    patch_file(current, path, chunks)?
+                }
+            };
+            match move_path {
+                Some(dest) => {
+                    set(
+                        staged,
+                        dest,
+                        Some(Contents::Text(new_contents, format)),
+                        index,
+                    );
+                    set(staged, path, None, index);
+                    affected.modified.push(dest.clone());
+                }
+                None => {
+                    set(
+                        staged,
+                        path,
+                        Some(Contents::Text(new_contents, format)),
+                        index,
+                    );
+                    affected.modified.push(path.clone());
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn set(staged: &mut Vec<StagedFile>, path: &Path, outcome: Option<Contents>, hunk: usize) {
+    match staged.iter_mut().find(|file| file.path == path) {
+        Some(file) => {
+            file.outcome = outcome;
+            file.hunk = hunk;
+        }
+        None => staged.push(StagedFile {
+            path: path.to_path_buf(),
+            outcome,
+            hunk,
+        }),
+    }
+}
+
+struct CommitFailure {
+    hunk: usize,
+    error: anyhow::Error,
+    /// How many files had been replaced or deleted when it failed.
+    undone: usize,
+    /// Files whose previous contents could not be put back.
+    unrestored: Vec<String>,
+}
+
+fn commit(staged: &[StagedFile]) -> Result<(), CommitFailure> {
+    let mut created_dirs: Vec<PathBuf> = Vec::new();
+    let mut prepared: Vec<PreparedFile> = Vec::new();
+    for file in staged {
+        match prepare(file, &mut created_dirs) {
+            Ok(ready) => prepared.push(ready),
+            Err(error) => {
+                discard(&prepared, &created_dirs);
+                return Err(CommitFailure {
+                    hunk: file.hunk,
+                    error,
+                    undone: 0,
+                    unrestored: Vec::new(),
+                });
+            }
+        }
+    }
+    let written = if prepared.len() < MIN_PARALLEL_FILES {
+        prepared.iter().map(write_tmp).collect::<Vec<_>>()
+    } else {
+        map_bounded(&prepared, write_tmp)
+    };
+    if let Some((file, error)) = prepared
+        .iter()
+        .zip(written)
+        .find_map(|(file, written)| written.err().map(|error| (file, error)))
+    {
+        discard(&prepared, &created_dirs);
+        return Err(CommitFailure {
+            hunk: file.staged.hunk,
+            error,
+            undone: 0,
+            unrestored: Vec::new(),
+        });
+    }
+
+    // Contents to restore for each file already replaced or deleted; `None`
+    // for a file that did not exist before.
+    let mut applied: Vec<(&PreparedFile, Option<Vec<u8>>)> = Vec::new();
+    for (position, file) in prepared.iter().enumerate() {
+        match replace(file) {
+            Ok(previous) => applied.push((file, previous)),
+            Err(err) => {
+                let path = file.staged.path.display();
+                let error = match file.tmp {
+                    Some(_) => {
+                        anyhow::Error::new(err).context(format!("Failed to write file {path}"))
+                    }
+                    None => {
+                        anyhow::Error::new(err).context(format!("Failed to delete file {path}"))
+                    }
+                };
+                return Err(roll_back(
+                    &applied,
+                    &prepared[position..],
+                    &created_dirs,
+                    file,
+                    error,
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Move the new contents of `file` into place, or delete it, returning what
+/// was there before.
+fn replace(file: &PreparedFile) -> io::Result<Option<Vec<u8>>> {
+    let previous = match fs::read(&file.target) {
+        Ok(bytes) => Some(bytes),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+        Err(err) => return Err(err),
+    };
+    match &file.tmp {
+        Some(tmp) => fs::rename(tmp, &file.target)?,
+        None => fs::remove_file(&file.target)?,
+    }
+    Ok(previous)
+}
+
+/// Work out where the new contents of `file` go, creating missing parent
+/// directories.
+fn prepare<'a>(
+    file: &'a StagedFile,
+    created_dirs: &mut Vec<PathBuf>,
+) -> anyhow::Result<PreparedFile<'a>> {
+    let path = &file.path;
+    if file.outcome.is_none() {
+        return Ok(PreparedFile {
+            staged: file,
+            target: path.clone(),
+            tmp: None,
+            permissions: None,
+        });
+    }
+    // Write to what a symlink points to rather than replace the link.
+    let target = match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_symlink() => fs::canonicalize(path)
+            .with_context(|| format!("Failed to resolve {}", path.display()))?,
+        _ => path.clone(),
+    };
+
+    let existing = fs::metadata(&target).ok();
+    if existing
+        .as_ref()
+        .is_some_and(|metadata| metadata.permissions().readonly())
+    {
+        return Err(anyhow::Error::new(io::Error::from(
+            io::ErrorKind::PermissionDenied,
+        )))
+        .with_context(|| format!("Failed to write file {}", path.display()));
+    }
+    if let Some(parent) = target.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        create_dirs(parent, created_dirs).with_context(|| {
+            format!("Failed to create parent directories for {}", path.display())
+        })?;
+    }
+
+    Ok(PreparedFile {
+        staged: file,
+        tmp: Some(tmp_path(&target)),
+        target,
+        permissions: existing.map(|metadata| metadata.permissions()),
+    })
+}
+
+/// Write the new contents of `file` to its temporary file and sync it.
+fn write_tmp(file: &PreparedFile) -> anyhow::Result<()> {
+    let (Some(tmp), Some(contents)) = (&file.tmp, &file.staged.outcome) else {
+        return Ok(());
+    };
+    let bytes = match contents {
+        Contents::Text(text, format) => format.encode(text).map(Cow::Owned),
+        Contents::Binary(bytes) => Ok(Cow::Borrowed(bytes.as_slice())),
+    };
+    let written = bytes.and_then(|bytes| {
+        let mut out = fs::File::create(tmp)?;
+        out.write_all(&bytes)?;
+        if let Some(permissions) = &file.permissions {
+            out.set_permissions(permissions.clone())?;
+        }
+        out.sync_all()
+    });
+    written.with_context(|| format!("Failed to write file {}", file.staged.path.display()))
+}
+
+/// Create `dir` and its missing ancestors, recording the ones created. The
+/// ancestors of a relative path end with an empty one, which stands for the
+/// current directory and is never created.
+fn create_dirs(dir: &Path, created_dirs: &mut Vec<PathBuf>) -> io::Result<()> {
+    let missing: Vec<&Path> = dir
+        .ancestors()
+        .take_while(|dir| !dir.as_os_str().is_empty() && !dir.exists())
+        .collect();
+    for dir in missing.into_iter().rev() {
+        fs::create_dir(dir)?;
+        created_dirs.push(dir.to_path_buf());
+    }
+    Ok(())
+}
+
+fn tmp_path(target: &Path) -> PathBuf {
+    let name = target
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    target.with_file_name(format!(".{name}.apply-patch-{}.tmp", std::process::id()))
+}
+
+/// Put back what `applied` replaced or deleted, newest first, and drop the
+/// temporary files that were never renamed into place.
+fn roll_back(
+    applied: &[(&PreparedFile, Option<Vec<u8>>)],
+    pending: &[PreparedFile],
+    created_dirs: &[PathBuf],
+    failed: &PreparedFile,
+    error: anyhow::Error,
+) -> CommitFailure {
+    let mut unrestored = Vec::new();
+    for (file, previous) in applied.iter().rev() {
+        let restored = match previous {
+            Some(bytes) => fs::write(&file.target, bytes),
+            None => fs::remove_file(&file.target),
+        };
+        if let Err(err) = restored {
+            unrestored.push(format!("{}: {err}", file.staged.path.display()));
+        }
+    }
+    discard(pending, created_dirs);
+    CommitFailure {
+        hunk: failed.staged.hunk,
+        error,
+        undone: applied.len(),
+        unrestored,
+    }
+}
+
+/// Remove temporary files and the directories created for them.
+fn discard(prepared: &[PreparedFile], created_dirs: &[PathBuf]) {
+    for tmp in prepared.iter().filter_map(|file| file.tmp.as_ref()) {
+        let _ = fs::remove_file(tmp);
+    }
+    for dir in created_dirs.iter().rev() {
+        // Only succeeds while the directory is empty, which it is unless
+        // something else wrote to it in the meantime.
+        let _ = fs::remove_dir(dir);
+    }
+}
+
+/// `f` applied to each of `items` on up to [`MAX_WORKERS`] threads, with the
+/// results in the order of `items`.
+fn map_bounded<T: Sync, R: Send>(items: &[T], f: impl Fn(&T) -> R + Sync) -> Vec<R> {
+    let workers = std::thread::available_parallelism()
+        .map_or(1, NonZeroUsize::get)
+        .min(MAX_WORKERS)
+        .min(items.len());
+    if workers < 2 {
+        return items.iter().map(f).collect();
+    }
+    let next = AtomicUsize::new(0);
+    let mut done: Vec<(usize, R)> = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = Vec::new();
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(item) = items.get(index) else {
+                            break;
+                        };
+                        done.push((index, f(item)));
+                    }
+                    done
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+            })
+            .collect()
+    });
+    done.sort_by_key(|(index, _)| *index);
+    done.into_iter().map(|(_, result)| result).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_patch;
+    use pretty_assertions::assert_eq;
+    use tempfile::tempdir;
+
+    #[test]
+    fn a_failing_hunk_leaves_every_file_unchanged() {
+        let dir = tempdir().unwrap();
+        let kept = dir.path().join("kept.txt");
+        let deleted = dir.path().join("deleted.txt");
+        fs::write(&kept, "one\n").unwrap();
+        fs::write(&deleted, "gone\n").unwrap();
+        let patch = format!(
+            "*** Begin Patch\n*** Update File: {kept}\n@@\n-one\n+ONE\n*** Delete File: \
+             {deleted}\n*** Add File: {added}\n+new\n*** Update File: {kept}\n@@\n-missing\n\
+             +changed\n*** End Patch",
+            kept = kept.display(),
+            deleted = deleted.display(),
+            added = dir.path().join("new/added.txt").display(),
+        );
+        let hunks = parse_patch(&patch).unwrap().hunks;
+
+        let err = apply_hunks_atomically(&hunks).err().expect("fails");
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "Failed to find expected lines in {kept}:\nmissing\nHunk 4 of 4 (Update File: \
+                 {kept}) failed; no files were changed.",
+                kept = kept.display()
+            )
+        );
+        assert_eq!(fs::read_to_string(&kept).unwrap(), "one\n");
+        assert_eq!(fs::read_to_string(&deleted).unwrap(), "gone\n");
+        assert!(!dir.path().join("new").exists());
+    }
+
+    #[test]
+    fn missing_parents_of_relative_paths_are_created() {
+        let root = PathBuf::from(format!(".create-dirs-test-{}", std::process::id()));
+        let nested = root.join("nested");
+        let mut created_dirs = Vec::new();
+
+        let result = create_dirs(&nested, &mut created_dirs);
+        let created = nested.is_dir();
+        let _ = fs::remove_dir_all(&root);
+        result.unwrap();
+        assert!(created);
+        assert_eq!(created_dirs, vec![root.clone(), nested]);
+    }
+
+    #[test]
+    fn binary_files_are_written_byte_for_byte() {
+        let dir = tempdir().unwrap();
+        let replaced = dir.path().join("logo.png");
+        let added = dir.path().join("assets/icon.bin");
+        fs::write(&replaced, "old").unwrap();
+        let patch = format!(
+            "*** Begin Patch\n*** Binary File: {replaced}\n+iVBORw0K\n*** Binary File: {added}\n\
+             +AP8A\n*** End Patch",
+            replaced = replaced.display(),
+            added = added.display(),
+        );
+        let hunks = parse_patch(&patch).unwrap().hunks;
+
+        let affected = apply_hunks_atomically(&hunks).unwrap();
+        assert_eq!(
+            (affected.added, affected.modified),
+            (vec![added.clone()], vec![replaced.clone()])
+        );
+        assert_eq!(fs::read(&replaced).unwrap(), b"\x89PNG\r\n".to_vec());
+        assert_eq!(fs::read(&added).unwrap(), vec![0, 255, 0]);
+    }
+
+    #[test]
+    fn applied_changes_are_rolled_back_when_a_later_write_fails() {
+        let dir = tempdir().unwrap();
+        let first = dir.path().join("first.txt");
+        let blocked = dir.path().join("blocked");
+        fs::write(&first, "before\n").unwrap();
+        // A directory where the patch expects to write a file makes the
+        // rename fail after `first.txt` has been replaced.
+        fs::create_dir(&blocked).unwrap();
+        fs::write(blocked.join("inner"), "x").unwrap();
+        let patch = format!(
+            "*** Begin Patch\n*** Update File: {first}\n@@\n-before\n+after\n*** Add File: \
+             {blocked}\n+contents\n*** End Patch",
+            first = first.display(),
+            blocked = blocked.display(),
+        );
+        let hunks = parse_patch(&patch).unwrap().hunks;
+
+        let err = apply_hunks_atomically(&hunks).err().expect("fails");
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "Failed to write file {blocked}\nHunk 2 of 2 (Add File: {blocked}) failed; the \
+                 files it had already changed were restored.",
+                blocked = blocked.display()
+            )
+        );
+        assert_eq!(fs::read_to_string(&first).unwrap(), "before\n");
+        let leftovers: Vec<String> = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect::<std::collections::BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        assert_eq!(
+            leftovers,
+            vec!["blocked".to_string(), "first.txt".to_string()]
+        );
+    }
+
+    #[test]
+    fn many_files_are_patched_with_the_first_failure_reported() {
+        let dir = tempdir().unwrap();
+        let paths: Vec<PathBuf> = (0..40)
+            .map(|i| dir.path().join(format!("file{i}.txt")))
+            .collect();
+        for path in &paths {
+            fs::write(path, "old\n").unwrap();
+        }
+        let patch_for = |fail: &[usize]| {
+            let hunks: String = paths
+                .iter()
+                .enumerate()
+                .map(|(i, path)| {
+                    let old = if fail.contains(&i) { "missing" } else { "old" };
+                    format!("*** Update File: {}\n@@\n-{old}\n+new\n", path.display())
+                })
+                .collect();
+            parse_patch(&format!("*** Begin Patch\n{hunks}*** End Patch"))
+                .unwrap()
+                .hunks
+        };
+
+        let err = apply_hunks_atomically(&patch_for(&[30, 10]))
+            .err()
+            .expect("fails");
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "Failed to find expected lines in {path}:\nmissing\nHunk 11 of 40 (Update \
+                 File: {path}) failed; no files were changed.",
+                path = paths[10].display()
+            )
+        );
+        assert!(
+            paths
+                .iter()
+                .all(|path| fs::read_to_string(path).unwrap() == "old\n")
+        );
+
+        let affected = apply_hunks_atomically(&patch_for(&[])).unwrap();
+        assert_eq!(affected.modified, paths);
+        assert!(
+            paths
+                .iter()
+                .all(|path| fs::read_to_string(path).unwrap() == "new\n")
+        );
+    }
+}
@@ -0,0 +1,154 @@
+use std::path::PathBuf;
+
+use anyhow::Context;
+use clap::Args;
+use clap::Parser;
+use clap::ValueEnum;
+use codex_core::audit::AuditEntry;
+use codex_core::audit::AuditVerification;
+use codex_core::audit::audit_csv;
+use codex_core::audit::list_audit_logs;
+use codex_core::audit::read_audit_log;
+use codex_core::audit::snake_case;
+use codex_core::audit::verify_audit_log;
+use codex_core::config::find_codex_home;
+
+#[derive(Debug, Parser)]
+pub struct AuditCommand {
+    #[command(subcommand)]
+    sub: AuditSubcommand,
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum AuditSubcommand {
+    /// List the recorded commands and patches, oldest first.
+    List(AuditQuery),
+
+    /// Print the recorded commands and patches as JSON or CSV.
+    Export {
+        #[command(flatten)]
+        query: AuditQuery,
+
+        #[arg(long, value_enum, default_value_t = ExportFormat::Json)]
+        format: ExportFormat,
+    },
+
+    /// Check that no entry of an audit log was changed, reordered or
+    /// removed. Exits with status 1 when one was.
+    Verify {
+        /// Only check the log of this session.
+        #[arg(long, value_name = "SESSION_ID")]
+        session: Option<String>,
+    },
+}
+
+#[derive(Debug, Args)]
+struct AuditQuery {
+    /// Only entries of this session.
+    #[arg(long, value_name = "SESSION_ID")]
+    session: Option<String>,
+
+    /// Only entries whose command line or patched files contain TEXT.
+    #[arg(long, value_name = "TEXT")]
+    grep: Option<String>,
+
+    /// Only entries that ended this way.
+    #[arg(
+        long,
+        value_parser = ["completed", "rejected", "sandbox_denied", "timed_out", "failed"],
+    )]
+    outcome: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum ExportFormat {
+    Json,
+    Csv,
+}
+
+pub fn run_audit_command(cmd: AuditCommand) -> anyhow::Result<()> {
+    match cmd.sub {
+        AuditSubcommand::List(query) => {
+            for entry in query_entries(&query)? {
+                let decision = match entry.decision {
+                    Some(decision) => snake_case(&decision),
+                    None => "-".to_string(),
+                };
+                let exit_code = match entry.exit_code {
+                    Some(exit_code) => exit_code.to_string(),
+                    None => "-".to_string(),
+                };
+                println!(
+                    "{}\t{}\t{}\t{exit_code}\t{decision} ({})\t{}",
+                    entry.time.format("%Y-%m-%d %H:%M:%S"),
+                    entry.conversation_id,
+                    snake_case(&entry.outcome),
+                    snake_case(&entry.decided_by),
+                    entry.action.target(),
+                );
+            }
+        }
+        AuditSubcommand::Export { query, format } => {
+            let entries = query_entries(&query)?;
+            match format {
+                ExportFormat::Json => println!("{}", serde_json::to_string_pretty(&entries)?),
+                ExportFormat::Csv => print!("{}", audit_csv(&entries)),
+            }
+        }
+        AuditSubcommand::Verify { session } => {
+            let mut broken = false;
+            for (conversation_id, path) in audit_logs(session.as_deref())? {
+                match verify_audit_log(&path)? {
+                    AuditVerification::Intact { entries } => {
+                        println!("ok      {conversation_id}: {entries} entries");
+                    }
+                    AuditVerification::Broken { line, reason } => {
+                        broken = true;
+                        println!("BROKEN  {conversation_id}: line {line}: {reason}");
+                    }
+                }
+            }
+            if broken {
+                std::process::exit(1);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The audit logs to read, least recently written first.
+fn audit_logs(session: Option<&str>) -> anyhow::Result<Vec<(String, PathBuf)>> {
+    let codex_home = find_codex_home().context("failed to resolve CODEX_HOME")?;
+    let mut logs = list_audit_logs(&codex_home)?;
+    if let Some(session) = session {
+        logs.retain(|(conversation_id, _)| conversation_id == session);
+        if logs.is_empty() {
+            anyhow::bail!("No audit log for session {session}");
+        }
+    }
+    Ok(logs)
+}
+
+fn query_entries(query: &AuditQuery) -> anyhow::Result<Vec<AuditEntry>> {
+    let mut entries = Vec::new();
+    for (_, path) in audit_logs(query.session.as_deref())? {
+        let log =
+            read_audit_log(&path).with_context(|| format!("failed to read {}", path.display()))?;
+        entries.extend(log.into_iter().filter(|entry| query.matches(entry)));
+    }
+    Ok(entries)
+}
+
+impl AuditQuery {
+    fn matches(&self, entry: &AuditEntry) -> bool {
+        if let Some(outcome) = &self.outcome
+            && snake_case(&entry.outcome) != *outcome
+        {
+            return false;
+        }
+        match &self.grep {
+            Some(text) => entry.action.target().contains(text.as_str()),
+            None => true,
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use clap::Parser;
+use codex_common::CliConfigOverrides;
+use codex_core::config::Config;
+use codex_core::config::ConfigOverrides;
+use codex_core::doctor::run_doctor;
+use codex_core::protocol::DoctorStatus;
+
+#[derive(Debug, Parser)]
+pub struct DoctorCommand {
+    /// Print the results as JSON.
+    #[arg(long, default_value_t = false)]
+    json: bool,
+}
+
+/// Run the checks and print their results. Exits with status 1 when one of
+/// them failed.
+pub async fn run_doctor_command(
+    cmd: DoctorCommand,
+    config_overrides: CliConfigOverrides,
+) -> anyhow::Result<()> {
+    let cli_kv_overrides = config_overrides
+        .parse_overrides()
+        .map_err(anyhow::Error::msg)?;
+    let config =
+        Config::load_with_cli_overrides(cli_kv_overrides, ConfigOverrides::default()).await?;
+    let checks = run_doctor(&config).await;
+    if cmd.json {
+        println!("{}", serde_json::to_string_pretty(&checks)?);
+    } else {
+        for check in &checks {
+            let status = match check.status {
+                DoctorStatus::Pass => "pass",
+                DoctorStatus::Warn => "warn",
+                DoctorStatus::Fail => "FAIL",
+            };
+            println!("{status}  {}: {}", check.name, check.message);
+            if let Some(fix) = &check.fix {
+                println!("      {fix}");
+            }
+        }
+    }
+    if checks
+        .iter()
+        .any(|check| check.status == DoctorStatus::Fail)
+    {
+        std::process::exit(1);
+    }
+    Ok(())
+}
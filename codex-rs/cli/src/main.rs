@@ -1,3 +1,4 @@
+use anyhow::Context;
 use clap::Args;
 use clap::CommandFactory;
 use clap::Parser;
@@ -27,15 +28,34 @@ use owo_colors::OwoColorize;
 use std::path::PathBuf;
 use supports_color::Stream;
 
+mod audit;
+mod doctor;
 mod mcp_cmd;
+mod proto;
+mod self_update;
 #[cfg(not(windows))]
 mod wsl_paths;
 
+use crate::audit::AuditCommand;
+use crate::audit::run_audit_command;
+use crate::doctor::DoctorCommand;
+use crate::doctor::run_doctor_command;
 use crate::mcp_cmd::McpCli;
+use crate::self_update::SelfUpdateCommand;
+use crate::self_update::launch_selected_version;
+use crate::self_update::run_self_update;
 
+use codex_core::approval_cache::clear_approvals;
+use codex_core::approval_cache::load_approvals;
+use codex_core::approval_cache::revoke_approval;
 use codex_core::config::Config;
 use codex_core::config::ConfigOverrides;
+use codex_core::config::find_codex_home;
 use codex_core::features::is_known_feature_key;
+use codex_core::project_cache::clear_project_caches;
+use codex_core::project_cache::evict_project_caches;
+use codex_core::project_cache::list_project_caches;
+use codex_core::project_cache::remove_project_cache;
 
 /// Codex CLI
 ///
@@ -84,6 +104,12 @@ enum Subcommand {
     /// [experimental] Run the Codex MCP server (stdio transport).
     McpServer,
 
+    /// [experimental] Run Codex as an Agent Client Protocol agent (stdio transport).
+    Acp,
+
+    /// [experimental] Drive a new session with JSON-RPC over stdio, for embedding Codex.
+    Proto,
+
     /// [experimental] Run the app server or related tooling.
     AppServer(AppServerCommand),
 
@@ -119,6 +145,24 @@ enum Subcommand {
 
     /// Inspect feature flags.
     Features(FeaturesCli),
+
+    /// List or revoke approvals remembered across sessions.
+    Approvals(ApprovalsCli),
+
+    /// List, prune or delete the per-project cache directories.
+    Cache(CacheCli),
+
+    /// Install a Codex release and make it the default, or pin the project
+    /// in the current directory to it.
+    SelfUpdate(SelfUpdateCommand),
+
+    /// Check the sandbox, shell, git, credentials and network access Codex
+    /// depends on, and suggest fixes.
+    Doctor(DoctorCommand),
+
+    /// List, verify or export the audit log of the commands and patches
+    /// sessions ran.
+    Audit(AuditCommand),
 }
 
 #[derive(Debug, Parser)]
@@ -143,10 +187,31 @@ struct ResumeCommand {
     #[arg(long = "all", default_value_t = false)]
     all: bool,
 
+    #[clap(flatten)]
+    fork: ResumeForkArgs,
+
     #[clap(flatten)]
     config_overrides: TuiCli,
 }
 
+#[derive(Debug, Parser)]
+struct ResumeForkArgs {
+    /// Fork the session into a new one working in its own git worktree,
+    /// leaving the original session and its files untouched.
+    #[arg(long = "fork", default_value_t = false)]
+    fork: bool,
+
+    /// With --fork, branch off before your Nth message in the session,
+    /// keeping only the turns before it. Defaults to keeping them all.
+    #[arg(
+        long = "turn",
+        value_name = "N",
+        requires = "fork",
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    turn: Option<u32>,
+}
+
 #[derive(Debug, Parser)]
 struct SandboxArgs {
     #[command(subcommand)]
@@ -395,6 +460,55 @@ enum FeaturesSubcommand {
     List,
 }
 
+#[derive(Debug, Parser)]
+struct ApprovalsCli {
+    #[command(subcommand)]
+    sub: ApprovalsSubcommand,
+}
+
+#[derive(Debug, Parser)]
+enum ApprovalsSubcommand {
+    /// List the remembered approvals that have not expired.
+    List,
+
+    /// Forget one remembered approval.
+    Revoke {
+        /// Id of the approval, as shown by `codex approvals list`.
+        id: String,
+    },
+
+    /// Forget every remembered approval.
+    Clear,
+}
+
+#[derive(Debug, Parser)]
+struct CacheCli {
+    #[command(subcommand)]
+    sub: CacheSubcommand,
+}
+
+#[derive(Debug, Parser)]
+enum CacheSubcommand {
+    /// List the project caches, least recently used first.
+    List,
+
+    /// Delete the least recently used caches until the rest fit the limit.
+    Prune {
+        /// Size limit in megabytes; defaults to `project_cache.max_size_mb`.
+        #[arg(long = "max-size-mb", value_name = "MB")]
+        max_size_mb: Option<u64>,
+    },
+
+    /// Delete one project's cache.
+    Remove {
+        /// Id of the cache, as shown by `codex cache list`.
+        id: String,
+    },
+
+    /// Delete every project cache.
+    Clear,
+}
+
 fn stage_str(stage: codex_core::features::Stage) -> &'static str {
     use codex_core::features::Stage;
     match stage {
@@ -415,6 +529,7 @@ fn pre_main_hardening() {
 }
 
 fn main() -> anyhow::Result<()> {
+    launch_selected_version();
     arg0_dispatch_or_else(|codex_linux_sandbox_exe| async move {
         cli_main(codex_linux_sandbox_exe).await?;
         Ok(())
@@ -452,6 +567,12 @@ async fn cli_main(codex_linux_sandbox_exe: Option<PathBuf>) -> anyhow::Result<()
         Some(Subcommand::McpServer) => {
             codex_mcp_server::run_main(codex_linux_sandbox_exe, root_config_overrides).await?;
         }
+        Some(Subcommand::Acp) => {
+            codex_acp_server::run_main(codex_linux_sandbox_exe, root_config_overrides).await?;
+        }
+        Some(Subcommand::Proto) => {
+            proto::run_main(codex_linux_sandbox_exe, root_config_overrides).await?;
+        }
         Some(Subcommand::Mcp(mut mcp_cli)) => {
             // Propagate any root-level config overrides (e.g. `-c key=value`).
             prepend_config_flags(&mut mcp_cli.config_overrides, root_config_overrides.clone());
@@ -475,6 +596,7 @@ async fn cli_main(codex_linux_sandbox_exe: Option<PathBuf>) -> anyhow::Result<()
             session_id,
             last,
             all,
+            fork,
             config_overrides,
         })) => {
             interactive = finalize_resume_interactive(
@@ -483,6 +605,7 @@ async fn cli_main(codex_linux_sandbox_exe: Option<PathBuf>) -> anyhow::Result<()
                 session_id,
                 last,
                 all,
+                fork,
                 config_overrides,
             );
             let exit_info = codex_tui::run_main(interactive, codex_linux_sandbox_exe).await?;
@@ -620,6 +743,89 @@ async fn cli_main(codex_linux_sandbox_exe: Option<PathBuf>) -> anyhow::Result<()
                 }
             }
         },
+        Some(Subcommand::Approvals(ApprovalsCli { sub })) => {
+            let codex_home = find_codex_home().context("failed to resolve CODEX_HOME")?;
+            match sub {
+                ApprovalsSubcommand::List => {
+                    for approval in load_approvals(&codex_home)? {
+                        println!(
+                            "{}\t{}\t{}\texpires {}",
+                            approval.id,
+                            approval.project_root.display(),
+                            approval.summary,
+                            approval.expires_at.format("%Y-%m-%d"),
+                        );
+                    }
+                }
+                ApprovalsSubcommand::Revoke { id } => {
+                    if !revoke_approval(&codex_home, &id)? {
+                        anyhow::bail!("No remembered approval with id {id}");
+                    }
+                    println!("Revoked approval {id}");
+                }
+                ApprovalsSubcommand::Clear => {
+                    let count = clear_approvals(&codex_home)?;
+                    println!("Forgot {count} approval(s)");
+                }
+            }
+        }
+        Some(Subcommand::Cache(CacheCli { sub })) => {
+            let codex_home = find_codex_home().context("failed to resolve CODEX_HOME")?;
+            match sub {
+                CacheSubcommand::List => {
+                    for cache in list_project_caches(&codex_home)? {
+                        println!(
+                            "{}\t{}\t{} MB\tlast used {}",
+                            cache.id,
+                            cache.project_root.display(),
+                            cache.size_bytes / (1024 * 1024),
+                            cache.last_used.format("%Y-%m-%d"),
+                        );
+                    }
+                }
+                CacheSubcommand::Prune { max_size_mb } => {
+                    let max_size_mb = match max_size_mb {
+                        Some(max_size_mb) => max_size_mb,
+                        None => {
+                            let cli_kv_overrides = root_config_overrides
+                                .parse_overrides()
+                                .map_err(anyhow::Error::msg)?;
+                            let config = Config::load_with_cli_overrides(
+                                cli_kv_overrides,
+                                ConfigOverrides::default(),
+                            )
+                            .await?;
+                            config.project_cache.max_size_mb
+                        }
+                    };
+                    let evicted =
+                        evict_project_caches(&codex_home, max_size_mb.saturating_mul(1024 * 1024))?;
+                    for cache in &evicted {
+                        println!("Deleted the cache of {}", cache.project_root.display());
+                    }
+                    println!("Deleted {} cache(s)", evicted.len());
+                }
+                CacheSubcommand::Remove { id } => {
+                    if !remove_project_cache(&codex_home, &id)? {
+                        anyhow::bail!("No project cache with id {id}");
+                    }
+                    println!("Deleted cache {id}");
+                }
+                CacheSubcommand::Clear => {
+                    let count = clear_project_caches(&codex_home)?;
+                    println!("Deleted {count} cache(s)");
+                }
+            }
+        }
+        Some(Subcommand::SelfUpdate(cmd)) => {
+            run_self_update(cmd, root_config_overrides).await?;
+        }
+        Some(Subcommand::Doctor(cmd)) => {
+            run_doctor_command(cmd, root_config_overrides).await?;
+        }
+        Some(Subcommand::Audit(cmd)) => {
+            run_audit_command(cmd)?;
+        }
     }
 
     Ok(())
@@ -643,6 +849,7 @@ fn finalize_resume_interactive(
     session_id: Option<String>,
     last: bool,
     show_all: bool,
+    fork: ResumeForkArgs,
     resume_cli: TuiCli,
 ) -> TuiCli {
     // Start with the parsed interactive CLI so resume shares the same
@@ -652,6 +859,8 @@ fn finalize_resume_interactive(
     interactive.resume_last = last;
     interactive.resume_session_id = resume_session_id;
     interactive.resume_show_all = show_all;
+    interactive.resume_fork = fork.fork;
+    interactive.resume_fork_nth_user_message = fork.turn.map(|turn| turn as usize - 1);
 
     // Merge resume-scoped flags and overrides with highest precedence.
     merge_resume_cli_flags(&mut interactive, resume_cli);
@@ -736,6 +945,7 @@ mod tests {
             session_id,
             last,
             all,
+            fork,
             config_overrides: resume_cli,
         }) = subcommand.expect("resume present")
         else {
@@ -748,6 +958,7 @@ mod tests {
             session_id,
             last,
             all,
+            fork,
             resume_cli,
         )
     }
@@ -837,6 +1048,21 @@ mod tests {
         assert!(!interactive.resume_show_all);
     }
 
+    #[test]
+    fn resume_fork_flags_select_the_turn() {
+        let interactive =
+            finalize_from_args(["codex", "resume", "1234", "--fork", "--turn", "3"].as_ref());
+        assert_eq!(interactive.resume_session_id.as_deref(), Some("1234"));
+        assert!(interactive.resume_fork);
+        assert_eq!(interactive.resume_fork_nth_user_message, Some(2));
+
+        let interactive = finalize_from_args(["codex", "resume", "--last", "--fork"].as_ref());
+        assert!(interactive.resume_fork);
+        assert_eq!(interactive.resume_fork_nth_user_message, None);
+
+        assert!(MultitoolCli::try_parse_from(["codex", "resume", "--turn", "2"]).is_err());
+    }
+
     #[test]
     fn resume_all_flag_sets_show_all() {
         let interactive = finalize_from_args(["codex", "resume", "--all"].as_ref());
@@ -0,0 +1,68 @@
+//! `codex proto`: a new session driven through the control protocol on stdio,
+//! for applications that embed Codex as a child process.
+
+use std::path::PathBuf;
+
+use anyhow::Result;
+use anyhow::anyhow;
+use codex_common::CliConfigOverrides;
+use codex_core::AuthManager;
+use codex_core::ConversationManager;
+use codex_core::NewConversation;
+use codex_core::config::Config;
+use codex_core::config::ConfigOverrides;
+use codex_core::control::serve_stream;
+use codex_core::protocol::CollaboratorRole;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::Op;
+use codex_protocol::protocol::SessionSource;
+
+pub async fn run_main(
+    codex_linux_sandbox_exe: Option<PathBuf>,
+    config_overrides: CliConfigOverrides,
+) -> Result<()> {
+    let cli_kv_overrides = config_overrides.parse_overrides().map_err(|e| anyhow!(e))?;
+    let overrides = ConfigOverrides {
+        codex_linux_sandbox_exe,
+        ..Default::default()
+    };
+    let config = Config::load_with_cli_overrides(cli_kv_overrides, overrides).await?;
+    let auth_manager = AuthManager::shared(
+        config.codex_home.clone(),
+        true,
+        config.cli_auth_credentials_store_mode,
+    );
+    let conversation_manager = ConversationManager::new(auth_manager, SessionSource::Exec);
+    let NewConversation {
+        conversation,
+        session_configured,
+        ..
+    } = conversation_manager.new_conversation(config).await?;
+
+    let controller = conversation.attach(CollaboratorRole::Controller)?;
+    // The client gets events through the control protocol; the session's own
+    // event stream only has to be drained.
+    let drain = tokio::spawn({
+        let conversation = conversation.clone();
+        async move {
+            while let Ok(event) = conversation.next_event().await {
+                if matches!(event.msg, EventMsg::ShutdownComplete) {
+                    break;
+                }
+            }
+        }
+    });
+
+    serve_stream(
+        controller,
+        &session_configured,
+        tokio::io::stdin(),
+        tokio::io::stdout(),
+    )
+    .await;
+
+    // The client closed stdin: end the session.
+    conversation.submit(Op::Shutdown).await?;
+    let _ = drain.await;
+    Ok(())
+}
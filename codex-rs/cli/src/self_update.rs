@@ -0,0 +1,133 @@
+use anyhow::Context;
+use clap::Parser;
+use codex_common::CliConfigOverrides;
+use codex_core::config::Config;
+use codex_core::config::ConfigOverrides;
+use codex_core::config::find_codex_home;
+use codex_core::self_update::CURRENT_VERSION;
+use codex_core::self_update::Launch;
+use codex_core::self_update::default_version;
+use codex_core::self_update::fetch_manifest;
+use codex_core::self_update::install_version;
+use codex_core::self_update::installed_versions;
+use codex_core::self_update::pin_version;
+use codex_core::self_update::resolve_launch;
+use codex_core::self_update::set_default_version;
+
+#[derive(Debug, Parser)]
+pub struct SelfUpdateCommand {
+    /// Install this version instead of the channel's latest. Installing an
+    /// earlier version rolls back to it.
+    #[arg(long, value_name = "VERSION")]
+    version: Option<String>,
+
+    /// Release channel to update from; defaults to `self_update.channel`.
+    #[arg(long, value_name = "CHANNEL")]
+    channel: Option<String>,
+
+    /// Pin the project in the current directory to the version by writing
+    /// `.codex/version`, instead of making it the default.
+    #[arg(long, default_value_t = false)]
+    pin: bool,
+
+    /// List the installed versions.
+    #[arg(long, default_value_t = false, conflicts_with_all = ["version", "channel", "pin"])]
+    list: bool,
+}
+
+pub async fn run_self_update(
+    cmd: SelfUpdateCommand,
+    config_overrides: CliConfigOverrides,
+) -> anyhow::Result<()> {
+    let codex_home = find_codex_home().context("failed to resolve CODEX_HOME")?;
+    if cmd.list {
+        let default = default_version(&codex_home);
+        for version in installed_versions(&codex_home)? {
+            let marker = if default.as_deref() == Some(version.as_str()) {
+                "\tdefault"
+            } else {
+                ""
+            };
+            println!("{version}{marker}");
+        }
+        return Ok(());
+    }
+
+    let mut cli_kv_overrides = config_overrides
+        .parse_overrides()
+        .map_err(anyhow::Error::msg)?;
+    if let Some(channel) = cmd.channel {
+        cli_kv_overrides.push((
+            "self_update.channel".to_string(),
+            toml::Value::String(channel),
+        ));
+    }
+    let config =
+        Config::load_with_cli_overrides(cli_kv_overrides, ConfigOverrides::default()).await?;
+    let manifest = fetch_manifest(&config.self_update).await?;
+    let version = match cmd.version {
+        Some(version) => version,
+        None => manifest.latest(config.self_update.channel)?.to_string(),
+    };
+    install_version(&codex_home, &manifest, &version).await?;
+    if cmd.pin {
+        let pin = pin_version(&config.cwd, &version)?;
+        println!("Pinned {} to Codex {version}", pin.display());
+    } else {
+        set_default_version(&codex_home, &version)?;
+        println!("Codex {version} is now the default");
+    }
+    Ok(())
+}
+
+/// Run the Codex version selected for the working directory, when it is not
+/// this one: the version pinned by the project, or else the default version
+/// installed by `codex self-update`. Returns when this executable should run.
+pub fn launch_selected_version() {
+    let (Ok(codex_home), Ok(cwd), Ok(current_exe)) = (
+        find_codex_home(),
+        std::env::current_dir(),
+        std::env::current_exe(),
+    ) else {
+        return;
+    };
+    match resolve_launch(&codex_home, &cwd, &current_exe) {
+        Launch::Current => {}
+        Launch::Missing(version) => eprintln!(
+            "WARNING: Codex {version} is selected but not installed; running {CURRENT_VERSION}. \
+             Install it with `codex self-update --version {version}`."
+        ),
+        Launch::Installed(executable) => {
+            let err = relaunch(&executable);
+            eprintln!(
+                "WARNING: failed to run {}: {err}; running {CURRENT_VERSION}.",
+                executable.display()
+            );
+        }
+    }
+}
+
+/// Replace this process with `executable`, keeping the arguments, including
+/// the name it was invoked by. Only returns on failure.
+#[cfg(unix)]
+fn relaunch(executable: &std::path::Path) -> std::io::Error {
+    use std::os::unix::process::CommandExt;
+
+    let mut args = std::env::args_os();
+    let mut command = std::process::Command::new(executable);
+    if let Some(arg0) = args.next() {
+        command.arg0(arg0);
+    }
+    command.args(args).exec()
+}
+
+#[cfg(not(unix))]
+fn relaunch(executable: &std::path::Path) -> std::io::Error {
+    match std::process::Command::new(executable)
+        .args(std::env::args_os().skip(1))
+        .status()
+    {
+        Ok(status) => std::process::exit(status.code().unwrap_or(1)),
+        Err(err) => err,
+    }
+}
@@ -1,12 +1,19 @@
+use crate::blocking_io::run_blocking;
 use crate::codex::Session;
 use crate::codex::TurnContext;
+use crate::dirty_files;
+use crate::features::Feature;
 use crate::function_tool::FunctionCallError;
+use crate::protocol::AskForApproval;
 use crate::protocol::FileChange;
+use crate::protocol::PatchHunk;
 use crate::protocol::ReviewDecision;
+use crate::protocol::UserChange;
 use crate::safety::SafetyCheck;
 use crate::safety::assess_patch_safety;
 use codex_apply_patch::ApplyPatchAction;
 use codex_apply_patch::ApplyPatchFileChange;
+use codex_apply_patch::MaybeApplyPatchVerified;
 use std::collections::HashMap;
 use std::path::PathBuf;
 
@@ -31,6 +38,27 @@ pub(crate) enum InternalApplyPatchInvocation {
 pub(crate) struct ApplyPatchExec {
     pub(crate) action: ApplyPatchAction,
     pub(crate) user_explicitly_approved_this_action: bool,
+    /// Tells the model which hunks the user rejected when only part of the
+    /// patch was approved.
+    pub(crate) review_note: Option<String>,
+}
+
+impl ApplyPatchExec {
+    /// `content` with the review note, if any, appended.
+    pub(crate) fn with_review_note(&self, content: String) -> String {
+        match &self.review_note {
+            Some(note) => format!("{content}\n{note}"),
+            None => content,
+        }
+    }
+}
+
+/// Parse `argv` as an `apply_patch` call and verify it against the files in
+/// `cwd`, which are read on the blocking pool.
+pub(crate) async fn verify_patch(argv: Vec<String>, cwd: PathBuf) -> MaybeApplyPatchVerified {
+    run_blocking(move || codex_apply_patch::maybe_parse_apply_patch_verified(&argv, &cwd))
+        .await
+        .unwrap_or_else(|err| MaybeApplyPatchVerified::CorrectnessError(err.into()))
 }
 
 pub(crate) async fn apply_patch(
@@ -39,20 +67,30 @@ pub(crate) async fn apply_patch(
     call_id: &str,
     action: ApplyPatchAction,
 ) -> InternalApplyPatchInvocation {
-    match assess_patch_safety(
+    let safety = assess_patch_safety(
         &action,
         turn_context.approval_policy,
         &turn_context.sandbox_policy,
         &turn_context.cwd,
-    ) {
+    );
+    let user_changes = match safety {
+        SafetyCheck::Reject { .. } => Vec::new(),
+        SafetyCheck::AutoApprove { .. } | SafetyCheck::AskUser => {
+            guarded_user_changes(sess, turn_context, &action).await
+        }
+    };
+    match safety {
         SafetyCheck::AutoApprove {
             user_explicitly_approved,
             ..
-        } => InternalApplyPatchInvocation::DelegateToExec(ApplyPatchExec {
-            action,
-            user_explicitly_approved_this_action: user_explicitly_approved,
-        }),
-        SafetyCheck::AskUser => {
+        } if user_changes.is_empty() => {
+            InternalApplyPatchInvocation::DelegateToExec(ApplyPatchExec {
+                action,
+                user_explicitly_approved_this_action: user_explicitly_approved,
+                review_note: None,
+            })
+        }
+        SafetyCheck::AutoApprove { .. } | SafetyCheck::AskUser => {
             // Compute a readable summary of path changes to include in the
             // approval request so the user can make an informed decision.
             //
@@ -60,21 +98,34 @@ pub(crate) async fn apply_patch(
             // give the user the option to expand the set of writable roots so
             // that similar patches can be auto-approved in the future during
             // this session.
+            let reason = if user_changes.is_empty() {
+                None
+            } else {
+                Some(dirty_files::approval_reason(&user_changes))
+            };
             let rx_approve = sess
                 .request_patch_approval(
                     turn_context,
                     call_id.to_owned(),
                     convert_apply_patch_to_protocol(&action),
-                    None,
+                    convert_review_hunks(&action),
+                    user_changes,
+                    reason,
                     None,
                 )
                 .await;
             match rx_approve.await.unwrap_or_default() {
-                ReviewDecision::Approved | ReviewDecision::ApprovedForSession => {
-                    InternalApplyPatchInvocation::DelegateToExec(ApplyPatchExec {
-                        action,
-                        user_explicitly_approved_this_action: true,
-                    })
+                ReviewDecision::Approved
+                | ReviewDecision::ApprovedForSession
+                | ReviewDecision::ApprovedPrefixForSession => {
+                    match sess.take_hunk_decisions(&turn_context.sub_id).await {
+                        Some(decisions) => apply_hunk_decisions(action, decisions).await,
+                        None => InternalApplyPatchInvocation::DelegateToExec(ApplyPatchExec {
+                            action,
+                            user_explicitly_approved_this_action: true,
+                            review_note: None,
+                        }),
+                    }
                 }
                 ReviewDecision::Denied | ReviewDecision::Abort => {
                     InternalApplyPatchInvocation::Output(Err(FunctionCallError::RespondToModel(
@@ -89,6 +140,146 @@ pub(crate) async fn apply_patch(
     }
 }
 
+/// The user's uncommitted changes to files `action` modifies, when the
+/// `dirty_file_guard` feature is on and there is someone to ask.
+async fn guarded_user_changes(
+    sess: &Session,
+    turn_context: &TurnContext,
+    action: &ApplyPatchAction,
+) -> Vec<UserChange> {
+    if matches!(turn_context.approval_policy, AskForApproval::Never)
+        || !sess.enabled(Feature::DirtyFileGuard).await
+    {
+        return Vec::new();
+    }
+    dirty_files::user_changes(sess, &turn_context.cwd, action).await
+}
+
+/// Narrow `action` down to the hunks the user approved.
+async fn apply_hunk_decisions(
+    action: ApplyPatchAction,
+    decisions: Vec<ReviewDecision>,
+) -> InternalApplyPatchInvocation {
+    let hunks = action.review_hunks();
+    let (accepted, rejected): (Vec<usize>, Vec<usize>) = (0..hunks.len()).partition(|index| {
+        matches!(
+            decisions.get(*index),
+            Some(
+                ReviewDecision::Approved
+                    | ReviewDecision::ApprovedForSession
+                    | ReviewDecision::ApprovedPrefixForSession
+            )
+        )
+    });
+    if rejected.is_empty() {
+        return InternalApplyPatchInvocation::DelegateToExec(ApplyPatchExec {
+            action,
+            user_explicitly_approved_this_action: true,
+            review_note: None,
+        });
+    }
+    // Selecting re-verifies the accepted hunks against the files.
+    let selected = match run_blocking(move || action.select_hunks(&accepted)).await {
+        Ok(selected) => selected,
+        Err(err) => Err(err.into()),
+    };
+    match selected {
+        Ok(Some(partial)) => {
+            let mut note = format!(
+                "The user rejected {} of {} hunks; these were not applied:",
+                rejected.len(),
+                hunks.len()
+            );
+            for index in rejected {
+                let hunk = &hunks[index];
+                note.push_str(&format!("\n{}\n{}", hunk.path.display(), hunk.unified_diff));
+            }
+            InternalApplyPatchInvocation::DelegateToExec(ApplyPatchExec {
+                action: partial,
+                user_explicitly_approved_this_action: true,
+                review_note: Some(note),
+            })
+        }
+        Ok(None) => InternalApplyPatchInvocation::Output(Err(FunctionCallError::RespondToModel(
+            "patch rejected by user".to_string(),
+        ))),
+        Err(err) => InternalApplyPatchInvocation::Output(Err(FunctionCallError::RespondToModel(
+            format!("failed to apply the hunks the user approved: {err}"),
+        ))),
+    }
+}
+
+/// What applying `action` would do, for a dry run: the affected files, one
+/// per line with `A`, `D`, `M` or `R` for added, deleted, modified and moved,
+/// followed by the diff of every hunk.
+pub(crate) fn dry_run_report(action: &ApplyPatchAction) -> String {
+    let mut files: Vec<(&PathBuf, &ApplyPatchFileChange)> = action.changes().iter().collect();
+    files.sort_by_key(|(path, _)| *path);
+    let mut report = format!(
+        "Dry run: the patch applies cleanly and would change {} file(s); nothing was written.",
+        files.len()
+    );
+    for (path, change) in files {
+        let line = match change {
+            ApplyPatchFileChange::Add { .. } => format!("A {}", path.display()),
+            ApplyPatchFileChange::Delete { .. } => format!("D {}", path.display()),
+            ApplyPatchFileChange::Update {
+                move_path: Some(dest),
+                ..
+            } => format!("R {} -> {}", path.display(), dest.display()),
+            ApplyPatchFileChange::Update { .. } => format!("M {}", path.display()),
+            ApplyPatchFileChange::Binary {
+                previous_size: None,
+                ..
+            } => format!("A {}", path.display()),
+            ApplyPatchFileChange::Binary { .. } => format!("M {}", path.display()),
+        };
+        report.push('\n');
+        report.push_str(&line);
+    }
+    for hunk in action.review_hunks() {
+        report.push_str(&format!("\n{}\n{}", hunk.path.display(), hunk.unified_diff));
+    }
+    report
+}
+
+/// Every file `action` writes, including the destinations of moves.
+pub(crate) fn target_paths(action: &ApplyPatchAction) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    for (path, change) in action.changes() {
+        paths.push(path.clone());
+        if let ApplyPatchFileChange::Update {
+            move_path: Some(dest),
+            ..
+        } = change
+        {
+            paths.push(dest.clone());
+        }
+    }
+    paths
+}
+
+/// Files the patch deletes outright.
+pub(crate) fn deleted_paths(action: &ApplyPatchAction) -> Vec<PathBuf> {
+    action
+        .changes()
+        .iter()
+        .filter(|(_, change)| matches!(change, ApplyPatchFileChange::Delete { .. }))
+        .map(|(path, _)| path.clone())
+        .collect()
+}
+
+pub(crate) fn convert_review_hunks(action: &ApplyPatchAction) -> Vec<PatchHunk> {
+    action
+        .review_hunks()
+        .into_iter()
+        .map(|hunk| PatchHunk {
+            path: hunk.path,
+            unified_diff: hunk.unified_diff,
+        })
+        .collect()
+}
+
 pub(crate) fn convert_apply_patch_to_protocol(
     action: &ApplyPatchAction,
 ) -> HashMap<PathBuf, FileChange> {
@@ -110,6 +301,13 @@ pub(crate) fn convert_apply_patch_to_protocol(
                 unified_diff: unified_diff.clone(),
                 move_path: move_path.clone(),
             },
+            ApplyPatchFileChange::Binary {
+                content,
+                previous_size,
+            } => FileChange::Binary {
+                size: content.len() as u64,
+                previous_size: *previous_size,
+            },
         };
         result.insert(path.clone(), protocol_change);
     }
@@ -139,4 +337,68 @@ mod tests {
             })
         );
     }
+
+    #[tokio::test]
+    async fn rejected_hunks_are_left_out_and_reported() {
+        let tmp = tempdir().expect("tmp");
+        std::fs::write(tmp.path().join("a.txt"), "one\ntwo\n").expect("write");
+        let patch = "*** Begin Patch\n\
+                     *** Update File: a.txt\n@@\n-one\n+ONE\n\
+                     *** Add File: b.txt\n+new\n\
+                     *** End Patch";
+        let argv = vec!["apply_patch".to_string(), patch.to_string()];
+        let codex_apply_patch::MaybeApplyPatchVerified::Body(action) =
+            codex_apply_patch::maybe_parse_apply_patch_verified(&argv, tmp.path())
+        else {
+            panic!("expected a verified patch");
+        };
+
+        let InternalApplyPatchInvocation::DelegateToExec(exec) =
+            apply_hunk_decisions(action, vec![ReviewDecision::Approved]).await
+        else {
+            panic!("expected the approved hunk to be applied");
+        };
+        assert_eq!(
+            (exec.action.patch.as_str(), exec.review_note.as_deref()),
+            (
+                "*** Begin Patch\n*** Update File: a.txt\n@@\n-one\n+ONE\n*** End Patch\n",
+                Some(&*format!(
+                    "The user rejected 1 of 2 hunks; these were not applied:\n\
+                     {}\n@@ -0,0 +1 @@\n+new\n",
+                    tmp.path().join("b.txt").display()
+                )),
+            )
+        );
+    }
+
+    #[test]
+    fn dry_run_report_lists_files_and_hunks() {
+        let tmp = tempdir().expect("tmp");
+        std::fs::write(tmp.path().join("a.txt"), "one\ntwo\n").expect("write");
+        let patch = "*** Begin Patch\n\
+                     *** Update File: a.txt\n@@\n-one\n+ONE\n\
+                     *** Add File: b.txt\n+new\n\
+                     *** End Patch";
+        let argv = vec!["apply_patch".to_string(), patch.to_string()];
+        let codex_apply_patch::MaybeApplyPatchVerified::Body(action) =
+            codex_apply_patch::maybe_parse_apply_patch_verified(&argv, tmp.path())
+        else {
+            panic!("expected a verified patch");
+        };
+
+        let a = tmp.path().join("a.txt");
+        let b = tmp.path().join("b.txt");
+        assert_eq!(
+            dry_run_report(&action),
+            format!(
+                "Dry run: the patch applies cleanly and would change 2 file(s); nothing was \
+                 written.\n\
+                 M {a}\nA {b}\n\
+                 {a}\n@@ -1,2 +1,2 @@\n-one\n+ONE\n two\n\n\
+                 {b}\n@@ -0,0 +1 @@\n+new\n",
+                a = a.display(),
+                b = b.display()
+            )
+        );
+    }
 }
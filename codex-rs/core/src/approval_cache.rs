@@ -0,0 +1,315 @@
+//! Approvals remembered across sessions.
+//!
+//! With `approval_cache.persist`, an "approve for session" decision on a
+//! command is also recorded in `$CODEX_HOME/approvals.json`, keyed by a hash
+//! of the serialized approval key and the project it was given in (the git
+//! repository root, or the working directory outside a repository). Later
+//! sessions in that project replay the approval under the same approval
+//! policy, sandbox policy and working directory until it expires after
+//! `approval_cache.ttl_days`. `codex approvals` lists and revokes entries.
+//!
+//! Sessions and `codex approvals` update the file under an advisory lock on
+//! `approvals.json.lock`, so that concurrent updates are not lost.
+
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+use chrono::DateTime;
+use chrono::Utc;
+use serde::Deserialize;
+use serde::Serialize;
+use sha2::Digest;
+use sha2::Sha256;
+use tempfile::NamedTempFile;
+
+use crate::git_info::get_git_repo_root;
+use crate::tools::sandboxing::ApprovalConditions;
+
+const APPROVALS_FILE: &str = "approvals.json";
+const LOCK_FILE: &str = "approvals.json.lock";
+
+/// Length of the id shown by `codex approvals list`.
+const ID_LEN: usize = 12;
+
+/// An approval recorded for replay in later sessions.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PersistedApproval {
+    /// Identifies the entry for `codex approvals revoke`.
+    pub id: String,
+    /// SHA-256 of the serialized approval key.
+    pub key_hash: String,
+    pub project_root: PathBuf,
+    /// What was approved, e.g. the command line and where it runs.
+    pub summary: String,
+    pub granted_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    /// What the approval was given under; it is replayed only under the
+    /// same. Entries recorded without them are never replayed.
+    #[serde(default)]
+    pub(crate) conditions: Option<ApprovalConditions>,
+}
+
+/// The file approvals are recorded in.
+pub fn approvals_path(codex_home: &Path) -> PathBuf {
+    codex_home.join(APPROVALS_FILE)
+}
+
+/// The recorded approvals that have not expired, oldest first.
+pub fn load_approvals(codex_home: &Path) -> io::Result<Vec<PersistedApproval>> {
+    let now = Utc::now();
+    Ok(read_all(codex_home)?
+        .into_iter()
+        .filter(|approval| approval.expires_at > now)
+        .collect())
+}
+
+/// Forget the approval with `id`. Returns whether there was one.
+pub fn revoke_approval(codex_home: &Path, id: &str) -> io::Result<bool> {
+    let _lock = lock(codex_home)?;
+    let mut approvals = load_approvals(codex_home)?;
+    let before = approvals.len();
+    approvals.retain(|approval| approval.id != id);
+    write_all(codex_home, &approvals)?;
+    Ok(approvals.len() != before)
+}
+
+/// Forget every recorded approval. Returns how many there were.
+pub fn clear_approvals(codex_home: &Path) -> io::Result<usize> {
+    let _lock = lock(codex_home)?;
+    let count = load_approvals(codex_home)?.len();
+    match std::fs::remove_file(approvals_path(codex_home)) {
+        Ok(()) => Ok(count),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(0),
+        Err(err) => Err(err),
+    }
+}
+
+/// The session's handle on the recorded approvals.
+#[derive(Debug, Clone)]
+pub(crate) struct PersistentApprovals {
+    codex_home: PathBuf,
+    ttl: chrono::Duration,
+}
+
+impl PersistentApprovals {
+    pub(crate) fn new(codex_home: PathBuf, ttl_days: u64) -> Self {
+        let ttl_days = i64::try_from(ttl_days).unwrap_or(i64::MAX);
+        Self {
+            codex_home,
+            ttl: chrono::Duration::try_days(ttl_days).unwrap_or(chrono::Duration::MAX),
+        }
+    }
+
+    /// Whether the serialized approval `key` was recorded for the project
+    /// of the working directory of `conditions`, under `conditions`, and has
+    /// not expired.
+    pub(crate) async fn contains(&self, key: &str, conditions: &ApprovalConditions) -> bool {
+        let this = self.clone();
+        let key = key.to_string();
+        let conditions = conditions.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            let id = entry_id(&key_hash(&key), &project_root(&conditions.cwd));
+            let approvals = load_approvals(&this.codex_home)?;
+            Ok::<_, io::Error>(approvals.iter().any(|approval| {
+                approval.id == id && approval.conditions.as_ref() == Some(&conditions)
+            }))
+        })
+        .await
+        .map_err(io::Error::other)
+        .and_then(|result| result);
+        result.unwrap_or_else(|err| {
+            tracing::warn!("failed to read recorded approvals: {err}");
+            false
+        })
+    }
+
+    /// Record the serialized approval `key` for the project of the working
+    /// directory of `conditions`.
+    pub(crate) async fn remember(
+        &self,
+        key: &str,
+        summary: String,
+        conditions: &ApprovalConditions,
+    ) {
+        let this = self.clone();
+        let key = key.to_string();
+        let conditions = conditions.clone();
+        let result = tokio::task::spawn_blocking(move || this.record(&key, summary, conditions))
+            .await
+            .map_err(io::Error::other)
+            .and_then(|result| result);
+        if let Err(err) = result {
+            tracing::warn!("failed to record approval: {err}");
+        }
+    }
+
+    fn record(&self, key: &str, summary: String, conditions: ApprovalConditions) -> io::Result<()> {
+        let key_hash = key_hash(key);
+        let project_root = project_root(&conditions.cwd);
+        let granted_at = Utc::now();
+        let approval = PersistedApproval {
+            id: entry_id(&key_hash, &project_root),
+            key_hash,
+            project_root,
+            summary,
+            granted_at,
+            expires_at: granted_at
+                .checked_add_signed(self.ttl)
+                .unwrap_or(DateTime::<Utc>::MAX_UTC),
+            conditions: Some(conditions),
+        };
+        let _lock = lock(&self.codex_home)?;
+        let mut approvals = load_approvals(&self.codex_home)?;
+        approvals.retain(|existing| existing.id != approval.id);
+        approvals.push(approval);
+        write_all(&self.codex_home, &approvals)
+    }
+}
+
+/// Hold the advisory lock on the approvals file until the returned file is
+/// dropped.
+fn lock(codex_home: &Path) -> io::Result<File> {
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(codex_home.join(LOCK_FILE))?;
+    file.lock()?;
+    Ok(file)
+}
+
+fn read_all(codex_home: &Path) -> io::Result<Vec<PersistedApproval>> {
+    match std::fs::read(approvals_path(codex_home)) {
+        Ok(json) => serde_json::from_slice(&json).map_err(io::Error::other),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(err) => Err(err),
+    }
+}
+
+/// Replace the file in one rename, so a concurrent reader never sees it
+/// half written.
+fn write_all(codex_home: &Path, approvals: &[PersistedApproval]) -> io::Result<()> {
+    let json = serde_json::to_vec_pretty(approvals).map_err(io::Error::other)?;
+    let mut tmp = NamedTempFile::new_in(codex_home)?;
+    tmp.write_all(&json)?;
+    tmp.persist(approvals_path(codex_home))
+        .map(drop)
+        .map_err(|err| err.error)
+}
+
+fn key_hash(key: &str) -> String {
+    format!("{:x}", Sha256::digest(key.as_bytes()))
+}
+
+fn project_root(cwd: &Path) -> PathBuf {
+    get_git_repo_root(cwd).unwrap_or_else(|| cwd.to_path_buf())
+}
+
+fn entry_id(key_hash: &str, project_root: &Path) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(key_hash.as_bytes());
+    hasher.update(project_root.to_string_lossy().as_bytes());
+    let mut id = format!("{:x}", hasher.finalize());
+    id.truncate(ID_LEN);
+    id
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_protocol::protocol::AskForApproval;
+    use codex_protocol::protocol::SandboxPolicy;
+    use pretty_assertions::assert_eq;
+    use tempfile::tempdir;
+
+    fn conditions(cwd: &Path, approval_policy: AskForApproval) -> ApprovalConditions {
+        ApprovalConditions {
+            approval_policy,
+            sandbox_policy: SandboxPolicy::new_read_only_policy(),
+            cwd: cwd.to_path_buf(),
+            files: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn approvals_are_replayed_per_project_until_revoked() {
+        let home = tempdir().expect("tempdir");
+        let project = tempdir().expect("tempdir");
+        let other = tempdir().expect("tempdir");
+        let approvals = PersistentApprovals::new(home.path().to_path_buf(), 30);
+        let key = r#"{"command":["cargo","test"],"escalated":false}"#;
+        let granted = conditions(project.path(), AskForApproval::OnRequest);
+
+        approvals
+            .remember(key, "cargo test".to_string(), &granted)
+            .await;
+        approvals
+            .remember(key, "cargo test".to_string(), &granted)
+            .await;
+        assert!(approvals.contains(key, &granted).await);
+        assert!(
+            !approvals
+                .contains(key, &conditions(other.path(), AskForApproval::OnRequest))
+                .await
+        );
+        assert!(
+            !approvals
+                .contains(r#"{"command":["cargo","build"]}"#, &granted)
+                .await
+        );
+
+        let listed = load_approvals(home.path()).expect("load");
+        assert_eq!(listed.len(), 1);
+        assert_eq!(
+            (listed[0].summary.as_str(), listed[0].project_root.as_path()),
+            ("cargo test", project.path())
+        );
+
+        assert!(revoke_approval(home.path(), &listed[0].id).expect("revoke"));
+        assert!(!approvals.contains(key, &granted).await);
+        assert_eq!(clear_approvals(home.path()).expect("clear"), 0);
+    }
+
+    #[tokio::test]
+    async fn approvals_are_replayed_only_under_the_same_conditions() {
+        let home = tempdir().expect("tempdir");
+        let project = tempdir().expect("tempdir");
+        let approvals = PersistentApprovals::new(home.path().to_path_buf(), 30);
+        let granted = conditions(project.path(), AskForApproval::OnRequest);
+        approvals
+            .remember("key", "cargo test".to_string(), &granted)
+            .await;
+
+        assert!(
+            !approvals
+                .contains(
+                    "key",
+                    &conditions(project.path(), AskForApproval::UnlessTrusted)
+                )
+                .await
+        );
+
+        // Entries recorded before conditions were kept are not replayed.
+        let mut listed = load_approvals(home.path()).expect("load");
+        listed[0].conditions = None;
+        write_all(home.path(), &listed).expect("write");
+        assert!(!approvals.contains("key", &granted).await);
+    }
+
+    #[tokio::test]
+    async fn expired_approvals_are_not_replayed() {
+        let home = tempdir().expect("tempdir");
+        let project = tempdir().expect("tempdir");
+        let approvals = PersistentApprovals::new(home.path().to_path_buf(), 0);
+        let granted = conditions(project.path(), AskForApproval::OnRequest);
+        approvals
+            .remember("key", "cargo test".to_string(), &granted)
+            .await;
+        assert!(!approvals.contains("key", &granted).await);
+        assert_eq!(load_approvals(home.path()).expect("load"), Vec::new());
+    }
+}
@@ -0,0 +1,177 @@
+//! `approval_rules`: user-configured rules that approve, refuse or ask about
+//! a command before the approval policy gets a say.
+//!
+//! The orchestrator consults the rules for every tool call that runs a
+//! command. The first rule whose conditions all hold decides, and the session
+//! announces it with `EventMsg::ApprovalRuleApplied` so frontends can show why
+//! a command ran without a prompt.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use codex_protocol::paths::is_within;
+use codex_protocol::protocol::ApprovalRuleDecision;
+use regex::Regex;
+use wildmatch::WildMatch;
+
+use crate::bash::extract_bash_command;
+use crate::config::types::ApprovalRule;
+
+/// The rule that decided a command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct AppliedRule {
+    /// The rule's name, or its position counting from 1.
+    pub(crate) label: String,
+    pub(crate) decision: ApprovalRuleDecision,
+}
+
+/// Checks that every `command_regex` compiles, so a typo fails at startup
+/// rather than silently never matching.
+pub(crate) fn validate_approval_rules(rules: &[ApprovalRule]) -> Result<(), String> {
+    for (index, rule) in rules.iter().enumerate() {
+        if let Some(pattern) = &rule.command_regex
+            && let Err(err) = Regex::new(pattern)
+        {
+            return Err(format!(
+                "approval rule {} has an invalid command_regex: {err}",
+                label(index, rule)
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// The first rule in `rules` that matches the command.
+pub(crate) fn find_approval_rule(
+    rules: &[ApprovalRule],
+    command: &[String],
+    cwd: &Path,
+    env: &HashMap<String, String>,
+) -> Option<AppliedRule> {
+    let line = command_line(command);
+    rules
+        .iter()
+        .enumerate()
+        .find(|(_, rule)| matches(rule, &line, cwd, env))
+        .map(|(index, rule)| AppliedRule {
+            label: label(index, rule),
+            decision: rule.decision,
+        })
+}
+
+fn matches(rule: &ApprovalRule, line: &str, cwd: &Path, env: &HashMap<String, String>) -> bool {
+    rule.command
+        .as_ref()
+        .is_none_or(|pattern| WildMatch::new(pattern).matches(line))
+        && rule
+            .command_regex
+            .as_ref()
+            .is_none_or(|pattern| Regex::new(pattern).is_ok_and(|regex| regex.is_match(line)))
+        && rule.cwd.as_ref().is_none_or(|root| is_within(cwd, root))
+        && rule.env.iter().all(|(name, pattern)| {
+            env.get(name)
+                .is_some_and(|value| WildMatch::new(pattern).matches(value))
+        })
+}
+
+/// The command as rules see it: the script of a `bash -lc` command, and the
+/// shell-quoted argv otherwise.
+pub(crate) fn command_line(command: &[String]) -> String {
+    if let Some((_, script)) = extract_bash_command(command) {
+        return script.to_string();
+    }
+    shlex::try_join(command.iter().map(String::as_str)).unwrap_or_else(|_| command.join(" "))
+}
+
+fn label(index: usize, rule: &ApprovalRule) -> String {
+    match &rule.name {
+        Some(name) => format!("`{name}`"),
+        None => format!("#{}", index + 1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::path::PathBuf;
+
+    fn rule(decision: ApprovalRuleDecision) -> ApprovalRule {
+        ApprovalRule {
+            name: None,
+            decision,
+            command: None,
+            command_regex: None,
+            cwd: None,
+            env: HashMap::new(),
+        }
+    }
+
+    fn argv(words: &[&str]) -> Vec<String> {
+        words.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn first_rule_whose_conditions_all_hold_decides() {
+        let rules = vec![
+            ApprovalRule {
+                name: Some("no pushes".to_string()),
+                command_regex: Some(r"^git push\b".to_string()),
+                ..rule(ApprovalRuleDecision::AutoDeny)
+            },
+            ApprovalRule {
+                command: Some("cargo test*".to_string()),
+                cwd: Some(PathBuf::from("/work")),
+                env: HashMap::from([("CI".to_string(), "t*".to_string())]),
+                ..rule(ApprovalRuleDecision::AutoApprove)
+            },
+            ApprovalRule {
+                command: Some("cargo *".to_string()),
+                ..rule(ApprovalRuleDecision::Ask)
+            },
+        ];
+        let ci = HashMap::from([("CI".to_string(), "true".to_string())]);
+        let work = Path::new("/work/app");
+
+        assert_eq!(
+            find_approval_rule(
+                &rules,
+                &argv(&["bash", "-lc", "git push origin"]),
+                work,
+                &ci
+            ),
+            Some(AppliedRule {
+                label: "`no pushes`".to_string(),
+                decision: ApprovalRuleDecision::AutoDeny,
+            })
+        );
+        assert_eq!(
+            find_approval_rule(&rules, &argv(&["cargo", "test", "-p", "core"]), work, &ci),
+            Some(AppliedRule {
+                label: "#2".to_string(),
+                decision: ApprovalRuleDecision::AutoApprove,
+            })
+        );
+        assert_eq!(
+            find_approval_rule(&rules, &argv(&["cargo", "test"]), Path::new("/tmp"), &ci),
+            Some(AppliedRule {
+                label: "#3".to_string(),
+                decision: ApprovalRuleDecision::Ask,
+            })
+        );
+        assert_eq!(
+            find_approval_rule(&rules, &argv(&["ls"]), work, &HashMap::new()),
+            None
+        );
+    }
+
+    #[test]
+    fn invalid_regexes_are_reported() {
+        let rules = vec![ApprovalRule {
+            command_regex: Some("(".to_string()),
+            ..rule(ApprovalRuleDecision::Ask)
+        }];
+        let err = validate_approval_rules(&rules).expect_err("invalid");
+        assert!(err.starts_with("approval rule #1 has an invalid command_regex"));
+    }
+}
@@ -0,0 +1,141 @@
+//! Session artifact store.
+//!
+//! Artifacts are files exchanged between the user and the agent that need not
+//! live in the workspace. The user hands one over with `Op::AddArtifact`; the
+//! agent sends one back with the `artifacts` tool after the user approves the
+//! transfer. Either way the file is copied under
+//! `$CODEX_HOME/artifacts/<conversation id>/<artifact id>/`, so later changes to
+//! the original do not affect what was exchanged.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_protocol::ConversationId;
+use codex_protocol::protocol::ArtifactDirection;
+use codex_protocol::protocol::ArtifactEvent;
+use uuid::Uuid;
+
+/// Default for `artifact_max_bytes`.
+pub(crate) const ARTIFACT_MAX_BYTES: u64 = 100 * 1024 * 1024;
+const ARTIFACTS_DIR: &str = "artifacts";
+
+/// Directory holding the artifacts of one conversation.
+pub(crate) fn artifacts_dir(codex_home: &Path, conversation_id: ConversationId) -> PathBuf {
+    codex_home
+        .join(ARTIFACTS_DIR)
+        .join(conversation_id.to_string())
+}
+
+/// Copy `source` into the store under `root`, enforcing `max_bytes`.
+pub(crate) async fn store_artifact(
+    root: &Path,
+    source: &Path,
+    name: Option<String>,
+    direction: ArtifactDirection,
+    max_bytes: u64,
+) -> Result<ArtifactEvent, String> {
+    let metadata = tokio::fs::metadata(source)
+        .await
+        .map_err(|err| format!("cannot read {}: {err}", source.display()))?;
+    if !metadata.is_file() {
+        return Err(format!("{} is not a regular file", source.display()));
+    }
+    let size_bytes = metadata.len();
+    if size_bytes > max_bytes {
+        return Err(format!(
+            "{} is {size_bytes} bytes, over the {max_bytes} byte artifact limit",
+            source.display()
+        ));
+    }
+
+    let name = artifact_name(name.as_deref(), source)?;
+    let id = Uuid::new_v4().to_string();
+    let dir = root.join(&id);
+    tokio::fs::create_dir_all(&dir)
+        .await
+        .map_err(|err| format!("failed to create {}: {err}", dir.display()))?;
+    let path = dir.join(&name);
+    tokio::fs::copy(source, &path)
+        .await
+        .map_err(|err| format!("failed to copy {}: {err}", source.display()))?;
+
+    Ok(ArtifactEvent {
+        id,
+        name,
+        direction,
+        path,
+        size_bytes,
+    })
+}
+
+/// The stored file name: `name` if given, else the source's file name. Path
+/// separators are replaced so the name cannot escape the artifact directory.
+fn artifact_name(name: Option<&str>, source: &Path) -> Result<String, String> {
+    let name = match name {
+        Some(name) => name.to_string(),
+        None => source
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+    };
+    let name = name.replace(['/', '\\'], "_");
+    match name.trim() {
+        "" | "." | ".." => Err(format!("invalid artifact name {name:?}")),
+        _ => Ok(name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::tempdir;
+
+    #[test]
+    fn names_cannot_escape_the_artifact_directory() {
+        let source = Path::new("/tmp/logs/bundle.tar.gz");
+        assert_eq!(artifact_name(None, source), Ok("bundle.tar.gz".to_string()));
+        assert_eq!(
+            artifact_name(Some("../../etc/passwd"), source),
+            Ok(".._.._etc_passwd".to_string())
+        );
+        assert_eq!(
+            artifact_name(Some(".."), source),
+            Err("invalid artifact name \"..\"".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn stores_a_copy_and_enforces_the_size_limit() {
+        let dir = tempdir().expect("tempdir");
+        let source = dir.path().join("report.csv");
+        std::fs::write(&source, "a,b\n1,2\n").expect("write");
+        let root = dir.path().join("store");
+
+        let artifact = store_artifact(&root, &source, None, ArtifactDirection::Inbound, 8)
+            .await
+            .expect("store");
+        assert_eq!(
+            artifact,
+            ArtifactEvent {
+                id: artifact.id.clone(),
+                name: "report.csv".to_string(),
+                direction: ArtifactDirection::Inbound,
+                path: root.join(&artifact.id).join("report.csv"),
+                size_bytes: 8,
+            }
+        );
+        assert_eq!(
+            std::fs::read_to_string(&artifact.path).expect("read"),
+            "a,b\n1,2\n"
+        );
+
+        assert_eq!(
+            store_artifact(&root, &source, None, ArtifactDirection::Outbound, 7).await,
+            Err(format!(
+                "{} is 8 bytes, over the 7 byte artifact limit",
+                source.display()
+            ))
+        );
+    }
+}
@@ -0,0 +1,540 @@
+//! Audit log of the commands and patches a session runs.
+//!
+//! Unless `audit.enabled` is turned off, every command and patch that goes
+//! through the tool orchestrator appends a line to
+//! `$CODEX_HOME/sessions/audit/<conversation id>.jsonl`: what ran and where,
+//! the sandbox it ran under, the approval decision and who made it, how it
+//! ended and how long it took. The file is only ever appended to, and its
+//! entries are chained: each holds the SHA-256 of the entry before it
+//! (`prev_hash`) and of its own line (`hash`), so editing, reordering or
+//! removing an entry breaks the chain from there on. [`verify_audit_log`]
+//! finds the first broken entry; `codex audit` lists, verifies and exports
+//! the entries.
+
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use chrono::DateTime;
+use chrono::Utc;
+use codex_protocol::ConversationId;
+use codex_protocol::protocol::ReviewDecision;
+use codex_protocol::protocol::SandboxPolicy;
+use serde::Deserialize;
+use serde::Serialize;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+
+use crate::error::CodexErr;
+use crate::error::SandboxErr;
+use crate::exec::SandboxType;
+use crate::parse_command::shlex_join;
+use crate::rollout::SESSIONS_SUBDIR;
+use crate::tools::sandboxing::ToolError;
+use crate::util::sha256_hex;
+
+const AUDIT_DIR: &str = "audit";
+
+/// The `prev_hash` of the first entry of a log.
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// The directory holding the audit logs, one per conversation.
+pub fn audit_dir(codex_home: &Path) -> PathBuf {
+    codex_home.join(SESSIONS_SUBDIR).join(AUDIT_DIR)
+}
+
+/// What a call ran.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AuditAction {
+    Command { command: Vec<String>, cwd: PathBuf },
+    Patch { files: Vec<PathBuf>, cwd: PathBuf },
+}
+
+impl AuditAction {
+    /// The command line, or the patched files separated by spaces.
+    pub fn target(&self) -> String {
+        match self {
+            AuditAction::Command { command, .. } => shlex_join(command),
+            AuditAction::Patch { files, .. } => {
+                let files: Vec<String> = files
+                    .iter()
+                    .map(|file| file.display().to_string())
+                    .collect();
+                files.join(" ")
+            }
+        }
+    }
+
+    pub fn cwd(&self) -> &Path {
+        match self {
+            AuditAction::Command { cwd, .. } | AuditAction::Patch { cwd, .. } => cwd,
+        }
+    }
+}
+
+/// Who decided that a call could run, or could not.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DecidedBy {
+    /// The approval policy or the static risk assessment, without asking.
+    #[default]
+    Policy,
+    /// An entry of `approval_rules`.
+    Rule,
+    /// The user, just now or with an approval given earlier in the session.
+    User,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditOutcome {
+    /// The call ran to the end, whatever its exit code.
+    Completed,
+    /// The call was refused before it ran.
+    Rejected,
+    /// The sandbox stopped the call and it was not run again without it.
+    SandboxDenied,
+    TimedOut,
+    /// The call could not be run or was killed.
+    Failed,
+}
+
+/// One line of an audit log.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Position in the log, starting at 1.
+    pub seq: u64,
+    pub time: DateTime<Utc>,
+    pub conversation_id: String,
+    pub turn_id: String,
+    pub call_id: String,
+    pub tool: String,
+    pub action: AuditAction,
+    /// The sandbox policy of the last attempt; `None` when nothing ran.
+    pub sandbox_policy: Option<SandboxPolicy>,
+    /// The sandbox the last attempt ran in, such as `macos_seatbelt` or
+    /// `none`; `None` when nothing ran.
+    pub sandbox: Option<String>,
+    /// Whether the last attempt ran without the sandbox the policy asks for.
+    pub escalated: bool,
+    /// The answer to the last approval prompt; `None` when none was shown.
+    pub decision: Option<ReviewDecision>,
+    pub decided_by: DecidedBy,
+    pub outcome: AuditOutcome,
+    pub exit_code: Option<i32>,
+    pub duration_ms: u64,
+    /// The `hash` of the entry before this one.
+    pub prev_hash: String,
+    /// SHA-256 of the entry's line up to this field.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub hash: String,
+}
+
+/// What the orchestrator learns about a call while running it.
+#[derive(Debug, Default)]
+pub(crate) struct AuditTrail {
+    pub(crate) sandbox_policy: Option<SandboxPolicy>,
+    pub(crate) sandbox: Option<SandboxType>,
+    pub(crate) escalated: bool,
+    pub(crate) decision: Option<ReviewDecision>,
+    pub(crate) decided_by: DecidedBy,
+}
+
+/// How a call that ended with `result` is recorded; `exit_code` is that of
+/// a successful run.
+pub(crate) fn audit_outcome<T>(
+    result: &Result<T, ToolError>,
+    exit_code: impl FnOnce(&T) -> Option<i32>,
+) -> (AuditOutcome, Option<i32>) {
+    match result {
+        Ok(out) => (AuditOutcome::Completed, exit_code(out)),
+        Err(ToolError::Rejected(_)) => (AuditOutcome::Rejected, None),
+        Err(ToolError::Codex(CodexErr::Sandbox(SandboxErr::Denied { output }))) => {
+            (AuditOutcome::SandboxDenied, Some(output.exit_code))
+        }
+        Err(ToolError::Codex(CodexErr::Sandbox(SandboxErr::Timeout { output }))) => {
+            (AuditOutcome::TimedOut, Some(output.exit_code))
+        }
+        Err(ToolError::Codex(_)) => (AuditOutcome::Failed, None),
+    }
+}
+
+/// A session's audit log.
+pub(crate) struct AuditLog {
+    path: PathBuf,
+    conversation_id: ConversationId,
+    /// `seq` and `hash` of the last entry, read from the file on first use
+    /// so that a resumed session continues its chain.
+    last: Mutex<Option<(u64, String)>>,
+}
+
+impl AuditLog {
+    pub(crate) fn new(codex_home: &Path, conversation_id: ConversationId) -> Self {
+        Self {
+            path: audit_dir(codex_home).join(format!("{conversation_id}.jsonl")),
+            conversation_id,
+            last: Mutex::new(None),
+        }
+    }
+
+    /// Appends an entry for a call of `tool` that ran `action`.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) async fn record(
+        &self,
+        turn_id: &str,
+        call_id: &str,
+        tool: &str,
+        action: AuditAction,
+        trail: AuditTrail,
+        (outcome, exit_code): (AuditOutcome, Option<i32>),
+        duration: Duration,
+    ) {
+        let mut last = self.last.lock().await;
+        let (seq, prev_hash) = match last.take() {
+            Some(last) => last,
+            None => last_link(&self.path).await,
+        };
+        let entry = AuditEntry {
+            seq: seq + 1,
+            time: Utc::now(),
+            conversation_id: self.conversation_id.to_string(),
+            turn_id: turn_id.to_string(),
+            call_id: call_id.to_string(),
+            tool: tool.to_string(),
+            action,
+            sandbox_policy: trail.sandbox_policy,
+            sandbox: trail
+                .sandbox
+                .map(|sandbox| sandbox_name(sandbox).to_string()),
+            escalated: trail.escalated,
+            decision: trail.decision,
+            decided_by: trail.decided_by,
+            outcome,
+            exit_code,
+            duration_ms: u64::try_from(duration.as_millis()).unwrap_or(u64::MAX),
+            prev_hash: prev_hash.clone(),
+            hash: String::new(),
+        };
+        match self.append(&entry).await {
+            Ok(hash) => *last = Some((entry.seq, hash)),
+            Err(err) => {
+                tracing::warn!("failed to write audit log entry: {err}");
+                *last = Some((seq, prev_hash));
+            }
+        }
+    }
+
+    /// Appends `entry`, whose `hash` is not set yet, and returns its hash.
+    async fn append(&self, entry: &AuditEntry) -> io::Result<String> {
+        let body = serde_json::to_string(entry).map_err(io::Error::other)?;
+        let hash = sha256_hex(body.as_bytes());
+        let line = format!("{},\"hash\":\"{hash}\"}}\n", &body[..body.len() - 1]);
+        if let Some(dir) = self.path.parent() {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+        let mut options = tokio::fs::OpenOptions::new();
+        options.create(true).append(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+        let mut file = options.open(&self.path).await?;
+        file.write_all(line.as_bytes()).await?;
+        file.flush().await?;
+        Ok(hash)
+    }
+}
+
+/// `seq` and `hash` of the last entry of the log at `path`.
+async fn last_link(path: &Path) -> (u64, String) {
+    let genesis = (0, GENESIS_HASH.to_string());
+    let contents = match tokio::fs::read_to_string(path).await {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return genesis,
+        Err(err) => {
+            tracing::warn!("failed to read audit log {}: {err}", path.display());
+            return genesis;
+        }
+    };
+    contents
+        .lines()
+        .rev()
+        .find(|line| !line.trim().is_empty())
+        .and_then(|line| serde_json::from_str::<AuditEntry>(line).ok())
+        .map_or(genesis, |entry| (entry.seq, entry.hash))
+}
+
+fn sandbox_name(sandbox: SandboxType) -> &'static str {
+    match sandbox {
+        SandboxType::None => "none",
+        SandboxType::MacosSeatbelt => "macos_seatbelt",
+        SandboxType::LinuxSeccomp => "linux_seccomp",
+        SandboxType::WindowsRestrictedToken => "windows_restricted_token",
+        SandboxType::Container => "container",
+    }
+}
+
+/// The audit logs under `codex_home` with the conversation each belongs
+/// to, least recently written first.
+pub fn list_audit_logs(codex_home: &Path) -> io::Result<Vec<(String, PathBuf)>> {
+    let dir = match std::fs::read_dir(audit_dir(codex_home)) {
+        Ok(dir) => dir,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+    let mut logs = Vec::new();
+    for entry in dir {
+        let path = entry?.path();
+        let Some(conversation_id) = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_suffix(".jsonl"))
+        else {
+            continue;
+        };
+        let modified = std::fs::metadata(&path)?.modified()?;
+        logs.push((modified, conversation_id.to_string(), path));
+    }
+    logs.sort();
+    Ok(logs
+        .into_iter()
+        .map(|(_, conversation_id, path)| (conversation_id, path))
+        .collect())
+}
+
+/// The entries of the audit log at `path`, oldest first.
+pub fn read_audit_log(path: &Path) -> io::Result<Vec<AuditEntry>> {
+    std::fs::read_to_string(path)?
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            serde_json::from_str(line)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+        })
+        .collect()
+}
+
+/// Whether the chain of an audit log holds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuditVerification {
+    Intact {
+        entries: usize,
+    },
+    /// The chain breaks at the 1-based `line`: it was changed, or an entry
+    /// before it was removed or moved.
+    Broken {
+        line: usize,
+        reason: String,
+    },
+}
+
+/// Checks every link of the audit log at `path`.
+pub fn verify_audit_log(path: &Path) -> io::Result<AuditVerification> {
+    let contents = std::fs::read_to_string(path)?;
+    let mut prev = (0, GENESIS_HASH.to_string());
+    let mut entries = 0;
+    for (index, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let broken = |reason: &str| AuditVerification::Broken {
+            line: index + 1,
+            reason: reason.to_string(),
+        };
+        let Ok(entry) = serde_json::from_str::<AuditEntry>(line) else {
+            return Ok(broken("not an audit entry"));
+        };
+        let body = line
+            .strip_suffix(&format!(",\"hash\":\"{}\"}}", entry.hash))
+            .map(|body| format!("{body}}}"));
+        if body.is_none_or(|body| sha256_hex(body.as_bytes()) != entry.hash) {
+            return Ok(broken("the entry does not match its hash"));
+        }
+        if entry.prev_hash != prev.1 {
+            return Ok(broken("prev_hash is not the hash of the entry before"));
+        }
+        if entry.seq != prev.0 + 1 {
+            return Ok(broken("seq does not follow the entry before"));
+        }
+        prev = (entry.seq, entry.hash);
+        entries += 1;
+    }
+    Ok(AuditVerification::Intact { entries })
+}
+
+const CSV_HEADER: &str = "seq,time,conversation_id,turn_id,call_id,tool,kind,target,cwd,\
+sandbox_policy,sandbox,escalated,decision,decided_by,outcome,exit_code,duration_ms,hash";
+
+/// `entries` as CSV, one row per entry, with [`AuditAction::target`] in
+/// `target`.
+pub fn audit_csv(entries: &[AuditEntry]) -> String {
+    let mut csv = format!("{CSV_HEADER}\n");
+    for entry in entries {
+        let kind = match &entry.action {
+            AuditAction::Command { .. } => "command",
+            AuditAction::Patch { .. } => "patch",
+        };
+        let fields = [
+            entry.seq.to_string(),
+            entry.time.to_rfc3339(),
+            entry.conversation_id.clone(),
+            entry.turn_id.clone(),
+            entry.call_id.clone(),
+            entry.tool.clone(),
+            kind.to_string(),
+            entry.action.target(),
+            entry.action.cwd().display().to_string(),
+            entry
+                .sandbox_policy
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_default(),
+            entry.sandbox.clone().unwrap_or_default(),
+            entry.escalated.to_string(),
+            entry
+                .decision
+                .map(|decision| snake_case(&decision))
+                .unwrap_or_default(),
+            snake_case(&entry.decided_by),
+            snake_case(&entry.outcome),
+            entry
+                .exit_code
+                .map(|code| code.to_string())
+                .unwrap_or_default(),
+            entry.duration_ms.to_string(),
+            entry.hash.clone(),
+        ];
+        let row: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+/// The serde name of a unit variant, such as `sandbox_denied`.
+pub fn snake_case<T: Serialize>(value: &T) -> String {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::String(name)) => name,
+        _ => String::new(),
+    }
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::tempdir;
+
+    fn trail() -> AuditTrail {
+        AuditTrail {
+            sandbox_policy: Some(SandboxPolicy::new_read_only_policy()),
+            sandbox: Some(SandboxType::LinuxSeccomp),
+            escalated: false,
+            decision: Some(ReviewDecision::Approved),
+            decided_by: DecidedBy::User,
+        }
+    }
+
+    async fn record(log: &AuditLog, command: &[&str]) {
+        let action = AuditAction::Command {
+            command: command.iter().map(ToString::to_string).collect(),
+            cwd: PathBuf::from("/repo"),
+        };
+        log.record(
+            "turn",
+            "call",
+            "shell",
+            action,
+            trail(),
+            (AuditOutcome::Completed, Some(0)),
+            Duration::from_millis(5),
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn entries_are_chained_and_edits_break_the_chain() {
+        let codex_home = tempdir().expect("tempdir");
+        let log = AuditLog::new(codex_home.path(), ConversationId::new());
+        record(&log, &["cargo", "test"]).await;
+        // A resumed session continues the chain of the same file.
+        let resumed = AuditLog {
+            path: log.path.clone(),
+            conversation_id: log.conversation_id,
+            last: Mutex::new(None),
+        };
+        record(&resumed, &["git", "status"]).await;
+
+        let entries = read_audit_log(&log.path).expect("read");
+        assert_eq!(
+            entries.iter().map(|entry| entry.seq).collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+        assert_eq!(entries[1].prev_hash, entries[0].hash);
+        assert_eq!(
+            verify_audit_log(&log.path).expect("verify"),
+            AuditVerification::Intact { entries: 2 }
+        );
+
+        let contents = std::fs::read_to_string(&log.path).expect("read");
+        std::fs::write(&log.path, contents.replace("cargo", "cargp")).expect("write");
+        assert_eq!(
+            verify_audit_log(&log.path).expect("verify"),
+            AuditVerification::Broken {
+                line: 1,
+                reason: "the entry does not match its hash".to_string(),
+            }
+        );
+
+        let second = contents.lines().nth(1).expect("second line");
+        std::fs::write(&log.path, format!("{second}\n")).expect("write");
+        assert_eq!(
+            verify_audit_log(&log.path).expect("verify"),
+            AuditVerification::Broken {
+                line: 1,
+                reason: "prev_hash is not the hash of the entry before".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn csv_quotes_fields_that_need_it() {
+        let entry = AuditEntry {
+            seq: 1,
+            time: DateTime::<Utc>::UNIX_EPOCH,
+            conversation_id: "c".to_string(),
+            turn_id: "t".to_string(),
+            call_id: "call".to_string(),
+            tool: "shell".to_string(),
+            action: AuditAction::Command {
+                command: vec!["echo".to_string(), "a, \"b\"".to_string()],
+                cwd: PathBuf::from("/repo"),
+            },
+            sandbox_policy: None,
+            sandbox: None,
+            escalated: false,
+            decision: None,
+            decided_by: DecidedBy::Policy,
+            outcome: AuditOutcome::Rejected,
+            exit_code: None,
+            duration_ms: 0,
+            prev_hash: GENESIS_HASH.to_string(),
+            hash: "h".to_string(),
+        };
+        assert_eq!(
+            audit_csv(&[entry]).lines().nth(1),
+            Some(
+                "1,1970-01-01T00:00:00+00:00,c,t,call,shell,command,\"echo 'a, \"\"b\"\"'\",\
+                 /repo,,,false,,policy,rejected,,0,h"
+            )
+        );
+    }
+}
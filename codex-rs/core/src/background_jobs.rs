@@ -0,0 +1,346 @@
+//! Shell commands started with `background: true`.
+//!
+//! A dev server or watch task never finishes, so waiting for it would hold the
+//! turn until the command times out. A background job is spawned under the
+//! same sandbox a foreground command would get, and the `shell` call returns
+//! its id at once. Output is buffered until the model reads it with
+//! `job_output`; `job_status` and `job_kill` report on and stop jobs. Jobs are
+//! killed when the session ends.
+
+use std::collections::BTreeMap;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::atomic::AtomicU32;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use std::time::Instant;
+
+use serde::Serialize;
+use tokio::io::AsyncRead;
+use tokio::io::AsyncReadExt;
+use tokio::process::Child;
+use tokio::sync::watch;
+use tokio_util::sync::CancellationToken;
+
+use crate::exec::SandboxType;
+use crate::protocol::SandboxPolicy;
+use crate::sandboxing::ExecEnv;
+use crate::spawn::StdioPolicy;
+use crate::spawn::spawn_child_async;
+
+/// Output kept per job; older unread output is dropped first.
+pub(crate) const JOB_OUTPUT_MAX_BYTES: usize = 1024 * 1024;
+const READ_CHUNK_SIZE: usize = 8192;
+/// How long `kill` waits for the job to exit.
+const KILL_WAIT: Duration = Duration::from_secs(2);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub(crate) enum JobStatus {
+    Running,
+    Exited { exit_code: i32 },
+    Killed,
+}
+
+/// Output a job produced since it was last read.
+#[derive(Debug, Default)]
+pub(crate) struct JobOutputBuffer {
+    unread: Vec<u8>,
+    /// Unread bytes discarded to stay within [`JOB_OUTPUT_MAX_BYTES`].
+    dropped: usize,
+}
+
+impl JobOutputBuffer {
+    pub(crate) fn push(&mut self, chunk: &[u8]) {
+        self.unread.extend_from_slice(chunk);
+        let excess = self.unread.len().saturating_sub(JOB_OUTPUT_MAX_BYTES);
+        if excess > 0 {
+            self.unread.drain(..excess);
+            self.dropped += excess;
+        }
+    }
+
+    /// The unread output and how many bytes of it were dropped.
+    pub(crate) fn take(&mut self) -> (Vec<u8>, usize) {
+        (
+            std::mem::take(&mut self.unread),
+            std::mem::take(&mut self.dropped),
+        )
+    }
+
+    fn unread_bytes(&self) -> usize {
+        self.unread.len()
+    }
+}
+
+pub(crate) struct BackgroundJob {
+    pub(crate) id: u32,
+    pub(crate) command: Vec<String>,
+    pub(crate) cwd: PathBuf,
+    started: Instant,
+    output: Arc<Mutex<JobOutputBuffer>>,
+    status: watch::Receiver<JobStatus>,
+    cancel: CancellationToken,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub(crate) struct JobSummary {
+    pub(crate) job_id: u32,
+    pub(crate) command: String,
+    pub(crate) cwd: PathBuf,
+    pub(crate) status: JobStatus,
+    pub(crate) elapsed_secs: u64,
+    pub(crate) unread_bytes: usize,
+}
+
+impl BackgroundJob {
+    pub(crate) fn status(&self) -> JobStatus {
+        *self.status.borrow()
+    }
+
+    pub(crate) fn summary(&self) -> JobSummary {
+        JobSummary {
+            job_id: self.id,
+            command: display_command(&self.command),
+            cwd: self.cwd.clone(),
+            status: self.status(),
+            elapsed_secs: self.started.elapsed().as_secs(),
+            unread_bytes: self.output().unread_bytes(),
+        }
+    }
+
+    /// Output produced since the last call, and how many bytes of it were
+    /// dropped because nobody read them in time.
+    pub(crate) fn take_output(&self) -> (String, usize) {
+        let (bytes, dropped) = self.output().take();
+        (String::from_utf8_lossy(&bytes).into_owned(), dropped)
+    }
+
+    /// Kills the job's process group and waits briefly for it to exit.
+    pub(crate) async fn kill(&self) -> JobStatus {
+        self.cancel.cancel();
+        let mut status = self.status.clone();
+        let _ = tokio::time::timeout(
+            KILL_WAIT,
+            status.wait_for(|status| *status != JobStatus::Running),
+        )
+        .await;
+        self.status()
+    }
+
+    fn output(&self) -> std::sync::MutexGuard<'_, JobOutputBuffer> {
+        self.output
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct BackgroundJobs {
+    next_id: AtomicU32,
+    jobs: Mutex<BTreeMap<u32, Arc<BackgroundJob>>>,
+}
+
+impl BackgroundJobs {
+    /// Spawns `env` as a background job and returns its id. `command` is the
+    /// command as the model wrote it, before any sandbox wrapper.
+    pub(crate) async fn spawn(
+        &self,
+        env: ExecEnv,
+        sandbox_policy: &SandboxPolicy,
+        command: Vec<String>,
+    ) -> io::Result<u32> {
+        if env.sandbox == SandboxType::WindowsRestrictedToken
+            && !matches!(sandbox_policy, SandboxPolicy::DangerFullAccess)
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "background jobs are not supported under the Windows sandbox",
+            ));
+        }
+        let (program, args) = env
+            .command
+            .split_first()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "command args are empty"))?;
+        let mut child = spawn_child_async(
+            PathBuf::from(program),
+            args.to_vec(),
+            env.arg0.as_deref(),
+            env.cwd.clone(),
+            sandbox_policy,
+            StdioPolicy::RedirectForShellTool,
+            env.env,
+            env.limits,
+        )
+        .await?;
+
+        let output = Arc::new(Mutex::new(JobOutputBuffer::default()));
+        if let Some(stdout) = child.stdout.take() {
+            tokio::spawn(collect_output(stdout, Arc::clone(&output)));
+        }
+        if let Some(stderr) = child.stderr.take() {
+            tokio::spawn(collect_output(stderr, Arc::clone(&output)));
+        }
+        let (status_tx, status) = watch::channel(JobStatus::Running);
+        let cancel = CancellationToken::new();
+        tokio::spawn(wait_for_exit(child, status_tx, cancel.clone()));
+
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let job = BackgroundJob {
+            id,
+            command,
+            cwd: env.cwd,
+            started: Instant::now(),
+            output,
+            status,
+            cancel,
+        };
+        self.jobs().insert(id, Arc::new(job));
+        Ok(id)
+    }
+
+    pub(crate) fn get(&self, id: u32) -> Option<Arc<BackgroundJob>> {
+        self.jobs().get(&id).cloned()
+    }
+
+    pub(crate) fn list(&self) -> Vec<Arc<BackgroundJob>> {
+        self.jobs().values().cloned().collect()
+    }
+
+    /// Kills every job that is still running; used when the session ends.
+    pub(crate) fn kill_all(&self) {
+        for job in self.jobs().values() {
+            job.cancel.cancel();
+        }
+    }
+
+    fn jobs(&self) -> std::sync::MutexGuard<'_, BTreeMap<u32, Arc<BackgroundJob>>> {
+        self.jobs
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+/// The message returned to the model in place of the command's output.
+pub(crate) fn started_message(id: u32) -> String {
+    format!(
+        "Started background job {id}. Read its output with job_output, check on it with \
+         job_status and stop it with job_kill.\n"
+    )
+}
+
+async fn collect_output<R: AsyncRead + Unpin>(mut reader: R, output: Arc<Mutex<JobOutputBuffer>>) {
+    let mut chunk = [0u8; READ_CHUNK_SIZE];
+    while let Ok(n) = reader.read(&mut chunk).await {
+        if n == 0 {
+            break;
+        }
+        output
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .push(&chunk[..n]);
+    }
+}
+
+async fn wait_for_exit(
+    mut child: Child,
+    status_tx: watch::Sender<JobStatus>,
+    cancel: CancellationToken,
+) {
+    let status = tokio::select! {
+        result = child.wait() => match result {
+            Ok(status) => JobStatus::Exited {
+                exit_code: status.code().unwrap_or(-1),
+            },
+            Err(_) => JobStatus::Exited { exit_code: -1 },
+        },
+        _ = cancel.cancelled() => {
+            kill_process_group(&child);
+            let _ = child.start_kill();
+            let _ = child.wait().await;
+            JobStatus::Killed
+        }
+    };
+    status_tx.send_replace(status);
+}
+
+/// Shell tool commands lead their own process group, so killing the group
+/// also stops what the command started, such as a dev server's workers.
+#[cfg(unix)]
+fn kill_process_group(child: &Child) {
+    if let Some(pid) = child.id() {
+        unsafe {
+            libc::killpg(pid as libc::pid_t, libc::SIGKILL);
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn kill_process_group(_: &Child) {}
+
+fn display_command(command: &[String]) -> String {
+    shlex::try_join(command.iter().map(String::as_str)).unwrap_or_else(|_| command.join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn output_is_read_once_and_capped() {
+        let mut buffer = JobOutputBuffer::default();
+        buffer.push(b"hello\n");
+        assert_eq!(buffer.take(), (b"hello\n".to_vec(), 0));
+        assert_eq!(buffer.take(), (Vec::new(), 0));
+
+        buffer.push(&vec![b'a'; JOB_OUTPUT_MAX_BYTES]);
+        buffer.push(b"tail");
+        let (unread, dropped) = buffer.take();
+        assert_eq!((unread.len(), dropped), (JOB_OUTPUT_MAX_BYTES, 4));
+        assert_eq!(&unread[unread.len() - 4..], b"tail");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn jobs_run_in_the_background_until_killed() {
+        let jobs = BackgroundJobs::default();
+        let command = vec![
+            "bash".to_string(),
+            "-c".to_string(),
+            "echo ready; sleep 30".to_string(),
+        ];
+        let env = ExecEnv {
+            command: command.clone(),
+            cwd: std::env::temp_dir(),
+            env: std::env::vars().collect(),
+            expiration: crate::exec::ExecExpiration::DefaultTimeout,
+            sandbox: SandboxType::None,
+            with_escalated_permissions: None,
+            justification: None,
+            arg0: None,
+            limits: None,
+        };
+        let id = jobs
+            .spawn(env, &SandboxPolicy::DangerFullAccess, command)
+            .await
+            .expect("spawn");
+        let job = jobs.get(id).expect("job");
+
+        let mut output = String::new();
+        for _ in 0..100 {
+            output.push_str(&job.take_output().0);
+            if output.contains("ready") {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(
+            (output.as_str(), job.status()),
+            ("ready\n", JobStatus::Running)
+        );
+        assert_eq!(job.kill().await, JobStatus::Killed);
+    }
+}
@@ -0,0 +1,71 @@
+//! Filesystem work without an async counterpart, kept off the async runtime.
+//!
+//! Verifying a patch reads every file it touches, and hashing, diffing or
+//! merging those files takes a while for large ones. Such work goes through
+//! [`run_blocking`], which runs it on tokio's blocking pool so that streaming
+//! output and cancellations are handled meanwhile. At most
+//! [`MAX_CONCURRENT_JOBS`] run at once, so that a burst of large patches does
+//! not take every blocking thread from the commands that need one.
+//!
+//! Patches themselves are written by a separate `apply_patch` process; what
+//! runs here is the verification, review and bookkeeping around it.
+
+use std::io;
+
+use tokio::sync::Semaphore;
+
+/// Blocking filesystem jobs allowed to run at the same time.
+pub(crate) const MAX_CONCURRENT_JOBS: usize = 8;
+
+static JOB_PERMITS: Semaphore = Semaphore::const_new(MAX_CONCURRENT_JOBS);
+
+/// Run `job` on the blocking pool once one of the permits is free. The
+/// permit is held until `job` returns, even when the caller stops waiting.
+pub(crate) async fn run_blocking<T, F>(job: F) -> io::Result<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let permit = JOB_PERMITS.acquire().await.map_err(io::Error::other)?;
+    tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        job()
+    })
+    .await
+    .map_err(io::Error::other)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::sync::Arc;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::atomic::Ordering;
+    use std::time::Duration;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn at_most_the_permitted_jobs_run_at_once() {
+        let running = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let jobs = (0..MAX_CONCURRENT_JOBS * 3).map(|index| {
+            let running = Arc::clone(&running);
+            let peak = Arc::clone(&peak);
+            run_blocking(move || {
+                let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                std::thread::sleep(Duration::from_millis(10));
+                running.fetch_sub(1, Ordering::SeqCst);
+                index
+            })
+        });
+        let results: Vec<usize> = futures::future::join_all(jobs)
+            .await
+            .into_iter()
+            .map(|result| result.expect("job"))
+            .collect();
+
+        assert_eq!(results, (0..MAX_CONCURRENT_JOBS * 3).collect::<Vec<_>>());
+        assert!(peak.load(Ordering::SeqCst) <= MAX_CONCURRENT_JOBS);
+    }
+}
@@ -0,0 +1,248 @@
+//! Caches for construction paths whose output depends only on their inputs.
+//!
+//! Tool definitions are rebuilt from the tools config and the MCP tool list
+//! for every turn, and every session started in a repository re-reads and
+//! concatenates the same AGENTS.md files. Both are keyed by a fingerprint of
+//! everything that goes into them, so a changed input misses the cache instead
+//! of returning stale output: the project docs cache stores a hash of the size
+//! and modification time of every doc it read and is discarded as soon as a
+//! doc is edited, added or removed.
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::LazyLock;
+use std::sync::Mutex;
+use std::time::UNIX_EPOCH;
+
+use serde::Deserialize;
+use serde::Serialize;
+use sha2::Digest;
+use sha2::Sha256;
+use tracing::debug;
+
+use crate::config::Config;
+use crate::project_doc::discover_project_doc_paths;
+use crate::project_doc::read_project_doc_files;
+use crate::tools::ToolRouter;
+use crate::tools::spec::ToolsConfig;
+
+/// Directory under `CODEX_HOME` holding the on-disk caches.
+const CACHE_DIR: &str = "cache";
+const PROJECT_DOCS_DIR: &str = "project_docs";
+
+/// Distinct tool configurations seen by one process are few (one per model
+/// and feature set), so the cache is simply cleared when it grows past this.
+const MAX_CACHED_ROUTERS: usize = 16;
+
+static TOOL_ROUTERS: LazyLock<Mutex<HashMap<String, Arc<ToolRouter>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// [`ToolRouter::from_config`], reusing the router built for identical inputs
+/// earlier in this process. Routers hold only stateless handlers, so sharing
+/// one between turns and sessions is safe.
+pub(crate) fn tool_router(
+    config: &ToolsConfig,
+    mcp_tools: Option<HashMap<String, mcp_types::Tool>>,
+) -> Arc<ToolRouter> {
+    let mcp_key = match &mcp_tools {
+        Some(tools) => match serde_json::to_string(&tools.iter().collect::<BTreeMap<_, _>>()) {
+            Ok(serialized) => serialized,
+            Err(err) => {
+                debug!("not caching tool definitions: {err}");
+                return Arc::new(ToolRouter::from_config(config, mcp_tools));
+            }
+        },
+        None => String::new(),
+    };
+    let key = fingerprint(&[format!("{config:?}").as_bytes(), mcp_key.as_bytes()]);
+
+    let Ok(mut routers) = TOOL_ROUTERS.lock() else {
+        return Arc::new(ToolRouter::from_config(config, mcp_tools));
+    };
+    if let Some(router) = routers.get(&key) {
+        return Arc::clone(router);
+    }
+    let router = Arc::new(ToolRouter::from_config(config, mcp_tools));
+    if routers.len() >= MAX_CACHED_ROUTERS {
+        routers.clear();
+    }
+    routers.insert(key, Arc::clone(&router));
+    router
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedProjectDocs {
+    workspace_hash: String,
+    contents: Option<String>,
+}
+
+/// [`crate::project_doc::read_project_docs`] backed by a cache under
+/// `CODEX_HOME`, one entry per working directory.
+pub(crate) async fn project_docs(config: &Config) -> std::io::Result<Option<String>> {
+    let max_bytes = config.project_doc_max_bytes;
+    if max_bytes == 0 {
+        return Ok(None);
+    }
+    let paths = discover_project_doc_paths(config)?;
+    let workspace_hash = project_docs_hash(&paths, max_bytes);
+    let cache_path = config
+        .codex_home
+        .join(CACHE_DIR)
+        .join(PROJECT_DOCS_DIR)
+        .join(format!(
+            "{}.json",
+            fingerprint(&[config.cwd.as_os_str().as_encoded_bytes()])
+        ));
+
+    let cached = read_cached(&cache_path)
+        .await
+        .filter(|cached| cached.workspace_hash == workspace_hash);
+    if let Some(cached) = cached {
+        return Ok(cached.contents);
+    }
+
+    let contents = read_project_doc_files(&paths, max_bytes).await?;
+    let entry = CachedProjectDocs {
+        workspace_hash,
+        contents,
+    };
+    write_cached(&cache_path, &entry).await;
+    Ok(entry.contents)
+}
+
+/// Hash of the inputs that determine the concatenated docs: the byte budget
+/// and the path, size and modification time of every doc file.
+fn project_docs_hash(paths: &[PathBuf], max_bytes: usize) -> String {
+    let mut parts: Vec<Vec<u8>> = vec![max_bytes.to_string().into_bytes()];
+    for path in paths {
+        let (len, modified) = std::fs::metadata(path)
+            .map(|metadata| {
+                let modified = metadata
+                    .modified()
+                    .ok()
+                    .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+                    .map(|since_epoch| since_epoch.as_nanos())
+                    .unwrap_or_default();
+                (metadata.len(), modified)
+            })
+            .unwrap_or_default();
+        parts.push(path.as_os_str().as_encoded_bytes().to_vec());
+        parts.push(format!("{len}:{modified}").into_bytes());
+    }
+    let parts: Vec<&[u8]> = parts.iter().map(Vec::as_slice).collect();
+    fingerprint(&parts)
+}
+
+async fn read_cached(path: &Path) -> Option<CachedProjectDocs> {
+    let contents = tokio::fs::read(path).await.ok()?;
+    serde_json::from_slice(&contents).ok()
+}
+
+/// Caching is best effort: failures are logged and otherwise ignored.
+async fn write_cached(path: &Path, entry: &CachedProjectDocs) {
+    let result = async {
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let serialized = serde_json::to_vec(entry).map_err(std::io::Error::other)?;
+        tokio::fs::write(path, serialized).await
+    }
+    .await;
+    if let Err(err) = result {
+        debug!("failed to write {}: {err}", path.display());
+    }
+}
+
+fn fingerprint(parts: &[&[u8]]) -> String {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        // Length-prefix every part so that ["ab", "c"] and ["a", "bc"] differ.
+        hasher.update(part.len().to_le_bytes());
+        hasher.update(part);
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ConfigOverrides;
+    use crate::config::ConfigToml;
+    use crate::features::Feature;
+    use crate::features::Features;
+    use crate::model_family::find_family_for_model;
+    use crate::tools::spec::ToolsConfigParams;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn project_docs_are_reread_after_a_doc_changes() {
+        let repo = TempDir::new().expect("tempdir");
+        let codex_home = TempDir::new().expect("tempdir");
+        std::fs::create_dir(repo.path().join(".git")).expect("create .git");
+        let doc = repo.path().join("AGENTS.md");
+        std::fs::write(&doc, "run the tests").expect("write doc");
+        let mut config = Config::load_from_base_config_with_overrides(
+            ConfigToml::default(),
+            ConfigOverrides::default(),
+            codex_home.path().to_path_buf(),
+        )
+        .expect("config");
+        config.cwd = repo.path().to_path_buf();
+
+        assert_eq!(
+            project_docs(&config).await.expect("docs"),
+            Some("run the tests".to_string())
+        );
+
+        // A second session in the same repository is served from the cache.
+        let cache_dir = codex_home.path().join(CACHE_DIR).join(PROJECT_DOCS_DIR);
+        let cache_file = std::fs::read_dir(&cache_dir)
+            .expect("cache dir")
+            .next()
+            .expect("cache entry")
+            .expect("cache entry")
+            .path();
+        let mut cached = read_cached(&cache_file).await.expect("cached docs");
+        cached.contents = Some("from the cache".to_string());
+        write_cached(&cache_file, &cached).await;
+        assert_eq!(
+            project_docs(&config).await.expect("docs"),
+            Some("from the cache".to_string())
+        );
+
+        std::fs::write(&doc, "run the tests and the linter").expect("write doc");
+        assert_eq!(
+            project_docs(&config).await.expect("docs"),
+            Some("run the tests and the linter".to_string())
+        );
+    }
+
+    #[test]
+    fn tool_routers_are_shared_for_identical_inputs() {
+        let model_family = find_family_for_model("gpt-5-codex").expect("model family");
+        let mut features = Features::with_defaults();
+        let config = ToolsConfig::new(&ToolsConfigParams {
+            model_family: &model_family,
+            features: &features,
+        });
+        features.enable(Feature::WebSearchRequest);
+        let other_config = ToolsConfig::new(&ToolsConfigParams {
+            model_family: &model_family,
+            features: &features,
+        });
+
+        let router = tool_router(&config, Some(HashMap::new()));
+        assert!(Arc::ptr_eq(
+            &router,
+            &tool_router(&config, Some(HashMap::new()))
+        ));
+        assert!(!Arc::ptr_eq(
+            &router,
+            &tool_router(&other_config, Some(HashMap::new()))
+        ));
+    }
+}
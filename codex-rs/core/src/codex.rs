@@ -1,25 +1,37 @@
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::fmt::Debug;
+use std::path::Path;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::atomic::AtomicU64;
 
 use crate::AuthManager;
 use crate::SandboxState;
+use crate::approval_cache::PersistentApprovals;
+use crate::audit::AuditLog;
+use crate::background_jobs::BackgroundJobs;
+use crate::build_cache;
 use crate::client_common::REVIEW_PROMPT;
 use crate::compact;
 use crate::compact::run_inline_auto_compact_task;
 use crate::compact::should_use_remote_compact_task;
 use crate::compact_remote::run_inline_remote_auto_compact_task;
+use crate::crash_report;
 use crate::features::Feature;
 use crate::function_tool::FunctionCallError;
+use crate::inline_script::inline_scripts;
+use crate::lazy_service::LazyService;
 use crate::parse_command::parse_command;
 use crate::parse_turn_item;
 use crate::response_processing::process_items;
+use crate::sandboxing::container::SessionContainers;
 use crate::terminal;
 use crate::truncate::TruncationPolicy;
 use crate::user_notification::UserNotifier;
 use crate::util::error_or_panic;
+use crate::workspace_index::WorkspaceIndex;
+use crate::workspace_index::stale_files_message;
 use async_channel::Receiver;
 use async_channel::Sender;
 use codex_protocol::ConversationId;
@@ -51,8 +63,10 @@ use serde_json;
 use serde_json::Value;
 use tokio::sync::Mutex;
 use tokio::sync::RwLock;
+use tokio::sync::Semaphore;
 use tokio::sync::oneshot;
 use tokio_util::sync::CancellationToken;
+use tokio_util::task::AbortOnDropHandle;
 use tracing::debug;
 use tracing::error;
 use tracing::info;
@@ -62,10 +76,13 @@ use crate::ModelProviderInfo;
 use crate::client::ModelClient;
 use crate::client_common::Prompt;
 use crate::client_common::ResponseEvent;
+use crate::collab::Collaborators;
 use crate::compact::collect_user_messages;
 use crate::config::Config;
+use crate::config::types::SandboxBackend;
 use crate::config::types::ShellEnvironmentPolicy;
 use crate::context_manager::ContextManager;
+use crate::dirty_files::FileDigest;
 use crate::environment_context::EnvironmentContext;
 use crate::error::CodexErr;
 use crate::error::Result as CodexResult;
@@ -75,47 +92,67 @@ use crate::mcp::auth::compute_auth_statuses;
 use crate::mcp_connection_manager::McpConnectionManager;
 use crate::model_family::find_family_for_model;
 use crate::openai_model_info::get_model_info;
+use crate::patch_journal::PatchJournal;
+use crate::project_cache::evict_project_caches;
 use crate::project_doc::get_user_instructions;
 use crate::protocol::AgentMessageContentDeltaEvent;
 use crate::protocol::AgentReasoningSectionBreakEvent;
 use crate::protocol::ApplyPatchApprovalRequestEvent;
+use crate::protocol::ApprovalResolvedEvent;
+use crate::protocol::ArtifactEvent;
 use crate::protocol::AskForApproval;
 use crate::protocol::BackgroundEventEvent;
+use crate::protocol::CommandExpansion;
 use crate::protocol::DeprecationNoticeEvent;
 use crate::protocol::Event;
 use crate::protocol::EventMsg;
+use crate::protocol::ExecApprovalBatchRequestEvent;
 use crate::protocol::ExecApprovalRequestEvent;
+use crate::protocol::IntegrityFinding;
 use crate::protocol::Op;
+use crate::protocol::PatchHunk;
 use crate::protocol::RateLimitSnapshot;
 use crate::protocol::ReasoningContentDeltaEvent;
 use crate::protocol::ReasoningRawContentDeltaEvent;
 use crate::protocol::ReviewDecision;
 use crate::protocol::SandboxCommandAssessment;
 use crate::protocol::SandboxPolicy;
+use crate::protocol::SandboxProbeEvent;
 use crate::protocol::SessionConfiguredEvent;
+use crate::protocol::StatusLineEvent;
 use crate::protocol::StreamErrorEvent;
 use crate::protocol::Submission;
 use crate::protocol::TokenCountEvent;
 use crate::protocol::TokenUsage;
 use crate::protocol::TokenUsageInfo;
 use crate::protocol::TurnDiffEvent;
+use crate::protocol::UserChange;
 use crate::protocol::WarningEvent;
+use crate::protocol::WorkspaceIntegrityEvent;
+use crate::read_ahead::ReadAhead;
+use crate::replay_bundle::ReplayBundle;
 use crate::rollout::RolloutRecorder;
 use crate::rollout::RolloutRecorderParams;
 use crate::shell;
 use crate::state::ActiveTurn;
 use crate::state::SessionServices;
 use crate::state::SessionState;
+use crate::status_line;
 use crate::tasks::GhostSnapshotTask;
 use crate::tasks::ReviewTask;
 use crate::tasks::SessionTask;
 use crate::tasks::SessionTaskContext;
 use crate::tools::ToolRouter;
 use crate::tools::context::SharedTurnDiffTracker;
+use crate::tools::custom::ToolRuntimeRegistry;
 use crate::tools::executor::DynToolExecutor;
 #[cfg(test)]
 use crate::tools::executor::default_tool_executor;
+use crate::tools::parallel::MAX_PARALLEL_TOOL_CALLS;
 use crate::tools::parallel::ToolCallRuntime;
+use crate::tools::parallel::WorkspaceLocks;
+use crate::tools::runtimes::compose::ComposeProject;
+use crate::tools::runtimes::web_fetch::WebFetchCache;
 use crate::tools::sandboxing::ApprovalStore;
 use crate::tools::spec::ToolsConfig;
 use crate::tools::spec::ToolsConfigParams;
@@ -146,6 +183,12 @@ pub struct Codex {
     pub(crate) next_id: AtomicU64,
     pub(crate) tx_sub: Sender<Submission>,
     pub(crate) rx_event: Receiver<Event>,
+    /// `None` for sub-agents, which cannot be joined.
+    pub(crate) collaborators: Option<Arc<Collaborators>>,
+    /// Listener for `collab_socket`; stops when the conversation is dropped.
+    pub(crate) _collab_server: Option<AbortOnDropHandle<()>>,
+    /// Listener for `control_socket`, likewise.
+    pub(crate) _control_server: Option<AbortOnDropHandle<()>>,
 }
 
 /// Wrapper returned by [`Codex::spawn`] containing the spawned [`Codex`],
@@ -158,18 +201,24 @@ pub struct CodexSpawnOk {
 
 pub(crate) const INITIAL_SUBMIT_ID: &str = "";
 pub(crate) const SUBMISSION_CHANNEL_CAPACITY: usize = 64;
+/// How long a command approval waits for others to be asked about with it,
+/// with `batch_approvals`.
+const APPROVAL_BATCH_WINDOW: std::time::Duration = std::time::Duration::from_millis(250);
 
 impl Codex {
     /// Spawn a new [`Codex`] and initialize the session.
     pub async fn spawn(
-        config: Config,
+        mut config: Config,
         auth_manager: Arc<AuthManager>,
         conversation_history: InitialHistory,
         session_source: SessionSource,
         tool_executor: DynToolExecutor,
+        tool_runtimes: ToolRuntimeRegistry,
     ) -> CodexResult<CodexSpawnOk> {
         let (tx_sub, rx_sub) = async_channel::bounded(SUBMISSION_CHANNEL_CAPACITY);
         let (tx_event, rx_event) = async_channel::unbounded();
+        let collaborators = Arc::new(Collaborators::new(tx_event.clone()));
+        let joinable = !matches!(session_source, SessionSource::SubAgent(_));
 
         let user_instructions = get_user_instructions(&config).await;
 
@@ -177,6 +226,13 @@ impl Codex {
             .await
             .map_err(|err| CodexErr::Fatal(format!("failed to load execpolicy: {err}")))?;
 
+        if config.shell_profile.import_env
+            && let Some(shell) = shell::profile_shell()
+        {
+            config.shell_environment_policy.login_shell_vars =
+                crate::shell_profile::login_shell_vars(&shell).await;
+        }
+
         let config = Arc::new(config);
 
         let session_configuration = SessionConfiguration {
@@ -204,9 +260,11 @@ impl Codex {
             config.clone(),
             auth_manager.clone(),
             tx_event.clone(),
+            Arc::clone(&collaborators),
             conversation_history,
             session_source_clone,
             tool_executor,
+            tool_runtimes,
         )
         .await
         .map_err(|e| {
@@ -215,12 +273,31 @@ impl Codex {
         })?;
         let conversation_id = session.conversation_id;
 
+        let collab_server = match &config.collab_socket {
+            Some(path) if joinable => crate::collab::serve(&collaborators, &tx_sub, path),
+            _ => None,
+        };
+        let control_server = match &config.control_socket {
+            Some(path) if joinable => crate::control::serve(
+                &collaborators,
+                &tx_sub,
+                path,
+                conversation_id,
+                config.model.clone(),
+                config.cwd.clone(),
+            ),
+            _ => None,
+        };
+
         // This task will run until Op::Shutdown is received.
         tokio::spawn(submission_loop(session, config, rx_sub));
         let codex = Codex {
             next_id: AtomicU64::new(0),
             tx_sub,
             rx_event,
+            collaborators: Some(collaborators),
+            _collab_server: collab_server,
+            _control_server: control_server,
         };
 
         Ok(CodexSpawnOk {
@@ -459,14 +536,17 @@ impl Session {
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     async fn new(
         session_configuration: SessionConfiguration,
         config: Arc<Config>,
         auth_manager: Arc<AuthManager>,
         tx_event: Sender<Event>,
+        collaborators: Arc<Collaborators>,
         initial_history: InitialHistory,
         session_source: SessionSource,
         tool_executor: DynToolExecutor,
+        tool_runtimes: ToolRuntimeRegistry,
     ) -> anyhow::Result<Arc<Self>> {
         debug!(
             "Configuring session: model={}; provider={:?}",
@@ -502,18 +582,21 @@ impl Session {
         // - initialize RolloutRecorder with new or resumed session info
         // - perform default shell discovery
         // - load history metadata
+        //
+        // Everything else that is slow to set up (MCP servers, the workspace
+        // index) is started in the background once the session is configured.
         let rollout_fut = RolloutRecorder::new(&config, rollout_params);
 
-        let default_shell = shell::default_user_shell();
+        let default_shell = if config.shell_profile.run_commands {
+            shell::profile_shell().unwrap_or_else(shell::default_user_shell)
+        } else {
+            shell::default_user_shell()
+        };
         let history_meta_fut = crate::message_history::history_metadata(&config);
-        let auth_statuses_fut = compute_auth_statuses(
-            config.mcp_servers.iter(),
-            config.mcp_oauth_credentials_store_mode,
-        );
 
         // Join all independent futures.
-        let (rollout_recorder, (history_log_id, history_entry_count), auth_statuses) =
-            tokio::join!(rollout_fut, history_meta_fut, auth_statuses_fut);
+        let (rollout_recorder, (history_log_id, history_entry_count)) =
+            tokio::join!(rollout_fut, history_meta_fut);
 
         let rollout_recorder = rollout_recorder.map_err(|e| {
             error!("failed to initialize rollout recorder: {e:#}");
@@ -539,6 +622,19 @@ impl Session {
             });
         }
 
+        if cfg!(target_os = "windows") && config.features.enabled(Feature::WindowsSandbox) {
+            let probe = crate::safety::probe_windows_sandbox(&config.codex_home);
+            let error = probe.token_error.as_ref().or(probe.job_error.as_ref());
+            post_session_configured_events.push(Event {
+                id: INITIAL_SUBMIT_ID.to_owned(),
+                msg: EventMsg::SandboxProbe(SandboxProbeEvent {
+                    available: probe.token_error.is_none(),
+                    limits: probe.job_error.is_none(),
+                    error: error.cloned(),
+                }),
+            });
+        }
+
         let otel_event_manager = OtelEventManager::new(
             conversation_id,
             config.model.as_str(),
@@ -562,6 +658,16 @@ impl Session {
             config.active_profile.clone(),
         );
 
+        if config.project_cache.enabled {
+            let codex_home = config.codex_home.clone();
+            let max_bytes = config.project_cache.max_size_mb.saturating_mul(1024 * 1024);
+            tokio::task::spawn_blocking(move || {
+                if let Err(err) = evict_project_caches(&codex_home, max_bytes) {
+                    warn!("failed to evict project caches: {err}");
+                }
+            });
+        }
+
         // Create the mutable state for the Session.
         let state = SessionState::new(session_configuration.clone());
 
@@ -569,6 +675,7 @@ impl Session {
             mcp_connection_manager: Arc::new(RwLock::new(McpConnectionManager::default())),
             mcp_startup_cancellation_token: CancellationToken::new(),
             unified_exec_manager: UnifiedExecSessionManager::default(),
+            background_jobs: BackgroundJobs::default(),
             notifier: UserNotifier::new(config.notify.clone()),
             rollout: Mutex::new(Some(rollout_recorder)),
             user_shell: default_shell,
@@ -576,7 +683,41 @@ impl Session {
             auth_manager: Arc::clone(&auth_manager),
             otel_event_manager,
             tool_approvals: Mutex::new(ApprovalStore::default()),
+            approval_prompt: Mutex::new(()),
+            batch_approvals: config.features.enabled(Feature::BatchApprovals),
+            persistent_approvals: config.approval_cache.persist.then(|| {
+                PersistentApprovals::new(config.codex_home.clone(), config.approval_cache.ttl_days)
+            }),
+            audit_log: config
+                .audit
+                .enabled
+                .then(|| AuditLog::new(&config.codex_home, conversation_id)),
             tool_executor,
+            tool_runtimes,
+            tool_call_permits: Arc::new(Semaphore::new(MAX_PARALLEL_TOOL_CALLS)),
+            workspace_locks: WorkspaceLocks::default(),
+            web_fetch_cache: WebFetchCache::default(),
+            condensed_events: config.features.enabled(Feature::CondensedEvents),
+            file_read_events: config.features.enabled(Feature::FileReadEvents),
+            collaborators,
+            read_ahead: config
+                .features
+                .enabled(Feature::ReadAhead)
+                .then(|| Arc::new(ReadAhead::default())),
+            patch_journal: config
+                .features
+                .enabled(Feature::UndoPatch)
+                .then(|| Mutex::new(PatchJournal::default())),
+            workspace_index: config.features.enabled(Feature::WorkspaceIndex).then(|| {
+                let cwd = config.cwd.clone();
+                LazyService::new(move || WorkspaceIndex::start(cwd.clone()))
+            }),
+            container: (config.sandbox_backend == SandboxBackend::Container)
+                .then(|| SessionContainers::new(&config, conversation_id)),
+            replay: config
+                .features
+                .enabled(Feature::ReproducibleExec)
+                .then(|| ReplayBundle::new(&config.codex_home, conversation_id)),
         };
 
         let sess = Arc::new(Session {
@@ -612,33 +753,47 @@ impl Session {
         for event in events {
             sess.send_event_raw(event).await;
         }
-        sess.services
-            .mcp_connection_manager
-            .write()
-            .await
-            .initialize(
-                config.mcp_servers.clone(),
-                config.mcp_oauth_credentials_store_mode,
-                auth_statuses.clone(),
-                tx_event.clone(),
-                sess.services.mcp_startup_cancellation_token.clone(),
-            )
-            .await;
 
+        // Start the MCP servers in the background. The write lock is taken
+        // before returning, so the first turn to list tools waits for them.
+        let mut mcp_connection_manager = Arc::clone(&sess.services.mcp_connection_manager)
+            .write_owned()
+            .await;
         let sandbox_state = SandboxState {
             sandbox_policy: session_configuration.sandbox_policy.clone(),
             codex_linux_sandbox_exe: config.codex_linux_sandbox_exe.clone(),
             sandbox_cwd: session_configuration.cwd.clone(),
         };
-        if let Err(e) = sess
-            .services
-            .mcp_connection_manager
-            .read()
-            .await
-            .notify_sandbox_state_change(&sandbox_state)
-            .await
-        {
-            tracing::error!("Failed to notify sandbox state change: {e}");
+        let mcp_config = Arc::clone(&config);
+        let mcp_tx_event = tx_event.clone();
+        let mcp_cancellation_token = sess.services.mcp_startup_cancellation_token.clone();
+        tokio::spawn(async move {
+            let auth_statuses = compute_auth_statuses(
+                mcp_config.mcp_servers.iter(),
+                mcp_config.mcp_oauth_credentials_store_mode,
+            )
+            .await;
+            mcp_connection_manager
+                .initialize(
+                    mcp_config.mcp_servers.clone(),
+                    mcp_config.mcp_oauth_credentials_store_mode,
+                    auth_statuses,
+                    mcp_tx_event,
+                    mcp_cancellation_token,
+                )
+                .await;
+            if let Err(e) = mcp_connection_manager
+                .notify_sandbox_state_change(&sandbox_state)
+                .await
+            {
+                tracing::error!("Failed to notify sandbox state change: {e}");
+            }
+        });
+        if let Some(index) = &sess.services.workspace_index {
+            index.warm_up();
+        }
+        if let Some(container) = &sess.services.container {
+            container.warm_up();
         }
 
         // record_initial_history can emit events. We record only after the SessionConfiguredEvent is emitted.
@@ -770,6 +925,7 @@ impl Session {
         if let Some(final_schema) = updates.final_output_json_schema {
             turn_context.final_output_json_schema = final_schema;
         }
+        turn_context.tools_config.custom_tools = self.services.tool_runtimes.specs();
         Arc::new(turn_context)
     }
 
@@ -811,12 +967,28 @@ impl Session {
     }
 
     pub(crate) async fn send_event_raw(&self, event: Event) {
+        crash_report::record_event(&event.msg);
         // Persist the event into rollout (recorder filters as needed)
         let rollout_items = vec![RolloutItem::EventMsg(event.msg.clone())];
         self.persist_rollout_items(&rollout_items).await;
+        let status_line = if self.services.condensed_events {
+            status_line::summarize(&event.msg).map(|message| Event {
+                id: event.id.clone(),
+                msg: EventMsg::StatusLine(StatusLineEvent { message }),
+            })
+        } else {
+            None
+        };
+        self.services.collaborators.broadcast(&event);
         if let Err(e) = self.tx_event.send(event).await {
             error!("failed to send tool call event: {e}");
         }
+        if let Some(status_line) = status_line {
+            self.services.collaborators.broadcast(&status_line);
+            if let Err(e) = self.tx_event.send(status_line).await {
+                error!("failed to send status line event: {e}");
+            }
+        }
     }
 
     async fn emit_turn_item_started(&self, turn_context: &TurnContext, item: &TurnItem) {
@@ -884,9 +1056,67 @@ impl Session {
         reason: Option<String>,
         risk: Option<SandboxCommandAssessment>,
     ) -> ReviewDecision {
+        self.request_command_approval_with_expansions(
+            turn_context,
+            call_id,
+            command,
+            cwd,
+            reason,
+            risk,
+            Vec::new(),
+            None,
+        )
+        .await
+    }
+
+    /// Like [`Self::request_command_approval`], for a command that runs
+    /// through aliases or functions of the login shell; see
+    /// [`crate::shell_profile`]. With a `trust_prefix`, the user is offered
+    /// to approve every command that starts with it for the session.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn request_command_approval_with_expansions(
+        &self,
+        turn_context: &TurnContext,
+        call_id: String,
+        command: Vec<String>,
+        cwd: PathBuf,
+        reason: Option<String>,
+        risk: Option<SandboxCommandAssessment>,
+        expansions: Vec<CommandExpansion>,
+        trust_prefix: Option<Vec<String>>,
+    ) -> ReviewDecision {
+        let parsed_cmd = parse_command(&command);
+        let scripts = inline_scripts(&command);
+        let request = ExecApprovalRequestEvent {
+            call_id,
+            turn_id: turn_context.sub_id.clone(),
+            command,
+            cwd,
+            reason,
+            risk,
+            parsed_cmd,
+            scripts,
+            expansions,
+            trust_prefix,
+        };
+        if self.services.batch_approvals {
+            return self.request_batched_approval(turn_context, request).await;
+        }
+
+        let (tx_approve, rx_approve) = oneshot::channel();
+        self.send_exec_approval_request(turn_context, request, tx_approve)
+            .await;
+        rx_approve.await.unwrap_or_default()
+    }
+
+    async fn send_exec_approval_request(
+        &self,
+        turn_context: &TurnContext,
+        request: ExecApprovalRequestEvent,
+        tx_approve: oneshot::Sender<ReviewDecision>,
+    ) {
         let sub_id = turn_context.sub_id.clone();
         // Add the tx_approve callback to the map before sending the request.
-        let (tx_approve, rx_approve) = oneshot::channel();
         let event_id = sub_id.clone();
         let prev_entry = {
             let mut active = self.active_turn.lock().await;
@@ -901,26 +1131,74 @@ impl Session {
         if prev_entry.is_some() {
             warn!("Overwriting existing pending approval for sub_id: {event_id}");
         }
+        self.send_event(turn_context, EventMsg::ExecApprovalRequest(request))
+            .await;
+    }
 
-        let parsed_cmd = parse_command(&command);
-        let event = EventMsg::ExecApprovalRequest(ExecApprovalRequestEvent {
-            call_id,
-            turn_id: turn_context.sub_id.clone(),
-            command,
-            cwd,
-            reason,
-            risk,
-            parsed_cmd,
-        });
-        self.send_event(turn_context, event).await;
+    /// With `batch_approvals`, requests that come in within
+    /// [`APPROVAL_BATCH_WINDOW`] of each other are sent as one
+    /// `ExecApprovalBatchRequest`. The request that starts a batch waits out
+    /// the window and sends it; a batch of one is sent as a plain
+    /// `ExecApprovalRequest`.
+    async fn request_batched_approval(
+        &self,
+        turn_context: &TurnContext,
+        request: ExecApprovalRequestEvent,
+    ) -> ReviewDecision {
+        let (tx_approve, rx_approve) = oneshot::channel();
+        let starts_batch = {
+            let mut active = self.active_turn.lock().await;
+            match active.as_mut() {
+                Some(at) => {
+                    let mut ts = at.turn_state.lock().await;
+                    ts.push_batched_approval(request, tx_approve)
+                }
+                None => false,
+            }
+        };
+        if starts_batch {
+            tokio::time::sleep(APPROVAL_BATCH_WINDOW).await;
+            let mut batch = {
+                let mut active = self.active_turn.lock().await;
+                match active.as_mut() {
+                    Some(at) => at.turn_state.lock().await.take_approval_batch(),
+                    None => Vec::new(),
+                }
+            };
+            if batch.len() == 1
+                && let Some((request, tx)) = batch.pop()
+            {
+                self.send_exec_approval_request(turn_context, request, tx)
+                    .await;
+            } else if let Some((first, _)) = batch.first() {
+                let id = first.call_id.clone();
+                let (requests, senders): (Vec<_>, Vec<_>) = batch.into_iter().unzip();
+                {
+                    let mut active = self.active_turn.lock().await;
+                    if let Some(at) = active.as_mut() {
+                        let mut ts = at.turn_state.lock().await;
+                        ts.insert_pending_batch(id.clone(), senders);
+                    }
+                }
+                let event = EventMsg::ExecApprovalBatchRequest(ExecApprovalBatchRequestEvent {
+                    id,
+                    turn_id: turn_context.sub_id.clone(),
+                    requests,
+                });
+                self.send_event(turn_context, event).await;
+            }
+        }
         rx_approve.await.unwrap_or_default()
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub async fn request_patch_approval(
         &self,
         turn_context: &TurnContext,
         call_id: String,
         changes: HashMap<PathBuf, FileChange>,
+        hunks: Vec<PatchHunk>,
+        user_changes: Vec<UserChange>,
         reason: Option<String>,
         grant_root: Option<PathBuf>,
     ) -> oneshot::Receiver<ReviewDecision> {
@@ -948,11 +1226,47 @@ impl Session {
             changes,
             reason,
             grant_root,
+            hunks,
+            user_changes,
         });
         self.send_event(turn_context, event).await;
         rx_approve
     }
 
+    /// Answer the patch approval `sub_id` hunk by hunk. The patch counts as
+    /// approved if any hunk is; the requester then applies only those hunks.
+    pub async fn notify_patch_hunk_approval(&self, sub_id: &str, decisions: Vec<ReviewDecision>) {
+        let decision = if decisions.iter().any(|decision| {
+            matches!(
+                decision,
+                ReviewDecision::Approved
+                    | ReviewDecision::ApprovedForSession
+                    | ReviewDecision::ApprovedPrefixForSession
+            )
+        }) {
+            ReviewDecision::Approved
+        } else {
+            ReviewDecision::Denied
+        };
+        {
+            let mut active = self.active_turn.lock().await;
+            if let Some(at) = active.as_mut() {
+                let mut ts = at.turn_state.lock().await;
+                ts.insert_hunk_decisions(sub_id.to_string(), decisions);
+            }
+        }
+        self.notify_approval(sub_id, decision).await;
+    }
+
+    /// The per-hunk decisions for the patch approval `sub_id`, if it was
+    /// answered with [`Op::PatchHunkApproval`].
+    pub(crate) async fn take_hunk_decisions(&self, sub_id: &str) -> Option<Vec<ReviewDecision>> {
+        let mut active = self.active_turn.lock().await;
+        let at = active.as_mut()?;
+        let mut ts = at.turn_state.lock().await;
+        ts.take_hunk_decisions(sub_id)
+    }
+
     pub async fn notify_approval(&self, sub_id: &str, decision: ReviewDecision) {
         let entry = {
             let mut active = self.active_turn.lock().await;
@@ -967,6 +1281,14 @@ impl Session {
         match entry {
             Some(tx_approve) => {
                 tx_approve.send(decision).ok();
+                self.send_event_raw(Event {
+                    id: sub_id.to_string(),
+                    msg: EventMsg::ApprovalResolved(ApprovalResolvedEvent {
+                        id: sub_id.to_string(),
+                        decision,
+                    }),
+                })
+                .await;
             }
             None => {
                 warn!("No pending approval found for sub_id: {sub_id}");
@@ -974,6 +1296,46 @@ impl Session {
         }
     }
 
+    /// Answers the commands of the batch `id` with `decisions`, in order;
+    /// see [`Op::ExecApprovalBatch`].
+    pub async fn notify_approval_batch(&self, id: &str, decisions: Vec<ReviewDecision>) {
+        let senders = {
+            let mut active = self.active_turn.lock().await;
+            match active.as_mut() {
+                Some(at) => at.turn_state.lock().await.remove_pending_batch(id),
+                None => None,
+            }
+        };
+        let Some(senders) = senders else {
+            warn!("No pending approval batch found for id: {id}");
+            return;
+        };
+        let mut decisions = decisions.into_iter();
+        let mut any_approved = false;
+        for tx in senders {
+            let decision = decisions.next().unwrap_or(ReviewDecision::Denied);
+            any_approved |= matches!(
+                decision,
+                ReviewDecision::Approved
+                    | ReviewDecision::ApprovedForSession
+                    | ReviewDecision::ApprovedPrefixForSession
+            );
+            tx.send(decision).ok();
+        }
+        self.send_event_raw(Event {
+            id: id.to_string(),
+            msg: EventMsg::ApprovalResolved(ApprovalResolvedEvent {
+                id: id.to_string(),
+                decision: if any_approved {
+                    ReviewDecision::Approved
+                } else {
+                    ReviewDecision::Denied
+                },
+            }),
+        })
+        .await;
+    }
+
     pub async fn resolve_elicitation(
         &self,
         server_name: String,
@@ -1117,6 +1479,129 @@ impl Session {
         state.clone_history()
     }
 
+    /// Remember files written by an applied patch so file-oriented tools
+    /// (e.g. coverage) can scope their reports to what the session changed.
+    pub(crate) async fn record_touched_paths<I>(&self, paths: I)
+    where
+        I: IntoIterator<Item = PathBuf>,
+    {
+        let mut state = self.state.lock().await;
+        state.record_touched_paths(paths);
+    }
+
+    pub(crate) async fn touched_paths(&self) -> Vec<PathBuf> {
+        let state = self.state.lock().await;
+        state.touched_paths()
+    }
+
+    /// Remember the contents applied patches left files in; see
+    /// [`crate::dirty_files`].
+    pub(crate) async fn record_patched_files(&self, files: Vec<(PathBuf, Option<FileDigest>)>) {
+        let mut state = self.state.lock().await;
+        state.record_patched_files(files);
+    }
+
+    /// The recorded patch results for those of `paths` that have one.
+    pub(crate) async fn patched_files(
+        &self,
+        paths: &[PathBuf],
+    ) -> HashMap<PathBuf, Option<FileDigest>> {
+        let state = self.state.lock().await;
+        state.patched_files(paths)
+    }
+
+    /// Remember how the workspace looks now that a turn has finished.
+    pub(crate) async fn snapshot_workspace(&self) {
+        if let Some(index) = &self.services.workspace_index {
+            let snapshot = index.get().await.snapshot().await;
+            self.state.lock().await.workspace_snapshot = Some(snapshot);
+        }
+    }
+
+    /// A note listing the files the model read that changed after the last
+    /// turn ended, e.g. because the user edited them in between.
+    async fn stale_files_item(&self) -> Option<ResponseItem> {
+        let index = self.services.workspace_index.as_ref()?;
+        let current = index.get().await.snapshot().await;
+        let (previous, read): (_, Vec<PathBuf>) = {
+            let mut state = self.state.lock().await;
+            let previous = state.workspace_snapshot.replace(current.clone())?;
+            (previous, state.files_read.keys().cloned().collect())
+        };
+        let changed: HashSet<PathBuf> = current.changed_since(&previous).into_iter().collect();
+        let stale: Vec<PathBuf> = read
+            .into_iter()
+            .filter(|path| changed.contains(&path.canonicalize().unwrap_or_else(|_| path.clone())))
+            .collect();
+        (!stale.is_empty()).then(|| stale_files_message(&stale))
+    }
+
+    /// Add a read of `path` by `tool` to the session's read ledger; `bytes`
+    /// is how much of the file's contents was returned to the model.
+    pub(crate) async fn record_file_read(&self, path: PathBuf, tool: &str, bytes: u64) {
+        let mut state = self.state.lock().await;
+        state.record_file_read(path, tool, bytes);
+    }
+
+    /// Remember `contents` as the version of `path` the model last saw.
+    pub(crate) async fn record_served_version(&self, path: PathBuf, contents: String) {
+        let mut state = self.state.lock().await;
+        state.record_served_version(path, contents);
+    }
+
+    pub(crate) async fn served_version(&self, path: &Path) -> Option<String> {
+        let state = self.state.lock().await;
+        state.served_version(path)
+    }
+
+    /// Track a compose project started by this session so it is torn down on
+    /// shutdown (unless `keep` is set).
+    pub(crate) async fn record_compose_project(&self, project: ComposeProject) {
+        let mut state = self.state.lock().await;
+        state.record_compose_project(project);
+    }
+
+    pub(crate) async fn forget_compose_project(&self, project: &ComposeProject) {
+        let mut state = self.state.lock().await;
+        state.forget_compose_project(project);
+    }
+
+    /// Quarantine files an escalated command created outside the workspace
+    /// and report them right away.
+    pub(crate) async fn quarantine_files(
+        &self,
+        turn_context: &TurnContext,
+        findings: Vec<IntegrityFinding>,
+    ) {
+        {
+            let mut state = self.state.lock().await;
+            state.quarantine(findings.clone());
+        }
+        self.send_event(
+            turn_context,
+            EventMsg::WorkspaceIntegrity(WorkspaceIntegrityEvent { findings }),
+        )
+        .await;
+    }
+
+    /// Record an artifact exchanged with the user and announce it.
+    pub(crate) async fn record_artifact(&self, sub_id: &str, artifact: ArtifactEvent) {
+        {
+            let mut state = self.state.lock().await;
+            state.record_artifact(artifact.clone());
+        }
+        self.send_event_raw(Event {
+            id: sub_id.to_string(),
+            msg: EventMsg::Artifact(artifact),
+        })
+        .await;
+    }
+
+    pub(crate) async fn artifacts(&self) -> Vec<ArtifactEvent> {
+        let state = self.state.lock().await;
+        state.artifacts()
+    }
+
     pub(crate) async fn update_token_usage_info(
         &self,
         turn_context: &TurnContext,
@@ -1432,6 +1917,12 @@ async fn submission_loop(sess: Arc<Session>, config: Arc<Config>, rx_sub: Receiv
             Op::PatchApproval { id, decision } => {
                 handlers::patch_approval(&sess, id, decision).await;
             }
+            Op::PatchHunkApproval { id, decisions } => {
+                handlers::patch_hunk_approval(&sess, id, decisions).await;
+            }
+            Op::ExecApprovalBatch { id, decisions } => {
+                handlers::exec_approval_batch(&sess, id, decisions).await;
+            }
             Op::AddToHistory { text } => {
                 handlers::add_to_history(&sess, &config, text).await;
             }
@@ -1445,9 +1936,30 @@ async fn submission_loop(sess: Arc<Session>, config: Arc<Config>, rx_sub: Receiv
             Op::ListCustomPrompts => {
                 handlers::list_custom_prompts(&sess, sub.id.clone()).await;
             }
+            Op::ListFilesRead => {
+                handlers::list_files_read(&sess, sub.id.clone()).await;
+            }
+            Op::GetContextUsage => {
+                handlers::context_usage(&sess, sub.id.clone()).await;
+            }
+            Op::ListApprovals => {
+                handlers::list_approvals(&sess, sub.id.clone()).await;
+            }
+            Op::RevokeApproval { id } => {
+                handlers::revoke_approval(&sess, sub.id.clone(), &id).await;
+            }
+            Op::SeedApprovals { approvals } => {
+                handlers::seed_approvals(&sess, sub.id.clone(), approvals).await;
+            }
             Op::Undo => {
                 handlers::undo(&sess, sub.id.clone()).await;
             }
+            Op::UndoPatch { count } => {
+                handlers::undo_patch(&sess, sub.id.clone(), count).await;
+            }
+            Op::RetryTurn { instructions } => {
+                handlers::retry_turn(&sess, sub.id.clone(), instructions).await;
+            }
             Op::Compact => {
                 handlers::compact(&sess, sub.id.clone()).await;
             }
@@ -1475,6 +1987,21 @@ async fn submission_loop(sess: Arc<Session>, config: Arc<Config>, rx_sub: Receiv
             Op::Review { review_request } => {
                 handlers::review(&sess, &config, sub.id.clone(), review_request).await;
             }
+            Op::AddArtifact { path, name } => {
+                handlers::add_artifact(&sess, &config, sub.id.clone(), path, name).await;
+            }
+            Op::CheckWorkspace => {
+                handlers::check_workspace(&sess, sub.id.clone()).await;
+            }
+            Op::CleanupWorkspace { ids } => {
+                handlers::cleanup_workspace(&sess, sub.id.clone(), ids).await;
+            }
+            Op::KeepQuarantined { ids } => {
+                handlers::keep_quarantined(&sess, sub.id.clone(), ids).await;
+            }
+            Op::RunDoctor => {
+                handlers::run_doctor(&sess, &config, sub.id.clone()).await;
+            }
             _ => {} // Ignore unknown ops; enum is non_exhaustive to allow extensions.
         }
     }
@@ -1487,28 +2014,45 @@ mod handlers {
     use crate::codex::SessionSettingsUpdate;
     use crate::codex::TurnContext;
 
+    use crate::artifacts::artifacts_dir;
+    use crate::artifacts::store_artifact;
+    use crate::build_cache;
     use crate::codex::spawn_review_thread;
     use crate::config::Config;
+    use crate::integrity;
     use crate::mcp::auth::compute_auth_statuses;
     use crate::tasks::CompactTask;
     use crate::tasks::RegularTask;
+    use crate::tasks::RetryTurnTask;
     use crate::tasks::UndoTask;
     use crate::tasks::UserShellCommandTask;
+    use crate::tools::runtimes::compose::teardown_projects;
     use codex_protocol::custom_prompts::CustomPrompt;
+    use codex_protocol::protocol::ApprovalCacheEvent;
+    use codex_protocol::protocol::ApprovalSeed;
+    use codex_protocol::protocol::ArtifactDirection;
     use codex_protocol::protocol::CodexErrorInfo;
+    use codex_protocol::protocol::ContextUsageEvent;
+    use codex_protocol::protocol::DoctorReportEvent;
     use codex_protocol::protocol::ErrorEvent;
     use codex_protocol::protocol::Event;
     use codex_protocol::protocol::EventMsg;
+    use codex_protocol::protocol::IntegrityFinding;
     use codex_protocol::protocol::ListCustomPromptsResponseEvent;
+    use codex_protocol::protocol::ListFilesReadResponseEvent;
     use codex_protocol::protocol::Op;
     use codex_protocol::protocol::ReviewDecision;
     use codex_protocol::protocol::ReviewRequest;
     use codex_protocol::protocol::TurnAbortReason;
+    use codex_protocol::protocol::UndoCompletedEvent;
+    use codex_protocol::protocol::WarningEvent;
+    use codex_protocol::protocol::WorkspaceIntegrityEvent;
 
     use codex_protocol::user_input::UserInput;
     use codex_rmcp_client::ElicitationAction;
     use codex_rmcp_client::ElicitationResponse;
     use mcp_types::RequestId;
+    use std::path::PathBuf;
     use std::sync::Arc;
     use tracing::info;
     use tracing::warn;
@@ -1567,6 +2111,10 @@ mod handlers {
                 sess.record_conversation_items(&current_context, std::slice::from_ref(&env_item))
                     .await;
             }
+            if let Some(stale_item) = sess.stale_files_item().await {
+                sess.record_conversation_items(&current_context, std::slice::from_ref(&stale_item))
+                    .await;
+            }
 
             sess.spawn_task(Arc::clone(&current_context), items, RegularTask)
                 .await;
@@ -1636,6 +2184,30 @@ mod handlers {
         }
     }
 
+    pub async fn patch_hunk_approval(
+        sess: &Arc<Session>,
+        id: String,
+        decisions: Vec<ReviewDecision>,
+    ) {
+        if decisions.contains(&ReviewDecision::Abort) {
+            sess.interrupt_task().await;
+        } else {
+            sess.notify_patch_hunk_approval(&id, decisions).await;
+        }
+    }
+
+    pub async fn exec_approval_batch(
+        sess: &Arc<Session>,
+        id: String,
+        decisions: Vec<ReviewDecision>,
+    ) {
+        if decisions.contains(&ReviewDecision::Abort) {
+            sess.interrupt_task().await;
+        } else {
+            sess.notify_approval_batch(&id, decisions).await;
+        }
+    }
+
     pub async fn add_to_history(sess: &Arc<Session>, config: &Arc<Config>, text: String) {
         let id = sess.conversation_id;
         let config = Arc::clone(config);
@@ -1713,6 +2285,99 @@ mod handlers {
         sess.send_event_raw(event).await;
     }
 
+    pub async fn add_artifact(
+        sess: &Session,
+        config: &Arc<Config>,
+        sub_id: String,
+        path: PathBuf,
+        name: Option<String>,
+    ) {
+        let stored = if path.is_absolute() {
+            store_artifact(
+                &artifacts_dir(&config.codex_home, sess.conversation_id),
+                &path,
+                name,
+                ArtifactDirection::Inbound,
+                config.artifact_max_bytes,
+            )
+            .await
+        } else {
+            Err(format!(
+                "artifact path must be absolute: {}",
+                path.display()
+            ))
+        };
+        match stored {
+            Ok(artifact) => sess.record_artifact(&sub_id, artifact).await,
+            Err(message) => {
+                sess.send_event_raw(Event {
+                    id: sub_id,
+                    msg: EventMsg::Error(ErrorEvent {
+                        message,
+                        codex_error_info: Some(CodexErrorInfo::Other),
+                    }),
+                })
+                .await;
+            }
+        }
+    }
+
+    pub async fn check_workspace(sess: &Session, sub_id: String) {
+        let findings = workspace_findings(sess).await;
+        sess.send_event_raw(Event {
+            id: sub_id,
+            msg: EventMsg::WorkspaceIntegrity(WorkspaceIntegrityEvent { findings }),
+        })
+        .await;
+    }
+
+    pub async fn cleanup_workspace(sess: &Session, sub_id: String, ids: Vec<String>) {
+        let mut remaining = Vec::new();
+        for finding in workspace_findings(sess).await {
+            if !ids.contains(&finding.id) {
+                remaining.push(finding);
+                continue;
+            }
+            if let Err(message) = integrity::clean_up(&finding) {
+                sess.send_event_raw(Event {
+                    id: sub_id.clone(),
+                    msg: EventMsg::Warning(WarningEvent { message }),
+                })
+                .await;
+                remaining.push(finding);
+            }
+        }
+        sess.send_event_raw(Event {
+            id: sub_id,
+            msg: EventMsg::WorkspaceIntegrity(WorkspaceIntegrityEvent {
+                findings: remaining,
+            }),
+        })
+        .await;
+    }
+
+    pub async fn keep_quarantined(sess: &Session, sub_id: String, ids: Vec<String>) {
+        {
+            let mut state = sess.state.lock().await;
+            state.release_quarantined(&ids);
+        }
+        check_workspace(sess, sub_id).await;
+    }
+
+    async fn workspace_findings(sess: &Session) -> Vec<IntegrityFinding> {
+        let (cwd, started_at, quarantined) = {
+            let mut state = sess.state.lock().await;
+            (
+                state.session_configuration.cwd.clone(),
+                state.started_at,
+                state.quarantined(),
+            )
+        };
+        let mut findings = integrity::check_workspace(&cwd, started_at).await;
+        findings.extend(quarantined);
+        findings
+    }
+
     pub async fn list_custom_prompts(sess: &Session, sub_id: String) {
         let custom_prompts: Vec<CustomPrompt> =
             if let Some(dir) = crate::custom_prompts::default_prompts_dir() {
@@ -1730,6 +2395,107 @@ mod handlers {
         sess.send_event_raw(event).await;
     }
 
+    pub async fn run_doctor(sess: &Session, config: &Arc<Config>, sub_id: String) {
+        let checks = crate::doctor::run_doctor(config).await;
+        let event = Event {
+            id: sub_id,
+            msg: EventMsg::DoctorReport(DoctorReportEvent { checks }),
+        };
+        sess.send_event_raw(event).await;
+    }
+
+    pub async fn list_files_read(sess: &Session, sub_id: String) {
+        let files = {
+            let state = sess.state.lock().await;
+            state.files_read()
+        };
+        let event = Event {
+            id: sub_id,
+            msg: EventMsg::ListFilesReadResponse(ListFilesReadResponseEvent { files }),
+        };
+        sess.send_event_raw(event).await;
+    }
+
+    pub async fn list_approvals(sess: &Session, sub_id: String) {
+        send_approval_cache(sess, sub_id).await;
+    }
+
+    pub async fn revoke_approval(sess: &Session, sub_id: String, id: &str) {
+        let revoked = sess.services.tool_approvals.lock().await.revoke(id);
+        if !revoked {
+            sess.send_event_raw(Event {
+                id: sub_id.clone(),
+                msg: EventMsg::Warning(WarningEvent {
+                    message: format!("No cached approval with id {id}."),
+                }),
+            })
+            .await;
+        }
+        send_approval_cache(sess, sub_id).await;
+    }
+
+    pub async fn seed_approvals(sess: &Session, sub_id: String, approvals: Vec<ApprovalSeed>) {
+        {
+            let mut store = sess.services.tool_approvals.lock().await;
+            for seed in approvals {
+                store.seed(seed);
+            }
+        }
+        send_approval_cache(sess, sub_id).await;
+    }
+
+    async fn send_approval_cache(sess: &Session, sub_id: String) {
+        let approvals = sess.services.tool_approvals.lock().await.list();
+        let event = Event {
+            id: sub_id,
+            msg: EventMsg::ApprovalCache(ApprovalCacheEvent { approvals }),
+        };
+        sess.send_event_raw(event).await;
+    }
+
+    pub async fn context_usage(sess: &Session, sub_id: String) {
+        let turn_context = sess
+            .new_turn_with_sub_id(sub_id.clone(), SessionSettingsUpdate::default())
+            .await;
+        let base_instructions = turn_context
+            .base_instructions
+            .clone()
+            .unwrap_or_else(|| turn_context.client.get_model_family().base_instructions);
+        let mcp_tools = sess
+            .services
+            .mcp_connection_manager
+            .read()
+            .await
+            .list_all_tools()
+            .await;
+        let router = build_cache::tool_router(
+            &turn_context.tools_config,
+            Some(
+                mcp_tools
+                    .into_iter()
+                    .map(|(name, tool)| (name, tool.tool))
+                    .collect(),
+            ),
+        );
+        let tool_definitions = serde_json::to_string(&router.specs()).unwrap_or_default();
+
+        let sources = {
+            let state = sess.state.lock().await;
+            state
+                .history
+                .usage_by_source(&base_instructions, &tool_definitions)
+        };
+        let event = Event {
+            id: sub_id,
+            msg: EventMsg::ContextUsage(ContextUsageEvent {
+                total_tokens: sources.iter().map(|entry| entry.tokens).sum(),
+                sources,
+                model_context_window: turn_context.client.get_model_context_window(),
+            }),
+        };
+        sess.send_event_raw(event).await;
+    }
+
     pub async fn undo(sess: &Arc<Session>, sub_id: String) {
         let turn_context = sess
             .new_turn_with_sub_id(sub_id, SessionSettingsUpdate::default())
@@ -1738,6 +2504,35 @@ mod handlers {
             .await;
     }
 
+    pub async fn undo_patch(sess: &Session, sub_id: String, count: usize) {
+        let (success, message) = match &sess.services.patch_journal {
+            Some(journal) => {
+                let outcome = journal.lock().await.undo(count).await;
+                (outcome.stopped.is_none(), outcome.summary())
+            }
+            None => (
+                false,
+                "Reverting patches needs the `undo_patch` feature.".to_string(),
+            ),
+        };
+        sess.send_event_raw(Event {
+            id: sub_id,
+            msg: EventMsg::UndoCompleted(UndoCompletedEvent {
+                success,
+                message: Some(message),
+            }),
+        })
+        .await;
+    }
+
+    pub async fn retry_turn(sess: &Arc<Session>, sub_id: String, instructions: String) {
+        let turn_context = sess
+            .new_turn_with_sub_id(sub_id, SessionSettingsUpdate::default())
+            .await;
+        sess.spawn_task(turn_context, Vec::new(), RetryTurnTask::new(instructions))
+            .await;
+    }
+
     pub async fn compact(sess: &Arc<Session>, sub_id: String) {
         let turn_context = sess
             .new_turn_with_sub_id(sub_id, SessionSettingsUpdate::default())
@@ -1777,6 +2572,27 @@ mod handlers {
             sess.send_event_raw(event).await;
         }
 
+        let compose_projects = {
+            let mut state = sess.state.lock().await;
+            state.take_compose_projects()
+        };
+        teardown_projects(compose_projects).await;
+        sess.services.background_jobs.kill_all();
+        if let Some(containers) = &sess.services.container {
+            containers.stop().await;
+        }
+
+        // Only interrupt the user about leftovers when there are some; an
+        // explicit `Op::CheckWorkspace` always gets an answer.
+        let findings = workspace_findings(sess).await;
+        if !findings.is_empty() {
+            sess.send_event_raw(Event {
+                id: sub_id.clone(),
+                msg: EventMsg::WorkspaceIntegrity(WorkspaceIntegrityEvent { findings }),
+            })
+            .await;
+        }
+
         let event = Event {
             id: sub_id,
             msg: EventMsg::ShutdownComplete,
@@ -2045,7 +2861,7 @@ async fn run_turn(
         .list_all_tools()
         .or_cancel(&cancellation_token)
         .await?;
-    let router = Arc::new(ToolRouter::from_config(
+    let router = build_cache::tool_router(
         &turn_context.tools_config,
         Some(
             mcp_tools
@@ -2053,7 +2869,7 @@ async fn run_turn(
                 .map(|(name, tool)| (name, tool.tool))
                 .collect(),
         ),
-    ));
+    );
 
     let model_supports_parallel = turn_context
         .client
@@ -2452,6 +3268,7 @@ mod tests {
     use crate::config::ConfigOverrides;
     use crate::config::ConfigToml;
     use crate::exec::ExecToolCallOutput;
+    use crate::exec::ExitReason;
     use crate::shell::default_user_shell;
     use crate::tools::format_exec_output_str;
 
@@ -2560,7 +3377,8 @@ mod tests {
             stderr: StreamOutput::new(String::new()),
             aggregated_output: StreamOutput::new("Command output".to_string()),
             duration: StdDuration::from_secs(1),
-            timed_out: true,
+            full_output_path: None,
+            exit_reason: ExitReason::TimedOut,
         };
         let (_, turn_context) = make_session_and_context();
 
@@ -2690,6 +3508,7 @@ mod tests {
             mcp_connection_manager: Arc::new(RwLock::new(McpConnectionManager::default())),
             mcp_startup_cancellation_token: CancellationToken::new(),
             unified_exec_manager: UnifiedExecSessionManager::default(),
+            background_jobs: BackgroundJobs::default(),
             notifier: UserNotifier::new(None),
             rollout: Mutex::new(None),
             user_shell: default_user_shell(),
@@ -2697,7 +3516,23 @@ mod tests {
             auth_manager: Arc::clone(&auth_manager),
             otel_event_manager: otel_event_manager.clone(),
             tool_approvals: Mutex::new(ApprovalStore::default()),
+            approval_prompt: Mutex::new(()),
+            batch_approvals: false,
+            persistent_approvals: None,
+            audit_log: None,
             tool_executor: default_tool_executor(),
+            tool_runtimes: ToolRuntimeRegistry::default(),
+            tool_call_permits: Arc::new(Semaphore::new(MAX_PARALLEL_TOOL_CALLS)),
+            workspace_locks: WorkspaceLocks::default(),
+            web_fetch_cache: WebFetchCache::default(),
+            condensed_events: false,
+            file_read_events: false,
+            collaborators: Arc::new(Collaborators::new(tx_event.clone())),
+            read_ahead: None,
+            patch_journal: None,
+            workspace_index: None,
+            container: None,
+            replay: None,
         };
 
         let turn_context = Session::make_turn_context(
@@ -2769,6 +3604,7 @@ mod tests {
             mcp_connection_manager: Arc::new(RwLock::new(McpConnectionManager::default())),
             mcp_startup_cancellation_token: CancellationToken::new(),
             unified_exec_manager: UnifiedExecSessionManager::default(),
+            background_jobs: BackgroundJobs::default(),
             notifier: UserNotifier::new(None),
             rollout: Mutex::new(None),
             user_shell: default_user_shell(),
@@ -2776,7 +3612,23 @@ mod tests {
             auth_manager: Arc::clone(&auth_manager),
             otel_event_manager: otel_event_manager.clone(),
             tool_approvals: Mutex::new(ApprovalStore::default()),
+            approval_prompt: Mutex::new(()),
+            batch_approvals: false,
+            persistent_approvals: None,
+            audit_log: None,
             tool_executor: default_tool_executor(),
+            tool_runtimes: ToolRuntimeRegistry::default(),
+            tool_call_permits: Arc::new(Semaphore::new(MAX_PARALLEL_TOOL_CALLS)),
+            workspace_locks: WorkspaceLocks::default(),
+            web_fetch_cache: WebFetchCache::default(),
+            condensed_events: false,
+            file_read_events: false,
+            collaborators: Arc::new(Collaborators::new(tx_event.clone())),
+            read_ahead: None,
+            patch_journal: None,
+            workspace_index: None,
+            container: None,
+            replay: None,
         };
 
         let turn_context = Arc::new(Session::make_turn_context(
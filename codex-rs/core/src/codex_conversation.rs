@@ -1,5 +1,8 @@
 use crate::codex::Codex;
+use crate::collab::Collaborator;
+use crate::error::CodexErr;
 use crate::error::Result as CodexResult;
+use crate::protocol::CollaboratorRole;
 use crate::protocol::Event;
 use crate::protocol::Op;
 use crate::protocol::Submission;
@@ -33,6 +36,15 @@ impl CodexConversation {
         self.codex.next_event().await
     }
 
+    /// Attach another client to this conversation. It receives every event
+    /// from now on and may submit what `role` allows.
+    pub fn attach(&self, role: CollaboratorRole) -> CodexResult<Collaborator> {
+        let collaborators = self.codex.collaborators.as_ref().ok_or_else(|| {
+            CodexErr::UnsupportedOperation("this conversation cannot be joined".to_string())
+        })?;
+        Ok(collaborators.attach(role, self.codex.tx_sub.clone()))
+    }
+
     pub fn rollout_path(&self) -> PathBuf {
         self.rollout_path.clone()
     }
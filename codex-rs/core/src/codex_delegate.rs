@@ -23,6 +23,7 @@ use crate::codex::Session;
 use crate::codex::TurnContext;
 use crate::config::Config;
 use crate::error::CodexErr;
+use crate::features::Feature;
 use codex_protocol::protocol::InitialHistory;
 
 /// Start an interactive sub-Codex conversation and return IO channels.
@@ -31,7 +32,7 @@ use codex_protocol::protocol::InitialHistory;
 /// Approval requests are handled via `parent_session` and are not surfaced.
 /// The returned `ops_tx` allows the caller to submit additional `Op`s to the sub-agent.
 pub(crate) async fn run_codex_conversation_interactive(
-    config: Config,
+    mut config: Config,
     auth_manager: Arc<AuthManager>,
     parent_session: Arc<Session>,
     parent_ctx: Arc<TurnContext>,
@@ -40,6 +41,8 @@ pub(crate) async fn run_codex_conversation_interactive(
 ) -> Result<Codex, CodexErr> {
     let (tx_sub, rx_sub) = async_channel::bounded(SUBMISSION_CHANNEL_CAPACITY);
     let (tx_ops, rx_ops) = async_channel::bounded(SUBMISSION_CHANNEL_CAPACITY);
+    // Approvals are forwarded to the parent session one request at a time.
+    config.features.disable(Feature::BatchApprovals);
 
     let CodexSpawnOk { codex, .. } = Codex::spawn(
         config,
@@ -47,6 +50,7 @@ pub(crate) async fn run_codex_conversation_interactive(
         initial_history.unwrap_or(InitialHistory::New),
         SessionSource::SubAgent(SubAgentSource::Review),
         parent_session.services.tool_executor.clone(),
+        parent_session.services.tool_runtimes.clone(),
     )
     .await?;
     let codex = Arc::new(codex);
@@ -82,6 +86,9 @@ pub(crate) async fn run_codex_conversation_interactive(
         next_id: AtomicU64::new(0),
         tx_sub: tx_ops,
         rx_event: rx_sub,
+        collaborators: None,
+        _collab_server: None,
+        _control_server: None,
     })
 }
 
@@ -147,6 +154,9 @@ pub(crate) async fn run_codex_conversation_one_shot(
         next_id: AtomicU64::new(0),
         rx_event: rx_bridge,
         tx_sub: tx_closed,
+        collaborators: None,
+        _collab_server: None,
+        _control_server: None,
     })
 }
 
@@ -262,6 +272,8 @@ async fn handle_patch_approval(
             parent_ctx,
             parent_ctx.sub_id.clone(),
             event.changes,
+            event.hunks,
+            event.user_changes,
             event.reason,
             event.grant_root,
         )
@@ -273,7 +285,13 @@ async fn handle_patch_approval(
         cancel_token,
     )
     .await;
-    let _ = codex.submit(Op::PatchApproval { id, decision }).await;
+    // Pass a hunk-by-hunk answer on as such so the sub-agent applies only the
+    // approved hunks.
+    let op = match parent_session.take_hunk_decisions(&parent_ctx.sub_id).await {
+        Some(decisions) => Op::PatchHunkApproval { id, decisions },
+        None => Op::PatchApproval { id, decision },
+    };
+    let _ = codex.submit(op).await;
 }
 
 /// Await an approval decision, aborting on cancellation.
@@ -0,0 +1,453 @@
+//! Additional clients attached to a live session.
+//!
+//! The client that started a conversation owns it through
+//! [`CodexConversation`](crate::CodexConversation). Other clients can join as
+//! collaborators: every collaborator receives the full event stream, and an
+//! [`CollaboratorRole::Approver`] may also answer approval prompts and
+//! interrupt the turn. Whoever answers a prompt first wins; the session then
+//! emits `ApprovalResolved` so the other participants can dismiss theirs.
+//!
+//! Collaborators attach in-process with
+//! [`CodexConversation::attach`](crate::CodexConversation::attach), or over
+//! the Unix socket configured with `collab_socket`. The socket speaks JSON
+//! lines: the client first sends `{"role": "observer" | "approver"}`, then
+//! receives one [`Event`] per line and may send one [`Op`] per line. Any byte
+//! relay (`ssh -L`, `socat`) can carry the socket to a remote supervisor. The
+//! JSON-RPC `control_socket` (see [`crate::control`]) attaches its clients as
+//! [`CollaboratorRole::Controller`]s.
+
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+
+use async_channel::Receiver;
+use async_channel::Sender;
+use codex_protocol::protocol::CollaboratorEvent;
+use codex_protocol::protocol::CollaboratorRole;
+use codex_protocol::protocol::Event;
+use codex_protocol::protocol::EventMsg;
+use codex_protocol::protocol::Op;
+use codex_protocol::protocol::Submission;
+use tokio_util::task::AbortOnDropHandle;
+use tracing::warn;
+
+use crate::error::CodexErr;
+use crate::error::Result as CodexResult;
+
+/// Fan-out of session events to attached collaborators.
+pub(crate) struct Collaborators {
+    next_id: AtomicU64,
+    /// The primary client's event channel, used to announce joins and leaves.
+    tx_event: Sender<Event>,
+    subscribers: Mutex<Vec<Subscriber>>,
+}
+
+struct Subscriber {
+    id: String,
+    tx_event: Sender<Event>,
+}
+
+impl Collaborators {
+    pub(crate) fn new(tx_event: Sender<Event>) -> Self {
+        Self {
+            next_id: AtomicU64::new(1),
+            tx_event,
+            subscribers: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Forward `event` to every collaborator, forgetting the ones that have
+    /// gone away.
+    pub(crate) fn broadcast(&self, event: &Event) {
+        let Ok(mut subscribers) = self.subscribers.lock() else {
+            return;
+        };
+        subscribers.retain(|subscriber| subscriber.tx_event.try_send(event.clone()).is_ok());
+    }
+
+    /// Receive every event from now on without joining as a participant, for
+    /// in-process bookkeeping that other participants need not know about.
+    pub(crate) fn subscribe(&self) -> Receiver<Event> {
+        let id = format!("internal-{}", self.next_id.fetch_add(1, Ordering::SeqCst));
+        let (tx_event, rx_event) = async_channel::unbounded();
+        if let Ok(mut subscribers) = self.subscribers.lock() {
+            subscribers.push(Subscriber { id, tx_event });
+        }
+        rx_event
+    }
+
+    pub(crate) fn attach(
+        self: &Arc<Self>,
+        role: CollaboratorRole,
+        tx_sub: Sender<Submission>,
+    ) -> Collaborator {
+        let id = format!("collab-{}", self.next_id.fetch_add(1, Ordering::SeqCst));
+        let (tx_event, rx_event) = async_channel::unbounded();
+        if let Ok(mut subscribers) = self.subscribers.lock() {
+            subscribers.push(Subscriber {
+                id: id.clone(),
+                tx_event,
+            });
+        }
+        self.announce(EventMsg::CollaboratorJoined(CollaboratorEvent {
+            id: id.clone(),
+            role,
+        }));
+        Collaborator {
+            id,
+            role,
+            hub: Arc::clone(self),
+            rx_event,
+            tx_sub,
+            next_sub_id: AtomicU64::new(0),
+        }
+    }
+
+    fn detach(&self, id: &str, role: CollaboratorRole) {
+        if let Ok(mut subscribers) = self.subscribers.lock() {
+            subscribers.retain(|subscriber| subscriber.id != id);
+        }
+        self.announce(EventMsg::CollaboratorLeft(CollaboratorEvent {
+            id: id.to_string(),
+            role,
+        }));
+    }
+
+    fn announce(&self, msg: EventMsg) {
+        let event = Event {
+            id: String::new(),
+            msg,
+        };
+        if let Err(err) = self.tx_event.try_send(event.clone()) {
+            warn!("failed to announce collaborator change: {err}");
+        }
+        self.broadcast(&event);
+    }
+}
+
+/// A client attached to a session it did not start.
+pub struct Collaborator {
+    id: String,
+    role: CollaboratorRole,
+    hub: Arc<Collaborators>,
+    rx_event: Receiver<Event>,
+    tx_sub: Sender<Submission>,
+    next_sub_id: AtomicU64,
+}
+
+impl Collaborator {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn role(&self) -> CollaboratorRole {
+        self.role
+    }
+
+    pub async fn next_event(&self) -> CodexResult<Event> {
+        self.rx_event
+            .recv()
+            .await
+            .map_err(|_| CodexErr::InternalAgentDied)
+    }
+
+    /// Submit `op` if this collaborator's role allows it.
+    pub async fn submit(&self, op: Op) -> CodexResult<String> {
+        if !may_submit(self.role, &op) {
+            return Err(CodexErr::UnsupportedOperation(match self.role {
+                CollaboratorRole::Observer => "observers cannot submit operations".to_string(),
+                CollaboratorRole::Approver => {
+                    "approvers may only answer approval prompts or interrupt the turn".to_string()
+                }
+                CollaboratorRole::Controller => {
+                    "controllers may only send or retry messages, answer approval prompts, \
+                     manage cached approvals or interrupt the turn"
+                        .to_string()
+                }
+            }));
+        }
+        let id = format!(
+            "{}-{}",
+            self.id,
+            self.next_sub_id.fetch_add(1, Ordering::SeqCst)
+        );
+        self.tx_sub
+            .send(Submission { id: id.clone(), op })
+            .await
+            .map_err(|_| CodexErr::InternalAgentDied)?;
+        Ok(id)
+    }
+}
+
+impl Drop for Collaborator {
+    fn drop(&mut self) {
+        self.hub.detach(&self.id, self.role);
+    }
+}
+
+fn may_submit(role: CollaboratorRole, op: &Op) -> bool {
+    match role {
+        CollaboratorRole::Observer => false,
+        CollaboratorRole::Approver => matches!(
+            op,
+            Op::ExecApproval { .. }
+                | Op::PatchApproval { .. }
+                | Op::PatchHunkApproval { .. }
+                | Op::ExecApprovalBatch { .. }
+                | Op::ResolveElicitation { .. }
+                | Op::Interrupt
+        ),
+        CollaboratorRole::Controller => matches!(
+            op,
+            Op::UserInput { .. }
+                | Op::UserTurn { .. }
+                | Op::RetryTurn { .. }
+                | Op::UndoPatch { .. }
+                | Op::ListApprovals
+                | Op::RevokeApproval { .. }
+                | Op::SeedApprovals { .. }
+                | Op::ExecApproval { .. }
+                | Op::PatchApproval { .. }
+                | Op::PatchHunkApproval { .. }
+                | Op::ExecApprovalBatch { .. }
+                | Op::ResolveElicitation { .. }
+                | Op::Interrupt
+        ),
+    }
+}
+
+/// Listen for collaborators on the Unix socket at `path` until the returned
+/// handle is dropped. Failures are logged rather than failing the session.
+pub(crate) fn serve(
+    hub: &Arc<Collaborators>,
+    tx_sub: &Sender<Submission>,
+    path: &Path,
+) -> Option<AbortOnDropHandle<()>> {
+    #[cfg(unix)]
+    match socket::listen(Arc::clone(hub), tx_sub.clone(), path) {
+        Ok(handle) => Some(handle),
+        Err(err) => {
+            warn!(
+                "failed to listen for collaborators on {}: {err}",
+                path.display()
+            );
+            None
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (hub, tx_sub);
+        warn!(
+            "collab_socket is only supported on Unix; ignoring {}",
+            path.display()
+        );
+        None
+    }
+}
+
+#[cfg(unix)]
+mod socket {
+    use std::io;
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::Path;
+    use std::sync::Arc;
+
+    use async_channel::Sender;
+    use codex_protocol::protocol::CollaboratorRole;
+    use codex_protocol::protocol::ErrorEvent;
+    use codex_protocol::protocol::Event;
+    use codex_protocol::protocol::EventMsg;
+    use codex_protocol::protocol::Op;
+    use codex_protocol::protocol::Submission;
+    use serde::Deserialize;
+    use tokio::io::AsyncBufReadExt;
+    use tokio::io::AsyncWriteExt;
+    use tokio::io::BufReader;
+    use tokio::net::UnixListener;
+    use tokio::net::UnixStream;
+    use tokio::task::JoinSet;
+    use tokio_util::task::AbortOnDropHandle;
+    use tracing::warn;
+
+    use super::Collaborators;
+
+    #[derive(Deserialize)]
+    struct Hello {
+        role: CollaboratorRole,
+    }
+
+    /// The socket is only accessible to the current user.
+    pub(super) fn listen(
+        hub: Arc<Collaborators>,
+        tx_sub: Sender<Submission>,
+        path: &Path,
+    ) -> io::Result<AbortOnDropHandle<()>> {
+        if path.exists() {
+            // A previous session that did not shut down cleanly leaves the
+            // socket file behind; binding fails until it is removed.
+            std::fs::remove_file(path)?;
+        }
+        let listener = UnixListener::bind(path)?;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+        Ok(AbortOnDropHandle::new(tokio::spawn(async move {
+            // Connections live in the set so they end with the server.
+            let mut connections = JoinSet::new();
+            loop {
+                match listener.accept().await {
+                    Ok((stream, _)) => {
+                        while connections.try_join_next().is_some() {}
+                        connections.spawn(handle_connection(
+                            stream,
+                            Arc::clone(&hub),
+                            tx_sub.clone(),
+                        ));
+                    }
+                    Err(err) => {
+                        warn!("collaborator socket accept failed: {err}");
+                        return;
+                    }
+                }
+            }
+        })))
+    }
+
+    async fn handle_connection(
+        stream: UnixStream,
+        hub: Arc<Collaborators>,
+        tx_sub: Sender<Submission>,
+    ) {
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+        let hello = match lines.next_line().await {
+            Ok(Some(line)) => serde_json::from_str::<Hello>(&line),
+            _ => return,
+        };
+        let hello = match hello {
+            Ok(hello) => hello,
+            Err(err) => {
+                let _ =
+                    write_event(&mut writer, &error_event(format!("invalid hello: {err}"))).await;
+                return;
+            }
+        };
+        let collaborator = hub.attach(hello.role, tx_sub);
+
+        loop {
+            let event = tokio::select! {
+                event = collaborator.next_event() => match event {
+                    Ok(event) => event,
+                    Err(_) => return,
+                },
+                line = lines.next_line() => {
+                    let Ok(Some(line)) = line else {
+                        return;
+                    };
+                    let result = match serde_json::from_str::<Op>(&line) {
+                        Ok(op) => collaborator.submit(op).await.map_err(|err| err.to_string()),
+                        Err(err) => Err(format!("invalid op: {err}")),
+                    };
+                    match result {
+                        Ok(_) => continue,
+                        Err(message) => error_event(message),
+                    }
+                }
+            };
+            let shutdown = matches!(event.msg, EventMsg::ShutdownComplete);
+            if write_event(&mut writer, &event).await.is_err() || shutdown {
+                return;
+            }
+        }
+    }
+
+    fn error_event(message: String) -> Event {
+        Event {
+            id: String::new(),
+            msg: EventMsg::Error(ErrorEvent {
+                message,
+                codex_error_info: None,
+            }),
+        }
+    }
+
+    async fn write_event<W: AsyncWriteExt + Unpin>(
+        writer: &mut W,
+        event: &Event,
+    ) -> io::Result<()> {
+        let mut line = serde_json::to_vec(event)?;
+        line.push(b'\n');
+        writer.write_all(&line).await?;
+        writer.flush().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_protocol::protocol::ReviewDecision;
+    use pretty_assertions::assert_eq;
+
+    #[tokio::test]
+    async fn collaborators_see_events_and_are_limited_by_role() {
+        let (tx_event, rx_event) = async_channel::unbounded();
+        let (tx_sub, rx_sub) = async_channel::unbounded();
+        let hub = Arc::new(Collaborators::new(tx_event));
+
+        let observer = hub.attach(CollaboratorRole::Observer, tx_sub.clone());
+        let EventMsg::CollaboratorJoined(joined) = rx_event.recv().await.expect("joined").msg
+        else {
+            panic!("expected CollaboratorJoined");
+        };
+        assert_eq!(
+            joined,
+            CollaboratorEvent {
+                id: "collab-1".to_string(),
+                role: CollaboratorRole::Observer,
+            }
+        );
+        // Collaborators see their own join like everyone else's.
+        assert!(matches!(
+            observer.next_event().await.expect("own join").msg,
+            EventMsg::CollaboratorJoined(_)
+        ));
+
+        hub.broadcast(&Event {
+            id: "1".to_string(),
+            msg: EventMsg::ShutdownComplete,
+        });
+        assert!(matches!(
+            observer.next_event().await.expect("event").msg,
+            EventMsg::ShutdownComplete
+        ));
+        assert!(observer.submit(Op::Interrupt).await.is_err());
+
+        let approver = hub.attach(CollaboratorRole::Approver, tx_sub);
+        let approval = Op::ExecApproval {
+            id: "call".to_string(),
+            decision: ReviewDecision::Approved,
+        };
+        assert_eq!(
+            approver.submit(approval).await.expect("submit"),
+            "collab-2-0"
+        );
+        assert!(matches!(
+            rx_sub.recv().await.expect("submission").op,
+            Op::ExecApproval { .. }
+        ));
+        assert!(approver.submit(Op::Compact).await.is_err());
+
+        drop(approver);
+        let _ = rx_event.recv().await.expect("second joined");
+        let EventMsg::CollaboratorLeft(left) = rx_event.recv().await.expect("left").msg else {
+            panic!("expected CollaboratorLeft");
+        };
+        assert_eq!(
+            left,
+            CollaboratorEvent {
+                id: "collab-2".to_string(),
+                role: CollaboratorRole::Approver,
+            }
+        );
+    }
+}
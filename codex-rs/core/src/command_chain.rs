@@ -0,0 +1,261 @@
+//! Splitting `bash -lc` chains into steps that are approved and run one by
+//! one.
+//!
+//! `cargo build && cargo test && git push` is one command to the approval
+//! prompt, so the user can only take or refuse all of it. With the
+//! `split_command_chains` feature the chain is run as separate steps, each
+//! asking for approval on its own, and `&&`, `||` and `;` decide which steps
+//! run exactly as the shell would. A chain is only split when doing so cannot
+//! change what it does: every step runs in a fresh shell, so a step that
+//! changes the shell's state, other than a plain `cd`, keeps the chain whole.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use tree_sitter::Node;
+
+use crate::bash::extract_bash_command;
+use crate::bash::try_parse_shell;
+
+/// Builtins whose effect would not carry over to the next step.
+const STATEFUL_BUILTINS: &[&str] = &[
+    ".", "alias", "builtin", "cd", "command", "declare", "enable", "eval", "exec", "export",
+    "hash", "local", "popd", "pushd", "read", "readonly", "set", "shift", "shopt", "source",
+    "trap", "typeset", "ulimit", "umask", "unalias", "unset",
+];
+
+/// Node kinds that change the shell's state wherever they appear.
+const STATEFUL_KINDS: &[&str] = &[
+    "declaration_command",
+    "function_definition",
+    "unset_command",
+];
+
+/// When a step runs, given how the previous one that ran exited.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum RunIf {
+    /// First step, or after `;` or a newline.
+    Always,
+    /// After `&&`.
+    Success,
+    /// After `||`.
+    Failure,
+}
+
+impl RunIf {
+    pub(crate) fn should_run(self, last_exit_code: i32) -> bool {
+        match self {
+            RunIf::Always => true,
+            RunIf::Success => last_exit_code == 0,
+            RunIf::Failure => last_exit_code != 0,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum StepAction {
+    /// Run this script in the chain's shell.
+    Run(String),
+    /// Run later steps in this directory, relative to the current one.
+    Cd(PathBuf),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct ChainStep {
+    pub(crate) run_if: RunIf,
+    pub(crate) action: StepAction,
+}
+
+impl ChainStep {
+    /// The step as the user would have typed it.
+    pub(crate) fn display(&self) -> String {
+        match &self.action {
+            StepAction::Run(script) => script.clone(),
+            StepAction::Cd(path) => format!("cd {}", path.display()),
+        }
+    }
+}
+
+/// The steps of a `bash -lc` chain, or `None` when `command` is not a chain
+/// that can be split safely.
+pub(crate) fn split_command_chain(command: &[String]) -> Option<Vec<ChainStep>> {
+    let (_, script) = extract_bash_command(command)?;
+    // A heredoc body is not part of its statement's source range.
+    if script.contains("<<") {
+        return None;
+    }
+    let tree = try_parse_shell(script)?;
+    let root = tree.root_node();
+    if root.has_error() {
+        return None;
+    }
+
+    let mut steps = Vec::new();
+    let mut cursor = root.walk();
+    for node in root.children(&mut cursor) {
+        match node.kind() {
+            ";" | "comment" => {}
+            // Backgrounded statements keep running alongside later ones.
+            "&" => return None,
+            _ => push_statement(node, RunIf::Always, script, &mut steps)?,
+        }
+    }
+    (steps.len() > 1).then_some(steps)
+}
+
+/// Appends the steps of `node`, flattening `a && b || c` lists.
+fn push_statement(
+    node: Node<'_>,
+    run_if: RunIf,
+    src: &str,
+    steps: &mut Vec<ChainStep>,
+) -> Option<()> {
+    if node.kind() == "list" {
+        let mut cursor = node.walk();
+        let children: Vec<Node<'_>> = node.children(&mut cursor).collect();
+        let [left, operator, right] = children.as_slice() else {
+            return None;
+        };
+        let right_run_if = match operator.kind() {
+            "&&" => RunIf::Success,
+            "||" => RunIf::Failure,
+            _ => return None,
+        };
+        push_statement(*left, run_if, src, steps)?;
+        return push_statement(*right, right_run_if, src, steps);
+    }
+
+    let text = node.utf8_text(src.as_bytes()).ok()?;
+    if let Some(dir) = plain_cd(node, src) {
+        steps.push(ChainStep {
+            run_if,
+            action: StepAction::Cd(dir),
+        });
+        return Some(());
+    }
+    if changes_shell_state(node, src) {
+        return None;
+    }
+    steps.push(ChainStep {
+        run_if,
+        action: StepAction::Run(text.to_string()),
+    });
+    Some(())
+}
+
+/// The directory of a `cd` with one literal argument.
+fn plain_cd(node: Node<'_>, src: &str) -> Option<PathBuf> {
+    if node.kind() != "command" {
+        return None;
+    }
+    let mut cursor = node.walk();
+    let words: Vec<Node<'_>> = node.named_children(&mut cursor).collect();
+    let [name, dir] = words.as_slice() else {
+        return None;
+    };
+    if name.utf8_text(src.as_bytes()).ok()? != "cd" || dir.kind() != "word" {
+        return None;
+    }
+    let dir = dir.utf8_text(src.as_bytes()).ok()?;
+    let literal = !dir.starts_with(['-', '~']) && !dir.contains(['$', '`', '*', '?', '\\']);
+    literal.then(|| PathBuf::from(dir))
+}
+
+fn changes_shell_state(node: Node<'_>, src: &str) -> bool {
+    let mut stack = vec![node];
+    while let Some(node) = stack.pop() {
+        let stateful = match node.kind() {
+            kind if STATEFUL_KINDS.contains(&kind) => true,
+            // `FOO=bar cmd` only sets FOO for `cmd`.
+            "variable_assignment" => node.parent().is_none_or(|p| p.kind() != "command"),
+            "command" => node
+                .child_by_field_name("name")
+                .and_then(|name| name.utf8_text(src.as_bytes()).ok())
+                .is_some_and(|name| STATEFUL_BUILTINS.contains(&name)),
+            _ => false,
+        };
+        if stateful {
+            return true;
+        }
+        let mut cursor = node.walk();
+        stack.extend(node.children(&mut cursor));
+    }
+    false
+}
+
+/// The directory a `cd` step moves to from `cwd`, if it exists.
+pub(crate) fn resolve_cd(cwd: &Path, dir: &Path) -> Option<PathBuf> {
+    let target = cwd.join(dir);
+    target.is_dir().then_some(target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn split(script: &str) -> Option<Vec<ChainStep>> {
+        split_command_chain(&["bash".to_string(), "-lc".to_string(), script.to_string()])
+    }
+
+    fn run(run_if: RunIf, script: &str) -> ChainStep {
+        ChainStep {
+            run_if,
+            action: StepAction::Run(script.to_string()),
+        }
+    }
+
+    #[test]
+    fn chains_split_at_top_level_operators() {
+        assert_eq!(
+            split("cargo build && cargo test | tee log; git push || echo 'push failed'"),
+            Some(vec![
+                run(RunIf::Always, "cargo build"),
+                run(RunIf::Success, "cargo test | tee log"),
+                run(RunIf::Always, "git push"),
+                run(RunIf::Failure, "echo 'push failed'"),
+            ])
+        );
+        assert_eq!(
+            split("cd app && (make; make install)"),
+            Some(vec![
+                ChainStep {
+                    run_if: RunIf::Always,
+                    action: StepAction::Cd(PathBuf::from("app")),
+                },
+                run(RunIf::Success, "(make; make install)"),
+            ])
+        );
+        assert_eq!(split("cargo test"), None);
+    }
+
+    #[test]
+    fn chains_that_change_shell_state_stay_whole() {
+        assert_eq!(split("export RUST_LOG=debug && cargo test"), None);
+        assert_eq!(split("FOO=1; echo $FOO"), None);
+        assert_eq!(split("cd $HOME && ls"), None);
+        assert_eq!(split("source env.sh && make"), None);
+        assert_eq!(split("npm start & sleep 5"), None);
+        assert_eq!(
+            split("RUST_LOG=debug cargo test; echo done"),
+            Some(vec![
+                run(RunIf::Always, "RUST_LOG=debug cargo test"),
+                run(RunIf::Always, "echo done"),
+            ])
+        );
+    }
+
+    #[test]
+    fn steps_run_as_the_shell_would_short_circuit() {
+        // `false && a || b` skips `a` and runs `b`.
+        let mut last_exit_code = 1;
+        let mut ran = Vec::new();
+        for (run_if, name, exit_code) in [(RunIf::Success, "a", 0), (RunIf::Failure, "b", 0)] {
+            if run_if.should_run(last_exit_code) {
+                ran.push(name);
+                last_exit_code = exit_code;
+            }
+        }
+        assert_eq!(ran, vec!["b"]);
+    }
+}
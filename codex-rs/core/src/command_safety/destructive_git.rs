@@ -0,0 +1,503 @@
+//! Git commands that discard uncommitted work, such as `git reset --hard`,
+//! `git clean -fdx` and `git checkout -- .`, and the `git_guard` policy
+//! applied to them.
+//!
+//! Before such a command runs, git is asked what it would lose. When that is
+//! anything at all, the approval request lists it, and with
+//! `git_guard.auto_stash` the work is stashed first so `git stash pop` can
+//! bring it back.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_protocol::protocol::AskForApproval;
+
+use crate::command_safety::commands_run;
+use crate::config::types::GitGuard;
+use crate::git_info::run_git_command_with_timeout;
+use crate::tools::sandboxing::ApprovalRequirement;
+
+/// Git options that come before the subcommand and take a separate value.
+const GIT_VALUE_OPTIONS: &[&str] = &[
+    "-C",
+    "-c",
+    "--config-env",
+    "--exec-path",
+    "--git-dir",
+    "--namespace",
+    "--work-tree",
+];
+/// Files named in a warning before the rest are counted.
+const MAX_LISTED_FILES: usize = 5;
+const STASH_MESSAGE_PREFIX: &str = "codex: before";
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Discard {
+    /// Resets tracked files under `paths`, or everywhere when empty. Staged
+    /// changes are lost too when `staged` is set.
+    Changes { paths: Vec<String>, staged: bool },
+    /// Deletes untracked files, as `git clean` with these arguments.
+    Untracked {
+        clean_args: Vec<String>,
+        ignored: bool,
+    },
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct DestructiveGit {
+    /// The directory git runs in, after any `-C`.
+    pub(crate) cwd: PathBuf,
+    pub(crate) discard: Discard,
+}
+
+/// What a destructive git command would discard.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct Loss {
+    pub(crate) command: DestructiveGit,
+    /// Files as git reports them, relative to the repository root for
+    /// changes and to `cwd` for untracked files.
+    pub(crate) files: Vec<String>,
+}
+
+impl Loss {
+    fn describe(&self) -> String {
+        let count = self.files.len();
+        let plural = if count == 1 { "" } else { "s" };
+        let mut listed = self
+            .files
+            .iter()
+            .take(MAX_LISTED_FILES)
+            .map(|file| format!("`{file}`"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        if count > MAX_LISTED_FILES {
+            let more = count - MAX_LISTED_FILES;
+            listed.push_str(&format!(" and {more} more"));
+        }
+        match self.command.discard {
+            Discard::Changes { .. } => {
+                format!("uncommitted changes to {count} file{plural} ({listed})")
+            }
+            Discard::Untracked { .. } => format!("{count} untracked file{plural} ({listed})"),
+        }
+    }
+
+    fn stash_plan(&self, message: &str) -> StashPlan {
+        let untracked = match &self.command.discard {
+            Discard::Changes { .. } => None,
+            Discard::Untracked { ignored, .. } => Some(UntrackedStash {
+                paths: self.files.clone(),
+                include_ignored: *ignored,
+            }),
+        };
+        StashPlan {
+            cwd: self.command.cwd.clone(),
+            message: message.to_string(),
+            untracked,
+        }
+    }
+}
+
+/// Work to stash before a destructive git command runs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StashPlan {
+    cwd: PathBuf,
+    message: String,
+    /// Untracked files to move into the stash; `None` snapshots tracked
+    /// changes without touching the working tree.
+    untracked: Option<UntrackedStash>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct UntrackedStash {
+    paths: Vec<String>,
+    include_ignored: bool,
+}
+
+impl StashPlan {
+    /// Stashes the work. Tracked changes are stored with `git stash create`
+    /// so the command still sees them; untracked files are moved into the
+    /// stash, which is what the command was about to do with them anyway.
+    pub async fn stash(&self) -> Result<(), String> {
+        let message = self.message.as_str();
+        let Some(untracked) = &self.untracked else {
+            let sha = run_git(&["stash", "create", message], &self.cwd).await?;
+            let sha = sha.trim();
+            if sha.is_empty() {
+                return Ok(());
+            }
+            return run_git(&["stash", "store", "-m", message, sha], &self.cwd)
+                .await
+                .map(drop);
+        };
+        let include = if untracked.include_ignored {
+            "--all"
+        } else {
+            "--include-untracked"
+        };
+        let mut args = vec!["stash", "push", include, "-m", message, "--"];
+        args.extend(untracked.paths.iter().map(String::as_str));
+        run_git(&args, &self.cwd).await.map(drop)
+    }
+}
+
+/// The requirement `git_guard` imposes on a command and the stashes to make
+/// before it runs.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct GitGuardOutcome {
+    pub(crate) requirement: ApprovalRequirement,
+    pub(crate) stash: Vec<StashPlan>,
+}
+
+/// Asks git what `command` would discard, and returns `None` when that is
+/// nothing or `requirement` already forbids the command. Otherwise the user
+/// is asked with a list of what would be lost, unless the approval policy is
+/// `never`, in which case `requirement` stands.
+pub(crate) async fn git_guard_requirement(
+    policy: &GitGuard,
+    command: &[String],
+    cwd: &Path,
+    approval_policy: AskForApproval,
+    requirement: &ApprovalRequirement,
+) -> Option<GitGuardOutcome> {
+    if matches!(requirement, ApprovalRequirement::Forbidden { .. }) {
+        return None;
+    }
+    let mut losses = Vec::new();
+    for destructive in find_destructive_git(command, cwd) {
+        if let Some(loss) = compute_loss(destructive).await {
+            losses.push(loss);
+        }
+    }
+    if losses.is_empty() {
+        return None;
+    }
+
+    let display =
+        shlex::try_join(command.iter().map(String::as_str)).unwrap_or_else(|_| command.join(" "));
+    let message = format!("{STASH_MESSAGE_PREFIX} {display}");
+    let stash = if policy.auto_stash {
+        losses
+            .iter()
+            .map(|loss| loss.stash_plan(&message))
+            .collect()
+    } else {
+        Vec::new()
+    };
+    if approval_policy == AskForApproval::Never {
+        return Some(GitGuardOutcome {
+            requirement: requirement.clone(),
+            stash,
+        });
+    }
+    let lost = losses
+        .iter()
+        .map(Loss::describe)
+        .collect::<Vec<_>>()
+        .join(" and ");
+    let recovery = if policy.auto_stash {
+        "They will be stashed first; `git stash pop` brings them back."
+    } else {
+        "This cannot be undone."
+    };
+    Some(GitGuardOutcome {
+        requirement: ApprovalRequirement::NeedsApproval {
+            reason: Some(format!("Warning: this command discards {lost}. {recovery}")),
+        },
+        stash,
+    })
+}
+
+/// The git commands in `command` that discard uncommitted work, including
+/// those inside a `bash -lc` script.
+pub(crate) fn find_destructive_git(command: &[String], cwd: &Path) -> Vec<DestructiveGit> {
+    commands_run(command)
+        .iter()
+        .filter_map(|command| destructive_git(command, cwd))
+        .collect()
+}
+
+fn destructive_git(command: &[String], cwd: &Path) -> Option<DestructiveGit> {
+    // Skip environment assignments such as `GIT_DIR=.git`.
+    let start = command.iter().position(|word| !word.contains('='))?;
+    let (program, args) = command[start..].split_first()?;
+    let name = Path::new(program).file_name()?.to_str()?;
+    if name.trim_end_matches(".exe") != "git" {
+        return None;
+    }
+
+    let mut cwd = cwd.to_path_buf();
+    let mut i = 0;
+    while let Some(arg) = args.get(i)
+        && arg.starts_with('-')
+    {
+        if GIT_VALUE_OPTIONS.contains(&arg.as_str()) {
+            if arg == "-C" {
+                cwd = cwd.join(args.get(i + 1)?);
+            }
+            i += 1;
+        }
+        i += 1;
+    }
+    let (subcommand, args) = args.get(i..)?.split_first()?;
+    let discard = match subcommand.as_str() {
+        "reset" => reset(args),
+        "clean" => clean(args),
+        "checkout" => checkout(args),
+        "restore" => restore(args),
+        "switch" => switch(args),
+        _ => None,
+    }?;
+    Some(DestructiveGit { cwd, discard })
+}
+
+fn reset(args: &[String]) -> Option<Discard> {
+    args.iter()
+        .any(|arg| arg == "--hard")
+        .then(|| Discard::Changes {
+            paths: Vec::new(),
+            staged: true,
+        })
+}
+
+fn clean(args: &[String]) -> Option<Discard> {
+    let has_flag = |short: char, long: &str| {
+        args.iter().any(|arg| {
+            arg == long
+                || (!arg.starts_with("--")
+                    && arg
+                        .strip_prefix('-')
+                        .is_some_and(|flags| flags.contains(short)))
+        })
+    };
+    // Without `-f` git refuses to clean; `-n` and `-i` only show or ask.
+    if !has_flag('f', "--force") || has_flag('n', "--dry-run") || has_flag('i', "--interactive") {
+        return None;
+    }
+    Some(Discard::Untracked {
+        clean_args: args.to_vec(),
+        ignored: has_flag('x', "") || has_flag('X', ""),
+    })
+}
+
+fn checkout(args: &[String]) -> Option<Discard> {
+    if args.iter().any(|arg| arg == "-f" || arg == "--force") {
+        return Some(Discard::Changes {
+            paths: Vec::new(),
+            staged: true,
+        });
+    }
+    match args.iter().position(|arg| arg == "--") {
+        // `git checkout HEAD -- src` restores from a commit, dropping staged
+        // changes too; `git checkout -- src` restores from the index.
+        Some(separator) => Some(Discard::Changes {
+            paths: args[separator + 1..].to_vec(),
+            staged: args[..separator].iter().any(|arg| !arg.starts_with('-')),
+        }),
+        None => args.iter().any(|arg| arg == ".").then(|| Discard::Changes {
+            paths: vec![".".to_string()],
+            staged: false,
+        }),
+    }
+}
+
+fn restore(args: &[String]) -> Option<Discard> {
+    let has = |flags: &[&str]| args.iter().any(|arg| flags.contains(&arg.as_str()));
+    let staged = has(&["-S", "--staged"]);
+    // `--staged` alone only unstages; the working tree is untouched.
+    if staged && !has(&["-W", "--worktree"]) {
+        return None;
+    }
+    let mut paths = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == "-s" || arg == "--source" {
+            iter.next();
+        } else if !arg.starts_with('-') {
+            paths.push(arg.clone());
+        }
+    }
+    Some(Discard::Changes { paths, staged })
+}
+
+fn switch(args: &[String]) -> Option<Discard> {
+    args.iter()
+        .any(|arg| matches!(arg.as_str(), "-f" | "--force" | "--discard-changes"))
+        .then(|| Discard::Changes {
+            paths: Vec::new(),
+            staged: true,
+        })
+}
+
+/// Asks git what `command` would discard, or `None` when nothing or git
+/// cannot tell.
+async fn compute_loss(command: DestructiveGit) -> Option<Loss> {
+    let files: Vec<String> = match &command.discard {
+        Discard::Changes { paths, staged } => {
+            let mut args = vec!["diff", "--name-only"];
+            if *staged {
+                args.push("HEAD");
+            }
+            args.push("--");
+            args.extend(paths.iter().map(String::as_str));
+            run_git(&args, &command.cwd)
+                .await
+                .ok()?
+                .lines()
+                .map(str::to_string)
+                .collect()
+        }
+        Discard::Untracked { clean_args, .. } => {
+            let mut args = vec!["clean"];
+            args.extend(clean_args.iter().map(String::as_str));
+            args.push("--dry-run");
+            run_git(&args, &command.cwd)
+                .await
+                .ok()?
+                .lines()
+                .filter_map(|line| line.strip_prefix("Would remove "))
+                .map(str::to_string)
+                .collect()
+        }
+    };
+    (!files.is_empty()).then_some(Loss { command, files })
+}
+
+async fn run_git(args: &[&str], cwd: &Path) -> Result<String, String> {
+    let output = run_git_command_with_timeout(args, cwd)
+        .await
+        .ok_or_else(|| format!("git {} did not finish", args.join(" ")))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn argv(words: &[&str]) -> Vec<String> {
+        words.iter().map(ToString::to_string).collect()
+    }
+
+    fn changes(paths: &[&str], staged: bool) -> Discard {
+        Discard::Changes {
+            paths: argv(paths),
+            staged,
+        }
+    }
+
+    fn discards(command: &[&str]) -> Vec<Discard> {
+        find_destructive_git(&argv(command), Path::new("/repo"))
+            .into_iter()
+            .map(|command| command.discard)
+            .collect()
+    }
+
+    #[test]
+    fn destructive_git_commands_are_recognized() {
+        assert_eq!(
+            discards(&["git", "reset", "--hard", "HEAD~1"]),
+            vec![changes(&[], true)]
+        );
+        assert_eq!(
+            discards(&["bash", "-lc", "git checkout -- . && git clean -fdx"]),
+            vec![
+                changes(&["."], false),
+                Discard::Untracked {
+                    clean_args: argv(&["-fdx"]),
+                    ignored: true,
+                },
+            ]
+        );
+        assert_eq!(
+            discards(&["git", "restore", "--source", "main", "src/lib.rs"]),
+            vec![changes(&["src/lib.rs"], false)]
+        );
+        assert_eq!(
+            find_destructive_git(
+                &argv(&["git", "-C", "app", "switch", "--discard-changes", "main"]),
+                Path::new("/repo")
+            ),
+            vec![DestructiveGit {
+                cwd: PathBuf::from("/repo/app"),
+                discard: changes(&[], true),
+            }]
+        );
+
+        assert_eq!(discards(&["git", "reset", "--soft", "HEAD~1"]), vec![]);
+        assert_eq!(discards(&["git", "clean", "-ndx"]), vec![]);
+        assert_eq!(discards(&["git", "restore", "--staged", "."]), vec![]);
+        assert_eq!(discards(&["git", "checkout", "main"]), vec![]);
+    }
+
+    #[tokio::test]
+    async fn approval_lists_what_would_be_lost_and_stash_keeps_it() {
+        let repo = tempfile::tempdir().expect("tempdir");
+        let cwd = repo.path();
+        let git = |args: &[&str]| {
+            let status = std::process::Command::new("git")
+                .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+                .args(args)
+                .current_dir(cwd)
+                .status()
+                .expect("git");
+            assert!(status.success(), "git {args:?}");
+        };
+        git(&["init", "-q"]);
+        std::fs::write(cwd.join("tracked.txt"), "one\n").expect("write");
+        git(&["add", "tracked.txt"]);
+        git(&["commit", "-qm", "init"]);
+        std::fs::write(cwd.join("tracked.txt"), "two\n").expect("write");
+
+        let command = argv(&["git", "reset", "--hard"]);
+        let skip = ApprovalRequirement::Skip {
+            bypass_sandbox: false,
+        };
+        let outcome = git_guard_requirement(
+            &GitGuard { auto_stash: true },
+            &command,
+            cwd,
+            AskForApproval::OnRequest,
+            &skip,
+        )
+        .await
+        .expect("outcome");
+        assert_eq!(
+            outcome.requirement,
+            ApprovalRequirement::NeedsApproval {
+                reason: Some(
+                    "Warning: this command discards uncommitted changes to 1 file \
+                     (`tracked.txt`). They will be stashed first; `git stash pop` brings them \
+                     back."
+                        .to_string()
+                ),
+            }
+        );
+
+        for plan in &outcome.stash {
+            plan.stash().await.expect("stash");
+        }
+        git(&["reset", "-q", "--hard"]);
+        git(&["stash", "pop", "-q"]);
+        assert_eq!(
+            std::fs::read_to_string(cwd.join("tracked.txt")).expect("read"),
+            "two\n"
+        );
+
+        let clean = argv(&["git", "clean", "-f"]);
+        assert_eq!(
+            git_guard_requirement(
+                &GitGuard::default(),
+                &clean,
+                cwd,
+                AskForApproval::OnRequest,
+                &skip
+            )
+            .await,
+            None
+        );
+    }
+}
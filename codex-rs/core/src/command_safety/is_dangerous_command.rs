@@ -4,6 +4,9 @@ use codex_protocol::protocol::SandboxPolicy;
 use crate::sandboxing::SandboxPermissions;
 
 use crate::bash::parse_shell_lc_plain_commands;
+use crate::bash::try_parse_shell;
+use crate::bash::try_parse_word_only_commands_sequence;
+use crate::inline_script::shell_scripts_run;
 use crate::is_safe_command::is_known_safe_command;
 #[cfg(windows)]
 #[path = "windows_dangerous_commands.rs"]
@@ -59,6 +62,20 @@ pub fn command_might_be_dangerous(command: &[String]) -> bool {
         return true;
     }
 
+    // Scripts fed to a shell, like the body of `bash <<EOF`, are checked the
+    // same way.
+    if shell_scripts_run(command).iter().any(|script| {
+        try_parse_shell(script)
+            .and_then(|tree| try_parse_word_only_commands_sequence(&tree, script))
+            .is_some_and(|commands| {
+                commands
+                    .iter()
+                    .any(|cmd| is_dangerous_to_call_with_exec(cmd))
+            })
+    }) {
+        return true;
+    }
+
     false
 }
 
@@ -111,6 +128,20 @@ mod tests {
         ])));
     }
 
+    #[test]
+    fn heredoc_fed_to_a_shell_is_checked() {
+        assert!(command_might_be_dangerous(&vec_str(&[
+            "bash",
+            "-lc",
+            "sh <<'EOF'\ngit reset --hard\nEOF"
+        ])));
+        assert!(!command_might_be_dangerous(&vec_str(&[
+            "bash",
+            "-lc",
+            "cat > notes.txt <<'EOF'\ngit reset --hard\nEOF"
+        ])));
+    }
+
     #[test]
     fn git_status_is_not_dangerous() {
         assert!(!command_might_be_dangerous(&vec_str(&["git", "status"])));
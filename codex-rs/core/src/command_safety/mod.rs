@@ -1,3 +1,37 @@
+use crate::bash::parse_shell_lc_plain_commands;
+use crate::inline_script::shell_scripts_run;
+
+pub(crate) mod destructive_git;
 pub mod is_dangerous_command;
 pub mod is_safe_command;
+pub(crate) mod privilege_escalation;
 pub mod windows_safe_commands;
+
+/// The commands `command` runs, including those in a `bash -lc` script or a
+/// script fed to a shell. A script too complex to parse exactly is split
+/// roughly at its control operators, so its commands may start with shell
+/// keywords such as `then` or `do`.
+pub(crate) fn commands_run(command: &[String]) -> Vec<Vec<String>> {
+    if let Some(commands) = parse_shell_lc_plain_commands(command) {
+        return commands;
+    }
+    let scripts = shell_scripts_run(command);
+    if scripts.is_empty() {
+        return vec![command.to_vec()];
+    }
+    scripts
+        .iter()
+        .flat_map(|script| rough_commands(script))
+        .collect()
+}
+
+fn rough_commands(script: &str) -> Vec<Vec<String>> {
+    script
+        .split([';', '&', '|', '(', ')', '`', '\n'])
+        .map(|segment| {
+            shlex::split(segment)
+                .unwrap_or_else(|| segment.split_whitespace().map(str::to_string).collect())
+        })
+        .filter(|words| !words.is_empty())
+        .collect()
+}
@@ -0,0 +1,392 @@
+//! Commands that run other commands with elevated privileges through `sudo`,
+//! `doas` or `runas`, and the `privilege_escalation` policy applied to them.
+
+use std::path::Path;
+
+use codex_protocol::protocol::AskForApproval;
+
+use crate::command_safety::commands_run;
+use crate::config::types::PrivilegeEscalation;
+use crate::config::types::PrivilegeEscalationMode;
+use crate::tools::sandboxing::ApprovalRequirement;
+
+/// `sudo` options that take a value, as in `sudo -u postgres psql`.
+const SUDO_VALUE_FLAGS: &[char] = &['C', 'D', 'g', 'h', 'p', 'r', 't', 'T', 'U', 'u'];
+const SUDO_VALUE_OPTIONS: &[&str] = &[
+    "chdir",
+    "close-from",
+    "command-timeout",
+    "group",
+    "host",
+    "other-user",
+    "prompt",
+    "role",
+    "type",
+    "user",
+];
+const DOAS_VALUE_FLAGS: &[char] = &['C', 'u'];
+const ENV_VALUE_FLAGS: &[char] = &['C', 'S', 'u'];
+const ENV_VALUE_OPTIONS: &[&str] = &["chdir", "split-string", "unset"];
+const XARGS_VALUE_FLAGS: &[char] = &['a', 'd', 'E', 'I', 'L', 'n', 'P', 's'];
+const XARGS_VALUE_OPTIONS: &[&str] = &[
+    "arg-file",
+    "delimiter",
+    "eof",
+    "max-args",
+    "max-chars",
+    "max-lines",
+    "max-procs",
+    "process-slot-var",
+];
+/// Shell words that can precede a command in a roughly split script; see
+/// [`commands_run`].
+const LEADING_KEYWORDS: &[&str] = &[
+    "!", "{", "do", "elif", "else", "exec", "if", "nohup", "then", "time", "until", "while",
+];
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct Escalation {
+    /// `sudo`, `doas` or `runas`.
+    pub(crate) program: &'static str,
+    /// The command run with elevated privileges; empty for an interactive
+    /// shell such as `sudo -i`.
+    pub(crate) target: Vec<String>,
+}
+
+impl Escalation {
+    pub(crate) fn describe(&self) -> String {
+        let program = self.program;
+        if self.target.is_empty() {
+            return format!("opens a privileged shell through {program}");
+        }
+        let target = shlex::try_join(self.target.iter().map(String::as_str))
+            .unwrap_or_else(|_| self.target.join(" "));
+        format!("runs `{target}` with elevated privileges through {program}")
+    }
+}
+
+/// The privilege escalations in `command`, including those inside a
+/// `bash -lc` script or a script fed to a shell.
+pub(crate) fn find_escalations(command: &[String]) -> Vec<Escalation> {
+    commands_run(command)
+        .iter()
+        .filter_map(|command| escalation(command))
+        .collect()
+}
+
+/// The requirement `privilege_escalation` imposes on `command`, or `None`
+/// when the command does not escalate privileges, every escalation targets an
+/// allowlisted command, or `requirement` already forbids it.
+pub(crate) fn privilege_escalation_requirement(
+    policy: &PrivilegeEscalation,
+    command: &[String],
+    approval_policy: AskForApproval,
+    requirement: &ApprovalRequirement,
+) -> Option<ApprovalRequirement> {
+    if matches!(requirement, ApprovalRequirement::Forbidden { .. }) {
+        return None;
+    }
+    let escalation = find_escalations(command)
+        .into_iter()
+        .find(|escalation| !is_allowed(&escalation.target, &policy.allow))?;
+    let description = escalation.describe();
+    Some(match policy.mode {
+        PrivilegeEscalationMode::Block => ApprovalRequirement::Forbidden {
+            reason: format!("{description}; blocked by the privilege_escalation policy"),
+        },
+        PrivilegeEscalationMode::Ask if approval_policy == AskForApproval::Never => {
+            ApprovalRequirement::Forbidden {
+                reason: format!("{description}; rejected by user approval settings"),
+            }
+        }
+        PrivilegeEscalationMode::Ask => ApprovalRequirement::NeedsApproval {
+            reason: Some(format!(
+                "Warning: this command {description}. Approve only if you would run it yourself."
+            )),
+        },
+    })
+}
+
+/// The password prompt `output` stops at, such as sudo's
+/// `[sudo] password for alice:`, when a command is waiting for one.
+pub(crate) fn trailing_password_prompt(output: &str) -> Option<&str> {
+    // A prompt leaves the cursor on its line, so it follows the last newline.
+    let line = output.rsplit('\n').next()?.trim();
+    (line.ends_with(':') && line.to_ascii_lowercase().contains("password")).then_some(line)
+}
+
+/// A note for the model when `output` ends at a password prompt.
+pub(crate) fn password_prompt_note(output: &str) -> Option<String> {
+    trailing_password_prompt(output).map(|prompt| {
+        format!(
+            "\n[The command is waiting for a password at `{prompt}`. Do not guess it or ask the \
+             user for it: send Ctrl-C (\\u0003) to stop the command and ask the user to run it \
+             in their own terminal.]"
+        )
+    })
+}
+
+fn escalation(command: &[String]) -> Option<Escalation> {
+    let mut command = command;
+    loop {
+        // Skip environment assignments such as `DEBIAN_FRONTEND=noninteractive`.
+        let start = command
+            .iter()
+            .position(|word| !word.contains('=') && !LEADING_KEYWORDS.contains(&word.as_str()))?;
+        let (program, args) = command[start..].split_first()?;
+        let name = Path::new(program)
+            .file_name()?
+            .to_str()?
+            .to_ascii_lowercase();
+        // Wrappers such as `env` and `timeout` run the command in their
+        // operands, which may escalate in turn.
+        command = match name.trim_end_matches(".exe") {
+            "env" => operands(args, ENV_VALUE_FLAGS, ENV_VALUE_OPTIONS),
+            "nice" => operands(args, &['n'], &["adjustment"]),
+            // `timeout 5 sudo reboot` runs the operands after the duration.
+            "timeout" => operands(args, &['k', 's'], &["kill-after", "signal"]).get(1..)?,
+            "xargs" => operands(args, XARGS_VALUE_FLAGS, XARGS_VALUE_OPTIONS),
+            // `command -v sudo` only looks the program up.
+            "command"
+                if args
+                    .iter()
+                    .take_while(|arg| arg.starts_with('-'))
+                    .any(|arg| arg == "-v" || arg == "-V") =>
+            {
+                return None;
+            }
+            "command" => operands(args, &[], &[]),
+            name => return escalation_through(name, args),
+        };
+    }
+}
+
+fn escalation_through(name: &str, args: &[String]) -> Option<Escalation> {
+    match name {
+        "sudo" => Some(Escalation {
+            program: "sudo",
+            target: operands(args, SUDO_VALUE_FLAGS, SUDO_VALUE_OPTIONS).to_vec(),
+        }),
+        "doas" => Some(Escalation {
+            program: "doas",
+            target: operands(args, DOAS_VALUE_FLAGS, &[]).to_vec(),
+        }),
+        // `runas /user:Administrator "net stop spooler"` takes the command as
+        // one argument.
+        "runas" => Some(Escalation {
+            program: "runas",
+            target: args
+                .iter()
+                .find(|arg| !arg.starts_with('/'))
+                .map(|target| {
+                    shlex::split(target)
+                        .unwrap_or_else(|| target.split_whitespace().map(str::to_string).collect())
+                })
+                .unwrap_or_default(),
+        }),
+        _ => None,
+    }
+}
+
+/// The arguments after the options of a `sudo`-like command.
+fn operands<'a>(args: &'a [String], value_flags: &[char], value_options: &[&str]) -> &'a [String] {
+    let mut i = 0;
+    while let Some(arg) = args.get(i) {
+        if arg == "--" {
+            return &args[i + 1..];
+        }
+        i += 1;
+        if let Some(option) = arg.strip_prefix("--") {
+            if value_options.contains(&option) {
+                i += 1;
+            }
+        } else if let Some(flags) = arg.strip_prefix('-') {
+            // In `-Eu root` the value follows the last flag; `-uroot` holds it.
+            if flags
+                .chars()
+                .position(|flag| value_flags.contains(&flag))
+                .is_some_and(|position| position == flags.chars().count() - 1)
+            {
+                i += 1;
+            }
+        } else {
+            return &args[i - 1..];
+        }
+    }
+    &[]
+}
+
+/// Whether `target` starts with one of the `allow` prefixes. A prefix's
+/// first word also matches the program by file name, so `apt-get` allows
+/// `/usr/bin/apt-get`.
+fn is_allowed(target: &[String], allow: &[String]) -> bool {
+    let Some((program, args)) = target.split_first() else {
+        return false;
+    };
+    let name = Path::new(program)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or(program);
+    allow.iter().any(|prefix| {
+        let words: Vec<&str> = prefix.split_whitespace().collect();
+        let Some((first, rest)) = words.split_first() else {
+            return false;
+        };
+        (first == program || *first == name)
+            && args.len() >= rest.len()
+            && rest.iter().zip(args).all(|(word, arg)| word == arg)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn argv(words: &[&str]) -> Vec<String> {
+        words.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn escalations_are_found_with_their_targets() {
+        assert_eq!(
+            find_escalations(&argv(&[
+                "sudo", "-Eu", "postgres", "psql", "-c", "select 1"
+            ])),
+            vec![Escalation {
+                program: "sudo",
+                target: argv(&["psql", "-c", "select 1"]),
+            }]
+        );
+        assert_eq!(
+            find_escalations(&argv(&[
+                "bash",
+                "-lc",
+                "cd app && doas -u root make install"
+            ])),
+            vec![Escalation {
+                program: "doas",
+                target: argv(&["make", "install"]),
+            }]
+        );
+        assert_eq!(
+            find_escalations(&argv(&["bash", "-lc", "for p in a b; do sudo -i; done"])),
+            vec![Escalation {
+                program: "sudo",
+                target: Vec::new(),
+            }]
+        );
+        assert_eq!(
+            find_escalations(&argv(&["runas", "/user:Administrator", "net stop spooler"])),
+            vec![Escalation {
+                program: "runas",
+                target: argv(&["net", "stop", "spooler"]),
+            }]
+        );
+        assert_eq!(find_escalations(&argv(&["cat", "sudoers"])), vec![]);
+    }
+
+    #[test]
+    fn escalations_are_found_through_wrapper_commands() {
+        let cases: &[(&[&str], &[&str])] = &[
+            (&["env", "-u", "HOME", "FOO=1", "sudo", "ls"], &["ls"]),
+            (
+                &["nice", "-n", "10", "sudo", "make", "install"],
+                &["make", "install"],
+            ),
+            (&["timeout", "5", "sudo", "reboot"], &["reboot"]),
+            (
+                &["timeout", "-s", "KILL", "5", "sudo", "reboot"],
+                &["reboot"],
+            ),
+            (&["xargs", "-n", "1", "sudo", "rm"], &["rm"]),
+            (&["command", "sudo", "id"], &["id"]),
+            (
+                &[
+                    "env", "nice", "timeout", "--signal", "TERM", "1m", "sudo", "id",
+                ],
+                &["id"],
+            ),
+        ];
+        for (command, target) in cases {
+            assert_eq!(
+                find_escalations(&argv(command)),
+                vec![Escalation {
+                    program: "sudo",
+                    target: argv(target),
+                }],
+                "{command:?}"
+            );
+        }
+        assert_eq!(find_escalations(&argv(&["command", "-v", "sudo"])), vec![]);
+        assert_eq!(find_escalations(&argv(&["timeout", "sudo"])), vec![]);
+    }
+
+    #[test]
+    fn policy_blocks_asks_or_passes_allowlisted_targets_through() {
+        let command = argv(&["sudo", "apt-get", "install", "-y", "jq"]);
+        let skip = ApprovalRequirement::Skip {
+            bypass_sandbox: false,
+        };
+        let ask = PrivilegeEscalation {
+            mode: PrivilegeEscalationMode::Ask,
+            allow: Vec::new(),
+        };
+        assert_eq!(
+            privilege_escalation_requirement(&ask, &command, AskForApproval::OnRequest, &skip),
+            Some(ApprovalRequirement::NeedsApproval {
+                reason: Some(
+                    "Warning: this command runs `apt-get install -y jq` with elevated privileges \
+                     through sudo. Approve only if you would run it yourself."
+                        .to_string()
+                ),
+            })
+        );
+        assert_eq!(
+            privilege_escalation_requirement(&ask, &command, AskForApproval::Never, &skip),
+            Some(ApprovalRequirement::Forbidden {
+                reason: "runs `apt-get install -y jq` with elevated privileges through sudo; \
+                         rejected by user approval settings"
+                    .to_string(),
+            })
+        );
+
+        let block = PrivilegeEscalation {
+            mode: PrivilegeEscalationMode::Block,
+            allow: vec!["apt-get install".to_string()],
+        };
+        assert_eq!(
+            privilege_escalation_requirement(&block, &command, AskForApproval::OnRequest, &skip),
+            None
+        );
+        assert_eq!(
+            privilege_escalation_requirement(
+                &block,
+                &argv(&["sudo", "/usr/bin/apt-get", "remove", "jq"]),
+                AskForApproval::OnRequest,
+                &skip
+            ),
+            Some(ApprovalRequirement::Forbidden {
+                reason: "runs `/usr/bin/apt-get remove jq` with elevated privileges through \
+                         sudo; blocked by the privilege_escalation policy"
+                    .to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn password_prompts_are_recognized_at_the_end_of_output() {
+        assert_eq!(
+            trailing_password_prompt("Reading lists...\n[sudo] password for alice: "),
+            Some("[sudo] password for alice:")
+        );
+        assert_eq!(
+            trailing_password_prompt("doas (alice@host) password:"),
+            Some("doas (alice@host) password:")
+        );
+        assert_eq!(
+            trailing_password_prompt("[sudo] password for alice: \nSorry, try again.\n"),
+            None
+        );
+    }
+}
@@ -1,18 +1,41 @@
+use crate::approval_rules::validate_approval_rules;
+use crate::artifacts::ARTIFACT_MAX_BYTES;
 use crate::auth::AuthCredentialsStoreMode;
+use crate::config::types::ApprovalCache;
+use crate::config::types::ApprovalRule;
+use crate::config::types::Audit;
+use crate::config::types::CommandEnvPolicy;
+use crate::config::types::CrashReports;
 use crate::config::types::DEFAULT_OTEL_ENVIRONMENT;
+use crate::config::types::GitGuard;
 use crate::config::types::History;
+use crate::config::types::Logging;
 use crate::config::types::McpServerConfig;
 use crate::config::types::Notice;
 use crate::config::types::Notifications;
 use crate::config::types::OtelConfig;
 use crate::config::types::OtelConfigToml;
 use crate::config::types::OtelExporterKind;
+use crate::config::types::OutputTruncation;
+use crate::config::types::PrivilegeEscalation;
+use crate::config::types::ProjectCache;
 use crate::config::types::ReasoningSummaryFormat;
+use crate::config::types::RiskAssessment;
+use crate::config::types::SandboxBackend;
+use crate::config::types::SandboxContainer;
+use crate::config::types::SandboxDenials;
+use crate::config::types::SandboxLimits;
+use crate::config::types::SandboxProfile;
+use crate::config::types::SandboxProfileRule;
 use crate::config::types::SandboxWorkspaceWrite;
+use crate::config::types::SelfUpdate;
+use crate::config::types::SensitiveReadPolicy;
 use crate::config::types::ShellEnvironmentPolicy;
 use crate::config::types::ShellEnvironmentPolicyToml;
+use crate::config::types::ShellProfile;
 use crate::config::types::Tui;
 use crate::config::types::UriBasedFileOpener;
+use crate::config::types::WebFetch;
 use crate::config_loader::LoadedConfigLayers;
 use crate::config_loader::load_config_as_toml;
 use crate::config_loader::load_config_layers_with_overrides;
@@ -34,6 +57,9 @@ use crate::project_doc::DEFAULT_PROJECT_DOC_FILENAME;
 use crate::project_doc::LOCAL_PROJECT_DOC_FILENAME;
 use crate::protocol::AskForApproval;
 use crate::protocol::SandboxPolicy;
+use crate::sandbox_profiles::validate_sandbox_profile_rules;
+use crate::secret_redaction::validate_redact_patterns;
+use crate::tools::runtimes::read_file::READ_FILE_MAX_BYTES;
 use codex_app_server_protocol::Tools;
 use codex_app_server_protocol::UserSavedConfig;
 use codex_protocol::config_types::ForcedLoginMethod;
@@ -195,6 +221,91 @@ pub struct Config {
     /// Token budget applied when storing tool/function outputs in the context manager.
     pub tool_output_token_limit: Option<usize>,
 
+    /// Maximum size of a file exchanged through the artifact channel, in either
+    /// direction.
+    pub artifact_max_bytes: u64,
+
+    /// Most bytes the `read_file` tool returns for one call.
+    pub read_file_max_bytes: usize,
+
+    /// Unix socket on which other clients can attach to the session as
+    /// observers or approvers.
+    pub collab_socket: Option<PathBuf>,
+
+    /// Unix socket serving a JSON-RPC API through which editor plugins and
+    /// scripts can inspect and drive the session.
+    pub control_socket: Option<PathBuf>,
+
+    /// How file contents with secrets or customer data are treated before
+    /// they reach the model.
+    pub sensitive_reads: SensitiveReadPolicy,
+
+    /// How commands run through `sudo`, `doas` or `runas` are approved.
+    pub privilege_escalation: PrivilegeEscalation,
+
+    /// How commands that discard uncommitted work are approved.
+    pub git_guard: GitGuard,
+
+    /// Rules that approve, refuse or ask about matching commands before the
+    /// approval policy is consulted.
+    pub approval_rules: Vec<ApprovalRule>,
+
+    /// Whether and for how long approvals outlive the session.
+    pub approval_cache: ApprovalCache,
+
+    /// Whether commands and patches are recorded in the session's audit log.
+    pub audit: Audit,
+
+    /// The cache directory commands in a project share across sessions.
+    pub project_cache: ProjectCache,
+
+    /// CPU, memory, process and file size limits on sandboxed commands.
+    pub sandbox_limits: SandboxLimits,
+
+    /// Directories in which writes the sandbox refused are granted without
+    /// asking.
+    pub sandbox_denials: SandboxDenials,
+
+    /// Whether `shell` commands are sandboxed by the platform or in a
+    /// container.
+    pub sandbox_backend: SandboxBackend,
+
+    /// The container `shell` commands run in with the container backend.
+    pub sandbox_container: SandboxContainer,
+
+    /// Named sandbox settings that replace the session's for some tool calls.
+    pub sandbox_profiles: HashMap<String, SandboxProfile>,
+
+    /// Which tool calls get which of `sandbox_profiles`; the first matching
+    /// rule decides.
+    pub sandbox_profile_rules: Vec<SandboxProfileRule>,
+
+    /// Static risk assessment of commands and the level refused outright.
+    pub risk_assessment: RiskAssessment,
+
+    /// Whether a panic writes a crash report, and how many events it keeps.
+    pub crash_reports: CrashReports,
+
+    /// Format, levels and rotation of the log file.
+    pub logging: Logging,
+
+    /// Release channel, manifest and signing key of `codex self-update`.
+    pub self_update: SelfUpdate,
+
+    /// How long command output is cut down and where the rest is kept.
+    pub output_truncation: OutputTruncation,
+
+    /// Allowed domains, size limit and caching of the `web_fetch` tool.
+    pub web_fetch: WebFetch,
+
+    /// Whether commands take the environment of a fish or nushell login
+    /// shell, and whether they run in it.
+    pub shell_profile: ShellProfile,
+
+    /// Variables every command is denied or limited to, and the secrets
+    /// redacted from command output.
+    pub command_env: CommandEnvPolicy,
+
     /// Directory containing all Codex state (defaults to `~/.codex` but can be
     /// overridden by the `CODEX_HOME` environment variable).
     pub codex_home: PathBuf,
@@ -636,6 +747,84 @@ pub struct ConfigToml {
     /// Token budget applied when storing tool/function outputs in the context manager.
     pub tool_output_token_limit: Option<usize>,
 
+    /// Maximum size of a file exchanged through the artifact channel.
+    pub artifact_max_bytes: Option<u64>,
+
+    /// Most bytes the `read_file` tool returns for one call.
+    pub read_file_max_bytes: Option<usize>,
+
+    /// Unix socket on which other clients can attach to the session.
+    pub collab_socket: Option<PathBuf>,
+
+    /// Unix socket serving the JSON-RPC control API.
+    pub control_socket: Option<PathBuf>,
+
+    /// Screening of file contents read by tools.
+    pub sensitive_reads: Option<SensitiveReadPolicy>,
+
+    /// Policy for commands that escalate privileges.
+    pub privilege_escalation: Option<PrivilegeEscalation>,
+
+    /// Guard for git commands that discard uncommitted work.
+    pub git_guard: Option<GitGuard>,
+
+    /// Rules deciding approval for matching commands, first match wins.
+    pub approval_rules: Option<Vec<ApprovalRule>>,
+
+    /// Remember approvals across sessions.
+    pub approval_cache: Option<ApprovalCache>,
+
+    /// Audit log of commands and patches.
+    pub audit: Option<Audit>,
+
+    /// Per-project cache directory for commands.
+    pub project_cache: Option<ProjectCache>,
+
+    /// Resource limits on sandboxed commands.
+    pub sandbox_limits: Option<SandboxLimits>,
+
+    /// What happens when the sandbox refuses a command.
+    pub sandbox_denials: Option<SandboxDenials>,
+
+    /// `platform` (default) or `container`.
+    pub sandbox_backend: Option<SandboxBackend>,
+
+    /// Runtime, image and network of the sandbox container.
+    pub sandbox_container: Option<SandboxContainer>,
+
+    /// Named sandbox settings, keyed by name.
+    #[serde(default)]
+    pub sandbox_profiles: HashMap<String, SandboxProfile>,
+
+    /// Rules assigning `sandbox_profiles` to tool calls.
+    pub sandbox_profile_rules: Option<Vec<SandboxProfileRule>>,
+
+    /// Level at which commands are refused without asking.
+    pub risk_assessment: Option<RiskAssessment>,
+
+    /// Opt-in crash reports.
+    pub crash_reports: Option<CrashReports>,
+
+    /// Format, levels and rotation of the log file.
+    pub logging: Option<Logging>,
+
+    /// Where `codex self-update` gets releases from.
+    pub self_update: Option<SelfUpdate>,
+
+    /// Head and tail windows for long command output.
+    pub output_truncation: Option<OutputTruncation>,
+
+    /// Settings for the `web_fetch` tool.
+    pub web_fetch: Option<WebFetch>,
+
+    /// Environment import from, and commands in, a fish or nushell login
+    /// shell.
+    pub shell_profile: Option<ShellProfile>,
+
+    /// Environment rules for every command and redaction of secrets from
+    /// their output.
+    pub command_env: Option<CommandEnvPolicy>,
+
     /// Profile to use from the `profiles` map.
     pub profile: Option<String>,
 
@@ -739,6 +928,8 @@ impl From<ConfigToml> for UserSavedConfig {
 #[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct ProjectConfig {
     pub trust_level: Option<TrustLevel>,
+    /// Overrides the `trash` feature for this project.
+    pub trash: Option<bool>,
 }
 
 impl ProjectConfig {
@@ -979,10 +1170,16 @@ impl Config {
             experimental_sandbox_command_assessment: sandbox_command_assessment_override,
         };
 
-        let features = Features::from_config(&cfg, &config_profile, feature_overrides);
+        let mut features = Features::from_config(&cfg, &config_profile, feature_overrides);
         #[cfg(target_os = "windows")]
         {
-            crate::safety::set_windows_sandbox_enabled(features.enabled(Feature::WindowsSandbox));
+            // Fall back to the unsandboxed behavior when the sandbox cannot
+            // be set up here.
+            let enabled = features.enabled(Feature::WindowsSandbox)
+                && crate::safety::probe_windows_sandbox(&codex_home)
+                    .token_error
+                    .is_none();
+            crate::safety::set_windows_sandbox_enabled(enabled);
         }
 
         let resolved_cwd = {
@@ -1019,7 +1216,19 @@ impl Config {
             .collect();
         let active_project = cfg
             .get_active_project(&resolved_cwd)
-            .unwrap_or(ProjectConfig { trust_level: None });
+            .unwrap_or(ProjectConfig {
+                trust_level: None,
+                trash: None,
+            });
+        match active_project.trash {
+            Some(true) => {
+                features.enable(Feature::Trash);
+            }
+            Some(false) => {
+                features.disable(Feature::Trash);
+            }
+            None => {}
+        }
 
         let SandboxPolicyResolution {
             policy: mut sandbox_policy,
@@ -1054,6 +1263,16 @@ impl Config {
             || config_profile.sandbox_mode.is_some()
             || cfg.sandbox_mode.is_some();
 
+        let approval_rules = cfg.approval_rules.unwrap_or_default();
+        validate_approval_rules(&approval_rules)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+        let sandbox_profile_rules = cfg.sandbox_profile_rules.unwrap_or_default();
+        validate_sandbox_profile_rules(&cfg.sandbox_profiles, &sandbox_profile_rules)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+        let command_env = cfg.command_env.unwrap_or_default();
+        validate_redact_patterns(&command_env)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+
         let mut model_providers = built_in_model_providers();
         // Merge user-defined providers into the built-in list.
         for (key, provider) in cfg.model_providers.into_iter() {
@@ -1204,6 +1423,31 @@ impl Config {
                 })
                 .collect(),
             tool_output_token_limit: cfg.tool_output_token_limit,
+            artifact_max_bytes: cfg.artifact_max_bytes.unwrap_or(ARTIFACT_MAX_BYTES),
+            read_file_max_bytes: cfg.read_file_max_bytes.unwrap_or(READ_FILE_MAX_BYTES),
+            collab_socket: cfg.collab_socket,
+            control_socket: cfg.control_socket,
+            sensitive_reads: cfg.sensitive_reads.unwrap_or_default(),
+            privilege_escalation: cfg.privilege_escalation.unwrap_or_default(),
+            git_guard: cfg.git_guard.unwrap_or_default(),
+            approval_rules,
+            approval_cache: cfg.approval_cache.unwrap_or_default(),
+            audit: cfg.audit.unwrap_or_default(),
+            project_cache: cfg.project_cache.unwrap_or_default(),
+            sandbox_limits: cfg.sandbox_limits.unwrap_or_default(),
+            sandbox_denials: cfg.sandbox_denials.unwrap_or_default(),
+            sandbox_backend: cfg.sandbox_backend.unwrap_or_default(),
+            sandbox_container: cfg.sandbox_container.unwrap_or_default(),
+            sandbox_profiles: cfg.sandbox_profiles,
+            sandbox_profile_rules,
+            risk_assessment: cfg.risk_assessment.unwrap_or_default(),
+            crash_reports: cfg.crash_reports.unwrap_or_default(),
+            logging: cfg.logging.unwrap_or_default(),
+            self_update: cfg.self_update.unwrap_or_default(),
+            output_truncation: cfg.output_truncation.unwrap_or_default(),
+            web_fetch: cfg.web_fetch.unwrap_or_default(),
+            shell_profile: cfg.shell_profile.unwrap_or_default(),
+            command_env,
             codex_home,
             history,
             file_opener: cfg.file_opener.unwrap_or(UriBasedFileOpener::VsCode),
@@ -2967,6 +3211,31 @@ model_verbosity = "high"
                 project_doc_max_bytes: PROJECT_DOC_MAX_BYTES,
                 project_doc_fallback_filenames: Vec::new(),
                 tool_output_token_limit: None,
+                artifact_max_bytes: ARTIFACT_MAX_BYTES,
+                read_file_max_bytes: READ_FILE_MAX_BYTES,
+                collab_socket: None,
+                control_socket: None,
+                sensitive_reads: SensitiveReadPolicy::Off,
+                privilege_escalation: PrivilegeEscalation::default(),
+                git_guard: GitGuard::default(),
+                approval_rules: Vec::new(),
+                approval_cache: ApprovalCache::default(),
+                audit: Audit::default(),
+                project_cache: ProjectCache::default(),
+                sandbox_limits: SandboxLimits::default(),
+                sandbox_denials: SandboxDenials::default(),
+                sandbox_backend: SandboxBackend::default(),
+                sandbox_container: SandboxContainer::default(),
+                sandbox_profiles: HashMap::new(),
+                sandbox_profile_rules: Vec::new(),
+                risk_assessment: RiskAssessment::default(),
+                crash_reports: CrashReports::default(),
+                logging: Logging::default(),
+                self_update: SelfUpdate::default(),
+                output_truncation: OutputTruncation::default(),
+                web_fetch: WebFetch::default(),
+                shell_profile: ShellProfile::default(),
+                command_env: CommandEnvPolicy::default(),
                 codex_home: fixture.codex_home(),
                 history: History::default(),
                 file_opener: UriBasedFileOpener::VsCode,
@@ -2989,7 +3258,10 @@ model_verbosity = "high"
                 use_experimental_use_rmcp_client: false,
                 features: Features::with_defaults(),
                 active_profile: Some("o3".to_string()),
-                active_project: ProjectConfig { trust_level: None },
+                active_project: ProjectConfig {
+                    trust_level: None,
+                    trash: None,
+                },
                 windows_wsl_setup_acknowledged: false,
                 notices: Default::default(),
                 disable_paste_burst: false,
@@ -3039,6 +3311,31 @@ model_verbosity = "high"
             project_doc_max_bytes: PROJECT_DOC_MAX_BYTES,
             project_doc_fallback_filenames: Vec::new(),
             tool_output_token_limit: None,
+            artifact_max_bytes: ARTIFACT_MAX_BYTES,
+            read_file_max_bytes: READ_FILE_MAX_BYTES,
+            collab_socket: None,
+            control_socket: None,
+            sensitive_reads: SensitiveReadPolicy::Off,
+            privilege_escalation: PrivilegeEscalation::default(),
+            git_guard: GitGuard::default(),
+            approval_rules: Vec::new(),
+            approval_cache: ApprovalCache::default(),
+            audit: Audit::default(),
+            project_cache: ProjectCache::default(),
+            sandbox_limits: SandboxLimits::default(),
+            sandbox_denials: SandboxDenials::default(),
+            sandbox_backend: SandboxBackend::default(),
+            sandbox_container: SandboxContainer::default(),
+            sandbox_profiles: HashMap::new(),
+            sandbox_profile_rules: Vec::new(),
+            risk_assessment: RiskAssessment::default(),
+            crash_reports: CrashReports::default(),
+            logging: Logging::default(),
+            self_update: SelfUpdate::default(),
+            output_truncation: OutputTruncation::default(),
+            web_fetch: WebFetch::default(),
+            shell_profile: ShellProfile::default(),
+            command_env: CommandEnvPolicy::default(),
             codex_home: fixture.codex_home(),
             history: History::default(),
             file_opener: UriBasedFileOpener::VsCode,
@@ -3061,7 +3358,10 @@ model_verbosity = "high"
             use_experimental_use_rmcp_client: false,
             features: Features::with_defaults(),
             active_profile: Some("gpt3".to_string()),
-            active_project: ProjectConfig { trust_level: None },
+            active_project: ProjectConfig {
+                trust_level: None,
+                trash: None,
+            },
             windows_wsl_setup_acknowledged: false,
             notices: Default::default(),
             disable_paste_burst: false,
@@ -3126,6 +3426,31 @@ model_verbosity = "high"
             project_doc_max_bytes: PROJECT_DOC_MAX_BYTES,
             project_doc_fallback_filenames: Vec::new(),
             tool_output_token_limit: None,
+            artifact_max_bytes: ARTIFACT_MAX_BYTES,
+            read_file_max_bytes: READ_FILE_MAX_BYTES,
+            collab_socket: None,
+            control_socket: None,
+            sensitive_reads: SensitiveReadPolicy::Off,
+            privilege_escalation: PrivilegeEscalation::default(),
+            git_guard: GitGuard::default(),
+            approval_rules: Vec::new(),
+            approval_cache: ApprovalCache::default(),
+            audit: Audit::default(),
+            project_cache: ProjectCache::default(),
+            sandbox_limits: SandboxLimits::default(),
+            sandbox_denials: SandboxDenials::default(),
+            sandbox_backend: SandboxBackend::default(),
+            sandbox_container: SandboxContainer::default(),
+            sandbox_profiles: HashMap::new(),
+            sandbox_profile_rules: Vec::new(),
+            risk_assessment: RiskAssessment::default(),
+            crash_reports: CrashReports::default(),
+            logging: Logging::default(),
+            self_update: SelfUpdate::default(),
+            output_truncation: OutputTruncation::default(),
+            web_fetch: WebFetch::default(),
+            shell_profile: ShellProfile::default(),
+            command_env: CommandEnvPolicy::default(),
             codex_home: fixture.codex_home(),
             history: History::default(),
             file_opener: UriBasedFileOpener::VsCode,
@@ -3148,7 +3473,10 @@ model_verbosity = "high"
             use_experimental_use_rmcp_client: false,
             features: Features::with_defaults(),
             active_profile: Some("zdr".to_string()),
-            active_project: ProjectConfig { trust_level: None },
+            active_project: ProjectConfig {
+                trust_level: None,
+                trash: None,
+            },
             windows_wsl_setup_acknowledged: false,
             notices: Default::default(),
             disable_paste_burst: false,
@@ -3199,6 +3527,31 @@ model_verbosity = "high"
             project_doc_max_bytes: PROJECT_DOC_MAX_BYTES,
             project_doc_fallback_filenames: Vec::new(),
             tool_output_token_limit: None,
+            artifact_max_bytes: ARTIFACT_MAX_BYTES,
+            read_file_max_bytes: READ_FILE_MAX_BYTES,
+            collab_socket: None,
+            control_socket: None,
+            sensitive_reads: SensitiveReadPolicy::Off,
+            privilege_escalation: PrivilegeEscalation::default(),
+            git_guard: GitGuard::default(),
+            approval_rules: Vec::new(),
+            approval_cache: ApprovalCache::default(),
+            audit: Audit::default(),
+            project_cache: ProjectCache::default(),
+            sandbox_limits: SandboxLimits::default(),
+            sandbox_denials: SandboxDenials::default(),
+            sandbox_backend: SandboxBackend::default(),
+            sandbox_container: SandboxContainer::default(),
+            sandbox_profiles: HashMap::new(),
+            sandbox_profile_rules: Vec::new(),
+            risk_assessment: RiskAssessment::default(),
+            crash_reports: CrashReports::default(),
+            logging: Logging::default(),
+            self_update: SelfUpdate::default(),
+            output_truncation: OutputTruncation::default(),
+            web_fetch: WebFetch::default(),
+            shell_profile: ShellProfile::default(),
+            command_env: CommandEnvPolicy::default(),
             codex_home: fixture.codex_home(),
             history: History::default(),
             file_opener: UriBasedFileOpener::VsCode,
@@ -3221,7 +3574,10 @@ model_verbosity = "high"
             use_experimental_use_rmcp_client: false,
             features: Features::with_defaults(),
             active_profile: Some("gpt5".to_string()),
-            active_project: ProjectConfig { trust_level: None },
+            active_project: ProjectConfig {
+                trust_level: None,
+                trash: None,
+            },
             windows_wsl_setup_acknowledged: false,
             notices: Default::default(),
             disable_paste_burst: false,
@@ -3504,6 +3860,7 @@ trust_level = "untrusted"
             test_path.to_string_lossy().to_string(),
             ProjectConfig {
                 trust_level: Some(TrustLevel::Untrusted),
+                trash: None,
             },
         );
 
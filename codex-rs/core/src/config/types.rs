@@ -3,6 +3,8 @@
 // Note this file should generally be restricted to simple struct/enum
 // definitions that do not contain business logic.
 
+use codex_protocol::protocol::ApprovalRuleDecision;
+use codex_protocol::protocol::SandboxRiskLevel;
 use serde::Deserializer;
 use std::collections::HashMap;
 use std::path::PathBuf;
@@ -271,6 +273,512 @@ pub enum HistoryPersistence {
     None,
 }
 
+/// What to do when a file read by a tool contains private keys, `.env`
+/// values, API tokens or customer data.
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum SensitiveReadPolicy {
+    /// Pass contents through unchanged.
+    #[default]
+    Off,
+    /// Replace the sensitive values with `<redacted>`.
+    Redact,
+    /// Ask the user whether the model may see the unredacted contents, and
+    /// redact them otherwise.
+    Ask,
+    /// Refuse the read.
+    Block,
+}
+
+/// How commands that run other commands with elevated privileges through
+/// `sudo`, `doas` or `runas` are treated.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct PrivilegeEscalation {
+    #[serde(default)]
+    pub mode: PrivilegeEscalationMode,
+    /// Command prefixes such as `apt-get install` that may run with elevated
+    /// privileges under the usual approval rules.
+    #[serde(default)]
+    pub allow: Vec<String>,
+}
+
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum PrivilegeEscalationMode {
+    /// Refuse the command.
+    Block,
+    /// Ask for approval with a warning, whatever the approval policy would
+    /// otherwise allow, and refuse the command under `never`.
+    #[default]
+    Ask,
+}
+
+/// How commands that discard uncommitted work, such as `git reset --hard`,
+/// are treated.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct GitGuard {
+    /// Stash the working tree before running such a command, so
+    /// `git stash pop` brings back what it discarded.
+    #[serde(default)]
+    pub auto_stash: bool,
+}
+
+/// Remembering "approve for session" decisions across sessions.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ApprovalCache {
+    /// Also record approved commands in `$CODEX_HOME/approvals.json`, so later
+    /// sessions in the same project replay them instead of asking.
+    #[serde(default)]
+    pub persist: bool,
+    /// Days a recorded approval is replayed before the user is asked again.
+    #[serde(default = "default_approval_ttl_days")]
+    pub ttl_days: u64,
+}
+
+const DEFAULT_APPROVAL_TTL_DAYS: u64 = 30;
+
+fn default_approval_ttl_days() -> u64 {
+    DEFAULT_APPROVAL_TTL_DAYS
+}
+
+impl Default for ApprovalCache {
+    fn default() -> Self {
+        Self {
+            persist: false,
+            ttl_days: DEFAULT_APPROVAL_TTL_DAYS,
+        }
+    }
+}
+
+/// The audit log of the commands and patches a session runs.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Audit {
+    /// Append an entry to `$CODEX_HOME/sessions/audit/<conversation id>.jsonl`
+    /// for every command and patch.
+    #[serde(default = "default_audit_enabled")]
+    pub enabled: bool,
+}
+
+fn default_audit_enabled() -> bool {
+    true
+}
+
+impl Default for Audit {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+/// The per-project cache directory that commands share across sessions.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ProjectCache {
+    /// Give commands a directory under `$CODEX_HOME/cache` in
+    /// `$CODEX_CACHE_DIR` that every session in the project shares.
+    #[serde(default)]
+    pub enabled: bool,
+    /// Once all project caches together take more megabytes than this, the
+    /// least recently used are deleted when a session starts.
+    #[serde(default = "default_project_cache_max_size_mb")]
+    pub max_size_mb: u64,
+}
+
+const DEFAULT_PROJECT_CACHE_MAX_SIZE_MB: u64 = 10 * 1024;
+
+fn default_project_cache_max_size_mb() -> u64 {
+    DEFAULT_PROJECT_CACHE_MAX_SIZE_MB
+}
+
+impl Default for ProjectCache {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_size_mb: DEFAULT_PROJECT_CACHE_MAX_SIZE_MB,
+        }
+    }
+}
+
+/// Resource limits on the commands that run in the sandbox, applied as
+/// rlimits on Unix. A limit that is not set is inherited from Codex.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SandboxLimits {
+    /// Seconds of CPU time each process may use before it gets `SIGXCPU`.
+    #[serde(default)]
+    pub cpu_secs: Option<u64>,
+    /// Megabytes of address space each process may map.
+    #[serde(default)]
+    pub memory_mb: Option<u64>,
+    /// Processes the user may have at once. The kernel counts all of the
+    /// user's processes against it, not only the command's.
+    #[serde(default)]
+    pub max_processes: Option<u64>,
+    /// Megabytes any one file the command writes may grow to.
+    #[serde(default)]
+    pub max_file_size_mb: Option<u64>,
+}
+
+/// What happens when the sandbox refuses a command.
+#[derive(Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct SandboxDenials {
+    /// Directories, absolute or under `~`, in which a refused write is
+    /// retried in the sandbox with the directory below the entry made
+    /// writable, without asking.
+    #[serde(default)]
+    pub auto_grant: Vec<PathBuf>,
+}
+
+/// What sandboxes the commands of the `shell` tool.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum SandboxBackend {
+    /// Seatbelt on macOS, landlock and seccomp on Linux, a restricted token
+    /// on Windows.
+    #[default]
+    Platform,
+    /// A container the session starts, configured by `sandbox_container`.
+    Container,
+}
+
+/// The container `sandbox_backend = "container"` runs commands in.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SandboxContainer {
+    #[serde(default)]
+    pub runtime: ContainerRuntime,
+    #[serde(default = "default_container_image")]
+    pub image: String,
+    /// Give the container a network even when the sandbox policy does not
+    /// allow network access.
+    #[serde(default)]
+    pub network_access: bool,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ContainerRuntime {
+    #[default]
+    Docker,
+    Podman,
+}
+
+impl ContainerRuntime {
+    pub fn program(self) -> &'static str {
+        match self {
+            ContainerRuntime::Docker => "docker",
+            ContainerRuntime::Podman => "podman",
+        }
+    }
+}
+
+const DEFAULT_CONTAINER_IMAGE: &str = "ubuntu:24.04";
+
+fn default_container_image() -> String {
+    DEFAULT_CONTAINER_IMAGE.to_string()
+}
+
+impl Default for SandboxContainer {
+    fn default() -> Self {
+        Self {
+            runtime: ContainerRuntime::default(),
+            image: default_container_image(),
+            network_access: false,
+        }
+    }
+}
+
+/// An entry in `sandbox_profiles`: sandbox settings that replace the
+/// session's for the tool calls `sandbox_profile_rules` assign it to.
+#[derive(Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct SandboxProfile {
+    /// What commands may write instead of the workspace, relative to the
+    /// session's working directory. An empty list makes them read-only.
+    #[serde(default)]
+    pub writable_roots: Option<Vec<PathBuf>>,
+    /// Replaces `sandbox_workspace_write.network_access`.
+    #[serde(default)]
+    pub network_access: Option<bool>,
+    /// Globs (`*`, `?`) over the names of variables to remove from the
+    /// command's environment.
+    #[serde(default)]
+    pub env_exclude: Vec<String>,
+    /// Variables to set in the command's environment.
+    #[serde(default)]
+    pub env_set: HashMap<String, String>,
+    /// Replaces `sandbox_limits`.
+    #[serde(default)]
+    pub limits: Option<SandboxLimits>,
+}
+
+/// An entry in `sandbox_profile_rules`. A tool call matches a rule when it
+/// meets every condition the rule sets, and the first matching rule picks
+/// the profile.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SandboxProfileRule {
+    /// Name of the profile in `sandbox_profiles`.
+    pub profile: String,
+    /// Name of the tool, such as `shell` or `apply_patch`.
+    #[serde(default)]
+    pub tool: Option<String>,
+    /// Glob (`*`, `?`) over the command line, which for a `bash -lc` command
+    /// is the script.
+    #[serde(default)]
+    pub command: Option<String>,
+    /// Regular expression searched for in the same command line.
+    #[serde(default)]
+    pub command_regex: Option<String>,
+}
+
+/// Settings of the static risk assessment of commands.
+#[derive(Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct RiskAssessment {
+    /// Commands assessed at this level or above are refused without asking,
+    /// whatever the approval policy. Commands an `approval_rules` entry
+    /// auto-approves still run.
+    #[serde(default)]
+    pub auto_deny: Option<SandboxRiskLevel>,
+}
+
+/// Writing a report when Codex crashes.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CrashReports {
+    /// Write a report to `$CODEX_HOME/crash_reports` when Codex panics.
+    #[serde(default)]
+    pub enabled: bool,
+    /// How many of the last events a report includes.
+    #[serde(default = "default_crash_report_events")]
+    pub max_events: usize,
+}
+
+const DEFAULT_CRASH_REPORT_EVENTS: usize = 50;
+
+fn default_crash_report_events() -> usize {
+    DEFAULT_CRASH_REPORT_EVENTS
+}
+
+impl Default for CrashReports {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_events: DEFAULT_CRASH_REPORT_EVENTS,
+        }
+    }
+}
+
+/// The log file: its format, its levels and when it is rotated.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Logging {
+    #[serde(default)]
+    pub format: LogFormat,
+    /// Levels per module, in the syntax of `RUST_LOG`, such as
+    /// `codex_core=info,codex_core::exec=debug`. `RUST_LOG` takes precedence.
+    #[serde(default)]
+    pub levels: Option<String>,
+    /// Size at which the log file is rotated.
+    #[serde(default = "default_log_max_file_size_mb")]
+    pub max_file_size_mb: u64,
+    /// How many rotated files are kept besides the current one.
+    #[serde(default = "default_log_max_files")]
+    pub max_files: usize,
+}
+
+const DEFAULT_LOG_MAX_FILE_SIZE_MB: u64 = 10;
+const DEFAULT_LOG_MAX_FILES: usize = 5;
+
+fn default_log_max_file_size_mb() -> u64 {
+    DEFAULT_LOG_MAX_FILE_SIZE_MB
+}
+
+fn default_log_max_files() -> usize {
+    DEFAULT_LOG_MAX_FILES
+}
+
+impl Default for Logging {
+    fn default() -> Self {
+        Self {
+            format: LogFormat::default(),
+            levels: None,
+            max_file_size_mb: DEFAULT_LOG_MAX_FILE_SIZE_MB,
+            max_files: DEFAULT_LOG_MAX_FILES,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// One JSON object per line.
+    #[default]
+    Json,
+    /// Plain text, as printed to a terminal.
+    Text,
+}
+
+/// Where `codex self-update` gets releases from.
+#[derive(Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct SelfUpdate {
+    #[serde(default)]
+    pub channel: UpdateChannel,
+    /// URL of the release manifest. Its signature is read from the same URL
+    /// with `.sig` appended.
+    #[serde(default)]
+    pub manifest_url: Option<String>,
+    /// Base64 Ed25519 public key the manifest must be signed with.
+    #[serde(default)]
+    pub public_key: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[serde(rename_all = "kebab-case")]
+pub enum UpdateChannel {
+    #[default]
+    Stable,
+    Beta,
+}
+
+impl std::fmt::Display for UpdateChannel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UpdateChannel::Stable => f.write_str("stable"),
+            UpdateChannel::Beta => f.write_str("beta"),
+        }
+    }
+}
+
+/// How long command output is cut down before it reaches the model.
+#[derive(Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct OutputTruncation {
+    /// KiB kept from the start of the output. With `tail_kib`, replaces the
+    /// even split of the model's output budget.
+    #[serde(default)]
+    pub head_kib: Option<usize>,
+    /// KiB kept from the end of the output.
+    #[serde(default)]
+    pub tail_kib: Option<usize>,
+    /// Save the full output of a command that was cut down to a file in the
+    /// session's artifacts, and tell the model where it is.
+    #[serde(default)]
+    pub spill: bool,
+}
+
+/// Settings for the `web_fetch` tool.
+#[derive(Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct WebFetch {
+    /// Hosts fetched without approval; `*.example.com` matches subdomains.
+    #[serde(default)]
+    pub allowed_domains: Vec<String>,
+    /// Most body bytes read per fetch.
+    #[serde(default)]
+    pub max_bytes: Option<usize>,
+    /// Seconds a fetched response is reused within the session; 0 disables
+    /// the cache.
+    #[serde(default)]
+    pub cache_ttl_secs: Option<u64>,
+}
+
+/// How commands work with a fish or nushell login shell, which Codex does
+/// not run commands in by default.
+#[derive(Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShellProfile {
+    /// Add the variables the login shell's profile sets, such as `PATH`, to
+    /// the environment of commands.
+    #[serde(default)]
+    pub import_env: bool,
+    /// Run commands in the login shell: the session's shell becomes fish or
+    /// nushell, and `shell` tool commands that do not start a shell
+    /// themselves run through it.
+    #[serde(default)]
+    pub run_commands: bool,
+    /// With `run_commands`, run a command whose name is an alias or
+    /// function of the profile in the form its approval showed: an alias
+    /// replaced by its expansion, a function passed over for the program.
+    #[serde(default)]
+    pub run_expanded: bool,
+}
+
+/// Rules for the environment of every command Codex spawns, on top of
+/// `shell_environment_policy`, and for keeping secrets out of their output.
+#[derive(Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommandEnvPolicy {
+    /// Globs (`*`, `?`, case-insensitive) of variables no command gets,
+    /// whichever tool built its environment.
+    #[serde(default)]
+    pub deny: Vec<String>,
+    /// When not empty, commands only get the variables matching one of
+    /// these globs.
+    #[serde(default)]
+    pub allow: Vec<String>,
+    /// Globs of variables whose values are secrets, in addition to those
+    /// whose names contain `KEY`, `TOKEN`, `SECRET`, `PASSWORD` or
+    /// `CREDENTIAL`. Their values are redacted from command output.
+    #[serde(default)]
+    pub secret_vars: Vec<String>,
+    /// Regular expressions whose matches are redacted from command output.
+    #[serde(default)]
+    pub redact_patterns: Vec<String>,
+    /// The locale and time zone commands run in.
+    #[serde(default)]
+    pub locale: CommandLocale,
+}
+
+/// `LANG`, `LC_ALL` and `TZ` for every command, so that the dates, sort
+/// order and messages of its output do not depend on the user's machine.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CommandLocale {
+    /// Keep the locale and time zone commands inherit instead.
+    #[serde(default)]
+    pub inherit: bool,
+    #[serde(default = "default_command_lang")]
+    pub lang: String,
+    #[serde(default = "default_command_lang")]
+    pub lc_all: String,
+    #[serde(default = "default_command_tz")]
+    pub tz: String,
+}
+
+const DEFAULT_COMMAND_LANG: &str = "C.UTF-8";
+const DEFAULT_COMMAND_TZ: &str = "UTC";
+
+fn default_command_lang() -> String {
+    DEFAULT_COMMAND_LANG.to_string()
+}
+
+fn default_command_tz() -> String {
+    DEFAULT_COMMAND_TZ.to_string()
+}
+
+impl Default for CommandLocale {
+    fn default() -> Self {
+        Self {
+            inherit: false,
+            lang: default_command_lang(),
+            lc_all: default_command_lang(),
+            tz: default_command_tz(),
+        }
+    }
+}
+
+/// An entry in `approval_rules`. A command matches a rule when it meets
+/// every condition the rule sets, and the first matching rule decides.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ApprovalRule {
+    /// Shown when the rule fires; defaults to the rule's position.
+    #[serde(default)]
+    pub name: Option<String>,
+    pub decision: ApprovalRuleDecision,
+    /// Glob (`*`, `?`) over the command line, which for a `bash -lc` command
+    /// is the script.
+    #[serde(default)]
+    pub command: Option<String>,
+    /// Regular expression searched for in the same command line.
+    #[serde(default)]
+    pub command_regex: Option<String>,
+    /// Directory the command must run in or below.
+    #[serde(default)]
+    pub cwd: Option<PathBuf>,
+    /// Variables the command's environment must set, each to a value
+    /// matching a glob.
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+}
+
 // ===== OTEL configuration =====
 
 #[derive(Deserialize, Debug, Clone, PartialEq)]
@@ -483,6 +991,11 @@ pub struct ShellEnvironmentPolicy {
 
     /// If true, the shell profile will be used to run the command.
     pub use_profile: bool,
+
+    /// Variables set by the profile of a fish or nushell login shell, taken
+    /// as part of the inherited environment. Filled in when the session
+    /// starts with `shell_profile.import_env`; not read from config.toml.
+    pub login_shell_vars: HashMap<String, String>,
 }
 
 impl From<ShellEnvironmentPolicyToml> for ShellEnvironmentPolicy {
@@ -512,6 +1025,7 @@ impl From<ShellEnvironmentPolicyToml> for ShellEnvironmentPolicy {
             r#set,
             include_only,
             use_profile,
+            login_shell_vars: HashMap::new(),
         }
     }
 }
@@ -1,14 +1,24 @@
 use crate::codex::TurnContext;
+use crate::compact::is_summary_message;
 use crate::context_manager::normalize;
 use crate::truncate::TruncationPolicy;
 use crate::truncate::approx_token_count;
 use crate::truncate::approx_tokens_from_byte_count;
 use crate::truncate::truncate_function_output_items_with_policy;
 use crate::truncate::truncate_text;
+use crate::user_instructions::UserInstructions;
+use crate::user_shell_command::is_user_shell_command_text;
+use crate::workspace_index::STALE_FILES_OPEN_TAG;
+use codex_protocol::models::ContentItem;
 use codex_protocol::models::FunctionCallOutputPayload;
 use codex_protocol::models::ResponseItem;
+use codex_protocol::protocol::ContextSource;
+use codex_protocol::protocol::ContextUsageEntry;
+use codex_protocol::protocol::ENVIRONMENT_CONTEXT_OPEN_TAG;
 use codex_protocol::protocol::TokenUsage;
 use codex_protocol::protocol::TokenUsageInfo;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
 use std::ops::Deref;
 
 /// Transcript of conversation history
@@ -92,6 +102,54 @@ impl ContextManager {
         Some(base_tokens.saturating_add(items_tokens))
     }
 
+    /// Break the estimate of [`Self::estimate_token_count`] down by where the
+    /// context came from, largest source first. Tool outputs are attributed to
+    /// the tool whose call produced them.
+    pub(crate) fn usage_by_source(
+        &self,
+        base_instructions: &str,
+        tool_definitions: &str,
+    ) -> Vec<ContextUsageEntry> {
+        let tool_names: HashMap<&str, &str> = self
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                ResponseItem::FunctionCall { name, call_id, .. }
+                | ResponseItem::CustomToolCall { name, call_id, .. } => {
+                    Some((call_id.as_str(), name.as_str()))
+                }
+                ResponseItem::LocalShellCall {
+                    call_id: Some(call_id),
+                    ..
+                } => Some((call_id.as_str(), "local_shell")),
+                _ => None,
+            })
+            .collect();
+
+        let mut usage: BTreeMap<ContextSource, i64> = BTreeMap::new();
+        let mut add = |source: ContextSource, text: &str| {
+            let tokens = i64::try_from(approx_token_count(text)).unwrap_or(i64::MAX);
+            if tokens > 0 {
+                let entry = usage.entry(source).or_default();
+                *entry = entry.saturating_add(tokens);
+            }
+        };
+        add(ContextSource::SystemPrompt, base_instructions);
+        add(ContextSource::ToolDefinitions, tool_definitions);
+        for item in &self.items {
+            if let Some(source) = context_source(item, &tool_names) {
+                add(source, &serde_json::to_string(item).unwrap_or_default());
+            }
+        }
+
+        let mut entries: Vec<ContextUsageEntry> = usage
+            .into_iter()
+            .map(|(source, tokens)| ContextUsageEntry { source, tokens })
+            .collect();
+        entries.sort_by(|a, b| b.tokens.cmp(&a.tokens));
+        entries
+    }
+
     pub(crate) fn remove_first_item(&mut self) {
         if !self.items.is_empty() {
             // Remove the oldest item (front of the list). Items are ordered from
@@ -247,6 +305,54 @@ fn is_api_message(message: &ResponseItem) -> bool {
     }
 }
 
+/// The source an item of history is attributed to, or `None` for items that
+/// are never sent to the model.
+fn context_source(item: &ResponseItem, tool_names: &HashMap<&str, &str>) -> Option<ContextSource> {
+    let tool_output = |call_id: &str| ContextSource::ToolOutput {
+        tool: tool_names.get(call_id).unwrap_or(&"unknown").to_string(),
+    };
+    match item {
+        ResponseItem::Message { role, content, .. } => match role.as_str() {
+            "assistant" => Some(ContextSource::AgentMessages),
+            "user" => Some(user_message_source(content)),
+            _ => Some(ContextSource::Instructions),
+        },
+        ResponseItem::Reasoning { .. } => Some(ContextSource::Reasoning),
+        ResponseItem::FunctionCall { .. }
+        | ResponseItem::CustomToolCall { .. }
+        | ResponseItem::LocalShellCall { .. }
+        | ResponseItem::WebSearchCall { .. } => Some(ContextSource::ToolCalls),
+        ResponseItem::FunctionCallOutput { call_id, .. }
+        | ResponseItem::CustomToolCallOutput { call_id, .. } => Some(tool_output(call_id)),
+        ResponseItem::CompactionSummary { .. } => Some(ContextSource::CompactionSummary),
+        ResponseItem::GhostSnapshot { .. } | ResponseItem::Other => None,
+    }
+}
+
+/// User-role messages also carry context injected by Codex itself.
+fn user_message_source(content: &[ContentItem]) -> ContextSource {
+    if UserInstructions::is_user_instructions(content) {
+        return ContextSource::Instructions;
+    }
+    let Some(ContentItem::InputText { text }) = content.first() else {
+        return ContextSource::UserMessages;
+    };
+    let text_start = text.trim_start();
+    if text_start.starts_with(ENVIRONMENT_CONTEXT_OPEN_TAG)
+        || text_start.starts_with(STALE_FILES_OPEN_TAG)
+    {
+        ContextSource::EnvironmentContext
+    } else if is_user_shell_command_text(text) {
+        ContextSource::ToolOutput {
+            tool: "user_shell".to_string(),
+        }
+    } else if is_summary_message(text) {
+        ContextSource::CompactionSummary
+    } else {
+        ContextSource::UserMessages
+    }
+}
+
 #[cfg(test)]
 #[path = "history_tests.rs"]
 mod tests;
@@ -817,3 +817,104 @@ fn normalize_mixed_inserts_and_removals_panics_in_debug() {
     let mut h = create_history_with_items(items);
     h.normalize_history();
 }
+
+#[test]
+fn usage_by_source_attributes_tool_outputs_to_their_tool() {
+    let input_text = |text: &str| ResponseItem::Message {
+        id: None,
+        role: "user".to_string(),
+        content: vec![ContentItem::InputText {
+            text: text.to_string(),
+        }],
+    };
+    let call = |name: &str, call_id: &str| ResponseItem::FunctionCall {
+        id: None,
+        name: name.to_string(),
+        arguments: "{}".to_string(),
+        call_id: call_id.to_string(),
+    };
+    let output = |call_id: &str, content: String| ResponseItem::FunctionCallOutput {
+        call_id: call_id.to_string(),
+        output: FunctionCallOutputPayload {
+            content,
+            ..Default::default()
+        },
+    };
+    let instructions = input_text(
+        "# AGENTS.md instructions for /repo\n\n<INSTRUCTIONS>\nbe brief\n</INSTRUCTIONS>",
+    );
+    let environment =
+        input_text("<environment_context>\n  <cwd>/repo</cwd>\n</environment_context>");
+    let user = input_text("fix the build");
+    let shell_call = call("shell", "call-1");
+    let shell_output = output("call-1", "x".repeat(4_000));
+    let read_call = call("read_file", "call-2");
+    let read_output = output("call-2", "y".repeat(2_000));
+    let agent = assistant_msg("done");
+    let items = vec![
+        instructions.clone(),
+        environment.clone(),
+        user.clone(),
+        shell_call.clone(),
+        shell_output.clone(),
+        read_call.clone(),
+        read_output.clone(),
+        agent.clone(),
+        ResponseItem::GhostSnapshot {
+            ghost_commit: GhostCommit::new("ghost-1".to_string(), None, Vec::new(), Vec::new()),
+        },
+    ];
+    let mut history = ContextManager::new();
+    history.replace(items);
+
+    let tokens = |items: &[&ResponseItem]| -> i64 {
+        items
+            .iter()
+            .map(|item| {
+                let serialized = serde_json::to_string(item).expect("serialize");
+                i64::try_from(truncate::approx_token_count(&serialized)).expect("tokens")
+            })
+            .sum()
+    };
+    let usage = history.usage_by_source(&"s".repeat(1_200), &"t".repeat(800));
+    assert!(
+        usage
+            .windows(2)
+            .all(|pair| pair[0].tokens >= pair[1].tokens)
+    );
+    assert_eq!(
+        usage[0].source,
+        ContextSource::ToolOutput {
+            tool: "shell".to_string()
+        }
+    );
+
+    let usage: BTreeMap<ContextSource, i64> = usage
+        .into_iter()
+        .map(|entry| (entry.source, entry.tokens))
+        .collect();
+    assert_eq!(
+        usage,
+        BTreeMap::from([
+            (ContextSource::SystemPrompt, 300),
+            (ContextSource::ToolDefinitions, 200),
+            (ContextSource::Instructions, tokens(&[&instructions])),
+            (ContextSource::EnvironmentContext, tokens(&[&environment])),
+            (ContextSource::UserMessages, tokens(&[&user])),
+            (ContextSource::AgentMessages, tokens(&[&agent])),
+            (ContextSource::ToolCalls, tokens(&[&shell_call, &read_call])),
+            (
+                ContextSource::ToolOutput {
+                    tool: "shell".to_string()
+                },
+                tokens(&[&shell_output])
+            ),
+            (
+                ContextSource::ToolOutput {
+                    tool: "read_file".to_string()
+                },
+                tokens(&[&read_output])
+            ),
+        ])
+    );
+}
@@ -0,0 +1,1234 @@
+//! JSON-RPC control protocol for driving a running session.
+//!
+//! The collaborator socket relays raw [`Event`]s and [`Op`]s. The control
+//! protocol instead offers a small, versioned JSON-RPC 2.0 API for editor
+//! plugins, scripts and applications that embed Codex. It is served on the
+//! Unix socket configured with `control_socket` and on stdio by `codex proto`.
+//! Every message is one JSON object on a single line, terminated by `\n`.
+//!
+//! - `initialize` `{"protocolVersion": …}`: returns the `protocolVersion` the
+//!   server speaks ([`PROTOCOL_VERSION`]) and its `serverInfo`. Optional; a
+//!   client asking for another version gets an error.
+//! - `session/state`: the conversation id, model, working directory, whether
+//!   a turn is running and the approval prompts waiting for an answer.
+//! - `session/sendMessage` `{"text": …}`: send a user message, as if typed,
+//!   and answer at once with its `submissionId`.
+//! - `session/prompt` `{"text": …}`: send a user message and answer when the
+//!   turn it joins ends, with `{"stopReason": "completed" | "interrupted"}`.
+//! - `session/interrupt`: interrupt the running turn.
+//! - `session/retry` `{"instructions": …}`: roll back the last turn and run
+//!   it again with `instructions` added to its message, answering at once
+//!   with the `submissionId`.
+//! - `approval/respond` `{"id": …, "decision": …}`: answer a pending approval
+//!   prompt; `id` is the one listed by `session/state`. Patches can instead
+//!   be answered per hunk with `{"id": …, "hunkDecisions": [...]}`.
+//! - `approvals/list`: the approvals the session replays without asking, as
+//!   `{"approvals": [...]}` with each entry's `id`, `summary`, `scope`
+//!   (`session` or `seeded`) and `age_secs`.
+//! - `approvals/revoke` `{"id": …}`: forget a cached approval, answering with
+//!   the remaining `approvals`.
+//! - `approvals/seed` `{"approvals": [...]}`: approve commands up front, e.g.
+//!   `{"type": "exec", "command": [...], "cwd": …}`, answering with the
+//!   resulting `approvals`.
+//! - `diff/render` `{"unifiedDiff": …}` or `{"changes": …}`: render a
+//!   `TurnDiff` event's diff, or the changes of a patch event, as structured
+//!   `files` (see [`crate::rendered_diff`]) for display.
+//! - `events/subscribe` / `events/unsubscribe`: start or stop receiving every
+//!   session event as a `session/event` notification.
+//! - `log/levels`: the levels the log file is written at, as `{"levels": …}`
+//!   in the syntax of `RUST_LOG`, or `null` when the process has no log file.
+//! - `log/setLevels` `{"levels": …}`: change those levels until Codex exits,
+//!   e.g. to `codex_core=info,codex_core::exec=trace` (see
+//!   [`crate::logging`]).
+//! - `$/cancelRequest` `{"id": …}` (notification): cancel a pending
+//!   `session/prompt`. Its turn is interrupted and the request fails with
+//!   code -32800.
+//!
+//! Messages to a client are queued. Once a client is [`OUTGOING_CAPACITY`]
+//! messages behind, streaming deltas (agent message, reasoning and command
+//! output chunks) are dropped rather than queued, and the next message it
+//! gets is an `events/dropped` `{"count": …}` notification. Other events and
+//! responses wait for room, and no further requests are read from the client
+//! meanwhile.
+//!
+//! Each client joins the session as a [`CollaboratorRole::Controller`], so
+//! the other participants see it come and go like any other collaborator.
+//!
+//! [`CollaboratorRole::Controller`]: codex_protocol::protocol::CollaboratorRole::Controller
+//! [`Op`]: codex_protocol::protocol::Op
+
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use async_channel::Sender;
+use codex_protocol::ConversationId;
+use codex_protocol::protocol::ApprovalSeed;
+use codex_protocol::protocol::Event;
+use codex_protocol::protocol::EventMsg;
+use codex_protocol::protocol::FileChange;
+use codex_protocol::protocol::Op;
+use codex_protocol::protocol::PatchHunk;
+use codex_protocol::protocol::ReviewDecision;
+use codex_protocol::protocol::SessionConfiguredEvent;
+use codex_protocol::protocol::Submission;
+use codex_protocol::user_input::UserInput;
+use mcp_types::JSONRPC_VERSION;
+use mcp_types::JSONRPCError;
+use mcp_types::JSONRPCErrorError;
+use mcp_types::JSONRPCMessage;
+use mcp_types::JSONRPCNotification;
+use mcp_types::JSONRPCResponse;
+use mcp_types::RequestId;
+use serde::Deserialize;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use serde_json::json;
+use tokio::io::AsyncBufReadExt;
+use tokio::io::AsyncRead;
+use tokio::io::AsyncWrite;
+use tokio::io::AsyncWriteExt;
+use tokio::io::BufReader;
+use tokio::sync::mpsc;
+use tokio::sync::mpsc::error::TrySendError;
+use tokio_util::task::AbortOnDropHandle;
+use tracing::warn;
+
+use crate::collab::Collaborator;
+use crate::collab::Collaborators;
+use crate::logging::log_levels;
+use crate::logging::set_log_levels;
+use crate::rendered_diff::render_file_changes;
+use crate::rendered_diff::render_unified_diff;
+
+/// Version of the control protocol, bumped on incompatible changes.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// How many messages may wait for a slow client before deltas are dropped.
+pub const OUTGOING_CAPACITY: usize = 256;
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const SERVER_ERROR: i64 = -32000;
+const REQUEST_CANCELLED: i64 = -32800;
+
+/// Listen for controllers on the Unix socket at `path` until the returned
+/// handle is dropped. Failures are logged rather than failing the session.
+pub(crate) fn serve(
+    hub: &Arc<Collaborators>,
+    tx_sub: &Sender<Submission>,
+    path: &Path,
+    conversation_id: ConversationId,
+    model: String,
+    cwd: PathBuf,
+) -> Option<AbortOnDropHandle<()>> {
+    #[cfg(unix)]
+    {
+        let state = SessionState::new(conversation_id, model, cwd);
+        match socket::listen(Arc::clone(hub), tx_sub.clone(), path, state) {
+            Ok(handle) => Some(handle),
+            Err(err) => {
+                warn!(
+                    "failed to listen for controllers on {}: {err}",
+                    path.display()
+                );
+                None
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (hub, tx_sub, conversation_id, model, cwd);
+        warn!(
+            "control_socket is only supported on Unix; ignoring {}",
+            path.display()
+        );
+        None
+    }
+}
+
+/// Serve the control protocol to a single client reading from `reader` and
+/// writing to `writer`, until either side goes away or the session shuts
+/// down. `collaborator` should have joined as a controller.
+pub async fn serve_stream<R, W>(
+    collaborator: Collaborator,
+    session: &SessionConfiguredEvent,
+    reader: R,
+    writer: W,
+) where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin + Send + 'static,
+{
+    let state = SessionState::new(
+        session.session_id,
+        session.model.clone(),
+        session.cwd.clone(),
+    );
+    let controller = Controller::new(collaborator, Arc::new(Mutex::new(state)), true);
+    handle_connection(reader, writer, controller).await;
+}
+
+#[cfg(unix)]
+mod socket {
+    use std::io;
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::Path;
+    use std::sync::Arc;
+    use std::sync::Mutex;
+
+    use async_channel::Receiver;
+    use async_channel::Sender;
+    use codex_protocol::protocol::CollaboratorRole;
+    use codex_protocol::protocol::Event;
+    use codex_protocol::protocol::Submission;
+    use tokio::net::UnixListener;
+    use tokio::task::JoinSet;
+    use tokio_util::task::AbortOnDropHandle;
+    use tracing::warn;
+
+    use super::Controller;
+    use super::SessionState;
+    use super::handle_connection;
+    use crate::collab::Collaborators;
+
+    /// The socket is only accessible to the current user.
+    pub(super) fn listen(
+        hub: Arc<Collaborators>,
+        tx_sub: Sender<Submission>,
+        path: &Path,
+        state: SessionState,
+    ) -> io::Result<AbortOnDropHandle<()>> {
+        if path.exists() {
+            // A previous session that did not shut down cleanly leaves the
+            // socket file behind; binding fails until it is removed.
+            std::fs::remove_file(path)?;
+        }
+        let listener = UnixListener::bind(path)?;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+        let state = Arc::new(Mutex::new(state));
+        let rx_event = hub.subscribe();
+        Ok(AbortOnDropHandle::new(tokio::spawn(async move {
+            // Connections live in the set so they end with the server.
+            let mut connections = JoinSet::new();
+            connections.spawn(track_state(rx_event, Arc::clone(&state)));
+            loop {
+                match listener.accept().await {
+                    Ok((stream, _)) => {
+                        while connections.try_join_next().is_some() {}
+                        let collaborator = hub.attach(CollaboratorRole::Controller, tx_sub.clone());
+                        // The state is shared and kept up to date by
+                        // `track_state`.
+                        let controller = Controller::new(collaborator, Arc::clone(&state), false);
+                        let (reader, writer) = stream.into_split();
+                        connections.spawn(handle_connection(reader, writer, controller));
+                    }
+                    Err(err) => {
+                        warn!("control socket accept failed: {err}");
+                        return;
+                    }
+                }
+            }
+        })))
+    }
+
+    async fn track_state(rx_event: Receiver<Event>, state: Arc<Mutex<SessionState>>) {
+        while let Ok(event) = rx_event.recv().await {
+            if let Ok(mut state) = state.lock() {
+                state.apply(&event);
+            }
+        }
+    }
+}
+
+/// What `session/state` reports.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SessionState {
+    conversation_id: ConversationId,
+    model: String,
+    cwd: PathBuf,
+    task_running: bool,
+    pending_approvals: Vec<PendingApproval>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PendingApproval {
+    /// The id to answer with: the id of the event carrying the prompt.
+    id: String,
+    call_id: String,
+    #[serde(flatten)]
+    request: ApprovalRequest,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+enum ApprovalRequest {
+    Exec {
+        command: Vec<String>,
+        cwd: PathBuf,
+        reason: Option<String>,
+    },
+    Patch {
+        files: Vec<PathBuf>,
+        reason: Option<String>,
+        hunks: Vec<PatchHunk>,
+    },
+}
+
+impl SessionState {
+    fn new(conversation_id: ConversationId, model: String, cwd: PathBuf) -> Self {
+        Self {
+            conversation_id,
+            model,
+            cwd,
+            task_running: false,
+            pending_approvals: Vec::new(),
+        }
+    }
+
+    fn apply(&mut self, event: &Event) {
+        let (call_id, request) = match &event.msg {
+            EventMsg::TaskStarted(_) => {
+                self.task_running = true;
+                return;
+            }
+            EventMsg::TaskComplete(_) | EventMsg::TurnAborted(_) => {
+                // Prompts that were not answered die with their turn.
+                self.task_running = false;
+                self.pending_approvals.clear();
+                return;
+            }
+            EventMsg::ApprovalResolved(resolved) => {
+                self.pending_approvals
+                    .retain(|pending| pending.id != resolved.id);
+                return;
+            }
+            EventMsg::ExecApprovalRequest(request) => (
+                request.call_id.clone(),
+                ApprovalRequest::Exec {
+                    command: request.command.clone(),
+                    cwd: request.cwd.clone(),
+                    reason: request.reason.clone(),
+                },
+            ),
+            EventMsg::ApplyPatchApprovalRequest(request) => {
+                let mut files: Vec<PathBuf> = request.changes.keys().cloned().collect();
+                files.sort();
+                (
+                    request.call_id.clone(),
+                    ApprovalRequest::Patch {
+                        files,
+                        reason: request.reason.clone(),
+                        hunks: request.hunks.clone(),
+                    },
+                )
+            }
+            _ => return,
+        };
+        // A turn has at most one open prompt per id; a new one replaces it.
+        self.pending_approvals
+            .retain(|pending| pending.id != event.id);
+        self.pending_approvals.push(PendingApproval {
+            id: event.id.clone(),
+            call_id,
+            request,
+        });
+    }
+}
+
+async fn handle_connection<R, W>(reader: R, writer: W, mut controller: Controller)
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin + Send + 'static,
+{
+    let (tx, rx) = mpsc::channel(OUTGOING_CAPACITY);
+    let writer_task = tokio::spawn(write_messages(writer, rx));
+    let mut outbox = Outbox { tx, dropped: 0 };
+    let mut lines = BufReader::new(reader).lines();
+    'connection: loop {
+        tokio::select! {
+            event = controller.collaborator.next_event() => {
+                let Ok(event) = event else {
+                    break;
+                };
+                let shutdown = matches!(event.msg, EventMsg::ShutdownComplete);
+                if controller.subscribed {
+                    let notification = event_notification(&event);
+                    let open = if is_delta(&event.msg) {
+                        outbox.offer(notification)
+                    } else {
+                        outbox.send(notification).await
+                    };
+                    if !open {
+                        break;
+                    }
+                }
+                for reply in controller.handle_event(&event) {
+                    if !outbox.send(reply).await {
+                        break 'connection;
+                    }
+                }
+                if shutdown {
+                    break;
+                }
+            }
+            line = lines.next_line() => {
+                let Ok(Some(line)) = line else {
+                    break;
+                };
+                let Some(reply) = controller.handle_line(&line).await else {
+                    continue;
+                };
+                if !outbox.send(reply).await {
+                    break;
+                }
+            }
+        }
+    }
+    // Let the writer flush what is already queued.
+    drop(outbox);
+    let _ = writer_task.await;
+}
+
+/// The queue of messages for one client.
+struct Outbox {
+    tx: mpsc::Sender<Value>,
+    /// Deltas dropped since the client last got a message.
+    dropped: u64,
+}
+
+impl Outbox {
+    /// Queue `message`, waiting for room. Returns `false` once the client is
+    /// gone.
+    async fn send(&mut self, message: Value) -> bool {
+        if self.dropped > 0 {
+            let dropped = dropped_notification(std::mem::take(&mut self.dropped));
+            if self.tx.send(dropped).await.is_err() {
+                return false;
+            }
+        }
+        self.tx.send(message).await.is_ok()
+    }
+
+    /// Queue `message` if there is room and drop it otherwise. Returns
+    /// `false` once the client is gone.
+    fn offer(&mut self, message: Value) -> bool {
+        if self.dropped > 0 {
+            match self.tx.try_send(dropped_notification(self.dropped)) {
+                Ok(()) => self.dropped = 0,
+                Err(TrySendError::Full(_)) => {
+                    self.dropped += 1;
+                    return true;
+                }
+                Err(TrySendError::Closed(_)) => return false,
+            }
+        }
+        match self.tx.try_send(message) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_)) => {
+                self.dropped += 1;
+                true
+            }
+            Err(TrySendError::Closed(_)) => false,
+        }
+    }
+}
+
+async fn write_messages<W: AsyncWrite + Unpin>(mut writer: W, mut rx: mpsc::Receiver<Value>) {
+    while let Some(message) = rx.recv().await {
+        if write_line(&mut writer, &message).await.is_err() {
+            return;
+        }
+    }
+}
+
+/// Events that are only worth delivering promptly.
+fn is_delta(msg: &EventMsg) -> bool {
+    matches!(
+        msg,
+        EventMsg::AgentMessageDelta(_)
+            | EventMsg::AgentReasoningDelta(_)
+            | EventMsg::AgentReasoningRawContentDelta(_)
+            | EventMsg::ExecCommandOutputDelta(_)
+    )
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct InitializeParams {
+    protocol_version: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct SendMessageParams {
+    text: String,
+}
+
+#[derive(Deserialize)]
+struct RetryParams {
+    instructions: String,
+}
+
+#[derive(Deserialize)]
+struct RevokeApprovalParams {
+    id: String,
+}
+
+#[derive(Deserialize)]
+struct SeedApprovalsParams {
+    approvals: Vec<ApprovalSeed>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RespondParams {
+    id: String,
+    decision: Option<ReviewDecision>,
+    hunk_decisions: Option<Vec<ReviewDecision>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RenderDiffParams {
+    unified_diff: Option<String>,
+    changes: Option<HashMap<PathBuf, FileChange>>,
+}
+
+#[derive(Deserialize)]
+struct SetLogLevelsParams {
+    levels: String,
+}
+
+#[derive(Deserialize)]
+struct CancelParams {
+    id: RequestId,
+}
+
+/// A `session/prompt` waiting for its turn to end.
+struct PendingPrompt {
+    request_id: RequestId,
+    cancelled: bool,
+}
+
+/// An `approvals/*` request waiting for the session's answer to its op.
+struct PendingApprovalCache {
+    submission_id: String,
+    request_id: RequestId,
+}
+
+/// How a request is answered.
+enum Reply {
+    Now(Value),
+    /// The answer is sent later, by [`Controller::handle_event`].
+    Deferred,
+}
+
+/// One connected client.
+struct Controller {
+    collaborator: Collaborator,
+    state: Arc<Mutex<SessionState>>,
+    /// Whether this controller keeps `state` up to date itself, rather than
+    /// sharing one kept by [`track_state`].
+    owns_state: bool,
+    subscribed: bool,
+    prompts: Vec<PendingPrompt>,
+    approval_requests: Vec<PendingApprovalCache>,
+}
+
+impl Controller {
+    fn new(collaborator: Collaborator, state: Arc<Mutex<SessionState>>, owns_state: bool) -> Self {
+        Self {
+            collaborator,
+            state,
+            owns_state,
+            subscribed: false,
+            prompts: Vec::new(),
+            approval_requests: Vec::new(),
+        }
+    }
+
+    /// Note `event` and return the answers to requests it completes.
+    fn handle_event(&mut self, event: &Event) -> Vec<Value> {
+        if let Some(mut state) = self.owns_state.then(|| self.state.lock().ok()).flatten() {
+            state.apply(event);
+        }
+        if let EventMsg::ApprovalCache(cache) = &event.msg {
+            let (answered, pending): (Vec<_>, Vec<_>) = std::mem::take(&mut self.approval_requests)
+                .into_iter()
+                .partition(|request| request.submission_id == event.id);
+            self.approval_requests = pending;
+            return answered
+                .into_iter()
+                .filter_map(|request| {
+                    serde_json::to_value(JSONRPCMessage::Response(JSONRPCResponse {
+                        id: request.request_id,
+                        jsonrpc: JSONRPC_VERSION.to_string(),
+                        result: json!({ "approvals": cache.approvals }),
+                    }))
+                    .ok()
+                })
+                .collect();
+        }
+        let stop_reason = match event.msg {
+            EventMsg::TaskComplete(_) => "completed",
+            EventMsg::TurnAborted(_) => "interrupted",
+            _ => return Vec::new(),
+        };
+        // A message sent while a turn runs joins that turn, so every waiting
+        // prompt ends with the next turn that ends.
+        std::mem::take(&mut self.prompts)
+            .into_iter()
+            .filter_map(|prompt| {
+                let reply = if prompt.cancelled {
+                    JSONRPCMessage::Error(JSONRPCError {
+                        error: rpc_error(REQUEST_CANCELLED, "request cancelled".to_string()),
+                        id: prompt.request_id,
+                        jsonrpc: JSONRPC_VERSION.to_string(),
+                    })
+                } else {
+                    JSONRPCMessage::Response(JSONRPCResponse {
+                        id: prompt.request_id,
+                        jsonrpc: JSONRPC_VERSION.to_string(),
+                        result: json!({ "stopReason": stop_reason }),
+                    })
+                };
+                serde_json::to_value(reply).ok()
+            })
+            .collect()
+    }
+
+    /// Handle one line from the client, returning the reply to send now, if
+    /// any. Notifications are handled like requests but never answered.
+    async fn handle_line(&mut self, line: &str) -> Option<Value> {
+        let message = match serde_json::from_str::<Value>(line) {
+            Ok(message) => message,
+            Err(err) => return Some(error_without_id(PARSE_ERROR, format!("{err}"))),
+        };
+        let reply = match serde_json::from_value::<JSONRPCMessage>(message) {
+            Ok(JSONRPCMessage::Request(request)) => {
+                match self
+                    .dispatch(Some(&request.id), &request.method, request.params)
+                    .await
+                {
+                    Ok(Reply::Now(result)) => JSONRPCMessage::Response(JSONRPCResponse {
+                        id: request.id,
+                        jsonrpc: JSONRPC_VERSION.to_string(),
+                        result,
+                    }),
+                    Ok(Reply::Deferred) => return None,
+                    Err(error) => JSONRPCMessage::Error(JSONRPCError {
+                        error,
+                        id: request.id,
+                        jsonrpc: JSONRPC_VERSION.to_string(),
+                    }),
+                }
+            }
+            Ok(JSONRPCMessage::Notification(notification)) => {
+                let _ = self
+                    .dispatch(None, &notification.method, notification.params)
+                    .await;
+                return None;
+            }
+            // The server never sends requests, so there is nothing to match
+            // responses against.
+            Ok(JSONRPCMessage::Response(_) | JSONRPCMessage::Error(_)) => return None,
+            Err(err) => return Some(error_without_id(INVALID_REQUEST, format!("{err}"))),
+        };
+        serde_json::to_value(reply).ok()
+    }
+
+    async fn dispatch(
+        &mut self,
+        request_id: Option<&RequestId>,
+        method: &str,
+        params: Option<Value>,
+    ) -> Result<Reply, JSONRPCErrorError> {
+        let result = match method {
+            "initialize" => {
+                let InitializeParams { protocol_version } = parse_params(params)?;
+                if let Some(version) = protocol_version.filter(|v| *v != PROTOCOL_VERSION) {
+                    return Err(rpc_error(
+                        INVALID_PARAMS,
+                        format!(
+                            "unsupported protocol version {version}; \
+                             this server speaks version {PROTOCOL_VERSION}"
+                        ),
+                    ));
+                }
+                json!({
+                    "protocolVersion": PROTOCOL_VERSION,
+                    "serverInfo": {
+                        "name": "codex",
+                        "version": env!("CARGO_PKG_VERSION"),
+                    },
+                })
+            }
+            "session/state" => {
+                let state = self.state.lock().map(|state| state.clone()).map_err(|_| {
+                    rpc_error(SERVER_ERROR, "session state is unavailable".to_string())
+                })?;
+                serde_json::to_value(state)
+                    .map_err(|err| rpc_error(SERVER_ERROR, err.to_string()))?
+            }
+            "session/sendMessage" => {
+                let SendMessageParams { text } = parse_params(params)?;
+                let id = self.send_message(text).await?;
+                json!({ "submissionId": id })
+            }
+            "session/prompt" => {
+                let SendMessageParams { text } = parse_params(params)?;
+                self.send_message(text).await?;
+                let Some(request_id) = request_id else {
+                    return Ok(Reply::Deferred);
+                };
+                self.prompts.push(PendingPrompt {
+                    request_id: request_id.clone(),
+                    cancelled: false,
+                });
+                return Ok(Reply::Deferred);
+            }
+            "session/interrupt" => {
+                self.submit(Op::Interrupt).await?;
+                json!({})
+            }
+            "session/retry" => {
+                let RetryParams { instructions } = parse_params(params)?;
+                let id = self.submit(Op::RetryTurn { instructions }).await?;
+                json!({ "submissionId": id })
+            }
+            "approvals/list" => {
+                return self
+                    .request_approval_cache(request_id, Op::ListApprovals)
+                    .await;
+            }
+            "approvals/revoke" => {
+                let RevokeApprovalParams { id } = parse_params(params)?;
+                return self
+                    .request_approval_cache(request_id, Op::RevokeApproval { id })
+                    .await;
+            }
+            "approvals/seed" => {
+                let SeedApprovalsParams { approvals } = parse_params(params)?;
+                return self
+                    .request_approval_cache(request_id, Op::SeedApprovals { approvals })
+                    .await;
+            }
+            "approval/respond" => {
+                let RespondParams {
+                    id,
+                    decision,
+                    hunk_decisions,
+                } = parse_params(params)?;
+                let request = self.state.lock().ok().and_then(|state| {
+                    state
+                        .pending_approvals
+                        .iter()
+                        .find(|pending| pending.id == id)
+                        .map(|pending| pending.request.clone())
+                });
+                let op = match (request, decision, hunk_decisions) {
+                    (None, _, _) => {
+                        return Err(rpc_error(
+                            INVALID_PARAMS,
+                            format!("no pending approval with id {id}"),
+                        ));
+                    }
+                    (Some(ApprovalRequest::Patch { .. }), None, Some(decisions)) => {
+                        Op::PatchHunkApproval { id, decisions }
+                    }
+                    (Some(ApprovalRequest::Exec { .. }), Some(decision), None) => {
+                        Op::ExecApproval { id, decision }
+                    }
+                    (Some(ApprovalRequest::Patch { .. }), Some(decision), None) => {
+                        Op::PatchApproval { id, decision }
+                    }
+                    _ => {
+                        return Err(rpc_error(
+                            INVALID_PARAMS,
+                            "expected either decision or, for patches, hunkDecisions".to_string(),
+                        ));
+                    }
+                };
+                self.submit(op).await?;
+                json!({})
+            }
+            "diff/render" => {
+                let files = match parse_params(params)? {
+                    RenderDiffParams {
+                        unified_diff: Some(diff),
+                        changes: None,
+                    } => render_unified_diff(&diff),
+                    RenderDiffParams {
+                        unified_diff: None,
+                        changes: Some(changes),
+                    } => render_file_changes(&changes),
+                    _ => {
+                        return Err(rpc_error(
+                            INVALID_PARAMS,
+                            "expected either unifiedDiff or changes".to_string(),
+                        ));
+                    }
+                };
+                json!({ "files": files })
+            }
+            "events/subscribe" => {
+                self.subscribed = true;
+                json!({})
+            }
+            "events/unsubscribe" => {
+                self.subscribed = false;
+                json!({})
+            }
+            "log/levels" => json!({ "levels": log_levels() }),
+            "log/setLevels" => {
+                let SetLogLevelsParams { levels } = parse_params(params)?;
+                set_log_levels(&levels).map_err(|err| rpc_error(INVALID_PARAMS, err))?;
+                json!({ "levels": levels })
+            }
+            "$/cancelRequest" => {
+                let CancelParams { id } = parse_params(params)?;
+                let Some(prompt) = self
+                    .prompts
+                    .iter_mut()
+                    .find(|prompt| prompt.request_id == id && !prompt.cancelled)
+                else {
+                    // Already answered, or never a cancellable request.
+                    return Ok(Reply::Now(json!({})));
+                };
+                prompt.cancelled = true;
+                self.submit(Op::Interrupt).await?;
+                json!({})
+            }
+            _ => {
+                return Err(rpc_error(
+                    METHOD_NOT_FOUND,
+                    format!("unknown method: {method}"),
+                ));
+            }
+        };
+        Ok(Reply::Now(result))
+    }
+
+    /// Submit `op` and answer with the cached approvals the session reports
+    /// once it has been handled.
+    async fn request_approval_cache(
+        &mut self,
+        request_id: Option<&RequestId>,
+        op: Op,
+    ) -> Result<Reply, JSONRPCErrorError> {
+        let submission_id = self.submit(op).await?;
+        if let Some(request_id) = request_id {
+            self.approval_requests.push(PendingApprovalCache {
+                submission_id,
+                request_id: request_id.clone(),
+            });
+        }
+        Ok(Reply::Deferred)
+    }
+
+    async fn send_message(&self, text: String) -> Result<String, JSONRPCErrorError> {
+        self.submit(Op::UserInput {
+            items: vec![UserInput::Text { text }],
+        })
+        .await
+    }
+
+    async fn submit(&self, op: Op) -> Result<String, JSONRPCErrorError> {
+        self.collaborator
+            .submit(op)
+            .await
+            .map_err(|err| rpc_error(SERVER_ERROR, err.to_string()))
+    }
+}
+
+fn parse_params<T: DeserializeOwned>(params: Option<Value>) -> Result<T, JSONRPCErrorError> {
+    serde_json::from_value(params.unwrap_or(Value::Null))
+        .map_err(|err| rpc_error(INVALID_PARAMS, format!("invalid params: {err}")))
+}
+
+fn rpc_error(code: i64, message: String) -> JSONRPCErrorError {
+    JSONRPCErrorError {
+        code,
+        data: None,
+        message,
+    }
+}
+
+/// Errors for messages whose id could not be read carry a `null` id, which
+/// [`JSONRPCError`] cannot represent.
+fn error_without_id(code: i64, message: String) -> Value {
+    json!({
+        "jsonrpc": JSONRPC_VERSION,
+        "id": null,
+        "error": rpc_error(code, message),
+    })
+}
+
+fn event_notification(event: &Event) -> Value {
+    notification("session/event", serde_json::to_value(event).ok())
+}
+
+fn dropped_notification(count: u64) -> Value {
+    notification("events/dropped", Some(json!({ "count": count })))
+}
+
+fn notification(method: &str, params: Option<Value>) -> Value {
+    let notification = JSONRPCMessage::Notification(JSONRPCNotification {
+        jsonrpc: JSONRPC_VERSION.to_string(),
+        method: method.to_string(),
+        params,
+    });
+    serde_json::to_value(notification).unwrap_or(Value::Null)
+}
+
+async fn write_line<W: AsyncWriteExt + Unpin, T: Serialize>(
+    writer: &mut W,
+    message: &T,
+) -> io::Result<()> {
+    let mut line = serde_json::to_vec(message)?;
+    line.push(b'\n');
+    writer.write_all(&line).await?;
+    writer.flush().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_protocol::protocol::AgentMessageDeltaEvent;
+    use codex_protocol::protocol::ApprovalCacheEvent;
+    use codex_protocol::protocol::ApprovalResolvedEvent;
+    use codex_protocol::protocol::ApprovalScope;
+    use codex_protocol::protocol::CachedApproval;
+    use codex_protocol::protocol::CollaboratorRole;
+    use codex_protocol::protocol::ExecApprovalRequestEvent;
+    use codex_protocol::protocol::TaskCompleteEvent;
+    use codex_protocol::protocol::TaskStartedEvent;
+    use pretty_assertions::assert_eq;
+
+    fn exec_request(id: &str) -> Event {
+        Event {
+            id: id.to_string(),
+            msg: EventMsg::ExecApprovalRequest(ExecApprovalRequestEvent {
+                call_id: "call-1".to_string(),
+                turn_id: id.to_string(),
+                command: vec!["cargo".to_string(), "test".to_string()],
+                cwd: PathBuf::from("/repo"),
+                reason: None,
+                risk: None,
+                parsed_cmd: Vec::new(),
+                scripts: Vec::new(),
+                expansions: Vec::new(),
+                trust_prefix: None,
+            }),
+        }
+    }
+
+    fn controller() -> (Controller, async_channel::Receiver<Submission>) {
+        let (tx_event, _rx_event) = async_channel::unbounded();
+        let (tx_sub, rx_sub) = async_channel::unbounded();
+        let hub = Arc::new(Collaborators::new(tx_event));
+        let mut state = SessionState::new(
+            ConversationId::default(),
+            "gpt-5-codex".to_string(),
+            PathBuf::from("/repo"),
+        );
+        state.apply(&exec_request("7"));
+        let controller = Controller::new(
+            hub.attach(CollaboratorRole::Controller, tx_sub),
+            Arc::new(Mutex::new(state)),
+            true,
+        );
+        (controller, rx_sub)
+    }
+
+    #[test]
+    fn state_tracks_turns_and_pending_approvals() {
+        let conversation_id = ConversationId::default();
+        let mut state =
+            SessionState::new(conversation_id, "gpt-5-codex".to_string(), "/repo".into());
+        state.apply(&Event {
+            id: "7".to_string(),
+            msg: EventMsg::TaskStarted(TaskStartedEvent {
+                model_context_window: None,
+            }),
+        });
+        state.apply(&exec_request("7"));
+        assert_eq!(
+            serde_json::to_value(&state).expect("serialize"),
+            json!({
+                "conversationId": conversation_id,
+                "model": "gpt-5-codex",
+                "cwd": "/repo",
+                "taskRunning": true,
+                "pendingApprovals": [{
+                    "id": "7",
+                    "callId": "call-1",
+                    "kind": "exec",
+                    "command": ["cargo", "test"],
+                    "cwd": "/repo",
+                    "reason": null,
+                }],
+            })
+        );
+
+        state.apply(&Event {
+            id: "7".to_string(),
+            msg: EventMsg::ApprovalResolved(ApprovalResolvedEvent {
+                id: "7".to_string(),
+                decision: ReviewDecision::Approved,
+            }),
+        });
+        assert_eq!(state.pending_approvals, Vec::new());
+    }
+
+    #[tokio::test]
+    async fn requests_are_answered_and_turned_into_ops() {
+        let (mut controller, rx_sub) = controller();
+
+        let reply = controller
+            .handle_line(
+                r#"{"jsonrpc":"2.0","id":1,"method":"approval/respond","params":{"id":"7","decision":"approved"}}"#,
+            )
+            .await;
+        assert_eq!(
+            reply,
+            Some(json!({"jsonrpc": "2.0", "id": 1, "result": {}}))
+        );
+        assert_eq!(
+            rx_sub.recv().await.expect("submission").op,
+            Op::ExecApproval {
+                id: "7".to_string(),
+                decision: ReviewDecision::Approved,
+            }
+        );
+
+        let reply = controller
+            .handle_line(
+                r#"{"jsonrpc":"2.0","id":2,"method":"session/sendMessage","params":{"text":"hi"}}"#,
+            )
+            .await;
+        assert_eq!(
+            reply,
+            Some(json!({
+                "jsonrpc": "2.0",
+                "id": 2,
+                "result": {"submissionId": "collab-1-1"},
+            }))
+        );
+
+        let reply = controller
+            .handle_line(
+                r#"{"jsonrpc":"2.0","id":3,"method":"diff/render","params":{"changes":{"a.md":{"type":"add","content":"hi\n"}}}}"#,
+            )
+            .await;
+        assert_eq!(
+            reply,
+            Some(json!({
+                "jsonrpc": "2.0",
+                "id": 3,
+                "result": {"files": [{
+                    "path": "a.md",
+                    "status": "added",
+                    "language": "markdown",
+                    "binary": false,
+                    "hunks": [{
+                        "old_start": 0,
+                        "old_count": 0,
+                        "new_start": 1,
+                        "new_count": 1,
+                        "lines": [{"kind": "added", "new_line": 1, "text": "hi"}],
+                    }],
+                }]},
+            }))
+        );
+
+        let reply = controller
+            .handle_line(
+                r#"{"jsonrpc":"2.0","id":4,"method":"session/retry","params":{"instructions":"use nom"}}"#,
+            )
+            .await;
+        assert_eq!(
+            reply,
+            Some(json!({
+                "jsonrpc": "2.0",
+                "id": 4,
+                "result": {"submissionId": "collab-1-2"},
+            }))
+        );
+        let ops: Vec<Op> = std::iter::from_fn(|| rx_sub.try_recv().ok())
+            .map(|submission| submission.op)
+            .collect();
+        assert_eq!(
+            ops,
+            vec![
+                Op::UserInput {
+                    items: vec![UserInput::Text {
+                        text: "hi".to_string()
+                    }],
+                },
+                Op::RetryTurn {
+                    instructions: "use nom".to_string(),
+                },
+            ]
+        );
+
+        let reply = controller
+            .handle_line(r#"{"jsonrpc":"2.0","id":5,"method":"session/fork"}"#)
+            .await;
+        assert_eq!(
+            reply,
+            Some(json!({
+                "jsonrpc": "2.0",
+                "id": 5,
+                "error": {"code": METHOD_NOT_FOUND, "message": "unknown method: session/fork"},
+            }))
+        );
+        assert_eq!(
+            controller
+                .handle_line("not json")
+                .await
+                .map(|reply| reply["id"].clone()),
+            Some(Value::Null)
+        );
+    }
+
+    #[tokio::test]
+    async fn prompts_are_answered_when_their_turn_ends() {
+        let (mut controller, rx_sub) = controller();
+        let turn_end = Event {
+            id: "collab-1-1".to_string(),
+            msg: EventMsg::TaskComplete(TaskCompleteEvent {
+                last_agent_message: None,
+            }),
+        };
+
+        let prompt = r#"{"jsonrpc":"2.0","id":1,"method":"session/prompt","params":{"text":"hi"}}"#;
+        assert_eq!(controller.handle_line(prompt).await, None);
+        assert_eq!(
+            controller.handle_event(&turn_end),
+            vec![json!({"jsonrpc": "2.0", "id": 1, "result": {"stopReason": "completed"}})]
+        );
+
+        let prompt = r#"{"jsonrpc":"2.0","id":2,"method":"session/prompt","params":{"text":"hi"}}"#;
+        assert_eq!(controller.handle_line(prompt).await, None);
+        let cancel = r#"{"jsonrpc":"2.0","method":"$/cancelRequest","params":{"id":2}}"#;
+        assert_eq!(controller.handle_line(cancel).await, None);
+        let ops: Vec<Op> = std::iter::from_fn(|| rx_sub.try_recv().ok())
+            .map(|submission| submission.op)
+            .collect();
+        assert_eq!(
+            ops,
+            vec![
+                Op::UserInput {
+                    items: vec![UserInput::Text {
+                        text: "hi".to_string()
+                    }],
+                },
+                Op::UserInput {
+                    items: vec![UserInput::Text {
+                        text: "hi".to_string()
+                    }],
+                },
+                Op::Interrupt,
+            ]
+        );
+        assert_eq!(
+            controller.handle_event(&turn_end),
+            vec![json!({
+                "jsonrpc": "2.0",
+                "id": 2,
+                "error": {"code": REQUEST_CANCELLED, "message": "request cancelled"},
+            })]
+        );
+    }
+
+    #[tokio::test]
+    async fn approval_requests_are_answered_with_the_cache() {
+        let (mut controller, rx_sub) = controller();
+
+        let seed = r#"{"jsonrpc":"2.0","id":1,"method":"approvals/seed","params":{"approvals":[{"type":"exec","command":["cargo","test"],"cwd":"/repo"}]}}"#;
+        assert_eq!(controller.handle_line(seed).await, None);
+        assert_eq!(
+            rx_sub.recv().await.expect("submission").op,
+            Op::SeedApprovals {
+                approvals: vec![ApprovalSeed::Exec {
+                    command: vec!["cargo".to_string(), "test".to_string()],
+                    cwd: PathBuf::from("/repo"),
+                    escalated: false,
+                }],
+            }
+        );
+
+        let cache = ApprovalCacheEvent {
+            approvals: vec![CachedApproval {
+                id: "1".to_string(),
+                summary: "cargo test in /repo".to_string(),
+                scope: ApprovalScope::Seeded,
+                age_secs: 0,
+            }],
+        };
+        let other = Event {
+            id: "collab-1-9".to_string(),
+            msg: EventMsg::ApprovalCache(cache.clone()),
+        };
+        assert_eq!(controller.handle_event(&other), Vec::<Value>::new());
+        let answer = Event {
+            id: "collab-1-0".to_string(),
+            msg: EventMsg::ApprovalCache(cache),
+        };
+        assert_eq!(
+            controller.handle_event(&answer),
+            vec![json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": {"approvals": [{
+                    "id": "1",
+                    "summary": "cargo test in /repo",
+                    "scope": "seeded",
+                    "age_secs": 0,
+                }]},
+            })]
+        );
+    }
+
+    #[tokio::test]
+    async fn slow_clients_lose_deltas_but_nothing_else() {
+        let (tx, mut rx) = mpsc::channel(2);
+        let mut outbox = Outbox { tx, dropped: 0 };
+        let delta = event_notification(&Event {
+            id: "1".to_string(),
+            msg: EventMsg::AgentMessageDelta(AgentMessageDeltaEvent {
+                delta: "x".to_string(),
+            }),
+        });
+
+        assert!(outbox.offer(delta.clone()));
+        assert!(outbox.offer(delta.clone()));
+        // The queue is full: these are dropped and counted.
+        assert!(outbox.offer(delta.clone()));
+        assert!(outbox.offer(delta.clone()));
+        assert_eq!(outbox.dropped, 2);
+
+        let (first, second) = (rx.recv().await, rx.recv().await);
+        assert_eq!((first, second), (Some(delta.clone()), Some(delta)));
+        assert!(
+            outbox
+                .send(json!({"jsonrpc": "2.0", "id": 1, "result": {}}))
+                .await
+        );
+        assert_eq!(
+            (rx.recv().await, rx.recv().await),
+            (
+                Some(dropped_notification(2)),
+                Some(json!({"jsonrpc": "2.0", "id": 1, "result": {}}))
+            )
+        );
+    }
+}
@@ -11,8 +11,14 @@ use crate::protocol::Event;
 use crate::protocol::EventMsg;
 use crate::protocol::SessionConfiguredEvent;
 use crate::rollout::RolloutRecorder;
+use crate::tools::custom::ToolRuntimeRegistry;
 use crate::tools::executor::DynToolExecutor;
 use crate::tools::executor::default_tool_executor;
+use codex_git::CreateGhostCommitOptions;
+use codex_git::GhostCommit;
+use codex_git::GitToolingError;
+use codex_git::create_detached_worktree;
+use codex_git::create_ghost_commit;
 use codex_protocol::ConversationId;
 use codex_protocol::items::TurnItem;
 use codex_protocol::models::ResponseItem;
@@ -23,6 +29,10 @@ use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::RwLock;
+use uuid::Uuid;
+
+/// Directory under `codex_home` holding the worktrees of forked sessions.
+const WORKTREES_SUBDIR: &str = "worktrees";
 
 /// Represents a newly created Codex conversation, including the first event
 /// (which is [`EventMsg::SessionConfigured`]).
@@ -39,6 +49,7 @@ pub struct ConversationManager {
     auth_manager: Arc<AuthManager>,
     session_source: SessionSource,
     tool_executor: DynToolExecutor,
+    tool_runtimes: ToolRuntimeRegistry,
 }
 
 impl ConversationManager {
@@ -56,9 +67,17 @@ impl ConversationManager {
             auth_manager,
             session_source,
             tool_executor,
+            tool_runtimes: ToolRuntimeRegistry::default(),
         }
     }
 
+    /// Offer the tools in `tool_runtimes` to the model in every conversation
+    /// this manager starts.
+    pub fn with_tool_runtimes(mut self, tool_runtimes: ToolRuntimeRegistry) -> Self {
+        self.tool_runtimes = tool_runtimes;
+        self
+    }
+
     /// Construct with a dummy AuthManager containing the provided CodexAuth.
     /// Used for integration tests: should not be used by ordinary business logic.
     pub fn with_auth(auth: CodexAuth) -> Self {
@@ -88,6 +107,7 @@ impl ConversationManager {
             InitialHistory::New,
             self.session_source.clone(),
             self.tool_executor.clone(),
+            self.tool_runtimes.clone(),
         )
         .await?;
         self.finalize_spawn(codex, conversation_id).await
@@ -165,6 +185,7 @@ impl ConversationManager {
             initial_history,
             self.session_source.clone(),
             self.tool_executor.clone(),
+            self.tool_runtimes.clone(),
         )
         .await?;
         self.finalize_spawn(codex, conversation_id).await
@@ -206,11 +227,110 @@ impl ConversationManager {
             history,
             self.session_source.clone(),
             self.tool_executor.clone(),
+            self.tool_runtimes.clone(),
         )
         .await?;
 
         self.finalize_spawn(codex, conversation_id).await
     }
+
+    /// Fork the conversation recorded at `path` into a new session that works
+    /// in its own git worktree, so the original line of work is left alone.
+    ///
+    /// The new session keeps the transcript before the nth (0-based) user
+    /// message, or all of it when `nth_user_message` is `None`. Its worktree,
+    /// under `codex_home/worktrees`, is checked out at the snapshot of the
+    /// workspace taken when that turn started (see the `undo` feature), or at
+    /// the current state of the workspace at `config.cwd` when the whole
+    /// transcript is kept or the turn has no snapshot. Like any new session,
+    /// it starts without approvals granted for the session or running
+    /// processes.
+    pub async fn fork_conversation_into_worktree(
+        &self,
+        nth_user_message: Option<usize>,
+        mut config: Config,
+        path: PathBuf,
+    ) -> CodexResult<NewConversation> {
+        let history = RolloutRecorder::get_rollout_history(&path).await?;
+        let (history, snapshot) = match nth_user_message {
+            Some(n) => {
+                let turns = user_message_positions(&history.get_rollout_items()).len();
+                if n >= turns {
+                    return Err(CodexErr::Fatal(format!(
+                        "cannot fork before turn {}: the session has {turns} turns",
+                        n + 1
+                    )));
+                }
+                let snapshot = turn_snapshot(&history, n);
+                (truncate_before_nth_user_message(history, n), snapshot)
+            }
+            None => (InitialHistory::Forked(history.get_rollout_items()), None),
+        };
+
+        let repo_path = config.cwd.clone();
+        let worktree = config
+            .codex_home
+            .join(WORKTREES_SUBDIR)
+            .join(Uuid::new_v4().to_string());
+        config.cwd = tokio::task::spawn_blocking(move || -> Result<PathBuf, GitToolingError> {
+            let commit = match snapshot {
+                Some(commit) => commit,
+                None => create_ghost_commit(&CreateGhostCommitOptions::new(&repo_path))?,
+            };
+            create_detached_worktree(&repo_path, &worktree, commit.id())
+        })
+        .await?
+        .map_err(|err| {
+            CodexErr::Fatal(format!("failed to create a worktree for the fork: {err}"))
+        })?;
+
+        let CodexSpawnOk {
+            codex,
+            conversation_id,
+        } = Codex::spawn(
+            config,
+            self.auth_manager.clone(),
+            history,
+            self.session_source.clone(),
+            self.tool_executor.clone(),
+            self.tool_runtimes.clone(),
+        )
+        .await?;
+
+        self.finalize_spawn(codex, conversation_id).await
+    }
+}
+
+/// Positions of the user messages in `items`, ignoring the messages that
+/// carry instructions and environment context.
+fn user_message_positions(items: &[RolloutItem]) -> Vec<usize> {
+    items
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, item)| match item {
+            RolloutItem::ResponseItem(item @ ResponseItem::Message { .. }) => matches!(
+                crate::event_mapping::parse_turn_item(item),
+                Some(TurnItem::UserMessage(_))
+            )
+            .then_some(idx),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The workspace snapshot recorded in the turn of the nth (0-based) user
+/// message, if any.
+fn turn_snapshot(history: &InitialHistory, n: usize) -> Option<GhostCommit> {
+    let items = history.get_rollout_items();
+    let positions = user_message_positions(&items);
+    let start = *positions.get(n)?;
+    let end = positions.get(n + 1).copied().unwrap_or(items.len());
+    items[start..end].iter().find_map(|item| match item {
+        RolloutItem::ResponseItem(ResponseItem::GhostSnapshot { ghost_commit }) => {
+            Some(ghost_commit.clone())
+        }
+        _ => None,
+    })
 }
 
 /// Return a prefix of `items` obtained by cutting strictly before the nth user message
@@ -220,17 +340,7 @@ fn truncate_before_nth_user_message(history: InitialHistory, n: usize) -> Initia
     let items: Vec<RolloutItem> = history.get_rollout_items();
 
     // Find indices of user message inputs in rollout order.
-    let mut user_positions: Vec<usize> = Vec::new();
-    for (idx, item) in items.iter().enumerate() {
-        if let RolloutItem::ResponseItem(item @ ResponseItem::Message { .. }) = item
-            && matches!(
-                crate::event_mapping::parse_turn_item(item),
-                Some(TurnItem::UserMessage(_))
-            )
-        {
-            user_positions.push(idx);
-        }
-    }
+    let user_positions = user_message_positions(&items);
 
     // If fewer than or equal to n user messages exist, treat as empty (out of range).
     if user_positions.len() <= n {
@@ -358,4 +468,31 @@ mod tests {
             serde_json::to_value(&expected).unwrap()
         );
     }
+
+    #[test]
+    fn finds_the_snapshot_taken_in_a_turn() {
+        let snapshot = |id: &str| GhostCommit::new(id.to_string(), None, Vec::new(), Vec::new());
+        let items: Vec<RolloutItem> = [
+            user_msg("u1"),
+            ResponseItem::GhostSnapshot {
+                ghost_commit: snapshot("c1"),
+            },
+            assistant_msg("a1"),
+            user_msg("u2"),
+            assistant_msg("a2"),
+            user_msg("u3"),
+            ResponseItem::GhostSnapshot {
+                ghost_commit: snapshot("c3"),
+            },
+        ]
+        .into_iter()
+        .map(RolloutItem::ResponseItem)
+        .collect();
+        let history = InitialHistory::Forked(items);
+
+        assert_eq!(turn_snapshot(&history, 0), Some(snapshot("c1")));
+        assert_eq!(turn_snapshot(&history, 1), None);
+        assert_eq!(turn_snapshot(&history, 2), Some(snapshot("c3")));
+        assert_eq!(turn_snapshot(&history, 3), None);
+    }
 }
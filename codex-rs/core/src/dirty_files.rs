@@ -0,0 +1,154 @@
+//! Guard against patches silently overwriting the user's work in progress.
+//!
+//! With the `dirty_file_guard` feature, a patch that would modify a file
+//! with uncommitted changes asks for approval and shows those changes,
+//! unless the file is exactly as an earlier patch of this session left it.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_apply_patch::ApplyPatchAction;
+use codex_git::uncommitted_changes;
+use sha2::Digest;
+use sha2::Sha256;
+use tracing::warn;
+
+use crate::apply_patch::target_paths;
+use crate::blocking_io::run_blocking;
+use crate::codex::Session;
+use crate::protocol::FileChange;
+use crate::protocol::UserChange;
+
+/// SHA-256 of a file's contents.
+pub(crate) type FileDigest = [u8; 32];
+
+pub(crate) fn file_digest(path: &Path) -> Option<FileDigest> {
+    std::fs::read(path)
+        .ok()
+        .map(|contents| Sha256::digest(contents).into())
+}
+
+/// The state each file touched by an applied patch was left in: its digest,
+/// or `None` if the patch deleted it or moved it away.
+pub(crate) fn patched_states(
+    changes: &HashMap<PathBuf, FileChange>,
+) -> Vec<(PathBuf, Option<FileDigest>)> {
+    let mut states = Vec::new();
+    for (path, change) in changes {
+        match change {
+            FileChange::Update {
+                move_path: Some(dest),
+                ..
+            } => {
+                states.push((path.clone(), None));
+                states.push((dest.clone(), file_digest(dest)));
+            }
+            FileChange::Delete { .. } => states.push((path.clone(), None)),
+            FileChange::Add { .. } | FileChange::Update { .. } | FileChange::Binary { .. } => {
+                states.push((path.clone(), file_digest(path)));
+            }
+        }
+    }
+    states
+}
+
+/// Whether `path` is still in the state `patched` records for it.
+fn left_by_session(patched: &HashMap<PathBuf, Option<FileDigest>>, path: &Path) -> bool {
+    patched
+        .get(path)
+        .is_some_and(|digest| *digest == file_digest(path))
+}
+
+/// Uncommitted changes the user has in files `action` would write.
+pub(crate) async fn user_changes(
+    sess: &Session,
+    cwd: &Path,
+    action: &ApplyPatchAction,
+) -> Vec<UserChange> {
+    let paths = target_paths(action);
+    let patched = sess.patched_files(&paths).await;
+    let cwd = cwd.to_path_buf();
+    let result = run_blocking(move || {
+        uncommitted_changes(&cwd, &paths).map(|changes| {
+            changes
+                .into_iter()
+                .filter(|change| !left_by_session(&patched, &change.path))
+                .map(|change| UserChange {
+                    path: change.path,
+                    unified_diff: change.unified_diff,
+                })
+                .collect()
+        })
+    })
+    .await;
+    match result {
+        Ok(Ok(changes)) => changes,
+        Ok(Err(err)) => {
+            warn!("failed to look for uncommitted changes: {err}");
+            Vec::new()
+        }
+        Err(err) => {
+            warn!("failed to look for uncommitted changes: {err}");
+            Vec::new()
+        }
+    }
+}
+
+/// Reason shown with the approval request for a patch touching `changes`.
+pub(crate) fn approval_reason(changes: &[UserChange]) -> String {
+    let paths: Vec<String> = changes
+        .iter()
+        .map(|change| change.path.display().to_string())
+        .collect();
+    format!(
+        "This patch modifies files with uncommitted changes you made: {}. Approving overwrites them.",
+        paths.join(", ")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::tempdir;
+
+    #[test]
+    fn files_changed_after_a_patch_are_the_users() {
+        let tmp = tempdir().expect("tmp");
+        let kept = tmp.path().join("kept.txt");
+        let edited = tmp.path().join("edited.txt");
+        let deleted = tmp.path().join("deleted.txt");
+        std::fs::write(&kept, "patched\n").expect("write");
+        std::fs::write(&edited, "patched\n").expect("write");
+        let changes = HashMap::from([
+            (
+                kept.clone(),
+                FileChange::Add {
+                    content: "patched\n".to_string(),
+                },
+            ),
+            (
+                edited.clone(),
+                FileChange::Add {
+                    content: "patched\n".to_string(),
+                },
+            ),
+            (
+                deleted.clone(),
+                FileChange::Delete {
+                    content: "old\n".to_string(),
+                },
+            ),
+        ]);
+        let patched: HashMap<_, _> = patched_states(&changes).into_iter().collect();
+
+        std::fs::write(&edited, "patched\nand edited by hand\n").expect("write");
+
+        let left: Vec<bool> = [&kept, &edited, &deleted, &tmp.path().join("other.txt")]
+            .into_iter()
+            .map(|path| left_by_session(&patched, path))
+            .collect();
+        assert_eq!(left, vec![true, false, true, false]);
+    }
+}
@@ -0,0 +1,350 @@
+//! Checks of the environment Codex runs commands in, for `codex doctor` and
+//! [`Op::RunDoctor`](codex_protocol::protocol::Op::RunDoctor): whether the
+//! sandbox can be used, the shell commands run in, git, the credentials of
+//! the model provider and whether the configured endpoints can be reached.
+//! Each check passes, warns or fails, and says what to do about anything it
+//! did not like.
+
+use std::path::Path;
+use std::time::Duration;
+
+use codex_app_server_protocol::AuthMode;
+use codex_protocol::protocol::DoctorCheck;
+use codex_protocol::protocol::DoctorStatus;
+use futures::future::join_all;
+use tokio::process::Command;
+
+use crate::auth::CodexAuth;
+use crate::auth::read_openai_api_key_from_env;
+use crate::config::Config;
+use crate::config::types::McpServerTransportConfig;
+use crate::config::types::SandboxBackend;
+use crate::exec::SandboxType;
+use crate::features::Feature;
+use crate::git_info::get_git_repo_root;
+use crate::protocol::SandboxPolicy;
+use crate::safety::get_platform_sandbox;
+use crate::safety::probe_windows_sandbox;
+#[cfg(target_os = "macos")]
+use crate::seatbelt::MACOS_PATH_TO_SEATBELT_EXECUTABLE;
+use crate::shell::default_user_shell;
+use crate::shell::profile_shell;
+
+/// How long an endpoint may take to answer before it counts as unreachable.
+const NETWORK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The security modules the Linux kernel runs, Landlock among them when
+/// file writes can be restricted.
+const LINUX_LSM_PATH: &str = "/sys/kernel/security/lsm";
+
+pub async fn run_doctor(config: &Config) -> Vec<DoctorCheck> {
+    let auth =
+        CodexAuth::from_auth_storage(&config.codex_home, config.cli_auth_credentials_store_mode);
+    let mut checks = vec![sandbox_check(config).await, shell_check()];
+    checks.extend(git_checks(&config.cwd).await);
+    checks.push(credentials_check(config, &auth));
+    checks.extend(network_checks(config, &auth.ok().flatten()).await);
+    checks
+}
+
+pub(crate) async fn sandbox_check(config: &Config) -> DoctorCheck {
+    const NAME: &str = "sandbox";
+    if matches!(config.sandbox_policy, SandboxPolicy::DangerFullAccess) {
+        return warn(
+            NAME,
+            "sandbox_mode is danger-full-access, so commands run without a sandbox",
+            "Use `--sandbox workspace-write` unless Codex runs in an isolated environment",
+        );
+    }
+    if config.sandbox_backend == SandboxBackend::Container {
+        let program = config.sandbox_container.runtime.program();
+        return match command_output(program, &["--version"], &config.cwd).await {
+            Some(version) => pass(NAME, format!("container ({version})")),
+            None => fail(
+                NAME,
+                format!("sandbox_backend is container but `{program}` did not run"),
+                format!("Install {program} or set `sandbox_backend = \"platform\"`"),
+            ),
+        };
+    }
+    match get_platform_sandbox() {
+        #[cfg(target_os = "macos")]
+        Some(SandboxType::MacosSeatbelt) => {
+            if Path::new(MACOS_PATH_TO_SEATBELT_EXECUTABLE).exists() {
+                pass(NAME, "Seatbelt")
+            } else {
+                fail(
+                    NAME,
+                    format!("{MACOS_PATH_TO_SEATBELT_EXECUTABLE} was not found"),
+                    "Commands cannot be sandboxed on this version of macOS",
+                )
+            }
+        }
+        #[cfg(not(target_os = "macos"))]
+        Some(SandboxType::MacosSeatbelt) => fail(
+            NAME,
+            "the seatbelt sandbox is only available on macOS",
+            "Set `sandbox_backend = \"container\"` to sandbox commands on this platform",
+        ),
+        Some(SandboxType::LinuxSeccomp) => {
+            let Some(exe) = config
+                .codex_linux_sandbox_exe
+                .as_ref()
+                .filter(|exe| exe.exists())
+            else {
+                return fail(
+                    NAME,
+                    "the codex-linux-sandbox helper was not found",
+                    "Run Codex from the `codex` executable, which includes the helper",
+                );
+            };
+            match std::fs::read_to_string(LINUX_LSM_PATH) {
+                Ok(lsm) if !landlock_enabled(&lsm) => warn(
+                    NAME,
+                    "the kernel does not run Landlock, so file writes cannot be restricted",
+                    "Use a kernel with Landlock enabled (5.13 or later, `landlock` in `lsm=`)",
+                ),
+                _ => pass(NAME, format!("Landlock and seccomp ({})", exe.display())),
+            }
+        }
+        Some(SandboxType::WindowsRestrictedToken) => {
+            let probe = probe_windows_sandbox(&config.codex_home);
+            match &probe.job_error {
+                Some(err) => warn(
+                    NAME,
+                    format!("restricted token, but job objects are unavailable: {err}"),
+                    "`sandbox_limits` are not enforced and leftover processes are not killed",
+                ),
+                None => pass(NAME, "restricted token and job object"),
+            }
+        }
+        Some(SandboxType::None | SandboxType::Container) | None => {
+            if !cfg!(target_os = "windows") {
+                fail(
+                    NAME,
+                    "no sandbox is available on this platform",
+                    "Run Codex in a container or VM, or set `sandbox_backend = \"container\"`",
+                )
+            } else if !config.features.enabled(Feature::WindowsSandbox) {
+                warn(
+                    NAME,
+                    "the Windows sandbox is off, so workspace-write runs as read-only",
+                    "Set `enable_experimental_windows_sandbox = true` under `[features]`",
+                )
+            } else {
+                let probe = probe_windows_sandbox(&config.codex_home);
+                let err = probe.token_error.as_deref().unwrap_or("unknown error");
+                fail(
+                    NAME,
+                    format!("the restricted token could not be created: {err}"),
+                    "Run `codex sandbox windows` to see the error in full",
+                )
+            }
+        }
+    }
+}
+
+fn shell_check() -> DoctorCheck {
+    const NAME: &str = "shell";
+    let shell = default_user_shell();
+    let path = shell.shell_path.display();
+    if !cfg!(windows) && !shell.shell_path.exists() {
+        return fail(
+            NAME,
+            format!("no usable shell was found and {path} does not exist"),
+            "Install bash or zsh",
+        );
+    }
+    match profile_shell() {
+        Some(profile) => warn(
+            NAME,
+            format!(
+                "commands run in {} ({path}) because the login shell, {}, is not POSIX",
+                shell.name(),
+                profile.name()
+            ),
+            "Set up PATH and the like in the profile of that shell as well",
+        ),
+        None => pass(NAME, format!("{} ({path})", shell.name())),
+    }
+}
+
+async fn git_checks(cwd: &Path) -> Vec<DoctorCheck> {
+    let Some(version) = command_output("git", &["--version"], cwd).await else {
+        return vec![fail(
+            "git",
+            "git was not found",
+            "Install git, which Codex uses to show and undo its changes",
+        )];
+    };
+    let mut checks = vec![pass("git", version)];
+    checks.push(match get_git_repo_root(cwd) {
+        Some(root) => pass("git repository", root.display().to_string()),
+        None => warn(
+            "git repository",
+            format!("{} is not in a git repository", cwd.display()),
+            "Run `git init` so changes can be reviewed and undone",
+        ),
+    });
+    checks.push(
+        match command_output("git", &["config", "user.email"], cwd).await {
+            Some(email) if !email.is_empty() => pass("git identity", email),
+            _ => warn(
+                "git identity",
+                "git has no user.email, so commits fail",
+                "Run `git config --global user.email <address>`",
+            ),
+        },
+    );
+    checks
+}
+
+fn credentials_check(config: &Config, auth: &std::io::Result<Option<CodexAuth>>) -> DoctorCheck {
+    const NAME: &str = "credentials";
+    let provider = &config.model_provider;
+    if !provider.requires_openai_auth {
+        return match provider.api_key() {
+            Ok(Some(_)) => pass(
+                NAME,
+                format!("{} is set", provider.env_key.as_deref().unwrap_or_default()),
+            ),
+            Ok(None) => pass(NAME, format!("{} needs no API key", provider.name)),
+            Err(err) => fail(
+                NAME,
+                err.to_string(),
+                provider
+                    .env_key
+                    .as_deref()
+                    .map(|key| format!("Set {key} in the environment"))
+                    .unwrap_or_default(),
+            ),
+        };
+    }
+    match auth {
+        Ok(Some(auth)) => match auth.mode {
+            AuthMode::ChatGPT => pass(NAME, "signed in with ChatGPT"),
+            AuthMode::ApiKey => pass(NAME, "signed in with an API key"),
+        },
+        Ok(None) if read_openai_api_key_from_env().is_some() => pass(NAME, "OPENAI_API_KEY is set"),
+        Ok(None) => fail(NAME, "not signed in", "Run `codex login`"),
+        Err(err) => fail(
+            NAME,
+            format!("the stored credentials could not be read: {err}"),
+            "Run `codex login` again",
+        ),
+    }
+}
+
+/// Whether the model provider and the MCP servers reached over HTTP answer.
+/// Any response counts, even an error status.
+async fn network_checks(config: &Config, auth: &Option<CodexAuth>) -> Vec<DoctorCheck> {
+    let mut endpoints = vec![(
+        format!("network: {}", config.model_provider.name),
+        config.model_provider.get_full_url(auth),
+    )];
+    let mut servers: Vec<_> = config
+        .mcp_servers
+        .iter()
+        .filter(|(_, server)| server.enabled)
+        .collect();
+    servers.sort_by(|(a, _), (b, _)| a.cmp(b));
+    for (name, server) in servers {
+        if let McpServerTransportConfig::StreamableHttp { url, .. } = &server.transport {
+            endpoints.push((format!("network: mcp server {name}"), url.clone()));
+        }
+    }
+    let client = match reqwest::Client::builder().timeout(NETWORK_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(err) => {
+            return vec![fail(
+                "network",
+                format!("no HTTP client could be created: {err}"),
+                "Check the TLS and proxy settings",
+            )];
+        }
+    };
+    join_all(endpoints.into_iter().map(|(name, url)| {
+        let client = &client;
+        async move {
+            match client.get(&url).send().await {
+                Ok(response) => pass(&name, format!("{url} answered {}", response.status())),
+                Err(err) => fail(
+                    &name,
+                    format!("{url} could not be reached: {err}"),
+                    "Check the network connection and HTTPS_PROXY",
+                ),
+            }
+        }
+    }))
+    .await
+}
+
+fn landlock_enabled(lsm: &str) -> bool {
+    lsm.trim().split(',').any(|module| module == "landlock")
+}
+
+/// The trimmed stdout of `program` when it runs and succeeds.
+async fn command_output(program: &str, args: &[&str], cwd: &Path) -> Option<String> {
+    let output = Command::new(program)
+        .args(args)
+        .current_dir(cwd)
+        .output()
+        .await
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn pass(name: &str, message: impl Into<String>) -> DoctorCheck {
+    DoctorCheck {
+        name: name.to_string(),
+        status: DoctorStatus::Pass,
+        message: message.into(),
+        fix: None,
+    }
+}
+
+fn warn(name: &str, message: impl Into<String>, fix: impl Into<String>) -> DoctorCheck {
+    DoctorCheck {
+        name: name.to_string(),
+        status: DoctorStatus::Warn,
+        message: message.into(),
+        fix: Some(fix.into()),
+    }
+}
+
+fn fail(name: &str, message: impl Into<String>, fix: impl Into<String>) -> DoctorCheck {
+    DoctorCheck {
+        name: name.to_string(),
+        status: DoctorStatus::Fail,
+        message: message.into(),
+        fix: Some(fix.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn landlock_is_found_in_the_lsm_list() {
+        assert!(landlock_enabled(
+            "lockdown,capability,landlock,yama,apparmor\n"
+        ));
+        assert!(!landlock_enabled("capability,yama,selinux"));
+    }
+
+    #[tokio::test]
+    async fn a_directory_outside_git_gets_a_warning() {
+        let dir = tempfile::tempdir().unwrap();
+        let checks = git_checks(dir.path()).await;
+        let repo = checks
+            .iter()
+            .find(|check| check.name == "git repository")
+            .unwrap();
+        assert_eq!(repo.status, DoctorStatus::Warn);
+    }
+}
@@ -0,0 +1,306 @@
+//! What a command run without the sandbox changed outside the workspace.
+//!
+//! With the `environment_diff` feature, global state that installers and
+//! setup scripts typically touch is snapshotted before an escalated command
+//! and again after it, and the differences are reported with
+//! `EventMsg::EnvironmentChanged`:
+//!
+//! - the commands found in the directories on `PATH`,
+//! - shell startup files such as `~/.bashrc` and `~/.zshrc`,
+//! - launchd agents and daemons on macOS, and systemd units on Linux,
+//! - the user's crontab.
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::ffi::OsString;
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_protocol::protocol::EnvironmentChange;
+use codex_protocol::protocol::EnvironmentChangeKind;
+use similar::ChangeTag;
+use similar::TextDiff;
+use tracing::warn;
+use walkdir::WalkDir;
+
+/// Shell startup files, relative to the home directory.
+const STARTUP_FILES: [&str; 8] = [
+    ".bashrc",
+    ".bash_profile",
+    ".bash_login",
+    ".profile",
+    ".zshrc",
+    ".zprofile",
+    ".zshenv",
+    ".config/fish/config.fish",
+];
+
+/// Startup files larger than this are compared by size only.
+const MAX_STARTUP_FILE_BYTES: u64 = 1024 * 1024;
+
+/// Most changed lines listed for one file or crontab.
+const MAX_LISTED_LINES: usize = 20;
+
+/// Where service definitions live: launchd on macOS, systemd elsewhere.
+/// Paths starting with `~/` are relative to the home directory.
+const SERVICE_DIRS: &[&str] = if cfg!(target_os = "macos") {
+    &[
+        "~/Library/LaunchAgents",
+        "/Library/LaunchAgents",
+        "/Library/LaunchDaemons",
+    ]
+} else {
+    &["~/.config/systemd/user", "/etc/systemd/system"]
+};
+
+/// The state compared before and after an escalated command.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct EnvironmentSnapshot {
+    path_commands: BTreeSet<PathBuf>,
+    /// Contents of each startup file, `None` when it does not exist.
+    startup_files: BTreeMap<PathBuf, Option<String>>,
+    services: BTreeSet<PathBuf>,
+    crontab: String,
+}
+
+impl EnvironmentSnapshot {
+    /// Snapshot the current state, reading commands from the directories on
+    /// `path` (the command's `PATH`, or this process's when `None`).
+    pub(crate) async fn take(path: Option<String>) -> Self {
+        tokio::task::spawn_blocking(move || {
+            let path = path
+                .map(OsString::from)
+                .or_else(|| std::env::var_os("PATH"))
+                .unwrap_or_default();
+            let home = dirs::home_dir();
+            Self {
+                path_commands: path_commands(&path),
+                startup_files: home.as_deref().map(startup_files).unwrap_or_default(),
+                services: services(home.as_deref()),
+                crontab: crontab(),
+            }
+        })
+        .await
+        .unwrap_or_else(|err| {
+            warn!("environment snapshot failed: {err}");
+            Self::default()
+        })
+    }
+
+    /// What changed between this snapshot and `after`.
+    pub(crate) fn diff(&self, after: &Self) -> Vec<EnvironmentChange> {
+        let mut changes = Vec::new();
+        for path in after.path_commands.difference(&self.path_commands) {
+            changes.push(change(
+                EnvironmentChangeKind::PathCommand,
+                format!("new command on PATH: {}", path.display()),
+                Vec::new(),
+            ));
+        }
+        for path in self.path_commands.difference(&after.path_commands) {
+            changes.push(change(
+                EnvironmentChangeKind::PathCommand,
+                format!("removed from PATH: {}", path.display()),
+                Vec::new(),
+            ));
+        }
+
+        for (path, contents) in &after.startup_files {
+            let before = self.startup_files.get(path).cloned().flatten();
+            let (verb, lines) = match (&before, contents) {
+                (None, None) => continue,
+                (Some(old), Some(new)) if old == new => continue,
+                (None, Some(new)) => ("created", line_diff("", new)),
+                (Some(old), None) => ("deleted", line_diff(old, "")),
+                (Some(old), Some(new)) => ("edited", line_diff(old, new)),
+            };
+            changes.push(change(
+                EnvironmentChangeKind::ShellStartupFile,
+                format!("{verb} {}", path.display()),
+                lines,
+            ));
+        }
+
+        for path in after.services.difference(&self.services) {
+            changes.push(change(
+                EnvironmentChangeKind::Service,
+                format!("new service definition: {}", path.display()),
+                Vec::new(),
+            ));
+        }
+        for path in self.services.difference(&after.services) {
+            changes.push(change(
+                EnvironmentChangeKind::Service,
+                format!("removed service definition: {}", path.display()),
+                Vec::new(),
+            ));
+        }
+
+        if self.crontab != after.crontab {
+            changes.push(change(
+                EnvironmentChangeKind::Crontab,
+                "edited the crontab".to_string(),
+                line_diff(&self.crontab, &after.crontab),
+            ));
+        }
+        changes
+    }
+}
+
+fn change(
+    kind: EnvironmentChangeKind,
+    description: String,
+    lines: Vec<String>,
+) -> EnvironmentChange {
+    EnvironmentChange {
+        kind,
+        description,
+        lines,
+    }
+}
+
+fn path_commands(path: &OsString) -> BTreeSet<PathBuf> {
+    std::env::split_paths(path)
+        .filter_map(|dir| std::fs::read_dir(dir).ok())
+        .flat_map(|entries| entries.filter_map(Result::ok))
+        .filter(|entry| !entry.file_type().is_ok_and(|file_type| file_type.is_dir()))
+        .map(|entry| entry.path())
+        .collect()
+}
+
+fn startup_files(home: &Path) -> BTreeMap<PathBuf, Option<String>> {
+    STARTUP_FILES
+        .iter()
+        .map(|name| {
+            let path = home.join(name);
+            let contents = match std::fs::metadata(&path) {
+                Ok(metadata) if metadata.len() > MAX_STARTUP_FILE_BYTES => {
+                    Some(format!("<{} bytes>", metadata.len()))
+                }
+                Ok(_) => std::fs::read(&path)
+                    .ok()
+                    .map(|bytes| String::from_utf8_lossy(&bytes).into_owned()),
+                Err(_) => None,
+            };
+            (path, contents)
+        })
+        .collect()
+}
+
+fn services(home: Option<&Path>) -> BTreeSet<PathBuf> {
+    SERVICE_DIRS
+        .iter()
+        .filter_map(|dir| match dir.strip_prefix("~/") {
+            Some(rest) => home.map(|home| home.join(rest)),
+            None => Some(PathBuf::from(dir)),
+        })
+        .flat_map(|dir| {
+            // systemd enables a unit with a symlink in a `*.wants` directory.
+            WalkDir::new(dir)
+                .min_depth(1)
+                .max_depth(2)
+                .into_iter()
+                .filter_map(Result::ok)
+                .filter(|entry| !entry.file_type().is_dir())
+                .map(walkdir::DirEntry::into_path)
+        })
+        .collect()
+}
+
+/// The user's crontab, empty when there is none or no `crontab` command.
+fn crontab() -> String {
+    if !cfg!(unix) {
+        return String::new();
+    }
+    match std::process::Command::new("crontab").arg("-l").output() {
+        Ok(output) if output.status.success() => {
+            String::from_utf8_lossy(&output.stdout).into_owned()
+        }
+        _ => String::new(),
+    }
+}
+
+/// Added and removed lines, prefixed with `+` and `-`.
+fn line_diff(old: &str, new: &str) -> Vec<String> {
+    let diff = TextDiff::from_lines(old, new);
+    let mut lines: Vec<String> = diff
+        .iter_all_changes()
+        .filter_map(|change| {
+            let sign = match change.tag() {
+                ChangeTag::Insert => '+',
+                ChangeTag::Delete => '-',
+                ChangeTag::Equal => return None,
+            };
+            Some(format!("{sign}{}", change.value().trim_end_matches('\n')))
+        })
+        .collect();
+    if lines.len() > MAX_LISTED_LINES {
+        let more = lines.len() - MAX_LISTED_LINES;
+        lines.truncate(MAX_LISTED_LINES);
+        lines.push(format!("… {more} more changed lines"));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn reports_new_commands_startup_file_edits_and_crontab_changes() {
+        let bashrc = PathBuf::from("/home/me/.bashrc");
+        let zshrc = PathBuf::from("/home/me/.zshrc");
+        let before = EnvironmentSnapshot {
+            path_commands: BTreeSet::from([PathBuf::from("/usr/bin/ls")]),
+            startup_files: BTreeMap::from([
+                (bashrc.clone(), Some("alias ll='ls -l'\n".to_string())),
+                (zshrc.clone(), None),
+            ]),
+            services: BTreeSet::new(),
+            crontab: String::new(),
+        };
+        let after = EnvironmentSnapshot {
+            path_commands: BTreeSet::from([
+                PathBuf::from("/usr/bin/ls"),
+                PathBuf::from("/usr/local/bin/tool"),
+            ]),
+            startup_files: BTreeMap::from([
+                (
+                    bashrc,
+                    Some("alias ll='ls -l'\nexport PATH=\"$HOME/.tool/bin:$PATH\"\n".to_string()),
+                ),
+                (zshrc, None),
+            ]),
+            services: BTreeSet::from([PathBuf::from("/etc/systemd/system/tool.service")]),
+            crontab: "0 * * * * tool update\n".to_string(),
+        };
+
+        assert_eq!(
+            before.diff(&after),
+            vec![
+                change(
+                    EnvironmentChangeKind::PathCommand,
+                    "new command on PATH: /usr/local/bin/tool".to_string(),
+                    Vec::new(),
+                ),
+                change(
+                    EnvironmentChangeKind::ShellStartupFile,
+                    "edited /home/me/.bashrc".to_string(),
+                    vec!["+export PATH=\"$HOME/.tool/bin:$PATH\"".to_string()],
+                ),
+                change(
+                    EnvironmentChangeKind::Service,
+                    "new service definition: /etc/systemd/system/tool.service".to_string(),
+                    Vec::new(),
+                ),
+                change(
+                    EnvironmentChangeKind::Crontab,
+                    "edited the crontab".to_string(),
+                    vec!["+0 * * * * tool update".to_string()],
+                ),
+            ]
+        );
+        assert_eq!(after.diff(&after), Vec::new());
+    }
+}
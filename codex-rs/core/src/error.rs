@@ -524,6 +524,7 @@ pub fn get_error_message_ui(e: &CodexErr) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::exec::ExitReason;
     use crate::exec::StreamOutput;
     use chrono::DateTime;
     use chrono::Duration as ChronoDuration;
@@ -590,7 +591,8 @@ mod tests {
             stderr: StreamOutput::new(String::new()),
             aggregated_output: StreamOutput::new("aggregate detail".to_string()),
             duration: Duration::from_millis(10),
-            timed_out: false,
+            full_output_path: None,
+            exit_reason: ExitReason::SandboxDenied,
         };
         let err = CodexErr::Sandbox(SandboxErr::Denied {
             output: Box::new(output),
@@ -606,7 +608,8 @@ mod tests {
             stderr: StreamOutput::new("stderr detail".to_string()),
             aggregated_output: StreamOutput::new(String::new()),
             duration: Duration::from_millis(10),
-            timed_out: false,
+            full_output_path: None,
+            exit_reason: ExitReason::SandboxDenied,
         };
         let err = CodexErr::Sandbox(SandboxErr::Denied {
             output: Box::new(output),
@@ -622,7 +625,8 @@ mod tests {
             stderr: StreamOutput::new(String::new()),
             aggregated_output: StreamOutput::new(String::new()),
             duration: Duration::from_millis(8),
-            timed_out: false,
+            full_output_path: None,
+            exit_reason: ExitReason::SandboxDenied,
         };
         let err = CodexErr::Sandbox(SandboxErr::Denied {
             output: Box::new(output),
@@ -665,7 +669,8 @@ mod tests {
             stderr: StreamOutput::new(String::new()),
             aggregated_output: StreamOutput::new(String::new()),
             duration: Duration::from_millis(5),
-            timed_out: false,
+            full_output_path: None,
+            exit_reason: ExitReason::SandboxDenied,
         };
         let err = CodexErr::Sandbox(SandboxErr::Denied {
             output: Box::new(output),
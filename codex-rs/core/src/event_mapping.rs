@@ -15,11 +15,12 @@ use uuid::Uuid;
 
 use crate::user_instructions::UserInstructions;
 use crate::user_shell_command::is_user_shell_command_text;
+use crate::workspace_index::STALE_FILES_OPEN_TAG;
 
 fn is_session_prefix(text: &str) -> bool {
     let trimmed = text.trim_start();
     let lowered = trimmed.to_ascii_lowercase();
-    lowered.starts_with("<environment_context>")
+    lowered.starts_with("<environment_context>") || lowered.starts_with(STALE_FILES_OPEN_TAG)
 }
 
 fn parse_user_message(message: &[ContentItem]) -> Option<UserMessageItem> {
@@ -2,23 +2,31 @@
 use std::os::unix::process::ExitStatusExt;
 
 use std::collections::HashMap;
+use std::fmt;
 use std::io;
 use std::path::Path;
 use std::path::PathBuf;
 use std::process::ExitStatus;
+use std::sync::Arc;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
 use std::time::Duration;
 use std::time::Instant;
 
 use async_channel::Sender;
+use serde::Serialize;
 use tokio::io::AsyncRead;
 use tokio::io::AsyncReadExt;
 use tokio::io::BufReader;
 use tokio::process::Child;
 use tokio_util::sync::CancellationToken;
 
+use crate::config::types::SandboxLimits;
 use crate::error::CodexErr;
 use crate::error::Result;
 use crate::error::SandboxErr;
+use crate::exec_progress::ProgressKind;
+use crate::exec_progress::ProgressParser;
 use crate::get_platform_sandbox;
 use crate::protocol::Event;
 use crate::protocol::EventMsg;
@@ -28,6 +36,8 @@ use crate::protocol::SandboxPolicy;
 use crate::sandboxing::CommandSpec;
 use crate::sandboxing::ExecEnv;
 use crate::sandboxing::SandboxManager;
+use crate::secret_redaction::SecretRedactor;
+use crate::secret_redaction::StreamRedactor;
 use crate::spawn::StdioPolicy;
 use crate::spawn::spawn_child_async;
 use crate::text_encoding::bytes_to_string_smart;
@@ -39,7 +49,10 @@ pub const DEFAULT_EXEC_COMMAND_TIMEOUT_MS: u64 = 10_000;
 const SIGKILL_CODE: i32 = 9;
 const TIMEOUT_CODE: i32 = 64;
 const EXIT_CODE_SIGNAL_BASE: i32 = 128; // conventional shell: 128 + signal
-const EXEC_TIMEOUT_EXIT_CODE: i32 = 124; // conventional timeout exit code
+pub(crate) const EXEC_TIMEOUT_EXIT_CODE: i32 = 124; // conventional timeout exit code
+const EXIT_CODE_SPAWN_FAILURE: i32 = -1;
+const SIGXCPU_CODE: i32 = 24;
+const SIGXFSZ_CODE: i32 = 25;
 
 // I/O buffer sizing
 const READ_CHUNK_SIZE: usize = 8192; // bytes per read
@@ -117,13 +130,23 @@ pub enum SandboxType {
 
     /// Only available on Windows.
     WindowsRestrictedToken,
+
+    /// The session's Docker or Podman container; see
+    /// [`crate::sandboxing::container`].
+    Container,
 }
 
+/// Where the output of a running command is streamed, as
+/// `ExecCommandOutputDelta` events. Each chunk names the stream it was read
+/// from and carries a sequence number shared by stdout and stderr, so that
+/// frontends can show the two apart and still in order.
 #[derive(Clone)]
 pub struct StdoutStream {
     pub sub_id: String,
     pub call_id: String,
     pub tx_event: Sender<Event>,
+    /// Applied to each chunk before it is sent.
+    pub(crate) redactor: Option<Arc<SecretRedactor>>,
 }
 
 pub async fn process_exec_tool_call(
@@ -164,6 +187,7 @@ pub async fn process_exec_tool_call(
         expiration,
         with_escalated_permissions,
         justification,
+        network: None,
     };
 
     let manager = SandboxManager::new();
@@ -174,6 +198,7 @@ pub async fn process_exec_tool_call(
             sandbox_type,
             sandbox_cwd,
             codex_linux_sandbox_exe.as_ref(),
+            None,
         )
         .map_err(CodexErr::from)?;
 
@@ -195,6 +220,7 @@ pub(crate) async fn execute_exec_env(
         with_escalated_permissions,
         justification,
         arg0,
+        limits,
     } = env;
 
     let params = ExecParams {
@@ -208,17 +234,20 @@ pub(crate) async fn execute_exec_env(
     };
 
     let start = Instant::now();
-    let raw_output_result = exec(params, sandbox, sandbox_policy, stdout_stream).await;
+    let raw_output_result = exec(params, sandbox, sandbox_policy, stdout_stream, limits).await;
     let duration = start.elapsed();
-    finalize_exec_result(raw_output_result, sandbox, duration)
+    finalize_exec_result(raw_output_result, sandbox, duration, limits.as_ref())
 }
 
 #[cfg(target_os = "windows")]
 async fn exec_windows_sandbox(
     params: ExecParams,
     sandbox_policy: &SandboxPolicy,
+    limits: Option<SandboxLimits>,
 ) -> Result<RawExecToolCallOutput> {
     use crate::config::find_codex_home;
+    use codex_windows_sandbox::JobLimit;
+    use codex_windows_sandbox::JobLimits;
     use codex_windows_sandbox::run_windows_sandbox_capture;
 
     let ExecParams {
@@ -243,6 +272,15 @@ async fn exec_windows_sandbox(
             "windows sandbox: failed to resolve codex_home: {err}"
         )))
     })?;
+    // The job object holds the command and its children together, so the
+    // limits apply to all of them rather than to each process.
+    let job_limits = limits
+        .map(|limits| JobLimits {
+            cpu_secs: limits.cpu_secs,
+            memory_mb: limits.memory_mb,
+            max_processes: limits.max_processes,
+        })
+        .unwrap_or_default();
     let spawn_res = tokio::task::spawn_blocking(move || {
         run_windows_sandbox_capture(
             policy_str.as_str(),
@@ -252,6 +290,7 @@ async fn exec_windows_sandbox(
             &cwd,
             env,
             timeout_ms,
+            &job_limits,
         )
     })
     .await;
@@ -288,12 +327,20 @@ async fn exec_windows_sandbox(
         truncated_after_lines: None,
     };
 
+    let limit_exceeded = capture.limit_exceeded.map(|limit| match limit {
+        JobLimit::CpuTime => ResourceLimit::Cpu,
+        JobLimit::Memory => ResourceLimit::Memory,
+        JobLimit::Processes => ResourceLimit::Processes,
+    });
+
     Ok(RawExecToolCallOutput {
         exit_status,
         stdout,
         stderr,
         aggregated_output,
         timed_out: capture.timed_out,
+        limit_exceeded,
+        spawn_failed: false,
     })
 }
 
@@ -301,38 +348,65 @@ fn finalize_exec_result(
     raw_output_result: std::result::Result<RawExecToolCallOutput, CodexErr>,
     sandbox_type: SandboxType,
     duration: Duration,
+    limits: Option<&SandboxLimits>,
 ) -> Result<ExecToolCallOutput> {
     match raw_output_result {
+        Ok(raw_output) if raw_output.spawn_failed => {
+            let message = raw_output.stderr.from_utf8_lossy();
+            Ok(ExecToolCallOutput {
+                exit_code: EXIT_CODE_SPAWN_FAILURE,
+                stdout: StreamOutput::new(String::new()),
+                stderr: message.clone(),
+                aggregated_output: message,
+                duration,
+                full_output_path: None,
+                exit_reason: ExitReason::SpawnFailure,
+            })
+        }
         Ok(raw_output) => {
             #[allow(unused_mut)]
             let mut timed_out = raw_output.timed_out;
+            #[allow(unused_mut)]
+            let mut exit_code = raw_output.exit_status.code().unwrap_or(-1);
+            #[allow(unused_mut)]
+            let mut exit_reason = ExitReason::from_exit_code(exit_code);
 
             #[cfg(target_family = "unix")]
             {
                 if let Some(signal) = raw_output.exit_status.signal() {
+                    let signal_exit_code = EXIT_CODE_SIGNAL_BASE + signal;
                     if signal == TIMEOUT_CODE {
                         timed_out = true;
                     } else {
-                        return Err(CodexErr::Sandbox(SandboxErr::Signal(signal)));
+                        exit_code = signal_exit_code;
+                        exit_reason = ExitReason::Signal(signal);
                     }
                 }
             }
 
-            let mut exit_code = raw_output.exit_status.code().unwrap_or(-1);
             if timed_out {
                 exit_code = EXEC_TIMEOUT_EXIT_CODE;
+                exit_reason = ExitReason::TimedOut;
             }
 
             let stdout = raw_output.stdout.from_utf8_lossy();
             let stderr = raw_output.stderr.from_utf8_lossy();
             let aggregated_output = raw_output.aggregated_output.from_utf8_lossy();
-            let exec_output = ExecToolCallOutput {
+            if !timed_out
+                && let Some(limit) = raw_output.limit_exceeded.or_else(|| {
+                    limits.and_then(|limits| exceeded_limit(limits, exit_code, &stderr.text))
+                })
+            {
+                exit_reason = ExitReason::LimitExceeded(limit);
+            }
+            let mut exec_output = ExecToolCallOutput {
                 exit_code,
                 stdout,
                 stderr,
                 aggregated_output,
                 duration,
-                timed_out,
+                full_output_path: None,
+                exit_reason,
             };
 
             if timed_out {
@@ -341,7 +415,10 @@ fn finalize_exec_result(
                 }));
             }
 
-            if is_likely_sandbox_denied(sandbox_type, &exec_output) {
+            if exec_output.exit_reason.is_exit_status()
+                && is_likely_sandbox_denied(sandbox_type, &exec_output)
+            {
+                exec_output.exit_reason = ExitReason::SandboxDenied;
                 return Err(CodexErr::Sandbox(SandboxErr::Denied {
                     output: Box::new(exec_output),
                 }));
@@ -370,6 +447,10 @@ pub(crate) mod errors {
                 SandboxTransformError::SeatbeltUnavailable => CodexErr::UnsupportedOperation(
                     "seatbelt sandbox is only available on macOS".to_string(),
                 ),
+                SandboxTransformError::NetworkProxy(err) => CodexErr::Io(err),
+                SandboxTransformError::ContainerNotStarted => CodexErr::UnsupportedOperation(
+                    "the sandbox container has not been started".to_string(),
+                ),
             }
         }
     }
@@ -450,6 +531,35 @@ struct RawExecToolCallOutput {
     pub stderr: StreamOutput<Vec<u8>>,
     pub aggregated_output: StreamOutput<Vec<u8>>,
     pub timed_out: bool,
+    /// Set by sandboxes that know which of their limits stopped the command.
+    pub limit_exceeded: Option<ResourceLimit>,
+    /// The command could not be started; `stderr` says why.
+    pub spawn_failed: bool,
+}
+
+impl RawExecToolCallOutput {
+    /// The output of a command that could not be started because of `err`.
+    fn spawn_failure(err: io::Error) -> Self {
+        let message = format!("execution error: {:?}", CodexErr::Io(err)).into_bytes();
+        Self {
+            exit_status: ExitStatus::default(),
+            stdout: StreamOutput {
+                text: Vec::new(),
+                truncated_after_lines: None,
+            },
+            stderr: StreamOutput {
+                text: message.clone(),
+                truncated_after_lines: None,
+            },
+            aggregated_output: StreamOutput {
+                text: message,
+                truncated_after_lines: None,
+            },
+            timed_out: false,
+            limit_exceeded: None,
+            spawn_failed: true,
+        }
+    }
 }
 
 impl StreamOutput<String> {
@@ -482,7 +592,125 @@ pub struct ExecToolCallOutput {
     pub stderr: StreamOutput<String>,
     pub aggregated_output: StreamOutput<String>,
     pub duration: Duration,
-    pub timed_out: bool,
+    /// Where the full output was saved when it was cut down; see
+    /// [`crate::output_truncation`].
+    pub full_output_path: Option<PathBuf>,
+    pub exit_reason: ExitReason,
+}
+
+impl ExecToolCallOutput {
+    /// Whether the command was killed when its timeout ran out.
+    pub fn timed_out(&self) -> bool {
+        self.exit_reason == ExitReason::TimedOut
+    }
+
+    /// The limit of `sandbox_limits` that most likely stopped the command.
+    pub fn limit_exceeded(&self) -> Option<ResourceLimit> {
+        match self.exit_reason {
+            ExitReason::LimitExceeded(limit) => Some(limit),
+            _ => None,
+        }
+    }
+}
+
+/// Why a command stopped, so that callers need not guess it from the exit
+/// code and the output.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExitReason {
+    /// It exited with status 0.
+    Success,
+    /// It exited with the nonzero status in `exit_code`.
+    NonZeroExit,
+    /// It was killed when its timeout ran out.
+    TimedOut,
+    /// The sandbox most likely stopped it by refusing a file, the network or
+    /// a system call.
+    SandboxDenied,
+    /// It was killed by this signal.
+    Signal(i32),
+    /// It most likely ran into this limit of `sandbox_limits`.
+    LimitExceeded(ResourceLimit),
+    /// It could not be started.
+    SpawnFailure,
+}
+
+impl ExitReason {
+    /// `Success` for status 0, `NonZeroExit` for any other.
+    pub fn from_exit_code(exit_code: i32) -> Self {
+        if exit_code == 0 {
+            ExitReason::Success
+        } else {
+            ExitReason::NonZeroExit
+        }
+    }
+
+    /// Whether the exit status alone says how the command ended.
+    pub fn is_exit_status(self) -> bool {
+        matches!(self, ExitReason::Success | ExitReason::NonZeroExit)
+    }
+}
+
+/// A limit of [`SandboxLimits`] a command ran into.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResourceLimit {
+    Cpu,
+    Memory,
+    Processes,
+    FileSize,
+}
+
+impl fmt::Display for ResourceLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ResourceLimit::Cpu => "CPU time",
+            ResourceLimit::Memory => "memory",
+            ResourceLimit::Processes => "process",
+            ResourceLimit::FileSize => "file size",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Which of `limits` a command that exited with `exit_code` most likely ran
+/// into. The CPU and file size limits are told apart by the signal they
+/// send. Running out of memory or processes only makes allocations and
+/// forks fail, so those are recognized by a kill or by the error messages
+/// common programs print.
+fn exceeded_limit(limits: &SandboxLimits, exit_code: i32, stderr: &str) -> Option<ResourceLimit> {
+    const MEMORY_KEYWORDS: [&str; 5] = [
+        "cannot allocate memory",
+        "out of memory",
+        "memoryerror",
+        "bad_alloc",
+        "memory allocation",
+    ];
+    const PROCESS_KEYWORDS: [&str; 3] = [
+        "resource temporarily unavailable",
+        "cannot fork",
+        "fork: retry",
+    ];
+
+    if exit_code == 0 {
+        return None;
+    }
+    let stderr = stderr.to_lowercase();
+    let mentions = |keywords: &[&str]| keywords.iter().any(|keyword| stderr.contains(keyword));
+    if limits.cpu_secs.is_some() && exit_code == EXIT_CODE_SIGNAL_BASE + SIGXCPU_CODE {
+        Some(ResourceLimit::Cpu)
+    } else if limits.max_file_size_mb.is_some() && exit_code == EXIT_CODE_SIGNAL_BASE + SIGXFSZ_CODE
+    {
+        Some(ResourceLimit::FileSize)
+    } else if limits.memory_mb.is_some()
+        && (exit_code == EXIT_CODE_SIGNAL_BASE + SIGKILL_CODE || mentions(&MEMORY_KEYWORDS))
+    {
+        Some(ResourceLimit::Memory)
+    } else if limits.max_processes.is_some() && mentions(&PROCESS_KEYWORDS) {
+        Some(ResourceLimit::Processes)
+    } else {
+        None
+    }
 }
 
 #[cfg_attr(not(target_os = "windows"), allow(unused_variables))]
@@ -491,12 +719,13 @@ async fn exec(
     sandbox: SandboxType,
     sandbox_policy: &SandboxPolicy,
     stdout_stream: Option<StdoutStream>,
+    limits: Option<SandboxLimits>,
 ) -> Result<RawExecToolCallOutput> {
     #[cfg(target_os = "windows")]
     if sandbox == SandboxType::WindowsRestrictedToken
         && !matches!(sandbox_policy, SandboxPolicy::DangerFullAccess)
     {
-        return exec_windows_sandbox(params, sandbox_policy).await;
+        return exec_windows_sandbox(params, sandbox_policy, limits).await;
     }
     let ExecParams {
         command,
@@ -513,8 +742,9 @@ async fn exec(
             "command args are empty",
         ))
     })?;
+    let progress = ProgressKind::detect(&command);
     let arg0_ref = arg0.as_deref();
-    let child = spawn_child_async(
+    let child = match spawn_child_async(
         PathBuf::from(program),
         args.into(),
         arg0_ref,
@@ -522,9 +752,14 @@ async fn exec(
         sandbox_policy,
         StdioPolicy::RedirectForShellTool,
         env,
+        limits,
     )
-    .await?;
-    consume_truncated_output(child, expiration, stdout_stream).await
+    .await
+    {
+        Ok(child) => child,
+        Err(err) => return Ok(RawExecToolCallOutput::spawn_failure(err)),
+    };
+    consume_truncated_output(child, expiration, stdout_stream, progress).await
 }
 
 /// Consumes the output of a child process, truncating it so it is suitable for
@@ -533,6 +768,7 @@ async fn consume_truncated_output(
     mut child: Child,
     expiration: ExecExpiration,
     stdout_stream: Option<StdoutStream>,
+    progress: Option<ProgressKind>,
 ) -> Result<RawExecToolCallOutput> {
     // Both stdout and stderr were configured with `Stdio::piped()`
     // above, therefore `take()` should normally return `Some`.  If it doesn't
@@ -550,18 +786,23 @@ async fn consume_truncated_output(
     })?;
 
     let (agg_tx, agg_rx) = async_channel::unbounded::<Vec<u8>>();
+    let seq = Arc::new(AtomicU64::new(0));
 
     let stdout_handle = tokio::spawn(read_capped(
         BufReader::new(stdout_reader),
         stdout_stream.clone(),
         false,
+        Arc::clone(&seq),
         Some(agg_tx.clone()),
+        progress,
     ));
     let stderr_handle = tokio::spawn(read_capped(
         BufReader::new(stderr_reader),
         stdout_stream.clone(),
         true,
+        seq,
         Some(agg_tx.clone()),
+        progress,
     ));
 
     let (exit_status, timed_out) = tokio::select! {
@@ -645,6 +886,8 @@ async fn consume_truncated_output(
         stderr,
         aggregated_output,
         timed_out,
+        limit_exceeded: None,
+        spawn_failed: false,
     })
 }
 
@@ -652,11 +895,18 @@ async fn read_capped<R: AsyncRead + Unpin + Send + 'static>(
     mut reader: R,
     stream: Option<StdoutStream>,
     is_stderr: bool,
+    seq: Arc<AtomicU64>,
     aggregate_tx: Option<Sender<Vec<u8>>>,
+    progress: Option<ProgressKind>,
 ) -> io::Result<StreamOutput<Vec<u8>>> {
     let mut buf = Vec::with_capacity(AGGREGATE_BUFFER_INITIAL_CAPACITY);
     let mut tmp = [0u8; READ_CHUNK_SIZE];
     let mut emitted_deltas: usize = 0;
+    let mut progress = progress.map(ProgressParser::new);
+    let mut redactor = stream
+        .as_ref()
+        .and_then(|stream| stream.redactor.clone())
+        .map(StreamRedactor::new);
 
     // No caps: append all bytes
 
@@ -669,23 +919,26 @@ async fn read_capped<R: AsyncRead + Unpin + Send + 'static>(
         if let Some(stream) = &stream
             && emitted_deltas < MAX_EXEC_OUTPUT_DELTAS_PER_CALL
         {
-            let chunk = tmp[..n].to_vec();
-            let msg = EventMsg::ExecCommandOutputDelta(ExecCommandOutputDeltaEvent {
-                call_id: stream.call_id.clone(),
-                stream: if is_stderr {
-                    ExecOutputStream::Stderr
-                } else {
-                    ExecOutputStream::Stdout
-                },
-                chunk,
-            });
-            let event = Event {
-                id: stream.sub_id.clone(),
-                msg,
+            let chunk = match redactor.as_mut() {
+                Some(redactor) => redactor.push(&tmp[..n]),
+                None => tmp[..n].to_vec(),
             };
-            #[allow(clippy::let_unit_value)]
-            let _ = stream.tx_event.send(event).await;
-            emitted_deltas += 1;
+            if !chunk.is_empty() {
+                send_output_delta(stream, is_stderr, &seq, chunk).await;
+                emitted_deltas += 1;
+            }
+        }
+
+        if let Some(stream) = &stream
+            && let Some(parser) = progress.as_mut()
+        {
+            for progress_event in parser.push(&stream.call_id, &tmp[..n]) {
+                let event = Event {
+                    id: stream.sub_id.clone(),
+                    msg: EventMsg::ExecCommandProgress(progress_event),
+                };
+                let _ = stream.tx_event.send(event).await;
+            }
         }
 
         if let Some(tx) = &aggregate_tx {
@@ -696,12 +949,45 @@ async fn read_capped<R: AsyncRead + Unpin + Send + 'static>(
         // Continue reading to EOF to avoid back-pressure
     }
 
+    if let Some(stream) = &stream
+        && let Some(redactor) = redactor.as_mut()
+    {
+        let rest = redactor.finish();
+        if !rest.is_empty() && emitted_deltas < MAX_EXEC_OUTPUT_DELTAS_PER_CALL {
+            send_output_delta(stream, is_stderr, &seq, rest).await;
+        }
+    }
+
     Ok(StreamOutput {
         text: buf,
         truncated_after_lines: None,
     })
 }
 
+async fn send_output_delta(
+    stream: &StdoutStream,
+    is_stderr: bool,
+    seq: &AtomicU64,
+    chunk: Vec<u8>,
+) {
+    let msg = EventMsg::ExecCommandOutputDelta(ExecCommandOutputDeltaEvent {
+        call_id: stream.call_id.clone(),
+        stream: if is_stderr {
+            ExecOutputStream::Stderr
+        } else {
+            ExecOutputStream::Stdout
+        },
+        seq: seq.fetch_add(1, Ordering::Relaxed),
+        chunk,
+    });
+    let event = Event {
+        id: stream.sub_id.clone(),
+        msg,
+    };
+    #[allow(clippy::let_unit_value)]
+    let _ = stream.tx_event.send(event).await;
+}
+
 #[cfg(unix)]
 fn synthetic_exit_status(code: i32) -> ExitStatus {
     use std::os::unix::process::ExitStatusExt;
@@ -765,10 +1051,42 @@ mod tests {
             stderr: StreamOutput::new(stderr.to_string()),
             aggregated_output: StreamOutput::new(aggregated.to_string()),
             duration: Duration::from_millis(1),
-            timed_out: false,
+            full_output_path: None,
+            exit_reason: ExitReason::from_exit_code(exit_code),
         }
     }
 
+    #[test]
+    fn exceeded_limits_are_told_apart() {
+        let limits = SandboxLimits {
+            cpu_secs: Some(10),
+            memory_mb: Some(512),
+            max_processes: Some(64),
+            max_file_size_mb: None,
+        };
+        let cases = [
+            (152, ""),
+            (153, ""),
+            (137, ""),
+            (1, "MemoryError"),
+            (128, "bash: fork: retry: Resource temporarily unavailable"),
+            (1, "error: no such file"),
+            (0, "out of memory"),
+        ];
+        assert_eq!(
+            cases.map(|(exit_code, stderr)| exceeded_limit(&limits, exit_code, stderr)),
+            [
+                Some(ResourceLimit::Cpu),
+                None,
+                Some(ResourceLimit::Memory),
+                Some(ResourceLimit::Memory),
+                Some(ResourceLimit::Processes),
+                None,
+                None,
+            ]
+        );
+    }
+
     #[test]
     fn sandbox_detection_requires_keywords() {
         let output = make_exec_output(1, "", "", "");
@@ -849,7 +1167,14 @@ mod tests {
             arg0: None,
         };
 
-        let output = exec(params, SandboxType::None, &SandboxPolicy::ReadOnly, None).await?;
+        let output = exec(
+            params,
+            SandboxType::None,
+            &SandboxPolicy::ReadOnly,
+            None,
+            None,
+        )
+        .await?;
         assert!(output.timed_out);
 
         let stdout = output.stdout.from_utf8_lossy().text;
@@ -877,6 +1202,36 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn spawn_failures_are_reported_as_output() -> Result<()> {
+        let params = ExecParams {
+            command: vec!["/nonexistent/codex-missing-binary".to_string()],
+            cwd: std::env::current_dir()?,
+            expiration: 1_000.into(),
+            env: HashMap::new(),
+            with_escalated_permissions: None,
+            justification: None,
+            arg0: None,
+        };
+        let cwd = std::env::current_dir()?;
+        let output = process_exec_tool_call(
+            params,
+            &SandboxPolicy::DangerFullAccess,
+            cwd.as_path(),
+            &None,
+            None,
+        )
+        .await?;
+        assert_eq!(output.exit_reason, ExitReason::SpawnFailure);
+        assert_eq!(output.exit_code, -1);
+        assert!(
+            output.stderr.text.starts_with("execution error: Io("),
+            "unexpected stderr: {}",
+            output.stderr.text
+        );
+        Ok(())
+    }
+
     #[tokio::test]
     async fn process_exec_tool_call_respects_cancellation_token() -> Result<()> {
         let command = long_running_command();
@@ -909,7 +1264,7 @@ mod tests {
             Err(CodexErr::Sandbox(SandboxErr::Timeout { output })) => output,
             other => panic!("expected timeout error, got {other:?}"),
         };
-        assert!(output.timed_out);
+        assert!(output.timed_out());
         assert_eq!(output.exit_code, EXEC_TIMEOUT_EXIT_CODE);
         Ok(())
     }
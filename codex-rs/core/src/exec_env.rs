@@ -1,3 +1,5 @@
+use crate::config::types::CommandEnvPolicy;
+use crate::config::types::CommandLocale;
 use crate::config::types::EnvironmentVariablePattern;
 use crate::config::types::ShellEnvironmentPolicy;
 use crate::config::types::ShellEnvironmentPolicyInherit;
@@ -12,7 +14,57 @@ use std::collections::HashSet;
 /// The derivation follows the algorithm documented in the struct-level comment
 /// for [`ShellEnvironmentPolicy`].
 pub fn create_env(policy: &ShellEnvironmentPolicy) -> HashMap<String, String> {
-    populate_env(std::env::vars(), policy)
+    // Variables from a fish or nushell profile override the process's own.
+    let login_shell_vars = policy
+        .login_shell_vars
+        .iter()
+        .map(|(key, val)| (key.clone(), val.clone()));
+    populate_env(std::env::vars().chain(login_shell_vars), policy)
+}
+
+/// Apply `command_env` to the environment of a command: its `deny` and
+/// `allow` lists, then its locale. Unlike [`create_env`], this also sees the
+/// variables a tool sets on its own, so it is applied to every command as it
+/// is spawned.
+pub(crate) fn prepare_command_env(
+    env: &HashMap<String, String>,
+    policy: &CommandEnvPolicy,
+) -> HashMap<String, String> {
+    let mut env = filter_command_env(env, policy);
+    apply_command_locale(&mut env, &policy.locale);
+    env
+}
+
+fn filter_command_env(
+    env: &HashMap<String, String>,
+    policy: &CommandEnvPolicy,
+) -> HashMap<String, String> {
+    let patterns = |globs: &[String]| -> Vec<EnvironmentVariablePattern> {
+        globs
+            .iter()
+            .map(|glob| EnvironmentVariablePattern::new_case_insensitive(glob))
+            .collect()
+    };
+    let deny = patterns(&policy.deny);
+    let allow = patterns(&policy.allow);
+    env.iter()
+        .filter(|(name, _)| {
+            !deny.iter().any(|pattern| pattern.matches(name))
+                && (allow.is_empty() || allow.iter().any(|pattern| pattern.matches(name)))
+        })
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect()
+}
+
+fn apply_command_locale(env: &mut HashMap<String, String>, locale: &CommandLocale) {
+    if locale.inherit {
+        return;
+    }
+    // `LANGUAGE` would still pick the language of messages.
+    env.remove("LANGUAGE");
+    env.insert("LANG".to_string(), locale.lang.clone());
+    env.insert("LC_ALL".to_string(), locale.lc_all.clone());
+    env.insert("TZ".to_string(), locale.tz.clone());
 }
 
 fn populate_env<I>(vars: I, policy: &ShellEnvironmentPolicy) -> HashMap<String, String>
@@ -81,6 +133,61 @@ mod tests {
             .collect()
     }
 
+    #[test]
+    fn command_env_denies_before_it_allows() {
+        let env: HashMap<String, String> = make_vars(&[
+            ("PATH", "/usr/bin"),
+            ("AWS_PROFILE", "prod"),
+            ("npm_config_cache", "/tmp/npm"),
+            ("HOME", "/home/user"),
+        ])
+        .into_iter()
+        .collect();
+        let policy = CommandEnvPolicy {
+            deny: vec!["aws_*".to_string()],
+            allow: vec!["PATH".to_string(), "AWS_*".to_string(), "NPM_*".to_string()],
+            ..Default::default()
+        };
+
+        let expected = hashmap! {
+            "PATH".to_string() => "/usr/bin".to_string(),
+            "npm_config_cache".to_string() => "/tmp/npm".to_string(),
+        };
+        assert_eq!(filter_command_env(&env, &policy), expected);
+    }
+
+    #[test]
+    fn command_env_sets_locale_unless_inherited() {
+        let env: HashMap<String, String> = make_vars(&[
+            ("PATH", "/usr/bin"),
+            ("LANG", "de_DE.UTF-8"),
+            ("LANGUAGE", "de"),
+            ("TZ", "Europe/Berlin"),
+        ])
+        .into_iter()
+        .collect();
+
+        let expected = hashmap! {
+            "PATH".to_string() => "/usr/bin".to_string(),
+            "LANG".to_string() => "C.UTF-8".to_string(),
+            "LC_ALL".to_string() => "C.UTF-8".to_string(),
+            "TZ".to_string() => "UTC".to_string(),
+        };
+        assert_eq!(
+            prepare_command_env(&env, &CommandEnvPolicy::default()),
+            expected
+        );
+
+        let policy = CommandEnvPolicy {
+            locale: CommandLocale {
+                inherit: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(prepare_command_env(&env, &policy), env);
+    }
+
     #[test]
     fn test_core_inherit_and_default_excludes() {
         let vars = make_vars(&[
@@ -0,0 +1,295 @@
+//! Progress reporting for long-running commands whose output we understand.
+//!
+//! [`ProgressKind::detect`] recognizes a handful of build, install and test
+//! commands. While such a command runs, its stdout/stderr are fed through a
+//! [`ProgressParser`], which turns the tool's own progress indicators into
+//! `ExecCommandProgress` events so frontends can draw a real progress bar.
+//!
+//! Only indicators the tools print when not attached to a terminal are
+//! understood; `npm` prints none, so `npm install` reports no progress.
+
+use std::path::Path;
+use std::sync::LazyLock;
+
+use regex_lite::Regex;
+
+use crate::protocol::ExecCommandProgressEvent;
+
+/// Partial lines longer than this are dropped rather than buffered; progress
+/// indicators are short.
+const MAX_PENDING_LINE_BYTES: usize = 4 * 1024;
+
+static CARGO_BUILDING: LazyLock<Regex> = LazyLock::new(|| {
+    #[expect(clippy::unwrap_used)]
+    Regex::new(r"Building \[[^\]]*\]\s+(\d+)/(\d+)(?::\s*(.+))?").unwrap()
+});
+static PYTEST_COLLECTED: LazyLock<Regex> = LazyLock::new(|| {
+    #[expect(clippy::unwrap_used)]
+    Regex::new(r"^collected (\d+) items?(?: / \d+ deselected / (\d+) selected)?").unwrap()
+});
+static PYTEST_PERCENT: LazyLock<Regex> = LazyLock::new(|| {
+    #[expect(clippy::unwrap_used)]
+    Regex::new(r"^(\S+).*\[\s*(\d+)%\]$").unwrap()
+});
+static YARN_STEP: LazyLock<Regex> = LazyLock::new(|| {
+    #[expect(clippy::unwrap_used)]
+    Regex::new(r"^\[(\d+)/(\d+)\]\s+(.+)").unwrap()
+});
+static PNPM_PROGRESS: LazyLock<Regex> = LazyLock::new(|| {
+    #[expect(clippy::unwrap_used)]
+    Regex::new(r"^Progress: resolved (\d+), reused \d+, downloaded \d+, added (\d+)").unwrap()
+});
+
+/// A long operation whose progress output we can parse.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ProgressKind {
+    /// `cargo build`, `cargo test`, `cargo check`, ...
+    Cargo,
+    Pytest,
+    Yarn,
+    Pnpm,
+}
+
+impl ProgressKind {
+    /// Look for a recognized program anywhere in `command`, so that commands
+    /// wrapped in `bash -lc` or a sandbox launcher are still detected.
+    pub(crate) fn detect(command: &[String]) -> Option<Self> {
+        let words: Vec<&str> = command
+            .iter()
+            .flat_map(|arg| arg.split(|c: char| c.is_whitespace() || ";&|()".contains(c)))
+            .filter(|word| !word.is_empty())
+            .collect();
+        words.iter().enumerate().find_map(|(idx, word)| {
+            let program = Path::new(word).file_name()?.to_str()?;
+            let next = words.get(idx + 1).copied();
+            match program {
+                "cargo"
+                    if matches!(
+                        next,
+                        Some("build" | "b" | "check" | "c" | "test" | "t" | "install" | "clippy")
+                    ) =>
+                {
+                    Some(Self::Cargo)
+                }
+                "pytest" | "py.test" => Some(Self::Pytest),
+                "python" | "python3" if next == Some("-m") => {
+                    (words.get(idx + 2) == Some(&"pytest")).then_some(Self::Pytest)
+                }
+                "yarn" if matches!(next, None | Some("install" | "add")) => Some(Self::Yarn),
+                "pnpm" if matches!(next, Some("install" | "i" | "add")) => Some(Self::Pnpm),
+                _ => None,
+            }
+        })
+    }
+
+    fn operation(self) -> &'static str {
+        match self {
+            Self::Cargo => "cargo",
+            Self::Pytest => "pytest",
+            Self::Yarn => "yarn",
+            Self::Pnpm => "pnpm install",
+        }
+    }
+}
+
+/// Incremental parser for one output stream of a recognized command.
+#[derive(Debug)]
+pub(crate) struct ProgressParser {
+    kind: ProgressKind,
+    pending: Vec<u8>,
+    /// Test count announced by pytest's `collected N items` line.
+    collected: Option<u64>,
+    last_percent: Option<u8>,
+}
+
+impl ProgressParser {
+    pub(crate) fn new(kind: ProgressKind) -> Self {
+        Self {
+            kind,
+            pending: Vec::new(),
+            collected: None,
+            last_percent: None,
+        }
+    }
+
+    /// Feed a chunk of output and return an event for every change in the
+    /// percentage complete. Progress bars redraw with `\r`, so both `\r` and
+    /// `\n` end a line.
+    pub(crate) fn push(&mut self, call_id: &str, chunk: &[u8]) -> Vec<ExecCommandProgressEvent> {
+        let mut events = Vec::new();
+        for &byte in chunk {
+            if byte == b'\n' || byte == b'\r' {
+                let line = String::from_utf8_lossy(&self.pending).into_owned();
+                self.pending.clear();
+                if let Some(event) = self.parse_line(call_id, line.trim()) {
+                    events.push(event);
+                }
+            } else if self.pending.len() < MAX_PENDING_LINE_BYTES {
+                self.pending.push(byte);
+            }
+        }
+        events
+    }
+
+    fn parse_line(&mut self, call_id: &str, line: &str) -> Option<ExecCommandProgressEvent> {
+        // `reported` is the tool's own percentage, when it prints one.
+        let (completed, total, reported, current): (u64, u64, Option<u64>, _) = match self.kind {
+            ProgressKind::Cargo => {
+                let caps = CARGO_BUILDING.captures(line)?;
+                (
+                    caps[1].parse().ok()?,
+                    caps[2].parse().ok()?,
+                    None,
+                    caps.get(3)
+                        .map(|current| current.as_str().trim().to_string()),
+                )
+            }
+            ProgressKind::Pytest => {
+                if let Some(caps) = PYTEST_COLLECTED.captures(line) {
+                    let selected = caps.get(2).unwrap_or(caps.get(1)?);
+                    self.collected = selected.as_str().parse().ok();
+                    return None;
+                }
+                let caps = PYTEST_PERCENT.captures(line)?;
+                let percent: u64 = caps[2].parse().ok()?;
+                let total = self.collected.unwrap_or(100);
+                (
+                    total * percent.min(100) / 100,
+                    total,
+                    Some(percent),
+                    Some(caps[1].to_string()),
+                )
+            }
+            ProgressKind::Yarn => {
+                let caps = YARN_STEP.captures(line)?;
+                // The step counter names the step being started, so step `n`
+                // of `m` means `n - 1` are done.
+                let step: u64 = caps[1].parse().ok()?;
+                (
+                    step.saturating_sub(1),
+                    caps[2].parse().ok()?,
+                    None,
+                    Some(caps[3].trim().to_string()),
+                )
+            }
+            ProgressKind::Pnpm => {
+                let caps = PNPM_PROGRESS.captures(line)?;
+                (caps[2].parse().ok()?, caps[1].parse().ok()?, None, None)
+            }
+        };
+        if total == 0 {
+            return None;
+        }
+        let completed = completed.min(total);
+        let percent = reported.unwrap_or(completed * 100 / total).min(100) as u8;
+        if self.last_percent == Some(percent) {
+            return None;
+        }
+        self.last_percent = Some(percent);
+        Some(ExecCommandProgressEvent {
+            call_id: call_id.to_string(),
+            operation: self.kind.operation().to_string(),
+            completed,
+            total,
+            percent,
+            current,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn command(script: &str) -> Vec<String> {
+        vec!["bash".to_string(), "-lc".to_string(), script.to_string()]
+    }
+
+    fn event(
+        operation: &str,
+        completed: u64,
+        total: u64,
+        percent: u8,
+        current: Option<&str>,
+    ) -> ExecCommandProgressEvent {
+        ExecCommandProgressEvent {
+            call_id: "call".to_string(),
+            operation: operation.to_string(),
+            completed,
+            total,
+            percent,
+            current: current.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn detects_recognized_commands() {
+        assert_eq!(
+            ProgressKind::detect(&command("cd core && cargo test -p codex-core")),
+            Some(ProgressKind::Cargo)
+        );
+        assert_eq!(
+            ProgressKind::detect(&command("python3 -m pytest tests/")),
+            Some(ProgressKind::Pytest)
+        );
+        assert_eq!(
+            ProgressKind::detect(&["/usr/local/bin/pnpm".to_string(), "install".to_string()]),
+            Some(ProgressKind::Pnpm)
+        );
+        assert_eq!(ProgressKind::detect(&command("cargo fmt")), None);
+        assert_eq!(ProgressKind::detect(&command("npm install")), None);
+    }
+
+    #[test]
+    fn parses_cargo_progress_bar() {
+        let mut parser = ProgressParser::new(ProgressKind::Cargo);
+        let output = b"   Compiling serde v1.0.0\r\
+            \x20   Building [=====>    ] 50/200: serde, tokio\r\
+            \x20   Building [=====>    ] 51/200: tokio\r";
+        assert_eq!(
+            parser.push("call", output),
+            vec![event("cargo", 50, 200, 25, Some("serde, tokio"))]
+        );
+    }
+
+    #[test]
+    fn parses_pytest_percentages_across_chunks() {
+        let mut parser = ProgressParser::new(ProgressKind::Pytest);
+        assert_eq!(
+            parser.push("call", b"collected 40 items\n\ntests/test_a.py ...."),
+            vec![]
+        );
+        assert_eq!(
+            parser.push("call", b"......   [ 25%]\ntests/test_b.py ..F   [ 32%]\n"),
+            vec![
+                event("pytest", 10, 40, 25, Some("tests/test_a.py")),
+                event("pytest", 12, 40, 32, Some("tests/test_b.py")),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_package_manager_steps() {
+        let mut yarn = ProgressParser::new(ProgressKind::Yarn);
+        assert_eq!(
+            yarn.push(
+                "call",
+                b"[1/4] Resolving packages...\n[3/4] Linking dependencies...\n"
+            ),
+            vec![
+                event("yarn", 0, 4, 0, Some("Resolving packages...")),
+                event("yarn", 2, 4, 50, Some("Linking dependencies...")),
+            ]
+        );
+
+        let mut pnpm = ProgressParser::new(ProgressKind::Pnpm);
+        assert_eq!(
+            pnpm.push(
+                "call",
+                b"Progress: resolved 80, reused 60, downloaded 0, added 20\n"
+            ),
+            vec![event("pnpm install", 20, 80, 25, None)]
+        );
+    }
+}
@@ -43,7 +43,7 @@ pub enum Feature {
     ExecPolicy,
     /// Enable the model-based risk assessments for sandboxed commands.
     SandboxCommandAssessment,
-    /// Enable Windows sandbox (restricted token) on Windows.
+    /// Enable Windows sandbox (restricted token and job object) on Windows.
     WindowsSandbox,
     /// Remote compaction enabled (only for ChatGPT auth)
     RemoteCompaction,
@@ -51,6 +51,103 @@ pub enum Feature {
     ShellTool,
     /// Allow model to call multiple tools in parallel (only for models supporting it).
     ParallelToolCalls,
+    /// Include the coverage tool that reports uncovered lines per file.
+    CoverageTool,
+    /// Include the run_tests tool with repeat/stress mode for flake detection.
+    TestRunnerTool,
+    /// Include the bench tool with named baselines under `.codex/bench`.
+    BenchTool,
+    /// Include the process tool for listing and terminating workspace processes.
+    ProcessTool,
+    /// Include the port tool for checking and waiting on TCP ports.
+    PortTool,
+    /// Include the wait_for tool for HTTP, file and log-line readiness checks.
+    WaitForTool,
+    /// Include the compose tool for session-scoped Docker Compose projects.
+    ComposeTool,
+    /// Include the env_snapshot tool describing the OS, hardware and toolchains.
+    EnvSnapshotTool,
+    /// Include the file_info tool for structured stat and per-line blame.
+    FileInfoTool,
+    /// Include the find_replace tool that turns bulk edits into a single patch.
+    FindReplaceTool,
+    /// Include the scaffold tool that renders file-tree templates as a patch.
+    ScaffoldTool,
+    /// Include the codemod tool that runs ast-grep/comby rewrite rules as a patch.
+    CodemodTool,
+    /// Include the migrate tool that dry-runs database migrations on a shadow database.
+    MigrateTool,
+    /// Include the artifacts tool for exchanging files with the user outside the workspace.
+    ArtifactsTool,
+    /// Include the approval-gated clipboard tool.
+    ClipboardTool,
+    /// Emit one-line `StatusLine` events summarizing tool activity.
+    CondensedEvents,
+    /// Prefetch files related to the ones `read_file` returns.
+    ReadAhead,
+    /// Include the `read_changes` tool (diff against the last read).
+    ReadChangesTool,
+    /// Emit `FileRead` events with language and syntax tokens for read_file results.
+    FileReadEvents,
+    /// Ask before patches modify files with uncommitted changes by the user.
+    DirtyFileGuard,
+    /// Include the write_file tool that writes whole files without a patch.
+    WriteFileTool,
+    /// Let the shell tool start background jobs, with job_status, job_output
+    /// and job_kill to follow them.
+    BackgroundJobs,
+    /// Approve and run `&&`/`||`/`;` chains one step at a time.
+    SplitCommandChains,
+    /// Let shell commands declare the network access they need.
+    CommandNetwork,
+    /// Copy files that patches delete to a session trash, with a tool to
+    /// restore them.
+    Trash,
+    /// Journal the patches applied in a session so that the last ones can be
+    /// reverted with the `undo_patch` tool or `Op::UndoPatch`.
+    UndoPatch,
+    /// Report files that commands run without the sandbox create outside the
+    /// workspace, so they can be deleted or kept.
+    Quarantine,
+    /// Report what commands run without the sandbox changed in `PATH`, shell
+    /// startup files, services and the crontab.
+    EnvironmentDiff,
+    /// Keep an incrementally updated index of the workspace's file hashes
+    /// and tell the model which files it read changed between turns.
+    WorkspaceIndex,
+    /// Merge a patch whose context no longer matches with the changes made
+    /// since the model read the file, instead of rejecting it.
+    PatchMerge,
+    /// Give the model the stdout and stderr of shell commands in separate,
+    /// labeled sections instead of interleaved.
+    LabeledExecStreams,
+    /// Run tool calls from one response in parallel even when they may
+    /// write, serializing only writes to the same working directory.
+    ParallelToolExecution,
+    /// Check function tool arguments against the tool's schema before the
+    /// handler runs, and report every problem to the model.
+    ArgumentValidation,
+    /// Include the `git` tool, which answers read-only status, diff, log and
+    /// blame queries with structured data.
+    GitTool,
+    /// Repair tool call arguments that are nearly valid JSON, such as ones
+    /// with trailing commas or single quotes, instead of rejecting them.
+    JsonRepair,
+    /// Include the `web_fetch` tool, which fetches URLs in-process with
+    /// per-host approval instead of `curl` through the shell.
+    WebFetchTool,
+    /// Publish the schema of structured tool output, such as `git` results
+    /// and MCP tools' output schemas, in the tool definitions.
+    ToolOutputSchemas,
+    /// Include the `search` tool, which searches workspace files in-process
+    /// instead of running `rg` or `grep` through the shell.
+    SearchTool,
+    /// Run commands with a pinned environment and fixed seeds, and record
+    /// them and the session's inputs in a bundle they can be replayed from.
+    ReproducibleExec,
+    /// Ask about commands that need approval at the same time in one
+    /// prompt instead of one after another.
+    BatchApprovals,
 }
 
 impl Feature {
@@ -306,7 +403,7 @@ pub const FEATURES: &[FeatureSpec] = &[
         id: Feature::WindowsSandbox,
         key: "enable_experimental_windows_sandbox",
         stage: Stage::Experimental,
-        default_enabled: false,
+        default_enabled: true,
     },
     FeatureSpec {
         id: Feature::RemoteCompaction,
@@ -326,4 +423,244 @@ pub const FEATURES: &[FeatureSpec] = &[
         stage: Stage::Stable,
         default_enabled: true,
     },
+    FeatureSpec {
+        id: Feature::CoverageTool,
+        key: "coverage_tool",
+        stage: Stage::Experimental,
+        default_enabled: false,
+    },
+    FeatureSpec {
+        id: Feature::TestRunnerTool,
+        key: "test_runner_tool",
+        stage: Stage::Experimental,
+        default_enabled: false,
+    },
+    FeatureSpec {
+        id: Feature::BenchTool,
+        key: "bench_tool",
+        stage: Stage::Experimental,
+        default_enabled: false,
+    },
+    FeatureSpec {
+        id: Feature::ProcessTool,
+        key: "process_tool",
+        stage: Stage::Experimental,
+        default_enabled: false,
+    },
+    FeatureSpec {
+        id: Feature::PortTool,
+        key: "port_tool",
+        stage: Stage::Experimental,
+        default_enabled: false,
+    },
+    FeatureSpec {
+        id: Feature::WaitForTool,
+        key: "wait_for_tool",
+        stage: Stage::Experimental,
+        default_enabled: false,
+    },
+    FeatureSpec {
+        id: Feature::ComposeTool,
+        key: "compose_tool",
+        stage: Stage::Experimental,
+        default_enabled: false,
+    },
+    FeatureSpec {
+        id: Feature::EnvSnapshotTool,
+        key: "env_snapshot_tool",
+        stage: Stage::Experimental,
+        default_enabled: false,
+    },
+    FeatureSpec {
+        id: Feature::FileInfoTool,
+        key: "file_info_tool",
+        stage: Stage::Experimental,
+        default_enabled: false,
+    },
+    FeatureSpec {
+        id: Feature::FindReplaceTool,
+        key: "find_replace_tool",
+        stage: Stage::Experimental,
+        default_enabled: false,
+    },
+    FeatureSpec {
+        id: Feature::ScaffoldTool,
+        key: "scaffold_tool",
+        stage: Stage::Experimental,
+        default_enabled: false,
+    },
+    FeatureSpec {
+        id: Feature::CodemodTool,
+        key: "codemod_tool",
+        stage: Stage::Experimental,
+        default_enabled: false,
+    },
+    FeatureSpec {
+        id: Feature::MigrateTool,
+        key: "migrate_tool",
+        stage: Stage::Experimental,
+        default_enabled: false,
+    },
+    FeatureSpec {
+        id: Feature::ArtifactsTool,
+        key: "artifacts_tool",
+        stage: Stage::Experimental,
+        default_enabled: false,
+    },
+    FeatureSpec {
+        id: Feature::ClipboardTool,
+        key: "clipboard_tool",
+        stage: Stage::Experimental,
+        default_enabled: false,
+    },
+    FeatureSpec {
+        id: Feature::CondensedEvents,
+        key: "condensed_events",
+        stage: Stage::Experimental,
+        default_enabled: false,
+    },
+    FeatureSpec {
+        id: Feature::ReadAhead,
+        key: "read_ahead",
+        stage: Stage::Experimental,
+        default_enabled: false,
+    },
+    FeatureSpec {
+        id: Feature::ReadChangesTool,
+        key: "read_changes_tool",
+        stage: Stage::Experimental,
+        default_enabled: false,
+    },
+    FeatureSpec {
+        id: Feature::FileReadEvents,
+        key: "file_read_events",
+        stage: Stage::Experimental,
+        default_enabled: false,
+    },
+    FeatureSpec {
+        id: Feature::DirtyFileGuard,
+        key: "dirty_file_guard",
+        stage: Stage::Experimental,
+        default_enabled: false,
+    },
+    FeatureSpec {
+        id: Feature::WriteFileTool,
+        key: "write_file_tool",
+        stage: Stage::Experimental,
+        default_enabled: false,
+    },
+    FeatureSpec {
+        id: Feature::BackgroundJobs,
+        key: "background_jobs",
+        stage: Stage::Experimental,
+        default_enabled: false,
+    },
+    FeatureSpec {
+        id: Feature::SplitCommandChains,
+        key: "split_command_chains",
+        stage: Stage::Experimental,
+        default_enabled: false,
+    },
+    FeatureSpec {
+        id: Feature::CommandNetwork,
+        key: "command_network",
+        stage: Stage::Experimental,
+        default_enabled: false,
+    },
+    FeatureSpec {
+        id: Feature::Trash,
+        key: "trash",
+        stage: Stage::Experimental,
+        default_enabled: false,
+    },
+    FeatureSpec {
+        id: Feature::UndoPatch,
+        key: "undo_patch",
+        stage: Stage::Experimental,
+        default_enabled: false,
+    },
+    FeatureSpec {
+        id: Feature::Quarantine,
+        key: "quarantine",
+        stage: Stage::Experimental,
+        default_enabled: false,
+    },
+    FeatureSpec {
+        id: Feature::EnvironmentDiff,
+        key: "environment_diff",
+        stage: Stage::Experimental,
+        default_enabled: false,
+    },
+    FeatureSpec {
+        id: Feature::WorkspaceIndex,
+        key: "workspace_index",
+        stage: Stage::Experimental,
+        default_enabled: false,
+    },
+    FeatureSpec {
+        id: Feature::PatchMerge,
+        key: "patch_merge",
+        stage: Stage::Experimental,
+        default_enabled: false,
+    },
+    FeatureSpec {
+        id: Feature::LabeledExecStreams,
+        key: "labeled_exec_streams",
+        stage: Stage::Experimental,
+        default_enabled: false,
+    },
+    FeatureSpec {
+        id: Feature::ParallelToolExecution,
+        key: "parallel_tool_execution",
+        stage: Stage::Experimental,
+        default_enabled: false,
+    },
+    FeatureSpec {
+        id: Feature::ArgumentValidation,
+        key: "argument_validation",
+        stage: Stage::Experimental,
+        default_enabled: false,
+    },
+    FeatureSpec {
+        id: Feature::GitTool,
+        key: "git_tool",
+        stage: Stage::Experimental,
+        default_enabled: false,
+    },
+    FeatureSpec {
+        id: Feature::JsonRepair,
+        key: "json_repair",
+        stage: Stage::Experimental,
+        default_enabled: false,
+    },
+    FeatureSpec {
+        id: Feature::WebFetchTool,
+        key: "web_fetch_tool",
+        stage: Stage::Experimental,
+        default_enabled: false,
+    },
+    FeatureSpec {
+        id: Feature::ToolOutputSchemas,
+        key: "tool_output_schemas",
+        stage: Stage::Experimental,
+        default_enabled: false,
+    },
+    FeatureSpec {
+        id: Feature::SearchTool,
+        key: "search_tool",
+        stage: Stage::Experimental,
+        default_enabled: false,
+    },
+    FeatureSpec {
+        id: Feature::ReproducibleExec,
+        key: "reproducible_exec",
+        stage: Stage::Experimental,
+        default_enabled: false,
+    },
+    FeatureSpec {
+        id: Feature::BatchApprovals,
+        key: "batch_approvals",
+        stage: Stage::Experimental,
+        default_enabled: false,
+    },
 ];
@@ -0,0 +1,245 @@
+//! Typed placeholders returned in place of the contents of binary or huge files.
+//!
+//! Tools that read, search or diff files describe such files the same way: by
+//! kind, size, content hash and a classification of their first bytes. The
+//! model then sees what the file is instead of mangled bytes or a read error,
+//! and can decide whether to inspect it with a dedicated command.
+
+use std::fmt;
+use std::path::Path;
+
+use serde::Serialize;
+use sha2::Digest;
+use sha2::Sha256;
+use tokio::io::AsyncReadExt;
+
+/// Text files larger than this are not returned inline.
+pub(crate) const HUGE_FILE_BYTES: u64 = 32 * 1024 * 1024;
+
+/// How much of a file is inspected to classify it.
+pub(crate) const SNIFF_BYTES: u64 = 8 * 1024;
+
+/// Files larger than this are not hashed, which would mean reading them in
+/// full just to describe them.
+const MAX_HASHED_BYTES: u64 = 256 * 1024 * 1024;
+
+const HASH_CHUNK_BYTES: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum PlaceholderKind {
+    Binary,
+    Huge,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub(crate) struct FilePlaceholder {
+    pub(crate) kind: PlaceholderKind,
+    pub(crate) size: u64,
+    /// Hex SHA-256 of the contents, or `None` when the file is too large to
+    /// hash.
+    pub(crate) sha256: Option<String>,
+    /// What the first bytes look like, e.g. `png image` or `utf-8 text`.
+    pub(crate) detected: &'static str,
+}
+
+impl fmt::Display for FilePlaceholder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self.kind {
+            PlaceholderKind::Binary => "binary",
+            PlaceholderKind::Huge => "huge",
+        };
+        let FilePlaceholder { size, detected, .. } = self;
+        write!(f, "[{kind} file omitted: {detected}, {size} bytes, ")?;
+        match &self.sha256 {
+            Some(sha256) => write!(f, "sha256 {sha256}]"),
+            None => write!(f, "too large to hash]"),
+        }
+    }
+}
+
+/// Describe `path` if its contents should not be returned inline: when it is
+/// binary or larger than [`HUGE_FILE_BYTES`]. Returns `Ok(None)` for ordinary
+/// text files and for anything that is not a regular file.
+pub(crate) async fn inspect(path: &Path) -> std::io::Result<Option<FilePlaceholder>> {
+    let metadata = tokio::fs::metadata(path).await?;
+    if !metadata.is_file() {
+        return Ok(None);
+    }
+    let size = metadata.len();
+
+    let mut head = Vec::new();
+    tokio::fs::File::open(path)
+        .await?
+        .take(SNIFF_BYTES)
+        .read_to_end(&mut head)
+        .await?;
+    let (detected, binary) = classify(&head);
+    let kind = if binary {
+        PlaceholderKind::Binary
+    } else if size > HUGE_FILE_BYTES {
+        PlaceholderKind::Huge
+    } else {
+        return Ok(None);
+    };
+
+    let sha256 = if size <= MAX_HASHED_BYTES {
+        Some(hash_file(path).await?)
+    } else {
+        None
+    };
+    Ok(Some(FilePlaceholder {
+        kind,
+        size,
+        sha256,
+        detected,
+    }))
+}
+
+/// Like [`inspect`] for contents already in memory.
+pub(crate) fn inspect_bytes(contents: &[u8]) -> Option<FilePlaceholder> {
+    let head = &contents[..contents.len().min(SNIFF_BYTES as usize)];
+    let (detected, binary) = classify(head);
+    let size = contents.len() as u64;
+    let kind = if binary {
+        PlaceholderKind::Binary
+    } else if size > HUGE_FILE_BYTES {
+        PlaceholderKind::Huge
+    } else {
+        return None;
+    };
+    Some(FilePlaceholder {
+        kind,
+        size,
+        sha256: Some(format!("{:x}", Sha256::digest(contents))),
+        detected,
+    })
+}
+
+const SIGNATURES: &[(&[u8], &str)] = &[
+    (b"\x89PNG\r\n\x1a\n", "png image"),
+    (b"\xff\xd8\xff", "jpeg image"),
+    (b"GIF87a", "gif image"),
+    (b"GIF89a", "gif image"),
+    (b"%PDF-", "pdf document"),
+    (b"PK\x03\x04", "zip archive"),
+    (b"\x1f\x8b", "gzip archive"),
+    (b"\xfd7zXZ\x00", "xz archive"),
+    (b"BZh", "bzip2 archive"),
+    (b"\x28\xb5\x2f\xfd", "zstd archive"),
+    (b"7z\xbc\xaf\x27\x1c", "7z archive"),
+    (b"\x7fELF", "elf executable"),
+    (b"\xfe\xed\xfa\xce", "mach-o executable"),
+    (b"\xfe\xed\xfa\xcf", "mach-o executable"),
+    (b"\xce\xfa\xed\xfe", "mach-o executable"),
+    (b"\xcf\xfa\xed\xfe", "mach-o executable"),
+    (b"MZ", "windows executable"),
+    (b"\x00asm", "wasm module"),
+    (b"SQLite format 3\x00", "sqlite database"),
+    (b"\xff\xfe", "utf-16 text"),
+    (b"\xfe\xff", "utf-16 text"),
+];
+
+/// Classify the first bytes of a file, returning a short description and
+/// whether the file is binary for the purposes of the text tools.
+pub(crate) fn classify(head: &[u8]) -> (&'static str, bool) {
+    if head.starts_with(b"RIFF") && head.get(8..12) == Some(b"WEBP".as_slice()) {
+        return ("webp image", true);
+    }
+    if let Some((_, detected)) = SIGNATURES
+        .iter()
+        .find(|(signature, _)| head.starts_with(signature))
+    {
+        return (detected, true);
+    }
+    if head.contains(&0) {
+        return ("unknown binary", true);
+    }
+    match std::str::from_utf8(head) {
+        Ok(_) => ("utf-8 text", false),
+        // The sniffed prefix may end in the middle of a character.
+        Err(err) if err.error_len().is_none() => ("utf-8 text", false),
+        Err(_) => ("non-utf-8 text", false),
+    }
+}
+
+async fn hash_file(path: &Path) -> std::io::Result<String> {
+    let mut file = tokio::fs::File::open(path).await?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0; HASH_CHUNK_BYTES];
+    loop {
+        let read = file.read(&mut buf).await?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buf[..read]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    #[test]
+    fn classifies_common_formats() {
+        let cases: [(&[u8], (&str, bool)); 7] = [
+            (b"\x89PNG\r\n\x1a\n\x00\x00\x00\rIHDR", ("png image", true)),
+            (b"%PDF-1.7\n%\xe2\xe3\xcf\xd3", ("pdf document", true)),
+            (b"\x7fELF\x02\x01\x01", ("elf executable", true)),
+            (b"RIFF\x24\x00\x00\x00WEBPVP8 ", ("webp image", true)),
+            (b"abc\x00def", ("unknown binary", true)),
+            (
+                "fn main() {}\n// caf\u{e9}".as_bytes(),
+                ("utf-8 text", false),
+            ),
+            (b"caf\xe9 au lait", ("non-utf-8 text", false)),
+        ];
+        for (head, expected) in cases {
+            assert_eq!(classify(head), expected);
+        }
+        // A multi-byte character cut off by the sniff window is still text.
+        assert_eq!(
+            classify(&"caf\u{e9}".as_bytes()[..4]),
+            ("utf-8 text", false)
+        );
+    }
+
+    #[tokio::test]
+    async fn describes_binary_files_and_skips_text() {
+        let dir = TempDir::new().expect("tempdir");
+        let image = dir.path().join("logo.png");
+        let contents = b"\x89PNG\r\n\x1a\n\x00\x00\x00\rIHDR".to_vec();
+        std::fs::write(&image, &contents).expect("write image");
+        let source = dir.path().join("main.rs");
+        std::fs::write(&source, "fn main() {}\n").expect("write source");
+
+        let placeholder = inspect(&image).await.expect("inspect");
+        let expected = FilePlaceholder {
+            kind: PlaceholderKind::Binary,
+            size: contents.len() as u64,
+            sha256: Some(format!("{:x}", Sha256::digest(&contents))),
+            detected: "png image",
+        };
+        assert_eq!(placeholder, Some(expected.clone()));
+        assert_eq!(inspect_bytes(&contents), Some(expected));
+        assert_eq!(inspect(&source).await.expect("inspect"), None);
+        assert_eq!(inspect(dir.path()).await.expect("inspect"), None);
+    }
+
+    #[test]
+    fn renders_as_a_single_line() {
+        let placeholder = FilePlaceholder {
+            kind: PlaceholderKind::Huge,
+            size: 40_000_000,
+            sha256: None,
+            detected: "utf-8 text",
+        };
+        assert_eq!(
+            placeholder.to_string(),
+            "[huge file omitted: utf-8 text, 40000000 bytes, too large to hash]"
+        );
+    }
+}
@@ -0,0 +1,406 @@
+//! Conversion of HTML pages to Markdown for the model.
+//!
+//! A small converter for what `web_fetch` returns: it keeps headings,
+//! paragraphs, lists, links, emphasis, code and preformatted blocks, drops
+//! the document head, scripts and styles, and decodes character references.
+//! It does not build a document tree, so malformed markup degrades to plain
+//! text instead of failing.
+
+use url::Url;
+
+/// Elements whose content is never shown.
+const SKIPPED: &[&str] = &[
+    "head", "iframe", "noscript", "script", "style", "svg", "template",
+];
+
+/// Elements that start and end a paragraph.
+const BLOCKS: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "dd",
+    "details",
+    "div",
+    "dl",
+    "dt",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "header",
+    "main",
+    "nav",
+    "p",
+    "section",
+    "summary",
+    "table",
+];
+
+struct Tag<'a> {
+    name: String,
+    closing: bool,
+    attributes: &'a str,
+}
+
+/// Convert `html` to Markdown, resolving relative links against `base`.
+pub(crate) fn html_to_markdown(html: &str, base: &Url) -> String {
+    let mut converter = Converter {
+        base,
+        out: String::new(),
+        pre: 0,
+        lists: Vec::new(),
+        links: Vec::new(),
+    };
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        converter.text(&rest[..start]);
+        rest = &rest[start..];
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+            continue;
+        }
+        // A `<` that does not open a tag is text, as in `a < b`.
+        if !rest[1..].starts_with(|c: char| c.is_ascii_alphabetic() || c == '/' || c == '!') {
+            converter.text("<");
+            rest = &rest[1..];
+            continue;
+        }
+        let Some(end) = rest.find('>') else {
+            rest = "";
+            break;
+        };
+        let tag = parse_tag(&rest[1..end]);
+        rest = &rest[end + 1..];
+        // Declarations such as `<!DOCTYPE html>` parse as no tag.
+        let Some(tag) = tag else {
+            continue;
+        };
+        if !tag.closing && SKIPPED.contains(&tag.name.as_str()) {
+            let close = format!("</{}", tag.name);
+            rest = match rest.to_ascii_lowercase().find(&close) {
+                Some(index) => rest[index..]
+                    .find('>')
+                    .map_or("", |end| &rest[index + end + 1..]),
+                None => "",
+            };
+            continue;
+        }
+        converter.tag(&tag);
+    }
+    converter.text(rest);
+    converter.finish()
+}
+
+fn parse_tag(inner: &str) -> Option<Tag<'_>> {
+    let (closing, inner) = match inner.strip_prefix('/') {
+        Some(inner) => (true, inner),
+        None => (false, inner),
+    };
+    let end = inner
+        .find(|c: char| c.is_whitespace() || c == '/')
+        .unwrap_or(inner.len());
+    let name = inner[..end].to_ascii_lowercase();
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    Some(Tag {
+        name,
+        closing,
+        attributes: &inner[end..],
+    })
+}
+
+/// The value of the attribute `name`, decoded.
+fn attribute(attributes: &str, name: &str) -> Option<String> {
+    let lower = attributes.to_ascii_lowercase();
+    let mut from = 0;
+    while let Some(found) = lower[from..].find(name) {
+        let start = from + found;
+        from = start + name.len();
+        let after = lower[from..].trim_start();
+        if !lower[..start].ends_with(char::is_whitespace) || !after.starts_with('=') {
+            continue;
+        }
+        let value = attributes[attributes.len() - after.len() + 1..].trim_start();
+        let value = match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => value[1..].split(quote).next(),
+            _ => value.split(char::is_whitespace).next(),
+        };
+        return value.map(decode_entities);
+    }
+    None
+}
+
+/// Decode the character references in `text`; unknown ones are kept as is.
+pub(crate) fn decode_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let decoded = rest[1..]
+            .find(';')
+            .filter(|end| *end <= 10)
+            .and_then(|end| Some((entity(&rest[1..1 + end])?, end + 2)));
+        match decoded {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn entity(name: &str) -> Option<char> {
+    let c = match name {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => '\u{a0}',
+        "ndash" => '\u{2013}',
+        "mdash" => '\u{2014}',
+        "hellip" => '\u{2026}',
+        "copy" => '\u{a9}',
+        _ => {
+            let number = name.strip_prefix('#')?;
+            let code = match number.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => number.parse().ok()?,
+            };
+            return char::from_u32(code);
+        }
+    };
+    Some(c)
+}
+
+struct Converter<'a> {
+    base: &'a Url,
+    out: String,
+    /// Depth of `<pre>` elements, inside which text is kept verbatim.
+    pre: usize,
+    /// Open lists, with the next number of an ordered one.
+    lists: Vec<Option<usize>>,
+    /// Open links: where their text starts in `out`, and their target.
+    links: Vec<(usize, Option<String>)>,
+}
+
+impl Converter<'_> {
+    fn text(&mut self, text: &str) {
+        let text = decode_entities(text);
+        if self.pre > 0 {
+            // A newline right after `<pre>` is not part of the content.
+            let text = if self.out.ends_with("```\n") {
+                text.strip_prefix('\n').unwrap_or(&text)
+            } else {
+                &text
+            };
+            self.out.push_str(text);
+            return;
+        }
+        for c in text.chars() {
+            if !c.is_whitespace() {
+                self.out.push(c);
+            } else if !self.out.is_empty() && !self.out.ends_with(char::is_whitespace) {
+                self.out.push(' ');
+            }
+        }
+    }
+
+    fn tag(&mut self, tag: &Tag) {
+        let name = tag.name.as_str();
+        match (name, tag.closing) {
+            ("h1" | "h2" | "h3" | "h4" | "h5" | "h6", false) => {
+                self.block();
+                let level = name[1..].parse().unwrap_or(1);
+                self.out.push_str(&"#".repeat(level));
+                self.out.push(' ');
+            }
+            ("h1" | "h2" | "h3" | "h4" | "h5" | "h6", true) => self.block(),
+            ("br", _) => self.out.push('\n'),
+            ("hr", false) => {
+                self.block();
+                self.out.push_str("---");
+                self.block();
+            }
+            ("pre", false) => {
+                self.block();
+                self.out.push_str("```\n");
+                self.pre += 1;
+            }
+            ("pre", true) if self.pre > 0 => {
+                self.pre -= 1;
+                self.line();
+                self.out.push_str("```");
+                self.block();
+            }
+            ("ul" | "ol", false) => {
+                if self.lists.is_empty() {
+                    self.block();
+                } else {
+                    self.line();
+                }
+                let start = attribute(tag.attributes, "start").and_then(|start| start.parse().ok());
+                self.lists.push((name == "ol").then(|| start.unwrap_or(1)));
+            }
+            ("ul" | "ol", true) => {
+                self.lists.pop();
+                if self.lists.is_empty() {
+                    self.block();
+                } else {
+                    self.line();
+                }
+            }
+            ("li", false) => {
+                self.line();
+                let depth = self.lists.len().saturating_sub(1);
+                self.out.push_str(&"  ".repeat(depth));
+                match self.lists.last_mut() {
+                    Some(Some(number)) => {
+                        self.out.push_str(&format!("{number}. "));
+                        *number += 1;
+                    }
+                    _ => self.out.push_str("- "),
+                }
+            }
+            ("li" | "tr", _) => self.line(),
+            ("td" | "th", false) if !self.out.ends_with('\n') => self.out.push_str(" | "),
+            ("a", false) => {
+                let href = attribute(tag.attributes, "href");
+                self.links.push((self.out.len(), href));
+                self.out.push('[');
+            }
+            ("a", true) => {
+                if let Some((start, href)) = self.links.pop() {
+                    self.close_link(start, href);
+                }
+            }
+            ("img", false) => {
+                if let Some(src) = attribute(tag.attributes, "src") {
+                    let alt = attribute(tag.attributes, "alt").unwrap_or_default();
+                    let src = self.resolve(&src);
+                    self.out.push_str(&format!("![{alt}]({src})"));
+                }
+            }
+            ("strong" | "b", _) if self.pre == 0 => self.out.push_str("**"),
+            ("em" | "i", _) if self.pre == 0 => self.out.push('*'),
+            ("code", _) if self.pre == 0 => self.out.push('`'),
+            (name, _) if BLOCKS.contains(&name) => self.block(),
+            _ => {}
+        }
+    }
+
+    /// End a link opened at `start`: a link with text and a target becomes
+    /// `[text](target)`, and any other is left as its text.
+    fn close_link(&mut self, start: usize, href: Option<String>) {
+        let has_text = !self.out[start + 1..].trim().is_empty();
+        match href {
+            Some(href)
+                if has_text && !href.starts_with('#') && !href.starts_with("javascript:") =>
+            {
+                let href = self.resolve(&href);
+                self.out.push_str(&format!("]({href})"));
+            }
+            _ => {
+                self.out.remove(start);
+            }
+        }
+    }
+
+    fn resolve(&self, href: &str) -> String {
+        self.base
+            .join(href)
+            .map_or_else(|_| href.to_string(), String::from)
+    }
+
+    /// Start a new paragraph.
+    fn block(&mut self) {
+        self.line();
+        if !self.out.is_empty() && !self.out.ends_with("\n\n") {
+            self.out.push('\n');
+        }
+    }
+
+    /// Start a new line.
+    fn line(&mut self) {
+        let trimmed = self.out.trim_end_matches(' ').len();
+        self.out.truncate(trimmed);
+        if !self.out.is_empty() && !self.out.ends_with('\n') {
+            self.out.push('\n');
+        }
+    }
+
+    /// The Markdown, without trailing spaces or runs of blank lines.
+    fn finish(self) -> String {
+        let mut markdown = String::with_capacity(self.out.len());
+        let mut blank = false;
+        for line in self.out.lines() {
+            let line = line.trim_end();
+            if line.is_empty() && blank {
+                continue;
+            }
+            blank = line.is_empty();
+            markdown.push_str(line);
+            markdown.push('\n');
+        }
+        markdown.trim().to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn pages_become_markdown() {
+        let html = r##"<!DOCTYPE html>
+<html><head><title>Docs</title><style>p { color: red }</style></head>
+<body>
+  <h1>Getting   started</h1>
+  <!-- navigation -->
+  <p>Install with <code>cargo install</code>, then read the
+     <a href="/guide">guide</a> &amp; the <A HREF='https://example.org/faq'>FAQ</A>.</p>
+  <script>alert("hi")</script>
+  <ul><li>One <b>bold</b> item</li><li>Two<ol start="3"><li>Nested</li></ol></li></ul>
+  <pre>
+fn main() {
+    println!("a &lt; b");
+}
+</pre>
+  <p>1 < 2 and <a href="#top"></a>an <img src="logo.png" alt="logo">.</p>
+</body></html>"##;
+        let base = Url::parse("https://example.com/docs/index.html").expect("url");
+        assert_eq!(
+            html_to_markdown(html, &base),
+            "# Getting started\n\n\
+             Install with `cargo install`, then read the [guide](https://example.com/guide) & \
+             the [FAQ](https://example.org/faq).\n\n\
+             - One **bold** item\n\
+             - Two\n  \
+             3. Nested\n\n\
+             ```\n\
+             fn main() {\n    \
+             println!(\"a < b\");\n\
+             }\n\
+             ```\n\n\
+             1 < 2 and an ![logo](https://example.com/docs/logo.png)."
+        );
+    }
+
+    #[test]
+    fn character_references_are_decoded() {
+        assert_eq!(
+            decode_entities("&lt;a&gt; &#169; &#x2014; &unknown; & AT&T"),
+            "<a> \u{a9} \u{2014} &unknown; & AT&T"
+        );
+    }
+}
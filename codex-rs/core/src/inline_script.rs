@@ -0,0 +1,293 @@
+//! Scripts embedded in commands.
+//!
+//! A command like `python3 -c '...'`, or a `bash -lc` script feeding a
+//! heredoc to an interpreter, runs code that its argv only shows as one long
+//! quoted word. The scripts are pulled out here so approval prompts can show
+//! them as code, and risk checks can look at what actually runs.
+
+use std::path::Path;
+
+use codex_protocol::protocol::InlineScript;
+
+use crate::bash::extract_bash_command;
+use crate::bash::parse_shell_lc_plain_commands;
+use crate::rendered_diff::language_for_path;
+use crate::syntax_highlight::highlight_lines;
+
+struct FoundScript {
+    language: Option<&'static str>,
+    source: String,
+    /// Whether the script is executed, rather than, say, written to a file.
+    runs: bool,
+}
+
+/// The scripts embedded in `command`, highlighted where a grammar is
+/// available.
+pub(crate) fn inline_scripts(command: &[String]) -> Vec<InlineScript> {
+    find_scripts(command)
+        .into_iter()
+        .map(|script| {
+            let lines: Vec<&str> = script.source.lines().collect();
+            let tokens = script
+                .language
+                .and_then(|language| highlight_lines(language, &lines))
+                .unwrap_or_default();
+            InlineScript {
+                language: script.language.map(str::to_string),
+                source: script.source,
+                tokens,
+            }
+        })
+        .collect()
+}
+
+/// Shell scripts that `command` runs by feeding them to a shell, such as the
+/// body of `bash <<EOF`.
+pub(crate) fn shell_scripts_run(command: &[String]) -> Vec<String> {
+    find_scripts(command)
+        .into_iter()
+        .filter(|script| script.runs && script.language == Some("bash"))
+        .map(|script| script.source)
+        .collect()
+}
+
+fn find_scripts(command: &[String]) -> Vec<FoundScript> {
+    let mut scripts = Vec::new();
+    collect_scripts(command, &mut scripts);
+    scripts.retain(|script| !script.source.trim().is_empty());
+    scripts
+}
+
+fn collect_scripts(command: &[String], scripts: &mut Vec<FoundScript>) {
+    if let Some((_, script)) = extract_bash_command(command) {
+        match parse_shell_lc_plain_commands(command) {
+            // A sequence of plain commands reads fine as a command line, but
+            // the commands may carry scripts of their own.
+            Some(commands) => {
+                for command in commands {
+                    collect_scripts(&command, scripts);
+                }
+            }
+            None => {
+                scripts.push(FoundScript {
+                    language: Some("bash"),
+                    source: script.to_string(),
+                    runs: true,
+                });
+                scripts.extend(heredocs(script));
+            }
+        }
+        return;
+    }
+    if let Some(script) = eval_argument(command) {
+        scripts.push(script);
+    }
+}
+
+/// The script passed with an interpreter's "evaluate this" flag, as in
+/// `python3 -c '...'` or `node -e '...'`.
+fn eval_argument(command: &[String]) -> Option<FoundScript> {
+    let (program, args) = command.split_first()?;
+    let language = language_for_program(program)?;
+    let flags: &[&str] = match language {
+        "python" => &["-c"],
+        "javascript" => &["-e", "--eval"],
+        "ruby" => &["-e"],
+        "perl" => &["-e", "-E"],
+        _ => return None,
+    };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if flags.contains(&arg.as_str()) {
+            return args.next().map(|source| FoundScript {
+                language: Some(language),
+                source: source.clone(),
+                runs: true,
+            });
+        }
+        // The first operand is a script file; what follows belongs to it.
+        if !arg.starts_with('-') {
+            return None;
+        }
+    }
+    None
+}
+
+fn language_for_program(program: &str) -> Option<&'static str> {
+    let name = Path::new(program).file_name()?.to_str()?;
+    // `python3.12` and `perl5` are the interpreters they are versions of.
+    match name.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.') {
+        "python" => Some("python"),
+        "node" => Some("javascript"),
+        "ruby" => Some("ruby"),
+        "perl" => Some("perl"),
+        "bash" | "sh" | "zsh" => Some("bash"),
+        "psql" | "sqlite" | "mysql" => Some("sql"),
+        _ => None,
+    }
+}
+
+/// The heredocs in a shell script. Only the first heredoc started on a line
+/// is recognized.
+fn heredocs(script: &str) -> Vec<FoundScript> {
+    let mut found = Vec::new();
+    let mut lines = script.lines();
+    while let Some(line) = lines.next() {
+        let Some(start) = HeredocStart::parse(line) else {
+            continue;
+        };
+        let mut body = Vec::new();
+        for line in lines.by_ref() {
+            let line = if start.strip_tabs {
+                line.trim_start_matches('\t')
+            } else {
+                line
+            };
+            if line == start.delimiter {
+                break;
+            }
+            body.push(line);
+        }
+        let program_language = start.program.and_then(language_for_program);
+        let target_language = start
+            .target
+            .and_then(|target| language_for_path(Path::new(target)));
+        found.push(FoundScript {
+            language: program_language.or(target_language),
+            source: body.join("\n"),
+            runs: program_language.is_some(),
+        });
+    }
+    found
+}
+
+struct HeredocStart<'a> {
+    delimiter: String,
+    /// `<<-` strips leading tabs from the body and the delimiter line.
+    strip_tabs: bool,
+    /// The command the heredoc is fed to.
+    program: Option<&'a str>,
+    /// The file the command's output is redirected to, if any.
+    target: Option<&'a str>,
+}
+
+impl<'a> HeredocStart<'a> {
+    fn parse(line: &'a str) -> Option<Self> {
+        let operator = line.find("<<")?;
+        let rest = &line[operator + 2..];
+        // `<<<` is a here-string, which has no body on the following lines.
+        if rest.starts_with('<') {
+            return None;
+        }
+        let (strip_tabs, rest) = match rest.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, rest),
+        };
+        let delimiter = heredoc_delimiter(rest.trim_start())?;
+
+        let statement = line[..operator]
+            .rsplit([';', '&', '|', '('])
+            .next()
+            .unwrap_or_default();
+        let program = statement
+            .split_whitespace()
+            .find(|word| !word.contains('='));
+        let target = line.split_once('>').and_then(|(_, after)| {
+            after
+                .trim_start_matches('>')
+                .split_whitespace()
+                .next()
+                .filter(|word| !word.starts_with(['&', '<']))
+        });
+        Some(Self {
+            delimiter,
+            strip_tabs,
+            program,
+            target,
+        })
+    }
+}
+
+/// The delimiter word at the start of `text`, without the quotes that stop
+/// the body from being expanded.
+fn heredoc_delimiter(text: &str) -> Option<String> {
+    let delimiter: String = match text.chars().next()? {
+        quote @ ('\'' | '"') => text[1..].chars().take_while(|c| *c != quote).collect(),
+        _ => text
+            .chars()
+            .take_while(|c| c.is_alphanumeric() || *c == '_')
+            .collect(),
+    };
+    (!delimiter.is_empty()).then_some(delimiter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn argv(words: &[&str]) -> Vec<String> {
+        words.iter().map(ToString::to_string).collect()
+    }
+
+    fn summary(command: &[String]) -> Vec<(Option<String>, String)> {
+        inline_scripts(command)
+            .into_iter()
+            .map(|script| (script.language, script.source))
+            .collect()
+    }
+
+    #[test]
+    fn interpreter_eval_flags_are_extracted() {
+        assert_eq!(
+            summary(&argv(&[
+                "python3",
+                "-u",
+                "-c",
+                "import os\nprint(os.getcwd())"
+            ])),
+            vec![(
+                Some("python".to_string()),
+                "import os\nprint(os.getcwd())".to_string()
+            )]
+        );
+        assert_eq!(
+            summary(&argv(&["bash", "-lc", "node -e 'console.log(1)' && ls"])),
+            vec![(Some("javascript".to_string()), "console.log(1)".to_string())]
+        );
+        // A script file is not inline, and neither is a plain command line.
+        assert_eq!(summary(&argv(&["python3", "build.py", "-c", "x"])), vec![]);
+        assert_eq!(summary(&argv(&["bash", "-lc", "cargo test"])), vec![]);
+    }
+
+    #[test]
+    fn heredocs_are_extracted_with_their_language() {
+        let script = [
+            "cat > setup.py <<'EOF'",
+            "print('hi')",
+            "EOF",
+            "python3 - <<-PY",
+            "\timport sys",
+            "\tPY",
+            "echo done",
+        ]
+        .join("\n");
+        assert_eq!(
+            summary(&argv(&["bash", "-lc", &script])),
+            vec![
+                (Some("bash".to_string()), script.clone()),
+                (Some("python".to_string()), "print('hi')".to_string()),
+                (Some("python".to_string()), "import sys".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn only_scripts_fed_to_a_shell_count_as_run() {
+        let script = "cat > clean.sh <<EOF\nrm -rf build\nEOF\nsh <<EOF\nrm -rf dist\nEOF";
+        assert_eq!(
+            shell_scripts_run(&argv(&["bash", "-lc", script])),
+            vec![script.to_string(), "rm -rf dist".to_string()]
+        );
+    }
+}
@@ -0,0 +1,329 @@
+//! Workspace integrity check run when a session ends.
+//!
+//! Commands that are interrupted, time out or crash can leave state behind
+//! that later confuses the user or the next session: git refuses to run while
+//! `index.lock` exists, `patch` and `git apply --reject` leave `.orig` and
+//! `.rej` files next to the sources, and jobs started with `&` or `nohup`
+//! keep running after the shell that spawned them exited.
+//!
+//! Only state created since the session started is reported, so files and
+//! processes the user had before are left alone. Every finding carries a
+//! stable id and a cleanup action that [`clean_up`] performs on request.
+
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use codex_protocol::protocol::IntegrityFinding;
+use codex_protocol::protocol::IntegrityFindingKind;
+use tracing::warn;
+use walkdir::WalkDir;
+
+use crate::git_info::get_git_repo_root;
+use crate::tools::runtimes::process::KillSignal;
+use crate::tools::runtimes::process::ProcessScope;
+use crate::tools::runtimes::process::ScopedProcess;
+use crate::tools::runtimes::process::elapsed_seconds;
+use crate::tools::runtimes::process::list_scoped_processes;
+use crate::tools::runtimes::process::send_signal;
+use crate::tools::sandboxing::ToolError;
+
+/// Lock files git creates next to the state it is rewriting.
+const GIT_LOCK_FILES: [&str; 6] = [
+    "index.lock",
+    "HEAD.lock",
+    "ORIG_HEAD.lock",
+    "config.lock",
+    "packed-refs.lock",
+    "shallow.lock",
+];
+
+/// Extensions of the backup and reject files written by `patch` and
+/// `git apply --reject` when a hunk does not apply.
+const PATCH_LEFTOVER_EXTENSIONS: [&str; 2] = ["orig", "rej"];
+
+/// Directories that are never searched for patch leftovers.
+const SKIPPED_DIRS: [&str; 3] = [".git", "node_modules", "target"];
+
+/// Bound the walk so that huge workspaces do not delay shutdown.
+const MAX_SCANNED_ENTRIES: usize = 50_000;
+
+const MAX_COMMAND_CHARS: usize = 80;
+
+/// Look for lock files, patch leftovers and orphaned processes created in
+/// `cwd` since `session_started`.
+pub(crate) async fn check_workspace(
+    cwd: &Path,
+    session_started: SystemTime,
+) -> Vec<IntegrityFinding> {
+    let processes = list_scoped_processes(cwd).await.unwrap_or_else(|err| {
+        warn!("failed to list processes for the workspace check: {err}");
+        Vec::new()
+    });
+    // A lock held by a git command that is still running is not stale.
+    let git_running = processes.iter().any(|p| is_git(&p.info.command));
+
+    let root = cwd.to_path_buf();
+    let mut findings = tokio::task::spawn_blocking(move || {
+        let mut findings = if git_running {
+            Vec::new()
+        } else {
+            stale_git_locks(&root, session_started)
+        };
+        findings.extend(patch_leftovers(&root, session_started));
+        findings
+    })
+    .await
+    .unwrap_or_else(|err| {
+        warn!("workspace check failed: {err}");
+        Vec::new()
+    });
+
+    let session_age = session_started.elapsed().unwrap_or_default();
+    findings.extend(orphaned_processes(processes, session_age));
+    findings
+}
+
+/// Perform the cleanup action of `finding`.
+pub(crate) fn clean_up(finding: &IntegrityFinding) -> Result<(), String> {
+    if let Some(path) = &finding.path {
+        let removed = if path
+            .symlink_metadata()
+            .is_ok_and(|metadata| metadata.is_dir())
+        {
+            std::fs::remove_dir_all(path)
+        } else {
+            std::fs::remove_file(path)
+        };
+        return removed.map_err(|err| format!("failed to delete {}: {err}", path.display()));
+    }
+    let Some(pid) = finding.pid else {
+        return Err(format!("finding {} has no cleanup action", finding.id));
+    };
+    send_signal(pid, KillSignal::Term).map_err(|err| match err {
+        ToolError::Rejected(reason) => reason,
+        ToolError::Codex(err) => err.to_string(),
+    })
+}
+
+fn stale_git_locks(cwd: &Path, session_started: SystemTime) -> Vec<IntegrityFinding> {
+    let Some(git_dir) = get_git_repo_root(cwd).and_then(|root| git_dir(&root)) else {
+        return Vec::new();
+    };
+    GIT_LOCK_FILES
+        .iter()
+        .map(|name| git_dir.join(name))
+        .filter(|path| modified_since(path, session_started))
+        .map(|path| IntegrityFinding {
+            id: format!("lock:{}", path.display()),
+            kind: IntegrityFindingKind::StaleLockFile,
+            description: format!(
+                "git lock file {} was left behind; git commands in this repository fail until \
+                 it is removed",
+                path.display()
+            ),
+            path: Some(path),
+            pid: None,
+            cleanup: "delete the lock file".to_string(),
+        })
+        .collect()
+}
+
+/// The git directory of the repository at `root`, following the `gitdir:`
+/// indirection used by worktrees and submodules.
+fn git_dir(root: &Path) -> Option<PathBuf> {
+    let dot_git = root.join(".git");
+    if dot_git.is_dir() {
+        return Some(dot_git);
+    }
+    let contents = std::fs::read_to_string(&dot_git).ok()?;
+    let target = contents.strip_prefix("gitdir:")?.trim();
+    Some(root.join(target))
+}
+
+fn patch_leftovers(cwd: &Path, session_started: SystemTime) -> Vec<IntegrityFinding> {
+    WalkDir::new(cwd)
+        .into_iter()
+        .filter_entry(|entry| {
+            !(entry.file_type().is_dir()
+                && entry
+                    .file_name()
+                    .to_str()
+                    .is_some_and(|name| SKIPPED_DIRS.contains(&name)))
+        })
+        .take(MAX_SCANNED_ENTRIES)
+        .filter_map(Result::ok)
+        .filter(|entry| {
+            entry.file_type().is_file()
+                && entry
+                    .path()
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .is_some_and(|ext| PATCH_LEFTOVER_EXTENSIONS.contains(&ext))
+                && modified_since(entry.path(), session_started)
+        })
+        .map(|entry| {
+            let path = entry.into_path();
+            IntegrityFinding {
+                id: format!("leftover:{}", path.display()),
+                kind: IntegrityFindingKind::PatchLeftover,
+                description: format!(
+                    "{} looks like a leftover from a partially applied patch",
+                    path.display()
+                ),
+                path: Some(path),
+                pid: None,
+                cleanup: "delete the file".to_string(),
+            }
+        })
+        .collect()
+}
+
+/// Workspace processes that started during the session. Processes still in
+/// Codex's own process tree are excluded: they belong to running tools (MCP
+/// servers, unified exec sessions) and are stopped with the session.
+fn orphaned_processes(
+    processes: Vec<ScopedProcess>,
+    session_age: Duration,
+) -> Vec<IntegrityFinding> {
+    processes
+        .into_iter()
+        .filter(|p| {
+            p.scope == ProcessScope::Workspace
+                && elapsed_seconds(&p.info.elapsed)
+                    .is_some_and(|elapsed| elapsed <= session_age.as_secs())
+        })
+        .map(|p| {
+            let pid = p.info.pid;
+            let mut command: String = p.info.command.chars().take(MAX_COMMAND_CHARS).collect();
+            if p.info.command.chars().nth(MAX_COMMAND_CHARS).is_some() {
+                command.push('…');
+            }
+            IntegrityFinding {
+                id: format!("process:{pid}"),
+                kind: IntegrityFindingKind::OrphanedProcess,
+                description: format!(
+                    "process {pid} ({command}) was started during this session and is still \
+                     running"
+                ),
+                path: None,
+                pid: Some(pid),
+                cleanup: "terminate the process".to_string(),
+            }
+        })
+        .collect()
+}
+
+fn modified_since(path: &Path, since: SystemTime) -> bool {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .is_ok_and(|modified| modified >= since)
+}
+
+fn is_git(command: &str) -> bool {
+    command
+        .split_whitespace()
+        .next()
+        .and_then(|program| Path::new(program).file_name())
+        .is_some_and(|name| name == "git")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::runtimes::process::ProcessInfo;
+    use pretty_assertions::assert_eq;
+    use std::fs::File;
+    use tempfile::tempdir;
+
+    const HOUR: Duration = Duration::from_secs(60 * 60);
+
+    fn touch(path: &Path, modified: SystemTime) {
+        let file = File::create(path).expect("create file");
+        file.set_modified(modified).expect("set mtime");
+    }
+
+    #[test]
+    fn reports_files_created_during_the_session() {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path();
+        let started = SystemTime::now() - HOUR;
+        std::fs::create_dir_all(root.join(".git")).expect("create .git");
+        std::fs::create_dir_all(root.join("src")).expect("create src");
+        std::fs::create_dir_all(root.join("node_modules")).expect("create node_modules");
+        touch(&root.join(".git/index.lock"), SystemTime::now());
+        touch(&root.join(".git/config.lock"), started - HOUR);
+        touch(&root.join("src/lib.rs.rej"), SystemTime::now());
+        touch(&root.join("src/old.rs.orig"), started - HOUR);
+        touch(&root.join("node_modules/dep.js.orig"), SystemTime::now());
+
+        let lock = root.join(".git/index.lock");
+        assert_eq!(
+            stale_git_locks(root, started),
+            vec![IntegrityFinding {
+                id: format!("lock:{}", lock.display()),
+                kind: IntegrityFindingKind::StaleLockFile,
+                description: format!(
+                    "git lock file {} was left behind; git commands in this repository fail \
+                     until it is removed",
+                    lock.display()
+                ),
+                path: Some(lock),
+                pid: None,
+                cleanup: "delete the lock file".to_string(),
+            }]
+        );
+
+        let leftovers = patch_leftovers(root, started);
+        let paths: Vec<Option<PathBuf>> = leftovers.iter().map(|f| f.path.clone()).collect();
+        assert_eq!(paths, vec![Some(root.join("src/lib.rs.rej"))]);
+
+        clean_up(&leftovers[0]).expect("clean up");
+        assert_eq!(patch_leftovers(root, started), Vec::new());
+    }
+
+    #[test]
+    fn follows_gitdir_files() {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("worktrees/feature")).expect("create gitdir");
+        std::fs::write(root.join(".git"), "gitdir: worktrees/feature\n").expect("write .git");
+        assert_eq!(git_dir(root), Some(root.join("worktrees/feature")));
+    }
+
+    #[test]
+    fn reports_workspace_processes_started_during_the_session() {
+        let process = |pid, elapsed: &str, scope| ScopedProcess {
+            info: ProcessInfo {
+                pid,
+                ppid: 1,
+                rss_kb: 0,
+                cpu_percent: 0.0,
+                elapsed: elapsed.to_string(),
+                command: "node server.js".to_string(),
+                cwd: None,
+            },
+            scope,
+        };
+        let processes = vec![
+            process(10, "05:00", ProcessScope::Workspace),
+            process(11, "2-00:00:00", ProcessScope::Workspace),
+            process(12, "00:10", ProcessScope::Session),
+        ];
+
+        assert_eq!(
+            orphaned_processes(processes, HOUR),
+            vec![IntegrityFinding {
+                id: "process:10".to_string(),
+                kind: IntegrityFindingKind::OrphanedProcess,
+                description: "process 10 (node server.js) was started during this session and \
+                              is still running"
+                    .to_string(),
+                path: None,
+                pid: Some(10),
+                cleanup: "terminate the process".to_string(),
+            }]
+        );
+    }
+}
@@ -1,4 +1,5 @@
 use crate::protocol::SandboxPolicy;
+use crate::sandboxing::network::SandboxNetwork;
 use crate::spawn::StdioPolicy;
 use crate::spawn::spawn_child_async;
 use std::collections::HashMap;
@@ -25,7 +26,9 @@ pub async fn spawn_command_under_linux_sandbox<P>(
 where
     P: AsRef<Path>,
 {
-    let args = create_linux_sandbox_command_args(command, sandbox_policy, sandbox_policy_cwd);
+    let network = SandboxNetwork::from_policy(sandbox_policy);
+    let args =
+        create_linux_sandbox_command_args(command, sandbox_policy, network, sandbox_policy_cwd);
     let arg0 = Some("codex-linux-sandbox");
     spawn_child_async(
         codex_linux_sandbox_exe.as_ref().to_path_buf(),
@@ -35,6 +38,7 @@ where
         sandbox_policy,
         stdio_policy,
         env,
+        None,
     )
     .await
 }
@@ -43,6 +47,7 @@ where
 pub(crate) fn create_linux_sandbox_command_args(
     command: Vec<String>,
     sandbox_policy: &SandboxPolicy,
+    network: SandboxNetwork,
     sandbox_policy_cwd: &Path,
 ) -> Vec<String> {
     #[expect(clippy::expect_used)]
@@ -54,12 +59,17 @@ pub(crate) fn create_linux_sandbox_command_args(
     #[expect(clippy::expect_used)]
     let sandbox_policy_json =
         serde_json::to_string(sandbox_policy).expect("Failed to serialize SandboxPolicy to JSON");
+    #[expect(clippy::expect_used)]
+    let network_json =
+        serde_json::to_string(&network).expect("Failed to serialize SandboxNetwork to JSON");
 
     let mut linux_cmd: Vec<String> = vec![
         "--sandbox-policy-cwd".to_string(),
         sandbox_policy_cwd,
         "--sandbox-policy".to_string(),
         sandbox_policy_json,
+        "--network".to_string(),
+        network_json,
         // Separator so that command arguments starting with `-` are not parsed as
         // options of the helper itself.
         "--".to_string(),
@@ -0,0 +1,77 @@
+//! Session services that are built on first use.
+//!
+//! Some services take long enough to build that constructing them up front
+//! would hold back the first turn; watching the workspace, for one, walks the
+//! whole tree. A [`LazyService`] keeps what it takes to build one. The session
+//! calls [`LazyService::warm_up`] once it is configured, which builds the
+//! service in the background, and [`LazyService::get`] waits for that build,
+//! or starts it if nothing has yet. Either way the service is built once.
+
+use std::future::Future;
+use std::sync::Arc;
+
+use futures::future::BoxFuture;
+use futures::future::FutureExt;
+use tokio::sync::OnceCell;
+
+type Init<T> = Arc<dyn Fn() -> BoxFuture<'static, T> + Send + Sync>;
+
+pub(crate) struct LazyService<T> {
+    cell: Arc<OnceCell<T>>,
+    init: Init<T>,
+}
+
+impl<T: Send + Sync + 'static> LazyService<T> {
+    pub(crate) fn new<F, Fut>(init: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = T> + Send + 'static,
+    {
+        Self {
+            cell: Arc::new(OnceCell::new()),
+            init: Arc::new(move || init().boxed()),
+        }
+    }
+
+    /// The service, built now unless a warm-up already did or is doing it.
+    pub(crate) async fn get(&self) -> &T {
+        self.cell.get_or_init(|| (self.init)()).await
+    }
+
+    /// Start building the service in the background.
+    pub(crate) fn warm_up(&self) {
+        if self.cell.initialized() {
+            return;
+        }
+        let cell = Arc::clone(&self.cell);
+        let init = Arc::clone(&self.init);
+        tokio::spawn(async move {
+            cell.get_or_init(|| init()).await;
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::atomic::Ordering;
+
+    #[tokio::test]
+    async fn the_service_is_built_once_whoever_asks_first() {
+        let builds = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&builds);
+        let service = LazyService::new(move || {
+            let counter = Arc::clone(&counter);
+            async move { counter.fetch_add(1, Ordering::SeqCst) + 1 }
+        });
+        assert_eq!(builds.load(Ordering::SeqCst), 0);
+
+        service.warm_up();
+        assert_eq!(*service.get().await, 1);
+        service.warm_up();
+        assert_eq!(*service.get().await, 1);
+        assert_eq!(builds.load(Ordering::SeqCst), 1);
+    }
+}
@@ -6,8 +6,15 @@
 #![deny(clippy::print_stdout, clippy::print_stderr)]
 
 mod apply_patch;
+pub mod approval_cache;
+mod approval_rules;
+mod artifacts;
+pub mod audit;
 pub mod auth;
+mod background_jobs;
 pub mod bash;
+mod blocking_io;
+mod build_cache;
 mod chat_completions;
 mod client;
 mod client_common;
@@ -16,20 +23,35 @@ mod codex_conversation;
 mod compact_remote;
 pub use codex_conversation::CodexConversation;
 mod codex_delegate;
+mod collab;
+pub use collab::Collaborator;
+mod command_chain;
 mod command_safety;
 pub mod config;
 pub mod config_loader;
 mod context_manager;
+pub mod control;
+pub mod crash_report;
 pub mod custom_prompts;
+mod dirty_files;
+pub mod doctor;
 mod environment_context;
+mod environment_diff;
 pub mod error;
 pub mod exec;
 pub mod exec_env;
 mod exec_policy;
+mod exec_progress;
 pub mod features;
+mod file_placeholder;
 mod flags;
 pub mod git_info;
+mod html_markdown;
+mod inline_script;
+mod integrity;
 pub mod landlock;
+mod lazy_service;
+pub mod logging;
 pub mod mcp;
 mod mcp_connection_manager;
 pub use mcp_connection_manager::MCP_SANDBOX_STATE_CAPABILITY;
@@ -39,11 +61,17 @@ mod mcp_tool_call;
 mod message_history;
 mod model_provider_info;
 pub mod parse_command;
+mod patch_journal;
 pub mod powershell;
+mod quarantine;
+mod read_ahead;
+mod replay_bundle;
+mod resource_usage;
 mod response_processing;
 pub mod sandboxing;
 mod text_encoding;
 pub mod token_data;
+mod trash;
 mod truncate;
 mod unified_exec;
 mod user_instructions;
@@ -62,7 +90,10 @@ pub use codex_protocol::protocol::InitialHistory;
 pub use conversation_manager::ConversationManager;
 pub use conversation_manager::NewConversation;
 pub use tools::context::{ToolInvocation, ToolOutput, ToolPayload};
+pub use tools::custom::{CustomToolCall, CustomToolRuntime, ToolRuntimeRegistry};
 pub use tools::executor::{default_tool_executor, DynToolExecutor, ToolExecutor};
+#[cfg(feature = "fault-injection")]
+pub use tools::fault_injection::{Fault, FaultInjectingExecutor, FaultPlan, InjectedFault};
 pub use tools::runtimes::apply_patch::ApplyPatchRequest;
 pub use tools::runtimes::shell::ShellRequest;
 pub use tools::sandboxing::{SandboxAttempt, ToolCtx, ToolError};
@@ -74,11 +105,19 @@ pub use auth::CodexAuth;
 pub mod default_client;
 pub mod model_family;
 mod openai_model_info;
+pub(crate) mod output_truncation;
+pub mod project_cache;
 pub mod project_doc;
+pub mod rendered_diff;
 mod rollout;
 pub(crate) mod safety;
+mod sandbox_profiles;
 pub mod seatbelt;
+mod secret_redaction;
+pub mod self_update;
+mod sensitive_content;
 pub mod shell;
+mod shell_profile;
 pub mod spawn;
 pub mod terminal;
 mod tools;
@@ -96,10 +135,13 @@ pub use rollout::list::parse_cursor;
 pub use rollout::list::read_head_for_summary;
 mod function_tool;
 mod state;
+mod status_line;
+mod syntax_highlight;
 mod tasks;
 mod user_notification;
 mod user_shell_command;
 pub mod util;
+mod workspace_index;
 
 pub use apply_patch::CODEX_APPLY_PATCH_ARG1;
 pub use command_safety::is_safe_command;
@@ -0,0 +1,385 @@
+//! The log file.
+//!
+//! Each line of the log is a JSON object (see [`LogFormat`] for the plain
+//! text alternative) with the `timestamp`, `level`, `target` and `message`
+//! of the event, its other fields under `fields`, and the fields of the
+//! spans it happened in. Turns run in a `turn` span with their `turn_id`
+//! and tool calls in a `tool_call` span with their `call_id`, so the lines
+//! of one call in a turn that ran several at once can be picked out with
+//! `jq 'select(.call_id == "…")'`.
+//!
+//! Levels are set per module with the `RUST_LOG` syntax, from `RUST_LOG`,
+//! `logging.levels` or the defaults of the binary, and can be changed while
+//! Codex runs with [`set_log_levels`], which the control socket exposes as
+//! `log/setLevels`. The file is rotated once it reaches
+//! `logging.max_file_size_mb`: `codex-tui.log` becomes `codex-tui.log.1`,
+//! and so on up to `logging.max_files`.
+
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::OnceLock;
+
+use serde_json::Map;
+use serde_json::Value;
+use tracing::Event;
+use tracing::Subscriber;
+use tracing::field::Field;
+use tracing::field::Visit;
+use tracing::span::Attributes;
+use tracing::span::Id;
+use tracing::span::Record;
+use tracing_subscriber::EnvFilter;
+use tracing_subscriber::Layer;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::reload;
+
+use crate::config::types::LogFormat;
+use crate::config::types::Logging;
+
+static LEVELS: OnceLock<LogLevels> = OnceLock::new();
+
+/// The levels of the log file and how to change them.
+struct LogLevels {
+    current: Mutex<String>,
+    reload: Box<dyn Fn(EnvFilter) -> Result<(), String> + Send + Sync>,
+}
+
+/// The layer writing the log to `make_writer`, in the format and at the
+/// levels of `logging`. `default_levels` apply when neither `RUST_LOG` nor
+/// `logging.levels` is set. The first layer built is the one
+/// [`set_log_levels`] changes.
+pub fn file_layer<S, W>(
+    logging: &Logging,
+    default_levels: &str,
+    make_writer: W,
+) -> Box<dyn Layer<S> + Send + Sync>
+where
+    S: Subscriber + for<'a> LookupSpan<'a> + 'static,
+    W: for<'a> MakeWriter<'a> + Send + Sync + 'static,
+{
+    let (levels, filter) = match std::env::var("RUST_LOG")
+        .ok()
+        .and_then(|levels| {
+            EnvFilter::try_new(&levels)
+                .ok()
+                .map(|filter| (levels, filter))
+        })
+        .or_else(|| {
+            let levels = logging.levels.clone()?;
+            EnvFilter::try_new(&levels)
+                .ok()
+                .map(|filter| (levels, filter))
+        }) {
+        Some(found) => found,
+        None => (default_levels.to_string(), EnvFilter::new(default_levels)),
+    };
+    let (filter, handle) = reload::Layer::new(filter);
+    let _ = LEVELS.set(LogLevels {
+        current: Mutex::new(levels),
+        reload: Box::new(move |filter| handle.reload(filter).map_err(|err| err.to_string())),
+    });
+    match logging.format {
+        LogFormat::Json => JsonLayer { make_writer }.with_filter(filter).boxed(),
+        LogFormat::Text => tracing_subscriber::fmt::layer()
+            .with_writer(make_writer)
+            .with_ansi(false)
+            .with_target(false)
+            .with_span_events(FmtSpan::CLOSE)
+            .with_filter(filter)
+            .boxed(),
+    }
+}
+
+/// The levels the log file is written at, if this process has one.
+pub fn log_levels() -> Option<String> {
+    let levels = LEVELS.get()?;
+    levels.current.lock().ok().map(|current| current.clone())
+}
+
+/// Changes the levels of the log file to `levels`, in the syntax of
+/// `RUST_LOG`, until Codex exits.
+pub fn set_log_levels(levels: &str) -> Result<(), String> {
+    let Some(log_levels) = LEVELS.get() else {
+        return Err("this process does not write a log file".to_string());
+    };
+    let filter = EnvFilter::try_new(levels)
+        .map_err(|err| format!("invalid log levels {levels:?}: {err}"))?;
+    (log_levels.reload)(filter)?;
+    if let Ok(mut current) = log_levels.current.lock() {
+        *current = levels.to_string();
+    }
+    tracing::info!("log levels set to {levels}");
+    Ok(())
+}
+
+/// A log file that is rotated once it reaches a size.
+pub struct RotatingFile {
+    path: PathBuf,
+    max_bytes: u64,
+    max_files: usize,
+    file: File,
+    written: u64,
+}
+
+impl RotatingFile {
+    /// Opens `path` for appending, to be rotated as `logging` says. A
+    /// `max_file_size_mb` of 0 never rotates it.
+    pub fn open(path: PathBuf, logging: &Logging) -> io::Result<Self> {
+        let file = open_log_file(&path)?;
+        let written = file.metadata()?.len();
+        Ok(Self {
+            path,
+            max_bytes: logging.max_file_size_mb.saturating_mul(1024 * 1024),
+            max_files: logging.max_files,
+            file,
+            written,
+        })
+    }
+
+    fn rotated(&self, index: usize) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{index}"));
+        PathBuf::from(name)
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        if self.max_files == 0 {
+            std::fs::remove_file(&self.path)?;
+        } else {
+            for index in (1..self.max_files).rev() {
+                let from = self.rotated(index);
+                if from.exists() {
+                    std::fs::rename(from, self.rotated(index + 1))?;
+                }
+            }
+            std::fs::rename(&self.path, self.rotated(1))?;
+        }
+        self.file = open_log_file(&self.path)?;
+        self.written = 0;
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.max_bytes > 0
+            && self.written > 0
+            && self.written + buf.len() as u64 > self.max_bytes
+        {
+            self.rotate()?;
+        }
+        let written = self.file.write(buf)?;
+        self.written += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+fn open_log_file(path: &Path) -> io::Result<File> {
+    let mut options = OpenOptions::new();
+    options.create(true).append(true);
+    // Only the current user may read the log.
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)
+}
+
+/// Writes each event as one line of JSON.
+struct JsonLayer<W> {
+    make_writer: W,
+}
+
+/// The fields a span was created or later recorded with.
+struct SpanFields(Map<String, Value>);
+
+impl<S, W> Layer<S> for JsonLayer<W>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'a> MakeWriter<'a> + 'static,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut fields = Map::new();
+        attrs.record(&mut JsonVisitor(&mut fields));
+        span.extensions_mut().insert(SpanFields(fields));
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        if let Some(fields) = span.extensions_mut().get_mut::<SpanFields>() {
+            values.record(&mut JsonVisitor(&mut fields.0));
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let mut line = Map::new();
+        let mut spans = Vec::new();
+        if let Some(scope) = ctx.event_scope(event) {
+            for span in scope.from_root() {
+                spans.push(Value::from(span.name()));
+                if let Some(fields) = span.extensions().get::<SpanFields>() {
+                    line.extend(fields.0.clone());
+                }
+            }
+        }
+        let mut fields = Map::new();
+        event.record(&mut JsonVisitor(&mut fields));
+        let metadata = event.metadata();
+        line.insert(
+            "timestamp".to_string(),
+            Value::from(chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true)),
+        );
+        line.insert(
+            "level".to_string(),
+            Value::from(metadata.level().to_string()),
+        );
+        line.insert("target".to_string(), Value::from(metadata.target()));
+        if let Some(message) = fields.remove("message") {
+            line.insert("message".to_string(), message);
+        }
+        if !fields.is_empty() {
+            line.insert("fields".to_string(), Value::Object(fields));
+        }
+        if !spans.is_empty() {
+            line.insert("spans".to_string(), Value::Array(spans));
+        }
+        let Ok(mut bytes) = serde_json::to_vec(&line) else {
+            return;
+        };
+        bytes.push(b'\n');
+        let _ = self.make_writer.make_writer().write_all(&bytes);
+    }
+}
+
+struct JsonVisitor<'a>(&'a mut Map<String, Value>);
+
+impl Visit for JsonVisitor<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.0
+            .insert(field.name().to_string(), Value::from(format!("{value:?}")));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::sync::Arc;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0
+                .lock()
+                .map_err(|_| io::Error::other("poisoned"))?
+                .extend(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn json_lines_carry_the_ids_of_their_spans() {
+        let buffer = Buffer::default();
+        let writer = buffer.clone();
+        let subscriber = tracing_subscriber::registry().with(JsonLayer {
+            make_writer: move || writer.clone(),
+        });
+        tracing::subscriber::with_default(subscriber, || {
+            let turn = tracing::info_span!("turn", turn_id = "t1");
+            let _turn = turn.enter();
+            let call = tracing::info_span!("tool_call", call_id = "c1", tool = "shell");
+            let _call = call.enter();
+            tracing::warn!(exit_code = 2, "command failed");
+        });
+
+        let output = buffer
+            .0
+            .lock()
+            .map(|bytes| bytes.clone())
+            .unwrap_or_default();
+        let mut line: Value = serde_json::from_slice(&output).unwrap_or_default();
+        if let Some(line) = line.as_object_mut() {
+            line.remove("timestamp");
+        }
+        assert_eq!(
+            line,
+            serde_json::json!({
+                "level": "WARN",
+                "target": "codex_core::logging::tests",
+                "message": "command failed",
+                "fields": { "exit_code": 2 },
+                "turn_id": "t1",
+                "call_id": "c1",
+                "tool": "shell",
+                "spans": ["turn", "tool_call"],
+            })
+        );
+    }
+
+    #[test]
+    fn full_files_are_rotated() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("codex.log");
+        let logging = Logging {
+            max_file_size_mb: 1,
+            max_files: 2,
+            ..Logging::default()
+        };
+        let mut file = RotatingFile::open(path.clone(), &logging)?;
+        let line = vec![b'x'; 700 * 1024];
+        for _ in 0..4 {
+            file.write_all(&line)?;
+        }
+
+        assert_eq!(std::fs::metadata(&path)?.len(), line.len() as u64);
+        assert!(dir.path().join("codex.log.1").exists());
+        assert!(dir.path().join("codex.log.2").exists());
+        assert!(!dir.path().join("codex.log.3").exists());
+        Ok(())
+    }
+}
@@ -0,0 +1,116 @@
+//! Head and tail windows for long command output.
+//!
+//! By default a command's output is cut to the model's output budget, half
+//! from the start and half from the end. `[output_truncation]` sets how many
+//! KiB of each end to keep instead, and with `spill = true` the full output of
+//! a command that was cut down is first written under the session's artifacts,
+//! at `output/<call id>.log`. The path is included in what the model sees, so
+//! it can read the parts it needs later.
+
+use std::path::Path;
+
+use tracing::warn;
+
+use crate::config::types::OutputTruncation;
+use crate::exec::ExecToolCallOutput;
+use crate::exec::StreamOutput;
+use crate::truncate::truncate_head_tail;
+
+/// Directory under the session's artifacts that holds spilled output.
+pub(crate) const SPILL_DIR: &str = "output";
+
+/// Cut `output` down to the configured windows, saving the full output under
+/// `spill_dir` first when it is enabled. `budget` is the model's output budget
+/// in bytes, used to tell whether output is long when no window is set.
+pub(crate) async fn truncate_output(
+    output: ExecToolCallOutput,
+    config: &OutputTruncation,
+    budget: usize,
+    spill_dir: &Path,
+    call_id: &str,
+) -> ExecToolCallOutput {
+    let window = match (config.head_kib, config.tail_kib) {
+        (None, None) => None,
+        (head, tail) => Some((head.unwrap_or(0) * 1024, tail.unwrap_or(0) * 1024)),
+    };
+    let limit = window.map_or(budget, |(head, tail)| head + tail);
+    if output.aggregated_output.text.len() <= limit {
+        return output;
+    }
+
+    let mut output = output;
+    if config.spill {
+        let path = spill_dir.join(format!("{}.log", sanitize(call_id)));
+        let written = async {
+            tokio::fs::create_dir_all(spill_dir).await?;
+            tokio::fs::write(&path, &output.aggregated_output.text).await
+        };
+        match written.await {
+            Ok(()) => output.full_output_path = Some(path),
+            Err(err) => warn!("failed to save full output to {}: {err}", path.display()),
+        }
+    }
+    if let Some((head, tail)) = window {
+        let cut = |stream: &StreamOutput<String>| StreamOutput {
+            text: truncate_head_tail(&stream.text, head, tail),
+            truncated_after_lines: stream.truncated_after_lines,
+        };
+        output.stdout = cut(&output.stdout);
+        output.stderr = cut(&output.stderr);
+        output.aggregated_output = cut(&output.aggregated_output);
+    }
+    output
+}
+
+/// Call ids come from the model; keep only what is safe in a file name.
+pub(crate) fn sanitize(call_id: &str) -> String {
+    call_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exec::ExitReason;
+    use pretty_assertions::assert_eq;
+    use std::time::Duration;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn long_output_keeps_its_ends_and_is_saved_in_full() {
+        let dir = tempdir().expect("tempdir");
+        let (head, tail) = ("h".repeat(1024), "t".repeat(2048));
+        let text = format!("{head}{}{tail}", "m".repeat(4096));
+        let output = ExecToolCallOutput {
+            exit_code: 0,
+            stdout: StreamOutput::new(text.clone()),
+            stderr: StreamOutput::new(String::new()),
+            aggregated_output: StreamOutput::new(text.clone()),
+            duration: Duration::from_secs(1),
+            full_output_path: None,
+            exit_reason: ExitReason::Success,
+        };
+        let config = OutputTruncation {
+            head_kib: Some(1),
+            tail_kib: Some(2),
+            spill: true,
+        };
+
+        let truncated = truncate_output(output, &config, 10, dir.path(), "call/1").await;
+        let expected = format!("{head}…4096 chars truncated…{tail}");
+        assert_eq!(truncated.aggregated_output.text, expected);
+        assert_eq!(truncated.stdout.text, expected);
+        assert_eq!(truncated.stderr.text, "");
+        let path = dir.path().join("call_1.log");
+        assert_eq!(truncated.full_output_path, Some(path.clone()));
+        assert_eq!(std::fs::read_to_string(path).expect("read"), text);
+    }
+}
@@ -0,0 +1,295 @@
+//! Undo journal for the patches applied in a session.
+//!
+//! With the `undo_patch` feature, the files an `apply_patch` call writes are
+//! read before the patch runs, and once it succeeds their previous contents
+//! are pushed onto the session's journal together with a hash of what the
+//! patch left behind. `Op::UndoPatch` and the `undo_patch` tool revert the
+//! most recent patches, newest first, by writing the previous contents back
+//! and deleting the files the patches created. A patch whose files changed
+//! after it was applied is not reverted, so later edits are never lost. The
+//! journal lives in memory and does not survive resuming a session.
+
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+
+use sha2::Digest;
+use sha2::Sha256;
+
+/// Past this many bytes of previous contents, the oldest patches are
+/// forgotten.
+const MAX_JOURNAL_BYTES: usize = 64 * 1024 * 1024;
+
+/// The files a patch is about to write, as they were before it ran.
+pub(crate) struct PatchSnapshot {
+    /// Previous contents, `None` for a file that did not exist.
+    files: Vec<(PathBuf, Option<Vec<u8>>)>,
+}
+
+impl PatchSnapshot {
+    pub(crate) async fn take(paths: &[PathBuf]) -> io::Result<Self> {
+        let mut files: Vec<(PathBuf, Option<Vec<u8>>)> = Vec::new();
+        for path in paths {
+            if files.iter().any(|(seen, _)| seen == path) {
+                continue;
+            }
+            files.push((path.clone(), read_if_exists(path).await?));
+        }
+        Ok(Self { files })
+    }
+
+    /// The journal entry for the patch of `call_id`, which has just
+    /// succeeded.
+    pub(crate) async fn applied(self, call_id: String) -> io::Result<JournalEntry> {
+        let mut files = Vec::with_capacity(self.files.len());
+        for (path, before) in self.files {
+            let after = read_if_exists(&path).await?.as_deref().map(hash);
+            files.push(JournaledFile {
+                path,
+                before,
+                after,
+            });
+        }
+        Ok(JournalEntry { call_id, files })
+    }
+}
+
+/// A patch that can be reverted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct JournalEntry {
+    call_id: String,
+    files: Vec<JournaledFile>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct JournaledFile {
+    path: PathBuf,
+    before: Option<Vec<u8>>,
+    /// SHA-256 of the contents the patch left, `None` when it deleted the
+    /// file.
+    after: Option<String>,
+}
+
+impl JournalEntry {
+    fn size(&self) -> usize {
+        self.files
+            .iter()
+            .map(|file| file.before.as_ref().map_or(0, Vec::len))
+            .sum()
+    }
+
+    /// The first file that no longer holds what the patch left.
+    async fn changed_since(&self) -> io::Result<Option<&Path>> {
+        for file in &self.files {
+            let current = read_if_exists(&file.path).await?.as_deref().map(hash);
+            if current != file.after {
+                return Ok(Some(&file.path));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// What [`PatchJournal::undo`] did.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct UndoOutcome {
+    /// Call ids of the reverted patches, newest first.
+    pub(crate) reverted: Vec<String>,
+    /// Files given back their previous contents.
+    pub(crate) restored: Vec<PathBuf>,
+    /// Files the reverted patches had created.
+    pub(crate) removed: Vec<PathBuf>,
+    /// Why fewer patches than asked for were reverted.
+    pub(crate) stopped: Option<String>,
+}
+
+impl UndoOutcome {
+    pub(crate) fn summary(&self) -> String {
+        let list = |paths: &[PathBuf]| {
+            paths
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let mut summary = match self.reverted.len() {
+            0 => "No patch was reverted.".to_string(),
+            1 => "Reverted 1 patch.".to_string(),
+            n => format!("Reverted {n} patches."),
+        };
+        if !self.restored.is_empty() {
+            summary.push_str(&format!(" Restored {}.", list(&self.restored)));
+        }
+        if !self.removed.is_empty() {
+            summary.push_str(&format!(" Deleted {}.", list(&self.removed)));
+        }
+        if let Some(reason) = &self.stopped {
+            summary.push_str(&format!(" Stopped: {reason}."));
+        }
+        summary
+    }
+}
+
+/// The session's applied patches, oldest first.
+#[derive(Debug, Default)]
+pub(crate) struct PatchJournal {
+    entries: Vec<JournalEntry>,
+}
+
+impl PatchJournal {
+    pub(crate) fn push(&mut self, entry: JournalEntry) {
+        self.entries.push(entry);
+        let mut size: usize = self.entries.iter().map(JournalEntry::size).sum();
+        while size > MAX_JOURNAL_BYTES && !self.entries.is_empty() {
+            size -= self.entries.remove(0).size();
+        }
+    }
+
+    /// The files the last `count` patches wrote.
+    pub(crate) fn paths(&self, count: usize) -> Vec<PathBuf> {
+        self.entries
+            .iter()
+            .rev()
+            .take(count)
+            .flat_map(|entry| entry.files.iter().map(|file| file.path.clone()))
+            .collect()
+    }
+
+    /// Revert the last `count` patches, newest first.
+    pub(crate) async fn undo(&mut self, count: usize) -> UndoOutcome {
+        let mut outcome = UndoOutcome::default();
+        while outcome.reverted.len() < count {
+            let Some(entry) = self.entries.last() else {
+                outcome.stopped = Some("there are no earlier patches to revert".to_string());
+                break;
+            };
+            match entry.changed_since().await {
+                Ok(None) => {}
+                Ok(Some(path)) => {
+                    outcome.stopped = Some(format!(
+                        "{} changed after patch {} was applied",
+                        path.display(),
+                        entry.call_id
+                    ));
+                    break;
+                }
+                Err(err) => {
+                    outcome.stopped = Some(format!("cannot read the files to revert: {err}"));
+                    break;
+                }
+            }
+            for file in &entry.files {
+                let reverted = match &file.before {
+                    Some(contents) => write_creating_parents(&file.path, contents).await,
+                    None => tokio::fs::remove_file(&file.path).await,
+                };
+                if let Err(err) = reverted {
+                    outcome.stopped =
+                        Some(format!("failed to revert {}: {err}", file.path.display()));
+                    return outcome;
+                }
+                match file.before {
+                    Some(_) => outcome.restored.push(file.path.clone()),
+                    None => outcome.removed.push(file.path.clone()),
+                }
+            }
+            outcome.reverted.push(entry.call_id.clone());
+            self.entries.pop();
+        }
+        outcome
+    }
+}
+
+async fn read_if_exists(path: &Path) -> io::Result<Option<Vec<u8>>> {
+    match tokio::fs::read(path).await {
+        Ok(contents) => Ok(Some(contents)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+async fn write_creating_parents(path: &Path, contents: &[u8]) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::write(path, contents).await
+}
+
+fn hash(contents: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(contents))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::tempdir;
+
+    async fn apply(journal: &mut PatchJournal, call_id: &str, writes: &[(&Path, Option<&str>)]) {
+        let paths: Vec<PathBuf> = writes.iter().map(|(path, _)| path.to_path_buf()).collect();
+        let snapshot = PatchSnapshot::take(&paths).await.expect("snapshot");
+        for (path, contents) in writes {
+            match contents {
+                Some(contents) => std::fs::write(path, contents).expect("write"),
+                None => std::fs::remove_file(path).expect("remove"),
+            }
+        }
+        journal.push(snapshot.applied(call_id.to_string()).await.expect("entry"));
+    }
+
+    #[tokio::test]
+    async fn patches_are_reverted_newest_first() {
+        let dir = tempdir().expect("tempdir");
+        let lib = dir.path().join("lib.rs");
+        let old = dir.path().join("old.rs");
+        let new = dir.path().join("new.rs");
+        std::fs::write(&lib, "v1").expect("write");
+        std::fs::write(&old, "moved").expect("write");
+        let mut journal = PatchJournal::default();
+        apply(&mut journal, "call-1", &[(&lib, Some("v2"))]).await;
+        apply(
+            &mut journal,
+            "call-2",
+            &[(&lib, Some("v3")), (&old, None), (&new, Some("moved"))],
+        )
+        .await;
+
+        let outcome = journal.undo(3).await;
+        assert_eq!(
+            outcome,
+            UndoOutcome {
+                reverted: vec!["call-2".to_string(), "call-1".to_string()],
+                restored: vec![lib.clone(), old.clone(), lib.clone()],
+                removed: vec![new.clone()],
+                stopped: Some("there are no earlier patches to revert".to_string()),
+            }
+        );
+        assert_eq!(std::fs::read_to_string(&lib).expect("read"), "v1");
+        assert_eq!(std::fs::read_to_string(&old).expect("read"), "moved");
+        assert!(!new.exists());
+    }
+
+    #[tokio::test]
+    async fn a_patch_whose_files_changed_since_is_kept() {
+        let dir = tempdir().expect("tempdir");
+        let lib = dir.path().join("lib.rs");
+        std::fs::write(&lib, "v1").expect("write");
+        let mut journal = PatchJournal::default();
+        apply(&mut journal, "call-1", &[(&lib, Some("v2"))]).await;
+        std::fs::write(&lib, "edited by hand").expect("write");
+
+        let outcome = journal.undo(1).await;
+        assert_eq!(
+            outcome.summary(),
+            format!(
+                "No patch was reverted. Stopped: {} changed after patch call-1 was applied.",
+                lib.display()
+            )
+        );
+        assert_eq!(
+            std::fs::read_to_string(&lib).expect("read"),
+            "edited by hand"
+        );
+        assert_eq!(journal.paths(1), vec![lib]);
+    }
+}
@@ -0,0 +1,252 @@
+//! Cache directories that persist across sessions, one per project.
+//!
+//! With `project_cache.enabled`, commands get `$CODEX_CACHE_DIR`, a directory
+//! under `$CODEX_HOME/cache` shared by every session in the same project (the
+//! git repository root, or the working directory outside a repository), where
+//! build outputs and package downloads survive from one run to the next. The
+//! caches of well-known tools are pointed there unless already set. When all
+//! project caches together outgrow `project_cache.max_size_mb`, the least
+//! recently used are deleted at the start of a session; `codex cache` lists
+//! and deletes them by hand.
+
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use chrono::DateTime;
+use chrono::Utc;
+use serde::Deserialize;
+use serde::Serialize;
+use sha2::Digest;
+use sha2::Sha256;
+use walkdir::WalkDir;
+
+use crate::git_info::get_git_repo_root;
+
+/// Environment variable holding the project's cache directory.
+pub const CACHE_DIR_ENV_VAR: &str = "CODEX_CACHE_DIR";
+
+const CACHE_DIR: &str = "cache";
+
+/// Records which project a cache belongs to; its modification time is when
+/// the cache was last used.
+const MARKER_FILE: &str = "project.json";
+
+/// Length of the id shown by `codex cache list`.
+const ID_LEN: usize = 12;
+
+/// Tool caches pointed into the project cache, with their subdirectory.
+const TOOL_CACHES: [(&str, &str); 3] = [
+    ("PIP_CACHE_DIR", "pip"),
+    ("npm_config_cache", "npm"),
+    ("GOCACHE", "go-build"),
+];
+
+/// A project's cache directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProjectCacheEntry {
+    /// Identifies the cache for `codex cache remove`.
+    pub id: String,
+    pub project_root: PathBuf,
+    pub path: PathBuf,
+    pub size_bytes: u64,
+    pub last_used: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Marker {
+    project_root: PathBuf,
+}
+
+/// The directory holding every project cache.
+pub fn cache_root(codex_home: &Path) -> PathBuf {
+    codex_home.join(CACHE_DIR)
+}
+
+/// Every project cache, least recently used first.
+pub fn list_project_caches(codex_home: &Path) -> io::Result<Vec<ProjectCacheEntry>> {
+    let mut entries = Vec::new();
+    let dirs = match std::fs::read_dir(cache_root(codex_home)) {
+        Ok(dirs) => dirs,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(entries),
+        Err(err) => return Err(err),
+    };
+    for dir in dirs {
+        let path = dir?.path();
+        let marker_path = path.join(MARKER_FILE);
+        let Ok(json) = std::fs::read(&marker_path) else {
+            continue;
+        };
+        let Ok(marker) = serde_json::from_slice::<Marker>(&json) else {
+            tracing::warn!("ignoring unreadable project cache {}", path.display());
+            continue;
+        };
+        let last_used = std::fs::metadata(&marker_path)
+            .and_then(|metadata| metadata.modified())
+            .unwrap_or(SystemTime::UNIX_EPOCH);
+        entries.push(ProjectCacheEntry {
+            id: path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            project_root: marker.project_root,
+            size_bytes: dir_size(&path),
+            last_used: last_used.into(),
+            path,
+        });
+    }
+    entries.sort_by_key(|entry| entry.last_used);
+    Ok(entries)
+}
+
+/// Delete the cache with `id`. Returns whether there was one.
+pub fn remove_project_cache(codex_home: &Path, id: &str) -> io::Result<bool> {
+    match list_project_caches(codex_home)?
+        .into_iter()
+        .find(|entry| entry.id == id)
+    {
+        Some(entry) => std::fs::remove_dir_all(entry.path).map(|()| true),
+        None => Ok(false),
+    }
+}
+
+/// Delete every project cache. Returns how many there were.
+pub fn clear_project_caches(codex_home: &Path) -> io::Result<usize> {
+    let count = list_project_caches(codex_home)?.len();
+    match std::fs::remove_dir_all(cache_root(codex_home)) {
+        Ok(()) => Ok(count),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(0),
+        Err(err) => Err(err),
+    }
+}
+
+/// Delete the least recently used caches until all of them together take at
+/// most `max_bytes`. Returns the deleted caches.
+pub fn evict_project_caches(
+    codex_home: &Path,
+    max_bytes: u64,
+) -> io::Result<Vec<ProjectCacheEntry>> {
+    let entries = list_project_caches(codex_home)?;
+    let mut total: u64 = entries.iter().map(|entry| entry.size_bytes).sum();
+    let mut evicted = Vec::new();
+    for entry in entries {
+        if total <= max_bytes {
+            break;
+        }
+        std::fs::remove_dir_all(&entry.path)?;
+        total = total.saturating_sub(entry.size_bytes);
+        evicted.push(entry);
+    }
+    Ok(evicted)
+}
+
+/// The cache directory of the project containing `cwd`, created if needed
+/// and marked as used now.
+pub(crate) fn project_cache_dir(codex_home: &Path, cwd: &Path) -> io::Result<PathBuf> {
+    let project_root = get_git_repo_root(cwd).unwrap_or_else(|| cwd.to_path_buf());
+    let mut id = format!(
+        "{:x}",
+        Sha256::digest(project_root.to_string_lossy().as_bytes())
+    );
+    id.truncate(ID_LEN);
+    let dir = cache_root(codex_home).join(id);
+    std::fs::create_dir_all(&dir)?;
+    let marker = serde_json::to_vec_pretty(&Marker { project_root }).map_err(io::Error::other)?;
+    std::fs::write(dir.join(MARKER_FILE), marker)?;
+    Ok(dir)
+}
+
+/// Export `dir` to a command, and point the caches of well-known tools into
+/// it unless the command's environment already sets them.
+pub(crate) fn set_cache_env(dir: &Path, env: &mut HashMap<String, String>) {
+    env.insert(
+        CACHE_DIR_ENV_VAR.to_string(),
+        dir.to_string_lossy().into_owned(),
+    );
+    for (var, subdir) in TOOL_CACHES {
+        env.entry(var.to_string())
+            .or_insert_with(|| dir.join(subdir).to_string_lossy().into_owned());
+    }
+}
+
+fn dir_size(dir: &Path) -> u64 {
+    WalkDir::new(dir)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_file())
+        .filter_map(|entry| entry.metadata().ok())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::time::Duration;
+    use tempfile::tempdir;
+
+    #[test]
+    fn least_recently_used_caches_are_evicted_first() {
+        let home = tempdir().expect("tempdir");
+        let old_project = tempdir().expect("tempdir");
+        let new_project = tempdir().expect("tempdir");
+
+        let old_dir = project_cache_dir(home.path(), old_project.path()).expect("cache");
+        std::fs::write(old_dir.join("blob"), vec![0; 4096]).expect("write");
+        std::thread::sleep(Duration::from_millis(20));
+        let new_dir = project_cache_dir(home.path(), new_project.path()).expect("cache");
+        std::fs::write(new_dir.join("blob"), vec![0; 4096]).expect("write");
+        assert_eq!(
+            project_cache_dir(home.path(), old_project.path()).expect("cache"),
+            old_dir
+        );
+        std::thread::sleep(Duration::from_millis(20));
+        // Using the old project's cache again makes the new one the oldest.
+        project_cache_dir(home.path(), old_project.path()).expect("cache");
+
+        let listed: Vec<PathBuf> = list_project_caches(home.path())
+            .expect("list")
+            .into_iter()
+            .map(|entry| entry.project_root)
+            .collect();
+        assert_eq!(
+            listed,
+            vec![
+                new_project.path().to_path_buf(),
+                old_project.path().to_path_buf()
+            ]
+        );
+
+        let evicted = evict_project_caches(home.path(), 6000).expect("evict");
+        assert_eq!(
+            evicted
+                .iter()
+                .map(|entry| entry.path.clone())
+                .collect::<Vec<_>>(),
+            vec![new_dir.clone()]
+        );
+        assert!(!new_dir.exists());
+        assert!(old_dir.join("blob").exists());
+        assert_eq!(clear_project_caches(home.path()).expect("clear"), 1);
+    }
+
+    #[test]
+    fn tool_caches_already_set_are_kept() {
+        let dir = Path::new("cache");
+        let mut env = HashMap::from([("GOCACHE".to_string(), "go".to_string())]);
+        set_cache_env(dir, &mut env);
+        let under = |subdir: &str| dir.join(subdir).to_string_lossy().into_owned();
+        assert_eq!(
+            env,
+            HashMap::from([
+                ("CODEX_CACHE_DIR".to_string(), "cache".to_string()),
+                ("PIP_CACHE_DIR".to_string(), under("pip")),
+                ("npm_config_cache".to_string(), under("npm")),
+                ("GOCACHE".to_string(), "go".to_string()),
+            ])
+        );
+    }
+}
@@ -13,6 +13,7 @@
 //!     that order.
 //! 3.  We do **not** walk past the Git root.
 
+use crate::build_cache;
 use crate::config::Config;
 use dunce::canonicalize as normalize_path;
 use std::path::PathBuf;
@@ -29,9 +30,10 @@ pub const LOCAL_PROJECT_DOC_FILENAME: &str = "AGENTS.override.md";
 const PROJECT_DOC_SEPARATOR: &str = "\n\n--- project-doc ---\n\n";
 
 /// Combines `Config::instructions` and `AGENTS.md` (if present) into a single
-/// string of instructions.
+/// string of instructions. The project docs are served from
+/// [`build_cache::project_docs`] while none of them changed.
 pub(crate) async fn get_user_instructions(config: &Config) -> Option<String> {
-    match read_project_docs(config).await {
+    match build_cache::project_docs(config).await {
         Ok(Some(project_doc)) => match &config.user_instructions {
             Some(original_instructions) => Some(format!(
                 "{original_instructions}{PROJECT_DOC_SEPARATOR}{project_doc}"
@@ -60,6 +62,14 @@ pub async fn read_project_docs(config: &Config) -> std::io::Result<Option<String
     }
 
     let paths = discover_project_doc_paths(config)?;
+    read_project_doc_files(&paths, max_total).await
+}
+
+/// Concatenate the docs at `paths`, reading at most `max_total` bytes in total.
+pub(crate) async fn read_project_doc_files(
+    paths: &[PathBuf],
+    max_total: usize,
+) -> std::io::Result<Option<String>> {
     if paths.is_empty() {
         return Ok(None);
     }
@@ -72,7 +82,7 @@ pub async fn read_project_docs(config: &Config) -> std::io::Result<Option<String
             break;
         }
 
-        let file = match tokio::fs::File::open(&p).await {
+        let file = match tokio::fs::File::open(p).await {
             Ok(f) => f,
             Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
             Err(e) => return Err(e),
@@ -218,6 +228,8 @@ mod tests {
         .expect("defaults for test should always succeed");
 
         config.cwd = root.path().to_path_buf();
+        // `codex_home` is deleted when this returns; keep the docs cache in `root`.
+        config.codex_home = root.path().join(".codex");
         config.project_doc_max_bytes = limit;
 
         config.user_instructions = instructions.map(ToOwned::to_owned);
@@ -0,0 +1,190 @@
+//! Quarantine for files that escalated commands create outside the workspace.
+//!
+//! A command that runs without the sandbox, because it asked for escalated
+//! permissions or was retried unsandboxed after a denial, can write anywhere
+//! the user can. With the `quarantine` feature, the places such commands
+//! typically write to (the home directory, the temp directory and, on Unix,
+//! `/usr/local` and `/opt`) are searched after the command for entries born
+//! while it ran. They are reported as workspace findings that the user can
+//! delete with `Op::CleanupWorkspace` or keep with `Op::KeepQuarantined`.
+//!
+//! Only birth times are consulted, so files the command merely modified are
+//! never reported. On filesystems that do not record birth times nothing is
+//! reported at all.
+
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use codex_protocol::protocol::IntegrityFinding;
+use codex_protocol::protocol::IntegrityFindingKind;
+use tracing::warn;
+use walkdir::DirEntry;
+use walkdir::WalkDir;
+
+/// How deep below each searched directory new entries are looked for.
+const MAX_DEPTH: usize = 4;
+
+/// Bound the walk so that a large home directory does not delay the turn.
+const MAX_SCANNED_ENTRIES: usize = 50_000;
+
+/// File times come from a coarse clock that can lag [`SystemTime::now`] by a
+/// scheduler tick, so an entry born just after `since` may carry an earlier
+/// time.
+const BIRTH_TIME_SLACK: Duration = Duration::from_millis(50);
+
+/// Directories that are never searched; they churn constantly and are not
+/// where stray files end up.
+const SKIPPED_DIRS: [&str; 4] = [".git", "node_modules", "target", ".cache"];
+
+/// Entries created under the usual system locations since `since`, leaving
+/// out anything under `excluded` (the workspace, the sandbox's writable roots
+/// and `$CODEX_HOME`).
+pub(crate) async fn files_created_outside(
+    excluded: Vec<PathBuf>,
+    since: SystemTime,
+) -> Vec<IntegrityFinding> {
+    tokio::task::spawn_blocking(move || scan(&search_roots(), &excluded, since))
+        .await
+        .unwrap_or_else(|err| {
+            warn!("quarantine scan failed: {err}");
+            Vec::new()
+        })
+}
+
+fn search_roots() -> Vec<PathBuf> {
+    let mut roots: Vec<PathBuf> = dirs::home_dir().into_iter().collect();
+    roots.push(std::env::temp_dir());
+    if cfg!(unix) {
+        roots.push(PathBuf::from("/usr/local"));
+        roots.push(PathBuf::from("/opt"));
+    }
+    roots
+}
+
+fn scan(roots: &[PathBuf], excluded: &[PathBuf], since: SystemTime) -> Vec<IntegrityFinding> {
+    let mut findings: Vec<IntegrityFinding> = Vec::new();
+    let mut scanned = 0;
+    for root in roots {
+        let mut walker = WalkDir::new(root)
+            .min_depth(1)
+            .max_depth(MAX_DEPTH)
+            .into_iter();
+        while let Some(entry) = walker.next() {
+            scanned += 1;
+            if scanned > MAX_SCANNED_ENTRIES {
+                return findings;
+            }
+            let Ok(entry) = entry else {
+                continue;
+            };
+            let is_dir = entry.file_type().is_dir();
+            if excluded.iter().any(|path| entry.path().starts_with(path)) || is_skipped(&entry) {
+                if is_dir {
+                    walker.skip_current_dir();
+                }
+                continue;
+            }
+            if !created_since(&entry, since) {
+                continue;
+            }
+            // A new directory is reported as a whole.
+            if is_dir {
+                walker.skip_current_dir();
+            }
+            let finding = escalated_file(entry.into_path(), is_dir);
+            if !findings.iter().any(|f| f.id == finding.id) {
+                findings.push(finding);
+            }
+        }
+    }
+    findings
+}
+
+fn is_skipped(entry: &DirEntry) -> bool {
+    entry.file_type().is_dir()
+        && entry
+            .file_name()
+            .to_str()
+            .is_some_and(|name| SKIPPED_DIRS.contains(&name))
+}
+
+fn created_since(entry: &DirEntry, since: SystemTime) -> bool {
+    let since = since.checked_sub(BIRTH_TIME_SLACK).unwrap_or(since);
+    entry
+        .metadata()
+        .ok()
+        .and_then(|metadata| metadata.created().ok())
+        .is_some_and(|created| created >= since)
+}
+
+fn escalated_file(path: PathBuf, is_dir: bool) -> IntegrityFinding {
+    let (noun, cleanup) = if is_dir {
+        ("directory", "delete the directory")
+    } else {
+        ("file", "delete the file")
+    };
+    IntegrityFinding {
+        id: format!("quarantine:{}", path.display()),
+        kind: IntegrityFindingKind::EscalatedFile,
+        description: format!(
+            "{noun} {} was created outside the workspace by a command that ran without the \
+             sandbox",
+            path.display()
+        ),
+        path: Some(path),
+        pid: None,
+        cleanup: cleanup.to_string(),
+    }
+}
+
+/// Whether the entry a quarantine finding points at is still there.
+pub(crate) fn still_exists(finding: &IntegrityFinding) -> bool {
+    finding
+        .path
+        .as_deref()
+        .is_some_and(|path| path.symlink_metadata().is_ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::tempdir;
+
+    #[test]
+    fn reports_entries_born_after_the_command_started() {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path().to_path_buf();
+        if std::fs::metadata(&root)
+            .and_then(|metadata| metadata.created())
+            .is_err()
+        {
+            // Birth times are not recorded here, so nothing can be reported.
+            return;
+        }
+        std::fs::create_dir_all(root.join("bin")).expect("mkdir");
+        std::fs::write(root.join("bin/old"), "old").expect("write");
+        std::thread::sleep(BIRTH_TIME_SLACK * 4);
+        let since = SystemTime::now();
+
+        std::fs::write(root.join("bin/old"), "modified").expect("write");
+        std::fs::write(root.join("bin/tool"), "new").expect("write");
+        std::fs::create_dir_all(root.join("share/tool/data")).expect("mkdir");
+        std::fs::write(root.join("share/tool/data/db"), "new").expect("write");
+        std::fs::create_dir_all(root.join("work")).expect("mkdir");
+        std::fs::write(root.join("work/out.txt"), "new").expect("write");
+        std::fs::create_dir_all(root.join("node_modules")).expect("mkdir");
+
+        let mut findings = scan(std::slice::from_ref(&root), &[root.join("work")], since);
+        findings.sort_by(|a, b| a.id.cmp(&b.id));
+        assert_eq!(
+            findings,
+            vec![
+                escalated_file(root.join("bin/tool"), false),
+                escalated_file(root.join("share"), true),
+            ]
+        );
+        assert!(still_exists(&findings[0]));
+    }
+}
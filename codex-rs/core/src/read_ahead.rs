@@ -0,0 +1,425 @@
+//! Speculative read-ahead for `read_file`.
+//!
+//! Edit loops usually read a file and then, one round-trip later, its test
+//! file, the module it belongs to or a file it includes. With the
+//! `read_ahead` feature enabled, every `read_file` call reads those related
+//! files in the background so that the follow-up call is served from memory.
+//! Prefetched contents are only used while the file's size and modification
+//! time are unchanged, so edits made in between are never hidden.
+
+use std::collections::HashMap;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use tokio::io::AsyncReadExt;
+use tokio::sync::Mutex;
+
+/// Related files prefetched for one read.
+const MAX_RELATED_FILES: usize = 8;
+/// Larger files are left to be read on demand.
+const MAX_PREFETCH_BYTES: u64 = 256 * 1024;
+/// The cache is cleared when it grows past this many files.
+const MAX_CACHED_FILES: usize = 64;
+/// Includes are only looked for near the top of a file.
+const MAX_SCANNED_BYTES: u64 = 16 * 1024;
+
+const JS_EXTENSIONS: [&str; 6] = ["ts", "tsx", "js", "jsx", "mjs", "cjs"];
+
+struct CachedFile {
+    contents: Arc<[u8]>,
+    len: u64,
+    modified: Option<SystemTime>,
+}
+
+/// Contents of files read ahead of the model asking for them.
+#[derive(Default)]
+pub(crate) struct ReadAhead {
+    files: Mutex<HashMap<PathBuf, CachedFile>>,
+}
+
+impl ReadAhead {
+    /// The prefetched contents of `path`, unless it changed since it was read.
+    pub(crate) async fn get(&self, path: &Path) -> Option<Arc<[u8]>> {
+        let metadata = tokio::fs::metadata(path).await.ok()?;
+        let files = self.files.lock().await;
+        let cached = files.get(path)?;
+        (cached.len == metadata.len() && cached.modified == metadata.modified().ok())
+            .then(|| Arc::clone(&cached.contents))
+    }
+
+    /// Read the files related to `path` in the background.
+    pub(crate) fn prefetch_related(self: &Arc<Self>, path: PathBuf) {
+        let read_ahead = Arc::clone(self);
+        tokio::spawn(async move {
+            for related in related_files(&path).await {
+                read_ahead.prefetch(related).await;
+            }
+        });
+    }
+
+    async fn prefetch(&self, path: PathBuf) {
+        if self.get(&path).await.is_some() {
+            return;
+        }
+        let Ok(metadata) = tokio::fs::metadata(&path).await else {
+            return;
+        };
+        if !metadata.is_file() || metadata.len() > MAX_PREFETCH_BYTES {
+            return;
+        }
+        let Ok(contents) = tokio::fs::read(&path).await else {
+            return;
+        };
+        // A write between the `metadata` and `read` calls leaves an older
+        // mtime in the entry, so the next `get` treats it as stale.
+        let entry = CachedFile {
+            len: contents.len() as u64,
+            contents: contents.into(),
+            modified: metadata.modified().ok(),
+        };
+        let mut files = self.files.lock().await;
+        if files.len() >= MAX_CACHED_FILES {
+            files.clear();
+        }
+        files.insert(path, entry);
+    }
+}
+
+/// Existing files related to `path`, most likely to be read next first.
+async fn related_files(path: &Path) -> Vec<PathBuf> {
+    let mut head = String::new();
+    if let Ok(file) = tokio::fs::File::open(path).await {
+        let mut bytes = Vec::new();
+        if file
+            .take(MAX_SCANNED_BYTES)
+            .read_to_end(&mut bytes)
+            .await
+            .is_ok()
+        {
+            head = String::from_utf8_lossy(&bytes).into_owned();
+        }
+    }
+
+    let mut related: Vec<PathBuf> = Vec::new();
+    for candidate in candidates(path, &head) {
+        if related.len() == MAX_RELATED_FILES {
+            break;
+        }
+        let candidate = normalize(&candidate);
+        if candidate == path || related.contains(&candidate) {
+            continue;
+        }
+        if tokio::fs::metadata(&candidate)
+            .await
+            .is_ok_and(|metadata| metadata.is_file())
+        {
+            related.push(candidate);
+        }
+    }
+    related
+}
+
+/// Paths that may be related to `path`, whose first bytes are `head`:
+/// the matching test or source file, the module the file belongs to and the
+/// files it includes. Candidates need not exist.
+fn candidates(path: &Path, head: &str) -> Vec<PathBuf> {
+    let (Some(dir), Some(name), Some(stem), Some(ext)) = (
+        path.parent(),
+        path.file_name().and_then(|name| name.to_str()),
+        path.file_stem().and_then(|stem| stem.to_str()),
+        path.extension().and_then(|ext| ext.to_str()),
+    ) else {
+        return Vec::new();
+    };
+
+    let mut out: Vec<PathBuf> = Vec::new();
+    match ext {
+        "rs" => {
+            match stem.strip_suffix("_tests") {
+                Some(source) => out.push(dir.join(format!("{source}.rs"))),
+                None => {
+                    out.push(dir.join(format!("{stem}_tests.rs")));
+                    out.push(dir.join(stem).join("tests.rs"));
+                    if let Some(parent) = dir.parent() {
+                        out.push(parent.join("tests").join(name));
+                    }
+                }
+            }
+            let is_module_root = matches!(name, "mod.rs" | "lib.rs" | "main.rs");
+            if !is_module_root {
+                out.push(dir.join("mod.rs"));
+                out.push(dir.with_extension("rs"));
+            }
+            // `mod foo;` in `a/mod.rs` refers to `a/foo.rs`, in `a/b.rs` to
+            // `a/b/foo.rs`.
+            let module_dir = if is_module_root {
+                dir.to_path_buf()
+            } else {
+                dir.join(stem)
+            };
+            for module in rust_modules(head) {
+                out.push(module_dir.join(format!("{module}.rs")));
+                out.push(module_dir.join(module).join("mod.rs"));
+            }
+        }
+        "py" => {
+            if let Some(source) = stem.strip_prefix("test_") {
+                out.push(dir.join(format!("{source}.py")));
+                if let Some(package_dir) = dir.parent() {
+                    out.push(package_dir.join(format!("{source}.py")));
+                }
+            } else if let Some(source) = stem.strip_suffix("_test") {
+                out.push(dir.join(format!("{source}.py")));
+            } else {
+                out.push(dir.join(format!("test_{stem}.py")));
+                out.push(dir.join(format!("{stem}_test.py")));
+                out.push(dir.join("tests").join(format!("test_{stem}.py")));
+            }
+            if name != "__init__.py" {
+                out.push(dir.join("__init__.py"));
+            }
+            for module in python_relative_imports(head) {
+                out.push(dir.join(format!("{module}.py")));
+                out.push(dir.join(module).join("__init__.py"));
+            }
+        }
+        "go" => match stem.strip_suffix("_test") {
+            Some(source) => out.push(dir.join(format!("{source}.go"))),
+            None => out.push(dir.join(format!("{stem}_test.go"))),
+        },
+        ext if JS_EXTENSIONS.contains(&ext) => {
+            let source = stem
+                .strip_suffix(".test")
+                .or_else(|| stem.strip_suffix(".spec"));
+            match source {
+                Some(source) => out.push(dir.join(format!("{source}.{ext}"))),
+                None => {
+                    out.push(dir.join(format!("{stem}.test.{ext}")));
+                    out.push(dir.join(format!("{stem}.spec.{ext}")));
+                    out.push(dir.join("__tests__").join(format!("{stem}.test.{ext}")));
+                }
+            }
+            if stem != "index" {
+                out.push(dir.join(format!("index.{ext}")));
+            }
+            for specifier in js_relative_imports(head) {
+                let target = dir.join(specifier);
+                if target.extension().is_some() {
+                    out.push(target);
+                    continue;
+                }
+                out.push(target.with_extension(ext));
+                out.extend(
+                    JS_EXTENSIONS
+                        .iter()
+                        .filter(|candidate| **candidate != ext)
+                        .map(|candidate| target.with_extension(candidate)),
+                );
+                out.push(target.join(format!("index.{ext}")));
+            }
+        }
+        "c" | "cc" | "cpp" | "cxx" | "h" | "hh" | "hpp" => {
+            let counterparts: &[&str] = if ext.starts_with('h') {
+                &["c", "cc", "cpp", "cxx"]
+            } else {
+                &["h", "hh", "hpp"]
+            };
+            out.extend(
+                counterparts
+                    .iter()
+                    .map(|counterpart| dir.join(format!("{stem}.{counterpart}"))),
+            );
+            out.extend(c_local_includes(head).map(|include| dir.join(include)));
+        }
+        _ => {}
+    }
+    out
+}
+
+/// Names declared with `mod name;`.
+fn rust_modules(head: &str) -> impl Iterator<Item = &str> {
+    head.lines().filter_map(|line| {
+        let line = line.trim();
+        let line = line.strip_prefix("pub ").unwrap_or(line);
+        let line = match line.strip_prefix("pub(") {
+            Some(rest) => rest.split_once(") ")?.1,
+            None => line,
+        };
+        line.strip_prefix("mod ")?.strip_suffix(';')
+    })
+}
+
+/// Modules imported with `from .name import ...` or `from . import name`.
+fn python_relative_imports(head: &str) -> Vec<&str> {
+    let mut modules = Vec::new();
+    for line in head.lines() {
+        let Some(rest) = line.trim().strip_prefix("from .") else {
+            continue;
+        };
+        // Parent-relative imports (`from ..x`) are left alone.
+        if rest.starts_with('.') {
+            continue;
+        }
+        let Some((module, names)) = rest.split_once(" import ") else {
+            continue;
+        };
+        if module.is_empty() {
+            modules.extend(
+                names
+                    .split(',')
+                    .filter_map(|name| name.split_whitespace().next()),
+            );
+        } else if let Some(first) = module.split('.').next() {
+            modules.push(first);
+        }
+    }
+    modules
+}
+
+/// Relative specifiers of `import ... from './x'`, `import './x'` and
+/// `require('./x')`.
+fn js_relative_imports(head: &str) -> Vec<&str> {
+    let mut specifiers = Vec::new();
+    for line in head.lines() {
+        let line = line.trim();
+        let quoted = if line.starts_with("import ") || line.starts_with("export ") {
+            line.rsplit_once(" from ")
+                .map_or(line.strip_prefix("import "), |(_, source)| Some(source))
+        } else {
+            line.split_once("require(").map(|(_, rest)| rest)
+        };
+        let Some(quoted) = quoted.map(str::trim_start) else {
+            continue;
+        };
+        let Some(quote) = quoted.chars().next().filter(|c| matches!(c, '\'' | '"')) else {
+            continue;
+        };
+        let Some((specifier, _)) = quoted[1..].split_once(quote) else {
+            continue;
+        };
+        if specifier.starts_with("./") || specifier.starts_with("../") {
+            specifiers.push(specifier);
+        }
+    }
+    specifiers
+}
+
+/// Files included with `#include "name"` (system includes are skipped).
+fn c_local_includes(head: &str) -> impl Iterator<Item = &str> {
+    head.lines().filter_map(|line| {
+        let rest = line.trim().strip_prefix('#')?.trim_start();
+        let rest = rest.strip_prefix("include")?.trim_start();
+        rest.strip_prefix('"')?
+            .split_once('"')
+            .map(|(name, _)| name)
+    })
+}
+
+/// Remove `.` and resolve `..` without touching the filesystem, so that the
+/// cache key matches the absolute path the model passes to `read_file`.
+fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::ParentDir => {
+                out.pop();
+            }
+            Component::CurDir => {}
+            other => out.push(other.as_os_str()),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::tempdir;
+
+    fn paths(paths: &[&str]) -> Vec<PathBuf> {
+        paths.iter().map(PathBuf::from).collect()
+    }
+
+    #[test]
+    fn rust_candidates_include_tests_parent_and_declared_modules() {
+        assert_eq!(
+            candidates(
+                Path::new("/repo/src/tools/spec.rs"),
+                "use std::fmt;\nmod builder;\npub(crate) mod schema;\n"
+            ),
+            paths(&[
+                "/repo/src/tools/spec_tests.rs",
+                "/repo/src/tools/spec/tests.rs",
+                "/repo/src/tests/spec.rs",
+                "/repo/src/tools/mod.rs",
+                "/repo/src/tools.rs",
+                "/repo/src/tools/spec/builder.rs",
+                "/repo/src/tools/spec/builder/mod.rs",
+                "/repo/src/tools/spec/schema.rs",
+                "/repo/src/tools/spec/schema/mod.rs",
+            ])
+        );
+        assert_eq!(
+            candidates(Path::new("/repo/src/history_tests.rs"), ""),
+            paths(&["/repo/src/history.rs", "/repo/src/mod.rs", "/repo/src.rs"])
+        );
+    }
+
+    #[test]
+    fn parses_relative_imports() {
+        let python = "import os\nfrom .models import User\nfrom . import views, urls\n\
+                      from ..shared import x\n";
+        assert_eq!(
+            python_relative_imports(python),
+            vec!["models", "views", "urls"]
+        );
+
+        let js = "import React from 'react';\nimport { a } from \"./a\";\nimport './styles.css';\n\
+                  const b = require('../lib/b');\nexport * from './c';\n";
+        assert_eq!(
+            js_relative_imports(js),
+            vec!["./a", "./styles.css", "../lib/b", "./c"]
+        );
+
+        let c = "#include <stdio.h>\n#include \"util.h\"\n#  include \"io/file.h\"\n";
+        assert_eq!(
+            c_local_includes(c).collect::<Vec<_>>(),
+            vec!["util.h", "io/file.h"]
+        );
+    }
+
+    #[tokio::test]
+    async fn serves_related_files_until_they_change() {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path();
+        std::fs::create_dir(root.join("lib")).expect("create lib");
+        let source = root.join("app.ts");
+        let test = root.join("app.test.ts");
+        let helper = root.join("lib/helper.ts");
+        std::fs::write(&source, "import { help } from './lib/helper';\n").expect("write");
+        std::fs::write(&test, "test('app', () => {});\n").expect("write");
+        std::fs::write(&helper, "export const help = 1;\n").expect("write");
+
+        assert_eq!(
+            related_files(&source).await,
+            vec![test.clone(), helper.clone()]
+        );
+
+        let read_ahead = ReadAhead::default();
+        for path in related_files(&source).await {
+            read_ahead.prefetch(path).await;
+        }
+        assert_eq!(
+            read_ahead.get(&helper).await.as_deref(),
+            Some(b"export const help = 1;\n".as_slice())
+        );
+
+        std::fs::write(&helper, "export const help = 2; // edited\n").expect("write");
+        assert_eq!(read_ahead.get(&helper).await, None);
+        assert_eq!(read_ahead.get(&source).await, None);
+    }
+}
@@ -0,0 +1,673 @@
+//! Converts patches and workspace diffs into [`RenderedFileDiff`]s: hunks with
+//! numbered lines, intraline highlights and the file's language, ready for a
+//! web or desktop frontend to display without parsing patch text itself.
+
+use std::collections::HashMap;
+use std::iter::Peekable;
+use std::path::Path;
+use std::path::PathBuf;
+use std::str::Lines;
+
+use codex_protocol::protocol::FileChange;
+use codex_protocol::rendered_diff::HighlightRange;
+use codex_protocol::rendered_diff::RenderedFileDiff;
+use codex_protocol::rendered_diff::RenderedFileStatus;
+use codex_protocol::rendered_diff::RenderedHunk;
+use codex_protocol::rendered_diff::RenderedLine;
+use codex_protocol::rendered_diff::RenderedLineKind;
+use similar::ChangeTag;
+use similar::TextDiff;
+
+const DEV_NULL: &str = "/dev/null";
+
+/// Changed lines that have less than this much in common with their
+/// counterpart are shown as wholly replaced rather than highlighted.
+const MIN_HIGHLIGHT_SIMILARITY: f32 = 0.5;
+
+/// Renders the changes of a patch, as carried by `PatchApplyBegin` and
+/// `ApplyPatchApprovalRequest` events, sorted by path.
+pub fn render_file_changes(changes: &HashMap<PathBuf, FileChange>) -> Vec<RenderedFileDiff> {
+    let mut files: Vec<RenderedFileDiff> = changes
+        .iter()
+        .map(|(path, change)| match change {
+            FileChange::Add { content } => RenderedFileDiff {
+                path: path.clone(),
+                old_path: None,
+                status: RenderedFileStatus::Added,
+                language: language_for_path(path).map(str::to_string),
+                binary: false,
+                hunks: whole_file_hunk(content, RenderedLineKind::Added),
+            },
+            FileChange::Delete { content } => RenderedFileDiff {
+                path: path.clone(),
+                old_path: None,
+                status: RenderedFileStatus::Deleted,
+                language: language_for_path(path).map(str::to_string),
+                binary: false,
+                hunks: whole_file_hunk(content, RenderedLineKind::Removed),
+            },
+            FileChange::Binary { previous_size, .. } => RenderedFileDiff {
+                path: path.clone(),
+                old_path: None,
+                status: if previous_size.is_some() {
+                    RenderedFileStatus::Modified
+                } else {
+                    RenderedFileStatus::Added
+                },
+                language: None,
+                binary: true,
+                hunks: Vec::new(),
+            },
+            FileChange::Update {
+                unified_diff,
+                move_path,
+            } => {
+                let new_path = move_path.as_ref().unwrap_or(path);
+                let mut lines = unified_diff.lines().peekable();
+                let mut hunks = Vec::new();
+                while let Some(line) = lines.next() {
+                    if let Some(hunk) = parse_hunk(line, &mut lines) {
+                        hunks.push(hunk);
+                    }
+                }
+                RenderedFileDiff {
+                    path: new_path.clone(),
+                    old_path: move_path.as_ref().map(|_| path.clone()),
+                    status: if move_path.is_some() {
+                        RenderedFileStatus::Renamed
+                    } else {
+                        RenderedFileStatus::Modified
+                    },
+                    language: language_for_path(new_path).map(str::to_string),
+                    binary: false,
+                    hunks,
+                }
+            }
+        })
+        .collect();
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    files
+}
+
+/// Renders a unified diff covering any number of files, such as the
+/// git-style diff of a `TurnDiff` event or the output of `git diff`.
+pub fn render_unified_diff(diff: &str) -> Vec<RenderedFileDiff> {
+    let mut files = Vec::new();
+    let mut file: Option<FileBuilder> = None;
+    let mut lines = diff.lines().peekable();
+    while let Some(line) = lines.next() {
+        if let Some(paths) = line.strip_prefix("diff --git ") {
+            files.extend(file.take().map(FileBuilder::finish));
+            file = Some(FileBuilder::from_git_header(paths));
+            continue;
+        }
+        // Plain diffs have no `diff --git` line; a new `---` header outside
+        // a hunk starts the next file.
+        let starts_plain_file = line.starts_with("--- ")
+            && file.as_ref().is_none_or(FileBuilder::has_paths)
+            && lines.peek().is_some_and(|next| next.starts_with("+++ "));
+        if starts_plain_file {
+            files.extend(file.take().map(FileBuilder::finish));
+        }
+        let current = file.get_or_insert_with(FileBuilder::default);
+        if let Some(path) = line.strip_prefix("--- ") {
+            current.old_path = header_path(path, "a/");
+            current.old_path_seen = true;
+        } else if let Some(path) = line.strip_prefix("+++ ") {
+            current.new_path = header_path(path, "b/");
+            current.new_path_seen = true;
+        } else if line.starts_with("new file mode ") {
+            current.added = true;
+        } else if line.starts_with("deleted file mode ") {
+            current.deleted = true;
+        } else if let Some(path) = line.strip_prefix("rename from ") {
+            current.old_path = Some(path.to_string());
+        } else if let Some(path) = line.strip_prefix("rename to ") {
+            current.new_path = Some(path.to_string());
+        } else if line.starts_with("Binary files ") || line == "GIT binary patch" {
+            current.binary = true;
+        } else if let Some(hunk) = parse_hunk(line, &mut lines) {
+            current.hunks.push(hunk);
+        }
+    }
+    files.extend(file.map(FileBuilder::finish));
+    files
+}
+
+/// The language of the file at `path` for syntax highlighting, guessed from
+/// its name, using the identifiers common to web highlighters.
+pub fn language_for_path(path: &Path) -> Option<&'static str> {
+    let name = path.file_name()?.to_str()?;
+    let by_name = match name {
+        "Makefile" | "makefile" | "GNUmakefile" => Some("makefile"),
+        "Dockerfile" | "Containerfile" => Some("dockerfile"),
+        "CMakeLists.txt" => Some("cmake"),
+        "Cargo.lock" => Some("toml"),
+        _ => None,
+    };
+    if by_name.is_some() {
+        return by_name;
+    }
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    let language = match extension.as_str() {
+        "rs" => "rust",
+        "py" | "pyi" => "python",
+        "js" | "mjs" | "cjs" => "javascript",
+        "jsx" => "jsx",
+        "ts" | "mts" | "cts" => "typescript",
+        "tsx" => "tsx",
+        "go" => "go",
+        "java" => "java",
+        "kt" | "kts" => "kotlin",
+        "swift" => "swift",
+        "c" | "h" => "c",
+        "cc" | "cpp" | "cxx" | "hh" | "hpp" | "hxx" => "cpp",
+        "cs" => "csharp",
+        "rb" => "ruby",
+        "php" => "php",
+        "scala" => "scala",
+        "hs" => "haskell",
+        "ml" | "mli" => "ocaml",
+        "ex" | "exs" => "elixir",
+        "erl" => "erlang",
+        "clj" | "cljs" => "clojure",
+        "lua" => "lua",
+        "r" => "r",
+        "jl" => "julia",
+        "dart" => "dart",
+        "zig" => "zig",
+        "sh" | "bash" | "zsh" => "bash",
+        "fish" => "fish",
+        "ps1" | "psm1" => "powershell",
+        "sql" => "sql",
+        "html" | "htm" => "html",
+        "css" => "css",
+        "scss" => "scss",
+        "less" => "less",
+        "vue" => "vue",
+        "svelte" => "svelte",
+        "json" | "jsonc" => "json",
+        "yaml" | "yml" => "yaml",
+        "toml" => "toml",
+        "xml" | "svg" => "xml",
+        "md" | "markdown" => "markdown",
+        "tex" => "latex",
+        "proto" => "protobuf",
+        "graphql" | "gql" => "graphql",
+        "tf" => "hcl",
+        "nix" => "nix",
+        "ipynb" => "json",
+        _ => return None,
+    };
+    Some(language)
+}
+
+#[derive(Default)]
+struct FileBuilder {
+    old_path: Option<String>,
+    new_path: Option<String>,
+    old_path_seen: bool,
+    new_path_seen: bool,
+    added: bool,
+    deleted: bool,
+    binary: bool,
+    hunks: Vec<RenderedHunk>,
+}
+
+impl FileBuilder {
+    /// Starts a file from the `a/X b/Y` part of a `diff --git` line. The
+    /// `---`/`+++` headers, when present, take precedence.
+    fn from_git_header(paths: &str) -> Self {
+        let (old, new) = paths
+            .strip_prefix("a/")
+            .and_then(|rest| rest.split_once(" b/"))
+            .unwrap_or((paths, paths));
+        Self {
+            old_path: Some(old.to_string()),
+            new_path: Some(new.to_string()),
+            ..Self::default()
+        }
+    }
+
+    fn has_paths(&self) -> bool {
+        self.old_path_seen || self.new_path_seen
+    }
+
+    fn finish(self) -> RenderedFileDiff {
+        let added = self.added || self.old_path.is_none();
+        let deleted = self.deleted || self.new_path.is_none();
+        let path = self
+            .new_path
+            .clone()
+            .or_else(|| self.old_path.clone())
+            .unwrap_or_default();
+        let status = if added {
+            RenderedFileStatus::Added
+        } else if deleted {
+            RenderedFileStatus::Deleted
+        } else if self.old_path != self.new_path {
+            RenderedFileStatus::Renamed
+        } else {
+            RenderedFileStatus::Modified
+        };
+        let path = PathBuf::from(path);
+        RenderedFileDiff {
+            old_path: match status {
+                RenderedFileStatus::Renamed => self.old_path.map(PathBuf::from),
+                _ => None,
+            },
+            status,
+            language: language_for_path(&path).map(str::to_string),
+            binary: self.binary,
+            hunks: self.hunks,
+            path,
+        }
+    }
+}
+
+/// The path in a `---` or `+++` header, without its `a/` or `b/` prefix or a
+/// trailing timestamp, or `None` for `/dev/null`.
+fn header_path(header: &str, prefix: &str) -> Option<String> {
+    let path = header.split('\t').next().unwrap_or(header);
+    if path == DEV_NULL {
+        return None;
+    }
+    Some(path.strip_prefix(prefix).unwrap_or(path).to_string())
+}
+
+/// Parses the hunk starting at the `@@` line `header`, consuming its lines
+/// from `lines`. Returns `None` if `header` does not start a hunk.
+fn parse_hunk(header: &str, lines: &mut Peekable<Lines<'_>>) -> Option<RenderedHunk> {
+    let (ranges, section) = header.strip_prefix("@@ ")?.split_once(" @@")?;
+    let mut ranges = ranges.split_whitespace();
+    let (old_start, old_count) = parse_range(ranges.next()?.strip_prefix('-')?)?;
+    let (new_start, new_count) = parse_range(ranges.next()?.strip_prefix('+')?)?;
+
+    let mut old_line = old_start;
+    let mut new_line = new_start;
+    let mut old_remaining = old_count;
+    let mut new_remaining = new_count;
+    let mut rendered = Vec::new();
+    while let Some(&line) = lines.peek() {
+        if line.starts_with('\\') {
+            // `\ No newline at end of file`
+            lines.next();
+            continue;
+        }
+        if old_remaining == 0 && new_remaining == 0 {
+            break;
+        }
+        // Some tools strip the space from empty context lines.
+        let (kind, text) = if let Some(text) = line.strip_prefix('+') {
+            (RenderedLineKind::Added, text)
+        } else if let Some(text) = line.strip_prefix('-') {
+            (RenderedLineKind::Removed, text)
+        } else if let Some(text) = line.strip_prefix(' ') {
+            (RenderedLineKind::Context, text)
+        } else if line.is_empty() {
+            (RenderedLineKind::Context, line)
+        } else {
+            break;
+        };
+        lines.next();
+        let (old, new) = match kind {
+            RenderedLineKind::Context => (Some(old_line), Some(new_line)),
+            RenderedLineKind::Removed => (Some(old_line), None),
+            RenderedLineKind::Added => (None, Some(new_line)),
+        };
+        if old.is_some() {
+            old_line += 1;
+            old_remaining = old_remaining.saturating_sub(1);
+        }
+        if new.is_some() {
+            new_line += 1;
+            new_remaining = new_remaining.saturating_sub(1);
+        }
+        rendered.push(RenderedLine {
+            kind,
+            old_line: old,
+            new_line: new,
+            text: text.to_string(),
+            highlights: Vec::new(),
+        });
+    }
+    highlight_changes(&mut rendered);
+
+    Some(RenderedHunk {
+        old_start,
+        old_count,
+        new_start,
+        new_count,
+        header: section.trim_start().to_string(),
+        lines: rendered,
+    })
+}
+
+/// Parses `start,count` or `start`, whose count is 1.
+fn parse_range(range: &str) -> Option<(u32, u32)> {
+    match range.split_once(',') {
+        Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+        None => Some((range.parse().ok()?, 1)),
+    }
+}
+
+/// A hunk holding all of `content`, for a file that is added or deleted.
+fn whole_file_hunk(content: &str, kind: RenderedLineKind) -> Vec<RenderedHunk> {
+    if content.is_empty() {
+        return Vec::new();
+    }
+    let lines: Vec<RenderedLine> = content
+        .lines()
+        .zip(1..)
+        .map(|(text, number)| RenderedLine {
+            kind,
+            old_line: (kind == RenderedLineKind::Removed).then_some(number),
+            new_line: (kind == RenderedLineKind::Added).then_some(number),
+            text: text.to_string(),
+            highlights: Vec::new(),
+        })
+        .collect();
+    let count = lines.len() as u32;
+    let (old_start, old_count, new_start, new_count) = match kind {
+        RenderedLineKind::Removed => (1, count, 0, 0),
+        _ => (0, 0, 1, count),
+    };
+    vec![RenderedHunk {
+        old_start,
+        old_count,
+        new_start,
+        new_count,
+        header: String::new(),
+        lines,
+    }]
+}
+
+/// Pairs each run of removed lines with the run of added lines that follows
+/// it, line by line, and highlights what changed within each pair.
+fn highlight_changes(lines: &mut [RenderedLine]) {
+    let mut i = 0;
+    while i < lines.len() {
+        let removed_start = i;
+        while i < lines.len() && lines[i].kind == RenderedLineKind::Removed {
+            i += 1;
+        }
+        let added_start = i;
+        while i < lines.len() && lines[i].kind == RenderedLineKind::Added {
+            i += 1;
+        }
+        let pairs = (added_start - removed_start).min(i - added_start);
+        for offset in 0..pairs {
+            let (old, new) = intraline_highlights(
+                &lines[removed_start + offset].text,
+                &lines[added_start + offset].text,
+            );
+            lines[removed_start + offset].highlights = old;
+            lines[added_start + offset].highlights = new;
+        }
+        if i == removed_start {
+            i += 1;
+        }
+    }
+}
+
+/// The character ranges that differ between `old` and `new`, compared word
+/// by word, or nothing if the lines are too different to be worth it.
+fn intraline_highlights(old: &str, new: &str) -> (Vec<HighlightRange>, Vec<HighlightRange>) {
+    let diff = TextDiff::from_words(old, new);
+    if diff.ratio() < MIN_HIGHLIGHT_SIMILARITY {
+        return (Vec::new(), Vec::new());
+    }
+    let mut old_ranges = Vec::new();
+    let mut new_ranges = Vec::new();
+    let mut old_offset = 0;
+    let mut new_offset = 0;
+    for change in diff.iter_all_changes() {
+        let len = change.value().chars().count() as u32;
+        match change.tag() {
+            ChangeTag::Equal => {
+                old_offset += len;
+                new_offset += len;
+            }
+            ChangeTag::Delete => {
+                push_range(&mut old_ranges, old_offset, old_offset + len);
+                old_offset += len;
+            }
+            ChangeTag::Insert => {
+                push_range(&mut new_ranges, new_offset, new_offset + len);
+                new_offset += len;
+            }
+        }
+    }
+    (old_ranges, new_ranges)
+}
+
+/// Appends `start..end`, merging it into the last range when they touch.
+fn push_range(ranges: &mut Vec<HighlightRange>, start: u32, end: u32) {
+    match ranges.last_mut() {
+        Some(last) if last.end == start => last.end = end,
+        _ => ranges.push(HighlightRange { start, end }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn line(
+        kind: RenderedLineKind,
+        old_line: Option<u32>,
+        new_line: Option<u32>,
+        text: &str,
+        highlights: &[(u32, u32)],
+    ) -> RenderedLine {
+        RenderedLine {
+            kind,
+            old_line,
+            new_line,
+            text: text.to_string(),
+            highlights: highlights
+                .iter()
+                .map(|&(start, end)| HighlightRange { start, end })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn renders_git_style_turn_diff() {
+        let diff = "diff --git a/src/lib.rs b/src/lib.rs\n\
+                    index 1111111..2222222 100644\n\
+                    --- a/src/lib.rs\n\
+                    +++ b/src/lib.rs\n\
+                    @@ -1,3 +1,3 @@ fn main() {\n \
+                    let a = 1;\n\
+                    -let b = old_value;\n\
+                    +let b = new_value;\n \
+                    let c = 3;\n\
+                    diff --git a/notes.txt b/notes.txt\n\
+                    deleted file mode 100644\n\
+                    --- a/notes.txt\n\
+                    +++ /dev/null\n\
+                    @@ -1 +0,0 @@\n\
+                    -bye\n\
+                    \\ No newline at end of file\n\
+                    diff --git a/old.py b/new.py\n\
+                    --- a/old.py\n\
+                    +++ b/new.py\n\
+                    @@ -2,0 +3 @@\n\
+                    +print(1)\n\
+                    diff --git a/logo.png b/logo.png\n\
+                    new file mode 100644\n\
+                    Binary files differ\n";
+
+        assert_eq!(
+            render_unified_diff(diff),
+            vec![
+                RenderedFileDiff {
+                    path: PathBuf::from("src/lib.rs"),
+                    old_path: None,
+                    status: RenderedFileStatus::Modified,
+                    language: Some("rust".to_string()),
+                    binary: false,
+                    hunks: vec![RenderedHunk {
+                        old_start: 1,
+                        old_count: 3,
+                        new_start: 1,
+                        new_count: 3,
+                        header: "fn main() {".to_string(),
+                        lines: vec![
+                            line(
+                                RenderedLineKind::Context,
+                                Some(1),
+                                Some(1),
+                                "let a = 1;",
+                                &[]
+                            ),
+                            line(
+                                RenderedLineKind::Removed,
+                                Some(2),
+                                None,
+                                "let b = old_value;",
+                                &[(8, 18)],
+                            ),
+                            line(
+                                RenderedLineKind::Added,
+                                None,
+                                Some(2),
+                                "let b = new_value;",
+                                &[(8, 18)],
+                            ),
+                            line(
+                                RenderedLineKind::Context,
+                                Some(3),
+                                Some(3),
+                                "let c = 3;",
+                                &[]
+                            ),
+                        ],
+                    }],
+                },
+                RenderedFileDiff {
+                    path: PathBuf::from("notes.txt"),
+                    old_path: None,
+                    status: RenderedFileStatus::Deleted,
+                    language: None,
+                    binary: false,
+                    hunks: vec![RenderedHunk {
+                        old_start: 1,
+                        old_count: 1,
+                        new_start: 0,
+                        new_count: 0,
+                        header: String::new(),
+                        lines: vec![line(RenderedLineKind::Removed, Some(1), None, "bye", &[])],
+                    }],
+                },
+                RenderedFileDiff {
+                    path: PathBuf::from("new.py"),
+                    old_path: Some(PathBuf::from("old.py")),
+                    status: RenderedFileStatus::Renamed,
+                    language: Some("python".to_string()),
+                    binary: false,
+                    hunks: vec![RenderedHunk {
+                        old_start: 2,
+                        old_count: 0,
+                        new_start: 3,
+                        new_count: 1,
+                        header: String::new(),
+                        lines: vec![line(
+                            RenderedLineKind::Added,
+                            None,
+                            Some(3),
+                            "print(1)",
+                            &[]
+                        )],
+                    }],
+                },
+                RenderedFileDiff {
+                    path: PathBuf::from("logo.png"),
+                    old_path: None,
+                    status: RenderedFileStatus::Added,
+                    language: None,
+                    binary: true,
+                    hunks: Vec::new(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn renders_patch_file_changes() {
+        let changes = HashMap::from([
+            (
+                PathBuf::from("/repo/b.ts"),
+                FileChange::Update {
+                    unified_diff: "@@ -1,2 +1,2 @@\n-const x = 1;\n+const x = 2;\n-abc\n+xyz\n"
+                        .to_string(),
+                    move_path: None,
+                },
+            ),
+            (
+                PathBuf::from("/repo/a/Makefile"),
+                FileChange::Add {
+                    content: "all:\n\ttrue\n".to_string(),
+                },
+            ),
+        ]);
+
+        assert_eq!(
+            render_file_changes(&changes),
+            vec![
+                RenderedFileDiff {
+                    path: PathBuf::from("/repo/a/Makefile"),
+                    old_path: None,
+                    status: RenderedFileStatus::Added,
+                    language: Some("makefile".to_string()),
+                    binary: false,
+                    hunks: vec![RenderedHunk {
+                        old_start: 0,
+                        old_count: 0,
+                        new_start: 1,
+                        new_count: 2,
+                        header: String::new(),
+                        lines: vec![
+                            line(RenderedLineKind::Added, None, Some(1), "all:", &[]),
+                            line(RenderedLineKind::Added, None, Some(2), "\ttrue", &[]),
+                        ],
+                    }],
+                },
+                RenderedFileDiff {
+                    path: PathBuf::from("/repo/b.ts"),
+                    old_path: None,
+                    status: RenderedFileStatus::Modified,
+                    language: Some("typescript".to_string()),
+                    binary: false,
+                    hunks: vec![RenderedHunk {
+                        old_start: 1,
+                        old_count: 2,
+                        new_start: 1,
+                        new_count: 2,
+                        header: String::new(),
+                        lines: vec![
+                            line(
+                                RenderedLineKind::Removed,
+                                Some(1),
+                                None,
+                                "const x = 1;",
+                                &[(10, 12)],
+                            ),
+                            line(
+                                RenderedLineKind::Added,
+                                None,
+                                Some(1),
+                                "const x = 2;",
+                                &[(10, 12)],
+                            ),
+                            line(RenderedLineKind::Removed, Some(2), None, "abc", &[]),
+                            line(RenderedLineKind::Added, None, Some(2), "xyz", &[]),
+                        ],
+                    }],
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,304 @@
+//! Reproducible execution.
+//!
+//! With the `reproducible_exec` feature, commands of the `shell` tool run
+//! with a pinned environment: only a few variables describing the user and
+//! the locale are kept, and the session's random seed is exported where
+//! tools read one. Every command and every input the model was given from
+//! outside the workspace, the files `read_file` returned and the responses
+//! `web_fetch` got, is recorded in a bundle under
+//! `$CODEX_HOME/artifacts/<conversation id>/replay/`:
+//!
+//! - `steps.jsonl`: one line per command or input, in the order they ran;
+//! - `inputs/<sha256>`: the contents of each input;
+//! - `replay.sh`: runs the commands again in order with the environment they
+//!   had, and reports the steps whose exit code or output differs.
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use codex_protocol::ConversationId;
+use serde::Serialize;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+use tracing::warn;
+
+use crate::artifacts::artifacts_dir;
+use crate::exec::ExecToolCallOutput;
+use crate::parse_command::shlex_join;
+use crate::util::set_executable;
+use crate::util::sha256_hex;
+
+const REPLAY_DIR: &str = "replay";
+
+/// The variables commands keep. The locale ones are already pinned by
+/// `command_env.locale` unless it inherits them.
+const PINNED_VARS: &[&str] = &[
+    "PATH", "HOME", "USER", "LOGNAME", "SHELL", "TMPDIR", "LANG", "LC_ALL", "TZ",
+];
+
+const SCRIPT_HEADER: &str = r#"#!/usr/bin/env bash
+# Runs the commands of a Codex session again, in order and with the
+# environment they had, and reports the steps that turn out differently.
+set -u
+out="$(mktemp -d)"
+digest() {
+  if command -v sha256sum >/dev/null; then sha256sum; else shasum -a 256; fi | cut -d' ' -f1
+}
+step() {
+  local n="$1" code="$2" sha="$3" dir="$4"
+  shift 4
+  (cd "$dir" && "$@") >"$out/$n" 2>&1 </dev/null
+  local got=$?
+  if [ "$got" = "$code" ] && [ "$(digest <"$out/$n")" = "$sha" ]; then
+    echo "step $n: same"
+  else
+    echo "step $n: differs, exit $got (recorded $code), output in $out/$n"
+  fi
+}
+"#;
+
+#[derive(Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum Step<'a> {
+    Command {
+        call_id: &'a str,
+        command: &'a [String],
+        cwd: &'a Path,
+        env: BTreeMap<&'a str, &'a str>,
+        exit_code: i32,
+        output_sha256: String,
+    },
+    File {
+        call_id: &'a str,
+        path: &'a Path,
+        sha256: String,
+    },
+    Fetch {
+        call_id: &'a str,
+        url: &'a str,
+        sha256: String,
+    },
+}
+
+#[derive(Serialize)]
+struct StepLine<'a> {
+    step: u64,
+    #[serde(flatten)]
+    step_data: Step<'a>,
+}
+
+/// Where an input of the session came from.
+pub(crate) enum ReplayInput<'a> {
+    File(&'a Path),
+    Fetch(&'a str),
+}
+
+pub(crate) struct ReplayBundle {
+    dir: PathBuf,
+    seed: u32,
+    /// Fixed for the session, for tools that honor `SOURCE_DATE_EPOCH`.
+    epoch: u64,
+    /// The number of the last step recorded.
+    steps: Mutex<u64>,
+}
+
+impl ReplayBundle {
+    pub(crate) fn new(codex_home: &Path, conversation_id: ConversationId) -> Self {
+        let epoch = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+        Self {
+            dir: artifacts_dir(codex_home, conversation_id).join(REPLAY_DIR),
+            seed: rand::random(),
+            epoch,
+            steps: Mutex::new(0),
+        }
+    }
+
+    /// `env` reduced to [`PINNED_VARS`], with the session's seeds set.
+    pub(crate) fn pinned_env(&self, env: &HashMap<String, String>) -> HashMap<String, String> {
+        let mut pinned: HashMap<String, String> = env
+            .iter()
+            .filter(|(name, _)| PINNED_VARS.contains(&name.as_str()))
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect();
+        let seeds = [
+            ("CODEX_REPLAY_SEED", self.seed.to_string()),
+            ("PYTHONHASHSEED", self.seed.to_string()),
+            ("SOURCE_DATE_EPOCH", self.epoch.to_string()),
+        ];
+        for (name, value) in seeds {
+            pinned.insert(name.to_string(), value);
+        }
+        pinned
+    }
+
+    /// Record a command that ran in `cwd` with `env`, before its output was
+    /// cut down or redacted.
+    pub(crate) async fn record_command(
+        &self,
+        call_id: &str,
+        command: &[String],
+        cwd: &Path,
+        env: &HashMap<String, String>,
+        output: &ExecToolCallOutput,
+    ) {
+        let env: BTreeMap<&str, &str> = env
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+            .collect();
+        let output_sha256 = sha256_hex(output.aggregated_output.text.as_bytes());
+        let mut steps = self.steps.lock().await;
+        let step = *steps + 1;
+        let script_line = script_line(step, output.exit_code, &output_sha256, cwd, &env, command);
+        let line = StepLine {
+            step,
+            step_data: Step::Command {
+                call_id,
+                command,
+                cwd,
+                env,
+                exit_code: output.exit_code,
+                output_sha256,
+            },
+        };
+        match self.append(&line, Some(&script_line)).await {
+            Ok(()) => *steps = step,
+            Err(err) => warn!("failed to record command in replay bundle: {err}"),
+        }
+    }
+
+    /// Record what the model was given from `input`.
+    pub(crate) async fn record_input(
+        &self,
+        call_id: &str,
+        input: ReplayInput<'_>,
+        contents: &[u8],
+    ) {
+        let sha256 = sha256_hex(contents);
+        let mut steps = self.steps.lock().await;
+        let step = *steps + 1;
+        let step_data = match input {
+            ReplayInput::File(path) => Step::File {
+                call_id,
+                path,
+                sha256: sha256.clone(),
+            },
+            ReplayInput::Fetch(url) => Step::Fetch {
+                call_id,
+                url,
+                sha256: sha256.clone(),
+            },
+        };
+        let result = async {
+            let inputs = self.dir.join("inputs");
+            tokio::fs::create_dir_all(&inputs).await?;
+            tokio::fs::write(inputs.join(&sha256), contents).await?;
+            self.append(&StepLine { step, step_data }, None).await
+        }
+        .await;
+        match result {
+            Ok(()) => *steps = step,
+            Err(err) => warn!("failed to record input in replay bundle: {err}"),
+        }
+    }
+
+    async fn append(&self, line: &StepLine<'_>, script_line: Option<&str>) -> io::Result<()> {
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let mut json = serde_json::to_string(line).map_err(io::Error::other)?;
+        json.push('\n');
+        append_to(&self.dir.join("steps.jsonl"), json.as_bytes()).await?;
+        if let Some(script_line) = script_line {
+            let script = self.dir.join("replay.sh");
+            if !tokio::fs::try_exists(&script).await? {
+                append_to(&script, SCRIPT_HEADER.as_bytes()).await?;
+                set_executable(&script).await?;
+            }
+            append_to(&script, script_line.as_bytes()).await?;
+        }
+        Ok(())
+    }
+}
+
+/// The line of `replay.sh` that runs `command` again with exactly `env`.
+fn script_line(
+    step: u64,
+    exit_code: i32,
+    output_sha256: &str,
+    cwd: &Path,
+    env: &BTreeMap<&str, &str>,
+    command: &[String],
+) -> String {
+    let mut args = vec![
+        "step".to_string(),
+        step.to_string(),
+        exit_code.to_string(),
+        output_sha256.to_string(),
+        cwd.to_string_lossy().into_owned(),
+        "env".to_string(),
+        "-i".to_string(),
+    ];
+    args.extend(env.iter().map(|(name, value)| format!("{name}={value}")));
+    args.extend(command.iter().cloned());
+    let mut line = shlex_join(&args);
+    line.push('\n');
+    line
+}
+
+async fn append_to(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await?;
+    file.write_all(contents).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn pinned_env_keeps_only_pinned_vars_and_sets_seeds() {
+        let bundle = ReplayBundle {
+            dir: PathBuf::from("/tmp/replay"),
+            seed: 42,
+            epoch: 1_700_000_000,
+            steps: Mutex::new(0),
+        };
+        let env = HashMap::from([
+            ("PATH".to_string(), "/usr/bin".to_string()),
+            ("TZ".to_string(), "UTC".to_string()),
+            ("RANDOM_TOKEN".to_string(), "abc".to_string()),
+        ]);
+        let expected: HashMap<String, String> = [
+            ("PATH", "/usr/bin"),
+            ("TZ", "UTC"),
+            ("CODEX_REPLAY_SEED", "42"),
+            ("PYTHONHASHSEED", "42"),
+            ("SOURCE_DATE_EPOCH", "1700000000"),
+        ]
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+        assert_eq!(bundle.pinned_env(&env), expected);
+    }
+
+    #[test]
+    fn script_lines_quote_the_command_and_environment() {
+        let env = BTreeMap::from([("LANG", "C.UTF-8"), ("PATH", "/usr/bin")]);
+        let command = ["bash", "-lc", "echo hi > out.txt"].map(String::from);
+        assert_eq!(
+            script_line(3, 0, "ab12", Path::new("/work/my app"), &env, &command),
+            "step 3 0 ab12 '/work/my app' env -i 'LANG=C.UTF-8' 'PATH=/usr/bin' bash -lc \
+             'echo hi > out.txt'\n"
+        );
+    }
+}
@@ -0,0 +1,118 @@
+//! Resource usage of the commands run during a turn.
+//!
+//! The kernel accumulates the usage of every child process Codex has reaped
+//! (`getrusage(RUSAGE_CHILDREN)`), so the footprint of a turn is the
+//! difference between a snapshot taken when it starts and one taken when it
+//! ends. Commands still running when the turn ends (for example long-lived
+//! unified exec sessions) are counted in the turn that reaps them.
+//!
+//! Network traffic is not reported: it cannot be attributed to child
+//! processes without cgroup or eBPF accounting.
+
+use crate::protocol::TurnResourceUsageEvent;
+
+/// Cumulative usage of reaped child processes at one point in time.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct ChildUsage {
+    cpu_seconds: f64,
+    max_rss_bytes: u64,
+    /// `None` where the platform does not report written bytes.
+    disk_write_bytes: Option<u64>,
+}
+
+impl ChildUsage {
+    #[cfg(unix)]
+    pub(crate) fn now() -> Option<Self> {
+        let mut usage = std::mem::MaybeUninit::<libc::rusage>::uninit();
+        // SAFETY: `usage` is a valid out-pointer for `getrusage`, which
+        // initializes it on success.
+        let usage = unsafe {
+            if libc::getrusage(libc::RUSAGE_CHILDREN, usage.as_mut_ptr()) != 0 {
+                return None;
+            }
+            usage.assume_init()
+        };
+        let seconds = |tv: libc::timeval| tv.tv_sec as f64 + tv.tv_usec as f64 / 1_000_000.0;
+        let max_rss = u64::try_from(usage.ru_maxrss).unwrap_or_default();
+        Some(Self {
+            cpu_seconds: seconds(usage.ru_utime) + seconds(usage.ru_stime),
+            // Linux reports kilobytes, macOS bytes.
+            max_rss_bytes: if cfg!(target_os = "macos") {
+                max_rss
+            } else {
+                max_rss * 1024
+            },
+            // Only Linux counts output in 512-byte units; elsewhere it is the
+            // number of block operations, which says nothing about size.
+            disk_write_bytes: cfg!(target_os = "linux")
+                .then(|| u64::try_from(usage.ru_oublock).unwrap_or_default() * 512),
+        })
+    }
+
+    #[cfg(not(unix))]
+    pub(crate) fn now() -> Option<Self> {
+        None
+    }
+
+    /// Usage between `start` and `self`, or `None` if no command finished in
+    /// between.
+    pub(crate) fn since(self, start: Self) -> Option<TurnResourceUsageEvent> {
+        let cpu_seconds = self.cpu_seconds - start.cpu_seconds;
+        if cpu_seconds <= 0.0 && self.max_rss_bytes == start.max_rss_bytes {
+            return None;
+        }
+        Some(TurnResourceUsageEvent {
+            cpu_seconds: cpu_seconds.max(0.0),
+            peak_rss_bytes: self.max_rss_bytes,
+            disk_write_bytes: self
+                .disk_write_bytes
+                .zip(start.disk_write_bytes)
+                .map(|(end, start)| end.saturating_sub(start)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn reports_the_difference_between_snapshots() {
+        let start = ChildUsage {
+            cpu_seconds: 1.5,
+            max_rss_bytes: 64 << 20,
+            disk_write_bytes: Some(4096),
+        };
+        assert_eq!(start.since(start), None);
+
+        let end = ChildUsage {
+            cpu_seconds: 4.0,
+            max_rss_bytes: 64 << 20,
+            disk_write_bytes: Some(1 << 20),
+        };
+        assert_eq!(
+            end.since(start),
+            Some(TurnResourceUsageEvent {
+                cpu_seconds: 2.5,
+                peak_rss_bytes: 64 << 20,
+                disk_write_bytes: Some((1 << 20) - 4096),
+            })
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn counts_reaped_children() {
+        let start = ChildUsage::now().expect("getrusage");
+        std::process::Command::new("sh")
+            .args(["-c", "i=0; while [ $i -lt 20000 ]; do i=$((i+1)); done"])
+            .status()
+            .expect("run sh");
+        let usage = ChildUsage::now()
+            .expect("getrusage")
+            .since(start)
+            .expect("child usage");
+        assert!(usage.peak_rss_bytes > 0);
+    }
+}
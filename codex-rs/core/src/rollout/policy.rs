@@ -62,8 +62,11 @@ pub(crate) fn should_persist_event_msg(ev: &EventMsg) -> bool {
         | EventMsg::WebSearchEnd(_)
         | EventMsg::ExecCommandBegin(_)
         | EventMsg::ExecCommandOutputDelta(_)
+        | EventMsg::ExecCommandProgress(_)
+        | EventMsg::TurnResourceUsage(_)
         | EventMsg::ExecCommandEnd(_)
         | EventMsg::ExecApprovalRequest(_)
+        | EventMsg::ExecApprovalBatchRequest(_)
         | EventMsg::ElicitationRequest(_)
         | EventMsg::ApplyPatchApprovalRequest(_)
         | EventMsg::BackgroundEvent(_)
@@ -73,13 +76,27 @@ pub(crate) fn should_persist_event_msg(ev: &EventMsg) -> bool {
         | EventMsg::TurnDiff(_)
         | EventMsg::GetHistoryEntryResponse(_)
         | EventMsg::UndoStarted(_)
+        | EventMsg::Artifact(_)
+        | EventMsg::StatusLine(_)
+        | EventMsg::CollaboratorJoined(_)
+        | EventMsg::CollaboratorLeft(_)
+        | EventMsg::ApprovalResolved(_)
+        | EventMsg::ApprovalRuleApplied(_)
+        | EventMsg::WorkspaceIntegrity(_)
+        | EventMsg::EnvironmentChanged(_)
+        | EventMsg::SandboxProbe(_)
+        | EventMsg::DoctorReport(_)
         | EventMsg::McpListToolsResponse(_)
         | EventMsg::McpStartupUpdate(_)
         | EventMsg::McpStartupComplete(_)
         | EventMsg::ListCustomPromptsResponse(_)
+        | EventMsg::ListFilesReadResponse(_)
+        | EventMsg::ContextUsage(_)
+        | EventMsg::ApprovalCache(_)
         | EventMsg::PlanUpdate(_)
         | EventMsg::ShutdownComplete
         | EventMsg::ViewImageToolCall(_)
+        | EventMsg::FileRead(_)
         | EventMsg::DeprecationNotice(_)
         | EventMsg::ItemStarted(_)
         | EventMsg::ItemCompleted(_)
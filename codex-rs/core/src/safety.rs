@@ -1,9 +1,10 @@
-use std::path::Component;
 use std::path::Path;
-use std::path::PathBuf;
+use std::sync::OnceLock;
 
 use codex_apply_patch::ApplyPatchAction;
 use codex_apply_patch::ApplyPatchFileChange;
+use codex_protocol::paths::normalize_for_comparison;
+use codex_windows_sandbox::SandboxProbe;
 
 use crate::exec::SandboxType;
 
@@ -27,6 +28,13 @@ pub fn set_windows_sandbox_enabled(enabled: bool) {
 #[allow(dead_code)]
 pub fn set_windows_sandbox_enabled(_enabled: bool) {}
 
+/// Whether the Windows sandbox can create its restricted tokens and job
+/// objects on this machine. Probed once per process.
+pub(crate) fn probe_windows_sandbox(codex_home: &Path) -> &'static SandboxProbe {
+    static PROBE: OnceLock<SandboxProbe> = OnceLock::new();
+    PROBE.get_or_init(|| codex_windows_sandbox::probe_sandbox(codex_home))
+}
+
 #[derive(Debug, PartialEq)]
 pub enum SafetyCheck {
     AutoApprove {
@@ -114,70 +122,52 @@ pub fn get_platform_sandbox() -> Option<SandboxType> {
     }
 }
 
+/// Whether `sandbox_policy` lets `path` be written. `path` and the writable
+/// roots are converted to absolute, normalized forms before the prefix check,
+/// which looks at `path` as spelled: symlinks are not resolved.
+pub(crate) fn is_path_writable(path: &Path, sandbox_policy: &SandboxPolicy, cwd: &Path) -> bool {
+    let writable_roots = match sandbox_policy {
+        SandboxPolicy::ReadOnly => return false,
+        SandboxPolicy::DangerFullAccess => return true,
+        SandboxPolicy::WorkspaceWrite { .. } => sandbox_policy.get_writable_roots_with_cwd(cwd),
+    };
+    let abs = normalize_for_comparison(&cwd.join(path));
+    writable_roots
+        .iter()
+        .any(|writable_root| writable_root.is_path_writable(&abs))
+}
+
 fn is_write_patch_constrained_to_writable_paths(
     action: &ApplyPatchAction,
     sandbox_policy: &SandboxPolicy,
     cwd: &Path,
 ) -> bool {
     // Early‑exit if there are no declared writable roots.
-    let writable_roots = match sandbox_policy {
+    match sandbox_policy {
         SandboxPolicy::ReadOnly => {
             return false;
         }
         SandboxPolicy::DangerFullAccess => {
             return true;
         }
-        SandboxPolicy::WorkspaceWrite { .. } => sandbox_policy.get_writable_roots_with_cwd(cwd),
-    };
-
-    // Normalize a path by removing `.` and resolving `..` without touching the
-    // filesystem (works even if the file does not exist).
-    fn normalize(path: &Path) -> Option<PathBuf> {
-        let mut out = PathBuf::new();
-        for comp in path.components() {
-            match comp {
-                Component::ParentDir => {
-                    out.pop();
-                }
-                Component::CurDir => { /* skip */ }
-                other => out.push(other.as_os_str()),
-            }
-        }
-        Some(out)
+        SandboxPolicy::WorkspaceWrite { .. } => {}
     }
 
-    // Determine whether `path` is inside **any** writable root. Both `path`
-    // and roots are converted to absolute, normalized forms before the
-    // prefix check.
-    let is_path_writable = |p: &PathBuf| {
-        let abs = if p.is_absolute() {
-            p.clone()
-        } else {
-            cwd.join(p)
-        };
-        let abs = match normalize(&abs) {
-            Some(v) => v,
-            None => return false,
-        };
-
-        writable_roots
-            .iter()
-            .any(|writable_root| writable_root.is_path_writable(&abs))
-    };
-
     for (path, change) in action.changes() {
         match change {
-            ApplyPatchFileChange::Add { .. } | ApplyPatchFileChange::Delete { .. } => {
-                if !is_path_writable(path) {
+            ApplyPatchFileChange::Add { .. }
+            | ApplyPatchFileChange::Delete { .. }
+            | ApplyPatchFileChange::Binary { .. } => {
+                if !is_path_writable(path, sandbox_policy, cwd) {
                     return false;
                 }
             }
             ApplyPatchFileChange::Update { move_path, .. } => {
-                if !is_path_writable(path) {
+                if !is_path_writable(path, sandbox_policy, cwd) {
                     return false;
                 }
                 if let Some(dest) = move_path
-                    && !is_path_writable(dest)
+                    && !is_path_writable(dest, sandbox_policy, cwd)
                 {
                     return false;
                 }
@@ -191,6 +181,7 @@ fn is_write_patch_constrained_to_writable_paths(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::path::PathBuf;
     use tempfile::TempDir;
 
     #[test]
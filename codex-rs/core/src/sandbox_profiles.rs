@@ -0,0 +1,220 @@
+//! `sandbox_profiles`: named sandbox settings that replace the session's for
+//! some tool calls, and `sandbox_profile_rules`, which pick the profile of a
+//! call by the tool's name or the command it runs.
+//!
+//! The orchestrator looks the profile up before it builds the call's
+//! [`SandboxAttempt`](crate::tools::sandboxing::SandboxAttempt). A profile
+//! only narrows or re-aims what the session's sandbox policy grants: its
+//! writable roots and network access apply under `workspace-write`, while a
+//! `read-only` session stays read-only and `danger-full-access` runs every
+//! command outside the sandbox as before.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_protocol::protocol::SandboxPolicy;
+use regex::Regex;
+use wildmatch::WildMatch;
+
+use crate::approval_rules::command_line;
+use crate::config::types::SandboxProfile;
+use crate::config::types::SandboxProfileRule;
+
+/// Checks that every rule names a defined profile and that every
+/// `command_regex` compiles, so a typo fails at startup.
+pub(crate) fn validate_sandbox_profile_rules(
+    profiles: &HashMap<String, SandboxProfile>,
+    rules: &[SandboxProfileRule],
+) -> Result<(), String> {
+    for (index, rule) in rules.iter().enumerate() {
+        let number = index + 1;
+        if !profiles.contains_key(&rule.profile) {
+            return Err(format!(
+                "sandbox profile rule #{number} names an undefined profile `{}`",
+                rule.profile
+            ));
+        }
+        if let Some(pattern) = &rule.command_regex
+            && let Err(err) = Regex::new(pattern)
+        {
+            return Err(format!(
+                "sandbox profile rule #{number} has an invalid command_regex: {err}"
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// The profile of the first rule that matches a call of `tool_name`, which
+/// runs `command` if it runs one. Rules with command conditions never match
+/// a call that runs no command.
+pub(crate) fn find_sandbox_profile<'a>(
+    profiles: &'a HashMap<String, SandboxProfile>,
+    rules: &[SandboxProfileRule],
+    tool_name: &str,
+    command: Option<&[String]>,
+) -> Option<&'a SandboxProfile> {
+    let line = command.map(command_line);
+    rules
+        .iter()
+        .find(|rule| matches(rule, tool_name, line.as_deref()))
+        .and_then(|rule| profiles.get(&rule.profile))
+}
+
+fn matches(rule: &SandboxProfileRule, tool_name: &str, line: Option<&str>) -> bool {
+    rule.tool.as_ref().is_none_or(|tool| tool == tool_name)
+        && rule
+            .command
+            .as_ref()
+            .is_none_or(|pattern| line.is_some_and(|line| WildMatch::new(pattern).matches(line)))
+        && rule.command_regex.as_ref().is_none_or(|pattern| {
+            line.is_some_and(|line| Regex::new(pattern).is_ok_and(|regex| regex.is_match(line)))
+        })
+}
+
+/// `policy` with the writable roots and network access of `profile`, and the
+/// directory the sandbox makes writable as the workspace. With
+/// `writable_roots`, the first root takes the place of `cwd` and the others
+/// replace the policy's extra roots; missing roots are created so the
+/// sandbox can grant them.
+pub(crate) fn apply_sandbox_profile(
+    profile: &SandboxProfile,
+    policy: &SandboxPolicy,
+    cwd: &Path,
+) -> (SandboxPolicy, PathBuf) {
+    let SandboxPolicy::WorkspaceWrite {
+        writable_roots,
+        network_access,
+        exclude_tmpdir_env_var,
+        exclude_slash_tmp,
+    } = policy
+    else {
+        return (policy.clone(), cwd.to_path_buf());
+    };
+    let (sandbox_cwd, writable_roots) = match &profile.writable_roots {
+        None => (cwd.to_path_buf(), writable_roots.clone()),
+        Some(roots) => {
+            let mut roots = roots.iter().map(|root| cwd.join(root));
+            let Some(first) = roots.next() else {
+                return (SandboxPolicy::ReadOnly, cwd.to_path_buf());
+            };
+            let rest: Vec<PathBuf> = roots.collect();
+            for root in std::iter::once(&first).chain(&rest) {
+                if let Err(err) = std::fs::create_dir_all(root) {
+                    tracing::warn!("failed to create {}: {err}", root.display());
+                }
+            }
+            (first, rest)
+        }
+    };
+    let policy = SandboxPolicy::WorkspaceWrite {
+        writable_roots,
+        network_access: profile.network_access.unwrap_or(*network_access),
+        exclude_tmpdir_env_var: *exclude_tmpdir_env_var,
+        exclude_slash_tmp: *exclude_slash_tmp,
+    };
+    (policy, sandbox_cwd)
+}
+
+/// Remove the variables `profile` excludes from `env` and set its own.
+pub(crate) fn apply_profile_env(profile: &SandboxProfile, env: &mut HashMap<String, String>) {
+    env.retain(|name, _| {
+        !profile
+            .env_exclude
+            .iter()
+            .any(|pattern| WildMatch::new(pattern).matches(name))
+    });
+    env.extend(
+        profile
+            .env_set
+            .iter()
+            .map(|(name, value)| (name.clone(), value.clone())),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn argv(words: &[&str]) -> Vec<String> {
+        words.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn first_matching_rule_picks_the_profile() {
+        let build = SandboxProfile {
+            writable_roots: Some(vec![PathBuf::from("target")]),
+            ..SandboxProfile::default()
+        };
+        let offline = SandboxProfile {
+            network_access: Some(false),
+            ..SandboxProfile::default()
+        };
+        let profiles = HashMap::from([
+            ("build".to_string(), build.clone()),
+            ("offline".to_string(), offline.clone()),
+        ]);
+        let rules = vec![
+            SandboxProfileRule {
+                profile: "build".to_string(),
+                tool: Some("shell".to_string()),
+                command: Some("cargo build*".to_string()),
+                command_regex: None,
+            },
+            SandboxProfileRule {
+                profile: "offline".to_string(),
+                tool: Some("shell".to_string()),
+                command: None,
+                command_regex: None,
+            },
+        ];
+        let cargo_build = argv(&["bash", "-lc", "cargo build --release"]);
+        let ls = argv(&["ls"]);
+
+        assert_eq!(
+            [
+                find_sandbox_profile(&profiles, &rules, "shell", Some(&cargo_build)),
+                find_sandbox_profile(&profiles, &rules, "shell", Some(&ls)),
+                find_sandbox_profile(&profiles, &rules, "apply_patch", None),
+            ],
+            [Some(&build), Some(&offline), None]
+        );
+        assert_eq!(validate_sandbox_profile_rules(&profiles, &rules), Ok(()));
+    }
+
+    #[test]
+    fn writable_roots_replace_the_workspace() {
+        let tmp = tempfile::tempdir().unwrap();
+        let profile = SandboxProfile {
+            writable_roots: Some(vec![PathBuf::from("target"), PathBuf::from("dist")]),
+            network_access: Some(true),
+            ..SandboxProfile::default()
+        };
+        let policy = SandboxPolicy::WorkspaceWrite {
+            writable_roots: vec![PathBuf::from("/elsewhere")],
+            network_access: false,
+            exclude_tmpdir_env_var: true,
+            exclude_slash_tmp: true,
+        };
+
+        let (applied, sandbox_cwd) = apply_sandbox_profile(&profile, &policy, tmp.path());
+
+        assert_eq!(sandbox_cwd, tmp.path().join("target"));
+        assert_eq!(
+            applied,
+            SandboxPolicy::WorkspaceWrite {
+                writable_roots: vec![tmp.path().join("dist")],
+                network_access: true,
+                exclude_tmpdir_env_var: true,
+                exclude_slash_tmp: true,
+            }
+        );
+        assert!(tmp.path().join("dist").is_dir());
+        assert_eq!(
+            apply_sandbox_profile(&profile, &SandboxPolicy::ReadOnly, tmp.path()).0,
+            SandboxPolicy::ReadOnly
+        );
+    }
+}
@@ -10,6 +10,7 @@ use crate::client::ModelClient;
 use crate::client_common::Prompt;
 use crate::client_common::ResponseEvent;
 use crate::config::Config;
+use crate::inline_script::inline_scripts;
 use crate::protocol::SandboxPolicy;
 use askama::Template;
 use codex_otel::otel_event_manager::OtelEventManager;
@@ -34,6 +35,7 @@ struct SandboxAssessmentPromptTemplate<'a> {
     working_directory: &'a str,
     command_argv: &'a str,
     command_joined: &'a str,
+    inline_scripts: Option<&'a str>,
     sandbox_failure_message: Option<&'a str>,
 }
 
@@ -58,6 +60,7 @@ pub(crate) async fn assess_command(
     let command_json = serde_json::to_string(command).unwrap_or_else(|_| "[]".to_string());
     let command_joined =
         shlex::try_join(command.iter().map(String::as_str)).unwrap_or_else(|_| command.join(" "));
+    let scripts = format_inline_scripts(command);
     let failure = failure_message
         .map(str::trim)
         .filter(|msg| !msg.is_empty())
@@ -83,6 +86,7 @@ pub(crate) async fn assess_command(
         working_directory: cwd_str.as_str(),
         command_argv: command_json.as_str(),
         command_joined: command_joined.as_str(),
+        inline_scripts: scripts.as_deref(),
         sandbox_failure_message: failure.as_deref(),
     };
     let rendered_prompt = match prompt_template.render() {
@@ -192,6 +196,19 @@ pub(crate) async fn assess_command(
     None
 }
 
+/// The scripts embedded in `command`, as fenced blocks, so the assessment
+/// covers the code that runs and not only the interpreter invocation.
+fn format_inline_scripts(command: &[String]) -> Option<String> {
+    let blocks: Vec<String> = inline_scripts(command)
+        .into_iter()
+        .map(|script| {
+            let language = script.language.unwrap_or_default();
+            format!("```{language}\n{}\n```", script.source)
+        })
+        .collect();
+    (!blocks.is_empty()).then(|| blocks.join("\n"))
+}
+
 fn summarize_sandbox_policy(policy: &SandboxPolicy) -> String {
     match policy {
         SandboxPolicy::DangerFullAccess => "danger-full-access".to_string(),
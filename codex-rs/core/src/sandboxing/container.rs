@@ -0,0 +1,399 @@
+//! The container backend of the sandbox.
+//!
+//! With `sandbox_backend = "container"`, a session starts a Docker or Podman
+//! container the first time it needs it and runs the sandboxed commands of
+//! the `shell` tool in it with `exec`. The working directory and the
+//! writable roots of the command's sandbox policy are bind-mounted at the
+//! same paths, the directory read-only when the policy does not let commands
+//! write to it, and the container has no network unless the policy or
+//! `sandbox_container.network_access` allows it. A command whose working
+//! directory or policy needs other mounts replaces the container with one
+//! that has them. The container is removed when the session shuts down.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+
+use codex_protocol::ConversationId;
+use tokio::process::Command;
+use tokio::sync::Mutex;
+use tracing::warn;
+
+use crate::config::Config;
+use crate::config::types::ContainerRuntime;
+use crate::config::types::SandboxContainer;
+use crate::protocol::SandboxPolicy;
+
+/// Long enough to pull an image the first time.
+const START_TIMEOUT: Duration = Duration::from_secs(300);
+const STOP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Variables that describe the host and would break the image's own setup,
+/// so commands keep the container's values.
+const HOST_ONLY_VARS: &[&str] = &["PATH", "HOME", "TMPDIR", "SHELL"];
+
+/// The container of a session, started on first use and restarted when a
+/// command needs other mounts than the running one has.
+#[derive(Clone)]
+pub(crate) struct SessionContainers {
+    config: SandboxContainer,
+    name: String,
+    /// What the session starts with, for [`SessionContainers::warm_up`].
+    initial: Mounts,
+    running: Arc<Mutex<Option<Arc<SessionContainer>>>>,
+}
+
+impl SessionContainers {
+    pub(crate) fn new(config: &Config, conversation_id: ConversationId) -> Self {
+        Self {
+            config: config.sandbox_container.clone(),
+            name: format!("codex-{conversation_id}"),
+            initial: Mounts::new(&config.cwd, &config.sandbox_policy, None),
+            running: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// A container that mounts what `policy` lets a command in `cwd` see,
+    /// the running one if it does. `scratch_dir`, which differs from call to
+    /// call, is not mounted.
+    pub(crate) async fn get(
+        &self,
+        cwd: &Path,
+        policy: &SandboxPolicy,
+        scratch_dir: Option<&Path>,
+    ) -> Result<Arc<SessionContainer>, String> {
+        let mounts = Mounts::new(cwd, policy, scratch_dir);
+        let mut running = self.running.lock().await;
+        if let Some(container) = running.as_ref()
+            && container.mounts == mounts
+        {
+            return Ok(Arc::clone(container));
+        }
+        if let Some(container) = running.take() {
+            container.stop().await;
+        }
+        let container = Arc::new(SessionContainer::start(&self.config, &self.name, mounts).await?);
+        *running = Some(Arc::clone(&container));
+        Ok(container)
+    }
+
+    /// Start the container for the session's working directory and policy
+    /// in the background.
+    pub(crate) fn warm_up(&self) {
+        let containers = self.clone();
+        tokio::spawn(async move {
+            let Mounts { cwd, policy } = containers.initial.clone();
+            if let Err(err) = containers.get(&cwd, &policy, None).await {
+                warn!("failed to start the sandbox container: {err}");
+            }
+        });
+    }
+
+    /// Remove the running container, if any.
+    pub(crate) async fn stop(&self) {
+        let running = self.running.lock().await.take();
+        if let Some(container) = running {
+            container.stop().await;
+        }
+    }
+}
+
+/// What a container mounts.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Mounts {
+    cwd: PathBuf,
+    policy: SandboxPolicy,
+}
+
+impl Mounts {
+    fn new(cwd: &Path, policy: &SandboxPolicy, scratch_dir: Option<&Path>) -> Self {
+        let mut policy = policy.clone();
+        if let Some(scratch_dir) = scratch_dir
+            && let SandboxPolicy::WorkspaceWrite { writable_roots, .. } = &mut policy
+        {
+            writable_roots.retain(|root| root != scratch_dir);
+        }
+        Self {
+            cwd: cwd.to_path_buf(),
+            policy,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct SessionContainer {
+    runtime: ContainerRuntime,
+    name: String,
+    mounts: Mounts,
+}
+
+impl SessionContainer {
+    /// Start the container `name` with `mounts`.
+    async fn start(config: &SandboxContainer, name: &str, mounts: Mounts) -> Result<Self, String> {
+        let program = config.runtime.program();
+        let args = run_args(config, name, &mounts.cwd, &mounts.policy, host_user());
+        let output = tokio::time::timeout(
+            START_TIMEOUT,
+            Command::new(program)
+                .args(&args)
+                .stdin(Stdio::null())
+                .output(),
+        )
+        .await
+        .map_err(|_| format!("`{program} run` timed out"))?
+        .map_err(|err| format!("failed to run {program}: {err}"))?;
+        if !output.status.success() {
+            return Err(format!(
+                "`{program} run` failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(Self {
+            runtime: config.runtime,
+            name: name.to_string(),
+            mounts,
+        })
+    }
+
+    /// The command that runs `command` in the container. The variables of
+    /// `env` are passed by name, so their values are read from the
+    /// environment of the `exec` client rather than shown on its command line.
+    pub(crate) fn exec_command(
+        &self,
+        command: Vec<String>,
+        cwd: &Path,
+        env: &HashMap<String, String>,
+    ) -> Vec<String> {
+        let mut args = vec![
+            self.runtime.program().to_string(),
+            "exec".to_string(),
+            "--workdir".to_string(),
+            cwd.to_string_lossy().into_owned(),
+        ];
+        let mut names: Vec<&String> = env
+            .keys()
+            .filter(|name| !HOST_ONLY_VARS.contains(&name.as_str()))
+            .collect();
+        names.sort();
+        for name in names {
+            args.push("--env".to_string());
+            args.push(name.clone());
+        }
+        args.push(self.name.clone());
+        args.extend(command);
+        args
+    }
+
+    pub(crate) async fn stop(&self) {
+        let program = self.runtime.program();
+        let result = tokio::time::timeout(
+            STOP_TIMEOUT,
+            Command::new(program)
+                .args(["rm", "--force", &self.name])
+                .stdin(Stdio::null())
+                .output(),
+        )
+        .await;
+        match result {
+            Ok(Ok(output)) if output.status.success() => {}
+            Ok(Ok(output)) => warn!(
+                "failed to remove container {}: {}",
+                self.name,
+                String::from_utf8_lossy(&output.stderr)
+            ),
+            Ok(Err(err)) => warn!("failed to run {program} rm: {err}"),
+            Err(_) => warn!("removing container {} timed out", self.name),
+        }
+    }
+}
+
+/// Commands run as the user running Codex, so that what they write to the
+/// workspace is not owned by root.
+#[cfg(unix)]
+fn host_user() -> Option<String> {
+    // SAFETY: getuid and getgid cannot fail.
+    let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+    Some(format!("{uid}:{gid}"))
+}
+
+#[cfg(not(unix))]
+fn host_user() -> Option<String> {
+    None
+}
+
+fn run_args(
+    config: &SandboxContainer,
+    name: &str,
+    cwd: &Path,
+    policy: &SandboxPolicy,
+    user: Option<String>,
+) -> Vec<String> {
+    let mut args: Vec<String> = ["run", "--detach", "--rm", "--init", "--name", name]
+        .map(String::from)
+        .into();
+    if let Some(user) = user {
+        args.push("--user".to_string());
+        args.push(user);
+    }
+    if !config.network_access && !policy.has_full_network_access() {
+        args.push("--network=none".to_string());
+    }
+    let mount = |path: &Path, read_only: bool| {
+        let path = path.to_string_lossy();
+        let suffix = if read_only { ":ro" } else { "" };
+        format!("--volume={path}:{path}{suffix}")
+    };
+    let writable_roots = policy.get_writable_roots_with_cwd(cwd);
+    if !writable_roots.iter().any(|root| root.root == cwd) {
+        args.push(mount(cwd, true));
+    }
+    for root in &writable_roots {
+        args.push(mount(&root.root, false));
+        for subpath in &root.read_only_subpaths {
+            args.push(mount(subpath, true));
+        }
+    }
+    args.push(format!("--workdir={}", cwd.to_string_lossy()));
+    args.push(config.image.clone());
+    args.extend(["sleep", "infinity"].map(String::from));
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::path::PathBuf;
+
+    #[test]
+    fn containers_mount_the_workspace_without_network() {
+        let cwd = PathBuf::from("/work/app");
+        let policy = SandboxPolicy::WorkspaceWrite {
+            writable_roots: vec![PathBuf::from("/work/cache")],
+            network_access: false,
+            exclude_tmpdir_env_var: true,
+            exclude_slash_tmp: true,
+        };
+        let config = SandboxContainer::default();
+        assert_eq!(
+            run_args(
+                &config,
+                "codex-1",
+                &cwd,
+                &policy,
+                Some("1000:1000".to_string())
+            ),
+            [
+                "run",
+                "--detach",
+                "--rm",
+                "--init",
+                "--name",
+                "codex-1",
+                "--user",
+                "1000:1000",
+                "--network=none",
+                "--volume=/work/cache:/work/cache",
+                "--volume=/work/app:/work/app",
+                "--workdir=/work/app",
+                "ubuntu:24.04",
+                "sleep",
+                "infinity",
+            ]
+        );
+        assert_eq!(
+            run_args(
+                &config,
+                "codex-1",
+                &cwd,
+                &SandboxPolicy::new_read_only_policy(),
+                None
+            ),
+            [
+                "run",
+                "--detach",
+                "--rm",
+                "--init",
+                "--name",
+                "codex-1",
+                "--network=none",
+                "--volume=/work/app:/work/app:ro",
+                "--workdir=/work/app",
+                "ubuntu:24.04",
+                "sleep",
+                "infinity",
+            ]
+        );
+    }
+
+    #[test]
+    fn mounts_ignore_the_scratch_dir() {
+        let cwd = Path::new("/work/app");
+        let policy = |writable_roots: Vec<PathBuf>| SandboxPolicy::WorkspaceWrite {
+            writable_roots,
+            network_access: false,
+            exclude_tmpdir_env_var: true,
+            exclude_slash_tmp: true,
+        };
+        let scratch = Path::new("/tmp/codex-scratch/call-1");
+        let base = Mounts::new(cwd, &policy(vec![PathBuf::from("/work/cache")]), None);
+        assert_eq!(
+            Mounts::new(
+                cwd,
+                &policy(vec![PathBuf::from("/work/cache"), scratch.to_path_buf()]),
+                Some(scratch)
+            ),
+            base
+        );
+        assert_ne!(Mounts::new(cwd, &policy(Vec::new()), Some(scratch)), base);
+        assert_ne!(
+            Mounts::new(
+                Path::new("/work/other"),
+                &policy(vec![PathBuf::from("/work/cache")]),
+                None
+            ),
+            base
+        );
+    }
+
+    #[test]
+    fn commands_get_the_environment_by_name() {
+        let container = SessionContainer {
+            runtime: ContainerRuntime::Podman,
+            name: "codex-1".to_string(),
+            mounts: Mounts::new(
+                Path::new("/work/app"),
+                &SandboxPolicy::new_read_only_policy(),
+                None,
+            ),
+        };
+        let env = HashMap::from([
+            ("PATH".to_string(), "/usr/bin".to_string()),
+            ("LANG".to_string(), "C.UTF-8".to_string()),
+            ("API_TOKEN".to_string(), "secret".to_string()),
+        ]);
+        assert_eq!(
+            container.exec_command(
+                vec!["cargo".to_string(), "test".to_string()],
+                Path::new("/work/app"),
+                &env
+            ),
+            [
+                "podman",
+                "exec",
+                "--workdir",
+                "/work/app",
+                "--env",
+                "API_TOKEN",
+                "--env",
+                "LANG",
+                "codex-1",
+                "cargo",
+                "test",
+            ]
+        );
+    }
+}
@@ -0,0 +1,403 @@
+//! Diagnosis of commands the sandbox stopped.
+//!
+//! When a sandboxed command fails in a way that looks like a denial,
+//! [`diagnose_denial`] reads its output for what was refused: the files it
+//! could not write, from `EPERM`, `EACCES` and `EROFS` messages and from
+//! Seatbelt violation lines such as `deny(1) file-write-create /path`, and
+//! the connections it could not open. The result is kept in
+//! [`SandboxRetryData`](crate::tools::sandboxing::SandboxRetryData), so the
+//! approval prompt for the retry without the sandbox can say what was
+//! blocked, and so a write under a directory listed in
+//! `sandbox_denials.auto_grant` can be retried in the sandbox with just that
+//! directory made writable; see [`auto_grant_roots`].
+//!
+//! Only the command's own output is read. Violations that Seatbelt reports to
+//! the system log alone are not seen.
+
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::LazyLock;
+
+use regex_lite::Regex;
+use serde::Serialize;
+
+use crate::exec::ExecToolCallOutput;
+
+/// More denials than this are dropped; the first few tell the story.
+const MAX_DENIALS: usize = 16;
+/// Denials named in the approval prompt before the rest are counted.
+const MAX_DESCRIBED: usize = 3;
+
+const DENIAL_WORDS: &[&str] = &[
+    "permission denied",
+    "operation not permitted",
+    "read-only file system",
+    "eacces",
+    "eperm",
+    "erofs",
+];
+const NETWORK_WORDS: &[&str] = &[
+    "connect to",
+    "connection",
+    "socket",
+    "resolve host",
+    "getaddrinfo",
+    "network is unreachable",
+];
+
+static SEATBELT_DENY: LazyLock<Regex> = LazyLock::new(|| {
+    #[expect(clippy::unwrap_used)]
+    Regex::new(r"deny\(\d+\)\s+([\w-]+)(?:\s+(\S.*))?$").unwrap()
+});
+static QUOTED: LazyLock<Regex> = LazyLock::new(|| {
+    #[expect(clippy::unwrap_used)]
+    Regex::new(r#"'([^']+)'|"([^"]+)"|‘([^’]+)’"#).unwrap()
+});
+static CONNECT_TARGET: LazyLock<Regex> = LazyLock::new(|| {
+    #[expect(clippy::unwrap_used)]
+    Regex::new(concat!(
+        r"host='([^']+)'(?:, port=(\d+))?",
+        r"|connect to ([\w.-]+) port (\d+)",
+        r"|resolve host:? ([\w.-]+)",
+    ))
+    .unwrap()
+});
+
+/// Something the sandbox refused a command.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub(crate) enum SandboxDenial {
+    /// Creating or writing the file or directory at `path`.
+    Write { path: PathBuf },
+    /// Opening a connection, to `target` (`host` or `host:port`) when the
+    /// output names it.
+    Network { target: Option<String> },
+    /// Another operation Seatbelt denied, such as `mach-lookup`.
+    Other { operation: String },
+}
+
+impl SandboxDenial {
+    /// What was blocked, with paths under `home` shown from `~`.
+    pub(crate) fn describe(&self, home: Option<&Path>) -> String {
+        match self {
+            SandboxDenial::Write { path } => {
+                format!("blocked writing to {}", display_path(path, home))
+            }
+            SandboxDenial::Network {
+                target: Some(target),
+            } => format!("blocked connecting to {target}"),
+            SandboxDenial::Network { target: None } => "blocked network access".to_string(),
+            SandboxDenial::Other { operation } => format!("blocked {operation}"),
+        }
+    }
+}
+
+/// What the sandbox refused the command that produced `output`, in the order
+/// the output mentions it. Relative paths are resolved against `cwd`.
+pub(crate) fn diagnose_denial(output: &ExecToolCallOutput, cwd: &Path) -> Vec<SandboxDenial> {
+    let mut denials = Vec::new();
+    let lines = output
+        .stderr
+        .text
+        .lines()
+        .chain(output.aggregated_output.text.lines());
+    for line in lines {
+        if let Some(denial) = denial_in_line(line, cwd)
+            && !denials.contains(&denial)
+        {
+            denials.push(denial);
+            if denials.len() == MAX_DENIALS {
+                break;
+            }
+        }
+    }
+    denials
+}
+
+/// The first few `denials` joined for the approval prompt, or `None` when
+/// nothing was diagnosed.
+pub(crate) fn describe_denials(denials: &[SandboxDenial], home: Option<&Path>) -> Option<String> {
+    if denials.is_empty() {
+        return None;
+    }
+    let mut text = denials
+        .iter()
+        .take(MAX_DESCRIBED)
+        .map(|denial| denial.describe(home))
+        .collect::<Vec<_>>()
+        .join("; ");
+    if denials.len() > MAX_DESCRIBED {
+        text.push_str(&format!(" and {} more", denials.len() - MAX_DESCRIBED));
+    }
+    Some(text)
+}
+
+/// The directories to make writable for a retry in the sandbox, when every
+/// one of `denials` is a write under a directory of `auto_grant`. A write
+/// deeper down is granted the directory right below the `auto_grant` entry,
+/// so that `~/.cache` lets pip write `~/.cache/pip` rather than the whole
+/// cache; a write directly in the entry is granted the entry. Empty when
+/// any denial is something else.
+pub(crate) fn auto_grant_roots(
+    denials: &[SandboxDenial],
+    auto_grant: &[PathBuf],
+    home: Option<&Path>,
+) -> Vec<PathBuf> {
+    let allowed: Vec<PathBuf> = auto_grant
+        .iter()
+        .filter_map(|dir| expand_home(dir, home))
+        .collect();
+    let mut roots = Vec::new();
+    for denial in denials {
+        let SandboxDenial::Write { path } = denial else {
+            return Vec::new();
+        };
+        let Some(root) = allowed.iter().find_map(|dir| grant_below(path, dir)) else {
+            return Vec::new();
+        };
+        if !roots.contains(&root) {
+            roots.push(root);
+        }
+    }
+    roots
+}
+
+fn denial_in_line(line: &str, cwd: &Path) -> Option<SandboxDenial> {
+    if let Some(caps) = SEATBELT_DENY.captures(line) {
+        let operation = caps.get(1)?.as_str();
+        let target = caps.get(2).map(|target| target.as_str().trim());
+        return Some(match target {
+            Some(path) if operation.starts_with("file-write") => SandboxDenial::Write {
+                path: cwd.join(path),
+            },
+            _ if operation.starts_with("network") => SandboxDenial::Network {
+                target: target.map(str::to_string),
+            },
+            _ => SandboxDenial::Other {
+                operation: operation.to_string(),
+            },
+        });
+    }
+
+    let lower = line.to_lowercase();
+    if !DENIAL_WORDS.iter().any(|word| lower.contains(word)) {
+        return None;
+    }
+    // Network errors often quote a URL path, so they are told apart first.
+    if NETWORK_WORDS.iter().any(|word| lower.contains(word)) {
+        return Some(SandboxDenial::Network {
+            target: connect_target(line),
+        });
+    }
+    denied_path(line).map(|path| SandboxDenial::Write {
+        path: cwd.join(path),
+    })
+}
+
+/// The path a `Permission denied` style message is about: a quoted path, as
+/// in `touch: cannot touch '/x'` or `[Errno 13] Permission denied: '/x'`, or
+/// an absolute path between colons, as in `bash: /x: Permission denied`.
+fn denied_path(line: &str) -> Option<&str> {
+    let quoted = QUOTED
+        .captures_iter(line)
+        .filter_map(|caps| caps.iter().skip(1).flatten().next())
+        .map(|quoted| quoted.as_str())
+        .find(|quoted| looks_like_path(quoted) || (quoted.contains('/') && !quoted.contains(' ')));
+    quoted.or_else(|| {
+        line.split(": ")
+            .map(str::trim)
+            .find(|segment| looks_like_path(segment))
+    })
+}
+
+fn looks_like_path(text: &str) -> bool {
+    text.starts_with('/') || text.starts_with("./") || text.starts_with("../")
+}
+
+fn connect_target(line: &str) -> Option<String> {
+    let caps = CONNECT_TARGET.captures(line)?;
+    let host = [1, 3, 5]
+        .into_iter()
+        .find_map(|group| caps.get(group))?
+        .as_str();
+    Some(match [2, 4].into_iter().find_map(|group| caps.get(group)) {
+        Some(port) => format!("{host}:{}", port.as_str()),
+        None => host.to_string(),
+    })
+}
+
+/// The directory of `allowed` to grant for a write to `path`, if `path` is
+/// under `allowed`. Paths that climb out with `..` and symlinked directories
+/// are not granted.
+fn grant_below(path: &Path, allowed: &Path) -> Option<PathBuf> {
+    let rest = path.strip_prefix(allowed).ok()?;
+    if !rest
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+    {
+        return None;
+    }
+    let mut components = rest.components();
+    let root = match (components.next(), components.next()) {
+        (Some(first), Some(_)) => allowed.join(first),
+        _ => allowed.to_path_buf(),
+    };
+    let symlinked =
+        std::fs::symlink_metadata(&root).is_ok_and(|meta| meta.file_type().is_symlink());
+    (!symlinked).then_some(root)
+}
+
+fn expand_home(dir: &Path, home: Option<&Path>) -> Option<PathBuf> {
+    match (dir.strip_prefix("~"), home) {
+        (Ok(rest), Some(home)) => Some(home.join(rest)),
+        (Ok(_), None) => None,
+        _ => dir.is_absolute().then(|| dir.to_path_buf()),
+    }
+}
+
+fn display_path(path: &Path, home: Option<&Path>) -> String {
+    match home.and_then(|home| path.strip_prefix(home).ok()) {
+        Some(rest) if rest.as_os_str().is_empty() => "~".to_string(),
+        Some(rest) => format!("~/{}", rest.display()),
+        None => path.display().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exec::ExitReason;
+    use crate::exec::StreamOutput;
+    use pretty_assertions::assert_eq;
+    use std::time::Duration;
+
+    fn failed(stderr: &str) -> ExecToolCallOutput {
+        ExecToolCallOutput {
+            exit_code: 1,
+            stdout: StreamOutput::new(String::new()),
+            stderr: StreamOutput::new(stderr.to_string()),
+            aggregated_output: StreamOutput::new(stderr.to_string()),
+            duration: Duration::from_secs(1),
+            full_output_path: None,
+            exit_reason: ExitReason::SandboxDenied,
+        }
+    }
+
+    fn write(path: &str) -> SandboxDenial {
+        SandboxDenial::Write {
+            path: PathBuf::from(path),
+        }
+    }
+
+    #[test]
+    fn writes_and_connections_are_read_from_common_messages() {
+        let output = failed(
+            "\
+PermissionError: [Errno 13] Permission denied: '/home/dev/.cache/pip'
+touch: cannot touch '/etc/motd': Permission denied
+bash: /usr/local/bin/tool: Operation not permitted
+mkdir: cannot create directory ‘build/out’: Read-only file system
+npm ERR! Error: EACCES: permission denied, mkdir '/home/dev/.npm/_cacache'
+Sandbox: python3(4242) deny(1) file-write-create /Users/dev/Library/Caches/pip
+Sandbox: python3(4242) deny(1) network-outbound 151.101.0.223:443
+Sandbox: python3(4242) deny(1) mach-lookup com.apple.securityd
+curl: (7) Failed to connect to example.com port 443: Operation not permitted
+WARNING: Retrying after connection broken by 'NewConnectionError(\"HTTPSConnectionPool(\
+host='pypi.org', port=443): Failed to establish a new connection: [Errno 1] Operation not \
+permitted\")': /simple/requests/
+error: could not compile `app` (bin \"app\") due to 2 previous errors
+",
+        );
+        assert_eq!(
+            diagnose_denial(&output, Path::new("/repo")),
+            vec![
+                write("/home/dev/.cache/pip"),
+                write("/etc/motd"),
+                write("/usr/local/bin/tool"),
+                write("/repo/build/out"),
+                write("/home/dev/.npm/_cacache"),
+                write("/Users/dev/Library/Caches/pip"),
+                SandboxDenial::Network {
+                    target: Some("151.101.0.223:443".to_string()),
+                },
+                SandboxDenial::Other {
+                    operation: "mach-lookup".to_string(),
+                },
+                SandboxDenial::Network {
+                    target: Some("example.com:443".to_string()),
+                },
+                SandboxDenial::Network {
+                    target: Some("pypi.org:443".to_string()),
+                },
+            ]
+        );
+        assert_eq!(
+            diagnose_denial(&failed("exit status 1\n"), Path::new("/")),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn denials_are_described_from_home() {
+        let home = Some(Path::new("/home/dev"));
+        let denials = vec![
+            write("/home/dev/.cache/pip"),
+            SandboxDenial::Network {
+                target: Some("pypi.org:443".to_string()),
+            },
+            SandboxDenial::Network { target: None },
+            write("/tmp/x"),
+        ];
+        assert_eq!(
+            describe_denials(&denials, home).as_deref(),
+            Some(
+                "blocked writing to ~/.cache/pip; blocked connecting to pypi.org:443; \
+                 blocked network access and 1 more"
+            )
+        );
+        assert_eq!(describe_denials(&[], home), None);
+    }
+
+    #[test]
+    fn auto_grant_gives_the_directory_below_the_allowed_one() {
+        let home = Some(Path::new("/home/dev"));
+        let auto_grant = vec![PathBuf::from("~/.cache"), PathBuf::from("/opt/tools")];
+        assert_eq!(
+            auto_grant_roots(
+                &[
+                    write("/home/dev/.cache/pip/http/abc"),
+                    write("/home/dev/.cache/pip/wheels"),
+                    write("/home/dev/.cache/lock"),
+                    write("/opt/tools/bin/x"),
+                ],
+                &auto_grant,
+                home,
+            ),
+            vec![
+                PathBuf::from("/home/dev/.cache/pip"),
+                PathBuf::from("/home/dev/.cache"),
+                PathBuf::from("/opt/tools/bin"),
+            ]
+        );
+
+        let outside = [write("/home/dev/.cache/pip/x"), write("/etc/passwd")];
+        assert_eq!(
+            auto_grant_roots(&outside, &auto_grant, home),
+            Vec::<PathBuf>::new()
+        );
+        let climbing = [write("/home/dev/.cache/../.ssh/id_rsa")];
+        assert_eq!(
+            auto_grant_roots(&climbing, &auto_grant, home),
+            Vec::<PathBuf>::new()
+        );
+        let network = [
+            write("/home/dev/.cache/pip/x"),
+            SandboxDenial::Network { target: None },
+        ];
+        assert_eq!(
+            auto_grant_roots(&network, &auto_grant, home),
+            Vec::<PathBuf>::new()
+        );
+    }
+}
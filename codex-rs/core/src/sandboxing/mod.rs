@@ -7,7 +7,13 @@ ready‑to‑spawn environment.
 */
 
 pub mod assessment;
+pub(crate) mod container;
+pub(crate) mod denial;
+pub mod network;
+mod network_proxy;
+pub(crate) mod risk;
 
+use crate::config::types::SandboxLimits;
 use crate::exec::ExecExpiration;
 use crate::exec::ExecToolCallOutput;
 use crate::exec::SandboxType;
@@ -23,6 +29,9 @@ use crate::seatbelt::create_seatbelt_command_args;
 use crate::spawn::CODEX_SANDBOX_ENV_VAR;
 use crate::spawn::CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR;
 use crate::tools::sandboxing::SandboxablePreference;
+use codex_protocol::protocol::NetworkAccess;
+use container::SessionContainer;
+use network::SandboxNetwork;
 use std::collections::HashMap;
 use std::path::Path;
 use std::path::PathBuf;
@@ -58,6 +67,9 @@ pub struct CommandSpec {
     pub expiration: ExecExpiration,
     pub with_escalated_permissions: Option<bool>,
     pub justification: Option<String>,
+    /// Network access the command declared in place of the policy's; only
+    /// enforced under a platform sandbox. See [`network`].
+    pub network: Option<NetworkAccess>,
 }
 
 #[derive(Debug)]
@@ -70,6 +82,8 @@ pub struct ExecEnv {
     pub with_escalated_permissions: Option<bool>,
     pub justification: Option<String>,
     pub arg0: Option<String>,
+    /// Resource limits set on the process as it is spawned.
+    pub limits: Option<SandboxLimits>,
 }
 
 pub enum SandboxPreference {
@@ -85,6 +99,10 @@ pub(crate) enum SandboxTransformError {
     #[cfg(not(target_os = "macos"))]
     #[error("seatbelt sandbox is only available on macOS")]
     SeatbeltUnavailable,
+    #[error("failed to start the network proxy: {0}")]
+    NetworkProxy(std::io::Error),
+    #[error("the sandbox container has not been started")]
+    ContainerNotStarted,
 }
 
 #[derive(Default)]
@@ -121,6 +139,7 @@ impl SandboxManager {
         sandbox: SandboxType,
         sandbox_policy_cwd: &Path,
         codex_linux_sandbox_exe: Option<&PathBuf>,
+        container: Option<&SessionContainer>,
     ) -> Result<ExecEnv, SandboxTransformError> {
         let mut env = spec.env;
         if !policy.has_full_network_access() {
@@ -138,10 +157,16 @@ impl SandboxManager {
             SandboxType::None => (command, HashMap::new(), None),
             #[cfg(target_os = "macos")]
             SandboxType::MacosSeatbelt => {
-                let mut seatbelt_env = HashMap::new();
+                let network = SandboxNetwork::for_command(policy, spec.network.as_ref())
+                    .map_err(SandboxTransformError::NetworkProxy)?;
+                let mut seatbelt_env = network.proxy_env();
                 seatbelt_env.insert(CODEX_SANDBOX_ENV_VAR.to_string(), "seatbelt".to_string());
-                let mut args =
-                    create_seatbelt_command_args(command.clone(), policy, sandbox_policy_cwd);
+                let mut args = create_seatbelt_command_args(
+                    command.clone(),
+                    policy,
+                    network,
+                    sandbox_policy_cwd,
+                );
                 let mut full_command = Vec::with_capacity(1 + args.len());
                 full_command.push(MACOS_PATH_TO_SEATBELT_EXECUTABLE.to_string());
                 full_command.append(&mut args);
@@ -152,14 +177,20 @@ impl SandboxManager {
             SandboxType::LinuxSeccomp => {
                 let exe = codex_linux_sandbox_exe
                     .ok_or(SandboxTransformError::MissingLinuxSandboxExecutable)?;
-                let mut args =
-                    create_linux_sandbox_command_args(command.clone(), policy, sandbox_policy_cwd);
+                let network = SandboxNetwork::for_command(policy, spec.network.as_ref())
+                    .map_err(SandboxTransformError::NetworkProxy)?;
+                let mut args = create_linux_sandbox_command_args(
+                    command.clone(),
+                    policy,
+                    network,
+                    sandbox_policy_cwd,
+                );
                 let mut full_command = Vec::with_capacity(1 + args.len());
                 full_command.push(exe.to_string_lossy().to_string());
                 full_command.append(&mut args);
                 (
                     full_command,
-                    HashMap::new(),
+                    network.proxy_env(),
                     Some("codex-linux-sandbox".to_string()),
                 )
             }
@@ -171,6 +202,14 @@ impl SandboxManager {
             // When building for non-Windows targets, this variant is never constructed.
             #[cfg(not(target_os = "windows"))]
             SandboxType::WindowsRestrictedToken => (command, HashMap::new(), None),
+            SandboxType::Container => {
+                let container = container.ok_or(SandboxTransformError::ContainerNotStarted)?;
+                (
+                    container.exec_command(command, &spec.cwd, &env),
+                    HashMap::new(),
+                    None,
+                )
+            }
         };
 
         env.extend(sandbox_env);
@@ -184,6 +223,7 @@ impl SandboxManager {
             with_escalated_permissions: spec.with_escalated_permissions,
             justification: spec.justification,
             arg0: arg0_override,
+            limits: None,
         })
     }
 
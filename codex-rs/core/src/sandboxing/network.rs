@@ -0,0 +1,129 @@
+/*
+Module: sandboxing::network
+
+The network access a sandbox enforces for one command. A `ShellRequest` may
+declare a `NetworkAccess` of its own; otherwise the session's SandboxPolicy
+decides, which is all or nothing. Each backend maps a `SandboxNetwork` onto
+its own mechanism: seatbelt rules on macOS, and a network namespace or
+Landlock and seccomp rules in codex-linux-sandbox.
+*/
+
+use std::collections::HashMap;
+use std::io;
+use std::str::FromStr;
+
+use codex_protocol::protocol::NetworkAccess;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::protocol::SandboxPolicy;
+use crate::sandboxing::network_proxy::start_allowlist_proxy;
+
+/// Proxy variables set for a command whose network goes through the
+/// allowlisting proxy, in the spellings common tools read.
+const PROXY_ENV_VARS: &[&str] = &[
+    "HTTP_PROXY",
+    "HTTPS_PROXY",
+    "ALL_PROXY",
+    "http_proxy",
+    "https_proxy",
+    "all_proxy",
+];
+const NO_PROXY_ENV_VARS: &[&str] = &["NO_PROXY", "no_proxy"];
+
+/// What a sandbox lets a command reach over the network.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum SandboxNetwork {
+    /// No restriction.
+    Full,
+    /// No network; Unix domain sockets still work.
+    None,
+    /// Connections to and from this machine only.
+    Loopback,
+    /// TCP connections to the allowlisting proxy listening on this loopback
+    /// port only.
+    Proxy { port: u16 },
+}
+
+impl SandboxNetwork {
+    /// The network `policy` allows every command.
+    pub fn from_policy(policy: &SandboxPolicy) -> Self {
+        if policy.has_full_network_access() {
+            SandboxNetwork::Full
+        } else {
+            SandboxNetwork::None
+        }
+    }
+
+    /// The network for a command that declared `requested`, starting the
+    /// proxy for an allowlist. Whether access beyond `policy` was approved is
+    /// settled before the command gets here.
+    pub(crate) fn for_command(
+        policy: &SandboxPolicy,
+        requested: Option<&NetworkAccess>,
+    ) -> io::Result<Self> {
+        Ok(match requested {
+            None => SandboxNetwork::from_policy(policy),
+            Some(NetworkAccess::None) => SandboxNetwork::None,
+            Some(NetworkAccess::Loopback) => SandboxNetwork::Loopback,
+            Some(NetworkAccess::Allowlist { hosts }) => SandboxNetwork::Proxy {
+                port: start_allowlist_proxy(hosts)?,
+            },
+        })
+    }
+
+    /// Environment that sends the command's HTTP and HTTPS traffic through
+    /// the proxy; empty unless this is [`SandboxNetwork::Proxy`].
+    pub(crate) fn proxy_env(self) -> HashMap<String, String> {
+        let SandboxNetwork::Proxy { port } = self else {
+            return HashMap::new();
+        };
+        let url = format!("http://127.0.0.1:{port}");
+        let proxy = PROXY_ENV_VARS
+            .iter()
+            .map(|name| (name.to_string(), url.clone()));
+        // Anything that skips the proxy is blocked by the sandbox anyway.
+        let no_proxy = NO_PROXY_ENV_VARS
+            .iter()
+            .map(|name| (name.to_string(), String::new()));
+        proxy.chain(no_proxy).collect()
+    }
+}
+
+impl FromStr for SandboxNetwork {
+    type Err = serde_json::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn declared_access_replaces_the_policy() {
+        let read_only = SandboxPolicy::new_read_only_policy();
+        assert_eq!(
+            SandboxNetwork::for_command(&read_only, None).expect("network"),
+            SandboxNetwork::None
+        );
+        assert_eq!(
+            SandboxNetwork::for_command(
+                &SandboxPolicy::DangerFullAccess,
+                Some(&NetworkAccess::Loopback)
+            )
+            .expect("network"),
+            SandboxNetwork::Loopback
+        );
+        assert_eq!(
+            "{\"mode\":\"proxy\",\"port\":8080}"
+                .parse::<SandboxNetwork>()
+                .expect("parse"),
+            SandboxNetwork::Proxy { port: 8080 }
+        );
+    }
+}
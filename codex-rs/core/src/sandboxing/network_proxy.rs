@@ -0,0 +1,254 @@
+/*
+Module: sandboxing::network_proxy
+
+An HTTP proxy on the loopback interface that only connects to allowlisted
+hosts. Commands with an allowlist are sandboxed so that this proxy is the
+only thing they can reach, and their proxy variables point at it. HTTPS goes
+through `CONNECT`; plain HTTP requests are forwarded with `Connection: close`
+so a client cannot reuse the upstream connection for another host.
+
+One proxy runs per distinct allowlist for the life of the process.
+*/
+
+use std::collections::HashMap;
+use std::io;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::OnceLock;
+
+use codex_protocol::protocol::NetworkAccess;
+use tokio::io::AsyncReadExt;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpListener;
+use tokio::net::TcpStream;
+use tokio::task::JoinHandle;
+
+/// Longest request head the proxy reads before giving up on a client.
+const MAX_HEAD_BYTES: usize = 16 * 1024;
+
+type Proxies = Mutex<HashMap<Vec<String>, (u16, JoinHandle<()>)>>;
+
+fn proxies() -> &'static Proxies {
+    static PROXIES: OnceLock<Proxies> = OnceLock::new();
+    PROXIES.get_or_init(Proxies::default)
+}
+
+/// The port of the proxy for `hosts`, starting it if needed. Must be called
+/// from within a Tokio runtime.
+pub(crate) fn start_allowlist_proxy(hosts: &[String]) -> io::Result<u16> {
+    let mut key: Vec<String> = hosts.iter().map(|host| host.to_ascii_lowercase()).collect();
+    key.sort();
+    key.dedup();
+
+    let mut proxies = proxies()
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    if let Some((port, task)) = proxies.get(&key)
+        && !task.is_finished()
+    {
+        return Ok(*port);
+    }
+
+    let runtime = tokio::runtime::Handle::try_current().map_err(io::Error::other)?;
+    let listener = std::net::TcpListener::bind(("127.0.0.1", 0))?;
+    listener.set_nonblocking(true)?;
+    let port = listener.local_addr()?.port();
+    let listener = {
+        let _guard = runtime.enter();
+        TcpListener::from_std(listener)?
+    };
+    let task = runtime.spawn(serve(listener, Arc::new(key.clone())));
+    proxies.insert(key, (port, task));
+    Ok(port)
+}
+
+async fn serve(listener: TcpListener, hosts: Arc<Vec<String>>) {
+    loop {
+        match listener.accept().await {
+            Ok((client, _)) => {
+                let hosts = Arc::clone(&hosts);
+                tokio::spawn(async move {
+                    if let Err(err) = handle_client(client, &hosts).await {
+                        tracing::debug!("network proxy connection failed: {err}");
+                    }
+                });
+            }
+            Err(err) => tracing::warn!("network proxy failed to accept a connection: {err}"),
+        }
+    }
+}
+
+/// A request's target, from its request line.
+#[derive(Debug, PartialEq, Eq)]
+struct Target {
+    tunnel: bool,
+    host: String,
+    port: u16,
+}
+
+async fn handle_client(mut client: TcpStream, hosts: &[String]) -> io::Result<()> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+    let head_len = loop {
+        let n = client.read(&mut chunk).await?;
+        if n == 0 {
+            return Ok(());
+        }
+        buf.extend_from_slice(&chunk[..n]);
+        if let Some(pos) = buf.windows(4).position(|window| window == b"\r\n\r\n") {
+            break pos + 4;
+        }
+        if buf.len() > MAX_HEAD_BYTES {
+            return respond(&mut client, "431 Request Header Fields Too Large", "").await;
+        }
+    };
+    let head = String::from_utf8_lossy(&buf[..head_len]).into_owned();
+    let Some(target) = parse_target(&head) else {
+        return respond(&mut client, "400 Bad Request", "unsupported proxy request").await;
+    };
+    if !hosts
+        .iter()
+        .any(|pattern| NetworkAccess::host_matches(pattern, &target.host))
+    {
+        let message = format!("{} is not in this command's network allowlist", target.host);
+        return respond(&mut client, "403 Forbidden", &message).await;
+    }
+
+    let mut upstream = match TcpStream::connect((target.host.as_str(), target.port)).await {
+        Ok(upstream) => upstream,
+        Err(err) => {
+            let message = format!("failed to connect to {}: {err}", target.host);
+            return respond(&mut client, "502 Bad Gateway", &message).await;
+        }
+    };
+    if target.tunnel {
+        client
+            .write_all(b"HTTP/1.1 200 Connection Established\r\n\r\n")
+            .await?;
+    } else {
+        upstream
+            .write_all(close_after_response(&head).as_bytes())
+            .await?;
+    }
+    upstream.write_all(&buf[head_len..]).await?;
+    tokio::io::copy_bidirectional(&mut client, &mut upstream).await?;
+    Ok(())
+}
+
+/// The target of `CONNECT host:port` or of an absolute-form HTTP request.
+fn parse_target(head: &str) -> Option<Target> {
+    let mut request_line = head.lines().next()?.split_whitespace();
+    let method = request_line.next()?;
+    let uri = request_line.next()?;
+    if method.eq_ignore_ascii_case("CONNECT") {
+        let (host, port) = split_authority(uri)?;
+        return Some(Target {
+            tunnel: true,
+            host,
+            port: port?,
+        });
+    }
+    let rest = uri.strip_prefix("http://")?;
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let (host, port) = split_authority(authority)?;
+    Some(Target {
+        tunnel: false,
+        host,
+        port: port.unwrap_or(80),
+    })
+}
+
+/// Splits `host[:port]`, where an IPv6 host is in brackets.
+fn split_authority(authority: &str) -> Option<(String, Option<u16>)> {
+    let (host, port) = match authority.strip_prefix('[') {
+        Some(rest) => {
+            let (host, rest) = rest.split_once(']')?;
+            (host, rest.strip_prefix(':'))
+        }
+        None => match authority.rsplit_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (authority, None),
+        },
+    };
+    if host.is_empty() {
+        return None;
+    }
+    let port = match port {
+        Some(port) => Some(port.parse().ok()?),
+        None => None,
+    };
+    Some((host.to_string(), port))
+}
+
+/// `head` with its connection headers replaced by `Connection: close`.
+fn close_after_response(head: &str) -> String {
+    let mut lines: Vec<&str> = head
+        .trim_end_matches("\r\n")
+        .split("\r\n")
+        .filter(|line| {
+            let name = line.split(':').next().unwrap_or_default().trim();
+            !["connection", "proxy-connection", "keep-alive"]
+                .iter()
+                .any(|header| name.eq_ignore_ascii_case(header))
+        })
+        .collect();
+    lines.push("Connection: close");
+    format!("{}\r\n\r\n", lines.join("\r\n"))
+}
+
+async fn respond(client: &mut TcpStream, status: &str, message: &str) -> io::Result<()> {
+    let len = message.len();
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: text/plain\r\nContent-Length: {len}\r\n\
+         Connection: close\r\n\r\n{message}"
+    );
+    client.write_all(response.as_bytes()).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn targets_are_read_from_connect_and_absolute_requests() {
+        assert_eq!(
+            parse_target("CONNECT crates.io:443 HTTP/1.1\r\nHost: crates.io:443\r\n\r\n"),
+            Some(Target {
+                tunnel: true,
+                host: "crates.io".to_string(),
+                port: 443,
+            })
+        );
+        assert_eq!(
+            parse_target("GET http://[::1]:8080/x HTTP/1.1\r\n\r\n"),
+            Some(Target {
+                tunnel: false,
+                host: "::1".to_string(),
+                port: 8080,
+            })
+        );
+        assert_eq!(parse_target("GET /x HTTP/1.1\r\n\r\n"), None);
+        assert_eq!(
+            close_after_response(
+                "GET http://a/ HTTP/1.1\r\nHost: a\r\nProxy-Connection: keep-alive\r\n\r\n"
+            ),
+            "GET http://a/ HTTP/1.1\r\nHost: a\r\nConnection: close\r\n\r\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn hosts_outside_the_allowlist_are_refused() {
+        let port = start_allowlist_proxy(&["*.example.com".to_string()]).expect("proxy");
+        let mut client = TcpStream::connect(("127.0.0.1", port))
+            .await
+            .expect("connect");
+        client
+            .write_all(b"CONNECT evil.test:443 HTTP/1.1\r\n\r\n")
+            .await
+            .expect("write");
+        let mut response = String::new();
+        client.read_to_string(&mut response).await.expect("read");
+        assert_eq!(response.lines().next(), Some("HTTP/1.1 403 Forbidden"));
+    }
+}
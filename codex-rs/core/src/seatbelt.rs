@@ -7,12 +7,15 @@ use std::path::PathBuf;
 use tokio::process::Child;
 
 use crate::protocol::SandboxPolicy;
+use crate::sandboxing::network::SandboxNetwork;
 use crate::spawn::CODEX_SANDBOX_ENV_VAR;
 use crate::spawn::StdioPolicy;
 use crate::spawn::spawn_child_async;
 
 const MACOS_SEATBELT_BASE_POLICY: &str = include_str!("seatbelt_base_policy.sbpl");
 const MACOS_SEATBELT_NETWORK_POLICY: &str = include_str!("seatbelt_network_policy.sbpl");
+const MACOS_SEATBELT_NETWORK_SERVICES_POLICY: &str =
+    include_str!("seatbelt_network_services_policy.sbpl");
 
 /// When working with `sandbox-exec`, only consider `sandbox-exec` in `/usr/bin`
 /// to defend against an attacker trying to inject a malicious version on the
@@ -28,7 +31,8 @@ pub async fn spawn_command_under_seatbelt(
     stdio_policy: StdioPolicy,
     mut env: HashMap<String, String>,
 ) -> std::io::Result<Child> {
-    let args = create_seatbelt_command_args(command, sandbox_policy, sandbox_policy_cwd);
+    let network = SandboxNetwork::from_policy(sandbox_policy);
+    let args = create_seatbelt_command_args(command, sandbox_policy, network, sandbox_policy_cwd);
     let arg0 = None;
     env.insert(CODEX_SANDBOX_ENV_VAR.to_string(), "seatbelt".to_string());
     spawn_child_async(
@@ -39,6 +43,7 @@ pub async fn spawn_command_under_seatbelt(
         sandbox_policy,
         stdio_policy,
         env,
+        None,
     )
     .await
 }
@@ -46,6 +51,7 @@ pub async fn spawn_command_under_seatbelt(
 pub(crate) fn create_seatbelt_command_args(
     command: Vec<String>,
     sandbox_policy: &SandboxPolicy,
+    network: SandboxNetwork,
     sandbox_policy_cwd: &Path,
 ) -> Vec<String> {
     let (file_write_policy, file_write_dir_params) = {
@@ -105,10 +111,23 @@ pub(crate) fn create_seatbelt_command_args(
     };
 
     // TODO(mbolin): apply_patch calls must also honor the SandboxPolicy.
-    let network_policy = if sandbox_policy.has_full_network_access() {
-        MACOS_SEATBELT_NETWORK_POLICY
-    } else {
-        ""
+    let network_policy = match network {
+        SandboxNetwork::Full => {
+            format!("{MACOS_SEATBELT_NETWORK_POLICY}\n{MACOS_SEATBELT_NETWORK_SERVICES_POLICY}")
+        }
+        SandboxNetwork::None => String::new(),
+        SandboxNetwork::Loopback => format!(
+            r#"(allow network-outbound (remote ip "localhost:*"))
+(allow network-inbound (local ip "localhost:*"))
+(allow network-bind (local ip "localhost:*"))
+(allow system-socket)
+{MACOS_SEATBELT_NETWORK_SERVICES_POLICY}"#
+        ),
+        SandboxNetwork::Proxy { port } => format!(
+            r#"(allow network-outbound (remote ip "localhost:{port}"))
+(allow system-socket)
+{MACOS_SEATBELT_NETWORK_SERVICES_POLICY}"#
+        ),
     };
 
     let full_policy = format!(
@@ -159,6 +178,7 @@ mod tests {
     use super::create_seatbelt_command_args;
     use super::macos_dir_params;
     use crate::protocol::SandboxPolicy;
+    use crate::sandboxing::network::SandboxNetwork;
     use pretty_assertions::assert_eq;
     use std::fs;
     use std::path::Path;
@@ -191,6 +211,7 @@ mod tests {
         let args = create_seatbelt_command_args(
             vec!["/bin/echo".to_string(), "hello".to_string()],
             &policy,
+            SandboxNetwork::from_policy(&policy),
             &cwd,
         );
 
@@ -267,6 +288,7 @@ mod tests {
         let args = create_seatbelt_command_args(
             vec!["/bin/echo".to_string(), "hello".to_string()],
             &policy,
+            SandboxNetwork::from_policy(&policy),
             root_with_git.as_path(),
         );
 
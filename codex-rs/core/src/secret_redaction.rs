@@ -0,0 +1,230 @@
+//! Redaction of secrets from command output.
+//!
+//! Before the output of a command reaches the model or the event stream,
+//! the values of secret environment variables are replaced with
+//! `<redacted>`, as are matches of the `command_env.redact_patterns`
+//! regular expressions. A variable is secret when its name contains `KEY`,
+//! `TOKEN`, `SECRET`, `PASSWORD` or `CREDENTIAL`, or matches one of
+//! `command_env.secret_vars`; both Codex's own environment and the
+//! command's are searched for them, since a login shell's profile may set
+//! again what `shell_environment_policy` removed.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use regex::Regex;
+
+use crate::config::types::CommandEnvPolicy;
+use crate::config::types::EnvironmentVariablePattern;
+use crate::exec::ExecToolCallOutput;
+use crate::exec::StreamOutput;
+
+pub(crate) const REDACTED: &str = "<redacted>";
+
+const SECRET_NAMES: &[&str] = &["*KEY*", "*TOKEN*", "*SECRET*", "*PASSWORD*", "*CREDENTIAL*"];
+
+/// Shorter values are not redacted: they are flags such as `1` or `true`
+/// rather than secrets, and would garble unrelated output.
+const MIN_SECRET_LEN: usize = 8;
+
+/// Most output a [`StreamRedactor`] holds back waiting for a line break.
+const MAX_HELD_BYTES: usize = 8 * 1024;
+
+/// Checks that every `redact_patterns` entry compiles, so a typo fails at
+/// startup rather than leaking what it was meant to catch.
+pub(crate) fn validate_redact_patterns(policy: &CommandEnvPolicy) -> Result<(), String> {
+    for pattern in &policy.redact_patterns {
+        if let Err(err) = Regex::new(pattern) {
+            return Err(format!(
+                "command_env.redact_patterns has an invalid pattern `{pattern}`: {err}"
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug)]
+pub(crate) struct SecretRedactor {
+    /// Longest first, so a secret containing another is redacted whole.
+    secrets: Vec<String>,
+    patterns: Vec<Regex>,
+}
+
+impl SecretRedactor {
+    /// The redactor for a command run with `env`, or `None` when there is
+    /// nothing to redact.
+    pub(crate) fn for_command(
+        policy: &CommandEnvPolicy,
+        env: &HashMap<String, String>,
+    ) -> Option<Arc<Self>> {
+        let inherited: Vec<(String, String)> = std::env::vars().collect();
+        let vars = inherited
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+            .chain(
+                env.iter()
+                    .map(|(name, value)| (name.as_str(), value.as_str())),
+            );
+        let redactor = Self::new(policy, vars);
+        (!redactor.secrets.is_empty() || !redactor.patterns.is_empty()).then(|| Arc::new(redactor))
+    }
+
+    fn new<'a>(
+        policy: &CommandEnvPolicy,
+        vars: impl IntoIterator<Item = (&'a str, &'a str)>,
+    ) -> Self {
+        let secret_names: Vec<EnvironmentVariablePattern> = SECRET_NAMES
+            .iter()
+            .map(ToString::to_string)
+            .chain(policy.secret_vars.iter().cloned())
+            .map(|name| EnvironmentVariablePattern::new_case_insensitive(&name))
+            .collect();
+        let mut secrets: Vec<String> = vars
+            .into_iter()
+            .filter(|(name, value)| {
+                value.len() >= MIN_SECRET_LEN
+                    && secret_names.iter().any(|pattern| pattern.matches(name))
+            })
+            .map(|(_, value)| value.to_string())
+            .collect();
+        secrets.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+        secrets.dedup();
+        // Patterns were checked when the config was loaded.
+        let patterns = policy
+            .redact_patterns
+            .iter()
+            .filter_map(|pattern| Regex::new(pattern).ok())
+            .collect();
+        Self { secrets, patterns }
+    }
+
+    pub(crate) fn redact<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let mut text = Cow::Borrowed(text);
+        for secret in &self.secrets {
+            if text.contains(secret.as_str()) {
+                text = Cow::Owned(text.replace(secret.as_str(), REDACTED));
+            }
+        }
+        for pattern in &self.patterns {
+            if pattern.is_match(&text) {
+                text = Cow::Owned(pattern.replace_all(&text, REDACTED).into_owned());
+            }
+        }
+        text
+    }
+
+    fn redact_bytes(&self, bytes: Vec<u8>) -> Vec<u8> {
+        let redacted = match self.redact(&String::from_utf8_lossy(&bytes)) {
+            Cow::Owned(redacted) => redacted,
+            // Nothing matched, so invalid UTF-8 is passed on as it was.
+            Cow::Borrowed(_) => return bytes,
+        };
+        redacted.into_bytes()
+    }
+
+    /// `output` with its stdout, stderr and aggregated output redacted.
+    pub(crate) fn redact_output(&self, output: ExecToolCallOutput) -> ExecToolCallOutput {
+        let redact = |stream: StreamOutput<String>| StreamOutput {
+            text: self.redact(&stream.text).into_owned(),
+            ..stream
+        };
+        ExecToolCallOutput {
+            stdout: redact(output.stdout),
+            stderr: redact(output.stderr),
+            aggregated_output: redact(output.aggregated_output),
+            ..output
+        }
+    }
+}
+
+/// Redacts output as it streams in. So that a secret split between two
+/// chunks is still caught, what follows the last line break of a chunk is
+/// held back for the next one, up to [`MAX_HELD_BYTES`].
+pub(crate) struct StreamRedactor {
+    redactor: Arc<SecretRedactor>,
+    held: Vec<u8>,
+}
+
+impl StreamRedactor {
+    pub(crate) fn new(redactor: Arc<SecretRedactor>) -> Self {
+        Self {
+            redactor,
+            held: Vec::new(),
+        }
+    }
+
+    /// The redacted output that is ready once `chunk` is read; may be empty.
+    pub(crate) fn push(&mut self, chunk: &[u8]) -> Vec<u8> {
+        self.held.extend_from_slice(chunk);
+        let ready = if self.held.len() > MAX_HELD_BYTES {
+            self.held.len()
+        } else {
+            self.held
+                .iter()
+                .rposition(|byte| *byte == b'\n')
+                .map_or(0, |newline| newline + 1)
+        };
+        let ready: Vec<u8> = self.held.drain(..ready).collect();
+        self.redactor.redact_bytes(ready)
+    }
+
+    /// The rest of the output, redacted, once the stream ends.
+    pub(crate) fn finish(&mut self) -> Vec<u8> {
+        self.redactor.redact_bytes(std::mem::take(&mut self.held))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn redactor() -> SecretRedactor {
+        let policy = CommandEnvPolicy {
+            secret_vars: vec!["DATABASE_URL".to_string()],
+            redact_patterns: vec![r"ghp_[A-Za-z0-9]{6}".to_string()],
+            ..Default::default()
+        };
+        let vars = [
+            ("OPENAI_API_KEY", "sk-abcdefgh12345678"),
+            ("DATABASE_URL", "postgres://me:hunter22@db/app"),
+            ("GITHUB_TOKEN", "1"),
+            ("HOME", "/home/me/projects"),
+        ];
+        SecretRedactor::new(&policy, vars)
+    }
+
+    #[test]
+    fn secret_values_and_patterns_are_redacted() {
+        let output = "key=sk-abcdefgh12345678\nurl postgres://me:hunter22@db/app\n\
+                      token ghp_a1B2c3 in /home/me/projects, GITHUB_TOKEN=1\n";
+        assert_eq!(
+            redactor().redact(output),
+            "key=<redacted>\nurl <redacted>\n\
+             token <redacted> in /home/me/projects, GITHUB_TOKEN=1\n"
+        );
+        assert_eq!(
+            redactor().redact("nothing to see"),
+            Cow::Borrowed("nothing to see")
+        );
+    }
+
+    #[test]
+    fn secrets_split_between_chunks_are_redacted() {
+        let mut stream = StreamRedactor::new(Arc::new(redactor()));
+        let chunks = [
+            stream.push(b"first line\nkey=sk-abcd"),
+            stream.push(b"efgh12345678 done\nlast"),
+            stream.finish(),
+        ];
+        assert_eq!(
+            chunks.map(|chunk| String::from_utf8_lossy(&chunk).into_owned()),
+            [
+                "first line\n".to_string(),
+                "key=<redacted> done\n".to_string(),
+                "last".to_string(),
+            ]
+        );
+    }
+}
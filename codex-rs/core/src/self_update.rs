@@ -0,0 +1,386 @@
+//! Installing, selecting and pinning Codex versions.
+//!
+//! `codex self-update` reads the release manifest at
+//! `self_update.manifest_url`, checks its Ed25519 signature against
+//! `self_update.public_key`, and installs the latest release of the
+//! configured channel, or the version asked for, to
+//! `$CODEX_HOME/versions/<version>/` after checking the executable against
+//! the SHA-256 the manifest lists for it. The installed version becomes the
+//! default, so rolling back is installing the previous version again.
+//!
+//! A project pins a version with a `.codex/version` file in its root. The
+//! `codex` launcher runs the version pinned by the working directory or one
+//! of its parents, or else the default version, by executing the installed
+//! executable with the same arguments.
+
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+
+use base64::Engine;
+use ring::signature::ED25519;
+use ring::signature::UnparsedPublicKey;
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::config::types::SelfUpdate;
+use crate::config::types::UpdateChannel;
+use crate::default_client::create_client;
+use crate::util::set_executable;
+use crate::util::sha256_hex;
+
+const VERSIONS_DIR: &str = "versions";
+
+/// Holds the default version, under [`VERSIONS_DIR`].
+const DEFAULT_VERSION_FILE: &str = "default";
+
+/// A project's version pin, relative to its root.
+pub const VERSION_PIN_FILE: &str = ".codex/version";
+
+/// The version of this executable.
+pub const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[derive(Debug, Error)]
+pub enum SelfUpdateError {
+    #[error("set `self_update.manifest_url` and `self_update.public_key` to update Codex")]
+    NotConfigured,
+    #[error("`self_update.public_key` is not valid base64")]
+    InvalidPublicKey,
+    #[error("the release manifest's signature does not verify")]
+    BadSignature,
+    #[error("`{0}` is not a valid version")]
+    InvalidVersion(String),
+    #[error("the release manifest has no version {0}")]
+    UnknownVersion(String),
+    #[error("the release manifest has no release on the {0} channel")]
+    UnknownChannel(String),
+    #[error("Codex {version} has no executable for {platform}")]
+    NoExecutable { version: String, platform: String },
+    #[error("the downloaded executable does not match the release manifest's SHA-256")]
+    ChecksumMismatch,
+    #[error("invalid release manifest: {0}")]
+    Manifest(#[from] serde_json::Error),
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// The releases `codex self-update` can install.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ReleaseManifest {
+    /// The latest version of each channel.
+    pub channels: HashMap<UpdateChannel, String>,
+    /// The executables of each version, by platform, such as `linux-x86_64`.
+    pub releases: HashMap<String, HashMap<String, ReleaseExecutable>>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ReleaseExecutable {
+    pub url: String,
+    pub sha256: String,
+}
+
+impl ReleaseManifest {
+    pub fn latest(&self, channel: UpdateChannel) -> Result<&str, SelfUpdateError> {
+        self.channels
+            .get(&channel)
+            .map(String::as_str)
+            .ok_or_else(|| SelfUpdateError::UnknownChannel(channel.to_string()))
+    }
+}
+
+/// Which executable the launcher runs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Launch {
+    /// This one: no version is selected, or it is this one.
+    Current,
+    /// The installed executable of the selected version.
+    Installed(PathBuf),
+    /// The selected version is not installed.
+    Missing(String),
+}
+
+/// The platform key of this executable in the release manifest.
+pub fn platform() -> String {
+    format!("{}-{}", std::env::consts::OS, std::env::consts::ARCH)
+}
+
+pub fn versions_dir(codex_home: &Path) -> PathBuf {
+    codex_home.join(VERSIONS_DIR)
+}
+
+pub fn installed_executable(codex_home: &Path, version: &str) -> PathBuf {
+    let name = format!("codex{}", std::env::consts::EXE_SUFFIX);
+    versions_dir(codex_home).join(version).join(name)
+}
+
+/// The installed versions, sorted.
+pub fn installed_versions(codex_home: &Path) -> io::Result<Vec<String>> {
+    let dirs = match std::fs::read_dir(versions_dir(codex_home)) {
+        Ok(dirs) => dirs,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+    let mut versions = Vec::new();
+    for dir in dirs {
+        let name = dir?.file_name().to_string_lossy().into_owned();
+        if is_valid_version(&name) && installed_executable(codex_home, &name).is_file() {
+            versions.push(name);
+        }
+    }
+    versions.sort();
+    Ok(versions)
+}
+
+pub fn default_version(codex_home: &Path) -> Option<String> {
+    read_version_file(&versions_dir(codex_home).join(DEFAULT_VERSION_FILE))
+}
+
+pub fn set_default_version(codex_home: &Path, version: &str) -> io::Result<()> {
+    let dir = versions_dir(codex_home);
+    std::fs::create_dir_all(&dir)?;
+    std::fs::write(dir.join(DEFAULT_VERSION_FILE), format!("{version}\n"))
+}
+
+/// The version pinned by `cwd` or its closest parent with a pin, and the
+/// pin file.
+pub fn find_version_pin(cwd: &Path) -> Option<(PathBuf, String)> {
+    cwd.ancestors().find_map(|dir| {
+        let pin = dir.join(VERSION_PIN_FILE);
+        read_version_file(&pin).map(|version| (pin, version))
+    })
+}
+
+/// Pin the project rooted at `project_root` to `version`.
+pub fn pin_version(project_root: &Path, version: &str) -> io::Result<PathBuf> {
+    let pin = project_root.join(VERSION_PIN_FILE);
+    if let Some(parent) = pin.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&pin, format!("{version}\n"))?;
+    Ok(pin)
+}
+
+/// What the launcher runs in `cwd`. Installed executables always run
+/// themselves, so that they are never relaunched in a loop.
+pub fn resolve_launch(codex_home: &Path, cwd: &Path, current_exe: &Path) -> Launch {
+    if let Ok(versions) = dunce::canonicalize(versions_dir(codex_home))
+        && dunce::canonicalize(current_exe).is_ok_and(|exe| exe.starts_with(versions))
+    {
+        return Launch::Current;
+    }
+    let selected = find_version_pin(cwd)
+        .map(|(_, version)| version)
+        .or_else(|| default_version(codex_home));
+    match selected {
+        None => Launch::Current,
+        Some(version) if version == CURRENT_VERSION => Launch::Current,
+        Some(version) => {
+            let executable = installed_executable(codex_home, &version);
+            if executable.is_file() {
+                Launch::Installed(executable)
+            } else {
+                Launch::Missing(version)
+            }
+        }
+    }
+}
+
+/// Download the release manifest and check its signature.
+pub async fn fetch_manifest(settings: &SelfUpdate) -> Result<ReleaseManifest, SelfUpdateError> {
+    let (Some(url), Some(public_key)) = (&settings.manifest_url, &settings.public_key) else {
+        return Err(SelfUpdateError::NotConfigured);
+    };
+    let client = create_client();
+    let manifest = client
+        .get(url.as_str())
+        .send()
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?;
+    let signature = client
+        .get(format!("{url}.sig"))
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+    verify_manifest(&manifest, &signature, public_key)
+}
+
+/// Install `version` from `manifest`, unless it is installed already, and
+/// return its executable.
+pub async fn install_version(
+    codex_home: &Path,
+    manifest: &ReleaseManifest,
+    version: &str,
+) -> Result<PathBuf, SelfUpdateError> {
+    if !is_valid_version(version) {
+        return Err(SelfUpdateError::InvalidVersion(version.to_string()));
+    }
+    let executable = installed_executable(codex_home, version);
+    if executable.is_file() {
+        return Ok(executable);
+    }
+    let platform = platform();
+    let release = manifest
+        .releases
+        .get(version)
+        .ok_or_else(|| SelfUpdateError::UnknownVersion(version.to_string()))?
+        .get(&platform)
+        .ok_or_else(|| SelfUpdateError::NoExecutable {
+            version: version.to_string(),
+            platform,
+        })?;
+    let bytes = create_client()
+        .get(release.url.as_str())
+        .send()
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?;
+    if !sha256_hex(&bytes).eq_ignore_ascii_case(release.sha256.trim()) {
+        return Err(SelfUpdateError::ChecksumMismatch);
+    }
+    let Some(dir) = executable.parent() else {
+        return Err(SelfUpdateError::InvalidVersion(version.to_string()));
+    };
+    tokio::fs::create_dir_all(dir).await?;
+    // Written next to its final path and renamed, so that a launcher never
+    // finds a partial executable.
+    let partial = executable.with_extension("partial");
+    tokio::fs::write(&partial, &bytes).await?;
+    set_executable(&partial).await?;
+    tokio::fs::rename(&partial, &executable).await?;
+    Ok(executable)
+}
+
+fn verify_manifest(
+    manifest: &[u8],
+    signature: &str,
+    public_key: &str,
+) -> Result<ReleaseManifest, SelfUpdateError> {
+    let engine = base64::engine::general_purpose::STANDARD;
+    let public_key = engine
+        .decode(public_key.trim())
+        .map_err(|_| SelfUpdateError::InvalidPublicKey)?;
+    let signature = engine
+        .decode(signature.trim())
+        .map_err(|_| SelfUpdateError::BadSignature)?;
+    UnparsedPublicKey::new(&ED25519, public_key)
+        .verify(manifest, &signature)
+        .map_err(|_| SelfUpdateError::BadSignature)?;
+    Ok(serde_json::from_slice(manifest)?)
+}
+
+/// The first line of a version file, if it holds a valid version. Pins come
+/// from repositories, so a version must not be able to name a path.
+fn read_version_file(path: &Path) -> Option<String> {
+    let contents = std::fs::read_to_string(path).ok()?;
+    let version = contents.lines().next()?.trim();
+    let version = version.strip_prefix('v').unwrap_or(version);
+    is_valid_version(version).then(|| version.to_string())
+}
+
+fn is_valid_version(version: &str) -> bool {
+    version.starts_with(|c: char| c.is_ascii_digit())
+        && version
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '+'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use ring::rand::SystemRandom;
+    use ring::signature::Ed25519KeyPair;
+    use ring::signature::KeyPair;
+    use tempfile::TempDir;
+
+    #[test]
+    fn manifests_must_be_signed_with_the_configured_key() {
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).expect("key");
+        let key_pair = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).expect("key pair");
+        let engine = base64::engine::general_purpose::STANDARD;
+        let public_key = engine.encode(key_pair.public_key().as_ref());
+        let manifest = br#"{
+            "channels": {"stable": "1.2.0"},
+            "releases": {
+                "1.2.0": {"linux-x86_64": {"url": "https://example.com/codex", "sha256": "ab"}}
+            }
+        }"#;
+        let signature = engine.encode(key_pair.sign(manifest).as_ref());
+
+        let verified = verify_manifest(manifest, &signature, &public_key).expect("verified");
+        assert_eq!(
+            verified,
+            ReleaseManifest {
+                channels: HashMap::from([(UpdateChannel::Stable, "1.2.0".to_string())]),
+                releases: HashMap::from([(
+                    "1.2.0".to_string(),
+                    HashMap::from([(
+                        "linux-x86_64".to_string(),
+                        ReleaseExecutable {
+                            url: "https://example.com/codex".to_string(),
+                            sha256: "ab".to_string(),
+                        },
+                    )]),
+                )]),
+            }
+        );
+        assert_eq!(verified.latest(UpdateChannel::Stable).ok(), Some("1.2.0"));
+
+        let tampered = String::from_utf8_lossy(manifest).replace("1.2.0", "1.3.0");
+        assert!(matches!(
+            verify_manifest(tampered.as_bytes(), &signature, &public_key),
+            Err(SelfUpdateError::BadSignature)
+        ));
+    }
+
+    #[test]
+    fn project_pins_take_precedence_over_the_default_version() {
+        let codex_home = TempDir::new().expect("codex home");
+        let project = TempDir::new().expect("project");
+        let cwd = project.path().join("src");
+        std::fs::create_dir_all(&cwd).expect("cwd");
+        let launcher = Path::new("/usr/local/bin/codex");
+
+        assert_eq!(
+            resolve_launch(codex_home.path(), &cwd, launcher),
+            Launch::Current
+        );
+
+        set_default_version(codex_home.path(), "1.1.0").expect("default");
+        assert_eq!(
+            resolve_launch(codex_home.path(), &cwd, launcher),
+            Launch::Missing("1.1.0".to_string())
+        );
+
+        pin_version(project.path(), "1.0.0").expect("pin");
+        let executable = installed_executable(codex_home.path(), "1.0.0");
+        std::fs::create_dir_all(executable.parent().expect("parent")).expect("version dir");
+        std::fs::write(&executable, "").expect("executable");
+        assert_eq!(
+            resolve_launch(codex_home.path(), &cwd, launcher),
+            Launch::Installed(executable.clone())
+        );
+        assert_eq!(
+            resolve_launch(codex_home.path(), &cwd, &executable),
+            Launch::Current
+        );
+
+        std::fs::write(project.path().join(VERSION_PIN_FILE), "../../bin/sh\n").expect("pin");
+        assert_eq!(
+            resolve_launch(codex_home.path(), &cwd, launcher),
+            Launch::Missing("1.1.0".to_string())
+        );
+        assert_eq!(
+            installed_versions(codex_home.path()).expect("versions"),
+            vec!["1.0.0".to_string()]
+        );
+    }
+}
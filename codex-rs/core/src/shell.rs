@@ -9,6 +9,8 @@ pub enum ShellType {
     PowerShell,
     Sh,
     Cmd,
+    Fish,
+    Nushell,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
@@ -25,6 +27,53 @@ impl Shell {
             ShellType::PowerShell => "powershell",
             ShellType::Sh => "sh",
             ShellType::Cmd => "cmd",
+            ShellType::Fish => "fish",
+            ShellType::Nushell => "nu",
+        }
+    }
+
+    /// Whether this is fish or nushell, which do not run POSIX shell syntax.
+    pub fn is_fish_or_nushell(&self) -> bool {
+        matches!(self.shell_type, ShellType::Fish | ShellType::Nushell)
+    }
+
+    /// The shell to run the scripts Codex writes itself, which use POSIX
+    /// syntax: this shell, unless it is fish or nushell.
+    pub fn script_shell(&self) -> Shell {
+        if self.is_fish_or_nushell() {
+            default_user_shell()
+        } else {
+            self.clone()
+        }
+    }
+
+    /// Join `args` into a command line that this shell splits back into
+    /// exactly `args`.
+    pub fn join_args(&self, args: &[String]) -> String {
+        args.iter()
+            .map(|arg| self.quote(arg))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn quote(&self, arg: &str) -> String {
+        let plain = !arg.is_empty()
+            && arg.chars().all(|c| {
+                c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '/' | ':' | '=')
+            });
+        if plain {
+            return arg.to_string();
+        }
+        match self.shell_type {
+            ShellType::Zsh | ShellType::Bash | ShellType::Sh => shlex::try_quote(arg)
+                .map(|quoted| quoted.to_string())
+                .unwrap_or_else(|_| arg.to_string()),
+            // Inside single quotes fish only knows the escapes `\\` and `\'`.
+            ShellType::Fish => format!("'{}'", arg.replace('\\', "\\\\").replace('\'', "\\'")),
+            // Nushell does not interpolate plain double-quoted strings.
+            ShellType::Nushell => format!("\"{}\"", arg.replace('\\', "\\\\").replace('"', "\\\"")),
+            ShellType::PowerShell => format!("'{}'", arg.replace('\'', "''")),
+            ShellType::Cmd => format!("\"{}\"", arg.replace('"', "\"\"")),
         }
     }
 
@@ -56,6 +105,15 @@ impl Shell {
                 args.push(command.to_string());
                 args
             }
+            ShellType::Fish | ShellType::Nushell => {
+                let mut args = vec![self.shell_path.to_string_lossy().to_string()];
+                if use_login_shell {
+                    args.push("--login".to_string());
+                }
+                args.push("-c".to_string());
+                args.push(command.to_string());
+                args
+            }
         }
     }
 }
@@ -179,6 +237,24 @@ fn get_cmd_shell(path: Option<&PathBuf>) -> Option<Shell> {
     })
 }
 
+fn get_fish_shell(path: Option<&PathBuf>) -> Option<Shell> {
+    let shell_path = get_shell_path(ShellType::Fish, path, "fish", vec!["/usr/bin/fish"]);
+
+    shell_path.map(|shell_path| Shell {
+        shell_type: ShellType::Fish,
+        shell_path,
+    })
+}
+
+fn get_nushell_shell(path: Option<&PathBuf>) -> Option<Shell> {
+    let shell_path = get_shell_path(ShellType::Nushell, path, "nu", vec!["/usr/bin/nu"]);
+
+    shell_path.map(|shell_path| Shell {
+        shell_type: ShellType::Nushell,
+        shell_path,
+    })
+}
+
 fn ultimate_fallback_shell() -> Shell {
     if cfg!(windows) {
         Shell {
@@ -206,6 +282,8 @@ pub fn get_shell(shell_type: ShellType, path: Option<&PathBuf>) -> Option<Shell>
         ShellType::PowerShell => get_powershell_shell(path),
         ShellType::Sh => get_sh_shell(path),
         ShellType::Cmd => get_cmd_shell(path),
+        ShellType::Fish => get_fish_shell(path),
+        ShellType::Nushell => get_nushell_shell(path),
     }
 }
 
@@ -217,6 +295,8 @@ pub fn detect_shell_type(shell_path: &PathBuf) -> Option<ShellType> {
         Some("bash") => Some(ShellType::Bash),
         Some("pwsh") => Some(ShellType::PowerShell),
         Some("powershell") => Some(ShellType::PowerShell),
+        Some("fish") => Some(ShellType::Fish),
+        Some("nu") => Some(ShellType::Nushell),
         _ => {
             let shell_name = shell_path.file_stem();
             if let Some(shell_name) = shell_name
@@ -234,12 +314,24 @@ pub fn default_user_shell() -> Shell {
     default_user_shell_from_path(get_user_shell_path())
 }
 
+/// The user's login shell when it is fish or nushell, which
+/// [`default_user_shell`] passes over because commands from the model are
+/// written for POSIX shells.
+pub fn profile_shell() -> Option<Shell> {
+    get_user_shell_path().and_then(|path| {
+        detect_shell_type(&path)
+            .filter(|shell_type| matches!(shell_type, ShellType::Fish | ShellType::Nushell))
+            .and_then(|shell_type| get_shell(shell_type, Some(&path)))
+    })
+}
+
 fn default_user_shell_from_path(user_shell_path: Option<PathBuf>) -> Shell {
     if cfg!(windows) {
         get_shell(ShellType::PowerShell, None).unwrap_or(ultimate_fallback_shell())
     } else {
         let user_default_shell = user_shell_path
             .and_then(|shell| detect_shell_type(&shell))
+            .filter(|shell_type| !matches!(shell_type, ShellType::Fish | ShellType::Nushell))
             .and_then(|shell_type| get_shell(shell_type, None));
 
         let shell_with_fallback = if cfg!(target_os = "macos") {
@@ -278,7 +370,14 @@ mod detect_shell_type_tests {
             detect_shell_type(&PathBuf::from("powershell")),
             Some(ShellType::PowerShell)
         );
-        assert_eq!(detect_shell_type(&PathBuf::from("fish")), None);
+        assert_eq!(
+            detect_shell_type(&PathBuf::from("fish")),
+            Some(ShellType::Fish)
+        );
+        assert_eq!(
+            detect_shell_type(&PathBuf::from("/usr/local/bin/nu")),
+            Some(ShellType::Nushell)
+        );
         assert_eq!(detect_shell_type(&PathBuf::from("other")), None);
         assert_eq!(
             detect_shell_type(&PathBuf::from("/bin/zsh")),
@@ -324,6 +423,37 @@ mod detect_shell_type_tests {
     }
 }
 
+#[cfg(test)]
+mod join_args_tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn join(shell_type: ShellType) -> String {
+        let shell = Shell {
+            shell_type,
+            shell_path: PathBuf::new(),
+        };
+        let args = ["grep", "-n", "it's", "a \"b\" \\c", "--color=never"].map(String::from);
+        shell.join_args(&args)
+    }
+
+    #[test]
+    fn arguments_are_quoted_for_each_shell() {
+        assert_eq!(
+            join(ShellType::Fish),
+            r#"grep -n 'it\'s' 'a "b" \\c' --color=never"#
+        );
+        assert_eq!(
+            join(ShellType::Nushell),
+            r#"grep -n "it's" "a \"b\" \\c" --color=never"#
+        );
+        assert_eq!(
+            join(ShellType::PowerShell),
+            r#"grep -n 'it''s' 'a "b" \c' --color=never"#
+        );
+    }
+}
+
 #[cfg(test)]
 #[cfg(unix)]
 mod tests {
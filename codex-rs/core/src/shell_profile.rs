@@ -0,0 +1,344 @@
+//! The environment, aliases and functions of fish and nushell login shells.
+//!
+//! Commands run in bash or zsh even for users whose login shell is fish or
+//! nushell (see [`crate::shell::default_user_shell`]), so the paths and
+//! variables those users set up in their shell's profile are missing. With
+//! `shell_profile.import_env`, the login shell is started once per session
+//! to print its environment, and the variables its profile adds or changes
+//! become part of the environment of commands.
+//!
+//! With `shell_profile.run_commands`, commands run in the login shell, where
+//! a name such as `ls` may be an alias or a function of the profile. Before
+//! such a command runs, the login shell is asked what its name stands for,
+//! so that approval prompts show it.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+
+use tokio::process::Command;
+use tokio::time::timeout;
+use tracing::warn;
+
+use crate::protocol::CommandExpansion;
+use crate::protocol::CommandExpansionKind;
+use crate::shell::Shell;
+use crate::shell::ShellType;
+use crate::shell::detect_shell_type;
+
+/// A profile that takes longer than this to load is not imported.
+const PROFILE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Passes the name to resolve to the login shell, which saves quoting it.
+const NAME_VAR: &str = "CODEX_RESOLVE_NAME";
+
+/// Variables that describe the shell process that printed the environment
+/// rather than the user's setup.
+const SHELL_STATE_VARS: &[&str] = &[
+    "_",
+    "CMD_DURATION_MS",
+    "CURRENT_FILE",
+    "FILE_PWD",
+    "LAST_EXIT_CODE",
+    "NU_VERSION",
+    "OLDPWD",
+    "PWD",
+    "SHLVL",
+];
+
+/// The variables the profile of `shell` sets that differ from the
+/// environment of this process. Empty when the shell fails or times out.
+pub(crate) async fn login_shell_vars(shell: &Shell) -> HashMap<String, String> {
+    // `env` runs as an external command, so each shell exports its variables
+    // as it would to any program; nushell turns lists such as `PATH` into
+    // strings, for one.
+    let print_env = match shell.shell_type {
+        ShellType::Nushell => "^env -0",
+        _ => "command env -0",
+    };
+    login_shell_output(shell, print_env, &[], "print its environment")
+        .await
+        .map(|printed| profile_vars(&printed, std::env::vars()))
+        .unwrap_or_default()
+}
+
+/// Whether `command` runs through `shell`, the session's shell: when that
+/// is fish or nushell, every command that does not start a shell itself
+/// does, so the profile's aliases and functions apply to it.
+pub(crate) fn runs_in_login_shell(shell: &Shell, command: &[String]) -> bool {
+    shell.is_fish_or_nushell()
+        && command
+            .first()
+            .is_some_and(|program| detect_shell_type(&PathBuf::from(program)).is_none())
+}
+
+/// The alias or function of the profile of `shell` that the name of
+/// `command` stands for, if the command runs in the login shell.
+pub(crate) async fn command_expansions(shell: &Shell, command: &[String]) -> Vec<CommandExpansion> {
+    let Some(name) = command
+        .first()
+        .filter(|_| runs_in_login_shell(shell, command))
+    else {
+        return Vec::new();
+    };
+    // Both print the kind of definition on one line and the definition
+    // after it, or nothing for a builtin or a program.
+    let script = match shell.shell_type {
+        ShellType::Nushell => concat!(
+            "let name = $env.CODEX_RESOLVE_NAME; ",
+            "let kind = (which $name | each {|found| $found.type} | str join); ",
+            "if $kind == 'alias' { ",
+            "print 'alias'; print (scope aliases | where name == $name | get 0.expansion) ",
+            "} else if $kind == 'custom' { print 'function'; print (view source $name) }",
+        ),
+        _ => concat!(
+            "if functions -q -- $CODEX_RESOLVE_NAME; ",
+            "echo function; functions --no-details -- $CODEX_RESOLVE_NAME; end",
+        ),
+    };
+    let purpose = format!("resolve `{name}`");
+    login_shell_output(shell, script, &[(NAME_VAR, name)], &purpose)
+        .await
+        .and_then(|output| parse_expansion(name, &output))
+        .into_iter()
+        .collect()
+}
+
+/// The command line that runs `command` in `shell` as its `expansions`
+/// showed it, for `shell_profile.run_expanded`. An alias is replaced by
+/// what it expands to, and a function is passed over for the program of
+/// the same name, so that a profile changed after the approval cannot
+/// change what runs.
+pub(crate) fn expanded_command_line(
+    shell: &Shell,
+    command: &[String],
+    expansions: &[CommandExpansion],
+) -> String {
+    let line = shell.join_args(command);
+    let Some((name, args)) = command.split_first() else {
+        return line;
+    };
+    let Some(expansion) = expansions.iter().find(|expansion| &expansion.name == name) else {
+        return line;
+    };
+    // `command` in fish and `^` in nushell run a program even when an alias
+    // or function has its name.
+    let program = match shell.shell_type {
+        ShellType::Nushell => format!("^{}", shell.join_args(std::slice::from_ref(name))),
+        _ => format!("command {}", shell.join_args(std::slice::from_ref(name))),
+    };
+    let head = match expansion.kind {
+        // An alias that starts with its own name, such as `ls -la` for `ls`,
+        // runs the program it shadows.
+        CommandExpansionKind::Alias => {
+            let definition = expansion.definition.trim();
+            match definition.strip_prefix(name.as_str()) {
+                Some(rest) if rest.is_empty() || rest.starts_with(' ') => {
+                    format!("{program}{rest}")
+                }
+                _ => definition.to_string(),
+            }
+        }
+        CommandExpansionKind::Function => program,
+    };
+    if args.is_empty() {
+        head
+    } else {
+        format!("{head} {}", shell.join_args(args))
+    }
+}
+
+/// What `script` prints when the login shell runs it, or `None` when the
+/// shell fails or times out.
+async fn login_shell_output(
+    shell: &Shell,
+    script: &str,
+    envs: &[(&str, &str)],
+    purpose: &str,
+) -> Option<String> {
+    let args = shell.derive_exec_args(script, true);
+    let output = timeout(
+        PROFILE_TIMEOUT,
+        Command::new(&args[0])
+            .args(&args[1..])
+            .envs(envs.iter().copied())
+            .stdin(Stdio::null())
+            .kill_on_drop(true)
+            .output(),
+    )
+    .await;
+    let name = shell.name();
+    match output {
+        Ok(Ok(output)) if output.status.success() => {
+            Some(String::from_utf8_lossy(&output.stdout).into_owned())
+        }
+        Ok(Ok(output)) => {
+            let status = output.status;
+            warn!("{name} exited with {status} trying to {purpose}");
+            None
+        }
+        Ok(Err(err)) => {
+            warn!("failed to start {name} to {purpose}: {err}");
+            None
+        }
+        Err(_) => {
+            warn!("{name} took longer than {PROFILE_TIMEOUT:?} to {purpose}");
+            None
+        }
+    }
+}
+
+/// The expansion of `name` in the output of the script in
+/// [`command_expansions`].
+fn parse_expansion(name: &str, output: &str) -> Option<CommandExpansion> {
+    let (kind, definition) = output.split_once('\n')?;
+    let definition = definition.trim();
+    let (kind, definition) = match kind.trim() {
+        "alias" => (CommandExpansionKind::Alias, definition.to_string()),
+        // `alias` in fish defines a function whose description records what
+        // the alias expands to.
+        "function" => match fish_alias(name, definition) {
+            Some(expansion) => (CommandExpansionKind::Alias, expansion),
+            None => (CommandExpansionKind::Function, definition.to_string()),
+        },
+        _ => return None,
+    };
+    Some(CommandExpansion {
+        name: name.to_string(),
+        kind,
+        definition,
+    })
+}
+
+/// The expansion in a fish function defined by `alias`, which starts with
+/// `function ls --wraps='ls -la' --description 'alias ls=ls -la'`.
+fn fish_alias(name: &str, definition: &str) -> Option<String> {
+    let first_line = definition.lines().next()?;
+    let (_, rest) = first_line.split_once(&format!("--description 'alias {name}="))?;
+    // Inside single quotes fish only knows the escapes `\\` and `\'`.
+    let mut expansion = String::new();
+    let mut chars = rest.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => expansion.push(chars.next()?),
+            '\'' => return Some(expansion),
+            c => expansion.push(c),
+        }
+    }
+    None
+}
+
+/// The variables in `printed`, the output of `env -0`, that are not in
+/// `current` or have another value there. Codex's own variables are left
+/// to Codex.
+fn profile_vars(
+    printed: &str,
+    current: impl IntoIterator<Item = (String, String)>,
+) -> HashMap<String, String> {
+    let current: HashMap<String, String> = current.into_iter().collect();
+    printed
+        .split('\0')
+        .filter_map(|entry| entry.split_once('='))
+        .filter(|(name, value)| {
+            !name.is_empty()
+                && !name.starts_with("CODEX_")
+                && !SHELL_STATE_VARS.contains(name)
+                && current.get(*name).map(String::as_str) != Some(*value)
+        })
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn shell(shell_type: ShellType) -> Shell {
+        Shell {
+            shell_type,
+            shell_path: PathBuf::new(),
+        }
+    }
+
+    fn expansion(name: &str, kind: CommandExpansionKind, definition: &str) -> CommandExpansion {
+        CommandExpansion {
+            name: name.to_string(),
+            kind,
+            definition: definition.to_string(),
+        }
+    }
+
+    #[test]
+    fn aliases_and_functions_are_recognized() {
+        let fish_alias = "function\nfunction ls --wraps='rm -rf' \
+                          --description 'alias ls=rm -rf'\n  rm -rf $argv\n        \nend\n";
+        let fish_function = "function\nfunction gco\n  git checkout $argv\nend\n";
+        let nu_alias = "alias\nls -la\n";
+        assert_eq!(
+            [
+                parse_expansion("ls", fish_alias),
+                parse_expansion("gco", fish_function),
+                parse_expansion("ll", nu_alias),
+                parse_expansion("cargo", ""),
+            ],
+            [
+                Some(expansion("ls", CommandExpansionKind::Alias, "rm -rf")),
+                Some(expansion(
+                    "gco",
+                    CommandExpansionKind::Function,
+                    "function gco\n  git checkout $argv\nend",
+                )),
+                Some(expansion("ll", CommandExpansionKind::Alias, "ls -la")),
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn expanded_command_lines_run_what_was_shown() {
+        let command = ["ls".to_string(), "my dir".to_string()];
+        let shadowing = [expansion("ls", CommandExpansionKind::Alias, "ls -la")];
+        let other = [expansion("ls", CommandExpansionKind::Alias, "rm -rf")];
+        let function = [expansion("ls", CommandExpansionKind::Function, "...")];
+        assert_eq!(
+            [
+                expanded_command_line(&shell(ShellType::Fish), &command, &shadowing),
+                expanded_command_line(&shell(ShellType::Fish), &command, &other),
+                expanded_command_line(&shell(ShellType::Nushell), &command, &function),
+                expanded_command_line(&shell(ShellType::Nushell), &command, &[]),
+            ],
+            [
+                "command ls -la 'my dir'".to_string(),
+                "rm -rf 'my dir'".to_string(),
+                "^ls \"my dir\"".to_string(),
+                "ls \"my dir\"".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn only_variables_the_profile_changed_are_imported() {
+        let printed = "PATH=/home/me/.cargo/bin:/usr/bin\0HOME=/home/me\0EDITOR=hx\0\
+                       PWD=/tmp\0SHLVL=2\0CODEX_HOME=/elsewhere\0GREETING=a=b\nc\0";
+        let current = [
+            ("PATH", "/usr/bin"),
+            ("HOME", "/home/me"),
+            ("CODEX_HOME", "/home/me/.codex"),
+        ]
+        .map(|(name, value)| (name.to_string(), value.to_string()));
+
+        assert_eq!(
+            profile_vars(printed, current),
+            HashMap::from([
+                (
+                    "PATH".to_string(),
+                    "/home/me/.cargo/bin:/usr/bin".to_string()
+                ),
+                ("EDITOR".to_string(), "hx".to_string()),
+                ("GREETING".to_string(), "a=b\nc".to_string()),
+            ])
+        );
+    }
+}
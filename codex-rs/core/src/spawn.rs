@@ -5,6 +5,7 @@ use tokio::process::Child;
 use tokio::process::Command;
 use tracing::trace;
 
+use crate::config::types::SandboxLimits;
 use crate::protocol::SandboxPolicy;
 
 /// Experimental environment variable that will be set to some non-empty value
@@ -35,6 +36,7 @@ pub enum StdioPolicy {
 /// For now, we take `SandboxPolicy` as a parameter to spawn_child() because
 /// we need to determine whether to set the
 /// `CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR` environment variable.
+#[allow(clippy::too_many_arguments)]
 pub(crate) async fn spawn_child_async(
     program: PathBuf,
     args: Vec<String>,
@@ -43,9 +45,10 @@ pub(crate) async fn spawn_child_async(
     sandbox_policy: &SandboxPolicy,
     stdio_policy: StdioPolicy,
     env: HashMap<String, String>,
+    #[cfg_attr(not(unix), allow(unused_variables))] limits: Option<SandboxLimits>,
 ) -> std::io::Result<Child> {
     trace!(
-        "spawn_child_async: {program:?} {args:?} {arg0:?} {cwd:?} {sandbox_policy:?} {stdio_policy:?} {env:?}"
+        "spawn_child_async: {program:?} {args:?} {arg0:?} {cwd:?} {sandbox_policy:?} {stdio_policy:?} {env:?} {limits:?}"
     );
 
     let mut cmd = Command::new(&program);
@@ -68,11 +71,25 @@ pub(crate) async fn spawn_child_async(
     unsafe {
         #[cfg(target_os = "linux")]
         let parent_pid = libc::getpid();
+        let detach_terminal = matches!(stdio_policy, StdioPolicy::RedirectForShellTool);
         cmd.pre_exec(move || {
-            if libc::setpgid(0, 0) == -1 {
+            // Shell tool commands have no terminal to prompt on. A new session
+            // detaches them from Codex's, so `sudo` fails asking for a
+            // password instead of waiting for one typed over the TUI. Either
+            // way the child leads its own process group.
+            let result = if detach_terminal {
+                libc::setsid()
+            } else {
+                libc::setpgid(0, 0)
+            };
+            if result == -1 {
                 return Err(std::io::Error::last_os_error());
             }
 
+            if let Some(limits) = &limits {
+                set_resource_limits(limits)?;
+            }
+
             // This relies on prctl(2), so it only works on Linux.
             #[cfg(target_os = "linux")]
             {
@@ -115,3 +132,32 @@ pub(crate) async fn spawn_child_async(
 
     cmd.kill_on_drop(true).spawn()
 }
+
+/// Applies `limits` to the calling process; run in the child before exec.
+#[cfg(unix)]
+fn set_resource_limits(limits: &SandboxLimits) -> std::io::Result<()> {
+    const MIB: u64 = 1024 * 1024;
+    let mib = |mb: Option<u64>| mb.map(|mb| mb.saturating_mul(MIB));
+    // The hard CPU limit is a second above the soft one, so that the process
+    // gets SIGXCPU rather than SIGKILL, which reads as running out of memory.
+    let resources = [
+        (libc::RLIMIT_CPU, limits.cpu_secs, 1),
+        (libc::RLIMIT_AS, mib(limits.memory_mb), 0),
+        (libc::RLIMIT_NPROC, limits.max_processes, 0),
+        (libc::RLIMIT_FSIZE, mib(limits.max_file_size_mb), 0),
+    ];
+    for (resource, limit, slack) in resources {
+        let Some(limit) = limit else {
+            continue;
+        };
+        let rlimit = libc::rlimit {
+            rlim_cur: limit,
+            rlim_max: limit.saturating_add(slack),
+        };
+        // SAFETY: `rlimit` is a valid, initialized struct for the call.
+        if unsafe { libc::setrlimit(resource, &rlimit) } == -1 {
+            return Err(std::io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
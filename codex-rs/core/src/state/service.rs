@@ -2,20 +2,36 @@ use std::sync::Arc;
 
 use crate::AuthManager;
 use crate::RolloutRecorder;
+use crate::approval_cache::PersistentApprovals;
+use crate::audit::AuditLog;
+use crate::background_jobs::BackgroundJobs;
+use crate::collab::Collaborators;
+use crate::lazy_service::LazyService;
 use crate::mcp_connection_manager::McpConnectionManager;
+use crate::patch_journal::PatchJournal;
+use crate::read_ahead::ReadAhead;
+use crate::replay_bundle::ReplayBundle;
+use crate::sandboxing::container::SessionContainers;
+use crate::tools::custom::ToolRuntimeRegistry;
 use crate::tools::executor::DynToolExecutor;
+use crate::tools::parallel::WorkspaceLocks;
+use crate::tools::runtimes::web_fetch::WebFetchCache;
 use crate::tools::sandboxing::ApprovalStore;
 use crate::unified_exec::UnifiedExecSessionManager;
 use crate::user_notification::UserNotifier;
+use crate::workspace_index::WorkspaceIndex;
 use codex_otel::otel_event_manager::OtelEventManager;
 use tokio::sync::Mutex;
 use tokio::sync::RwLock;
+use tokio::sync::Semaphore;
 use tokio_util::sync::CancellationToken;
 
 pub(crate) struct SessionServices {
     pub(crate) mcp_connection_manager: Arc<RwLock<McpConnectionManager>>,
     pub(crate) mcp_startup_cancellation_token: CancellationToken,
     pub(crate) unified_exec_manager: UnifiedExecSessionManager,
+    /// Commands the `shell` tool started with `background: true`.
+    pub(crate) background_jobs: BackgroundJobs,
     pub(crate) notifier: UserNotifier,
     pub(crate) rollout: Mutex<Option<RolloutRecorder>>,
     pub(crate) user_shell: crate::shell::Shell,
@@ -23,5 +39,37 @@ pub(crate) struct SessionServices {
     pub(crate) auth_manager: Arc<AuthManager>,
     pub(crate) otel_event_manager: OtelEventManager,
     pub(crate) tool_approvals: Mutex<ApprovalStore>,
+    /// Held while the user is asked for an approval, so that calls running
+    /// in parallel ask one at a time.
+    pub(crate) approval_prompt: Mutex<()>,
+    /// Set when the `batch_approvals` feature is enabled: calls do not take
+    /// `approval_prompt`, and their approvals are asked about together.
+    pub(crate) batch_approvals: bool,
+    /// Set when `approval_cache.persist` is on.
+    pub(crate) persistent_approvals: Option<PersistentApprovals>,
+    /// Set when `audit.enabled` is on.
+    pub(crate) audit_log: Option<AuditLog>,
     pub(crate) tool_executor: DynToolExecutor,
+    /// Tools contributed by the embedder.
+    pub(crate) tool_runtimes: ToolRuntimeRegistry,
+    /// Bounds the tool calls running at once with `parallel_tool_execution`.
+    pub(crate) tool_call_permits: Arc<Semaphore>,
+    pub(crate) workspace_locks: WorkspaceLocks,
+    /// Responses the `web_fetch` tool may reuse.
+    pub(crate) web_fetch_cache: WebFetchCache,
+    /// Follow activity events with a one-line `StatusLine` summary.
+    pub(crate) condensed_events: bool,
+    /// Follow `read_file` results with a `FileRead` event for previews.
+    pub(crate) file_read_events: bool,
+    pub(crate) collaborators: Arc<Collaborators>,
+    /// Set when the `read_ahead` feature is enabled.
+    pub(crate) read_ahead: Option<Arc<ReadAhead>>,
+    /// Set when the `undo_patch` feature is enabled.
+    pub(crate) patch_journal: Option<Mutex<PatchJournal>>,
+    /// Set when the `workspace_index` feature is enabled.
+    pub(crate) workspace_index: Option<LazyService<Arc<WorkspaceIndex>>>,
+    /// Set with `sandbox_backend = "container"`.
+    pub(crate) container: Option<SessionContainers>,
+    /// Set when the `reproducible_exec` feature is enabled.
+    pub(crate) replay: Option<ReplayBundle>,
 }
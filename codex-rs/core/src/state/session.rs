@@ -1,19 +1,54 @@
 //! Session-wide mutable state.
 
 use codex_protocol::models::ResponseItem;
+use codex_protocol::protocol::ArtifactEvent;
+use codex_protocol::protocol::FileReadEntry;
+use codex_protocol::protocol::IntegrityFinding;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::SystemTime;
 
 use crate::codex::SessionConfiguration;
 use crate::context_manager::ContextManager;
+use crate::dirty_files::FileDigest;
 use crate::protocol::RateLimitSnapshot;
 use crate::protocol::TokenUsage;
 use crate::protocol::TokenUsageInfo;
+use crate::quarantine::still_exists;
+use crate::tools::runtimes::compose::ComposeProject;
 use crate::truncate::TruncationPolicy;
+use crate::workspace_index::WorkspaceSnapshot;
 
 /// Persistent, session-scoped state previously stored directly on `Session`.
 pub(crate) struct SessionState {
     pub(crate) session_configuration: SessionConfiguration,
     pub(crate) history: ContextManager,
     pub(crate) latest_rate_limits: Option<RateLimitSnapshot>,
+    /// Files created, modified or moved by successfully applied patches.
+    pub(crate) touched_paths: BTreeSet<PathBuf>,
+    /// How applied patches last left each file they touched, so the
+    /// `dirty_file_guard` can tell later edits by the user apart.
+    pub(crate) patched_files: HashMap<PathBuf, Option<FileDigest>>,
+    /// Files whose contents tools returned to the model.
+    pub(crate) files_read: BTreeMap<PathBuf, FileReadEntry>,
+    /// Contents of files when `read_file` or `read_changes` last returned
+    /// them, the base `read_changes` diffs against.
+    pub(crate) served_versions: HashMap<PathBuf, String>,
+    /// Compose projects brought up by this session.
+    pub(crate) compose_projects: Vec<ComposeProject>,
+    /// Files exchanged through the artifact channel, in arrival order.
+    pub(crate) artifacts: Vec<ArtifactEvent>,
+    /// Files escalated commands created outside the workspace that the user
+    /// has neither deleted nor chosen to keep.
+    pub(crate) quarantined: Vec<IntegrityFinding>,
+    /// When the session started; the workspace check only reports state
+    /// created after this.
+    pub(crate) started_at: SystemTime,
+    /// The workspace index as the last turn left it.
+    pub(crate) workspace_snapshot: Option<WorkspaceSnapshot>,
 }
 
 impl SessionState {
@@ -24,6 +59,15 @@ impl SessionState {
             session_configuration,
             history,
             latest_rate_limits: None,
+            touched_paths: BTreeSet::new(),
+            patched_files: HashMap::new(),
+            files_read: BTreeMap::new(),
+            served_versions: HashMap::new(),
+            compose_projects: Vec::new(),
+            artifacts: Vec::new(),
+            quarantined: Vec::new(),
+            started_at: SystemTime::now(),
+            workspace_snapshot: None,
         }
     }
 
@@ -71,6 +115,108 @@ impl SessionState {
         (self.token_info(), self.latest_rate_limits.clone())
     }
 
+    // Touched-file helpers
+    pub(crate) fn record_touched_paths<I>(&mut self, paths: I)
+    where
+        I: IntoIterator<Item = PathBuf>,
+    {
+        self.touched_paths.extend(paths);
+    }
+
+    pub(crate) fn touched_paths(&self) -> Vec<PathBuf> {
+        self.touched_paths.iter().cloned().collect()
+    }
+
+    pub(crate) fn record_patched_files<I>(&mut self, files: I)
+    where
+        I: IntoIterator<Item = (PathBuf, Option<FileDigest>)>,
+    {
+        self.patched_files.extend(files);
+    }
+
+    pub(crate) fn patched_files(&self, paths: &[PathBuf]) -> HashMap<PathBuf, Option<FileDigest>> {
+        paths
+            .iter()
+            .filter_map(|path| {
+                self.patched_files
+                    .get(path)
+                    .map(|digest| (path.clone(), *digest))
+            })
+            .collect()
+    }
+
+    // Read ledger helpers
+    pub(crate) fn record_file_read(&mut self, path: PathBuf, tool: &str, bytes: u64) {
+        let entry = self
+            .files_read
+            .entry(path)
+            .or_insert_with_key(|path| FileReadEntry {
+                path: path.clone(),
+                reads: 0,
+                bytes: 0,
+                tools: Vec::new(),
+            });
+        entry.reads += 1;
+        entry.bytes += bytes;
+        if !entry.tools.iter().any(|t| t == tool) {
+            entry.tools.push(tool.to_string());
+        }
+    }
+
+    pub(crate) fn files_read(&self) -> Vec<FileReadEntry> {
+        self.files_read.values().cloned().collect()
+    }
+
+    pub(crate) fn record_served_version(&mut self, path: PathBuf, contents: String) {
+        self.served_versions.insert(path, contents);
+    }
+
+    pub(crate) fn served_version(&self, path: &Path) -> Option<String> {
+        self.served_versions.get(path).cloned()
+    }
+
+    // Compose project helpers
+    pub(crate) fn record_compose_project(&mut self, project: ComposeProject) {
+        self.forget_compose_project(&project);
+        self.compose_projects.push(project);
+    }
+
+    pub(crate) fn forget_compose_project(&mut self, project: &ComposeProject) {
+        self.compose_projects
+            .retain(|p| p.cwd != project.cwd || p.files != project.files);
+    }
+
+    pub(crate) fn take_compose_projects(&mut self) -> Vec<ComposeProject> {
+        std::mem::take(&mut self.compose_projects)
+    }
+
+    // Artifact helpers
+    pub(crate) fn record_artifact(&mut self, artifact: ArtifactEvent) {
+        self.artifacts.push(artifact);
+    }
+
+    pub(crate) fn artifacts(&self) -> Vec<ArtifactEvent> {
+        self.artifacts.clone()
+    }
+
+    // Quarantine helpers
+    pub(crate) fn quarantine(&mut self, findings: Vec<IntegrityFinding>) {
+        for finding in findings {
+            self.quarantined.retain(|f| f.id != finding.id);
+            self.quarantined.push(finding);
+        }
+    }
+
+    pub(crate) fn release_quarantined(&mut self, ids: &[String]) {
+        self.quarantined.retain(|f| !ids.contains(&f.id));
+    }
+
+    /// The quarantined files that still exist; the rest are forgotten.
+    pub(crate) fn quarantined(&mut self) -> Vec<IntegrityFinding> {
+        self.quarantined.retain(still_exists);
+        self.quarantined.clone()
+    }
+
     pub(crate) fn set_token_usage_full(&mut self, context_window: i64) {
         self.history.set_token_usage_full(context_window);
     }
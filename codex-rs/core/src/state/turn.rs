@@ -12,6 +12,7 @@ use codex_protocol::models::ResponseInputItem;
 use tokio::sync::oneshot;
 
 use crate::codex::TurnContext;
+use crate::protocol::ExecApprovalRequestEvent;
 use crate::protocol::ReviewDecision;
 use crate::tasks::SessionTask;
 
@@ -67,6 +68,15 @@ impl ActiveTurn {
 #[derive(Default)]
 pub(crate) struct TurnState {
     pending_approvals: HashMap<String, oneshot::Sender<ReviewDecision>>,
+    /// Per-hunk answers to patch approvals, read by the caller that requested
+    /// the approval once the overall decision arrives.
+    hunk_decisions: HashMap<String, Vec<ReviewDecision>>,
+    /// Command approvals collected to be asked about together, with
+    /// `batch_approvals`.
+    approval_batch: Vec<(ExecApprovalRequestEvent, oneshot::Sender<ReviewDecision>)>,
+    /// Batches waiting for an answer, by batch id, in the order of their
+    /// requests.
+    pending_batches: HashMap<String, Vec<oneshot::Sender<ReviewDecision>>>,
     pending_input: Vec<ResponseInputItem>,
 }
 
@@ -86,8 +96,54 @@ impl TurnState {
         self.pending_approvals.remove(key)
     }
 
+    /// Record per-hunk decisions for the approval `key`, if it is pending.
+    pub(crate) fn insert_hunk_decisions(&mut self, key: String, decisions: Vec<ReviewDecision>) {
+        if self.pending_approvals.contains_key(&key) {
+            self.hunk_decisions.insert(key, decisions);
+        }
+    }
+
+    pub(crate) fn take_hunk_decisions(&mut self, key: &str) -> Option<Vec<ReviewDecision>> {
+        self.hunk_decisions.remove(key)
+    }
+
+    /// Adds `request` to the batch being collected. Returns `true` when it
+    /// starts the batch, so the caller is the one to send it.
+    pub(crate) fn push_batched_approval(
+        &mut self,
+        request: ExecApprovalRequestEvent,
+        tx: oneshot::Sender<ReviewDecision>,
+    ) -> bool {
+        self.approval_batch.push((request, tx));
+        self.approval_batch.len() == 1
+    }
+
+    pub(crate) fn take_approval_batch(
+        &mut self,
+    ) -> Vec<(ExecApprovalRequestEvent, oneshot::Sender<ReviewDecision>)> {
+        std::mem::take(&mut self.approval_batch)
+    }
+
+    pub(crate) fn insert_pending_batch(
+        &mut self,
+        id: String,
+        senders: Vec<oneshot::Sender<ReviewDecision>>,
+    ) {
+        self.pending_batches.insert(id, senders);
+    }
+
+    pub(crate) fn remove_pending_batch(
+        &mut self,
+        id: &str,
+    ) -> Option<Vec<oneshot::Sender<ReviewDecision>>> {
+        self.pending_batches.remove(id)
+    }
+
     pub(crate) fn clear_pending(&mut self) {
         self.pending_approvals.clear();
+        self.hunk_decisions.clear();
+        self.approval_batch.clear();
+        self.pending_batches.clear();
         self.pending_input.clear();
     }
 
@@ -0,0 +1,241 @@
+//! One-line summaries of session activity for the condensed event stream.
+//!
+//! When [`Feature::CondensedEvents`](crate::features::Feature::CondensedEvents)
+//! is enabled, every event that describes something the agent is doing is
+//! followed by an [`EventMsg::StatusLine`] with a short, human-readable
+//! description ("Reading src/main.rs", "Waiting for approval to run cargo
+//! test"). Minimal UIs and text-to-speech frontends can render only those
+//! lines instead of interpreting the full event stream themselves.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_protocol::parse_command::ParsedCommand;
+use codex_protocol::plan_tool::StepStatus;
+use codex_protocol::protocol::ArtifactDirection;
+use codex_protocol::protocol::EventMsg;
+use codex_protocol::protocol::FileChange;
+use codex_protocol::protocol::TurnAbortReason;
+
+/// Status lines are meant to be spoken or shown in a single row.
+const MAX_STATUS_CHARS: usize = 80;
+
+/// Summarize `msg`, or `None` for events that carry no user-visible activity
+/// (deltas, token counts, end events of successful steps, ...).
+pub(crate) fn summarize(msg: &EventMsg) -> Option<String> {
+    let line = match msg {
+        EventMsg::TaskStarted(_) => "Working".to_string(),
+        EventMsg::TaskComplete(_) => "Done".to_string(),
+        EventMsg::TurnAborted(ev) => match ev.reason {
+            TurnAbortReason::Interrupted => "Stopped".to_string(),
+            TurnAbortReason::Replaced | TurnAbortReason::ReviewEnded => return None,
+        },
+        EventMsg::Error(ev) => format!("Error: {}", ev.message),
+        EventMsg::ExecCommandBegin(ev) => describe_command(&ev.parsed_cmd, &ev.command),
+        EventMsg::ExecCommandEnd(ev) if ev.exit_code != 0 => {
+            format!("Command failed with exit code {}", ev.exit_code)
+        }
+        EventMsg::ExecApprovalRequest(ev) => {
+            format!("Waiting for approval to run {}", ev.command.join(" "))
+        }
+        EventMsg::ExecApprovalBatchRequest(ev) => {
+            format!("Waiting for approval to run {} commands", ev.requests.len())
+        }
+        EventMsg::ApplyPatchApprovalRequest(ev) => {
+            format!("Waiting for approval to {}", describe_changes(&ev.changes))
+        }
+        EventMsg::PatchApplyBegin(ev) => capitalize(&describe_changes(&ev.changes)),
+        EventMsg::PatchApplyEnd(ev) if !ev.success => "Failed to apply edits".to_string(),
+        EventMsg::McpToolCallBegin(ev) => {
+            format!("Calling {}.{}", ev.invocation.server, ev.invocation.tool)
+        }
+        EventMsg::McpToolCallEnd(ev) if !ev.is_success() => {
+            format!("{}.{} failed", ev.invocation.server, ev.invocation.tool)
+        }
+        EventMsg::WebSearchEnd(ev) => format!("Searched the web for {}", ev.query),
+        EventMsg::ViewImageToolCall(ev) => format!("Viewing {}", file_name(&ev.path)),
+        EventMsg::PlanUpdate(args) => {
+            let step = args
+                .plan
+                .iter()
+                .find(|item| matches!(item.status, StepStatus::InProgress))?;
+            format!("Now: {}", step.step)
+        }
+        EventMsg::Artifact(ev) => match ev.direction {
+            ArtifactDirection::Inbound => format!("Received {}", ev.name),
+            ArtifactDirection::Outbound => format!("Sent {}", ev.name),
+        },
+        EventMsg::EnteredReviewMode(_) => "Reviewing changes".to_string(),
+        _ => return None,
+    };
+    Some(shorten(&line))
+}
+
+fn describe_command(parsed: &[ParsedCommand], command: &[String]) -> String {
+    let mut reads = Vec::new();
+    for part in parsed {
+        match part {
+            ParsedCommand::Read { name, .. } => reads.push(name.as_str()),
+            ParsedCommand::ListFiles { path, .. } => {
+                return match path {
+                    Some(path) => format!("Listing files in {path}"),
+                    None => "Listing files".to_string(),
+                };
+            }
+            ParsedCommand::Search { query, path, .. } => {
+                return match (query, path) {
+                    (Some(query), Some(path)) => format!("Searching for {query} in {path}"),
+                    (Some(query), None) => format!("Searching for {query}"),
+                    (None, _) => "Searching files".to_string(),
+                };
+            }
+            ParsedCommand::Unknown { cmd } => return format!("Running {cmd}"),
+        }
+    }
+    if reads.is_empty() {
+        format!("Running {}", command.join(" "))
+    } else {
+        format!("Reading {}", reads.join(", "))
+    }
+}
+
+fn describe_changes(changes: &HashMap<PathBuf, FileChange>) -> String {
+    if let [(path, change)] = changes.iter().collect::<Vec<_>>().as_slice() {
+        let verb = match change {
+            FileChange::Add { .. }
+            | FileChange::Binary {
+                previous_size: None,
+                ..
+            } => "create",
+            FileChange::Delete { .. } => "delete",
+            FileChange::Update { .. } | FileChange::Binary { .. } => "edit",
+        };
+        format!("{verb} {}", file_name(path))
+    } else {
+        format!("edit {} files", changes.len())
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or(path.as_os_str())
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Keep the first line only and cap its length.
+fn shorten(line: &str) -> String {
+    let line = line.lines().next().unwrap_or_default().trim();
+    if line.chars().count() <= MAX_STATUS_CHARS {
+        return line.to_string();
+    }
+    let mut short: String = line.chars().take(MAX_STATUS_CHARS - 1).collect();
+    short.push('…');
+    short
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_protocol::plan_tool::PlanItemArg;
+    use codex_protocol::plan_tool::UpdatePlanArgs;
+    use codex_protocol::protocol::AgentMessageDeltaEvent;
+    use codex_protocol::protocol::ErrorEvent;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn describes_parsed_commands() {
+        let command = vec!["bash".to_string(), "-lc".to_string(), "cat a b".to_string()];
+        assert_eq!(
+            describe_command(
+                &[
+                    ParsedCommand::Read {
+                        cmd: "cat a".to_string(),
+                        name: "a".to_string(),
+                        path: PathBuf::from("a"),
+                    },
+                    ParsedCommand::Read {
+                        cmd: "cat b".to_string(),
+                        name: "b".to_string(),
+                        path: PathBuf::from("b"),
+                    },
+                ],
+                &command
+            ),
+            "Reading a, b"
+        );
+        assert_eq!(
+            describe_command(
+                &[ParsedCommand::Search {
+                    cmd: "rg foo src".to_string(),
+                    query: Some("foo".to_string()),
+                    path: Some("src".to_string()),
+                }],
+                &command
+            ),
+            "Searching for foo in src"
+        );
+        assert_eq!(describe_command(&[], &command), "Running bash -lc cat a b");
+    }
+
+    #[test]
+    fn describes_patch_changes() {
+        let mut changes = HashMap::new();
+        changes.insert(
+            PathBuf::from("/repo/src/lib.rs"),
+            FileChange::Add {
+                content: String::new(),
+            },
+        );
+        assert_eq!(describe_changes(&changes), "create lib.rs");
+        changes.insert(
+            PathBuf::from("/repo/src/main.rs"),
+            FileChange::Delete {
+                content: String::new(),
+            },
+        );
+        assert_eq!(describe_changes(&changes), "edit 2 files");
+    }
+
+    #[test]
+    fn summarizes_only_activity() {
+        assert_eq!(
+            summarize(&EventMsg::AgentMessageDelta(AgentMessageDeltaEvent {
+                delta: "hi".to_string(),
+            })),
+            None
+        );
+        assert_eq!(
+            summarize(&EventMsg::PlanUpdate(UpdatePlanArgs {
+                explanation: None,
+                plan: vec![
+                    PlanItemArg {
+                        step: "write tests".to_string(),
+                        status: StepStatus::Completed,
+                    },
+                    PlanItemArg {
+                        step: "fix the parser".to_string(),
+                        status: StepStatus::InProgress,
+                    },
+                ],
+            })),
+            Some("Now: fix the parser".to_string())
+        );
+        assert_eq!(
+            summarize(&EventMsg::Error(ErrorEvent {
+                message: format!("{}\nsecond line", "x".repeat(100)),
+                codex_error_info: None,
+            })),
+            Some(format!("Error: {}…", "x".repeat(MAX_STATUS_CHARS - 8)))
+        );
+    }
+}
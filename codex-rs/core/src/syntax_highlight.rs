@@ -0,0 +1,135 @@
+//! Tree-sitter syntax tokens for file previews sent to frontends.
+//!
+//! Only languages whose grammar is built in get tokens; for now that is
+//! bash, whose grammar also backs command parsing.
+
+use std::sync::OnceLock;
+
+use codex_protocol::protocol::SyntaxToken;
+use tree_sitter_highlight::HighlightConfiguration;
+use tree_sitter_highlight::HighlightEvent;
+use tree_sitter_highlight::Highlighter;
+
+/// Highlight names recognized in the grammars' queries, reported as the
+/// token `kind`.
+const HIGHLIGHT_NAMES: &[&str] = &[
+    "comment", "constant", "embedded", "function", "keyword", "number", "operator", "property",
+    "string",
+];
+
+fn bash_config() -> Option<&'static HighlightConfiguration> {
+    static CONFIG: OnceLock<Option<HighlightConfiguration>> = OnceLock::new();
+    CONFIG
+        .get_or_init(|| {
+            let mut config = HighlightConfiguration::new(
+                tree_sitter_bash::LANGUAGE.into(),
+                "bash",
+                tree_sitter_bash::HIGHLIGHT_QUERY,
+                "",
+                "",
+            )
+            .ok()?;
+            config.configure(HIGHLIGHT_NAMES);
+            Some(config)
+        })
+        .as_ref()
+}
+
+fn config_for(language: &str) -> Option<&'static HighlightConfiguration> {
+    match language {
+        "bash" => bash_config(),
+        _ => None,
+    }
+}
+
+/// Highlights `lines` as one snippet of `language`, returning the tokens of
+/// each line, or `None` if the language has no built-in grammar.
+pub(crate) fn highlight_lines(language: &str, lines: &[&str]) -> Option<Vec<Vec<SyntaxToken>>> {
+    let config = config_for(language)?;
+    let source = lines.join("\n");
+    let mut line_starts = Vec::with_capacity(lines.len());
+    let mut offset = 0;
+    for line in lines {
+        line_starts.push(offset);
+        offset += line.len() + 1;
+    }
+
+    let mut tokens = vec![Vec::new(); lines.len()];
+    let mut highlighter = Highlighter::new();
+    let events = highlighter
+        .highlight(config, source.as_bytes(), None, |_| None)
+        .ok()?;
+    let mut stack = Vec::new();
+    for event in events {
+        match event.ok()? {
+            HighlightEvent::HighlightStart(highlight) => stack.push(highlight.0),
+            HighlightEvent::HighlightEnd => {
+                stack.pop();
+            }
+            HighlightEvent::Source { start, end } => {
+                let Some(&kind) = stack.last() else {
+                    continue;
+                };
+                // A token spanning several lines is split at each newline.
+                let mut start = start;
+                while start < end {
+                    let index = line_starts.partition_point(|&line_start| line_start <= start) - 1;
+                    let line = lines[index];
+                    let line_start = line_starts[index];
+                    let line_end = (line_start + line.len()).min(end);
+                    if start < line_end {
+                        push_token(
+                            &mut tokens[index],
+                            char_offset(line, start - line_start),
+                            char_offset(line, line_end - line_start),
+                            HIGHLIGHT_NAMES[kind],
+                        );
+                    }
+                    start = line_start + line.len() + 1;
+                }
+            }
+        }
+    }
+    Some(tokens)
+}
+
+fn char_offset(line: &str, byte_offset: usize) -> u32 {
+    line.get(..byte_offset)
+        .map_or(0, |prefix| prefix.chars().count()) as u32
+}
+
+/// Appends a token, merging it into the last one when they touch and have
+/// the same kind.
+fn push_token(tokens: &mut Vec<SyntaxToken>, start: u32, end: u32, kind: &str) {
+    match tokens.last_mut() {
+        Some(last) if last.end == start && last.kind == kind => last.end = end,
+        _ => tokens.push(SyntaxToken {
+            start,
+            end,
+            kind: kind.to_string(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn token(start: u32, end: u32, kind: &str) -> SyntaxToken {
+        SyntaxToken {
+            start,
+            end,
+            kind: kind.to_string(),
+        }
+    }
+
+    #[test]
+    fn highlights_bash_per_line() {
+        let tokens = highlight_lines("bash", &["# setup", "echo \"hé\""]).expect("bash grammar");
+
+        assert_eq!(tokens[0], vec![token(0, 7, "comment")]);
+        assert!(tokens[1].contains(&token(5, 9, "string")), "{tokens:?}");
+        assert_eq!(highlight_lines("rust", &["fn main() {}"]), None);
+    }
+}
@@ -1,6 +1,7 @@
 mod compact;
 mod ghost_snapshot;
 mod regular;
+mod retry;
 mod review;
 mod undo;
 mod user_shell;
@@ -13,6 +14,7 @@ use tokio::select;
 use tokio::sync::Notify;
 use tokio_util::sync::CancellationToken;
 use tokio_util::task::AbortOnDropHandle;
+use tracing::Instrument;
 use tracing::trace;
 use tracing::warn;
 
@@ -23,6 +25,7 @@ use crate::protocol::EventMsg;
 use crate::protocol::TaskCompleteEvent;
 use crate::protocol::TurnAbortReason;
 use crate::protocol::TurnAbortedEvent;
+use crate::resource_usage::ChildUsage;
 use crate::state::ActiveTurn;
 use crate::state::RunningTask;
 use crate::state::TaskKind;
@@ -31,6 +34,7 @@ use codex_protocol::user_input::UserInput;
 pub(crate) use compact::CompactTask;
 pub(crate) use ghost_snapshot::GhostSnapshotTask;
 pub(crate) use regular::RegularTask;
+pub(crate) use retry::RetryTurnTask;
 pub(crate) use review::ReviewTask;
 pub(crate) use undo::UndoTask;
 pub(crate) use user_shell::UserShellCommandTask;
@@ -118,8 +122,10 @@ impl Session {
             let ctx = Arc::clone(&turn_context);
             let task_for_run = Arc::clone(&task);
             let task_cancellation_token = cancellation_token.child_token();
-            tokio::spawn(async move {
+            let span = tracing::info_span!("turn", turn_id = %turn_context.sub_id);
+            let run = async move {
                 let ctx_for_finish = Arc::clone(&ctx);
+                let usage_at_start = ChildUsage::now();
                 let last_agent_message = task_for_run
                     .run(
                         Arc::clone(&session_ctx),
@@ -132,11 +138,22 @@ impl Session {
                 if !task_cancellation_token.is_cancelled() {
                     // Emit completion uniformly from spawn site so all tasks share the same lifecycle.
                     let sess = session_ctx.clone_session();
+                    if let Some(usage) = usage_at_start
+                        .zip(ChildUsage::now())
+                        .and_then(|(start, end)| end.since(start))
+                    {
+                        sess.send_event(
+                            ctx_for_finish.as_ref(),
+                            EventMsg::TurnResourceUsage(usage),
+                        )
+                        .await;
+                    }
                     sess.on_task_finished(ctx_for_finish, last_agent_message)
                         .await;
                 }
                 done_clone.notify_waiters();
-            })
+            };
+            tokio::spawn(run.instrument(span))
         };
 
         let running_task = RunningTask {
@@ -168,6 +185,7 @@ impl Session {
             *active = None;
         }
         drop(active);
+        self.snapshot_workspace().await;
         let event = EventMsg::TaskComplete(TaskCompleteEvent { last_agent_message });
         self.send_event(turn_context.as_ref(), event).await;
     }
@@ -0,0 +1,244 @@
+use std::sync::Arc;
+
+use crate::codex::TurnContext;
+use crate::event_mapping::parse_turn_item;
+use crate::protocol::EventMsg;
+use crate::protocol::UndoCompletedEvent;
+use crate::protocol::UndoStartedEvent;
+use crate::state::TaskKind;
+use crate::tasks::SessionTask;
+use crate::tasks::SessionTaskContext;
+use async_trait::async_trait;
+use codex_git::GhostCommit;
+use codex_git::restore_ghost_commit;
+use codex_protocol::items::TurnItem;
+use codex_protocol::models::ResponseItem;
+use codex_protocol::user_input::UserInput;
+use tokio_util::sync::CancellationToken;
+use tracing::info;
+use tracing::warn;
+
+/// Rolls back the last turn, both its workspace changes and its place in the
+/// history, then runs it again with the user's message plus `instructions`.
+pub(crate) struct RetryTurnTask {
+    instructions: String,
+}
+
+impl RetryTurnTask {
+    pub(crate) fn new(instructions: String) -> Self {
+        Self { instructions }
+    }
+}
+
+#[async_trait]
+impl SessionTask for RetryTurnTask {
+    fn kind(&self) -> TaskKind {
+        TaskKind::Regular
+    }
+
+    async fn run(
+        self: Arc<Self>,
+        session: Arc<SessionTaskContext>,
+        ctx: Arc<TurnContext>,
+        _input: Vec<UserInput>,
+        cancellation_token: CancellationToken,
+    ) -> Option<String> {
+        let sess = session.clone_session();
+        sess.send_event(
+            ctx.as_ref(),
+            EventMsg::UndoStarted(UndoStartedEvent {
+                message: Some("Rolling back the last turn to retry it...".to_string()),
+            }),
+        )
+        .await;
+
+        let mut items = sess.clone_history().await.get_history();
+        let Some(LastTurn {
+            start,
+            input,
+            snapshot,
+        }) = last_turn(&items)
+        else {
+            sess.send_event(
+                ctx.as_ref(),
+                EventMsg::UndoCompleted(UndoCompletedEvent {
+                    success: false,
+                    message: Some("No turn to retry.".to_string()),
+                }),
+            )
+            .await;
+            return None;
+        };
+        // Without a snapshot the turn's edits would stay in the workspace
+        // while the model no longer knows about them.
+        let Some(ghost_commit) = snapshot else {
+            sess.send_event(
+                ctx.as_ref(),
+                EventMsg::UndoCompleted(UndoCompletedEvent {
+                    success: false,
+                    message: Some(
+                        "The last turn has no snapshot to roll back to, so it cannot be retried."
+                            .to_string(),
+                    ),
+                }),
+            )
+            .await;
+            return None;
+        };
+
+        if cancellation_token.is_cancelled() {
+            sess.send_event(
+                ctx.as_ref(),
+                EventMsg::UndoCompleted(UndoCompletedEvent {
+                    success: false,
+                    message: Some("Retry cancelled.".to_string()),
+                }),
+            )
+            .await;
+            return None;
+        }
+
+        let commit_id = ghost_commit.id().to_string();
+        let repo_path = ctx.cwd.clone();
+        let restore_result =
+            tokio::task::spawn_blocking(move || restore_ghost_commit(&repo_path, &ghost_commit))
+                .await;
+        let error = match restore_result {
+            Ok(Ok(())) => None,
+            Ok(Err(err)) => Some(err.to_string()),
+            Err(err) => Some(err.to_string()),
+        };
+        if let Some(err) = error {
+            let message = format!("Failed to restore snapshot {commit_id}: {err}");
+            warn!("{message}");
+            sess.send_event(
+                ctx.as_ref(),
+                EventMsg::UndoCompleted(UndoCompletedEvent {
+                    success: false,
+                    message: Some(message),
+                }),
+            )
+            .await;
+            return None;
+        }
+
+        items.truncate(start);
+        sess.replace_history(items).await;
+        info!(commit_id = commit_id, "Retry restored ghost snapshot");
+        let short_id: String = commit_id.chars().take(7).collect();
+        sess.send_event(
+            ctx.as_ref(),
+            EventMsg::UndoCompleted(UndoCompletedEvent {
+                success: true,
+                message: Some(format!(
+                    "Restored snapshot {short_id}; retrying the last turn."
+                )),
+            }),
+        )
+        .await;
+
+        crate::codex::run_task(
+            sess,
+            ctx,
+            retry_input(input, &self.instructions),
+            cancellation_token,
+        )
+        .await
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct LastTurn {
+    /// Index of the turn's user message in the history.
+    start: usize,
+    /// What the user sent to start the turn.
+    input: Vec<UserInput>,
+    /// The workspace as it was before the turn.
+    snapshot: Option<GhostCommit>,
+}
+
+fn last_turn(items: &[ResponseItem]) -> Option<LastTurn> {
+    let (start, input) =
+        items
+            .iter()
+            .enumerate()
+            .rev()
+            .find_map(|(idx, item)| match parse_turn_item(item) {
+                Some(TurnItem::UserMessage(message)) => Some((idx, message.content)),
+                _ => None,
+            })?;
+    let snapshot = items[start..].iter().find_map(|item| match item {
+        ResponseItem::GhostSnapshot { ghost_commit } => Some(ghost_commit.clone()),
+        _ => None,
+    });
+    Some(LastTurn {
+        start,
+        input,
+        snapshot,
+    })
+}
+
+/// The original message with the amended instructions appended.
+fn retry_input(mut input: Vec<UserInput>, instructions: &str) -> Vec<UserInput> {
+    let instructions = instructions.trim();
+    if !instructions.is_empty() {
+        input.push(UserInput::Text {
+            text: instructions.to_string(),
+        });
+    }
+    input
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_protocol::models::ContentItem;
+    use pretty_assertions::assert_eq;
+
+    fn message(role: &str, text: &str) -> ResponseItem {
+        ResponseItem::Message {
+            id: None,
+            role: role.to_string(),
+            content: vec![ContentItem::InputText {
+                text: text.to_string(),
+            }],
+        }
+    }
+
+    fn text(text: &str) -> UserInput {
+        UserInput::Text {
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn finds_the_last_turn_and_its_snapshot() {
+        let snapshot = GhostCommit::new("c2".to_string(), None, Vec::new(), Vec::new());
+        let items = vec![
+            message("user", "first"),
+            message("assistant", "done"),
+            message("user", "add a parser"),
+            message("assistant", "working on it"),
+            ResponseItem::GhostSnapshot {
+                ghost_commit: snapshot.clone(),
+            },
+            message("assistant", "added"),
+        ];
+
+        let turn = last_turn(&items).expect("last turn");
+
+        assert_eq!(
+            turn,
+            LastTurn {
+                start: 2,
+                input: vec![text("add a parser")],
+                snapshot: Some(snapshot),
+            }
+        );
+        assert_eq!(
+            retry_input(turn.input, " but use nom "),
+            vec![text("add a parser"), text("but use nom")]
+        );
+        assert_eq!(last_turn(&[message("assistant", "hello")]), None);
+    }
+}
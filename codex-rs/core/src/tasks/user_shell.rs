@@ -11,6 +11,7 @@ use uuid::Uuid;
 
 use crate::codex::TurnContext;
 use crate::exec::ExecToolCallOutput;
+use crate::exec::ExitReason;
 use crate::exec::SandboxType;
 use crate::exec::StdoutStream;
 use crate::exec::StreamOutput;
@@ -24,6 +25,7 @@ use crate::protocol::ExecCommandSource;
 use crate::protocol::SandboxPolicy;
 use crate::protocol::TaskStartedEvent;
 use crate::sandboxing::ExecEnv;
+use crate::secret_redaction::SecretRedactor;
 use crate::state::TaskKind;
 use crate::tools::format_exec_output_str;
 use crate::user_shell_command::user_shell_command_record_item;
@@ -102,12 +104,16 @@ impl SessionTask for UserShellCommandTask {
             with_escalated_permissions: None,
             justification: None,
             arg0: None,
+            limits: None,
         };
 
+        let redactor =
+            SecretRedactor::for_command(&turn_context.client.config().command_env, &exec_env.env);
         let stdout_stream = Some(StdoutStream {
             sub_id: turn_context.sub_id.clone(),
             call_id: call_id.clone(),
             tx_event: session.get_tx_event(),
+            redactor: redactor.clone(),
         });
 
         let sandbox_policy = SandboxPolicy::DangerFullAccess;
@@ -124,7 +130,8 @@ impl SessionTask for UserShellCommandTask {
                     stderr: StreamOutput::new(aborted_message.clone()),
                     aggregated_output: StreamOutput::new(aborted_message.clone()),
                     duration: Duration::ZERO,
-                    timed_out: false,
+                    full_output_path: None,
+                    exit_reason: ExitReason::NonZeroExit,
                 };
                 let output_items = [user_shell_command_record_item(
                     &raw_command,
@@ -156,6 +163,10 @@ impl SessionTask for UserShellCommandTask {
                     .await;
             }
             Ok(Ok(output)) => {
+                let output = match &redactor {
+                    Some(redactor) => redactor.redact_output(output),
+                    None => output,
+                };
                 session
                     .send_event(
                         turn_context.as_ref(),
@@ -198,7 +209,8 @@ impl SessionTask for UserShellCommandTask {
                     stderr: StreamOutput::new(message.clone()),
                     aggregated_output: StreamOutput::new(message.clone()),
                     duration: Duration::ZERO,
-                    timed_out: false,
+                    full_output_path: None,
+                    exit_reason: ExitReason::SpawnFailure,
                 };
                 session
                     .send_event(
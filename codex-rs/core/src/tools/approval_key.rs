@@ -0,0 +1,292 @@
+//! Canonical command lines for approval caching.
+//!
+//! Spellings of a command that run the same thing should share one cached
+//! approval instead of each prompting the user again. [`canonical_command`]
+//! passes the command through [`STEPS`], each of which removes one kind of
+//! inessential difference; add a step to teach it another.
+//!
+//! [`trust_prefix`] picks the words of a canonical command a user can trust
+//! for the rest of the session, so that approving `cargo test -p core` can
+//! cover every `cargo test` run.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_protocol::paths::normalize_for_comparison;
+
+use crate::bash::parse_shell_lc_plain_commands;
+
+type Step = fn(Vec<String>, &Path) -> Vec<String>;
+
+/// Applied in order; later steps see the output of earlier ones.
+const STEPS: &[Step] = &[unwrap_shell, drop_no_op_flags, resolve_paths];
+
+/// Flags that only change how output is presented.
+const NO_OP_FLAGS: &[&str] = &[
+    "--color",
+    "--color=always",
+    "--color=auto",
+    "--color=never",
+    "--colour",
+    "--colour=always",
+    "--colour=auto",
+    "--colour=never",
+    "--no-color",
+    "--no-colour",
+    "--no-pager",
+];
+
+/// The form of `command`, run in `cwd`, that approvals are cached under.
+pub(crate) fn canonical_command(command: &[String], cwd: &Path) -> Vec<String> {
+    STEPS
+        .iter()
+        .fold(command.to_vec(), |command, step| step(command, cwd))
+}
+
+/// Programs that run whatever their arguments say; trusting them by name
+/// would trust anything. `find -exec`, sed's `e` command and awk's
+/// `system()` count.
+const RUNS_ANYTHING: &[&str] = &[
+    "bash", "sh", "zsh", "dash", "fish", "nu", "env", "sudo", "doas", "su", "xargs", "parallel",
+    "find", "sed", "awk", "exec", "eval", "nohup", "nice", "time", "timeout", "watch", "ssh",
+];
+
+/// Shells that [`unwrap_shell`] leaves in front of the words of a `-lc`
+/// script; trusted together with a prefix of those words.
+const LOGIN_SHELLS: &[&str] = &["bash", "zsh", "sh"];
+
+/// Programs that only read, trusted by name alone.
+const READ_ONLY_TOOLS: &[&str] = &[
+    "cat", "cut", "df", "diff", "du", "echo", "grep", "head", "ls", "nl", "pwd", "stat", "tail",
+    "tr", "wc", "which",
+];
+
+/// Test runners, trusted by name like `cargo test` is with its subcommand.
+const TEST_RUNNERS: &[&str] = &[
+    "pytest", "py.test", "jest", "vitest", "mocha", "rspec", "phpunit", "ctest",
+];
+
+/// Interpreters, trusted together with the script or module they run.
+const INTERPRETERS: &[&str] = &[
+    "python", "python3", "node", "ruby", "perl", "deno", "bun", "php", "Rscript",
+];
+
+/// Tools whose first argument picks what they do, trusted together with it:
+/// `cargo test` but not `cargo publish`.
+const SUBCOMMAND_TOOLS: &[&str] = &[
+    "cargo", "git", "npm", "pnpm", "yarn", "npx", "go", "uv", "poetry", "pip", "pip3", "make",
+    "just", "docker", "kubectl", "gh", "dotnet", "mvn", "gradle", "bundle", "rake", "mix",
+];
+
+/// The words a command that starts like the canonical `command` has to
+/// start with to be trusted along with it, or `None` when no prefix is safe
+/// to offer, e.g. for a shell script, `sudo` or a program not known to be
+/// safe to trust by prefix.
+pub(crate) fn trust_prefix(command: &[String]) -> Option<Vec<String>> {
+    let program = command.first()?;
+    if program.contains(char::is_whitespace) {
+        return None;
+    }
+    let name = Path::new(program).file_name()?.to_str()?;
+    let argument = command.get(1).map(String::as_str);
+    if LOGIN_SHELLS.contains(&name) && argument == Some("-lc") && command.len() > 2 {
+        let inner = trust_prefix(&command[2..])?;
+        return Some(command[..2].iter().cloned().chain(inner).collect());
+    }
+    let words = if RUNS_ANYTHING.contains(&name) {
+        return None;
+    } else if INTERPRETERS.contains(&name) {
+        match argument? {
+            "-m" => 3,
+            arg if arg.starts_with('-') => return None,
+            _ => 2,
+        }
+    } else if SUBCOMMAND_TOOLS.contains(&name) {
+        match argument {
+            Some(arg) if !arg.starts_with('-') => 2,
+            _ => return None,
+        }
+    } else if READ_ONLY_TOOLS.contains(&name) || TEST_RUNNERS.contains(&name) {
+        1
+    } else {
+        return None;
+    };
+    (command.len() >= words).then(|| command[..words].to_vec())
+}
+
+/// `bash -c "cargo test"` runs the same as `cargo test` when the script is a
+/// single plain command. A login shell can set up a different environment,
+/// so `bash -lc "cargo test"` only becomes `bash -lc cargo test`.
+fn unwrap_shell(command: Vec<String>, _cwd: &Path) -> Vec<String> {
+    let inner = match parse_shell_lc_plain_commands(&command) {
+        Some(mut commands) if commands.len() == 1 => commands.remove(0),
+        _ => return command,
+    };
+    match command.as_slice() {
+        [shell, flag, _] if flag == "-lc" => [shell.clone(), flag.clone()]
+            .into_iter()
+            .chain(inner)
+            .collect(),
+        _ => inner,
+    }
+}
+
+/// Drop [`NO_OP_FLAGS`] given before a `--` separator, unless they follow an
+/// option that may take them as its value, like the pattern in
+/// `grep -e --color`. The program name is never dropped.
+fn drop_no_op_flags(command: Vec<String>, _cwd: &Path) -> Vec<String> {
+    let takes_value = |option: &str| {
+        option.starts_with('-') && !option.contains('=') && !NO_OP_FLAGS.contains(&option)
+    };
+    let mut past_options = false;
+    let keep: Vec<bool> = command
+        .iter()
+        .enumerate()
+        .map(|(index, arg)| {
+            if arg == "--" {
+                past_options = true;
+            }
+            index == 0
+                || past_options
+                || !NO_OP_FLAGS.contains(&arg.as_str())
+                || takes_value(&command[index - 1])
+        })
+        .collect();
+    command
+        .into_iter()
+        .zip(keep)
+        .filter_map(|(arg, keep)| keep.then_some(arg))
+        .collect()
+}
+
+/// Rewrite path arguments as absolute, normalized paths, so `./src/lib.rs`,
+/// `src/lib.rs` and `/repo/src/lib.rs` agree when run in `/repo`.
+fn resolve_paths(command: Vec<String>, cwd: &Path) -> Vec<String> {
+    command
+        .into_iter()
+        .map(|arg| match resolved_path(&arg, cwd) {
+            Some(path) => path.to_string_lossy().into_owned(),
+            None => arg,
+        })
+        .collect()
+}
+
+/// Arguments count as paths when they are absolute or start with `./` or
+/// `../`, or when they contain a separator and name an existing file. Other
+/// words, like `test` in `cargo test`, are left alone even if a file of that
+/// name exists.
+fn resolved_path(arg: &str, cwd: &Path) -> Option<PathBuf> {
+    if arg.starts_with('-') || !arg.contains(['/', std::path::MAIN_SEPARATOR]) {
+        return None;
+    }
+    let path = Path::new(arg);
+    let explicit = path.is_absolute() || arg.starts_with("./") || arg.starts_with("../");
+    let resolved = cwd.join(path);
+    (explicit || resolved.exists()).then(|| normalize_for_comparison(&resolved))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::tempdir;
+
+    fn argv(words: &[&str]) -> Vec<String> {
+        words.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn equivalent_spellings_share_a_canonical_form() {
+        let tmp = tempdir().expect("tmp");
+        let cwd = tmp.path();
+        std::fs::create_dir(cwd.join("src")).expect("mkdir");
+        std::fs::write(cwd.join("src/lib.rs"), "").expect("write");
+        let lib = normalize_for_comparison(&cwd.join("src/lib.rs"))
+            .to_string_lossy()
+            .into_owned();
+
+        let spellings = [
+            argv(&["git", "diff", "src/lib.rs"]),
+            argv(&[
+                "git",
+                "--no-pager",
+                "diff",
+                "--color=always",
+                "./src/lib.rs",
+            ]),
+            argv(&["bash", "-c", "git diff --no-color ./src/../src/lib.rs"]),
+            argv(&["git", "diff", &lib]),
+        ];
+        for command in spellings {
+            assert_eq!(
+                canonical_command(&command, cwd),
+                argv(&["git", "diff", &lib]),
+                "{command:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn meaningful_differences_are_kept() {
+        let tmp = tempdir().expect("tmp");
+        let cwd = tmp.path();
+        std::fs::create_dir(cwd.join("test")).expect("mkdir");
+
+        let kept = [
+            // A bare word is not a path, even if one of that name exists.
+            argv(&["cargo", "test"]),
+            // Nor is something shaped like a path that does not exist.
+            argv(&["git", "push", "origin/main"]),
+            // Arguments after `--` are passed through untouched.
+            argv(&["grep", "--", "--color", "notes.txt"]),
+            // So is the value of an option, even if it looks like a no-op flag.
+            argv(&["grep", "-e", "--color", "notes.txt"]),
+            // Several commands are not collapsed into one.
+            argv(&["bash", "-lc", "cargo fmt && cargo test"]),
+        ];
+        for command in kept {
+            assert_eq!(canonical_command(&command, cwd), command);
+        }
+
+        // A login shell stays in front of the command it runs.
+        assert_eq!(
+            canonical_command(&argv(&["bash", "-lc", "cargo test --no-pager"]), cwd),
+            argv(&["bash", "-lc", "cargo", "test"])
+        );
+    }
+
+    #[test]
+    fn trust_prefixes_stop_where_the_command_starts_to_matter() {
+        let cases = [
+            (argv(&["ls", "-la", "src"]), Some(argv(&["ls"]))),
+            (argv(&["pytest", "-x", "tests"]), Some(argv(&["pytest"]))),
+            (argv(&["go", "test", "./..."]), Some(argv(&["go", "test"]))),
+            (
+                argv(&["npm", "test", "--", "--watch=false"]),
+                Some(argv(&["npm", "test"])),
+            ),
+            (
+                argv(&["cargo", "test", "-p", "core"]),
+                Some(argv(&["cargo", "test"])),
+            ),
+            (
+                argv(&["python3", "-m", "pytest", "-q"]),
+                Some(argv(&["python3", "-m", "pytest"])),
+            ),
+            (argv(&["python3", "-c", "print(1)"]), None),
+            (argv(&["git", "--version"]), None),
+            (argv(&["sudo", "pytest"]), None),
+            (argv(&["find", ".", "-exec", "rm", "{}", ";"]), None),
+            (argv(&["sed", "-i", "s/a/b/", "notes.txt"]), None),
+            (argv(&["cargo build", "--release"]), None),
+            (argv(&["bash", "-lc", "pytest && rm -rf /"]), None),
+            (
+                argv(&["bash", "-lc", "cargo", "test", "-p", "core"]),
+                Some(argv(&["bash", "-lc", "cargo", "test"])),
+            ),
+        ];
+        for (command, expected) in cases {
+            assert_eq!(trust_prefix(&command), expected, "{command:?}");
+        }
+    }
+}
@@ -0,0 +1,109 @@
+//! Tools contributed by applications that embed Codex.
+//!
+//! An embedder implements [`CustomToolRuntime`], registers it under a tool
+//! name together with a description and a JSON schema for its arguments, and
+//! hands the [`ToolRuntimeRegistry`] to
+//! [`ConversationManager::with_tool_runtimes`](crate::ConversationManager::with_tool_runtimes).
+//! The model sees each registered tool as a function tool. Its calls go
+//! through the tool orchestrator like the built-in tools, so the session's
+//! approval policy and approval cache apply to them.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::Value;
+use tracing::warn;
+
+use crate::protocol::SandboxPolicy;
+
+/// One call of a custom tool by the model.
+#[derive(Debug, Clone)]
+pub struct CustomToolCall {
+    pub call_id: String,
+    /// The arguments, as sent by the model.
+    pub arguments: Value,
+    /// Working directory of the turn.
+    pub cwd: PathBuf,
+    /// The turn's sandbox policy, for runtimes that start processes or write
+    /// files and should respect it.
+    pub sandbox_policy: SandboxPolicy,
+}
+
+#[async_trait]
+pub trait CustomToolRuntime: Send + Sync {
+    /// Whether a call has effects the user should approve, subject to the
+    /// session's approval policy. Read-only tools return `false`.
+    fn needs_approval(&self, _arguments: &Value) -> bool {
+        true
+    }
+
+    /// How a call is shown in the approval prompt.
+    fn describe(&self, tool_name: &str, arguments: &Value) -> Vec<String> {
+        vec![tool_name.to_string(), arguments.to_string()]
+    }
+
+    /// Run the call. The `Ok` text is returned to the model as the tool's
+    /// output; the `Err` text is reported to the model as a failure.
+    async fn run(&self, call: CustomToolCall) -> Result<String, String>;
+}
+
+/// The definition the model is given for a custom tool.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct CustomToolSpec {
+    pub(crate) name: String,
+    pub(crate) description: String,
+    pub(crate) input_schema: Value,
+}
+
+#[derive(Clone)]
+struct RegisteredTool {
+    spec: CustomToolSpec,
+    runtime: Arc<dyn CustomToolRuntime>,
+}
+
+/// Custom tools available to a session, by name.
+#[derive(Clone, Default)]
+pub struct ToolRuntimeRegistry {
+    tools: BTreeMap<String, RegisteredTool>,
+}
+
+impl ToolRuntimeRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Offer `runtime` to the model as the tool `name`, taking arguments
+    /// described by the JSON schema `input_schema`. Names clashing with a
+    /// built-in or MCP tool are ignored when tools are assembled for a turn.
+    pub fn register(
+        &mut self,
+        name: impl Into<String>,
+        description: impl Into<String>,
+        input_schema: Value,
+        runtime: Arc<dyn CustomToolRuntime>,
+    ) {
+        let name = name.into();
+        let tool = RegisteredTool {
+            spec: CustomToolSpec {
+                name: name.clone(),
+                description: description.into(),
+                input_schema,
+            },
+            runtime,
+        };
+        if self.tools.insert(name.clone(), tool).is_some() {
+            warn!("overwriting custom tool {name}");
+        }
+    }
+
+    pub(crate) fn runtime(&self, name: &str) -> Option<Arc<dyn CustomToolRuntime>> {
+        self.tools.get(name).map(|tool| Arc::clone(&tool.runtime))
+    }
+
+    /// Definitions of the registered tools, ordered by name.
+    pub(crate) fn specs(&self) -> Vec<CustomToolSpec> {
+        self.tools.values().map(|tool| tool.spec.clone()).collect()
+    }
+}
@@ -1,8 +1,11 @@
+use crate::blocking_io::run_blocking;
 use crate::codex::Session;
 use crate::codex::TurnContext;
+use crate::dirty_files::patched_states;
 use crate::error::CodexErr;
 use crate::error::SandboxErr;
 use crate::exec::ExecToolCallOutput;
+use crate::features::Feature;
 use crate::function_tool::FunctionCallError;
 use crate::parse_command::parse_command;
 use crate::protocol::EventMsg;
@@ -13,6 +16,7 @@ use crate::protocol::FileChange;
 use crate::protocol::PatchApplyBeginEvent;
 use crate::protocol::PatchApplyEndEvent;
 use crate::protocol::TurnDiffEvent;
+use crate::sensitive_content::filter_read;
 use crate::tools::context::SharedTurnDiffTracker;
 use crate::tools::sandboxing::ToolError;
 use codex_protocol::parse_command::ParsedCommand;
@@ -20,6 +24,7 @@ use std::collections::HashMap;
 use std::path::Path;
 use std::path::PathBuf;
 use std::time::Duration;
+use tracing::warn;
 
 use super::format_exec_output_str;
 
@@ -257,6 +262,20 @@ impl ToolEmitter {
         output: &ExecToolCallOutput,
         ctx: ToolEventCtx<'_>,
     ) -> String {
+        let labeled;
+        let output = if matches!(self, Self::Shell { .. })
+            && ctx
+                .turn
+                .client
+                .config()
+                .features
+                .enabled(Feature::LabeledExecStreams)
+        {
+            labeled = super::with_labeled_streams(output);
+            &labeled
+        } else {
+            output
+        };
         match self {
             Self::Shell { freeform: true, .. } => {
                 super::format_exec_output_for_model_freeform(output, ctx.turn.truncation_policy)
@@ -265,11 +284,40 @@ impl ToolEmitter {
         }
     }
 
+    /// Screen the output of shell commands that read files for secrets
+    /// before it reaches the model.
+    async fn screen_reads(
+        &self,
+        ctx: ToolEventCtx<'_>,
+        mut output: ExecToolCallOutput,
+    ) -> Result<ExecToolCallOutput, ToolError> {
+        let Self::Shell {
+            cwd, parsed_cmd, ..
+        } = self
+        else {
+            return Ok(output);
+        };
+        let paths = read_paths(cwd, parsed_cmd);
+        if paths.is_empty() {
+            return Ok(output);
+        }
+        let text = std::mem::take(&mut output.aggregated_output.text);
+        output.aggregated_output.text =
+            filter_read(ctx.session, ctx.turn, ctx.call_id, &paths, text)
+                .await
+                .map_err(ToolError::Rejected)?;
+        Ok(output)
+    }
+
     pub async fn finish(
         &self,
         ctx: ToolEventCtx<'_>,
         out: Result<ExecToolCallOutput, ToolError>,
     ) -> Result<String, FunctionCallError> {
+        let out = match out {
+            Ok(output) => self.screen_reads(ctx, output).await,
+            Err(err) => Err(err),
+        };
         let (event, result) = match out {
             Ok(output) => {
                 let content = self.format_exec_output_for_model(&output, ctx);
@@ -375,6 +423,14 @@ async fn emit_exec_stage(
                 duration: output.duration,
                 formatted_output: format_exec_output_str(&output, ctx.turn.truncation_policy),
             };
+            let reads = attribute_reads(
+                exec_input.cwd,
+                exec_input.parsed_cmd,
+                exec_result.formatted_output.len(),
+            );
+            for (path, bytes) in reads {
+                ctx.session.record_file_read(path, "shell", bytes).await;
+            }
             emit_exec_end(ctx, exec_input, exec_result).await;
         }
         ToolEventStage::Failure(ToolEventFailure::Message(message)) => {
@@ -392,6 +448,36 @@ async fn emit_exec_stage(
     }
 }
 
+/// Files a command reads, resolved against its working directory.
+fn read_paths(cwd: &Path, parsed_cmd: &[ParsedCommand]) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = Vec::new();
+    for part in parsed_cmd {
+        if let ParsedCommand::Read { path, .. } = part {
+            let path = cwd.join(path);
+            if !paths.contains(&path) {
+                paths.push(path);
+            }
+        }
+    }
+    paths
+}
+
+/// Files read by a command, each with the bytes of output attributed to it:
+/// all of it when the command read a single file, none otherwise.
+fn attribute_reads(
+    cwd: &Path,
+    parsed_cmd: &[ParsedCommand],
+    output_bytes: usize,
+) -> Vec<(PathBuf, u64)> {
+    let paths = read_paths(cwd, parsed_cmd);
+    let bytes = if paths.len() == 1 {
+        output_bytes as u64
+    } else {
+        0
+    };
+    paths.into_iter().map(|path| (path, bytes)).collect()
+}
+
 async fn emit_exec_end(
     ctx: ToolEventCtx<'_>,
     exec_input: ExecCommandInput<'_>,
@@ -426,6 +512,28 @@ async fn emit_patch_end(
     stderr: String,
     success: bool,
 ) {
+    if success {
+        let touched = changes.iter().filter_map(|(path, change)| match change {
+            FileChange::Delete { .. } => None,
+            FileChange::Update {
+                move_path: Some(dest),
+                ..
+            } => Some(dest.clone()),
+            FileChange::Add { .. } | FileChange::Update { .. } | FileChange::Binary { .. } => {
+                Some(path.clone())
+            }
+        });
+        ctx.session.record_touched_paths(touched).await;
+
+        if ctx.session.enabled(Feature::DirtyFileGuard).await {
+            let changes = changes.clone();
+            match run_blocking(move || patched_states(&changes)).await {
+                Ok(states) => ctx.session.record_patched_files(states).await,
+                Err(err) => warn!("failed to record patched files: {err}"),
+            }
+        }
+    }
+
     ctx.session
         .send_event(
             ctx.turn,
@@ -452,3 +560,43 @@ async fn emit_patch_end(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn read(path: &str) -> ParsedCommand {
+        ParsedCommand::Read {
+            cmd: format!("cat {path}"),
+            name: path.to_string(),
+            path: PathBuf::from(path),
+        }
+    }
+
+    #[test]
+    fn attributes_output_to_a_single_read() {
+        let cwd = Path::new("/repo");
+        assert_eq!(
+            attribute_reads(cwd, &[read("src/lib.rs"), read("/repo/src/lib.rs")], 120),
+            vec![(PathBuf::from("/repo/src/lib.rs"), 120)]
+        );
+        assert_eq!(
+            attribute_reads(cwd, &[read("a.txt"), read("b.txt")], 120),
+            vec![
+                (PathBuf::from("/repo/a.txt"), 0),
+                (PathBuf::from("/repo/b.txt"), 0),
+            ]
+        );
+        assert_eq!(
+            attribute_reads(
+                cwd,
+                &[ParsedCommand::Unknown {
+                    cmd: "make".to_string()
+                }],
+                120
+            ),
+            Vec::new()
+        );
+    }
+}
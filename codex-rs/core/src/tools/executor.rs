@@ -1,13 +1,21 @@
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 
 use async_trait::async_trait;
 
 use crate::CODEX_APPLY_PATCH_ARG1;
+use crate::artifacts::artifacts_dir;
+use crate::background_jobs::started_message;
 use crate::exec::ExecToolCallOutput;
+use crate::exec::ExitReason;
 use crate::exec::StdoutStream;
+use crate::exec::StreamOutput;
+use crate::output_truncation::SPILL_DIR;
+use crate::output_truncation::truncate_output;
 use crate::sandboxing::CommandSpec;
 use crate::sandboxing::execute_env;
+use crate::secret_redaction::SecretRedactor;
 use crate::tools::runtimes::apply_patch::ApplyPatchRequest;
 use crate::tools::runtimes::build_command_spec;
 use crate::tools::runtimes::shell::ShellRequest;
@@ -49,21 +57,72 @@ impl ToolExecutor for DefaultToolExecutor {
         attempt: &SandboxAttempt<'_>,
         ctx: &ToolCtx<'_>,
     ) -> Result<ExecToolCallOutput, ToolError> {
-        let spec = build_command_spec(
+        let config = ctx.turn.client.config();
+        let mut spec = build_command_spec(
             &req.command,
             &req.cwd,
             &req.env,
+            &config.command_env,
             req.timeout_ms.into(),
             req.with_escalated_permissions,
             req.justification.clone(),
         )?;
+        spec.network = req.network.clone();
+        let replay = ctx.session.services.replay.as_ref();
+        if let Some(replay) = replay {
+            spec.env = replay.pinned_env(&spec.env);
+        }
+        // What the replay bundle records: the command before sandboxing.
+        let replayed = (replay.is_some() && !req.background).then(|| {
+            let mut command = vec![spec.program.clone()];
+            command.extend(spec.args.iter().cloned());
+            (command, spec.cwd.clone(), spec.env.clone())
+        });
+        let redactor = SecretRedactor::for_command(&config.command_env, &spec.env);
+        // A background job outlives the call's scratch directory.
+        let without_scratch = SandboxAttempt {
+            scratch_dir: None,
+            ..*attempt
+        };
+        let attempt = if req.background {
+            &without_scratch
+        } else {
+            attempt
+        };
         let env = attempt
             .env_for(spec)
             .map_err(|err| ToolError::Codex(err.into()))?;
-        let out = execute_env(env, attempt.policy, stdout_stream(ctx))
+        if req.background {
+            let jobs = &ctx.session.services.background_jobs;
+            let id = jobs
+                .spawn(env, attempt.policy, req.command.clone())
+                .await
+                .map_err(|err| {
+                    ToolError::Rejected(format!("failed to start background job: {err}"))
+                })?;
+            return Ok(background_job_output(id));
+        }
+        let out = execute_env(env, attempt.policy, stdout_stream(ctx, redactor.clone()))
             .await
             .map_err(ToolError::Codex)?;
-        Ok(out)
+        if let (Some(replay), Some((command, cwd, env))) = (replay, &replayed) {
+            replay
+                .record_command(&ctx.call_id, command, cwd, env, &out)
+                .await;
+        }
+        let out = match &redactor {
+            Some(redactor) => redactor.redact_output(out),
+            None => out,
+        };
+        let spill_dir = artifacts_dir(&config.codex_home, ctx.conversation_id()).join(SPILL_DIR);
+        Ok(truncate_output(
+            out,
+            &config.output_truncation,
+            ctx.turn.truncation_policy.byte_budget(),
+            &spill_dir,
+            &ctx.call_id,
+        )
+        .await)
     }
 
     async fn run_apply_patch(
@@ -76,18 +135,33 @@ impl ToolExecutor for DefaultToolExecutor {
         let env = attempt
             .env_for(spec)
             .map_err(|err| ToolError::Codex(err.into()))?;
-        let out = execute_env(env, attempt.policy, stdout_stream(ctx))
+        let out = execute_env(env, attempt.policy, stdout_stream(ctx, None))
             .await
             .map_err(ToolError::Codex)?;
         Ok(out)
     }
 }
 
-fn stdout_stream(ctx: &ToolCtx<'_>) -> Option<StdoutStream> {
+/// What a background `shell` call returns: the job id in place of output.
+fn background_job_output(id: u32) -> ExecToolCallOutput {
+    let message = started_message(id);
+    ExecToolCallOutput {
+        exit_code: 0,
+        stdout: StreamOutput::new(message.clone()),
+        stderr: StreamOutput::new(String::new()),
+        aggregated_output: StreamOutput::new(message),
+        duration: Duration::ZERO,
+        full_output_path: None,
+        exit_reason: ExitReason::Success,
+    }
+}
+
+fn stdout_stream(ctx: &ToolCtx<'_>, redactor: Option<Arc<SecretRedactor>>) -> Option<StdoutStream> {
     Some(StdoutStream {
         sub_id: ctx.turn.sub_id.clone(),
         call_id: ctx.call_id.clone(),
         tx_event: ctx.session.get_tx_event(),
+        redactor,
     })
 }
 
@@ -108,5 +182,6 @@ fn build_apply_patch_spec(req: &ApplyPatchRequest) -> Result<CommandSpec, ToolEr
         env: HashMap::new(),
         with_escalated_permissions: None,
         justification: None,
+        network: None,
     })
 }
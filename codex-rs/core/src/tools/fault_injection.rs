@@ -0,0 +1,326 @@
+//! Fault injection for testing how the orchestrator handles failing calls.
+//!
+//! [`FaultInjectingExecutor`] wraps a [`ToolExecutor`] and, following a
+//! seeded [`FaultPlan`], makes some of the calls it runs fail the way real
+//! commands do: they time out, lose the end of their output, are denied by
+//! the sandbox, or cannot be spawned. The same plan injects the same faults
+//! into the same sequence of calls, so a run that uncovers a bug can be
+//! replayed. Only built with the `fault-injection` feature.
+
+use std::collections::HashMap;
+use std::io;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use rand::Rng;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+use crate::error::CodexErr;
+use crate::error::SandboxErr;
+use crate::exec::DEFAULT_EXEC_COMMAND_TIMEOUT_MS;
+use crate::exec::EXEC_TIMEOUT_EXIT_CODE;
+use crate::exec::ExecToolCallOutput;
+use crate::exec::ExitReason;
+use crate::exec::SandboxType;
+use crate::exec::StreamOutput;
+use crate::tools::executor::DynToolExecutor;
+use crate::tools::executor::ToolExecutor;
+use crate::tools::runtimes::apply_patch::ApplyPatchRequest;
+use crate::tools::runtimes::shell::ShellRequest;
+use crate::tools::sandboxing::SandboxAttempt;
+use crate::tools::sandboxing::ToolCtx;
+use crate::tools::sandboxing::ToolError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Fault {
+    /// The call does not run and reports a timeout, as if it had been killed
+    /// when its timeout ran out.
+    Timeout,
+    /// The call runs, but only the first half of its output comes back.
+    PartialOutput,
+    /// The call does not run and reports a sandbox denial. Only injected
+    /// into sandboxed attempts.
+    SandboxDenied,
+    /// The call does not run because its program cannot be started.
+    SpawnFailure,
+}
+
+/// Which calls fail, and how.
+#[derive(Debug, Clone)]
+pub struct FaultPlan {
+    seed: u64,
+    rate: f64,
+    faults: Vec<Fault>,
+    forced: HashMap<usize, Option<Fault>>,
+}
+
+impl FaultPlan {
+    /// Gives a fifth of the calls one of the faults, picked at random.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rate: 0.2,
+            faults: vec![
+                Fault::Timeout,
+                Fault::PartialOutput,
+                Fault::SandboxDenied,
+                Fault::SpawnFailure,
+            ],
+            forced: HashMap::new(),
+        }
+    }
+
+    /// The share of calls, between 0 and 1, that get a fault.
+    pub fn rate(mut self, rate: f64) -> Self {
+        self.rate = rate.clamp(0.0, 1.0);
+        self
+    }
+
+    /// The faults to pick from.
+    pub fn faults(mut self, faults: impl IntoIterator<Item = Fault>) -> Self {
+        self.faults = faults.into_iter().collect();
+        self
+    }
+
+    /// Gives the call at 0-based `index` `fault`, or no fault for `None`,
+    /// whatever the dice say. The other calls keep their faults.
+    pub fn at(mut self, index: usize, fault: Option<Fault>) -> Self {
+        self.forced.insert(index, fault);
+        self
+    }
+}
+
+/// A fault given to a call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InjectedFault {
+    /// Position of the call among those the executor ran, from 0.
+    pub index: usize,
+    pub call_id: String,
+    pub fault: Fault,
+}
+
+/// Rolls the faults of a [`FaultPlan`], one call at a time.
+struct FaultDice {
+    plan: FaultPlan,
+    rng: StdRng,
+    calls: usize,
+}
+
+impl FaultDice {
+    fn new(plan: FaultPlan) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(plan.seed),
+            plan,
+            calls: 0,
+        }
+    }
+
+    /// The fault of the next call, if any. Every call draws the same numbers,
+    /// so forcing one call does not change the faults of the others.
+    fn roll(&mut self, sandboxed: bool) -> (usize, Option<Fault>) {
+        let index = self.calls;
+        self.calls += 1;
+        let hit = self.rng.random_bool(self.plan.rate);
+        let pick = self.rng.random_range(0..self.plan.faults.len().max(1));
+        let fault = match self.plan.forced.get(&index) {
+            Some(fault) => *fault,
+            None if hit => self.plan.faults.get(pick).copied(),
+            None => None,
+        };
+        // Outside the sandbox there is nothing to deny the call.
+        let fault = fault.filter(|fault| sandboxed || *fault != Fault::SandboxDenied);
+        (index, fault)
+    }
+}
+
+/// Runs calls through another executor, injecting the faults of a plan.
+pub struct FaultInjectingExecutor {
+    inner: DynToolExecutor,
+    dice: Mutex<FaultDice>,
+    injected: Mutex<Vec<InjectedFault>>,
+}
+
+impl FaultInjectingExecutor {
+    pub fn new(inner: DynToolExecutor, plan: FaultPlan) -> Self {
+        Self {
+            inner,
+            dice: Mutex::new(FaultDice::new(plan)),
+            injected: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// The faults injected so far, in the order of their calls.
+    pub fn injected(&self) -> Vec<InjectedFault> {
+        match self.injected.lock() {
+            Ok(injected) => injected.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        }
+    }
+
+    fn next_fault(&self, attempt: &SandboxAttempt<'_>, ctx: &ToolCtx<'_>) -> Option<Fault> {
+        let sandboxed = attempt.sandbox != SandboxType::None;
+        let (index, fault) = match self.dice.lock() {
+            Ok(mut dice) => dice.roll(sandboxed),
+            Err(poisoned) => poisoned.into_inner().roll(sandboxed),
+        };
+        let fault = fault?;
+        let injected = InjectedFault {
+            index,
+            call_id: ctx.call_id.clone(),
+            fault,
+        };
+        match self.injected.lock() {
+            Ok(mut all) => all.push(injected),
+            Err(poisoned) => poisoned.into_inner().push(injected),
+        }
+        Some(fault)
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for FaultInjectingExecutor {
+    async fn run_shell(
+        &self,
+        req: &ShellRequest,
+        attempt: &SandboxAttempt<'_>,
+        ctx: &ToolCtx<'_>,
+    ) -> Result<ExecToolCallOutput, ToolError> {
+        let timeout_ms = req.timeout_ms.unwrap_or(DEFAULT_EXEC_COMMAND_TIMEOUT_MS);
+        let fault = self.next_fault(attempt, ctx);
+        if let Some(result) = fault.and_then(|fault| injected_result(fault, timeout_ms)) {
+            return result;
+        }
+        let output = self.inner.run_shell(req, attempt, ctx).await?;
+        Ok(apply_to_output(fault, output))
+    }
+
+    async fn run_apply_patch(
+        &self,
+        req: &ApplyPatchRequest,
+        attempt: &SandboxAttempt<'_>,
+        ctx: &ToolCtx<'_>,
+    ) -> Result<ExecToolCallOutput, ToolError> {
+        let timeout_ms = req.timeout_ms.unwrap_or(DEFAULT_EXEC_COMMAND_TIMEOUT_MS);
+        let fault = self.next_fault(attempt, ctx);
+        if let Some(result) = fault.and_then(|fault| injected_result(fault, timeout_ms)) {
+            return result;
+        }
+        let output = self.inner.run_apply_patch(req, attempt, ctx).await?;
+        Ok(apply_to_output(fault, output))
+    }
+}
+
+/// What a call given `fault` reports instead of running, if it does not run.
+fn injected_result(fault: Fault, timeout_ms: u64) -> Option<Result<ExecToolCallOutput, ToolError>> {
+    let err = match fault {
+        Fault::Timeout => CodexErr::Sandbox(SandboxErr::Timeout {
+            output: Box::new(ExecToolCallOutput {
+                exit_code: EXEC_TIMEOUT_EXIT_CODE,
+                duration: Duration::from_millis(timeout_ms),
+                exit_reason: ExitReason::TimedOut,
+                ..failed_output("")
+            }),
+        }),
+        Fault::SandboxDenied => CodexErr::Sandbox(SandboxErr::Denied {
+            output: Box::new(ExecToolCallOutput {
+                exit_reason: ExitReason::SandboxDenied,
+                ..failed_output("Operation not permitted\n")
+            }),
+        }),
+        Fault::SpawnFailure => {
+            let err = CodexErr::Io(io::Error::new(
+                io::ErrorKind::NotFound,
+                "No such file or directory (os error 2)",
+            ));
+            return Some(Ok(ExecToolCallOutput {
+                exit_code: -1,
+                exit_reason: ExitReason::SpawnFailure,
+                ..failed_output(&format!("execution error: {err:?}"))
+            }));
+        }
+        Fault::PartialOutput => return None,
+    };
+    Some(Err(ToolError::Codex(err)))
+}
+
+fn apply_to_output(fault: Option<Fault>, output: ExecToolCallOutput) -> ExecToolCallOutput {
+    match fault {
+        Some(Fault::PartialOutput) => cut_output(output),
+        _ => output,
+    }
+}
+
+fn failed_output(stderr: &str) -> ExecToolCallOutput {
+    ExecToolCallOutput {
+        exit_code: 1,
+        stdout: StreamOutput::new(String::new()),
+        stderr: StreamOutput::new(stderr.to_string()),
+        aggregated_output: StreamOutput::new(stderr.to_string()),
+        duration: Duration::ZERO,
+        full_output_path: None,
+        exit_reason: ExitReason::NonZeroExit,
+    }
+}
+
+/// `output` with only the first half of its stdout, stderr and combined
+/// output.
+fn cut_output(mut output: ExecToolCallOutput) -> ExecToolCallOutput {
+    for text in [
+        &mut output.stdout.text,
+        &mut output.stderr.text,
+        &mut output.aggregated_output.text,
+    ] {
+        let mut end = text.len() / 2;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        text.truncate(end);
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn rolls(plan: FaultPlan, sandboxed: bool) -> Vec<Option<Fault>> {
+        let mut dice = FaultDice::new(plan);
+        (0..64).map(|_| dice.roll(sandboxed).1).collect()
+    }
+
+    #[test]
+    fn plans_with_the_same_seed_inject_the_same_faults() {
+        let first = rolls(FaultPlan::new(42).rate(0.5), true);
+        assert_eq!(first, rolls(FaultPlan::new(42).rate(0.5), true));
+        assert!(first.iter().any(Option::is_some));
+        assert!(first.iter().any(Option::is_none));
+
+        let forced = rolls(
+            FaultPlan::new(42).rate(0.5).at(3, Some(Fault::Timeout)),
+            true,
+        );
+        assert_eq!(forced[3], Some(Fault::Timeout));
+        assert_eq!(forced[4..], first[4..]);
+
+        let unsandboxed = rolls(FaultPlan::new(42).rate(1.0), false);
+        assert!(!unsandboxed.contains(&Some(Fault::SandboxDenied)));
+        assert_eq!(rolls(FaultPlan::new(42).rate(0.0), true), vec![None; 64]);
+    }
+
+    #[test]
+    fn partial_output_keeps_the_first_half_on_a_char_boundary() {
+        let output = ExecToolCallOutput {
+            exit_code: 0,
+            exit_reason: ExitReason::Success,
+            stdout: StreamOutput::new("abcdef".to_string()),
+            aggregated_output: StreamOutput::new("aé".to_string()),
+            ..failed_output("")
+        };
+        let output = cut_output(output);
+        assert_eq!(output.stdout.text, "abc");
+        assert_eq!(output.aggregated_output.text, "a");
+    }
+}
@@ -1,27 +1,44 @@
 use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
 
 use crate::apply_patch;
 use crate::apply_patch::InternalApplyPatchInvocation;
 use crate::apply_patch::convert_apply_patch_to_protocol;
+use crate::apply_patch::deleted_paths;
+use crate::apply_patch::target_paths;
+use crate::apply_patch::verify_patch;
+use crate::blocking_io::run_blocking;
 use crate::client_common::tools::FreeformTool;
 use crate::client_common::tools::FreeformToolFormat;
 use crate::client_common::tools::ResponsesApiTool;
 use crate::client_common::tools::ToolSpec;
+use crate::codex::Session;
+use crate::codex::TurnContext;
+use crate::features::Feature;
 use crate::function_tool::FunctionCallError;
+use crate::tools::context::SharedTurnDiffTracker;
 use crate::tools::context::ToolInvocation;
 use crate::tools::context::ToolOutput;
 use crate::tools::context::ToolPayload;
 use crate::tools::events::ToolEmitter;
 use crate::tools::events::ToolEventCtx;
+use crate::tools::handlers::read_changes::record_served_version;
 use crate::tools::orchestrator::ToolOrchestrator;
 use crate::tools::registry::ToolHandler;
 use crate::tools::registry::ToolKind;
 use crate::tools::runtimes::apply_patch::ApplyPatchRequest;
 use crate::tools::runtimes::apply_patch::ApplyPatchRuntime;
 use crate::tools::sandboxing::ToolCtx;
+use crate::tools::sandboxing::ToolError;
 use crate::tools::spec::ApplyPatchToolArgs;
 use crate::tools::spec::JsonSchema;
 use async_trait::async_trait;
+use codex_apply_patch::ApplyPatchAction;
+use codex_apply_patch::Hunk;
+use codex_apply_patch::MergedPatch;
 use serde::Deserialize;
 use serde::Serialize;
 
@@ -56,16 +73,16 @@ impl ToolHandler for ApplyPatchHandler {
             payload,
         } = invocation;
 
-        let patch_input = match payload {
+        let (patch_input, dry_run) = match payload {
             ToolPayload::Function { arguments } => {
                 let args: ApplyPatchToolArgs = serde_json::from_str(&arguments).map_err(|e| {
                     FunctionCallError::RespondToModel(format!(
                         "failed to parse function arguments: {e:?}"
                     ))
                 })?;
-                args.input
+                (args.input, args.dry_run)
             }
-            ToolPayload::Custom { input } => input,
+            ToolPayload::Custom { input } => (input, false),
             _ => {
                 return Err(FunctionCallError::RespondToModel(
                     "apply_patch handler received unsupported payload".to_string(),
@@ -73,88 +90,258 @@ impl ToolHandler for ApplyPatchHandler {
             }
         };
 
-        // Re-parse and verify the patch so we can compute changes and approval.
-        // Avoid building temporary ExecParams/command vectors; derive directly from inputs.
-        let cwd = turn.cwd.clone();
-        let command = vec!["apply_patch".to_string(), patch_input.clone()];
-        match codex_apply_patch::maybe_parse_apply_patch_verified(&command, &cwd) {
-            codex_apply_patch::MaybeApplyPatchVerified::Body(changes) => {
-                match apply_patch::apply_patch(session.as_ref(), turn.as_ref(), &call_id, changes)
-                    .await
-                {
-                    InternalApplyPatchInvocation::Output(item) => {
-                        let content = item?;
-                        Ok(ToolOutput::Function {
-                            content,
-                            content_items: None,
-                            success: Some(true),
-                        })
-                    }
-                    InternalApplyPatchInvocation::DelegateToExec(apply) => {
-                        let emitter = ToolEmitter::apply_patch(
-                            convert_apply_patch_to_protocol(&apply.action),
-                            !apply.user_explicitly_approved_this_action,
-                        );
-                        let event_ctx = ToolEventCtx::new(
-                            session.as_ref(),
-                            turn.as_ref(),
-                            &call_id,
-                            Some(&tracker),
-                        );
-                        emitter.begin(event_ctx).await;
-
-                        let req = ApplyPatchRequest {
-                            patch: apply.action.patch.clone(),
-                            cwd: apply.action.cwd.clone(),
-                            timeout_ms: None,
-                            user_explicitly_approved: apply.user_explicitly_approved_this_action,
-                            codex_exe: turn.codex_linux_sandbox_exe.clone(),
-                        };
-
-                        let mut orchestrator = ToolOrchestrator::new();
-                        let mut runtime = ApplyPatchRuntime::new();
-                        let tool_ctx = ToolCtx {
-                            session: session.as_ref(),
-                            turn: turn.as_ref(),
-                            call_id: call_id.clone(),
-                            tool_name: tool_name.to_string(),
-                        };
-                        let out = orchestrator
-                            .run(&mut runtime, &req, &tool_ctx, &turn, turn.approval_policy)
-                            .await;
-                        let event_ctx = ToolEventCtx::new(
-                            session.as_ref(),
-                            turn.as_ref(),
-                            &call_id,
-                            Some(&tracker),
-                        );
-                        let content = emitter.finish(event_ctx, out).await?;
-                        Ok(ToolOutput::Function {
-                            content,
-                            content_items: None,
-                            success: Some(true),
-                        })
-                    }
+        if dry_run {
+            return dry_run_patch_input(&session, &turn, &call_id, &tool_name, patch_input).await;
+        }
+        apply_patch_input(&session, &turn, &tracker, &call_id, &tool_name, patch_input).await
+    }
+}
+
+/// Check `patch_input` without applying it: the reply lists the files the
+/// patch would change and its diff, or why it does not apply.
+async fn dry_run_patch_input(
+    session: &Arc<Session>,
+    turn: &Arc<TurnContext>,
+    call_id: &str,
+    tool_name: &str,
+    patch_input: String,
+) -> Result<ToolOutput, FunctionCallError> {
+    let req = ApplyPatchRequest {
+        patch: patch_input,
+        cwd: turn.cwd.clone(),
+        files: Vec::new(),
+        deleted: Vec::new(),
+        timeout_ms: None,
+        user_explicitly_approved: false,
+        codex_exe: turn.codex_linux_sandbox_exe.clone(),
+        dry_run: true,
+    };
+    let tool_ctx = ToolCtx {
+        session: session.as_ref(),
+        turn: turn.as_ref(),
+        call_id: call_id.to_string(),
+        tool_name: tool_name.to_string(),
+    };
+    let out = ToolOrchestrator::new()
+        .run(
+            &mut ApplyPatchRuntime::new(),
+            Arc::new(req),
+            &tool_ctx,
+            turn,
+            turn.approval_policy,
+        )
+        .await
+        .map_err(|err| {
+            let message = match err {
+                ToolError::Rejected(message) => message,
+                ToolError::Codex(err) => err.to_string(),
+            };
+            FunctionCallError::RespondToModel(format!("dry run failed: {message}"))
+        })?;
+    Ok(ToolOutput::Function {
+        content: out.aggregated_output.text,
+        content_items: None,
+        success: Some(out.exit_code == 0),
+    })
+}
+
+/// Verify `patch_input` and run it through the regular patch approval, diff
+/// and sandboxed-apply pipeline. Tools that synthesize patches (bulk
+/// find-and-replace, scaffolding, codemods) use this so their edits are
+/// reviewed exactly like a model-authored `apply_patch` call.
+pub(crate) async fn apply_patch_input(
+    session: &Arc<Session>,
+    turn: &Arc<TurnContext>,
+    tracker: &SharedTurnDiffTracker,
+    call_id: &str,
+    tool_name: &str,
+    patch_input: String,
+) -> Result<ToolOutput, FunctionCallError> {
+    // Re-parse and verify the patch so we can compute changes and approval.
+    // Avoid building temporary ExecParams/command vectors; derive directly from inputs.
+    let cwd = turn.cwd.clone();
+    let command = vec!["apply_patch".to_string(), patch_input.clone()];
+    match verify_patch(command, cwd.clone()).await {
+        codex_apply_patch::MaybeApplyPatchVerified::Body(changes) => {
+            apply_verified(session, turn, tracker, call_id, tool_name, changes).await
+        }
+        codex_apply_patch::MaybeApplyPatchVerified::CorrectnessError(parse_error) => {
+            let failure = format!("apply_patch verification failed: {parse_error}");
+            match merge_drifted(session.as_ref(), &cwd, &patch_input).await {
+                Some(MergedPatch::Clean { patch, merged }) => {
+                    let command = vec!["apply_patch".to_string(), patch];
+                    let codex_apply_patch::MaybeApplyPatchVerified::Body(changes) =
+                        verify_patch(command, cwd.clone()).await
+                    else {
+                        return Err(FunctionCallError::RespondToModel(failure));
+                    };
+                    let output =
+                        apply_verified(session, turn, tracker, call_id, tool_name, changes).await?;
+                    Ok(with_merge_note(output, &merged))
                 }
+                Some(MergedPatch::Conflicts(conflicts)) => Err(FunctionCallError::RespondToModel(
+                    conflict_report(&failure, &conflicts),
+                )),
+                None => Err(FunctionCallError::RespondToModel(failure)),
             }
-            codex_apply_patch::MaybeApplyPatchVerified::CorrectnessError(parse_error) => {
-                Err(FunctionCallError::RespondToModel(format!(
-                    "apply_patch verification failed: {parse_error}"
-                )))
-            }
-            codex_apply_patch::MaybeApplyPatchVerified::ShellParseError(error) => {
-                tracing::trace!("Failed to parse apply_patch input, {error:?}");
-                Err(FunctionCallError::RespondToModel(
-                    "apply_patch handler received invalid patch input".to_string(),
-                ))
-            }
-            codex_apply_patch::MaybeApplyPatchVerified::NotApplyPatch => {
-                Err(FunctionCallError::RespondToModel(
-                    "apply_patch handler received non-apply_patch input".to_string(),
-                ))
+        }
+        codex_apply_patch::MaybeApplyPatchVerified::ShellParseError(error) => {
+            tracing::trace!("Failed to parse apply_patch input, {error:?}");
+            Err(FunctionCallError::RespondToModel(
+                "apply_patch handler received invalid patch input".to_string(),
+            ))
+        }
+        codex_apply_patch::MaybeApplyPatchVerified::NotApplyPatch => {
+            Err(FunctionCallError::RespondToModel(
+                "apply_patch handler received non-apply_patch input".to_string(),
+            ))
+        }
+    }
+}
+
+/// Review and apply a verified patch.
+async fn apply_verified(
+    session: &Arc<Session>,
+    turn: &Arc<TurnContext>,
+    tracker: &SharedTurnDiffTracker,
+    call_id: &str,
+    tool_name: &str,
+    changes: ApplyPatchAction,
+) -> Result<ToolOutput, FunctionCallError> {
+    match apply_patch::apply_patch(session.as_ref(), turn.as_ref(), call_id, changes).await {
+        InternalApplyPatchInvocation::Output(item) => {
+            let content = item?;
+            Ok(ToolOutput::Function {
+                content,
+                content_items: None,
+                success: Some(true),
+            })
+        }
+        InternalApplyPatchInvocation::DelegateToExec(apply) => {
+            let emitter = ToolEmitter::apply_patch(
+                convert_apply_patch_to_protocol(&apply.action),
+                !apply.user_explicitly_approved_this_action,
+            );
+            let event_ctx =
+                ToolEventCtx::new(session.as_ref(), turn.as_ref(), call_id, Some(tracker));
+            emitter.begin(event_ctx).await;
+
+            let req = Arc::new(ApplyPatchRequest {
+                patch: apply.action.patch.clone(),
+                cwd: apply.action.cwd.clone(),
+                files: target_paths(&apply.action),
+                deleted: deleted_paths(&apply.action),
+                timeout_ms: None,
+                user_explicitly_approved: apply.user_explicitly_approved_this_action,
+                codex_exe: turn.codex_linux_sandbox_exe.clone(),
+                dry_run: false,
+            });
+
+            let mut orchestrator = ToolOrchestrator::new();
+            let mut runtime = ApplyPatchRuntime::new();
+            let tool_ctx = ToolCtx {
+                session: session.as_ref(),
+                turn: turn.as_ref(),
+                call_id: call_id.to_string(),
+                tool_name: tool_name.to_string(),
+            };
+            let out = orchestrator
+                .run(
+                    &mut runtime,
+                    Arc::clone(&req),
+                    &tool_ctx,
+                    turn,
+                    turn.approval_policy,
+                )
+                .await;
+            let event_ctx =
+                ToolEventCtx::new(session.as_ref(), turn.as_ref(), call_id, Some(tracker));
+            let content = emitter.finish(event_ctx, out).await?;
+            // The model knows what its own patch left in these files.
+            if session.enabled(Feature::PatchMerge).await {
+                for path in &req.files {
+                    record_served_version(session.as_ref(), path).await;
+                }
             }
+            let content = apply.with_review_note(content);
+            Ok(ToolOutput::Function {
+                content,
+                content_items: None,
+                success: Some(true),
+            })
+        }
+    }
+}
+
+/// With `patch_merge`, merge a patch whose context no longer matches with the
+/// changes made since the model last read the files it updates.
+async fn merge_drifted(session: &Session, cwd: &Path, patch: &str) -> Option<MergedPatch> {
+    if !session.enabled(Feature::PatchMerge).await {
+        return None;
+    }
+    let hunks = codex_apply_patch::parse_patch(patch).ok()?.hunks;
+    let mut bases = HashMap::new();
+    for hunk in hunks
+        .iter()
+        .filter(|hunk| matches!(hunk, Hunk::UpdateFile { .. }))
+    {
+        let path = hunk.resolve_path(cwd);
+        if let Some(base) = session.served_version(&path).await {
+            bases.insert(path, base);
         }
     }
+    let (cwd, patch) = (cwd.to_path_buf(), patch.to_string());
+    run_blocking(move || codex_apply_patch::merge_drifted_patch(&patch, &cwd, &bases))
+        .await
+        .ok()
+        .flatten()
+}
+
+fn with_merge_note(output: ToolOutput, merged: &[PathBuf]) -> ToolOutput {
+    let ToolOutput::Function {
+        content,
+        content_items,
+        success,
+    } = output
+    else {
+        return output;
+    };
+    let content = format!(
+        "{content}\nNote: {} changed after you last read it; your edits were merged with \
+         those changes.",
+        display_paths(merged)
+    );
+    ToolOutput::Function {
+        content,
+        content_items,
+        success,
+    }
+}
+
+/// Tell the model where its edits and the changes made since it read the
+/// files overlap, so that it can write a patch resolving them.
+fn conflict_report(failure: &str, conflicts: &[(PathBuf, String)]) -> String {
+    let paths: Vec<PathBuf> = conflicts.iter().map(|(path, _)| path.clone()).collect();
+    let mut report = format!(
+        "{failure}\n{} changed after you last read it, and your edits overlap with those \
+         changes; nothing was written. Below is the merge with conflict markers: your edits \
+         follow `<<<<<<< ours`, the version you read follows `|||||||` and the current contents \
+         follow `=======`. Write a new patch against the current contents that resolves the \
+         conflicts.",
+        display_paths(&paths)
+    );
+    for (path, marked) in conflicts {
+        report.push_str(&format!("\n\n{}\n{marked}", path.display()));
+    }
+    report
+}
+
+fn display_paths(paths: &[PathBuf]) -> String {
+    paths
+        .iter()
+        .map(|path| path.display().to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
@@ -187,6 +374,16 @@ pub(crate) fn create_apply_patch_json_tool() -> ToolSpec {
             description: Some(r#"The entire contents of the apply_patch command"#.to_string()),
         },
     );
+    properties.insert(
+        "dry_run".to_string(),
+        JsonSchema::Boolean {
+            description: Some(
+                "Only check that the patch applies and show the files and diff it would \
+                 produce, without writing anything."
+                    .to_string(),
+            ),
+        },
+    );
 
     ToolSpec::Function(ResponsesApiTool {
         name: "apply_patch".to_string(),
@@ -199,11 +396,12 @@ Your patch language is a stripped‑down, file‑oriented diff format designed t
 
 Within that envelope, you get a sequence of file operations.
 You MUST include a header to specify the action you are taking.
-Each operation starts with one of three headers:
+Each operation starts with one of four headers:
 
 *** Add File: <path> - create a new file. Every following line is a + line (the initial contents).
 *** Delete File: <path> - remove an existing file. Nothing follows.
 *** Update File: <path> - patch an existing file in place (optionally with a rename).
+*** Binary File: <path> - create or replace a binary file. Every following line is a + line of base64.
 
 May be immediately followed by *** Move to: <new path> if you want to rename the file.
 Then one or more “hunks”, each introduced by @@ (optionally followed by a hunk header).
@@ -231,9 +429,10 @@ The full grammar definition is below:
 Patch := Begin { FileOp } End
 Begin := "*** Begin Patch" NEWLINE
 End := "*** End Patch" NEWLINE
-FileOp := AddFile | DeleteFile | UpdateFile
+FileOp := AddFile | DeleteFile | UpdateFile | BinaryFile
 AddFile := "*** Add File: " path NEWLINE { "+" line NEWLINE }
 DeleteFile := "*** Delete File: " path NEWLINE
+BinaryFile := "*** Binary File: " path NEWLINE { "+" base64 NEWLINE }
 UpdateFile := "*** Update File: " path NEWLINE [ MoveTo ] { Hunk }
 MoveTo := "*** Move to: " newPath NEWLINE
 Hunk := "@@" [ header ] NEWLINE { HunkLine } [ "*** End of File" NEWLINE ]
@@ -0,0 +1,149 @@
+use async_trait::async_trait;
+use codex_protocol::protocol::ArtifactDirection;
+use codex_protocol::protocol::AskForApproval;
+use codex_protocol::protocol::ReviewDecision;
+use serde::Deserialize;
+
+use crate::artifacts::artifacts_dir;
+use crate::artifacts::store_artifact;
+use crate::function_tool::FunctionCallError;
+use crate::tools::context::ToolInvocation;
+use crate::tools::context::ToolOutput;
+use crate::tools::context::ToolPayload;
+use crate::tools::registry::ToolHandler;
+use crate::tools::registry::ToolKind;
+
+pub struct ArtifactsHandler;
+
+#[derive(Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+enum ArtifactsArgs {
+    /// List the artifacts exchanged so far in this session.
+    List,
+    /// Send a file to the user.
+    Send {
+        path: String,
+        #[serde(default)]
+        name: Option<String>,
+    },
+}
+
+#[async_trait]
+impl ToolHandler for ArtifactsHandler {
+    fn kind(&self) -> ToolKind {
+        ToolKind::Function
+    }
+
+    fn is_mutating(&self, invocation: &ToolInvocation) -> bool {
+        match &invocation.payload {
+            ToolPayload::Function { arguments } => !matches!(
+                serde_json::from_str::<ArtifactsArgs>(arguments),
+                Ok(ArtifactsArgs::List)
+            ),
+            _ => true,
+        }
+    }
+
+    async fn handle(&self, invocation: ToolInvocation) -> Result<ToolOutput, FunctionCallError> {
+        let ToolInvocation {
+            session,
+            turn,
+            call_id,
+            tool_name,
+            payload,
+            ..
+        } = invocation;
+
+        let ToolPayload::Function { arguments } = payload else {
+            return Err(FunctionCallError::RespondToModel(format!(
+                "unsupported payload for artifacts handler: {tool_name}"
+            )));
+        };
+
+        let args: ArtifactsArgs = serde_json::from_str(&arguments).map_err(|err| {
+            FunctionCallError::RespondToModel(format!(
+                "failed to parse function arguments: {err:?}"
+            ))
+        })?;
+
+        let content = match args {
+            ArtifactsArgs::List => {
+                serde_json::to_string(&session.artifacts().await).map_err(|err| {
+                    FunctionCallError::Fatal(format!("failed to serialize artifacts: {err}"))
+                })?
+            }
+            ArtifactsArgs::Send { path, name } => {
+                // Outbound transfers always need an explicit, per-artifact
+                // approval, so they are unavailable when we may not ask.
+                if turn.approval_policy == AskForApproval::Never {
+                    return Err(FunctionCallError::RespondToModel(
+                        "sending artifacts requires user approval, which the current approval \
+                         policy does not allow"
+                            .to_string(),
+                    ));
+                }
+                let path = turn.resolve_path(Some(path));
+                let size = tokio::fs::metadata(&path)
+                    .await
+                    .map(|metadata| metadata.len())
+                    .map_err(|err| {
+                        FunctionCallError::RespondToModel(format!(
+                            "cannot read {}: {err}",
+                            path.display()
+                        ))
+                    })?;
+                let config = turn.client.config();
+                if size > config.artifact_max_bytes {
+                    return Err(FunctionCallError::RespondToModel(format!(
+                        "{} is {size} bytes, over the {} byte artifact limit",
+                        path.display(),
+                        config.artifact_max_bytes
+                    )));
+                }
+
+                let decision = session
+                    .request_command_approval(
+                        turn.as_ref(),
+                        call_id,
+                        vec!["send-artifact".to_string(), path.display().to_string()],
+                        turn.cwd.clone(),
+                        Some(format!(
+                            "Send {} ({size} bytes) to you as an artifact",
+                            path.display()
+                        )),
+                        None,
+                    )
+                    .await;
+                match decision {
+                    ReviewDecision::Approved
+                    | ReviewDecision::ApprovedForSession
+                    | ReviewDecision::ApprovedPrefixForSession => {}
+                    ReviewDecision::Denied | ReviewDecision::Abort => {
+                        return Err(FunctionCallError::RespondToModel(
+                            "artifact transfer rejected by user".to_string(),
+                        ));
+                    }
+                }
+
+                let artifact = store_artifact(
+                    &artifacts_dir(&config.codex_home, session.conversation_id()),
+                    &path,
+                    name,
+                    ArtifactDirection::Outbound,
+                    config.artifact_max_bytes,
+                )
+                .await
+                .map_err(FunctionCallError::RespondToModel)?;
+                let content = format!("sent {} ({} bytes)", artifact.name, artifact.size_bytes);
+                session.record_artifact(&turn.sub_id, artifact).await;
+                content
+            }
+        };
+
+        Ok(ToolOutput::Function {
+            content,
+            content_items: None,
+            success: Some(true),
+        })
+    }
+}
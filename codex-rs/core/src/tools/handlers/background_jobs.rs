@@ -0,0 +1,156 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::background_jobs::BackgroundJob;
+use crate::background_jobs::JobStatus;
+use crate::background_jobs::JobSummary;
+use crate::codex::Session;
+use crate::function_tool::FunctionCallError;
+use crate::tools::context::ToolInvocation;
+use crate::tools::context::ToolOutput;
+use crate::tools::context::ToolPayload;
+use crate::tools::handlers::to_json;
+use crate::tools::registry::ToolHandler;
+use crate::tools::registry::ToolKind;
+use crate::truncate::TruncationPolicy;
+use crate::truncate::formatted_truncate_text;
+use crate::unified_exec::resolve_max_tokens;
+
+/// Handles `job_status`, `job_output` and `job_kill` for jobs the shell tool
+/// started with `background: true`.
+pub struct BackgroundJobsHandler;
+
+#[derive(Deserialize)]
+struct JobStatusArgs {
+    #[serde(default)]
+    job_id: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct JobOutputArgs {
+    job_id: u32,
+    #[serde(default)]
+    max_output_tokens: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct JobKillArgs {
+    job_id: u32,
+}
+
+#[derive(Serialize)]
+struct JobOutputResponse {
+    job_id: u32,
+    status: JobStatus,
+    output: String,
+    /// Output discarded because the job produced more than is buffered
+    /// between reads.
+    #[serde(skip_serializing_if = "is_zero")]
+    dropped_bytes: usize,
+}
+
+// serde hands `skip_serializing_if` predicates a reference.
+#[allow(clippy::trivially_copy_pass_by_ref)]
+fn is_zero(value: &usize) -> bool {
+    *value == 0
+}
+
+#[async_trait]
+impl ToolHandler for BackgroundJobsHandler {
+    fn kind(&self) -> ToolKind {
+        ToolKind::Function
+    }
+
+    fn is_mutating(&self, invocation: &ToolInvocation) -> bool {
+        invocation.tool_name == "job_kill"
+    }
+
+    async fn handle(&self, invocation: ToolInvocation) -> Result<ToolOutput, FunctionCallError> {
+        let ToolInvocation {
+            session,
+            tool_name,
+            payload,
+            ..
+        } = invocation;
+
+        let ToolPayload::Function { arguments } = payload else {
+            return Err(FunctionCallError::RespondToModel(format!(
+                "unsupported payload for background jobs handler: {tool_name}"
+            )));
+        };
+
+        let content = match tool_name.as_str() {
+            "job_status" => {
+                let args: JobStatusArgs = parse_args(&arguments)?;
+                match args.job_id {
+                    Some(job_id) => {
+                        to_json(&find_job(&session, job_id)?.summary(), "background job")?
+                    }
+                    None => {
+                        let jobs = session.services.background_jobs.list();
+                        let summaries: Vec<JobSummary> =
+                            jobs.iter().map(|job| job.summary()).collect();
+                        to_json(&summaries, "background job")?
+                    }
+                }
+            }
+            "job_output" => {
+                let args: JobOutputArgs = parse_args(&arguments)?;
+                let job = find_job(&session, args.job_id)?;
+                // Read the status first, so a job reported as exited has no
+                // output left to read.
+                let status = job.status();
+                let (output, dropped_bytes) = job.take_output();
+                let max_tokens = resolve_max_tokens(args.max_output_tokens);
+                to_json(
+                    &JobOutputResponse {
+                        job_id: job.id,
+                        status,
+                        output: formatted_truncate_text(
+                            &output,
+                            TruncationPolicy::Tokens(max_tokens),
+                        ),
+                        dropped_bytes,
+                    },
+                    "background job",
+                )?
+            }
+            "job_kill" => {
+                let args: JobKillArgs = parse_args(&arguments)?;
+                let job = find_job(&session, args.job_id)?;
+                if job.status() == JobStatus::Running {
+                    job.kill().await;
+                }
+                to_json(&job.summary(), "background job")?
+            }
+            _ => {
+                return Err(FunctionCallError::RespondToModel(format!(
+                    "unsupported background jobs tool: {tool_name}"
+                )));
+            }
+        };
+
+        Ok(ToolOutput::Function {
+            content,
+            content_items: None,
+            success: Some(true),
+        })
+    }
+}
+
+fn parse_args<'a, T: Deserialize<'a>>(arguments: &'a str) -> Result<T, FunctionCallError> {
+    serde_json::from_str(arguments).map_err(|err| {
+        FunctionCallError::RespondToModel(format!("failed to parse function arguments: {err:?}"))
+    })
+}
+
+fn find_job(session: &Session, job_id: u32) -> Result<Arc<BackgroundJob>, FunctionCallError> {
+    session.services.background_jobs.get(job_id).ok_or_else(|| {
+        FunctionCallError::RespondToModel(format!(
+            "no background job {job_id}; job_status lists the jobs in this session"
+        ))
+    })
+}
@@ -0,0 +1,254 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::exec_env::create_env;
+use crate::exec_policy::create_approval_requirement_for_command;
+use crate::function_tool::FunctionCallError;
+use crate::protocol::ExecCommandSource;
+use crate::sandboxing::SandboxPermissions;
+use crate::tools::context::ToolInvocation;
+use crate::tools::context::ToolOutput;
+use crate::tools::context::ToolPayload;
+use crate::tools::events::ToolEmitter;
+use crate::tools::events::ToolEventCtx;
+use crate::tools::events::ToolEventStage;
+use crate::tools::format_exec_output_str;
+use crate::tools::orchestrator::ToolOrchestrator;
+use crate::tools::registry::ToolHandler;
+use crate::tools::registry::ToolKind;
+use crate::tools::runtimes::bench::BENCH_DIR;
+use crate::tools::runtimes::bench::Baseline;
+use crate::tools::runtimes::bench::BenchComparison;
+use crate::tools::runtimes::bench::BenchRequest;
+use crate::tools::runtimes::bench::BenchRuntime;
+use crate::tools::runtimes::bench::BenchTool;
+use crate::tools::runtimes::bench::Measurement;
+use crate::tools::runtimes::bench::compare;
+use crate::tools::runtimes::bench::load_baseline;
+use crate::tools::runtimes::bench::save_baseline;
+use crate::tools::runtimes::bench::validate_baseline_name;
+use crate::tools::sandboxing::ToolCtx;
+
+pub struct BenchHandler;
+
+const DEFAULT_THRESHOLD_PCT: f64 = 5.0;
+
+fn default_threshold_pct() -> f64 {
+    DEFAULT_THRESHOLD_PCT
+}
+
+#[derive(Deserialize)]
+struct BenchArgs {
+    #[serde(default)]
+    tool: Option<BenchTool>,
+    #[serde(default)]
+    args: Vec<String>,
+    #[serde(default)]
+    workdir: Option<String>,
+    #[serde(default)]
+    timeout_ms: Option<u64>,
+    #[serde(default)]
+    save_baseline: Option<String>,
+    #[serde(default)]
+    compare_to: Option<String>,
+    #[serde(default = "default_threshold_pct")]
+    threshold_pct: f64,
+}
+
+#[derive(Serialize)]
+struct BenchResponse {
+    tool: BenchTool,
+    exit_code: i32,
+    measurements: Vec<Measurement>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    saved_baseline: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comparison: Option<BenchComparison>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    output: Option<String>,
+}
+
+#[async_trait]
+impl ToolHandler for BenchHandler {
+    fn kind(&self) -> ToolKind {
+        ToolKind::Function
+    }
+
+    fn is_mutating(&self, _invocation: &ToolInvocation) -> bool {
+        true
+    }
+
+    async fn handle(&self, invocation: ToolInvocation) -> Result<ToolOutput, FunctionCallError> {
+        let ToolInvocation {
+            session,
+            turn,
+            call_id,
+            tool_name,
+            payload,
+            ..
+        } = invocation;
+
+        let ToolPayload::Function { arguments } = payload else {
+            return Err(FunctionCallError::RespondToModel(format!(
+                "unsupported payload for bench handler: {tool_name}"
+            )));
+        };
+
+        let args: BenchArgs = serde_json::from_str(&arguments).map_err(|err| {
+            FunctionCallError::RespondToModel(format!(
+                "failed to parse function arguments: {err:?}"
+            ))
+        })?;
+
+        for name in args.save_baseline.iter().chain(args.compare_to.iter()) {
+            validate_baseline_name(name).map_err(FunctionCallError::RespondToModel)?;
+        }
+
+        let cwd = turn.resolve_path(args.workdir.clone());
+        // Load the comparison baseline up front so a typo fails fast instead
+        // of after a long benchmark run.
+        let baseline = match &args.compare_to {
+            Some(name) => Some(load_baseline(&cwd, name).await.map_err(|err| {
+                FunctionCallError::RespondToModel(format!(
+                    "failed to load baseline `{name}` from {BENCH_DIR}: {err}"
+                ))
+            })?),
+            None => None,
+        };
+
+        let tool = match args.tool {
+            Some(tool) => tool,
+            None if cwd.join("Cargo.toml").is_file() => BenchTool::Criterion,
+            None => {
+                return Err(FunctionCallError::RespondToModel(
+                    "could not detect a benchmark tool; pass `tool` explicitly".to_string(),
+                ));
+            }
+        };
+        if tool == BenchTool::Hyperfine && args.args.is_empty() {
+            return Err(FunctionCallError::RespondToModel(
+                "hyperfine needs at least one command in `args`".to_string(),
+            ));
+        }
+
+        let export_path = cwd
+            .join(BENCH_DIR)
+            .join("runs")
+            .join(format!("{call_id}.json"));
+        let script = tool.script(&args.args, &export_path);
+        let command = session
+            .user_shell()
+            .script_shell()
+            .derive_exec_args(&script, true);
+
+        let emitter = ToolEmitter::shell(
+            command.clone(),
+            cwd.clone(),
+            ExecCommandSource::Agent,
+            false,
+        );
+        let event_ctx = ToolEventCtx::new(session.as_ref(), turn.as_ref(), &call_id, None);
+        emitter.begin(event_ctx).await;
+
+        let req = BenchRequest {
+            tool,
+            approval_requirement: create_approval_requirement_for_command(
+                &turn.exec_policy,
+                &command,
+                turn.approval_policy,
+                &turn.sandbox_policy,
+                SandboxPermissions::from(false),
+            ),
+            command,
+            cwd: cwd.clone(),
+            export_path,
+            timeout_ms: args.timeout_ms,
+            env: create_env(&turn.shell_environment_policy),
+        };
+        let mut orchestrator = ToolOrchestrator::new();
+        let mut runtime = BenchRuntime::new();
+        let tool_ctx = ToolCtx {
+            session: session.as_ref(),
+            turn: turn.as_ref(),
+            call_id: call_id.clone(),
+            tool_name: tool_name.to_string(),
+        };
+        let out = orchestrator
+            .run(
+                &mut runtime,
+                Arc::new(req),
+                &tool_ctx,
+                &turn,
+                turn.approval_policy,
+            )
+            .await;
+
+        let event_ctx = ToolEventCtx::new(session.as_ref(), turn.as_ref(), &call_id, None);
+        let run = match out {
+            Ok(run) => run,
+            Err(err) => {
+                return emitter.finish(event_ctx, Err(err)).await.map(|content| {
+                    ToolOutput::Function {
+                        content,
+                        content_items: None,
+                        success: Some(false),
+                    }
+                });
+            }
+        };
+        emitter
+            .emit(event_ctx, ToolEventStage::Success(run.output.clone()))
+            .await;
+
+        let exit_code = run.output.exit_code;
+        let succeeded = exit_code == 0 && !run.measurements.is_empty();
+
+        let saved_baseline = match &args.save_baseline {
+            Some(name) if succeeded => {
+                let baseline = Baseline {
+                    name: name.clone(),
+                    tool,
+                    created_at: chrono::Utc::now().to_rfc3339(),
+                    measurements: run.measurements.clone(),
+                };
+                Some(save_baseline(&cwd, &baseline).await.map_err(|err| {
+                    FunctionCallError::RespondToModel(format!(
+                        "benchmarks ran but saving baseline `{name}` failed: {err}"
+                    ))
+                })?)
+            }
+            _ => None,
+        };
+
+        let comparison = baseline.map(|baseline| {
+            compare(
+                &baseline.measurements,
+                &run.measurements,
+                args.threshold_pct,
+            )
+        });
+
+        let response = BenchResponse {
+            tool,
+            exit_code,
+            measurements: run.measurements,
+            saved_baseline,
+            comparison,
+            output: (!succeeded)
+                .then(|| format_exec_output_str(&run.output, turn.truncation_policy)),
+        };
+        let content = serde_json::to_string(&response).map_err(|err| {
+            FunctionCallError::Fatal(format!("failed to serialize benchmark report: {err}"))
+        })?;
+
+        Ok(ToolOutput::Function {
+            content,
+            content_items: None,
+            success: Some(succeeded),
+        })
+    }
+}
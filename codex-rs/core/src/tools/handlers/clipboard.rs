@@ -0,0 +1,245 @@
+use async_trait::async_trait;
+use codex_protocol::protocol::AskForApproval;
+use codex_protocol::protocol::ReviewDecision;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::function_tool::FunctionCallError;
+use crate::tools::context::ToolInvocation;
+use crate::tools::context::ToolOutput;
+use crate::tools::context::ToolPayload;
+use crate::tools::registry::ToolHandler;
+use crate::tools::registry::ToolKind;
+use crate::tools::sandboxing::with_cached_approval;
+use crate::truncate::formatted_truncate_text;
+
+pub struct ClipboardHandler;
+
+/// Characters of the text being copied shown in the approval prompt.
+const WRITE_PREVIEW_CHARS: usize = 200;
+
+#[derive(Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+enum ClipboardArgs {
+    Read,
+    Write { text: String },
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum ClipboardAccess {
+    Read,
+    Write,
+}
+
+/// Reads and writes are approved separately, so allowing the agent to fill the
+/// clipboard for the session does not let it read what the user copies.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+struct ApprovalKey {
+    clipboard: ClipboardAccess,
+}
+
+#[async_trait]
+impl ToolHandler for ClipboardHandler {
+    fn kind(&self) -> ToolKind {
+        ToolKind::Function
+    }
+
+    fn is_mutating(&self, invocation: &ToolInvocation) -> bool {
+        match &invocation.payload {
+            ToolPayload::Function { arguments } => !matches!(
+                serde_json::from_str::<ClipboardArgs>(arguments),
+                Ok(ClipboardArgs::Read)
+            ),
+            _ => true,
+        }
+    }
+
+    async fn handle(&self, invocation: ToolInvocation) -> Result<ToolOutput, FunctionCallError> {
+        let ToolInvocation {
+            session,
+            turn,
+            call_id,
+            tool_name,
+            payload,
+            ..
+        } = invocation;
+
+        let ToolPayload::Function { arguments } = payload else {
+            return Err(FunctionCallError::RespondToModel(format!(
+                "unsupported payload for clipboard handler: {tool_name}"
+            )));
+        };
+
+        let args: ClipboardArgs = serde_json::from_str(&arguments).map_err(|err| {
+            FunctionCallError::RespondToModel(format!(
+                "failed to parse function arguments: {err:?}"
+            ))
+        })?;
+        // The clipboard lives outside the sandbox, so every access is approved
+        // by the user; there is no way to do that under `never`.
+        if turn.approval_policy == AskForApproval::Never {
+            return Err(FunctionCallError::RespondToModel(
+                "clipboard access requires user approval, which the current approval policy \
+                 does not allow"
+                    .to_string(),
+            ));
+        }
+
+        let (access, reason) = match &args {
+            ClipboardArgs::Read => (
+                ClipboardAccess::Read,
+                "Read the contents of your clipboard".to_string(),
+            ),
+            ClipboardArgs::Write { text } => (
+                ClipboardAccess::Write,
+                format!(
+                    "Copy {} characters to your clipboard:\n{}",
+                    text.chars().count(),
+                    preview(text)
+                ),
+            ),
+        };
+        let command = vec![
+            "clipboard".to_string(),
+            match access {
+                ClipboardAccess::Read => "read",
+                ClipboardAccess::Write => "write",
+            }
+            .to_string(),
+        ];
+        let decision = with_cached_approval(
+            &session.services,
+            turn.as_ref(),
+            ApprovalKey { clipboard: access },
+            || {
+                session.request_command_approval(
+                    turn.as_ref(),
+                    call_id,
+                    command,
+                    turn.cwd.clone(),
+                    Some(reason),
+                    None,
+                )
+            },
+        )
+        .await;
+        if matches!(decision, ReviewDecision::Denied | ReviewDecision::Abort) {
+            return Err(FunctionCallError::RespondToModel(
+                "clipboard access rejected by user".to_string(),
+            ));
+        }
+
+        let content = match args {
+            ClipboardArgs::Read => {
+                let text = tokio::task::spawn_blocking(system::read_text)
+                    .await
+                    .map_err(|err| FunctionCallError::Fatal(err.to_string()))?
+                    .map_err(FunctionCallError::RespondToModel)?;
+                formatted_truncate_text(&text, turn.truncation_policy)
+            }
+            ClipboardArgs::Write { text } => {
+                let chars = text.chars().count();
+                tokio::task::spawn_blocking(move || system::write_text(text))
+                    .await
+                    .map_err(|err| FunctionCallError::Fatal(err.to_string()))?
+                    .map_err(FunctionCallError::RespondToModel)?;
+                format!("copied {chars} characters to the clipboard")
+            }
+        };
+
+        Ok(ToolOutput::Function {
+            content,
+            content_items: None,
+            success: Some(true),
+        })
+    }
+}
+
+fn preview(text: &str) -> String {
+    let mut preview: String = text.chars().take(WRITE_PREVIEW_CHARS).collect();
+    if text.chars().nth(WRITE_PREVIEW_CHARS).is_some() {
+        preview.push('…');
+    }
+    preview
+}
+
+#[cfg(not(target_os = "android"))]
+mod system {
+    use std::sync::Mutex;
+    use std::sync::OnceLock;
+
+    /// On X11 and Wayland the process that set the clipboard has to keep
+    /// serving it, so a single handle is kept alive for the life of the
+    /// process instead of being dropped after each write.
+    fn clipboard() -> Result<&'static Mutex<arboard::Clipboard>, String> {
+        static CLIPBOARD: OnceLock<Mutex<arboard::Clipboard>> = OnceLock::new();
+        if let Some(clipboard) = CLIPBOARD.get() {
+            return Ok(clipboard);
+        }
+        let clipboard =
+            arboard::Clipboard::new().map_err(|err| format!("clipboard unavailable: {err}"))?;
+        Ok(CLIPBOARD.get_or_init(|| Mutex::new(clipboard)))
+    }
+
+    pub(super) fn read_text() -> Result<String, String> {
+        let mut clipboard = clipboard()?
+            .lock()
+            .map_err(|_| "clipboard lock poisoned".to_string())?;
+        clipboard
+            .get_text()
+            .map_err(|err| format!("failed to read the clipboard: {err}"))
+    }
+
+    pub(super) fn write_text(text: String) -> Result<(), String> {
+        let mut clipboard = clipboard()?
+            .lock()
+            .map_err(|_| "clipboard lock poisoned".to_string())?;
+        clipboard
+            .set_text(text)
+            .map_err(|err| format!("failed to write the clipboard: {err}"))
+    }
+}
+
+/// Android/Termux does not support arboard; return a clear error.
+#[cfg(target_os = "android")]
+mod system {
+    pub(super) fn read_text() -> Result<String, String> {
+        Err("clipboard access is unsupported on Android".to_string())
+    }
+
+    pub(super) fn write_text(_text: String) -> Result<(), String> {
+        Err("clipboard access is unsupported on Android".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn previews_are_bounded() {
+        assert_eq!(preview("short"), "short");
+        let long = "x".repeat(WRITE_PREVIEW_CHARS + 1);
+        assert_eq!(
+            preview(&long),
+            format!("{}…", "x".repeat(WRITE_PREVIEW_CHARS))
+        );
+    }
+
+    #[test]
+    fn read_and_write_are_approved_separately() {
+        assert_eq!(
+            serde_json::to_string(&ApprovalKey {
+                clipboard: ClipboardAccess::Read
+            })
+            .expect("serialize"),
+            r#"{"clipboard":"read"}"#
+        );
+        assert!(matches!(
+            serde_json::from_str::<ClipboardArgs>(r#"{"action":"write","text":"hi"}"#),
+            Ok(ClipboardArgs::Write { text }) if text == "hi"
+        ));
+    }
+}
@@ -0,0 +1,524 @@
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tokio::time::timeout;
+
+use crate::function_tool::FunctionCallError;
+use crate::tools::context::ToolInvocation;
+use crate::tools::context::ToolOutput;
+use crate::tools::context::ToolPayload;
+use crate::tools::handlers::apply_patch::apply_patch_input;
+use crate::tools::patch_builder::PatchBuilder;
+use crate::tools::registry::ToolHandler;
+use crate::tools::registry::ToolKind;
+
+pub struct CodemodHandler;
+
+const MAX_CHANGED_FILES: usize = 500;
+const ENGINE_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum Engine {
+    AstGrep,
+    Comby,
+}
+
+#[derive(Deserialize)]
+struct CodemodRule {
+    pattern: String,
+    rewrite: String,
+    /// ast-grep language name (`rust`, `typescript`, ...) or, for comby, the
+    /// file extension used to pick a matcher (`.go`, `.py`, ...).
+    language: String,
+}
+
+#[derive(Deserialize)]
+struct CodemodArgs {
+    engine: Engine,
+    rules: Vec<CodemodRule>,
+    /// Files or directories to rewrite, relative to the turn cwd.
+    #[serde(default)]
+    paths: Vec<String>,
+}
+
+/// A single ast-grep match as printed by `--json`.
+#[derive(Deserialize, Debug, PartialEq)]
+struct AstGrepMatch {
+    file: String,
+    #[serde(default)]
+    replacement: Option<String>,
+    range: AstGrepRange,
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+struct AstGrepRange {
+    #[serde(rename = "byteOffset")]
+    byte_offset: ByteOffset,
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+struct ByteOffset {
+    start: usize,
+    end: usize,
+}
+
+/// One line of comby's `-json-lines` output.
+#[derive(Deserialize)]
+struct CombyResult {
+    #[serde(default)]
+    uri: Option<String>,
+    #[serde(default)]
+    rewritten_source: Option<String>,
+}
+
+impl Engine {
+    fn program(self) -> &'static str {
+        match self {
+            Engine::AstGrep => "ast-grep",
+            Engine::Comby => "comby",
+        }
+    }
+
+    /// Arguments that list matches of `rule` on disk without rewriting anything.
+    /// comby always scans the whole cwd; its results are filtered by `paths`
+    /// afterwards.
+    fn scan_args(self, rule: &CodemodRule, paths: &[String]) -> Vec<String> {
+        match self {
+            Engine::AstGrep => {
+                let mut args = vec![
+                    "run".to_string(),
+                    "--pattern".to_string(),
+                    rule.pattern.clone(),
+                    "--lang".to_string(),
+                    rule.language.clone(),
+                    "--json=compact".to_string(),
+                    "--".to_string(),
+                ];
+                if paths.is_empty() {
+                    args.push(".".to_string());
+                } else {
+                    args.extend(paths.iter().cloned());
+                }
+                args
+            }
+            Engine::Comby => vec![
+                rule.pattern.clone(),
+                String::new(),
+                rule.language.clone(),
+                "-match-only".to_string(),
+                "-json-lines".to_string(),
+            ],
+        }
+    }
+
+    /// Arguments that rewrite a single file fed on stdin.
+    fn rewrite_args(self, rule: &CodemodRule) -> Vec<String> {
+        match self {
+            Engine::AstGrep => vec![
+                "run".to_string(),
+                "--pattern".to_string(),
+                rule.pattern.clone(),
+                "--rewrite".to_string(),
+                rule.rewrite.clone(),
+                "--lang".to_string(),
+                rule.language.clone(),
+                "--stdin".to_string(),
+                "--json=compact".to_string(),
+            ],
+            Engine::Comby => vec![
+                rule.pattern.clone(),
+                rule.rewrite.clone(),
+                "-stdin".to_string(),
+                "-json-lines".to_string(),
+                "-matcher".to_string(),
+                rule.language.clone(),
+            ],
+        }
+    }
+}
+
+#[async_trait]
+impl ToolHandler for CodemodHandler {
+    fn kind(&self) -> ToolKind {
+        ToolKind::Function
+    }
+
+    fn is_mutating(&self, _invocation: &ToolInvocation) -> bool {
+        true
+    }
+
+    async fn handle(&self, invocation: ToolInvocation) -> Result<ToolOutput, FunctionCallError> {
+        let ToolInvocation {
+            session,
+            turn,
+            tracker,
+            call_id,
+            tool_name,
+            payload,
+        } = invocation;
+
+        let ToolPayload::Function { arguments } = payload else {
+            return Err(FunctionCallError::RespondToModel(format!(
+                "unsupported payload for codemod handler: {tool_name}"
+            )));
+        };
+
+        let args: CodemodArgs = serde_json::from_str(&arguments).map_err(|err| {
+            FunctionCallError::RespondToModel(format!(
+                "failed to parse function arguments: {err:?}"
+            ))
+        })?;
+        if args.rules.is_empty() {
+            return Err(FunctionCallError::RespondToModel(
+                "`rules` must contain at least one rule".to_string(),
+            ));
+        }
+
+        // Find the files each rule matches on disk, then rewrite every file by
+        // running its matching rules in order, so later rules see the output of
+        // earlier ones.
+        let mut matched_by_rule = Vec::with_capacity(args.rules.len());
+        for rule in &args.rules {
+            let stdout = run_engine(
+                args.engine,
+                &args.engine.scan_args(rule, &args.paths),
+                &turn.cwd,
+                None,
+            )
+            .await?;
+            let matched = parse_scan(args.engine, &stdout, &turn.cwd).map_err(|err| {
+                FunctionCallError::RespondToModel(format!(
+                    "failed to parse {} output: {err}",
+                    args.engine.program()
+                ))
+            })?;
+            matched_by_rule.push(
+                matched
+                    .into_iter()
+                    .filter(|path| within_paths(path, &args.paths))
+                    .collect::<BTreeSet<_>>(),
+            );
+        }
+        let files: BTreeSet<&PathBuf> = matched_by_rule.iter().flatten().collect();
+        if files.len() > MAX_CHANGED_FILES {
+            return Err(FunctionCallError::RespondToModel(format!(
+                "more than {MAX_CHANGED_FILES} files match; narrow `paths` or the patterns"
+            )));
+        }
+
+        let mut builder = PatchBuilder::new();
+        for file in files {
+            let Ok(old) = tokio::fs::read_to_string(turn.cwd.join(file)).await else {
+                continue;
+            };
+            let mut new = old.clone();
+            for (rule, matched) in args.rules.iter().zip(&matched_by_rule) {
+                if !matched.contains(file) {
+                    continue;
+                }
+                let stdout = run_engine(
+                    args.engine,
+                    &args.engine.rewrite_args(rule),
+                    &turn.cwd,
+                    Some(&new),
+                )
+                .await?;
+                new = apply_rewrite(args.engine, &new, &stdout).map_err(|err| {
+                    FunctionCallError::RespondToModel(format!(
+                        "failed to rewrite {}: {err}",
+                        file.display()
+                    ))
+                })?;
+            }
+            builder.update_file(file, &old, &new);
+        }
+
+        if builder.is_empty() {
+            return Ok(ToolOutput::Function {
+                content: "no matches; nothing to change".to_string(),
+                content_items: None,
+                success: Some(true),
+            });
+        }
+
+        let summary = format!(
+            "{} rule(s) changed {} file(s)",
+            args.rules.len(),
+            builder.file_count()
+        );
+        let output = apply_patch_input(
+            &session,
+            &turn,
+            &tracker,
+            &call_id,
+            &tool_name,
+            builder.build(),
+        )
+        .await?;
+        Ok(match output {
+            ToolOutput::Function {
+                content,
+                content_items,
+                success,
+            } => ToolOutput::Function {
+                content: format!("{summary}\n{content}"),
+                content_items,
+                success,
+            },
+            other => other,
+        })
+    }
+}
+
+async fn run_engine(
+    engine: Engine,
+    args: &[String],
+    cwd: &Path,
+    stdin: Option<&str>,
+) -> Result<String, FunctionCallError> {
+    let program = engine.program();
+    let mut command = Command::new(program);
+    command
+        .args(args)
+        .current_dir(cwd)
+        .stdin(if stdin.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    let mut child = command.spawn().map_err(|err| {
+        FunctionCallError::RespondToModel(format!(
+            "failed to launch {program}: {err}. Ensure it is installed and on PATH."
+        ))
+    })?;
+    if let (Some(input), Some(mut pipe)) = (stdin, child.stdin.take()) {
+        pipe.write_all(input.as_bytes()).await.map_err(|err| {
+            FunctionCallError::RespondToModel(format!("failed to write to {program}: {err}"))
+        })?;
+    }
+
+    let output = timeout(ENGINE_TIMEOUT, child.wait_with_output())
+        .await
+        .map_err(|_| FunctionCallError::RespondToModel(format!("{program} timed out")))?
+        .map_err(|err| FunctionCallError::RespondToModel(format!("{program} failed: {err}")))?;
+    if !output.status.success() && output.stdout.is_empty() {
+        return Err(FunctionCallError::RespondToModel(format!(
+            "{program} failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Files with at least one match, relative to `cwd`.
+fn parse_scan(engine: Engine, stdout: &str, cwd: &Path) -> Result<Vec<PathBuf>, String> {
+    let files: Vec<String> = match engine {
+        Engine::AstGrep => parse_ast_grep(stdout)?
+            .into_iter()
+            .map(|m| m.file)
+            .collect(),
+        Engine::Comby => parse_comby(stdout)?
+            .into_iter()
+            .filter_map(|result| result.uri)
+            .collect(),
+    };
+    Ok(files
+        .into_iter()
+        .map(|file| {
+            let path = PathBuf::from(file);
+            match path.strip_prefix(cwd) {
+                Ok(relative) => relative.to_path_buf(),
+                Err(_) => path,
+            }
+        })
+        .collect())
+}
+
+/// Apply the rewrite reported by the engine for `source`. Returns `source`
+/// unchanged when nothing matched.
+fn apply_rewrite(engine: Engine, source: &str, stdout: &str) -> Result<String, String> {
+    match engine {
+        Engine::AstGrep => {
+            let mut matches = parse_ast_grep(stdout)?;
+            matches.sort_by_key(|m| m.range.byte_offset.start);
+            let mut rewritten = String::with_capacity(source.len());
+            let mut cursor = 0;
+            for m in matches {
+                let ByteOffset { start, end } = m.range.byte_offset;
+                // Overlapping matches (e.g. nested expressions) keep the outer rewrite.
+                if start < cursor {
+                    continue;
+                }
+                let Some(replacement) = m.replacement else {
+                    continue;
+                };
+                let (Some(before), Some(_)) = (source.get(cursor..start), source.get(start..end))
+                else {
+                    return Err(format!("match range {start}..{end} is out of bounds"));
+                };
+                rewritten.push_str(before);
+                rewritten.push_str(&replacement);
+                cursor = end;
+            }
+            rewritten.push_str(&source[cursor..]);
+            Ok(rewritten)
+        }
+        Engine::Comby => Ok(parse_comby(stdout)?
+            .into_iter()
+            .filter_map(|result| result.rewritten_source)
+            .next_back()
+            .unwrap_or_else(|| source.to_string())),
+    }
+}
+
+fn parse_ast_grep(stdout: &str) -> Result<Vec<AstGrepMatch>, String> {
+    if stdout.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    serde_json::from_str(stdout).map_err(|err| err.to_string())
+}
+
+fn parse_comby(stdout: &str) -> Result<Vec<CombyResult>, String> {
+    stdout
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| serde_json::from_str(line).map_err(|err| err.to_string()))
+        .collect()
+}
+
+fn within_paths(path: &Path, paths: &[String]) -> bool {
+    paths.is_empty() || paths.iter().any(|prefix| path.starts_with(prefix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn rule() -> CodemodRule {
+        CodemodRule {
+            pattern: "foo($A)".to_string(),
+            rewrite: "bar($A)".to_string(),
+            language: "rust".to_string(),
+        }
+    }
+
+    #[test]
+    fn ast_grep_scan_lists_files_relative_to_cwd() {
+        let stdout = r#"[
+            {"file":"/repo/src/a.rs","range":{"byteOffset":{"start":0,"end":6}}},
+            {"file":"src/b.rs","range":{"byteOffset":{"start":3,"end":9}}}
+        ]"#;
+        assert_eq!(
+            parse_scan(Engine::AstGrep, stdout, Path::new("/repo")),
+            Ok(vec![PathBuf::from("src/a.rs"), PathBuf::from("src/b.rs")])
+        );
+        assert_eq!(
+            parse_scan(Engine::AstGrep, "", Path::new("/repo")),
+            Ok(Vec::new())
+        );
+    }
+
+    #[test]
+    fn comby_scan_reads_uris_from_json_lines() {
+        let stdout = "{\"uri\":\"lib.go\",\"matches\":[]}\n{\"uri\":\"/repo/cmd/main.go\"}\n";
+        assert_eq!(
+            parse_scan(Engine::Comby, stdout, Path::new("/repo")),
+            Ok(vec![PathBuf::from("lib.go"), PathBuf::from("cmd/main.go")])
+        );
+    }
+
+    #[test]
+    fn ast_grep_rewrites_apply_in_order_and_skip_overlaps() {
+        let source = "foo(1); foo(foo(2));";
+        let stdout = r#"[
+            {"file":"STDIN","replacement":"bar(foo(2))","range":{"byteOffset":{"start":8,"end":19}}},
+            {"file":"STDIN","replacement":"bar(1)","range":{"byteOffset":{"start":0,"end":6}}},
+            {"file":"STDIN","replacement":"bar(2)","range":{"byteOffset":{"start":12,"end":18}}}
+        ]"#;
+        assert_eq!(
+            apply_rewrite(Engine::AstGrep, source, stdout),
+            Ok("bar(1); bar(foo(2));".to_string())
+        );
+        assert_eq!(
+            apply_rewrite(Engine::AstGrep, source, "[]"),
+            Ok(source.to_string())
+        );
+    }
+
+    #[test]
+    fn ast_grep_rejects_out_of_bounds_ranges() {
+        let stdout =
+            r#"[{"file":"STDIN","replacement":"x","range":{"byteOffset":{"start":2,"end":99}}}]"#;
+        assert_eq!(
+            apply_rewrite(Engine::AstGrep, "abc", stdout),
+            Err("match range 2..99 is out of bounds".to_string())
+        );
+    }
+
+    #[test]
+    fn comby_rewrite_uses_rewritten_source() {
+        let stdout = "{\"uri\":null,\"rewritten_source\":\"bar(x)\\n\",\"diff\":\"\"}\n";
+        assert_eq!(
+            apply_rewrite(Engine::Comby, "foo(x)\n", stdout),
+            Ok("bar(x)\n".to_string())
+        );
+        assert_eq!(
+            apply_rewrite(Engine::Comby, "foo(x)\n", ""),
+            Ok("foo(x)\n".to_string())
+        );
+    }
+
+    #[test]
+    fn engine_arguments() {
+        assert_eq!(
+            Engine::AstGrep.rewrite_args(&rule()),
+            vec![
+                "run",
+                "--pattern",
+                "foo($A)",
+                "--rewrite",
+                "bar($A)",
+                "--lang",
+                "rust",
+                "--stdin",
+                "--json=compact",
+            ]
+        );
+        assert_eq!(
+            Engine::AstGrep.scan_args(&rule(), &["src".to_string()]),
+            vec![
+                "run",
+                "--pattern",
+                "foo($A)",
+                "--lang",
+                "rust",
+                "--json=compact",
+                "--",
+                "src",
+            ]
+        );
+        let comby = CodemodRule {
+            pattern: "foo(:[a])".to_string(),
+            rewrite: "bar(:[a])".to_string(),
+            language: ".go".to_string(),
+        };
+        assert_eq!(
+            Engine::Comby.scan_args(&comby, &[]),
+            vec!["foo(:[a])", "", ".go", "-match-only", "-json-lines"]
+        );
+        assert!(within_paths(Path::new("cmd/main.go"), &["cmd".to_string()]));
+        assert!(!within_paths(Path::new("lib.go"), &["cmd".to_string()]));
+    }
+}
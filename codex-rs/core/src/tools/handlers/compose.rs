@@ -0,0 +1,210 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::exec_env::create_env;
+use crate::exec_policy::create_approval_requirement_for_command;
+use crate::function_tool::FunctionCallError;
+use crate::protocol::ExecCommandSource;
+use crate::sandboxing::SandboxPermissions;
+use crate::tools::context::ToolInvocation;
+use crate::tools::context::ToolOutput;
+use crate::tools::context::ToolPayload;
+use crate::tools::events::ToolEmitter;
+use crate::tools::events::ToolEventCtx;
+use crate::tools::events::ToolEventStage;
+use crate::tools::format_exec_output_str;
+use crate::tools::orchestrator::ToolOrchestrator;
+use crate::tools::registry::ToolHandler;
+use crate::tools::registry::ToolKind;
+use crate::tools::runtimes::compose::ComposeAction;
+use crate::tools::runtimes::compose::ComposeProject;
+use crate::tools::runtimes::compose::ComposeRequest;
+use crate::tools::runtimes::compose::ComposeRuntime;
+use crate::tools::runtimes::compose::ServiceStatus;
+use crate::tools::sandboxing::ToolCtx;
+
+pub struct ComposeHandler;
+
+const DEFAULT_LOG_TAIL: i64 = 200;
+
+fn default_tail() -> i64 {
+    DEFAULT_LOG_TAIL
+}
+
+#[derive(Deserialize)]
+struct ComposeArgs {
+    action: ComposeAction,
+    #[serde(default)]
+    services: Vec<String>,
+    /// Compose files passed with `-f`; defaults to compose's own discovery.
+    #[serde(default)]
+    files: Vec<String>,
+    #[serde(default)]
+    workdir: Option<String>,
+    /// For `up`: keep the containers running after the session ends.
+    #[serde(default)]
+    keep: bool,
+    #[serde(default = "default_tail")]
+    tail: i64,
+    #[serde(default)]
+    timeout_ms: Option<u64>,
+}
+
+#[derive(Serialize)]
+struct ComposeResponse {
+    action: ComposeAction,
+    exit_code: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    services: Option<Vec<ServiceStatus>>,
+    /// For `up`: whether the project will outlive the session.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    kept: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    output: Option<String>,
+}
+
+#[async_trait]
+impl ToolHandler for ComposeHandler {
+    fn kind(&self) -> ToolKind {
+        ToolKind::Function
+    }
+
+    fn is_mutating(&self, invocation: &ToolInvocation) -> bool {
+        match &invocation.payload {
+            ToolPayload::Function { arguments } => !matches!(
+                serde_json::from_str::<ComposeArgs>(arguments),
+                Ok(ComposeArgs {
+                    action: ComposeAction::Ps | ComposeAction::Logs,
+                    ..
+                })
+            ),
+            _ => true,
+        }
+    }
+
+    async fn handle(&self, invocation: ToolInvocation) -> Result<ToolOutput, FunctionCallError> {
+        let ToolInvocation {
+            session,
+            turn,
+            call_id,
+            tool_name,
+            payload,
+            ..
+        } = invocation;
+
+        let ToolPayload::Function { arguments } = payload else {
+            return Err(FunctionCallError::RespondToModel(format!(
+                "unsupported payload for compose handler: {tool_name}"
+            )));
+        };
+
+        let args: ComposeArgs = serde_json::from_str(&arguments).map_err(|err| {
+            FunctionCallError::RespondToModel(format!(
+                "failed to parse function arguments: {err:?}"
+            ))
+        })?;
+        if args.tail < 0 {
+            return Err(FunctionCallError::RespondToModel(
+                "`tail` must not be negative".to_string(),
+            ));
+        }
+
+        let cwd = turn.resolve_path(args.workdir.clone());
+        let project = ComposeProject {
+            cwd: cwd.clone(),
+            files: args.files.clone(),
+            keep: args.keep,
+        };
+        let command = project.action_args(args.action, &args.services, args.tail);
+
+        let emitter = ToolEmitter::shell(
+            command.clone(),
+            cwd.clone(),
+            ExecCommandSource::Agent,
+            false,
+        );
+        let event_ctx = ToolEventCtx::new(session.as_ref(), turn.as_ref(), &call_id, None);
+        emitter.begin(event_ctx).await;
+
+        let req = ComposeRequest {
+            action: args.action,
+            approval_requirement: create_approval_requirement_for_command(
+                &turn.exec_policy,
+                &command,
+                turn.approval_policy,
+                &turn.sandbox_policy,
+                SandboxPermissions::from(false),
+            ),
+            command,
+            status_command: (args.action == ComposeAction::Up).then(|| project.status_args()),
+            cwd,
+            timeout_ms: args.timeout_ms,
+            env: create_env(&turn.shell_environment_policy),
+        };
+        let mut orchestrator = ToolOrchestrator::new();
+        let mut runtime = ComposeRuntime::new();
+        let tool_ctx = ToolCtx {
+            session: session.as_ref(),
+            turn: turn.as_ref(),
+            call_id: call_id.clone(),
+            tool_name: tool_name.to_string(),
+        };
+        let out = orchestrator
+            .run(
+                &mut runtime,
+                Arc::new(req),
+                &tool_ctx,
+                &turn,
+                turn.approval_policy,
+            )
+            .await;
+
+        let event_ctx = ToolEventCtx::new(session.as_ref(), turn.as_ref(), &call_id, None);
+        let run = match out {
+            Ok(run) => run,
+            Err(err) => {
+                return emitter.finish(event_ctx, Err(err)).await.map(|content| {
+                    ToolOutput::Function {
+                        content,
+                        content_items: None,
+                        success: Some(false),
+                    }
+                });
+            }
+        };
+        emitter
+            .emit(event_ctx, ToolEventStage::Success(run.output.clone()))
+            .await;
+
+        let exit_code = run.output.exit_code;
+        let succeeded = exit_code == 0;
+        match args.action {
+            // A partially failed `up` may still have started containers, so
+            // track it either way; `down` is idempotent.
+            ComposeAction::Up => session.record_compose_project(project).await,
+            ComposeAction::Down if succeeded => session.forget_compose_project(&project).await,
+            _ => {}
+        }
+
+        let response = ComposeResponse {
+            action: args.action,
+            exit_code,
+            services: run.services,
+            kept: (args.action == ComposeAction::Up).then_some(args.keep),
+            output: (!succeeded || args.action == ComposeAction::Logs)
+                .then(|| format_exec_output_str(&run.output, turn.truncation_policy)),
+        };
+        let content = serde_json::to_string(&response).map_err(|err| {
+            FunctionCallError::Fatal(format!("failed to serialize compose result: {err}"))
+        })?;
+
+        Ok(ToolOutput::Function {
+            content,
+            content_items: None,
+            success: Some(succeeded),
+        })
+    }
+}
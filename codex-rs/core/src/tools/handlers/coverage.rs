@@ -0,0 +1,236 @@
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use codex_protocol::paths::normalize_for_comparison;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::artifacts::artifacts_dir;
+use crate::exec_env::create_env;
+use crate::exec_policy::create_approval_requirement_for_command;
+use crate::function_tool::FunctionCallError;
+use crate::output_truncation::sanitize;
+use crate::protocol::ExecCommandSource;
+use crate::sandboxing::SandboxPermissions;
+use crate::tools::context::ToolInvocation;
+use crate::tools::context::ToolOutput;
+use crate::tools::context::ToolPayload;
+use crate::tools::events::ToolEmitter;
+use crate::tools::events::ToolEventCtx;
+use crate::tools::events::ToolEventStage;
+use crate::tools::format_exec_output_str;
+use crate::tools::orchestrator::ToolOrchestrator;
+use crate::tools::registry::ToolHandler;
+use crate::tools::registry::ToolKind;
+use crate::tools::runtimes::coverage::COVERAGE_ARTIFACT_DIR;
+use crate::tools::runtimes::coverage::CoverageRequest;
+use crate::tools::runtimes::coverage::CoverageRuntime;
+use crate::tools::runtimes::coverage::CoverageTool;
+use crate::tools::runtimes::coverage::FileCoverage;
+use crate::tools::sandboxing::ToolCtx;
+
+pub struct CoverageHandler;
+
+#[derive(Deserialize)]
+struct CoverageArgs {
+    #[serde(default)]
+    tool: Option<CoverageTool>,
+    #[serde(default)]
+    args: Vec<String>,
+    #[serde(default)]
+    workdir: Option<String>,
+    /// Restrict the report to these files instead of the session's touched files.
+    #[serde(default)]
+    paths: Vec<String>,
+    #[serde(default)]
+    all_files: bool,
+    #[serde(default)]
+    timeout_ms: Option<u64>,
+}
+
+#[derive(Serialize)]
+struct CoverageResponse {
+    tool: CoverageTool,
+    exit_code: i32,
+    report_path: PathBuf,
+    files: Vec<FileCoverage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    note: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    output: Option<String>,
+}
+
+#[async_trait]
+impl ToolHandler for CoverageHandler {
+    fn kind(&self) -> ToolKind {
+        ToolKind::Function
+    }
+
+    fn is_mutating(&self, _invocation: &ToolInvocation) -> bool {
+        // Coverage runs execute the project's test suite and write artifacts.
+        true
+    }
+
+    async fn handle(&self, invocation: ToolInvocation) -> Result<ToolOutput, FunctionCallError> {
+        let ToolInvocation {
+            session,
+            turn,
+            call_id,
+            tool_name,
+            payload,
+            ..
+        } = invocation;
+
+        let ToolPayload::Function { arguments } = payload else {
+            return Err(FunctionCallError::RespondToModel(format!(
+                "unsupported payload for coverage handler: {tool_name}"
+            )));
+        };
+
+        let args: CoverageArgs = serde_json::from_str(&arguments).map_err(|err| {
+            FunctionCallError::RespondToModel(format!(
+                "failed to parse function arguments: {err:?}"
+            ))
+        })?;
+
+        let cwd = turn.resolve_path(args.workdir.clone());
+        let Some(tool) = args.tool.or_else(|| CoverageTool::detect(&cwd)) else {
+            return Err(FunctionCallError::RespondToModel(format!(
+                "could not detect a coverage tool in `{}`; pass `tool` explicitly",
+                cwd.display()
+            )));
+        };
+
+        let config = turn.client.config();
+        let report_dir = artifacts_dir(&config.codex_home, session.conversation_id())
+            .join(COVERAGE_ARTIFACT_DIR)
+            .join(sanitize(&call_id));
+        let script = tool.script(&report_dir, &args.args);
+        let command = session
+            .user_shell()
+            .script_shell()
+            .derive_exec_args(&script, true);
+
+        let emitter = ToolEmitter::shell(
+            command.clone(),
+            cwd.clone(),
+            ExecCommandSource::Agent,
+            false,
+        );
+        let event_ctx = ToolEventCtx::new(session.as_ref(), turn.as_ref(), &call_id, None);
+        emitter.begin(event_ctx).await;
+
+        let req = CoverageRequest {
+            approval_requirement: create_approval_requirement_for_command(
+                &turn.exec_policy,
+                &command,
+                turn.approval_policy,
+                &turn.sandbox_policy,
+                SandboxPermissions::from(false),
+            ),
+            command,
+            cwd: cwd.clone(),
+            report_dir,
+            timeout_ms: args.timeout_ms,
+            env: create_env(&turn.shell_environment_policy),
+        };
+        let mut orchestrator = ToolOrchestrator::new();
+        let mut runtime = CoverageRuntime::new();
+        let tool_ctx = ToolCtx {
+            session: session.as_ref(),
+            turn: turn.as_ref(),
+            call_id: call_id.clone(),
+            tool_name: tool_name.to_string(),
+        };
+        let out = orchestrator
+            .run(
+                &mut runtime,
+                Arc::new(req),
+                &tool_ctx,
+                &turn,
+                turn.approval_policy,
+            )
+            .await;
+
+        let event_ctx = ToolEventCtx::new(session.as_ref(), turn.as_ref(), &call_id, None);
+        let run = match out {
+            Ok(run) => run,
+            Err(err) => {
+                // Reuse the shell failure formatting so denials and timeouts
+                // read the same as they do for `shell`.
+                return emitter.finish(event_ctx, Err(err)).await.map(|content| {
+                    ToolOutput::Function {
+                        content,
+                        content_items: None,
+                        success: Some(false),
+                    }
+                });
+            }
+        };
+        emitter
+            .emit(event_ctx, ToolEventStage::Success(run.output.clone()))
+            .await;
+
+        let exit_code = run.output.exit_code;
+        let (files, note) = match run.files {
+            Some(files) if args.all_files => (files, None),
+            Some(files) => {
+                let selected: Vec<PathBuf> = if args.paths.is_empty() {
+                    session.touched_paths().await
+                } else {
+                    args.paths.iter().map(|path| cwd.join(path)).collect()
+                }
+                .iter()
+                .map(|path| comparable_path(path))
+                .collect();
+                if selected.is_empty() {
+                    (
+                        Vec::new(),
+                        Some(
+                            "no files were touched in this session; pass `paths` or `all_files`"
+                                .to_string(),
+                        ),
+                    )
+                } else {
+                    let files = files
+                        .into_iter()
+                        .filter(|file| selected.contains(&comparable_path(&file.path)))
+                        .collect();
+                    (files, None)
+                }
+            }
+            None => (
+                Vec::new(),
+                Some("coverage tool did not produce an LCOV report".to_string()),
+            ),
+        };
+        let output = (exit_code != 0 || note.is_some())
+            .then(|| format_exec_output_str(&run.output, turn.truncation_policy));
+
+        let response = CoverageResponse {
+            tool,
+            exit_code,
+            report_path: run.report_path,
+            files,
+            note,
+            output,
+        };
+        let content = serde_json::to_string(&response).map_err(|err| {
+            FunctionCallError::Fatal(format!("failed to serialize coverage report: {err}"))
+        })?;
+
+        Ok(ToolOutput::Function {
+            content,
+            content_items: None,
+            success: Some(exit_code == 0),
+        })
+    }
+}
+
+/// `path` in the form touched files and report entries are compared in, so
+/// that relative, `..` and symlinked spellings of a file agree.
+fn comparable_path(path: &Path) -> PathBuf {
+    normalize_for_comparison(&path.canonicalize().unwrap_or_else(|_| path.to_path_buf()))
+}
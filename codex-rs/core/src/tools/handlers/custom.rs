@@ -0,0 +1,114 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::function_tool::FunctionCallError;
+use crate::tools::context::ToolInvocation;
+use crate::tools::context::ToolOutput;
+use crate::tools::context::ToolPayload;
+use crate::tools::custom::CustomToolCall;
+use crate::tools::orchestrator::ToolOrchestrator;
+use crate::tools::registry::ToolHandler;
+use crate::tools::registry::ToolKind;
+use crate::tools::runtimes::custom::CustomRuntime;
+use crate::tools::runtimes::custom::CustomToolRequest;
+use crate::tools::sandboxing::ApprovalRequirement;
+use crate::tools::sandboxing::ToolCtx;
+use crate::tools::sandboxing::ToolError;
+
+/// Dispatches calls of every custom tool to the runtime the session's
+/// [`crate::ToolRuntimeRegistry`] has under the called name.
+pub struct CustomToolHandler;
+
+#[async_trait]
+impl ToolHandler for CustomToolHandler {
+    fn kind(&self) -> ToolKind {
+        ToolKind::Function
+    }
+
+    fn is_mutating(&self, invocation: &ToolInvocation) -> bool {
+        let ToolPayload::Function { arguments } = &invocation.payload else {
+            return true;
+        };
+        let Some(runtime) = invocation
+            .session
+            .services
+            .tool_runtimes
+            .runtime(&invocation.tool_name)
+        else {
+            return true;
+        };
+        serde_json::from_str::<serde_json::Value>(arguments)
+            .ok()
+            .is_none_or(|arguments| runtime.needs_approval(&arguments))
+    }
+
+    async fn handle(&self, invocation: ToolInvocation) -> Result<ToolOutput, FunctionCallError> {
+        let ToolInvocation {
+            session,
+            turn,
+            call_id,
+            tool_name,
+            payload,
+            ..
+        } = invocation;
+
+        let ToolPayload::Function { arguments } = payload else {
+            return Err(FunctionCallError::RespondToModel(format!(
+                "unsupported payload for custom tool: {tool_name}"
+            )));
+        };
+        let Some(runtime) = session.services.tool_runtimes.runtime(&tool_name) else {
+            return Err(FunctionCallError::RespondToModel(format!(
+                "unsupported call: {tool_name}"
+            )));
+        };
+        let arguments: serde_json::Value = serde_json::from_str(&arguments).map_err(|err| {
+            FunctionCallError::RespondToModel(format!(
+                "failed to parse function arguments: {err:?}"
+            ))
+        })?;
+
+        let req = CustomToolRequest {
+            display: runtime.describe(&tool_name, &arguments),
+            approval_requirement: (!runtime.needs_approval(&arguments)).then_some(
+                ApprovalRequirement::Skip {
+                    bypass_sandbox: false,
+                },
+            ),
+            call: CustomToolCall {
+                call_id: call_id.clone(),
+                arguments,
+                cwd: turn.cwd.clone(),
+                sandbox_policy: turn.sandbox_policy.clone(),
+            },
+        };
+        let mut orchestrator = ToolOrchestrator::new();
+        let mut runtime = CustomRuntime::new(tool_name.clone(), runtime);
+        let tool_ctx = ToolCtx {
+            session: session.as_ref(),
+            turn: turn.as_ref(),
+            call_id,
+            tool_name: tool_name.clone(),
+        };
+        let content = orchestrator
+            .run(
+                &mut runtime,
+                Arc::new(req),
+                &tool_ctx,
+                &turn,
+                turn.approval_policy,
+            )
+            .await
+            .map_err(|err| match err {
+                ToolError::Rejected(reason) => FunctionCallError::RespondToModel(reason),
+                ToolError::Codex(err) => FunctionCallError::RespondToModel(err.to_string()),
+            })?;
+
+        Ok(ToolOutput::Function {
+            content,
+            content_items: None,
+            success: Some(true),
+        })
+    }
+}
@@ -0,0 +1,450 @@
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::num::NonZeroUsize;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures::future::join_all;
+use serde::Deserialize;
+use serde::Serialize;
+use tokio::process::Command;
+use tokio::time::timeout;
+
+use crate::config::types::EnvironmentVariablePattern;
+use crate::exec_env::create_env;
+use crate::exec_env::prepare_command_env;
+use crate::function_tool::FunctionCallError;
+use crate::tools::context::ToolInvocation;
+use crate::tools::context::ToolOutput;
+use crate::tools::context::ToolPayload;
+use crate::tools::registry::ToolHandler;
+use crate::tools::registry::ToolKind;
+
+pub struct EnvSnapshotHandler;
+
+const VERSION_TIMEOUT: Duration = Duration::from_secs(5);
+const REDACTED: &str = "<redacted>";
+
+/// Toolchains probed by default, with the arguments that print their version.
+const TOOLCHAINS: &[(&str, &[&str])] = &[
+    ("git", &["--version"]),
+    ("rustc", &["--version"]),
+    ("cargo", &["--version"]),
+    ("node", &["--version"]),
+    ("npm", &["--version"]),
+    ("pnpm", &["--version"]),
+    ("yarn", &["--version"]),
+    ("bun", &["--version"]),
+    ("deno", &["--version"]),
+    ("python3", &["--version"]),
+    ("pip3", &["--version"]),
+    ("uv", &["--version"]),
+    ("go", &["version"]),
+    ("java", &["-version"]),
+    ("ruby", &["--version"]),
+    ("gcc", &["--version"]),
+    ("clang", &["--version"]),
+    ("cmake", &["--version"]),
+    ("make", &["--version"]),
+    ("docker", &["--version"]),
+];
+
+/// Environment variables worth reporting: exact names, then prefixes.
+const RELEVANT_VARS: &[&str] = &[
+    "PATH",
+    "SHELL",
+    "HOME",
+    "USER",
+    "LANG",
+    "LC_ALL",
+    "TZ",
+    "TERM",
+    "TMPDIR",
+    "CI",
+    "VIRTUAL_ENV",
+    "CONDA_DEFAULT_ENV",
+    "PYTHONPATH",
+    "NODE_ENV",
+    "JAVA_HOME",
+    "GOPATH",
+    "GOROOT",
+];
+const RELEVANT_PREFIXES: &[&str] = &["CARGO_", "RUST", "NPM_CONFIG_", "DOCKER_", "GITHUB_"];
+
+/// Values of variables matching these patterns are replaced with [`REDACTED`].
+const SECRET_PATTERNS: &[&str] = &[
+    "*KEY*",
+    "*SECRET*",
+    "*TOKEN*",
+    "*PASSWORD*",
+    "*PASSWD*",
+    "*CREDENTIAL*",
+    "*AUTH*",
+];
+
+#[derive(Deserialize)]
+struct EnvSnapshotArgs {
+    /// Additional binaries to probe with `--version`.
+    #[serde(default)]
+    extra_tools: Vec<String>,
+    #[serde(default = "default_true")]
+    include_env: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Serialize)]
+struct Toolchain {
+    name: String,
+    path: PathBuf,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<String>,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+struct Memory {
+    total_mb: i64,
+    available_mb: i64,
+}
+
+#[derive(Serialize)]
+struct EnvSnapshot {
+    os: String,
+    os_version: String,
+    arch: &'static str,
+    cpus: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    memory: Option<Memory>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    container: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    virtualization: Option<&'static str>,
+    shell: &'static str,
+    cwd: PathBuf,
+    toolchains: Vec<Toolchain>,
+    /// Tools probed but not found on `PATH`.
+    missing: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    env: Option<BTreeMap<String, String>>,
+}
+
+#[async_trait]
+impl ToolHandler for EnvSnapshotHandler {
+    fn kind(&self) -> ToolKind {
+        ToolKind::Function
+    }
+
+    async fn handle(&self, invocation: ToolInvocation) -> Result<ToolOutput, FunctionCallError> {
+        let ToolInvocation {
+            session,
+            turn,
+            payload,
+            ..
+        } = invocation;
+
+        let arguments = match payload {
+            ToolPayload::Function { arguments } => arguments,
+            _ => {
+                return Err(FunctionCallError::RespondToModel(
+                    "env_snapshot handler received unsupported payload".to_string(),
+                ));
+            }
+        };
+
+        let args: EnvSnapshotArgs = serde_json::from_str(&arguments).map_err(|err| {
+            FunctionCallError::RespondToModel(format!(
+                "failed to parse function arguments: {err:?}"
+            ))
+        })?;
+
+        // Probe with the environment commands actually run with.
+        let env = prepare_command_env(
+            &create_env(&turn.shell_environment_policy),
+            &turn.client.config().command_env,
+        );
+        let mut tools: Vec<(String, Vec<String>)> = TOOLCHAINS
+            .iter()
+            .map(|(name, version_args)| {
+                let version_args = version_args.iter().map(|arg| (*arg).to_string()).collect();
+                ((*name).to_string(), version_args)
+            })
+            .collect();
+        for name in args.extra_tools {
+            if !tools.iter().any(|(existing, _)| *existing == name) {
+                tools.push((name, vec!["--version".to_string()]));
+            }
+        }
+        let probes = join_all(
+            tools
+                .iter()
+                .map(|(name, version_args)| probe_tool(name, version_args, &env, &turn.cwd)),
+        )
+        .await;
+        let mut toolchains = Vec::new();
+        let mut missing = Vec::new();
+        for ((name, _), probe) in tools.into_iter().zip(probes) {
+            match probe {
+                Some(toolchain) => toolchains.push(toolchain),
+                None => missing.push(name),
+            }
+        }
+
+        let os_info = os_info::get();
+        let snapshot = EnvSnapshot {
+            os: os_info.os_type().to_string(),
+            os_version: os_info.version().to_string(),
+            arch: std::env::consts::ARCH,
+            cpus: std::thread::available_parallelism().map_or(1, NonZeroUsize::get),
+            memory: read_to_string("/proc/meminfo")
+                .await
+                .and_then(|meminfo| parse_meminfo(&meminfo)),
+            container: detect_container(&env).await,
+            virtualization: detect_virtualization().await,
+            shell: session.user_shell().name(),
+            cwd: turn.cwd.clone(),
+            toolchains,
+            missing,
+            env: args.include_env.then(|| relevant_env(&env)),
+        };
+
+        let content = serde_json::to_string(&snapshot).map_err(|err| {
+            FunctionCallError::Fatal(format!("failed to serialize environment snapshot: {err}"))
+        })?;
+
+        Ok(ToolOutput::Function {
+            content,
+            content_items: None,
+            success: Some(true),
+        })
+    }
+}
+
+async fn probe_tool(
+    name: &str,
+    version_args: &[String],
+    env: &HashMap<String, String>,
+    cwd: &Path,
+) -> Option<Toolchain> {
+    let path = which::which_in(name, env.get("PATH"), cwd).ok()?;
+    let output = timeout(
+        VERSION_TIMEOUT,
+        Command::new(&path)
+            .args(version_args)
+            .env_clear()
+            .envs(env)
+            .current_dir(cwd)
+            .kill_on_drop(true)
+            .output(),
+    )
+    .await;
+    // Some tools (e.g. `java -version`) print their version on stderr.
+    let version = match output {
+        Ok(Ok(output)) => first_line(&output.stdout).or_else(|| first_line(&output.stderr)),
+        _ => None,
+    };
+    Some(Toolchain {
+        name: name.to_string(),
+        path,
+        version,
+    })
+}
+
+fn first_line(bytes: &[u8]) -> Option<String> {
+    String::from_utf8_lossy(bytes)
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map(str::to_string)
+}
+
+async fn read_to_string(path: &str) -> Option<String> {
+    tokio::fs::read_to_string(path).await.ok()
+}
+
+fn parse_meminfo(meminfo: &str) -> Option<Memory> {
+    let field = |name: &str| {
+        meminfo.lines().find_map(|line| {
+            let rest = line.strip_prefix(name)?.strip_prefix(':')?;
+            rest.trim()
+                .trim_end_matches("kB")
+                .trim()
+                .parse::<i64>()
+                .ok()
+        })
+    };
+    let total_kb = field("MemTotal")?;
+    Some(Memory {
+        total_mb: total_kb / 1024,
+        available_mb: field("MemAvailable").unwrap_or(0) / 1024,
+    })
+}
+
+async fn detect_container(env: &HashMap<String, String>) -> Option<&'static str> {
+    let dockerenv = tokio::fs::metadata("/.dockerenv").await.is_ok();
+    let containerenv = tokio::fs::metadata("/run/.containerenv").await.is_ok();
+    let cgroup = read_to_string("/proc/1/cgroup").await.unwrap_or_default();
+    classify_container(dockerenv, containerenv, &cgroup, env)
+}
+
+fn classify_container(
+    dockerenv: bool,
+    containerenv: bool,
+    cgroup: &str,
+    env: &HashMap<String, String>,
+) -> Option<&'static str> {
+    if env.contains_key("KUBERNETES_SERVICE_HOST") || cgroup.contains("kubepods") {
+        Some("kubernetes")
+    } else if dockerenv || cgroup.contains("docker") {
+        Some("docker")
+    } else if containerenv || cgroup.contains("libpod") {
+        Some("podman")
+    } else if cgroup.contains("containerd") {
+        Some("containerd")
+    } else if cgroup.contains("lxc") {
+        Some("lxc")
+    } else {
+        None
+    }
+}
+
+async fn detect_virtualization() -> Option<&'static str> {
+    let proc_version = read_to_string("/proc/version").await.unwrap_or_default();
+    let vendor = read_to_string("/sys/class/dmi/id/sys_vendor")
+        .await
+        .unwrap_or_default();
+    let product = read_to_string("/sys/class/dmi/id/product_name")
+        .await
+        .unwrap_or_default();
+    classify_virtualization(&proc_version, &format!("{vendor} {product}"))
+}
+
+fn classify_virtualization(proc_version: &str, dmi: &str) -> Option<&'static str> {
+    let proc_version = proc_version.to_ascii_lowercase();
+    let dmi = dmi.to_ascii_lowercase();
+    if proc_version.contains("microsoft") {
+        Some("wsl")
+    } else if dmi.contains("virtualbox") {
+        Some("virtualbox")
+    } else if dmi.contains("vmware") {
+        Some("vmware")
+    } else if dmi.contains("amazon ec2") {
+        Some("aws")
+    } else if dmi.contains("google compute engine") {
+        Some("gce")
+    } else if dmi.contains("microsoft corporation") {
+        Some("hyperv")
+    } else if dmi.contains("qemu") || dmi.contains("kvm") {
+        Some("kvm")
+    } else {
+        None
+    }
+}
+
+fn relevant_env(env: &HashMap<String, String>) -> BTreeMap<String, String> {
+    let secrets: Vec<EnvironmentVariablePattern> = SECRET_PATTERNS
+        .iter()
+        .copied()
+        .map(EnvironmentVariablePattern::new_case_insensitive)
+        .collect();
+    env.iter()
+        .filter(|(name, _)| {
+            RELEVANT_VARS.contains(&name.as_str())
+                || RELEVANT_PREFIXES
+                    .iter()
+                    .any(|prefix| name.starts_with(prefix))
+        })
+        .map(|(name, value)| {
+            let value = if secrets.iter().any(|pattern| pattern.matches(name)) {
+                REDACTED.to_string()
+            } else {
+                value.clone()
+            };
+            (name.clone(), value)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parses_meminfo() {
+        let meminfo = "MemTotal:       16384000 kB\nMemFree:         1024000 kB\n\
+                       MemAvailable:    8192000 kB\n";
+        assert_eq!(
+            parse_meminfo(meminfo),
+            Some(Memory {
+                total_mb: 16000,
+                available_mb: 8000,
+            })
+        );
+        assert_eq!(parse_meminfo("garbage"), None);
+    }
+
+    #[test]
+    fn classifies_containers() {
+        let no_env = HashMap::new();
+        assert_eq!(
+            classify_container(false, false, "0::/system.slice/docker-abc.scope\n", &no_env),
+            Some("docker")
+        );
+        assert_eq!(
+            classify_container(false, true, "0::/\n", &no_env),
+            Some("podman")
+        );
+        let k8s = HashMap::from([(
+            "KUBERNETES_SERVICE_HOST".to_string(),
+            "10.0.0.1".to_string(),
+        )]);
+        assert_eq!(
+            classify_container(true, false, "0::/\n", &k8s),
+            Some("kubernetes")
+        );
+        assert_eq!(
+            classify_container(false, false, "0::/init.scope\n", &no_env),
+            None
+        );
+    }
+
+    #[test]
+    fn classifies_virtualization() {
+        assert_eq!(
+            classify_virtualization("Linux version 5.15.0-microsoft-standard-WSL2", ""),
+            Some("wsl")
+        );
+        assert_eq!(
+            classify_virtualization("Linux version 6.1.0", "QEMU Standard PC (Q35 + ICH9, 2009)"),
+            Some("kvm")
+        );
+        assert_eq!(
+            classify_virtualization("Linux version 6.1.0", "Dell Inc. XPS 13"),
+            None
+        );
+    }
+
+    #[test]
+    fn relevant_env_filters_and_redacts() {
+        let env = HashMap::from([
+            ("PATH".to_string(), "/usr/bin".to_string()),
+            ("CARGO_HOME".to_string(), "/home/dev/.cargo".to_string()),
+            ("CARGO_REGISTRY_TOKEN".to_string(), "hunter2".to_string()),
+            ("GITHUB_AUTH".to_string(), "ghp_abc".to_string()),
+            ("EDITOR".to_string(), "vim".to_string()),
+        ]);
+        assert_eq!(
+            relevant_env(&env),
+            BTreeMap::from([
+                ("CARGO_HOME".to_string(), "/home/dev/.cargo".to_string()),
+                ("CARGO_REGISTRY_TOKEN".to_string(), REDACTED.to_string()),
+                ("GITHUB_AUTH".to_string(), REDACTED.to_string()),
+                ("PATH".to_string(), "/usr/bin".to_string()),
+            ])
+        );
+    }
+}
@@ -0,0 +1,311 @@
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use async_trait::async_trait;
+use chrono::DateTime;
+use chrono::Utc;
+use serde::Deserialize;
+use serde::Serialize;
+use tokio::process::Command;
+use tokio::time::timeout;
+
+use crate::function_tool::FunctionCallError;
+use crate::tools::context::ToolInvocation;
+use crate::tools::context::ToolOutput;
+use crate::tools::context::ToolPayload;
+use crate::tools::registry::ToolHandler;
+use crate::tools::registry::ToolKind;
+use crate::tools::runtimes::git::BlameHunk;
+use crate::tools::runtimes::git::parse_blame_porcelain;
+
+pub struct FileInfoHandler;
+
+const MAX_PATHS: usize = 100;
+const BLAME_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Deserialize)]
+struct FileInfoArgs {
+    paths: Vec<String>,
+    /// Attribute lines of regular files to the commits that last changed them.
+    #[serde(default)]
+    blame: bool,
+    /// 1-indexed, inclusive line range for `blame`; defaults to the whole file.
+    #[serde(default)]
+    start_line: Option<i64>,
+    #[serde(default)]
+    end_line: Option<i64>,
+}
+
+#[derive(Serialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum FileKind {
+    File,
+    Dir,
+    Symlink,
+    Other,
+}
+
+#[derive(Serialize)]
+struct PathInfo {
+    path: PathBuf,
+    exists: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    kind: Option<FileKind>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    size: Option<u64>,
+    /// Permission bits in octal, e.g. `0755`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mode: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    modified: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    symlink_target: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    blame: Option<Vec<BlameHunk>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    blame_error: Option<String>,
+}
+
+#[async_trait]
+impl ToolHandler for FileInfoHandler {
+    fn kind(&self) -> ToolKind {
+        ToolKind::Function
+    }
+
+    async fn handle(&self, invocation: ToolInvocation) -> Result<ToolOutput, FunctionCallError> {
+        let ToolInvocation { payload, turn, .. } = invocation;
+
+        let arguments = match payload {
+            ToolPayload::Function { arguments } => arguments,
+            _ => {
+                return Err(FunctionCallError::RespondToModel(
+                    "file_info handler received unsupported payload".to_string(),
+                ));
+            }
+        };
+
+        let args: FileInfoArgs = serde_json::from_str(&arguments).map_err(|err| {
+            FunctionCallError::RespondToModel(format!(
+                "failed to parse function arguments: {err:?}"
+            ))
+        })?;
+
+        if args.paths.is_empty() || args.paths.len() > MAX_PATHS {
+            return Err(FunctionCallError::RespondToModel(format!(
+                "pass between 1 and {MAX_PATHS} paths"
+            )));
+        }
+        let range = match (args.start_line, args.end_line) {
+            (None, None) => None,
+            (start, end) => {
+                let start = start.unwrap_or(1);
+                if start < 1 || end.is_some_and(|end| end < start) {
+                    return Err(FunctionCallError::RespondToModel(
+                        "start_line must be >= 1 and end_line must not precede it".to_string(),
+                    ));
+                }
+                Some((start, end))
+            }
+        };
+
+        let mut infos = Vec::with_capacity(args.paths.len());
+        for path in args.paths {
+            let path = turn.resolve_path(Some(path));
+            let mut info = stat(&path).await;
+            if args.blame && info.kind == Some(FileKind::File) {
+                match blame(&path, range).await {
+                    Ok(hunks) => info.blame = Some(hunks),
+                    Err(err) => info.blame_error = Some(err),
+                }
+            }
+            infos.push(info);
+        }
+
+        let content = serde_json::to_string(&infos).map_err(|err| {
+            FunctionCallError::Fatal(format!("failed to serialize file info: {err}"))
+        })?;
+
+        Ok(ToolOutput::Function {
+            content,
+            content_items: None,
+            success: Some(true),
+        })
+    }
+}
+
+async fn stat(path: &Path) -> PathInfo {
+    let mut info = PathInfo {
+        path: path.to_path_buf(),
+        exists: false,
+        kind: None,
+        size: None,
+        mode: None,
+        modified: None,
+        symlink_target: None,
+        blame: None,
+        blame_error: None,
+    };
+    let Ok(meta) = tokio::fs::symlink_metadata(path).await else {
+        return info;
+    };
+
+    let file_type = meta.file_type();
+    info.exists = true;
+    info.kind = Some(if file_type.is_symlink() {
+        FileKind::Symlink
+    } else if file_type.is_dir() {
+        FileKind::Dir
+    } else if file_type.is_file() {
+        FileKind::File
+    } else {
+        FileKind::Other
+    });
+    info.size = Some(meta.len());
+    info.mode = mode_string(&meta);
+    info.modified = meta.modified().ok().map(format_time);
+    if file_type.is_symlink() {
+        info.symlink_target = tokio::fs::read_link(path).await.ok();
+    }
+    info
+}
+
+#[cfg(unix)]
+fn mode_string(meta: &std::fs::Metadata) -> Option<String> {
+    use std::os::unix::fs::PermissionsExt;
+    Some(format!("{:04o}", meta.permissions().mode() & 0o7777))
+}
+
+#[cfg(not(unix))]
+fn mode_string(_meta: &std::fs::Metadata) -> Option<String> {
+    None
+}
+
+fn format_time(time: SystemTime) -> String {
+    DateTime::<Utc>::from(time).to_rfc3339()
+}
+
+async fn blame(path: &Path, range: Option<(i64, Option<i64>)>) -> Result<Vec<BlameHunk>, String> {
+    let (Some(dir), Some(file_name)) = (path.parent(), path.file_name()) else {
+        return Err("not a file path".to_string());
+    };
+    let mut command = Command::new("git");
+    command
+        .arg("-C")
+        .arg(dir)
+        .args(["blame", "--porcelain"])
+        .kill_on_drop(true);
+    if let Some((start, end)) = range {
+        let end = end.map(|end| end.to_string()).unwrap_or_default();
+        command.arg(format!("-L{start},{end}"));
+    }
+    command.arg("--").arg(file_name);
+
+    let output = timeout(BLAME_TIMEOUT, command.output())
+        .await
+        .map_err(|_| "git blame timed out".to_string())?
+        .map_err(|err| format!("failed to run git blame: {err}"))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(parse_blame_porcelain(&String::from_utf8_lossy(
+        &output.stdout,
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::runtimes::git::UNCOMMITTED;
+    use pretty_assertions::assert_eq;
+    use tempfile::tempdir;
+
+    const A: &str = "1111111111111111111111111111111111111111";
+    const B: &str = "2222222222222222222222222222222222222222";
+
+    #[test]
+    fn groups_porcelain_lines_into_hunks() {
+        let output = format!(
+            "{A} 1 1 2\n\
+             author Ada\n\
+             author-mail <ada@example.com>\n\
+             author-time 1700000000\n\
+             author-tz +0000\n\
+             summary Initial commit\n\
+             filename src/lib.rs\n\
+             \tfn main() {{\n\
+             {A} 2 2\n\
+             \t}}\n\
+             {B} 5 3 1\n\
+             author Grace\n\
+             author-time 1710000000\n\
+             summary Add helper\n\
+             filename src/lib.rs\n\
+             \tfn helper() {{}}\n\
+             {UNCOMMITTED} 4 4 1\n\
+             author Not Committed Yet\n\
+             author-time 1720000000\n\
+             summary Version of src/lib.rs from src/lib.rs\n\
+             filename src/lib.rs\n\
+             \t// wip\n"
+        );
+
+        assert_eq!(
+            parse_blame_porcelain(&output),
+            vec![
+                BlameHunk {
+                    start_line: 1,
+                    end_line: 2,
+                    commit: Some(A.to_string()),
+                    author: "Ada".to_string(),
+                    date: Some("2023-11-14T22:13:20+00:00".to_string()),
+                    summary: "Initial commit".to_string(),
+                },
+                BlameHunk {
+                    start_line: 3,
+                    end_line: 3,
+                    commit: Some(B.to_string()),
+                    author: "Grace".to_string(),
+                    date: Some("2024-03-09T16:00:00+00:00".to_string()),
+                    summary: "Add helper".to_string(),
+                },
+                BlameHunk {
+                    start_line: 4,
+                    end_line: 4,
+                    commit: None,
+                    author: "Not Committed Yet".to_string(),
+                    date: Some("2024-07-03T09:46:40+00:00".to_string()),
+                    summary: "Version of src/lib.rs from src/lib.rs".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn stat_reports_kind_size_and_symlink_target() {
+        let dir = tempdir().expect("tempdir");
+        let file = dir.path().join("a.txt");
+        std::fs::write(&file, "hello").expect("write");
+
+        let info = stat(&file).await;
+        assert_eq!(
+            (info.exists, info.kind, info.size),
+            (true, Some(FileKind::File), Some(5))
+        );
+
+        let missing = stat(&dir.path().join("missing")).await;
+        assert_eq!((missing.exists, missing.kind), (false, None));
+
+        #[cfg(unix)]
+        {
+            let link = dir.path().join("link");
+            std::os::unix::fs::symlink(&file, &link).expect("symlink");
+            let info = stat(&link).await;
+            assert_eq!(
+                (info.kind, info.symlink_target),
+                (Some(FileKind::Symlink), Some(file))
+            );
+        }
+    }
+}
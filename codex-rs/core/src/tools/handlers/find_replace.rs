@@ -0,0 +1,267 @@
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use regex_lite::NoExpand;
+use regex_lite::Regex;
+use serde::Deserialize;
+use tokio::process::Command;
+use tokio::time::timeout;
+
+use crate::function_tool::FunctionCallError;
+use crate::tools::context::ToolInvocation;
+use crate::tools::context::ToolOutput;
+use crate::tools::context::ToolPayload;
+use crate::tools::handlers::apply_patch::apply_patch_input;
+use crate::tools::patch_builder::PatchBuilder;
+use crate::tools::registry::ToolHandler;
+use crate::tools::registry::ToolKind;
+
+pub struct FindReplaceHandler;
+
+const MAX_CHANGED_FILES: usize = 500;
+/// Files larger than this are skipped; they are almost never hand-edited source.
+const MAX_FILE_BYTES: u64 = 2 * 1024 * 1024;
+const LIST_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Deserialize)]
+struct FindReplaceArgs {
+    find: String,
+    replace: String,
+    /// Treat `find` as a regular expression; `replace` may use `$1`/`${name}`.
+    #[serde(default)]
+    regex: bool,
+    #[serde(default)]
+    case_insensitive: bool,
+    /// Only match `find` at word boundaries (useful for identifier renames).
+    #[serde(default)]
+    whole_word: bool,
+    /// Files or directories to search, relative to the turn cwd.
+    #[serde(default)]
+    paths: Vec<String>,
+    /// Glob filters passed to `rg --glob`, e.g. `*.rs` or `!vendor/**`.
+    #[serde(default)]
+    include: Vec<String>,
+}
+
+#[async_trait]
+impl ToolHandler for FindReplaceHandler {
+    fn kind(&self) -> ToolKind {
+        ToolKind::Function
+    }
+
+    fn is_mutating(&self, _invocation: &ToolInvocation) -> bool {
+        true
+    }
+
+    async fn handle(&self, invocation: ToolInvocation) -> Result<ToolOutput, FunctionCallError> {
+        let ToolInvocation {
+            session,
+            turn,
+            tracker,
+            call_id,
+            tool_name,
+            payload,
+        } = invocation;
+
+        let ToolPayload::Function { arguments } = payload else {
+            return Err(FunctionCallError::RespondToModel(format!(
+                "unsupported payload for find_replace handler: {tool_name}"
+            )));
+        };
+
+        let args: FindReplaceArgs = serde_json::from_str(&arguments).map_err(|err| {
+            FunctionCallError::RespondToModel(format!(
+                "failed to parse function arguments: {err:?}"
+            ))
+        })?;
+        if args.find.is_empty() {
+            return Err(FunctionCallError::RespondToModel(
+                "`find` must not be empty".to_string(),
+            ));
+        }
+
+        let pattern = build_pattern(&args)
+            .map_err(|err| FunctionCallError::RespondToModel(format!("invalid pattern: {err}")))?;
+        let files = list_files(&turn.cwd, &args.paths, &args.include).await?;
+
+        let mut builder = PatchBuilder::new();
+        let mut replacements = 0;
+        for file in files {
+            let path = turn.cwd.join(&file);
+            let small_enough = tokio::fs::metadata(&path)
+                .await
+                .is_ok_and(|meta| meta.len() <= MAX_FILE_BYTES);
+            if !small_enough {
+                continue;
+            }
+            // Binary and non-UTF-8 files are left alone.
+            let Ok(old) = tokio::fs::read_to_string(&path).await else {
+                continue;
+            };
+            let count = pattern.find_iter(&old).count();
+            if count == 0 {
+                continue;
+            }
+            let new = if args.regex {
+                pattern.replace_all(&old, args.replace.as_str())
+            } else {
+                pattern.replace_all(&old, NoExpand(&args.replace))
+            };
+            if builder.update_file(&file, &old, &new) {
+                replacements += count;
+            }
+            if builder.file_count() > MAX_CHANGED_FILES {
+                return Err(FunctionCallError::RespondToModel(format!(
+                    "more than {MAX_CHANGED_FILES} files would change; narrow `paths` or `include`"
+                )));
+            }
+        }
+
+        if builder.is_empty() {
+            return Ok(ToolOutput::Function {
+                content: "no matches; nothing to change".to_string(),
+                content_items: None,
+                success: Some(true),
+            });
+        }
+
+        let summary = format!(
+            "replaced {replacements} occurrence(s) in {} file(s)",
+            builder.file_count()
+        );
+        let output = apply_patch_input(
+            &session,
+            &turn,
+            &tracker,
+            &call_id,
+            &tool_name,
+            builder.build(),
+        )
+        .await?;
+        Ok(match output {
+            ToolOutput::Function {
+                content,
+                content_items,
+                success,
+            } => ToolOutput::Function {
+                content: format!("{summary}\n{content}"),
+                content_items,
+                success,
+            },
+            other => other,
+        })
+    }
+}
+
+fn build_pattern(args: &FindReplaceArgs) -> Result<Regex, regex_lite::Error> {
+    let mut pattern = if args.regex {
+        args.find.clone()
+    } else {
+        regex_lite::escape(&args.find)
+    };
+    if args.whole_word {
+        pattern = format!(r"\b(?:{pattern})\b");
+    }
+    if args.case_insensitive {
+        pattern = format!("(?i){pattern}");
+    }
+    Regex::new(&pattern)
+}
+
+/// List candidate files with `rg --files`, which honours `.gitignore` and
+/// skips hidden directories. Returned paths are relative to `cwd`.
+async fn list_files(
+    cwd: &Path,
+    paths: &[String],
+    include: &[String],
+) -> Result<Vec<PathBuf>, FunctionCallError> {
+    let mut command = Command::new("rg");
+    command
+        .current_dir(cwd)
+        .arg("--files")
+        .arg("--no-messages")
+        .kill_on_drop(true);
+    for glob in include {
+        command.arg("--glob").arg(glob);
+    }
+    command.arg("--").args(paths);
+
+    let output = timeout(LIST_TIMEOUT, command.output())
+        .await
+        .map_err(|_| FunctionCallError::RespondToModel("rg timed out".to_string()))?
+        .map_err(|err| {
+            FunctionCallError::RespondToModel(format!(
+                "failed to launch rg: {err}. Ensure ripgrep is installed and on PATH."
+            ))
+        })?;
+    match output.status.code() {
+        Some(0 | 1) => {}
+        _ => {
+            return Err(FunctionCallError::RespondToModel(format!(
+                "rg failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            )));
+        }
+    }
+
+    let mut files: Vec<PathBuf> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(PathBuf::from)
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn args(find: &str, replace: &str) -> FindReplaceArgs {
+        FindReplaceArgs {
+            find: find.to_string(),
+            replace: replace.to_string(),
+            regex: false,
+            case_insensitive: false,
+            whole_word: false,
+            paths: Vec::new(),
+            include: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn literal_patterns_are_escaped() {
+        let args = args("a.b(", "x");
+        let pattern = build_pattern(&args).expect("pattern");
+        assert_eq!(
+            pattern.replace_all("a.b( axb(", NoExpand(&args.replace)),
+            "x axb("
+        );
+    }
+
+    #[test]
+    fn whole_word_and_case_insensitive() {
+        let mut args = args("foo", "bar");
+        args.whole_word = true;
+        args.case_insensitive = true;
+        let pattern = build_pattern(&args).expect("pattern");
+        assert_eq!(
+            pattern.replace_all("Foo foobar FOO", NoExpand(&args.replace)),
+            "bar foobar bar"
+        );
+    }
+
+    #[test]
+    fn regex_replacements_expand_captures() {
+        let mut args = args(r"get_(\w+)\(\)", "$1()");
+        args.regex = true;
+        let pattern = build_pattern(&args).expect("pattern");
+        assert_eq!(
+            pattern.replace_all("x.get_name() + y.get_id()", args.replace.as_str()),
+            "x.name() + y.id()"
+        );
+    }
+}
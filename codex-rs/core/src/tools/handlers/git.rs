@@ -0,0 +1,136 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::Deserialize;
+
+use crate::exec_env::create_env;
+use crate::function_tool::FunctionCallError;
+use crate::protocol::ExecCommandSource;
+use crate::tools::context::ToolInvocation;
+use crate::tools::context::ToolOutput;
+use crate::tools::context::ToolPayload;
+use crate::tools::events::ToolEmitter;
+use crate::tools::events::ToolEventCtx;
+use crate::tools::events::ToolEventStage;
+use crate::tools::format_exec_output_str;
+use crate::tools::orchestrator::ToolOrchestrator;
+use crate::tools::registry::ToolHandler;
+use crate::tools::registry::ToolKind;
+use crate::tools::runtimes::git::GitQuery;
+use crate::tools::runtimes::git::GitRequest;
+use crate::tools::runtimes::git::GitRuntime;
+use crate::tools::sandboxing::ToolCtx;
+
+pub struct GitHandler;
+
+#[derive(Deserialize)]
+struct GitArgs {
+    #[serde(flatten)]
+    query: GitQuery,
+    #[serde(default)]
+    workdir: Option<String>,
+}
+
+#[async_trait]
+impl ToolHandler for GitHandler {
+    fn kind(&self) -> ToolKind {
+        ToolKind::Function
+    }
+
+    async fn handle(&self, invocation: ToolInvocation) -> Result<ToolOutput, FunctionCallError> {
+        let ToolInvocation {
+            session,
+            turn,
+            call_id,
+            tool_name,
+            payload,
+            ..
+        } = invocation;
+
+        let ToolPayload::Function { arguments } = payload else {
+            return Err(FunctionCallError::RespondToModel(format!(
+                "unsupported payload for git handler: {tool_name}"
+            )));
+        };
+
+        let args: GitArgs = serde_json::from_str(&arguments).map_err(|err| {
+            FunctionCallError::RespondToModel(format!(
+                "failed to parse function arguments: {err:?}"
+            ))
+        })?;
+        let command = args
+            .query
+            .command()
+            .map_err(FunctionCallError::RespondToModel)?;
+        let cwd = turn.resolve_path(args.workdir);
+
+        let emitter = ToolEmitter::shell(
+            command.clone(),
+            cwd.clone(),
+            ExecCommandSource::Agent,
+            false,
+        );
+        let event_ctx = ToolEventCtx::new(session.as_ref(), turn.as_ref(), &call_id, None);
+        emitter.begin(event_ctx).await;
+
+        let req = GitRequest {
+            query: args.query,
+            command,
+            cwd,
+            env: create_env(&turn.shell_environment_policy),
+        };
+        let mut orchestrator = ToolOrchestrator::new();
+        let mut runtime = GitRuntime::new();
+        let tool_ctx = ToolCtx {
+            session: session.as_ref(),
+            turn: turn.as_ref(),
+            call_id: call_id.clone(),
+            tool_name: tool_name.to_string(),
+        };
+        let out = orchestrator
+            .run(
+                &mut runtime,
+                Arc::new(req),
+                &tool_ctx,
+                &turn,
+                turn.approval_policy,
+            )
+            .await;
+
+        let event_ctx = ToolEventCtx::new(session.as_ref(), turn.as_ref(), &call_id, None);
+        let run = match out {
+            Ok(run) => run,
+            Err(err) => {
+                return emitter.finish(event_ctx, Err(err)).await.map(|content| {
+                    ToolOutput::Function {
+                        content,
+                        content_items: None,
+                        success: Some(false),
+                    }
+                });
+            }
+        };
+        emitter
+            .emit(event_ctx, ToolEventStage::Success(run.output.clone()))
+            .await;
+
+        let Some(result) = run.result else {
+            // Not a repository, an unknown revision and the like: git's own
+            // message says best what went wrong.
+            return Ok(ToolOutput::Function {
+                content: format_exec_output_str(&run.output, turn.truncation_policy),
+                content_items: None,
+                success: Some(false),
+            });
+        };
+        let content = serde_json::to_string(&result).map_err(|err| {
+            FunctionCallError::Fatal(format!("failed to serialize git result: {err}"))
+        })?;
+
+        Ok(ToolOutput::Function {
+            content,
+            content_items: None,
+            success: Some(true),
+        })
+    }
+}
@@ -6,6 +6,7 @@ use serde::Deserialize;
 use tokio::process::Command;
 use tokio::time::timeout;
 
+use crate::file_placeholder;
 use crate::function_tool::FunctionCallError;
 use crate::tools::context::ToolInvocation;
 use crate::tools::context::ToolOutput;
@@ -86,6 +87,7 @@ impl ToolHandler for GrepFilesHandler {
 
         let search_results =
             run_rg_search(pattern, include.as_deref(), &search_path, limit, &turn.cwd).await?;
+        let search_results = describe_placeholders(search_results, &turn.cwd).await;
 
         if search_results.is_empty() {
             Ok(ToolOutput::Function {
@@ -110,6 +112,19 @@ async fn verify_path_exists(path: &Path) -> Result<(), FunctionCallError> {
     Ok(())
 }
 
+/// Matches inside binary or huge files are listed with their placeholder, so
+/// the model knows not to `read_file` them.
+async fn describe_placeholders(results: Vec<String>, cwd: &Path) -> Vec<String> {
+    let mut described = Vec::with_capacity(results.len());
+    for result in results {
+        match file_placeholder::inspect(&cwd.join(&result)).await {
+            Ok(Some(placeholder)) => described.push(format!("{result} {placeholder}")),
+            _ => described.push(result),
+        }
+    }
+    described
+}
+
 async fn run_rg_search(
     pattern: &str,
     include: Option<&str>,
@@ -264,6 +279,24 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn binary_matches_are_described() {
+        let temp = tempdir().expect("create temp dir");
+        let dir = temp.path();
+        std::fs::write(dir.join("notes.txt"), "alpha").unwrap();
+        std::fs::write(dir.join("blob.bin"), b"alpha\x00\x01").unwrap();
+
+        let results = vec!["notes.txt".to_string(), "blob.bin".to_string()];
+        let placeholder = file_placeholder::inspect(&dir.join("blob.bin"))
+            .await
+            .unwrap()
+            .expect("placeholder");
+        assert_eq!(
+            describe_placeholders(results, dir).await,
+            vec!["notes.txt".to_string(), format!("blob.bin {placeholder}")]
+        );
+    }
+
     fn rg_available() -> bool {
         StdCommand::new("rg")
             .arg("--version")
@@ -0,0 +1,324 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use codex_protocol::protocol::AskForApproval;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::codex::Session;
+use crate::codex::TurnContext;
+use crate::exec::ExecToolCallOutput;
+use crate::exec_env::create_env;
+use crate::exec_policy::create_approval_requirement_for_command;
+use crate::function_tool::FunctionCallError;
+use crate::protocol::ExecCommandSource;
+use crate::sandboxing::SandboxPermissions;
+use crate::tools::context::ToolInvocation;
+use crate::tools::context::ToolOutput;
+use crate::tools::context::ToolPayload;
+use crate::tools::events::ToolEmitter;
+use crate::tools::events::ToolEventCtx;
+use crate::tools::events::ToolEventStage;
+use crate::tools::format_exec_output_str;
+use crate::tools::orchestrator::ToolOrchestrator;
+use crate::tools::registry::ToolHandler;
+use crate::tools::registry::ToolKind;
+use crate::tools::runtimes::migrate::DATABASE_URL_ENV_VAR;
+use crate::tools::runtimes::migrate::MigrateRequest;
+use crate::tools::runtimes::migrate::MigrateRuntime;
+use crate::tools::runtimes::migrate::MigrationPlan;
+use crate::tools::runtimes::migrate::MigrationTool;
+use crate::tools::sandboxing::ApprovalRequirement;
+use crate::tools::sandboxing::ToolCtx;
+
+pub struct MigrateHandler;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum MigrateAction {
+    /// List pending migrations and their SQL.
+    Plan,
+    /// Plan, then apply to the shadow database only.
+    DryRun,
+    /// Plan, dry run, then apply to the target database.
+    Apply,
+}
+
+#[derive(Deserialize)]
+struct MigrateArgs {
+    tool: MigrationTool,
+    action: MigrateAction,
+    /// Target database; defaults to whatever the tool reads from the
+    /// environment or `.env`.
+    #[serde(default)]
+    database_url: Option<String>,
+    /// Disposable database at the same version as the target, used for the
+    /// compulsory dry run.
+    #[serde(default)]
+    shadow_database_url: Option<String>,
+    #[serde(default)]
+    workdir: Option<String>,
+    #[serde(default)]
+    timeout_ms: Option<u64>,
+}
+
+#[derive(Serialize)]
+struct MigrateResponse {
+    action: MigrateAction,
+    #[serde(flatten)]
+    plan: MigrationPlan,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dry_run_exit_code: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    apply_exit_code: Option<i32>,
+    /// Output of the step that failed, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    output: Option<String>,
+}
+
+/// State shared by every step of a single `migrate` call.
+struct Steps<'a> {
+    session: &'a Session,
+    turn: &'a TurnContext,
+    call_id: &'a str,
+    tool_name: &'a str,
+    tool: MigrationTool,
+    cwd: PathBuf,
+    timeout_ms: Option<u64>,
+}
+
+#[async_trait]
+impl ToolHandler for MigrateHandler {
+    fn kind(&self) -> ToolKind {
+        ToolKind::Function
+    }
+
+    fn is_mutating(&self, invocation: &ToolInvocation) -> bool {
+        match &invocation.payload {
+            ToolPayload::Function { arguments } => !matches!(
+                serde_json::from_str::<MigrateArgs>(arguments),
+                Ok(MigrateArgs {
+                    action: MigrateAction::Plan,
+                    ..
+                })
+            ),
+            _ => true,
+        }
+    }
+
+    async fn handle(&self, invocation: ToolInvocation) -> Result<ToolOutput, FunctionCallError> {
+        let ToolInvocation {
+            session,
+            turn,
+            call_id,
+            tool_name,
+            payload,
+            ..
+        } = invocation;
+
+        let ToolPayload::Function { arguments } = payload else {
+            return Err(FunctionCallError::RespondToModel(format!(
+                "unsupported payload for migrate handler: {tool_name}"
+            )));
+        };
+
+        let args: MigrateArgs = serde_json::from_str(&arguments).map_err(|err| {
+            FunctionCallError::RespondToModel(format!(
+                "failed to parse function arguments: {err:?}"
+            ))
+        })?;
+        let shadow_url = match args.action {
+            MigrateAction::Plan => None,
+            MigrateAction::DryRun | MigrateAction::Apply => Some(shadow_database_url(&args)?),
+        };
+
+        let steps = Steps {
+            session: session.as_ref(),
+            turn: turn.as_ref(),
+            call_id: &call_id,
+            tool_name: &tool_name,
+            tool: args.tool,
+            cwd: turn.resolve_path(args.workdir.clone()),
+            timeout_ms: args.timeout_ms,
+        };
+        let database_url = args.database_url.as_deref();
+
+        let plan = steps.plan(database_url).await?;
+        let mut response = MigrateResponse {
+            action: args.action,
+            plan,
+            dry_run_exit_code: None,
+            apply_exit_code: None,
+            output: None,
+        };
+        let Some(shadow_url) = shadow_url else {
+            return respond(&response, true);
+        };
+        if response.plan.pending.is_empty() && response.plan.sql.trim().is_empty() {
+            return respond(&response, true);
+        }
+
+        // The dry run only tells us something if the shadow database is at the
+        // same version as the target.
+        let shadow_plan = steps.plan(Some(shadow_url)).await?;
+        if shadow_plan.pending != response.plan.pending {
+            return Err(FunctionCallError::RespondToModel(format!(
+                "shadow database is not at the same version as the target (pending there: \
+                 {:?}, pending on target: {:?}); reset it before migrating",
+                shadow_plan.pending, response.plan.pending
+            )));
+        }
+
+        let dry_run = steps
+            .run(steps.tool.apply_args(), Some(shadow_url), None)
+            .await?;
+        response.dry_run_exit_code = Some(dry_run.exit_code);
+        if dry_run.exit_code != 0 {
+            response.output = Some(format_exec_output_str(&dry_run, turn.truncation_policy));
+            return respond(&response, false);
+        }
+        if args.action == MigrateAction::DryRun {
+            return respond(&response, true);
+        }
+
+        let applied = steps
+            .run(
+                steps.tool.apply_args(),
+                database_url,
+                Some(response.plan.sql.clone()),
+            )
+            .await?;
+        let succeeded = applied.exit_code == 0;
+        response.apply_exit_code = Some(applied.exit_code);
+        if !succeeded {
+            response.output = Some(format_exec_output_str(&applied, turn.truncation_policy));
+        }
+        respond(&response, succeeded)
+    }
+}
+
+impl Steps<'_> {
+    /// Run the tool's status command against `database_url` and collect the
+    /// pending migrations and their SQL.
+    async fn plan(&self, database_url: Option<&str>) -> Result<MigrationPlan, FunctionCallError> {
+        let output = self
+            .run(self.tool.status_args(), database_url, None)
+            .await?;
+        let pending = self.tool.parse_pending(&output.stdout.text);
+        // `prisma migrate status` exits non-zero when migrations are pending.
+        if output.exit_code != 0 && pending.is_empty() {
+            return Err(FunctionCallError::RespondToModel(format_exec_output_str(
+                &output,
+                self.turn.truncation_policy,
+            )));
+        }
+        self.tool
+            .load_plan(&self.cwd, &output.stdout.text)
+            .await
+            .map_err(FunctionCallError::RespondToModel)
+    }
+
+    /// Run one command under the orchestrator, emitting the usual exec events.
+    /// `sql` is set for the real apply, which always asks for approval unless
+    /// the approval policy is `never`.
+    async fn run(
+        &self,
+        command: Vec<String>,
+        database_url: Option<&str>,
+        sql: Option<String>,
+    ) -> Result<ExecToolCallOutput, FunctionCallError> {
+        let emitter = ToolEmitter::shell(
+            command.clone(),
+            self.cwd.clone(),
+            ExecCommandSource::Agent,
+            false,
+        );
+        let event_ctx = ToolEventCtx::new(self.session, self.turn, self.call_id, None);
+        emitter.begin(event_ctx).await;
+
+        let mut env = create_env(&self.turn.shell_environment_policy);
+        if let Some(url) = database_url {
+            env.insert(DATABASE_URL_ENV_VAR.to_string(), url.to_string());
+        }
+        let mut approval_requirement = create_approval_requirement_for_command(
+            &self.turn.exec_policy,
+            &command,
+            self.turn.approval_policy,
+            &self.turn.sandbox_policy,
+            SandboxPermissions::from(false),
+        );
+        if sql.is_some()
+            && self.turn.approval_policy != AskForApproval::Never
+            && !matches!(approval_requirement, ApprovalRequirement::Forbidden { .. })
+        {
+            approval_requirement = ApprovalRequirement::NeedsApproval { reason: None };
+        }
+        let req = MigrateRequest {
+            command,
+            cwd: self.cwd.clone(),
+            timeout_ms: self.timeout_ms,
+            env,
+            sql,
+            approval_requirement,
+        };
+        let mut orchestrator = ToolOrchestrator::new();
+        let mut runtime = MigrateRuntime::new();
+        let tool_ctx = ToolCtx {
+            session: self.session,
+            turn: self.turn,
+            call_id: self.call_id.to_string(),
+            tool_name: self.tool_name.to_string(),
+        };
+        let out = orchestrator
+            .run(
+                &mut runtime,
+                Arc::new(req),
+                &tool_ctx,
+                self.turn,
+                self.turn.approval_policy,
+            )
+            .await;
+
+        let event_ctx = ToolEventCtx::new(self.session, self.turn, self.call_id, None);
+        match out {
+            Ok(output) => {
+                emitter
+                    .emit(event_ctx, ToolEventStage::Success(output.clone()))
+                    .await;
+                Ok(output)
+            }
+            Err(err) => {
+                let message = emitter.finish(event_ctx, Err(err)).await?;
+                Err(FunctionCallError::RespondToModel(message))
+            }
+        }
+    }
+}
+
+fn shadow_database_url(args: &MigrateArgs) -> Result<&str, FunctionCallError> {
+    match args.shadow_database_url.as_deref() {
+        None => Err(FunctionCallError::RespondToModel(
+            "`shadow_database_url` is required: migrations are always dry-run against a shadow \
+             database first"
+                .to_string(),
+        )),
+        Some(shadow) if Some(shadow) == args.database_url.as_deref() => {
+            Err(FunctionCallError::RespondToModel(
+                "`shadow_database_url` must differ from `database_url`".to_string(),
+            ))
+        }
+        Some(shadow) => Ok(shadow),
+    }
+}
+
+fn respond(response: &MigrateResponse, success: bool) -> Result<ToolOutput, FunctionCallError> {
+    let content = serde_json::to_string(response).map_err(|err| {
+        FunctionCallError::Fatal(format!("failed to serialize migrate result: {err}"))
+    })?;
+    Ok(ToolOutput::Function {
+        content,
+        content_items: None,
+        success: Some(success),
+    })
+}
@@ -1,26 +1,84 @@
 pub mod apply_patch;
+mod artifacts;
+mod background_jobs;
+mod bench;
+mod clipboard;
+mod codemod;
+mod compose;
+mod coverage;
+mod custom;
+mod env_snapshot;
+mod file_info;
+mod find_replace;
+mod git;
 mod grep_files;
 mod list_dir;
 mod mcp;
 mod mcp_resource;
+mod migrate;
 mod plan;
+mod port;
+mod process;
+mod read_changes;
 mod read_file;
+mod scaffold;
+mod search;
 mod shell;
+mod test_runner;
 mod test_sync;
+mod trash;
+mod undo_patch;
 mod unified_exec;
 mod view_image;
+mod wait_for;
+mod web_fetch;
+mod write_file;
 
 pub use plan::PLAN_TOOL;
 
 pub use apply_patch::ApplyPatchHandler;
+pub use artifacts::ArtifactsHandler;
+pub use background_jobs::BackgroundJobsHandler;
+pub use bench::BenchHandler;
+pub use clipboard::ClipboardHandler;
+pub use codemod::CodemodHandler;
+pub use compose::ComposeHandler;
+pub use coverage::CoverageHandler;
+pub use custom::CustomToolHandler;
+pub use env_snapshot::EnvSnapshotHandler;
+pub use file_info::FileInfoHandler;
+pub use find_replace::FindReplaceHandler;
+pub use git::GitHandler;
 pub use grep_files::GrepFilesHandler;
 pub use list_dir::ListDirHandler;
 pub use mcp::McpHandler;
 pub use mcp_resource::McpResourceHandler;
+pub use migrate::MigrateHandler;
 pub use plan::PlanHandler;
+pub use port::PortHandler;
+pub use process::ProcessHandler;
+pub use read_changes::ReadChangesHandler;
 pub use read_file::ReadFileHandler;
+pub use scaffold::ScaffoldHandler;
+pub use search::SearchHandler;
 pub use shell::ShellCommandHandler;
 pub use shell::ShellHandler;
+pub use test_runner::TestRunnerHandler;
 pub use test_sync::TestSyncHandler;
+pub use trash::TrashHandler;
+pub use undo_patch::UndoPatchHandler;
 pub use unified_exec::UnifiedExecHandler;
 pub use view_image::ViewImageHandler;
+pub use wait_for::WaitForHandler;
+pub use web_fetch::WebFetchHandler;
+pub use write_file::WriteFileHandler;
+
+use serde::Serialize;
+
+use crate::function_tool::FunctionCallError;
+
+/// Serializes `value`, a `what` a handler responds with, for the model.
+pub(crate) fn to_json<T: Serialize>(value: &T, what: &str) -> Result<String, FunctionCallError> {
+    serde_json::to_string(value)
+        .map_err(|err| FunctionCallError::Fatal(format!("failed to serialize {what}: {err}")))
+}
@@ -0,0 +1,436 @@
+use std::io::ErrorKind;
+use std::time::Duration;
+use std::time::Instant;
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde::Serialize;
+use tokio::net::TcpListener;
+use tokio::net::TcpStream;
+use tokio::process::Command;
+use tokio::time::timeout;
+
+use crate::function_tool::FunctionCallError;
+use crate::tools::context::ToolInvocation;
+use crate::tools::context::ToolOutput;
+use crate::tools::context::ToolPayload;
+use crate::tools::handlers::to_json;
+use crate::tools::registry::ToolHandler;
+use crate::tools::registry::ToolKind;
+
+pub struct PortHandler;
+
+const DEFAULT_HOST: &str = "127.0.0.1";
+const DEFAULT_WAIT_TIMEOUT_MS: u64 = 30_000;
+const MAX_WAIT_TIMEOUT_MS: u64 = 10 * 60 * 1000;
+const DEFAULT_POLL_INTERVAL_MS: u64 = 250;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
+const LSOF_TIMEOUT: Duration = Duration::from_secs(10);
+
+fn default_host() -> String {
+    DEFAULT_HOST.to_string()
+}
+
+fn default_wait_timeout_ms() -> u64 {
+    DEFAULT_WAIT_TIMEOUT_MS
+}
+
+fn default_poll_interval_ms() -> u64 {
+    DEFAULT_POLL_INTERVAL_MS
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+enum PortArgs {
+    /// Report whether the port is free and who is listening on it.
+    Check {
+        port: u16,
+        #[serde(default = "default_host")]
+        host: String,
+    },
+    /// Poll until a TCP connection to the port succeeds.
+    Wait {
+        port: u16,
+        #[serde(default = "default_host")]
+        host: String,
+        #[serde(default = "default_wait_timeout_ms")]
+        timeout_ms: u64,
+        #[serde(default = "default_poll_interval_ms")]
+        interval_ms: u64,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct Listener {
+    address: String,
+    port: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pid: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    command: Option<String>,
+}
+
+#[derive(Serialize)]
+struct CheckResponse {
+    port: u16,
+    host: String,
+    free: bool,
+    listeners: Vec<Listener>,
+}
+
+#[derive(Serialize)]
+struct WaitResponse {
+    port: u16,
+    host: String,
+    accepting: bool,
+    waited_ms: i64,
+    attempts: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_error: Option<String>,
+}
+
+#[async_trait]
+impl ToolHandler for PortHandler {
+    fn kind(&self) -> ToolKind {
+        ToolKind::Function
+    }
+
+    async fn handle(&self, invocation: ToolInvocation) -> Result<ToolOutput, FunctionCallError> {
+        let ToolInvocation { payload, .. } = invocation;
+
+        let arguments = match payload {
+            ToolPayload::Function { arguments } => arguments,
+            _ => {
+                return Err(FunctionCallError::RespondToModel(
+                    "port handler received unsupported payload".to_string(),
+                ));
+            }
+        };
+
+        let args: PortArgs = serde_json::from_str(&arguments).map_err(|err| {
+            FunctionCallError::RespondToModel(format!(
+                "failed to parse function arguments: {err:?}"
+            ))
+        })?;
+
+        let (content, success) = match args {
+            PortArgs::Check { port, host } => {
+                let free = is_port_free(&host, port).await;
+                let listeners = find_listeners(port).await;
+                let response = CheckResponse {
+                    port,
+                    host,
+                    free,
+                    listeners,
+                };
+                (to_json(&response, "port status")?, true)
+            }
+            PortArgs::Wait {
+                port,
+                host,
+                timeout_ms,
+                interval_ms,
+            } => {
+                let response = wait_for_port(
+                    host,
+                    port,
+                    Duration::from_millis(timeout_ms.min(MAX_WAIT_TIMEOUT_MS)),
+                    Duration::from_millis(interval_ms.max(10)),
+                )
+                .await;
+                let accepting = response.accepting;
+                (to_json(&response, "port status")?, accepting)
+            }
+        };
+
+        Ok(ToolOutput::Function {
+            content,
+            content_items: None,
+            success: Some(success),
+        })
+    }
+}
+
+/// A port is free when we can bind it ourselves. Any bind error other than
+/// "address in use" (e.g. permission denied for privileged ports) is treated
+/// as free-but-unbindable, which is what the caller cares about.
+async fn is_port_free(host: &str, port: u16) -> bool {
+    match TcpListener::bind((host, port)).await {
+        Ok(_) => true,
+        Err(err) => err.kind() != ErrorKind::AddrInUse,
+    }
+}
+
+async fn wait_for_port(
+    host: String,
+    port: u16,
+    limit: Duration,
+    interval: Duration,
+) -> WaitResponse {
+    let started = Instant::now();
+    let mut attempts = 0;
+    let mut last_error = None;
+    let accepting = loop {
+        attempts += 1;
+        match timeout(CONNECT_TIMEOUT, TcpStream::connect((host.as_str(), port))).await {
+            Ok(Ok(_)) => break true,
+            Ok(Err(err)) => last_error = Some(err.to_string()),
+            Err(_) => last_error = Some("connection attempt timed out".to_string()),
+        }
+        if started.elapsed() + interval > limit {
+            break false;
+        }
+        tokio::time::sleep(interval).await;
+    };
+    WaitResponse {
+        port,
+        host,
+        accepting,
+        waited_ms: started.elapsed().as_millis() as i64,
+        attempts,
+        last_error: if accepting { None } else { last_error },
+    }
+}
+
+/// Best-effort lookup of listening sockets on `port`: procfs on Linux,
+/// `lsof` elsewhere. Returns an empty list when neither is available.
+async fn find_listeners(port: u16) -> Vec<Listener> {
+    if cfg!(target_os = "linux") {
+        let mut listeners = Vec::new();
+        for table in ["/proc/net/tcp", "/proc/net/tcp6"] {
+            if let Ok(contents) = tokio::fs::read_to_string(table).await {
+                listeners.extend(parse_proc_net_tcp(&contents, port));
+            }
+        }
+        let owners = socket_owners(&listeners).await;
+        return listeners
+            .into_iter()
+            .map(|(mut listener, inode)| {
+                if let Some((_, pid, command)) = owners.iter().find(|(owner, ..)| *owner == inode) {
+                    listener.pid = Some(*pid);
+                    listener.command = Some(command.clone());
+                }
+                listener
+            })
+            .collect();
+    }
+
+    let output = timeout(
+        LSOF_TIMEOUT,
+        Command::new("lsof")
+            .args(["-nP", &format!("-iTCP:{port}"), "-sTCP:LISTEN", "-Fpcn"])
+            .output(),
+    )
+    .await;
+    match output {
+        Ok(Ok(output)) => parse_lsof_output(&String::from_utf8_lossy(&output.stdout)),
+        _ => Vec::new(),
+    }
+}
+
+/// Parse `/proc/net/tcp{,6}` and return listening sockets bound to `port`
+/// together with their socket inode.
+fn parse_proc_net_tcp(contents: &str, port: u16) -> Vec<(Listener, String)> {
+    const TCP_LISTEN: &str = "0A";
+    contents
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 10 || fields[3] != TCP_LISTEN {
+                return None;
+            }
+            let (addr_hex, port_hex) = fields[1].split_once(':')?;
+            let local_port = u16::from_str_radix(port_hex, 16).ok()?;
+            if local_port != port {
+                return None;
+            }
+            let listener = Listener {
+                address: decode_proc_address(addr_hex)?,
+                port: local_port,
+                pid: None,
+                command: None,
+            };
+            Some((listener, fields[9].to_string()))
+        })
+        .collect()
+}
+
+/// procfs prints addresses as native-endian 32-bit words in hex.
+fn decode_proc_address(hex: &str) -> Option<String> {
+    let words = (0..hex.len() / 8)
+        .map(|i| u32::from_str_radix(&hex[i * 8..i * 8 + 8], 16))
+        .collect::<Result<Vec<u32>, _>>()
+        .ok()?;
+    let bytes: Vec<u8> = words.iter().flat_map(|word| word.to_ne_bytes()).collect();
+    match bytes.len() {
+        4 => Some(std::net::Ipv4Addr::new(bytes[0], bytes[1], bytes[2], bytes[3]).to_string()),
+        16 => {
+            let octets: [u8; 16] = bytes.try_into().ok()?;
+            Some(std::net::Ipv6Addr::from(octets).to_string())
+        }
+        _ => None,
+    }
+}
+
+/// Map socket inodes to `(inode, pid, command)` by scanning `/proc/*/fd`.
+/// Processes we cannot inspect are skipped silently.
+async fn socket_owners(listeners: &[(Listener, String)]) -> Vec<(String, i64, String)> {
+    if listeners.is_empty() {
+        return Vec::new();
+    }
+    let wanted: Vec<String> = listeners
+        .iter()
+        .map(|(_, inode)| format!("socket:[{inode}]"))
+        .collect();
+    let Ok(mut procs) = tokio::fs::read_dir("/proc").await else {
+        return Vec::new();
+    };
+    let mut owners = Vec::new();
+    while let Ok(Some(entry)) = procs.next_entry().await {
+        let Some(pid) = entry
+            .file_name()
+            .to_str()
+            .and_then(|name| name.parse::<i64>().ok())
+        else {
+            continue;
+        };
+        let Ok(mut fds) = tokio::fs::read_dir(entry.path().join("fd")).await else {
+            continue;
+        };
+        while let Ok(Some(fd)) = fds.next_entry().await {
+            let Ok(target) = tokio::fs::read_link(fd.path()).await else {
+                continue;
+            };
+            let target = target.to_string_lossy();
+            if let Some(socket) = wanted.iter().find(|w| **w == target) {
+                let inode = socket["socket:[".len()..socket.len() - 1].to_string();
+                let command = tokio::fs::read(entry.path().join("cmdline"))
+                    .await
+                    .map(|raw| {
+                        String::from_utf8_lossy(&raw)
+                            .split('\0')
+                            .filter(|part| !part.is_empty())
+                            .collect::<Vec<_>>()
+                            .join(" ")
+                    })
+                    .unwrap_or_default();
+                owners.push((inode, pid, command));
+            }
+        }
+    }
+    owners
+}
+
+/// Parse `lsof -F pcn` output: `p<pid>`, `c<command>` and one `n<addr:port>`
+/// line per socket.
+fn parse_lsof_output(output: &str) -> Vec<Listener> {
+    let mut listeners = Vec::new();
+    let mut pid = None;
+    let mut command = None;
+    for line in output.lines() {
+        let Some(tag) = line.chars().next() else {
+            continue;
+        };
+        let value = &line[tag.len_utf8()..];
+        match tag {
+            'p' => {
+                pid = value.parse::<i64>().ok();
+                command = None;
+            }
+            'c' => command = Some(value.to_string()),
+            'n' => {
+                if let Some((address, port)) = value.rsplit_once(':')
+                    && let Ok(port) = port.parse::<u16>()
+                {
+                    listeners.push(Listener {
+                        address: address.trim_matches(['[', ']']).to_string(),
+                        port,
+                        pid,
+                        command: command.clone(),
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+    listeners
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parses_listening_sockets_from_procfs() {
+        let contents = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 0100007F:0BB8 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 4242 1 0 100 0 0 10 0
+   1: 0100007F:0BB8 0100007F:D431 01 00000000:00000000 00:00000000 00000000  1000        0 4343 1 0 20 4 30 10 -1
+   2: 00000000:1F90 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 4444 1 0 100 0 0 10 0
+";
+        assert_eq!(
+            parse_proc_net_tcp(contents, 3000),
+            vec![(
+                Listener {
+                    address: "127.0.0.1".to_string(),
+                    port: 3000,
+                    pid: None,
+                    command: None,
+                },
+                "4242".to_string(),
+            )]
+        );
+    }
+
+    #[test]
+    fn decodes_ipv6_loopback() {
+        assert_eq!(
+            decode_proc_address("00000000000000000000000001000000"),
+            Some("::1".to_string())
+        );
+    }
+
+    #[test]
+    fn parses_lsof_field_output() {
+        let output = "p4321\ncnode\nn*:3000\nn[::1]:3000\np99\ncpython3\nn127.0.0.1:3000\n";
+        let node = |address: &str| Listener {
+            address: address.to_string(),
+            port: 3000,
+            pid: Some(4321),
+            command: Some("node".to_string()),
+        };
+        assert_eq!(
+            parse_lsof_output(output),
+            vec![
+                node("*"),
+                node("::1"),
+                Listener {
+                    address: "127.0.0.1".to_string(),
+                    port: 3000,
+                    pid: Some(99),
+                    command: Some("python3".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn bound_port_is_reported_busy_and_accepting() {
+        let listener = TcpListener::bind((DEFAULT_HOST, 0))
+            .await
+            .expect("bind ephemeral port");
+        let port = listener.local_addr().expect("local addr").port();
+
+        assert!(!is_port_free(DEFAULT_HOST, port).await);
+        let response = wait_for_port(
+            DEFAULT_HOST.to_string(),
+            port,
+            Duration::from_secs(5),
+            Duration::from_millis(10),
+        )
+        .await;
+        assert!(response.accepting);
+        assert_eq!(response.attempts, 1);
+    }
+}
@@ -0,0 +1,181 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use codex_protocol::protocol::AskForApproval;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::function_tool::FunctionCallError;
+use crate::tools::context::ToolInvocation;
+use crate::tools::context::ToolOutput;
+use crate::tools::context::ToolPayload;
+use crate::tools::handlers::to_json;
+use crate::tools::orchestrator::ToolOrchestrator;
+use crate::tools::registry::ToolHandler;
+use crate::tools::registry::ToolKind;
+use crate::tools::runtimes::process::KillSignal;
+use crate::tools::runtimes::process::ProcessKillRequest;
+use crate::tools::runtimes::process::ProcessKillRuntime;
+use crate::tools::runtimes::process::ScopedProcess;
+use crate::tools::runtimes::process::list_scoped_processes;
+use crate::tools::runtimes::process::process_start_time;
+use crate::tools::sandboxing::ApprovalRequirement;
+use crate::tools::sandboxing::ToolCtx;
+use crate::tools::sandboxing::ToolError;
+
+pub struct ProcessHandler;
+
+#[derive(Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+enum ProcessArgs {
+    List {
+        #[serde(default)]
+        filter: Option<String>,
+    },
+    Inspect {
+        pid: i64,
+    },
+    Kill {
+        pid: i64,
+        #[serde(default = "default_signal")]
+        signal: KillSignal,
+    },
+}
+
+fn default_signal() -> KillSignal {
+    KillSignal::Term
+}
+
+#[derive(Serialize)]
+struct InspectResponse<'a> {
+    #[serde(flatten)]
+    process: &'a ScopedProcess,
+    children: Vec<i64>,
+}
+
+#[async_trait]
+impl ToolHandler for ProcessHandler {
+    fn kind(&self) -> ToolKind {
+        ToolKind::Function
+    }
+
+    fn is_mutating(&self, invocation: &ToolInvocation) -> bool {
+        match &invocation.payload {
+            ToolPayload::Function { arguments } => !matches!(
+                serde_json::from_str::<ProcessArgs>(arguments),
+                Ok(ProcessArgs::List { .. } | ProcessArgs::Inspect { .. })
+            ),
+            _ => true,
+        }
+    }
+
+    async fn handle(&self, invocation: ToolInvocation) -> Result<ToolOutput, FunctionCallError> {
+        let ToolInvocation {
+            session,
+            turn,
+            call_id,
+            tool_name,
+            payload,
+            ..
+        } = invocation;
+
+        let ToolPayload::Function { arguments } = payload else {
+            return Err(FunctionCallError::RespondToModel(format!(
+                "unsupported payload for process handler: {tool_name}"
+            )));
+        };
+
+        let args: ProcessArgs = serde_json::from_str(&arguments).map_err(|err| {
+            FunctionCallError::RespondToModel(format!(
+                "failed to parse function arguments: {err:?}"
+            ))
+        })?;
+
+        let processes = list_scoped_processes(&turn.cwd)
+            .await
+            .map_err(FunctionCallError::RespondToModel)?;
+
+        let content = match args {
+            ProcessArgs::List { filter } => {
+                let processes: Vec<&ScopedProcess> = processes
+                    .iter()
+                    .filter(|p| {
+                        filter
+                            .as_deref()
+                            .is_none_or(|needle| p.info.command.contains(needle))
+                    })
+                    .collect();
+                to_json(&processes, "process listing")?
+            }
+            ProcessArgs::Inspect { pid } => {
+                let process = find_process(&processes, pid)?;
+                let children = processes
+                    .iter()
+                    .filter(|p| p.info.ppid == pid)
+                    .map(|p| p.info.pid)
+                    .collect();
+                to_json(&InspectResponse { process, children }, "process listing")?
+            }
+            ProcessArgs::Kill { pid, signal } => {
+                let process = find_process(&processes, pid)?;
+                let req = ProcessKillRequest {
+                    pid,
+                    signal,
+                    command: process.info.command.clone(),
+                    started: process_start_time(pid),
+                    cwd: turn.cwd.clone(),
+                    approval_requirement: match turn.approval_policy {
+                        AskForApproval::Never => ApprovalRequirement::Skip {
+                            bypass_sandbox: false,
+                        },
+                        _ => ApprovalRequirement::NeedsApproval { reason: None },
+                    },
+                };
+                let mut orchestrator = ToolOrchestrator::new();
+                let mut runtime = ProcessKillRuntime::new();
+                let tool_ctx = ToolCtx {
+                    session: session.as_ref(),
+                    turn: turn.as_ref(),
+                    call_id: call_id.clone(),
+                    tool_name: tool_name.to_string(),
+                };
+                orchestrator
+                    .run(
+                        &mut runtime,
+                        Arc::new(req),
+                        &tool_ctx,
+                        &turn,
+                        turn.approval_policy,
+                    )
+                    .await
+                    .map_err(|err| {
+                        let reason = match err {
+                            ToolError::Rejected(reason) => reason,
+                            ToolError::Codex(err) => err.to_string(),
+                        };
+                        FunctionCallError::RespondToModel(format!(
+                            "failed to terminate process {pid}: {reason}"
+                        ))
+                    })?;
+                format!("sent SIG{} to process {pid}", signal.name())
+            }
+        };
+
+        Ok(ToolOutput::Function {
+            content,
+            content_items: None,
+            success: Some(true),
+        })
+    }
+}
+
+fn find_process(
+    processes: &[ScopedProcess],
+    pid: i64,
+) -> Result<&ScopedProcess, FunctionCallError> {
+    processes.iter().find(|p| p.info.pid == pid).ok_or_else(|| {
+        FunctionCallError::RespondToModel(format!(
+            "process {pid} was not started by this session and does not belong to the workspace"
+        ))
+    })
+}
@@ -0,0 +1,182 @@
+use std::path::Path;
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use codex_apply_patch::TextFormat;
+use serde::Deserialize;
+use similar::TextDiff;
+
+use crate::codex::Session;
+use crate::file_placeholder;
+use crate::function_tool::FunctionCallError;
+use crate::sensitive_content::filter_read;
+use crate::tools::context::ToolInvocation;
+use crate::tools::context::ToolOutput;
+use crate::tools::context::ToolPayload;
+use crate::tools::registry::ToolHandler;
+use crate::tools::registry::ToolKind;
+
+pub struct ReadChangesHandler;
+
+/// Larger files are not kept in memory; `read_changes` asks for a full read
+/// of them instead.
+const MAX_SERVED_VERSION_BYTES: u64 = 1024 * 1024;
+
+const CONTEXT_LINES: usize = 3;
+
+#[derive(Deserialize)]
+struct ReadChangesArgs {
+    file_path: String,
+}
+
+#[async_trait]
+impl ToolHandler for ReadChangesHandler {
+    fn kind(&self) -> ToolKind {
+        ToolKind::Function
+    }
+
+    async fn handle(&self, invocation: ToolInvocation) -> Result<ToolOutput, FunctionCallError> {
+        let ToolInvocation {
+            session,
+            turn,
+            call_id,
+            tool_name,
+            payload,
+            ..
+        } = invocation;
+
+        let ToolPayload::Function { arguments } = payload else {
+            return Err(FunctionCallError::RespondToModel(format!(
+                "unsupported payload for read_changes handler: {tool_name}"
+            )));
+        };
+
+        let args: ReadChangesArgs = serde_json::from_str(&arguments).map_err(|err| {
+            FunctionCallError::RespondToModel(format!(
+                "failed to parse function arguments: {err:?}"
+            ))
+        })?;
+        let path = PathBuf::from(&args.file_path);
+        if !path.is_absolute() {
+            return Err(FunctionCallError::RespondToModel(
+                "file_path must be an absolute path".to_string(),
+            ));
+        }
+
+        let Some(previous) = session.served_version(&path).await else {
+            return Err(FunctionCallError::RespondToModel(format!(
+                "{} has not been read in this session; use read_file instead",
+                path.display()
+            )));
+        };
+        if let Ok(Some(placeholder)) = file_placeholder::inspect(&path).await {
+            return Ok(ToolOutput::Function {
+                content: placeholder.to_string(),
+                content_items: None,
+                success: Some(true),
+            });
+        }
+        let current = read_text(&path).await.map_err(|err| {
+            FunctionCallError::RespondToModel(format!("failed to read file: {err}"))
+        })?;
+
+        let diff = diff_since(&previous, &current, &path);
+        session.record_served_version(path.clone(), current).await;
+        let content = match diff {
+            Some(diff) => filter_read(
+                session.as_ref(),
+                turn.as_ref(),
+                &call_id,
+                std::slice::from_ref(&path),
+                diff,
+            )
+            .await
+            .map_err(FunctionCallError::RespondToModel)?,
+            None => "No changes since the file was last read.".to_string(),
+        };
+        session
+            .record_file_read(path, &tool_name, content.len() as u64)
+            .await;
+        Ok(ToolOutput::Function {
+            content,
+            content_items: None,
+            success: Some(true),
+        })
+    }
+}
+
+/// Remember the current contents of `path` as the version the model has seen,
+/// after `read_file` returned (part of) it.
+pub(crate) async fn record_served_version(session: &Session, path: &Path) {
+    let small_enough = tokio::fs::metadata(path)
+        .await
+        .is_ok_and(|metadata| metadata.len() <= MAX_SERVED_VERSION_BYTES);
+    if !small_enough {
+        return;
+    }
+    if let Ok(contents) = read_text(path).await {
+        session
+            .record_served_version(path.to_path_buf(), contents)
+            .await;
+    }
+}
+
+/// Contents of `path` as normalized UTF-8 text.
+async fn read_text(path: &Path) -> std::io::Result<String> {
+    let bytes = tokio::fs::read(path).await?;
+    match TextFormat::decode(&bytes) {
+        Some((text, _)) => Ok(text),
+        None => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "file is not text",
+        )),
+    }
+}
+
+/// Unified diff from `previous` to `current`, or `None` if they are equal.
+fn diff_since(previous: &str, current: &str, path: &Path) -> Option<String> {
+    if previous == current {
+        return None;
+    }
+    let display = path.display();
+    Some(
+        TextDiff::from_lines(previous, current)
+            .unified_diff()
+            .context_radius(CONTEXT_LINES)
+            .header(
+                &format!("{display} (last read)"),
+                &format!("{display} (now)"),
+            )
+            .to_string(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn diffs_against_the_last_served_version() {
+        let path = Path::new("/repo/src/lib.rs");
+        let previous = "a\nb\nc\nd\ne\nf\ng\nh\ni\n";
+        let current = "a\nb\nc\nd\ne\nf\ng\nH\ni\n";
+
+        assert_eq!(diff_since(previous, previous, path), None);
+        assert_eq!(
+            diff_since(previous, current, path),
+            Some(
+                "--- /repo/src/lib.rs (last read)\n\
+                 +++ /repo/src/lib.rs (now)\n\
+                 @@ -5,5 +5,5 @@\n \
+                 e\n \
+                 f\n \
+                 g\n\
+                 -h\n\
+                 +H\n \
+                 i\n"
+                .to_string()
+            )
+        );
+    }
+}
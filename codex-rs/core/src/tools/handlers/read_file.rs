@@ -1,16 +1,39 @@
 use std::collections::VecDeque;
+use std::io::Cursor;
 use std::path::PathBuf;
+use std::sync::Arc;
 
 use async_trait::async_trait;
+use codex_apply_patch::TextFormat;
 use codex_utils_string::take_bytes_at_char_boundary;
 use serde::Deserialize;
 
+use crate::exec_policy::create_approval_requirement_for_command;
+use crate::features::Feature;
+use crate::file_placeholder;
+use crate::file_placeholder::HUGE_FILE_BYTES;
 use crate::function_tool::FunctionCallError;
+use crate::protocol::EventMsg;
+use crate::protocol::FileReadEvent;
+use crate::protocol::FileReadLine;
+use crate::rendered_diff::language_for_path;
+use crate::replay_bundle::ReplayInput;
+use crate::sandboxing::SandboxPermissions;
+use crate::sensitive_content::filter_read;
+use crate::syntax_highlight::highlight_lines;
 use crate::tools::context::ToolInvocation;
 use crate::tools::context::ToolOutput;
 use crate::tools::context::ToolPayload;
+use crate::tools::handlers::read_changes::record_served_version;
+use crate::tools::orchestrator::ToolOrchestrator;
 use crate::tools::registry::ToolHandler;
 use crate::tools::registry::ToolKind;
+use crate::tools::runtimes::read_file::ReadFileOutput;
+use crate::tools::runtimes::read_file::ReadFileRequest;
+use crate::tools::runtimes::read_file::ReadFileRuntime;
+use crate::tools::runtimes::read_file::ReadRange;
+use crate::tools::sandboxing::ToolCtx;
+use crate::tools::sandboxing::ToolError;
 
 pub struct ReadFileHandler;
 
@@ -37,6 +60,12 @@ struct ReadFileArgs {
     /// Optional indentation configuration used when `mode` is `Indentation`.
     #[serde(default)]
     indentation: Option<IndentationArgs>,
+    /// Read raw bytes from this 0-based offset instead of lines.
+    #[serde(default)]
+    byte_offset: Option<u64>,
+    /// Number of raw bytes to read; defaults to the rest of the file.
+    #[serde(default)]
+    byte_limit: Option<u64>,
 }
 
 #[derive(Deserialize)]
@@ -96,7 +125,14 @@ impl ToolHandler for ReadFileHandler {
     }
 
     async fn handle(&self, invocation: ToolInvocation) -> Result<ToolOutput, FunctionCallError> {
-        let ToolInvocation { payload, .. } = invocation;
+        let ToolInvocation {
+            session,
+            turn,
+            call_id,
+            tool_name,
+            payload,
+            ..
+        } = invocation;
 
         let arguments = match payload {
             ToolPayload::Function { arguments } => arguments,
@@ -119,6 +155,8 @@ impl ToolHandler for ReadFileHandler {
             limit,
             mode,
             indentation,
+            byte_offset,
+            byte_limit,
         } = args;
 
         if offset == 0 {
@@ -140,15 +178,163 @@ impl ToolHandler for ReadFileHandler {
             ));
         }
 
-        let collected = match mode {
-            ReadMode::Slice => slice::read(&path, offset, limit).await?,
+        let config = turn.client.config();
+        let range = match (byte_offset, byte_limit, &mode) {
+            (None, None, ReadMode::Slice) => ReadRange::Lines { offset, limit },
+            // Indentation mode looks around the anchor line, so it needs the
+            // whole file; the placeholder check below bounds its size.
+            (None, None, ReadMode::Indentation) => ReadRange::All,
+            (offset, limit, _) => ReadRange::Bytes {
+                offset: offset.unwrap_or(0),
+                limit: limit.unwrap_or(u64::MAX),
+            },
+        };
+
+        let read_ahead = session.services.read_ahead.as_ref();
+        let prefetched = match read_ahead {
+            Some(read_ahead) => read_ahead.get(&path).await,
+            None => None,
+        };
+        // Byte ranges are how binary files are meant to be inspected.
+        if !matches!(range, ReadRange::Bytes { .. }) {
+            let placeholder = match &prefetched {
+                Some(contents) => file_placeholder::inspect_bytes(contents),
+                // Errors surface from the read below with the usual message.
+                None => file_placeholder::inspect(&path).await.ok().flatten(),
+            };
+            if let Some(placeholder) = placeholder {
+                return Ok(ToolOutput::Function {
+                    content: placeholder.to_string(),
+                    content_items: None,
+                    success: Some(true),
+                });
+            }
+        }
+
+        let cat = vec!["cat".to_string(), path.display().to_string()];
+        let req = ReadFileRequest {
+            path: path.clone(),
+            cwd: turn.cwd.clone(),
+            range,
+            max_bytes: match range {
+                ReadRange::All => HUGE_FILE_BYTES as usize,
+                _ => config.read_file_max_bytes,
+            },
+            prefetched,
+            approval_requirement: create_approval_requirement_for_command(
+                &turn.exec_policy,
+                &cat,
+                turn.approval_policy,
+                &turn.sandbox_policy,
+                SandboxPermissions::from(false),
+            ),
+        };
+        let mut orchestrator = ToolOrchestrator::new();
+        let mut runtime = ReadFileRuntime::new();
+        let tool_ctx = ToolCtx {
+            session: session.as_ref(),
+            turn: turn.as_ref(),
+            call_id: call_id.clone(),
+            tool_name: tool_name.clone(),
+        };
+        let output = orchestrator
+            .run(
+                &mut runtime,
+                Arc::new(req),
+                &tool_ctx,
+                &turn,
+                turn.approval_policy,
+            )
+            .await
+            .map_err(|err| match err {
+                ToolError::Rejected(reason) => FunctionCallError::RespondToModel(reason),
+                ToolError::Codex(err) => FunctionCallError::RespondToModel(err.to_string()),
+            })?;
+
+        if let Some(replay) = &session.services.replay {
+            replay
+                .record_input(&call_id, ReplayInput::File(&path), &output.contents)
+                .await;
+        }
+
+        if let ReadRange::Bytes { offset, .. } = range {
+            let content = filter_read(
+                session.as_ref(),
+                turn.as_ref(),
+                &call_id,
+                std::slice::from_ref(&path),
+                format_byte_range(offset, &output),
+            )
+            .await
+            .map_err(FunctionCallError::RespondToModel)?;
+            session
+                .record_file_read(path, &tool_name, content.len() as u64)
+                .await;
+            return Ok(ToolOutput::Function {
+                content,
+                content_items: None,
+                success: Some(true),
+            });
+        }
+
+        // Legacy-encoded files are converted to UTF-8 here; apply_patch
+        // writes edits back in the same encoding.
+        let (text, format) = TextFormat::decode(&output.contents).unwrap_or_else(|| {
+            let text = String::from_utf8_lossy(&output.contents).into_owned();
+            (text, TextFormat::default())
+        });
+        let encoding = format.encoding();
+        let reader = Cursor::new(text.as_bytes());
+        let mut collected = match mode {
+            // The runtime returned the requested lines, starting at `offset`.
+            ReadMode::Slice => slice::read_from(reader, offset, offset, limit).await?,
             ReadMode::Indentation => {
                 let indentation = indentation.unwrap_or_default();
-                indentation::read_block(&path, offset, limit, indentation).await?
+                indentation::read_block_from(reader, offset, limit, indentation).await?
             }
         };
+        if output.truncated {
+            let next = offset + collected.len();
+            collected.push(format!(
+                "[output truncated at {} bytes; continue from line {next}]",
+                config.read_file_max_bytes
+            ));
+        }
+        if let Some(read_ahead) = read_ahead {
+            read_ahead.prefetch_related(path.clone());
+        }
+        let content = filter_read(
+            session.as_ref(),
+            turn.as_ref(),
+            &call_id,
+            std::slice::from_ref(&path),
+            collected.join("\n"),
+        )
+        .await
+        .map_err(FunctionCallError::RespondToModel)?;
+        if session.services.file_read_events {
+            session
+                .send_event(
+                    turn.as_ref(),
+                    EventMsg::FileRead(file_read_event(call_id, path.clone(), &content)),
+                )
+                .await;
+        }
+        let content = if encoding.is_utf8() {
+            content
+        } else {
+            let name = encoding.name();
+            format!("[encoding: {name}; shown as UTF-8, edits are saved as {name}]\n{content}")
+        };
+        if turn.tools_config.include_read_changes_tool || session.enabled(Feature::PatchMerge).await
+        {
+            record_served_version(session.as_ref(), &path).await;
+        }
+        session
+            .record_file_read(path, &tool_name, content.len() as u64)
+            .await;
         Ok(ToolOutput::Function {
-            content: collected.join("\n"),
+            content,
             content_items: None,
             success: Some(true),
         })
@@ -158,23 +344,19 @@ impl ToolHandler for ReadFileHandler {
 mod slice {
     use crate::function_tool::FunctionCallError;
     use crate::tools::handlers::read_file::format_line;
-    use std::path::Path;
-    use tokio::fs::File;
+    use tokio::io::AsyncBufRead;
     use tokio::io::AsyncBufReadExt;
-    use tokio::io::BufReader;
 
-    pub async fn read(
-        path: &Path,
+    /// Collects `limit` lines starting at line `offset`, where the reader's
+    /// first line is line `first_line`.
+    pub async fn read_from<R: AsyncBufRead + Unpin>(
+        mut reader: R,
+        first_line: usize,
         offset: usize,
         limit: usize,
     ) -> Result<Vec<String>, FunctionCallError> {
-        let file = File::open(path).await.map_err(|err| {
-            FunctionCallError::RespondToModel(format!("failed to read file: {err}"))
-        })?;
-
-        let mut reader = BufReader::new(file);
         let mut collected = Vec::new();
-        let mut seen = 0usize;
+        let mut seen = first_line - 1;
         let mut buffer = Vec::new();
 
         loop {
@@ -230,13 +412,11 @@ mod indentation {
     use crate::tools::handlers::read_file::format_line;
     use crate::tools::handlers::read_file::trim_empty_lines;
     use std::collections::VecDeque;
-    use std::path::Path;
-    use tokio::fs::File;
+    use tokio::io::AsyncBufRead;
     use tokio::io::AsyncBufReadExt;
-    use tokio::io::BufReader;
 
-    pub async fn read_block(
-        path: &Path,
+    pub async fn read_block_from<R: AsyncBufRead + Unpin>(
+        reader: R,
         offset: usize,
         limit: usize,
         options: IndentationArgs,
@@ -255,7 +435,7 @@ mod indentation {
             ));
         }
 
-        let collected = collect_file_lines(path).await?;
+        let collected = collect_lines(reader).await?;
         if collected.is_empty() || anchor_line > collected.len() {
             return Err(FunctionCallError::RespondToModel(
                 "anchor_line exceeds file length".to_string(),
@@ -367,12 +547,9 @@ mod indentation {
             .collect())
     }
 
-    async fn collect_file_lines(path: &Path) -> Result<Vec<LineRecord>, FunctionCallError> {
-        let file = File::open(path).await.map_err(|err| {
-            FunctionCallError::RespondToModel(format!("failed to read file: {err}"))
-        })?;
-
-        let mut reader = BufReader::new(file);
+    async fn collect_lines<R: AsyncBufRead + Unpin>(
+        mut reader: R,
+    ) -> Result<Vec<LineRecord>, FunctionCallError> {
         let mut buffer = Vec::new();
         let mut lines = Vec::new();
         let mut number = 0usize;
@@ -431,6 +608,56 @@ mod indentation {
     }
 }
 
+/// Describes the `L{n}: ` lines of a `read_file` result for frontends, with
+/// syntax tokens when the file's language has a built-in grammar.
+fn file_read_event(call_id: String, path: PathBuf, content: &str) -> FileReadEvent {
+    let mut lines: Vec<FileReadLine> = content
+        .lines()
+        .filter_map(|line| {
+            let (number, text) = line.strip_prefix('L')?.split_once(": ")?;
+            Some(FileReadLine {
+                number: number.parse().ok()?,
+                text: text.to_string(),
+                tokens: Vec::new(),
+            })
+        })
+        .collect();
+    let language = language_for_path(&path);
+    let texts: Vec<&str> = lines.iter().map(|line| line.text.as_str()).collect();
+    if let Some(tokens) = language.and_then(|language| highlight_lines(language, &texts)) {
+        for (line, tokens) in lines.iter_mut().zip(tokens) {
+            line.tokens = tokens;
+        }
+    }
+    FileReadEvent {
+        call_id,
+        path,
+        language: language.map(str::to_string),
+        lines,
+    }
+}
+
+/// A byte range of a binary file is shown as a hex dump, 16 bytes to a line;
+/// one of a text file as text.
+fn format_byte_range(offset: u64, output: &ReadFileOutput) -> String {
+    let end = offset + output.contents.len() as u64;
+    let file_len = output.file_len;
+    let mut lines = vec![format!("[bytes {offset}..{end} of {file_len}]")];
+    if output.binary {
+        for (index, chunk) in output.contents.chunks(16).enumerate() {
+            let address = offset + (index * 16) as u64;
+            let hex: Vec<String> = chunk.iter().map(|byte| format!("{byte:02x}")).collect();
+            lines.push(format!("{address:08x}: {}", hex.join(" ")));
+        }
+    } else {
+        lines.push(String::from_utf8_lossy(&output.contents).into_owned());
+    }
+    if output.truncated {
+        lines.push(format!("[output truncated; continue from byte {end}]"));
+    }
+    lines.join("\n")
+}
+
 fn format_line(bytes: &[u8]) -> String {
     let decoded = String::from_utf8_lossy(bytes);
     if decoded.len() > MAX_LINE_LENGTH {
@@ -493,11 +720,31 @@ mod defaults {
 
 #[cfg(test)]
 mod tests {
-    use super::indentation::read_block;
-    use super::slice::read;
     use super::*;
     use pretty_assertions::assert_eq;
+    use std::path::Path;
     use tempfile::NamedTempFile;
+    use tokio::fs::File;
+    use tokio::io::BufReader;
+
+    async fn read(
+        path: &Path,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<String>, FunctionCallError> {
+        let file = File::open(path).await.expect("open file");
+        slice::read_from(BufReader::new(file), 1, offset, limit).await
+    }
+
+    async fn read_block(
+        path: &Path,
+        offset: usize,
+        limit: usize,
+        options: IndentationArgs,
+    ) -> Result<Vec<String>, FunctionCallError> {
+        let file = File::open(path).await.expect("open file");
+        indentation::read_block_from(BufReader::new(file), offset, limit, options).await
+    }
 
     #[tokio::test]
     async fn reads_requested_range() -> anyhow::Result<()> {
@@ -996,4 +1243,38 @@ private:
         );
         Ok(())
     }
+
+    #[test]
+    fn file_read_event_numbers_and_highlights_lines() {
+        let event = file_read_event(
+            "call-1".to_string(),
+            PathBuf::from("/repo/run.sh"),
+            "L3: # go\nL4: \n[notice]",
+        );
+
+        assert_eq!(
+            event,
+            FileReadEvent {
+                call_id: "call-1".to_string(),
+                path: PathBuf::from("/repo/run.sh"),
+                language: Some("bash".to_string()),
+                lines: vec![
+                    FileReadLine {
+                        number: 3,
+                        text: "# go".to_string(),
+                        tokens: vec![crate::protocol::SyntaxToken {
+                            start: 0,
+                            end: 4,
+                            kind: "comment".to_string(),
+                        }],
+                    },
+                    FileReadLine {
+                        number: 4,
+                        text: String::new(),
+                        tokens: Vec::new(),
+                    },
+                ],
+            }
+        );
+    }
 }
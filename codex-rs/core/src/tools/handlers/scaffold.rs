@@ -0,0 +1,461 @@
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::function_tool::FunctionCallError;
+use crate::tools::context::ToolInvocation;
+use crate::tools::context::ToolOutput;
+use crate::tools::context::ToolPayload;
+use crate::tools::handlers::apply_patch::apply_patch_input;
+use crate::tools::patch_builder::PatchBuilder;
+use crate::tools::registry::ToolHandler;
+use crate::tools::registry::ToolKind;
+
+pub struct ScaffoldHandler;
+
+/// Template directory, relative to the project root or to `CODEX_HOME`.
+/// Project templates shadow user/org templates of the same name.
+const TEMPLATES_DIR: &str = ".codex/templates";
+const CODEX_HOME_TEMPLATES_DIR: &str = "templates";
+/// Suffix stripped from template file names, so templates can contain files
+/// such as `Cargo.toml` without tooling picking them up.
+const TEMPLATE_SUFFIX: &str = ".tmpl";
+const MAX_TEMPLATE_FILES: usize = 200;
+
+#[derive(Deserialize)]
+struct ScaffoldArgs {
+    /// Template to instantiate; omit to list the available templates.
+    #[serde(default)]
+    template: Option<String>,
+    /// Directory the template is rendered into, relative to the turn cwd.
+    #[serde(default)]
+    dest: Option<String>,
+    #[serde(default)]
+    variables: BTreeMap<String, String>,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+struct TemplateSummary {
+    name: String,
+    source: PathBuf,
+    variables: BTreeSet<String>,
+    files: Vec<String>,
+}
+
+/// One file of a template: its path relative to the template root and its
+/// (unrendered) contents.
+struct TemplateFile {
+    path: String,
+    contents: String,
+}
+
+#[async_trait]
+impl ToolHandler for ScaffoldHandler {
+    fn kind(&self) -> ToolKind {
+        ToolKind::Function
+    }
+
+    fn is_mutating(&self, invocation: &ToolInvocation) -> bool {
+        match &invocation.payload {
+            ToolPayload::Function { arguments } => !matches!(
+                serde_json::from_str::<ScaffoldArgs>(arguments),
+                Ok(ScaffoldArgs { template: None, .. })
+            ),
+            _ => true,
+        }
+    }
+
+    async fn handle(&self, invocation: ToolInvocation) -> Result<ToolOutput, FunctionCallError> {
+        let ToolInvocation {
+            session,
+            turn,
+            tracker,
+            call_id,
+            tool_name,
+            payload,
+        } = invocation;
+
+        let ToolPayload::Function { arguments } = payload else {
+            return Err(FunctionCallError::RespondToModel(format!(
+                "unsupported payload for scaffold handler: {tool_name}"
+            )));
+        };
+
+        let args: ScaffoldArgs = serde_json::from_str(&arguments).map_err(|err| {
+            FunctionCallError::RespondToModel(format!(
+                "failed to parse function arguments: {err:?}"
+            ))
+        })?;
+
+        let roots = [
+            turn.cwd.join(TEMPLATES_DIR),
+            turn.client
+                .config()
+                .codex_home
+                .join(CODEX_HOME_TEMPLATES_DIR),
+        ];
+
+        let Some(name) = args.template else {
+            let templates = list_templates(&roots).await;
+            let content = serde_json::to_string(&templates).map_err(|err| {
+                FunctionCallError::Fatal(format!("failed to serialize templates: {err}"))
+            })?;
+            return Ok(ToolOutput::Function {
+                content,
+                content_items: None,
+                success: Some(true),
+            });
+        };
+
+        if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+            return Err(FunctionCallError::RespondToModel(format!(
+                "invalid template name `{name}`"
+            )));
+        }
+        let mut template_dir = None;
+        for root in &roots {
+            let candidate = root.join(&name);
+            if tokio::fs::metadata(&candidate)
+                .await
+                .is_ok_and(|meta| meta.is_dir())
+            {
+                template_dir = Some(candidate);
+                break;
+            }
+        }
+        let Some(template_dir) = template_dir else {
+            return Err(FunctionCallError::RespondToModel(format!(
+                "template `{name}` not found in {TEMPLATES_DIR} or $CODEX_HOME/{CODEX_HOME_TEMPLATES_DIR}"
+            )));
+        };
+        let files = read_template(&template_dir)
+            .await
+            .map_err(FunctionCallError::RespondToModel)?;
+
+        let dest = PathBuf::from(args.dest.unwrap_or_default());
+        let rendered = render_template(&files, &args.variables).map_err(|missing| {
+            FunctionCallError::RespondToModel(format!(
+                "missing template variables: {}",
+                missing.into_iter().collect::<Vec<_>>().join(", ")
+            ))
+        })?;
+
+        let mut builder = PatchBuilder::new();
+        for (path, contents) in rendered {
+            let path = dest.join(path);
+            if tokio::fs::symlink_metadata(turn.cwd.join(&path))
+                .await
+                .is_ok()
+            {
+                return Err(FunctionCallError::RespondToModel(format!(
+                    "{} already exists; scaffolding never overwrites files",
+                    path.display()
+                )));
+            }
+            builder.add_file(&path, &contents);
+        }
+        if builder.is_empty() {
+            return Err(FunctionCallError::RespondToModel(format!(
+                "template `{name}` has no files"
+            )));
+        }
+
+        apply_patch_input(
+            &session,
+            &turn,
+            &tracker,
+            &call_id,
+            &tool_name,
+            builder.build(),
+        )
+        .await
+    }
+}
+
+async fn list_templates(roots: &[PathBuf]) -> Vec<TemplateSummary> {
+    let mut seen = BTreeSet::new();
+    let mut templates = Vec::new();
+    for root in roots {
+        let Ok(mut entries) = tokio::fs::read_dir(root).await else {
+            continue;
+        };
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let is_dir = entry.file_type().await.is_ok_and(|ty| ty.is_dir());
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if !is_dir || name.starts_with('.') || !seen.insert(name.clone()) {
+                continue;
+            }
+            let Ok(files) = read_template(&entry.path()).await else {
+                continue;
+            };
+            let mut variables = BTreeSet::new();
+            for file in &files {
+                collect_variables(&file.path, &mut variables);
+                collect_variables(&file.contents, &mut variables);
+            }
+            templates.push(TemplateSummary {
+                name,
+                source: entry.path(),
+                variables,
+                files: files.into_iter().map(|file| file.path).collect(),
+            });
+        }
+    }
+    templates.sort_by(|a, b| a.name.cmp(&b.name));
+    templates
+}
+
+async fn read_template(root: &Path) -> Result<Vec<TemplateFile>, String> {
+    let mut files = Vec::new();
+    let mut queue = vec![PathBuf::new()];
+    while let Some(relative) = queue.pop() {
+        let mut entries = tokio::fs::read_dir(root.join(&relative))
+            .await
+            .map_err(|err| format!("failed to read template {}: {err}", root.display()))?;
+        while let Some(entry) = entries
+            .next_entry()
+            .await
+            .map_err(|err| format!("failed to read template {}: {err}", root.display()))?
+        {
+            let path = relative.join(entry.file_name());
+            let file_type = entry
+                .file_type()
+                .await
+                .map_err(|err| format!("failed to read {}: {err}", path.display()))?;
+            if file_type.is_dir() {
+                queue.push(path);
+            } else if file_type.is_file() {
+                let contents = tokio::fs::read_to_string(entry.path())
+                    .await
+                    .map_err(|err| {
+                        format!("template file {} is not text: {err}", path.display())
+                    })?;
+                files.push(TemplateFile {
+                    path: path.to_string_lossy().replace('\\', "/"),
+                    contents,
+                });
+                if files.len() > MAX_TEMPLATE_FILES {
+                    return Err(format!(
+                        "template {} has more than {MAX_TEMPLATE_FILES} files",
+                        root.display()
+                    ));
+                }
+            }
+        }
+    }
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(files)
+}
+
+/// Render file paths and contents. Returns the names of all referenced but
+/// undefined variables on failure.
+fn render_template(
+    files: &[TemplateFile],
+    variables: &BTreeMap<String, String>,
+) -> Result<Vec<(PathBuf, String)>, BTreeSet<String>> {
+    let mut missing = BTreeSet::new();
+    let rendered: Vec<(PathBuf, String)> = files
+        .iter()
+        .map(|file| {
+            let path = render(&file.path, variables, &mut missing);
+            let path = path.strip_suffix(TEMPLATE_SUFFIX).unwrap_or(&path);
+            let contents = render(&file.contents, variables, &mut missing);
+            (PathBuf::from(path), contents)
+        })
+        .collect();
+    if missing.is_empty() {
+        Ok(rendered)
+    } else {
+        Err(missing)
+    }
+}
+
+/// Substitute `{{name}}` and `{{name|filter}}` placeholders. Supported
+/// filters: `snake`, `kebab`, `camel`, `pascal`, `upper`, `lower`.
+fn render(
+    text: &str,
+    variables: &BTreeMap<String, String>,
+    missing: &mut BTreeSet<String>,
+) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        out.push_str(&rest[..start]);
+        let placeholder = &rest[start + 2..start + 2 + len];
+        let (name, filter) = match placeholder.split_once('|') {
+            Some((name, filter)) => (name.trim(), Some(filter.trim())),
+            None => (placeholder.trim(), None),
+        };
+        match variables.get(name) {
+            Some(value) => out.push_str(&apply_filter(value, filter)),
+            None => {
+                missing.insert(name.to_string());
+            }
+        }
+        rest = &rest[start + 2 + len + 2..];
+    }
+    out.push_str(rest);
+    out
+}
+
+fn collect_variables(text: &str, variables: &mut BTreeSet<String>) {
+    render(text, &BTreeMap::new(), variables);
+}
+
+fn apply_filter(value: &str, filter: Option<&str>) -> String {
+    let words = || split_words(value);
+    match filter {
+        Some("snake") => words().join("_"),
+        Some("kebab") => words().join("-"),
+        Some("pascal") => words().iter().map(|word| capitalize(word)).collect(),
+        Some("camel") => words()
+            .iter()
+            .enumerate()
+            .map(|(i, word)| {
+                if i == 0 {
+                    word.clone()
+                } else {
+                    capitalize(word)
+                }
+            })
+            .collect(),
+        Some("upper") => value.to_uppercase(),
+        Some("lower") => value.to_lowercase(),
+        _ => value.to_string(),
+    }
+}
+
+/// Split `fooBar`, `foo_bar`, `foo-bar` or `Foo Bar` into lowercase words.
+fn split_words(value: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut prev_lower = false;
+    for ch in value.chars() {
+        if !ch.is_alphanumeric() {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            prev_lower = false;
+            continue;
+        }
+        if ch.is_uppercase() && prev_lower && !current.is_empty() {
+            words.push(std::mem::take(&mut current));
+        }
+        prev_lower = ch.is_lowercase() || ch.is_ascii_digit();
+        current.extend(ch.to_lowercase());
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::tempdir;
+
+    fn vars(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| ((*k).to_string(), (*v).to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn renders_placeholders_with_filters() {
+        let variables = vars(&[("name", "userProfile card")]);
+        let mut missing = BTreeSet::new();
+        assert_eq!(
+            render(
+                "{{name|pascal}} {{ name | snake }} {{name|kebab}} {{name|camel}} {{other}}",
+                &variables,
+                &mut missing,
+            ),
+            "UserProfileCard user_profile_card user-profile-card userProfileCard "
+        );
+        assert_eq!(missing, BTreeSet::from(["other".to_string()]));
+    }
+
+    #[test]
+    fn render_template_strips_suffix_and_reports_missing() {
+        let files = vec![
+            TemplateFile {
+                path: "src/{{module|snake}}.rs".to_string(),
+                contents: "pub struct {{module|pascal}};\n".to_string(),
+            },
+            TemplateFile {
+                path: "tests/{{module|snake}}.rs.tmpl".to_string(),
+                contents: "use crate::{{module|snake}};\n".to_string(),
+            },
+        ];
+
+        assert_eq!(
+            render_template(&files, &vars(&[("module", "RateLimiter")])),
+            Ok(vec![
+                (
+                    PathBuf::from("src/rate_limiter.rs"),
+                    "pub struct RateLimiter;\n".to_string()
+                ),
+                (
+                    PathBuf::from("tests/rate_limiter.rs"),
+                    "use crate::rate_limiter;\n".to_string()
+                ),
+            ])
+        );
+        assert_eq!(
+            render_template(&files, &BTreeMap::new()),
+            Err(BTreeSet::from(["module".to_string()]))
+        );
+    }
+
+    #[tokio::test]
+    async fn lists_templates_with_project_shadowing_home() {
+        let project = tempdir().expect("tempdir");
+        let home = tempdir().expect("tempdir");
+        for (root, body) in [(project.path(), "project"), (home.path(), "home")] {
+            let dir = root.join("component");
+            std::fs::create_dir_all(&dir).expect("mkdir");
+            std::fs::write(dir.join("{{name}}.tsx"), body).expect("write");
+        }
+        std::fs::create_dir_all(home.path().join("crate")).expect("mkdir");
+        std::fs::write(home.path().join("crate").join("lib.rs"), "// {{crate}}").expect("write");
+
+        let templates =
+            list_templates(&[project.path().to_path_buf(), home.path().to_path_buf()]).await;
+        assert_eq!(
+            templates,
+            vec![
+                TemplateSummary {
+                    name: "component".to_string(),
+                    source: project.path().join("component"),
+                    variables: BTreeSet::from(["name".to_string()]),
+                    files: vec!["{{name}}.tsx".to_string()],
+                },
+                TemplateSummary {
+                    name: "crate".to_string(),
+                    source: home.path().join("crate"),
+                    variables: BTreeSet::from(["crate".to_string()]),
+                    files: vec!["lib.rs".to_string()],
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,131 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::Deserialize;
+
+use crate::function_tool::FunctionCallError;
+use crate::tools::context::ToolInvocation;
+use crate::tools::context::ToolOutput;
+use crate::tools::context::ToolPayload;
+use crate::tools::orchestrator::ToolOrchestrator;
+use crate::tools::registry::ToolHandler;
+use crate::tools::registry::ToolKind;
+use crate::tools::runtimes::search::DEFAULT_MAX_MATCHES;
+use crate::tools::runtimes::search::MAX_CONTEXT_LINES;
+use crate::tools::runtimes::search::MAX_MATCHES;
+use crate::tools::runtimes::search::SearchRequest;
+use crate::tools::runtimes::search::SearchRuntime;
+use crate::tools::sandboxing::ToolCtx;
+use crate::tools::sandboxing::ToolError;
+
+pub struct SearchHandler;
+
+#[derive(Deserialize)]
+struct SearchArgs {
+    pattern: String,
+    #[serde(default)]
+    literal: bool,
+    #[serde(default)]
+    case_insensitive: bool,
+    /// Relative paths are resolved against the turn's working directory.
+    #[serde(default)]
+    path: Option<String>,
+    #[serde(default)]
+    include: Vec<String>,
+    #[serde(default)]
+    exclude: Vec<String>,
+    #[serde(default)]
+    context_lines: usize,
+    #[serde(default)]
+    max_matches: Option<usize>,
+}
+
+#[async_trait]
+impl ToolHandler for SearchHandler {
+    fn kind(&self) -> ToolKind {
+        ToolKind::Function
+    }
+
+    async fn handle(&self, invocation: ToolInvocation) -> Result<ToolOutput, FunctionCallError> {
+        let ToolInvocation {
+            session,
+            turn,
+            call_id,
+            tool_name,
+            payload,
+            ..
+        } = invocation;
+
+        let ToolPayload::Function { arguments } = payload else {
+            return Err(FunctionCallError::RespondToModel(format!(
+                "unsupported payload for search handler: {tool_name}"
+            )));
+        };
+
+        let args: SearchArgs = serde_json::from_str(&arguments).map_err(|err| {
+            FunctionCallError::RespondToModel(format!(
+                "failed to parse function arguments: {err:?}"
+            ))
+        })?;
+        if args.pattern.is_empty() {
+            return Err(FunctionCallError::RespondToModel(
+                "pattern must not be empty".to_string(),
+            ));
+        }
+
+        // The workspace: the working directory and the sandbox's writable roots.
+        let mut readable_roots = vec![turn.cwd.clone()];
+        readable_roots.extend(
+            turn.sandbox_policy
+                .get_writable_roots_with_cwd(&turn.cwd)
+                .into_iter()
+                .map(|writable| writable.root),
+        );
+        let req = SearchRequest {
+            pattern: args.pattern,
+            literal: args.literal,
+            case_insensitive: args.case_insensitive,
+            include: args.include,
+            exclude: args.exclude,
+            context_lines: args.context_lines.min(MAX_CONTEXT_LINES),
+            max_matches: args
+                .max_matches
+                .unwrap_or(DEFAULT_MAX_MATCHES)
+                .clamp(1, MAX_MATCHES),
+            root: turn.resolve_path(args.path),
+            cwd: turn.cwd.clone(),
+            readable_roots,
+        };
+
+        let mut orchestrator = ToolOrchestrator::new();
+        let mut runtime = SearchRuntime::new();
+        let tool_ctx = ToolCtx {
+            session: session.as_ref(),
+            turn: turn.as_ref(),
+            call_id: call_id.clone(),
+            tool_name: tool_name.clone(),
+        };
+        let output = orchestrator
+            .run(
+                &mut runtime,
+                Arc::new(req),
+                &tool_ctx,
+                &turn,
+                turn.approval_policy,
+            )
+            .await
+            .map_err(|err| match err {
+                ToolError::Rejected(reason) => FunctionCallError::RespondToModel(reason),
+                ToolError::Codex(err) => FunctionCallError::RespondToModel(err.to_string()),
+            })?;
+
+        let content = serde_json::to_string(&output).map_err(|err| {
+            FunctionCallError::Fatal(format!("failed to serialize search results: {err}"))
+        })?;
+        Ok(ToolOutput::Function {
+            content,
+            content_items: None,
+            success: Some(true),
+        })
+    }
+}
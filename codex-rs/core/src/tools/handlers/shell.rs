@@ -1,19 +1,31 @@
 use async_trait::async_trait;
 use codex_protocol::models::ShellCommandToolCallParams;
 use codex_protocol::models::ShellToolCallParams;
+use codex_protocol::protocol::AskForApproval;
+use codex_protocol::protocol::NetworkAccess;
+use std::path::PathBuf;
 use std::sync::Arc;
 
 use crate::apply_patch;
 use crate::apply_patch::InternalApplyPatchInvocation;
 use crate::apply_patch::convert_apply_patch_to_protocol;
+use crate::apply_patch::deleted_paths;
+use crate::apply_patch::target_paths;
+use crate::apply_patch::verify_patch;
 use crate::codex::TurnContext;
+use crate::command_chain::ChainStep;
+use crate::command_chain::StepAction;
+use crate::command_chain::resolve_cd;
+use crate::command_chain::split_command_chain;
 use crate::exec::ExecParams;
 use crate::exec_env::create_env;
 use crate::exec_policy::create_approval_requirement_for_command;
+use crate::features::Feature;
 use crate::function_tool::FunctionCallError;
 use crate::is_safe_command::is_known_safe_command;
 use crate::protocol::ExecCommandSource;
 use crate::sandboxing::SandboxPermissions;
+use crate::shell_profile::command_expansions;
 use crate::tools::context::ToolInvocation;
 use crate::tools::context::ToolOutput;
 use crate::tools::context::ToolPayload;
@@ -26,7 +38,9 @@ use crate::tools::runtimes::apply_patch::ApplyPatchRequest;
 use crate::tools::runtimes::apply_patch::ApplyPatchRuntime;
 use crate::tools::runtimes::shell::ShellRequest;
 use crate::tools::runtimes::shell::ShellRuntime;
+use crate::tools::sandboxing::ApprovalRequirement;
 use crate::tools::sandboxing::ToolCtx;
+use crate::tools::sandboxing::ToolError;
 
 pub struct ShellHandler;
 
@@ -93,6 +107,19 @@ impl ToolHandler for ShellHandler {
         }
     }
 
+    fn workdir(&self, invocation: &ToolInvocation) -> PathBuf {
+        let workdir = match &invocation.payload {
+            ToolPayload::Function { arguments } => {
+                serde_json::from_str::<ShellToolCallParams>(arguments)
+                    .ok()
+                    .and_then(|params| params.workdir)
+            }
+            ToolPayload::LocalShell { params } => params.workdir.clone(),
+            _ => None,
+        };
+        invocation.turn.resolve_path(workdir)
+    }
+
     async fn handle(&self, invocation: ToolInvocation) -> Result<ToolOutput, FunctionCallError> {
         let ToolInvocation {
             session,
@@ -111,6 +138,8 @@ impl ToolHandler for ShellHandler {
                             "failed to parse function arguments: {e:?}"
                         ))
                     })?;
+                let background = params.background.unwrap_or(false);
+                let network = params.network.clone();
                 let exec_params = Self::to_exec_params(params, turn.as_ref());
                 Self::run_exec_like(
                     tool_name.as_str(),
@@ -120,10 +149,14 @@ impl ToolHandler for ShellHandler {
                     tracker,
                     call_id,
                     false,
+                    background,
+                    network,
                 )
                 .await
             }
             ToolPayload::LocalShell { params } => {
+                let background = params.background.unwrap_or(false);
+                let network = params.network.clone();
                 let exec_params = Self::to_exec_params(params, turn.as_ref());
                 Self::run_exec_like(
                     tool_name.as_str(),
@@ -133,6 +166,8 @@ impl ToolHandler for ShellHandler {
                     tracker,
                     call_id,
                     false,
+                    background,
+                    network,
                 )
                 .await
             }
@@ -153,6 +188,18 @@ impl ToolHandler for ShellCommandHandler {
         matches!(payload, ToolPayload::Function { .. })
     }
 
+    fn workdir(&self, invocation: &ToolInvocation) -> PathBuf {
+        let workdir = match &invocation.payload {
+            ToolPayload::Function { arguments } => {
+                serde_json::from_str::<ShellCommandToolCallParams>(arguments)
+                    .ok()
+                    .and_then(|params| params.workdir)
+            }
+            _ => None,
+        };
+        invocation.turn.resolve_path(workdir)
+    }
+
     async fn handle(&self, invocation: ToolInvocation) -> Result<ToolOutput, FunctionCallError> {
         let ToolInvocation {
             session,
@@ -172,6 +219,8 @@ impl ToolHandler for ShellCommandHandler {
         let params: ShellCommandToolCallParams = serde_json::from_str(&arguments).map_err(|e| {
             FunctionCallError::RespondToModel(format!("failed to parse function arguments: {e:?}"))
         })?;
+        let background = params.background.unwrap_or(false);
+        let network = params.network.clone();
         let exec_params = Self::to_exec_params(params, session.as_ref(), turn.as_ref());
         ShellHandler::run_exec_like(
             tool_name.as_str(),
@@ -181,12 +230,15 @@ impl ToolHandler for ShellCommandHandler {
             tracker,
             call_id,
             true,
+            background,
+            network,
         )
         .await
     }
 }
 
 impl ShellHandler {
+    #[allow(clippy::too_many_arguments)]
     async fn run_exec_like(
         tool_name: &str,
         exec_params: ExecParams,
@@ -195,7 +247,21 @@ impl ShellHandler {
         tracker: crate::tools::context::SharedTurnDiffTracker,
         call_id: String,
         freeform: bool,
+        background: bool,
+        network: Option<NetworkAccess>,
     ) -> Result<ToolOutput, FunctionCallError> {
+        if background && !turn.tools_config.include_background_jobs {
+            return Err(FunctionCallError::RespondToModel(
+                "background jobs are disabled; run the command without `background`".to_string(),
+            ));
+        }
+        if network.is_some() && !turn.tools_config.include_command_network {
+            return Err(FunctionCallError::RespondToModel(
+                "per-command network access is disabled; run the command without `network`"
+                    .to_string(),
+            ));
+        }
+
         // Approval policy guard for explicit escalation in non-OnRequest modes.
         if exec_params.with_escalated_permissions.unwrap_or(false)
             && !matches!(
@@ -210,10 +276,7 @@ impl ShellHandler {
         }
 
         // Intercept apply_patch if present.
-        match codex_apply_patch::maybe_parse_apply_patch_verified(
-            &exec_params.command,
-            &exec_params.cwd,
-        ) {
+        match verify_patch(exec_params.command.clone(), exec_params.cwd.clone()).await {
             codex_apply_patch::MaybeApplyPatchVerified::Body(changes) => {
                 match apply_patch::apply_patch(session.as_ref(), turn.as_ref(), &call_id, changes)
                     .await
@@ -243,9 +306,12 @@ impl ShellHandler {
                         let req = ApplyPatchRequest {
                             patch: apply.action.patch.clone(),
                             cwd: apply.action.cwd.clone(),
+                            files: target_paths(&apply.action),
+                            deleted: deleted_paths(&apply.action),
                             timeout_ms: exec_params.expiration.timeout_ms(),
                             user_explicitly_approved: apply.user_explicitly_approved_this_action,
                             codex_exe: turn.codex_linux_sandbox_exe.clone(),
+                            dry_run: false,
                         };
                         let mut orchestrator = ToolOrchestrator::new();
                         let mut runtime = ApplyPatchRuntime::new();
@@ -256,7 +322,13 @@ impl ShellHandler {
                             tool_name: tool_name.to_string(),
                         };
                         let out = orchestrator
-                            .run(&mut runtime, &req, &tool_ctx, &turn, turn.approval_policy)
+                            .run(
+                                &mut runtime,
+                                Arc::new(req),
+                                &tool_ctx,
+                                &turn,
+                                turn.approval_policy,
+                            )
                             .await;
                         let event_ctx = ToolEventCtx::new(
                             session.as_ref(),
@@ -265,6 +337,7 @@ impl ShellHandler {
                             Some(&tracker),
                         );
                         let content = emitter.finish(event_ctx, out).await?;
+                        let content = apply.with_review_note(content);
                         return Ok(ToolOutput::Function {
                             content,
                             content_items: None,
@@ -287,6 +360,34 @@ impl ShellHandler {
             }
         }
 
+        let approval_requirement = Self::approval_for(
+            &turn,
+            &exec_params.command,
+            exec_params.with_escalated_permissions,
+            network.as_ref(),
+        );
+        // Splitting only matters when the chain as a whole would be asked about.
+        if !background
+            && matches!(
+                approval_requirement,
+                ApprovalRequirement::NeedsApproval { .. }
+            )
+            && session.enabled(Feature::SplitCommandChains).await
+            && let Some(steps) = split_command_chain(&exec_params.command)
+        {
+            return Self::run_chain(
+                tool_name,
+                &exec_params,
+                network.as_ref(),
+                &steps,
+                session,
+                turn,
+                &call_id,
+                freeform,
+            )
+            .await;
+        }
+
         let source = ExecCommandSource::Agent;
         let emitter = ToolEmitter::shell(
             exec_params.command.clone(),
@@ -301,16 +402,13 @@ impl ShellHandler {
             command: exec_params.command.clone(),
             cwd: exec_params.cwd.clone(),
             timeout_ms: exec_params.expiration.timeout_ms(),
-            env: exec_params.env.clone(),
+            env: Arc::new(exec_params.env.clone()),
             with_escalated_permissions: exec_params.with_escalated_permissions,
             justification: exec_params.justification.clone(),
-            approval_requirement: create_approval_requirement_for_command(
-                &turn.exec_policy,
-                &exec_params.command,
-                turn.approval_policy,
-                &turn.sandbox_policy,
-                SandboxPermissions::from(exec_params.with_escalated_permissions.unwrap_or(false)),
-            ),
+            background,
+            network,
+            approval_requirement,
+            expansions: command_expansions(session.user_shell(), &exec_params.command).await,
         };
         let mut orchestrator = ToolOrchestrator::new();
         let mut runtime = ShellRuntime::new();
@@ -321,7 +419,13 @@ impl ShellHandler {
             tool_name: tool_name.to_string(),
         };
         let out = orchestrator
-            .run(&mut runtime, &req, &tool_ctx, &turn, turn.approval_policy)
+            .run(
+                &mut runtime,
+                Arc::new(req),
+                &tool_ctx,
+                &turn,
+                turn.approval_policy,
+            )
             .await;
         let event_ctx = ToolEventCtx::new(session.as_ref(), turn.as_ref(), &call_id, None);
         let content = emitter.finish(event_ctx, out).await?;
@@ -331,6 +435,179 @@ impl ShellHandler {
             success: Some(true),
         })
     }
+
+    /// The approval requirement for `command`.
+    fn approval_for(
+        turn: &TurnContext,
+        command: &[String],
+        with_escalated_permissions: Option<bool>,
+        network: Option<&NetworkAccess>,
+    ) -> ApprovalRequirement {
+        let requirement = create_approval_requirement_for_command(
+            &turn.exec_policy,
+            command,
+            turn.approval_policy,
+            &turn.sandbox_policy,
+            SandboxPermissions::from(with_escalated_permissions.unwrap_or(false)),
+        );
+        match network {
+            Some(network) => network_requirement(turn, network, requirement),
+            None => requirement,
+        }
+    }
+
+    /// Runs the steps of a split chain one at a time, each approved on its
+    /// own and shown as its own command. A rejected step ends the chain.
+    #[allow(clippy::too_many_arguments)]
+    async fn run_chain(
+        tool_name: &str,
+        exec_params: &ExecParams,
+        network: Option<&NetworkAccess>,
+        steps: &[ChainStep],
+        session: Arc<crate::codex::Session>,
+        turn: Arc<TurnContext>,
+        call_id: &str,
+        freeform: bool,
+    ) -> Result<ToolOutput, FunctionCallError> {
+        // `bash -lc`: each step runs in the chain's own shell.
+        let shell = &exec_params.command[..2];
+        let mut cwd = exec_params.cwd.clone();
+        let env = Arc::new(exec_params.env.clone());
+        let mut last_exit_code = 0;
+        let mut stopped_at = None;
+        let mut sections = Vec::new();
+        for (index, step) in steps.iter().enumerate() {
+            let number = index + 1;
+            let header = format!("[step {number}] $ {}", step.display());
+            if let Some(stopped_at) = stopped_at {
+                sections.push(format!(
+                    "{header}\nnot run: the chain stopped at step {stopped_at}"
+                ));
+                continue;
+            }
+            if !step.run_if.should_run(last_exit_code) {
+                sections.push(format!("{header}\nskipped"));
+                continue;
+            }
+            let script = match &step.action {
+                StepAction::Run(script) => script,
+                StepAction::Cd(dir) => {
+                    match resolve_cd(&cwd, dir) {
+                        Some(dir) => {
+                            cwd = dir;
+                            last_exit_code = 0;
+                            sections.push(header);
+                        }
+                        None => {
+                            last_exit_code = 1;
+                            sections.push(format!("{header}\nno such directory"));
+                        }
+                    }
+                    continue;
+                }
+            };
+
+            let mut command = shell.to_vec();
+            command.push(script.clone());
+            let step_call_id = format!("{call_id}-step{number}");
+            let approval_requirement = Self::approval_for(
+                &turn,
+                &command,
+                exec_params.with_escalated_permissions,
+                network,
+            );
+            let emitter = ToolEmitter::shell(
+                command.clone(),
+                cwd.clone(),
+                ExecCommandSource::Agent,
+                freeform,
+            );
+            let event_ctx = ToolEventCtx::new(session.as_ref(), turn.as_ref(), &step_call_id, None);
+            emitter.begin(event_ctx).await;
+
+            let expansions = command_expansions(session.user_shell(), &command).await;
+            let req = ShellRequest {
+                command,
+                cwd: cwd.clone(),
+                timeout_ms: exec_params.expiration.timeout_ms(),
+                env: Arc::clone(&env),
+                with_escalated_permissions: exec_params.with_escalated_permissions,
+                justification: exec_params.justification.clone(),
+                background: false,
+                network: network.cloned(),
+                approval_requirement,
+                expansions,
+            };
+            let mut orchestrator = ToolOrchestrator::new();
+            let mut runtime = ShellRuntime::new();
+            let tool_ctx = ToolCtx {
+                session: session.as_ref(),
+                turn: turn.as_ref(),
+                call_id: step_call_id.clone(),
+                tool_name: tool_name.to_string(),
+            };
+            let out = orchestrator
+                .run(
+                    &mut runtime,
+                    Arc::new(req),
+                    &tool_ctx,
+                    &turn,
+                    turn.approval_policy,
+                )
+                .await;
+            match &out {
+                Ok(output) => last_exit_code = output.exit_code,
+                Err(ToolError::Rejected(_)) => stopped_at = Some(number),
+                Err(ToolError::Codex(_)) => last_exit_code = 1,
+            }
+            let event_ctx = ToolEventCtx::new(session.as_ref(), turn.as_ref(), &step_call_id, None);
+            let content = match emitter.finish(event_ctx, out).await {
+                Ok(content) | Err(FunctionCallError::RespondToModel(content)) => content,
+                Err(err) => return Err(err),
+            };
+            sections.push(format!("{header}\n{content}"));
+        }
+
+        let content = sections.join("\n\n");
+        if stopped_at.is_some() || last_exit_code != 0 {
+            return Err(FunctionCallError::RespondToModel(content));
+        }
+        Ok(ToolOutput::Function {
+            content,
+            content_items: None,
+            success: Some(true),
+        })
+    }
+}
+
+/// The requirement for a command that declared `network`: access beyond the
+/// sandbox policy needs approval, and is refused under `never`.
+pub(crate) fn network_requirement(
+    turn: &TurnContext,
+    network: &NetworkAccess,
+    requirement: ApprovalRequirement,
+) -> ApprovalRequirement {
+    if !network.exceeds(&turn.sandbox_policy) {
+        return requirement;
+    }
+    match requirement {
+        ApprovalRequirement::Forbidden { .. } | ApprovalRequirement::NeedsApproval { .. } => {
+            requirement
+        }
+        ApprovalRequirement::Skip { .. } if turn.approval_policy == AskForApproval::Never => {
+            ApprovalRequirement::Forbidden {
+                reason: format!(
+                    "the command asks for {network}, which the sandbox does not allow; rejected \
+                     by user approval settings"
+                ),
+            }
+        }
+        ApprovalRequirement::Skip { .. } => ApprovalRequirement::NeedsApproval {
+            reason: Some(format!(
+                "This command asks for {network}, which the sandbox does not otherwise allow."
+            )),
+        },
+    }
 }
 
 #[cfg(test)]
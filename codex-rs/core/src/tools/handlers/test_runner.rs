@@ -0,0 +1,196 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::exec_env::create_env;
+use crate::exec_policy::create_approval_requirement_for_command;
+use crate::function_tool::FunctionCallError;
+use crate::protocol::ExecCommandSource;
+use crate::sandboxing::SandboxPermissions;
+use crate::tools::context::ToolInvocation;
+use crate::tools::context::ToolOutput;
+use crate::tools::context::ToolPayload;
+use crate::tools::events::ToolEmitter;
+use crate::tools::events::ToolEventCtx;
+use crate::tools::events::ToolEventStage;
+use crate::tools::format_exec_output_str;
+use crate::tools::orchestrator::ToolOrchestrator;
+use crate::tools::registry::ToolHandler;
+use crate::tools::registry::ToolKind;
+use crate::tools::runtimes::test_runner::MAX_REPEAT;
+use crate::tools::runtimes::test_runner::RepeatSummary;
+use crate::tools::runtimes::test_runner::TestRunRequest;
+use crate::tools::runtimes::test_runner::TestRunnerRuntime;
+use crate::tools::runtimes::test_runner::summarize_runs;
+use crate::tools::sandboxing::ToolCtx;
+
+pub struct TestRunnerHandler;
+
+fn default_repeat() -> i64 {
+    1
+}
+
+#[derive(Deserialize)]
+struct RunTestsArgs {
+    command: String,
+    #[serde(default)]
+    workdir: Option<String>,
+    #[serde(default)]
+    timeout_ms: Option<u64>,
+    #[serde(default = "default_repeat")]
+    repeat: i64,
+    #[serde(default)]
+    parallel: bool,
+    #[serde(default)]
+    seed_env: Option<String>,
+    #[serde(default)]
+    base_seed: i64,
+}
+
+#[derive(Serialize)]
+struct RepeatResponse {
+    #[serde(flatten)]
+    summary: RepeatSummary,
+    /// Output of the first failing run, so the failure can be inspected.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    first_failure_output: Option<String>,
+}
+
+#[async_trait]
+impl ToolHandler for TestRunnerHandler {
+    fn kind(&self) -> ToolKind {
+        ToolKind::Function
+    }
+
+    fn is_mutating(&self, _invocation: &ToolInvocation) -> bool {
+        true
+    }
+
+    async fn handle(&self, invocation: ToolInvocation) -> Result<ToolOutput, FunctionCallError> {
+        let ToolInvocation {
+            session,
+            turn,
+            call_id,
+            tool_name,
+            payload,
+            ..
+        } = invocation;
+
+        let ToolPayload::Function { arguments } = payload else {
+            return Err(FunctionCallError::RespondToModel(format!(
+                "unsupported payload for run_tests handler: {tool_name}"
+            )));
+        };
+
+        let args: RunTestsArgs = serde_json::from_str(&arguments).map_err(|err| {
+            FunctionCallError::RespondToModel(format!(
+                "failed to parse function arguments: {err:?}"
+            ))
+        })?;
+
+        if !(1..=MAX_REPEAT).contains(&args.repeat) {
+            return Err(FunctionCallError::RespondToModel(format!(
+                "repeat must be between 1 and {MAX_REPEAT}"
+            )));
+        }
+
+        let cwd = turn.resolve_path(args.workdir.clone());
+        let command = session.user_shell().derive_exec_args(&args.command, true);
+
+        let emitter = ToolEmitter::shell(
+            command.clone(),
+            cwd.clone(),
+            ExecCommandSource::Agent,
+            false,
+        );
+        let event_ctx = ToolEventCtx::new(session.as_ref(), turn.as_ref(), &call_id, None);
+        emitter.begin(event_ctx).await;
+
+        let req = TestRunRequest {
+            approval_requirement: create_approval_requirement_for_command(
+                &turn.exec_policy,
+                &command,
+                turn.approval_policy,
+                &turn.sandbox_policy,
+                SandboxPermissions::from(false),
+            ),
+            command,
+            cwd,
+            timeout_ms: args.timeout_ms,
+            env: create_env(&turn.shell_environment_policy),
+            repeat: args.repeat,
+            parallel: args.parallel,
+            seed_env: args.seed_env,
+            base_seed: args.base_seed,
+        };
+        let mut orchestrator = ToolOrchestrator::new();
+        let mut runtime = TestRunnerRuntime::new();
+        let tool_ctx = ToolCtx {
+            session: session.as_ref(),
+            turn: turn.as_ref(),
+            call_id: call_id.clone(),
+            tool_name: tool_name.to_string(),
+        };
+        let out = orchestrator
+            .run(
+                &mut runtime,
+                Arc::new(req),
+                &tool_ctx,
+                &turn,
+                turn.approval_policy,
+            )
+            .await;
+
+        let event_ctx = ToolEventCtx::new(session.as_ref(), turn.as_ref(), &call_id, None);
+        let mut runs = match out {
+            Ok(runs) => runs,
+            Err(err) => {
+                return emitter.finish(event_ctx, Err(err)).await.map(|content| {
+                    ToolOutput::Function {
+                        content,
+                        content_items: None,
+                        success: Some(false),
+                    }
+                });
+            }
+        };
+
+        // A single run behaves exactly like a shell call.
+        if args.repeat == 1
+            && let Some(run) = runs.pop()
+        {
+            let content = emitter.finish(event_ctx, Ok(run.output)).await?;
+            return Ok(ToolOutput::Function {
+                content,
+                content_items: None,
+                success: Some(true),
+            });
+        }
+
+        let summary = summarize_runs(&runs);
+        let first_failure = runs.iter().find(|run| run.output.exit_code != 0);
+        if let Some(run) = first_failure.or(runs.last()) {
+            emitter
+                .emit(event_ctx, ToolEventStage::Success(run.output.clone()))
+                .await;
+        }
+
+        let success = summary.failed_runs == 0;
+        let response = RepeatResponse {
+            summary,
+            first_failure_output: first_failure
+                .map(|run| format_exec_output_str(&run.output, turn.truncation_policy)),
+        };
+        let content = serde_json::to_string(&response).map_err(|err| {
+            FunctionCallError::Fatal(format!("failed to serialize test summary: {err}"))
+        })?;
+
+        Ok(ToolOutput::Function {
+            content,
+            content_items: None,
+            success: Some(success),
+        })
+    }
+}
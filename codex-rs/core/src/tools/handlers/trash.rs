@@ -0,0 +1,121 @@
+use async_trait::async_trait;
+use codex_protocol::protocol::AskForApproval;
+use codex_protocol::protocol::ReviewDecision;
+use serde::Deserialize;
+
+use crate::function_tool::FunctionCallError;
+use crate::safety::is_path_writable;
+use crate::tools::context::ToolInvocation;
+use crate::tools::context::ToolOutput;
+use crate::tools::context::ToolPayload;
+use crate::tools::registry::ToolHandler;
+use crate::tools::registry::ToolKind;
+use crate::trash::find_in_trash;
+use crate::trash::list_trash;
+use crate::trash::restore_from_trash;
+use crate::trash::trash_dir;
+
+pub struct TrashHandler;
+
+#[derive(Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+enum TrashArgs {
+    /// List the files deleted so far in this session.
+    List,
+    /// Put a deleted file back.
+    Restore { path: String },
+}
+
+#[async_trait]
+impl ToolHandler for TrashHandler {
+    fn kind(&self) -> ToolKind {
+        ToolKind::Function
+    }
+
+    fn is_mutating(&self, invocation: &ToolInvocation) -> bool {
+        match &invocation.payload {
+            ToolPayload::Function { arguments } => !matches!(
+                serde_json::from_str::<TrashArgs>(arguments),
+                Ok(TrashArgs::List)
+            ),
+            _ => true,
+        }
+    }
+
+    async fn handle(&self, invocation: ToolInvocation) -> Result<ToolOutput, FunctionCallError> {
+        let ToolInvocation {
+            session,
+            turn,
+            call_id,
+            tool_name,
+            payload,
+            ..
+        } = invocation;
+
+        let ToolPayload::Function { arguments } = payload else {
+            return Err(FunctionCallError::RespondToModel(format!(
+                "unsupported payload for trash handler: {tool_name}"
+            )));
+        };
+
+        let args: TrashArgs = serde_json::from_str(&arguments).map_err(|err| {
+            FunctionCallError::RespondToModel(format!(
+                "failed to parse function arguments: {err:?}"
+            ))
+        })?;
+
+        let root = trash_dir(&turn.client.config().codex_home, session.conversation_id());
+        let content = match args {
+            TrashArgs::List => serde_json::to_string(&list_trash(&root).await).map_err(|err| {
+                FunctionCallError::Fatal(format!("failed to serialize the trash: {err}"))
+            })?,
+            TrashArgs::Restore { path } => {
+                let path = turn.resolve_path(Some(path));
+                let entry = find_in_trash(&root, &path).await.ok_or_else(|| {
+                    FunctionCallError::RespondToModel(format!(
+                        "{} is not in the trash",
+                        path.display()
+                    ))
+                })?;
+
+                // The restore is an in-process write, so the sandbox does not
+                // see it; ask before writing outside the writable roots.
+                if !is_path_writable(&path, &turn.sandbox_policy, &turn.cwd) {
+                    if turn.approval_policy == AskForApproval::Never {
+                        return Err(FunctionCallError::RespondToModel(format!(
+                            "{} is outside the sandbox's writable roots and the approval policy \
+                             does not allow asking",
+                            path.display()
+                        )));
+                    }
+                    let decision = session
+                        .request_command_approval(
+                            turn.as_ref(),
+                            call_id,
+                            vec!["restore".to_string(), path.display().to_string()],
+                            turn.cwd.clone(),
+                            Some(format!("Restore the deleted {} from trash", path.display())),
+                            None,
+                        )
+                        .await;
+                    if matches!(decision, ReviewDecision::Denied | ReviewDecision::Abort) {
+                        return Err(FunctionCallError::RespondToModel(
+                            "restore rejected by user".to_string(),
+                        ));
+                    }
+                }
+
+                restore_from_trash(&root, &entry)
+                    .await
+                    .map_err(FunctionCallError::RespondToModel)?;
+                format!("restored {} ({} bytes)", path.display(), entry.size_bytes)
+            }
+        };
+
+        Ok(ToolOutput::Function {
+            content,
+            content_items: None,
+            success: Some(true),
+        })
+    }
+}
@@ -0,0 +1,106 @@
+use async_trait::async_trait;
+use codex_protocol::protocol::AskForApproval;
+use codex_protocol::protocol::ReviewDecision;
+use serde::Deserialize;
+
+use crate::function_tool::FunctionCallError;
+use crate::safety::is_path_writable;
+use crate::tools::context::ToolInvocation;
+use crate::tools::context::ToolOutput;
+use crate::tools::context::ToolPayload;
+use crate::tools::registry::ToolHandler;
+use crate::tools::registry::ToolKind;
+
+pub struct UndoPatchHandler;
+
+#[derive(Deserialize)]
+struct UndoPatchArgs {
+    /// How many of the most recent patches to revert.
+    #[serde(default = "default_count")]
+    count: usize,
+}
+
+fn default_count() -> usize {
+    1
+}
+
+#[async_trait]
+impl ToolHandler for UndoPatchHandler {
+    fn kind(&self) -> ToolKind {
+        ToolKind::Function
+    }
+
+    fn is_mutating(&self, _invocation: &ToolInvocation) -> bool {
+        true
+    }
+
+    async fn handle(&self, invocation: ToolInvocation) -> Result<ToolOutput, FunctionCallError> {
+        let ToolInvocation {
+            session,
+            turn,
+            call_id,
+            tool_name,
+            payload,
+            ..
+        } = invocation;
+
+        let ToolPayload::Function { arguments } = payload else {
+            return Err(FunctionCallError::RespondToModel(format!(
+                "unsupported payload for undo_patch handler: {tool_name}"
+            )));
+        };
+
+        let args: UndoPatchArgs = serde_json::from_str(&arguments).map_err(|err| {
+            FunctionCallError::RespondToModel(format!(
+                "failed to parse function arguments: {err:?}"
+            ))
+        })?;
+
+        let Some(journal) = &session.services.patch_journal else {
+            return Err(FunctionCallError::RespondToModel(
+                "undo_patch is not available in this session".to_string(),
+            ));
+        };
+        let mut journal = journal.lock().await;
+
+        // Reverting is an in-process write, so the sandbox does not see it;
+        // ask before writing outside the writable roots.
+        let outside: Vec<String> = journal
+            .paths(args.count)
+            .iter()
+            .filter(|path| !is_path_writable(path, &turn.sandbox_policy, &turn.cwd))
+            .map(|path| path.display().to_string())
+            .collect();
+        if !outside.is_empty() {
+            if turn.approval_policy == AskForApproval::Never {
+                return Err(FunctionCallError::RespondToModel(format!(
+                    "{} is outside the sandbox's writable roots and the approval policy does not \
+                     allow asking",
+                    outside.join(", ")
+                )));
+            }
+            let decision = session
+                .request_command_approval(
+                    turn.as_ref(),
+                    call_id,
+                    vec!["undo_patch".to_string(), args.count.to_string()],
+                    turn.cwd.clone(),
+                    Some(format!("Revert earlier patches to {}", outside.join(", "))),
+                    None,
+                )
+                .await;
+            if matches!(decision, ReviewDecision::Denied | ReviewDecision::Abort) {
+                return Err(FunctionCallError::RespondToModel(
+                    "undo rejected by user".to_string(),
+                ));
+            }
+        }
+
+        let outcome = journal.undo(args.count).await;
+        Ok(ToolOutput::Function {
+            content: outcome.summary(),
+            content_items: None,
+            success: Some(outcome.stopped.is_none()),
+        })
+    }
+}
@@ -98,6 +98,19 @@ impl ToolHandler for UnifiedExecHandler {
         !is_known_safe_command(&command)
     }
 
+    fn workdir(&self, invocation: &ToolInvocation) -> PathBuf {
+        let (ToolPayload::Function { arguments } | ToolPayload::UnifiedExec { arguments }) =
+            &invocation.payload
+        else {
+            return invocation.turn.cwd.clone();
+        };
+        let workdir = serde_json::from_str::<ExecCommandArgs>(arguments)
+            .ok()
+            .and_then(|params| params.workdir)
+            .filter(|workdir| !workdir.is_empty());
+        invocation.turn.resolve_path(workdir)
+    }
+
     async fn handle(&self, invocation: ToolInvocation) -> Result<ToolOutput, FunctionCallError> {
         let ToolInvocation {
             session,
@@ -218,7 +231,9 @@ impl ToolHandler for UnifiedExecHandler {
         if !response.output.is_empty() {
             let delta = ExecCommandOutputDeltaEvent {
                 call_id: response.event_call_id.clone(),
+                // The terminal merges stderr into stdout.
                 stream: ExecOutputStream::Stdout,
+                seq: response.output_seq,
                 chunk: response.output.as_bytes().to_vec(),
             };
             session
@@ -31,6 +31,7 @@ impl ToolHandler for ViewImageHandler {
             turn,
             payload,
             call_id,
+            tool_name,
             ..
         } = invocation;
 
@@ -72,6 +73,9 @@ impl ToolHandler for ViewImageHandler {
                     "unable to attach image (no active task)".to_string(),
                 )
             })?;
+        session
+            .record_file_read(event_path.clone(), &tool_name, metadata.len())
+            .await;
 
         session
             .send_event(
@@ -0,0 +1,360 @@
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::Instant;
+
+use async_trait::async_trait;
+use regex_lite::Regex;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::function_tool::FunctionCallError;
+use crate::tools::context::ToolInvocation;
+use crate::tools::context::ToolOutput;
+use crate::tools::context::ToolPayload;
+use crate::tools::registry::ToolHandler;
+use crate::tools::registry::ToolKind;
+
+pub struct WaitForHandler;
+
+const DEFAULT_TIMEOUT_MS: u64 = 60_000;
+const MAX_TIMEOUT_MS: u64 = 10 * 60 * 1000;
+const DEFAULT_INTERVAL_MS: u64 = 500;
+const MIN_INTERVAL_MS: u64 = 50;
+const HTTP_PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+fn default_timeout_ms() -> u64 {
+    DEFAULT_TIMEOUT_MS
+}
+
+fn default_interval_ms() -> u64 {
+    DEFAULT_INTERVAL_MS
+}
+
+fn default_status() -> u16 {
+    200
+}
+
+#[derive(Deserialize)]
+struct WaitForArgs {
+    /// URL that must answer with `status`.
+    #[serde(default)]
+    url: Option<String>,
+    #[serde(default = "default_status")]
+    status: u16,
+    /// Path that must exist.
+    #[serde(default)]
+    file: Option<String>,
+    /// Log file that must contain a line matching `pattern`.
+    #[serde(default)]
+    log_file: Option<String>,
+    #[serde(default)]
+    pattern: Option<String>,
+    /// Only consider log output written after the wait started.
+    #[serde(default)]
+    new_lines_only: bool,
+    #[serde(default = "default_timeout_ms")]
+    timeout_ms: u64,
+    #[serde(default = "default_interval_ms")]
+    interval_ms: u64,
+}
+
+enum WaitCondition {
+    Http {
+        url: reqwest::Url,
+        status: u16,
+    },
+    File {
+        path: PathBuf,
+    },
+    LogLine {
+        path: PathBuf,
+        pattern: Regex,
+        offset: u64,
+    },
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+struct WaitForResponse {
+    satisfied: bool,
+    waited_ms: i64,
+    attempts: i64,
+    /// What the last probe observed, e.g. the HTTP status or a connect error.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_observation: Option<String>,
+    /// The matching log line, for `log_file` waits.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    matched_line: Option<String>,
+}
+
+enum Probe {
+    Satisfied(Option<String>),
+    Pending(String),
+}
+
+#[async_trait]
+impl ToolHandler for WaitForHandler {
+    fn kind(&self) -> ToolKind {
+        ToolKind::Function
+    }
+
+    async fn handle(&self, invocation: ToolInvocation) -> Result<ToolOutput, FunctionCallError> {
+        let ToolInvocation { payload, turn, .. } = invocation;
+
+        let arguments = match payload {
+            ToolPayload::Function { arguments } => arguments,
+            _ => {
+                return Err(FunctionCallError::RespondToModel(
+                    "wait_for handler received unsupported payload".to_string(),
+                ));
+            }
+        };
+
+        let args: WaitForArgs = serde_json::from_str(&arguments).map_err(|err| {
+            FunctionCallError::RespondToModel(format!(
+                "failed to parse function arguments: {err:?}"
+            ))
+        })?;
+
+        let condition = match (&args.url, &args.file, &args.log_file) {
+            (Some(url), None, None) => {
+                let url = reqwest::Url::parse(url).map_err(|err| {
+                    FunctionCallError::RespondToModel(format!("invalid url `{url}`: {err}"))
+                })?;
+                // The probe runs in-process, outside the command sandbox, so only
+                // local endpoints are reachable unless the session has network access.
+                if !turn.sandbox_policy.has_full_network_access() && !is_loopback(&url) {
+                    return Err(FunctionCallError::RespondToModel(
+                        "network access is restricted; wait_for can only probe localhost URLs"
+                            .to_string(),
+                    ));
+                }
+                WaitCondition::Http {
+                    url,
+                    status: args.status,
+                }
+            }
+            (None, Some(file), None) => WaitCondition::File {
+                path: turn.resolve_path(Some(file.clone())),
+            },
+            (None, None, Some(log_file)) => {
+                let Some(pattern) = args.pattern.as_deref() else {
+                    return Err(FunctionCallError::RespondToModel(
+                        "`pattern` is required with `log_file`".to_string(),
+                    ));
+                };
+                let pattern = Regex::new(pattern).map_err(|err| {
+                    FunctionCallError::RespondToModel(format!("invalid pattern: {err}"))
+                })?;
+                let path = turn.resolve_path(Some(log_file.clone()));
+                let offset = if args.new_lines_only {
+                    tokio::fs::metadata(&path)
+                        .await
+                        .map(|meta| meta.len())
+                        .unwrap_or(0)
+                } else {
+                    0
+                };
+                WaitCondition::LogLine {
+                    path,
+                    pattern,
+                    offset,
+                }
+            }
+            _ => {
+                return Err(FunctionCallError::RespondToModel(
+                    "specify exactly one of `url`, `file` or `log_file`".to_string(),
+                ));
+            }
+        };
+
+        let response = wait(
+            &condition,
+            Duration::from_millis(args.timeout_ms.min(MAX_TIMEOUT_MS)),
+            Duration::from_millis(args.interval_ms.max(MIN_INTERVAL_MS)),
+        )
+        .await;
+        let satisfied = response.satisfied;
+        let content = serde_json::to_string(&response).map_err(|err| {
+            FunctionCallError::Fatal(format!("failed to serialize wait result: {err}"))
+        })?;
+
+        Ok(ToolOutput::Function {
+            content,
+            content_items: None,
+            success: Some(satisfied),
+        })
+    }
+}
+
+fn is_loopback(url: &reqwest::Url) -> bool {
+    match url.host() {
+        Some(url::Host::Domain(domain)) => domain.eq_ignore_ascii_case("localhost"),
+        Some(url::Host::Ipv4(ip)) => ip.is_loopback(),
+        Some(url::Host::Ipv6(ip)) => ip.is_loopback(),
+        None => false,
+    }
+}
+
+async fn wait(condition: &WaitCondition, limit: Duration, interval: Duration) -> WaitForResponse {
+    let started = Instant::now();
+    let client = reqwest::Client::builder()
+        .timeout(HTTP_PROBE_TIMEOUT)
+        .build()
+        .ok();
+    let mut attempts = 0;
+    let mut last_observation = None;
+    let matched_line = loop {
+        attempts += 1;
+        match probe(condition, client.as_ref()).await {
+            Probe::Satisfied(line) => break Some(line),
+            Probe::Pending(observation) => last_observation = Some(observation),
+        }
+        if started.elapsed() + interval > limit {
+            break None;
+        }
+        tokio::time::sleep(interval).await;
+    };
+    WaitForResponse {
+        satisfied: matched_line.is_some(),
+        waited_ms: started.elapsed().as_millis() as i64,
+        attempts,
+        last_observation: if matched_line.is_some() {
+            None
+        } else {
+            last_observation
+        },
+        matched_line: matched_line.flatten(),
+    }
+}
+
+async fn probe(condition: &WaitCondition, client: Option<&reqwest::Client>) -> Probe {
+    match condition {
+        WaitCondition::Http { url, status } => {
+            let Some(client) = client else {
+                return Probe::Pending("failed to build HTTP client".to_string());
+            };
+            match client.get(url.clone()).send().await {
+                Ok(response) if response.status().as_u16() == *status => Probe::Satisfied(None),
+                Ok(response) => Probe::Pending(format!("HTTP {}", response.status())),
+                Err(err) => Probe::Pending(err.to_string()),
+            }
+        }
+        WaitCondition::File { path } => match tokio::fs::metadata(path).await {
+            Ok(_) => Probe::Satisfied(None),
+            Err(err) => Probe::Pending(format!("{}: {err}", path.display())),
+        },
+        WaitCondition::LogLine {
+            path,
+            pattern,
+            offset,
+        } => {
+            let contents = match tokio::fs::read(path).await {
+                Ok(contents) => contents,
+                Err(err) => return Probe::Pending(format!("{}: {err}", path.display())),
+            };
+            // A truncated or rotated log starts over from the beginning.
+            let start = usize::try_from(*offset)
+                .ok()
+                .filter(|start| *start <= contents.len())
+                .unwrap_or(0);
+            let text = String::from_utf8_lossy(&contents[start..]);
+            match text.lines().find(|line| pattern.is_match(line)) {
+                Some(line) => Probe::Satisfied(Some(line.to_string())),
+                None => Probe::Pending(format!("no matching line in {}", path.display())),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn waits_for_file_created_later() {
+        let dir = tempdir().expect("tempdir");
+        let path = dir.path().join("ready");
+        let writer_path = path.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            tokio::fs::write(writer_path, "")
+                .await
+                .expect("write marker");
+        });
+
+        let condition = WaitCondition::File { path };
+        let response = wait(
+            &condition,
+            Duration::from_secs(5),
+            Duration::from_millis(20),
+        )
+        .await;
+
+        assert!(response.satisfied);
+        assert!(response.attempts > 1);
+    }
+
+    #[tokio::test]
+    async fn log_wait_skips_lines_before_offset() {
+        let dir = tempdir().expect("tempdir");
+        let path = dir.path().join("server.log");
+        let old = "listening on :3000\n";
+        std::fs::write(&path, format!("{old}restarting\nlistening on :3001\n")).expect("write log");
+
+        let condition = WaitCondition::LogLine {
+            path,
+            pattern: Regex::new("listening on :\\d+").expect("regex"),
+            offset: old.len() as u64,
+        };
+        let response = wait(
+            &condition,
+            Duration::from_millis(100),
+            Duration::from_millis(20),
+        )
+        .await;
+
+        assert_eq!(
+            response,
+            WaitForResponse {
+                satisfied: true,
+                waited_ms: response.waited_ms,
+                attempts: 1,
+                last_observation: None,
+                matched_line: Some("listening on :3001".to_string()),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn reports_last_observation_on_timeout() {
+        let dir = tempdir().expect("tempdir");
+        let path = dir.path().join("never");
+        let condition = WaitCondition::File { path: path.clone() };
+
+        let response = wait(
+            &condition,
+            Duration::from_millis(50),
+            Duration::from_millis(20),
+        )
+        .await;
+
+        assert!(!response.satisfied);
+        assert!(
+            response
+                .last_observation
+                .as_deref()
+                .is_some_and(|observation| observation.starts_with(&path.display().to_string()))
+        );
+    }
+
+    #[test]
+    fn only_local_urls_count_as_loopback() {
+        let url = |s: &str| reqwest::Url::parse(s).expect("url");
+        assert!(is_loopback(&url("http://localhost:3000/health")));
+        assert!(is_loopback(&url("http://127.0.0.1:8080")));
+        assert!(is_loopback(&url("http://[::1]:8080")));
+        assert!(!is_loopback(&url("https://example.com")));
+    }
+}
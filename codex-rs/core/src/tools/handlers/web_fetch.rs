@@ -0,0 +1,195 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use url::Url;
+
+use crate::exec_policy::create_approval_requirement_for_command;
+use crate::function_tool::FunctionCallError;
+use crate::replay_bundle::ReplayInput;
+use crate::sandboxing::SandboxPermissions;
+use crate::tools::context::ToolInvocation;
+use crate::tools::context::ToolOutput;
+use crate::tools::context::ToolPayload;
+use crate::tools::handlers::shell::network_requirement;
+use crate::tools::orchestrator::ToolOrchestrator;
+use crate::tools::registry::ToolHandler;
+use crate::tools::registry::ToolKind;
+use crate::tools::runtimes::web_fetch::FetchMethod;
+use crate::tools::runtimes::web_fetch::WEB_FETCH_CACHE_TTL;
+use crate::tools::runtimes::web_fetch::WEB_FETCH_MAX_BYTES;
+use crate::tools::runtimes::web_fetch::WebFetchOutput;
+use crate::tools::runtimes::web_fetch::WebFetchRequest;
+use crate::tools::runtimes::web_fetch::WebFetchRuntime;
+use crate::tools::sandboxing::ApprovalRequirement;
+use crate::tools::sandboxing::ToolCtx;
+use crate::tools::sandboxing::ToolError;
+
+pub struct WebFetchHandler;
+
+#[derive(Deserialize)]
+struct WebFetchArgs {
+    url: String,
+    #[serde(default)]
+    method: FetchMethod,
+    /// Lower than `web_fetch.max_bytes` to read less of the body.
+    #[serde(default)]
+    max_bytes: Option<usize>,
+}
+
+#[async_trait]
+impl ToolHandler for WebFetchHandler {
+    fn kind(&self) -> ToolKind {
+        ToolKind::Function
+    }
+
+    async fn handle(&self, invocation: ToolInvocation) -> Result<ToolOutput, FunctionCallError> {
+        let ToolInvocation {
+            session,
+            turn,
+            call_id,
+            tool_name,
+            payload,
+            ..
+        } = invocation;
+
+        let ToolPayload::Function { arguments } = payload else {
+            return Err(FunctionCallError::RespondToModel(format!(
+                "unsupported payload for web_fetch handler: {tool_name}"
+            )));
+        };
+
+        let args: WebFetchArgs = serde_json::from_str(&arguments).map_err(|err| {
+            FunctionCallError::RespondToModel(format!(
+                "failed to parse function arguments: {err:?}"
+            ))
+        })?;
+        let url = Url::parse(&args.url).map_err(|err| {
+            FunctionCallError::RespondToModel(format!("`{}` is not a URL: {err}", args.url))
+        })?;
+        if !matches!(url.scheme(), "http" | "https") || url.host_str().is_none() {
+            return Err(FunctionCallError::RespondToModel(format!(
+                "`{url}` is not an http or https URL"
+            )));
+        }
+
+        let config = turn.client.config();
+        let settings = &config.web_fetch;
+        let limit = settings.max_bytes.unwrap_or(WEB_FETCH_MAX_BYTES);
+        let cache_ttl = settings
+            .cache_ttl_secs
+            .map_or(WEB_FETCH_CACHE_TTL, Duration::from_secs);
+        let mut req = WebFetchRequest {
+            url,
+            method: args.method,
+            cwd: turn.cwd.clone(),
+            max_bytes: args
+                .max_bytes
+                .map_or(limit, |max_bytes| max_bytes.min(limit)),
+            cache_ttl: (!cache_ttl.is_zero()).then_some(cache_ttl),
+            allowed_domains: settings.allowed_domains.clone(),
+            approval_requirement: ApprovalRequirement::Skip {
+                bypass_sandbox: false,
+            },
+        };
+        // Allowed domains need no approval. Any other fetch is approved like
+        // the equivalent `curl` command that asks for network access to the
+        // host, so exec policy rules for `curl` apply to it too.
+        if !req.is_allowed(req.url.host_str().unwrap_or_default()) {
+            let curl = vec!["curl".to_string(), req.url.to_string()];
+            let requirement = create_approval_requirement_for_command(
+                &turn.exec_policy,
+                &curl,
+                turn.approval_policy,
+                &turn.sandbox_policy,
+                SandboxPermissions::from(false),
+            );
+            req.approval_requirement = network_requirement(&turn, &req.network(), requirement);
+        }
+
+        let mut orchestrator = ToolOrchestrator::new();
+        let mut runtime = WebFetchRuntime::new();
+        let tool_ctx = ToolCtx {
+            session: session.as_ref(),
+            turn: turn.as_ref(),
+            call_id: call_id.clone(),
+            tool_name: tool_name.clone(),
+        };
+        let output = orchestrator
+            .run(
+                &mut runtime,
+                Arc::new(req),
+                &tool_ctx,
+                &turn,
+                turn.approval_policy,
+            )
+            .await
+            .map_err(|err| match err {
+                ToolError::Rejected(reason) => FunctionCallError::RespondToModel(reason),
+                ToolError::Codex(err) => FunctionCallError::RespondToModel(err.to_string()),
+            })?;
+
+        if let Some(replay) = &session.services.replay {
+            let input = ReplayInput::Fetch(&output.url);
+            replay
+                .record_input(&call_id, input, output.content.as_bytes())
+                .await;
+        }
+
+        Ok(ToolOutput::Function {
+            content: format_output(&output),
+            content_items: None,
+            success: Some((200..400).contains(&output.status)),
+        })
+    }
+}
+
+/// The response as the model sees it: a few header lines, then the body.
+fn format_output(output: &WebFetchOutput) -> String {
+    let mut lines = vec![
+        format!("URL: {}", output.url),
+        format!("Status: {}", output.status),
+    ];
+    if let Some(content_type) = &output.content_type {
+        lines.push(format!("Content-Type: {content_type}"));
+    }
+    if let Some(content_length) = output.content_length {
+        lines.push(format!("Content-Length: {content_length}"));
+    }
+    if output.truncated {
+        lines.push("Note: the body was cut short at the size limit".to_string());
+    }
+    if output.cached {
+        lines.push("Note: served from this session's cache".to_string());
+    }
+    if !output.content.is_empty() {
+        lines.push(String::new());
+        lines.push(output.content.clone());
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn output_lists_headers_before_the_body() {
+        let output = WebFetchOutput {
+            url: "https://example.com/".to_string(),
+            status: 200,
+            content_type: Some("text/html".to_string()),
+            content_length: None,
+            content: "# Example".to_string(),
+            truncated: true,
+            cached: false,
+        };
+        assert_eq!(
+            format_output(&output),
+            "URL: https://example.com/\nStatus: 200\nContent-Type: text/html\n\
+             Note: the body was cut short at the size limit\n\n# Example"
+        );
+    }
+}
@@ -0,0 +1,157 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use codex_apply_patch::TextFormat;
+use serde::Deserialize;
+
+use crate::function_tool::FunctionCallError;
+use crate::tools::context::ToolInvocation;
+use crate::tools::context::ToolOutput;
+use crate::tools::context::ToolPayload;
+use crate::tools::events::ToolEmitter;
+use crate::tools::events::ToolEventCtx;
+use crate::tools::orchestrator::ToolOrchestrator;
+use crate::tools::registry::ToolHandler;
+use crate::tools::registry::ToolKind;
+use crate::tools::runtimes::write_file::WriteFileRequest;
+use crate::tools::runtimes::write_file::WriteFileRuntime;
+use crate::tools::runtimes::write_file::file_change;
+use crate::tools::runtimes::write_file::write_approval_requirement;
+use crate::tools::sandboxing::ApprovalRequirement;
+use crate::tools::sandboxing::ToolCtx;
+
+pub struct WriteFileHandler;
+
+#[derive(Deserialize)]
+struct WriteFileArgs {
+    /// Absolute path to the file to create or overwrite.
+    file_path: String,
+    /// The file's complete new contents.
+    content: String,
+}
+
+#[async_trait]
+impl ToolHandler for WriteFileHandler {
+    fn kind(&self) -> ToolKind {
+        ToolKind::Function
+    }
+
+    fn is_mutating(&self, _invocation: &ToolInvocation) -> bool {
+        true
+    }
+
+    async fn handle(&self, invocation: ToolInvocation) -> Result<ToolOutput, FunctionCallError> {
+        let ToolInvocation {
+            session,
+            turn,
+            tracker,
+            call_id,
+            tool_name,
+            payload,
+        } = invocation;
+
+        let ToolPayload::Function { arguments } = payload else {
+            return Err(FunctionCallError::RespondToModel(
+                "write_file handler received unsupported payload".to_string(),
+            ));
+        };
+
+        let args: WriteFileArgs = serde_json::from_str(&arguments).map_err(|err| {
+            FunctionCallError::RespondToModel(format!(
+                "failed to parse function arguments: {err:?}"
+            ))
+        })?;
+
+        let path = PathBuf::from(&args.file_path);
+        if !path.is_absolute() {
+            return Err(FunctionCallError::RespondToModel(
+                "file_path must be an absolute path".to_string(),
+            ));
+        }
+
+        let existing = match tokio::fs::read(&path).await {
+            Ok(bytes) => match TextFormat::decode(&bytes) {
+                Some(decoded) => Some(decoded),
+                None => {
+                    return Err(FunctionCallError::RespondToModel(format!(
+                        "{} is not a text file; write_file only overwrites text files",
+                        path.display()
+                    )));
+                }
+            },
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+            Err(err) => {
+                return Err(FunctionCallError::RespondToModel(format!(
+                    "failed to read {}: {err}",
+                    path.display()
+                )));
+            }
+        };
+        // Line endings are restored from the existing file on write.
+        let contents = args.content.replace("\r\n", "\n");
+        let (old, format) = match existing {
+            Some((old, format)) => (Some(old), format),
+            None => (None, TextFormat::default()),
+        };
+        if old.as_deref() == Some(contents.as_str()) {
+            return Ok(ToolOutput::Function {
+                content: format!("{} already has these contents", path.display()),
+                content_items: None,
+                success: Some(true),
+            });
+        }
+
+        let changes = HashMap::from([(path.clone(), file_change(old.as_deref(), &contents))]);
+        let approval_requirement = write_approval_requirement(
+            &path,
+            turn.approval_policy,
+            &turn.sandbox_policy,
+            &turn.cwd,
+        );
+        let emitter = ToolEmitter::apply_patch(
+            changes.clone(),
+            matches!(approval_requirement, ApprovalRequirement::Skip { .. }),
+        );
+        let req = WriteFileRequest {
+            path,
+            cwd: turn.cwd.clone(),
+            contents,
+            format,
+            exists: old.is_some(),
+            changes,
+            approval_requirement,
+        };
+
+        let event_ctx =
+            ToolEventCtx::new(session.as_ref(), turn.as_ref(), &call_id, Some(&tracker));
+        emitter.begin(event_ctx).await;
+
+        let mut orchestrator = ToolOrchestrator::new();
+        let mut runtime = WriteFileRuntime::new();
+        let tool_ctx = ToolCtx {
+            session: session.as_ref(),
+            turn: turn.as_ref(),
+            call_id: call_id.clone(),
+            tool_name,
+        };
+        let out = orchestrator
+            .run(
+                &mut runtime,
+                Arc::new(req),
+                &tool_ctx,
+                &turn,
+                turn.approval_policy,
+            )
+            .await;
+        let event_ctx =
+            ToolEventCtx::new(session.as_ref(), turn.as_ref(), &call_id, Some(&tracker));
+        let content = emitter.finish(event_ctx, out).await?;
+        Ok(ToolOutput::Function {
+            content,
+            content_items: None,
+            success: Some(true),
+        })
+    }
+}
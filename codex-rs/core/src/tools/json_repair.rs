@@ -0,0 +1,148 @@
+//! Repair of nearly valid JSON in tool call arguments.
+//!
+//! Models, local ones especially, sometimes write arguments that are almost
+//! JSON: a trailing comma, a raw newline inside a string, strings in single
+//! quotes. Rejecting those costs a whole turn for the model to resend the
+//! same call. With the `json_repair` feature, arguments that do not parse are
+//! passed through one tolerant rewrite, and the call runs with the result
+//! when that parses; the output of the call tells the model what was fixed.
+
+use serde_json::Value;
+
+/// One kind of fix applied to the arguments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum JsonRepair {
+    TrailingComma,
+    ControlCharacter,
+    SingleQuotes,
+}
+
+impl JsonRepair {
+    fn describe(self) -> &'static str {
+        match self {
+            JsonRepair::TrailingComma => "removed a trailing comma",
+            JsonRepair::ControlCharacter => "escaped a line break or tab inside a string",
+            JsonRepair::SingleQuotes => "replaced single quotes around strings with double quotes",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct RepairedJson {
+    pub(crate) json: String,
+    /// In the order first applied, without repeats.
+    pub(crate) repairs: Vec<JsonRepair>,
+}
+
+impl RepairedJson {
+    /// The note prepended to the output of the repaired call.
+    pub(crate) fn note(&self) -> String {
+        let repairs: Vec<&str> = self
+            .repairs
+            .iter()
+            .map(|repair| repair.describe())
+            .collect();
+        format!(
+            "Note: the arguments of this call were not valid JSON and were repaired before it \
+             ran ({}). Send valid JSON arguments.",
+            repairs.join("; ")
+        )
+    }
+}
+
+/// Rewrite `input` into valid JSON. Returns `None` when `input` is already
+/// valid, or when the rewrite does not parse either.
+pub(crate) fn repair_json(input: &str) -> Option<RepairedJson> {
+    if serde_json::from_str::<Value>(input).is_ok() {
+        return None;
+    }
+    let mut json = String::with_capacity(input.len());
+    let mut repairs = Vec::new();
+    let mut note = |repair: JsonRepair| {
+        if !repairs.contains(&repair) {
+            repairs.push(repair);
+        }
+    };
+    // The quote that opened the string being copied, if any.
+    let mut quote: Option<char> = None;
+    let mut chars = input.char_indices();
+    while let Some((index, c)) = chars.next() {
+        match (quote, c) {
+            (None, '"' | '\'') => {
+                if c == '\'' {
+                    note(JsonRepair::SingleQuotes);
+                }
+                quote = Some(c);
+                json.push('"');
+            }
+            (None, ',') => {
+                let rest = input[index + 1..].trim_start();
+                if rest.starts_with('}') || rest.starts_with(']') {
+                    note(JsonRepair::TrailingComma);
+                } else {
+                    json.push(',');
+                }
+            }
+            (None, c) => json.push(c),
+            (Some(open), '\\') => match chars.next() {
+                // `\'` is not a JSON escape, and a single quote needs none.
+                Some((_, '\'')) if open == '\'' => json.push('\''),
+                Some((_, escaped)) => {
+                    json.push('\\');
+                    json.push(escaped);
+                }
+                None => json.push('\\'),
+            },
+            (Some(open), c) if c == open => {
+                quote = None;
+                json.push('"');
+            }
+            (Some(_), '"') => json.push_str("\\\""),
+            (Some(_), c) if (c as u32) < 0x20 => {
+                note(JsonRepair::ControlCharacter);
+                match c {
+                    '\n' => json.push_str("\\n"),
+                    '\r' => json.push_str("\\r"),
+                    '\t' => json.push_str("\\t"),
+                    c => json.push_str(&format!("\\u{:04x}", c as u32)),
+                }
+            }
+            (Some(_), c) => json.push(c),
+        }
+    }
+    if repairs.is_empty() || serde_json::from_str::<Value>(&json).is_err() {
+        return None;
+    }
+    Some(RepairedJson { json, repairs })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn nearly_valid_arguments_are_repaired() {
+        let input = "{'command': ['echo', 'say \"hi\"', 'don\\'t',], 'script': \"a\n\tb\",}";
+        assert_eq!(
+            repair_json(input),
+            Some(RepairedJson {
+                json: r#"{"command": ["echo", "say \"hi\"", "don't"], "script": "a\n\tb"}"#
+                    .to_string(),
+                repairs: vec![
+                    JsonRepair::SingleQuotes,
+                    JsonRepair::TrailingComma,
+                    JsonRepair::ControlCharacter,
+                ],
+            })
+        );
+    }
+
+    #[test]
+    fn valid_and_hopeless_arguments_are_left_alone() {
+        assert_eq!(repair_json(r#"{"command": ["ls", "a,]"]}"#), None);
+        assert_eq!(repair_json("{\"command\": [\"ls\""), None);
+        assert_eq!(repair_json("command: ls"), None);
+        assert_eq!(repair_json("{'command': ['echo', 'it''s'],}"), None);
+    }
+}
@@ -1,16 +1,26 @@
+pub(crate) mod approval_key;
 pub mod context;
+pub mod custom;
 pub mod events;
 pub mod executor;
+#[cfg(feature = "fault-injection")]
+pub mod fault_injection;
 pub(crate) mod handlers;
+pub(crate) mod json_repair;
 pub mod orchestrator;
 pub mod parallel;
+pub(crate) mod patch_builder;
 pub mod registry;
 pub mod router;
 pub mod runtimes;
 pub mod sandboxing;
+pub(crate) mod scratch;
 pub mod spec;
+pub(crate) mod validation;
 
 use crate::exec::ExecToolCallOutput;
+use crate::exec::ExitReason;
+use crate::exec::StreamOutput;
 use crate::truncate::TruncationPolicy;
 use crate::truncate::formatted_truncate_text;
 use crate::truncate::truncate_text;
@@ -32,6 +42,7 @@ pub fn format_exec_output_for_model_structured(
     let ExecToolCallOutput {
         exit_code,
         duration,
+        exit_reason,
         ..
     } = exec_output;
 
@@ -39,6 +50,8 @@ pub fn format_exec_output_for_model_structured(
     struct ExecMetadata {
         exit_code: i32,
         duration_seconds: f32,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        exit_reason: Option<ExitReason>,
     }
 
     #[derive(Serialize)]
@@ -57,6 +70,7 @@ pub fn format_exec_output_for_model_structured(
         metadata: ExecMetadata {
             exit_code: *exit_code,
             duration_seconds,
+            exit_reason: (!exit_reason.is_exit_status()).then_some(*exit_reason),
         },
     };
 
@@ -78,6 +92,9 @@ pub fn format_exec_output_for_model_freeform(
     let mut sections = Vec::new();
 
     sections.push(format!("Exit code: {}", exec_output.exit_code));
+    if let Some(stopped_by) = stopped_by(exec_output) {
+        sections.push(format!("Stopped by: {stopped_by}"));
+    }
     sections.push(format!("Wall time: {duration_seconds} seconds"));
     if total_lines != formatted_output.lines().count() {
         sections.push(format!("Total output lines: {total_lines}"));
@@ -85,10 +102,43 @@ pub fn format_exec_output_for_model_freeform(
 
     sections.push("Output:".to_string());
     sections.push(formatted_output);
+    if let Some(path) = &exec_output.full_output_path {
+        sections.push(format!("Full output: {}", path.display()));
+    }
 
     sections.join("\n")
 }
 
+/// What stopped a command, when its exit status does not say.
+fn stopped_by(exec_output: &ExecToolCallOutput) -> Option<String> {
+    match exec_output.exit_reason {
+        ExitReason::Success | ExitReason::NonZeroExit => None,
+        ExitReason::TimedOut => Some("its timeout".to_string()),
+        ExitReason::SandboxDenied => Some("the sandbox, which refused it access".to_string()),
+        ExitReason::Signal(signal) => Some(format!("signal {signal}")),
+        ExitReason::LimitExceeded(limit) => Some(format!("the sandbox's {limit} limit")),
+        ExitReason::SpawnFailure => Some("a failure to start it".to_string()),
+    }
+}
+
+/// `exec_output` with its combined output replaced by stdout and stderr in
+/// sections headed `[stdout]` and `[stderr]`, so that the model can tell
+/// them apart. A stream that printed nothing is left out.
+pub(crate) fn with_labeled_streams(exec_output: &ExecToolCallOutput) -> ExecToolCallOutput {
+    let sections: Vec<String> = [
+        ("stdout", &exec_output.stdout.text),
+        ("stderr", &exec_output.stderr.text),
+    ]
+    .into_iter()
+    .filter(|(_, text)| !text.is_empty())
+    .map(|(label, text)| format!("[{label}]\n{}", text.trim_end_matches('\n')))
+    .collect();
+    ExecToolCallOutput {
+        aggregated_output: StreamOutput::new(sections.join("\n")),
+        ..exec_output.clone()
+    }
+}
+
 pub fn format_exec_output_str(
     exec_output: &ExecToolCallOutput,
     truncation_policy: TruncationPolicy,
@@ -99,15 +149,109 @@ pub fn format_exec_output_str(
 
     let content = aggregated_output.text.as_str();
 
-    let body = if exec_output.timed_out {
+    let body = if exec_output.timed_out() {
         format!(
             "command timed out after {} milliseconds\n{content}",
             exec_output.duration.as_millis()
         )
+    } else if let Some(limit) = exec_output.limit_exceeded() {
+        format!("command was stopped by the sandbox's {limit} limit\n{content}")
     } else {
         content.to_string()
     };
 
     // Truncate for model consumption before serialization.
-    formatted_truncate_text(&body, truncation_policy)
+    let mut formatted = formatted_truncate_text(&body, truncation_policy);
+    if let Some(path) = &exec_output.full_output_path {
+        formatted.push_str(&format!("\n[full output: {}]", path.display()));
+    }
+    formatted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exec::ResourceLimit;
+    use pretty_assertions::assert_eq;
+    use std::time::Duration;
+
+    #[test]
+    fn labeled_streams_are_given_separate_sections() {
+        let output = ExecToolCallOutput {
+            exit_code: 1,
+            stdout: StreamOutput::new("compiling\nlinking\n".to_string()),
+            stderr: StreamOutput::new("error: undefined symbol\n".to_string()),
+            aggregated_output: StreamOutput::new(
+                "compiling\nerror: undefined symbol\nlinking\n".to_string(),
+            ),
+            duration: Duration::from_secs(1),
+            full_output_path: None,
+            exit_reason: ExitReason::NonZeroExit,
+        };
+        assert_eq!(
+            with_labeled_streams(&output).aggregated_output.text,
+            "[stdout]\ncompiling\nlinking\n[stderr]\nerror: undefined symbol"
+        );
+
+        let quiet = ExecToolCallOutput {
+            stderr: StreamOutput::new(String::new()),
+            ..output
+        };
+        assert_eq!(
+            with_labeled_streams(&quiet).aggregated_output.text,
+            "[stdout]\ncompiling\nlinking"
+        );
+    }
+
+    #[test]
+    fn exit_reason_is_shown_when_the_exit_code_does_not_tell_it() {
+        let output = ExecToolCallOutput {
+            exit_code: 124,
+            stdout: StreamOutput::new(String::new()),
+            stderr: StreamOutput::new(String::new()),
+            aggregated_output: StreamOutput::new("still waiting".to_string()),
+            duration: Duration::from_secs(10),
+            full_output_path: None,
+            exit_reason: ExitReason::TimedOut,
+        };
+        let policy = TruncationPolicy::Bytes(1024);
+        assert_eq!(
+            format_exec_output_for_model_freeform(&output, policy),
+            "Exit code: 124\nStopped by: its timeout\nWall time: 10 seconds\nOutput:\nstill waiting"
+        );
+        let structured: serde_json::Value =
+            serde_json::from_str(&format_exec_output_for_model_structured(&output, policy))
+                .unwrap_or_default();
+        assert_eq!(structured["metadata"]["exit_reason"], "timed_out");
+
+        let killed = ExecToolCallOutput {
+            exit_reason: ExitReason::Signal(9),
+            ..output.clone()
+        };
+        let structured: serde_json::Value =
+            serde_json::from_str(&format_exec_output_for_model_structured(&killed, policy))
+                .unwrap_or_default();
+        assert_eq!(structured["metadata"]["exit_reason"]["signal"], 9);
+
+        let limited = ExecToolCallOutput {
+            exit_code: 137,
+            exit_reason: ExitReason::LimitExceeded(ResourceLimit::Memory),
+            ..output.clone()
+        };
+        let structured: serde_json::Value =
+            serde_json::from_str(&format_exec_output_for_model_structured(&limited, policy))
+                .unwrap_or_default();
+        assert_eq!(
+            structured["metadata"]["exit_reason"]["limit_exceeded"],
+            "memory"
+        );
+
+        let failed = ExecToolCallOutput {
+            exit_code: 1,
+            exit_reason: ExitReason::NonZeroExit,
+            ..output
+        };
+        assert!(!format_exec_output_for_model_freeform(&failed, policy).contains("Stopped by"));
+        assert!(!format_exec_output_for_model_structured(&failed, policy).contains("exit_reason"));
+    }
 }
@@ -3,24 +3,59 @@ Module: orchestrator
 
 Central place for approvals + sandbox selection + retry semantics. Drives a
 simple sequence for any ToolRuntime: approval → select sandbox → attempt →
-retry without sandbox on denial (no re‑approval thanks to caching).
+retry without sandbox on denial (no re‑approval thanks to caching). A denial
+is diagnosed first; writes refused under `sandbox_denials.auto_grant` are
+retried in the sandbox with the directories they need made writable.
+Commands that escalate privileges through `sudo` or discard uncommitted git
+work are held to the `privilege_escalation` and `git_guard` policies here,
+whichever tool runs them.
+
+The request is shared through an `Arc` rather than cloned for each step; a
+runtime that needs the retry to differ overrides fields copy-on-write.
 */
+use crate::approval_rules::AppliedRule;
+use crate::approval_rules::find_approval_rule;
+use crate::audit::AuditAction;
+use crate::audit::AuditTrail;
+use crate::audit::DecidedBy;
+use crate::audit::audit_outcome;
+use crate::codex::TurnContext;
+use crate::command_safety::destructive_git::git_guard_requirement;
+use crate::command_safety::privilege_escalation::privilege_escalation_requirement;
 use crate::error::CodexErr;
 use crate::error::SandboxErr;
 use crate::error::get_error_message_ui;
-use crate::exec::ExecToolCallOutput;
+use crate::project_cache::project_cache_dir;
+use crate::sandbox_profiles::apply_sandbox_profile;
+use crate::sandbox_profiles::find_sandbox_profile;
 use crate::sandboxing::SandboxManager;
+use crate::sandboxing::denial::SandboxDenial;
+use crate::sandboxing::denial::auto_grant_roots;
+use crate::sandboxing::denial::describe_denials;
+use crate::sandboxing::denial::diagnose_denial;
+use crate::sandboxing::risk::classify_command;
+use crate::sandboxing::risk::merge_assessments;
 use crate::tools::sandboxing::ApprovalCtx;
 use crate::tools::sandboxing::ApprovalRequirement;
 use crate::tools::sandboxing::ProvidesSandboxRetryData;
 use crate::tools::sandboxing::SandboxAttempt;
 use crate::tools::sandboxing::SandboxOverride;
+use crate::tools::sandboxing::SandboxRetryData;
 use crate::tools::sandboxing::ToolCtx;
 use crate::tools::sandboxing::ToolError;
 use crate::tools::sandboxing::ToolRuntime;
 use crate::tools::sandboxing::default_approval_requirement;
+use crate::tools::scratch::ScratchDir;
+use crate::tools::scratch::with_writable_root;
+use codex_protocol::protocol::ApprovalRuleAppliedEvent;
+use codex_protocol::protocol::ApprovalRuleDecision;
 use codex_protocol::protocol::AskForApproval;
+use codex_protocol::protocol::EventMsg;
 use codex_protocol::protocol::ReviewDecision;
+use codex_protocol::protocol::SandboxPolicy;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Instant;
 
 pub(crate) struct ToolOrchestrator {
     sandbox: SandboxManager,
@@ -36,10 +71,55 @@ impl ToolOrchestrator {
     pub async fn run<Rq, Out, T>(
         &mut self,
         tool: &mut T,
-        req: &Rq,
+        req: Arc<Rq>,
+        tool_ctx: &ToolCtx<'_>,
+        turn_ctx: &crate::codex::TurnContext,
+        approval_policy: AskForApproval,
+    ) -> Result<Out, ToolError>
+    where
+        T: ToolRuntime<Rq, Out>,
+        Rq: ProvidesSandboxRetryData,
+    {
+        let audit_log = tool_ctx.session.services.audit_log.as_ref();
+        let action = audit_log.and_then(|_| {
+            tool.audit_action(&req).or_else(|| {
+                req.sandbox_retry_data().map(|data| AuditAction::Command {
+                    command: data.command,
+                    cwd: data.cwd,
+                })
+            })
+        });
+        let started = Instant::now();
+        let mut trail = AuditTrail::default();
+        let result = self
+            .run_attempts(tool, req, tool_ctx, turn_ctx, approval_policy, &mut trail)
+            .await;
+        if let (Some(audit_log), Some(action)) = (audit_log, action) {
+            let outcome = audit_outcome(&result, |out| tool.exit_code(out));
+            audit_log
+                .record(
+                    &turn_ctx.sub_id,
+                    &tool_ctx.call_id,
+                    &tool_ctx.tool_name,
+                    action,
+                    trail,
+                    outcome,
+                    started.elapsed(),
+                )
+                .await;
+        }
+        result
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn run_attempts<Rq, Out, T>(
+        &mut self,
+        tool: &mut T,
+        mut req: Arc<Rq>,
         tool_ctx: &ToolCtx<'_>,
         turn_ctx: &crate::codex::TurnContext,
         approval_policy: AskForApproval,
+        trail: &mut AuditTrail,
     ) -> Result<Out, ToolError>
     where
         T: ToolRuntime<Rq, Out>,
@@ -54,20 +134,81 @@ impl ToolOrchestrator {
         // 1) Approval
         let mut already_approved = false;
 
-        let requirement = tool.approval_requirement(req).unwrap_or_else(|| {
+        let requirement = tool.approval_requirement(&req).unwrap_or_else(|| {
             default_approval_requirement(approval_policy, &turn_ctx.sandbox_policy)
         });
+        let mut retry_data = req.sandbox_retry_data();
+        let config = turn_ctx.client.config();
+        // The sandbox keeps `sudo` from gaining privileges, so an approved
+        // escalation runs outside it.
+        let escalation = retry_data.as_ref().and_then(|data| {
+            privilege_escalation_requirement(
+                &config.privilege_escalation,
+                &data.command,
+                approval_policy,
+                &requirement,
+            )
+        });
+        let escalates = escalation.is_some();
+        let requirement = escalation.unwrap_or(requirement);
+        let guard = match &retry_data {
+            Some(data) if !escalates => {
+                git_guard_requirement(
+                    &config.git_guard,
+                    &data.command,
+                    &data.cwd,
+                    approval_policy,
+                    &requirement,
+                )
+                .await
+            }
+            _ => None,
+        };
+        let (requirement, stash_first) = match guard {
+            Some(outcome) => (outcome.requirement, outcome.stash),
+            None => (requirement, Vec::new()),
+        };
+        let rule = approval_rule_for(retry_data.as_ref(), &requirement, tool_ctx, turn_ctx).await;
+        let auto_approved = rule
+            .as_ref()
+            .is_some_and(|rule| rule.decision == ApprovalRuleDecision::AutoApprove);
+        let ruled = rule.is_some();
+        let requirement = match rule {
+            Some(rule) => apply_approval_rule(rule, requirement, approval_policy),
+            None => requirement,
+        };
+        let static_risk = retry_data
+            .as_ref()
+            .and_then(|data| classify_command(&data.command, &data.cwd, &turn_ctx.sandbox_policy));
+        // Refused under every approval policy, `never` included; only an
+        // approval rule written for the command lets it through.
+        if let (Some(level), Some(risk)) = (config.risk_assessment.auto_deny, &static_risk)
+            && risk.risk_level >= level
+            && !auto_approved
+        {
+            let level = level.as_str();
+            let description = &risk.description;
+            return Err(ToolError::Rejected(format!(
+                "blocked as {level} risk: {description}"
+            )));
+        }
         match requirement {
             ApprovalRequirement::Skip { .. } => {
                 otel.tool_decision(otel_tn, otel_ci, ReviewDecision::Approved, otel_cfg);
+                if auto_approved {
+                    trail.decided_by = DecidedBy::Rule;
+                }
             }
             ApprovalRequirement::Forbidden { reason } => {
+                if ruled {
+                    trail.decided_by = DecidedBy::Rule;
+                }
                 return Err(ToolError::Rejected(reason));
             }
             ApprovalRequirement::NeedsApproval { reason } => {
                 let mut risk = None;
 
-                if let Some(metadata) = req.sandbox_retry_data() {
+                if let Some(metadata) = &retry_data {
                     risk = tool_ctx
                         .session
                         .assess_sandbox_command(
@@ -78,6 +219,7 @@ impl ToolOrchestrator {
                         )
                         .await;
                 }
+                let risk = merge_assessments(risk, static_risk.as_ref());
 
                 let approval_ctx = ApprovalCtx {
                     session: tool_ctx.session,
@@ -86,7 +228,9 @@ impl ToolOrchestrator {
                     retry_reason: reason,
                     risk,
                 };
-                let decision = tool.start_approval_async(req, approval_ctx).await;
+                let decision = tool.start_approval_async(&req, approval_ctx).await;
+                trail.decision = Some(decision);
+                trail.decided_by = DecidedBy::User;
 
                 otel.tool_decision(otel_tn, otel_ci, decision, otel_user.clone());
 
@@ -94,41 +238,169 @@ impl ToolOrchestrator {
                     ReviewDecision::Denied | ReviewDecision::Abort => {
                         return Err(ToolError::Rejected("rejected by user".to_string()));
                     }
-                    ReviewDecision::Approved | ReviewDecision::ApprovedForSession => {}
+                    ReviewDecision::Approved
+                    | ReviewDecision::ApprovedForSession
+                    | ReviewDecision::ApprovedPrefixForSession => {}
                 }
                 already_approved = true;
             }
         }
 
+        for plan in &stash_first {
+            plan.stash().await.map_err(|err| {
+                ToolError::Rejected(format!(
+                    "failed to stash uncommitted work ({err}); the command was not run"
+                ))
+            })?;
+        }
+
         // 2) First attempt under the selected sandbox.
-        let initial_sandbox = match tool.sandbox_mode_for_first_attempt(req) {
-            SandboxOverride::BypassSandboxFirstAttempt => crate::exec::SandboxType::None,
-            SandboxOverride::NoOverride => self
-                .sandbox
-                .select_initial(&turn_ctx.sandbox_policy, tool.sandbox_preference()),
+        let bypass_sandbox = escalates
+            || matches!(
+                tool.sandbox_mode_for_first_attempt(&req),
+                SandboxOverride::BypassSandboxFirstAttempt
+            );
+        let initial_sandbox = if bypass_sandbox {
+            crate::exec::SandboxType::None
+        } else {
+            self.sandbox
+                .select_initial(&turn_ctx.sandbox_policy, tool.sandbox_preference())
+        };
+        let sandboxed_policy = !matches!(turn_ctx.sandbox_policy, SandboxPolicy::DangerFullAccess);
+        let scratch = if tool.wants_scratch_dir() {
+            ScratchDir::create(tool_ctx.scratch_dir())
+                .inspect_err(|err| tracing::warn!("failed to create scratch directory: {err}"))
+                .ok()
+        } else {
+            None
+        };
+        let profile = find_sandbox_profile(
+            &config.sandbox_profiles,
+            &config.sandbox_profile_rules,
+            &tool_ctx.tool_name,
+            retry_data.as_ref().map(|data| data.command.as_slice()),
+        );
+        let (profile_policy, sandbox_cwd) = match profile {
+            Some(profile) => {
+                apply_sandbox_profile(profile, &turn_ctx.sandbox_policy, &turn_ctx.cwd)
+            }
+            None => (turn_ctx.sandbox_policy.clone(), turn_ctx.cwd.clone()),
         };
+        let mut policy = match &scratch {
+            Some(scratch) => scratch.widen(&profile_policy),
+            None => profile_policy,
+        };
+        let scratch_dir = scratch.as_ref().map(ScratchDir::path);
+        let limits = profile
+            .and_then(|profile| profile.limits.as_ref())
+            .unwrap_or(&config.sandbox_limits);
+        let cache_dir = if config.project_cache.enabled {
+            project_cache_dir(&config.codex_home, &turn_ctx.cwd)
+                .inspect_err(|err| tracing::warn!("failed to create project cache: {err}"))
+                .ok()
+        } else {
+            None
+        };
+        if let Some(dir) = &cache_dir {
+            policy = with_writable_root(&policy, dir);
+        }
 
         // Platform-specific flag gating is handled by SandboxManager::select_initial
         // via crate::safety::get_platform_sandbox().
         let initial_attempt = SandboxAttempt {
             sandbox: initial_sandbox,
-            policy: &turn_ctx.sandbox_policy,
+            policy: &policy,
             manager: &self.sandbox,
-            sandbox_cwd: &turn_ctx.cwd,
+            sandbox_cwd: &sandbox_cwd,
             codex_linux_sandbox_exe: turn_ctx.codex_linux_sandbox_exe.as_ref(),
+            escalated: bypass_sandbox && sandboxed_policy,
+            scratch_dir,
+            cache_dir: cache_dir.as_deref(),
+            limits: (sandboxed_policy && !bypass_sandbox).then_some(limits),
+            container: None,
+            profile,
         };
+        trail.sandbox_policy = Some(policy.clone());
+        trail.sandbox = Some(initial_attempt.sandbox);
+        trail.escalated = initial_attempt.escalated;
 
-        match tool.run(req, &initial_attempt, tool_ctx).await {
+        match tool.run(&req, &initial_attempt, tool_ctx).await {
             Ok(out) => {
                 // We have a successful initial result
                 Ok(out)
             }
-            Err(ToolError::Codex(CodexErr::Sandbox(SandboxErr::Denied { output }))) => {
-                if !tool.escalate_on_failure() {
+            Err(ToolError::Codex(CodexErr::Sandbox(SandboxErr::Denied { mut output }))) => {
+                if !tool.escalate_on_failure(output.exit_reason) {
                     return Err(ToolError::Codex(CodexErr::Sandbox(SandboxErr::Denied {
                         output,
                     })));
                 }
+                let home = dirs::home_dir();
+                let denial_cwd = retry_data
+                    .as_ref()
+                    .map_or(turn_ctx.cwd.clone(), |data| data.cwd.clone());
+                let mut denials = diagnose_denial(&output, &denial_cwd);
+
+                // Writes refused under `sandbox_denials.auto_grant` are retried
+                // in the sandbox with just the directories they need.
+                let grants = auto_grant_roots(
+                    &denials,
+                    &config.sandbox_denials.auto_grant,
+                    home.as_deref(),
+                );
+                if !grants.is_empty()
+                    && initial_attempt.sandbox != crate::exec::SandboxType::None
+                    && matches!(policy, SandboxPolicy::WorkspaceWrite { .. })
+                {
+                    let mut granted_policy = policy.clone();
+                    for root in &grants {
+                        if let Err(err) = std::fs::create_dir_all(root) {
+                            tracing::warn!("failed to create {}: {err}", root.display());
+                        }
+                        granted_policy = with_writable_root(&granted_policy, root);
+                    }
+                    let shown: Vec<String> = grants
+                        .iter()
+                        .map(|root| root.display().to_string())
+                        .collect();
+                    tool_ctx
+                        .session
+                        .notify_background_event(
+                            turn_ctx,
+                            format!(
+                                "sandbox_denials.auto_grant: retrying with {} writable",
+                                shown.join(", ")
+                            ),
+                        )
+                        .await;
+                    let granted_attempt = SandboxAttempt {
+                        sandbox: initial_attempt.sandbox,
+                        policy: &granted_policy,
+                        manager: &self.sandbox,
+                        sandbox_cwd: &sandbox_cwd,
+                        codex_linux_sandbox_exe: initial_attempt.codex_linux_sandbox_exe,
+                        escalated: initial_attempt.escalated,
+                        scratch_dir,
+                        cache_dir: cache_dir.as_deref(),
+                        limits: initial_attempt.limits,
+                        container: initial_attempt.container,
+                        profile,
+                    };
+                    trail.sandbox_policy = Some(granted_policy.clone());
+                    match tool.run(&req, &granted_attempt, tool_ctx).await {
+                        Err(ToolError::Codex(CodexErr::Sandbox(SandboxErr::Denied {
+                            output: denied_again,
+                        }))) => {
+                            denials = diagnose_denial(&denied_again, &denial_cwd);
+                            output = denied_again;
+                        }
+                        other => return other,
+                    }
+                }
+                if let Some(data) = &mut retry_data {
+                    data.denials = denials.clone();
+                }
+
                 // Under `Never` or `OnRequest`, do not retry without sandbox; surface a concise
                 // sandbox denial that preserves the original output.
                 if !tool.wants_no_sandbox_approval(approval_policy) {
@@ -137,16 +409,19 @@ impl ToolOrchestrator {
                     })));
                 }
 
-                // Ask for approval before retrying without sandbox.
+                // Ask for approval before retrying without sandbox. An
+                // `auto-approve` rule only covers the sandboxed run.
                 if !tool.should_bypass_approval(approval_policy, already_approved) {
                     let mut risk = None;
 
-                    if let Some(metadata) = req.sandbox_retry_data() {
-                        let err = SandboxErr::Denied {
-                            output: output.clone(),
+                    if let Some(metadata) = &retry_data {
+                        let failure = match describe_denials(&metadata.denials, home.as_deref()) {
+                            Some(blocked) => blocked,
+                            None => get_error_message_ui(&CodexErr::Sandbox(SandboxErr::Denied {
+                                output: output.clone(),
+                            })),
                         };
-                        let friendly = get_error_message_ui(&CodexErr::Sandbox(err));
-                        let failure_summary = format!("failed in sandbox: {friendly}");
+                        let failure_summary = format!("failed in sandbox: {failure}");
 
                         risk = tool_ctx
                             .session
@@ -158,8 +433,9 @@ impl ToolOrchestrator {
                             )
                             .await;
                     }
+                    let risk = merge_assessments(risk, static_risk.as_ref());
 
-                    let reason_msg = build_denial_reason_from_output(output.as_ref());
+                    let reason_msg = build_denial_reason(&denials, home.as_deref());
                     let approval_ctx = ApprovalCtx {
                         session: tool_ctx.session,
                         turn: turn_ctx,
@@ -168,35 +444,411 @@ impl ToolOrchestrator {
                         risk,
                     };
 
-                    let decision = tool.start_approval_async(req, approval_ctx).await;
+                    let decision = tool.start_approval_async(&req, approval_ctx).await;
+                    trail.decision = Some(decision);
+                    trail.decided_by = DecidedBy::User;
                     otel.tool_decision(otel_tn, otel_ci, decision, otel_user);
 
                     match decision {
                         ReviewDecision::Denied | ReviewDecision::Abort => {
                             return Err(ToolError::Rejected("rejected by user".to_string()));
                         }
-                        ReviewDecision::Approved | ReviewDecision::ApprovedForSession => {}
+                        ReviewDecision::Approved
+                        | ReviewDecision::ApprovedForSession
+                        | ReviewDecision::ApprovedPrefixForSession => {}
                     }
                 }
 
                 let escalated_attempt = SandboxAttempt {
                     sandbox: crate::exec::SandboxType::None,
-                    policy: &turn_ctx.sandbox_policy,
+                    policy: &policy,
                     manager: &self.sandbox,
                     sandbox_cwd: &turn_ctx.cwd,
                     codex_linux_sandbox_exe: None,
+                    escalated: sandboxed_policy,
+                    scratch_dir,
+                    cache_dir: cache_dir.as_deref(),
+                    limits: None,
+                    container: None,
+                    profile,
                 };
+                trail.sandbox = Some(escalated_attempt.sandbox);
+                trail.escalated = escalated_attempt.escalated;
 
                 // Second attempt.
-                (*tool).run(req, &escalated_attempt, tool_ctx).await
+                tool.override_for_retry(&mut req);
+                (*tool).run(&req, &escalated_attempt, tool_ctx).await
             }
             other => other,
         }
     }
 }
 
-fn build_denial_reason_from_output(_output: &ExecToolCallOutput) -> String {
-    // Keep approval reason terse and stable for UX/tests, but accept the
-    // output so we can evolve heuristics later without touching call sites.
-    "command failed; retry without sandbox?".to_string()
+/// The configured approval rule matching the command described by `data`,
+/// announced to frontends. Rules cannot lift a requirement that forbids the
+/// command.
+async fn approval_rule_for(
+    data: Option<&SandboxRetryData>,
+    requirement: &ApprovalRequirement,
+    tool_ctx: &ToolCtx<'_>,
+    turn_ctx: &TurnContext,
+) -> Option<AppliedRule> {
+    if matches!(requirement, ApprovalRequirement::Forbidden { .. }) {
+        return None;
+    }
+    let data = data?;
+    let config = turn_ctx.client.config();
+    let rule = find_approval_rule(&config.approval_rules, &data.command, &data.cwd, &data.env)?;
+    tool_ctx
+        .session
+        .send_event(
+            turn_ctx,
+            EventMsg::ApprovalRuleApplied(ApprovalRuleAppliedEvent {
+                call_id: tool_ctx.call_id.clone(),
+                command: data.command.clone(),
+                cwd: data.cwd.clone(),
+                rule: rule.label.clone(),
+                decision: rule.decision,
+            }),
+        )
+        .await;
+    Some(rule)
+}
+
+fn apply_approval_rule(
+    rule: AppliedRule,
+    requirement: ApprovalRequirement,
+    approval_policy: AskForApproval,
+) -> ApprovalRequirement {
+    let label = rule.label;
+    match rule.decision {
+        ApprovalRuleDecision::AutoApprove => match requirement {
+            ApprovalRequirement::NeedsApproval { .. } => ApprovalRequirement::Skip {
+                bypass_sandbox: false,
+            },
+            other => other,
+        },
+        ApprovalRuleDecision::AutoDeny => ApprovalRequirement::Forbidden {
+            reason: format!("blocked by approval rule {label}"),
+        },
+        ApprovalRuleDecision::Ask if approval_policy == AskForApproval::Never => {
+            ApprovalRequirement::Forbidden {
+                reason: format!(
+                    "approval rule {label} asks before running this command; rejected by user \
+                     approval settings"
+                ),
+            }
+        }
+        ApprovalRuleDecision::Ask => match requirement {
+            ApprovalRequirement::NeedsApproval { reason } => {
+                ApprovalRequirement::NeedsApproval { reason }
+            }
+            _ => ApprovalRequirement::NeedsApproval {
+                reason: Some(format!(
+                    "Approval rule {label} asks before running this command."
+                )),
+            },
+        },
+    }
+}
+
+/// The reason shown when asking to retry a denied command without the
+/// sandbox: what the sandbox blocked, when the output tells.
+fn build_denial_reason(denials: &[SandboxDenial], home: Option<&Path>) -> String {
+    match describe_denials(denials, home) {
+        Some(blocked) => format!("{blocked}; retry without sandbox?"),
+        None => "command failed; retry without sandbox?".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::ModelClient;
+    use crate::codex::Session;
+    use crate::codex::make_session_and_context;
+    use crate::config::Config;
+    use crate::config::types::ApprovalRule;
+    use crate::exec::ExecToolCallOutput;
+    use crate::exec::ExitReason;
+    use crate::exec::SandboxType;
+    use crate::exec::StreamOutput;
+    use crate::tools::sandboxing::Approvable;
+    use crate::tools::sandboxing::Sandboxable;
+    use crate::tools::sandboxing::SandboxablePreference;
+    use futures::future::BoxFuture;
+    use pretty_assertions::assert_eq;
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    struct CommandRequest {
+        command: Vec<String>,
+        cwd: PathBuf,
+    }
+
+    impl ProvidesSandboxRetryData for CommandRequest {
+        fn sandbox_retry_data(&self) -> Option<SandboxRetryData> {
+            Some(SandboxRetryData {
+                command: self.command.clone(),
+                cwd: self.cwd.clone(),
+                env: HashMap::new(),
+                denials: Vec::new(),
+            })
+        }
+    }
+
+    /// A tool that runs without asking, approves whatever it is asked
+    /// about, and records the reasons it was asked with and the sandboxes
+    /// it ran in. With `deny_first`, the first attempt fails as the sandbox
+    /// would fail it.
+    #[derive(Default)]
+    struct RecordingRuntime {
+        deny_first: bool,
+        asked: Vec<Option<String>>,
+        sandboxes: Vec<SandboxType>,
+    }
+
+    impl Sandboxable for RecordingRuntime {
+        fn sandbox_preference(&self) -> SandboxablePreference {
+            SandboxablePreference::Auto
+        }
+    }
+
+    impl Approvable<CommandRequest> for RecordingRuntime {
+        type ApprovalKey = Vec<String>;
+
+        fn approval_key(&self, req: &CommandRequest) -> Self::ApprovalKey {
+            req.command.clone()
+        }
+
+        fn approval_requirement(&self, _req: &CommandRequest) -> Option<ApprovalRequirement> {
+            Some(ApprovalRequirement::Skip {
+                bypass_sandbox: false,
+            })
+        }
+
+        fn start_approval_async<'a>(
+            &'a mut self,
+            _req: &'a CommandRequest,
+            ctx: ApprovalCtx<'a>,
+        ) -> BoxFuture<'a, ReviewDecision> {
+            self.asked.push(ctx.retry_reason);
+            Box::pin(async { ReviewDecision::Approved })
+        }
+    }
+
+    impl ToolRuntime<CommandRequest, ()> for RecordingRuntime {
+        async fn run(
+            &mut self,
+            _req: &CommandRequest,
+            attempt: &SandboxAttempt<'_>,
+            _ctx: &ToolCtx<'_>,
+        ) -> Result<(), ToolError> {
+            self.sandboxes.push(attempt.sandbox);
+            if self.deny_first && self.sandboxes.len() == 1 {
+                let output = ExecToolCallOutput {
+                    exit_code: 1,
+                    stdout: StreamOutput::new(String::new()),
+                    stderr: StreamOutput::new(String::new()),
+                    aggregated_output: StreamOutput::new(String::new()),
+                    duration: Duration::ZERO,
+                    full_output_path: None,
+                    exit_reason: ExitReason::SandboxDenied,
+                };
+                return Err(ToolError::Codex(CodexErr::Sandbox(SandboxErr::Denied {
+                    output: Box::new(output),
+                })));
+            }
+            Ok(())
+        }
+    }
+
+    /// A session and turn whose configuration `configure` adjusted.
+    fn session_and_turn(configure: impl FnOnce(&mut Config)) -> (Session, TurnContext) {
+        let (session, mut turn) = make_session_and_context();
+        let mut config = (*turn.client.config()).clone();
+        configure(&mut config);
+        turn.client = ModelClient::new(
+            Arc::new(config),
+            turn.client.get_auth_manager(),
+            turn.client.get_otel_event_manager(),
+            turn.client.get_provider(),
+            turn.client.get_reasoning_effort(),
+            turn.client.get_reasoning_summary(),
+            session.conversation_id(),
+            turn.client.get_session_source(),
+        );
+        (session, turn)
+    }
+
+    async fn run_command(
+        runtime: &mut RecordingRuntime,
+        (session, turn): &(Session, TurnContext),
+        command: &[&str],
+        cwd: &Path,
+        approval_policy: AskForApproval,
+    ) -> Result<(), ToolError> {
+        let req = CommandRequest {
+            command: command.iter().map(|word| (*word).to_string()).collect(),
+            cwd: cwd.to_path_buf(),
+        };
+        let tool_ctx = ToolCtx {
+            session,
+            turn,
+            call_id: "call-1".to_string(),
+            tool_name: "run_tests".to_string(),
+        };
+        ToolOrchestrator::new()
+            .run(runtime, Arc::new(req), &tool_ctx, turn, approval_policy)
+            .await
+    }
+
+    /// A repository whose one tracked file has uncommitted changes, and a
+    /// way to run git in it.
+    fn dirty_repo() -> (tempfile::TempDir, impl Fn(&[&str]) -> String) {
+        let repo = tempfile::tempdir().expect("tempdir");
+        let cwd = repo.path().to_path_buf();
+        let git = move |args: &[&str]| {
+            let output = std::process::Command::new("git")
+                .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+                .args(args)
+                .current_dir(&cwd)
+                .output()
+                .expect("git");
+            assert!(output.status.success(), "git {args:?}");
+            String::from_utf8_lossy(&output.stdout).into_owned()
+        };
+        git(&["init", "-q"]);
+        std::fs::write(repo.path().join("tracked.txt"), "one\n").expect("write");
+        git(&["add", "tracked.txt"]);
+        git(&["commit", "-qm", "init"]);
+        std::fs::write(repo.path().join("tracked.txt"), "two\n").expect("write");
+        (repo, git)
+    }
+
+    #[tokio::test]
+    async fn privilege_escalation_is_asked_about_for_every_tool() {
+        let session = session_and_turn(|_| {});
+        let mut runtime = RecordingRuntime::default();
+        run_command(
+            &mut runtime,
+            &session,
+            &["sudo", "pytest"],
+            &session.1.cwd,
+            AskForApproval::OnRequest,
+        )
+        .await
+        .expect("approved");
+
+        assert_eq!(
+            runtime.asked,
+            vec![Some(
+                "Warning: this command runs `pytest` with elevated privileges through sudo. \
+                 Approve only if you would run it yourself."
+                    .to_string()
+            )]
+        );
+        assert_eq!(runtime.sandboxes, vec![SandboxType::None]);
+    }
+
+    #[tokio::test]
+    async fn privilege_escalation_is_refused_under_never() {
+        let session = session_and_turn(|_| {});
+        let mut runtime = RecordingRuntime::default();
+        let result = run_command(
+            &mut runtime,
+            &session,
+            &["sudo", "pytest"],
+            &session.1.cwd,
+            AskForApproval::Never,
+        )
+        .await;
+
+        assert!(matches!(
+            result,
+            Err(ToolError::Rejected(reason)) if reason.contains("rejected by user approval settings")
+        ));
+        assert!(runtime.sandboxes.is_empty());
+    }
+
+    #[tokio::test]
+    async fn destructive_git_is_asked_about_for_every_tool() {
+        let (repo, _git) = dirty_repo();
+        let session = session_and_turn(|_| {});
+        let mut runtime = RecordingRuntime::default();
+        run_command(
+            &mut runtime,
+            &session,
+            &["git", "reset", "--hard"],
+            repo.path(),
+            AskForApproval::OnRequest,
+        )
+        .await
+        .expect("approved");
+
+        assert_eq!(
+            runtime.asked,
+            vec![Some(
+                "Warning: this command discards uncommitted changes to 1 file (`tracked.txt`). \
+                 This cannot be undone."
+                    .to_string()
+            )]
+        );
+    }
+
+    #[tokio::test]
+    async fn a_retried_command_stashes_once() {
+        let (repo, git) = dirty_repo();
+        let session = session_and_turn(|config| config.git_guard.auto_stash = true);
+        let mut runtime = RecordingRuntime {
+            deny_first: true,
+            ..Default::default()
+        };
+        run_command(
+            &mut runtime,
+            &session,
+            &["git", "reset", "--hard"],
+            repo.path(),
+            AskForApproval::OnFailure,
+        )
+        .await
+        .expect("retried outside the sandbox");
+
+        assert_eq!(runtime.sandboxes.len(), 2);
+        assert_eq!(git(&["stash", "list"]).lines().count(), 1);
+    }
+
+    #[tokio::test]
+    async fn an_auto_approve_rule_does_not_cover_the_retry_without_sandbox() {
+        let session = session_and_turn(|config| {
+            config.approval_rules = vec![ApprovalRule {
+                name: None,
+                decision: ApprovalRuleDecision::AutoApprove,
+                command: Some("pytest*".to_string()),
+                command_regex: None,
+                cwd: None,
+                env: HashMap::new(),
+            }];
+        });
+        let mut runtime = RecordingRuntime {
+            deny_first: true,
+            ..Default::default()
+        };
+        run_command(
+            &mut runtime,
+            &session,
+            &["pytest"],
+            &session.1.cwd,
+            AskForApproval::OnFailure,
+        )
+        .await
+        .expect("retried outside the sandbox");
+
+        assert_eq!(
+            runtime.asked,
+            vec![Some("command failed; retry without sandbox?".to_string())]
+        );
+        assert_eq!(runtime.sandboxes.len(), 2);
+    }
 }
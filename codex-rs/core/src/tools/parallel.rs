@@ -1,15 +1,24 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::sync::PoisonError;
 use std::time::Instant;
 
+use tokio::sync::OwnedRwLockReadGuard;
+use tokio::sync::OwnedRwLockWriteGuard;
 use tokio::sync::RwLock;
 use tokio_util::either::Either;
 use tokio_util::sync::CancellationToken;
 use tokio_util::task::AbortOnDropHandle;
+use tracing::Instrument;
 
 use crate::codex::Session;
 use crate::codex::TurnContext;
 use crate::error::CodexErr;
+use crate::features::Feature;
 use crate::function_tool::FunctionCallError;
+use crate::git_info::get_git_repo_root;
 use crate::tools::context::SharedTurnDiffTracker;
 use crate::tools::context::ToolPayload;
 use crate::tools::router::ToolCall;
@@ -17,6 +26,10 @@ use crate::tools::router::ToolRouter;
 use codex_protocol::models::FunctionCallOutputPayload;
 use codex_protocol::models::ResponseInputItem;
 
+/// Tool calls that may run at once in a session with
+/// `parallel_tool_execution`.
+pub(crate) const MAX_PARALLEL_TOOL_CALLS: usize = 4;
+
 pub(crate) struct ToolCallRuntime {
     router: Arc<ToolRouter>,
     session: Arc<Session>,
@@ -47,6 +60,17 @@ impl ToolCallRuntime {
         cancellation_token: CancellationToken,
     ) -> impl std::future::Future<Output = Result<ResponseInputItem, CodexErr>> {
         let supports_parallel = self.router.tool_supports_parallel(&call.tool_name);
+        // With `parallel_tool_execution`, the other tools run in parallel too,
+        // bounded by the session's permits; what keeps two of them from
+        // writing the same workspace at once is its lock in `WorkspaceLocks`.
+        let parallel_execution = !supports_parallel
+            && self
+                .turn_context
+                .client
+                .config()
+                .features
+                .enabled(Feature::ParallelToolExecution);
+        let permits = Arc::clone(&self.session.services.tool_call_permits);
 
         let router = Arc::clone(&self.router);
         let session = Arc::clone(&self.session);
@@ -54,27 +78,38 @@ impl ToolCallRuntime {
         let tracker = Arc::clone(&self.tracker);
         let lock = Arc::clone(&self.parallel_execution);
         let started = Instant::now();
+        let span = tracing::info_span!(
+            "tool_call",
+            call_id = %call.call_id,
+            tool = %call.tool_name
+        );
+
+        let run = async move {
+            tokio::select! {
+                _ = cancellation_token.cancelled() => {
+                    let secs = started.elapsed().as_secs_f32().max(0.1);
+                    Ok(Self::aborted_response(&call, secs))
+                },
+                res = async {
+                    let _guard = if supports_parallel || parallel_execution {
+                        Either::Left(lock.read().await)
+                    } else {
+                        Either::Right(lock.write().await)
+                    };
+                    let _permit = if parallel_execution {
+                        permits.acquire_owned().await.ok()
+                    } else {
+                        None
+                    };
 
+                    router
+                        .dispatch_tool_call(session, turn, tracker, call.clone())
+                        .await
+                } => res,
+            }
+        };
         let handle: AbortOnDropHandle<Result<ResponseInputItem, FunctionCallError>> =
-            AbortOnDropHandle::new(tokio::spawn(async move {
-                tokio::select! {
-                    _ = cancellation_token.cancelled() => {
-                        let secs = started.elapsed().as_secs_f32().max(0.1);
-                        Ok(Self::aborted_response(&call, secs))
-                    },
-                    res = async {
-                        let _guard = if supports_parallel {
-                            Either::Left(lock.read().await)
-                        } else {
-                            Either::Right(lock.write().await)
-                        };
-
-                        router
-                            .dispatch_tool_call(session, turn, tracker, call.clone())
-                            .await
-                    } => res,
-                }
-            }));
+            AbortOnDropHandle::new(tokio::spawn(run.instrument(span)));
 
         async move {
             match handle.await {
@@ -119,3 +154,83 @@ impl ToolCallRuntime {
         }
     }
 }
+
+/// One lock per workspace, for tool calls that run in parallel: calls that
+/// only read share it, and a call that may write holds it alone. A call's
+/// workspace is the git repository its working directory is in, or the
+/// directory itself outside a repository, so that calls in different
+/// subdirectories of one checkout still wait for each other.
+#[derive(Default)]
+pub(crate) struct WorkspaceLocks {
+    locks: std::sync::Mutex<HashMap<PathBuf, Arc<RwLock<()>>>>,
+}
+
+pub(crate) type WorkspaceGuard = Either<OwnedRwLockReadGuard<()>, OwnedRwLockWriteGuard<()>>;
+
+impl WorkspaceLocks {
+    pub(crate) async fn lock(&self, cwd: &Path, exclusive: bool) -> WorkspaceGuard {
+        let workspace = get_git_repo_root(cwd).unwrap_or_else(|| cwd.to_path_buf());
+        let lock = {
+            let mut locks = self.locks.lock().unwrap_or_else(PoisonError::into_inner);
+            Arc::clone(locks.entry(workspace).or_default())
+        };
+        if exclusive {
+            Either::Right(lock.write_owned().await)
+        } else {
+            Either::Left(lock.read_owned().await)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn a_writer_waits_only_for_calls_in_its_own_directory() {
+        let locks = WorkspaceLocks::default();
+        let first = locks.lock(Path::new("/repo"), false).await;
+        let second = locks.lock(Path::new("/repo"), false).await;
+        let _elsewhere = locks.lock(Path::new("/other"), true).await;
+
+        let writer = locks.lock(Path::new("/repo"), true);
+        tokio::pin!(writer);
+        assert!(
+            tokio::time::timeout(Duration::from_millis(20), &mut writer)
+                .await
+                .is_err()
+        );
+        drop(first);
+        drop(second);
+        assert!(
+            tokio::time::timeout(Duration::from_millis(20), &mut writer)
+                .await
+                .is_ok()
+        );
+    }
+
+    #[tokio::test]
+    async fn directories_of_one_repository_share_a_lock() {
+        let repo = tempfile::tempdir().expect("tempdir");
+        std::fs::create_dir(repo.path().join(".git")).expect("mkdir .git");
+        let subdir = repo.path().join("crates/app");
+        std::fs::create_dir_all(&subdir).expect("mkdir");
+
+        let locks = WorkspaceLocks::default();
+        let reader = locks.lock(&subdir, false).await;
+        let writer = locks.lock(repo.path(), true);
+        tokio::pin!(writer);
+        assert!(
+            tokio::time::timeout(Duration::from_millis(20), &mut writer)
+                .await
+                .is_err()
+        );
+        drop(reader);
+        assert!(
+            tokio::time::timeout(Duration::from_millis(20), &mut writer)
+                .await
+                .is_ok()
+        );
+    }
+}
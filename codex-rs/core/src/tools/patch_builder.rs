@@ -0,0 +1,141 @@
+//! Builds `apply_patch` envelopes from before/after file contents.
+//!
+//! Tools that compute edits programmatically (bulk find-and-replace,
+//! scaffolding, codemods) render them with [`PatchBuilder`] and hand the text
+//! to the regular `apply_patch` pipeline, so the user reviews one
+//! consolidated patch instead of a batch of opaque in-place rewrites.
+
+use std::path::Path;
+
+use similar::ChangeTag;
+use similar::TextDiff;
+
+/// Unchanged lines kept around each hunk so it can be located on apply.
+const CONTEXT_LINES: usize = 3;
+
+#[derive(Default)]
+pub(crate) struct PatchBuilder {
+    body: String,
+    files: usize,
+}
+
+impl PatchBuilder {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn add_file(&mut self, path: &Path, contents: &str) {
+        self.body
+            .push_str(&format!("*** Add File: {}\n", path.display()));
+        for line in contents.lines() {
+            self.body.push('+');
+            self.body.push_str(line);
+            self.body.push('\n');
+        }
+        self.files += 1;
+    }
+
+    /// Record an update of `path` from `old` to `new`. Returns `false` (and
+    /// records nothing) when the contents are identical.
+    pub(crate) fn update_file(&mut self, path: &Path, old: &str, new: &str) -> bool {
+        if old == new {
+            return false;
+        }
+        self.body
+            .push_str(&format!("*** Update File: {}\n", path.display()));
+        let diff = TextDiff::from_lines(old, new);
+        for group in diff.grouped_ops(CONTEXT_LINES) {
+            self.body.push_str("@@\n");
+            for op in &group {
+                for change in diff.iter_changes(op) {
+                    self.body.push(match change.tag() {
+                        ChangeTag::Equal => ' ',
+                        ChangeTag::Delete => '-',
+                        ChangeTag::Insert => '+',
+                    });
+                    let value = change.value();
+                    self.body
+                        .push_str(value.strip_suffix('\n').unwrap_or(value));
+                    self.body.push('\n');
+                }
+            }
+        }
+        self.files += 1;
+        true
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.files == 0
+    }
+
+    pub(crate) fn file_count(&self) -> usize {
+        self.files
+    }
+
+    pub(crate) fn build(self) -> String {
+        format!("*** Begin Patch\n{}*** End Patch\n", self.body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_apply_patch::ApplyPatchFileChange;
+    use codex_apply_patch::MaybeApplyPatchVerified;
+    use codex_apply_patch::maybe_parse_apply_patch_verified;
+    use pretty_assertions::assert_eq;
+    use tempfile::tempdir;
+
+    #[test]
+    fn renders_update_hunks_with_context() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\nold\n";
+        let new = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\nnew\n";
+        let mut builder = PatchBuilder::new();
+        assert!(!builder.update_file(Path::new("same.txt"), old, old));
+        assert!(builder.update_file(Path::new("src/lib.rs"), old, new));
+
+        assert_eq!(
+            builder.build(),
+            "*** Begin Patch\n\
+             *** Update File: src/lib.rs\n\
+             @@\n \
+             h\n \
+             i\n \
+             j\n\
+             -old\n\
+             +new\n\
+             *** End Patch\n"
+        );
+    }
+
+    #[test]
+    fn built_patch_round_trips_through_apply_patch_verification() {
+        let dir = tempdir().expect("tempdir");
+        let old = "fn foo() {}\n\nfn bar() {\n    foo();\n}\n\nfn baz() {}\n";
+        let new = "fn qux() {}\n\nfn bar() {\n    qux();\n}\n\nfn baz() {}\n";
+        std::fs::write(dir.path().join("lib.rs"), old).expect("write");
+
+        let mut builder = PatchBuilder::new();
+        builder.update_file(Path::new("lib.rs"), old, new);
+        builder.add_file(Path::new("new.rs"), "pub mod x;\n");
+        assert_eq!(builder.file_count(), 2);
+
+        let argv = vec!["apply_patch".to_string(), builder.build()];
+        let MaybeApplyPatchVerified::Body(action) =
+            maybe_parse_apply_patch_verified(&argv, dir.path())
+        else {
+            panic!("patch did not verify");
+        };
+        let new_content = match action.changes().get(&dir.path().join("lib.rs")) {
+            Some(ApplyPatchFileChange::Update { new_content, .. }) => new_content.clone(),
+            other => panic!("unexpected change: {other:?}"),
+        };
+        assert_eq!(new_content, new);
+        assert_eq!(
+            action.changes().get(&dir.path().join("new.rs")),
+            Some(&ApplyPatchFileChange::Add {
+                content: "pub mod x;\n".to_string(),
+            })
+        );
+    }
+}
@@ -1,8 +1,10 @@
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
 
 use crate::client_common::tools::ToolSpec;
+use crate::features::Feature;
 use crate::function_tool::FunctionCallError;
 use crate::tools::context::ToolInvocation;
 use crate::tools::context::ToolOutput;
@@ -34,6 +36,12 @@ pub trait ToolHandler: Send + Sync {
         false
     }
 
+    /// The directory the call works in, whose workspace lock it takes with
+    /// `parallel_tool_execution`.
+    fn workdir(&self, invocation: &ToolInvocation) -> PathBuf {
+        invocation.turn.cwd.clone()
+    }
+
     async fn handle(&self, invocation: ToolInvocation) -> Result<ToolOutput, FunctionCallError>;
 }
 
@@ -110,7 +118,21 @@ impl ToolRegistry {
                     let output_cell = &output_cell;
                     let invocation = invocation;
                     async move {
-                        if handler.is_mutating(&invocation) {
+                        let mutating = handler.is_mutating(&invocation);
+                        let parallel = invocation
+                            .turn
+                            .client
+                            .config()
+                            .features
+                            .enabled(Feature::ParallelToolExecution);
+                        let _workspace_guard = if parallel {
+                            let locks = &invocation.session.services.workspace_locks;
+                            let workdir = handler.workdir(&invocation);
+                            Some(locks.lock(&workdir, mutating).await)
+                        } else {
+                            None
+                        };
+                        if mutating {
                             tracing::trace!("waiting for tool gate");
                             invocation.turn.tool_call_gate.wait_ready().await;
                             tracing::trace!("tool gate released");
@@ -184,6 +206,10 @@ impl ToolRegistryBuilder {
             .push(ConfiguredToolSpec::new(spec, supports_parallel_tool_calls));
     }
 
+    pub fn has_handler(&self, name: &str) -> bool {
+        self.handlers.contains_key(name)
+    }
+
     pub fn register_handler(&mut self, name: impl Into<String>, handler: Arc<dyn ToolHandler>) {
         let name = name.into();
         if self
@@ -4,14 +4,17 @@ use std::sync::Arc;
 use crate::client_common::tools::ToolSpec;
 use crate::codex::Session;
 use crate::codex::TurnContext;
+use crate::features::Feature;
 use crate::function_tool::FunctionCallError;
 use crate::tools::context::SharedTurnDiffTracker;
 use crate::tools::context::ToolInvocation;
 use crate::tools::context::ToolPayload;
+use crate::tools::json_repair::repair_json;
 use crate::tools::registry::ConfiguredToolSpec;
 use crate::tools::registry::ToolRegistry;
 use crate::tools::spec::ToolsConfig;
 use crate::tools::spec::build_specs;
+use crate::tools::validation::validate_arguments;
 use codex_protocol::models::LocalShellAction;
 use codex_protocol::models::ResponseInputItem;
 use codex_protocol::models::ResponseItem;
@@ -116,6 +119,8 @@ impl ToolRouter {
                             timeout_ms: exec.timeout_ms,
                             with_escalated_permissions: None,
                             justification: None,
+                            background: None,
+                            network: None,
                         };
                         Ok(Some(ToolCall {
                             tool_name: "local_shell".to_string(),
@@ -139,11 +144,43 @@ impl ToolRouter {
         let ToolCall {
             tool_name,
             call_id,
-            payload,
+            mut payload,
         } = call;
+        let features = &turn.client.config().features;
+        let repair_note = match &mut payload {
+            ToolPayload::Function { arguments } | ToolPayload::UnifiedExec { arguments }
+                if features.enabled(Feature::JsonRepair) =>
+            {
+                repair_json(arguments).map(|repaired| {
+                    let note = repaired.note();
+                    *arguments = repaired.json;
+                    note
+                })
+            }
+            _ => None,
+        };
+        if repair_note.is_some() {
+            session
+                .notify_background_event(
+                    &turn,
+                    format!("repaired malformed JSON arguments of `{tool_name}`"),
+                )
+                .await;
+        }
         let payload_outputs_custom = matches!(payload, ToolPayload::Custom { .. });
         let failure_call_id = call_id.clone();
 
+        if let ToolPayload::Function { arguments } = &payload
+            && features.enabled(Feature::ArgumentValidation)
+            && let Err(message) = self.validate(&tool_name, arguments)
+        {
+            return Ok(Self::failure_response(
+                failure_call_id,
+                payload_outputs_custom,
+                FunctionCallError::RespondToModel(message),
+            ));
+        }
+
         let invocation = ToolInvocation {
             session,
             turn,
@@ -153,10 +190,20 @@ impl ToolRouter {
             payload,
         };
 
-        match self.registry.dispatch(invocation).await {
-            Ok(response) => Ok(response),
-            Err(FunctionCallError::Fatal(message)) => Err(FunctionCallError::Fatal(message)),
-            Err(err) => Ok(Self::failure_response(
+        match (self.registry.dispatch(invocation).await, repair_note) {
+            (
+                Ok(ResponseInputItem::FunctionCallOutput {
+                    call_id,
+                    mut output,
+                }),
+                Some(note),
+            ) => {
+                output.content = format!("{note}\n\n{}", output.content);
+                Ok(ResponseInputItem::FunctionCallOutput { call_id, output })
+            }
+            (Ok(response), _) => Ok(response),
+            (Err(FunctionCallError::Fatal(message)), _) => Err(FunctionCallError::Fatal(message)),
+            (Err(err), _) => Ok(Self::failure_response(
                 failure_call_id,
                 payload_outputs_custom,
                 err,
@@ -164,6 +211,18 @@ impl ToolRouter {
         }
     }
 
+    /// Check the arguments of a call to a function tool against its schema.
+    fn validate(&self, tool_name: &str, arguments: &str) -> Result<(), String> {
+        let schema = self.specs.iter().find_map(|config| match &config.spec {
+            ToolSpec::Function(tool) if tool.name == tool_name => Some(&tool.parameters),
+            _ => None,
+        });
+        match schema {
+            Some(schema) => validate_arguments(tool_name, schema, arguments),
+            None => Ok(()),
+        }
+    }
+
     fn failure_response(
         call_id: String,
         payload_outputs_custom: bool,
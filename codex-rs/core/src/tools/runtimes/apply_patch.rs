@@ -3,10 +3,22 @@
 //! Assumes `apply_patch` verification/approval happened upstream. Reuses that
 //! decision to avoid re-prompting, builds the self-invocation command for
 //! `codex --codex-run-as-apply-patch`, and runs under the current
-//! `SandboxAttempt` with a minimal environment.
+//! `SandboxAttempt` with a minimal environment. A dry run only re-verifies
+//! the patch and reports what it would change, without writing anything.
+//! With the `undo_patch` feature, a patch that succeeds is journaled so that
+//! it can be reverted later.
+use crate::apply_patch::dry_run_report;
+use crate::apply_patch::verify_patch;
+use crate::audit::AuditAction;
 use crate::exec::ExecToolCallOutput;
+use crate::exec::ExitReason;
+use crate::exec::StreamOutput;
+use crate::features::Feature;
+use crate::patch_journal::PatchSnapshot;
 use crate::tools::sandboxing::Approvable;
+use crate::tools::sandboxing::ApprovalConditions;
 use crate::tools::sandboxing::ApprovalCtx;
+use crate::tools::sandboxing::ApprovalRequirement;
 use crate::tools::sandboxing::ProvidesSandboxRetryData;
 use crate::tools::sandboxing::SandboxAttempt;
 use crate::tools::sandboxing::SandboxRetryData;
@@ -15,19 +27,33 @@ use crate::tools::sandboxing::SandboxablePreference;
 use crate::tools::sandboxing::ToolCtx;
 use crate::tools::sandboxing::ToolError;
 use crate::tools::sandboxing::ToolRuntime;
-use crate::tools::sandboxing::with_cached_approval;
+use crate::tools::sandboxing::with_cached_approval_under;
+use crate::trash::drop_undeleted;
+use crate::trash::keep_copies;
+use crate::trash::trash_dir;
+use codex_protocol::paths::normalize_for_comparison;
 use codex_protocol::protocol::AskForApproval;
 use codex_protocol::protocol::ReviewDecision;
 use futures::future::BoxFuture;
 use std::path::PathBuf;
+use std::time::Duration;
 
 #[derive(Clone, Debug)]
 pub struct ApplyPatchRequest {
     pub patch: String,
     pub cwd: PathBuf,
+    /// Files the patch writes; a session approval of the patch lapses once
+    /// any of them changes.
+    pub files: Vec<PathBuf>,
+    /// Files the patch deletes, which are copied to the session trash first
+    /// when the `trash` feature is on.
+    pub deleted: Vec<PathBuf>,
     pub timeout_ms: Option<u64>,
     pub user_explicitly_approved: bool,
     pub codex_exe: Option<PathBuf>,
+    /// Only check that the patch applies cleanly and report the files and
+    /// diff it would produce; nothing is written and no approval is asked.
+    pub dry_run: bool,
 }
 
 impl ProvidesSandboxRetryData for ApplyPatchRequest {
@@ -55,8 +81,8 @@ impl Sandboxable for ApplyPatchRuntime {
     fn sandbox_preference(&self) -> SandboxablePreference {
         SandboxablePreference::Auto
     }
-    fn escalate_on_failure(&self) -> bool {
-        true
+    fn escalate_on_failure(&self, reason: ExitReason) -> bool {
+        reason == ExitReason::SandboxDenied
     }
 }
 
@@ -66,7 +92,7 @@ impl Approvable<ApplyPatchRequest> for ApplyPatchRuntime {
     fn approval_key(&self, req: &ApplyPatchRequest) -> Self::ApprovalKey {
         ApprovalKey {
             patch: req.patch.clone(),
-            cwd: req.cwd.clone(),
+            cwd: normalize_for_comparison(&req.cwd),
         }
     }
 
@@ -83,8 +109,9 @@ impl Approvable<ApplyPatchRequest> for ApplyPatchRuntime {
         let retry_reason = ctx.retry_reason.clone();
         let risk = ctx.risk.clone();
         let user_explicitly_approved = req.user_explicitly_approved;
+        let conditions = ApprovalConditions::for_turn(turn).with_files(&req.files);
         Box::pin(async move {
-            with_cached_approval(&session.services, key, move || async move {
+            with_cached_approval_under(&session.services, conditions, key, move || async move {
                 if let Some(reason) = retry_reason {
                     session
                         .request_command_approval(
@@ -106,6 +133,12 @@ impl Approvable<ApplyPatchRequest> for ApplyPatchRuntime {
         })
     }
 
+    fn approval_requirement(&self, req: &ApplyPatchRequest) -> Option<ApprovalRequirement> {
+        req.dry_run.then_some(ApprovalRequirement::Skip {
+            bypass_sandbox: false,
+        })
+    }
+
     fn wants_no_sandbox_approval(&self, policy: AskForApproval) -> bool {
         !matches!(policy, AskForApproval::Never)
     }
@@ -118,7 +151,84 @@ impl ToolRuntime<ApplyPatchRequest, ExecToolCallOutput> for ApplyPatchRuntime {
         attempt: &SandboxAttempt<'_>,
         ctx: &ToolCtx<'_>,
     ) -> Result<ExecToolCallOutput, ToolError> {
+        if req.dry_run {
+            return Ok(dry_run_output(req).await);
+        }
+        let config = ctx.turn.client.config();
+        let trash = (config.features.enabled(Feature::Trash) && !req.deleted.is_empty())
+            .then(|| trash_dir(&config.codex_home, ctx.session.conversation_id()));
+        let kept = match &trash {
+            Some(root) => keep_copies(root, &req.deleted)
+                .await
+                .map_err(|err| ToolError::Rejected(format!("{err}; the patch was not applied")))?,
+            None => Vec::new(),
+        };
+        let journal = ctx.session.services.patch_journal.as_ref();
+        let snapshot = match journal {
+            Some(_) => PatchSnapshot::take(&req.files)
+                .await
+                .inspect_err(|err| tracing::warn!("cannot journal the patch for undo: {err}"))
+                .ok(),
+            None => None,
+        };
         let executor = ctx.session.services.tool_executor.clone();
-        executor.run_apply_patch(req, attempt, ctx).await
+        let result = executor.run_apply_patch(req, attempt, ctx).await;
+        if let Some(root) = &trash {
+            drop_undeleted(root, &kept).await;
+        }
+        if let (Some(journal), Some(snapshot), Ok(output)) = (journal, snapshot, &result)
+            && output.exit_code == 0
+        {
+            match snapshot.applied(ctx.call_id.clone()).await {
+                Ok(entry) => journal.lock().await.push(entry),
+                Err(err) => tracing::warn!("cannot journal the patch for undo: {err}"),
+            }
+        }
+        result
+    }
+
+    fn audit_action(&self, req: &ApplyPatchRequest) -> Option<AuditAction> {
+        // A dry run changes nothing.
+        (!req.dry_run).then(|| AuditAction::Patch {
+            files: req.files.clone(),
+            cwd: req.cwd.clone(),
+        })
+    }
+
+    fn exit_code(&self, out: &ExecToolCallOutput) -> Option<i32> {
+        Some(out.exit_code)
+    }
+}
+
+/// Verify the patch against the files as they are now and report what it
+/// would change, in place of running it.
+async fn dry_run_output(req: &ApplyPatchRequest) -> ExecToolCallOutput {
+    let command = vec!["apply_patch".to_string(), req.patch.clone()];
+    let (exit_code, message) = match verify_patch(command, req.cwd.clone()).await {
+        codex_apply_patch::MaybeApplyPatchVerified::Body(action) => (0, dry_run_report(&action)),
+        codex_apply_patch::MaybeApplyPatchVerified::CorrectnessError(err) => {
+            (1, format!("Dry run: the patch does not apply: {err}"))
+        }
+        codex_apply_patch::MaybeApplyPatchVerified::ShellParseError(err) => (
+            1,
+            format!("Dry run: the patch could not be parsed: {err:?}"),
+        ),
+        codex_apply_patch::MaybeApplyPatchVerified::NotApplyPatch => {
+            (1, "Dry run: the input is not a patch".to_string())
+        }
+    };
+    let (stdout, stderr) = if exit_code == 0 {
+        (message.clone(), String::new())
+    } else {
+        (String::new(), message.clone())
+    };
+    ExecToolCallOutput {
+        exit_code,
+        stdout: StreamOutput::new(stdout),
+        stderr: StreamOutput::new(stderr),
+        aggregated_output: StreamOutput::new(message),
+        duration: Duration::ZERO,
+        full_output_path: None,
+        exit_reason: ExitReason::from_exit_code(exit_code),
     }
 }
@@ -0,0 +1,484 @@
+/*
+Runtime: bench
+
+Runs benchmark suites (criterion via `cargo bench`, hyperfine) under the
+orchestrator and normalizes their results into per-benchmark mean times.
+Results can be stored as named baselines under `.codex/bench` and compared
+against a later run to report regressions and improvements as deltas.
+*/
+use crate::exec::ExecToolCallOutput;
+use crate::tools::runtimes::CommandApprovalKey;
+use crate::tools::runtimes::command_retry_data;
+use crate::tools::runtimes::exec_via_executor;
+use crate::tools::runtimes::join_args;
+use crate::tools::runtimes::quote;
+use crate::tools::runtimes::request_command_approval;
+use crate::tools::sandboxing::Approvable;
+use crate::tools::sandboxing::ApprovalCtx;
+use crate::tools::sandboxing::ApprovalRequirement;
+use crate::tools::sandboxing::ProvidesSandboxRetryData;
+use crate::tools::sandboxing::SandboxAttempt;
+use crate::tools::sandboxing::SandboxRetryData;
+use crate::tools::sandboxing::Sandboxable;
+use crate::tools::sandboxing::SandboxablePreference;
+use crate::tools::sandboxing::ToolCtx;
+use crate::tools::sandboxing::ToolError;
+use crate::tools::sandboxing::ToolRuntime;
+use codex_protocol::protocol::ReviewDecision;
+use futures::future::BoxFuture;
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// Workspace-relative directory holding named baselines and run exports.
+pub(crate) const BENCH_DIR: &str = ".codex/bench";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BenchTool {
+    Criterion,
+    Hyperfine,
+}
+
+impl BenchTool {
+    /// Shell script that runs the suite. Hyperfine results are exported as
+    /// JSON to `export_path`; criterion results are parsed from stdout.
+    pub(crate) fn script(self, args: &[String], export_path: &Path) -> String {
+        let args = join_args(args);
+        match self {
+            BenchTool::Criterion => format!("cargo bench{args}"),
+            BenchTool::Hyperfine => {
+                let export = quote(&export_path.to_string_lossy());
+                format!("hyperfine --export-json {export}{args}")
+            }
+        }
+    }
+}
+
+/// Mean wall time for one benchmark, in nanoseconds.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Measurement {
+    pub name: String,
+    pub mean_ns: f64,
+}
+
+/// Parse criterion's human-readable output, e.g.
+/// `fib 20   time:   [24.500 µs 24.625 µs 24.750 µs]`. Long benchmark
+/// names are printed on their own line with the timing on the next one.
+pub(crate) fn parse_criterion_output(output: &str) -> Vec<Measurement> {
+    let mut measurements = Vec::new();
+    let mut pending_name: Option<&str> = None;
+    for line in output.lines() {
+        let Some((head, tail)) = line.split_once("time:") else {
+            let trimmed = line.trim();
+            pending_name = (!trimmed.is_empty() && !line.starts_with(' ')).then_some(trimmed);
+            continue;
+        };
+        let head = head.trim();
+        let name = if head.is_empty() {
+            pending_name.take()
+        } else {
+            Some(head)
+        };
+        pending_name = None;
+        let Some(name) = name else {
+            continue;
+        };
+        let Some(inner) = tail
+            .split_once('[')
+            .and_then(|(_, rest)| rest.split_once(']'))
+            .map(|(inner, _)| inner)
+        else {
+            continue;
+        };
+        // `[low unit estimate unit high unit]`: the middle pair is the estimate.
+        let tokens: Vec<&str> = inner.split_whitespace().collect();
+        if tokens.len() != 6 {
+            continue;
+        }
+        if let (Ok(value), Some(scale)) = (tokens[2].parse::<f64>(), unit_to_ns(tokens[3])) {
+            measurements.push(Measurement {
+                name: name.to_string(),
+                mean_ns: value * scale,
+            });
+        }
+    }
+    measurements
+}
+
+fn unit_to_ns(unit: &str) -> Option<f64> {
+    match unit {
+        "ps" => Some(1e-3),
+        "ns" => Some(1.0),
+        "µs" | "us" => Some(1e3),
+        "ms" => Some(1e6),
+        "s" => Some(1e9),
+        _ => None,
+    }
+}
+
+#[derive(Deserialize)]
+struct HyperfineExport {
+    results: Vec<HyperfineResult>,
+}
+
+#[derive(Deserialize)]
+struct HyperfineResult {
+    command: String,
+    /// Mean wall time in seconds.
+    mean: f64,
+}
+
+/// Parse a `hyperfine --export-json` document.
+pub(crate) fn parse_hyperfine_export(contents: &str) -> Result<Vec<Measurement>, String> {
+    let export: HyperfineExport = serde_json::from_str(contents)
+        .map_err(|err| format!("failed to parse hyperfine export: {err}"))?;
+    Ok(export
+        .results
+        .into_iter()
+        .map(|result| Measurement {
+            name: result.command,
+            mean_ns: result.mean * 1e9,
+        })
+        .collect())
+}
+
+/// A named set of measurements persisted under `.codex/bench/<name>.json`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Baseline {
+    pub name: String,
+    pub tool: BenchTool,
+    pub created_at: String,
+    pub measurements: Vec<Measurement>,
+}
+
+/// Baseline names become file names, so keep them to a safe alphabet.
+pub(crate) fn validate_baseline_name(name: &str) -> Result<(), String> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        && !name.starts_with('.');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "invalid baseline name `{name}`: use letters, digits, `-`, `_` or `.`"
+        ))
+    }
+}
+
+pub(crate) fn baseline_path(root: &Path, name: &str) -> PathBuf {
+    root.join(BENCH_DIR).join(format!("{name}.json"))
+}
+
+pub(crate) async fn save_baseline(root: &Path, baseline: &Baseline) -> std::io::Result<PathBuf> {
+    let path = baseline_path(root, &baseline.name);
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let json = serde_json::to_vec_pretty(baseline).map_err(std::io::Error::other)?;
+    tokio::fs::write(&path, json).await?;
+    Ok(path)
+}
+
+pub(crate) async fn load_baseline(root: &Path, name: &str) -> std::io::Result<Baseline> {
+    let contents = tokio::fs::read(baseline_path(root, name)).await?;
+    serde_json::from_slice(&contents).map_err(std::io::Error::other)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeltaKind {
+    Regression,
+    Improvement,
+    Unchanged,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct BenchDelta {
+    pub name: String,
+    pub baseline_ns: f64,
+    pub current_ns: f64,
+    /// Relative change in percent; positive means slower.
+    pub change_pct: f64,
+    pub kind: DeltaKind,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct BenchComparison {
+    pub deltas: Vec<BenchDelta>,
+    /// Benchmarks present only in the current run.
+    pub added: Vec<String>,
+    /// Benchmarks present only in the baseline.
+    pub missing: Vec<String>,
+}
+
+/// Compare `current` against `baseline`. Changes within `threshold_pct`
+/// percent are reported as unchanged to absorb measurement noise.
+pub(crate) fn compare(
+    baseline: &[Measurement],
+    current: &[Measurement],
+    threshold_pct: f64,
+) -> BenchComparison {
+    let before: BTreeMap<&str, f64> = baseline
+        .iter()
+        .map(|m| (m.name.as_str(), m.mean_ns))
+        .collect();
+    let after: BTreeMap<&str, f64> = current
+        .iter()
+        .map(|m| (m.name.as_str(), m.mean_ns))
+        .collect();
+
+    let mut comparison = BenchComparison::default();
+    for (name, current_ns) in &after {
+        let Some(baseline_ns) = before.get(name).copied() else {
+            comparison.added.push((*name).to_string());
+            continue;
+        };
+        let change_pct = if baseline_ns > 0.0 {
+            ((current_ns - baseline_ns) / baseline_ns * 10_000.0).round() / 100.0
+        } else {
+            0.0
+        };
+        let kind = if change_pct > threshold_pct {
+            DeltaKind::Regression
+        } else if change_pct < -threshold_pct {
+            DeltaKind::Improvement
+        } else {
+            DeltaKind::Unchanged
+        };
+        comparison.deltas.push(BenchDelta {
+            name: (*name).to_string(),
+            baseline_ns,
+            current_ns: *current_ns,
+            change_pct,
+            kind,
+        });
+    }
+    comparison.missing = before
+        .keys()
+        .filter(|name| !after.contains_key(*name))
+        .map(|name| (*name).to_string())
+        .collect();
+    comparison
+}
+
+#[derive(Clone, Debug)]
+pub struct BenchRequest {
+    pub tool: BenchTool,
+    pub command: Vec<String>,
+    pub cwd: PathBuf,
+    pub export_path: PathBuf,
+    pub timeout_ms: Option<u64>,
+    pub env: HashMap<String, String>,
+    pub approval_requirement: ApprovalRequirement,
+}
+
+impl ProvidesSandboxRetryData for BenchRequest {
+    fn sandbox_retry_data(&self) -> Option<SandboxRetryData> {
+        command_retry_data(&self.command, &self.cwd, &self.env)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct BenchRun {
+    pub output: ExecToolCallOutput,
+    pub measurements: Vec<Measurement>,
+}
+
+#[derive(Default)]
+pub struct BenchRuntime;
+
+impl BenchRuntime {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Sandboxable for BenchRuntime {
+    fn sandbox_preference(&self) -> SandboxablePreference {
+        SandboxablePreference::Auto
+    }
+}
+
+impl Approvable<BenchRequest> for BenchRuntime {
+    type ApprovalKey = CommandApprovalKey;
+
+    fn approval_key(&self, req: &BenchRequest) -> Self::ApprovalKey {
+        CommandApprovalKey::new(&req.command, &req.cwd)
+    }
+
+    fn start_approval_async<'a>(
+        &'a mut self,
+        req: &'a BenchRequest,
+        ctx: ApprovalCtx<'a>,
+    ) -> BoxFuture<'a, ReviewDecision> {
+        let reason = ctx.retry_reason.clone();
+        request_command_approval(self.approval_key(req), &req.command, &req.cwd, reason, ctx)
+    }
+
+    fn approval_requirement(&self, req: &BenchRequest) -> Option<ApprovalRequirement> {
+        Some(req.approval_requirement.clone())
+    }
+}
+
+impl ToolRuntime<BenchRequest, BenchRun> for BenchRuntime {
+    async fn run(
+        &mut self,
+        req: &BenchRequest,
+        attempt: &SandboxAttempt<'_>,
+        ctx: &ToolCtx<'_>,
+    ) -> Result<BenchRun, ToolError> {
+        if let Some(parent) = req.export_path.parent() {
+            tokio::fs::create_dir_all(parent).await.map_err(|err| {
+                ToolError::Rejected(format!(
+                    "failed to create benchmark directory {}: {err}",
+                    parent.display()
+                ))
+            })?;
+        }
+
+        let output = exec_via_executor(
+            req.command.clone(),
+            &req.cwd,
+            &req.env,
+            req.timeout_ms,
+            attempt,
+            ctx,
+        )
+        .await?;
+
+        let measurements = match req.tool {
+            BenchTool::Criterion => parse_criterion_output(&output.stdout.text),
+            BenchTool::Hyperfine => match tokio::fs::read_to_string(&req.export_path).await {
+                Ok(contents) => parse_hyperfine_export(&contents).map_err(ToolError::Rejected)?,
+                Err(_) => Vec::new(),
+            },
+        };
+
+        Ok(BenchRun {
+            output,
+            measurements,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::tempdir;
+
+    fn m(name: &str, mean_ns: f64) -> Measurement {
+        Measurement {
+            name: name.to_string(),
+            mean_ns,
+        }
+    }
+
+    #[test]
+    fn parses_criterion_inline_and_wrapped_names() {
+        let output = "\
+Benchmarking fib 20: Analyzing
+fib 20                  time:   [24.500 µs 24.625 µs 24.750 µs]
+                        change: [-1.2% +0.1% +1.3%] (p = 0.91 > 0.05)
+a/very/long/benchmark/name/that/wraps
+                        time:   [1.0000 ms 2.0000 ms 3.0000 ms]
+";
+        assert_eq!(
+            parse_criterion_output(output),
+            vec![
+                m("fib 20", 24_625.0),
+                m("a/very/long/benchmark/name/that/wraps", 2_000_000.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_hyperfine_export() {
+        let json = r#"{"results":[{"command":"sleep 0.5","mean":0.5,"stddev":0.001}]}"#;
+        assert_eq!(
+            parse_hyperfine_export(json),
+            Ok(vec![m("sleep 0.5", 500_000_000.0)])
+        );
+    }
+
+    #[test]
+    fn compare_classifies_deltas_with_threshold() {
+        let baseline = vec![
+            m("fast", 100.0),
+            m("slow", 100.0),
+            m("same", 100.0),
+            m("gone", 1.0),
+        ];
+        let current = vec![
+            m("fast", 80.0),
+            m("slow", 120.0),
+            m("same", 103.0),
+            m("new", 5.0),
+        ];
+
+        assert_eq!(
+            compare(&baseline, &current, 5.0),
+            BenchComparison {
+                deltas: vec![
+                    BenchDelta {
+                        name: "fast".to_string(),
+                        baseline_ns: 100.0,
+                        current_ns: 80.0,
+                        change_pct: -20.0,
+                        kind: DeltaKind::Improvement,
+                    },
+                    BenchDelta {
+                        name: "same".to_string(),
+                        baseline_ns: 100.0,
+                        current_ns: 103.0,
+                        change_pct: 3.0,
+                        kind: DeltaKind::Unchanged,
+                    },
+                    BenchDelta {
+                        name: "slow".to_string(),
+                        baseline_ns: 100.0,
+                        current_ns: 120.0,
+                        change_pct: 20.0,
+                        kind: DeltaKind::Regression,
+                    },
+                ],
+                added: vec!["new".to_string()],
+                missing: vec!["gone".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn baseline_names_are_restricted() {
+        assert_eq!(validate_baseline_name("main-2024.1"), Ok(()));
+        assert!(validate_baseline_name("../escape").is_err());
+        assert!(validate_baseline_name("").is_err());
+        assert!(validate_baseline_name(".hidden").is_err());
+    }
+
+    #[tokio::test]
+    async fn baselines_round_trip_through_bench_dir() {
+        let dir = tempdir().expect("tempdir");
+        let baseline = Baseline {
+            name: "main".to_string(),
+            tool: BenchTool::Hyperfine,
+            created_at: "2024-01-01T00:00:00Z".to_string(),
+            measurements: vec![m("sleep 0.1", 1.0)],
+        };
+
+        let path = save_baseline(dir.path(), &baseline)
+            .await
+            .expect("save baseline");
+        assert_eq!(path, dir.path().join(".codex/bench/main.json"));
+        assert_eq!(
+            load_baseline(dir.path(), "main").await.expect("load"),
+            baseline
+        );
+    }
+}
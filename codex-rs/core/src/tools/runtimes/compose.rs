@@ -0,0 +1,424 @@
+/*
+Runtime: compose
+
+Drives `docker compose up/down/ps/logs` for a workspace under the orchestrator.
+Projects brought up by a session are recorded on the session and torn down
+when it shuts down unless they were started with `keep`. `up` and `ps` also
+report structured per-service status parsed from `docker compose ps --format json`.
+*/
+use crate::exec::ExecToolCallOutput;
+use crate::tools::runtimes::CommandApprovalKey;
+use crate::tools::runtimes::command_retry_data;
+use crate::tools::runtimes::exec_via_executor;
+use crate::tools::runtimes::request_command_approval;
+use crate::tools::sandboxing::Approvable;
+use crate::tools::sandboxing::ApprovalCtx;
+use crate::tools::sandboxing::ApprovalRequirement;
+use crate::tools::sandboxing::ProvidesSandboxRetryData;
+use crate::tools::sandboxing::SandboxAttempt;
+use crate::tools::sandboxing::SandboxRetryData;
+use crate::tools::sandboxing::Sandboxable;
+use crate::tools::sandboxing::SandboxablePreference;
+use crate::tools::sandboxing::ToolCtx;
+use crate::tools::sandboxing::ToolError;
+use crate::tools::sandboxing::ToolRuntime;
+use codex_protocol::protocol::ReviewDecision;
+use futures::future::BoxFuture;
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+use tracing::warn;
+
+const TEARDOWN_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ComposeAction {
+    Up,
+    Down,
+    Ps,
+    Logs,
+}
+
+/// A compose project started by this session, identified by its directory and
+/// the compose files passed with `-f`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct ComposeProject {
+    pub cwd: PathBuf,
+    pub files: Vec<String>,
+    /// Leave the containers running when the session ends.
+    pub keep: bool,
+}
+
+impl ComposeProject {
+    /// `docker compose` followed by the `-f` flags selecting this project.
+    fn base_args(&self) -> Vec<String> {
+        let mut args = vec!["docker".to_string(), "compose".to_string()];
+        for file in &self.files {
+            args.push("-f".to_string());
+            args.push(file.clone());
+        }
+        args
+    }
+
+    /// Argv for `action` restricted to `services`. `logs` shows the last
+    /// `tail` lines.
+    pub(crate) fn action_args(
+        &self,
+        action: ComposeAction,
+        services: &[String],
+        tail: i64,
+    ) -> Vec<String> {
+        let mut args = self.base_args();
+        match action {
+            ComposeAction::Up => args.extend(["up", "--detach", "--wait"].map(String::from)),
+            ComposeAction::Down => args.extend(["down", "--remove-orphans"].map(String::from)),
+            ComposeAction::Ps => return self.status_args(),
+            ComposeAction::Logs => {
+                args.extend(["logs", "--no-color", "--timestamps", "--tail"].map(String::from));
+                args.push(tail.to_string());
+            }
+        }
+        if action != ComposeAction::Down {
+            args.extend(services.iter().cloned());
+        }
+        args
+    }
+
+    pub(crate) fn status_args(&self) -> Vec<String> {
+        let mut args = self.base_args();
+        args.extend(["ps", "--all", "--format", "json"].map(String::from));
+        args
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct PublishedPort {
+    pub url: String,
+    pub target_port: i64,
+    pub published_port: i64,
+    pub protocol: String,
+}
+
+/// Status of one service container as reported by `docker compose ps`.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ServiceStatus {
+    pub service: String,
+    pub container: String,
+    /// `running`, `exited`, `restarting`, ...
+    pub state: String,
+    /// `healthy`, `unhealthy` or `starting` when the service has a healthcheck.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub health: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i64>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub ports: Vec<PublishedPort>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct PsEntry {
+    #[serde(default)]
+    service: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    state: String,
+    #[serde(default)]
+    health: String,
+    #[serde(default)]
+    exit_code: i64,
+    #[serde(default)]
+    publishers: Option<Vec<PsPublisher>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct PsPublisher {
+    #[serde(rename = "URL", default)]
+    url: String,
+    #[serde(default)]
+    target_port: i64,
+    #[serde(default)]
+    published_port: i64,
+    #[serde(default)]
+    protocol: String,
+}
+
+/// Parse `docker compose ps --format json`. Compose >= 2.21 prints one JSON
+/// object per line; older releases print a single array.
+pub(crate) fn parse_ps_json(output: &str) -> Result<Vec<ServiceStatus>, String> {
+    let trimmed = output.trim();
+    let entries: Vec<PsEntry> = if trimmed.starts_with('[') {
+        serde_json::from_str(trimmed).map_err(|err| format!("invalid compose ps output: {err}"))?
+    } else {
+        trimmed
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(serde_json::from_str)
+            .collect::<Result<_, _>>()
+            .map_err(|err| format!("invalid compose ps output: {err}"))?
+    };
+
+    Ok(entries
+        .into_iter()
+        .map(|entry| ServiceStatus {
+            exit_code: (entry.state == "exited").then_some(entry.exit_code),
+            service: entry.service,
+            container: entry.name,
+            state: entry.state,
+            health: (!entry.health.is_empty()).then_some(entry.health),
+            ports: entry
+                .publishers
+                .unwrap_or_default()
+                .into_iter()
+                // Exposed-but-unpublished ports are reported with port 0.
+                .filter(|p| p.published_port != 0)
+                .map(|p| PublishedPort {
+                    url: p.url,
+                    target_port: p.target_port,
+                    published_port: p.published_port,
+                    protocol: p.protocol,
+                })
+                .collect(),
+        })
+        .collect())
+}
+
+/// Stop projects the session started, except those marked `keep`. Runs
+/// directly rather than through the orchestrator: the session is ending and
+/// these containers were already approved when they were brought up.
+pub(crate) async fn teardown_projects(projects: Vec<ComposeProject>) {
+    for project in projects.into_iter().filter(|project| !project.keep) {
+        let args = project.action_args(ComposeAction::Down, &[], 0);
+        let Some((program, args)) = args.split_first() else {
+            continue;
+        };
+        let result = tokio::time::timeout(
+            TEARDOWN_TIMEOUT,
+            tokio::process::Command::new(program)
+                .args(args)
+                .current_dir(&project.cwd)
+                .output(),
+        )
+        .await;
+        match result {
+            Ok(Ok(output)) if output.status.success() => {}
+            Ok(Ok(output)) => warn!(
+                "docker compose down failed in {}: {}",
+                project.cwd.display(),
+                String::from_utf8_lossy(&output.stderr)
+            ),
+            Ok(Err(err)) => warn!("failed to run docker compose down: {err}"),
+            Err(_) => warn!("docker compose down timed out in {}", project.cwd.display()),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ComposeRequest {
+    pub action: ComposeAction,
+    pub command: Vec<String>,
+    /// Follow-up `ps` run after `up` so the result includes service status.
+    pub status_command: Option<Vec<String>>,
+    pub cwd: PathBuf,
+    pub timeout_ms: Option<u64>,
+    pub env: HashMap<String, String>,
+    pub approval_requirement: ApprovalRequirement,
+}
+
+impl ProvidesSandboxRetryData for ComposeRequest {
+    fn sandbox_retry_data(&self) -> Option<SandboxRetryData> {
+        command_retry_data(&self.command, &self.cwd, &self.env)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ComposeRun {
+    pub output: ExecToolCallOutput,
+    pub services: Option<Vec<ServiceStatus>>,
+}
+
+#[derive(Default)]
+pub struct ComposeRuntime;
+
+impl ComposeRuntime {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Sandboxable for ComposeRuntime {
+    fn sandbox_preference(&self) -> SandboxablePreference {
+        SandboxablePreference::Auto
+    }
+}
+
+impl Approvable<ComposeRequest> for ComposeRuntime {
+    type ApprovalKey = CommandApprovalKey;
+
+    fn approval_key(&self, req: &ComposeRequest) -> Self::ApprovalKey {
+        CommandApprovalKey::new(&req.command, &req.cwd)
+    }
+
+    fn start_approval_async<'a>(
+        &'a mut self,
+        req: &'a ComposeRequest,
+        ctx: ApprovalCtx<'a>,
+    ) -> BoxFuture<'a, ReviewDecision> {
+        let reason = ctx.retry_reason.clone();
+        request_command_approval(self.approval_key(req), &req.command, &req.cwd, reason, ctx)
+    }
+
+    fn approval_requirement(&self, req: &ComposeRequest) -> Option<ApprovalRequirement> {
+        Some(req.approval_requirement.clone())
+    }
+}
+
+impl ToolRuntime<ComposeRequest, ComposeRun> for ComposeRuntime {
+    async fn run(
+        &mut self,
+        req: &ComposeRequest,
+        attempt: &SandboxAttempt<'_>,
+        ctx: &ToolCtx<'_>,
+    ) -> Result<ComposeRun, ToolError> {
+        let output = exec_via_executor(
+            req.command.clone(),
+            &req.cwd,
+            &req.env,
+            req.timeout_ms,
+            attempt,
+            ctx,
+        )
+        .await?;
+
+        let status_output = match (&req.status_command, req.action) {
+            (_, ComposeAction::Ps) => Some(output.stdout.text.clone()),
+            (Some(status_command), _) => {
+                let status = exec_via_executor(
+                    status_command.clone(),
+                    &req.cwd,
+                    &req.env,
+                    req.timeout_ms,
+                    attempt,
+                    ctx,
+                )
+                .await?;
+                (status.exit_code == 0).then_some(status.stdout.text)
+            }
+            (None, _) => None,
+        };
+        let services = match status_output {
+            Some(text) if output.exit_code == 0 => {
+                Some(parse_ps_json(&text).map_err(ToolError::Rejected)?)
+            }
+            _ => None,
+        };
+
+        Ok(ComposeRun { output, services })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn project(files: &[&str]) -> ComposeProject {
+        ComposeProject {
+            cwd: PathBuf::from("/work/repo"),
+            files: files.iter().map(|f| (*f).to_string()).collect(),
+            keep: false,
+        }
+    }
+
+    #[test]
+    fn builds_action_args() {
+        let db = vec!["db".to_string()];
+        assert_eq!(
+            project(&["compose.yaml", "compose.test.yaml"]).action_args(ComposeAction::Up, &db, 0),
+            vec![
+                "docker",
+                "compose",
+                "-f",
+                "compose.yaml",
+                "-f",
+                "compose.test.yaml",
+                "up",
+                "--detach",
+                "--wait",
+                "db",
+            ]
+        );
+        assert_eq!(
+            project(&[]).action_args(ComposeAction::Logs, &db, 50),
+            vec![
+                "docker",
+                "compose",
+                "logs",
+                "--no-color",
+                "--timestamps",
+                "--tail",
+                "50",
+                "db",
+            ]
+        );
+        assert_eq!(
+            project(&[]).action_args(ComposeAction::Down, &db, 0),
+            vec!["docker", "compose", "down", "--remove-orphans"]
+        );
+    }
+
+    #[test]
+    fn parses_ndjson_ps_output() {
+        let output = r#"{"Name":"repo-db-1","Service":"db","State":"running","Health":"healthy","ExitCode":0,"Publishers":[{"URL":"0.0.0.0","TargetPort":5432,"PublishedPort":5432,"Protocol":"tcp"},{"URL":"","TargetPort":8080,"PublishedPort":0,"Protocol":"tcp"}]}
+{"Name":"repo-migrate-1","Service":"migrate","State":"exited","Health":"","ExitCode":1,"Publishers":null}
+"#;
+        assert_eq!(
+            parse_ps_json(output),
+            Ok(vec![
+                ServiceStatus {
+                    service: "db".to_string(),
+                    container: "repo-db-1".to_string(),
+                    state: "running".to_string(),
+                    health: Some("healthy".to_string()),
+                    exit_code: None,
+                    ports: vec![PublishedPort {
+                        url: "0.0.0.0".to_string(),
+                        target_port: 5432,
+                        published_port: 5432,
+                        protocol: "tcp".to_string(),
+                    }],
+                },
+                ServiceStatus {
+                    service: "migrate".to_string(),
+                    container: "repo-migrate-1".to_string(),
+                    state: "exited".to_string(),
+                    health: None,
+                    exit_code: Some(1),
+                    ports: Vec::new(),
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn parses_legacy_array_ps_output() {
+        let output = r#"[{"Name":"repo-web-1","Service":"web","State":"running"}]"#;
+        assert_eq!(
+            parse_ps_json(output),
+            Ok(vec![ServiceStatus {
+                service: "web".to_string(),
+                container: "repo-web-1".to_string(),
+                state: "running".to_string(),
+                health: None,
+                exit_code: None,
+                ports: Vec::new(),
+            }])
+        );
+        assert_eq!(parse_ps_json(""), Ok(Vec::new()));
+    }
+}
@@ -0,0 +1,398 @@
+/*
+Runtime: coverage
+
+Runs a project's coverage tooling (cargo-llvm-cov, coverage.py, istanbul/nyc)
+under the orchestrator. Every backend is driven so that it writes an LCOV
+report into a per-call artifact directory; the runtime then parses that report
+into per-file uncovered line ranges the model can target with new tests.
+*/
+use crate::exec::ExecToolCallOutput;
+use crate::tools::runtimes::CommandApprovalKey;
+use crate::tools::runtimes::command_retry_data;
+use crate::tools::runtimes::exec_via_executor;
+use crate::tools::runtimes::join_args;
+use crate::tools::runtimes::quote;
+use crate::tools::runtimes::request_command_approval;
+use crate::tools::sandboxing::Approvable;
+use crate::tools::sandboxing::ApprovalCtx;
+use crate::tools::sandboxing::ApprovalRequirement;
+use crate::tools::sandboxing::ProvidesSandboxRetryData;
+use crate::tools::sandboxing::SandboxAttempt;
+use crate::tools::sandboxing::SandboxRetryData;
+use crate::tools::sandboxing::Sandboxable;
+use crate::tools::sandboxing::SandboxablePreference;
+use crate::tools::sandboxing::ToolCtx;
+use crate::tools::sandboxing::ToolError;
+use crate::tools::sandboxing::ToolRuntime;
+use crate::tools::scratch::with_writable_root;
+use codex_protocol::protocol::ReviewDecision;
+use futures::future::BoxFuture;
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// Directory under the session's artifacts that holds coverage reports, so
+/// that they stay out of the user's workspace.
+pub(crate) const COVERAGE_ARTIFACT_DIR: &str = "coverage";
+
+/// File name of the LCOV report written by every backend.
+const LCOV_FILE_NAME: &str = "lcov.info";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CoverageTool {
+    CargoLlvmCov,
+    CoveragePy,
+    Istanbul,
+}
+
+impl CoverageTool {
+    /// Guess the coverage backend from the manifests present in `cwd`.
+    pub(crate) fn detect(cwd: &Path) -> Option<Self> {
+        const PYTHON_MARKERS: [&str; 5] = [
+            "pyproject.toml",
+            "setup.py",
+            "setup.cfg",
+            "pytest.ini",
+            "tox.ini",
+        ];
+
+        if cwd.join("Cargo.toml").is_file() {
+            Some(CoverageTool::CargoLlvmCov)
+        } else if PYTHON_MARKERS.iter().any(|name| cwd.join(name).is_file()) {
+            Some(CoverageTool::CoveragePy)
+        } else if cwd.join("package.json").is_file() {
+            Some(CoverageTool::Istanbul)
+        } else {
+            None
+        }
+    }
+
+    /// Shell script that runs the tool and leaves an LCOV report at
+    /// `report_dir/lcov.info`. The script exits with the test run's status
+    /// even when report generation is a separate step.
+    pub(crate) fn script(self, report_dir: &Path, extra_args: &[String]) -> String {
+        let report_dir_str = report_dir.to_string_lossy().to_string();
+        let report_path = report_dir
+            .join(LCOV_FILE_NAME)
+            .to_string_lossy()
+            .to_string();
+        let extra = join_args(extra_args);
+        match self {
+            CoverageTool::CargoLlvmCov => {
+                let output = quote(&report_path);
+                format!("cargo llvm-cov --lcov --output-path {output}{extra}")
+            }
+            CoverageTool::CoveragePy => {
+                let output = quote(&report_path);
+                format!(
+                    "python -m coverage run -m pytest{extra}; status=$?; python -m coverage lcov -o {output}; exit $status"
+                )
+            }
+            CoverageTool::Istanbul => {
+                let dir = quote(&report_dir_str);
+                let test_command = if extra_args.is_empty() {
+                    " npm test".to_string()
+                } else {
+                    extra
+                };
+                format!("npx --yes nyc --reporter=lcovonly --report-dir {dir}{test_command}")
+            }
+        }
+    }
+}
+
+/// Inclusive range of 1-based line numbers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct LineRange {
+    pub start: i64,
+    pub end: i64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct FileCoverage {
+    pub path: PathBuf,
+    pub lines_found: i64,
+    pub lines_hit: i64,
+    pub uncovered: Vec<LineRange>,
+}
+
+/// Parse an LCOV tracefile into per-file line coverage. Relative `SF:` paths
+/// are resolved against `root`; duplicate records for the same file are
+/// merged by summing per-line hit counts.
+pub(crate) fn parse_lcov(contents: &str, root: &Path) -> Vec<FileCoverage> {
+    let mut files: BTreeMap<PathBuf, BTreeMap<i64, i64>> = BTreeMap::new();
+    let mut current: Option<PathBuf> = None;
+
+    for line in contents.lines() {
+        let line = line.trim();
+        if let Some(source) = line.strip_prefix("SF:") {
+            let path = PathBuf::from(source);
+            let path = if path.is_absolute() {
+                path
+            } else {
+                root.join(path)
+            };
+            files.entry(path.clone()).or_default();
+            current = Some(path);
+        } else if let Some(data) = line.strip_prefix("DA:") {
+            let Some(path) = current.as_ref() else {
+                continue;
+            };
+            let mut parts = data.split(',');
+            let line_number = parts.next().and_then(|v| v.trim().parse::<i64>().ok());
+            let hits = parts.next().and_then(|v| v.trim().parse::<i64>().ok());
+            if let (Some(line_number), Some(hits)) = (line_number, hits)
+                && let Some(lines) = files.get_mut(path)
+            {
+                *lines.entry(line_number).or_insert(0) += hits;
+            }
+        } else if line == "end_of_record" {
+            current = None;
+        }
+    }
+
+    files
+        .into_iter()
+        .map(|(path, lines)| {
+            let lines_found = lines.len() as i64;
+            let lines_hit = lines.values().filter(|hits| **hits > 0).count() as i64;
+            let uncovered = collapse_ranges(
+                lines
+                    .iter()
+                    .filter(|(_, hits)| **hits == 0)
+                    .map(|(line, _)| *line),
+            );
+            FileCoverage {
+                path,
+                lines_found,
+                lines_hit,
+                uncovered,
+            }
+        })
+        .collect()
+}
+
+/// Collapse an ascending sequence of line numbers into inclusive ranges.
+fn collapse_ranges<I>(lines: I) -> Vec<LineRange>
+where
+    I: IntoIterator<Item = i64>,
+{
+    let mut ranges: Vec<LineRange> = Vec::new();
+    for line in lines {
+        match ranges.last_mut() {
+            Some(range) if range.end + 1 == line => range.end = line,
+            _ => ranges.push(LineRange {
+                start: line,
+                end: line,
+            }),
+        }
+    }
+    ranges
+}
+
+#[derive(Clone, Debug)]
+pub struct CoverageRequest {
+    pub command: Vec<String>,
+    pub cwd: PathBuf,
+    pub report_dir: PathBuf,
+    pub timeout_ms: Option<u64>,
+    pub env: HashMap<String, String>,
+    pub approval_requirement: ApprovalRequirement,
+}
+
+impl ProvidesSandboxRetryData for CoverageRequest {
+    fn sandbox_retry_data(&self) -> Option<SandboxRetryData> {
+        command_retry_data(&self.command, &self.cwd, &self.env)
+    }
+}
+
+/// Result of a coverage run: the raw command output plus the parsed report,
+/// when the tool managed to produce one.
+#[derive(Clone, Debug)]
+pub struct CoverageRun {
+    pub output: ExecToolCallOutput,
+    pub report_path: PathBuf,
+    pub files: Option<Vec<FileCoverage>>,
+}
+
+#[derive(Default)]
+pub struct CoverageRuntime;
+
+impl CoverageRuntime {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Sandboxable for CoverageRuntime {
+    fn sandbox_preference(&self) -> SandboxablePreference {
+        SandboxablePreference::Auto
+    }
+}
+
+impl Approvable<CoverageRequest> for CoverageRuntime {
+    type ApprovalKey = CommandApprovalKey;
+
+    fn approval_key(&self, req: &CoverageRequest) -> Self::ApprovalKey {
+        CommandApprovalKey::new(&req.command, &req.cwd)
+    }
+
+    fn start_approval_async<'a>(
+        &'a mut self,
+        req: &'a CoverageRequest,
+        ctx: ApprovalCtx<'a>,
+    ) -> BoxFuture<'a, ReviewDecision> {
+        let reason = ctx.retry_reason.clone();
+        request_command_approval(self.approval_key(req), &req.command, &req.cwd, reason, ctx)
+    }
+
+    fn approval_requirement(&self, req: &CoverageRequest) -> Option<ApprovalRequirement> {
+        Some(req.approval_requirement.clone())
+    }
+}
+
+impl ToolRuntime<CoverageRequest, CoverageRun> for CoverageRuntime {
+    async fn run(
+        &mut self,
+        req: &CoverageRequest,
+        attempt: &SandboxAttempt<'_>,
+        ctx: &ToolCtx<'_>,
+    ) -> Result<CoverageRun, ToolError> {
+        tokio::fs::create_dir_all(&req.report_dir)
+            .await
+            .map_err(|err| {
+                ToolError::Rejected(format!(
+                    "failed to create coverage artifact directory {}: {err}",
+                    req.report_dir.display()
+                ))
+            })?;
+
+        // The report directory is outside the workspace, so the sandbox has
+        // to be told the command may write there.
+        let policy = with_writable_root(attempt.policy, &req.report_dir);
+        let attempt = SandboxAttempt {
+            policy: &policy,
+            ..*attempt
+        };
+        let output = exec_via_executor(
+            req.command.clone(),
+            &req.cwd,
+            &req.env,
+            req.timeout_ms,
+            &attempt,
+            ctx,
+        )
+        .await?;
+
+        let report_path = req.report_dir.join(LCOV_FILE_NAME);
+        let files = tokio::fs::read_to_string(&report_path)
+            .await
+            .ok()
+            .map(|contents| parse_lcov(&contents, &req.cwd));
+
+        Ok(CoverageRun {
+            output,
+            report_path,
+            files,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::tempdir;
+
+    #[test]
+    fn parse_lcov_collapses_uncovered_lines_into_ranges() {
+        let lcov = "TN:\nSF:src/lib.rs\nDA:1,3\nDA:2,0\nDA:3,0\nDA:5,1\nDA:7,0\nLF:5\nLH:2\nend_of_record\n";
+        let root = Path::new("/repo");
+
+        let files = parse_lcov(lcov, root);
+
+        assert_eq!(
+            files,
+            vec![FileCoverage {
+                path: PathBuf::from("/repo/src/lib.rs"),
+                lines_found: 5,
+                lines_hit: 2,
+                uncovered: vec![
+                    LineRange { start: 2, end: 3 },
+                    LineRange { start: 7, end: 7 },
+                ],
+            }]
+        );
+    }
+
+    #[test]
+    fn parse_lcov_merges_duplicate_records() {
+        let lcov =
+            "SF:/abs/a.py\nDA:1,0\nDA:2,0\nend_of_record\nSF:/abs/a.py\nDA:1,4\nend_of_record\n";
+
+        let files = parse_lcov(lcov, Path::new("/ignored"));
+
+        assert_eq!(
+            files,
+            vec![FileCoverage {
+                path: PathBuf::from("/abs/a.py"),
+                lines_found: 2,
+                lines_hit: 1,
+                uncovered: vec![LineRange { start: 2, end: 2 }],
+            }]
+        );
+    }
+
+    #[test]
+    fn parse_lcov_ignores_data_outside_records() {
+        let files = parse_lcov("DA:1,0\nend_of_record\n", Path::new("/repo"));
+        assert_eq!(files, Vec::new());
+    }
+
+    #[test]
+    fn detect_prefers_cargo_then_python_then_node() {
+        let dir = tempdir().expect("tempdir");
+        assert_eq!(CoverageTool::detect(dir.path()), None);
+
+        std::fs::write(dir.path().join("package.json"), "{}").expect("write package.json");
+        assert_eq!(
+            CoverageTool::detect(dir.path()),
+            Some(CoverageTool::Istanbul)
+        );
+
+        std::fs::write(dir.path().join("pyproject.toml"), "").expect("write pyproject");
+        assert_eq!(
+            CoverageTool::detect(dir.path()),
+            Some(CoverageTool::CoveragePy)
+        );
+
+        std::fs::write(dir.path().join("Cargo.toml"), "").expect("write Cargo.toml");
+        assert_eq!(
+            CoverageTool::detect(dir.path()),
+            Some(CoverageTool::CargoLlvmCov)
+        );
+    }
+
+    #[test]
+    fn scripts_write_lcov_into_report_dir() {
+        let dir = Path::new("/codex-home/artifacts/conversation/coverage/call-1");
+        let extra = vec!["-p".to_string(), "my crate".to_string()];
+
+        assert_eq!(
+            CoverageTool::CargoLlvmCov.script(dir, &extra),
+            "cargo llvm-cov --lcov --output-path /codex-home/artifacts/conversation/coverage/call-1/lcov.info -p 'my crate'"
+        );
+        assert_eq!(
+            CoverageTool::CoveragePy.script(dir, &[]),
+            "python -m coverage run -m pytest; status=$?; python -m coverage lcov -o /codex-home/artifacts/conversation/coverage/call-1/lcov.info; exit $status"
+        );
+        assert_eq!(
+            CoverageTool::Istanbul.script(dir, &[]),
+            "npx --yes nyc --reporter=lcovonly --report-dir /codex-home/artifacts/conversation/coverage/call-1 npm test"
+        );
+    }
+}
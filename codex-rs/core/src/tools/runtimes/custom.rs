@@ -0,0 +1,130 @@
+/*
+Runtime: custom
+
+Adapts an embedder's CustomToolRuntime to the orchestrator: approvals are
+requested like commands, showing the runtime's description of the call, and
+cached per tool and arguments. Custom runtimes run in-process, so there is no
+sandbox to apply or escalate out of.
+*/
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::exec::ExitReason;
+use crate::tools::custom::CustomToolCall;
+use crate::tools::custom::CustomToolRuntime;
+use crate::tools::sandboxing::Approvable;
+use crate::tools::sandboxing::ApprovalCtx;
+use crate::tools::sandboxing::ApprovalRequirement;
+use crate::tools::sandboxing::ProvidesSandboxRetryData;
+use crate::tools::sandboxing::SandboxAttempt;
+use crate::tools::sandboxing::SandboxRetryData;
+use crate::tools::sandboxing::Sandboxable;
+use crate::tools::sandboxing::SandboxablePreference;
+use crate::tools::sandboxing::ToolCtx;
+use crate::tools::sandboxing::ToolError;
+use crate::tools::sandboxing::ToolRuntime;
+use crate::tools::sandboxing::with_cached_approval;
+use codex_protocol::paths::normalize_for_comparison;
+use codex_protocol::protocol::ReviewDecision;
+use futures::future::BoxFuture;
+
+#[derive(Clone, Debug)]
+pub struct CustomToolRequest {
+    pub call: CustomToolCall,
+    /// The call as shown in approval prompts.
+    pub display: Vec<String>,
+    /// `None` leaves the requirement to the approval policy.
+    pub approval_requirement: Option<ApprovalRequirement>,
+}
+
+impl ProvidesSandboxRetryData for CustomToolRequest {
+    fn sandbox_retry_data(&self) -> Option<SandboxRetryData> {
+        Some(SandboxRetryData {
+            command: self.display.clone(),
+            cwd: self.call.cwd.clone(),
+            env: HashMap::new(),
+            denials: Vec::new(),
+        })
+    }
+}
+
+pub struct CustomRuntime {
+    tool_name: String,
+    runtime: Arc<dyn CustomToolRuntime>,
+}
+
+#[derive(serde::Serialize, Clone, Debug, Eq, PartialEq, Hash)]
+pub(crate) struct ApprovalKey {
+    tool: String,
+    arguments: String,
+    cwd: PathBuf,
+}
+
+impl CustomRuntime {
+    pub fn new(tool_name: String, runtime: Arc<dyn CustomToolRuntime>) -> Self {
+        Self { tool_name, runtime }
+    }
+}
+
+impl Sandboxable for CustomRuntime {
+    fn sandbox_preference(&self) -> SandboxablePreference {
+        SandboxablePreference::Forbid
+    }
+    fn escalate_on_failure(&self, _reason: ExitReason) -> bool {
+        false
+    }
+}
+
+impl Approvable<CustomToolRequest> for CustomRuntime {
+    type ApprovalKey = ApprovalKey;
+
+    fn approval_key(&self, req: &CustomToolRequest) -> Self::ApprovalKey {
+        ApprovalKey {
+            tool: self.tool_name.clone(),
+            arguments: req.call.arguments.to_string(),
+            cwd: normalize_for_comparison(&req.call.cwd),
+        }
+    }
+
+    fn start_approval_async<'a>(
+        &'a mut self,
+        req: &'a CustomToolRequest,
+        ctx: ApprovalCtx<'a>,
+    ) -> BoxFuture<'a, ReviewDecision> {
+        let key = self.approval_key(req);
+        let command = req.display.clone();
+        let cwd = req.call.cwd.clone();
+        let reason = ctx.retry_reason.clone();
+        let risk = ctx.risk.clone();
+        let session = ctx.session;
+        let turn = ctx.turn;
+        let call_id = ctx.call_id.to_string();
+        Box::pin(async move {
+            with_cached_approval(&session.services, turn, key, move || async move {
+                session
+                    .request_command_approval(turn, call_id, command, cwd, reason, risk)
+                    .await
+            })
+            .await
+        })
+    }
+
+    fn approval_requirement(&self, req: &CustomToolRequest) -> Option<ApprovalRequirement> {
+        req.approval_requirement.clone()
+    }
+}
+
+impl ToolRuntime<CustomToolRequest, String> for CustomRuntime {
+    async fn run(
+        &mut self,
+        req: &CustomToolRequest,
+        _attempt: &SandboxAttempt<'_>,
+        _ctx: &ToolCtx<'_>,
+    ) -> Result<String, ToolError> {
+        self.runtime
+            .run(req.call.clone())
+            .await
+            .map_err(ToolError::Rejected)
+    }
+}
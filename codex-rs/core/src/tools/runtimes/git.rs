@@ -0,0 +1,934 @@
+/*
+Runtime: git
+
+Answers read-only git queries (status, diff, log, blame) for the `git` tool
+and parses git's output into structured data. Each query is a fixed git
+invocation that takes no optional locks, runs no external diff or textconv
+drivers and writes nothing, and revisions are refused when they could be
+taken for options. Since nothing can change, the runtime needs no approval
+under any policy; its approval key names the query and directory only.
+*/
+use crate::exec::ExecToolCallOutput;
+use crate::exec::ExitReason;
+use crate::tools::runtimes::exec_via_executor;
+use crate::tools::sandboxing::Approvable;
+use crate::tools::sandboxing::ApprovalCtx;
+use crate::tools::sandboxing::ApprovalRequirement;
+use crate::tools::sandboxing::ProvidesSandboxRetryData;
+use crate::tools::sandboxing::SandboxAttempt;
+use crate::tools::sandboxing::SandboxRetryData;
+use crate::tools::sandboxing::Sandboxable;
+use crate::tools::sandboxing::SandboxablePreference;
+use crate::tools::sandboxing::ToolCtx;
+use crate::tools::sandboxing::ToolError;
+use crate::tools::sandboxing::ToolRuntime;
+use crate::tools::sandboxing::with_cached_approval;
+use crate::tools::spec::JsonSchema;
+use chrono::DateTime;
+use chrono::Utc;
+use codex_protocol::paths::normalize_for_comparison;
+use codex_protocol::protocol::ReviewDecision;
+use futures::future::BoxFuture;
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Commits `log` returns unless asked for another number.
+const DEFAULT_LOG_LIMIT: usize = 20;
+const MAX_LOG_LIMIT: usize = 200;
+const GIT_TIMEOUT_MS: u64 = 30_000;
+
+/// Commit id git blame reports for lines that are not committed yet.
+pub(crate) const UNCOMMITTED: &str = "0000000000000000000000000000000000000000";
+
+/// Options every query runs with, ahead of the subcommand.
+const GIT_OPTIONS: [&str; 4] = [
+    "--no-pager",
+    "--no-optional-locks",
+    "-c",
+    "core.quotepath=off",
+];
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(tag = "query", rename_all = "snake_case")]
+pub enum GitQuery {
+    Status,
+    Diff {
+        /// Compare the index with `HEAD` instead of the working tree with the
+        /// index.
+        #[serde(default)]
+        staged: bool,
+        /// Compare with this revision, or a range such as `main...HEAD`.
+        #[serde(default)]
+        rev: Option<String>,
+        #[serde(default)]
+        paths: Vec<String>,
+    },
+    Log {
+        #[serde(default)]
+        limit: Option<usize>,
+        #[serde(default)]
+        rev: Option<String>,
+        #[serde(default)]
+        paths: Vec<String>,
+    },
+    Blame {
+        path: String,
+        #[serde(default)]
+        start_line: Option<i64>,
+        #[serde(default)]
+        end_line: Option<i64>,
+    },
+}
+
+impl GitQuery {
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            GitQuery::Status => "status",
+            GitQuery::Diff { .. } => "diff",
+            GitQuery::Log { .. } => "log",
+            GitQuery::Blame { .. } => "blame",
+        }
+    }
+
+    /// The git command line answering the query.
+    pub(crate) fn command(&self) -> Result<Vec<String>, String> {
+        let mut command: Vec<String> = std::iter::once("git")
+            .chain(GIT_OPTIONS)
+            .map(String::from)
+            .collect();
+        let rev_and_paths = |command: &mut Vec<String>, rev: &Option<String>, paths: &[String]| {
+            if let Some(rev) = rev {
+                if rev.starts_with('-') {
+                    return Err(format!("`{rev}` is not a revision"));
+                }
+                command.push(rev.clone());
+            }
+            command.push("--".to_string());
+            command.extend(paths.iter().cloned());
+            Ok(())
+        };
+        match self {
+            GitQuery::Status => {
+                command.extend(["status", "--porcelain=v2", "--branch", "-z"].map(String::from))
+            }
+            GitQuery::Diff { staged, rev, paths } => {
+                command.extend(
+                    [
+                        "diff",
+                        "--no-color",
+                        "--no-ext-diff",
+                        "--no-textconv",
+                        "--find-renames",
+                    ]
+                    .map(String::from),
+                );
+                if *staged {
+                    command.push("--cached".to_string());
+                }
+                rev_and_paths(&mut command, rev, paths)?;
+            }
+            GitQuery::Log { limit, rev, paths } => {
+                let limit = limit.unwrap_or(DEFAULT_LOG_LIMIT).clamp(1, MAX_LOG_LIMIT);
+                command.extend([
+                    "log".to_string(),
+                    "--no-color".to_string(),
+                    format!("--max-count={limit}"),
+                    "--format=%H%x1f%an%x1f%ae%x1f%aI%x1f%s%x1e".to_string(),
+                ]);
+                rev_and_paths(&mut command, rev, paths)?;
+            }
+            GitQuery::Blame {
+                path,
+                start_line,
+                end_line,
+            } => {
+                command.extend(["blame", "--porcelain"].map(String::from));
+                if let Some(start) = start_line {
+                    let end = end_line.map(|end| end.to_string()).unwrap_or_default();
+                    command.push(format!("-L{start},{end}"));
+                }
+                command.push("--".to_string());
+                command.push(path.clone());
+            }
+        }
+        Ok(command)
+    }
+
+    /// Parse what git printed for the query.
+    pub(crate) fn parse(&self, stdout: &str) -> GitResult {
+        match self {
+            GitQuery::Status => GitResult::Status(parse_status(stdout)),
+            GitQuery::Diff { .. } => GitResult::Diff {
+                files: parse_diff(stdout),
+            },
+            GitQuery::Log { .. } => GitResult::Log {
+                commits: parse_log(stdout),
+            },
+            GitQuery::Blame { .. } => GitResult::Blame {
+                hunks: parse_blame_porcelain(stdout),
+            },
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(tag = "query", rename_all = "snake_case")]
+pub enum GitResult {
+    Status(GitStatus),
+    Diff { files: Vec<DiffFile> },
+    Log { commits: Vec<LogEntry> },
+    Blame { hunks: Vec<BlameHunk> },
+}
+
+#[derive(Serialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct GitStatus {
+    /// `None` on a detached `HEAD`.
+    pub branch: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upstream: Option<String>,
+    pub ahead: u64,
+    pub behind: u64,
+    pub entries: Vec<StatusEntry>,
+}
+
+/// One changed path. `index` and `worktree` are git's status letters, such
+/// as `M`, `A`, `D` or `R`, with `.` for unchanged.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct StatusEntry {
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub orig_path: Option<String>,
+    pub index: String,
+    pub worktree: String,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub untracked: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub conflicted: bool,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffStatus {
+    Added,
+    Deleted,
+    Modified,
+    Renamed,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct DiffFile {
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old_path: Option<String>,
+    pub status: DiffStatus,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub binary: bool,
+    pub additions: u64,
+    pub deletions: u64,
+    pub hunks: Vec<DiffHunk>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct DiffHunk {
+    pub old_start: u64,
+    pub old_lines: u64,
+    pub new_start: u64,
+    pub new_lines: u64,
+    /// The enclosing function or section git found for the hunk.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub header: String,
+    /// The hunk's lines, each starting with ` `, `+` or `-`.
+    pub lines: Vec<String>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct LogEntry {
+    pub commit: String,
+    pub author: String,
+    pub email: String,
+    pub date: String,
+    pub subject: String,
+}
+
+/// A run of consecutive lines last changed by the same commit.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub(crate) struct BlameHunk {
+    pub(crate) start_line: i64,
+    pub(crate) end_line: i64,
+    /// `None` for lines with uncommitted changes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) commit: Option<String>,
+    pub(crate) author: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) date: Option<String>,
+    pub(crate) summary: String,
+}
+
+/// The schema of a serialized [`GitResult`]: one object whose `query` names
+/// the query, with the fields of that query's result.
+fn git_result_schema() -> JsonSchema {
+    let string = |description: &str| JsonSchema::String {
+        description: Some(description.to_string()),
+    };
+    let number = |description: &str| JsonSchema::Number {
+        description: Some(description.to_string()),
+    };
+    let boolean = |description: &str| JsonSchema::Boolean {
+        description: Some(description.to_string()),
+    };
+    let array = |items: JsonSchema, description: &str| JsonSchema::Array {
+        items: Box::new(items),
+        description: Some(description.to_string()),
+    };
+    let object = |properties: Vec<(&str, JsonSchema)>, required: &[&str]| JsonSchema::Object {
+        properties: properties
+            .into_iter()
+            .map(|(name, schema)| (name.to_string(), schema))
+            .collect(),
+        required: Some(required.iter().map(ToString::to_string).collect()),
+        additional_properties: Some(false.into()),
+    };
+
+    let entry = object(
+        vec![
+            ("path", string("The path, relative to the repository root.")),
+            (
+                "orig_path",
+                string("For a rename, the path it was renamed from."),
+            ),
+            (
+                "index",
+                string("The status letter in the index, `.` if unchanged."),
+            ),
+            (
+                "worktree",
+                string("The status letter in the working tree, `.` if unchanged."),
+            ),
+            (
+                "untracked",
+                boolean("Present and true for an untracked path."),
+            ),
+            (
+                "conflicted",
+                boolean("Present and true for a path with merge conflicts."),
+            ),
+        ],
+        &["path", "index", "worktree"],
+    );
+    let hunk = object(
+        vec![
+            (
+                "old_start",
+                number("The first line of the hunk in the old file."),
+            ),
+            (
+                "old_lines",
+                number("The number of lines of the hunk in the old file."),
+            ),
+            (
+                "new_start",
+                number("The first line of the hunk in the new file."),
+            ),
+            (
+                "new_lines",
+                number("The number of lines of the hunk in the new file."),
+            ),
+            (
+                "header",
+                string("The enclosing function or section, when git found one."),
+            ),
+            (
+                "lines",
+                array(
+                    string("A line of the hunk."),
+                    "The hunk's lines, each starting with ` `, `+` or `-`.",
+                ),
+            ),
+        ],
+        &["old_start", "old_lines", "new_start", "new_lines", "lines"],
+    );
+    let file = object(
+        vec![
+            ("path", string("The path, relative to the repository root.")),
+            (
+                "old_path",
+                string("For a rename, the path it was renamed from."),
+            ),
+            (
+                "status",
+                string("One of \"added\", \"deleted\", \"modified\" or \"renamed\"."),
+            ),
+            (
+                "binary",
+                boolean("Present and true for a binary file, which has no hunks."),
+            ),
+            ("additions", number("The number of added lines.")),
+            ("deletions", number("The number of deleted lines.")),
+            ("hunks", array(hunk, "The changed hunks.")),
+        ],
+        &["path", "status", "additions", "deletions", "hunks"],
+    );
+    let commit = object(
+        vec![
+            ("commit", string("The full commit id.")),
+            ("author", string("The author's name.")),
+            ("email", string("The author's email.")),
+            ("date", string("The author date, in RFC 3339 format.")),
+            ("subject", string("The first line of the commit message.")),
+        ],
+        &["commit", "author", "email", "date", "subject"],
+    );
+    let blame = object(
+        vec![
+            (
+                "start_line",
+                number("The first line of the range, 1-based."),
+            ),
+            ("end_line", number("The last line of the range, inclusive.")),
+            (
+                "commit",
+                string("The commit that last changed the lines; absent if uncommitted."),
+            ),
+            ("author", string("The author of that commit.")),
+            ("date", string("The author date, in RFC 3339 format.")),
+            (
+                "summary",
+                string("The first line of that commit's message."),
+            ),
+        ],
+        &["start_line", "end_line", "author", "summary"],
+    );
+    object(
+        vec![
+            (
+                "query",
+                string("The query answered: \"status\", \"diff\", \"log\" or \"blame\"."),
+            ),
+            (
+                "branch",
+                string("status: the current branch, null on a detached HEAD."),
+            ),
+            ("upstream", string("status: the upstream branch, if any.")),
+            (
+                "ahead",
+                number("status: commits on the branch and not on its upstream."),
+            ),
+            (
+                "behind",
+                number("status: commits on the upstream and not on the branch."),
+            ),
+            (
+                "entries",
+                array(entry, "status: the changed and untracked paths."),
+            ),
+            ("files", array(file, "diff: the changed files.")),
+            ("commits", array(commit, "log: the commits, newest first.")),
+            (
+                "hunks",
+                array(
+                    blame,
+                    "blame: line ranges by the commit that last changed them.",
+                ),
+            ),
+        ],
+        &["query"],
+    )
+}
+
+/// Parse `git status --porcelain=v2 --branch -z`.
+fn parse_status(output: &str) -> GitStatus {
+    let mut status = GitStatus::default();
+    let mut records = output.split('\0').filter(|record| !record.is_empty());
+    while let Some(record) = records.next() {
+        if let Some(header) = record.strip_prefix("# ") {
+            let (key, value) = header.split_once(' ').unwrap_or((header, ""));
+            match key {
+                "branch.head" if value != "(detached)" => status.branch = Some(value.to_string()),
+                "branch.upstream" => status.upstream = Some(value.to_string()),
+                "branch.ab" => {
+                    for count in value.split(' ') {
+                        if let Some(ahead) = count.strip_prefix('+') {
+                            status.ahead = ahead.parse().unwrap_or_default();
+                        } else if let Some(behind) = count.strip_prefix('-') {
+                            status.behind = behind.parse().unwrap_or_default();
+                        }
+                    }
+                }
+                _ => {}
+            }
+            continue;
+        }
+        let (kind, rest) = record.split_once(' ').unwrap_or((record, ""));
+        let entry = match kind {
+            // Ordinary, renamed or copied, and unmerged entries: the path
+            // follows a fixed number of fields.
+            "1" | "2" | "u" => {
+                let fields = match kind {
+                    "1" => 7,
+                    "2" => 8,
+                    _ => 9,
+                };
+                let mut parts = rest.splitn(fields + 1, ' ');
+                let mut xy = parts.next().unwrap_or_default().chars();
+                let Some(path) = parts.nth(fields - 1) else {
+                    continue;
+                };
+                let index = xy.next().unwrap_or('.');
+                let worktree = xy.next().unwrap_or('.');
+                StatusEntry {
+                    path: path.to_string(),
+                    orig_path: (kind == "2")
+                        .then(|| records.next().unwrap_or_default().to_string()),
+                    index: index.to_string(),
+                    worktree: worktree.to_string(),
+                    untracked: false,
+                    conflicted: kind == "u",
+                }
+            }
+            "?" => StatusEntry {
+                path: rest.to_string(),
+                orig_path: None,
+                index: "?".to_string(),
+                worktree: "?".to_string(),
+                untracked: true,
+                conflicted: false,
+            },
+            _ => continue,
+        };
+        status.entries.push(entry);
+    }
+    status
+}
+
+/// Parse the unified diff `git diff` prints into files and hunks.
+fn parse_diff(output: &str) -> Vec<DiffFile> {
+    let mut files: Vec<DiffFile> = Vec::new();
+    for line in output.lines() {
+        if let Some(paths) = line.strip_prefix("diff --git ") {
+            let path = paths
+                .rsplit_once(" b/")
+                .map_or(paths, |(_, path)| path)
+                .to_string();
+            files.push(DiffFile {
+                path,
+                old_path: None,
+                status: DiffStatus::Modified,
+                binary: false,
+                additions: 0,
+                deletions: 0,
+                hunks: Vec::new(),
+            });
+            continue;
+        }
+        let Some(file) = files.last_mut() else {
+            continue;
+        };
+        if let Some(hunk) = file.hunks.last_mut()
+            && let Some(marker) = line.chars().next()
+            && matches!(marker, ' ' | '+' | '-' | '\\')
+            && !line.starts_with("--- ")
+            && !line.starts_with("+++ ")
+        {
+            match marker {
+                '+' => file.additions += 1,
+                '-' => file.deletions += 1,
+                _ => {}
+            }
+            hunk.lines.push(line.to_string());
+        } else if let Some(range) = line.strip_prefix("@@ ") {
+            if let Some(hunk) = parse_hunk_header(range) {
+                file.hunks.push(hunk);
+            }
+        } else if line.starts_with("new file mode") {
+            file.status = DiffStatus::Added;
+        } else if line.starts_with("deleted file mode") {
+            file.status = DiffStatus::Deleted;
+        } else if let Some(from) = line.strip_prefix("rename from ") {
+            file.status = DiffStatus::Renamed;
+            file.old_path = Some(from.to_string());
+        } else if let Some(to) = line.strip_prefix("rename to ") {
+            file.path = to.to_string();
+        } else if let Some(to) = line.strip_prefix("+++ b/") {
+            file.path = to.to_string();
+        } else if line.starts_with("Binary files ") || line == "GIT binary patch" {
+            file.binary = true;
+        }
+    }
+    files
+}
+
+/// Parse `-a,b +c,d @@ header`, the rest of a hunk header after `@@ `.
+fn parse_hunk_header(range: &str) -> Option<DiffHunk> {
+    let (ranges, header) = range.split_once(" @@").unwrap_or((range, ""));
+    let (old, new) = ranges.split_once(' ')?;
+    let span = |range: &str| -> Option<(u64, u64)> {
+        match range.split_once(',') {
+            Some((start, lines)) => Some((start.parse().ok()?, lines.parse().ok()?)),
+            None => Some((range.parse().ok()?, 1)),
+        }
+    };
+    let (old_start, old_lines) = span(old.strip_prefix('-')?)?;
+    let (new_start, new_lines) = span(new.strip_prefix('+')?)?;
+    Some(DiffHunk {
+        old_start,
+        old_lines,
+        new_start,
+        new_lines,
+        header: header.trim().to_string(),
+        lines: Vec::new(),
+    })
+}
+
+/// Parse `git log` with fields separated by `\x1f` and records by `\x1e`.
+fn parse_log(output: &str) -> Vec<LogEntry> {
+    output
+        .split('\x1e')
+        .filter_map(|record| {
+            let mut fields = record.trim_start_matches('\n').split('\x1f');
+            Some(LogEntry {
+                commit: fields
+                    .next()
+                    .filter(|commit| !commit.is_empty())?
+                    .to_string(),
+                author: fields.next()?.to_string(),
+                email: fields.next()?.to_string(),
+                date: fields.next()?.to_string(),
+                subject: fields.next()?.to_string(),
+            })
+        })
+        .collect()
+}
+
+#[derive(Default)]
+struct CommitInfo {
+    author: String,
+    author_time: Option<i64>,
+    summary: String,
+}
+
+/// Parse `git blame --porcelain` into runs of consecutive lines per commit.
+pub(crate) fn parse_blame_porcelain(output: &str) -> Vec<BlameHunk> {
+    let mut commits: HashMap<String, CommitInfo> = HashMap::new();
+    let mut lines: Vec<(i64, String)> = Vec::new();
+    let mut current: Option<(String, i64)> = None;
+
+    for line in output.lines() {
+        if line.starts_with('\t') {
+            // Content line: closes the entry started by the last header.
+            if let Some(entry) = current.take() {
+                lines.push((entry.1, entry.0));
+            }
+            continue;
+        }
+        let mut parts = line.split(' ');
+        let first = parts.next().unwrap_or_default();
+        let is_header = first.len() == 40 && first.bytes().all(|b| b.is_ascii_hexdigit());
+        if is_header {
+            if let Some(final_line) = parts.nth(1).and_then(|n| n.parse().ok()) {
+                commits.entry(first.to_string()).or_default();
+                current = Some((first.to_string(), final_line));
+            }
+            continue;
+        }
+        let Some((sha, _)) = &current else {
+            continue;
+        };
+        let Some(commit) = commits.get_mut(sha) else {
+            continue;
+        };
+        let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+        match key {
+            "author" => commit.author = value.to_string(),
+            "author-time" => commit.author_time = value.parse().ok(),
+            "summary" => commit.summary = value.to_string(),
+            _ => {}
+        }
+    }
+
+    lines.sort_by_key(|(line, _)| *line);
+    let mut hunks: Vec<BlameHunk> = Vec::new();
+    for (line, sha) in lines {
+        let commit = (sha != UNCOMMITTED).then_some(sha);
+        if let Some(last) = hunks.last_mut()
+            && last.end_line + 1 == line
+            && last.commit == commit
+        {
+            last.end_line = line;
+            continue;
+        }
+        let info = commit
+            .as_ref()
+            .and_then(|sha| commits.get(sha))
+            .or_else(|| commits.get(UNCOMMITTED));
+        hunks.push(BlameHunk {
+            start_line: line,
+            end_line: line,
+            author: info.map(|info| info.author.clone()).unwrap_or_default(),
+            date: info
+                .and_then(|info| info.author_time)
+                .and_then(|secs| DateTime::<Utc>::from_timestamp(secs, 0))
+                .map(|date| date.to_rfc3339()),
+            summary: info.map(|info| info.summary.clone()).unwrap_or_default(),
+            commit,
+        });
+    }
+    hunks
+}
+
+#[derive(Clone, Debug)]
+pub struct GitRequest {
+    pub query: GitQuery,
+    pub command: Vec<String>,
+    pub cwd: PathBuf,
+    pub env: HashMap<String, String>,
+}
+
+impl ProvidesSandboxRetryData for GitRequest {
+    fn sandbox_retry_data(&self) -> Option<SandboxRetryData> {
+        Some(SandboxRetryData {
+            command: self.command.clone(),
+            cwd: self.cwd.clone(),
+            env: self.env.clone(),
+            denials: Vec::new(),
+        })
+    }
+}
+
+/// Result of a git query: the raw command output plus the parsed result,
+/// when git succeeded.
+#[derive(Clone, Debug)]
+pub struct GitRun {
+    pub output: ExecToolCallOutput,
+    pub result: Option<GitResult>,
+}
+
+#[derive(Default)]
+pub struct GitRuntime;
+
+#[derive(Serialize, Clone, Debug, Eq, PartialEq, Hash)]
+pub(crate) struct ApprovalKey {
+    query: &'static str,
+    cwd: PathBuf,
+}
+
+impl GitRuntime {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Sandboxable for GitRuntime {
+    fn sandbox_preference(&self) -> SandboxablePreference {
+        SandboxablePreference::Auto
+    }
+    fn escalate_on_failure(&self, _reason: ExitReason) -> bool {
+        false
+    }
+}
+
+impl Approvable<GitRequest> for GitRuntime {
+    type ApprovalKey = ApprovalKey;
+
+    fn approval_key(&self, req: &GitRequest) -> Self::ApprovalKey {
+        ApprovalKey {
+            query: req.query.name(),
+            cwd: normalize_for_comparison(&req.cwd),
+        }
+    }
+
+    fn start_approval_async<'a>(
+        &'a mut self,
+        req: &'a GitRequest,
+        ctx: ApprovalCtx<'a>,
+    ) -> BoxFuture<'a, ReviewDecision> {
+        let key = self.approval_key(req);
+        let command = req.command.clone();
+        let cwd = req.cwd.clone();
+        let reason = ctx.retry_reason.clone();
+        let risk = ctx.risk.clone();
+        let session = ctx.session;
+        let turn = ctx.turn;
+        let call_id = ctx.call_id.to_string();
+        Box::pin(async move {
+            with_cached_approval(&session.services, turn, key, move || async move {
+                session
+                    .request_command_approval(turn, call_id, command, cwd, reason, risk)
+                    .await
+            })
+            .await
+        })
+    }
+
+    fn approval_requirement(&self, _req: &GitRequest) -> Option<ApprovalRequirement> {
+        // Every query is read-only, so it runs in the sandbox unasked.
+        Some(ApprovalRequirement::Skip {
+            bypass_sandbox: false,
+        })
+    }
+}
+
+impl ToolRuntime<GitRequest, GitRun> for GitRuntime {
+    async fn run(
+        &mut self,
+        req: &GitRequest,
+        attempt: &SandboxAttempt<'_>,
+        ctx: &ToolCtx<'_>,
+    ) -> Result<GitRun, ToolError> {
+        let output = exec_via_executor(
+            req.command.clone(),
+            &req.cwd,
+            &req.env,
+            Some(GIT_TIMEOUT_MS),
+            attempt,
+            ctx,
+        )
+        .await?;
+        let result = (output.exit_code == 0).then(|| req.query.parse(&output.stdout.text));
+        Ok(GitRun { output, result })
+    }
+
+    fn output_schema(&self) -> Option<JsonSchema> {
+        Some(git_result_schema())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn status_reports_branch_and_entries() {
+        let output = "# branch.oid 1234\0# branch.head main\0# branch.upstream origin/main\0\
+                      # branch.ab +2 -1\0\
+                      1 .M N... 100644 100644 100644 abc abc src/lib.rs\0\
+                      2 R. N... 100644 100644 100644 abc abc R100 src/new name.rs\0src/old.rs\0\
+                      u UU N... 100644 100644 100644 100644 a b c conflict.txt\0\
+                      ? notes.md\0";
+        assert_eq!(
+            parse_status(output),
+            GitStatus {
+                branch: Some("main".to_string()),
+                upstream: Some("origin/main".to_string()),
+                ahead: 2,
+                behind: 1,
+                entries: vec![
+                    StatusEntry {
+                        path: "src/lib.rs".to_string(),
+                        orig_path: None,
+                        index: ".".to_string(),
+                        worktree: "M".to_string(),
+                        untracked: false,
+                        conflicted: false,
+                    },
+                    StatusEntry {
+                        path: "src/new name.rs".to_string(),
+                        orig_path: Some("src/old.rs".to_string()),
+                        index: "R".to_string(),
+                        worktree: ".".to_string(),
+                        untracked: false,
+                        conflicted: false,
+                    },
+                    StatusEntry {
+                        path: "conflict.txt".to_string(),
+                        orig_path: None,
+                        index: "U".to_string(),
+                        worktree: "U".to_string(),
+                        untracked: false,
+                        conflicted: true,
+                    },
+                    StatusEntry {
+                        path: "notes.md".to_string(),
+                        orig_path: None,
+                        index: "?".to_string(),
+                        worktree: "?".to_string(),
+                        untracked: true,
+                        conflicted: false,
+                    },
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn diff_is_split_into_files_and_hunks() {
+        let output = "diff --git a/src/lib.rs b/src/lib.rs\n\
+                      index 1111111..2222222 100644\n\
+                      --- a/src/lib.rs\n\
+                      +++ b/src/lib.rs\n\
+                      @@ -1,3 +1,3 @@ fn main() {\n \
+                      let a = 1;\n\
+                      -let b = 2;\n\
+                      +let b = 3;\n \
+                      }\n\
+                      diff --git a/logo.png b/logo.png\n\
+                      new file mode 100644\n\
+                      Binary files /dev/null and b/logo.png differ\n";
+        assert_eq!(
+            parse_diff(output),
+            vec![
+                DiffFile {
+                    path: "src/lib.rs".to_string(),
+                    old_path: None,
+                    status: DiffStatus::Modified,
+                    binary: false,
+                    additions: 1,
+                    deletions: 1,
+                    hunks: vec![DiffHunk {
+                        old_start: 1,
+                        old_lines: 3,
+                        new_start: 1,
+                        new_lines: 3,
+                        header: "fn main() {".to_string(),
+                        lines: vec![
+                            " let a = 1;".to_string(),
+                            "-let b = 2;".to_string(),
+                            "+let b = 3;".to_string(),
+                            " }".to_string(),
+                        ],
+                    }],
+                },
+                DiffFile {
+                    path: "logo.png".to_string(),
+                    old_path: None,
+                    status: DiffStatus::Added,
+                    binary: true,
+                    additions: 0,
+                    deletions: 0,
+                    hunks: Vec::new(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn revisions_that_look_like_options_are_refused() {
+        let query = GitQuery::Diff {
+            staged: false,
+            rev: Some("--output=/tmp/x".to_string()),
+            paths: Vec::new(),
+        };
+        assert_eq!(
+            query.command(),
+            Err("`--output=/tmp/x` is not a revision".to_string())
+        );
+        let query = GitQuery::Log {
+            limit: Some(5),
+            rev: None,
+            paths: vec!["src".to_string()],
+        };
+        assert_eq!(
+            query.command().map(|command| command[5..].to_vec()),
+            Ok(vec![
+                "log".to_string(),
+                "--no-color".to_string(),
+                "--max-count=5".to_string(),
+                "--format=%H%x1f%an%x1f%ae%x1f%aI%x1f%s%x1e".to_string(),
+                "--".to_string(),
+                "src".to_string(),
+            ])
+        );
+    }
+}
@@ -0,0 +1,352 @@
+/*
+Runtime: migrate
+
+Runs database migration tools (diesel, sqlx, alembic, prisma) under the
+orchestrator. The handler first lists the pending migrations and collects the
+SQL they will execute, applies them to a shadow database as a dry run, and only
+then applies them to the real database. The collected SQL is attached to the
+approval request for the real apply, so the user reviews the statements rather
+than an opaque `migrate run` command.
+*/
+use crate::exec::ExecToolCallOutput;
+use crate::tools::runtimes::command_retry_data;
+use crate::tools::runtimes::exec_via_executor;
+use crate::tools::runtimes::request_command_approval;
+use crate::tools::sandboxing::Approvable;
+use crate::tools::sandboxing::ApprovalCtx;
+use crate::tools::sandboxing::ApprovalRequirement;
+use crate::tools::sandboxing::ProvidesSandboxRetryData;
+use crate::tools::sandboxing::SandboxAttempt;
+use crate::tools::sandboxing::SandboxRetryData;
+use crate::tools::sandboxing::Sandboxable;
+use crate::tools::sandboxing::SandboxablePreference;
+use crate::tools::sandboxing::ToolCtx;
+use crate::tools::sandboxing::ToolError;
+use crate::tools::sandboxing::ToolRuntime;
+use crate::truncate::TruncationPolicy;
+use crate::truncate::truncate_text;
+use codex_protocol::paths::normalize_for_comparison;
+use codex_protocol::protocol::ReviewDecision;
+use futures::future::BoxFuture;
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// Environment variable every supported tool reads the connection string from.
+/// Alembic projects must read it in `env.py`.
+pub(crate) const DATABASE_URL_ENV_VAR: &str = "DATABASE_URL";
+/// Budget for the SQL embedded in an approval request.
+const APPROVAL_SQL_BYTES: usize = 8 * 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MigrationTool {
+    Diesel,
+    Sqlx,
+    Alembic,
+    Prisma,
+}
+
+/// Pending migrations and the SQL they will run, in application order.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub(crate) struct MigrationPlan {
+    pub pending: Vec<String>,
+    pub sql: String,
+}
+
+impl MigrationTool {
+    /// Command that reports pending migrations. For alembic this renders the
+    /// upgrade SQL in offline mode instead.
+    pub(crate) fn status_args(self) -> Vec<String> {
+        let args: &[&str] = match self {
+            MigrationTool::Diesel => &["diesel", "migration", "list"],
+            MigrationTool::Sqlx => &["sqlx", "migrate", "info"],
+            MigrationTool::Alembic => &["alembic", "upgrade", "head", "--sql"],
+            MigrationTool::Prisma => &["prisma", "migrate", "status"],
+        };
+        args.iter().map(ToString::to_string).collect()
+    }
+
+    pub(crate) fn apply_args(self) -> Vec<String> {
+        let args: &[&str] = match self {
+            MigrationTool::Diesel => &["diesel", "migration", "run"],
+            MigrationTool::Sqlx => &["sqlx", "migrate", "run"],
+            MigrationTool::Alembic => &["alembic", "upgrade", "head"],
+            MigrationTool::Prisma => &["prisma", "migrate", "deploy"],
+        };
+        args.iter().map(ToString::to_string).collect()
+    }
+
+    /// Names of the pending migrations reported by the status command.
+    pub(crate) fn parse_pending(self, stdout: &str) -> Vec<String> {
+        match self {
+            // `  [ ] 2024-01-01-000000_create_users`
+            MigrationTool::Diesel => stdout
+                .lines()
+                .filter_map(|line| line.trim().strip_prefix("[ ]"))
+                .map(|name| name.trim().to_string())
+                .collect(),
+            // `20240101000000/pending create users`
+            MigrationTool::Sqlx => stdout
+                .lines()
+                .filter_map(|line| {
+                    let (version, rest) = line.trim().split_once('/')?;
+                    rest.starts_with("pending").then(|| version.to_string())
+                })
+                .collect(),
+            // `-- Running upgrade 1a2b -> 3c4d`
+            MigrationTool::Alembic => stdout
+                .lines()
+                .filter_map(|line| line.trim().strip_prefix("-- Running upgrade "))
+                .map(|revision| revision.trim().to_string())
+                .collect(),
+            // A list of names following "... have not yet been applied:",
+            // terminated by a blank line.
+            MigrationTool::Prisma => stdout
+                .lines()
+                .skip_while(|line| !line.contains("not yet been applied"))
+                .skip(1)
+                .map(str::trim)
+                .take_while(|line| !line.is_empty())
+                .map(ToString::to_string)
+                .collect(),
+        }
+    }
+
+    /// Collect the plan from the status command's output, reading the SQL of
+    /// each pending migration from the project's migrations directory.
+    pub(crate) async fn load_plan(self, cwd: &Path, stdout: &str) -> Result<MigrationPlan, String> {
+        let pending = self.parse_pending(stdout);
+        if self == MigrationTool::Alembic {
+            return Ok(MigrationPlan {
+                pending,
+                sql: stdout.to_string(),
+            });
+        }
+
+        let mut sql = String::new();
+        for name in &pending {
+            let path = self.migration_sql_path(cwd, name).await?;
+            let contents = tokio::fs::read_to_string(&path)
+                .await
+                .map_err(|err| format!("failed to read {}: {err}", path.display()))?;
+            sql.push_str(&format!("-- {name}\n{}\n", contents.trim_end()));
+        }
+        Ok(MigrationPlan { pending, sql })
+    }
+
+    async fn migration_sql_path(self, cwd: &Path, name: &str) -> Result<PathBuf, String> {
+        match self {
+            MigrationTool::Diesel => Ok(cwd.join("migrations").join(name).join("up.sql")),
+            MigrationTool::Prisma => Ok(cwd
+                .join("prisma/migrations")
+                .join(name)
+                .join("migration.sql")),
+            // sqlx names files `<version>_<description>.sql`, or `.up.sql` for
+            // reversible migrations.
+            MigrationTool::Sqlx => {
+                let dir = cwd.join("migrations");
+                let mut entries = tokio::fs::read_dir(&dir)
+                    .await
+                    .map_err(|err| format!("failed to read {}: {err}", dir.display()))?;
+                let prefix = format!("{name}_");
+                while let Ok(Some(entry)) = entries.next_entry().await {
+                    let file_name = entry.file_name().to_string_lossy().into_owned();
+                    if file_name.starts_with(&prefix)
+                        && file_name.ends_with(".sql")
+                        && !file_name.ends_with(".down.sql")
+                    {
+                        return Ok(entry.path());
+                    }
+                }
+                Err(format!(
+                    "no migration file for version {name} in {}",
+                    dir.display()
+                ))
+            }
+            MigrationTool::Alembic => Err("alembic plans are rendered, not read".to_string()),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct MigrateRequest {
+    pub command: Vec<String>,
+    pub cwd: PathBuf,
+    pub timeout_ms: Option<u64>,
+    /// Includes `DATABASE_URL` for the database this step targets.
+    pub env: HashMap<String, String>,
+    /// SQL shown with the approval request; set for the real apply.
+    pub sql: Option<String>,
+    pub approval_requirement: ApprovalRequirement,
+}
+
+impl ProvidesSandboxRetryData for MigrateRequest {
+    fn sandbox_retry_data(&self) -> Option<SandboxRetryData> {
+        command_retry_data(&self.command, &self.cwd, &self.env)
+    }
+}
+
+#[derive(Default)]
+pub struct MigrateRuntime;
+
+/// Approvals are keyed on the SQL as well as the command, so approving one
+/// set of migrations for the session does not approve the next.
+#[derive(Serialize, Clone, Debug, Eq, PartialEq, Hash)]
+pub(crate) struct ApprovalKey {
+    command: Vec<String>,
+    cwd: PathBuf,
+    sql: Option<String>,
+}
+
+impl MigrateRuntime {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Sandboxable for MigrateRuntime {
+    fn sandbox_preference(&self) -> SandboxablePreference {
+        SandboxablePreference::Auto
+    }
+}
+
+impl Approvable<MigrateRequest> for MigrateRuntime {
+    type ApprovalKey = ApprovalKey;
+
+    fn approval_key(&self, req: &MigrateRequest) -> Self::ApprovalKey {
+        ApprovalKey {
+            command: req.command.clone(),
+            cwd: normalize_for_comparison(&req.cwd),
+            sql: req.sql.clone(),
+        }
+    }
+
+    fn start_approval_async<'a>(
+        &'a mut self,
+        req: &'a MigrateRequest,
+        ctx: ApprovalCtx<'a>,
+    ) -> BoxFuture<'a, ReviewDecision> {
+        let reason = match (&req.sql, ctx.retry_reason.clone()) {
+            (Some(sql), Some(reason)) => Some(format!("{reason}\n\n{}", approval_sql(sql))),
+            (Some(sql), None) => Some(approval_sql(sql)),
+            (None, reason) => reason,
+        };
+        request_command_approval(self.approval_key(req), &req.command, &req.cwd, reason, ctx)
+    }
+
+    fn approval_requirement(&self, req: &MigrateRequest) -> Option<ApprovalRequirement> {
+        Some(req.approval_requirement.clone())
+    }
+}
+
+fn approval_sql(sql: &str) -> String {
+    format!(
+        "Dry run against the shadow database succeeded. Applying:\n{}",
+        truncate_text(sql, TruncationPolicy::Bytes(APPROVAL_SQL_BYTES))
+    )
+}
+
+impl ToolRuntime<MigrateRequest, ExecToolCallOutput> for MigrateRuntime {
+    async fn run(
+        &mut self,
+        req: &MigrateRequest,
+        attempt: &SandboxAttempt<'_>,
+        ctx: &ToolCtx<'_>,
+    ) -> Result<ExecToolCallOutput, ToolError> {
+        exec_via_executor(
+            req.command.clone(),
+            &req.cwd,
+            &req.env,
+            req.timeout_ms,
+            attempt,
+            ctx,
+        )
+        .await
+    }
+
+    fn exit_code(&self, out: &ExecToolCallOutput) -> Option<i32> {
+        Some(out.exit_code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::tempdir;
+
+    #[test]
+    fn parses_pending_migrations() {
+        assert_eq!(
+            MigrationTool::Diesel.parse_pending(
+                "Migrations:\n  [X] 00000000000000_diesel_initial_setup\n  \
+                 [ ] 2024-01-01-000000_create_users\n"
+            ),
+            vec!["2024-01-01-000000_create_users"]
+        );
+        assert_eq!(
+            MigrationTool::Sqlx.parse_pending(
+                "20240101000000/installed create users\n20240102000000/pending add email\n"
+            ),
+            vec!["20240102000000"]
+        );
+        assert_eq!(
+            MigrationTool::Alembic.parse_pending(
+                "BEGIN;\n\n-- Running upgrade  -> 1a2b\n\nCREATE TABLE t (id INT);\n\n\
+                 -- Running upgrade 1a2b -> 3c4d\n\nCOMMIT;\n"
+            ),
+            vec!["-> 1a2b", "1a2b -> 3c4d"]
+        );
+        assert_eq!(
+            MigrationTool::Prisma.parse_pending(
+                "2 migrations found in prisma/migrations\n\
+                 Following migrations have not yet been applied:\n\
+                 20240101000000_init\n20240102000000_add_email\n\n\
+                 To apply migrations in production run prisma migrate deploy.\n"
+            ),
+            vec!["20240101000000_init", "20240102000000_add_email"]
+        );
+    }
+
+    #[tokio::test]
+    async fn loads_sql_for_pending_migrations() {
+        let dir = tempdir().expect("tempdir");
+        let migrations = dir.path().join("migrations");
+        std::fs::create_dir_all(&migrations).expect("mkdir");
+        std::fs::write(
+            migrations.join("20240102000000_add_email.up.sql"),
+            "ALTER TABLE users ADD email TEXT;\n",
+        )
+        .expect("write up");
+        std::fs::write(
+            migrations.join("20240102000000_add_email.down.sql"),
+            "ALTER TABLE users DROP email;\n",
+        )
+        .expect("write down");
+
+        let plan = MigrationTool::Sqlx
+            .load_plan(dir.path(), "20240102000000/pending add email\n")
+            .await
+            .expect("plan");
+        assert_eq!(
+            plan,
+            MigrationPlan {
+                pending: vec!["20240102000000".to_string()],
+                sql: "-- 20240102000000\nALTER TABLE users ADD email TEXT;\n".to_string(),
+            }
+        );
+
+        assert_eq!(
+            MigrationTool::Sqlx
+                .load_plan(dir.path(), "20240103000000/pending missing\n")
+                .await,
+            Err(format!(
+                "no migration file for version 20240103000000 in {}",
+                migrations.display()
+            ))
+        );
+    }
+}
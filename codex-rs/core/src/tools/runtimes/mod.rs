@@ -3,23 +3,55 @@ Module: runtimes
 
 Concrete ToolRuntime implementations for specific tools. Each runtime stays
 small and focused and reuses the orchestrator for approvals + sandbox + retry.
+Runtimes that run one command line share its approval key, retry data and
+approval request through the helpers below.
 */
+use crate::config::types::CommandEnvPolicy;
 use crate::exec::ExecExpiration;
+use crate::exec::ExecToolCallOutput;
+use crate::exec_env::prepare_command_env;
 use crate::sandboxing::CommandSpec;
+use crate::tools::runtimes::shell::ShellRequest;
+use crate::tools::sandboxing::ApprovalCtx;
+use crate::tools::sandboxing::ApprovalRequirement;
+use crate::tools::sandboxing::SandboxAttempt;
+use crate::tools::sandboxing::SandboxRetryData;
+use crate::tools::sandboxing::ToolCtx;
 use crate::tools::sandboxing::ToolError;
+use crate::tools::sandboxing::with_cached_approval;
+use codex_protocol::paths::normalize_for_comparison;
+use codex_protocol::protocol::ReviewDecision;
+use futures::future::BoxFuture;
+use serde::Serialize;
 use std::collections::HashMap;
 use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
 
 pub mod apply_patch;
+pub mod bench;
+pub mod compose;
+pub mod coverage;
+pub mod custom;
+pub mod git;
+pub mod migrate;
+pub mod process;
+pub mod read_file;
+pub mod search;
 pub mod shell;
+pub mod test_runner;
 pub mod unified_exec;
+pub mod web_fetch;
+pub mod write_file;
 
 /// Shared helper to construct a CommandSpec from a tokenized command line.
-/// Validates that at least a program is present.
+/// Validates that at least a program is present, and filters `env` through
+/// `command_env`.
 pub(crate) fn build_command_spec(
     command: &[String],
     cwd: &Path,
     env: &HashMap<String, String>,
+    env_policy: &CommandEnvPolicy,
     expiration: ExecExpiration,
     with_escalated_permissions: Option<bool>,
     justification: Option<String>,
@@ -31,9 +63,121 @@ pub(crate) fn build_command_spec(
         program: program.clone(),
         args: args.to_vec(),
         cwd: cwd.to_path_buf(),
-        env: env.clone(),
+        env: prepare_command_env(env, env_policy),
         expiration,
         with_escalated_permissions,
         justification,
+        network: None,
+    })
+}
+
+/// Runs a runtime-built command through the session's `ToolExecutor`, exactly
+/// as `ShellRuntime` would. Runtimes wrapping external tooling use this so an
+/// embedder-provided executor sees every spawned process. Approval has already
+/// been resolved by the orchestrator at this point.
+pub(crate) async fn exec_via_executor(
+    command: Vec<String>,
+    cwd: &Path,
+    env: &HashMap<String, String>,
+    timeout_ms: Option<u64>,
+    attempt: &SandboxAttempt<'_>,
+    ctx: &ToolCtx<'_>,
+) -> Result<ExecToolCallOutput, ToolError> {
+    let req = ShellRequest {
+        command,
+        cwd: cwd.to_path_buf(),
+        timeout_ms,
+        env: Arc::new(env.clone()),
+        with_escalated_permissions: None,
+        justification: None,
+        background: false,
+        network: None,
+        approval_requirement: ApprovalRequirement::Skip {
+            bypass_sandbox: false,
+        },
+        expansions: Vec::new(),
+    };
+    let executor = ctx.session.services.tool_executor.clone();
+    executor.run_shell(&req, attempt, ctx).await
+}
+
+/// Approval key of runtimes that run one command line. Runs of the same
+/// command in the same directory share an approval, whichever tool asked.
+#[derive(Serialize, Clone, Debug, Eq, PartialEq, Hash)]
+pub(crate) struct CommandApprovalKey {
+    command: Vec<String>,
+    cwd: PathBuf,
+}
+
+impl CommandApprovalKey {
+    pub(crate) fn new(command: &[String], cwd: &Path) -> Self {
+        Self {
+            command: command.to_vec(),
+            cwd: normalize_for_comparison(cwd),
+        }
+    }
+}
+
+/// Retry data of a request that runs `command` in `cwd` with `env`.
+pub(crate) fn command_retry_data(
+    command: &[String],
+    cwd: &Path,
+    env: &HashMap<String, String>,
+) -> Option<SandboxRetryData> {
+    Some(SandboxRetryData {
+        command: command.to_vec(),
+        cwd: cwd.to_path_buf(),
+        env: env.clone(),
+        denials: Vec::new(),
+    })
+}
+
+/// Asks to run `command` in `cwd`, giving `reason`, unless an approval cached
+/// under `key` covers it; the `start_approval_async` of runtimes that run a
+/// command line.
+pub(crate) fn request_command_approval<'a, K>(
+    key: K,
+    command: &[String],
+    cwd: &Path,
+    reason: Option<String>,
+    ctx: ApprovalCtx<'a>,
+) -> BoxFuture<'a, ReviewDecision>
+where
+    K: Serialize + Clone + Send + 'a,
+{
+    let command = command.to_vec();
+    let cwd = cwd.to_path_buf();
+    let ApprovalCtx {
+        session,
+        turn,
+        call_id,
+        risk,
+        ..
+    } = ctx;
+    let call_id = call_id.to_string();
+    Box::pin(async move {
+        with_cached_approval(&session.services, turn, key, move || async move {
+            session
+                .request_command_approval(turn, call_id, command, cwd, reason, risk)
+                .await
+        })
+        .await
+    })
+}
+
+/// `arg` quoted for a POSIX shell; left as is if it cannot be quoted.
+pub(crate) fn quote(arg: &str) -> String {
+    shlex::try_quote(arg)
+        .map(|quoted| quoted.to_string())
+        .unwrap_or_else(|_| arg.to_string())
+}
+
+/// `args` quoted for a POSIX shell, each preceded by a space so that the
+/// result can be appended to a command.
+pub(crate) fn join_args(args: &[String]) -> String {
+    args.iter().fold(String::new(), |mut acc, arg| {
+        acc.push(' ');
+        acc.push_str(&quote(arg));
+        acc
     })
 }
@@ -0,0 +1,479 @@
+/*
+Runtime: process
+
+Lists processes that belong to this session (descendants of the Codex process)
+or to the workspace (working directory or a command-line argument under the
+workspace root), and terminates them after approval. Listing is read-only and happens in
+the handler; only the kill path goes through the orchestrator.
+*/
+use crate::exec::ExitReason;
+use crate::tools::runtimes::request_command_approval;
+use crate::tools::sandboxing::Approvable;
+use crate::tools::sandboxing::ApprovalCtx;
+use crate::tools::sandboxing::ApprovalRequirement;
+use crate::tools::sandboxing::ProvidesSandboxRetryData;
+use crate::tools::sandboxing::SandboxAttempt;
+use crate::tools::sandboxing::SandboxRetryData;
+use crate::tools::sandboxing::Sandboxable;
+use crate::tools::sandboxing::SandboxablePreference;
+use crate::tools::sandboxing::ToolCtx;
+use crate::tools::sandboxing::ToolError;
+use crate::tools::sandboxing::ToolRuntime;
+use codex_protocol::protocol::ReviewDecision;
+use futures::future::BoxFuture;
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::process::Command;
+use tokio::time::timeout;
+
+const PS_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// One row of `ps` output.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ProcessInfo {
+    pub pid: i64,
+    pub ppid: i64,
+    /// Resident set size in KiB.
+    pub rss_kb: i64,
+    pub cpu_percent: f64,
+    /// Elapsed time as printed by `ps` (`[[dd-]hh:]mm:ss`).
+    pub elapsed: String,
+    pub command: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<PathBuf>,
+}
+
+/// Why a process is considered part of this session's scope.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProcessScope {
+    /// Spawned (directly or transitively) by this Codex process.
+    Session,
+    /// Runs in, or is given a path under, the workspace root.
+    Workspace,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ScopedProcess {
+    #[serde(flatten)]
+    pub info: ProcessInfo,
+    pub scope: ProcessScope,
+}
+
+/// Arguments for `ps` that print the columns parsed by [`parse_ps_output`].
+/// Supported by both procps (Linux) and BSD `ps` (macOS).
+pub(crate) const PS_ARGS: [&str; 13] = [
+    "axww", "-o", "pid=", "-o", "ppid=", "-o", "rss=", "-o", "pcpu=", "-o", "etime=", "-o", "args=",
+];
+
+/// Parse `ps -o pid=,ppid=,rss=,pcpu=,etime=,args=` output.
+pub(crate) fn parse_ps_output(output: &str) -> Vec<ProcessInfo> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut rest = line.trim_start();
+            let mut fields = Vec::with_capacity(5);
+            for _ in 0..5 {
+                let end = rest.find(char::is_whitespace)?;
+                fields.push(&rest[..end]);
+                rest = rest[end..].trim_start();
+            }
+            Some(ProcessInfo {
+                pid: fields[0].parse().ok()?,
+                ppid: fields[1].parse().ok()?,
+                rss_kb: fields[2].parse().ok()?,
+                cpu_percent: fields[3].parse().ok()?,
+                elapsed: fields[4].to_string(),
+                command: rest.trim_end().to_string(),
+                cwd: None,
+            })
+        })
+        .collect()
+}
+
+/// Select the processes that belong to `root_pid`'s process tree or to the
+/// workspace. The root process itself and the `ps` invocation are excluded.
+pub(crate) fn scope_processes(
+    processes: Vec<ProcessInfo>,
+    root_pid: i64,
+    workspace: &Path,
+) -> Vec<ScopedProcess> {
+    let parents: HashMap<i64, i64> = processes.iter().map(|p| (p.pid, p.ppid)).collect();
+    let is_descendant = |pid: i64| {
+        let mut current = pid;
+        // Bound the walk in case `ps` raced with pid reuse and produced a cycle.
+        for _ in 0..parents.len() {
+            match parents.get(&current) {
+                Some(&parent) if parent == root_pid => return true,
+                Some(&parent) if parent > 1 && parent != current => current = parent,
+                _ => return false,
+            }
+        }
+        false
+    };
+    processes
+        .into_iter()
+        .filter(|p| p.pid != root_pid && !p.command.starts_with("ps "))
+        .filter_map(|info| {
+            let scope = if is_descendant(info.pid) {
+                ProcessScope::Session
+            } else if info
+                .cwd
+                .as_deref()
+                .is_some_and(|cwd| cwd.starts_with(workspace))
+                || references_path(&info.command, workspace)
+            {
+                ProcessScope::Workspace
+            } else {
+                return None;
+            };
+            Some(ScopedProcess { info, scope })
+        })
+        .collect()
+}
+
+/// Whether an argument of `command`, or the value of a `--flag=value`
+/// argument, is a path at or under `dir`. Paths are compared by component, so
+/// `/work/repo2` is not under `/work/repo`.
+fn references_path(command: &str, dir: &Path) -> bool {
+    command.split_whitespace().any(|arg| {
+        let value = match arg.split_once('=') {
+            Some((_, value)) if arg.starts_with('-') => value,
+            _ => arg,
+        };
+        Path::new(value).starts_with(dir)
+    })
+}
+
+/// Convert `ps`'s `[[dd-]hh:]mm:ss` elapsed time to seconds.
+pub(crate) fn elapsed_seconds(elapsed: &str) -> Option<u64> {
+    let (days, clock) = match elapsed.split_once('-') {
+        Some((days, clock)) => (days.parse::<u64>().ok()?, clock),
+        None => (0, elapsed),
+    };
+    let mut seconds = 0;
+    let mut fields = 0;
+    for field in clock.split(':') {
+        seconds = seconds * 60 + field.parse::<u64>().ok()?;
+        fields += 1;
+    }
+    if !(2..=3).contains(&fields) {
+        return None;
+    }
+    Some(days * 24 * 60 * 60 + seconds)
+}
+
+/// Run `ps` and keep the processes in this process's tree or `workspace`.
+pub(crate) async fn list_scoped_processes(workspace: &Path) -> Result<Vec<ScopedProcess>, String> {
+    let output = timeout(PS_TIMEOUT, Command::new("ps").args(PS_ARGS).output())
+        .await
+        .map_err(|_| "ps timed out".to_string())?
+        .map_err(|err| format!("failed to run ps: {err}"))?;
+    if !output.status.success() {
+        return Err(format!(
+            "ps failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    let mut processes = parse_ps_output(&String::from_utf8_lossy(&output.stdout));
+    for process in &mut processes {
+        process.cwd = process_cwd(process.pid);
+    }
+    Ok(scope_processes(
+        processes,
+        i64::from(std::process::id()),
+        workspace,
+    ))
+}
+
+/// Best-effort working directory lookup; only available via procfs.
+pub(crate) fn process_cwd(pid: i64) -> Option<PathBuf> {
+    if cfg!(target_os = "linux") {
+        std::fs::read_link(format!("/proc/{pid}/cwd")).ok()
+    } else {
+        None
+    }
+}
+
+/// Best-effort start time in clock ticks since boot, which tells a process
+/// from a later one given the same pid; only available via procfs.
+pub(crate) fn process_start_time(pid: i64) -> Option<u64> {
+    if cfg!(target_os = "linux") {
+        parse_start_time(&std::fs::read_to_string(format!("/proc/{pid}/stat")).ok()?)
+    } else {
+        None
+    }
+}
+
+/// The `starttime` field of a `/proc/<pid>/stat` line. The command name
+/// before it is parenthesized and may contain spaces, so fields are counted
+/// from the last `)`, after which `state` is the third field of the line and
+/// `starttime` the twenty-second.
+fn parse_start_time(stat: &str) -> Option<u64> {
+    let (_, fields) = stat.rsplit_once(')')?;
+    fields.split_whitespace().nth(19)?.parse().ok()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KillSignal {
+    Term,
+    Kill,
+}
+
+impl KillSignal {
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            KillSignal::Term => "TERM",
+            KillSignal::Kill => "KILL",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ProcessKillRequest {
+    pub pid: i64,
+    pub signal: KillSignal,
+    /// Command line of the target, shown to the user when asking for approval.
+    pub command: String,
+    /// Start time of the target, from [`process_start_time`], checked again
+    /// before the signal is sent in case the pid went to another process.
+    pub started: Option<u64>,
+    pub cwd: PathBuf,
+    pub approval_requirement: ApprovalRequirement,
+}
+
+impl ProcessKillRequest {
+    fn display_command(&self) -> Vec<String> {
+        vec![
+            "kill".to_string(),
+            format!("-{}", self.signal.name()),
+            self.pid.to_string(),
+        ]
+    }
+}
+
+impl ProvidesSandboxRetryData for ProcessKillRequest {
+    fn sandbox_retry_data(&self) -> Option<SandboxRetryData> {
+        Some(SandboxRetryData {
+            command: self.display_command(),
+            cwd: self.cwd.clone(),
+            env: HashMap::new(),
+            denials: Vec::new(),
+        })
+    }
+}
+
+#[derive(Default)]
+pub struct ProcessKillRuntime;
+
+#[derive(Serialize, Clone, Debug, Eq, PartialEq, Hash)]
+pub(crate) struct ApprovalKey {
+    pid: i64,
+    signal: KillSignal,
+    /// The command line and start time tell the approved process from a
+    /// later one that reuses its pid.
+    command: String,
+    started: Option<u64>,
+}
+
+impl ProcessKillRuntime {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Sandboxable for ProcessKillRuntime {
+    fn sandbox_preference(&self) -> SandboxablePreference {
+        // Signals are delivered directly; there is no child process to sandbox.
+        SandboxablePreference::Forbid
+    }
+    fn escalate_on_failure(&self, _reason: ExitReason) -> bool {
+        false
+    }
+}
+
+impl Approvable<ProcessKillRequest> for ProcessKillRuntime {
+    type ApprovalKey = ApprovalKey;
+
+    fn approval_key(&self, req: &ProcessKillRequest) -> Self::ApprovalKey {
+        ApprovalKey {
+            pid: req.pid,
+            signal: req.signal,
+            command: req.command.clone(),
+            started: req.started,
+        }
+    }
+
+    fn start_approval_async<'a>(
+        &'a mut self,
+        req: &'a ProcessKillRequest,
+        ctx: ApprovalCtx<'a>,
+    ) -> BoxFuture<'a, ReviewDecision> {
+        let reason = ctx
+            .retry_reason
+            .clone()
+            .or_else(|| Some(format!("terminate `{}`", req.command)));
+        request_command_approval(
+            self.approval_key(req),
+            &req.display_command(),
+            &req.cwd,
+            reason,
+            ctx,
+        )
+    }
+
+    fn approval_requirement(&self, req: &ProcessKillRequest) -> Option<ApprovalRequirement> {
+        Some(req.approval_requirement.clone())
+    }
+}
+
+impl ToolRuntime<ProcessKillRequest, ()> for ProcessKillRuntime {
+    async fn run(
+        &mut self,
+        req: &ProcessKillRequest,
+        _attempt: &SandboxAttempt<'_>,
+        _ctx: &ToolCtx<'_>,
+    ) -> Result<(), ToolError> {
+        if req.started.is_some() && process_start_time(req.pid) != req.started {
+            return Err(ToolError::Rejected(format!(
+                "process {} exited before it was signaled",
+                req.pid
+            )));
+        }
+        send_signal(req.pid, req.signal)
+    }
+}
+
+#[cfg(unix)]
+pub(crate) fn send_signal(pid: i64, signal: KillSignal) -> Result<(), ToolError> {
+    let pid = libc::pid_t::try_from(pid)
+        .map_err(|_| ToolError::Rejected(format!("invalid pid {pid}")))?;
+    let signo = match signal {
+        KillSignal::Term => libc::SIGTERM,
+        KillSignal::Kill => libc::SIGKILL,
+    };
+    // SAFETY: kill(2) has no memory-safety preconditions.
+    let rc = unsafe { libc::kill(pid, signo) };
+    if rc == 0 {
+        Ok(())
+    } else {
+        let err = std::io::Error::last_os_error();
+        Err(ToolError::Rejected(format!(
+            "failed to send SIG{} to {pid}: {err}",
+            signal.name()
+        )))
+    }
+}
+
+#[cfg(not(unix))]
+pub(crate) fn send_signal(pid: i64, _signal: KillSignal) -> Result<(), ToolError> {
+    Err(ToolError::Rejected(format!(
+        "terminating process {pid} is not supported on this platform"
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn process(pid: i64, ppid: i64, command: &str) -> ProcessInfo {
+        ProcessInfo {
+            pid,
+            ppid,
+            rss_kb: 0,
+            cpu_percent: 0.0,
+            elapsed: "00:01".to_string(),
+            command: command.to_string(),
+            cwd: None,
+        }
+    }
+
+    #[test]
+    fn parses_ps_rows_with_spaces_in_args() {
+        let output = "  101     1  20480  1.5    01:02:03 node server.js --port 3000\n\
+                      bogus line\n\
+                      202   101    512  0.0       00:05 sleep 10\n";
+        assert_eq!(
+            parse_ps_output(output),
+            vec![
+                ProcessInfo {
+                    pid: 101,
+                    ppid: 1,
+                    rss_kb: 20480,
+                    cpu_percent: 1.5,
+                    elapsed: "01:02:03".to_string(),
+                    command: "node server.js --port 3000".to_string(),
+                    cwd: None,
+                },
+                ProcessInfo {
+                    pid: 202,
+                    ppid: 101,
+                    rss_kb: 512,
+                    cpu_percent: 0.0,
+                    elapsed: "00:05".to_string(),
+                    command: "sleep 10".to_string(),
+                    cwd: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_elapsed_times() {
+        assert_eq!(elapsed_seconds("00:05"), Some(5));
+        assert_eq!(elapsed_seconds("01:02:03"), Some(3723));
+        assert_eq!(elapsed_seconds("2-00:00:10"), Some(172_810));
+        assert_eq!(elapsed_seconds("5"), None);
+        assert_eq!(elapsed_seconds("ab:cd"), None);
+    }
+
+    #[test]
+    fn scope_keeps_descendants_and_workspace_processes() {
+        let mut orphan = process(40, 1, "python -m http.server");
+        orphan.cwd = Some(PathBuf::from("/work/repo/site"));
+        let processes = vec![
+            process(10, 1, "codex"),
+            process(11, 10, "bash -lc npm run dev"),
+            process(12, 11, "node dev-server.js"),
+            process(20, 1, "unrelated"),
+            process(30, 1, "vite /work/repo/app"),
+            process(31, 1, "node server.js --root=/work/repo"),
+            process(32, 1, "vite /work/repo2/app"),
+            process(33, 1, "grep -r repo /work"),
+            orphan,
+            process(50, 10, "ps axww -o pid="),
+        ];
+
+        let scoped: Vec<(i64, ProcessScope)> =
+            scope_processes(processes, 10, Path::new("/work/repo"))
+                .into_iter()
+                .map(|p| (p.info.pid, p.scope))
+                .collect();
+
+        assert_eq!(
+            scoped,
+            vec![
+                (11, ProcessScope::Session),
+                (12, ProcessScope::Session),
+                (30, ProcessScope::Workspace),
+                (31, ProcessScope::Workspace),
+                (40, ProcessScope::Workspace),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_start_times_after_command_names_with_spaces() {
+        let stat = "4242 (my (odd) name) S 1 4242 4242 0 -1 4194560 100 0 0 0 1 2 0 0 20 0 \
+                    1 0 987654 123456 789 18446744073709551615";
+        assert_eq!(parse_start_time(stat), Some(987_654));
+        assert_eq!(parse_start_time("4242 (truncated"), None);
+    }
+}
@@ -0,0 +1,368 @@
+/*
+Runtime: read_file
+
+Reads a line or byte range of a file in-process, so the model does not need
+to spawn `cat` through the shell runtime. Approval is decided as for the
+equivalent `cat <path>` command, so exec policy rules and the approval policy
+treat both the same way. Every sandbox policy allows reads, so there is no
+sandbox to run under or escalate out of.
+*/
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::io::SeekFrom;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::exec::ExitReason;
+use crate::file_placeholder;
+use crate::tools::sandboxing::Approvable;
+use crate::tools::sandboxing::ApprovalCtx;
+use crate::tools::sandboxing::ApprovalRequirement;
+use crate::tools::sandboxing::ProvidesSandboxRetryData;
+use crate::tools::sandboxing::SandboxAttempt;
+use crate::tools::sandboxing::SandboxRetryData;
+use crate::tools::sandboxing::Sandboxable;
+use crate::tools::sandboxing::SandboxablePreference;
+use crate::tools::sandboxing::ToolCtx;
+use crate::tools::sandboxing::ToolError;
+use crate::tools::sandboxing::ToolRuntime;
+use crate::tools::sandboxing::with_cached_approval;
+use codex_protocol::paths::normalize_for_comparison;
+use codex_protocol::protocol::ReviewDecision;
+use futures::future::BoxFuture;
+use tokio::io::AsyncBufReadExt;
+use tokio::io::AsyncRead;
+use tokio::io::AsyncReadExt;
+use tokio::io::AsyncSeek;
+use tokio::io::AsyncSeekExt;
+use tokio::io::BufReader;
+
+/// Default for `read_file_max_bytes`.
+pub(crate) const READ_FILE_MAX_BYTES: usize = 1024 * 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReadRange {
+    /// The whole file.
+    All,
+    /// `limit` lines starting at the 1-based line `offset`.
+    Lines { offset: usize, limit: usize },
+    /// `limit` bytes starting at the 0-based byte `offset`.
+    Bytes { offset: u64, limit: u64 },
+}
+
+#[derive(Clone, Debug)]
+pub struct ReadFileRequest {
+    pub path: PathBuf,
+    pub cwd: PathBuf,
+    pub range: ReadRange,
+    /// The most bytes returned; longer ranges are cut short.
+    pub max_bytes: usize,
+    /// The file's contents if they were already read ahead of the call.
+    pub prefetched: Option<Arc<[u8]>>,
+    pub approval_requirement: ApprovalRequirement,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ReadFileOutput {
+    pub contents: Vec<u8>,
+    /// Whether the file's first bytes classify it as binary.
+    pub binary: bool,
+    /// Size of the whole file.
+    pub file_len: u64,
+    /// Whether `max_bytes` cut the range short.
+    pub truncated: bool,
+}
+
+impl ProvidesSandboxRetryData for ReadFileRequest {
+    fn sandbox_retry_data(&self) -> Option<SandboxRetryData> {
+        Some(SandboxRetryData {
+            command: display_command(&self.path),
+            cwd: self.cwd.clone(),
+            env: HashMap::new(),
+            denials: Vec::new(),
+        })
+    }
+}
+
+/// The command a read is shown as in approval prompts.
+fn display_command(path: &std::path::Path) -> Vec<String> {
+    vec!["read_file".to_string(), path.display().to_string()]
+}
+
+#[derive(Default)]
+pub struct ReadFileRuntime;
+
+#[derive(serde::Serialize, Clone, Debug, Eq, PartialEq, Hash)]
+pub(crate) struct ApprovalKey {
+    path: PathBuf,
+}
+
+impl ReadFileRuntime {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Sandboxable for ReadFileRuntime {
+    fn sandbox_preference(&self) -> SandboxablePreference {
+        SandboxablePreference::Forbid
+    }
+    fn escalate_on_failure(&self, _reason: ExitReason) -> bool {
+        false
+    }
+}
+
+impl Approvable<ReadFileRequest> for ReadFileRuntime {
+    type ApprovalKey = ApprovalKey;
+
+    fn approval_key(&self, req: &ReadFileRequest) -> Self::ApprovalKey {
+        ApprovalKey {
+            path: normalize_for_comparison(&req.path),
+        }
+    }
+
+    fn start_approval_async<'a>(
+        &'a mut self,
+        req: &'a ReadFileRequest,
+        ctx: ApprovalCtx<'a>,
+    ) -> BoxFuture<'a, ReviewDecision> {
+        let key = self.approval_key(req);
+        let command = display_command(&req.path);
+        let cwd = req.cwd.clone();
+        let reason = ctx.retry_reason.clone();
+        let risk = ctx.risk.clone();
+        let session = ctx.session;
+        let turn = ctx.turn;
+        let call_id = ctx.call_id.to_string();
+        Box::pin(async move {
+            with_cached_approval(&session.services, turn, key, move || async move {
+                session
+                    .request_command_approval(turn, call_id, command, cwd, reason, risk)
+                    .await
+            })
+            .await
+        })
+    }
+
+    fn approval_requirement(&self, req: &ReadFileRequest) -> Option<ApprovalRequirement> {
+        Some(req.approval_requirement.clone())
+    }
+}
+
+impl ToolRuntime<ReadFileRequest, ReadFileOutput> for ReadFileRuntime {
+    async fn run(
+        &mut self,
+        req: &ReadFileRequest,
+        _attempt: &SandboxAttempt<'_>,
+        _ctx: &ToolCtx<'_>,
+    ) -> Result<ReadFileOutput, ToolError> {
+        let result = match &req.prefetched {
+            Some(contents) => {
+                read_range(Cursor::new(Arc::clone(contents)), req.range, req.max_bytes).await
+            }
+            None => match tokio::fs::File::open(&req.path).await {
+                Ok(file) => read_range(file, req.range, req.max_bytes).await,
+                Err(err) => Err(err),
+            },
+        };
+        result.map_err(|err| ToolError::Rejected(format!("failed to read file: {err}")))
+    }
+}
+
+/// Reads `range` of `source`, returning at most `max_bytes`. A line range
+/// ends at the last whole line that fits, unless even the first line does
+/// not, which is then cut.
+pub(crate) async fn read_range<R>(
+    mut source: R,
+    range: ReadRange,
+    max_bytes: usize,
+) -> std::io::Result<ReadFileOutput>
+where
+    R: AsyncRead + AsyncSeek + Unpin,
+{
+    let file_len = source.seek(SeekFrom::End(0)).await?;
+    source.seek(SeekFrom::Start(0)).await?;
+    let mut head = Vec::new();
+    (&mut source)
+        .take(file_placeholder::SNIFF_BYTES)
+        .read_to_end(&mut head)
+        .await?;
+    let (_, binary) = file_placeholder::classify(&head);
+    source.seek(SeekFrom::Start(0)).await?;
+
+    let max = max_bytes as u64;
+    let mut contents = Vec::new();
+    let truncated = match range {
+        ReadRange::All => {
+            (&mut source)
+                .take(max.saturating_add(1))
+                .read_to_end(&mut contents)
+                .await?;
+            let truncated = contents.len() > max_bytes;
+            contents.truncate(max_bytes);
+            truncated
+        }
+        ReadRange::Bytes { offset, limit } => {
+            source.seek(SeekFrom::Start(offset)).await?;
+            (&mut source)
+                .take(limit.min(max))
+                .read_to_end(&mut contents)
+                .await?;
+            limit > max && offset.saturating_add(max) < file_len
+        }
+        ReadRange::Lines { offset, limit } => {
+            let mut reader = BufReader::new(&mut source);
+            let mut line = Vec::new();
+            let mut number = 0;
+            let mut taken = 0;
+            let mut truncated = false;
+            while taken < limit {
+                line.clear();
+                if reader.read_until(b'\n', &mut line).await? == 0 {
+                    break;
+                }
+                number += 1;
+                if number < offset {
+                    continue;
+                }
+                if contents.len() + line.len() > max_bytes {
+                    if taken == 0 {
+                        contents.extend_from_slice(&line[..max_bytes]);
+                    }
+                    truncated = true;
+                    break;
+                }
+                contents.extend_from_slice(&line);
+                taken += 1;
+            }
+            truncated
+        }
+    };
+    Ok(ReadFileOutput {
+        contents,
+        binary,
+        file_len,
+        truncated,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    async fn read(contents: &[u8], range: ReadRange, max_bytes: usize) -> ReadFileOutput {
+        read_range(Cursor::new(contents.to_vec()), range, max_bytes)
+            .await
+            .expect("read")
+    }
+
+    #[tokio::test]
+    async fn reads_line_and_byte_ranges() {
+        let text = b"one\ntwo\nthree\nfour\n";
+        assert_eq!(
+            read(
+                text,
+                ReadRange::Lines {
+                    offset: 2,
+                    limit: 2
+                },
+                100
+            )
+            .await,
+            ReadFileOutput {
+                contents: b"two\nthree\n".to_vec(),
+                binary: false,
+                file_len: 19,
+                truncated: false,
+            }
+        );
+        assert_eq!(
+            read(
+                text,
+                ReadRange::Bytes {
+                    offset: 4,
+                    limit: 5
+                },
+                100
+            )
+            .await,
+            ReadFileOutput {
+                contents: b"two\nt".to_vec(),
+                binary: false,
+                file_len: 19,
+                truncated: false,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn ranges_are_cut_at_max_bytes() {
+        let text = b"one\ntwo\nthree\nfour\n";
+        assert_eq!(
+            read(
+                text,
+                ReadRange::Lines {
+                    offset: 1,
+                    limit: 4
+                },
+                10
+            )
+            .await,
+            ReadFileOutput {
+                contents: b"one\ntwo\n".to_vec(),
+                binary: false,
+                file_len: 19,
+                truncated: true,
+            }
+        );
+        assert_eq!(
+            read(
+                text,
+                ReadRange::Lines {
+                    offset: 3,
+                    limit: 1
+                },
+                3
+            )
+            .await,
+            ReadFileOutput {
+                contents: b"thr".to_vec(),
+                binary: false,
+                file_len: 19,
+                truncated: true,
+            }
+        );
+        assert_eq!(
+            read(text, ReadRange::All, 4).await,
+            ReadFileOutput {
+                contents: b"one\n".to_vec(),
+                binary: false,
+                file_len: 19,
+                truncated: true,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn detects_binary_files() {
+        let elf = b"\x7fELF\x02\x01\x01\x00\x00";
+        assert_eq!(
+            read(
+                elf,
+                ReadRange::Bytes {
+                    offset: 0,
+                    limit: 4
+                },
+                100
+            )
+            .await,
+            ReadFileOutput {
+                contents: b"\x7fELF".to_vec(),
+                binary: true,
+                file_len: 9,
+                truncated: false,
+            }
+        );
+    }
+}
@@ -0,0 +1,426 @@
+/*
+Runtime: search
+
+Searches the text files under a directory for a regular expression or a
+literal string in-process, for the `search` tool. Like ripgrep, the walk
+honors `.gitignore` and `.ignore` files and skips hidden and binary files;
+include and exclude globs narrow it further, and it stops at a cap on
+matches. A search only reads, and only below the roots the sandbox lets
+commands work in (the working directory and the writable roots), so it needs
+no approval and runs under no sandbox.
+*/
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::blocking_io::run_blocking;
+use crate::exec::ExitReason;
+use crate::file_placeholder;
+use crate::tools::sandboxing::Approvable;
+use crate::tools::sandboxing::ApprovalCtx;
+use crate::tools::sandboxing::ApprovalRequirement;
+use crate::tools::sandboxing::ProvidesSandboxRetryData;
+use crate::tools::sandboxing::SandboxAttempt;
+use crate::tools::sandboxing::SandboxRetryData;
+use crate::tools::sandboxing::Sandboxable;
+use crate::tools::sandboxing::SandboxablePreference;
+use crate::tools::sandboxing::ToolCtx;
+use crate::tools::sandboxing::ToolError;
+use crate::tools::sandboxing::ToolRuntime;
+use crate::tools::sandboxing::with_cached_approval;
+use crate::tools::spec::JsonSchema;
+use codex_protocol::paths::normalize_for_comparison;
+use codex_protocol::protocol::ReviewDecision;
+use futures::future::BoxFuture;
+use ignore::WalkBuilder;
+use ignore::overrides::OverrideBuilder;
+use regex::RegexBuilder;
+use serde::Serialize;
+
+/// Matches returned unless the call asks for another number.
+pub(crate) const DEFAULT_MAX_MATCHES: usize = 100;
+pub(crate) const MAX_MATCHES: usize = 1000;
+pub(crate) const MAX_CONTEXT_LINES: usize = 10;
+/// Files larger than this are skipped; they are generated or data.
+const MAX_FILE_BYTES: u64 = 8 * 1024 * 1024;
+/// Longer lines are cut, so that one minified file cannot fill the output.
+const MAX_LINE_CHARS: usize = 300;
+
+#[derive(Clone, Debug)]
+pub struct SearchRequest {
+    pub pattern: String,
+    /// Match `pattern` as a plain string rather than a regular expression.
+    pub literal: bool,
+    pub case_insensitive: bool,
+    /// Globs a file must match one of to be searched, as in `.gitignore`.
+    pub include: Vec<String>,
+    /// Globs of files and directories to skip.
+    pub exclude: Vec<String>,
+    /// Lines shown before and after each match.
+    pub context_lines: usize,
+    pub max_matches: usize,
+    /// The directory or file to search.
+    pub root: PathBuf,
+    pub cwd: PathBuf,
+    /// Only paths below these may be searched.
+    pub readable_roots: Vec<PathBuf>,
+}
+
+#[derive(Serialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SearchOutput {
+    pub matches: Vec<SearchMatch>,
+    pub files_searched: usize,
+    /// The search stopped at `max_matches`; more lines match.
+    pub truncated: bool,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct SearchMatch {
+    /// Relative to the working directory when below it.
+    pub path: String,
+    /// 1-based.
+    pub line_number: usize,
+    pub line: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub before: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub after: Vec<String>,
+}
+
+impl ProvidesSandboxRetryData for SearchRequest {
+    fn sandbox_retry_data(&self) -> Option<SandboxRetryData> {
+        Some(SandboxRetryData {
+            command: display_command(self),
+            cwd: self.cwd.clone(),
+            env: HashMap::new(),
+            denials: Vec::new(),
+        })
+    }
+}
+
+/// The command a search is shown as in approval prompts.
+fn display_command(req: &SearchRequest) -> Vec<String> {
+    vec![
+        "search".to_string(),
+        req.pattern.clone(),
+        req.root.display().to_string(),
+    ]
+}
+
+/// Search the files under `req.root`.
+pub(crate) fn search(req: &SearchRequest) -> Result<SearchOutput, String> {
+    let pattern = if req.literal {
+        regex::escape(&req.pattern)
+    } else {
+        req.pattern.clone()
+    };
+    let regex = RegexBuilder::new(&pattern)
+        .case_insensitive(req.case_insensitive)
+        .build()
+        .map_err(|err| format!("invalid pattern: {err}"))?;
+    let mut overrides = OverrideBuilder::new(&req.root);
+    for glob in &req.include {
+        overrides
+            .add(glob)
+            .map_err(|err| format!("invalid include glob `{glob}`: {err}"))?;
+    }
+    for glob in &req.exclude {
+        overrides
+            .add(&format!("!{glob}"))
+            .map_err(|err| format!("invalid exclude glob `{glob}`: {err}"))?;
+    }
+    let overrides = overrides
+        .build()
+        .map_err(|err| format!("invalid globs: {err}"))?;
+    let walker = WalkBuilder::new(&req.root)
+        .overrides(overrides)
+        .sort_by_file_name(Ord::cmp)
+        .build();
+
+    let mut output = SearchOutput::default();
+    for entry in walker.flatten() {
+        let is_file = entry
+            .file_type()
+            .is_some_and(|file_type| file_type.is_file());
+        if !is_file
+            || !entry
+                .metadata()
+                .is_ok_and(|meta| meta.len() <= MAX_FILE_BYTES)
+        {
+            continue;
+        }
+        let Ok(bytes) = std::fs::read(entry.path()) else {
+            continue;
+        };
+        let head = &bytes[..bytes.len().min(file_placeholder::SNIFF_BYTES as usize)];
+        if file_placeholder::classify(head).1 {
+            continue;
+        }
+        output.files_searched += 1;
+        let path = entry
+            .path()
+            .strip_prefix(&req.cwd)
+            .unwrap_or(entry.path())
+            .display()
+            .to_string();
+        let text = String::from_utf8_lossy(&bytes);
+        let lines: Vec<&str> = text.lines().collect();
+        let context = |lines: &[&str]| lines.iter().map(|line| clip(line)).collect();
+        for (index, line) in lines.iter().enumerate() {
+            if !regex.is_match(line) {
+                continue;
+            }
+            if output.matches.len() == req.max_matches {
+                output.truncated = true;
+                return Ok(output);
+            }
+            let before = index.saturating_sub(req.context_lines);
+            let after = (index + 1 + req.context_lines).min(lines.len());
+            output.matches.push(SearchMatch {
+                path: path.clone(),
+                line_number: index + 1,
+                line: clip(line),
+                before: context(&lines[before..index]),
+                after: context(&lines[index + 1..after]),
+            });
+        }
+    }
+    Ok(output)
+}
+
+fn clip(line: &str) -> String {
+    match line.char_indices().nth(MAX_LINE_CHARS) {
+        Some((end, _)) => format!("{}…", &line[..end]),
+        None => line.to_string(),
+    }
+}
+
+/// Whether `path` is below one of `roots`, once symlinks are resolved.
+fn is_readable(path: &Path, roots: &[PathBuf]) -> bool {
+    roots.iter().any(|root| {
+        let root = dunce::canonicalize(root).unwrap_or_else(|_| root.clone());
+        path.starts_with(root)
+    })
+}
+
+/// The schema of a serialized [`SearchOutput`].
+fn search_output_schema() -> JsonSchema {
+    let string = |description: &str| JsonSchema::String {
+        description: Some(description.to_string()),
+    };
+    let number = |description: &str| JsonSchema::Number {
+        description: Some(description.to_string()),
+    };
+    let array = |items: JsonSchema, description: &str| JsonSchema::Array {
+        items: Box::new(items),
+        description: Some(description.to_string()),
+    };
+    let object = |properties: Vec<(&str, JsonSchema)>, required: &[&str]| JsonSchema::Object {
+        properties: properties
+            .into_iter()
+            .map(|(name, schema)| (name.to_string(), schema))
+            .collect(),
+        required: Some(required.iter().map(ToString::to_string).collect()),
+        additional_properties: Some(false.into()),
+    };
+    let lines = || JsonSchema::String { description: None };
+
+    let found = object(
+        vec![
+            (
+                "path",
+                string("The file, relative to the working directory when below it."),
+            ),
+            ("line_number", number("The matching line's number, from 1.")),
+            ("line", string("The matching line.")),
+            ("before", array(lines(), "Context lines before the match.")),
+            ("after", array(lines(), "Context lines after the match.")),
+        ],
+        &["path", "line_number", "line"],
+    );
+    object(
+        vec![
+            ("matches", array(found, "Matching lines, by file and line.")),
+            (
+                "files_searched",
+                number("How many text files were searched."),
+            ),
+            (
+                "truncated",
+                JsonSchema::Boolean {
+                    description: Some("Whether the search stopped at `max_matches`.".to_string()),
+                },
+            ),
+        ],
+        &["matches", "files_searched", "truncated"],
+    )
+}
+
+#[derive(Default)]
+pub struct SearchRuntime;
+
+#[derive(Serialize, Clone, Debug, Eq, PartialEq, Hash)]
+pub(crate) struct ApprovalKey {
+    root: PathBuf,
+}
+
+impl SearchRuntime {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Sandboxable for SearchRuntime {
+    fn sandbox_preference(&self) -> SandboxablePreference {
+        SandboxablePreference::Forbid
+    }
+    fn escalate_on_failure(&self, _reason: ExitReason) -> bool {
+        false
+    }
+}
+
+impl Approvable<SearchRequest> for SearchRuntime {
+    type ApprovalKey = ApprovalKey;
+
+    fn approval_key(&self, req: &SearchRequest) -> Self::ApprovalKey {
+        ApprovalKey {
+            root: normalize_for_comparison(&req.root),
+        }
+    }
+
+    fn start_approval_async<'a>(
+        &'a mut self,
+        req: &'a SearchRequest,
+        ctx: ApprovalCtx<'a>,
+    ) -> BoxFuture<'a, ReviewDecision> {
+        let key = self.approval_key(req);
+        let command = display_command(req);
+        let cwd = req.cwd.clone();
+        let reason = ctx.retry_reason.clone();
+        let risk = ctx.risk.clone();
+        let session = ctx.session;
+        let turn = ctx.turn;
+        let call_id = ctx.call_id.to_string();
+        Box::pin(async move {
+            with_cached_approval(&session.services, turn, key, move || async move {
+                session
+                    .request_command_approval(turn, call_id, command, cwd, reason, risk)
+                    .await
+            })
+            .await
+        })
+    }
+
+    fn approval_requirement(&self, _req: &SearchRequest) -> Option<ApprovalRequirement> {
+        // A search only reads below the readable roots.
+        Some(ApprovalRequirement::Skip {
+            bypass_sandbox: false,
+        })
+    }
+}
+
+impl ToolRuntime<SearchRequest, SearchOutput> for SearchRuntime {
+    async fn run(
+        &mut self,
+        req: &SearchRequest,
+        _attempt: &SandboxAttempt<'_>,
+        _ctx: &ToolCtx<'_>,
+    ) -> Result<SearchOutput, ToolError> {
+        let root = dunce::canonicalize(&req.root).map_err(|err| {
+            ToolError::Rejected(format!("cannot search {}: {err}", req.root.display()))
+        })?;
+        if !is_readable(&root, &req.readable_roots) {
+            return Err(ToolError::Rejected(format!(
+                "{} is outside the directories the sandbox allows searching",
+                req.root.display()
+            )));
+        }
+        let req = SearchRequest {
+            root,
+            cwd: dunce::canonicalize(&req.cwd).unwrap_or_else(|_| req.cwd.clone()),
+            ..req.clone()
+        };
+        run_blocking(move || search(&req))
+            .await
+            .map_err(|err| ToolError::Rejected(format!("search failed: {err}")))?
+            .map_err(ToolError::Rejected)
+    }
+
+    fn output_schema(&self) -> Option<JsonSchema> {
+        Some(search_output_schema())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn request(root: &Path, pattern: &str) -> SearchRequest {
+        SearchRequest {
+            pattern: pattern.to_string(),
+            literal: false,
+            case_insensitive: false,
+            include: Vec::new(),
+            exclude: Vec::new(),
+            context_lines: 0,
+            max_matches: DEFAULT_MAX_MATCHES,
+            root: root.to_path_buf(),
+            cwd: root.to_path_buf(),
+            readable_roots: vec![root.to_path_buf()],
+        }
+    }
+
+    #[test]
+    fn finds_matches_with_context_in_included_files() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("src/gen")).expect("mkdir");
+        std::fs::write(root.join("src/lib.rs"), "fn a() {\n    Needle(1);\n}\n").expect("write");
+        std::fs::write(root.join("src/gen/out.rs"), "needle(\n").expect("write");
+        std::fs::write(root.join("notes.txt"), "needle(\n").expect("write");
+        std::fs::write(root.join("src/data.rs"), b"needle(\0").expect("write");
+
+        let req = SearchRequest {
+            literal: true,
+            case_insensitive: true,
+            include: vec!["*.rs".to_string()],
+            exclude: vec!["gen/".to_string()],
+            context_lines: 1,
+            ..request(root, "needle(")
+        };
+        assert_eq!(
+            search(&req),
+            Ok(SearchOutput {
+                matches: vec![SearchMatch {
+                    path: "src/lib.rs".to_string(),
+                    line_number: 2,
+                    line: "    Needle(1);".to_string(),
+                    before: vec!["fn a() {".to_string()],
+                    after: vec!["}".to_string()],
+                }],
+                files_searched: 1,
+                truncated: false,
+            })
+        );
+    }
+
+    #[test]
+    fn stops_at_the_match_cap() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        std::fs::write(dir.path().join("a.txt"), "x1\nx2\nx3\n").expect("write");
+
+        let req = SearchRequest {
+            max_matches: 2,
+            ..request(dir.path(), r"x\d")
+        };
+        let output = search(&req).expect("search");
+        assert_eq!(
+            (output.matches.len(), output.truncated),
+            (2, true),
+            "{output:?}"
+        );
+        let err = search(&request(dir.path(), "(")).expect_err("invalid pattern");
+        assert!(err.starts_with("invalid pattern: "), "{err}");
+    }
+}
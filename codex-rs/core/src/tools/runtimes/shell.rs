@@ -4,7 +4,16 @@ Runtime: shell
 Executes shell requests under the orchestrator: asks for approval when needed,
 builds a CommandSpec, and runs it under the current SandboxAttempt.
 */
+use crate::environment_diff::EnvironmentSnapshot;
 use crate::exec::ExecToolCallOutput;
+use crate::exec::ExitReason;
+use crate::exec::SandboxType;
+use crate::features::Feature;
+use crate::quarantine::files_created_outside;
+use crate::shell_profile::expanded_command_line;
+use crate::shell_profile::runs_in_login_shell;
+use crate::tools::approval_key::canonical_command;
+use crate::tools::approval_key::trust_prefix;
 use crate::tools::sandboxing::Approvable;
 use crate::tools::sandboxing::ApprovalCtx;
 use crate::tools::sandboxing::ApprovalRequirement;
@@ -18,19 +27,39 @@ use crate::tools::sandboxing::ToolCtx;
 use crate::tools::sandboxing::ToolError;
 use crate::tools::sandboxing::ToolRuntime;
 use crate::tools::sandboxing::with_cached_approval;
+use codex_protocol::paths::normalize_for_comparison;
+use codex_protocol::protocol::CommandExpansion;
+use codex_protocol::protocol::EnvironmentChangedEvent;
+use codex_protocol::protocol::EventMsg;
+use codex_protocol::protocol::NetworkAccess;
 use codex_protocol::protocol::ReviewDecision;
+use codex_protocol::protocol::SandboxPolicy;
 use futures::future::BoxFuture;
+use std::collections::HashMap;
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::SystemTime;
 
 #[derive(Clone, Debug)]
 pub struct ShellRequest {
     pub command: Vec<String>,
     pub cwd: PathBuf,
     pub timeout_ms: Option<u64>,
-    pub env: std::collections::HashMap<String, String>,
+    /// Shared so that copies of the request, such as the steps of a chain
+    /// or a retry with overrides, do not copy the environment.
+    pub env: Arc<HashMap<String, String>>,
     pub with_escalated_permissions: Option<bool>,
     pub justification: Option<String>,
+    /// Start the command as a background job instead of waiting for it;
+    /// `timeout_ms` does not apply. See [`crate::background_jobs`].
+    pub background: bool,
+    /// Network access the command declared in place of the sandbox
+    /// policy's; see [`crate::sandboxing::network`].
+    pub network: Option<NetworkAccess>,
     pub approval_requirement: ApprovalRequirement,
+    /// What the command's name stands for in the login shell it runs in;
+    /// see [`crate::shell_profile`].
+    pub expansions: Vec<CommandExpansion>,
 }
 
 impl ProvidesSandboxRetryData for ShellRequest {
@@ -38,6 +67,8 @@ impl ProvidesSandboxRetryData for ShellRequest {
         Some(SandboxRetryData {
             command: self.command.clone(),
             cwd: self.cwd.clone(),
+            env: HashMap::clone(&self.env),
+            denials: Vec::new(),
         })
     }
 }
@@ -50,6 +81,7 @@ pub(crate) struct ApprovalKey {
     command: Vec<String>,
     cwd: PathBuf,
     escalated: bool,
+    network: Option<NetworkAccess>,
 }
 
 impl ShellRuntime {
@@ -62,8 +94,8 @@ impl Sandboxable for ShellRuntime {
     fn sandbox_preference(&self) -> SandboxablePreference {
         SandboxablePreference::Auto
     }
-    fn escalate_on_failure(&self) -> bool {
-        true
+    fn escalate_on_failure(&self, reason: ExitReason) -> bool {
+        reason == ExitReason::SandboxDenied
     }
 }
 
@@ -72,9 +104,10 @@ impl Approvable<ShellRequest> for ShellRuntime {
 
     fn approval_key(&self, req: &ShellRequest) -> Self::ApprovalKey {
         ApprovalKey {
-            command: req.command.clone(),
-            cwd: req.cwd.clone(),
+            command: canonical_command(&req.command, &req.cwd),
+            cwd: normalize_for_comparison(&req.cwd),
             escalated: req.with_escalated_permissions.unwrap_or(false),
+            network: req.network.clone(),
         }
     }
 
@@ -84,6 +117,7 @@ impl Approvable<ShellRequest> for ShellRuntime {
         ctx: ApprovalCtx<'a>,
     ) -> BoxFuture<'a, ReviewDecision> {
         let key = self.approval_key(req);
+        let trust_prefix = trust_prefix(&key.command);
         let command = req.command.clone();
         let cwd = req.cwd.clone();
         let reason = ctx
@@ -91,13 +125,23 @@ impl Approvable<ShellRequest> for ShellRuntime {
             .clone()
             .or_else(|| req.justification.clone());
         let risk = ctx.risk.clone();
+        let expansions = req.expansions.clone();
         let session = ctx.session;
         let turn = ctx.turn;
         let call_id = ctx.call_id.to_string();
         Box::pin(async move {
-            with_cached_approval(&session.services, key, move || async move {
+            with_cached_approval(&session.services, turn, key, move || async move {
                 session
-                    .request_command_approval(turn, call_id, command, cwd, reason, risk)
+                    .request_command_approval_with_expansions(
+                        turn,
+                        call_id,
+                        command,
+                        cwd,
+                        reason,
+                        risk,
+                        expansions,
+                        trust_prefix,
+                    )
                     .await
             })
             .await
@@ -131,7 +175,105 @@ impl ToolRuntime<ShellRequest, ExecToolCallOutput> for ShellRuntime {
         attempt: &SandboxAttempt<'_>,
         ctx: &ToolCtx<'_>,
     ) -> Result<ExecToolCallOutput, ToolError> {
+        // With `sandbox_backend = "container"`, sandboxed commands run in the
+        // session's container in place of the platform sandbox, with the
+        // mounts this attempt's policy calls for.
+        let container = match &ctx.session.services.container {
+            Some(containers)
+                if !attempt.escalated
+                    && !matches!(attempt.policy, SandboxPolicy::DangerFullAccess) =>
+            {
+                let container = containers
+                    .get(attempt.sandbox_cwd, attempt.policy, attempt.scratch_dir)
+                    .await
+                    .map_err(|err| {
+                        ToolError::Rejected(format!("failed to start the sandbox container: {err}"))
+                    })?;
+                Some(container)
+            }
+            _ => None,
+        };
+        let in_container = container.as_deref().map(|container| SandboxAttempt {
+            sandbox: SandboxType::Container,
+            container: Some(container),
+            ..*attempt
+        });
+        let attempt = in_container.as_ref().unwrap_or(attempt);
+        // With `shell_profile.run_commands` the session's shell can be fish
+        // or nushell. A command that does not start a shell itself runs
+        // through it, so the user's aliases and functions apply. The
+        // container has its own shells.
+        let shell = ctx.session.user_shell();
+        let config = ctx.turn.client.config();
+        let in_login_shell;
+        let req = if container.is_none() && runs_in_login_shell(shell, &req.command) {
+            let line = if config.shell_profile.run_expanded {
+                expanded_command_line(shell, &req.command, &req.expansions)
+            } else {
+                shell.join_args(&req.command)
+            };
+            in_login_shell = ShellRequest {
+                command: shell.derive_exec_args(&line, true),
+                ..req.clone()
+            };
+            &in_login_shell
+        } else {
+            req
+        };
         let executor = ctx.session.services.tool_executor.clone();
-        executor.run_shell(req, attempt, ctx).await
+        let quarantine = attempt.escalated && config.features.enabled(Feature::Quarantine);
+        let environment_diff =
+            attempt.escalated && config.features.enabled(Feature::EnvironmentDiff);
+        if !quarantine && !environment_diff {
+            return executor.run_shell(req, attempt, ctx).await;
+        }
+
+        let path = req.env.get("PATH").cloned();
+        let before = if environment_diff {
+            Some(EnvironmentSnapshot::take(path.clone()).await)
+        } else {
+            None
+        };
+        let started = SystemTime::now();
+        let result = executor.run_shell(req, attempt, ctx).await;
+
+        if let Some(before) = before {
+            let changes = before.diff(&EnvironmentSnapshot::take(path).await);
+            if !changes.is_empty() {
+                let event = EnvironmentChangedEvent {
+                    call_id: ctx.call_id.clone(),
+                    command: req.command.clone(),
+                    changes,
+                };
+                ctx.session
+                    .send_event(ctx.turn, EventMsg::EnvironmentChanged(event))
+                    .await;
+            }
+        }
+        if !quarantine {
+            return result;
+        }
+        // Whatever the sandbox would have let the command write is not a
+        // dropping of the escalation.
+        let mut excluded: Vec<PathBuf> = attempt
+            .policy
+            .get_writable_roots_with_cwd(attempt.sandbox_cwd)
+            .into_iter()
+            .map(|root| root.root)
+            .collect();
+        excluded.extend([
+            attempt.sandbox_cwd.to_path_buf(),
+            req.cwd.clone(),
+            config.codex_home.clone(),
+        ]);
+        let findings = files_created_outside(excluded, started).await;
+        if !findings.is_empty() {
+            ctx.session.quarantine_files(ctx.turn, findings).await;
+        }
+        result
+    }
+
+    fn exit_code(&self, out: &ExecToolCallOutput) -> Option<i32> {
+        Some(out.exit_code)
     }
 }
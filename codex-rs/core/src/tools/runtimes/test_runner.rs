@@ -0,0 +1,393 @@
+/*
+Runtime: test runner
+
+Runs a test command under the orchestrator. In repeat (stress) mode the same
+command is executed several times, optionally concurrently and with a distinct
+seed per run, and per-test outcomes are aggregated so intermittent failures can
+be reported as suspected flakes instead of being mistaken for real regressions.
+*/
+use crate::exec::ExecToolCallOutput;
+use crate::tools::runtimes::CommandApprovalKey;
+use crate::tools::runtimes::command_retry_data;
+use crate::tools::runtimes::exec_via_executor;
+use crate::tools::runtimes::request_command_approval;
+use crate::tools::sandboxing::Approvable;
+use crate::tools::sandboxing::ApprovalCtx;
+use crate::tools::sandboxing::ApprovalRequirement;
+use crate::tools::sandboxing::ProvidesSandboxRetryData;
+use crate::tools::sandboxing::SandboxAttempt;
+use crate::tools::sandboxing::SandboxRetryData;
+use crate::tools::sandboxing::Sandboxable;
+use crate::tools::sandboxing::SandboxablePreference;
+use crate::tools::sandboxing::ToolCtx;
+use crate::tools::sandboxing::ToolError;
+use crate::tools::sandboxing::ToolRuntime;
+use codex_protocol::protocol::ReviewDecision;
+use futures::StreamExt;
+use futures::future::BoxFuture;
+use futures::stream;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+
+/// Upper bound on repetitions for a single call.
+pub(crate) const MAX_REPEAT: i64 = 100;
+
+#[derive(Clone, Debug)]
+pub struct TestRunRequest {
+    pub command: Vec<String>,
+    pub cwd: PathBuf,
+    pub timeout_ms: Option<u64>,
+    pub env: HashMap<String, String>,
+    /// Number of times to run the command (at least 1).
+    pub repeat: i64,
+    /// Run repetitions concurrently instead of one after another.
+    pub parallel: bool,
+    /// When set, each run receives `<seed_env>=<base_seed + run index>`.
+    pub seed_env: Option<String>,
+    pub base_seed: i64,
+    pub approval_requirement: ApprovalRequirement,
+}
+
+impl ProvidesSandboxRetryData for TestRunRequest {
+    fn sandbox_retry_data(&self) -> Option<SandboxRetryData> {
+        command_retry_data(&self.command, &self.cwd, &self.env)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct TestRun {
+    pub seed: Option<i64>,
+    pub output: ExecToolCallOutput,
+}
+
+#[derive(Default)]
+pub struct TestRunnerRuntime;
+
+impl TestRunnerRuntime {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Sandboxable for TestRunnerRuntime {
+    fn sandbox_preference(&self) -> SandboxablePreference {
+        SandboxablePreference::Auto
+    }
+}
+
+impl Approvable<TestRunRequest> for TestRunnerRuntime {
+    type ApprovalKey = CommandApprovalKey;
+
+    fn approval_key(&self, req: &TestRunRequest) -> Self::ApprovalKey {
+        CommandApprovalKey::new(&req.command, &req.cwd)
+    }
+
+    fn start_approval_async<'a>(
+        &'a mut self,
+        req: &'a TestRunRequest,
+        ctx: ApprovalCtx<'a>,
+    ) -> BoxFuture<'a, ReviewDecision> {
+        let reason = ctx.retry_reason.clone();
+        request_command_approval(self.approval_key(req), &req.command, &req.cwd, reason, ctx)
+    }
+
+    fn approval_requirement(&self, req: &TestRunRequest) -> Option<ApprovalRequirement> {
+        Some(req.approval_requirement.clone())
+    }
+}
+
+impl ToolRuntime<TestRunRequest, Vec<TestRun>> for TestRunnerRuntime {
+    async fn run(
+        &mut self,
+        req: &TestRunRequest,
+        attempt: &SandboxAttempt<'_>,
+        ctx: &ToolCtx<'_>,
+    ) -> Result<Vec<TestRun>, ToolError> {
+        let repeat = req.repeat.clamp(1, MAX_REPEAT);
+        let plans: Vec<(Option<i64>, HashMap<String, String>)> = (0..repeat)
+            .map(|index| {
+                let mut env = req.env.clone();
+                let seed = req.seed_env.as_ref().map(|name| {
+                    let seed = req.base_seed + index;
+                    env.insert(name.clone(), seed.to_string());
+                    seed
+                });
+                (seed, env)
+            })
+            .collect();
+
+        if req.parallel {
+            let runs = plans.into_iter().map(|(seed, env)| async move {
+                exec_via_executor(
+                    req.command.clone(),
+                    &req.cwd,
+                    &env,
+                    req.timeout_ms,
+                    attempt,
+                    ctx,
+                )
+                .await
+                .map(|output| TestRun { seed, output })
+            });
+            // Keep at most one run per CPU in flight; `buffered` still yields
+            // them in seed order.
+            let limit = std::thread::available_parallelism().map_or(1, NonZeroUsize::get);
+            stream::iter(runs)
+                .buffered(limit)
+                .collect::<Vec<_>>()
+                .await
+                .into_iter()
+                .collect()
+        } else {
+            let mut runs = Vec::with_capacity(plans.len());
+            for (seed, env) in &plans {
+                let output = exec_via_executor(
+                    req.command.clone(),
+                    &req.cwd,
+                    env,
+                    req.timeout_ms,
+                    attempt,
+                    ctx,
+                )
+                .await?;
+                runs.push(TestRun {
+                    seed: *seed,
+                    output,
+                });
+            }
+            Ok(runs)
+        }
+    }
+}
+
+/// Outcome of a single test case as reported by the test harness.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum TestOutcome {
+    Passed,
+    Failed,
+}
+
+/// Extract per-test outcomes from libtest (`cargo test`), pytest (`-v` or
+/// `-rA`) and go test (`-v`) output. Lines that do not look like a test result
+/// are ignored, so unknown harnesses simply yield no per-test data.
+pub(crate) fn parse_test_outcomes(output: &str) -> Vec<(String, TestOutcome)> {
+    let mut outcomes = Vec::new();
+    for line in output.lines() {
+        let line = line.trim();
+        // libtest: `test path::to::name ... ok`
+        if let Some(rest) = line.strip_prefix("test ")
+            && let Some((name, result)) = rest.split_once(" ... ")
+        {
+            let outcome = match result.trim() {
+                "ok" => Some(TestOutcome::Passed),
+                "FAILED" => Some(TestOutcome::Failed),
+                _ => None,
+            };
+            if let Some(outcome) = outcome {
+                outcomes.push((name.trim().to_string(), outcome));
+            }
+            continue;
+        }
+        // go test -v: `--- PASS: TestName (0.00s)`
+        if let Some(rest) = line.strip_prefix("--- ") {
+            let parsed = rest
+                .strip_prefix("PASS: ")
+                .map(|name| (name, TestOutcome::Passed))
+                .or_else(|| {
+                    rest.strip_prefix("FAIL: ")
+                        .map(|name| (name, TestOutcome::Failed))
+                });
+            if let Some((name, outcome)) = parsed
+                && let Some(name) = name.split_whitespace().next()
+            {
+                outcomes.push((name.to_string(), outcome));
+            }
+            continue;
+        }
+        let mut words = line.split_whitespace();
+        let (Some(first), Some(second)) = (words.next(), words.next()) else {
+            continue;
+        };
+        // pytest -rA summary: `PASSED tests/test_x.py::test_y`
+        // pytest -v: `tests/test_x.py::test_y PASSED [ 50%]`
+        let parsed = match (pytest_outcome(first), pytest_outcome(second)) {
+            (Some(outcome), _) if second.contains("::") => Some((second, outcome)),
+            (_, Some(outcome)) if first.contains("::") => Some((first, outcome)),
+            _ => None,
+        };
+        if let Some((name, outcome)) = parsed {
+            outcomes.push((name.to_string(), outcome));
+        }
+    }
+    outcomes
+}
+
+fn pytest_outcome(word: &str) -> Option<TestOutcome> {
+    match word {
+        "PASSED" => Some(TestOutcome::Passed),
+        "FAILED" | "ERROR" => Some(TestOutcome::Failed),
+        _ => None,
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct TestStats {
+    pub name: String,
+    pub passed: i64,
+    pub failed: i64,
+    pub pass_rate: f64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct RepeatSummary {
+    pub runs: i64,
+    pub passed_runs: i64,
+    pub failed_runs: i64,
+    pub pass_rate: f64,
+    /// Seeds of the runs that failed, when seeds were assigned.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub failing_seeds: Vec<i64>,
+    /// Tests that both passed and failed across runs.
+    pub suspected_flakes: Vec<TestStats>,
+    /// Tests that failed in every run they were seen in.
+    pub consistent_failures: Vec<String>,
+}
+
+/// Aggregate repeated runs into pass rates and flake suspects.
+pub(crate) fn summarize_runs(runs: &[TestRun]) -> RepeatSummary {
+    let mut per_test: BTreeMap<String, (i64, i64)> = BTreeMap::new();
+    let mut passed_runs = 0;
+    let mut failing_seeds = Vec::new();
+
+    for run in runs {
+        if run.output.exit_code == 0 && !run.output.timed_out() {
+            passed_runs += 1;
+        } else if let Some(seed) = run.seed {
+            failing_seeds.push(seed);
+        }
+        for (name, outcome) in parse_test_outcomes(&run.output.aggregated_output.text) {
+            let entry = per_test.entry(name).or_insert((0, 0));
+            match outcome {
+                TestOutcome::Passed => entry.0 += 1,
+                TestOutcome::Failed => entry.1 += 1,
+            }
+        }
+    }
+
+    let mut suspected_flakes = Vec::new();
+    let mut consistent_failures = Vec::new();
+    for (name, (passed, failed)) in per_test {
+        if failed == 0 {
+            continue;
+        }
+        if passed == 0 {
+            consistent_failures.push(name);
+        } else {
+            suspected_flakes.push(TestStats {
+                name,
+                passed,
+                failed,
+                pass_rate: rate(passed, passed + failed),
+            });
+        }
+    }
+
+    let total = runs.len() as i64;
+    RepeatSummary {
+        runs: total,
+        passed_runs,
+        failed_runs: total - passed_runs,
+        pass_rate: rate(passed_runs, total),
+        failing_seeds,
+        suspected_flakes,
+        consistent_failures,
+    }
+}
+
+fn rate(passed: i64, total: i64) -> f64 {
+    if total == 0 {
+        return 0.0;
+    }
+    // Two decimal places are plenty for a pass rate.
+    ((passed as f64 / total as f64) * 100.0).round() / 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exec::ExitReason;
+    use crate::exec::StreamOutput;
+    use pretty_assertions::assert_eq;
+    use std::time::Duration;
+
+    fn run(seed: Option<i64>, exit_code: i32, text: &str) -> TestRun {
+        TestRun {
+            seed,
+            output: ExecToolCallOutput {
+                exit_code,
+                stdout: StreamOutput::new(text.to_string()),
+                stderr: StreamOutput::new(String::new()),
+                aggregated_output: StreamOutput::new(text.to_string()),
+                duration: Duration::from_millis(10),
+                full_output_path: None,
+                exit_reason: ExitReason::from_exit_code(exit_code),
+            },
+        }
+    }
+
+    #[test]
+    fn parses_libtest_pytest_and_go_output() {
+        let output = "\
+running 2 tests
+test a::works ... ok
+test a::racy ... FAILED
+test a::skipped ... ignored
+tests/test_x.py::test_y PASSED                [ 50%]
+FAILED tests/test_x.py::test_z - AssertionError
+--- PASS: TestGo (0.00s)
+--- FAIL: TestGoBad (0.01s)
+";
+        assert_eq!(
+            parse_test_outcomes(output),
+            vec![
+                ("a::works".to_string(), TestOutcome::Passed),
+                ("a::racy".to_string(), TestOutcome::Failed),
+                ("tests/test_x.py::test_y".to_string(), TestOutcome::Passed),
+                ("tests/test_x.py::test_z".to_string(), TestOutcome::Failed),
+                ("TestGo".to_string(), TestOutcome::Passed),
+                ("TestGoBad".to_string(), TestOutcome::Failed),
+            ]
+        );
+    }
+
+    #[test]
+    fn summarize_separates_flakes_from_consistent_failures() {
+        let runs = vec![
+            run(Some(1), 0, "test racy ... ok\ntest steady ... ok\n"),
+            run(Some(2), 101, "test racy ... FAILED\ntest steady ... ok\n"),
+            run(Some(3), 0, "test racy ... ok\ntest steady ... ok\n"),
+            run(Some(4), 101, "test racy ... ok\ntest broken ... FAILED\n"),
+        ];
+
+        assert_eq!(
+            summarize_runs(&runs),
+            RepeatSummary {
+                runs: 4,
+                passed_runs: 2,
+                failed_runs: 2,
+                pass_rate: 0.5,
+                failing_seeds: vec![2, 4],
+                suspected_flakes: vec![TestStats {
+                    name: "racy".to_string(),
+                    passed: 3,
+                    failed: 1,
+                    pass_rate: 0.75,
+                }],
+                consistent_failures: vec!["broken".to_string()],
+            }
+        );
+    }
+}
@@ -7,6 +7,8 @@ the session manager to spawn PTYs once an ExecEnv is prepared.
 use crate::error::CodexErr;
 use crate::error::SandboxErr;
 use crate::exec::ExecExpiration;
+use crate::exec::ExitReason;
+use crate::tools::approval_key::canonical_command;
 use crate::tools::runtimes::build_command_spec;
 use crate::tools::sandboxing::Approvable;
 use crate::tools::sandboxing::ApprovalCtx;
@@ -24,6 +26,7 @@ use crate::tools::sandboxing::with_cached_approval;
 use crate::unified_exec::UnifiedExecError;
 use crate::unified_exec::UnifiedExecSession;
 use crate::unified_exec::UnifiedExecSessionManager;
+use codex_protocol::paths::normalize_for_comparison;
 use codex_protocol::protocol::ReviewDecision;
 use futures::future::BoxFuture;
 use std::collections::HashMap;
@@ -44,6 +47,8 @@ impl ProvidesSandboxRetryData for UnifiedExecRequest {
         Some(SandboxRetryData {
             command: self.command.clone(),
             cwd: self.cwd.clone(),
+            env: self.env.clone(),
+            denials: Vec::new(),
         })
     }
 }
@@ -90,8 +95,12 @@ impl Sandboxable for UnifiedExecRuntime<'_> {
         SandboxablePreference::Auto
     }
 
-    fn escalate_on_failure(&self) -> bool {
-        true
+    fn escalate_on_failure(&self, reason: ExitReason) -> bool {
+        reason == ExitReason::SandboxDenied
+    }
+
+    fn wants_scratch_dir(&self) -> bool {
+        false
     }
 }
 
@@ -100,8 +109,8 @@ impl Approvable<UnifiedExecRequest> for UnifiedExecRuntime<'_> {
 
     fn approval_key(&self, req: &UnifiedExecRequest) -> Self::ApprovalKey {
         UnifiedExecApprovalKey {
-            command: req.command.clone(),
-            cwd: req.cwd.clone(),
+            command: canonical_command(&req.command, &req.cwd),
+            cwd: normalize_for_comparison(&req.cwd),
             escalated: req.with_escalated_permissions.unwrap_or(false),
         }
     }
@@ -123,7 +132,7 @@ impl Approvable<UnifiedExecRequest> for UnifiedExecRuntime<'_> {
             .or_else(|| req.justification.clone());
         let risk = ctx.risk.clone();
         Box::pin(async move {
-            with_cached_approval(&session.services, key, || async move {
+            with_cached_approval(&session.services, turn, key, || async move {
                 session
                     .request_command_approval(turn, call_id, command, cwd, reason, risk)
                     .await
@@ -157,12 +166,13 @@ impl<'a> ToolRuntime<UnifiedExecRequest, UnifiedExecSession> for UnifiedExecRunt
         &mut self,
         req: &UnifiedExecRequest,
         attempt: &SandboxAttempt<'_>,
-        _ctx: &ToolCtx<'_>,
+        ctx: &ToolCtx<'_>,
     ) -> Result<UnifiedExecSession, ToolError> {
         let spec = build_command_spec(
             &req.command,
             &req.cwd,
             &req.env,
+            &ctx.turn.client.config().command_env,
             ExecExpiration::DefaultTimeout,
             req.with_escalated_permissions,
             req.justification.clone(),
@@ -0,0 +1,427 @@
+/*
+Runtime: web_fetch
+
+Fetches a URL in-process with GET or HEAD, so the model does not need to run
+`curl` through the shell runtime. The fetch is a network request to one host:
+hosts in `web_fetch.allowed_domains` are fetched without asking, and any other
+host is approved like a command that asks for network access to that host, so
+the approval is remembered per host. Redirects are followed only to the
+approved host or to allowed domains. Bodies are read up to a size limit, HTML
+is converted to Markdown, and successful responses are cached for the session.
+*/
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::PoisonError;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::default_client::get_codex_user_agent;
+use crate::exec::ExitReason;
+use crate::html_markdown::html_to_markdown;
+use crate::tools::sandboxing::Approvable;
+use crate::tools::sandboxing::ApprovalCtx;
+use crate::tools::sandboxing::ApprovalRequirement;
+use crate::tools::sandboxing::ProvidesSandboxRetryData;
+use crate::tools::sandboxing::SandboxAttempt;
+use crate::tools::sandboxing::SandboxRetryData;
+use crate::tools::sandboxing::Sandboxable;
+use crate::tools::sandboxing::SandboxablePreference;
+use crate::tools::sandboxing::ToolCtx;
+use crate::tools::sandboxing::ToolError;
+use crate::tools::sandboxing::ToolRuntime;
+use crate::tools::sandboxing::with_cached_approval;
+use codex_protocol::protocol::NetworkAccess;
+use codex_protocol::protocol::ReviewDecision;
+use futures::future::BoxFuture;
+use reqwest::header::CONTENT_TYPE;
+use reqwest::header::LOCATION;
+use serde::Deserialize;
+use serde::Serialize;
+use url::Url;
+
+/// Default for `web_fetch.max_bytes`.
+pub(crate) const WEB_FETCH_MAX_BYTES: usize = 512 * 1024;
+/// Default for `web_fetch.cache_ttl_secs`.
+pub(crate) const WEB_FETCH_CACHE_TTL: Duration = Duration::from_secs(300);
+const FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+const MAX_REDIRECTS: usize = 5;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FetchMethod {
+    #[default]
+    Get,
+    Head,
+}
+
+impl FetchMethod {
+    fn as_str(self) -> &'static str {
+        match self {
+            FetchMethod::Get => "GET",
+            FetchMethod::Head => "HEAD",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct WebFetchRequest {
+    pub url: Url,
+    pub method: FetchMethod,
+    pub cwd: PathBuf,
+    /// The most body bytes read; longer bodies are cut short.
+    pub max_bytes: usize,
+    /// How long a response is reused; `None` disables the cache.
+    pub cache_ttl: Option<Duration>,
+    /// Hosts fetched, and redirected to, without approval.
+    pub allowed_domains: Vec<String>,
+    pub approval_requirement: ApprovalRequirement,
+}
+
+impl WebFetchRequest {
+    /// The command a fetch is shown as in approval prompts.
+    pub(crate) fn display_command(&self) -> Vec<String> {
+        vec![
+            "web_fetch".to_string(),
+            self.method.as_str().to_string(),
+            self.url.to_string(),
+        ]
+    }
+
+    /// The network access the fetch needs.
+    pub(crate) fn network(&self) -> NetworkAccess {
+        NetworkAccess::Allowlist {
+            hosts: vec![self.url.host_str().unwrap_or_default().to_string()],
+        }
+    }
+
+    /// Whether `host` is fetched without approval.
+    pub(crate) fn is_allowed(&self, host: &str) -> bool {
+        self.allowed_domains
+            .iter()
+            .any(|pattern| NetworkAccess::host_matches(pattern, host))
+    }
+
+    /// Whether a redirect to `url` stays within what was approved.
+    fn may_follow(&self, url: &Url) -> bool {
+        let host = url.host_str().unwrap_or_default();
+        matches!(url.scheme(), "http" | "https")
+            && (self.url.host_str() == Some(host) || self.is_allowed(host))
+    }
+}
+
+impl ProvidesSandboxRetryData for WebFetchRequest {
+    fn sandbox_retry_data(&self) -> Option<SandboxRetryData> {
+        Some(SandboxRetryData {
+            command: self.display_command(),
+            cwd: self.cwd.clone(),
+            env: HashMap::new(),
+            denials: Vec::new(),
+        })
+    }
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct WebFetchOutput {
+    /// The URL of the response, after redirects.
+    pub url: String,
+    pub status: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_length: Option<u64>,
+    /// The body, as Markdown for HTML; empty for HEAD.
+    pub content: String,
+    /// Whether `max_bytes` cut the body short.
+    pub truncated: bool,
+    /// Whether the response came from the session's cache.
+    pub cached: bool,
+}
+
+/// Responses fetched earlier in the session, by method and URL.
+#[derive(Default)]
+pub(crate) struct WebFetchCache {
+    responses: Mutex<HashMap<(FetchMethod, String), (Instant, WebFetchOutput)>>,
+}
+
+impl WebFetchCache {
+    fn get(&self, method: FetchMethod, url: &Url, ttl: Duration) -> Option<WebFetchOutput> {
+        let responses = self
+            .responses
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let (fetched, output) = responses.get(&(method, url.to_string()))?;
+        (fetched.elapsed() < ttl).then(|| output.clone())
+    }
+
+    fn insert(&self, method: FetchMethod, url: &Url, output: WebFetchOutput) {
+        let mut responses = self
+            .responses
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        responses.insert((method, url.to_string()), (Instant::now(), output));
+    }
+}
+
+#[derive(Default)]
+pub struct WebFetchRuntime;
+
+#[derive(Serialize, Clone, Debug, Eq, PartialEq, Hash)]
+pub(crate) struct ApprovalKey {
+    host: String,
+}
+
+impl WebFetchRuntime {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Sandboxable for WebFetchRuntime {
+    fn sandbox_preference(&self) -> SandboxablePreference {
+        SandboxablePreference::Forbid
+    }
+    fn escalate_on_failure(&self, _reason: ExitReason) -> bool {
+        false
+    }
+}
+
+impl Approvable<WebFetchRequest> for WebFetchRuntime {
+    type ApprovalKey = ApprovalKey;
+
+    fn approval_key(&self, req: &WebFetchRequest) -> Self::ApprovalKey {
+        ApprovalKey {
+            host: req.url.host_str().unwrap_or_default().to_ascii_lowercase(),
+        }
+    }
+
+    fn start_approval_async<'a>(
+        &'a mut self,
+        req: &'a WebFetchRequest,
+        ctx: ApprovalCtx<'a>,
+    ) -> BoxFuture<'a, ReviewDecision> {
+        let key = self.approval_key(req);
+        let command = req.display_command();
+        let cwd = req.cwd.clone();
+        let reason = ctx.retry_reason.clone();
+        let risk = ctx.risk.clone();
+        let session = ctx.session;
+        let turn = ctx.turn;
+        let call_id = ctx.call_id.to_string();
+        Box::pin(async move {
+            with_cached_approval(&session.services, turn, key, move || async move {
+                session
+                    .request_command_approval(turn, call_id, command, cwd, reason, risk)
+                    .await
+            })
+            .await
+        })
+    }
+
+    fn approval_requirement(&self, req: &WebFetchRequest) -> Option<ApprovalRequirement> {
+        Some(req.approval_requirement.clone())
+    }
+}
+
+impl ToolRuntime<WebFetchRequest, WebFetchOutput> for WebFetchRuntime {
+    async fn run(
+        &mut self,
+        req: &WebFetchRequest,
+        _attempt: &SandboxAttempt<'_>,
+        ctx: &ToolCtx<'_>,
+    ) -> Result<WebFetchOutput, ToolError> {
+        let cache = &ctx.session.services.web_fetch_cache;
+        if let Some(ttl) = req.cache_ttl
+            && let Some(output) = cache.get(req.method, &req.url, ttl)
+        {
+            return Ok(WebFetchOutput {
+                cached: true,
+                ..output
+            });
+        }
+        let output = fetch(req)
+            .await
+            .map_err(|err| ToolError::Rejected(format!("failed to fetch {}: {err}", req.url)))?;
+        if req.cache_ttl.is_some() && (200..300).contains(&output.status) {
+            cache.insert(req.method, &req.url, output.clone());
+        }
+        Ok(output)
+    }
+}
+
+async fn fetch(req: &WebFetchRequest) -> Result<WebFetchOutput, String> {
+    let client = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .timeout(FETCH_TIMEOUT)
+        .user_agent(get_codex_user_agent())
+        .build()
+        .map_err(|err| err.to_string())?;
+    let method = match req.method {
+        FetchMethod::Get => reqwest::Method::GET,
+        FetchMethod::Head => reqwest::Method::HEAD,
+    };
+    let mut url = req.url.clone();
+    for _ in 0..=MAX_REDIRECTS {
+        let response = client
+            .request(method.clone(), url.clone())
+            .send()
+            .await
+            .map_err(|err| err.to_string())?;
+        let location = response
+            .headers()
+            .get(LOCATION)
+            .and_then(|location| location.to_str().ok())
+            .and_then(|location| url.join(location).ok());
+        match location {
+            Some(location) if response.status().is_redirection() => {
+                if req.may_follow(&location) {
+                    url = location;
+                    continue;
+                }
+                return Ok(WebFetchOutput {
+                    url: url.to_string(),
+                    status: response.status().as_u16(),
+                    content_type: None,
+                    content_length: None,
+                    content: format!(
+                        "Redirected to {location}, on a host this fetch was not approved for; \
+                         fetch that URL to follow the redirect."
+                    ),
+                    truncated: false,
+                    cached: false,
+                });
+            }
+            _ => return read_response(response, req).await,
+        }
+    }
+    Err(format!("stopped after {MAX_REDIRECTS} redirects"))
+}
+
+async fn read_response(
+    mut response: reqwest::Response,
+    req: &WebFetchRequest,
+) -> Result<WebFetchOutput, String> {
+    let url = response.url().clone();
+    let status = response.status().as_u16();
+    let content_type = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    let content_length = response.content_length();
+    let mut body = Vec::new();
+    let mut truncated = false;
+    if req.method == FetchMethod::Get {
+        while let Some(chunk) = response.chunk().await.map_err(|err| err.to_string())? {
+            let room = req.max_bytes - body.len();
+            if chunk.len() > room {
+                body.extend_from_slice(&chunk[..room]);
+                truncated = true;
+                break;
+            }
+            body.extend_from_slice(&chunk);
+        }
+    }
+    Ok(WebFetchOutput {
+        content: render_body(&body, content_type.as_deref(), &url),
+        url: url.to_string(),
+        status,
+        content_type,
+        content_length,
+        truncated,
+        cached: false,
+    })
+}
+
+/// The body as the model sees it: HTML as Markdown, other text as is, and a
+/// placeholder for anything else.
+fn render_body(body: &[u8], content_type: Option<&str>, url: &Url) -> String {
+    if body.is_empty() {
+        return String::new();
+    }
+    let mime = content_type
+        .and_then(|content_type| content_type.split(';').next())
+        .map(|mime| mime.trim().to_ascii_lowercase());
+    let text = String::from_utf8_lossy(body);
+    let is_html = match mime.as_deref() {
+        Some(mime) => mime == "text/html" || mime == "application/xhtml+xml",
+        None => text.trim_start().get(..5).is_some_and(|start| {
+            start.eq_ignore_ascii_case("<!doc") || start.eq_ignore_ascii_case("<html")
+        }),
+    };
+    if is_html {
+        return html_to_markdown(&text, url);
+    }
+    let is_text = match mime.as_deref() {
+        Some(mime) => {
+            mime.starts_with("text/")
+                || ["json", "xml", "javascript", "yaml", "toml"]
+                    .iter()
+                    .any(|kind| mime.contains(kind))
+        }
+        None => std::str::from_utf8(body).is_ok(),
+    };
+    if is_text {
+        text.into_owned()
+    } else {
+        format!(
+            "[binary content: {}, {} bytes not shown]",
+            mime.as_deref().unwrap_or("unknown type"),
+            body.len()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn request(url: &str) -> WebFetchRequest {
+        WebFetchRequest {
+            url: Url::parse(url).expect("url"),
+            method: FetchMethod::Get,
+            cwd: PathBuf::from("/"),
+            max_bytes: WEB_FETCH_MAX_BYTES,
+            cache_ttl: None,
+            allowed_domains: vec!["*.rust-lang.org".to_string()],
+            approval_requirement: ApprovalRequirement::Skip {
+                bypass_sandbox: false,
+            },
+        }
+    }
+
+    #[test]
+    fn redirects_stay_on_approved_hosts() {
+        let req = request("https://example.com/a");
+        let follows = |url: &str| req.may_follow(&Url::parse(url).expect("url"));
+        assert_eq!(
+            [
+                follows("https://example.com/b"),
+                follows("https://doc.rust-lang.org/std"),
+                follows("https://evil.example.net/"),
+                follows("file:///etc/passwd"),
+            ],
+            [true, true, false, false]
+        );
+    }
+
+    #[test]
+    fn bodies_are_rendered_by_content_type() {
+        let url = Url::parse("https://example.com/").expect("url");
+        assert_eq!(
+            [
+                render_body(b"<html><p>Hi &amp; bye</p></html>", Some("text/html"), &url),
+                render_body(b"{\"a\": 1}", Some("application/json; charset=utf-8"), &url),
+                render_body(b"<!DOCTYPE html><h2>Title</h2>", None, &url),
+                render_body(&[0x89, b'P', b'N', b'G'], Some("image/png"), &url),
+            ],
+            [
+                "Hi & bye".to_string(),
+                "{\"a\": 1}".to_string(),
+                "## Title".to_string(),
+                "[binary content: image/png, 4 bytes not shown]".to_string(),
+            ]
+        );
+    }
+}
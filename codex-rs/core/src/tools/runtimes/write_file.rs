@@ -0,0 +1,343 @@
+/*
+Runtime: write_file
+
+Creates or overwrites a whole file in-process, so writing a file does not
+need a patch that spells out every line. The write is approved like a patch:
+the request shows the diff against the current contents. Nothing sandboxes an
+in-process write, so the handler checks the sandbox's writable roots itself
+and asks before writing anywhere else.
+*/
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Instant;
+
+use crate::audit::AuditAction;
+use crate::exec::ExecToolCallOutput;
+use crate::exec::ExitReason;
+use crate::exec::StreamOutput;
+use crate::protocol::FileChange;
+use crate::safety::is_path_writable;
+use crate::tools::sandboxing::Approvable;
+use crate::tools::sandboxing::ApprovalConditions;
+use crate::tools::sandboxing::ApprovalCtx;
+use crate::tools::sandboxing::ApprovalRequirement;
+use crate::tools::sandboxing::ProvidesSandboxRetryData;
+use crate::tools::sandboxing::SandboxAttempt;
+use crate::tools::sandboxing::SandboxRetryData;
+use crate::tools::sandboxing::Sandboxable;
+use crate::tools::sandboxing::SandboxablePreference;
+use crate::tools::sandboxing::ToolCtx;
+use crate::tools::sandboxing::ToolError;
+use crate::tools::sandboxing::ToolRuntime;
+use crate::tools::sandboxing::with_cached_approval_under;
+use codex_apply_patch::TextFormat;
+use codex_protocol::paths::normalize_for_comparison;
+use codex_protocol::protocol::AskForApproval;
+use codex_protocol::protocol::ReviewDecision;
+use codex_protocol::protocol::SandboxPolicy;
+use futures::future::BoxFuture;
+use similar::TextDiff;
+
+/// Unchanged lines shown around each hunk of the approval diff.
+const DIFF_CONTEXT_LINES: usize = 3;
+
+#[derive(Clone, Debug)]
+pub struct WriteFileRequest {
+    pub path: PathBuf,
+    pub cwd: PathBuf,
+    /// The new contents, with `\n` line endings.
+    pub contents: String,
+    /// Encoding, BOM and line endings to write in; an overwritten file keeps
+    /// its own.
+    pub format: TextFormat,
+    /// Whether the file exists; a new file's missing directories are created.
+    pub exists: bool,
+    /// The write as shown in the approval request and patch events.
+    pub changes: HashMap<PathBuf, FileChange>,
+    pub approval_requirement: ApprovalRequirement,
+}
+
+impl ProvidesSandboxRetryData for WriteFileRequest {
+    fn sandbox_retry_data(&self) -> Option<SandboxRetryData> {
+        None
+    }
+}
+
+#[derive(Default)]
+pub struct WriteFileRuntime;
+
+#[derive(serde::Serialize, Clone, Debug, Eq, PartialEq, Hash)]
+pub(crate) struct ApprovalKey {
+    path: PathBuf,
+    contents: String,
+}
+
+impl WriteFileRuntime {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Sandboxable for WriteFileRuntime {
+    fn sandbox_preference(&self) -> SandboxablePreference {
+        SandboxablePreference::Forbid
+    }
+    fn escalate_on_failure(&self, _reason: ExitReason) -> bool {
+        false
+    }
+}
+
+impl Approvable<WriteFileRequest> for WriteFileRuntime {
+    type ApprovalKey = ApprovalKey;
+
+    fn approval_key(&self, req: &WriteFileRequest) -> Self::ApprovalKey {
+        ApprovalKey {
+            path: normalize_for_comparison(&req.path),
+            contents: req.contents.clone(),
+        }
+    }
+
+    fn start_approval_async<'a>(
+        &'a mut self,
+        req: &'a WriteFileRequest,
+        ctx: ApprovalCtx<'a>,
+    ) -> BoxFuture<'a, ReviewDecision> {
+        let key = self.approval_key(req);
+        let changes = req.changes.clone();
+        let reason = ctx.retry_reason.clone();
+        let session = ctx.session;
+        let turn = ctx.turn;
+        let call_id = ctx.call_id.to_string();
+        // A session approval lapses once the file no longer has the contents
+        // the diff was rendered against.
+        let conditions =
+            ApprovalConditions::for_turn(turn).with_files(std::slice::from_ref(&req.path));
+        Box::pin(async move {
+            with_cached_approval_under(&session.services, conditions, key, move || async move {
+                let rx_approve = session
+                    .request_patch_approval(
+                        turn,
+                        call_id,
+                        changes,
+                        Vec::new(),
+                        Vec::new(),
+                        reason,
+                        None,
+                    )
+                    .await;
+                rx_approve.await.unwrap_or_default()
+            })
+            .await
+        })
+    }
+
+    fn approval_requirement(&self, req: &WriteFileRequest) -> Option<ApprovalRequirement> {
+        Some(req.approval_requirement.clone())
+    }
+}
+
+impl ToolRuntime<WriteFileRequest, ExecToolCallOutput> for WriteFileRuntime {
+    async fn run(
+        &mut self,
+        req: &WriteFileRequest,
+        _attempt: &SandboxAttempt<'_>,
+        _ctx: &ToolCtx<'_>,
+    ) -> Result<ExecToolCallOutput, ToolError> {
+        let started = Instant::now();
+        let bytes = req
+            .format
+            .encode(&req.contents)
+            .map_err(|err| ToolError::Rejected(format!("failed to encode file: {err}")))?;
+        let new_parent = req.path.parent().filter(|_| !req.exists);
+        if let Some(parent) = new_parent {
+            tokio::fs::create_dir_all(parent).await.map_err(|err| {
+                ToolError::Rejected(format!("failed to create {}: {err}", parent.display()))
+            })?;
+        }
+        tokio::fs::write(&req.path, bytes)
+            .await
+            .map_err(|err| ToolError::Rejected(format!("failed to write file: {err}")))?;
+
+        let status = if req.exists { 'M' } else { 'A' };
+        let stdout = format!(
+            "Success. Updated the following files:\n{status} {}\n",
+            req.path.display()
+        );
+        Ok(ExecToolCallOutput {
+            exit_code: 0,
+            stdout: StreamOutput::new(stdout.clone()),
+            stderr: StreamOutput::new(String::new()),
+            aggregated_output: StreamOutput::new(stdout),
+            duration: started.elapsed(),
+            full_output_path: None,
+            exit_reason: ExitReason::Success,
+        })
+    }
+
+    fn audit_action(&self, req: &WriteFileRequest) -> Option<AuditAction> {
+        Some(AuditAction::Patch {
+            files: vec![req.path.clone()],
+            cwd: req.cwd.clone(),
+        })
+    }
+
+    fn exit_code(&self, out: &ExecToolCallOutput) -> Option<i32> {
+        Some(out.exit_code)
+    }
+}
+
+/// The write of `new` over `old` (`None` for a file that does not exist) as a
+/// patch change.
+pub(crate) fn file_change(old: Option<&str>, new: &str) -> FileChange {
+    match old {
+        None => FileChange::Add {
+            content: new.to_string(),
+        },
+        Some(old) => FileChange::Update {
+            unified_diff: TextDiff::from_lines(old, new)
+                .unified_diff()
+                .context_radius(DIFF_CONTEXT_LINES)
+                .to_string(),
+            move_path: None,
+        },
+    }
+}
+
+/// Whether writing `path` needs approval. Writes inside the sandbox's
+/// writable roots follow the approval policy like patches do; anything else
+/// is asked about, or refused under `AskForApproval::Never`.
+pub(crate) fn write_approval_requirement(
+    path: &Path,
+    approval_policy: AskForApproval,
+    sandbox_policy: &SandboxPolicy,
+    cwd: &Path,
+) -> ApprovalRequirement {
+    // A symlink is written through, so its target must be writable too.
+    let target = std::fs::symlink_metadata(path)
+        .is_ok_and(|meta| meta.file_type().is_symlink())
+        .then(|| std::fs::canonicalize(path).ok())
+        .flatten();
+    let writable = is_path_writable(path, sandbox_policy, cwd)
+        && target.is_none_or(|target| is_path_writable(&target, sandbox_policy, cwd));
+    match approval_policy {
+        AskForApproval::UnlessTrusted => ApprovalRequirement::NeedsApproval { reason: None },
+        _ if writable => ApprovalRequirement::Skip {
+            bypass_sandbox: false,
+        },
+        AskForApproval::Never => ApprovalRequirement::Forbidden {
+            reason: "writing outside of the project; rejected by user approval settings"
+                .to_string(),
+        },
+        AskForApproval::OnFailure | AskForApproval::OnRequest => {
+            ApprovalRequirement::NeedsApproval {
+                reason: Some(format!(
+                    "{} is outside the sandbox's writable roots",
+                    path.display()
+                )),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    #[test]
+    fn new_files_are_additions_and_existing_files_are_diffed() {
+        assert_eq!(
+            file_change(None, "one\n"),
+            FileChange::Add {
+                content: "one\n".to_string()
+            }
+        );
+        assert_eq!(
+            file_change(Some("one\ntwo\n"), "one\n2\n"),
+            FileChange::Update {
+                unified_diff: "@@ -1,2 +1,2 @@\n one\n-two\n+2\n".to_string(),
+                move_path: None,
+            }
+        );
+    }
+
+    #[test]
+    fn writes_outside_the_writable_roots_need_approval() {
+        let tmp = TempDir::new().expect("tempdir");
+        let cwd = tmp.path().join("project");
+        let inside = cwd.join("src/main.rs");
+        let outside = tmp.path().join("elsewhere.txt");
+        let workspace = SandboxPolicy::WorkspaceWrite {
+            writable_roots: vec![],
+            network_access: false,
+            exclude_tmpdir_env_var: true,
+            exclude_slash_tmp: true,
+        };
+
+        assert_eq!(
+            write_approval_requirement(&inside, AskForApproval::OnRequest, &workspace, &cwd),
+            ApprovalRequirement::Skip {
+                bypass_sandbox: false
+            }
+        );
+        assert_eq!(
+            write_approval_requirement(&outside, AskForApproval::OnRequest, &workspace, &cwd),
+            ApprovalRequirement::NeedsApproval {
+                reason: Some(format!(
+                    "{} is outside the sandbox's writable roots",
+                    outside.display()
+                )),
+            }
+        );
+        assert_eq!(
+            write_approval_requirement(&outside, AskForApproval::Never, &workspace, &cwd),
+            ApprovalRequirement::Forbidden {
+                reason: "writing outside of the project; rejected by user approval settings"
+                    .to_string(),
+            }
+        );
+        assert_eq!(
+            write_approval_requirement(
+                &outside,
+                AskForApproval::Never,
+                &SandboxPolicy::DangerFullAccess,
+                &cwd
+            ),
+            ApprovalRequirement::Skip {
+                bypass_sandbox: false
+            }
+        );
+        assert_eq!(
+            write_approval_requirement(&inside, AskForApproval::UnlessTrusted, &workspace, &cwd),
+            ApprovalRequirement::NeedsApproval { reason: None }
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_out_of_the_writable_roots_need_approval() {
+        let tmp = TempDir::new().expect("tempdir");
+        let cwd = tmp.path().join("project");
+        std::fs::create_dir(&cwd).expect("mkdir");
+        let outside = tmp.path().join("elsewhere.txt");
+        std::fs::write(&outside, "").expect("write");
+        let link = cwd.join("link.txt");
+        std::os::unix::fs::symlink(&outside, &link).expect("symlink");
+        let workspace = SandboxPolicy::WorkspaceWrite {
+            writable_roots: vec![],
+            network_access: false,
+            exclude_tmpdir_env_var: true,
+            exclude_slash_tmp: true,
+        };
+
+        assert_eq!(
+            write_approval_requirement(&link, AskForApproval::Never, &workspace, &cwd),
+            ApprovalRequirement::Forbidden {
+                reason: "writing outside of the project; rejected by user approval settings"
+                    .to_string(),
+            }
+        );
+    }
+}
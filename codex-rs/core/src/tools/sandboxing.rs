@@ -4,16 +4,37 @@
 //! `ApprovalCtx`, `Approvable`) together with the sandbox orchestration traits
 //! and helpers (`Sandboxable`, `ToolRuntime`, `SandboxAttempt`, etc.).
 
+use crate::audit::AuditAction;
 use crate::codex::Session;
 use crate::codex::TurnContext;
+use crate::config::types::SandboxLimits;
+use crate::config::types::SandboxProfile;
+use crate::dirty_files::FileDigest;
+use crate::dirty_files::file_digest;
 use crate::error::CodexErr;
+use crate::exec::ExitReason;
+use crate::parse_command::shlex_join;
+use crate::project_cache::set_cache_env;
+use crate::protocol::ApprovalScope;
+use crate::protocol::ApprovalSeed;
+use crate::protocol::CachedApproval;
 use crate::protocol::SandboxCommandAssessment;
 use crate::protocol::SandboxPolicy;
+use crate::sandbox_profiles::apply_profile_env;
 use crate::sandboxing::CommandSpec;
 use crate::sandboxing::SandboxManager;
 use crate::sandboxing::SandboxTransformError;
+use crate::sandboxing::container::SessionContainer;
+use crate::sandboxing::denial::SandboxDenial;
 use crate::state::SessionServices;
+use crate::tools::approval_key::canonical_command;
+use crate::tools::approval_key::trust_prefix;
+use crate::tools::runtimes::unified_exec::UnifiedExecApprovalKey;
+use crate::tools::scratch::SCRATCH_DIR_ENV_VAR;
+use crate::tools::scratch::scratch_dir;
+use crate::tools::spec::JsonSchema;
 use codex_protocol::ConversationId;
+use codex_protocol::paths::normalize_for_comparison;
 use codex_protocol::protocol::AskForApproval;
 use codex_protocol::protocol::ReviewDecision;
 use std::collections::HashMap;
@@ -21,38 +42,312 @@ use std::fmt::Debug;
 use std::hash::Hash;
 use std::path::Path;
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Instant;
 
 use futures::Future;
 use futures::future::BoxFuture;
+use serde::Deserialize;
 use serde::Serialize;
+use serde_json::Map;
+use serde_json::Value;
+
+/// The circumstances an approval was given in. A cached decision is only
+/// replayed while they hold; once the policies, the working directory (and
+/// with it the project's trust level) or the files the action targets
+/// change, the user is asked again.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct ApprovalConditions {
+    pub(crate) approval_policy: AskForApproval,
+    pub(crate) sandbox_policy: SandboxPolicy,
+    pub(crate) cwd: PathBuf,
+    /// Contents of the target files, `None` for files that do not exist.
+    pub(crate) files: Vec<(PathBuf, Option<FileDigest>)>,
+}
+
+impl ApprovalConditions {
+    pub(crate) fn for_turn(turn: &TurnContext) -> Self {
+        Self {
+            approval_policy: turn.approval_policy,
+            sandbox_policy: turn.sandbox_policy.clone(),
+            cwd: turn.cwd.clone(),
+            files: Vec::new(),
+        }
+    }
+
+    /// Also require `paths` to keep their current contents.
+    pub(crate) fn with_files(mut self, paths: &[PathBuf]) -> Self {
+        self.files = paths
+            .iter()
+            .map(|path| (path.clone(), file_digest(path)))
+            .collect();
+        self
+    }
+}
+
+#[derive(Clone, Debug)]
+struct StoredApproval {
+    id: u64,
+    decision: ReviewDecision,
+    /// `None` for seeded approvals, which hold under any conditions.
+    conditions: Option<ApprovalConditions>,
+    granted_at: Instant,
+}
+
+/// A session approval for every command that starts with `prefix`.
+#[derive(Clone, Debug)]
+struct PrefixApproval {
+    prefix: Vec<String>,
+    /// The fields of the key it was given for other than the command, such
+    /// as the working directory; a key has to share them to be covered.
+    rest: Map<String, Value>,
+    approval: StoredApproval,
+}
 
 #[derive(Clone, Default, Debug)]
 pub(crate) struct ApprovalStore {
     // Store serialized keys for generic caching across requests.
-    map: HashMap<String, ReviewDecision>,
+    map: HashMap<String, StoredApproval>,
+    prefixes: Vec<PrefixApproval>,
+    next_id: u64,
 }
 
 impl ApprovalStore {
-    pub fn get<K>(&self, key: &K) -> Option<ReviewDecision>
+    /// The decision cached for `key`, if it was given under `conditions`.
+    /// A decision given under other conditions is stale and dropped.
+    pub fn get<K>(&mut self, key: &K, conditions: &ApprovalConditions) -> Option<ReviewDecision>
     where
         K: Serialize,
     {
         let s = serde_json::to_string(key).ok()?;
-        self.map.get(&s).cloned()
+        let Some(cached) = self.map.get(&s) else {
+            return self.get_by_prefix(&s, conditions);
+        };
+        if cached
+            .conditions
+            .as_ref()
+            .is_none_or(|given_under| given_under == conditions)
+        {
+            return Some(cached.decision);
+        }
+        self.map.remove(&s);
+        None
     }
 
-    pub fn put<K>(&mut self, key: K, value: ReviewDecision)
+    /// The prefix approval covering the serialized `key`, if any.
+    fn get_by_prefix(
+        &mut self,
+        key: &str,
+        conditions: &ApprovalConditions,
+    ) -> Option<ReviewDecision> {
+        let (command, rest) = split_command(key)?;
+        let index = self
+            .prefixes
+            .iter()
+            .position(|trusted| trusted.rest == rest && command.starts_with(&trusted.prefix))?;
+        let approval = &self.prefixes[index].approval;
+        if approval
+            .conditions
+            .as_ref()
+            .is_none_or(|given_under| given_under == conditions)
+        {
+            return Some(approval.decision);
+        }
+        self.prefixes.remove(index);
+        None
+    }
+
+    /// Approve, for the rest of the session, every command that starts with
+    /// the [`trust_prefix`] of the command in `key` and otherwise matches
+    /// it. Returns `false`, storing nothing, when `key` has no such prefix.
+    pub fn put_prefix<K>(&mut self, key: &K, conditions: ApprovalConditions) -> bool
+    where
+        K: Serialize,
+    {
+        let Some((command, rest)) = serde_json::to_string(key)
+            .ok()
+            .and_then(|key| split_command(&key))
+        else {
+            return false;
+        };
+        let Some(prefix) = trust_prefix(&command) else {
+            return false;
+        };
+        self.prefixes
+            .retain(|trusted| trusted.prefix != prefix || trusted.rest != rest);
+        self.next_id += 1;
+        self.prefixes.push(PrefixApproval {
+            prefix,
+            rest,
+            approval: StoredApproval {
+                id: self.next_id,
+                decision: ReviewDecision::ApprovedPrefixForSession,
+                conditions: Some(conditions),
+                granted_at: Instant::now(),
+            },
+        });
+        true
+    }
+
+    pub fn put<K>(&mut self, key: K, value: ReviewDecision, conditions: ApprovalConditions)
     where
         K: Serialize,
     {
-        if let Ok(s) = serde_json::to_string(&key) {
-            self.map.insert(s, value);
+        self.insert(&key, value, Some(conditions));
+    }
+
+    /// Approve `seed` for the rest of the session, whatever its conditions.
+    pub fn seed(&mut self, seed: ApprovalSeed) {
+        match seed {
+            // The key the shell and unified exec runtimes cache under.
+            ApprovalSeed::Exec {
+                command,
+                cwd,
+                escalated,
+            } => {
+                let key = UnifiedExecApprovalKey {
+                    command: canonical_command(&command, &cwd),
+                    cwd: normalize_for_comparison(&cwd),
+                    escalated,
+                };
+                self.insert(&key, ReviewDecision::ApprovedForSession, None);
+            }
+        }
+    }
+
+    /// Forget the approval listed with `id`. Returns whether there was one.
+    pub fn revoke(&mut self, id: &str) -> bool {
+        let before = self.map.len() + self.prefixes.len();
+        self.map.retain(|_, cached| cached.id.to_string() != id);
+        self.prefixes
+            .retain(|trusted| trusted.approval.id.to_string() != id);
+        self.map.len() + self.prefixes.len() != before
+    }
+
+    /// The cached approvals, oldest first.
+    pub fn list(&self) -> Vec<CachedApproval> {
+        let mut entries: Vec<(String, &StoredApproval)> = self
+            .map
+            .iter()
+            .map(|(key, cached)| (summarize_key(key), cached))
+            .chain(self.prefixes.iter().map(|trusted| {
+                let command = format!("{} *", shlex_join(&trusted.prefix));
+                (summarize_command(command, &trusted.rest), &trusted.approval)
+            }))
+            .collect();
+        entries.sort_by_key(|(_, cached)| cached.id);
+        entries
+            .into_iter()
+            .map(|(summary, cached)| CachedApproval {
+                id: cached.id.to_string(),
+                summary,
+                scope: match cached.conditions {
+                    Some(_) => ApprovalScope::Session,
+                    None => ApprovalScope::Seeded,
+                },
+                age_secs: cached.granted_at.elapsed().as_secs(),
+            })
+            .collect()
+    }
+
+    fn insert<K>(
+        &mut self,
+        key: &K,
+        decision: ReviewDecision,
+        conditions: Option<ApprovalConditions>,
+    ) where
+        K: Serialize,
+    {
+        if let Ok(s) = serde_json::to_string(key) {
+            self.next_id += 1;
+            self.map.insert(
+                s,
+                StoredApproval {
+                    id: self.next_id,
+                    decision,
+                    conditions,
+                    granted_at: Instant::now(),
+                },
+            );
         }
     }
 }
 
+/// A readable description of the serialized approval `key`.
+fn summarize_key(key: &str) -> String {
+    let Ok(Value::Object(fields)) = serde_json::from_str::<Value>(key) else {
+        return key.to_string();
+    };
+    let cwd = fields.get("cwd").and_then(Value::as_str);
+    if let Some(Value::Array(words)) = fields.get("command") {
+        let words: Vec<String> = words
+            .iter()
+            .filter_map(Value::as_str)
+            .map(str::to_string)
+            .collect();
+        return summarize_command(shlex_join(&words), &fields);
+    }
+    if let Some(patch) = fields.get("patch").and_then(Value::as_str) {
+        let files: Vec<&str> = patch
+            .lines()
+            .filter_map(|line| {
+                ["*** Add File: ", "*** Update File: ", "*** Delete File: "]
+                    .into_iter()
+                    .find_map(|prefix| line.strip_prefix(prefix))
+            })
+            .collect();
+        let mut summary = format!("patch to {}", files.join(", "));
+        if let Some(cwd) = cwd {
+            summary.push_str(&format!(" in {cwd}"));
+        }
+        return summary;
+    }
+    key.to_string()
+}
+
+/// `command` followed by where and how it runs according to the other
+/// `fields` of its key.
+fn summarize_command(mut command: String, fields: &Map<String, Value>) -> String {
+    if let Some(cwd) = fields.get("cwd").and_then(Value::as_str) {
+        command.push_str(&format!(" in {cwd}"));
+    }
+    if fields.get("escalated") == Some(&Value::Bool(true)) {
+        command.push_str(", outside the sandbox");
+    }
+    command
+}
+
+/// The command of the serialized approval `key` and its other fields, for
+/// keys that have a command.
+fn split_command(key: &str) -> Option<(Vec<String>, Map<String, Value>)> {
+    let Ok(Value::Object(mut fields)) = serde_json::from_str::<Value>(key) else {
+        return None;
+    };
+    let command = serde_json::from_value(fields.remove("command")?).ok()?;
+    Some((command, fields))
+}
+
+/// Replay the session approval cached for `key` if the circumstances of
+/// `turn` are those it was given in; otherwise ask with `fetch`.
 pub(crate) async fn with_cached_approval<K, F, Fut>(
     services: &SessionServices,
+    turn: &TurnContext,
+    key: K,
+    fetch: F,
+) -> ReviewDecision
+where
+    K: Serialize + Clone,
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = ReviewDecision>,
+{
+    with_cached_approval_under(services, ApprovalConditions::for_turn(turn), key, fetch).await
+}
+
+/// [`with_cached_approval`] with explicit conditions, for approvals that
+/// also depend on the state of files.
+pub(crate) async fn with_cached_approval_under<K, F, Fut>(
+    services: &SessionServices,
+    conditions: ApprovalConditions,
     key: K,
     fetch: F,
 ) -> ReviewDecision
@@ -62,17 +357,57 @@ where
     Fut: Future<Output = ReviewDecision>,
 {
     {
-        let store = services.tool_approvals.lock().await;
-        if let Some(decision) = store.get(&key) {
+        let mut store = services.tool_approvals.lock().await;
+        if let Some(decision) = store.get(&key, &conditions) {
             return decision;
         }
     }
 
+    // Approvals that also depend on file contents are never remembered
+    // across sessions.
+    let persistent = services
+        .persistent_approvals
+        .as_ref()
+        .filter(|_| conditions.files.is_empty())
+        .zip(serde_json::to_string(&key).ok());
+    if let Some((approvals, serialized)) = &persistent
+        && approvals.contains(serialized, &conditions).await
+    {
+        let mut store = services.tool_approvals.lock().await;
+        store.put(key, ReviewDecision::ApprovedForSession, conditions);
+        return ReviewDecision::ApprovedForSession;
+    }
+
+    // Calls running in parallel ask one at a time, and one that waited may
+    // find its answer was already given for another. With batched approvals
+    // they ask together instead.
+    let _prompt = if services.batch_approvals {
+        None
+    } else {
+        Some(services.approval_prompt.lock().await)
+    };
+    {
+        let mut store = services.tool_approvals.lock().await;
+        if let Some(decision) = store.get(&key, &conditions) {
+            return decision;
+        }
+    }
     let decision = fetch().await;
 
     if matches!(decision, ReviewDecision::ApprovedForSession) {
+        if let Some((approvals, serialized)) = &persistent {
+            approvals
+                .remember(serialized, summarize_key(serialized), &conditions)
+                .await;
+        }
+        let mut store = services.tool_approvals.lock().await;
+        store.put(key, ReviewDecision::ApprovedForSession, conditions);
+    } else if matches!(decision, ReviewDecision::ApprovedPrefixForSession) {
+        // Commands without a prefix worth trusting are remembered as is.
         let mut store = services.tool_approvals.lock().await;
-        store.put(key, ReviewDecision::ApprovedForSession);
+        if !store.put_prefix(&key, conditions.clone()) {
+            store.put(key, ReviewDecision::ApprovedForSession, conditions);
+        }
     }
 
     decision
@@ -174,13 +509,19 @@ pub(crate) enum SandboxablePreference {
     Auto,
     #[allow(dead_code)] // Will be used by later tools.
     Require,
-    #[allow(dead_code)] // Will be used by later tools.
     Forbid,
 }
 
 pub(crate) trait Sandboxable {
     fn sandbox_preference(&self) -> SandboxablePreference;
-    fn escalate_on_failure(&self) -> bool {
+    /// Whether a call the sandbox stopped for `reason` may be retried
+    /// without it.
+    fn escalate_on_failure(&self, reason: ExitReason) -> bool {
+        reason == ExitReason::SandboxDenied
+    }
+    /// Whether the call gets a scratch directory. Runtimes whose processes
+    /// outlive the call opt out, since the directory goes away with it.
+    fn wants_scratch_dir(&self) -> bool {
         true
     }
 }
@@ -196,6 +537,12 @@ impl<'a> ToolCtx<'a> {
     pub fn conversation_id(&self) -> ConversationId {
         self.session.conversation_id()
     }
+
+    /// The call's scratch directory, which exists while the orchestrator runs
+    /// the call and is removed afterwards.
+    pub fn scratch_dir(&self) -> PathBuf {
+        scratch_dir(self.conversation_id(), &self.call_id)
+    }
 }
 
 /// Captures the command metadata needed to re-run a tool request without sandboxing.
@@ -203,6 +550,12 @@ impl<'a> ToolCtx<'a> {
 pub(crate) struct SandboxRetryData {
     pub command: Vec<String>,
     pub cwd: PathBuf,
+    /// Environment the command runs with, for `approval_rules`.
+    pub env: HashMap<String, String>,
+    /// What the sandbox refused the command, filled in by the orchestrator
+    /// once a sandboxed attempt was denied; see
+    /// [`crate::sandboxing::denial::diagnose_denial`].
+    pub denials: Vec<SandboxDenial>,
 }
 
 pub(crate) trait ProvidesSandboxRetryData {
@@ -222,6 +575,30 @@ pub(crate) trait ToolRuntime<Req, Out>: Approvable<Req> + Sandboxable {
         attempt: &SandboxAttempt<'_>,
         ctx: &ToolCtx,
     ) -> Result<Out, ToolError>;
+
+    /// Adjust the request before it is retried without the sandbox. The
+    /// request is shared, so overrides go through `Arc::make_mut`, which
+    /// copies it only while another owner still holds it.
+    fn override_for_retry(&mut self, _req: &mut Arc<Req>) {}
+
+    /// The schema of the JSON the tool returns to the model, for runtimes
+    /// whose output is structured. With the `tool_output_schemas` feature it
+    /// is published with the tool's definition.
+    fn output_schema(&self) -> Option<JsonSchema> {
+        None
+    }
+
+    /// What the audit log records the call as running, for runtimes whose
+    /// request has no command line in [`ProvidesSandboxRetryData`]; see
+    /// [`crate::audit`].
+    fn audit_action(&self, _req: &Req) -> Option<AuditAction> {
+        None
+    }
+
+    /// The exit code of a run that returned `out`, for the audit log.
+    fn exit_code(&self, _out: &Out) -> Option<i32> {
+        None
+    }
 }
 
 pub struct SandboxAttempt<'a> {
@@ -230,19 +607,207 @@ pub struct SandboxAttempt<'a> {
     pub(crate) manager: &'a SandboxManager,
     pub(crate) sandbox_cwd: &'a Path,
     pub(crate) codex_linux_sandbox_exe: Option<&'a std::path::PathBuf>,
+    /// Whether this attempt runs without the sandbox the policy asks for,
+    /// after approval or at the tool's request.
+    pub(crate) escalated: bool,
+    /// Exported to the command as `CODEX_SCRATCH_DIR`.
+    pub(crate) scratch_dir: Option<&'a Path>,
+    /// The project cache, exported as `CODEX_CACHE_DIR`.
+    pub(crate) cache_dir: Option<&'a Path>,
+    /// `sandbox_limits`, unless the attempt runs outside the sandbox.
+    pub(crate) limits: Option<&'a SandboxLimits>,
+    /// The container a [`crate::exec::SandboxType::Container`] attempt runs in.
+    pub(crate) container: Option<&'a SessionContainer>,
+    /// The profile `sandbox_profile_rules` picked for the call, whose
+    /// environment settings apply to the command.
+    pub(crate) profile: Option<&'a SandboxProfile>,
 }
 
 impl<'a> SandboxAttempt<'a> {
     pub(crate) fn env_for(
         &self,
-        spec: CommandSpec,
+        mut spec: CommandSpec,
     ) -> Result<crate::sandboxing::ExecEnv, SandboxTransformError> {
-        self.manager.transform(
+        if let Some(dir) = self.scratch_dir {
+            spec.env.insert(
+                SCRATCH_DIR_ENV_VAR.to_string(),
+                dir.to_string_lossy().into_owned(),
+            );
+        }
+        if let Some(dir) = self.cache_dir {
+            set_cache_env(dir, &mut spec.env);
+        }
+        if let Some(profile) = self.profile {
+            apply_profile_env(profile, &mut spec.env);
+        }
+        let mut env = self.manager.transform(
             spec,
             self.policy,
             self.sandbox,
             self.sandbox_cwd,
             self.codex_linux_sandbox_exe,
-        )
+            self.container,
+        )?;
+        env.limits = self.limits.copied();
+        Ok(env)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::tempdir;
+
+    #[test]
+    fn approvals_lapse_when_their_conditions_change() {
+        let tmp = tempdir().expect("tmp");
+        let file = tmp.path().join("a.txt");
+        std::fs::write(&file, "one\n").expect("write");
+        let conditions = ApprovalConditions {
+            approval_policy: AskForApproval::OnRequest,
+            sandbox_policy: SandboxPolicy::new_read_only_policy(),
+            cwd: tmp.path().to_path_buf(),
+            files: Vec::new(),
+        }
+        .with_files(std::slice::from_ref(&file));
+        let mut store = ApprovalStore::default();
+
+        store.put(
+            "key",
+            ReviewDecision::ApprovedForSession,
+            conditions.clone(),
+        );
+        let full_access = ApprovalConditions {
+            sandbox_policy: SandboxPolicy::DangerFullAccess,
+            ..conditions.clone()
+        };
+        assert_eq!(
+            [
+                store.get(&"key", &conditions),
+                store.get(&"key", &full_access),
+                // The stale entry is gone for good.
+                store.get(&"key", &conditions),
+            ],
+            [Some(ReviewDecision::ApprovedForSession), None, None]
+        );
+
+        store.put(
+            "key",
+            ReviewDecision::ApprovedForSession,
+            conditions.clone(),
+        );
+        std::fs::write(&file, "two\n").expect("write");
+        let edited = conditions.with_files(std::slice::from_ref(&file));
+        assert_eq!(store.get(&"key", &edited), None);
+    }
+
+    #[test]
+    fn approvals_can_be_listed_seeded_and_revoked() {
+        let conditions = ApprovalConditions {
+            approval_policy: AskForApproval::OnRequest,
+            sandbox_policy: SandboxPolicy::new_read_only_policy(),
+            cwd: PathBuf::from("/repo"),
+            files: Vec::new(),
+        };
+        let mut store = ApprovalStore::default();
+        store.put(
+            serde_json::json!({
+                "patch": "*** Begin Patch\n*** Add File: a.txt\n+a\n*** End Patch",
+                "cwd": "/repo",
+            }),
+            ReviewDecision::ApprovedForSession,
+            conditions.clone(),
+        );
+        store.seed(ApprovalSeed::Exec {
+            command: vec!["cargo".to_string(), "test".to_string()],
+            cwd: PathBuf::from("/repo"),
+            escalated: true,
+        });
+
+        let listed: Vec<(String, String, ApprovalScope)> = store
+            .list()
+            .into_iter()
+            .map(|entry| (entry.id, entry.summary, entry.scope))
+            .collect();
+        assert_eq!(
+            listed,
+            vec![
+                (
+                    "1".to_string(),
+                    "patch to a.txt in /repo".to_string(),
+                    ApprovalScope::Session,
+                ),
+                (
+                    "2".to_string(),
+                    "cargo test in /repo, outside the sandbox".to_string(),
+                    ApprovalScope::Seeded,
+                ),
+            ]
+        );
+
+        // Seeded approvals hold whatever the conditions.
+        let seeded_key = UnifiedExecApprovalKey {
+            command: vec!["cargo".to_string(), "test".to_string()],
+            cwd: normalize_for_comparison(Path::new("/repo")),
+            escalated: true,
+        };
+        let full_access = ApprovalConditions {
+            sandbox_policy: SandboxPolicy::DangerFullAccess,
+            ..conditions
+        };
+        assert_eq!(
+            store.get(&seeded_key, &full_access),
+            Some(ReviewDecision::ApprovedForSession)
+        );
+
+        assert_eq!((store.revoke("2"), store.revoke("2")), (true, false));
+        assert_eq!(store.get(&seeded_key, &full_access), None);
+    }
+    #[test]
+    fn trusted_prefixes_cover_commands_that_start_with_them() {
+        let conditions = ApprovalConditions {
+            approval_policy: AskForApproval::OnRequest,
+            sandbox_policy: SandboxPolicy::new_read_only_policy(),
+            cwd: PathBuf::from("/repo"),
+            files: Vec::new(),
+        };
+        let key = |command: &[&str], cwd: &str| {
+            serde_json::json!({
+                "command": command,
+                "cwd": cwd,
+                "escalated": false,
+            })
+        };
+        let mut store = ApprovalStore::default();
+        assert!(store.put_prefix(
+            &key(&["cargo", "test", "-p", "core"], "/repo"),
+            conditions.clone()
+        ));
+        assert!(!store.put_prefix(
+            &key(&["sudo", "cargo", "test"], "/repo"),
+            conditions.clone()
+        ));
+
+        assert_eq!(
+            [
+                store.get(&key(&["cargo", "test", "--lib"], "/repo"), &conditions),
+                store.get(&key(&["cargo", "test"], "/elsewhere"), &conditions),
+                store.get(&key(&["cargo", "testx"], "/repo"), &conditions),
+            ],
+            [Some(ReviewDecision::ApprovedPrefixForSession), None, None]
+        );
+        let listed: Vec<String> = store
+            .list()
+            .into_iter()
+            .map(|entry| entry.summary)
+            .collect();
+        assert_eq!(listed, vec!["cargo test * in /repo".to_string()]);
+
+        assert!(store.revoke("1"));
+        assert_eq!(
+            store.get(&key(&["cargo", "test"], "/repo"), &conditions),
+            None
+        );
     }
 }
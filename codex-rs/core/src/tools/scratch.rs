@@ -0,0 +1,127 @@
+//! Per-call scratch directories.
+//!
+//! Each tool call run by the orchestrator gets a fresh directory under the
+//! system temp directory, named after the conversation and the call, that is
+//! removed with its contents once the call finishes. Runtimes find it with
+//! [`ToolCtx::scratch_dir`](crate::tools::sandboxing::ToolCtx::scratch_dir)
+//! and commands in `$CODEX_SCRATCH_DIR`. Under `workspace-write` the sandbox
+//! lets commands write there, so temporary files need not go into the
+//! workspace or a shared `/tmp`.
+
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_protocol::ConversationId;
+use codex_protocol::protocol::SandboxPolicy;
+
+/// Environment variable holding the call's scratch directory.
+pub const SCRATCH_DIR_ENV_VAR: &str = "CODEX_SCRATCH_DIR";
+
+const SCRATCH_ROOT: &str = "codex-scratch";
+
+/// Where the scratch directory of call `call_id` lives.
+pub(crate) fn scratch_dir(conversation_id: ConversationId, call_id: &str) -> PathBuf {
+    let call: String = call_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    std::env::temp_dir()
+        .join(SCRATCH_ROOT)
+        .join(conversation_id.to_string())
+        .join(call)
+}
+
+/// The scratch directory of a call in progress, removed when dropped.
+#[derive(Debug)]
+pub(crate) struct ScratchDir {
+    path: PathBuf,
+}
+
+impl ScratchDir {
+    pub(crate) fn create(path: PathBuf) -> io::Result<Self> {
+        std::fs::create_dir_all(&path)?;
+        Ok(Self { path })
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// `policy` with the scratch directory among its writable roots.
+    pub(crate) fn widen(&self, policy: &SandboxPolicy) -> SandboxPolicy {
+        with_writable_root(policy, &self.path)
+    }
+}
+
+/// `policy` with `dir` among its writable roots, when it has any.
+pub(crate) fn with_writable_root(policy: &SandboxPolicy, dir: &Path) -> SandboxPolicy {
+    let mut policy = policy.clone();
+    if let SandboxPolicy::WorkspaceWrite { writable_roots, .. } = &mut policy {
+        writable_roots.push(dir.to_path_buf());
+    }
+    policy
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        match std::fs::remove_dir_all(&self.path) {
+            Ok(()) => {}
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => tracing::warn!(
+                "failed to remove scratch directory {}: {err}",
+                self.path.display()
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::tempdir;
+
+    #[test]
+    fn scratch_dir_is_writable_and_removed_after_the_call() {
+        let tmp = tempdir().expect("tempdir");
+        let path = tmp.path().join("call_1");
+        let scratch = ScratchDir::create(path.clone()).expect("create");
+        std::fs::write(path.join("out.txt"), "temp").expect("write");
+
+        assert_eq!(
+            scratch.widen(&SandboxPolicy::new_workspace_write_policy()),
+            SandboxPolicy::WorkspaceWrite {
+                writable_roots: vec![path.clone()],
+                network_access: false,
+                exclude_tmpdir_env_var: false,
+                exclude_slash_tmp: false,
+            }
+        );
+        assert_eq!(
+            scratch.widen(&SandboxPolicy::ReadOnly),
+            SandboxPolicy::ReadOnly
+        );
+
+        drop(scratch);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn call_ids_are_made_safe_for_paths() {
+        let id = ConversationId::new();
+        assert_eq!(
+            scratch_dir(id, "call/../1"),
+            std::env::temp_dir()
+                .join(SCRATCH_ROOT)
+                .join(id.to_string())
+                .join("call____1")
+        );
+    }
+}
@@ -3,11 +3,15 @@ use crate::client_common::tools::ToolSpec;
 use crate::features::Feature;
 use crate::features::Features;
 use crate::model_family::ModelFamily;
+use crate::tools::custom::CustomToolSpec;
 use crate::tools::handlers::PLAN_TOOL;
 use crate::tools::handlers::apply_patch::ApplyPatchToolType;
 use crate::tools::handlers::apply_patch::create_apply_patch_freeform_tool;
 use crate::tools::handlers::apply_patch::create_apply_patch_json_tool;
 use crate::tools::registry::{ToolHandler, ToolRegistryBuilder};
+use crate::tools::runtimes::search::DEFAULT_MAX_MATCHES;
+use crate::tools::runtimes::search::MAX_CONTEXT_LINES;
+use crate::tools::runtimes::search::MAX_MATCHES;
 use once_cell::sync::Lazy;
 use serde::Deserialize;
 use serde::Serialize;
@@ -37,7 +41,37 @@ pub(crate) struct ToolsConfig {
     pub apply_patch_tool_type: Option<ApplyPatchToolType>,
     pub web_search_request: bool,
     pub include_view_image_tool: bool,
+    pub include_coverage_tool: bool,
+    pub include_test_runner_tool: bool,
+    pub include_bench_tool: bool,
+    pub include_process_tool: bool,
+    pub include_port_tool: bool,
+    pub include_wait_for_tool: bool,
+    pub include_compose_tool: bool,
+    pub include_env_snapshot_tool: bool,
+    pub include_file_info_tool: bool,
+    pub include_find_replace_tool: bool,
+    pub include_scaffold_tool: bool,
+    pub include_codemod_tool: bool,
+    pub include_migrate_tool: bool,
+    pub include_artifacts_tool: bool,
+    pub include_clipboard_tool: bool,
+    pub include_read_changes_tool: bool,
+    pub include_write_file_tool: bool,
+    pub include_git_tool: bool,
+    pub include_web_fetch_tool: bool,
+    pub include_search_tool: bool,
+    /// Tool descriptions end with the schema of the tool's JSON output.
+    pub include_output_schemas: bool,
+    /// The shell tool takes `background`, and the job tools are included.
+    pub include_background_jobs: bool,
+    /// The shell tool takes `network`.
+    pub include_command_network: bool,
+    pub include_trash_tool: bool,
+    pub include_undo_patch_tool: bool,
     pub experimental_supported_tools: Vec<String>,
+    /// Tools registered by the embedder; see [`crate::ToolRuntimeRegistry`].
+    pub custom_tools: Vec<CustomToolSpec>,
 }
 
 pub(crate) struct ToolsConfigParams<'a> {
@@ -54,6 +88,27 @@ impl ToolsConfig {
         let include_apply_patch_tool = features.enabled(Feature::ApplyPatchFreeform);
         let include_web_search_request = features.enabled(Feature::WebSearchRequest);
         let include_view_image_tool = features.enabled(Feature::ViewImageTool);
+        let include_coverage_tool = features.enabled(Feature::CoverageTool);
+        let include_test_runner_tool = features.enabled(Feature::TestRunnerTool);
+        let include_bench_tool = features.enabled(Feature::BenchTool);
+        let include_process_tool = features.enabled(Feature::ProcessTool);
+        let include_port_tool = features.enabled(Feature::PortTool);
+        let include_wait_for_tool = features.enabled(Feature::WaitForTool);
+        let include_compose_tool = features.enabled(Feature::ComposeTool);
+        let include_env_snapshot_tool = features.enabled(Feature::EnvSnapshotTool);
+        let include_file_info_tool = features.enabled(Feature::FileInfoTool);
+        let include_find_replace_tool = features.enabled(Feature::FindReplaceTool);
+        let include_scaffold_tool = features.enabled(Feature::ScaffoldTool);
+        let include_codemod_tool = features.enabled(Feature::CodemodTool);
+        let include_migrate_tool = features.enabled(Feature::MigrateTool);
+        let include_artifacts_tool = features.enabled(Feature::ArtifactsTool);
+        let include_clipboard_tool = features.enabled(Feature::ClipboardTool);
+        let include_read_changes_tool = features.enabled(Feature::ReadChangesTool);
+        let include_write_file_tool = features.enabled(Feature::WriteFileTool);
+        let include_git_tool = features.enabled(Feature::GitTool);
+        let include_web_fetch_tool = features.enabled(Feature::WebFetchTool);
+        let include_search_tool = features.enabled(Feature::SearchTool);
+        let include_output_schemas = features.enabled(Feature::ToolOutputSchemas);
 
         let shell_type = if !features.enabled(Feature::ShellTool) {
             ConfigShellToolType::Disabled
@@ -62,6 +117,18 @@ impl ToolsConfig {
         } else {
             model_family.shell_type.clone()
         };
+        let include_background_jobs = features.enabled(Feature::BackgroundJobs)
+            && matches!(
+                shell_type,
+                ConfigShellToolType::Default | ConfigShellToolType::ShellCommand
+            );
+        let include_command_network = features.enabled(Feature::CommandNetwork)
+            && matches!(
+                shell_type,
+                ConfigShellToolType::Default | ConfigShellToolType::ShellCommand
+            );
+        let include_trash_tool = features.enabled(Feature::Trash);
+        let include_undo_patch_tool = features.enabled(Feature::UndoPatch);
 
         let apply_patch_tool_type = match model_family.apply_patch_tool_type {
             Some(ApplyPatchToolType::Freeform) => Some(ApplyPatchToolType::Freeform),
@@ -80,7 +147,33 @@ impl ToolsConfig {
             apply_patch_tool_type,
             web_search_request: include_web_search_request,
             include_view_image_tool,
+            include_coverage_tool,
+            include_test_runner_tool,
+            include_bench_tool,
+            include_process_tool,
+            include_port_tool,
+            include_wait_for_tool,
+            include_compose_tool,
+            include_env_snapshot_tool,
+            include_file_info_tool,
+            include_find_replace_tool,
+            include_scaffold_tool,
+            include_codemod_tool,
+            include_migrate_tool,
+            include_artifacts_tool,
+            include_clipboard_tool,
+            include_read_changes_tool,
+            include_write_file_tool,
+            include_git_tool,
+            include_web_fetch_tool,
+            include_search_tool,
+            include_output_schemas,
+            include_background_jobs,
+            include_command_network,
+            include_trash_tool,
+            include_undo_patch_tool,
             experimental_supported_tools: model_family.experimental_supported_tools.clone(),
+            custom_tools: Vec::new(),
         }
     }
 }
@@ -247,171 +340,1505 @@ fn create_write_stdin_tool() -> ToolSpec {
         "max_output_tokens".to_string(),
         JsonSchema::Number {
             description: Some(
-                "Maximum number of tokens to return. Excess output will be truncated.".to_string(),
+                "Maximum number of tokens to return. Excess output will be truncated.".to_string(),
+            ),
+        },
+    );
+
+    ToolSpec::Function(ResponsesApiTool {
+        name: "write_stdin".to_string(),
+        description:
+            "Writes characters to an existing unified exec session and returns recent output."
+                .to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["session_id".to_string()]),
+            additional_properties: Some(false.into()),
+        },
+    })
+}
+
+/// Adds the `background` parameter to a shell tool's properties.
+fn insert_background_property(properties: &mut BTreeMap<String, JsonSchema>) {
+    properties.insert(
+        "background".to_string(),
+        JsonSchema::Boolean {
+            description: Some(
+                "Start the command as a background job and return its id right away, for dev \
+                 servers, watchers and other commands that do not finish. The timeout does not \
+                 apply; follow the job with job_output, job_status and job_kill."
+                    .to_string(),
+            ),
+        },
+    );
+}
+
+/// Adds the `network` parameter to a shell tool's properties.
+fn insert_network_property(properties: &mut BTreeMap<String, JsonSchema>) {
+    let mut network = BTreeMap::new();
+    network.insert(
+        "mode".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "The network the command needs: `none`, `loopback` for this machine only, or \
+                 `allowlist` for HTTP and HTTPS to `hosts` only. Access the sandbox does not \
+                 already allow needs the user's approval."
+                    .to_string(),
+            ),
+        },
+    );
+    network.insert(
+        "hosts".to_string(),
+        JsonSchema::Array {
+            items: Box::new(JsonSchema::String { description: None }),
+            description: Some(
+                "Hosts for `allowlist`; `*.example.com` also matches subdomains.".to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "network".to_string(),
+        JsonSchema::Object {
+            properties: network,
+            required: Some(vec!["mode".to_string()]),
+            additional_properties: Some(false.into()),
+        },
+    );
+}
+
+fn create_shell_tool(background: bool, network: bool) -> ToolSpec {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "command".to_string(),
+        JsonSchema::Array {
+            items: Box::new(JsonSchema::String { description: None }),
+            description: Some("The command to execute".to_string()),
+        },
+    );
+    properties.insert(
+        "workdir".to_string(),
+        JsonSchema::String {
+            description: Some("The working directory to execute the command in".to_string()),
+        },
+    );
+    properties.insert(
+        "timeout_ms".to_string(),
+        JsonSchema::Number {
+            description: Some("The timeout for the command in milliseconds".to_string()),
+        },
+    );
+
+    properties.insert(
+        "with_escalated_permissions".to_string(),
+        JsonSchema::Boolean {
+            description: Some("Whether to request escalated permissions. Set to true if command needs to be run without sandbox restrictions".to_string()),
+        },
+    );
+    properties.insert(
+        "justification".to_string(),
+        JsonSchema::String {
+            description: Some("Only set if with_escalated_permissions is true. 1-sentence explanation of why we want to run this command.".to_string()),
+        },
+    );
+    if background {
+        insert_background_property(&mut properties);
+    }
+    if network {
+        insert_network_property(&mut properties);
+    }
+
+    let description  = if cfg!(windows) {
+        r#"Runs a Powershell command (Windows) and returns its output. Arguments to `shell` will be passed to CreateProcessW(). Most commands should be prefixed with ["powershell.exe", "-Command"].
+        
+Examples of valid command strings:
+
+- ls -a (show hidden): ["powershell.exe", "-Command", "Get-ChildItem -Force"]
+- recursive find by name: ["powershell.exe", "-Command", "Get-ChildItem -Recurse -Filter *.py"]
+- recursive grep: ["powershell.exe", "-Command", "Get-ChildItem -Path C:\\myrepo -Recurse | Select-String -Pattern 'TODO' -CaseSensitive"]
+- ps aux | grep python: ["powershell.exe", "-Command", "Get-Process | Where-Object { $_.ProcessName -like '*python*' }"]
+- setting an env var: ["powershell.exe", "-Command", "$env:FOO='bar'; echo $env:FOO"]
+- running an inline Python script: ["powershell.exe", "-Command", "@'\\nprint('Hello, world!')\\n'@ | python -"]"#
+    } else {
+        r#"Runs a shell command and returns its output.
+- The arguments to `shell` will be passed to execvp(). Most terminal commands should be prefixed with ["bash", "-lc"].
+- Always set the `workdir` param when using the shell function. Do not use `cd` unless absolutely necessary."#
+    }.to_string();
+
+    ToolSpec::Function(ResponsesApiTool {
+        name: "shell".to_string(),
+        description,
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["command".to_string()]),
+            additional_properties: Some(false.into()),
+        },
+    })
+}
+
+fn create_shell_command_tool(background: bool, network: bool) -> ToolSpec {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "command".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "The shell script to execute in the user's default shell".to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "workdir".to_string(),
+        JsonSchema::String {
+            description: Some("The working directory to execute the command in".to_string()),
+        },
+    );
+    properties.insert(
+        "timeout_ms".to_string(),
+        JsonSchema::Number {
+            description: Some("The timeout for the command in milliseconds".to_string()),
+        },
+    );
+    properties.insert(
+        "with_escalated_permissions".to_string(),
+        JsonSchema::Boolean {
+            description: Some("Whether to request escalated permissions. Set to true if command needs to be run without sandbox restrictions".to_string()),
+        },
+    );
+    properties.insert(
+        "justification".to_string(),
+        JsonSchema::String {
+            description: Some("Only set if with_escalated_permissions is true. 1-sentence explanation of why we want to run this command.".to_string()),
+        },
+    );
+    if background {
+        insert_background_property(&mut properties);
+    }
+    if network {
+        insert_network_property(&mut properties);
+    }
+
+    let description = if cfg!(windows) {
+        r#"Runs a Powershell command (Windows) and returns its output.
+        
+Examples of valid command strings:
+
+- ls -a (show hidden): "Get-ChildItem -Force"
+- recursive find by name: "Get-ChildItem -Recurse -Filter *.py"
+- recursive grep: "Get-ChildItem -Path C:\\myrepo -Recurse | Select-String -Pattern 'TODO' -CaseSensitive"
+- ps aux | grep python: "Get-Process | Where-Object { $_.ProcessName -like '*python*' }"
+- setting an env var: "$env:FOO='bar'; echo $env:FOO"
+- running an inline Python script: "@'\\nprint('Hello, world!')\\n'@ | python -"#
+    } else {
+        r#"Runs a shell command and returns its output.
+- Always set the `workdir` param when using the shell_command function. Do not use `cd` unless absolutely necessary."#
+    }.to_string();
+
+    ToolSpec::Function(ResponsesApiTool {
+        name: "shell_command".to_string(),
+        description,
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["command".to_string()]),
+            additional_properties: Some(false.into()),
+        },
+    })
+}
+
+fn create_view_image_tool() -> ToolSpec {
+    // Support only local filesystem path.
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "path".to_string(),
+        JsonSchema::String {
+            description: Some("Local filesystem path to an image file".to_string()),
+        },
+    );
+
+    ToolSpec::Function(ResponsesApiTool {
+        name: "view_image".to_string(),
+        description:
+            "Attach a local image (by filesystem path) to the conversation context for this turn."
+                .to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["path".to_string()]),
+            additional_properties: Some(false.into()),
+        },
+    })
+}
+
+fn create_coverage_tool() -> ToolSpec {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "tool".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "Coverage backend: \"cargo_llvm_cov\", \"coverage_py\" or \"istanbul\". \
+                 Detected from the project manifests when omitted."
+                    .to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "args".to_string(),
+        JsonSchema::Array {
+            items: Box::new(JsonSchema::String { description: None }),
+            description: Some(
+                "Extra arguments passed to the test command (e.g. a test filter).".to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "workdir".to_string(),
+        JsonSchema::String {
+            description: Some("The working directory to run coverage in.".to_string()),
+        },
+    );
+    properties.insert(
+        "paths".to_string(),
+        JsonSchema::Array {
+            items: Box::new(JsonSchema::String { description: None }),
+            description: Some(
+                "Files to report on. Defaults to the files modified in this session.".to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "all_files".to_string(),
+        JsonSchema::Boolean {
+            description: Some("Report on every file in the coverage report.".to_string()),
+        },
+    );
+    properties.insert(
+        "timeout_ms".to_string(),
+        JsonSchema::Number {
+            description: Some("The timeout for the coverage run in milliseconds.".to_string()),
+        },
+    );
+
+    ToolSpec::Function(ResponsesApiTool {
+        name: "coverage".to_string(),
+        description: "Runs the project's tests under coverage tooling and returns uncovered line \
+                      ranges for the files you changed. Use it to decide which tests to write."
+            .to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: None,
+            additional_properties: Some(false.into()),
+        },
+    })
+}
+
+fn create_git_tool() -> ToolSpec {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "query".to_string(),
+        JsonSchema::String {
+            description: Some("One of \"status\", \"diff\", \"log\" or \"blame\".".to_string()),
+        },
+    );
+    properties.insert(
+        "staged".to_string(),
+        JsonSchema::Boolean {
+            description: Some(
+                "diff: compare the index with HEAD instead of the working tree with the index."
+                    .to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "rev".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "diff, log: a revision or range, such as \"HEAD~3\" or \"main...HEAD\"."
+                    .to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "paths".to_string(),
+        JsonSchema::Array {
+            items: Box::new(JsonSchema::String { description: None }),
+            description: Some("diff, log: limit the query to these paths.".to_string()),
+        },
+    );
+    properties.insert(
+        "limit".to_string(),
+        JsonSchema::Number {
+            description: Some(
+                "log: the number of commits to return (default 20, at most 200).".to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "path".to_string(),
+        JsonSchema::String {
+            description: Some("blame: the file to blame.".to_string()),
+        },
+    );
+    properties.insert(
+        "start_line".to_string(),
+        JsonSchema::Number {
+            description: Some("blame: the first line to blame, 1-based.".to_string()),
+        },
+    );
+    properties.insert(
+        "end_line".to_string(),
+        JsonSchema::Number {
+            description: Some("blame: the last line to blame, inclusive.".to_string()),
+        },
+    );
+    properties.insert(
+        "workdir".to_string(),
+        JsonSchema::String {
+            description: Some("The directory in the repository to run git in.".to_string()),
+        },
+    );
+
+    ToolSpec::Function(ResponsesApiTool {
+        name: "git".to_string(),
+        description: "Queries the git repository and returns the result as JSON: status (branch, \
+                      upstream and changed paths), diff (files and hunks), log (commits) or \
+                      blame (line ranges per commit). Read-only; prefer it to running git in \
+                      the shell."
+            .to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["query".to_string()]),
+            additional_properties: Some(false.into()),
+        },
+    })
+}
+
+fn create_web_fetch_tool() -> ToolSpec {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "url".to_string(),
+        JsonSchema::String {
+            description: Some("The http or https URL to fetch.".to_string()),
+        },
+    );
+    properties.insert(
+        "method".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "\"get\" (default) for the body, or \"head\" for the status and headers only."
+                    .to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "max_bytes".to_string(),
+        JsonSchema::Number {
+            description: Some("Read at most this many bytes of the body.".to_string()),
+        },
+    );
+
+    ToolSpec::Function(ResponsesApiTool {
+        name: "web_fetch".to_string(),
+        description: "Fetches a web page or file over HTTP and returns its status, headers and \
+                      body, with HTML converted to Markdown. Prefer it to running curl in the \
+                      shell; hosts outside the allowed domains may need the user's approval."
+            .to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["url".to_string()]),
+            additional_properties: Some(false.into()),
+        },
+    })
+}
+
+fn create_search_tool() -> ToolSpec {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "pattern".to_string(),
+        JsonSchema::String {
+            description: Some("The regular expression (Rust syntax) to search for.".to_string()),
+        },
+    );
+    properties.insert(
+        "literal".to_string(),
+        JsonSchema::Boolean {
+            description: Some("Match `pattern` as a plain string instead.".to_string()),
+        },
+    );
+    properties.insert(
+        "case_insensitive".to_string(),
+        JsonSchema::Boolean {
+            description: Some("Ignore case when matching.".to_string()),
+        },
+    );
+    properties.insert(
+        "path".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "The directory or file to search; defaults to the working directory.".to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "include".to_string(),
+        JsonSchema::Array {
+            items: Box::new(JsonSchema::String { description: None }),
+            description: Some(
+                "Only search files matching one of these globs, e.g. `*.rs`.".to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "exclude".to_string(),
+        JsonSchema::Array {
+            items: Box::new(JsonSchema::String { description: None }),
+            description: Some(
+                "Skip files and directories matching these globs, e.g. `tests/`.".to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "context_lines".to_string(),
+        JsonSchema::Number {
+            description: Some(format!(
+                "Lines to show before and after each match (0-{MAX_CONTEXT_LINES}, default 0)."
+            )),
+        },
+    );
+    properties.insert(
+        "max_matches".to_string(),
+        JsonSchema::Number {
+            description: Some(format!(
+                "Stop after this many matches (default {DEFAULT_MAX_MATCHES}, at most \
+                 {MAX_MATCHES})."
+            )),
+        },
+    );
+
+    ToolSpec::Function(ResponsesApiTool {
+        name: "search".to_string(),
+        description: "Searches the text files in the workspace for a regular expression or \
+                      string, like ripgrep: ignored, hidden and binary files are skipped. \
+                      Returns the matching lines with their paths and line numbers. Read-only; \
+                      prefer it to running rg or grep in the shell."
+            .to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["pattern".to_string()]),
+            additional_properties: Some(false.into()),
+        },
+    })
+}
+
+fn create_run_tests_tool() -> ToolSpec {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "command".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "The test command to run, e.g. `cargo test -p foo racy_`.".to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "workdir".to_string(),
+        JsonSchema::String {
+            description: Some("The working directory to run the tests in.".to_string()),
+        },
+    );
+    properties.insert(
+        "timeout_ms".to_string(),
+        JsonSchema::Number {
+            description: Some("The timeout for each run in milliseconds.".to_string()),
+        },
+    );
+    properties.insert(
+        "repeat".to_string(),
+        JsonSchema::Number {
+            description: Some(
+                "How many times to run the command (1-100, default 1). Values above 1 return \
+                 aggregated pass rates and suspected flaky tests."
+                    .to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "parallel".to_string(),
+        JsonSchema::Boolean {
+            description: Some(
+                "Run the repetitions concurrently, at most one per CPU at a time.".to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "seed_env".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "Environment variable that receives a distinct seed for each run (e.g. \
+                 `PROPTEST_RNG_SEED`)."
+                    .to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "base_seed".to_string(),
+        JsonSchema::Number {
+            description: Some("Seed for the first run; later runs increment it.".to_string()),
+        },
+    );
+
+    ToolSpec::Function(ResponsesApiTool {
+        name: "run_tests".to_string(),
+        description: "Runs a test command, optionally many times to detect flaky tests."
+            .to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["command".to_string()]),
+            additional_properties: Some(false.into()),
+        },
+    })
+}
+
+fn create_bench_tool() -> ToolSpec {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "tool".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "Benchmark runner: \"criterion\" (`cargo bench`) or \"hyperfine\". Defaults \
+                 to criterion in Cargo projects."
+                    .to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "args".to_string(),
+        JsonSchema::Array {
+            items: Box::new(JsonSchema::String { description: None }),
+            description: Some(
+                "Arguments for the runner: a bench filter for criterion, or the commands to \
+                 compare for hyperfine."
+                    .to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "workdir".to_string(),
+        JsonSchema::String {
+            description: Some("The working directory to run the benchmarks in.".to_string()),
+        },
+    );
+    properties.insert(
+        "timeout_ms".to_string(),
+        JsonSchema::Number {
+            description: Some("The timeout for the benchmark run in milliseconds.".to_string()),
+        },
+    );
+    properties.insert(
+        "save_baseline".to_string(),
+        JsonSchema::String {
+            description: Some("Store the results as a named baseline.".to_string()),
+        },
+    );
+    properties.insert(
+        "compare_to".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "Name of a stored baseline to compare the results against.".to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "threshold_pct".to_string(),
+        JsonSchema::Number {
+            description: Some(
+                "Changes smaller than this percentage are reported as unchanged (default 5)."
+                    .to_string(),
+            ),
+        },
+    );
+
+    ToolSpec::Function(ResponsesApiTool {
+        name: "bench".to_string(),
+        description: "Runs benchmarks, optionally saving the results as a named baseline or \
+                      comparing them against one to report regressions and improvements."
+            .to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: None,
+            additional_properties: Some(false.into()),
+        },
+    })
+}
+
+fn create_job_status_tool() -> ToolSpec {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "job_id".to_string(),
+        JsonSchema::Number {
+            description: Some("The job to report on; omit to list every job.".to_string()),
+        },
+    );
+
+    ToolSpec::Function(ResponsesApiTool {
+        name: "job_status".to_string(),
+        description: "Reports whether background jobs started with the shell tool are still \
+                      running, their exit codes, and how much output is waiting to be read."
+            .to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: None,
+            additional_properties: Some(false.into()),
+        },
+    })
+}
+
+fn create_job_output_tool() -> ToolSpec {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "job_id".to_string(),
+        JsonSchema::Number {
+            description: Some("The job whose output to read.".to_string()),
+        },
+    );
+    properties.insert(
+        "max_output_tokens".to_string(),
+        JsonSchema::Number {
+            description: Some(
+                "Maximum number of tokens of output to return. Excess output will be truncated."
+                    .to_string(),
+            ),
+        },
+    );
+
+    ToolSpec::Function(ResponsesApiTool {
+        name: "job_output".to_string(),
+        description: "Returns the output a background job produced since its output was last \
+                      read, along with its status."
+            .to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["job_id".to_string()]),
+            additional_properties: Some(false.into()),
+        },
+    })
+}
+
+fn create_job_kill_tool() -> ToolSpec {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "job_id".to_string(),
+        JsonSchema::Number {
+            description: Some("The job to stop.".to_string()),
+        },
+    );
+
+    ToolSpec::Function(ResponsesApiTool {
+        name: "job_kill".to_string(),
+        description: "Stops a background job and the processes it started.".to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["job_id".to_string()]),
+            additional_properties: Some(false.into()),
+        },
+    })
+}
+
+fn create_process_tool() -> ToolSpec {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "action".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "\"list\" processes started by this session or running in the workspace, \
+                 \"inspect\" one process, or \"kill\" one (requires approval)."
+                    .to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "pid".to_string(),
+        JsonSchema::Number {
+            description: Some("Target process id for `inspect` and `kill`.".to_string()),
+        },
+    );
+    properties.insert(
+        "filter".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "Only list processes whose command line contains this text.".to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "signal".to_string(),
+        JsonSchema::String {
+            description: Some("Signal for `kill`: \"term\" (default) or \"kill\".".to_string()),
+        },
+    );
+
+    ToolSpec::Function(ResponsesApiTool {
+        name: "process".to_string(),
+        description: "Lists, inspects (CPU, memory, uptime, children) and terminates processes \
+                      started by this session or running in the workspace, e.g. a dev server \
+                      left behind by an earlier command."
+            .to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["action".to_string()]),
+            additional_properties: Some(false.into()),
+        },
+    })
+}
+
+fn create_port_tool() -> ToolSpec {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "action".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "\"check\" whether the port is free and what is listening on it, or \"wait\" \
+                 until it accepts TCP connections."
+                    .to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "port".to_string(),
+        JsonSchema::Number {
+            description: Some("TCP port number.".to_string()),
+        },
+    );
+    properties.insert(
+        "host".to_string(),
+        JsonSchema::String {
+            description: Some("Host to check (defaults to 127.0.0.1).".to_string()),
+        },
+    );
+    properties.insert(
+        "timeout_ms".to_string(),
+        JsonSchema::Number {
+            description: Some(
+                "For `wait`: give up after this many milliseconds (default 30000).".to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "interval_ms".to_string(),
+        JsonSchema::Number {
+            description: Some(
+                "For `wait`: delay between connection attempts (default 250).".to_string(),
+            ),
+        },
+    );
+
+    ToolSpec::Function(ResponsesApiTool {
+        name: "port".to_string(),
+        description: "Checks whether a TCP port is free and which process listens on it, or waits \
+                      until a port accepts connections. Prefer this over `lsof`/`nc` loops."
+            .to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["action".to_string(), "port".to_string()]),
+            additional_properties: Some(false.into()),
+        },
+    })
+}
+
+fn create_wait_for_tool() -> ToolSpec {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "url".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "Wait until a GET to this URL returns `status`. Only localhost URLs are allowed \
+                 when network access is restricted."
+                    .to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "status".to_string(),
+        JsonSchema::Number {
+            description: Some("Expected HTTP status for `url` (default 200).".to_string()),
+        },
+    );
+    properties.insert(
+        "file".to_string(),
+        JsonSchema::String {
+            description: Some("Wait until this path exists.".to_string()),
+        },
+    );
+    properties.insert(
+        "log_file".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "Wait until this file contains a line matching `pattern`.".to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "pattern".to_string(),
+        JsonSchema::String {
+            description: Some("Regex matched against each line of `log_file`.".to_string()),
+        },
+    );
+    properties.insert(
+        "new_lines_only".to_string(),
+        JsonSchema::Boolean {
+            description: Some(
+                "Ignore lines already in `log_file` when the wait starts (default false)."
+                    .to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "timeout_ms".to_string(),
+        JsonSchema::Number {
+            description: Some(
+                "Give up after this many milliseconds (default 60000, max 600000).".to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "interval_ms".to_string(),
+        JsonSchema::Number {
+            description: Some("Delay between probes (default 500).".to_string()),
+        },
+    );
+
+    ToolSpec::Function(ResponsesApiTool {
+        name: "wait_for".to_string(),
+        description: "Waits until a service is ready: an HTTP endpoint returns the expected \
+                      status, a file exists, or a log line appears. Pass exactly one of `url`, \
+                      `file` or `log_file`. Use this instead of `sleep` before integration tests."
+            .to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: None,
+            additional_properties: Some(false.into()),
+        },
+    })
+}
+
+fn create_compose_tool() -> ToolSpec {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "action".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "One of \"up\" (start detached and wait for healthchecks), \"down\", \"ps\" \
+                 or \"logs\"."
+                    .to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "services".to_string(),
+        JsonSchema::Array {
+            items: Box::new(JsonSchema::String { description: None }),
+            description: Some(
+                "Limit `up`, `ps` or `logs` to these services (default: all).".to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "files".to_string(),
+        JsonSchema::Array {
+            items: Box::new(JsonSchema::String { description: None }),
+            description: Some(
+                "Compose files to pass with `-f` (default: compose's own discovery).".to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "workdir".to_string(),
+        JsonSchema::String {
+            description: Some("Project directory (defaults to the turn cwd).".to_string()),
+        },
+    );
+    properties.insert(
+        "keep".to_string(),
+        JsonSchema::Boolean {
+            description: Some(
+                "For `up`: leave the containers running when the session ends. Only set this \
+                 when the user asked to keep them."
+                    .to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "tail".to_string(),
+        JsonSchema::Number {
+            description: Some("For `logs`: number of lines per service (default 200).".to_string()),
+        },
+    );
+    properties.insert(
+        "timeout_ms".to_string(),
+        JsonSchema::Number {
+            description: Some("The timeout for the command in milliseconds.".to_string()),
+        },
+    );
+
+    ToolSpec::Function(ResponsesApiTool {
+        name: "compose".to_string(),
+        description: "Manages the workspace's Docker Compose project and reports structured \
+                      per-service status (state, health, published ports). Projects started \
+                      with `up` are torn down automatically when the session ends unless `keep` \
+                      is set."
+            .to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["action".to_string()]),
+            additional_properties: Some(false.into()),
+        },
+    })
+}
+
+fn create_env_snapshot_tool() -> ToolSpec {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "extra_tools".to_string(),
+        JsonSchema::Array {
+            items: Box::new(JsonSchema::String { description: None }),
+            description: Some(
+                "Additional binaries to look up on PATH and probe with `--version`.".to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "include_env".to_string(),
+        JsonSchema::Boolean {
+            description: Some(
+                "Include relevant environment variables, with secrets redacted (default true)."
+                    .to_string(),
+            ),
+        },
+    );
+
+    ToolSpec::Function(ResponsesApiTool {
+        name: "env_snapshot".to_string(),
+        description: "Returns a structured snapshot of the execution environment: OS and \
+                      version, architecture, CPU count, memory, container/VM detection, \
+                      installed toolchains with versions, and relevant environment variables. \
+                      Use this instead of probing with `which`/`--version` commands."
+            .to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: None,
+            additional_properties: Some(false.into()),
+        },
+    })
+}
+
+fn create_file_info_tool() -> ToolSpec {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "paths".to_string(),
+        JsonSchema::Array {
+            items: Box::new(JsonSchema::String { description: None }),
+            description: Some("Files or directories to inspect (at most 100).".to_string()),
+        },
+    );
+    properties.insert(
+        "blame".to_string(),
+        JsonSchema::Boolean {
+            description: Some(
+                "Also report the commit, author and date that last changed each run of lines \
+                 in regular files."
+                    .to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "start_line".to_string(),
+        JsonSchema::Number {
+            description: Some("First line (1-indexed) to blame.".to_string()),
+        },
+    );
+    properties.insert(
+        "end_line".to_string(),
+        JsonSchema::Number {
+            description: Some("Last line (inclusive) to blame.".to_string()),
+        },
+    );
+
+    ToolSpec::Function(ResponsesApiTool {
+        name: "file_info".to_string(),
+        description: "Returns structured stat information for paths (kind, size, permission \
+                      mode, modification time, symlink target) and optionally git blame \
+                      attribution per line range. Prefer this over parsing `stat` or \
+                      `git blame` output."
+            .to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["paths".to_string()]),
+            additional_properties: Some(false.into()),
+        },
+    })
+}
+
+fn create_find_replace_tool() -> ToolSpec {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "find".to_string(),
+        JsonSchema::String {
+            description: Some("Text (or regex when `regex` is true) to search for.".to_string()),
+        },
+    );
+    properties.insert(
+        "replace".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "Replacement text. With `regex`, `$1` or `${name}` insert capture groups."
+                    .to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "regex".to_string(),
+        JsonSchema::Boolean {
+            description: Some("Interpret `find` as a regular expression.".to_string()),
+        },
+    );
+    properties.insert(
+        "case_insensitive".to_string(),
+        JsonSchema::Boolean {
+            description: Some("Match case-insensitively.".to_string()),
+        },
+    );
+    properties.insert(
+        "whole_word".to_string(),
+        JsonSchema::Boolean {
+            description: Some(
+                "Only match at word boundaries; use this for identifier renames.".to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "paths".to_string(),
+        JsonSchema::Array {
+            items: Box::new(JsonSchema::String { description: None }),
+            description: Some(
+                "Files or directories to search (default: the working directory).".to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "include".to_string(),
+        JsonSchema::Array {
+            items: Box::new(JsonSchema::String { description: None }),
+            description: Some(
+                "Glob filters such as `*.rs` or `!vendor/**`. Ignored files are skipped."
+                    .to_string(),
+            ),
+        },
+    );
+
+    ToolSpec::Function(ResponsesApiTool {
+        name: "find_replace".to_string(),
+        description: "Finds and replaces text across the project and applies the result as a \
+                      single patch that goes through the normal review. Use this instead of \
+                      `sed -i` for renames and bulk edits."
+            .to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["find".to_string(), "replace".to_string()]),
+            additional_properties: Some(false.into()),
+        },
+    })
+}
+
+fn create_scaffold_tool() -> ToolSpec {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "template".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "Template to instantiate. Omit to list available templates and the variables \
+                 they use."
+                    .to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "dest".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "Directory to render the template into (defaults to the working directory)."
+                    .to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "variables".to_string(),
+        JsonSchema::Object {
+            properties: BTreeMap::new(),
+            required: None,
+            additional_properties: Some(JsonSchema::String { description: None }.into()),
+        },
+    );
+
+    ToolSpec::Function(ResponsesApiTool {
+        name: "scaffold".to_string(),
+        description: "Instantiates a file-tree template from `.codex/templates/<name>` (or \
+                      `$CODEX_HOME/templates/<name>`) and applies it as a patch for review. \
+                      `{{var}}` placeholders in paths and contents are replaced from `variables`; \
+                      `{{var|snake}}`, `kebab`, `camel`, `pascal`, `upper` and `lower` convert \
+                      case. Existing files are never overwritten."
+            .to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: None,
+            additional_properties: Some(false.into()),
+        },
+    })
+}
+
+fn create_codemod_tool() -> ToolSpec {
+    let mut rule_properties = BTreeMap::new();
+    rule_properties.insert(
+        "pattern".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "Structural pattern, e.g. `foo($A)` for ast-grep or `foo(:[a])` for comby."
+                    .to_string(),
+            ),
+        },
+    );
+    rule_properties.insert(
+        "rewrite".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "Replacement using the captures from `pattern`, e.g. `bar($A)`.".to_string(),
+            ),
+        },
+    );
+    rule_properties.insert(
+        "language".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "ast-grep language (`rust`, `typescript`, ...) or comby file extension (`.go`)."
+                    .to_string(),
+            ),
+        },
+    );
+
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "engine".to_string(),
+        JsonSchema::String {
+            description: Some("Rewrite engine: `ast_grep` or `comby`.".to_string()),
+        },
+    );
+    properties.insert(
+        "rules".to_string(),
+        JsonSchema::Array {
+            items: Box::new(JsonSchema::Object {
+                properties: rule_properties,
+                required: Some(vec![
+                    "pattern".to_string(),
+                    "rewrite".to_string(),
+                    "language".to_string(),
+                ]),
+                additional_properties: Some(false.into()),
+            }),
+            description: Some("Rewrite rules, applied in order to each file.".to_string()),
+        },
+    );
+    properties.insert(
+        "paths".to_string(),
+        JsonSchema::Array {
+            items: Box::new(JsonSchema::String { description: None }),
+            description: Some(
+                "Files or directories to rewrite (default: the working directory).".to_string(),
+            ),
+        },
+    );
+
+    ToolSpec::Function(ResponsesApiTool {
+        name: "codemod".to_string(),
+        description: "Runs structural rewrite rules with ast-grep or comby and applies the \
+                      combined result as a single patch that goes through the normal review. \
+                      Prefer this over regex edits for syntax-aware refactors."
+            .to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["engine".to_string(), "rules".to_string()]),
+            additional_properties: Some(false.into()),
+        },
+    })
+}
+
+fn create_migrate_tool() -> ToolSpec {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "tool".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "Migration tool: `diesel`, `sqlx`, `alembic` or `prisma`.".to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "action".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "`plan` lists pending migrations and their SQL, `dry_run` also applies them to \
+                 the shadow database, `apply` dry-runs and then applies them to the target."
+                    .to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "database_url".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "Target database URL (default: `DATABASE_URL` from the environment or `.env`)."
+                    .to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "shadow_database_url".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "Disposable database at the same version as the target. Required for `dry_run` \
+                 and `apply`."
+                    .to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "workdir".to_string(),
+        JsonSchema::String {
+            description: Some("Project directory (default: the working directory).".to_string()),
+        },
+    );
+    properties.insert(
+        "timeout_ms".to_string(),
+        JsonSchema::Number {
+            description: Some("Timeout for each step in milliseconds.".to_string()),
+        },
+    );
+
+    ToolSpec::Function(ResponsesApiTool {
+        name: "migrate".to_string(),
+        description: "Runs database migrations with diesel, sqlx, alembic or prisma. Pending \
+                      migrations are always applied to a shadow database first; the SQL is \
+                      shown to the user when approving the real apply."
+            .to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["tool".to_string(), "action".to_string()]),
+            additional_properties: Some(false.into()),
+        },
+    })
+}
+
+fn create_artifacts_tool() -> ToolSpec {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "action".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "`list` shows the files the user handed over (read them from `path`); `send` \
+                 delivers a file to the user."
+                    .to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "path".to_string(),
+        JsonSchema::String {
+            description: Some("For `send`: the file to deliver.".to_string()),
+        },
+    );
+    properties.insert(
+        "name".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "For `send`: name to show the user (default: the file name).".to_string(),
+            ),
+        },
+    );
+
+    ToolSpec::Function(ResponsesApiTool {
+        name: "artifacts".to_string(),
+        description: "Exchanges files with the user outside the workspace, such as a log bundle \
+                      they provided or a generated report. Each `send` asks the user for approval."
+            .to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["action".to_string()]),
+            additional_properties: Some(false.into()),
+        },
+    })
+}
+
+fn create_undo_patch_tool() -> ToolSpec {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "count".to_string(),
+        JsonSchema::Number {
+            description: Some(
+                "How many of the most recent patches to revert (default 1).".to_string(),
+            ),
+        },
+    );
+
+    ToolSpec::Function(ResponsesApiTool {
+        name: "undo_patch".to_string(),
+        description: "Reverts the most recent apply_patch edits of this session, newest first, \
+                      by restoring the files they changed and deleting the files they created. \
+                      A patch whose files have changed since it was applied is not reverted."
+            .to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: None,
+            additional_properties: Some(false.into()),
+        },
+    })
+}
+
+fn create_trash_tool() -> ToolSpec {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "action".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "`list` shows the files deleted so far in this session; `restore` puts one back."
+                    .to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "path".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "For `restore`: the deleted file, as listed. The most recent copy is restored."
+                    .to_string(),
             ),
         },
     );
 
     ToolSpec::Function(ResponsesApiTool {
-        name: "write_stdin".to_string(),
-        description:
-            "Writes characters to an existing unified exec session and returns recent output."
-                .to_string(),
+        name: "trash".to_string(),
+        description: "Recovers files deleted by apply_patch in this session. Restoring does not \
+                      replace a file that has since been created at the same path."
+            .to_string(),
         strict: false,
         parameters: JsonSchema::Object {
             properties,
-            required: Some(vec!["session_id".to_string()]),
+            required: Some(vec!["action".to_string()]),
             additional_properties: Some(false.into()),
         },
     })
 }
 
-fn create_shell_tool() -> ToolSpec {
+fn create_clipboard_tool() -> ToolSpec {
     let mut properties = BTreeMap::new();
     properties.insert(
-        "command".to_string(),
-        JsonSchema::Array {
-            items: Box::new(JsonSchema::String { description: None }),
-            description: Some("The command to execute".to_string()),
-        },
-    );
-    properties.insert(
-        "workdir".to_string(),
+        "action".to_string(),
         JsonSchema::String {
-            description: Some("The working directory to execute the command in".to_string()),
-        },
-    );
-    properties.insert(
-        "timeout_ms".to_string(),
-        JsonSchema::Number {
-            description: Some("The timeout for the command in milliseconds".to_string()),
-        },
-    );
-
-    properties.insert(
-        "with_escalated_permissions".to_string(),
-        JsonSchema::Boolean {
-            description: Some("Whether to request escalated permissions. Set to true if command needs to be run without sandbox restrictions".to_string()),
+            description: Some("`read` or `write`.".to_string()),
         },
     );
     properties.insert(
-        "justification".to_string(),
+        "text".to_string(),
         JsonSchema::String {
-            description: Some("Only set if with_escalated_permissions is true. 1-sentence explanation of why we want to run this command.".to_string()),
+            description: Some("For `write`: the text to put on the clipboard.".to_string()),
         },
     );
 
-    let description  = if cfg!(windows) {
-        r#"Runs a Powershell command (Windows) and returns its output. Arguments to `shell` will be passed to CreateProcessW(). Most commands should be prefixed with ["powershell.exe", "-Command"].
-        
-Examples of valid command strings:
-
-- ls -a (show hidden): ["powershell.exe", "-Command", "Get-ChildItem -Force"]
-- recursive find by name: ["powershell.exe", "-Command", "Get-ChildItem -Recurse -Filter *.py"]
-- recursive grep: ["powershell.exe", "-Command", "Get-ChildItem -Path C:\\myrepo -Recurse | Select-String -Pattern 'TODO' -CaseSensitive"]
-- ps aux | grep python: ["powershell.exe", "-Command", "Get-Process | Where-Object { $_.ProcessName -like '*python*' }"]
-- setting an env var: ["powershell.exe", "-Command", "$env:FOO='bar'; echo $env:FOO"]
-- running an inline Python script: ["powershell.exe", "-Command", "@'\\nprint('Hello, world!')\\n'@ | python -"]"#
-    } else {
-        r#"Runs a shell command and returns its output.
-- The arguments to `shell` will be passed to execvp(). Most terminal commands should be prefixed with ["bash", "-lc"].
-- Always set the `workdir` param when using the shell function. Do not use `cd` unless absolutely necessary."#
-    }.to_string();
-
     ToolSpec::Function(ResponsesApiTool {
-        name: "shell".to_string(),
-        description,
+        name: "clipboard".to_string(),
+        description: "Reads text from or writes text to the user's system clipboard, with their \
+                      approval. Use it when the user refers to something they copied or asks for \
+                      output on their clipboard."
+            .to_string(),
         strict: false,
         parameters: JsonSchema::Object {
             properties,
-            required: Some(vec!["command".to_string()]),
+            required: Some(vec!["action".to_string()]),
             additional_properties: Some(false.into()),
         },
     })
 }
 
-fn create_shell_command_tool() -> ToolSpec {
+fn create_read_changes_tool() -> ToolSpec {
     let mut properties = BTreeMap::new();
     properties.insert(
-        "command".to_string(),
+        "file_path".to_string(),
         JsonSchema::String {
             description: Some(
-                "The shell script to execute in the user's default shell".to_string(),
+                "Absolute path to a file returned earlier by `read_file` or `read_changes`."
+                    .to_string(),
             ),
         },
     );
-    properties.insert(
-        "workdir".to_string(),
-        JsonSchema::String {
-            description: Some("The working directory to execute the command in".to_string()),
-        },
-    );
-    properties.insert(
-        "timeout_ms".to_string(),
-        JsonSchema::Number {
-            description: Some("The timeout for the command in milliseconds".to_string()),
-        },
-    );
-    properties.insert(
-        "with_escalated_permissions".to_string(),
-        JsonSchema::Boolean {
-            description: Some("Whether to request escalated permissions. Set to true if command needs to be run without sandbox restrictions".to_string()),
-        },
-    );
-    properties.insert(
-        "justification".to_string(),
-        JsonSchema::String {
-            description: Some("Only set if with_escalated_permissions is true. 1-sentence explanation of why we want to run this command.".to_string()),
-        },
-    );
-
-    let description = if cfg!(windows) {
-        r#"Runs a Powershell command (Windows) and returns its output.
-        
-Examples of valid command strings:
-
-- ls -a (show hidden): "Get-ChildItem -Force"
-- recursive find by name: "Get-ChildItem -Recurse -Filter *.py"
-- recursive grep: "Get-ChildItem -Path C:\\myrepo -Recurse | Select-String -Pattern 'TODO' -CaseSensitive"
-- ps aux | grep python: "Get-Process | Where-Object { $_.ProcessName -like '*python*' }"
-- setting an env var: "$env:FOO='bar'; echo $env:FOO"
-- running an inline Python script: "@'\\nprint('Hello, world!')\\n'@ | python -"#
-    } else {
-        r#"Runs a shell command and returns its output.
-- Always set the `workdir` param when using the shell_command function. Do not use `cd` unless absolutely necessary."#
-    }.to_string();
 
     ToolSpec::Function(ResponsesApiTool {
-        name: "shell_command".to_string(),
-        description,
+        name: "read_changes".to_string(),
+        description: "Returns a unified diff of a file against the version last returned to you \
+                      by `read_file` or `read_changes` in this session. Use it instead of \
+                      re-reading a file you already read to see what edits or commands changed."
+            .to_string(),
         strict: false,
         parameters: JsonSchema::Object {
             properties,
-            required: Some(vec!["command".to_string()]),
+            required: Some(vec!["file_path".to_string()]),
             additional_properties: Some(false.into()),
         },
     })
 }
 
-fn create_view_image_tool() -> ToolSpec {
-    // Support only local filesystem path.
+fn create_write_file_tool() -> ToolSpec {
     let mut properties = BTreeMap::new();
     properties.insert(
-        "path".to_string(),
+        "file_path".to_string(),
         JsonSchema::String {
-            description: Some("Local filesystem path to an image file".to_string()),
+            description: Some("Absolute path to the file to create or overwrite.".to_string()),
+        },
+    );
+    properties.insert(
+        "content".to_string(),
+        JsonSchema::String {
+            description: Some("The complete new contents of the file.".to_string()),
         },
     );
 
     ToolSpec::Function(ResponsesApiTool {
-        name: "view_image".to_string(),
-        description:
-            "Attach a local image (by filesystem path) to the conversation context for this turn."
-                .to_string(),
+        name: "write_file".to_string(),
+        description: "Creates a file, or replaces all of an existing file's contents, without \
+                      a patch. Missing parent directories are created. Use `apply_patch` to \
+                      change part of a file."
+            .to_string(),
         strict: false,
         parameters: JsonSchema::Object {
             properties,
-            required: Some(vec!["path".to_string()]),
+            required: Some(vec!["file_path".to_string(), "content".to_string()]),
             additional_properties: Some(false.into()),
         },
     })
@@ -562,6 +1989,25 @@ fn create_read_file_tool() -> ToolSpec {
         },
     );
 
+    properties.insert(
+        "byte_offset".to_string(),
+        JsonSchema::Number {
+            description: Some(
+                "Read raw bytes starting at this 0-based offset instead of lines. Binary files \
+                 are shown as a hex dump."
+                    .to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "byte_limit".to_string(),
+        JsonSchema::Number {
+            description: Some(
+                "The maximum number of bytes to return when reading bytes.".to_string(),
+            ),
+        },
+    );
+
     let mut indentation_properties = BTreeMap::new();
     indentation_properties.insert(
         "anchor_line".to_string(),
@@ -776,6 +2222,8 @@ fn create_read_mcp_resource_tool() -> ToolSpec {
 #[derive(Serialize, Deserialize)]
 pub(crate) struct ApplyPatchToolArgs {
     pub(crate) input: String,
+    #[serde(default)]
+    pub(crate) dry_run: bool,
 }
 
 /// Returns JSON values that are compatible with Function Calling in the
@@ -859,6 +2307,42 @@ pub(crate) fn mcp_tool_to_openai_tool(
     })
 }
 
+/// Publish the schema of a tool's JSON output to the model. Function tools
+/// have no field for it, so it goes at the end of the description.
+fn with_output_schema(spec: ToolSpec, schema: Option<&impl Serialize>) -> ToolSpec {
+    let Some(schema) = schema.and_then(|schema| serde_json::to_string(schema).ok()) else {
+        return spec;
+    };
+    match spec {
+        ToolSpec::Function(mut tool) => {
+            tool.description.push_str(&format!(
+                "\n\nThe output is JSON matching this schema: {schema}"
+            ));
+            ToolSpec::Function(tool)
+        }
+        spec => spec,
+    }
+}
+
+/// The function tool definition of an embedder's custom tool. Its schema is
+/// sanitized like those of MCP tools.
+fn custom_tool_to_openai_tool(
+    tool: &CustomToolSpec,
+) -> Result<ResponsesApiTool, serde_json::Error> {
+    let mut input_schema = tool.input_schema.clone();
+    if let JsonValue::Object(map) = &mut input_schema {
+        map.entry("properties")
+            .or_insert_with(|| JsonValue::Object(serde_json::Map::new()));
+    }
+    sanitize_json_schema(&mut input_schema);
+    Ok(ResponsesApiTool {
+        name: tool.name.clone(),
+        description: tool.description.clone(),
+        strict: false,
+        parameters: serde_json::from_value::<JsonSchema>(input_schema)?,
+    })
+}
+
 /// Sanitize a JSON Schema (as serde_json::Value) so it can fit our limited
 /// JsonSchema enum. This function:
 /// - Ensures every schema object has a "type". If missing, infers it from
@@ -976,17 +2460,44 @@ pub(crate) fn build_specs(
     mcp_tools: Option<HashMap<String, mcp_types::Tool>>,
 ) -> ToolRegistryBuilder {
     use crate::tools::handlers::ApplyPatchHandler;
+    use crate::tools::handlers::ArtifactsHandler;
+    use crate::tools::handlers::BackgroundJobsHandler;
+    use crate::tools::handlers::BenchHandler;
+    use crate::tools::handlers::ClipboardHandler;
+    use crate::tools::handlers::CodemodHandler;
+    use crate::tools::handlers::ComposeHandler;
+    use crate::tools::handlers::CoverageHandler;
+    use crate::tools::handlers::CustomToolHandler;
+    use crate::tools::handlers::EnvSnapshotHandler;
+    use crate::tools::handlers::FileInfoHandler;
+    use crate::tools::handlers::FindReplaceHandler;
+    use crate::tools::handlers::GitHandler;
     use crate::tools::handlers::GrepFilesHandler;
     use crate::tools::handlers::ListDirHandler;
     use crate::tools::handlers::McpHandler;
     use crate::tools::handlers::McpResourceHandler;
+    use crate::tools::handlers::MigrateHandler;
     use crate::tools::handlers::PlanHandler;
+    use crate::tools::handlers::PortHandler;
+    use crate::tools::handlers::ProcessHandler;
+    use crate::tools::handlers::ReadChangesHandler;
     use crate::tools::handlers::ReadFileHandler;
+    use crate::tools::handlers::ScaffoldHandler;
+    use crate::tools::handlers::SearchHandler;
     use crate::tools::handlers::ShellCommandHandler;
     use crate::tools::handlers::ShellHandler;
+    use crate::tools::handlers::TestRunnerHandler;
     use crate::tools::handlers::TestSyncHandler;
+    use crate::tools::handlers::TrashHandler;
+    use crate::tools::handlers::UndoPatchHandler;
     use crate::tools::handlers::UnifiedExecHandler;
     use crate::tools::handlers::ViewImageHandler;
+    use crate::tools::handlers::WaitForHandler;
+    use crate::tools::handlers::WebFetchHandler;
+    use crate::tools::handlers::WriteFileHandler;
+    use crate::tools::runtimes::git::GitRuntime;
+    use crate::tools::runtimes::search::SearchRuntime;
+    use crate::tools::sandboxing::ToolRuntime;
 
     let mut builder = ToolRegistryBuilder::new();
 
@@ -1001,7 +2512,10 @@ pub(crate) fn build_specs(
 
     match &config.shell_type {
         ConfigShellToolType::Default => {
-            builder.push_spec(create_shell_tool());
+            builder.push_spec(create_shell_tool(
+                config.include_background_jobs,
+                config.include_command_network,
+            ));
         }
         ConfigShellToolType::Local => {
             builder.push_spec(ToolSpec::LocalShell {});
@@ -1016,7 +2530,10 @@ pub(crate) fn build_specs(
             // Do nothing.
         }
         ConfigShellToolType::ShellCommand => {
-            builder.push_spec(create_shell_command_tool());
+            builder.push_spec(create_shell_command_tool(
+                config.include_background_jobs,
+                config.include_command_network,
+            ));
         }
     }
 
@@ -1028,6 +2545,16 @@ pub(crate) fn build_specs(
         builder.register_handler("shell_command", shell_command_handler);
     }
 
+    if config.include_background_jobs {
+        let background_jobs_handler = Arc::new(BackgroundJobsHandler);
+        builder.push_spec_with_parallel_support(create_job_status_tool(), true);
+        builder.push_spec_with_parallel_support(create_job_output_tool(), true);
+        builder.push_spec(create_job_kill_tool());
+        builder.register_handler("job_status", background_jobs_handler.clone());
+        builder.register_handler("job_output", background_jobs_handler.clone());
+        builder.register_handler("job_kill", background_jobs_handler);
+    }
+
     builder.push_spec_with_parallel_support(create_list_mcp_resources_tool(), true);
     builder.push_spec_with_parallel_support(create_list_mcp_resource_templates_tool(), true);
     builder.push_spec_with_parallel_support(create_read_mcp_resource_tool(), true);
@@ -1099,6 +2626,152 @@ pub(crate) fn build_specs(
         builder.register_handler("view_image", view_image_handler);
     }
 
+    if config.include_coverage_tool {
+        let coverage_handler = Arc::new(CoverageHandler);
+        builder.push_spec(create_coverage_tool());
+        builder.register_handler("coverage", coverage_handler);
+    }
+
+    if config.include_test_runner_tool {
+        let test_runner_handler = Arc::new(TestRunnerHandler);
+        builder.push_spec(create_run_tests_tool());
+        builder.register_handler("run_tests", test_runner_handler);
+    }
+
+    if config.include_bench_tool {
+        let bench_handler = Arc::new(BenchHandler);
+        builder.push_spec(create_bench_tool());
+        builder.register_handler("bench", bench_handler);
+    }
+
+    if config.include_process_tool {
+        let process_handler = Arc::new(ProcessHandler);
+        builder.push_spec(create_process_tool());
+        builder.register_handler("process", process_handler);
+    }
+
+    if config.include_port_tool {
+        let port_handler = Arc::new(PortHandler);
+        builder.push_spec_with_parallel_support(create_port_tool(), true);
+        builder.register_handler("port", port_handler);
+    }
+
+    if config.include_wait_for_tool {
+        let wait_for_handler = Arc::new(WaitForHandler);
+        builder.push_spec_with_parallel_support(create_wait_for_tool(), true);
+        builder.register_handler("wait_for", wait_for_handler);
+    }
+
+    if config.include_compose_tool {
+        let compose_handler = Arc::new(ComposeHandler);
+        builder.push_spec(create_compose_tool());
+        builder.register_handler("compose", compose_handler);
+    }
+
+    if config.include_env_snapshot_tool {
+        let env_snapshot_handler = Arc::new(EnvSnapshotHandler);
+        builder.push_spec_with_parallel_support(create_env_snapshot_tool(), true);
+        builder.register_handler("env_snapshot", env_snapshot_handler);
+    }
+
+    if config.include_file_info_tool {
+        let file_info_handler = Arc::new(FileInfoHandler);
+        builder.push_spec_with_parallel_support(create_file_info_tool(), true);
+        builder.register_handler("file_info", file_info_handler);
+    }
+
+    if config.include_find_replace_tool {
+        let find_replace_handler = Arc::new(FindReplaceHandler);
+        builder.push_spec(create_find_replace_tool());
+        builder.register_handler("find_replace", find_replace_handler);
+    }
+
+    if config.include_scaffold_tool {
+        let scaffold_handler = Arc::new(ScaffoldHandler);
+        builder.push_spec(create_scaffold_tool());
+        builder.register_handler("scaffold", scaffold_handler);
+    }
+
+    if config.include_codemod_tool {
+        let codemod_handler = Arc::new(CodemodHandler);
+        builder.push_spec(create_codemod_tool());
+        builder.register_handler("codemod", codemod_handler);
+    }
+
+    if config.include_migrate_tool {
+        let migrate_handler = Arc::new(MigrateHandler);
+        builder.push_spec(create_migrate_tool());
+        builder.register_handler("migrate", migrate_handler);
+    }
+
+    if config.include_artifacts_tool {
+        let artifacts_handler = Arc::new(ArtifactsHandler);
+        builder.push_spec(create_artifacts_tool());
+        builder.register_handler("artifacts", artifacts_handler);
+    }
+
+    if config.include_trash_tool {
+        let trash_handler = Arc::new(TrashHandler);
+        builder.push_spec(create_trash_tool());
+        builder.register_handler("trash", trash_handler);
+    }
+
+    if config.include_undo_patch_tool {
+        let undo_patch_handler = Arc::new(UndoPatchHandler);
+        builder.push_spec(create_undo_patch_tool());
+        builder.register_handler("undo_patch", undo_patch_handler);
+    }
+
+    if config.include_clipboard_tool {
+        let clipboard_handler = Arc::new(ClipboardHandler);
+        builder.push_spec(create_clipboard_tool());
+        builder.register_handler("clipboard", clipboard_handler);
+    }
+
+    if config.include_read_changes_tool {
+        let read_changes_handler = Arc::new(ReadChangesHandler);
+        builder.push_spec(create_read_changes_tool());
+        builder.register_handler("read_changes", read_changes_handler);
+    }
+
+    if config.include_write_file_tool {
+        let write_file_handler = Arc::new(WriteFileHandler);
+        builder.push_spec(create_write_file_tool());
+        builder.register_handler("write_file", write_file_handler);
+    }
+
+    if config.include_git_tool {
+        let git_handler = Arc::new(GitHandler);
+        let output_schema = config
+            .include_output_schemas
+            .then(|| GitRuntime::new().output_schema())
+            .flatten();
+        builder.push_spec_with_parallel_support(
+            with_output_schema(create_git_tool(), output_schema.as_ref()),
+            true,
+        );
+        builder.register_handler("git", git_handler);
+    }
+
+    if config.include_web_fetch_tool {
+        let web_fetch_handler = Arc::new(WebFetchHandler);
+        builder.push_spec_with_parallel_support(create_web_fetch_tool(), true);
+        builder.register_handler("web_fetch", web_fetch_handler);
+    }
+
+    if config.include_search_tool {
+        let search_handler = Arc::new(SearchHandler);
+        let output_schema = config
+            .include_output_schemas
+            .then(|| SearchRuntime::new().output_schema())
+            .flatten();
+        builder.push_spec_with_parallel_support(
+            with_output_schema(create_search_tool(), output_schema.as_ref()),
+            true,
+        );
+        builder.register_handler("search", search_handler);
+    }
+
     if let Some(mcp_tools) = mcp_tools {
         let mut entries: Vec<(String, mcp_types::Tool)> = mcp_tools.into_iter().collect();
         entries.sort_by(|a, b| a.0.cmp(&b.0));
@@ -1106,7 +2779,14 @@ pub(crate) fn build_specs(
         for (name, tool) in entries.into_iter() {
             match mcp_tool_to_openai_tool(name.clone(), tool.clone()) {
                 Ok(converted_tool) => {
-                    builder.push_spec(ToolSpec::Function(converted_tool));
+                    let output_schema = tool
+                        .output_schema
+                        .as_ref()
+                        .filter(|_| config.include_output_schemas);
+                    builder.push_spec(with_output_schema(
+                        ToolSpec::Function(converted_tool),
+                        output_schema,
+                    ));
                     builder.register_handler(name, mcp_handler.clone());
                 }
                 Err(e) => {
@@ -1116,6 +2796,26 @@ pub(crate) fn build_specs(
         }
     }
 
+    let custom_tool_handler = Arc::new(CustomToolHandler);
+    for tool in &config.custom_tools {
+        if builder.has_handler(&tool.name) {
+            tracing::warn!(
+                "custom tool {} clashes with another tool; skipping it",
+                tool.name
+            );
+            continue;
+        }
+        match custom_tool_to_openai_tool(tool) {
+            Ok(converted_tool) => {
+                builder.push_spec(ToolSpec::Function(converted_tool));
+                builder.register_handler(tool.name.clone(), custom_tool_handler.clone());
+            }
+            Err(e) => {
+                tracing::error!("Failed to convert custom tool {:?}: {e:?}", tool.name);
+            }
+        }
+    }
+
     builder
 }
 
@@ -1144,6 +2844,7 @@ mod tests {
     use crate::model_family::find_family_for_model;
     use crate::tools::registry::ConfiguredToolSpec;
     use mcp_types::ToolInputSchema;
+    use mcp_types::ToolOutputSchema;
     use pretty_assertions::assert_eq;
 
     use super::*;
@@ -1652,6 +3353,109 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_build_specs_custom_tools_converted() {
+        let model_family = find_family_for_model("o3").expect("o3 should be a valid model family");
+        let features = Features::with_defaults();
+        let mut config = ToolsConfig::new(&ToolsConfigParams {
+            model_family: &model_family,
+            features: &features,
+        });
+        config.custom_tools = vec![
+            CustomToolSpec {
+                name: "deploy".to_string(),
+                description: "Deploy the app".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {"env": {"type": "string"}},
+                    "required": ["env"],
+                }),
+            },
+            // Clashes with a built-in tool, so it is left out.
+            CustomToolSpec {
+                name: "update_plan".to_string(),
+                description: "Not the real planner".to_string(),
+                input_schema: json!({"type": "object"}),
+            },
+        ];
+
+        let (tools, _) = build_specs(&config, None).build();
+
+        assert_eq!(
+            &find_tool(&tools, "deploy").spec,
+            &ToolSpec::Function(ResponsesApiTool {
+                name: "deploy".to_string(),
+                description: "Deploy the app".to_string(),
+                strict: false,
+                parameters: JsonSchema::Object {
+                    properties: BTreeMap::from([(
+                        "env".to_string(),
+                        JsonSchema::String { description: None },
+                    )]),
+                    required: Some(vec!["env".to_string()]),
+                    additional_properties: None,
+                },
+            })
+        );
+        let plans = tools
+            .iter()
+            .filter(|tool| tool_name(&tool.spec) == "update_plan")
+            .count();
+        assert_eq!(plans, 1);
+    }
+
+    #[test]
+    fn test_build_specs_publishes_mcp_output_schema() {
+        let model_family = find_family_for_model("o3").expect("o3 should be a valid model family");
+        let mut features = Features::with_defaults();
+        features.enable(Feature::ToolOutputSchemas);
+        let config = ToolsConfig::new(&ToolsConfigParams {
+            model_family: &model_family,
+            features: &features,
+        });
+        let (tools, _) = build_specs(
+            &config,
+            Some(HashMap::from([(
+                "users/lookup".to_string(),
+                mcp_types::Tool {
+                    name: "lookup".to_string(),
+                    input_schema: ToolInputSchema {
+                        properties: None,
+                        required: None,
+                        r#type: "object".to_string(),
+                    },
+                    output_schema: Some(ToolOutputSchema {
+                        properties: Some(serde_json::json!({"id": {"type": "string"}})),
+                        required: Some(vec!["id".to_string()]),
+                        r#type: "object".to_string(),
+                    }),
+                    title: None,
+                    annotations: None,
+                    description: Some("Look up a user".to_string()),
+                },
+            )])),
+        )
+        .build();
+
+        let tool = find_tool(&tools, "users/lookup");
+        assert_eq!(
+            &tool.spec,
+            &ToolSpec::Function(ResponsesApiTool {
+                name: "users/lookup".to_string(),
+                description: "Look up a user\n\nThe output is JSON matching this schema: \
+                              {\"properties\":{\"id\":{\"type\":\"string\"}},\
+                              \"required\":[\"id\"],\"type\":\"object\"}"
+                    .to_string(),
+                strict: false,
+                parameters: JsonSchema::Object {
+                    properties: BTreeMap::new(),
+                    required: None,
+                    additional_properties: None,
+                },
+            })
+        );
+    }
+
     #[test]
     fn test_build_specs_mcp_tools_sorted_by_name() {
         let model_family = find_family_for_model("o3").expect("o3 should be a valid model family");
@@ -1949,7 +3753,7 @@ mod tests {
 
     #[test]
     fn test_shell_tool() {
-        let tool = super::create_shell_tool();
+        let tool = super::create_shell_tool(false, false);
         let ToolSpec::Function(ResponsesApiTool {
             description, name, ..
         }) = &tool
@@ -1979,7 +3783,7 @@ Examples of valid command strings:
 
     #[test]
     fn test_shell_command_tool() {
-        let tool = super::create_shell_command_tool();
+        let tool = super::create_shell_command_tool(false, false);
         let ToolSpec::Function(ResponsesApiTool {
             description, name, ..
         }) = &tool
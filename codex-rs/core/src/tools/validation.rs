@@ -0,0 +1,295 @@
+//! Checks the arguments of a function tool call against the tool's schema.
+//!
+//! Handlers parse arguments with serde, which reports the first problem in
+//! terms of Rust types, and accepts values that only fail later, such as a
+//! negative timeout or an empty path. With the `argument_validation` feature,
+//! the arguments are checked before the handler runs, against the declared
+//! [`JsonSchema`] (types, required and unknown properties) and against the
+//! constraints the built-in tools place on arguments that the schema cannot
+//! express: counts and durations are non-negative integers, paths are
+//! non-empty, and some strings take one of a few values. Every problem is
+//! reported to the model at once, by argument name.
+
+use serde_json::Value;
+
+use crate::tools::spec::AdditionalProperties;
+use crate::tools::spec::JsonSchema;
+
+/// String arguments that take one of a few values, as `(tool, argument
+/// path, values)`; `*` matches every tool.
+const ALLOWED_VALUES: &[(&str, &str, &[&str])] = &[
+    ("*", "network.mode", &["none", "loopback", "allowlist"]),
+    ("artifacts", "action", &["list", "send"]),
+    ("clipboard", "action", &["read", "write"]),
+    ("codemod", "engine", &["ast_grep", "comby"]),
+    ("compose", "action", &["up", "down", "ps", "logs"]),
+    ("git", "query", &["status", "diff", "log", "blame"]),
+    (
+        "coverage",
+        "tool",
+        &["cargo_llvm_cov", "coverage_py", "istanbul"],
+    ),
+    ("migrate", "action", &["plan", "dry_run", "apply"]),
+    ("migrate", "tool", &["diesel", "sqlx", "alembic", "prisma"]),
+    ("trash", "action", &["list", "restore"]),
+    ("web_fetch", "method", &["get", "head"]),
+];
+
+/// Number arguments with bounds other than those of a count.
+const RANGES: &[(&str, f64, f64)] = &[
+    ("port", 0.0, 65535.0),
+    ("status", 100.0, 599.0),
+    ("threshold_pct", 0.0, 100.0),
+];
+
+/// Number arguments that count or measure something.
+const COUNT_NAMES: &[&str] = &[
+    "base_seed",
+    "count",
+    "depth",
+    "limit",
+    "offset",
+    "pid",
+    "repeat",
+    "tail",
+];
+const COUNT_SUFFIXES: &[&str] = &[
+    "_id", "_levels", "_limit", "_line", "_lines", "_ms", "_offset", "_tokens",
+];
+
+/// String arguments that name a file or directory.
+const PATH_NAMES: &[&str] = &["cwd", "path", "workdir"];
+
+/// Check `arguments` for `tool` against `schema`. The error lists every
+/// problem found.
+pub(crate) fn validate_arguments(
+    tool: &str,
+    schema: &JsonSchema,
+    arguments: &str,
+) -> Result<(), String> {
+    let value: Value = serde_json::from_str(arguments)
+        .map_err(|err| format!("arguments for `{tool}` are not valid JSON: {err}"))?;
+    let mut problems = Vec::new();
+    check(tool, schema, &value, "", &mut problems);
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(format!(
+            "invalid arguments for `{tool}`: {}",
+            problems.join("; ")
+        ))
+    }
+}
+
+fn check(tool: &str, schema: &JsonSchema, value: &Value, path: &str, problems: &mut Vec<String>) {
+    let name = if path.is_empty() { "arguments" } else { path };
+    match (schema, value) {
+        (JsonSchema::Boolean { .. }, Value::Bool(_)) => {}
+        (JsonSchema::String { .. }, Value::String(text)) => {
+            check_string(tool, path, text, problems)
+        }
+        (JsonSchema::Number { .. }, Value::Number(number)) => {
+            if let Some(number) = number.as_f64() {
+                check_number(path, number, problems);
+            }
+        }
+        (JsonSchema::Array { items, .. }, Value::Array(values)) => {
+            for (index, item) in values.iter().enumerate() {
+                check(tool, items, item, &format!("{path}[{index}]"), problems);
+            }
+        }
+        (
+            JsonSchema::Object {
+                properties,
+                required,
+                additional_properties,
+            },
+            Value::Object(map),
+        ) => {
+            let join = |key: &str| {
+                if path.is_empty() {
+                    key.to_string()
+                } else {
+                    format!("{path}.{key}")
+                }
+            };
+            for key in required.iter().flatten() {
+                if map.get(key).is_none_or(Value::is_null) {
+                    problems.push(format!("`{}` is required", join(key)));
+                }
+            }
+            // Sorted, so that problems are listed in the same order whatever
+            // order the model wrote the arguments in.
+            let mut entries: Vec<(&String, &Value)> = map.iter().collect();
+            entries.sort_by_key(|(key, _)| *key);
+            for (key, item) in entries {
+                let schema = match (properties.get(key), additional_properties) {
+                    (Some(schema), _) => schema,
+                    (None, Some(AdditionalProperties::Schema(schema))) => schema,
+                    (None, Some(AdditionalProperties::Boolean(false))) => {
+                        problems.push(format!("`{}` is not a known argument", join(key)));
+                        continue;
+                    }
+                    (None, _) => continue,
+                };
+                // Optional arguments may be given as null.
+                if !item.is_null() {
+                    check(tool, schema, item, &join(key), problems);
+                }
+            }
+        }
+        (schema, value) => problems.push(format!(
+            "`{name}` must be {}, got {}",
+            expected(schema),
+            describe(value)
+        )),
+    }
+}
+
+fn check_string(tool: &str, path: &str, text: &str, problems: &mut Vec<String>) {
+    let allowed = ALLOWED_VALUES
+        .iter()
+        .find(|(name, argument, _)| (*name == "*" || *name == tool) && *argument == path);
+    if let Some((_, _, values)) = allowed
+        && !values.contains(&text)
+    {
+        let values: Vec<String> = values.iter().map(|value| format!("\"{value}\"")).collect();
+        problems.push(format!(
+            "`{path}` must be one of {}, got \"{text}\"",
+            values.join(", ")
+        ));
+    }
+    let name = last_segment(path);
+    if (PATH_NAMES.contains(&name) || name.ends_with("_path"))
+        && (text.trim().is_empty() || text.contains('\0'))
+    {
+        problems.push(format!("`{path}` must be a non-empty path"));
+    }
+}
+
+fn check_number(path: &str, number: f64, problems: &mut Vec<String>) {
+    let name = last_segment(path);
+    if let Some((_, min, max)) = RANGES.iter().find(|(range, _, _)| *range == name) {
+        if number.fract() != 0.0 || number < *min || number > *max {
+            problems.push(format!(
+                "`{path}` must be an integer from {min} to {max}, got {number}"
+            ));
+        }
+        return;
+    }
+    let is_count =
+        COUNT_NAMES.contains(&name) || COUNT_SUFFIXES.iter().any(|suffix| name.ends_with(suffix));
+    if is_count && (number.fract() != 0.0 || number < 0.0) {
+        problems.push(format!(
+            "`{path}` must be a non-negative integer, got {number}"
+        ));
+    }
+}
+
+/// The argument name at the end of `path`, without array indices.
+fn last_segment(path: &str) -> &str {
+    let name = path.rsplit('.').next().unwrap_or(path);
+    name.split('[').next().unwrap_or(name)
+}
+
+fn expected(schema: &JsonSchema) -> &'static str {
+    match schema {
+        JsonSchema::Boolean { .. } => "a boolean",
+        JsonSchema::String { .. } => "a string",
+        JsonSchema::Number { .. } => "a number",
+        JsonSchema::Array { .. } => "an array",
+        JsonSchema::Object { .. } => "an object",
+    }
+}
+
+fn describe(value: &Value) -> String {
+    match value {
+        Value::Null => "null".to_string(),
+        Value::Bool(value) => format!("boolean {value}"),
+        Value::Number(value) => format!("number {value}"),
+        Value::String(value) => format!("string {value:?}"),
+        Value::Array(_) => "an array".to_string(),
+        Value::Object(_) => "an object".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::collections::BTreeMap;
+
+    fn shell_schema() -> JsonSchema {
+        let network =
+            BTreeMap::from([("mode".to_string(), JsonSchema::String { description: None })]);
+        JsonSchema::Object {
+            properties: BTreeMap::from([
+                (
+                    "command".to_string(),
+                    JsonSchema::Array {
+                        items: Box::new(JsonSchema::String { description: None }),
+                        description: None,
+                    },
+                ),
+                (
+                    "workdir".to_string(),
+                    JsonSchema::String { description: None },
+                ),
+                (
+                    "timeout_ms".to_string(),
+                    JsonSchema::Number { description: None },
+                ),
+                (
+                    "network".to_string(),
+                    JsonSchema::Object {
+                        properties: network,
+                        required: Some(vec!["mode".to_string()]),
+                        additional_properties: Some(false.into()),
+                    },
+                ),
+            ]),
+            required: Some(vec!["command".to_string()]),
+            additional_properties: Some(false.into()),
+        }
+    }
+
+    #[test]
+    fn well_formed_arguments_pass() {
+        let arguments =
+            r#"{"command":["ls"],"workdir":null,"timeout_ms":1000,"network":{"mode":"loopback"}}"#;
+        assert_eq!(
+            validate_arguments("shell", &shell_schema(), arguments),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn every_problem_is_reported_by_argument_name() {
+        let arguments = serde_json::json!({
+            "command": ["ls", 3],
+            "workdir": "",
+            "timeout_ms": -5,
+            "network": {"mode": "wan"},
+            "sudo": true,
+        })
+        .to_string();
+        assert_eq!(
+            validate_arguments("shell", &shell_schema(), &arguments),
+            Err(
+                "invalid arguments for `shell`: `command[1]` must be a string, got number 3; \
+                 `network.mode` must be one of \"none\", \"loopback\", \"allowlist\", got \
+                 \"wan\"; `sudo` is not a known argument; `timeout_ms` must be a non-negative \
+                 integer, got -5; `workdir` must be a non-empty path"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            validate_arguments("shell", &shell_schema(), r#"{"timeout_ms":"10s"}"#),
+            Err(
+                "invalid arguments for `shell`: `command` is required; `timeout_ms` must be a \
+                 number, got string \"10s\""
+                    .to_string()
+            )
+        );
+    }
+}
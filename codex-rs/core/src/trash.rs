@@ -0,0 +1,173 @@
+//! Session trash for files deleted by patches.
+//!
+//! With the `trash` feature, a file that an `apply_patch` call deletes is
+//! first copied to `$CODEX_HOME/trash/<conversation id>/<entry id>/`, and the
+//! `trash` tool lists those copies and puts one back where it was. Copies are
+//! taken before the patch runs, because the patch itself runs in a sandboxed
+//! child process that cannot write under `$CODEX_HOME`; copies of files the
+//! patch did not end up deleting are dropped again afterwards.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_protocol::ConversationId;
+use serde::Deserialize;
+use serde::Serialize;
+use uuid::Uuid;
+
+const TRASH_DIR: &str = "trash";
+const ENTRY_FILE: &str = "entry.json";
+const CONTENTS_FILE: &str = "contents";
+
+/// Directory holding the trash of one conversation.
+pub(crate) fn trash_dir(codex_home: &Path, conversation_id: ConversationId) -> PathBuf {
+    codex_home.join(TRASH_DIR).join(conversation_id.to_string())
+}
+
+/// A deleted file kept in the trash.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct TrashEntry {
+    pub(crate) id: String,
+    /// Where the file was deleted from.
+    pub(crate) path: PathBuf,
+    pub(crate) size_bytes: u64,
+    /// RFC 3339 time the copy was taken.
+    pub(crate) deleted_at: String,
+}
+
+/// Copies each of `paths` into the trash under `root` ahead of its deletion.
+pub(crate) async fn keep_copies(root: &Path, paths: &[PathBuf]) -> Result<Vec<TrashEntry>, String> {
+    let mut entries = Vec::new();
+    for path in paths {
+        let metadata = tokio::fs::metadata(path)
+            .await
+            .map_err(|err| format!("cannot read {}: {err}", path.display()))?;
+        let entry = TrashEntry {
+            id: Uuid::new_v4().to_string(),
+            path: path.clone(),
+            size_bytes: metadata.len(),
+            deleted_at: chrono::Utc::now().to_rfc3339(),
+        };
+        let dir = root.join(&entry.id);
+        tokio::fs::create_dir_all(&dir)
+            .await
+            .map_err(|err| format!("failed to create {}: {err}", dir.display()))?;
+        tokio::fs::copy(path, dir.join(CONTENTS_FILE))
+            .await
+            .map_err(|err| format!("failed to copy {} to the trash: {err}", path.display()))?;
+        let json = serde_json::to_vec_pretty(&entry).map_err(|err| err.to_string())?;
+        tokio::fs::write(dir.join(ENTRY_FILE), json)
+            .await
+            .map_err(|err| format!("failed to record {} in the trash: {err}", path.display()))?;
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+/// Drops the copies of files that still exist, such as when the patch that
+/// was to delete them failed.
+pub(crate) async fn drop_undeleted(root: &Path, entries: &[TrashEntry]) {
+    for entry in entries {
+        if tokio::fs::try_exists(&entry.path).await.unwrap_or(true)
+            && let Err(err) = tokio::fs::remove_dir_all(root.join(&entry.id)).await
+        {
+            tracing::warn!("failed to drop trash entry {}: {err}", entry.id);
+        }
+    }
+}
+
+/// The files in the trash under `root`, most recently deleted first.
+pub(crate) async fn list_trash(root: &Path) -> Vec<TrashEntry> {
+    let mut entries = Vec::new();
+    let Ok(mut dirs) = tokio::fs::read_dir(root).await else {
+        return entries;
+    };
+    while let Ok(Some(dir)) = dirs.next_entry().await {
+        let Ok(json) = tokio::fs::read(dir.path().join(ENTRY_FILE)).await else {
+            continue;
+        };
+        match serde_json::from_slice::<TrashEntry>(&json) {
+            Ok(entry) => entries.push(entry),
+            Err(err) => tracing::warn!("ignoring unreadable trash entry: {err}"),
+        }
+    }
+    entries.sort_by(|a, b| b.deleted_at.cmp(&a.deleted_at));
+    entries
+}
+
+/// The most recently deleted copy of `path`.
+pub(crate) async fn find_in_trash(root: &Path, path: &Path) -> Option<TrashEntry> {
+    list_trash(root)
+        .await
+        .into_iter()
+        .find(|entry| entry.path == path)
+}
+
+/// Puts `entry` back where it was deleted from and removes it from the
+/// trash. A file that has since been created at that path is not replaced.
+pub(crate) async fn restore_from_trash(root: &Path, entry: &TrashEntry) -> Result<(), String> {
+    let path = &entry.path;
+    if tokio::fs::try_exists(path).await.unwrap_or(true) {
+        return Err(format!(
+            "{} exists again; move it aside before restoring the deleted copy",
+            path.display()
+        ));
+    }
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|err| format!("failed to create {}: {err}", parent.display()))?;
+    }
+    let dir = root.join(&entry.id);
+    tokio::fs::copy(dir.join(CONTENTS_FILE), path)
+        .await
+        .map_err(|err| format!("failed to restore {}: {err}", path.display()))?;
+    if let Err(err) = tokio::fs::remove_dir_all(&dir).await {
+        tracing::warn!("failed to drop restored trash entry {}: {err}", entry.id);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn deleted_files_can_be_restored() {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path().join("trash");
+        let kept = dir.path().join("src/notes.txt");
+        let gone = dir.path().join("src/old.txt");
+        std::fs::create_dir_all(dir.path().join("src")).expect("mkdir");
+        std::fs::write(&kept, "keep").expect("write");
+        std::fs::write(&gone, "old contents").expect("write");
+
+        let entries = keep_copies(&root, &[kept.clone(), gone.clone()])
+            .await
+            .expect("copy");
+        std::fs::remove_file(&gone).expect("delete");
+        drop_undeleted(&root, &entries).await;
+
+        let entry = find_in_trash(&root, &gone).await.expect("trashed");
+        assert_eq!(list_trash(&root).await, vec![entry.clone()]);
+        assert_eq!(entry.size_bytes, 12);
+
+        std::fs::write(&gone, "new").expect("write");
+        assert_eq!(
+            restore_from_trash(&root, &entry).await,
+            Err(format!(
+                "{} exists again; move it aside before restoring the deleted copy",
+                gone.display()
+            ))
+        );
+        std::fs::remove_file(&gone).expect("delete");
+        restore_from_trash(&root, &entry).await.expect("restore");
+        assert_eq!(
+            std::fs::read_to_string(&gone).expect("read"),
+            "old contents"
+        );
+        assert_eq!(list_trash(&root).await, vec![]);
+    }
+}
@@ -216,6 +216,20 @@ fn truncate_with_byte_estimate(s: &str, policy: TruncationPolicy) -> String {
     assemble_truncated_output(left, right, &marker)
 }
 
+/// Keep the first `head` and the last `tail` bytes of `content`, on UTF-8
+/// boundaries, with a marker counting the characters removed in between.
+pub(crate) fn truncate_head_tail(content: &str, head: usize, tail: usize) -> String {
+    if content.len() <= head.saturating_add(tail) {
+        return content.to_string();
+    }
+    let (removed_chars, left, right) = split_string(content, head, tail);
+    let marker = format_truncation_marker(
+        TruncationPolicy::Bytes(head + tail),
+        u64::try_from(removed_chars).unwrap_or(u64::MAX),
+    );
+    assemble_truncated_output(left, right, &marker)
+}
+
 fn split_string(s: &str, beginning_bytes: usize, end_bytes: usize) -> (usize, &str, &str) {
     if s.is_empty() {
         return (0, "", "");
@@ -310,6 +324,7 @@ mod tests {
     use super::formatted_truncate_text;
     use super::split_string;
     use super::truncate_function_output_items_with_policy;
+    use super::truncate_head_tail;
     use super::truncate_text;
     use super::truncate_with_token_budget;
     use codex_protocol::models::FunctionCallOutputContentItem;
@@ -341,6 +356,16 @@ mod tests {
         assert_eq!(split_string("abcdef", 4, 4), (0, "abcd", "ef"));
     }
 
+    #[test]
+    fn truncate_head_tail_keeps_uneven_windows() {
+        let content = "first line\n".to_string() + &"x".repeat(100) + "\nlast line";
+        assert_eq!(
+            truncate_head_tail(&content, 11, 10),
+            "first line\n…100 chars truncated…\nlast line"
+        );
+        assert_eq!(truncate_head_tail("short", 2, 3), "short");
+    }
+
     #[test]
     fn split_string_respects_utf8_boundaries() {
         assert_eq!(split_string("😀abc😀", 5, 5), (1, "😀a", "c😀"));
@@ -86,6 +86,8 @@ pub(crate) struct WriteStdinRequest<'a> {
 pub(crate) struct UnifiedExecResponse {
     pub event_call_id: String,
     pub chunk_id: String,
+    /// Orders `output` among the output deltas of `event_call_id`.
+    pub output_seq: u64,
     pub wall_time: Duration,
     pub output: String,
     pub session_id: Option<i32>,
@@ -2,6 +2,8 @@
 
 use std::collections::VecDeque;
 use std::sync::Arc;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
 use tokio::sync::Mutex;
 use tokio::sync::Notify;
 use tokio::sync::mpsc;
@@ -11,6 +13,7 @@ use tokio::time::Duration;
 use tokio_util::sync::CancellationToken;
 
 use crate::exec::ExecToolCallOutput;
+use crate::exec::ExitReason;
 use crate::exec::SandboxType;
 use crate::exec::StreamOutput;
 use crate::exec::is_likely_sandbox_denied;
@@ -81,6 +84,8 @@ pub(crate) struct UnifiedExecSession {
     cancellation_token: CancellationToken,
     output_task: JoinHandle<()>,
     sandbox_type: SandboxType,
+    /// Sequence number of the next output delta sent for the session.
+    output_seq: AtomicU64,
 }
 
 impl UnifiedExecSession {
@@ -121,9 +126,15 @@ impl UnifiedExecSession {
             cancellation_token,
             output_task,
             sandbox_type,
+            output_seq: AtomicU64::new(0),
         }
     }
 
+    /// The sequence number for the next chunk of output sent as a delta.
+    pub(super) fn next_output_seq(&self) -> u64 {
+        self.output_seq.fetch_add(1, Ordering::Relaxed)
+    }
+
     pub(super) fn writer_sender(&self) -> mpsc::Sender<Vec<u8>> {
         self.session.writer_sender()
     }
@@ -175,7 +186,8 @@ impl UnifiedExecSession {
             stderr: StreamOutput::new(String::new()),
             aggregated_output: StreamOutput::new(aggregated_text.clone()),
             duration: Duration::ZERO,
-            timed_out: false,
+            full_output_path: None,
+            exit_reason: ExitReason::from_exit_code(exit_code),
         };
 
         if is_likely_sandbox_denied(self.sandbox_type(), &exec_output) {
@@ -188,6 +200,10 @@ impl UnifiedExecSession {
             } else {
                 snippet
             };
+            let exec_output = ExecToolCallOutput {
+                exit_reason: ExitReason::SandboxDenied,
+                ..exec_output
+            };
             return Err(UnifiedExecError::sandbox_denied(message, exec_output));
         }
 
@@ -9,7 +9,10 @@ use tokio_util::sync::CancellationToken;
 
 use crate::codex::Session;
 use crate::codex::TurnContext;
+use crate::command_safety::privilege_escalation::password_prompt_note;
+use crate::command_safety::privilege_escalation::trailing_password_prompt;
 use crate::exec::ExecToolCallOutput;
+use crate::exec::ExitReason;
 use crate::exec::StreamOutput;
 use crate::exec_env::create_env;
 use crate::exec_policy::create_approval_requirement_for_command;
@@ -53,6 +56,7 @@ struct PreparedSessionHandles {
     turn_ref: Arc<TurnContext>,
     command: Vec<String>,
     cwd: PathBuf,
+    output_seq: u64,
 }
 
 impl UnifiedExecSessionManager {
@@ -96,9 +100,13 @@ impl UnifiedExecSessionManager {
         let wall_time = Instant::now().saturating_duration_since(start);
 
         let text = String::from_utf8_lossy(&collected).to_string();
-        let output = formatted_truncate_text(&text, TruncationPolicy::Tokens(max_tokens));
+        let mut output = formatted_truncate_text(&text, TruncationPolicy::Tokens(max_tokens));
         let chunk_id = generate_chunk_id();
+        let output_seq = session.next_output_seq();
         let has_exited = session.has_exited();
+        if !has_exited && let Some(note) = password_prompt_note(&text) {
+            output.push_str(&note);
+        }
         let stored_id = self
             .store_session(session, context, &request.command, cwd.clone(), start)
             .await;
@@ -116,6 +124,7 @@ impl UnifiedExecSessionManager {
         let response = UnifiedExecResponse {
             event_call_id: context.call_id.clone(),
             chunk_id,
+            output_seq,
             wall_time,
             output,
             session_id,
@@ -160,6 +169,7 @@ impl UnifiedExecSessionManager {
             turn_ref,
             command: session_command,
             cwd: session_cwd,
+            output_seq,
         } = self.prepare_session_handles(session_id).await?;
 
         let interaction_emitter = ToolEmitter::unified_exec(
@@ -209,11 +219,16 @@ impl UnifiedExecSessionManager {
         let wall_time = Instant::now().saturating_duration_since(start);
 
         let text = String::from_utf8_lossy(&collected).to_string();
-        let output = formatted_truncate_text(&text, TruncationPolicy::Tokens(max_tokens));
+        let mut output = formatted_truncate_text(&text, TruncationPolicy::Tokens(max_tokens));
         let original_token_count = approx_token_count(&text);
         let chunk_id = generate_chunk_id();
 
         let status = self.refresh_session_state(session_id).await;
+        if matches!(status, SessionStatus::Alive { .. })
+            && let Some(note) = password_prompt_note(&text)
+        {
+            output.push_str(&note);
+        }
         let (session_id, exit_code, completion_entry, event_call_id) = match status {
             SessionStatus::Alive { exit_code, call_id } => {
                 (Some(session_id), exit_code, None, call_id)
@@ -230,6 +245,7 @@ impl UnifiedExecSessionManager {
         let response = UnifiedExecResponse {
             event_call_id,
             chunk_id,
+            output_seq,
             wall_time,
             output,
             session_id,
@@ -244,7 +260,8 @@ impl UnifiedExecSessionManager {
             stderr: StreamOutput::new(String::new()),
             aggregated_output: StreamOutput::new(response.output.clone()),
             duration: response.wall_time,
-            timed_out: false,
+            full_output_path: None,
+            exit_reason: ExitReason::from_exit_code(response.exit_code.unwrap_or(0)),
         };
         interaction_emitter
             .emit(
@@ -313,6 +330,7 @@ impl UnifiedExecSessionManager {
             turn_ref: Arc::clone(&entry.turn_ref),
             command: entry.command.clone(),
             cwd: entry.cwd.clone(),
+            output_seq: entry.session.next_output_seq(),
         })
     }
 
@@ -362,7 +380,8 @@ impl UnifiedExecSessionManager {
             stderr: StreamOutput::new(String::new()),
             aggregated_output: StreamOutput::new(aggregated_output),
             duration,
-            timed_out: false,
+            full_output_path: None,
+            exit_reason: ExitReason::from_exit_code(exit_code),
         };
         let event_ctx = ToolEventCtx::new(
             entry.session_ref.as_ref(),
@@ -395,7 +414,8 @@ impl UnifiedExecSessionManager {
             stderr: StreamOutput::new(String::new()),
             aggregated_output: StreamOutput::new(aggregated_output),
             duration,
-            timed_out: false,
+            full_output_path: None,
+            exit_reason: ExitReason::from_exit_code(exit_code),
         };
         let event_ctx = ToolEventCtx::new(
             context.session.as_ref(),
@@ -456,19 +476,20 @@ impl UnifiedExecSessionManager {
     ) -> Result<UnifiedExecSession, UnifiedExecError> {
         let mut orchestrator = ToolOrchestrator::new();
         let mut runtime = UnifiedExecRuntime::new(self);
+        let approval_requirement = create_approval_requirement_for_command(
+            &context.turn.exec_policy,
+            command,
+            context.turn.approval_policy,
+            &context.turn.sandbox_policy,
+            SandboxPermissions::from(with_escalated_permissions.unwrap_or(false)),
+        );
         let req = UnifiedExecToolRequest::new(
             command.to_vec(),
             cwd,
             create_env(&context.turn.shell_environment_policy),
             with_escalated_permissions,
             justification,
-            create_approval_requirement_for_command(
-                &context.turn.exec_policy,
-                command,
-                context.turn.approval_policy,
-                &context.turn.sandbox_policy,
-                SandboxPermissions::from(with_escalated_permissions.unwrap_or(false)),
-            ),
+            approval_requirement,
         );
         let tool_ctx = ToolCtx {
             session: context.session.as_ref(),
@@ -479,7 +500,7 @@ impl UnifiedExecSessionManager {
         orchestrator
             .run(
                 &mut runtime,
-                &req,
+                Arc::new(req),
                 &tool_ctx,
                 context.turn.as_ref(),
                 context.turn.approval_policy,
@@ -495,6 +516,7 @@ impl UnifiedExecSessionManager {
         deadline: Instant,
     ) -> Vec<u8> {
         const POST_EXIT_OUTPUT_GRACE: Duration = Duration::from_millis(25);
+        const PROMPT_TAIL_BYTES: usize = 256;
 
         let mut collected: Vec<u8> = Vec::with_capacity(4096);
         let mut exit_signal_received = cancellation_token.is_cancelled();
@@ -540,6 +562,13 @@ impl UnifiedExecSessionManager {
                 collected.extend_from_slice(&chunk);
             }
 
+            // Nothing more arrives until someone types the password, so
+            // return instead of waiting out the deadline.
+            let tail = &collected[collected.len().saturating_sub(PROMPT_TAIL_BYTES)..];
+            if trailing_password_prompt(&String::from_utf8_lossy(tail)).is_some() {
+                break;
+            }
+
             exit_signal_received |= cancellation_token.is_cancelled();
             if Instant::now() >= deadline {
                 break;
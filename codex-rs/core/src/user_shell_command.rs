@@ -69,6 +69,7 @@ pub fn user_shell_command_record_item(
 mod tests {
     use super::*;
     use crate::codex::make_session_and_context;
+    use crate::exec::ExitReason;
     use crate::exec::StreamOutput;
     use pretty_assertions::assert_eq;
 
@@ -88,7 +89,8 @@ mod tests {
             stderr: StreamOutput::new(String::new()),
             aggregated_output: StreamOutput::new("hi".to_string()),
             duration: Duration::from_secs(1),
-            timed_out: false,
+            full_output_path: None,
+            exit_reason: ExitReason::Success,
         };
         let (_, turn_context) = make_session_and_context();
         let item = user_shell_command_record_item("echo hi", &exec_output, &turn_context);
@@ -112,7 +114,8 @@ mod tests {
             stderr: StreamOutput::new("stderr-only".to_string()),
             aggregated_output: StreamOutput::new("combined output wins".to_string()),
             duration: Duration::from_millis(120),
-            timed_out: false,
+            full_output_path: None,
+            exit_reason: ExitReason::NonZeroExit,
         };
         let (_, turn_context) = make_session_and_context();
         let record = format_user_shell_command_record("false", &exec_output, &turn_context);
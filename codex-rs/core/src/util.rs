@@ -1,6 +1,10 @@
+use std::io;
+use std::path::Path;
 use std::time::Duration;
 
 use rand::Rng;
+use sha2::Digest;
+use sha2::Sha256;
 use tracing::debug;
 use tracing::error;
 
@@ -37,6 +41,27 @@ pub(crate) fn try_parse_error_message(text: &str) -> String {
     text.to_string()
 }
 
+/// The SHA-256 digest of `contents` as lowercase hex.
+pub(crate) fn sha256_hex(contents: &[u8]) -> String {
+    Sha256::digest(contents)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// Makes the file at `path` executable by everyone. A no-op where files have
+/// no executable bit.
+#[cfg(unix)]
+pub(crate) async fn set_executable(path: &Path) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    tokio::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755)).await
+}
+
+#[cfg(not(unix))]
+pub(crate) async fn set_executable(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
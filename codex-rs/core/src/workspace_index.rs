@@ -0,0 +1,354 @@
+//! Content hashes of the files in the workspace, kept current incrementally.
+//!
+//! Features that need to know whether anything changed since some earlier
+//! point share the session's [`WorkspaceIndex`] instead of each re-hashing
+//! the tree. The index keeps the size, modification time and SHA-256 of every
+//! file under the workspace root. A file watcher records the paths that
+//! change, and a refresh re-hashes only those; when no watcher could be
+//! started, or it reports having missed events, the refresh walks the tree and
+//! re-hashes only the files whose size or modification time changed.
+//!
+//! With the `workspace_index` feature, the session takes a snapshot at the end
+//! of every turn, and the next turn starts with a note listing the files the
+//! model read that changed in between.
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::PoisonError;
+use std::time::SystemTime;
+
+use codex_protocol::models::ContentItem;
+use codex_protocol::models::ResponseItem;
+use notify::RecommendedWatcher;
+use notify::RecursiveMode;
+use notify::Watcher;
+use sha2::Digest;
+use sha2::Sha256;
+use tracing::debug;
+use walkdir::WalkDir;
+
+use crate::blocking_io::run_blocking;
+use crate::dirty_files::FileDigest;
+
+/// Directories that are never indexed; they are large, churn constantly and
+/// are rebuilt from the rest of the tree.
+const SKIPPED_DIRS: [&str; 3] = [".git", "node_modules", "target"];
+
+/// Bound the index so that a huge checkout does not stall the session.
+const MAX_INDEXED_FILES: usize = 200_000;
+
+/// Opens the note listing files that changed after the model read them.
+pub(crate) const STALE_FILES_OPEN_TAG: &str = "<stale_files>";
+
+/// What a file looked like the last time it was hashed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Stamp {
+    len: u64,
+    modified: Option<SystemTime>,
+}
+
+impl Stamp {
+    fn of(metadata: &std::fs::Metadata) -> Self {
+        Self {
+            len: metadata.len(),
+            modified: metadata.modified().ok(),
+        }
+    }
+}
+
+/// Paths the watcher reported since the last refresh.
+#[derive(Debug, Default)]
+struct Changes {
+    paths: HashSet<PathBuf>,
+    /// The watcher missed events; only a full walk is accurate.
+    rescan: bool,
+}
+
+#[derive(Debug, Default)]
+struct IndexState {
+    stamps: HashMap<PathBuf, Stamp>,
+    digests: Arc<HashMap<PathBuf, FileDigest>>,
+    walked: bool,
+}
+
+/// The content hash of every file under one root.
+pub(crate) struct WorkspaceIndex {
+    root: PathBuf,
+    state: Mutex<IndexState>,
+    changes: Arc<Mutex<Changes>>,
+    /// `None` when watching failed, in which case every refresh walks.
+    watcher: Option<RecommendedWatcher>,
+}
+
+/// The content hashes at one point in time.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct WorkspaceSnapshot {
+    digests: Arc<HashMap<PathBuf, FileDigest>>,
+}
+
+impl WorkspaceSnapshot {
+    /// The files added, modified or removed since `earlier`, sorted.
+    pub(crate) fn changed_since(&self, earlier: &WorkspaceSnapshot) -> Vec<PathBuf> {
+        if Arc::ptr_eq(&self.digests, &earlier.digests) {
+            return Vec::new();
+        }
+        let mut changed: Vec<PathBuf> = self
+            .digests
+            .iter()
+            .filter(|(path, digest)| earlier.digests.get(*path) != Some(digest))
+            .map(|(path, _)| path.clone())
+            .chain(
+                earlier
+                    .digests
+                    .keys()
+                    .filter(|path| !self.digests.contains_key(*path))
+                    .cloned(),
+            )
+            .collect();
+        changed.sort();
+        changed
+    }
+}
+
+impl WorkspaceIndex {
+    /// Index `root`, watching it for changes when the platform allows. No
+    /// file is hashed until the first [`WorkspaceIndex::snapshot`]. Indexed
+    /// paths start with the canonical form of `root`, as watchers report them.
+    pub(crate) fn new(root: PathBuf) -> Self {
+        let root = root.canonicalize().unwrap_or(root);
+        let changes = Arc::new(Mutex::new(Changes::default()));
+        let watcher = watch(&root, Arc::clone(&changes))
+            .inspect_err(|err| debug!("not watching {}: {err}", root.display()))
+            .ok();
+        Self {
+            root,
+            state: Mutex::new(IndexState::default()),
+            changes,
+            watcher,
+        }
+    }
+
+    /// [`WorkspaceIndex::new`] on the blocking pool; setting up a recursive
+    /// watch visits every directory under `root`.
+    pub(crate) async fn start(root: PathBuf) -> Arc<Self> {
+        let fallback = root.clone();
+        match run_blocking(move || Self::new(root)).await {
+            Ok(index) => Arc::new(index),
+            Err(err) => {
+                debug!("workspace index setup failed to run in the background: {err}");
+                Arc::new(Self::new(fallback))
+            }
+        }
+    }
+
+    /// Bring the index up to date and return the current hashes.
+    pub(crate) async fn snapshot(self: &Arc<Self>) -> WorkspaceSnapshot {
+        let index = Arc::clone(self);
+        run_blocking(move || index.refresh())
+            .await
+            .unwrap_or_else(|err| {
+                debug!("workspace index refresh failed: {err}");
+                WorkspaceSnapshot::default()
+            })
+    }
+
+    fn refresh(&self) -> WorkspaceSnapshot {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        let changes =
+            std::mem::take(&mut *self.changes.lock().unwrap_or_else(PoisonError::into_inner));
+        if !state.walked || changes.rescan || self.watcher.is_none() {
+            self.walk(&mut state, &self.root);
+            state.walked = true;
+        } else {
+            for path in changes.paths {
+                self.update(&mut state, &path);
+            }
+        }
+        WorkspaceSnapshot {
+            digests: Arc::clone(&state.digests),
+        }
+    }
+
+    /// Re-index everything under `dir`, dropping entries for files that are
+    /// gone.
+    fn walk(&self, state: &mut IndexState, dir: &Path) {
+        let mut seen: HashSet<PathBuf> = HashSet::new();
+        let walker = WalkDir::new(dir)
+            .into_iter()
+            .filter_entry(|entry| entry.depth() == 0 || !is_skipped_dir(entry.path()))
+            .filter_map(Result::ok)
+            .filter(|entry| entry.file_type().is_file())
+            .take(MAX_INDEXED_FILES);
+        for entry in walker {
+            let path = entry.into_path();
+            if let Ok(metadata) = std::fs::metadata(&path) {
+                index_file(state, &path, &metadata);
+            }
+            seen.insert(path);
+        }
+        let gone: Vec<PathBuf> = state
+            .stamps
+            .keys()
+            .filter(|path| path.starts_with(dir) && !seen.contains(*path))
+            .cloned()
+            .collect();
+        for path in gone {
+            forget(state, &path);
+        }
+    }
+
+    /// Re-index one path the watcher reported.
+    fn update(&self, state: &mut IndexState, path: &Path) {
+        if !path.starts_with(&self.root)
+            || path
+                .strip_prefix(&self.root)
+                .is_ok_and(|relative| relative.components().any(|c| is_skipped_dir(c.as_ref())))
+        {
+            return;
+        }
+        match std::fs::metadata(path) {
+            Ok(metadata) if metadata.is_file() => index_file(state, path, &metadata),
+            // A directory that appeared or was renamed into place.
+            Ok(metadata) if metadata.is_dir() => self.walk(state, path),
+            Ok(_) => {}
+            Err(_) => {
+                // Gone, possibly with everything below it.
+                let gone: Vec<PathBuf> = state
+                    .stamps
+                    .keys()
+                    .filter(|indexed| indexed.starts_with(path))
+                    .cloned()
+                    .collect();
+                for indexed in gone {
+                    forget(state, &indexed);
+                }
+            }
+        }
+    }
+}
+
+/// The note telling the model that `paths`, which it read earlier, changed
+/// after the last turn ended.
+pub(crate) fn stale_files_message(paths: &[PathBuf]) -> ResponseItem {
+    let mut text = format!(
+        "{STALE_FILES_OPEN_TAG}\nThese files changed after you last read them, since the previous \
+         turn ended; read them again before relying on their contents:\n"
+    );
+    for path in paths {
+        text.push_str(&format!("- {}\n", path.display()));
+    }
+    text.push_str("</stale_files>");
+    ResponseItem::Message {
+        id: None,
+        role: "user".to_string(),
+        content: vec![ContentItem::InputText { text }],
+    }
+}
+
+fn watch(root: &Path, changes: Arc<Mutex<Changes>>) -> notify::Result<RecommendedWatcher> {
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        let mut changes = changes.lock().unwrap_or_else(PoisonError::into_inner);
+        match event {
+            Ok(event) if !event.need_rescan() => changes.paths.extend(event.paths),
+            _ => changes.rescan = true,
+        }
+    })?;
+    watcher.watch(root, RecursiveMode::Recursive)?;
+    Ok(watcher)
+}
+
+fn is_skipped_dir(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| SKIPPED_DIRS.contains(&name))
+}
+
+/// Hash `path` unless it looks as it did when it was last hashed.
+fn index_file(state: &mut IndexState, path: &Path, metadata: &std::fs::Metadata) {
+    let stamp = Stamp::of(metadata);
+    if state.stamps.get(path) == Some(&stamp) {
+        return;
+    }
+    let Ok(contents) = std::fs::read(path) else {
+        forget(state, path);
+        return;
+    };
+    let digest: FileDigest = Sha256::digest(contents).into();
+    state.stamps.insert(path.to_path_buf(), stamp);
+    if state.digests.get(path) != Some(&digest) {
+        Arc::make_mut(&mut state.digests).insert(path.to_path_buf(), digest);
+    }
+}
+
+fn forget(state: &mut IndexState, path: &Path) {
+    state.stamps.remove(path);
+    if state.digests.contains_key(path) {
+        Arc::make_mut(&mut state.digests).remove(path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn reports_files_added_modified_and_removed_between_snapshots() {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path().canonicalize().expect("canonicalize");
+        std::fs::create_dir_all(root.join("src")).expect("mkdir");
+        std::fs::create_dir_all(root.join("target")).expect("mkdir");
+        std::fs::write(root.join("src/lib.rs"), "fn a() {}").expect("write");
+        std::fs::write(root.join("src/old.rs"), "old").expect("write");
+        std::fs::write(root.join("README.md"), "readme").expect("write");
+        let index = Arc::new(WorkspaceIndex::new(root.clone()));
+
+        let before = index.snapshot().await;
+        assert_eq!(
+            index.snapshot().await.changed_since(&before),
+            Vec::<PathBuf>::new()
+        );
+
+        std::fs::write(root.join("src/lib.rs"), "fn a() { b() }").expect("write");
+        std::fs::remove_file(root.join("src/old.rs")).expect("remove");
+        std::fs::create_dir_all(root.join("src/new")).expect("mkdir");
+        std::fs::write(root.join("src/new/mod.rs"), "new").expect("write");
+        std::fs::write(root.join("target/out"), "build output").expect("write");
+        // Rewriting a file with the same contents is not a change.
+        std::fs::write(root.join("README.md"), "readme").expect("write");
+        // Watchers deliver events asynchronously.
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+
+        assert_eq!(
+            index.snapshot().await.changed_since(&before),
+            vec![
+                root.join("src/lib.rs"),
+                root.join("src/new/mod.rs"),
+                root.join("src/old.rs"),
+            ]
+        );
+    }
+
+    #[test]
+    fn unchanged_files_are_not_rehashed() {
+        let dir = tempdir().expect("tempdir");
+        let path = dir.path().join("file.txt");
+        std::fs::write(&path, "contents").expect("write");
+        let metadata = std::fs::metadata(&path).expect("metadata");
+        let mut state = IndexState::default();
+        index_file(&mut state, &path, &metadata);
+        let first = Arc::clone(&state.digests);
+
+        index_file(&mut state, &path, &metadata);
+        assert!(Arc::ptr_eq(&first, &state.digests));
+        assert_eq!(
+            *state.digests,
+            HashMap::from([(path, Sha256::digest(b"contents").into())])
+        );
+    }
+}
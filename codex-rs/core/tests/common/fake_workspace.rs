@@ -0,0 +1,399 @@
+//! Throwaway workspaces built from declarative fixtures.
+//!
+//! A [`WorkspaceFixture`] lists the commits of a git history and the files
+//! left in the working tree, each with its permissions. Building the same
+//! fixture with the same seed gives the same files and the same commit ids,
+//! so tests can assert on either. Pass the workspace to
+//! [`TestCodexBuilder::with_workspace`] to run a real session in it, and
+//! answer its approval prompts from a script with
+//! [`TestCodex::submit_turn_with_approvals`]:
+//!
+//! ```ignore
+//! let workspace = fake_workspace()
+//!     .commit("initial", [("README.md", "hello\n")])
+//!     .executable("build.sh", "#!/bin/sh\nexit 0\n")
+//!     .build()?;
+//! let test = test_codex().with_workspace(&workspace).build(&server).await?;
+//! let outcome = test
+//!     .submit_turn_with_approvals(
+//!         "run the build",
+//!         AskForApproval::UnlessTrusted,
+//!         SandboxPolicy::new_read_only_policy(),
+//!         ScriptedApprovals::new([ReviewDecision::Approved]),
+//!     )
+//!     .await?;
+//! ```
+
+use std::collections::VecDeque;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::Arc;
+
+use anyhow::Context;
+use anyhow::Result;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::ReviewDecision;
+use serde::Deserialize;
+use tempfile::TempDir;
+
+#[cfg(doc)]
+use crate::test_codex::TestCodex;
+#[cfg(doc)]
+use crate::test_codex::TestCodexBuilder;
+
+/// Commit time of the first commit of a fixture with seed 0; later commits
+/// are a minute apart.
+const BASE_COMMIT_TIME: u64 = 1_700_000_000;
+
+const WORDS: &[&str] = &[
+    "alpha", "bravo", "charlie", "delta", "echo", "foxtrot", "golf", "hotel", "india", "juliet",
+    "kilo", "lima", "mike", "november", "oscar", "papa",
+];
+
+pub fn fake_workspace() -> FakeWorkspaceBuilder {
+    FakeWorkspaceBuilder {
+        fixture: WorkspaceFixture::default(),
+    }
+}
+
+/// A workspace as data, e.g. read from a JSON file next to a test.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WorkspaceFixture {
+    /// Seeds generated file contents and commit times.
+    #[serde(default)]
+    pub seed: u64,
+    /// The git history, oldest first. Without commits the workspace is not a
+    /// git repository.
+    #[serde(default)]
+    pub commits: Vec<CommitFixture>,
+    /// Files written after the last commit and left uncommitted.
+    #[serde(default)]
+    pub files: Vec<FileFixture>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CommitFixture {
+    pub message: String,
+    /// Files added or replaced by the commit.
+    #[serde(default)]
+    pub files: Vec<FileFixture>,
+    /// Files deleted by the commit.
+    #[serde(default)]
+    pub remove: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FileFixture {
+    /// Relative to the workspace; missing directories are created.
+    pub path: PathBuf,
+    #[serde(default)]
+    pub contents: String,
+    /// Lines of words derived from the seed and the path, appended to
+    /// `contents`.
+    #[serde(default)]
+    pub generated_lines: usize,
+    #[serde(default)]
+    pub mode: FileMode,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileMode {
+    #[default]
+    Regular,
+    /// Executable by its owner; the same as `Regular` on Windows.
+    Executable,
+    ReadOnly,
+}
+
+impl FileFixture {
+    fn new(path: impl Into<PathBuf>, contents: impl Into<String>, mode: FileMode) -> Self {
+        Self {
+            path: path.into(),
+            contents: contents.into(),
+            generated_lines: 0,
+            mode,
+        }
+    }
+
+    fn write(&self, root: &Path, seed: u64) -> Result<()> {
+        let path = root.join(&self.path);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        // A read-only file from an earlier commit is replaced, not edited.
+        if path.exists() {
+            std::fs::remove_file(&path)?;
+        }
+        let mut contents = self.contents.clone();
+        contents.push_str(&generated_text(seed, &self.path, self.generated_lines));
+        std::fs::write(&path, contents)
+            .with_context(|| format!("failed to write {}", path.display()))?;
+        match self.mode {
+            FileMode::Regular => {}
+            FileMode::Executable => {
+                #[cfg(unix)]
+                {
+                    use std::os::unix::fs::PermissionsExt;
+                    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))?;
+                }
+            }
+            FileMode::ReadOnly => {
+                let mut permissions = std::fs::metadata(&path)?.permissions();
+                permissions.set_readonly(true);
+                std::fs::set_permissions(&path, permissions)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+pub struct FakeWorkspaceBuilder {
+    fixture: WorkspaceFixture,
+}
+
+impl FakeWorkspaceBuilder {
+    pub fn from_fixture(fixture: WorkspaceFixture) -> Self {
+        Self { fixture }
+    }
+
+    pub fn from_json(json: &str) -> Result<Self> {
+        Ok(Self::from_fixture(serde_json::from_str(json)?))
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.fixture.seed = seed;
+        self
+    }
+
+    /// Adds a commit with `files`, on top of the commits added before.
+    pub fn commit<P, C>(mut self, message: &str, files: impl IntoIterator<Item = (P, C)>) -> Self
+    where
+        P: Into<PathBuf>,
+        C: Into<String>,
+    {
+        self.fixture.commits.push(CommitFixture {
+            message: message.to_string(),
+            files: files
+                .into_iter()
+                .map(|(path, contents)| FileFixture::new(path, contents, FileMode::Regular))
+                .collect(),
+            remove: Vec::new(),
+        });
+        self
+    }
+
+    /// Writes an uncommitted file.
+    pub fn file(self, path: impl Into<PathBuf>, contents: impl Into<String>) -> Self {
+        self.with_file(FileFixture::new(path, contents, FileMode::Regular))
+    }
+
+    pub fn executable(self, path: impl Into<PathBuf>, contents: impl Into<String>) -> Self {
+        self.with_file(FileFixture::new(path, contents, FileMode::Executable))
+    }
+
+    pub fn read_only(self, path: impl Into<PathBuf>, contents: impl Into<String>) -> Self {
+        self.with_file(FileFixture::new(path, contents, FileMode::ReadOnly))
+    }
+
+    /// Writes an uncommitted file of `lines` lines derived from the seed.
+    pub fn generated(self, path: impl Into<PathBuf>, lines: usize) -> Self {
+        self.with_file(FileFixture {
+            generated_lines: lines,
+            ..FileFixture::new(path, "", FileMode::Regular)
+        })
+    }
+
+    pub fn with_file(mut self, file: FileFixture) -> Self {
+        self.fixture.files.push(file);
+        self
+    }
+
+    pub fn build(self) -> Result<FakeWorkspace> {
+        let WorkspaceFixture {
+            seed,
+            commits,
+            files,
+        } = self.fixture;
+        let dir = TempDir::new()?;
+        let root = dir.path();
+        if !commits.is_empty() {
+            git(root, &["-c", "init.defaultBranch=main", "init", "-q"], None)?;
+        }
+        let first_commit_time = BASE_COMMIT_TIME + seed % (365 * 24 * 60 * 60);
+        for (index, commit) in (0u64..).zip(&commits) {
+            for file in &commit.files {
+                file.write(root, seed)?;
+            }
+            for path in &commit.remove {
+                std::fs::remove_file(root.join(path))
+                    .with_context(|| format!("failed to remove {}", path.display()))?;
+            }
+            git(root, &["add", "-A"], None)?;
+            git(
+                root,
+                &["commit", "-q", "--allow-empty", "-m", &commit.message],
+                Some(first_commit_time + index * 60),
+            )?;
+        }
+        for file in &files {
+            file.write(root, seed)?;
+        }
+        Ok(FakeWorkspace {
+            dir: Arc::new(dir),
+            seed,
+        })
+    }
+}
+
+/// A workspace in a temporary directory, removed when the last reference to
+/// it is dropped.
+pub struct FakeWorkspace {
+    dir: Arc<TempDir>,
+    seed: u64,
+}
+
+impl FakeWorkspace {
+    pub fn path(&self) -> &Path {
+        self.dir.path()
+    }
+
+    pub fn join(&self, rel: impl AsRef<Path>) -> PathBuf {
+        self.path().join(rel)
+    }
+
+    pub fn read(&self, rel: impl AsRef<Path>) -> Result<String> {
+        let path = self.join(rel);
+        std::fs::read_to_string(&path).with_context(|| format!("failed to read {}", path.display()))
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Runs git in the workspace and returns its trimmed output.
+    pub fn git(&self, args: &[&str]) -> Result<String> {
+        git(self.path(), args, None)
+    }
+
+    pub fn head(&self) -> Result<String> {
+        self.git(&["rev-parse", "HEAD"])
+    }
+
+    pub(crate) fn dir(&self) -> Arc<TempDir> {
+        Arc::clone(&self.dir)
+    }
+}
+
+/// Answers to the approval prompts of a turn, in the order they are asked.
+/// A prompt with no answer left fails the turn's test.
+pub struct ScriptedApprovals {
+    decisions: VecDeque<ReviewDecision>,
+}
+
+impl ScriptedApprovals {
+    pub fn new(decisions: impl IntoIterator<Item = ReviewDecision>) -> Self {
+        Self {
+            decisions: decisions.into_iter().collect(),
+        }
+    }
+
+    /// For turns expected not to ask at all.
+    pub fn none() -> Self {
+        Self::new([])
+    }
+
+    pub(crate) fn next(&mut self, asked: &str) -> Result<ReviewDecision> {
+        self.decisions
+            .pop_front()
+            .with_context(|| format!("no scripted answer left for approval of {asked}"))
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.decisions.len()
+    }
+}
+
+/// An approval prompt of a scripted turn and the answer it got.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AskedApproval {
+    Exec {
+        command: Vec<String>,
+        decision: ReviewDecision,
+    },
+    Patch {
+        files: Vec<PathBuf>,
+        decision: ReviewDecision,
+    },
+}
+
+/// What a turn run with scripted approvals did.
+#[derive(Debug, Default)]
+pub struct TurnOutcome {
+    /// Every event of the turn, up to and including `TaskComplete`.
+    pub events: Vec<EventMsg>,
+    pub approvals: Vec<AskedApproval>,
+}
+
+fn git(root: &Path, args: &[&str], time: Option<u64>) -> Result<String> {
+    let mut command = Command::new("git");
+    command
+        .current_dir(root)
+        .env("GIT_CONFIG_NOSYSTEM", "1")
+        .args([
+            "-c",
+            "user.name=Codex Fixture",
+            "-c",
+            "user.email=fixture@example.com",
+            "-c",
+            "commit.gpgsign=false",
+        ])
+        .args(args);
+    if let Some(time) = time {
+        let date = format!("@{time} +0000");
+        command
+            .env("GIT_AUTHOR_DATE", &date)
+            .env("GIT_COMMITTER_DATE", &date);
+    }
+    let output = command
+        .output()
+        .with_context(|| format!("failed to run git {args:?}"))?;
+    anyhow::ensure!(
+        output.status.success(),
+        "git {args:?} failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// `lines` lines of words that depend only on `seed` and `path`.
+fn generated_text(seed: u64, path: &Path, lines: usize) -> String {
+    let mut state = path
+        .to_string_lossy()
+        .bytes()
+        .fold(seed ^ 0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        });
+    let mut next = move || {
+        // splitmix64
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    };
+    let mut text = String::new();
+    for _ in 0..lines {
+        let words = 3 + next() % 6;
+        let line: Vec<&str> = (0..words)
+            .map(|_| WORDS[(next() % WORDS.len() as u64) as usize])
+            .collect();
+        text.push_str(&line.join(" "));
+        text.push('\n');
+    }
+    text
+}
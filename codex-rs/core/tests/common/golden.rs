@@ -0,0 +1,341 @@
+//! Golden transcripts of scripted turns.
+//!
+//! A [`GoldenScript`] holds everything a turn depends on: the workspace it
+//! starts in, the approval and sandbox policies, what the model answers to
+//! each request, and the user's answers to the approval prompts.
+//! [`run_golden_script`] plays it against a real session and renders what
+//! happened as text: the approval prompts with their answers and the
+//! commands and patches that ran, in order, then the files left in the
+//! workspace. Tests compare the text with a checked-in `.transcript` file, so
+//! a change to the approval or sandbox ladder that alters behavior shows up
+//! as a diff. With `CODEX_UPDATE_GOLDEN=1` set they rewrite the files
+//! instead.
+
+use std::collections::HashSet;
+use std::path::Path;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use codex_core::protocol::AskForApproval;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::ReviewDecision;
+use codex_core::protocol::SandboxPolicy;
+use serde::Deserialize;
+use serde_json::Value;
+use walkdir::WalkDir;
+
+use crate::fake_workspace::AskedApproval;
+use crate::fake_workspace::FakeWorkspace;
+use crate::fake_workspace::FakeWorkspaceBuilder;
+use crate::fake_workspace::ScriptedApprovals;
+use crate::fake_workspace::TurnOutcome;
+use crate::fake_workspace::WorkspaceFixture;
+use crate::responses::ev_assistant_message;
+use crate::responses::ev_completed;
+use crate::responses::ev_custom_tool_call;
+use crate::responses::ev_function_call;
+use crate::responses::ev_response_created;
+use crate::responses::mount_sse_once;
+use crate::responses::sse;
+use crate::responses::start_mock_server;
+use crate::test_codex::test_codex;
+
+/// Files larger than this are shown by size only.
+const MAX_SHOWN_FILE_BYTES: usize = 4096;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GoldenScript {
+    #[serde(default = "default_model")]
+    pub model: String,
+    #[serde(default = "default_prompt")]
+    pub prompt: String,
+    #[serde(default)]
+    pub workspace: WorkspaceFixture,
+    pub approval_policy: AskForApproval,
+    pub sandbox_policy: SandboxPolicy,
+    /// The model's answer to each request of the turn, in order. The last
+    /// one should end the turn with a message.
+    pub responses: Vec<Vec<ScriptedOutput>>,
+    /// Answers to the approval prompts in the order they are asked; the turn
+    /// must ask exactly this many.
+    #[serde(default)]
+    pub approvals: Vec<ReviewDecision>,
+}
+
+/// An item of a scripted model response.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum ScriptedOutput {
+    /// `arguments` is the JSON the tool is called with.
+    FunctionCall {
+        call_id: String,
+        name: String,
+        arguments: Value,
+    },
+    CustomToolCall {
+        call_id: String,
+        name: String,
+        input: String,
+    },
+    Message {
+        text: String,
+    },
+}
+
+fn default_model() -> String {
+    "gpt-5.1".to_string()
+}
+
+fn default_prompt() -> String {
+    "follow the script".to_string()
+}
+
+/// Whether golden files should be rewritten rather than compared.
+pub fn update_golden() -> bool {
+    std::env::var_os("CODEX_UPDATE_GOLDEN").is_some()
+}
+
+/// Runs the turn of `script` in a fresh session and returns its transcript.
+pub async fn run_golden_script(script: GoldenScript) -> Result<String> {
+    let GoldenScript {
+        model,
+        prompt,
+        workspace,
+        approval_policy,
+        sandbox_policy,
+        responses,
+        approvals,
+    } = script;
+    let workspace = FakeWorkspaceBuilder::from_fixture(workspace).build()?;
+    let server = start_mock_server().await;
+    let mut mocks = Vec::new();
+    for (index, outputs) in (1..).zip(&responses) {
+        mocks.push(mount_sse_once(&server, response_body(index, outputs)?).await);
+    }
+    let test = test_codex()
+        .with_model(&model)
+        .with_workspace(&workspace)
+        .build(&server)
+        .await?;
+
+    let answers = approvals.len();
+    let outcome = test
+        .submit_turn_with_approvals(
+            &prompt,
+            approval_policy,
+            sandbox_policy,
+            ScriptedApprovals::new(approvals),
+        )
+        .await?;
+    anyhow::ensure!(
+        outcome.approvals.len() == answers,
+        "the turn asked for {} approval(s) but the script answers {answers}",
+        outcome.approvals.len()
+    );
+    let unused = mocks
+        .iter()
+        .filter(|mock| mock.requests().is_empty())
+        .count();
+    anyhow::ensure!(
+        unused == 0,
+        "the turn ended with {unused} scripted response(s) left"
+    );
+
+    let calls: Vec<(&str, &str)> = responses
+        .iter()
+        .flatten()
+        .filter_map(|output| match output {
+            ScriptedOutput::FunctionCall { call_id, name, .. }
+            | ScriptedOutput::CustomToolCall { call_id, name, .. } => {
+                Some((call_id.as_str(), name.as_str()))
+            }
+            ScriptedOutput::Message { .. } => None,
+        })
+        .collect();
+    transcript(&outcome, &calls, &workspace)
+}
+
+fn response_body(index: usize, outputs: &[ScriptedOutput]) -> Result<String> {
+    let response_id = format!("resp-{index}");
+    let mut events = vec![ev_response_created(&response_id)];
+    for (item, output) in (1..).zip(outputs) {
+        events.push(match output {
+            ScriptedOutput::FunctionCall {
+                call_id,
+                name,
+                arguments,
+            } => ev_function_call(call_id, name, &serde_json::to_string(arguments)?),
+            ScriptedOutput::CustomToolCall {
+                call_id,
+                name,
+                input,
+            } => ev_custom_tool_call(call_id, name, input),
+            ScriptedOutput::Message { text } => {
+                ev_assistant_message(&format!("msg-{index}-{item}"), text)
+            }
+        });
+    }
+    events.push(ev_completed(&response_id));
+    Ok(sse(events))
+}
+
+/// Renders what a turn did: its approval prompts and the ends of its
+/// commands and patches in the order they happened, the scripted `calls`
+/// that were neither, and the files left in `workspace`. Paths under the
+/// workspace are shown relative to `<workspace>`.
+pub fn transcript(
+    outcome: &TurnOutcome,
+    calls: &[(&str, &str)],
+    workspace: &FakeWorkspace,
+) -> Result<String> {
+    let mut lines = vec!["# turn".to_string()];
+    let mut approvals = outcome.approvals.iter();
+    let mut ran = HashSet::new();
+    for event in &outcome.events {
+        match event {
+            EventMsg::ExecApprovalRequest(_) | EventMsg::ApplyPatchApprovalRequest(_) => {
+                lines.extend(approvals.next().map(approval_line));
+            }
+            EventMsg::ExecApprovalBatchRequest(batch) => {
+                for _ in &batch.requests {
+                    lines.extend(approvals.next().map(approval_line));
+                }
+            }
+            EventMsg::ExecCommandEnd(end) => {
+                ran.insert(end.call_id.as_str());
+                let mut line = format!(
+                    "exec {} `{}`: exit {}",
+                    end.call_id,
+                    display_command(&end.command),
+                    end.exit_code
+                );
+                // Calls stopped before or instead of running end with -1 and
+                // the reason as their only output.
+                if end.exit_code == -1
+                    && let Some(reason) = end.aggregated_output.lines().next()
+                {
+                    line.push_str(&format!(" ({reason})"));
+                }
+                lines.push(line);
+            }
+            EventMsg::PatchApplyEnd(end) => {
+                ran.insert(end.call_id.as_str());
+                let status = if end.success { "applied" } else { "failed" };
+                lines.push(format!(
+                    "patch {} {}: {status}",
+                    end.call_id,
+                    display_files(end.changes.keys())
+                ));
+            }
+            _ => {}
+        }
+    }
+    for (call_id, name) in calls {
+        if !ran.contains(call_id) {
+            lines.push(format!("{name} {call_id}: no command or patch"));
+        }
+    }
+
+    lines.push("# workspace".to_string());
+    let root = workspace.path();
+    let walker = WalkDir::new(root)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| entry.file_name() != ".git");
+    for entry in walker {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let rel = entry.path().strip_prefix(root)?;
+        let rel = rel.to_string_lossy().replace('\\', "/");
+        lines.push(format!("{rel}{}", mode_suffix(entry.path())?));
+        let bytes = std::fs::read(entry.path())?;
+        match String::from_utf8(bytes) {
+            Ok(text) if text.len() <= MAX_SHOWN_FILE_BYTES => {
+                lines.extend(text.lines().map(|line| format!("  | {line}")));
+            }
+            Ok(text) => lines.push(format!("  <{} bytes>", text.len())),
+            Err(err) => lines.push(format!("  <{} bytes>", err.as_bytes().len())),
+        }
+    }
+    if root.join(".git").exists() {
+        lines.push("# git status".to_string());
+        let status = workspace.git(&["status", "--porcelain"])?;
+        if status.is_empty() {
+            lines.push("(clean)".to_string());
+        } else {
+            lines.extend(status.lines().map(str::to_string));
+        }
+    }
+
+    let mut text = lines.join("\n");
+    text.push('\n');
+    // Events may carry the canonical path, e.g. under /private on macOS.
+    for root in [root.canonicalize()?, root.to_path_buf()] {
+        text = text.replace(&root.display().to_string(), "<workspace>");
+    }
+    Ok(text)
+}
+
+fn approval_line(approval: &AskedApproval) -> String {
+    match approval {
+        AskedApproval::Exec { command, decision } => format!(
+            "approval exec `{}`: {}",
+            display_command(command),
+            decision_name(*decision)
+        ),
+        AskedApproval::Patch { files, decision } => format!(
+            "approval patch {}: {}",
+            display_files(files),
+            decision_name(*decision)
+        ),
+    }
+}
+
+fn decision_name(decision: ReviewDecision) -> String {
+    match serde_json::to_value(decision) {
+        Ok(Value::String(name)) => name,
+        _ => format!("{decision:?}"),
+    }
+}
+
+/// The script of a `bash -lc` style command, so that transcripts do not
+/// depend on the user's shell; other commands joined as a shell would read
+/// them.
+fn display_command(command: &[String]) -> String {
+    if let [shell, flag, script] = command
+        && (flag == "-lc" || flag == "-c")
+        && Path::new(shell)
+            .file_name()
+            .is_some_and(|name| name == "bash" || name == "zsh" || name == "sh")
+    {
+        return script.clone();
+    }
+    shlex::try_join(command.iter().map(String::as_str)).unwrap_or_else(|_| command.join(" "))
+}
+
+fn display_files<'a>(files: impl IntoIterator<Item = &'a PathBuf>) -> String {
+    let mut files: Vec<String> = files
+        .into_iter()
+        .map(|file| file.display().to_string())
+        .collect();
+    files.sort();
+    files.join(", ")
+}
+
+fn mode_suffix(path: &Path) -> Result<&'static str> {
+    let permissions = std::fs::metadata(path)?.permissions();
+    if permissions.readonly() {
+        return Ok(" (read-only)");
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if permissions.mode() & 0o100 != 0 {
+            return Ok(" (executable)");
+        }
+    }
+    Ok("")
+}
@@ -11,6 +11,8 @@ use regex_lite::Regex;
 #[cfg(target_os = "linux")]
 use assert_cmd::cargo::cargo_bin;
 
+pub mod fake_workspace;
+pub mod golden;
 pub mod responses;
 pub mod test_codex;
 pub mod test_codex_exec;
@@ -3,6 +3,7 @@ use std::path::Path;
 use std::path::PathBuf;
 use std::sync::Arc;
 
+use anyhow::Context;
 use anyhow::Result;
 use codex_core::CodexAuth;
 use codex_core::CodexConversation;
@@ -21,8 +22,13 @@ use codex_protocol::config_types::ReasoningSummary;
 use codex_protocol::user_input::UserInput;
 use serde_json::Value;
 use tempfile::TempDir;
+use tokio::time::Duration;
 use wiremock::MockServer;
 
+use crate::fake_workspace::AskedApproval;
+use crate::fake_workspace::FakeWorkspace;
+use crate::fake_workspace::ScriptedApprovals;
+use crate::fake_workspace::TurnOutcome;
 use crate::load_default_config_for_test;
 use crate::responses::start_mock_server;
 use crate::wait_for_event;
@@ -50,6 +56,7 @@ pub enum ShellModelOutput {
 pub struct TestCodexBuilder {
     config_mutators: Vec<Box<ConfigMutator>>,
     auth: CodexAuth,
+    workspace: Option<Arc<TempDir>>,
 }
 
 impl TestCodexBuilder {
@@ -66,6 +73,12 @@ impl TestCodexBuilder {
         self
     }
 
+    /// Runs the session in `workspace` instead of an empty directory.
+    pub fn with_workspace(mut self, workspace: &FakeWorkspace) -> Self {
+        self.workspace = Some(workspace.dir());
+        self
+    }
+
     pub fn with_model(self, model: &str) -> Self {
         let new_model = model.to_string();
         self.with_config(move |config| {
@@ -131,7 +144,10 @@ impl TestCodexBuilder {
             base_url: Some(format!("{}/v1", server.uri())),
             ..built_in_model_providers()["openai"].clone()
         };
-        let cwd = Arc::new(TempDir::new()?);
+        let cwd = match &self.workspace {
+            Some(workspace) => Arc::clone(workspace),
+            None => Arc::new(TempDir::new()?),
+        };
         let mut config = load_default_config_for_test(home);
         config.cwd = cwd.path().to_path_buf();
         config.model_provider = model_provider;
@@ -218,6 +234,91 @@ impl TestCodex {
         .await;
         Ok(())
     }
+
+    /// Runs a turn, answering its approval prompts from `approvals`, and
+    /// returns its events and the prompts it asked.
+    pub async fn submit_turn_with_approvals(
+        &self,
+        prompt: &str,
+        approval_policy: AskForApproval,
+        sandbox_policy: SandboxPolicy,
+        mut approvals: ScriptedApprovals,
+    ) -> Result<TurnOutcome> {
+        let session_model = self.session_configured.model.clone();
+        self.codex
+            .submit(Op::UserTurn {
+                items: vec![UserInput::Text {
+                    text: prompt.into(),
+                }],
+                final_output_json_schema: None,
+                cwd: self.cwd.path().to_path_buf(),
+                approval_policy,
+                sandbox_policy,
+                model: session_model,
+                effort: None,
+                summary: ReasoningSummary::Auto,
+            })
+            .await?;
+
+        let mut outcome = TurnOutcome::default();
+        loop {
+            let event = tokio::time::timeout(Duration::from_secs(10), self.codex.next_event())
+                .await
+                .context("timeout waiting for event")??;
+            match &event.msg {
+                EventMsg::ExecApprovalRequest(request) => {
+                    let decision = approvals.next(&request.command.join(" "))?;
+                    outcome.approvals.push(AskedApproval::Exec {
+                        command: request.command.clone(),
+                        decision,
+                    });
+                    self.codex
+                        .submit(Op::ExecApproval {
+                            id: event.id.clone(),
+                            decision,
+                        })
+                        .await?;
+                }
+                EventMsg::ExecApprovalBatchRequest(batch) => {
+                    let mut decisions = Vec::new();
+                    for request in &batch.requests {
+                        let decision = approvals.next(&request.command.join(" "))?;
+                        outcome.approvals.push(AskedApproval::Exec {
+                            command: request.command.clone(),
+                            decision,
+                        });
+                        decisions.push(decision);
+                    }
+                    self.codex
+                        .submit(Op::ExecApprovalBatch {
+                            id: batch.id.clone(),
+                            decisions,
+                        })
+                        .await?;
+                }
+                EventMsg::ApplyPatchApprovalRequest(request) => {
+                    let mut files: Vec<PathBuf> = request.changes.keys().cloned().collect();
+                    files.sort();
+                    let decision = approvals.next(&format!("a patch of {files:?}"))?;
+                    outcome
+                        .approvals
+                        .push(AskedApproval::Patch { files, decision });
+                    self.codex
+                        .submit(Op::PatchApproval {
+                            id: event.id.clone(),
+                            decision,
+                        })
+                        .await?;
+                }
+                _ => {}
+            }
+            let done = matches!(event.msg, EventMsg::TaskComplete(_));
+            outcome.events.push(event.msg);
+            if done {
+                return Ok(outcome);
+            }
+        }
+    }
 }
 
 pub struct TestCodexHarness {
@@ -351,5 +452,6 @@ pub fn test_codex() -> TestCodexBuilder {
     TestCodexBuilder {
         config_mutators: vec![],
         auth: CodexAuth::from_api_key("dummy"),
+        workspace: None,
     }
 }
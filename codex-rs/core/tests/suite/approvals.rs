@@ -446,6 +446,24 @@ impl Expectation {
     }
 }
 
+/// `reason` with `{path}` replaced by the file `action` writes, shown the way
+/// retry prompts show it: from `~` when it is under the home directory.
+fn expand_reason(reason: &str, action: &ActionKind, test: &TestCodex) -> String {
+    let ActionKind::WriteFile { target, .. } = action else {
+        return reason.to_string();
+    };
+    let (path, _) = target.resolve_for_patch(test);
+    let home = dirs::home_dir();
+    let shown = match home
+        .as_deref()
+        .and_then(|home| path.strip_prefix(home).ok())
+    {
+        Some(rest) => format!("~/{}", rest.display()),
+        None => path.display().to_string(),
+    };
+    reason.replace("{path}", &shown)
+}
+
 #[derive(Clone)]
 enum Outcome {
     Auto,
@@ -950,7 +968,7 @@ fn scenarios() -> Vec<ScenarioSpec> {
             model_override: Some("gpt-5"),
             outcome: Outcome::ExecApproval {
                 decision: ReviewDecision::Approved,
-                expected_reason: Some("command failed; retry without sandbox?"),
+                expected_reason: Some("blocked writing to {path}; retry without sandbox?"),
             },
             expectation: Expectation::FileCreated {
                 target: TargetPath::Workspace("ro_on_failure.txt"),
@@ -971,7 +989,7 @@ fn scenarios() -> Vec<ScenarioSpec> {
             model_override: Some("gpt-5.1"),
             outcome: Outcome::ExecApproval {
                 decision: ReviewDecision::Approved,
-                expected_reason: Some("command failed; retry without sandbox?"),
+                expected_reason: Some("blocked writing to {path}; retry without sandbox?"),
             },
             expectation: Expectation::FileCreatedNoExitCode {
                 target: TargetPath::Workspace("ro_on_failure_5_1.txt"),
@@ -1329,7 +1347,7 @@ fn scenarios() -> Vec<ScenarioSpec> {
             model_override: Some("gpt-5"),
             outcome: Outcome::ExecApproval {
                 decision: ReviewDecision::Approved,
-                expected_reason: Some("command failed; retry without sandbox?"),
+                expected_reason: Some("blocked writing to {path}; retry without sandbox?"),
             },
             expectation: Expectation::FileCreated {
                 target: TargetPath::OutsideWorkspace("ww_on_failure.txt"),
@@ -1514,8 +1532,8 @@ async fn run_scenario(scenario: &ScenarioSpec) -> Result<()> {
             let approval = expect_exec_approval(&test, command).await;
             if let Some(expected_reason) = expected_reason {
                 assert_eq!(
-                    approval.reason.as_deref(),
-                    Some(*expected_reason),
+                    approval.reason,
+                    Some(expand_reason(expected_reason, &scenario.action, &test)),
                     "unexpected approval reason for {}",
                     scenario.name
                 );
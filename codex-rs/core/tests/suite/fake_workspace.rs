@@ -0,0 +1,119 @@
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+use anyhow::Result;
+use codex_core::protocol::AskForApproval;
+use codex_core::protocol::SandboxPolicy;
+use codex_protocol::protocol::ReviewDecision;
+use core_test_support::fake_workspace::AskedApproval;
+use core_test_support::fake_workspace::FakeWorkspaceBuilder;
+use core_test_support::fake_workspace::ScriptedApprovals;
+use core_test_support::fake_workspace::fake_workspace;
+use core_test_support::responses::ev_assistant_message;
+use core_test_support::responses::ev_completed;
+use core_test_support::responses::ev_function_call;
+use core_test_support::responses::ev_response_created;
+use core_test_support::responses::mount_sse_once;
+use core_test_support::responses::sse;
+use core_test_support::responses::start_mock_server;
+use core_test_support::skip_if_no_network;
+use core_test_support::test_codex::test_codex;
+use pretty_assertions::assert_eq;
+use serde_json::json;
+
+const FIXTURE: &str = r##"{
+    "seed": 7,
+    "commits": [
+        { "message": "initial", "files": [{ "path": "README.md", "contents": "hello\n" }] },
+        {
+            "message": "add sources",
+            "files": [
+                { "path": "src/lib.rs", "generated_lines": 20 },
+                { "path": "build.sh", "contents": "#!/bin/sh\n", "mode": "executable" }
+            ],
+            "remove": ["README.md"]
+        }
+    ],
+    "files": [{ "path": "LICENSE", "contents": "MIT\n", "mode": "read_only" }]
+}"##;
+
+#[test]
+fn fixtures_build_the_same_workspace_every_time() -> Result<()> {
+    let first = FakeWorkspaceBuilder::from_json(FIXTURE)?.build()?;
+    let second = FakeWorkspaceBuilder::from_json(FIXTURE)?.build()?;
+
+    assert_eq!(first.head()?, second.head()?);
+    assert_eq!(first.read("src/lib.rs")?, second.read("src/lib.rs")?);
+    assert_eq!(first.read("src/lib.rs")?.lines().count(), 20);
+    assert!(!first.join("README.md").exists());
+    assert_eq!(first.git(&["status", "--porcelain"])?, "?? LICENSE");
+    assert!(
+        std::fs::metadata(first.join("LICENSE"))?
+            .permissions()
+            .readonly()
+    );
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(first.join("build.sh"))?
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o111, 0o111);
+    }
+
+    let reseeded = FakeWorkspaceBuilder::from_json(FIXTURE)?.seed(8).build()?;
+    assert_ne!(first.head()?, reseeded.head()?);
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn scripted_approvals_answer_a_real_session() -> Result<()> {
+    skip_if_no_network!(Ok(()));
+
+    let workspace = fake_workspace()
+        .commit("initial", [("README.md", "hello\n")])
+        .build()?;
+    let server = start_mock_server().await;
+    let args = json!({ "command": "touch created.txt", "timeout_ms": 10_000 });
+    mount_sse_once(
+        &server,
+        sse(vec![
+            ev_response_created("resp-1"),
+            ev_function_call("call-1", "shell_command", &serde_json::to_string(&args)?),
+            ev_completed("resp-1"),
+        ]),
+    )
+    .await;
+    mount_sse_once(
+        &server,
+        sse(vec![
+            ev_assistant_message("msg-1", "done"),
+            ev_completed("resp-2"),
+        ]),
+    )
+    .await;
+    let test = test_codex()
+        .with_model("gpt-5")
+        .with_workspace(&workspace)
+        .build(&server)
+        .await?;
+
+    let outcome = test
+        .submit_turn_with_approvals(
+            "create a file",
+            AskForApproval::UnlessTrusted,
+            SandboxPolicy::DangerFullAccess,
+            ScriptedApprovals::new([ReviewDecision::Approved]),
+        )
+        .await?;
+
+    let [AskedApproval::Exec { command, decision }] = outcome.approvals.as_slice() else {
+        panic!("expected one exec approval, got {:?}", outcome.approvals);
+    };
+    assert_eq!(
+        command.last().map(String::as_str),
+        Some("touch created.txt")
+    );
+    assert_eq!(*decision, ReviewDecision::Approved);
+    assert!(workspace.join("created.txt").exists());
+    Ok(())
+}
@@ -0,0 +1,49 @@
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use anyhow::Result;
+use core_test_support::golden::GoldenScript;
+use core_test_support::golden::run_golden_script;
+use core_test_support::golden::update_golden;
+use core_test_support::skip_if_no_network;
+use pretty_assertions::assert_eq;
+
+/// Replays every script in `tests/golden` and compares what the turn did
+/// with the `.transcript` file next to it.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn golden_transcripts_are_unchanged() -> Result<()> {
+    skip_if_no_network!(Ok(()));
+
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+    let mut scripts: Vec<PathBuf> = std::fs::read_dir(&dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<_>>()?;
+    scripts.retain(|path| path.extension().is_some_and(|ext| ext == "json"));
+    scripts.sort();
+    assert!(!scripts.is_empty(), "no scripts in {}", dir.display());
+
+    for script_path in scripts {
+        let script: GoldenScript = serde_json::from_str(&std::fs::read_to_string(&script_path)?)
+            .with_context(|| format!("invalid script {}", script_path.display()))?;
+        let actual = run_golden_script(script)
+            .await
+            .with_context(|| format!("failed to replay {}", script_path.display()))?;
+        let transcript_path = script_path.with_extension("transcript");
+        if update_golden() {
+            std::fs::write(&transcript_path, actual)?;
+            continue;
+        }
+        let expected = std::fs::read_to_string(&transcript_path)
+            .with_context(|| format!("missing {}", transcript_path.display()))?;
+        assert_eq!(
+            expected,
+            actual,
+            "{} changed; rerun with CODEX_UPDATE_GOLDEN=1 if that is intended",
+            transcript_path.display()
+        );
+    }
+    Ok(())
+}
@@ -29,7 +29,11 @@ mod compact_resume_fork;
 mod deprecation_notice;
 mod exec;
 mod exec_policy;
+#[cfg(not(target_os = "windows"))]
+mod fake_workspace;
 mod fork_conversation;
+#[cfg(not(target_os = "windows"))]
+mod golden_transcripts;
 mod grep_files;
 mod items;
 mod json_result;
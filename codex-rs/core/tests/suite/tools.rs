@@ -386,8 +386,15 @@ async fn shell_timeout_includes_timeout_prefix_and_metadata() -> Result<()> {
 
     // The exec path can report a timeout in two ways depending on timing:
     // 1) Structured JSON with exit_code 124 and a timeout prefix (preferred), or
-    // 2) A plain error string if the child is observed as killed by a signal first.
-    if let Ok(output_json) = serde_json::from_str::<Value>(output_str) {
+    // 2) A signal exit reason if the child is observed as killed by a signal first.
+    let output_json: Value = serde_json::from_str(output_str)?;
+    if output_json["metadata"]["exit_reason"]["signal"].is_i64() {
+        // Fallback: accept the signal classification path to deflake the test.
+        assert!(
+            output_json["metadata"]["exit_code"].as_i64() > Some(128),
+            "expected a signal exit code: {output_str}"
+        );
+    } else {
         assert_eq!(
             output_json["metadata"]["exit_code"].as_i64(),
             Some(124),
@@ -399,10 +406,6 @@ async fn shell_timeout_includes_timeout_prefix_and_metadata() -> Result<()> {
             stdout.contains("command timed out"),
             "timeout output missing `command timed out`: {stdout}"
         );
-    } else {
-        // Fallback: accept the signal classification path to deflake the test.
-        let signal_pattern = r"(?is)^execution error:.*signal.*$";
-        assert_regex_match(signal_pattern, output_str);
     }
 
     Ok(())
@@ -557,21 +560,21 @@ async fn shell_spawn_failure_truncates_exec_error() -> Result<()> {
         .and_then(Value::as_str)
         .expect("spawn failure output string");
 
-    let spawn_error_pattern = r#"(?s)^Exit code: -?\d+
-Wall time: [0-9]+(?:\.[0-9]+)? seconds
+    let spawn_error_pattern = r#"(?s)^Exit code: -1
+(?:Stopped by: a failure to start it
+)?Wall time: [0-9]+(?:\.[0-9]+)? seconds
 Output:
 execution error: .*$"#;
-    let spawn_truncated_pattern = r#"(?s)^Exit code: -?\d+
-Wall time: [0-9]+(?:\.[0-9]+)? seconds
+    let spawn_truncated_pattern = r#"(?s)^Exit code: -1
+(?:Stopped by: a failure to start it
+)?Wall time: [0-9]+(?:\.[0-9]+)? seconds
 Total output lines: \d+
 Output:
 
 execution error: .*$"#;
     let spawn_error_regex = Regex::new(spawn_error_pattern)?;
-    let spawn_truncated_regex = Regex::new(spawn_truncated_pattern)?;
-    if !spawn_error_regex.is_match(output) && !spawn_truncated_regex.is_match(output) {
-        let fallback_pattern = r"(?s)^execution error: .*$";
-        assert_regex_match(fallback_pattern, output);
+    if !spawn_error_regex.is_match(output) {
+        assert_regex_match(spawn_truncated_pattern, output);
     }
     assert!(output.len() <= 10 * 1024);
 
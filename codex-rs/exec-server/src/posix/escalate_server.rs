@@ -98,9 +98,9 @@ impl EscalateServer {
         escalate_task.abort();
         let result = ExecResult {
             exit_code: result.exit_code,
+            timed_out: result.timed_out(),
             output: result.aggregated_output.text,
             duration: result.duration,
-            timed_out: result.timed_out,
         };
         Ok(result)
     }
@@ -3,8 +3,13 @@ use codex_common::elapsed::format_elapsed;
 use codex_core::config::Config;
 use codex_core::protocol::AgentMessageEvent;
 use codex_core::protocol::AgentReasoningRawContentEvent;
+use codex_core::protocol::ApprovalRuleAppliedEvent;
+use codex_core::protocol::ApprovalRuleDecision;
+use codex_core::protocol::ArtifactDirection;
+use codex_core::protocol::ArtifactEvent;
 use codex_core::protocol::BackgroundEventEvent;
 use codex_core::protocol::DeprecationNoticeEvent;
+use codex_core::protocol::EnvironmentChangedEvent;
 use codex_core::protocol::ErrorEvent;
 use codex_core::protocol::Event;
 use codex_core::protocol::EventMsg;
@@ -16,13 +21,16 @@ use codex_core::protocol::McpToolCallBeginEvent;
 use codex_core::protocol::McpToolCallEndEvent;
 use codex_core::protocol::PatchApplyBeginEvent;
 use codex_core::protocol::PatchApplyEndEvent;
+use codex_core::protocol::SandboxProbeEvent;
 use codex_core::protocol::SessionConfiguredEvent;
 use codex_core::protocol::StreamErrorEvent;
 use codex_core::protocol::TaskCompleteEvent;
 use codex_core::protocol::TurnAbortReason;
 use codex_core::protocol::TurnDiffEvent;
+use codex_core::protocol::TurnResourceUsageEvent;
 use codex_core::protocol::WarningEvent;
 use codex_core::protocol::WebSearchEndEvent;
+use codex_core::protocol::WorkspaceIntegrityEvent;
 use codex_protocol::num_format::format_with_separators;
 use owo_colors::OwoColorize;
 use owo_colors::Style;
@@ -172,6 +180,53 @@ impl EventProcessor for EventProcessorWithHumanOutput {
                     "warning:".style(self.yellow).style(self.bold)
                 );
             }
+            EventMsg::WorkspaceIntegrity(WorkspaceIntegrityEvent { findings }) => {
+                for finding in findings {
+                    ts_msg!(
+                        self,
+                        "{} {} ({})",
+                        "leftover:".style(self.yellow).style(self.bold),
+                        finding.description,
+                        finding.cleanup.style(self.dimmed)
+                    );
+                }
+            }
+            EventMsg::EnvironmentChanged(EnvironmentChangedEvent {
+                command, changes, ..
+            }) => {
+                ts_msg!(
+                    self,
+                    "{} {}",
+                    "environment changed by".style(self.yellow).style(self.bold),
+                    escape_command(&command).style(self.bold)
+                );
+                for change in changes {
+                    ts_msg!(self, "  {}", change.description);
+                    for line in change.lines {
+                        ts_msg!(self, "    {}", line.style(self.dimmed));
+                    }
+                }
+            }
+            EventMsg::SandboxProbe(SandboxProbeEvent {
+                available,
+                limits,
+                error,
+            }) => {
+                let error = error.unwrap_or_default();
+                if !available {
+                    ts_msg!(
+                        self,
+                        "{} the sandbox is unavailable, so commands run without it: {error}",
+                        "warning:".style(self.yellow).style(self.bold)
+                    );
+                } else if !limits {
+                    ts_msg!(
+                        self,
+                        "{} sandbox limits are unavailable, so commands run without them: {error}",
+                        "warning:".style(self.yellow).style(self.bold)
+                    );
+                }
+            }
             EventMsg::DeprecationNotice(DeprecationNoticeEvent { summary, details }) => {
                 ts_msg!(
                     self,
@@ -221,6 +276,42 @@ impl EventProcessor for EventProcessorWithHumanOutput {
             EventMsg::BackgroundEvent(BackgroundEventEvent { message }) => {
                 ts_msg!(self, "{}", message.style(self.dimmed));
             }
+            EventMsg::Artifact(ArtifactEvent {
+                name,
+                direction,
+                path,
+                size_bytes,
+                ..
+            }) => {
+                let label = match direction {
+                    ArtifactDirection::Inbound => "artifact received:",
+                    ArtifactDirection::Outbound => "artifact sent:",
+                };
+                ts_msg!(
+                    self,
+                    "{} {name} ({size_bytes} bytes) {}",
+                    label.style(self.cyan),
+                    path.display().to_string().style(self.dimmed)
+                );
+            }
+            EventMsg::ApprovalRuleApplied(ApprovalRuleAppliedEvent {
+                command,
+                rule,
+                decision,
+                ..
+            }) => {
+                let action = match decision {
+                    ApprovalRuleDecision::AutoApprove => "approved",
+                    ApprovalRuleDecision::AutoDeny => "denied",
+                    ApprovalRuleDecision::Ask => "asks for",
+                };
+                ts_msg!(
+                    self,
+                    "{} {action} {}",
+                    format!("approval rule {rule}").style(self.cyan),
+                    escape_command(&command).style(self.bold)
+                );
+            }
             EventMsg::StreamError(StreamErrorEvent { message, .. }) => {
                 ts_msg!(self, "{}", message.style(self.dimmed));
             }
@@ -253,6 +344,20 @@ impl EventProcessor for EventProcessorWithHumanOutput {
             EventMsg::TokenCount(ev) => {
                 self.last_total_token_usage = ev.info;
             }
+            EventMsg::TurnResourceUsage(TurnResourceUsageEvent {
+                cpu_seconds,
+                peak_rss_bytes,
+                disk_write_bytes,
+            }) => {
+                let mut usage = format!(
+                    "{cpu_seconds:.1}s CPU, peak RSS {} MiB",
+                    peak_rss_bytes >> 20
+                );
+                if let Some(written) = disk_write_bytes {
+                    usage.push_str(&format!(", {} MiB written", written >> 20));
+                }
+                ts_msg!(self, "{} {}", "resources used:".style(self.dimmed), usage);
+            }
 
             EventMsg::AgentReasoningSectionBreak(_) => {
                 if !self.show_agent_reasoning {
@@ -403,6 +508,22 @@ impl EventProcessor for EventProcessorWithHumanOutput {
                                 eprintln!("{}", line.style(self.red));
                             }
                         }
+                        FileChange::Binary {
+                            size,
+                            previous_size,
+                        } => {
+                            let header = format!(
+                                "{} {}",
+                                format_file_change(change),
+                                path.to_string_lossy()
+                            );
+                            eprintln!("{}", header.style(self.magenta));
+                            eprintln!(
+                                "{}",
+                                FileChange::binary_summary(*size, *previous_size)
+                                    .style(self.dimmed)
+                            );
+                        }
                         FileChange::Update {
                             unified_diff,
                             move_path,
@@ -562,12 +683,23 @@ impl EventProcessor for EventProcessorWithHumanOutput {
             EventMsg::ShutdownComplete => return CodexStatus::Shutdown,
             EventMsg::WebSearchBegin(_)
             | EventMsg::ExecApprovalRequest(_)
+            | EventMsg::ExecApprovalBatchRequest(_)
             | EventMsg::ApplyPatchApprovalRequest(_)
             | EventMsg::ExecCommandOutputDelta(_)
+            | EventMsg::ExecCommandProgress(_)
             | EventMsg::GetHistoryEntryResponse(_)
             | EventMsg::McpListToolsResponse(_)
             | EventMsg::ListCustomPromptsResponse(_)
+            | EventMsg::ListFilesReadResponse(_)
+            | EventMsg::DoctorReport(_)
+            | EventMsg::ContextUsage(_)
+            | EventMsg::ApprovalCache(_)
             | EventMsg::RawResponseItem(_)
+            | EventMsg::StatusLine(_)
+            | EventMsg::FileRead(_)
+            | EventMsg::CollaboratorJoined(_)
+            | EventMsg::CollaboratorLeft(_)
+            | EventMsg::ApprovalResolved(_)
             | EventMsg::UserMessage(_)
             | EventMsg::EnteredReviewMode(_)
             | EventMsg::ExitedReviewMode(_)
@@ -623,6 +755,11 @@ fn format_file_change(change: &FileChange) -> &'static str {
         FileChange::Update {
             move_path: None, ..
         } => "M",
+        FileChange::Binary {
+            previous_size: None,
+            ..
+        } => "A",
+        FileChange::Binary { .. } => "M",
     }
 }
 
@@ -332,7 +332,11 @@ impl EventProcessorWithJsonOutput {
         match kind {
             FileChange::Add { .. } => PatchChangeKind::Add,
             FileChange::Delete { .. } => PatchChangeKind::Delete,
-            FileChange::Update { .. } => PatchChangeKind::Update,
+            FileChange::Binary {
+                previous_size: None,
+                ..
+            } => PatchChangeKind::Add,
+            FileChange::Update { .. } | FileChange::Binary { .. } => PatchChangeKind::Update,
         }
     }
 
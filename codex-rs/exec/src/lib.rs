@@ -255,6 +255,14 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
         std::process::exit(1);
     }
 
+    codex_core::crash_report::install_crash_reporter(&config, |path| {
+        eprintln!(
+            "Crash report saved to {}. It stays on this machine unless you share it.",
+            path.display()
+        );
+    })
+    .await;
+
     let otel = codex_core::otel_init::build_provider(&config, env!("CARGO_PKG_VERSION"));
 
     #[allow(clippy::print_stderr)]
@@ -6,12 +6,15 @@ use codex_core::error::CodexErr;
 use codex_core::error::Result;
 use codex_core::error::SandboxErr;
 use codex_core::protocol::SandboxPolicy;
+use codex_core::sandboxing::network::SandboxNetwork;
 
 use landlock::ABI;
 use landlock::Access;
 use landlock::AccessFs;
+use landlock::AccessNet;
 use landlock::CompatLevel;
 use landlock::Compatible;
+use landlock::NetPort;
 use landlock::Ruleset;
 use landlock::RulesetAttr;
 use landlock::RulesetCreatedAttr;
@@ -29,10 +32,26 @@ use seccompiler::apply_filter;
 /// them, not the entire CLI process.
 pub(crate) fn apply_sandbox_policy_to_current_thread(
     sandbox_policy: &SandboxPolicy,
+    network: SandboxNetwork,
     cwd: &Path,
 ) -> Result<()> {
-    if !sandbox_policy.has_full_network_access() {
-        install_network_seccomp_filter_on_current_thread()?;
+    match network {
+        SandboxNetwork::Full => {}
+        SandboxNetwork::None => install_network_seccomp_filter_on_current_thread()?,
+        // Without user namespaces or Landlock network rules, the command
+        // gets no network rather than more than it asked for.
+        SandboxNetwork::Loopback => {
+            if isolate_loopback_network().is_err() {
+                install_network_seccomp_filter_on_current_thread()?;
+            }
+        }
+        SandboxNetwork::Proxy { port } => {
+            if install_proxy_only_landlock_rules_on_current_thread(port).is_ok() {
+                install_inet_stream_seccomp_filter_on_current_thread()?;
+            } else {
+                install_network_seccomp_filter_on_current_thread()?;
+            }
+        }
     }
 
     if !sandbox_policy.has_full_disk_write_access() {
@@ -124,6 +143,112 @@ fn install_network_seccomp_filter_on_current_thread() -> std::result::Result<(),
     rules.insert(libc::SYS_socket, vec![unix_only_rule.clone()]);
     rules.insert(libc::SYS_socketpair, vec![unix_only_rule]); // always deny (Unix can use socketpair but fine, keep open?)
 
+    apply_seccomp_deny_rules(rules)
+}
+
+/// Moves the process into new user and network namespaces, where only a
+/// loopback interface of its own exists. Must run before any other thread
+/// is started.
+fn isolate_loopback_network() -> std::io::Result<()> {
+    let uid = unsafe { libc::getuid() };
+    let gid = unsafe { libc::getgid() };
+    if unsafe { libc::unshare(libc::CLONE_NEWUSER | libc::CLONE_NEWNET) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    // Keep the caller's ids, so files it owns still look like its own.
+    std::fs::write("/proc/self/setgroups", "deny")?;
+    std::fs::write("/proc/self/uid_map", format!("{uid} {uid} 1"))?;
+    std::fs::write("/proc/self/gid_map", format!("{gid} {gid} 1"))?;
+    bring_up_loopback_interface()
+}
+
+/// A new network namespace starts with `lo` down.
+fn bring_up_loopback_interface() -> std::io::Result<()> {
+    let fd = unsafe { libc::socket(libc::AF_INET, libc::SOCK_DGRAM | libc::SOCK_CLOEXEC, 0) };
+    if fd < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    let mut request: libc::ifreq = unsafe { std::mem::zeroed() };
+    for (dst, src) in request.ifr_name.iter_mut().zip(b"lo") {
+        *dst = *src as libc::c_char;
+    }
+    let result = unsafe {
+        if libc::ioctl(fd, libc::SIOCGIFFLAGS as _, &mut request) < 0 {
+            Err(std::io::Error::last_os_error())
+        } else {
+            request.ifr_ifru.ifru_flags |= (libc::IFF_UP | libc::IFF_RUNNING) as libc::c_short;
+            if libc::ioctl(fd, libc::SIOCSIFFLAGS as _, &request) < 0 {
+                Err(std::io::Error::last_os_error())
+            } else {
+                Ok(())
+            }
+        }
+    };
+    unsafe {
+        libc::close(fd);
+    }
+    result
+}
+
+/// Installs Landlock network rules allowing TCP connections to `port` only,
+/// which is where the allowlisting proxy listens. Landlock matches ports,
+/// not addresses, so the same port on another host stays reachable; the
+/// proxy's port is picked at random. Fails on kernels without Landlock
+/// network support (before 6.7).
+fn install_proxy_only_landlock_rules_on_current_thread(port: u16) -> Result<()> {
+    let status = Ruleset::default()
+        .set_compatibility(CompatLevel::HardRequirement)
+        .handle_access(AccessNet::from_all(ABI::V4))?
+        .create()?
+        .add_rule(NetPort::new(port, AccessNet::ConnectTcp))?
+        .set_no_new_privs(true)
+        .restrict_self()?;
+
+    if status.ruleset != landlock::RulesetStatus::FullyEnforced {
+        return Err(CodexErr::Sandbox(SandboxErr::LandlockRestrict));
+    }
+
+    Ok(())
+}
+
+/// Installs a seccomp filter that leaves TCP as the only IP protocol, since
+/// Landlock network rules do not cover UDP or raw sockets.
+fn install_inet_stream_seccomp_filter_on_current_thread() -> std::result::Result<(), SandboxErr> {
+    let mut rules: BTreeMap<i64, Vec<SeccompRule>> = BTreeMap::new();
+
+    let mut socket_rules = vec![SeccompRule::new(
+        [libc::AF_UNIX, libc::AF_INET, libc::AF_INET6]
+            .into_iter()
+            .map(|domain| {
+                SeccompCondition::new(0, SeccompCmpArgLen::Dword, SeccompCmpOp::Ne, domain as u64)
+            })
+            .collect::<std::result::Result<Vec<_>, _>>()?,
+    )?];
+    for domain in [libc::AF_INET, libc::AF_INET6] {
+        for socket_type in [libc::SOCK_DGRAM, libc::SOCK_RAW, libc::SOCK_SEQPACKET] {
+            socket_rules.push(SeccompRule::new(vec![
+                SeccompCondition::new(0, SeccompCmpArgLen::Dword, SeccompCmpOp::Eq, domain as u64)?,
+                // The low bits hold the type; the rest are flags such as
+                // SOCK_CLOEXEC.
+                SeccompCondition::new(
+                    1,
+                    SeccompCmpArgLen::Dword,
+                    SeccompCmpOp::MaskedEq(0xf),
+                    socket_type as u64,
+                )?,
+            ])?);
+        }
+    }
+    rules.insert(libc::SYS_socket, socket_rules);
+    rules.insert(libc::SYS_ptrace, vec![]);
+
+    apply_seccomp_deny_rules(rules)
+}
+
+/// Applies a seccomp filter that fails the matching syscalls with `EPERM`.
+fn apply_seccomp_deny_rules(
+    rules: BTreeMap<i64, Vec<SeccompRule>>,
+) -> std::result::Result<(), SandboxErr> {
     let filter = SeccompFilter::new(
         rules,
         SeccompAction::Allow,                     // default – allow
@@ -1,4 +1,5 @@
 use clap::Parser;
+use codex_core::sandboxing::network::SandboxNetwork;
 use std::ffi::CString;
 use std::path::PathBuf;
 
@@ -14,6 +15,11 @@ pub struct LandlockCommand {
     #[arg(long = "sandbox-policy")]
     pub sandbox_policy: codex_core::protocol::SandboxPolicy,
 
+    /// Network access for this command, when it differs from what the
+    /// sandbox policy allows.
+    #[arg(long = "network")]
+    pub network: Option<SandboxNetwork>,
+
     /// Full command args to run under landlock.
     #[arg(trailing_var_arg = true)]
     pub command: Vec<String>,
@@ -23,10 +29,14 @@ pub fn run_main() -> ! {
     let LandlockCommand {
         sandbox_policy_cwd,
         sandbox_policy,
+        network,
         command,
     } = LandlockCommand::parse();
 
-    if let Err(e) = apply_sandbox_policy_to_current_thread(&sandbox_policy, &sandbox_policy_cwd) {
+    let network = network.unwrap_or_else(|| SandboxNetwork::from_policy(&sandbox_policy));
+    if let Err(e) =
+        apply_sandbox_policy_to_current_thread(&sandbox_policy, network, &sandbox_policy_cwd)
+    {
         panic!("error running landlock: {e:?}");
     }
 
@@ -181,6 +181,9 @@ async fn run_codex_tool_session_inner(
                         reason: _,
                         risk,
                         parsed_cmd,
+                        scripts: _,
+                        expansions: _,
+                        trust_prefix: _,
                     }) => {
                         handle_exec_approval_request(
                             command,
@@ -218,6 +221,8 @@ async fn run_codex_tool_session_inner(
                         reason,
                         grant_root,
                         changes,
+                        hunks: _,
+                        user_changes: _,
                     }) => {
                         handle_patch_approval_request(
                             call_id,
@@ -280,8 +285,13 @@ async fn run_codex_tool_session_inner(
                     | EventMsg::McpToolCallEnd(_)
                     | EventMsg::McpListToolsResponse(_)
                     | EventMsg::ListCustomPromptsResponse(_)
+                    | EventMsg::ListFilesReadResponse(_)
+                    | EventMsg::ContextUsage(_)
+                    | EventMsg::ApprovalCache(_)
                     | EventMsg::ExecCommandBegin(_)
                     | EventMsg::ExecCommandOutputDelta(_)
+                    | EventMsg::ExecCommandProgress(_)
+                    | EventMsg::TurnResourceUsage(_)
                     | EventMsg::ExecCommandEnd(_)
                     | EventMsg::BackgroundEvent(_)
                     | EventMsg::StreamError(_)
@@ -296,6 +306,7 @@ async fn run_codex_tool_session_inner(
                     | EventMsg::UserMessage(_)
                     | EventMsg::ShutdownComplete
                     | EventMsg::ViewImageToolCall(_)
+                    | EventMsg::FileRead(_)
                     | EventMsg::RawResponseItem(_)
                     | EventMsg::EnteredReviewMode(_)
                     | EventMsg::ItemStarted(_)
@@ -305,6 +316,17 @@ async fn run_codex_tool_session_inner(
                     | EventMsg::ReasoningRawContentDelta(_)
                     | EventMsg::UndoStarted(_)
                     | EventMsg::UndoCompleted(_)
+                    | EventMsg::Artifact(_)
+                    | EventMsg::StatusLine(_)
+                    | EventMsg::CollaboratorJoined(_)
+                    | EventMsg::CollaboratorLeft(_)
+                    | EventMsg::ApprovalResolved(_)
+                    | EventMsg::ApprovalRuleApplied(_)
+                    | EventMsg::WorkspaceIntegrity(_)
+                    | EventMsg::EnvironmentChanged(_)
+                    | EventMsg::SandboxProbe(_)
+                    | EventMsg::DoctorReport(_)
+                    | EventMsg::ExecApprovalBatchRequest(_)
                     | EventMsg::ExitedReviewMode(_)
                     | EventMsg::DeprecationNotice(_) => {
                         // For now, we do not do anything extra for these
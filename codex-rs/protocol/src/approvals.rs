@@ -3,13 +3,17 @@ use std::path::PathBuf;
 
 use crate::parse_command::ParsedCommand;
 use crate::protocol::FileChange;
+use crate::protocol::SyntaxToken;
 use mcp_types::RequestId;
 use schemars::JsonSchema;
 use serde::Deserialize;
 use serde::Serialize;
 use ts_rs::TS;
 
-#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, Hash, JsonSchema, TS)]
+/// Ordered from least to most risky.
+#[derive(
+    Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord, Hash, JsonSchema, TS,
+)]
 #[serde(rename_all = "snake_case")]
 pub enum SandboxRiskLevel {
     Low,
@@ -21,6 +25,28 @@ pub enum SandboxRiskLevel {
 pub struct SandboxCommandAssessment {
     pub description: String,
     pub risk_level: SandboxRiskLevel,
+    /// What the static analysis of the command found, each with its own
+    /// level. `risk_level` is at least the highest of them.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub reasons: Vec<RiskReason>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, Hash, JsonSchema, TS)]
+#[serde(rename_all = "snake_case")]
+pub enum RiskCategory {
+    RecursiveDelete,
+    RemoteScript,
+    PrivilegeEscalation,
+    WriteOutsideWorkspace,
+    PackageInstall,
+}
+
+/// A risky pattern found in a command.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, JsonSchema, TS)]
+pub struct RiskReason {
+    pub category: RiskCategory,
+    pub risk_level: SandboxRiskLevel,
+    pub detail: String,
 }
 
 impl SandboxRiskLevel {
@@ -52,6 +78,60 @@ pub struct ExecApprovalRequestEvent {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub risk: Option<SandboxCommandAssessment>,
     pub parsed_cmd: Vec<ParsedCommand>,
+    /// Scripts embedded in the command, such as the body of `python3 -c` or
+    /// of a heredoc, so they can be reviewed as code.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub scripts: Vec<InlineScript>,
+    /// Aliases and functions of the user's shell profile that the command
+    /// would run through, when it runs in the login shell.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub expansions: Vec<CommandExpansion>,
+    /// The words a command has to start with to be covered when the user
+    /// answers `ReviewDecision::ApprovedPrefixForSession`, e.g. `["pytest"]`
+    /// or `["cargo", "test"]`. `None` when trusting a prefix is not offered.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub trust_prefix: Option<Vec<String>>,
+}
+
+/// Several commands waiting for approval at once, asked about together.
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema, TS)]
+pub struct ExecApprovalBatchRequestEvent {
+    /// The id to answer with in `Op::ExecApprovalBatch`.
+    pub id: String,
+    /// Turn ID that the commands belong to.
+    pub turn_id: String,
+    pub requests: Vec<ExecApprovalRequestEvent>,
+}
+
+/// What a command name stands for in the user's login shell.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, JsonSchema, TS)]
+pub struct CommandExpansion {
+    pub name: String,
+    pub kind: CommandExpansionKind,
+    /// The text an alias expands to, or the source of a function.
+    pub definition: String,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, JsonSchema, TS)]
+#[serde(rename_all = "snake_case")]
+pub enum CommandExpansionKind {
+    Alias,
+    Function,
+}
+
+/// A script passed inline to an interpreter by a command.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, JsonSchema, TS)]
+pub struct InlineScript {
+    /// The script's language, such as `bash` or `python`, when it is known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub language: Option<String>,
+    pub source: String,
+    /// Syntax highlighting spans for each line of `source`. Empty when no
+    /// grammar for the language is built in.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tokens: Vec<Vec<SyntaxToken>>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, JsonSchema, TS)]
@@ -87,4 +167,66 @@ pub struct ApplyPatchApprovalRequestEvent {
     /// When set, the agent is asking the user to allow writes under this root for the remainder of the session.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub grant_root: Option<PathBuf>,
+    /// The patch split into hunks that can be accepted or rejected one by one
+    /// with `Op::PatchHunkApproval`, in the order decisions are given.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hunks: Vec<PatchHunk>,
+    /// Uncommitted changes the user has in files this patch would modify,
+    /// made outside this session. Approving overwrites them.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub user_changes: Vec<UserChange>,
+}
+
+/// One independently reviewable part of a patch awaiting approval: an added or
+/// deleted file, one chunk of an updated file, or a rename.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, JsonSchema, TS)]
+pub struct PatchHunk {
+    pub path: PathBuf,
+    /// Unified diff of this hunk alone; empty for a plain rename.
+    pub unified_diff: String,
+}
+
+/// A file's pending, uncommitted changes that were not made by the agent.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, JsonSchema, TS)]
+pub struct UserChange {
+    pub path: PathBuf,
+    /// Unified diff from the last commit to the file on disk.
+    pub unified_diff: String,
+}
+
+/// How long a cached approval is replayed.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, JsonSchema, TS)]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalScope {
+    /// Given with "approve for session"; lapses when the policies, the working
+    /// directory or the files it covered change.
+    Session,
+    /// Seeded with `Op::SeedApprovals`; holds for the rest of the session.
+    Seeded,
+}
+
+/// One approval the session replays instead of asking again.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, JsonSchema, TS)]
+pub struct CachedApproval {
+    /// Identifies the entry for `Op::RevokeApproval`.
+    pub id: String,
+    /// What was approved, e.g. the command line and where it runs.
+    pub summary: String,
+    pub scope: ApprovalScope,
+    /// Seconds since the approval was given.
+    pub age_secs: u64,
+}
+
+/// An approval to grant up front, before the agent asks for it.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, JsonSchema, TS)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ApprovalSeed {
+    /// Running exactly `command` in `cwd`, through the shell tools.
+    Exec {
+        command: Vec<String>,
+        cwd: PathBuf,
+        /// Whether the approval also covers running outside the sandbox.
+        #[serde(default)]
+        escalated: bool,
+    },
 }
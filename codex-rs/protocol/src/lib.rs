@@ -9,6 +9,8 @@ pub mod message_history;
 pub mod models;
 pub mod num_format;
 pub mod parse_command;
+pub mod paths;
 pub mod plan_tool;
 pub mod protocol;
+pub mod rendered_diff;
 pub mod user_input;
@@ -0,0 +1,208 @@
+//! Path normalization for comparing paths rather than opening them.
+//!
+//! Approval keys, writable-root containment and other path checks must treat
+//! equivalent spellings of a path as the same path. On Windows that means
+//! `C:\Repo\Foo`, `c:/repo/foo` and `\\?\C:\Repo\Foo` all compare equal;
+//! otherwise a differently spelled path could miss a cached approval or slip
+//! past a writable-root check. Normalization is lexical and never touches the
+//! filesystem, so it also works for paths that do not exist yet.
+//!
+//! [`to_extended_length`] goes the other way: it produces the spelling of a
+//! path to hand to Windows APIs that reject paths longer than `MAX_PATH`.
+
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// The form of `path` to use when comparing it with other paths: `.` and `..`
+/// resolved and, on Windows, separators, verbatim prefixes and case folded.
+pub fn normalize_for_comparison(path: &Path) -> PathBuf {
+    if cfg!(windows) {
+        PathBuf::from(normalize_windows(&path.to_string_lossy()))
+    } else {
+        normalize_lexically(path)
+    }
+}
+
+/// Whether `path` is `root` or lies inside it, after normalizing both.
+pub fn is_within(path: &Path, root: &Path) -> bool {
+    normalize_for_comparison(path).starts_with(normalize_for_comparison(root))
+}
+
+/// Paths this long (in UTF-16 units) need the extended-length form. This is
+/// `MAX_PATH` minus room for an 8.3 file name, the limit of `CreateDirectoryW`.
+const MAX_SHORT_PATH: usize = 248;
+
+/// The `\\?\` extended-length form of `path` on Windows when it is absolute and
+/// too long for the regular Win32 path limit, so deeply nested trees such as
+/// `node_modules` stay reachable. Returns `path` unchanged otherwise. The Rust
+/// standard library already does this for `std::fs`; this is for paths passed
+/// to Win32 APIs directly.
+pub fn to_extended_length(path: &Path) -> PathBuf {
+    let extended = if cfg!(windows) {
+        extended_length_windows(&path.to_string_lossy())
+    } else {
+        None
+    };
+    extended.map_or_else(|| path.to_path_buf(), PathBuf::from)
+}
+
+fn normalize_lexically(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::ParentDir => {
+                out.pop();
+            }
+            Component::CurDir => {}
+            other => out.push(other.as_os_str()),
+        }
+    }
+    out
+}
+
+/// Windows rules, implemented on strings so they can be tested everywhere.
+fn normalize_windows(path: &str) -> String {
+    let path = path.replace('/', "\\");
+    let path = if let Some(rest) = path.strip_prefix(r"\\?\UNC\") {
+        format!(r"\\{rest}")
+    } else if let Some(rest) = path.strip_prefix(r"\\?\") {
+        rest.to_string()
+    } else {
+        path
+    };
+
+    let (prefix, rest, absolute) = if let Some(unc) = path.strip_prefix(r"\\") {
+        let mut parts = unc.splitn(3, '\\');
+        let server = parts.next().unwrap_or_default();
+        let share = parts.next().unwrap_or_default();
+        (
+            format!(r"\\{server}\{share}"),
+            parts.next().unwrap_or_default(),
+            true,
+        )
+    } else if path.as_bytes().get(1) == Some(&b':') {
+        let (drive, rest) = path.split_at(2);
+        (drive.to_string(), rest, rest.starts_with('\\'))
+    } else {
+        (String::new(), path.as_str(), path.starts_with('\\'))
+    };
+
+    let mut parts: Vec<&str> = Vec::new();
+    for part in rest.split('\\') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            part => parts.push(part),
+        }
+    }
+
+    let mut normalized = prefix;
+    if absolute {
+        normalized.push('\\');
+    }
+    normalized.push_str(&parts.join("\\"));
+    normalized.to_lowercase()
+}
+
+fn extended_length_windows(path: &str) -> Option<String> {
+    if path.encode_utf16().count() < MAX_SHORT_PATH || path.starts_with(r"\\?\") {
+        return None;
+    }
+    let path = path.replace('/', "\\");
+    // Extended-length paths reach the file system unparsed, so `.` and `..`
+    // have to be resolved here.
+    let resolve = |path: &str| {
+        let mut parts: Vec<&str> = Vec::new();
+        for part in path.split('\\') {
+            match part {
+                "" | "." => {}
+                ".." => {
+                    if parts.len() > 1 {
+                        parts.pop();
+                    }
+                }
+                part => parts.push(part),
+            }
+        }
+        parts.join("\\")
+    };
+    if let Some(unc) = path.strip_prefix(r"\\") {
+        Some(format!(r"\\?\UNC\{}", resolve(unc)))
+    } else if path.as_bytes().get(1) == Some(&b':') && path.as_bytes().get(2) == Some(&b'\\') {
+        Some(format!(r"\\?\{}", resolve(&path)))
+    } else {
+        // Relative and drive-relative paths have no extended-length form.
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn windows_spellings_of_one_path_are_equal() {
+        for spelling in [
+            r"C:\Repo\Foo",
+            "c:/repo/foo",
+            r"\\?\C:\Repo\Foo",
+            r"C:\Repo\.\Bar\..\Foo\",
+            r"C:\\Repo\\Foo",
+        ] {
+            assert_eq!(normalize_windows(spelling), r"c:\repo\foo", "{spelling}");
+        }
+        assert_eq!(normalize_windows(r"C:\"), r"c:\");
+    }
+
+    #[test]
+    fn windows_unc_paths_keep_their_share() {
+        for spelling in [
+            r"\\?\UNC\Server\Share\Dir",
+            "//server/share/dir",
+            r"\\Server\Share\Other\..\Dir",
+        ] {
+            assert_eq!(
+                normalize_windows(spelling),
+                r"\\server\share\dir",
+                "{spelling}"
+            );
+        }
+    }
+
+    #[test]
+    fn unix_paths_resolve_dot_components_only() {
+        assert_eq!(
+            normalize_lexically(Path::new("/repo/./src/../Lib/")),
+            PathBuf::from("/repo/Lib")
+        );
+        assert!(is_within(
+            Path::new("/repo/src/../lib/a.rs"),
+            Path::new("/repo")
+        ));
+        assert!(!is_within(
+            Path::new("/repo/../etc/passwd"),
+            Path::new("/repo")
+        ));
+    }
+
+    #[test]
+    fn long_windows_paths_get_the_extended_length_prefix() {
+        let deep = "node_modules\\pkg\\".repeat(20);
+        assert_eq!(
+            extended_length_windows(&format!(r"C:\repo\.\{deep}index.js")),
+            Some(format!(r"\\?\C:\repo\{deep}index.js"))
+        );
+        assert_eq!(
+            extended_length_windows(&format!(r"\\server\share\{deep}index.js")),
+            Some(format!(r"\\?\UNC\server\share\{deep}index.js"))
+        );
+        assert_eq!(extended_length_windows(&format!(r"{deep}index.js")), None);
+        assert_eq!(extended_length_windows(r"C:\repo\index.js"), None);
+        let extended = format!(r"\\?\C:\repo\{deep}index.js");
+        assert_eq!(extended_length_windows(&extended), None);
+    }
+}
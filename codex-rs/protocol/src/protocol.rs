@@ -21,6 +21,7 @@ use crate::models::ContentItem;
 use crate::models::ResponseItem;
 use crate::num_format::format_with_separators;
 use crate::parse_command::ParsedCommand;
+use crate::paths::is_within;
 use crate::plan_tool::UpdatePlanArgs;
 use crate::user_input::UserInput;
 use mcp_types::CallToolResult;
@@ -37,10 +38,21 @@ use strum_macros::Display;
 use ts_rs::TS;
 
 pub use crate::approvals::ApplyPatchApprovalRequestEvent;
+pub use crate::approvals::ApprovalScope;
+pub use crate::approvals::ApprovalSeed;
+pub use crate::approvals::CachedApproval;
+pub use crate::approvals::CommandExpansion;
+pub use crate::approvals::CommandExpansionKind;
 pub use crate::approvals::ElicitationAction;
+pub use crate::approvals::ExecApprovalBatchRequestEvent;
 pub use crate::approvals::ExecApprovalRequestEvent;
+pub use crate::approvals::InlineScript;
+pub use crate::approvals::PatchHunk;
+pub use crate::approvals::RiskCategory;
+pub use crate::approvals::RiskReason;
 pub use crate::approvals::SandboxCommandAssessment;
 pub use crate::approvals::SandboxRiskLevel;
+pub use crate::approvals::UserChange;
 
 /// Open/close tags for special user-input blocks. Used across crates to avoid
 /// duplicated hardcoded strings.
@@ -156,6 +168,25 @@ pub enum Op {
         decision: ReviewDecision,
     },
 
+    /// Approve some hunks of a code patch and reject the others. Only the
+    /// approved hunks are applied.
+    PatchHunkApproval {
+        /// The id of the submission we are approving
+        id: String,
+        /// One decision per entry of the request's `hunks`, in order. Missing
+        /// entries count as denied; `abort` for any hunk aborts the turn.
+        decisions: Vec<ReviewDecision>,
+    },
+
+    /// Answer an `ExecApprovalBatchRequest`.
+    ExecApprovalBatch {
+        /// The `id` of the batch.
+        id: String,
+        /// One decision per entry of the batch's `requests`, in order. Missing
+        /// entries count as denied; `abort` for any command aborts the turn.
+        decisions: Vec<ReviewDecision>,
+    },
+
     /// Resolve an MCP elicitation request.
     ResolveElicitation {
         /// Name of the MCP server that issued the request.
@@ -185,6 +216,29 @@ pub enum Op {
     /// Request the list of available custom prompts.
     ListCustomPrompts,
 
+    /// Request the ledger of files whose contents tools exposed to the model
+    /// in this session. Reply is delivered via `EventMsg::ListFilesReadResponse`.
+    ListFilesRead,
+
+    /// Request an estimate of how much of the model's context each source
+    /// (instructions, tool outputs, messages) currently occupies. Reply is
+    /// delivered via `EventMsg::ContextUsage`.
+    GetContextUsage,
+
+    /// Request the approvals this session replays without asking. Reply is
+    /// delivered via `EventMsg::ApprovalCache`.
+    ListApprovals,
+
+    /// Forget the cached approval with the given id, as listed in
+    /// `EventMsg::ApprovalCache`, so the user is asked again next time. The
+    /// remaining approvals are sent back in `EventMsg::ApprovalCache`.
+    RevokeApproval { id: String },
+
+    /// Grant approvals up front, e.g. for commands an embedder trusts. Seeded
+    /// approvals hold for the rest of the session, whatever its settings. The
+    /// resulting approvals are sent back in `EventMsg::ApprovalCache`.
+    SeedApprovals { approvals: Vec<ApprovalSeed> },
+
     /// Request the agent to summarize the current conversation context.
     /// The agent will use its existing context (either conversation history or previous response id)
     /// to generate a summary which will be returned as an AgentMessage event.
@@ -193,6 +247,16 @@ pub enum Op {
     /// Request Codex to undo a turn (turn are stacked so it is the same effect as CMD + Z).
     Undo,
 
+    /// Revert the last `count` patches applied in this session, newest first
+    /// (with the `undo_patch` feature). The result is reported with
+    /// [`EventMsg::UndoCompleted`].
+    UndoPatch { count: usize },
+
+    /// Redo the last turn differently: roll back its workspace changes (like
+    /// `Undo`), drop it from the history, and run it again with its user
+    /// message followed by `instructions`, e.g. "but keep the public API".
+    RetryTurn { instructions: String },
+
     /// Request a code review from the agent.
     Review { review_request: ReviewRequest },
 
@@ -208,6 +272,36 @@ pub enum Op {
         /// The raw command string after '!'
         command: String,
     },
+
+    /// Hand a file to the agent without placing it in the workspace (a log
+    /// bundle, a CSV export). The file is copied into the session's artifact
+    /// store and announced with [`EventMsg::Artifact`].
+    AddArtifact {
+        path: PathBuf,
+        /// Display name; defaults to the file name of `path`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        name: Option<String>,
+    },
+
+    /// Look for state a crashed or cancelled command may have left in the
+    /// workspace. This server sends [`EventMsg::WorkspaceIntegrity`] in
+    /// response, with an empty list when nothing was found.
+    CheckWorkspace,
+
+    /// Apply the cleanup action of the findings with the given ids, as
+    /// reported by the last [`EventMsg::WorkspaceIntegrity`]. The workspace is
+    /// checked again first, and the findings that remain are reported.
+    CleanupWorkspace { ids: Vec<String> },
+
+    /// Stop reporting the quarantined files with the given ids, leaving them
+    /// where the escalated command created them. This server answers with
+    /// [`EventMsg::WorkspaceIntegrity`] like [`Op::CleanupWorkspace`].
+    KeepQuarantined { ids: Vec<String> },
+
+    /// Check the environment commands run in: the sandbox, the shell, git,
+    /// credentials and the configured endpoints. This server sends
+    /// [`EventMsg::DoctorReport`] in response.
+    RunDoctor,
 }
 
 /// Determines the conditions under which the user is consulted to approve
@@ -307,13 +401,13 @@ pub struct WritableRoot {
 impl WritableRoot {
     pub fn is_path_writable(&self, path: &Path) -> bool {
         // Check if the path is under the root.
-        if !path.starts_with(&self.root) {
+        if !is_within(path, &self.root) {
             return false;
         }
 
         // Check if the path is under any of the read-only subpaths.
         for subpath in &self.read_only_subpaths {
-            if path.starts_with(subpath) {
+            if is_within(path, subpath) {
                 return false;
             }
         }
@@ -330,6 +424,51 @@ impl FromStr for SandboxPolicy {
     }
 }
 
+/// Network access a single command needs, in place of what the
+/// [`SandboxPolicy`] allows. Access beyond the policy needs approval.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema, TS)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum NetworkAccess {
+    /// No network at all.
+    None,
+    /// Connections to and from this machine only.
+    Loopback,
+    /// HTTP and HTTPS to these hosts only, through a proxy that refuses the
+    /// rest. `*.example.com` also matches subdomains.
+    Allowlist { hosts: Vec<String> },
+}
+
+impl NetworkAccess {
+    /// Whether this allows more than `policy` does.
+    pub fn exceeds(&self, policy: &SandboxPolicy) -> bool {
+        !policy.has_full_network_access() && *self != NetworkAccess::None
+    }
+
+    /// Whether an allowlist entry matches `host`.
+    pub fn host_matches(pattern: &str, host: &str) -> bool {
+        let pattern = pattern.trim_end_matches('.').to_ascii_lowercase();
+        let host = host.trim_end_matches('.').to_ascii_lowercase();
+        match pattern.strip_prefix("*.") {
+            Some(domain) => host
+                .strip_suffix(domain)
+                .is_some_and(|subdomain| subdomain.ends_with('.')),
+            None => host == pattern,
+        }
+    }
+}
+
+impl fmt::Display for NetworkAccess {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NetworkAccess::None => f.write_str("no network access"),
+            NetworkAccess::Loopback => f.write_str("network access to this machine only"),
+            NetworkAccess::Allowlist { hosts } => {
+                write!(f, "network access to {}", hosts.join(", "))
+            }
+        }
+    }
+}
+
 impl SandboxPolicy {
     /// Returns a policy with read-only disk access and no network.
     pub fn new_read_only_policy() -> Self {
@@ -461,6 +600,10 @@ pub enum EventMsg {
     /// Agent has completed all actions
     TaskComplete(TaskCompleteEvent),
 
+    /// Resources used by the commands the agent ran during the turn; sent
+    /// just before `TaskComplete` when at least one command finished.
+    TurnResourceUsage(TurnResourceUsageEvent),
+
     /// Usage update for the current session, including totals and last turn.
     /// Optional means unknown — UIs should not display when `None`.
     TokenCount(TokenCountEvent),
@@ -511,17 +654,29 @@ pub enum EventMsg {
     /// Incremental chunk of output from a running command.
     ExecCommandOutputDelta(ExecCommandOutputDeltaEvent),
 
+    /// Percent-complete of a running command whose progress output Codex
+    /// recognizes (cargo, pytest, yarn, pnpm).
+    ExecCommandProgress(ExecCommandProgressEvent),
+
     ExecCommandEnd(ExecCommandEndEvent),
 
     /// Notification that the agent attached a local image via the view_image tool.
     ViewImageToolCall(ViewImageToolCallEvent),
 
+    /// Lines the read_file tool returned, for frontends to preview; emitted
+    /// only when the `file_read_events` feature is enabled.
+    FileRead(FileReadEvent),
+
     ExecApprovalRequest(ExecApprovalRequestEvent),
 
     ElicitationRequest(ElicitationRequestEvent),
 
     ApplyPatchApprovalRequest(ApplyPatchApprovalRequestEvent),
 
+    /// Several commands need approval at once; sent instead of one
+    /// `ExecApprovalRequest` each when the `batch_approvals` feature is on.
+    ExecApprovalBatchRequest(ExecApprovalBatchRequestEvent),
+
     /// Notification advising the user that something they are using has been
     /// deprecated and should be phased out.
     DeprecationNotice(DeprecationNoticeEvent),
@@ -532,6 +687,43 @@ pub enum EventMsg {
 
     UndoCompleted(UndoCompletedEvent),
 
+    /// A file entered the session's artifact store, either handed over by the
+    /// user or sent back by the agent.
+    Artifact(ArtifactEvent),
+
+    /// One-line, human-readable summary of the event before it, emitted only
+    /// when the `condensed_events` feature is enabled.
+    StatusLine(StatusLineEvent),
+
+    /// Another client attached to this session.
+    CollaboratorJoined(CollaboratorEvent),
+
+    /// A collaborator detached from this session.
+    CollaboratorLeft(CollaboratorEvent),
+
+    /// An approval prompt was answered, possibly by another participant;
+    /// frontends still showing the prompt should dismiss it.
+    ApprovalResolved(ApprovalResolvedEvent),
+
+    /// One of the configured `approval_rules` decided whether a command runs
+    /// or asks, so frontends can show why it ran without a prompt.
+    ApprovalRuleApplied(ApprovalRuleAppliedEvent),
+
+    /// Leftover state found in the workspace, sent on request and, when there
+    /// is something to report, right before [`EventMsg::ShutdownComplete`].
+    WorkspaceIntegrity(WorkspaceIntegrityEvent),
+
+    /// Global state outside the workspace that a command run without the
+    /// sandbox changed, such as commands on `PATH` or shell startup files.
+    EnvironmentChanged(EnvironmentChangedEvent),
+
+    /// Whether the platform sandbox could be set up, sent after
+    /// [`EventMsg::SessionConfigured`] on platforms where it is probed.
+    SandboxProbe(SandboxProbeEvent),
+
+    /// Results of the checks requested with [`Op::RunDoctor`].
+    DoctorReport(DoctorReportEvent),
+
     /// Notification that a model stream experienced an error or disconnect
     /// and the system is handling it (e.g., retrying with backoff).
     StreamError(StreamErrorEvent),
@@ -554,6 +746,16 @@ pub enum EventMsg {
     /// List of custom prompts available to the agent.
     ListCustomPromptsResponse(ListCustomPromptsResponseEvent),
 
+    /// Files read by tools during the session.
+    ListFilesReadResponse(ListFilesReadResponseEvent),
+
+    /// Context consumed by each source, in response to `Op::GetContextUsage`.
+    ContextUsage(ContextUsageEvent),
+
+    /// The approvals the session replays, in response to `Op::ListApprovals`,
+    /// `Op::RevokeApproval` and `Op::SeedApprovals`.
+    ApprovalCache(ApprovalCacheEvent),
+
     PlanUpdate(UpdatePlanArgs),
 
     TurnAborted(TurnAbortedEvent),
@@ -744,6 +946,18 @@ pub struct TaskCompleteEvent {
     pub last_agent_message: Option<String>,
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, JsonSchema, TS)]
+pub struct TurnResourceUsageEvent {
+    /// User plus system CPU time of the commands that finished in the turn.
+    pub cpu_seconds: f64,
+    /// Largest resident set size of any command run so far in the session;
+    /// the OS tracks only the maximum since Codex started.
+    pub peak_rss_bytes: u64,
+    /// Bytes the commands wrote to storage; only measured on Linux.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disk_write_bytes: Option<u64>,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, JsonSchema, TS)]
 pub struct TaskStartedEvent {
     pub model_context_window: Option<i64>,
@@ -1358,6 +1572,41 @@ pub struct ViewImageToolCallEvent {
     pub path: PathBuf,
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, JsonSchema, TS)]
+pub struct FileReadEvent {
+    /// Identifier for the originating tool call.
+    pub call_id: String,
+    pub path: PathBuf,
+    /// Language guessed from the file name, such as `rust` or `bash`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub language: Option<String>,
+    /// The lines returned to the model, in order.
+    pub lines: Vec<FileReadLine>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, JsonSchema, TS)]
+pub struct FileReadLine {
+    /// 1-based line number in the file.
+    pub number: u32,
+    pub text: String,
+    /// Syntax highlighting spans, derived with tree-sitter from the lines
+    /// returned. Empty when no grammar for the language is built in.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tokens: Vec<SyntaxToken>,
+}
+
+/// A highlighted range of characters (Unicode scalar values, not bytes) in a
+/// line, with `end` exclusive.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, JsonSchema, TS)]
+pub struct SyntaxToken {
+    pub start: u32,
+    pub end: u32,
+    /// The tree-sitter highlight name, such as `keyword`, `string` or
+    /// `comment`.
+    pub kind: String,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, JsonSchema, TS)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecOutputStream {
@@ -1372,6 +1621,10 @@ pub struct ExecCommandOutputDeltaEvent {
     pub call_id: String,
     /// Which stream produced this chunk.
     pub stream: ExecOutputStream,
+    /// Position of this chunk among the chunks of the call, counting both
+    /// streams, from 0. Chunks are sent in this order.
+    #[serde(default)]
+    pub seq: u64,
     /// Raw bytes from the stream (may not be valid UTF-8).
     #[serde_as(as = "serde_with::base64::Base64")]
     #[schemars(with = "String")]
@@ -1379,6 +1632,21 @@ pub struct ExecCommandOutputDeltaEvent {
     pub chunk: Vec<u8>,
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, JsonSchema, TS)]
+pub struct ExecCommandProgressEvent {
+    /// Identifier for the ExecCommandBegin of the command making progress.
+    pub call_id: String,
+    /// The recognized operation, e.g. `cargo` or `pytest`.
+    pub operation: String,
+    /// Units of work done so far (crates, tests, install steps, packages).
+    pub completed: u64,
+    pub total: u64,
+    pub percent: u8,
+    /// What the command is working on now, when it says.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current: Option<String>,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, JsonSchema, TS)]
 pub struct BackgroundEventEvent {
     pub message: String,
@@ -1406,6 +1674,183 @@ pub struct UndoCompletedEvent {
     pub message: Option<String>,
 }
 
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, JsonSchema, TS)]
+#[serde(rename_all = "snake_case")]
+pub enum ArtifactDirection {
+    /// Handed to the agent by the user.
+    Inbound,
+    /// Sent to the user by the agent, after the user approved the transfer.
+    Outbound,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, JsonSchema, TS)]
+pub struct ArtifactEvent {
+    pub id: String,
+    pub name: String,
+    pub direction: ArtifactDirection,
+    /// Location of the stored copy, outside the workspace.
+    pub path: PathBuf,
+    pub size_bytes: u64,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, JsonSchema, TS)]
+pub struct StatusLineEvent {
+    pub message: String,
+}
+
+/// What a client attached to someone else's session may do.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, Display, JsonSchema, TS)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum CollaboratorRole {
+    /// Sees the event stream only.
+    Observer,
+    /// Sees the event stream and may answer approval prompts or interrupt.
+    Approver,
+    /// May also send user messages; used by the `control_socket`.
+    Controller,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, JsonSchema, TS)]
+pub struct CollaboratorEvent {
+    pub id: String,
+    pub role: CollaboratorRole,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, JsonSchema, TS)]
+pub struct ApprovalResolvedEvent {
+    /// The id the answer was submitted with, i.e. the id of the event that
+    /// carried the approval request.
+    pub id: String,
+    pub decision: ReviewDecision,
+}
+
+/// What an approval rule does with the commands it matches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema, TS)]
+#[serde(rename_all = "kebab-case")]
+pub enum ApprovalRuleDecision {
+    /// Run without asking.
+    AutoApprove,
+    /// Refuse without asking.
+    AutoDeny,
+    /// Ask, even when the approval policy would not.
+    Ask,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, JsonSchema, TS)]
+pub struct ApprovalRuleAppliedEvent {
+    pub call_id: String,
+    pub command: Vec<String>,
+    pub cwd: PathBuf,
+    /// The rule's `name`, or its position in `approval_rules` counting from 1.
+    pub rule: String,
+    pub decision: ApprovalRuleDecision,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, JsonSchema, TS)]
+pub struct ApprovalCacheEvent {
+    /// Oldest first.
+    pub approvals: Vec<CachedApproval>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, JsonSchema, TS)]
+#[serde(rename_all = "snake_case")]
+pub enum IntegrityFindingKind {
+    /// A git lock file created during the session whose owner is gone.
+    StaleLockFile,
+    /// A `.orig` or `.rej` file left behind by a partially applied patch.
+    PatchLeftover,
+    /// A process started during the session that outlived the command that
+    /// spawned it.
+    OrphanedProcess,
+    /// A file or directory created outside the workspace by a command that
+    /// ran without the sandbox.
+    EscalatedFile,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, JsonSchema, TS)]
+pub struct IntegrityFinding {
+    /// Stable identifier to pass to [`Op::CleanupWorkspace`].
+    pub id: String,
+    pub kind: IntegrityFindingKind,
+    pub description: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pid: Option<i64>,
+    /// What cleaning up this finding does, e.g. "delete the file".
+    pub cleanup: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, JsonSchema, TS)]
+pub struct WorkspaceIntegrityEvent {
+    pub findings: Vec<IntegrityFinding>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, JsonSchema, TS)]
+#[serde(rename_all = "snake_case")]
+pub enum EnvironmentChangeKind {
+    /// A command appeared on or disappeared from `PATH`.
+    PathCommand,
+    /// A shell startup file such as `~/.bashrc` was created, edited or
+    /// deleted.
+    ShellStartupFile,
+    /// A launchd agent or daemon, or a systemd unit, was added or removed.
+    Service,
+    /// The user's crontab changed.
+    Crontab,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, JsonSchema, TS)]
+pub struct EnvironmentChange {
+    pub kind: EnvironmentChangeKind,
+    pub description: String,
+    /// Lines added (`+`) and removed (`-`) for files and crontabs.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub lines: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, JsonSchema, TS)]
+pub struct EnvironmentChangedEvent {
+    pub call_id: String,
+    pub command: Vec<String>,
+    pub changes: Vec<EnvironmentChange>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, JsonSchema, TS)]
+#[serde(rename_all = "snake_case")]
+pub enum DoctorStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, JsonSchema, TS)]
+pub struct DoctorCheck {
+    pub name: String,
+    pub status: DoctorStatus,
+    pub message: String,
+    /// What to do about a warning or failure.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fix: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, JsonSchema, TS)]
+pub struct DoctorReportEvent {
+    pub checks: Vec<DoctorCheck>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, JsonSchema, TS)]
+pub struct SandboxProbeEvent {
+    /// Commands run in the sandbox; otherwise they run without it.
+    pub available: bool,
+    /// `sandbox_limits` can be enforced.
+    pub limits: bool,
+    /// Why the sandbox or its limits are unavailable.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, JsonSchema, TS)]
 pub struct StreamErrorEvent {
     pub message: String,
@@ -1536,6 +1981,68 @@ pub struct ListCustomPromptsResponseEvent {
     pub custom_prompts: Vec<CustomPrompt>,
 }
 
+/// One file in the session's read ledger.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, JsonSchema, TS)]
+pub struct FileReadEntry {
+    pub path: PathBuf,
+    /// Number of tool calls that read the file.
+    pub reads: u64,
+    /// Bytes of the file's contents returned to the model. Output of a shell
+    /// command that read several files is not attributed to any of them.
+    pub bytes: u64,
+    /// Tools that read the file, in the order they first did.
+    pub tools: Vec<String>,
+}
+
+/// Response payload for `Op::ListFilesRead`.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, JsonSchema, TS)]
+pub struct ListFilesReadResponseEvent {
+    /// Sorted by path.
+    pub files: Vec<FileReadEntry>,
+}
+
+/// Where a part of the model's context comes from.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord, JsonSchema, TS)]
+#[serde(tag = "type", rename_all = "snake_case")]
+#[ts(tag = "type")]
+pub enum ContextSource {
+    /// Base instructions of the model.
+    SystemPrompt,
+    /// Definitions of the tools offered to the model.
+    ToolDefinitions,
+    /// AGENTS.md and developer instructions.
+    Instructions,
+    /// The `<environment_context>` messages describing cwd, sandbox and shell.
+    EnvironmentContext,
+    UserMessages,
+    AgentMessages,
+    Reasoning,
+    /// Arguments of the tool calls made by the model.
+    ToolCalls,
+    /// Output returned by one tool.
+    ToolOutput {
+        tool: String,
+    },
+    /// Summaries that replaced history during compaction.
+    CompactionSummary,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, JsonSchema, TS)]
+pub struct ContextUsageEntry {
+    pub source: ContextSource,
+    pub tokens: i64,
+}
+
+/// Response payload for `Op::GetContextUsage`. Token counts are estimated from
+/// byte sizes and are meant for comparing sources, not for billing.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, JsonSchema, TS)]
+pub struct ContextUsageEvent {
+    /// Sorted by tokens, largest first.
+    pub sources: Vec<ContextUsageEntry>,
+    pub total_tokens: i64,
+    pub model_context_window: Option<i64>,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, JsonSchema, TS)]
 pub struct SessionConfiguredEvent {
     /// Name left as session_id instead of conversation_id for backwards compatibility.
@@ -1588,6 +2095,11 @@ pub enum ReviewDecision {
     /// remainder of the session.
     ApprovedForSession,
 
+    /// User has approved this command and wants to automatically approve any
+    /// future command that starts with the request's `trust_prefix` and runs
+    /// in the same `cwd` for the remainder of the session.
+    ApprovedPrefixForSession,
+
     /// User has denied this command and the agent should not execute it, but
     /// it should continue the session and try something else.
     #[default]
@@ -1612,6 +2124,24 @@ pub enum FileChange {
         unified_diff: String,
         move_path: Option<PathBuf>,
     },
+    /// A file created or replaced with raw bytes, shown by size.
+    Binary {
+        size: u64,
+        /// Size of the file it replaces, `None` when it is created.
+        previous_size: Option<u64>,
+    },
+}
+
+impl FileChange {
+    /// What is shown for a binary file in place of its contents.
+    pub fn binary_summary(size: u64, previous_size: Option<u64>) -> String {
+        match previous_size {
+            Some(previous) => {
+                format!("Binary file replaced: {previous} bytes before, {size} bytes after")
+            }
+            None => format!("Binary file added: {size} bytes"),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, JsonSchema, TS)]
@@ -1645,6 +2175,26 @@ mod tests {
     use serde_json::json;
     use tempfile::NamedTempFile;
 
+    #[test]
+    fn network_access_parses_and_matches_hosts() -> Result<()> {
+        let access: NetworkAccess =
+            serde_json::from_value(json!({"mode": "allowlist", "hosts": ["*.npmjs.org"]}))?;
+        assert_eq!(
+            access,
+            NetworkAccess::Allowlist {
+                hosts: vec!["*.npmjs.org".to_string()],
+            }
+        );
+        assert_eq!(
+            ["registry.npmjs.org", "npmjs.org", "evilnpmjs.org"]
+                .map(|host| NetworkAccess::host_matches("*.npmjs.org", host)),
+            [true, false, false]
+        );
+        assert!(access.exceeds(&SandboxPolicy::new_read_only_policy()));
+        assert!(!access.exceeds(&SandboxPolicy::DangerFullAccess));
+        Ok(())
+    }
+
     #[test]
     fn item_started_event_from_web_search_emits_begin_event() {
         let event = ItemStartedEvent {
@@ -1725,11 +2275,12 @@ mod tests {
         let event = ExecCommandOutputDeltaEvent {
             call_id: "call21".to_string(),
             stream: ExecOutputStream::Stdout,
+            seq: 3,
             chunk: vec![1, 2, 3, 4, 5],
         };
         let serialized = serde_json::to_string(&event)?;
         assert_eq!(
-            r#"{"call_id":"call21","stream":"stdout","chunk":"AQIDBAU="}"#,
+            r#"{"call_id":"call21","stream":"stdout","seq":3,"chunk":"AQIDBAU="}"#,
             serialized,
         );
 
@@ -0,0 +1,90 @@
+//! Diffs in a structured form that any frontend can display without parsing
+//! patch text. Produced by `codex_core::rendered_diff`.
+
+use std::path::PathBuf;
+
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde::Serialize;
+use ts_rs::TS;
+
+/// The changes to one file.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, JsonSchema, TS)]
+pub struct RenderedFileDiff {
+    /// Path of the file after the change.
+    pub path: PathBuf,
+    /// Path of the file before the change, if it was renamed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub old_path: Option<PathBuf>,
+    pub status: RenderedFileStatus,
+    /// Language of the file for syntax highlighting, guessed from its name,
+    /// such as `rust` or `typescript`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub language: Option<String>,
+    /// Whether the file is binary; binary files have no hunks.
+    #[serde(default)]
+    pub binary: bool,
+    pub hunks: Vec<RenderedHunk>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, JsonSchema, TS)]
+#[serde(rename_all = "snake_case")]
+pub enum RenderedFileStatus {
+    Added,
+    Deleted,
+    Modified,
+    Renamed,
+}
+
+/// A contiguous region of changes with its surrounding context.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, JsonSchema, TS)]
+pub struct RenderedHunk {
+    /// First line of the hunk in the old file, 1-based; 0 when it has none.
+    pub old_start: u32,
+    pub old_count: u32,
+    /// First line of the hunk in the new file, 1-based; 0 when it has none.
+    pub new_start: u32,
+    pub new_count: u32,
+    /// Text after the `@@ … @@` range, usually the enclosing function.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub header: String,
+    pub lines: Vec<RenderedLine>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, JsonSchema, TS)]
+pub struct RenderedLine {
+    pub kind: RenderedLineKind,
+    /// Line number in the old file, for context and removed lines.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub old_line: Option<u32>,
+    /// Line number in the new file, for context and added lines.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub new_line: Option<u32>,
+    /// The line without its `+`, `-` or ` ` prefix and line ending.
+    pub text: String,
+    /// The parts of a changed line that differ from its counterpart on the
+    /// other side. Empty when the whole line is new or the two lines have
+    /// too little in common to compare.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub highlights: Vec<HighlightRange>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, JsonSchema, TS)]
+#[serde(rename_all = "snake_case")]
+pub enum RenderedLineKind {
+    Context,
+    Added,
+    Removed,
+}
+
+/// A range of characters (Unicode scalar values, not bytes) in a line, with
+/// `end` exclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, JsonSchema, TS)]
+pub struct HighlightRange {
+    pub start: u32,
+    pub end: u32,
+}
@@ -298,6 +298,28 @@ impl App {
                     resumed.session_configured,
                 )
             }
+            ResumeSelection::Fork {
+                path,
+                nth_user_message,
+            } => {
+                let forked = conversation_manager
+                    .fork_conversation_into_worktree(nth_user_message, config.clone(), path.clone())
+                    .await
+                    .wrap_err_with(|| format!("Failed to fork session from {}", path.display()))?;
+                // The fork works in its own worktree.
+                config.cwd = forked.session_configured.cwd.clone();
+                let init = crate::chatwidget::ChatWidgetInit {
+                    config: config.clone(),
+                    frame_requester: tui.frame_requester(),
+                    app_event_tx: app_event_tx.clone(),
+                    initial_prompt: initial_prompt.clone(),
+                    initial_images: initial_images.clone(),
+                    enhanced_keys_supported,
+                    auth_manager: auth_manager.clone(),
+                    feedback: feedback.clone(),
+                };
+                ChatWidget::new_from_existing(init, forked.conversation, forked.session_configured)
+            }
         };
 
         chat_widget.maybe_prompt_windows_sandbox_enable();
@@ -831,6 +853,19 @@ impl App {
                         "E X E C".to_string(),
                     ));
                 }
+                ApprovalRequest::ExecBatch { requests, .. } => {
+                    let _ = tui.enter_alt_screen();
+                    let mut lines = Vec::new();
+                    for request in requests {
+                        if !lines.is_empty() {
+                            lines.push(Line::from(""));
+                        }
+                        let full_cmd = strip_bash_lc_and_escape(&request.command);
+                        lines.extend(highlight_bash_to_lines(&full_cmd));
+                    }
+                    self.overlay =
+                        Some(Overlay::new_static_with_lines(lines, "E X E C".to_string()));
+                }
                 ApprovalRequest::McpElicitation {
                     server_name,
                     message,
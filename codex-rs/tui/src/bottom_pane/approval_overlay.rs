@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::collections::VecDeque;
 use std::path::PathBuf;
 
 use crate::app_event::AppEvent;
@@ -9,6 +10,7 @@ use crate::bottom_pane::list_selection_view::ListSelectionView;
 use crate::bottom_pane::list_selection_view::SelectionItem;
 use crate::bottom_pane::list_selection_view::SelectionViewParams;
 use crate::diff_render::DiffSummary;
+use crate::exec_command::escape_command;
 use crate::exec_command::strip_bash_lc_and_escape;
 use crate::history_cell;
 use crate::key_hint;
@@ -16,12 +18,17 @@ use crate::key_hint::KeyBinding;
 use crate::render::highlight::highlight_bash_to_lines;
 use crate::render::renderable::ColumnRenderable;
 use crate::render::renderable::Renderable;
+use codex_core::protocol::CommandExpansion;
+use codex_core::protocol::CommandExpansionKind;
 use codex_core::protocol::ElicitationAction;
+use codex_core::protocol::ExecApprovalRequestEvent;
 use codex_core::protocol::FileChange;
+use codex_core::protocol::InlineScript;
 use codex_core::protocol::Op;
 use codex_core::protocol::ReviewDecision;
 use codex_core::protocol::SandboxCommandAssessment;
 use codex_core::protocol::SandboxRiskLevel;
+use codex_core::protocol::SyntaxToken;
 use crossterm::event::KeyCode;
 use crossterm::event::KeyEvent;
 use crossterm::event::KeyEventKind;
@@ -43,6 +50,16 @@ pub(crate) enum ApprovalRequest {
         command: Vec<String>,
         reason: Option<String>,
         risk: Option<SandboxCommandAssessment>,
+        scripts: Vec<InlineScript>,
+        expansions: Vec<CommandExpansion>,
+        /// Offered as "trust commands starting with this" when present.
+        trust_prefix: Option<Vec<String>>,
+    },
+    /// Several commands asked about together; answered with one decision
+    /// per command.
+    ExecBatch {
+        id: String,
+        requests: Vec<ExecApprovalRequestEvent>,
     },
     ApplyPatch {
         id: String,
@@ -57,6 +74,14 @@ pub(crate) enum ApprovalRequest {
     },
 }
 
+/// A batch the user chose to answer command by command.
+struct BatchProgress {
+    id: String,
+    total: usize,
+    remaining: VecDeque<ExecApprovalRequestEvent>,
+    decisions: Vec<ReviewDecision>,
+}
+
 /// Modal overlay asking the user to approve or deny one or more requests.
 pub(crate) struct ApprovalOverlay {
     current_request: Option<ApprovalRequest>,
@@ -67,6 +92,7 @@ pub(crate) struct ApprovalOverlay {
     options: Vec<ApprovalOption>,
     current_complete: bool,
     done: bool,
+    batch: Option<BatchProgress>,
 }
 
 impl ApprovalOverlay {
@@ -80,6 +106,7 @@ impl ApprovalOverlay {
             options: Vec::new(),
             current_complete: false,
             done: false,
+            batch: None,
         };
         view.set_current(request);
         view
@@ -90,34 +117,45 @@ impl ApprovalOverlay {
     }
 
     fn set_current(&mut self, request: ApprovalRequest) {
-        self.current_request = Some(request.clone());
-        let ApprovalRequestState { variant, header } = ApprovalRequestState::from(request);
-        self.current_variant = Some(variant.clone());
-        self.current_complete = false;
-        let (options, params) = Self::build_options(variant, header);
-        self.options = options;
-        self.list = ListSelectionView::new(params, self.app_event_tx.clone());
-    }
-
-    fn build_options(
-        variant: ApprovalVariant,
-        header: Box<dyn Renderable>,
-    ) -> (Vec<ApprovalOption>, SelectionViewParams) {
-        let (options, title) = match &variant {
-            ApprovalVariant::Exec { .. } => (
-                exec_options(),
+        let (options, title) = match &request {
+            ApprovalRequest::Exec { trust_prefix, .. } => (
+                exec_options(trust_prefix.as_deref()),
                 "Would you like to run the following command?".to_string(),
             ),
-            ApprovalVariant::ApplyPatch { .. } => (
+            ApprovalRequest::ExecBatch { requests, .. } => (
+                batch_options(),
+                format!(
+                    "Would you like to run the following {} commands?",
+                    requests.len()
+                ),
+            ),
+            ApprovalRequest::ApplyPatch { .. } => (
                 patch_options(),
                 "Would you like to make the following edits?".to_string(),
             ),
-            ApprovalVariant::McpElicitation { server_name, .. } => (
+            ApprovalRequest::McpElicitation { server_name, .. } => (
                 elicitation_options(),
                 format!("{server_name} needs your approval."),
             ),
         };
+        self.show(request, options, title);
+    }
 
+    fn show(&mut self, request: ApprovalRequest, options: Vec<ApprovalOption>, title: String) {
+        self.current_request = Some(request.clone());
+        let ApprovalRequestState { variant, header } = ApprovalRequestState::from(request);
+        self.current_variant = Some(variant);
+        self.current_complete = false;
+        let params = Self::build_params(&options, title, header);
+        self.options = options;
+        self.list = ListSelectionView::new(params, self.app_event_tx.clone());
+    }
+
+    fn build_params(
+        options: &[ApprovalOption],
+        title: String,
+        header: Box<dyn Renderable>,
+    ) -> SelectionViewParams {
         let header = Box::new(ColumnRenderable::with([
             Line::from(title.bold()).into(),
             Line::from("").into(),
@@ -136,7 +174,7 @@ impl ApprovalOverlay {
             })
             .collect();
 
-        let params = SelectionViewParams {
+        SelectionViewParams {
             footer_hint: Some(Line::from(vec![
                 "Press ".into(),
                 key_hint::plain(KeyCode::Enter).into(),
@@ -147,9 +185,7 @@ impl ApprovalOverlay {
             items,
             header,
             ..Default::default()
-        };
-
-        (options, params)
+        }
     }
 
     fn apply_selection(&mut self, actual_idx: usize) {
@@ -159,11 +195,31 @@ impl ApprovalOverlay {
         let Some(option) = self.options.get(actual_idx) else {
             return;
         };
-        if let Some(variant) = self.current_variant.as_ref() {
-            match (&variant, &option.decision) {
+        let decision = option.decision.clone();
+        if let Some(variant) = self.current_variant.clone() {
+            match (&variant, &decision) {
+                (ApprovalVariant::Exec { command, .. }, ApprovalDecision::Review(decision))
+                    if self.batch.is_some() =>
+                {
+                    let decision = *decision;
+                    self.handle_batch_item_decision(command, decision);
+                    if self.batch.is_some() {
+                        return;
+                    }
+                }
                 (ApprovalVariant::Exec { id, command }, ApprovalDecision::Review(decision)) => {
                     self.handle_exec_decision(id, command, *decision);
                 }
+                (
+                    ApprovalVariant::ExecBatch { id, commands },
+                    ApprovalDecision::Review(decision),
+                ) => {
+                    self.handle_exec_batch_decision(id, commands, vec![*decision; commands.len()]);
+                }
+                (ApprovalVariant::ExecBatch { .. }, ApprovalDecision::EachCommand) => {
+                    self.start_batch_items();
+                    return;
+                }
                 (ApprovalVariant::ApplyPatch { id, .. }, ApprovalDecision::Review(decision)) => {
                     self.handle_patch_decision(id, *decision);
                 }
@@ -193,6 +249,84 @@ impl ApprovalOverlay {
         }));
     }
 
+    fn handle_exec_batch_decision(
+        &self,
+        id: &str,
+        commands: &[Vec<String>],
+        decisions: Vec<ReviewDecision>,
+    ) {
+        for (command, decision) in commands.iter().zip(&decisions) {
+            let cell = history_cell::new_approval_decision_cell(command.clone(), *decision);
+            self.app_event_tx.send(AppEvent::InsertHistoryCell(cell));
+        }
+        self.app_event_tx
+            .send(AppEvent::CodexOp(Op::ExecApprovalBatch {
+                id: id.to_string(),
+                decisions,
+            }));
+    }
+
+    /// Asks about the commands of the current batch one by one.
+    fn start_batch_items(&mut self) {
+        let Some(ApprovalRequest::ExecBatch { id, requests }) = self.current_request.take() else {
+            return;
+        };
+        self.batch = Some(BatchProgress {
+            id,
+            total: requests.len(),
+            remaining: requests.into(),
+            decisions: Vec::new(),
+        });
+        self.show_next_batch_item();
+    }
+
+    fn show_next_batch_item(&mut self) {
+        let Some(batch) = self.batch.as_mut() else {
+            return;
+        };
+        let Some(request) = batch.remaining.pop_front() else {
+            return;
+        };
+        let title = format!(
+            "Command {} of {}: would you like to run it?",
+            batch.decisions.len() + 1,
+            batch.total
+        );
+        let options = batch_item_options(request.trust_prefix.as_deref());
+        let request = ApprovalRequest::Exec {
+            id: batch.id.clone(),
+            command: request.command,
+            reason: request.reason,
+            risk: request.risk,
+            scripts: request.scripts,
+            expansions: request.expansions,
+            trust_prefix: request.trust_prefix,
+        };
+        self.show(request, options, title);
+    }
+
+    /// Records the answer for one command of a batch, and sends them all
+    /// once every command has one. Aborting ends the batch at once.
+    fn handle_batch_item_decision(&mut self, command: &[String], decision: ReviewDecision) {
+        let cell = history_cell::new_approval_decision_cell(command.to_vec(), decision);
+        self.app_event_tx.send(AppEvent::InsertHistoryCell(cell));
+        let Some(batch) = self.batch.as_mut() else {
+            return;
+        };
+        batch.decisions.push(decision);
+        if decision != ReviewDecision::Abort && !batch.remaining.is_empty() {
+            self.show_next_batch_item();
+            return;
+        }
+        if let Some(batch) = self.batch.take() {
+            self.app_event_tx
+                .send(AppEvent::CodexOp(Op::ExecApprovalBatch {
+                    id: batch.id,
+                    decisions: batch.decisions,
+                }));
+        }
+    }
+
     fn handle_patch_decision(&self, id: &str, decision: ReviewDecision) {
         self.app_event_tx.send(AppEvent::CodexOp(Op::PatchApproval {
             id: id.to_string(),
@@ -269,13 +403,22 @@ impl BottomPaneView for ApprovalOverlay {
         if self.done {
             return CancellationEvent::Handled;
         }
-        if !self.current_complete
+        if let Some(batch) = self.batch.take() {
+            self.app_event_tx
+                .send(AppEvent::CodexOp(Op::ExecApprovalBatch {
+                    id: batch.id,
+                    decisions: vec![ReviewDecision::Abort],
+                }));
+        } else if !self.current_complete
             && let Some(variant) = self.current_variant.as_ref()
         {
             match &variant {
                 ApprovalVariant::Exec { id, command } => {
                     self.handle_exec_decision(id, command, ReviewDecision::Abort);
                 }
+                ApprovalVariant::ExecBatch { id, commands } => {
+                    self.handle_exec_batch_decision(id, commands, vec![ReviewDecision::Abort]);
+                }
                 ApprovalVariant::ApplyPatch { id, .. } => {
                     self.handle_patch_decision(id, ReviewDecision::Abort);
                 }
@@ -336,6 +479,9 @@ impl From<ApprovalRequest> for ApprovalRequestState {
                 command,
                 reason,
                 risk,
+                scripts,
+                expansions,
+                trust_prefix: _,
             } => {
                 let reason = reason.filter(|item| !item.is_empty());
                 let has_reason = reason.is_some();
@@ -354,11 +500,47 @@ impl From<ApprovalRequest> for ApprovalRequestState {
                     first.spans.insert(0, Span::from("$ "));
                 }
                 header.extend(full_cmd_lines);
+                for script in &scripts {
+                    header.extend(render_script_lines(script));
+                }
+                for expansion in &expansions {
+                    header.extend(render_expansion_lines(expansion));
+                }
                 Self {
                     variant: ApprovalVariant::Exec { id, command },
                     header: Box::new(Paragraph::new(header).wrap(Wrap { trim: false })),
                 }
             }
+            ApprovalRequest::ExecBatch { id, requests } => {
+                let mut header: Vec<Line<'static>> = Vec::new();
+                for (index, request) in requests.iter().enumerate() {
+                    let full_cmd = strip_bash_lc_and_escape(&request.command);
+                    let mut lines = highlight_bash_to_lines(&full_cmd);
+                    if let Some(first) = lines.first_mut() {
+                        first
+                            .spans
+                            .insert(0, Span::from(format!("{}. $ ", index + 1)));
+                    }
+                    header.extend(lines);
+                    if let Some(reason) = request.reason.as_ref().filter(|item| !item.is_empty()) {
+                        header.push(Line::from(format!("   {reason}").italic()));
+                    }
+                    if let Some(risk) = &request.risk {
+                        header.push(Line::from(vec![
+                            "   Risk: ".into(),
+                            risk_level_span(risk.risk_level),
+                        ]));
+                    }
+                }
+                let commands = requests
+                    .into_iter()
+                    .map(|request| request.command)
+                    .collect();
+                Self {
+                    variant: ApprovalVariant::ExecBatch { id, commands },
+                    header: Box::new(Paragraph::new(header).wrap(Wrap { trim: false })),
+                }
+            }
             ApprovalRequest::ApplyPatch {
                 id,
                 reason,
@@ -404,12 +586,16 @@ impl From<ApprovalRequest> for ApprovalRequestState {
     }
 }
 
-fn render_risk_lines(risk: &SandboxCommandAssessment) -> Vec<Line<'static>> {
-    let level_span = match risk.risk_level {
+fn risk_level_span(level: SandboxRiskLevel) -> Span<'static> {
+    match level {
         SandboxRiskLevel::Low => "LOW".green().bold(),
         SandboxRiskLevel::Medium => "MEDIUM".cyan().bold(),
         SandboxRiskLevel::High => "HIGH".red().bold(),
-    };
+    }
+}
+
+fn render_risk_lines(risk: &SandboxCommandAssessment) -> Vec<Line<'static>> {
+    let level_span = risk_level_span(risk.risk_level);
 
     let mut lines = Vec::new();
 
@@ -422,16 +608,96 @@ fn render_risk_lines(risk: &SandboxCommandAssessment) -> Vec<Line<'static>> {
     }
 
     lines.push(vec!["Risk: ".into(), level_span].into());
+    // Reasons found by the static analysis, unless the summary is made of
+    // them already.
+    for reason in &risk.reasons {
+        if !description.contains(&reason.detail) {
+            lines.push(Line::from(format!("  - {}", reason.detail).dim()));
+        }
+    }
     lines.push(Line::from(""));
     lines
 }
 
+/// Longest script shown in full; longer ones are cut off with a note.
+const MAX_SCRIPT_LINES: usize = 20;
+
+fn render_script_lines(script: &InlineScript) -> Vec<Line<'static>> {
+    let title = match script.language.as_deref() {
+        Some(language) => format!("{language} script:"),
+        None => "Script:".to_string(),
+    };
+    let mut lines = vec![Line::from(""), Line::from(title.bold())];
+    let source_lines: Vec<&str> = script.source.lines().collect();
+    for (index, text) in source_lines.iter().take(MAX_SCRIPT_LINES).enumerate() {
+        let tokens = script
+            .tokens
+            .get(index)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        lines.push(render_highlighted_line(text, tokens));
+    }
+    if source_lines.len() > MAX_SCRIPT_LINES {
+        let hidden = source_lines.len() - MAX_SCRIPT_LINES;
+        lines.push(Line::from(format!("  … {hidden} more lines").dim()));
+    }
+    lines
+}
+
+/// An alias or function the command runs through, so that what it really
+/// runs is reviewed along with it.
+fn render_expansion_lines(expansion: &CommandExpansion) -> Vec<Line<'static>> {
+    let name = &expansion.name;
+    let title = match expansion.kind {
+        CommandExpansionKind::Alias => format!("`{name}` is an alias for:"),
+        CommandExpansionKind::Function => format!("`{name}` is a function:"),
+    };
+    let mut lines = vec![Line::from(""), Line::from(title.bold())];
+    let definition_lines: Vec<&str> = expansion.definition.lines().collect();
+    for text in definition_lines.iter().take(MAX_SCRIPT_LINES) {
+        lines.push(Line::from(format!("  {text}")));
+    }
+    if definition_lines.len() > MAX_SCRIPT_LINES {
+        let hidden = definition_lines.len() - MAX_SCRIPT_LINES;
+        lines.push(Line::from(format!("  … {hidden} more lines").dim()));
+    }
+    lines
+}
+
+/// Styles `text` by its tokens, whose ranges count characters.
+fn render_highlighted_line(text: &str, tokens: &[SyntaxToken]) -> Line<'static> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut spans = vec![Span::from("  ")];
+    let mut pos = 0;
+    for token in tokens {
+        let start = (token.start as usize).clamp(pos, chars.len());
+        let end = (token.end as usize).clamp(start, chars.len());
+        if start > pos {
+            spans.push(chars[pos..start].iter().collect::<String>().into());
+        }
+        let word: String = chars[start..end].iter().collect();
+        spans.push(match token.kind.as_str() {
+            "comment" | "operator" | "string" => word.dim(),
+            _ => word.into(),
+        });
+        pos = end;
+    }
+    if pos < chars.len() {
+        spans.push(chars[pos..].iter().collect::<String>().into());
+    }
+    Line::from(spans)
+}
+
 #[derive(Clone)]
 enum ApprovalVariant {
     Exec {
         id: String,
         command: Vec<String>,
     },
+    ExecBatch {
+        id: String,
+        commands: Vec<Vec<String>>,
+    },
     ApplyPatch {
         id: String,
     },
@@ -444,6 +710,8 @@ enum ApprovalVariant {
 #[derive(Clone)]
 enum ApprovalDecision {
     Review(ReviewDecision),
+    /// Answer the commands of a batch one by one.
+    EachCommand,
     McpElicitation(ElicitationAction),
 }
 
@@ -463,8 +731,8 @@ impl ApprovalOption {
     }
 }
 
-fn exec_options() -> Vec<ApprovalOption> {
-    vec![
+fn exec_options(trust_prefix: Option<&[String]>) -> Vec<ApprovalOption> {
+    let mut options = vec![
         ApprovalOption {
             label: "Yes, proceed".to_string(),
             decision: ApprovalDecision::Review(ReviewDecision::Approved),
@@ -483,9 +751,82 @@ fn exec_options() -> Vec<ApprovalOption> {
             display_shortcut: Some(key_hint::plain(KeyCode::Esc)),
             additional_shortcuts: vec![key_hint::plain(KeyCode::Char('n'))],
         },
+    ];
+    if let Some(prefix) = trust_prefix {
+        options.insert(2, trust_prefix_option(prefix));
+    }
+    options
+}
+
+/// Approves every command that starts with `prefix` for the session.
+fn trust_prefix_option(prefix: &[String]) -> ApprovalOption {
+    ApprovalOption {
+        label: format!(
+            "Yes, and trust `{} *` for this session",
+            escape_command(prefix)
+        ),
+        decision: ApprovalDecision::Review(ReviewDecision::ApprovedPrefixForSession),
+        display_shortcut: None,
+        additional_shortcuts: vec![key_hint::plain(KeyCode::Char('p'))],
+    }
+}
+
+fn batch_options() -> Vec<ApprovalOption> {
+    vec![
+        ApprovalOption {
+            label: "Yes, run all of them".to_string(),
+            decision: ApprovalDecision::Review(ReviewDecision::Approved),
+            display_shortcut: None,
+            additional_shortcuts: vec![key_hint::plain(KeyCode::Char('y'))],
+        },
+        ApprovalOption {
+            label: "Choose for each command".to_string(),
+            decision: ApprovalDecision::EachCommand,
+            display_shortcut: None,
+            additional_shortcuts: vec![key_hint::plain(KeyCode::Char('c'))],
+        },
+        ApprovalOption {
+            label: "No, and tell Codex what to do differently".to_string(),
+            decision: ApprovalDecision::Review(ReviewDecision::Abort),
+            display_shortcut: Some(key_hint::plain(KeyCode::Esc)),
+            additional_shortcuts: vec![key_hint::plain(KeyCode::Char('n'))],
+        },
     ]
 }
 
+fn batch_item_options(trust_prefix: Option<&[String]>) -> Vec<ApprovalOption> {
+    let mut options = vec![
+        ApprovalOption {
+            label: "Yes, run it".to_string(),
+            decision: ApprovalDecision::Review(ReviewDecision::Approved),
+            display_shortcut: None,
+            additional_shortcuts: vec![key_hint::plain(KeyCode::Char('y'))],
+        },
+        ApprovalOption {
+            label: "Yes, and don't ask again for this command".to_string(),
+            decision: ApprovalDecision::Review(ReviewDecision::ApprovedForSession),
+            display_shortcut: None,
+            additional_shortcuts: vec![key_hint::plain(KeyCode::Char('a'))],
+        },
+        ApprovalOption {
+            label: "No, skip this one".to_string(),
+            decision: ApprovalDecision::Review(ReviewDecision::Denied),
+            display_shortcut: None,
+            additional_shortcuts: vec![key_hint::plain(KeyCode::Char('n'))],
+        },
+        ApprovalOption {
+            label: "No, and tell Codex what to do differently".to_string(),
+            decision: ApprovalDecision::Review(ReviewDecision::Abort),
+            display_shortcut: Some(key_hint::plain(KeyCode::Esc)),
+            additional_shortcuts: Vec::new(),
+        },
+    ];
+    if let Some(prefix) = trust_prefix {
+        options.insert(2, trust_prefix_option(prefix));
+    }
+    options
+}
+
 fn patch_options() -> Vec<ApprovalOption> {
     vec![
         ApprovalOption {
@@ -539,6 +880,9 @@ mod tests {
             command: vec!["echo".to_string(), "hi".to_string()],
             reason: Some("reason".to_string()),
             risk: None,
+            scripts: Vec::new(),
+            expansions: Vec::new(),
+            trust_prefix: None,
         }
     }
 
@@ -571,6 +915,48 @@ mod tests {
         assert!(saw_op, "expected approval decision to emit an op");
     }
 
+    #[test]
+    fn batch_can_be_answered_per_command() {
+        let (tx, mut rx) = unbounded_channel::<AppEvent>();
+        let tx = AppEventSender::new(tx);
+        let request = |call_id: &str| ExecApprovalRequestEvent {
+            call_id: call_id.to_string(),
+            turn_id: "turn".to_string(),
+            command: vec!["touch".to_string(), call_id.to_string()],
+            cwd: PathBuf::from("/tmp"),
+            reason: None,
+            risk: None,
+            parsed_cmd: Vec::new(),
+            scripts: Vec::new(),
+            expansions: Vec::new(),
+            trust_prefix: None,
+        };
+        let batch = ApprovalRequest::ExecBatch {
+            id: "a".to_string(),
+            requests: vec![request("a"), request("b")],
+        };
+        let mut view = ApprovalOverlay::new(batch, tx);
+
+        for key in ['c', 'y', 'n'] {
+            view.handle_key_event(KeyEvent::new(KeyCode::Char(key), KeyModifiers::NONE));
+        }
+
+        let mut answers = Vec::new();
+        while let Ok(ev) = rx.try_recv() {
+            if let AppEvent::CodexOp(Op::ExecApprovalBatch { id, decisions }) = ev {
+                answers.push((id, decisions));
+            }
+        }
+        assert_eq!(
+            answers,
+            vec![(
+                "a".to_string(),
+                vec![ReviewDecision::Approved, ReviewDecision::Denied]
+            )]
+        );
+        assert!(view.is_complete());
+    }
+
     #[test]
     fn header_includes_command_snippet() {
         let (tx, _rx) = unbounded_channel::<AppEvent>();
@@ -581,6 +967,9 @@ mod tests {
             command,
             reason: None,
             risk: None,
+            scripts: Vec::new(),
+            expansions: Vec::new(),
+            trust_prefix: None,
         };
 
         let view = ApprovalOverlay::new(exec_request, tx);
@@ -602,6 +991,91 @@ mod tests {
         );
     }
 
+    #[test]
+    fn header_shows_inline_scripts_as_code() {
+        let (tx, _rx) = unbounded_channel::<AppEvent>();
+        let tx = AppEventSender::new(tx);
+        let exec_request = ApprovalRequest::Exec {
+            id: "test".into(),
+            command: vec![
+                "python3".into(),
+                "-c".into(),
+                "import os\nos.remove('x')".into(),
+            ],
+            reason: None,
+            risk: None,
+            scripts: vec![InlineScript {
+                language: Some("python".into()),
+                source: "import os\nos.remove('x')".into(),
+                tokens: Vec::new(),
+            }],
+            expansions: Vec::new(),
+            trust_prefix: None,
+        };
+
+        let view = ApprovalOverlay::new(exec_request, tx);
+        let area = Rect::new(0, 0, 80, view.desired_height(80));
+        let mut buf = Buffer::empty(area);
+        view.render(area, &mut buf);
+
+        let rendered: Vec<String> = (0..buf.area.height)
+            .map(|row| {
+                (0..buf.area.width)
+                    .map(|col| buf[(col, row)].symbol().to_string())
+                    .collect::<String>()
+                    .trim_end()
+                    .to_string()
+            })
+            .collect();
+        let script_start = rendered
+            .iter()
+            .position(|line| line == "python script:")
+            .expect("script title");
+        assert_eq!(
+            rendered[script_start + 1..script_start + 3].to_vec(),
+            vec!["  import os".to_string(), "  os.remove('x')".to_string()]
+        );
+    }
+
+    #[test]
+    fn header_shows_what_an_alias_expands_to() {
+        let (tx, _rx) = unbounded_channel::<AppEvent>();
+        let tx = AppEventSender::new(tx);
+        let exec_request = ApprovalRequest::Exec {
+            id: "test".into(),
+            command: vec!["ls".into(), "src".into()],
+            reason: None,
+            risk: None,
+            scripts: Vec::new(),
+            expansions: vec![CommandExpansion {
+                name: "ls".into(),
+                kind: CommandExpansionKind::Alias,
+                definition: "rm -rf".into(),
+            }],
+            trust_prefix: None,
+        };
+
+        let view = ApprovalOverlay::new(exec_request, tx);
+        let area = Rect::new(0, 0, 80, view.desired_height(80));
+        let mut buf = Buffer::empty(area);
+        view.render(area, &mut buf);
+
+        let rendered: Vec<String> = (0..buf.area.height)
+            .map(|row| {
+                (0..buf.area.width)
+                    .map(|col| buf[(col, row)].symbol().to_string())
+                    .collect::<String>()
+                    .trim_end()
+                    .to_string()
+            })
+            .collect();
+        let title = rendered
+            .iter()
+            .position(|line| line == "`ls` is an alias for:")
+            .expect("expansion title");
+        assert_eq!(rendered[title + 1], "  rm -rf");
+    }
+
     #[test]
     fn exec_history_cell_wraps_with_two_space_indent() {
         let command = vec![
@@ -651,4 +1125,32 @@ mod tests {
         }
         assert_eq!(decision, Some(ReviewDecision::ApprovedForSession));
     }
+
+    #[test]
+    fn trusting_a_prefix_is_offered_when_core_suggests_one() {
+        let (tx_raw, mut rx) = unbounded_channel::<AppEvent>();
+        let tx = AppEventSender::new(tx_raw);
+        let request = ApprovalRequest::Exec {
+            id: "test".into(),
+            command: vec!["pytest".into(), "-x".into()],
+            reason: None,
+            risk: None,
+            scripts: Vec::new(),
+            expansions: Vec::new(),
+            trust_prefix: Some(vec!["pytest".into()]),
+        };
+        let mut view = ApprovalOverlay::new(request, tx);
+        let labels: Vec<&str> = view.options.iter().map(|opt| opt.label.as_str()).collect();
+        assert_eq!(labels[2], "Yes, and trust `pytest *` for this session");
+
+        view.handle_key_event(KeyEvent::new(KeyCode::Char('p'), KeyModifiers::NONE));
+
+        let mut decision = None;
+        while let Ok(ev) = rx.try_recv() {
+            if let AppEvent::CodexOp(Op::ExecApproval { decision: d, .. }) = ev {
+                decision = Some(d);
+            }
+        }
+        assert_eq!(decision, Some(ReviewDecision::ApprovedPrefixForSession));
+    }
 }
@@ -553,6 +553,9 @@ mod tests {
             command: vec!["echo".into(), "ok".into()],
             reason: None,
             risk: None,
+            scripts: Vec::new(),
+            expansions: Vec::new(),
+            trust_prefix: None,
         }
     }
 
@@ -18,17 +18,29 @@ use codex_core::protocol::AgentReasoningEvent;
 use codex_core::protocol::AgentReasoningRawContentDeltaEvent;
 use codex_core::protocol::AgentReasoningRawContentEvent;
 use codex_core::protocol::ApplyPatchApprovalRequestEvent;
+use codex_core::protocol::ApprovalRuleAppliedEvent;
+use codex_core::protocol::ApprovalRuleDecision;
+use codex_core::protocol::ArtifactDirection;
+use codex_core::protocol::ArtifactEvent;
 use codex_core::protocol::BackgroundEventEvent;
+use codex_core::protocol::ContextSource;
+use codex_core::protocol::ContextUsageEvent;
 use codex_core::protocol::DeprecationNoticeEvent;
+use codex_core::protocol::DoctorReportEvent;
+use codex_core::protocol::DoctorStatus;
+use codex_core::protocol::EnvironmentChangedEvent;
 use codex_core::protocol::ErrorEvent;
 use codex_core::protocol::Event;
 use codex_core::protocol::EventMsg;
+use codex_core::protocol::ExecApprovalBatchRequestEvent;
 use codex_core::protocol::ExecApprovalRequestEvent;
 use codex_core::protocol::ExecCommandBeginEvent;
 use codex_core::protocol::ExecCommandEndEvent;
 use codex_core::protocol::ExecCommandSource;
 use codex_core::protocol::ExitedReviewModeEvent;
+use codex_core::protocol::IntegrityFindingKind;
 use codex_core::protocol::ListCustomPromptsResponseEvent;
+use codex_core::protocol::ListFilesReadResponseEvent;
 use codex_core::protocol::McpListToolsResponseEvent;
 use codex_core::protocol::McpStartupCompleteEvent;
 use codex_core::protocol::McpStartupStatus;
@@ -39,6 +51,7 @@ use codex_core::protocol::Op;
 use codex_core::protocol::PatchApplyBeginEvent;
 use codex_core::protocol::RateLimitSnapshot;
 use codex_core::protocol::ReviewRequest;
+use codex_core::protocol::SandboxProbeEvent;
 use codex_core::protocol::StreamErrorEvent;
 use codex_core::protocol::TaskCompleteEvent;
 use codex_core::protocol::TokenUsage;
@@ -52,6 +65,7 @@ use codex_core::protocol::ViewImageToolCallEvent;
 use codex_core::protocol::WarningEvent;
 use codex_core::protocol::WebSearchBeginEvent;
 use codex_core::protocol::WebSearchEndEvent;
+use codex_core::protocol::WorkspaceIntegrityEvent;
 use codex_protocol::ConversationId;
 use codex_protocol::approvals::ElicitationRequestEvent;
 use codex_protocol::parse_command::ParsedCommand;
@@ -89,6 +103,7 @@ use crate::diff_render::display_path_for;
 use crate::exec_cell::CommandOutput;
 use crate::exec_cell::ExecCell;
 use crate::exec_cell::new_active_exec_command;
+use crate::exec_command::strip_bash_lc_and_escape;
 use crate::get_git_diff::get_git_diff;
 use crate::history_cell;
 use crate::history_cell::AgentMessageCell;
@@ -104,6 +119,7 @@ use crate::render::renderable::RenderableExt;
 use crate::render::renderable::RenderableItem;
 use crate::slash_command::SlashCommand;
 use crate::status::RateLimitSnapshotDisplay;
+use crate::status::format_tokens_compact;
 use crate::text_formatting::truncate_text;
 use crate::tui::FrameRequester;
 mod interrupts;
@@ -278,6 +294,9 @@ pub(crate) struct ChatWidget {
     current_status_header: String,
     // Previous status header to restore after a transient stream retry.
     retry_status_header: Option<String>,
+    // Ids of the leftovers reported by the last workspace check; `/cleanup`
+    // cleans these up when set.
+    workspace_findings: Vec<String>,
     conversation_id: Option<ConversationId>,
     frame_requester: FrameRequester,
     // Whether to include the initial welcome banner on session configured
@@ -725,6 +744,14 @@ impl ChatWidget {
         );
     }
 
+    fn on_exec_approval_batch_request(&mut self, ev: ExecApprovalBatchRequestEvent) {
+        let ev2 = ev.clone();
+        self.defer_or_handle(
+            |q| q.push_exec_approval_batch(ev),
+            |s| s.handle_exec_approval_batch_now(ev2),
+        );
+    }
+
     fn on_apply_patch_approval_request(&mut self, id: String, ev: ApplyPatchApprovalRequestEvent) {
         let id2 = id.clone();
         let ev2 = ev.clone();
@@ -866,6 +893,99 @@ impl ChatWidget {
         }
     }
 
+    fn on_artifact(&mut self, event: ArtifactEvent) {
+        let ArtifactEvent {
+            name,
+            direction,
+            path,
+            size_bytes,
+            ..
+        } = event;
+        let message = match direction {
+            ArtifactDirection::Inbound => format!("Artifact received: {name} ({size_bytes} bytes)"),
+            ArtifactDirection::Outbound => format!("Artifact sent: {name} ({size_bytes} bytes)"),
+        };
+        self.add_info_message(message, Some(path.display().to_string()));
+    }
+
+    fn on_approval_rule_applied(&mut self, event: ApprovalRuleAppliedEvent) {
+        let action = match event.decision {
+            ApprovalRuleDecision::AutoApprove => "ran without asking",
+            ApprovalRuleDecision::AutoDeny => "refused",
+            ApprovalRuleDecision::Ask => "asked before running",
+        };
+        let command = strip_bash_lc_and_escape(&event.command);
+        self.add_info_message(
+            format!("Approval rule {} {action}: {command}", event.rule),
+            None,
+        );
+    }
+
+    fn on_workspace_integrity(&mut self, event: WorkspaceIntegrityEvent) {
+        if event.findings.is_empty() {
+            self.add_info_message("No leftovers found in the workspace".to_string(), None);
+        }
+        for finding in &event.findings {
+            let keep = if finding.kind == IntegrityFindingKind::EscalatedFile {
+                ", /keep keeps it"
+            } else {
+                ""
+            };
+            self.on_warning(format!(
+                "{} (/cleanup will {}{keep})",
+                finding.description, finding.cleanup
+            ));
+        }
+        self.workspace_findings = event.findings.into_iter().map(|f| f.id).collect();
+    }
+
+    fn on_environment_changed(&mut self, event: EnvironmentChangedEvent) {
+        let command = strip_bash_lc_and_escape(&event.command);
+        let mut lines: Vec<Line<'static>> =
+            vec![format!("{command} changed the environment").bold().into()];
+        for change in event.changes {
+            lines.push(Line::from(vec!["  ".into(), change.description.into()]));
+            for line in change.lines {
+                lines.push(Line::from(vec!["    ".into(), line.dim()]));
+            }
+        }
+        self.add_plain_history_lines(lines);
+    }
+
+    fn on_sandbox_probe(&mut self, event: SandboxProbeEvent) {
+        let error = event.error.unwrap_or_default();
+        if !event.available {
+            self.on_warning(format!(
+                "The sandbox is unavailable, so commands run without it: {error}"
+            ));
+        } else if !event.limits {
+            self.on_warning(format!(
+                "Sandbox limits are unavailable, so commands run without them: {error}"
+            ));
+        }
+    }
+
+    fn on_doctor_report(&mut self, event: DoctorReportEvent) {
+        let mut lines: Vec<Line<'static>> = vec!["Doctor".bold().into()];
+        for check in event.checks {
+            let status = match check.status {
+                DoctorStatus::Pass => "pass".green(),
+                DoctorStatus::Warn => "warn".cyan(),
+                DoctorStatus::Fail => "fail".red(),
+            };
+            lines.push(Line::from(vec![
+                "  ".into(),
+                status,
+                format!(" {}: ", check.name).bold(),
+                check.message.into(),
+            ]));
+            if let Some(fix) = check.fix {
+                lines.push(Line::from(vec!["        ".into(), fix.dim()]));
+            }
+        }
+        self.add_plain_history_lines(lines);
+    }
+
     fn on_stream_error(&mut self, message: String) {
         if self.retry_status_header.is_none() {
             self.retry_status_header = Some(self.current_status_header.clone());
@@ -1022,6 +1142,22 @@ impl ChatWidget {
             command: ev.command,
             reason: ev.reason,
             risk: ev.risk,
+            scripts: ev.scripts,
+            expansions: ev.expansions,
+            trust_prefix: ev.trust_prefix,
+        };
+        self.bottom_pane.push_approval_request(request);
+        self.request_redraw();
+    }
+
+    pub(crate) fn handle_exec_approval_batch_now(&mut self, ev: ExecApprovalBatchRequestEvent) {
+        self.flush_answer_stream_with_separator();
+        let command = format!("{} commands", ev.requests.len());
+        self.notify(Notification::ExecApprovalRequested { command });
+
+        let request = ApprovalRequest::ExecBatch {
+            id: ev.id,
+            requests: ev.requests,
         };
         self.bottom_pane.push_approval_request(request);
         self.request_redraw();
@@ -1202,6 +1338,7 @@ impl ChatWidget {
             full_reasoning_buffer: String::new(),
             current_status_header: String::from("Working"),
             retry_status_header: None,
+            workspace_findings: Vec::new(),
             conversation_id: None,
             queued_user_messages: VecDeque::new(),
             show_welcome_banner: true,
@@ -1277,6 +1414,7 @@ impl ChatWidget {
             full_reasoning_buffer: String::new(),
             current_status_header: String::from("Working"),
             retry_status_header: None,
+            workspace_findings: Vec::new(),
             conversation_id: None,
             queued_user_messages: VecDeque::new(),
             show_welcome_banner: true,
@@ -1445,6 +1583,32 @@ impl ChatWidget {
             SlashCommand::Undo => {
                 self.app_event_tx.send(AppEvent::CodexOp(Op::Undo));
             }
+            SlashCommand::FilesRead => {
+                self.app_event_tx.send(AppEvent::CodexOp(Op::ListFilesRead));
+            }
+            SlashCommand::Context => {
+                self.app_event_tx
+                    .send(AppEvent::CodexOp(Op::GetContextUsage));
+            }
+            SlashCommand::Doctor => {
+                self.app_event_tx.send(AppEvent::CodexOp(Op::RunDoctor));
+            }
+            SlashCommand::Cleanup => {
+                let op = if self.workspace_findings.is_empty() {
+                    Op::CheckWorkspace
+                } else {
+                    Op::CleanupWorkspace {
+                        ids: std::mem::take(&mut self.workspace_findings),
+                    }
+                };
+                self.app_event_tx.send(AppEvent::CodexOp(op));
+            }
+            SlashCommand::Keep => {
+                self.app_event_tx
+                    .send(AppEvent::CodexOp(Op::KeepQuarantined {
+                        ids: std::mem::take(&mut self.workspace_findings),
+                    }));
+            }
             SlashCommand::Diff => {
                 self.add_diff_in_progress();
                 let tx = self.app_event_tx.clone();
@@ -1516,6 +1680,8 @@ impl ChatWidget {
                         ]),
                         reason: None,
                         grant_root: Some(PathBuf::from("/tmp")),
+                        hunks: Vec::new(),
+                        user_changes: Vec::new(),
                     }),
                 }));
             }
@@ -1708,6 +1874,7 @@ impl ChatWidget {
                 // For replayed events, synthesize an empty id (these should not occur).
                 self.on_exec_approval_request(id.unwrap_or_default(), ev)
             }
+            EventMsg::ExecApprovalBatchRequest(ev) => self.on_exec_approval_batch_request(ev),
             EventMsg::ApplyPatchApprovalRequest(ev) => {
                 self.on_apply_patch_approval_request(id.unwrap_or_default(), ev)
             }
@@ -1727,6 +1894,8 @@ impl ChatWidget {
             EventMsg::GetHistoryEntryResponse(ev) => self.on_get_history_entry_response(ev),
             EventMsg::McpListToolsResponse(ev) => self.on_list_mcp_tools(ev),
             EventMsg::ListCustomPromptsResponse(ev) => self.on_list_custom_prompts(ev),
+            EventMsg::ListFilesReadResponse(ev) => self.on_list_files_read(ev),
+            EventMsg::ContextUsage(ev) => self.on_context_usage(ev),
             EventMsg::ShutdownComplete => self.on_shutdown_complete(),
             EventMsg::TurnDiff(TurnDiffEvent { unified_diff }) => self.on_turn_diff(unified_diff),
             EventMsg::DeprecationNotice(ev) => self.on_deprecation_notice(ev),
@@ -1735,6 +1904,18 @@ impl ChatWidget {
             }
             EventMsg::UndoStarted(ev) => self.on_undo_started(ev),
             EventMsg::UndoCompleted(ev) => self.on_undo_completed(ev),
+            EventMsg::Artifact(ev) => self.on_artifact(ev),
+            EventMsg::ApprovalRuleApplied(ev) => self.on_approval_rule_applied(ev),
+            EventMsg::WorkspaceIntegrity(ev) => self.on_workspace_integrity(ev),
+            EventMsg::EnvironmentChanged(ev) => self.on_environment_changed(ev),
+            EventMsg::SandboxProbe(ev) => self.on_sandbox_probe(ev),
+            EventMsg::DoctorReport(ev) => self.on_doctor_report(ev),
+            EventMsg::CollaboratorJoined(ev) => {
+                self.add_info_message(format!("An {} joined the session", ev.role), None)
+            }
+            EventMsg::CollaboratorLeft(ev) => {
+                self.add_info_message(format!("An {} left the session", ev.role), None)
+            }
             EventMsg::StreamError(StreamErrorEvent { message, .. }) => {
                 self.on_stream_error(message)
             }
@@ -1752,7 +1933,13 @@ impl ChatWidget {
             | EventMsg::ItemCompleted(_)
             | EventMsg::AgentMessageContentDelta(_)
             | EventMsg::ReasoningContentDelta(_)
-            | EventMsg::ReasoningRawContentDelta(_) => {}
+            | EventMsg::ReasoningRawContentDelta(_)
+            | EventMsg::ExecCommandProgress(_)
+            | EventMsg::TurnResourceUsage(_)
+            | EventMsg::ApprovalResolved(_)
+            | EventMsg::StatusLine(_)
+            | EventMsg::FileRead(_)
+            | EventMsg::ApprovalCache(_) => {}
         }
     }
 
@@ -2792,6 +2979,80 @@ impl ChatWidget {
         self.bottom_pane.set_custom_prompts(ev.custom_prompts);
     }
 
+    fn on_list_files_read(&mut self, ev: ListFilesReadResponseEvent) {
+        if ev.files.is_empty() {
+            self.add_info_message("No files have been read in this session".to_string(), None);
+            return;
+        }
+        let mut lines: Vec<Line<'static>> = vec![
+            format!("Files read in this session ({})", ev.files.len())
+                .bold()
+                .into(),
+        ];
+        for file in ev.files {
+            let path = file
+                .path
+                .strip_prefix(&self.config.cwd)
+                .unwrap_or(&file.path)
+                .display()
+                .to_string();
+            let reads = if file.reads == 1 { "read" } else { "reads" };
+            lines.push(Line::from(vec![
+                "  ".into(),
+                path.into(),
+                format!(
+                    "  {} {reads}, {} bytes via {}",
+                    file.reads,
+                    file.bytes,
+                    file.tools.join(", ")
+                )
+                .dim(),
+            ]));
+        }
+        self.add_plain_history_lines(lines);
+    }
+
+    fn on_context_usage(&mut self, ev: ContextUsageEvent) {
+        let total = format_tokens_compact(ev.total_tokens);
+        let header = match ev.model_context_window {
+            Some(window) => format!(
+                "Context usage: ~{total} of {} tokens",
+                format_tokens_compact(window)
+            ),
+            None => format!("Context usage: ~{total} tokens"),
+        };
+        let mut lines: Vec<Line<'static>> = vec![header.bold().into()];
+        for entry in ev.sources {
+            let label = match entry.source {
+                ContextSource::SystemPrompt => "system prompt".to_string(),
+                ContextSource::ToolDefinitions => "tool definitions".to_string(),
+                ContextSource::Instructions => "instructions".to_string(),
+                ContextSource::EnvironmentContext => "environment context".to_string(),
+                ContextSource::UserMessages => "user messages".to_string(),
+                ContextSource::AgentMessages => "agent messages".to_string(),
+                ContextSource::Reasoning => "reasoning".to_string(),
+                ContextSource::ToolCalls => "tool calls".to_string(),
+                ContextSource::ToolOutput { tool } => format!("{tool} output"),
+                ContextSource::CompactionSummary => "compaction summaries".to_string(),
+            };
+            let percent = if ev.total_tokens > 0 {
+                entry.tokens * 100 / ev.total_tokens
+            } else {
+                0
+            };
+            lines.push(Line::from(vec![
+                "  ".into(),
+                label.into(),
+                format!(
+                    "  ~{} tokens ({percent}%)",
+                    format_tokens_compact(entry.tokens)
+                )
+                .dim(),
+            ]));
+        }
+        self.add_plain_history_lines(lines);
+    }
+
     pub(crate) fn open_review_popup(&mut self) {
         let mut items: Vec<SelectionItem> = Vec::new();
 
@@ -1,6 +1,7 @@
 use std::collections::VecDeque;
 
 use codex_core::protocol::ApplyPatchApprovalRequestEvent;
+use codex_core::protocol::ExecApprovalBatchRequestEvent;
 use codex_core::protocol::ExecApprovalRequestEvent;
 use codex_core::protocol::ExecCommandBeginEvent;
 use codex_core::protocol::ExecCommandEndEvent;
@@ -14,6 +15,7 @@ use super::ChatWidget;
 #[derive(Debug)]
 pub(crate) enum QueuedInterrupt {
     ExecApproval(String, ExecApprovalRequestEvent),
+    ExecApprovalBatch(ExecApprovalBatchRequestEvent),
     ApplyPatchApproval(String, ApplyPatchApprovalRequestEvent),
     Elicitation(ElicitationRequestEvent),
     ExecBegin(ExecCommandBeginEvent),
@@ -44,6 +46,10 @@ impl InterruptManager {
         self.queue.push_back(QueuedInterrupt::ExecApproval(id, ev));
     }
 
+    pub(crate) fn push_exec_approval_batch(&mut self, ev: ExecApprovalBatchRequestEvent) {
+        self.queue.push_back(QueuedInterrupt::ExecApprovalBatch(ev));
+    }
+
     pub(crate) fn push_apply_patch_approval(
         &mut self,
         id: String,
@@ -81,6 +87,7 @@ impl InterruptManager {
         while let Some(q) = self.queue.pop_front() {
             match q {
                 QueuedInterrupt::ExecApproval(id, ev) => chat.handle_exec_approval_now(id, ev),
+                QueuedInterrupt::ExecApprovalBatch(ev) => chat.handle_exec_approval_batch_now(ev),
                 QueuedInterrupt::ApplyPatchApproval(id, ev) => {
                     chat.handle_apply_patch_approval_now(id, ev)
                 }
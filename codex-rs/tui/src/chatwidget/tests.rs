@@ -365,6 +365,7 @@ fn make_chatwidget_manual() -> (
         full_reasoning_buffer: String::new(),
         current_status_header: String::from("Working"),
         retry_status_header: None,
+        workspace_findings: Vec::new(),
         conversation_id: None,
         frame_requester: FrameRequester::test_dummy(),
         show_welcome_banner: true,
@@ -590,6 +591,9 @@ fn exec_approval_emits_proposed_command_and_decision_history() {
         ),
         risk: None,
         parsed_cmd: vec![],
+        scripts: vec![],
+        expansions: vec![],
+        trust_prefix: None,
     };
     chat.handle_codex_event(Event {
         id: "sub-short".into(),
@@ -634,6 +638,9 @@ fn exec_approval_decision_truncates_multiline_and_long_commands() {
         ),
         risk: None,
         parsed_cmd: vec![],
+        scripts: vec![],
+        expansions: vec![],
+        trust_prefix: None,
     };
     chat.handle_codex_event(Event {
         id: "sub-multi".into(),
@@ -684,6 +691,9 @@ fn exec_approval_decision_truncates_multiline_and_long_commands() {
         reason: None,
         risk: None,
         parsed_cmd: vec![],
+        scripts: vec![],
+        expansions: vec![],
+        trust_prefix: None,
     };
     chat.handle_codex_event(Event {
         id: "sub-long".into(),
@@ -1855,6 +1865,9 @@ fn approval_modal_exec_snapshot() {
         ),
         risk: None,
         parsed_cmd: vec![],
+        scripts: vec![],
+        expansions: vec![],
+        trust_prefix: None,
     };
     chat.handle_codex_event(Event {
         id: "sub-approve".into(),
@@ -1901,6 +1914,9 @@ fn approval_modal_exec_without_reason_snapshot() {
         reason: None,
         risk: None,
         parsed_cmd: vec![],
+        scripts: vec![],
+        expansions: vec![],
+        trust_prefix: None,
     };
     chat.handle_codex_event(Event {
         id: "sub-approve-noreason".into(),
@@ -1940,6 +1956,8 @@ fn approval_modal_patch_snapshot() {
         changes,
         reason: Some("The model wants to apply changes".into()),
         grant_root: Some(PathBuf::from("/tmp")),
+        hunks: Vec::new(),
+        user_changes: Vec::new(),
     };
     chat.handle_codex_event(Event {
         id: "sub-approve-patch".into(),
@@ -2114,6 +2132,9 @@ fn status_widget_and_approval_modal_snapshot() {
         ),
         risk: None,
         parsed_cmd: vec![],
+        scripts: vec![],
+        expansions: vec![],
+        trust_prefix: None,
     };
     chat.handle_codex_event(Event {
         id: "sub-approve-exec".into(),
@@ -2193,6 +2214,8 @@ fn apply_patch_events_emit_history_cells() {
         changes,
         reason: None,
         grant_root: None,
+        hunks: Vec::new(),
+        user_changes: Vec::new(),
     };
     chat.handle_codex_event(Event {
         id: "s1".into(),
@@ -2292,6 +2315,8 @@ fn apply_patch_manual_approval_adjusts_header() {
             changes: proposed_changes,
             reason: None,
             grant_root: None,
+            hunks: Vec::new(),
+            user_changes: Vec::new(),
         }),
     });
     drain_insert_history(&mut rx);
@@ -2341,6 +2366,8 @@ fn apply_patch_manual_flow_snapshot() {
             changes: proposed_changes,
             reason: Some("Manual review required".into()),
             grant_root: None,
+            hunks: Vec::new(),
+            user_changes: Vec::new(),
         }),
     });
     let history_before_apply = drain_insert_history(&mut rx);
@@ -2392,6 +2419,8 @@ fn apply_patch_approval_sends_op_with_submission_id() {
         changes,
         reason: None,
         grant_root: None,
+        hunks: Vec::new(),
+        user_changes: Vec::new(),
     };
     chat.handle_codex_event(Event {
         id: "sub-123".into(),
@@ -2432,6 +2461,8 @@ fn apply_patch_full_flow_integration_like() {
             changes,
             reason: None,
             grant_root: None,
+            hunks: Vec::new(),
+            user_changes: Vec::new(),
         }),
     });
 
@@ -2512,6 +2543,8 @@ fn apply_patch_untrusted_shows_approval_modal() {
             changes,
             reason: None,
             grant_root: None,
+            hunks: Vec::new(),
+            user_changes: Vec::new(),
         }),
     });
 
@@ -2561,6 +2594,8 @@ fn apply_patch_request_shows_diff_summary() {
             changes,
             reason: None,
             grant_root: None,
+            hunks: Vec::new(),
+            user_changes: Vec::new(),
         }),
     });
 
@@ -32,6 +32,16 @@ pub struct Cli {
     #[clap(skip)]
     pub resume_show_all: bool,
 
+    /// Internal: fork the selected session into its own worktree instead of
+    /// resuming it. Set by `codex resume --fork`.
+    #[clap(skip)]
+    pub resume_fork: bool,
+
+    /// Internal: with `resume_fork`, keep only the transcript before this
+    /// (0-based) user message.
+    #[clap(skip)]
+    pub resume_fork_nth_user_message: Option<usize>,
+
     /// Model the agent should use.
     #[arg(long, short = 'm')]
     pub model: Option<String>,
@@ -103,6 +103,7 @@ fn collect_rows(changes: &HashMap<PathBuf, FileChange>) -> Vec<Row> {
             FileChange::Add { content } => (content.lines().count(), 0),
             FileChange::Delete { content } => (0, content.lines().count()),
             FileChange::Update { unified_diff, .. } => calculate_add_remove_from_diff(unified_diff),
+            FileChange::Binary { .. } => (0, 0),
         };
         let move_path = match change {
             FileChange::Update {
@@ -153,7 +154,11 @@ fn render_changes_block(rows: Vec<Row>, wrap_cols: usize, cwd: &Path) -> Vec<RtL
     let mut header_spans: Vec<RtSpan<'static>> = vec!["• ".dim()];
     if let [row] = &rows[..] {
         let verb = match &row.change {
-            FileChange::Add { .. } => "Added",
+            FileChange::Add { .. }
+            | FileChange::Binary {
+                previous_size: None,
+                ..
+            } => "Added",
             FileChange::Delete { .. } => "Deleted",
             _ => "Edited",
         };
@@ -219,6 +224,14 @@ fn render_change(change: &FileChange, out: &mut Vec<RtLine<'static>>, width: usi
                 ));
             }
         }
+        FileChange::Binary {
+            size,
+            previous_size,
+        } => {
+            out.push(RtLine::from(
+                FileChange::binary_summary(*size, *previous_size).dim(),
+            ));
+        }
         FileChange::Update { unified_diff, .. } => {
             if let Ok(patch) = diffy::Patch::from_str(unified_diff) {
                 let mut max_line_number = 0;
@@ -421,6 +421,19 @@ pub fn new_approval_decision_cell(
                 ],
             )
         }
+        ApprovedPrefixForSession => {
+            let snippet = Span::from(exec_snippet(&command)).dim();
+            (
+                "✔ ".green(),
+                vec![
+                    "You ".into(),
+                    "approved".bold(),
+                    " codex to run ".into(),
+                    snippet,
+                    " and commands like it this session".bold(),
+                ],
+            )
+        }
         Denied => {
             let snippet = Span::from(exec_snippet(&command)).dim();
             (
@@ -21,14 +21,13 @@ use codex_core::config::load_config_as_toml_with_cli_overrides;
 use codex_core::config::resolve_oss_provider;
 use codex_core::find_conversation_path_by_id_str;
 use codex_core::get_platform_sandbox;
+use codex_core::logging::RotatingFile;
 use codex_core::protocol::AskForApproval;
 use codex_protocol::config_types::SandboxMode;
 use opentelemetry_appender_tracing::layer::OpenTelemetryTracingBridge;
-use std::fs::OpenOptions;
 use std::path::PathBuf;
 use tracing::error;
 use tracing_appender::non_blocking;
-use tracing_subscriber::EnvFilter;
 use tracing_subscriber::filter::Targets;
 use tracing_subscriber::prelude::*;
 
@@ -239,37 +238,19 @@ pub async fn run_main(
     let active_profile = config.active_profile.clone();
     let log_dir = codex_core::config::log_dir(&config)?;
     std::fs::create_dir_all(&log_dir)?;
-    // Open (or create) your log file, appending to it.
-    let mut log_file_opts = OpenOptions::new();
-    log_file_opts.create(true).append(true);
-
-    // Ensure the file is only readable and writable by the current user.
-    // Doing the equivalent to `chmod 600` on Windows is quite a bit more code
-    // and requires the Windows API crates, so we can reconsider that when
-    // Codex CLI is officially supported on Windows.
-    #[cfg(unix)]
-    {
-        use std::os::unix::fs::OpenOptionsExt;
-        log_file_opts.mode(0o600);
-    }
-
-    let log_file = log_file_opts.open(log_dir.join("codex-tui.log"))?;
+    // Open (or create) the log file, appending to it and rotating it once it
+    // grows past `logging.max_file_size_mb`.
+    let log_file = RotatingFile::open(log_dir.join("codex-tui.log"), &config.logging)?;
 
     // Wrap file in non‑blocking writer.
     let (non_blocking, _guard) = non_blocking(log_file);
 
-    // use RUST_LOG env var, default to info for codex crates.
-    let env_filter = || {
-        EnvFilter::try_from_default_env().unwrap_or_else(|_| {
-            EnvFilter::new("codex_core=info,codex_tui=info,codex_rmcp_client=info")
-        })
-    };
-
-    let file_layer = tracing_subscriber::fmt::layer()
-        .with_writer(non_blocking)
-        .with_target(false)
-        .with_span_events(tracing_subscriber::fmt::format::FmtSpan::CLOSE)
-        .with_filter(env_filter());
+    // use RUST_LOG env var or `logging.levels`, default to info for codex crates.
+    let file_layer = codex_core::logging::file_layer(
+        &config.logging,
+        "codex_core=info,codex_tui=info,codex_rmcp_client=info",
+        non_blocking,
+    );
 
     let feedback = codex_feedback::CodexFeedback::new();
     let targets = Targets::new().with_default(tracing::Level::TRACE);
@@ -344,6 +325,7 @@ async fn run_ratatui_app(
     feedback: codex_feedback::CodexFeedback,
 ) -> color_eyre::Result<AppExitInfo> {
     color_eyre::install()?;
+    codex_core::crash_report::install_crash_reporter(&initial_config, report_crash).await;
 
     // Forward panic reports through tracing so they appear in the UI status
     // line, but do not swallow the default/color-eyre panic handler.
@@ -493,6 +475,16 @@ async fn run_ratatui_app(
         resume_picker::ResumeSelection::StartFresh
     };
 
+    let resume_selection = match resume_selection {
+        resume_picker::ResumeSelection::Resume(path) if cli.resume_fork => {
+            resume_picker::ResumeSelection::Fork {
+                path,
+                nth_user_message: cli.resume_fork_nth_user_message,
+            }
+        }
+        other => other,
+    };
+
     let Cli { prompt, images, .. } = cli;
 
     let app_result = App::run(
@@ -526,6 +518,17 @@ fn restore() {
     }
 }
 
+#[expect(
+    clippy::print_stderr,
+    reason = "The panic hooks restored the terminal before this runs."
+)]
+fn report_crash(path: &std::path::Path) {
+    eprintln!(
+        "Crash report saved to {}. It stays on this machine unless you share it.",
+        path.display()
+    );
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum LoginStatus {
     AuthMode(AuthMode),
@@ -621,7 +624,10 @@ mod tests {
             temp_dir.path().to_path_buf(),
         )?;
         config.did_user_set_custom_approval_policy_or_sandbox_mode = false;
-        config.active_project = ProjectConfig { trust_level: None };
+        config.active_project = ProjectConfig {
+            trust_level: None,
+            trash: None,
+        };
         config.set_windows_sandbox_globally(false);
 
         let should_show = should_show_trust_screen(&config);
@@ -648,7 +654,10 @@ mod tests {
             temp_dir.path().to_path_buf(),
         )?;
         config.did_user_set_custom_approval_policy_or_sandbox_mode = false;
-        config.active_project = ProjectConfig { trust_level: None };
+        config.active_project = ProjectConfig {
+            trust_level: None,
+            trash: None,
+        };
         config.set_windows_sandbox_globally(true);
 
         let should_show = should_show_trust_screen(&config);
@@ -677,6 +686,7 @@ mod tests {
         config.did_user_set_custom_approval_policy_or_sandbox_mode = false;
         config.active_project = ProjectConfig {
             trust_level: Some(TrustLevel::Untrusted),
+            trash: None,
         };
 
         let should_show = should_show_trust_screen(&config);
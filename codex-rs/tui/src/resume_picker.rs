@@ -42,6 +42,12 @@ const LOAD_NEAR_THRESHOLD: usize = 5;
 pub enum ResumeSelection {
     StartFresh,
     Resume(PathBuf),
+    /// Fork the session recorded at `path` into its own worktree, keeping the
+    /// transcript before the nth user message (all of it when `None`).
+    Fork {
+        path: PathBuf,
+        nth_user_message: Option<usize>,
+    },
     Exit,
 }
 
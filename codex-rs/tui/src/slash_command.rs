@@ -18,10 +18,15 @@ pub enum SlashCommand {
     New,
     Init,
     Compact,
+    Context,
     Undo,
     Diff,
+    FilesRead,
+    Cleanup,
+    Keep,
     Mention,
     Status,
+    Doctor,
     Mcp,
     Logout,
     Quit,
@@ -40,11 +45,16 @@ impl SlashCommand {
             SlashCommand::Init => "create an AGENTS.md file with instructions for Codex",
             SlashCommand::Compact => "summarize conversation to prevent hitting the context limit",
             SlashCommand::Review => "review my current changes and find issues",
+            SlashCommand::Context => "show what is taking up the context window",
             SlashCommand::Undo => "ask Codex to undo a turn",
             SlashCommand::Quit | SlashCommand::Exit => "exit Codex",
             SlashCommand::Diff => "show git diff (including untracked files)",
+            SlashCommand::FilesRead => "list the files whose contents were shown to the model",
+            SlashCommand::Cleanup => "find and clean up leftovers of interrupted commands",
+            SlashCommand::Keep => "keep files that escalated commands created elsewhere",
             SlashCommand::Mention => "mention a file",
             SlashCommand::Status => "show current session configuration and token usage",
+            SlashCommand::Doctor => "check the sandbox, shell, git, credentials and network",
             SlashCommand::Model => "choose what model and reasoning effort to use",
             SlashCommand::Approvals => "choose what Codex can do without approval",
             SlashCommand::Mcp => "list configured MCP tools",
@@ -70,10 +80,15 @@ impl SlashCommand {
             | SlashCommand::Model
             | SlashCommand::Approvals
             | SlashCommand::Review
+            | SlashCommand::Cleanup
+            | SlashCommand::Keep
             | SlashCommand::Logout => false,
             SlashCommand::Diff
             | SlashCommand::Mention
+            | SlashCommand::FilesRead
+            | SlashCommand::Context
             | SlashCommand::Status
+            | SlashCommand::Doctor
             | SlashCommand::Mcp
             | SlashCommand::Feedback
             | SlashCommand::Quit
@@ -7,6 +7,8 @@ mod errors;
 mod ghost_commits;
 mod operations;
 mod platform;
+mod uncommitted;
+mod worktree;
 
 pub use apply::ApplyGitRequest;
 pub use apply::ApplyGitResult;
@@ -29,6 +31,9 @@ use schemars::JsonSchema;
 use serde::Deserialize;
 use serde::Serialize;
 use ts_rs::TS;
+pub use uncommitted::UncommittedChange;
+pub use uncommitted::uncommitted_changes;
+pub use worktree::create_detached_worktree;
 
 type CommitID = String;
 
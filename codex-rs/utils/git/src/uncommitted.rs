@@ -0,0 +1,225 @@
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::GitToolingError;
+use crate::operations::ensure_git_repository;
+use crate::operations::resolve_head;
+use crate::operations::resolve_repository_root;
+use crate::operations::run_git_for_stdout_all;
+
+/// A file whose working-tree contents differ from `HEAD`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UncommittedChange {
+    /// The path as it was passed in.
+    pub path: PathBuf,
+    /// Unified diff from `HEAD` to the working tree. Untracked files show up
+    /// as added in full.
+    pub unified_diff: String,
+}
+
+/// Returns the uncommitted changes, staged or not, to those of `paths` that
+/// have any, in the order given. Untracked files count as changed; ignored
+/// files and paths outside the repository containing `repo_path` do not.
+/// Outside a git repository nothing is reported.
+pub fn uncommitted_changes(
+    repo_path: &Path,
+    paths: &[PathBuf],
+) -> Result<Vec<UncommittedChange>, GitToolingError> {
+    match ensure_git_repository(repo_path) {
+        Ok(()) => {}
+        Err(GitToolingError::NotAGitRepository { .. }) => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    }
+    let repo_root = resolve_repository_root(repo_path)?;
+
+    let mut by_relative = HashMap::new();
+    for path in paths {
+        if let Some(relative) = relative_to_root(&repo_root, path) {
+            by_relative.insert(relative, path);
+        }
+    }
+    if by_relative.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut args = vec![
+        OsString::from("status"),
+        OsString::from("--porcelain=v1"),
+        OsString::from("-z"),
+        OsString::from("--untracked-files=all"),
+        OsString::from("--"),
+    ];
+    args.extend(
+        by_relative
+            .keys()
+            .map(|path| path.as_os_str().to_os_string()),
+    );
+    let status = run_git_for_stdout_all(repo_root.as_path(), args, None)?;
+    let has_head = resolve_head(repo_root.as_path())?.is_some();
+
+    let mut dirty = HashMap::new();
+    let mut entries = status.split('\0');
+    while let Some(entry) = entries.next() {
+        let (Some(code), Some(path)) = (entry.get(..2), entry.get(3..)) else {
+            continue;
+        };
+        if code.starts_with(['R', 'C']) {
+            // Renames and copies are followed by their source path.
+            entries.next();
+        }
+        let relative = PathBuf::from(path);
+        let Some(original) = by_relative.get(&relative) else {
+            continue;
+        };
+        let unified_diff = if code == "??" || !has_head {
+            added_file_diff(&repo_root, &relative)?
+        } else {
+            run_git_for_stdout_all(
+                repo_root.as_path(),
+                vec![
+                    OsString::from("diff"),
+                    OsString::from("--no-color"),
+                    OsString::from("--no-ext-diff"),
+                    OsString::from("HEAD"),
+                    OsString::from("--"),
+                    relative.into_os_string(),
+                ],
+                None,
+            )?
+        };
+        dirty.insert(original.to_path_buf(), unified_diff);
+    }
+
+    Ok(paths
+        .iter()
+        .filter_map(|path| {
+            dirty.remove(path).map(|unified_diff| UncommittedChange {
+                path: path.clone(),
+                unified_diff,
+            })
+        })
+        .collect())
+}
+
+fn relative_to_root(repo_root: &Path, path: &Path) -> Option<PathBuf> {
+    if let Ok(relative) = path.strip_prefix(repo_root) {
+        return Some(relative.to_path_buf());
+    }
+    // Either side may go through a symlink (e.g. `/tmp` on macOS); compare
+    // canonical forms, resolving the parent since the file may not exist.
+    let root = repo_root.canonicalize().ok()?;
+    let parent = path.parent()?.canonicalize().ok()?;
+    let relative = parent.strip_prefix(root).ok()?;
+    Some(relative.join(path.file_name()?))
+}
+
+fn added_file_diff(repo_root: &Path, relative: &Path) -> Result<String, GitToolingError> {
+    let name = relative.to_string_lossy();
+    let header = format!("diff --git a/{name} b/{name}\n");
+    let contents = match std::fs::read(repo_root.join(relative)) {
+        Ok(contents) => contents,
+        // Deleted since `git status` ran.
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(String::new()),
+        Err(err) => return Err(err.into()),
+    };
+    let Ok(text) = String::from_utf8(contents) else {
+        return Ok(format!(
+            "{header}Binary files /dev/null and b/{name} differ\n"
+        ));
+    };
+    let lines: Vec<&str> = text.lines().collect();
+    let mut diff = format!(
+        "{header}--- /dev/null\n+++ b/{name}\n@@ -0,0 +1,{} @@\n",
+        lines.len()
+    );
+    for line in lines {
+        diff.push('+');
+        diff.push_str(line);
+        diff.push('\n');
+    }
+    Ok(diff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::process::Command;
+    use tempfile::tempdir;
+
+    fn run_git_in(repo_path: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .current_dir(repo_path)
+            .args(args)
+            .status()
+            .expect("git command");
+        assert!(status.success(), "git command failed: {args:?}");
+    }
+
+    #[test]
+    fn reports_modified_and_untracked_files_only() -> Result<(), GitToolingError> {
+        let temp = tempdir()?;
+        let repo = temp.path();
+        run_git_in(repo, &["init", "--initial-branch=main"]);
+        std::fs::write(repo.join("clean.txt"), "clean\n")?;
+        std::fs::write(repo.join("edited.txt"), "one\n")?;
+        run_git_in(repo, &["add", "."]);
+        run_git_in(
+            repo,
+            &[
+                "-c",
+                "user.name=Tester",
+                "-c",
+                "user.email=test@example.com",
+                "commit",
+                "-m",
+                "init",
+            ],
+        );
+        std::fs::write(repo.join("edited.txt"), "two\n")?;
+        std::fs::write(repo.join("new.txt"), "draft\n")?;
+
+        let paths = [
+            repo.join("new.txt"),
+            repo.join("clean.txt"),
+            repo.join("edited.txt"),
+            repo.join("missing.txt"),
+        ];
+        let changes = uncommitted_changes(repo, &paths)?;
+
+        assert_eq!(
+            changes,
+            vec![
+                UncommittedChange {
+                    path: repo.join("new.txt"),
+                    unified_diff: "diff --git a/new.txt b/new.txt\n--- /dev/null\n\
+                                   +++ b/new.txt\n@@ -0,0 +1,1 @@\n+draft\n"
+                        .to_string(),
+                },
+                UncommittedChange {
+                    path: repo.join("edited.txt"),
+                    unified_diff: "diff --git a/edited.txt b/edited.txt\n\
+                                   index 5626abf..f719efd 100644\n\
+                                   --- a/edited.txt\n+++ b/edited.txt\n\
+                                   @@ -1 +1 @@\n-one\n+two\n"
+                        .to_string(),
+                },
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn nothing_is_dirty_outside_a_repository() -> Result<(), GitToolingError> {
+        let temp = tempdir()?;
+        std::fs::write(temp.path().join("a.txt"), "a\n")?;
+
+        assert_eq!(
+            uncommitted_changes(temp.path(), &[temp.path().join("a.txt")])?,
+            Vec::new()
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,101 @@
+use std::ffi::OsString;
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::GitToolingError;
+use crate::operations::ensure_git_repository;
+use crate::operations::repo_subdir;
+use crate::operations::resolve_repository_root;
+use crate::operations::run_git_for_status;
+
+/// Checks out `commit` of the repository containing `repo_path` into a new
+/// linked worktree at `worktree_path`, with a detached `HEAD`.
+///
+/// Returns the directory of the new worktree that corresponds to `repo_path`,
+/// which differs from `worktree_path` when `repo_path` is a subdirectory of
+/// the repository.
+pub fn create_detached_worktree(
+    repo_path: &Path,
+    worktree_path: &Path,
+    commit: &str,
+) -> Result<PathBuf, GitToolingError> {
+    ensure_git_repository(repo_path)?;
+    let repo_root = resolve_repository_root(repo_path)?;
+    let repo_prefix = repo_subdir(repo_root.as_path(), repo_path);
+
+    run_git_for_status(
+        repo_root.as_path(),
+        vec![
+            OsString::from("worktree"),
+            OsString::from("add"),
+            OsString::from("--detach"),
+            worktree_path.as_os_str().to_os_string(),
+            OsString::from(commit),
+        ],
+        None,
+    )?;
+
+    Ok(match repo_prefix {
+        Some(prefix) => worktree_path.join(prefix),
+        None => worktree_path.to_path_buf(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::create_detached_worktree;
+    use crate::GitToolingError;
+    use pretty_assertions::assert_eq;
+    use std::path::Path;
+    use std::process::Command;
+    use tempfile::tempdir;
+
+    fn run_git_in(repo_path: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .current_dir(repo_path)
+            .args(args)
+            .status()
+            .expect("git command");
+        assert!(status.success(), "git command failed: {args:?}");
+    }
+
+    fn commit(repo_path: &Path, message: &str) {
+        run_git_in(
+            repo_path,
+            &[
+                "-c",
+                "user.name=Tester",
+                "-c",
+                "user.email=test@example.com",
+                "commit",
+                "-m",
+                message,
+            ],
+        );
+    }
+
+    #[test]
+    fn worktree_checks_out_the_commit_and_maps_the_subdirectory() -> Result<(), GitToolingError> {
+        let temp = tempdir()?;
+        let repo = temp.path().join("repo");
+        std::fs::create_dir_all(repo.join("app"))?;
+        run_git_in(&repo, &["init", "--initial-branch=main"]);
+        std::fs::write(repo.join("app/main.txt"), "first\n")?;
+        run_git_in(&repo, &["add", "."]);
+        commit(&repo, "first");
+        std::fs::write(repo.join("app/main.txt"), "second\n")?;
+        run_git_in(&repo, &["add", "."]);
+        commit(&repo, "second");
+
+        let worktree = temp.path().join("fork");
+        let cwd = create_detached_worktree(&repo.join("app"), &worktree, "HEAD~1")?;
+
+        assert_eq!(cwd, worktree.join("app"));
+        assert_eq!(std::fs::read_to_string(cwd.join("main.txt"))?, "first\n");
+        assert_eq!(
+            std::fs::read_to_string(repo.join("app/main.txt"))?,
+            "second\n"
+        );
+        Ok(())
+    }
+}
@@ -1,4 +1,5 @@
 ﻿use crate::winutil::to_wide;
+use crate::winutil::to_wide_path;
 use anyhow::anyhow;
 use anyhow::Result;
 use std::ffi::c_void;
@@ -164,7 +165,7 @@ pub unsafe fn add_allow_ace(path: &Path, psid: *mut c_void) -> Result<bool> {
     let mut p_sd: *mut c_void = std::ptr::null_mut();
     let mut p_dacl: *mut ACL = std::ptr::null_mut();
     let code = GetNamedSecurityInfoW(
-        to_wide(path).as_ptr(),
+        to_wide_path(path).as_ptr(),
         1,
         DACL_SECURITY_INFORMATION,
         std::ptr::null_mut(),
@@ -195,7 +196,7 @@ pub unsafe fn add_allow_ace(path: &Path, psid: *mut c_void) -> Result<bool> {
         let code2 = SetEntriesInAclW(1, &explicit, p_dacl, &mut p_new_dacl);
         if code2 == ERROR_SUCCESS {
             let code3 = SetNamedSecurityInfoW(
-                to_wide(path).as_ptr() as *mut u16,
+                to_wide_path(path).as_ptr() as *mut u16,
                 1,
                 DACL_SECURITY_INFORMATION,
                 std::ptr::null_mut(),
@@ -221,7 +222,7 @@ pub unsafe fn add_deny_write_ace(path: &Path, psid: *mut c_void) -> Result<bool>
     let mut p_sd: *mut c_void = std::ptr::null_mut();
     let mut p_dacl: *mut ACL = std::ptr::null_mut();
     let code = GetNamedSecurityInfoW(
-        to_wide(path).as_ptr(),
+        to_wide_path(path).as_ptr(),
         1,
         DACL_SECURITY_INFORMATION,
         std::ptr::null_mut(),
@@ -256,7 +257,7 @@ pub unsafe fn add_deny_write_ace(path: &Path, psid: *mut c_void) -> Result<bool>
         let code2 = SetEntriesInAclW(1, &explicit, p_dacl, &mut p_new_dacl);
         if code2 == ERROR_SUCCESS {
             let code3 = SetNamedSecurityInfoW(
-                to_wide(path).as_ptr() as *mut u16,
+                to_wide_path(path).as_ptr() as *mut u16,
                 1,
                 DACL_SECURITY_INFORMATION,
                 std::ptr::null_mut(),
@@ -282,7 +283,7 @@ pub unsafe fn revoke_ace(path: &Path, psid: *mut c_void) {
     let mut p_sd: *mut c_void = std::ptr::null_mut();
     let mut p_dacl: *mut ACL = std::ptr::null_mut();
     let code = GetNamedSecurityInfoW(
-        to_wide(path).as_ptr(),
+        to_wide_path(path).as_ptr(),
         1,
         DACL_SECURITY_INFORMATION,
         std::ptr::null_mut(),
@@ -313,7 +314,7 @@ pub unsafe fn revoke_ace(path: &Path, psid: *mut c_void) {
     let code2 = SetEntriesInAclW(1, &explicit, p_dacl, &mut p_new_dacl);
     if code2 == ERROR_SUCCESS {
         let _ = SetNamedSecurityInfoW(
-            to_wide(path).as_ptr() as *mut u16,
+            to_wide_path(path).as_ptr() as *mut u16,
             1,
             DACL_SECURITY_INFORMATION,
             std::ptr::null_mut(),
@@ -6,7 +6,7 @@ use crate::policy::SandboxPolicy;
 use crate::token::convert_string_sid_to_sid;
 use crate::token::world_sid;
 use anyhow::anyhow;
-use crate::winutil::to_wide;
+use crate::winutil::to_wide_path;
 use anyhow::Result;
 use std::collections::HashMap;
 use std::collections::HashSet;
@@ -115,7 +115,7 @@ unsafe fn path_has_world_write_allow(path: &Path) -> Result<bool> {
     let mut p_dacl: *mut ACL = std::ptr::null_mut();
 
     let mut try_named = false;
-    let wpath = to_wide(path);
+    let wpath = to_wide_path(path);
     let h = CreateFileW(
         wpath.as_ptr(),
         0x00020000, // READ_CONTROL
@@ -0,0 +1,130 @@
+use crate::JobLimit;
+use crate::JobLimits;
+use anyhow::anyhow;
+use anyhow::Result;
+use std::ffi::c_void;
+use windows_sys::Win32::Foundation::CloseHandle;
+use windows_sys::Win32::Foundation::GetLastError;
+use windows_sys::Win32::Foundation::HANDLE;
+use windows_sys::Win32::System::JobObjects::AssignProcessToJobObject;
+use windows_sys::Win32::System::JobObjects::CreateJobObjectW;
+use windows_sys::Win32::System::JobObjects::JobObjectExtendedLimitInformation;
+use windows_sys::Win32::System::JobObjects::JobObjectLimitViolationInformation;
+use windows_sys::Win32::System::JobObjects::QueryInformationJobObject;
+use windows_sys::Win32::System::JobObjects::SetInformationJobObject;
+use windows_sys::Win32::System::JobObjects::TerminateJobObject;
+use windows_sys::Win32::System::JobObjects::JOBOBJECT_EXTENDED_LIMIT_INFORMATION;
+use windows_sys::Win32::System::JobObjects::JOBOBJECT_LIMIT_VIOLATION_INFORMATION;
+use windows_sys::Win32::System::JobObjects::JOB_OBJECT_LIMIT_ACTIVE_PROCESS;
+use windows_sys::Win32::System::JobObjects::JOB_OBJECT_LIMIT_JOB_MEMORY;
+use windows_sys::Win32::System::JobObjects::JOB_OBJECT_LIMIT_JOB_TIME;
+use windows_sys::Win32::System::JobObjects::JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+
+/// The exit code of the processes of a job that ran out of CPU time.
+const ERROR_NOT_ENOUGH_QUOTA: u32 = 1816;
+
+/// CPU time in the 100-nanosecond units job objects count in.
+const TICKS_PER_SEC: i64 = 10_000_000;
+
+/// A job object holding a sandboxed command and every process it starts.
+/// Closing it kills whatever is still running.
+pub struct Job {
+    handle: HANDLE,
+}
+
+impl Job {
+    pub fn new(limits: &JobLimits) -> Result<Self> {
+        let handle = unsafe { CreateJobObjectW(std::ptr::null(), std::ptr::null()) };
+        if handle == 0 {
+            return Err(anyhow!("CreateJobObjectW failed: {}", unsafe {
+                GetLastError()
+            }));
+        }
+        let job = Self { handle };
+        let mut info: JOBOBJECT_EXTENDED_LIMIT_INFORMATION = unsafe { std::mem::zeroed() };
+        let basic = &mut info.BasicLimitInformation;
+        basic.LimitFlags = JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+        if let Some(cpu_secs) = limits.cpu_secs {
+            basic.LimitFlags |= JOB_OBJECT_LIMIT_JOB_TIME;
+            basic.PerJobUserTimeLimit =
+                i64::try_from(cpu_secs).map_or(i64::MAX, |secs| secs.saturating_mul(TICKS_PER_SEC));
+        }
+        if let Some(max_processes) = limits.max_processes {
+            basic.LimitFlags |= JOB_OBJECT_LIMIT_ACTIVE_PROCESS;
+            basic.ActiveProcessLimit = u32::try_from(max_processes).unwrap_or(u32::MAX);
+        }
+        if let Some(memory_mb) = limits.memory_mb {
+            basic.LimitFlags |= JOB_OBJECT_LIMIT_JOB_MEMORY;
+            info.JobMemoryLimit =
+                usize::try_from(memory_mb.saturating_mul(1024 * 1024)).unwrap_or(usize::MAX);
+        }
+        let ok = unsafe {
+            SetInformationJobObject(
+                job.handle,
+                JobObjectExtendedLimitInformation,
+                &info as *const _ as *const c_void,
+                std::mem::size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+            )
+        };
+        if ok == 0 {
+            return Err(anyhow!("SetInformationJobObject failed: {}", unsafe {
+                GetLastError()
+            }));
+        }
+        Ok(job)
+    }
+
+    pub fn assign(&self, process: HANDLE) -> Result<()> {
+        if unsafe { AssignProcessToJobObject(self.handle, process) } == 0 {
+            return Err(anyhow!("AssignProcessToJobObject failed: {}", unsafe {
+                GetLastError()
+            }));
+        }
+        Ok(())
+    }
+
+    /// Kill every process in the job.
+    pub fn terminate(&self, exit_code: u32) {
+        unsafe {
+            TerminateJobObject(self.handle, exit_code);
+        }
+    }
+
+    /// The limit that stopped the job's command, if one did.
+    pub fn exceeded_limit(&self, limits: &JobLimits, exit_code: u32) -> Option<JobLimit> {
+        if limits.cpu_secs.is_some() && exit_code == ERROR_NOT_ENOUGH_QUOTA {
+            return Some(JobLimit::CpuTime);
+        }
+        let mut violation: JOBOBJECT_LIMIT_VIOLATION_INFORMATION = unsafe { std::mem::zeroed() };
+        let ok = unsafe {
+            QueryInformationJobObject(
+                self.handle,
+                JobObjectLimitViolationInformation,
+                &mut violation as *mut _ as *mut c_void,
+                std::mem::size_of::<JOBOBJECT_LIMIT_VIOLATION_INFORMATION>() as u32,
+                std::ptr::null_mut(),
+            )
+        };
+        if ok == 0 {
+            return None;
+        }
+        let violated = violation.ViolationLimitFlags;
+        if violated & JOB_OBJECT_LIMIT_JOB_MEMORY != 0 {
+            Some(JobLimit::Memory)
+        } else if violated & JOB_OBJECT_LIMIT_JOB_TIME != 0 {
+            Some(JobLimit::CpuTime)
+        } else if violated & JOB_OBJECT_LIMIT_ACTIVE_PROCESS != 0 {
+            Some(JobLimit::Processes)
+        } else {
+            None
+        }
+    }
+}
+
+impl Drop for Job {
+    fn drop(&mut self) {
+        unsafe {
+            CloseHandle(self.handle);
+        }
+    }
+}
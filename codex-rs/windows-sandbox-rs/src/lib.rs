@@ -4,13 +4,46 @@ macro_rules! windows_modules {
     };
 }
 
-windows_modules!(acl, allow, audit, cap, env, logging, policy, token, winutil);
+windows_modules!(acl, allow, audit, cap, env, job, logging, policy, token, winutil);
+
+/// Limits the job object of a sandboxed command enforces on the command and
+/// the processes it starts together.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct JobLimits {
+    /// Seconds of user-mode CPU time.
+    pub cpu_secs: Option<u64>,
+    /// Megabytes of committed memory.
+    pub memory_mb: Option<u64>,
+    /// Processes running at once.
+    pub max_processes: Option<u64>,
+}
+
+/// The limit of [`JobLimits`] that stopped a command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobLimit {
+    CpuTime,
+    Memory,
+    Processes,
+}
+
+/// What `probe_sandbox` found: `None` where the sandbox could do its part.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SandboxProbe {
+    /// Why no restricted token could be created. Commands cannot be
+    /// sandboxed without one.
+    pub token_error: Option<String>,
+    /// Why no job object could be created. Commands are sandboxed without
+    /// limits, and processes they leave behind are not killed.
+    pub job_error: Option<String>,
+}
 
 #[cfg(target_os = "windows")]
 pub use audit::world_writable_warning_details;
 #[cfg(target_os = "windows")]
 pub use windows_impl::preflight_audit_everyone_writable;
 #[cfg(target_os = "windows")]
+pub use windows_impl::probe_sandbox;
+#[cfg(target_os = "windows")]
 pub use windows_impl::run_windows_sandbox_capture;
 #[cfg(target_os = "windows")]
 pub use windows_impl::CaptureResult;
@@ -18,6 +51,8 @@ pub use windows_impl::CaptureResult;
 #[cfg(not(target_os = "windows"))]
 pub use stub::preflight_audit_everyone_writable;
 #[cfg(not(target_os = "windows"))]
+pub use stub::probe_sandbox;
+#[cfg(not(target_os = "windows"))]
 pub use stub::run_windows_sandbox_capture;
 #[cfg(not(target_os = "windows"))]
 pub use stub::world_writable_warning_details;
@@ -36,6 +71,7 @@ mod windows_impl {
     use super::env::apply_no_network_to_env;
     use super::env::ensure_non_interactive_pager;
     use super::env::normalize_null_device_env;
+    use super::job::Job;
     use super::logging::debug_log;
     use super::logging::log_failure;
     use super::logging::log_note;
@@ -46,6 +82,9 @@ mod windows_impl {
     use super::token::convert_string_sid_to_sid;
     use super::winutil::format_last_error;
     use super::winutil::to_wide;
+    use super::JobLimit;
+    use super::JobLimits;
+    use super::SandboxProbe;
     use anyhow::Result;
     use std::collections::HashMap;
     use std::ffi::c_void;
@@ -62,7 +101,9 @@ mod windows_impl {
     use windows_sys::Win32::System::Pipes::CreatePipe;
     use windows_sys::Win32::System::Threading::CreateProcessAsUserW;
     use windows_sys::Win32::System::Threading::GetExitCodeProcess;
+    use windows_sys::Win32::System::Threading::ResumeThread;
     use windows_sys::Win32::System::Threading::WaitForSingleObject;
+    use windows_sys::Win32::System::Threading::CREATE_SUSPENDED;
     use windows_sys::Win32::System::Threading::CREATE_UNICODE_ENVIRONMENT;
     use windows_sys::Win32::System::Threading::INFINITE;
     use windows_sys::Win32::System::Threading::PROCESS_INFORMATION;
@@ -180,6 +221,32 @@ mod windows_impl {
         pub stdout: Vec<u8>,
         pub stderr: Vec<u8>,
         pub timed_out: bool,
+        pub limit_exceeded: Option<JobLimit>,
+    }
+
+    /// Try what sandboxing a command takes, without running one: creating
+    /// the restricted token of the read-only policy and a job object.
+    pub fn probe_sandbox(codex_home: &Path) -> SandboxProbe {
+        let token_error = (|| -> Result<()> {
+            ensure_codex_home_exists(codex_home)?;
+            let caps = load_or_create_cap_sids(codex_home);
+            let psid = unsafe { convert_string_sid_to_sid(&caps.readonly) }
+                .ok_or_else(|| anyhow::anyhow!("invalid capability SID {}", caps.readonly))?;
+            let (h_token, _) = unsafe { super::token::create_readonly_token_with_cap(psid)? };
+            unsafe {
+                CloseHandle(h_token);
+            }
+            Ok(())
+        })()
+        .err()
+        .map(|err| err.to_string());
+        let job_error = Job::new(&JobLimits::default())
+            .err()
+            .map(|err| err.to_string());
+        SandboxProbe {
+            token_error,
+            job_error,
+        }
     }
 
     pub fn preflight_audit_everyone_writable(
@@ -208,6 +275,7 @@ mod windows_impl {
         Ok(Vec::new())
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn run_windows_sandbox_capture(
         policy_json_or_preset: &str,
         sandbox_policy_cwd: &Path,
@@ -216,6 +284,7 @@ mod windows_impl {
         cwd: &Path,
         mut env_map: HashMap<String, String>,
         timeout_ms: Option<u64>,
+        limits: &JobLimits,
     ) -> Result<CaptureResult> {
         let policy = parse_policy(policy_json_or_preset)?;
         let apply_network_block = should_apply_network_block(&policy);
@@ -306,6 +375,18 @@ mod windows_impl {
             .join(" ");
         let mut cmdline: Vec<u16> = to_wide(&cmdline_str);
         let env_block = make_env_block(&env_map);
+        // The command starts suspended so that it is in the job before it
+        // can start processes of its own.
+        let job = match Job::new(limits) {
+            Ok(job) => Some(job),
+            Err(err) => {
+                log_note(
+                    &format!("job object unavailable, running without limits: {err}"),
+                    logs_base_dir,
+                );
+                None
+            }
+        };
         let desktop = to_wide("Winsta0\\Default");
         si.lpDesktop = desktop.as_ptr() as *mut u16;
         let spawn_res = unsafe {
@@ -316,7 +397,7 @@ mod windows_impl {
                 ptr::null_mut(),
                 ptr::null_mut(),
                 1,
-                CREATE_UNICODE_ENVIRONMENT,
+                CREATE_UNICODE_ENVIRONMENT | CREATE_SUSPENDED,
                 env_block.as_ptr() as *mut c_void,
                 to_wide(cwd).as_ptr(),
                 &si,
@@ -347,6 +428,17 @@ mod windows_impl {
             return Err(anyhow::anyhow!("CreateProcessAsUserW failed: {}", err));
         }
 
+        let job = job.filter(|job| match job.assign(pi.hProcess) {
+            Ok(()) => true,
+            Err(err) => {
+                log_note(&format!("running without limits: {err}"), logs_base_dir);
+                false
+            }
+        });
+        unsafe {
+            ResumeThread(pi.hThread);
+        }
+
         unsafe {
             CloseHandle(in_r);
             // Close the parent's stdin write end so the child sees EOF immediately.
@@ -408,11 +500,19 @@ mod windows_impl {
             unsafe {
                 GetExitCodeProcess(pi.hProcess, &mut exit_code_u32);
             }
+        } else if let Some(job) = &job {
+            job.terminate(1);
         } else {
             unsafe {
                 windows_sys::Win32::System::Threading::TerminateProcess(pi.hProcess, 1);
             }
         }
+        let limit_exceeded = match &job {
+            Some(job) if !timed_out => job.exceeded_limit(limits, exit_code_u32),
+            _ => None,
+        };
+        // Closing the job kills the processes the command left running.
+        drop(job);
 
         unsafe {
             if pi.hThread != 0 {
@@ -452,6 +552,7 @@ mod windows_impl {
             stdout,
             stderr,
             timed_out,
+            limit_exceeded,
         })
     }
 
@@ -488,6 +589,9 @@ mod windows_impl {
 
 #[cfg(not(target_os = "windows"))]
 mod stub {
+    use super::JobLimit;
+    use super::JobLimits;
+    use super::SandboxProbe;
     use anyhow::bail;
     use anyhow::Result;
     use codex_protocol::protocol::SandboxPolicy;
@@ -500,6 +604,15 @@ mod stub {
         pub stdout: Vec<u8>,
         pub stderr: Vec<u8>,
         pub timed_out: bool,
+        pub limit_exceeded: Option<JobLimit>,
+    }
+
+    pub fn probe_sandbox(_codex_home: &Path) -> SandboxProbe {
+        let unavailable = || Some("the Windows sandbox is only available on Windows".to_string());
+        SandboxProbe {
+            token_error: unavailable(),
+            job_error: unavailable(),
+        }
     }
 
     pub fn preflight_audit_everyone_writable(
@@ -512,6 +625,7 @@ mod stub {
         bail!("Windows sandbox is only available on Windows")
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn run_windows_sandbox_capture(
         _policy_json_or_preset: &str,
         _sandbox_policy_cwd: &Path,
@@ -520,6 +634,7 @@ mod stub {
         _cwd: &Path,
         _env_map: HashMap<String, String>,
         _timeout_ms: Option<u64>,
+        _limits: &JobLimits,
     ) -> Result<CaptureResult> {
         bail!("Windows sandbox is only available on Windows")
     }
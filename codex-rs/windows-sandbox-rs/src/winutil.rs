@@ -1,5 +1,7 @@
+use codex_protocol::paths::to_extended_length;
 use std::ffi::OsStr;
 use std::os::windows::ffi::OsStrExt;
+use std::path::Path;
 use windows_sys::Win32::Foundation::LocalFree;
 use windows_sys::Win32::Foundation::HLOCAL;
 use windows_sys::Win32::System::Diagnostics::Debug::FormatMessageW;
@@ -13,6 +15,12 @@ pub fn to_wide<S: AsRef<OsStr>>(s: S) -> Vec<u16> {
     v
 }
 
+// `to_wide` for a path handed to a Win32 file API. Paths longer than MAX_PATH
+// are converted to their `\\?\` form so deeply nested trees stay reachable.
+pub fn to_wide_path(path: &Path) -> Vec<u16> {
+    to_wide(to_extended_length(path))
+}
+
 // Produce a readable description for a Win32 error code.
 pub fn format_last_error(err: i32) -> String {
     unsafe {